@@ -1,16 +1,29 @@
 use std::path::Path;
+use std::sync::Arc;
 use image::DynamicImage;
 use log::info;
-use crate::tiff::errors::TiffResult;
+use crate::tiff::errors::{TiffError, TiffResult};
+use crate::dataset::Dataset;
+use crate::tiff::dataset_cache::{DatasetCache, ParsedDataset, DEFAULT_CACHED_DATASETS};
+use crate::utils::chip_utils::ChipOptions;
 use crate::utils::logger::Logger;
-use crate::extractor::{Region, ImageExtractor};
-use crate::coordinate::BoundingBox;
+use crate::utils::postgis_utils::PostgisOptions;
+use crate::extractor::{BufferLayout, Centre, DecodedWindow, ExtractOptions, Region, ImageExtractor, StretchMode, WindowBlock};
+use crate::coordinate::{BoundingBox, Crs};
+use crate::utils::coordinate_utils;
 use crate::compression::CompressionConverter;
 use crate::compression::CompressionFactory;
 
 /// Main interface to the RasterKit library
+///
+/// A RasterKit is `Send + Sync`: one instance can be shared, e.g. in an
+/// `Arc`, by all the request handlers of a server. Parsed file headers are
+/// cached across calls and threads, and parsed again when a file changes
+/// on disk.
 pub struct RasterKit {
     logger: Logger,
+    /// Parsed headers of recently used files
+    datasets: DatasetCache,
 }
 
 impl RasterKit {
@@ -24,7 +37,45 @@ impl RasterKit {
     pub fn new(log_file: Option<&str>) -> TiffResult<Self> {
         let log_path = log_file.unwrap_or("rasterkit.log");
         let logger = Logger::new(log_path)?;
-        Ok(RasterKit { logger })
+        Ok(RasterKit { logger, datasets: DatasetCache::new(DEFAULT_CACHED_DATASETS) })
+    }
+
+    /// Look up the parsed header of a file in the dataset cache
+    fn dataset(&self, input_path: &str) -> TiffResult<Arc<ParsedDataset>> {
+        self.datasets.open(input_path, &self.logger)
+    }
+
+    /// Open a file for several operations
+    ///
+    /// The header is parsed once, or taken from the cache, and kept by the
+    /// returned handle.
+    ///
+    /// # Arguments
+    /// * `input_path` - Path to the TIFF file
+    ///
+    /// # Returns
+    /// A handle to the parsed file, or an error if it is not a readable TIFF
+    pub fn open(&self, input_path: &str) -> TiffResult<Dataset<'_>> {
+        info!("Opening dataset {}", input_path);
+        Ok(Dataset::new(self, self.dataset(input_path)?))
+    }
+
+    /// Logger of this instance
+    pub(crate) fn logger(&self) -> &Logger {
+        &self.logger
+    }
+
+    /// Number of files whose parsed headers are cached
+    pub fn cached_datasets(&self) -> usize {
+        self.datasets.len()
+    }
+
+    /// Forget every cached file header
+    ///
+    /// Files are parsed again on their next use. Changed files are noticed
+    /// without this, by their modification time and length.
+    pub fn clear_dataset_cache(&self) {
+        self.datasets.clear();
     }
 
     /// Analyze a TIFF file and return information about its structure
@@ -35,197 +86,146 @@ impl RasterKit {
     /// # Returns
     /// String containing analysis information or an error
     pub fn analyze(&self, input_path: &str) -> TiffResult<String> {
-        // Create a TIFF reader and load the file directly
-        let mut reader = crate::tiff::TiffReader::new(&self.logger);
-        let tiff = reader.load(input_path)?;
-
-        // Format a summary of the file
-        let mut result = format!("TIFF Analysis Results:\n");
-        result.push_str(&format!("  Format: {}\n", if tiff.is_big_tiff { "BigTIFF" } else { "TIFF" }));
-        result.push_str(&format!("  Number of IFDs: {}\n", tiff.ifd_count()));
-
-        // Add details for each IFD
-        for (i, ifd) in tiff.ifds.iter().enumerate() {
-            result.push_str(&format!("\nIFD #{} (offset: {})\n", i, ifd.offset));
-            result.push_str(&format!("  Number of entries: {}\n", ifd.entries.len()));
-
-            if let Some((width, height)) = ifd.get_dimensions() {
-                result.push_str(&format!("  Dimensions: {}x{}\n", width, height));
-            }
-
-            result.push_str(&format!("  Samples per pixel: {}\n", ifd.get_samples_per_pixel()));
-
-            // Add compression info
-            if let Some(entry) = ifd.get_entry(crate::tiff::constants::tags::COMPRESSION) {
-                result.push_str(&format!("  Compression: {} ({})\n",
-                                         entry.value_offset,
-                                         crate::utils::tiff_code_translators::compression_code_to_name(entry.value_offset)));
-            }
-        }
+        Ok(self.open(input_path)?.analyze())
+    }
 
-        Ok(result)
+    /// Describe a TIFF file in the layout of gdalinfo
+    ///
+    /// # Arguments
+    /// * `input_path` - Path to the TIFF file to describe
+    ///
+    /// # Returns
+    /// The summary (driver, size, CRS, corner coordinates, bands) or an error
+    pub fn info(&self, input_path: &str) -> TiffResult<String> {
+        crate::utils::info_utils::raster_info(input_path, &self.logger)
     }
 
     /// Extract an image from a TIFF file
     ///
-    /// This method provides several ways to specify the region to extract:
+    /// The options pick what to extract, by one of:
     /// - Pixel region with (x, y, width, height)
     /// - Geographic bounding box with "minx,miny,maxx,maxy"
     /// - Geographic coordinate and radius with (x,y) + radius in meters
     ///
-    /// The coordinate and radius approach allows extracting circular or square regions
-    /// around a specific point of interest.
+    /// and how to present it: a colormap, a value filter, a circular cut, a
+    /// stretch of chosen bands and the output format.
     ///
     /// # Arguments
     /// * `input_path` - Path to the input TIFF file
     /// * `output_path` - Path where to save the extracted image
-    /// * `region` - Optional pixel region to extract (x, y, width, height)
-    /// * `bbox` - Optional geographic bounding box as "minx,miny,maxx,maxy"
-    /// * `coordinate` - Optional geographic coordinate as "x,y"
-    /// * `radius` - Optional radius in meters around the coordinate
-    /// * `shape` - Optional shape for coordinate-based extraction ("circle" or "square")
-    /// * `crs` - Optional CRS code for the bounding box/coordinate coordinates
-    /// * `colormap_path` - Optional path to a colormap file to apply
-    /// * `filter_range` - Optional value range to filter (e.g., "15,160")
-    /// * `filter_transparency` - Whether to make filtered pixels transparent
+    /// * `options` - What to extract and how
     ///
     /// # Returns
     /// Result indicating success or an error
-    pub fn extract(&self,
-                   input_path: &str,
-                   output_path: &str,
-                   region: Option<(u32, u32, u32, u32)>,
-                   bbox: Option<&str>,
-                   coordinate: Option<&str>,
-                   radius: Option<f64>,
-                   shape: Option<&str>,
-                   crs: Option<u32>,
-                   colormap_path: Option<&str>,
-                   filter_range: Option<&str>,
-                   filter_transparency: bool) -> TiffResult<()> {
-
-        // Handle coordinate + radius extraction by converting to a bounding box
-        let effective_bbox = if let (Some(coord_str), Some(rad)) = (coordinate, radius) {
-            let shape_type = shape.unwrap_or("square");
-            info!("Using coordinate-based extraction with {} meters radius (shape: {})",
-              rad, shape_type);
-
-            match crate::utils::coordinate_utils::coord_to_bbox(coord_str, rad, shape_type, crs) {
-                Ok(bbox_str) => {
-                    info!("Converted coordinate to bounding box: {}", bbox_str);
-                    Some(bbox_str)
-                },
-                Err(e) => return Err(e),
-            }
-        } else {
-            bbox.map(|s| {
-                info!("Using bounding box extraction: {}", s);
-                s.to_string()
-            })
-        };
+    pub fn extract(&self, input_path: &str, output_path: &str, options: &ExtractOptions) -> TiffResult<()> {
+        let shape = options.shape.as_deref();
 
         // If a colormap is specified, handle with colormap extraction
-        if let Some(cmap_path) = colormap_path {
+        if let (Some(cmap_path), false) = (options.colormap.as_deref(), options.renders()) {
             info!("Colormap specified, using colormap extraction with '{}'", cmap_path);
 
             // First determine the extraction region
-            let extraction_region = self.determine_extraction_region(input_path, region, effective_bbox.as_deref(), crs)?;
+            let extraction_region = self.options_region(input_path, options)?;
 
             // Convert the Region to the tuple format expected by extract_with_colormap
             let region_tuple = extraction_region.map(|r| (r.x, r.y, r.width, r.height));
 
-            // Use the extract_with_colormap function
+            let (filter_range, filter_transparency) = options.filter.as_ref()
+                .map_or((None, false), |(range, transparent)| (Some(range.as_str()), *transparent));
             return self.extract_with_colormap(input_path, output_path, cmap_path, region_tuple, shape, filter_range, filter_transparency);
         }
 
-        // Regular extraction without colormap
-        let mut extractor = ImageExtractor::new(&self.logger);
-
-        // Determine the extraction region
-        let extraction_region = self.determine_extraction_region(input_path, region, effective_bbox.as_deref(), crs)?;
-
-        // Check if we need to filter the values
-        if let Some(range_str) = filter_range {
-            use crate::utils::filter_utils;
-
-            // Extract image to memory first
-            let image = extractor.extract_image(input_path, extraction_region)?;
-
-            // Parse and apply filter
-            if let Ok((min_value, max_value)) = filter_utils::parse_filter_range(range_str) {
-                info!("Applying filter range {}-{}", min_value, max_value);
+        // Filtered and rendered images are built in memory first
+        if options.filter.is_some() || options.renders() {
+            let image = self.extract_to_buffer(input_path, options)?;
+            return crate::utils::mask_utils::save_shaped_image(&image, output_path, shape.unwrap_or("square"), options.output_format);
+        }
 
-                // Apply filter
-                let filtered_image = filter_utils::filter_image_values(
-                    &image,
-                    min_value,
-                    max_value,
-                    0,  // Background value
-                    filter_transparency
-                );
+        // Perform the extraction
+        let extraction_region = self.options_region(input_path, options)?;
+        let mut extractor = ImageExtractor::new(&self.logger);
+        extractor.set_output_format(options.output_format);
+        extractor.extract_to_file(input_path, output_path, extraction_region, shape)
+    }
 
-                // Apply shape mask if needed
-                let final_image = if let Some(shape_str) = shape {
-                    if shape_str.to_lowercase() == "circle" {
-                        crate::utils::mask_utils::apply_shape_mask(&filtered_image, shape_str)
-                    } else {
-                        filtered_image
-                    }
-                } else {
-                    filtered_image
+    /// Resolve the region to extract from extraction options
+    ///
+    /// A coordinate and radius are turned into a bounding box first.
+    ///
+    /// # Arguments
+    /// * `input_path` - Path to the input TIFF file
+    /// * `options` - Options holding a region, bounding box or coordinate
+    ///
+    /// # Returns
+    /// An optional Region for extraction, or None to extract the entire image
+    fn options_region(&self, input_path: &str, options: &ExtractOptions) -> TiffResult<Option<Region>> {
+        let effective_bbox = match options.coordinate {
+            Some((centre, rad)) => {
+                let shape_type = options.shape.as_deref().unwrap_or("square");
+                info!("Using coordinate-based extraction with {} meters radius (shape: {})", rad, shape_type);
+
+                let (bbox_str, crs) = match centre {
+                    Centre::LonLat(position) => {
+                        let bbox_str = coordinate_utils::coord_to_bbox(
+                            &position.to_string(), rad, shape_type, Some(Crs::WGS84.epsg()))?;
+                        (bbox_str, Crs::WGS84)
+                    },
+                    Centre::Projected(position) => {
+                        let crs = options.crs.ok_or_else(|| TiffError::GenericError(
+                            "A projected coordinate needs the CRS it is in".to_string()))?;
+                        if crs.is_geographic() {
+                            return Err(TiffError::GenericError(format!(
+                                "{} is geographic; give the centre as a LonLat instead", crs)));
+                        }
+                        (coordinate_utils::projected_coord_to_bbox(&position.to_string(), rad)?, crs)
+                    },
                 };
+                info!("Converted coordinate to bounding box: {} ({})", bbox_str, crs);
+                let bbox = BoundingBox::from_string(&bbox_str).map_err(TiffError::GenericError)?;
+                Some(BoundingBox { epsg: Some(crs.epsg()), ..bbox })
+            },
+            None => options.bbox.map(|bbox| {
+                info!("Using bounding box extraction: {},{},{},{}", bbox.min_x, bbox.min_y, bbox.max_x, bbox.max_y);
+                BoundingBox { epsg: bbox.epsg.or(options.crs.map(Crs::epsg)), ..bbox }
+            }),
+        };
 
-                // Save the filtered image
-                return crate::utils::mask_utils::save_shaped_image(&final_image, output_path, shape.unwrap_or("square"));
-            }
-        }
-
-        // Perform the extraction (without filtering)
-        extractor.extract_to_file(input_path, output_path, extraction_region, shape)
+        self.determine_extraction_region(input_path, options.region, effective_bbox)
     }
 
     /// Helper method to determine extraction region from parameters
     ///
     /// Analyzes the provided extraction parameters and determines the
-    /// appropriate region to extract. Handles pixel coordinates, geographic
-    /// bounding boxes, and geographic coordinates with radius.
+    /// appropriate region to extract. Handles pixel coordinates and
+    /// bounding boxes in map coordinates.
     ///
     /// # Arguments
     /// * `input_path` - Path to the input TIFF file
     /// * `region` - Optional pixel region to extract (x, y, width, height)
-    /// * `bbox` - Optional geographic bounding box as "minx,miny,maxx,maxy"
-    /// * `crs` - Optional CRS code for the bounding box coordinates
+    /// * `bbox` - Optional bounding box, with the EPSG code of its coordinates
     ///
     /// # Returns
     /// An optional Region for extraction, or None to extract the entire image
     fn determine_extraction_region(&self,
                                    input_path: &str,
                                    region: Option<(u32, u32, u32, u32)>,
-                                   bbox: Option<&str>,
-                                   crs: Option<u32>) -> TiffResult<Option<Region>> {
+                                   bbox: Option<BoundingBox>) -> TiffResult<Option<Region>> {
         if let Some((x, y, width, height)) = region {
             info!("Using pixel region: x={}, y={}, width={}, height={}", x, y, width, height);
             Ok(Some(Region::new(x, y, width, height)))
-        } else if let Some(bbox_str) = bbox {
-            info!("Converting bounding box '{}' to pixel region", bbox_str);
-
-            // Parse bounding box and convert to pixel coordinates
-            let mut bbox = BoundingBox::from_string(bbox_str)
-                .map_err(|e| crate::tiff::errors::TiffError::GenericError(e))?;
-
-            // Set CRS code if provided
-            if let Some(code) = crs {
+        } else if let Some(bbox) = bbox {
+            info!("Converting bounding box {:?} to pixel region", bbox);
+            if let Some(code) = bbox.epsg {
                 info!("Using CRS code {} for coordinate transformation", code);
-                bbox.epsg = Some(code);
             }
 
-            // Create a reader to access the TIFF file
-            let mut reader = crate::tiff::TiffReader::new(&self.logger);
-            let tiff = reader.load(input_path)?;
+            // Read tag values through the cached header of the file
+            let dataset = self.dataset(input_path)?;
+            let reader = dataset.reader(&self.logger);
 
             // Determine the extraction region from the bounding box
             let region = crate::utils::image_extraction_utils::determine_extraction_region(
-                bbox, &tiff, &reader, input_path, &self.logger)?;
+                bbox, &dataset.tiff, &reader, input_path, &self.logger, None)?;
 
             Ok(Some(region))
         } else {
@@ -257,8 +257,27 @@ impl RasterKit {
         converter.convert_file(input_path, output_path, compression_code)
     }
 
+    /// Embed an ICC color profile in an existing TIFF file
+    ///
+    /// The profile is validated before the file is touched; a profile
+    /// already in the file is replaced.
+    ///
+    /// # Arguments
+    /// * `tiff_path` - TIFF file to update in place
+    /// * `profile_path` - Path to the `.icc` / `.icm` profile
+    ///
+    /// # Returns
+    /// Result indicating success or an error
+    pub fn embed_icc_profile(&self, tiff_path: &str, profile_path: &str) -> TiffResult<()> {
+        let profile = crate::utils::icc_utils::load_icc_profile(profile_path)?;
+        crate::utils::icc_utils::embed_icc_profile(tiff_path, &profile)
+    }
+
     /// Extract the colormap from a TIFF file
     ///
+    /// Every palette index is kept; see `extract_colormap_with_options`
+    /// to drop leading black entries.
+    ///
     /// # Arguments
     /// * `input_path` - Path to the input TIFF file
     /// * `output_path` - Path where to save the colormap
@@ -266,7 +285,164 @@ impl RasterKit {
     /// # Returns
     /// Result indicating success or an error
     pub fn extract_colormap(&self, input_path: &str, output_path: &str) -> TiffResult<()> {
-        crate::utils::colormap_utils::extract_colormap(input_path, output_path, &self.logger)
+        self.extract_colormap_with_options(input_path, output_path, false)
+    }
+
+    /// Extract the colormap from a TIFF file, optionally trimming leading black entries
+    ///
+    /// # Arguments
+    /// * `input_path` - Path to the input TIFF file
+    /// * `output_path` - Path where to save the colormap
+    /// * `trim_leading_black` - Drop leading pure-black palette entries (off keeps every index)
+    ///
+    /// # Returns
+    /// Result indicating success or an error
+    pub fn extract_colormap_with_options(&self, input_path: &str, output_path: &str,
+                                         trim_leading_black: bool) -> TiffResult<()> {
+        crate::utils::colormap_utils::extract_colormap(input_path, output_path, trim_leading_black, &self.logger)
+    }
+
+    /// Generate a classified colormap from the values of a raster
+    ///
+    /// Classes are derived from the native sample values, leaving out NoData
+    /// pixels; rasters with fractional or negative values cannot be
+    /// classified into a colormap. The colormap is saved as SLD and can be
+    /// applied with `extract_with_colormap`.
+    ///
+    /// # Arguments
+    /// * `input_path` - Path to the input TIFF file
+    /// * `output_path` - Path of the SLD file to write
+    /// * `spec` - "method[:classes[:scheme]]", e.g. "quantile:7:viridis"
+    /// * `region` - Optional pixel region to classify (x, y, width, height)
+    ///
+    /// # Returns
+    /// Result indicating success or an error
+    pub fn generate_colormap(&self,
+                             input_path: &str,
+                             output_path: &str,
+                             spec: &str,
+                             region: Option<(u32, u32, u32, u32)>) -> TiffResult<()> {
+        use crate::tiff::colormap::ColorMap;
+        use crate::utils::classification_utils::{self, ValueHistogram};
+
+        let (method, classes, scheme) = classification_utils::parse_auto_spec(spec)?;
+        let array = self.extract_array_data(input_path, region)?;
+
+        let histogram = ValueHistogram::from_samples(&array.data.to_f64(), array.nodata)?;
+        let colormap = ColorMap::from_statistics(&histogram, classes, method, scheme)?;
+
+        let layer_name = Path::new(input_path)
+            .file_stem()
+            .map(|s| s.to_string_lossy().to_string())
+            .unwrap_or_else(|| "layer".to_string());
+        colormap.to_sld_file(output_path, &layer_name)
+    }
+
+    /// Render a legend image for a colormap
+    ///
+    /// # Arguments
+    /// * `colormap_path` - Path to the colormap (SLD, CSV or palette TIFF)
+    /// * `output_path` - Path of the legend image (format from extension)
+    /// * `style` - "continuous" or "discrete"; None follows the colormap type
+    ///
+    /// # Returns
+    /// Result indicating success or an error
+    pub fn render_colormap_legend(&self,
+                                  colormap_path: &str,
+                                  output_path: &str,
+                                  style: Option<&str>) -> TiffResult<()> {
+        use crate::utils::legend_utils::{self, LegendStyle};
+
+        let style = style.map(LegendStyle::from_string).transpose()?;
+        let colormap = crate::utils::colormap_utils::load_colormap(colormap_path, &self.logger)?;
+        legend_utils::save_legend(&colormap, output_path, style)
+    }
+
+    /// Colorize an entire single-band raster with a colormap
+    ///
+    /// # Arguments
+    /// * `input_path` - Path to the input raster
+    /// * `output_path` - Path of the colorized output (GeoTIFF keeps georeferencing)
+    /// * `colormap_path` - Path to the colormap (SLD, CSV or palette TIFF)
+    ///
+    /// # Returns
+    /// Result indicating success or an error
+    pub fn colorize(&self, input_path: &str, output_path: &str, colormap_path: &str) -> TiffResult<()> {
+        use crate::utils::colormap_utils;
+
+        let colormap = colormap_utils::load_colormap(colormap_path, &self.logger)?;
+        colormap_utils::colorize_file(input_path, output_path, &colormap, &self.logger)
+    }
+
+    /// Convert a palette-color TIFF to RGB or to single-band class indices
+    ///
+    /// # Arguments
+    /// * `input_path` - Path to the palette TIFF
+    /// * `output_path` - Path of the uncompressed GeoTIFF to write
+    /// * `mode` - "rgb" to expand the palette colors, "index" to keep the indices
+    ///
+    /// # Returns
+    /// Result indicating success or an error
+    pub fn depalettize(&self, input_path: &str, output_path: &str, mode: &str) -> TiffResult<()> {
+        use crate::utils::depalettize_utils::{self, DepalettizeMode};
+
+        let mode = DepalettizeMode::from_string(mode)?;
+        depalettize_utils::depalettize_file(input_path, output_path, mode, &self.logger)
+    }
+
+    /// Add reduced-resolution overviews to a TIFF, as gdaladdo does
+    ///
+    /// # Arguments
+    /// * `input_path` - TIFF to add overviews to
+    /// * `output_path` - Path to write, or None to update the input in place
+    /// * `levels` - Reduction factors such as [2, 4, 8], or empty for the defaults
+    /// * `resampling` - "nearest", "bilinear" or "average"
+    ///
+    /// # Returns
+    /// The width and height of each overview, largest first
+    pub fn build_overviews(&self,
+                           input_path: &str,
+                           output_path: Option<&str>,
+                           levels: &[u32],
+                           resampling: &str) -> TiffResult<Vec<(u32, u32)>> {
+        use crate::utils::overview_utils;
+        use crate::utils::resize_utils::Resampling;
+
+        let resampling = Resampling::from_string(resampling)?;
+        overview_utils::build_overviews(input_path, output_path, levels, resampling, &self.logger)
+    }
+
+    /// Rescale, offset and/or merge colormap values and save the result as SLD
+    ///
+    /// # Arguments
+    /// * `colormap_path` - Path to the colormap (SLD, CSV or palette TIFF)
+    /// * `output_path` - Path of the SLD to write
+    /// * `rescale` - Optional "FROM_MIN:FROM_MAX:TO_MIN:TO_MAX" value rescaling
+    /// * `offset` - Optional constant added to every value after rescaling
+    /// * `merge_path` - Optional colormap merged in last; its entries win on equal values
+    ///
+    /// # Returns
+    /// Result indicating success or an error
+    pub fn transform_colormap(&self,
+                              colormap_path: &str,
+                              output_path: &str,
+                              rescale: Option<&str>,
+                              offset: Option<i32>,
+                              merge_path: Option<&str>) -> TiffResult<()> {
+        use crate::utils::colormap_utils::{self, ColormapTransform};
+
+        let transform = ColormapTransform {
+            rescale: rescale.map(colormap_utils::parse_rescale_spec).transpose()?,
+            offset,
+            merge: merge_path.map(str::to_string),
+        };
+        if transform.is_empty() {
+            return Err(crate::tiff::errors::TiffError::GenericError(
+                "No colormap transform requested (give a rescale, offset or merge)".to_string()));
+        }
+
+        colormap_utils::transform_colormap_file(colormap_path, output_path, &transform, &self.logger)?;
+        Ok(())
     }
 
     /// Apply a colormap to an image during extraction
@@ -329,7 +505,8 @@ impl RasterKit {
             input_path,
             extraction_region,
             &self.logger,
-            shape
+            shape,
+            None
         )
     }
 
@@ -347,7 +524,7 @@ impl RasterKit {
     /// # Arguments
     /// * `input_path` - Path to the input TIFF file
     /// * `output_path` - Path where to save the extracted array
-    /// * `format` - Format for the output (csv, json, or npy)
+    /// * `format` - Format for the output (csv, csv-long, json, npy, safetensors, sqlite or gpkg)
     /// * `region` - Optional pixel region to extract (x, y, width, height)
     ///
     /// # Returns
@@ -393,108 +570,141 @@ impl RasterKit {
         extractor.extract_array_data(input_path, extraction_region)
     }
 
-    /// Extract an image from a TIFF file to memory
+    /// Describe the window a decode into a caller buffer would produce
+    ///
+    /// # Arguments
+    /// * `input_path` - Path to the input TIFF file
+    /// * `region` - Optional pixel region to decode (x, y, width, height)
+    ///
+    /// # Returns
+    /// The window's shape and sample type, to size a buffer with
+    pub fn describe_window(&self,
+                           input_path: &str,
+                           region: Option<(u32, u32, u32, u32)>) -> TiffResult<DecodedWindow> {
+        let window = region.map(|(x, y, width, height)| Region::new(x, y, width, height));
+        crate::extractor::describe_window(input_path, window, &self.logger)
+    }
+
+    /// Decode raw samples straight into a caller-provided byte buffer
     ///
-    /// This method provides the same region specification options as `extract`,
-    /// but returns the image in memory instead of writing it to a file.
+    /// Unlike `extract_array_data`, no image is built on the way: samples
+    /// keep their stored width (in native byte order) and all bands, and
+    /// only the blocks the window touches are read.
     ///
     /// # Arguments
     /// * `input_path` - Path to the input TIFF file
-    /// * `region` - Optional pixel region to extract (x, y, width, height)
-    /// * `bbox` - Optional geographic bounding box as "minx,miny,maxx,maxy"
-    /// * `coordinate` - Optional geographic coordinate as "x,y"
-    /// * `radius` - Optional radius in meters around the coordinate
-    /// * `shape` - Optional shape for coordinate-based extraction ("circle" or "square")
-    /// * `crs` - Optional CRS code for the bounding box/coordinate coordinates
-    /// * `colormap_path` - Optional path to a colormap file to apply
-    /// * `filter_range` - Optional value range to filter (e.g., "15,160")
-    /// * `filter_transparency` - Whether to make filtered pixels transparent
+    /// * `region` - Optional pixel region to decode (x, y, width, height)
+    /// * `layout` - Interleaved or band-sequential sample order
+    /// * `buffer` - Buffer of at least `DecodedWindow::byte_len` bytes
     ///
     /// # Returns
-    /// Result containing the extracted image or an error
-    pub fn extract_to_buffer(&self,
+    /// The shape and sample type of what was decoded, or an error
+    pub fn decode_into_bytes(&self,
                              input_path: &str,
                              region: Option<(u32, u32, u32, u32)>,
-                             bbox: Option<&str>,
-                             coordinate: Option<&str>,
-                             radius: Option<f64>,
-                             shape: Option<&str>,
-                             crs: Option<u32>,
-                             colormap_path: Option<&str>,
-                             filter_range: Option<&str>,
-                             filter_transparency: bool) -> TiffResult<DynamicImage> {
-
-        // Handle coordinate + radius extraction by converting to a bounding box
-        let effective_bbox = if let (Some(coord_str), Some(rad)) = (coordinate, radius) {
-            let shape_type = shape.unwrap_or("square");
-            info!("Using coordinate-based extraction with {} meters radius (shape: {})",
-            rad, shape_type);
-
-            match crate::utils::coordinate_utils::coord_to_bbox(coord_str, rad, shape_type, crs) {
-                Ok(bbox_str) => {
-                    info!("Converted coordinate to bounding box: {}", bbox_str);
-                    Some(bbox_str)
-                },
-                Err(e) => return Err(e),
-            }
-        } else {
-            bbox.map(|s| {
-                info!("Using bounding box extraction: {}", s);
-                s.to_string()
-            })
-        };
-
-        // Determine the extraction region
-        let extraction_region = self.determine_extraction_region(input_path, region, effective_bbox.as_deref(), crs)?;
-
-        // Create an extractor instance
-        let mut extractor = ImageExtractor::new(&self.logger);
+                             layout: BufferLayout,
+                             buffer: &mut [u8]) -> TiffResult<DecodedWindow> {
+        let window = region.map(|(x, y, width, height)| Region::new(x, y, width, height));
+        crate::extractor::decode_into_bytes(input_path, window, layout, buffer, &self.logger)
+    }
 
-        // If a colormap is specified, handle with colormap extraction
-        if let Some(cmap_path) = colormap_path {
-            info!("Colormap specified, using colormap extraction with '{}'", cmap_path);
+    /// Decode every band of a multi-band window in its stored type
+    ///
+    /// # Arguments
+    /// * `input_path` - Path to the input TIFF file
+    /// * `region` - Optional pixel region to decode (x, y, width, height)
+    ///
+    /// # Returns
+    /// One set of samples per band, or none for single-band windows and
+    /// sub-byte samples
+    fn decode_bands(&self,
+                    input_path: &str,
+                    region: Option<(u32, u32, u32, u32)>) -> TiffResult<Vec<crate::extractor::ArraySamples>> {
+        use crate::extractor::ArraySamples;
+
+        let shape = self.describe_window(input_path, region)?;
+        if shape.bands < 2 || shape.bytes_per_sample == 0 {
+            return Ok(Vec::new());
+        }
 
-            // Extract image data to memory
-            let mut image = extractor.extract_image(input_path, extraction_region)?;
-
-            // Apply filtering if specified
-            if let Some(range_str) = filter_range {
-                use crate::utils::filter_utils;
-
-                if let Ok((min_value, max_value)) = filter_utils::parse_filter_range(range_str) {
-                    info!("Applying filter range {}-{}", min_value, max_value);
-
-                    // Apply filter
-                    image = filter_utils::filter_image_values(
-                        &image,
-                        min_value,
-                        max_value,
-                        0,
-                        filter_transparency
-                    );
-                }
-            }
+        crate::utils::memory_utils::check_fits(
+            &format!("The {}x{} window of {} bands", shape.width, shape.height, shape.bands), shape.byte_len() as u64)?;
+        let mut samples = vec![0u8; shape.byte_len()];
+        self.decode_into_bytes(input_path, region, BufferLayout::BandSequential, &mut samples)?;
+        let band_len = shape.width as usize * shape.height as usize * shape.bytes_per_sample;
+        Ok(samples.chunks_exact(band_len)
+            .map(|band| ArraySamples::from_native_bytes(band.to_vec(), &shape))
+            .collect())
+    }
 
-            // Apply colormap to the extracted image
-            let grayscale = image.to_luma8();
-            let colormap = crate::utils::colormap_utils::load_colormap(cmap_path, &self.logger)?;
-            let rgb_image = crate::utils::colormap_utils::apply_colormap_to_image(&grayscale, &colormap);
+    /// Decode sample values straight into a caller-provided float buffer
+    ///
+    /// # Arguments
+    /// * `input_path` - Path to the input TIFF file
+    /// * `region` - Optional pixel region to decode (x, y, width, height)
+    /// * `layout` - Interleaved or band-sequential sample order
+    /// * `buffer` - Buffer of at least `DecodedWindow::samples` values
+    ///
+    /// # Returns
+    /// The shape and sample type of what was decoded, or an error
+    pub fn decode_into_f32(&self,
+                           input_path: &str,
+                           region: Option<(u32, u32, u32, u32)>,
+                           layout: BufferLayout,
+                           buffer: &mut [f32]) -> TiffResult<DecodedWindow> {
+        let window = region.map(|(x, y, width, height)| Region::new(x, y, width, height));
+        crate::extractor::decode_into_f32(input_path, window, layout, buffer, &self.logger)
+    }
 
-            // Apply shape mask if needed
-            if let Some(shape_str) = shape {
-                if shape_str.to_lowercase() == "circle" {
-                    return Ok(crate::utils::mask_utils::apply_shape_mask(&DynamicImage::ImageRgb8(rgb_image), shape_str));
-                }
-            }
+    /// Stream a window to a callback one decoded block at a time
+    ///
+    /// For windows too large to hold: only the blocks the window touches
+    /// are read, and each is dropped once the callback returns, so memory
+    /// stays at about one block. Samples are laid out as for
+    /// `decode_into_bytes` with interleaved bands.
+    ///
+    /// # Arguments
+    /// * `input_path` - Path to the input TIFF file
+    /// * `region` - Optional pixel region to stream (x, y, width, height)
+    /// * `visit` - Receives the window's shape and each block; an error
+    ///   stops the extraction
+    ///
+    /// # Returns
+    /// The shape and sample type of the streamed window, or an error
+    pub fn extract_windowed(&self,
+                            input_path: &str,
+                            region: Option<(u32, u32, u32, u32)>,
+                            visit: impl FnMut(&DecodedWindow, &WindowBlock) -> TiffResult<()>) -> TiffResult<DecodedWindow> {
+        let window = region.map(|(x, y, width, height)| Region::new(x, y, width, height));
+        crate::extractor::extract_windowed(input_path, window, &self.logger, visit)
+    }
 
-            return Ok(DynamicImage::ImageRgb8(rgb_image));
-        }
+    /// Extract an image from a TIFF file to memory
+    ///
+    /// This method takes the same options as `extract`, but returns the
+    /// image in memory instead of writing it to a file; the output format
+    /// is not used.
+    ///
+    /// # Arguments
+    /// * `input_path` - Path to the input TIFF file
+    /// * `options` - What to extract and how
+    ///
+    /// # Returns
+    /// Result containing the extracted image or an error
+    pub fn extract_to_buffer(&self, input_path: &str, options: &ExtractOptions) -> TiffResult<DynamicImage> {
+        // Determine the extraction region
+        let extraction_region = self.options_region(input_path, options)?;
 
-        // Extract the image without colormap
-        let mut image = extractor.extract_image(input_path, extraction_region)?;
+        // Rendered bands come from the stored samples, the rest from the extractor
+        let mut image = if options.renders() {
+            let stretch = options.stretch.unwrap_or(StretchMode::Linear);
+            crate::utils::render_utils::render_window(input_path, extraction_region, &options.bands, stretch, &self.logger)?
+        } else {
+            ImageExtractor::new(&self.logger).extract_image(input_path, extraction_region)?
+        };
 
         // Apply filtering if specified
-        if let Some(range_str) = filter_range {
+        if let Some((range_str, filter_transparency)) = &options.filter {
             use crate::utils::filter_utils;
 
             if let Ok((min_value, max_value)) = filter_utils::parse_filter_range(range_str) {
@@ -506,13 +716,22 @@ impl RasterKit {
                     min_value,
                     max_value,
                     0,
-                    filter_transparency
+                    *filter_transparency
                 );
             }
         }
 
+        // If a colormap is specified, apply it to the extracted values
+        if let Some(cmap_path) = &options.colormap {
+            info!("Colormap specified, using colormap extraction with '{}'", cmap_path);
+
+            let grayscale = image.to_luma8();
+            let colormap = crate::utils::colormap_utils::load_colormap(cmap_path, &self.logger)?;
+            image = DynamicImage::ImageRgb8(crate::utils::colormap_utils::apply_colormap_to_image(&grayscale, &colormap));
+        }
+
         // Apply shape mask if needed
-        if let Some(shape_str) = shape {
+        if let Some(shape_str) = &options.shape {
             if shape_str.to_lowercase() == "circle" {
                 image = crate::utils::mask_utils::apply_shape_mask(&image, shape_str);
             }
@@ -520,4 +739,359 @@ impl RasterKit {
 
         Ok(image)
     }
-}
\ No newline at end of file
+
+    /// Compute a proximity (distance) raster
+    ///
+    /// For every pixel, computes the distance to the nearest target pixel of
+    /// the first band and writes the result as a float32 GeoTIFF.
+    ///
+    /// # Arguments
+    /// * `input_path` - Path to the input TIFF file
+    /// * `output_path` - Path where to save the proximity raster
+    /// * `target_values` - Optional target values (e.g., "1,3,10-12"); defaults to all non-zero pixels
+    /// * `use_map_units` - Whether to measure distances in map units instead of pixels
+    /// * `max_distance` - Optional maximum distance; farther pixels are written as NoData
+    ///
+    /// # Returns
+    /// Result indicating success or an error
+    pub fn proximity(&self,
+                     input_path: &str,
+                     output_path: &str,
+                     target_values: Option<&str>,
+                     use_map_units: bool,
+                     max_distance: Option<f64>) -> TiffResult<()> {
+        use crate::utils::proximity_utils::{self, ProximityOptions};
+
+        let options = ProximityOptions {
+            target_values: match target_values {
+                Some(values) => proximity_utils::parse_target_values(values)?,
+                None => Vec::new(),
+            },
+            use_map_units,
+            max_distance,
+            ..ProximityOptions::default()
+        };
+
+        proximity_utils::create_proximity_raster(input_path, output_path, &options, &self.logger)
+    }
+
+    /// Fill NoData gaps by interpolation
+    ///
+    /// Interpolates NoData pixels of every band from valid pixels within the
+    /// search distance (inverse distance weighting) and writes the result as
+    /// a GeoTIFF of the source's sample type.
+    ///
+    /// # Arguments
+    /// * `input_path` - Path to the input TIFF file
+    /// * `output_path` - Path where to save the filled raster
+    /// * `max_search_distance` - Maximum search distance in pixels
+    /// * `smoothing_iterations` - Number of smoothing passes over filled pixels
+    /// * `nodata` - Optional NoData value overriding the one in the file
+    ///
+    /// # Returns
+    /// Result indicating success or an error
+    pub fn fill_nodata(&self,
+                       input_path: &str,
+                       output_path: &str,
+                       max_search_distance: u32,
+                       smoothing_iterations: u32,
+                       nodata: Option<f64>) -> TiffResult<()> {
+        use crate::utils::fillnodata_utils::{self, FillNodataOptions};
+
+        let options = FillNodataOptions {
+            max_search_distance,
+            smoothing_iterations,
+            nodata_value: nodata,
+        };
+
+        fillnodata_utils::fill_nodata_file(input_path, output_path, &options, &self.logger)
+    }
+
+    /// Cut a raster into fixed-size chips for machine learning
+    ///
+    /// Writes the chips to `output_dir/images` (and `output_dir/labels` when a
+    /// label raster is given) together with an `index.csv`.
+    ///
+    /// # Arguments
+    /// * `input_path` - Path to the input TIFF file
+    /// * `output_dir` - Directory where to save the chips
+    /// * `options` - Chip size, overlap, format, labels and normalization
+    ///
+    /// # Returns
+    /// The number of chips written or an error
+    pub fn export_chips(&self,
+                        input_path: &str,
+                        output_dir: &str,
+                        options: &ChipOptions) -> TiffResult<usize> {
+        crate::utils::chip_utils::export_chips(input_path, output_dir, options, &self.logger)
+    }
+
+    /// Extract array data as a normalized float32 tensor
+    ///
+    /// Pixel values are scaled per band (min/max to [0, 1] or z-score) and
+    /// written as NPY, NPZ or safetensors, with shape (h, w) or (h, w, bands).
+    /// NoData pixels are left out of the statistics and written as NaN. NPZ
+    /// and safetensors outputs also record the statistics used so the
+    /// scaling can be reproduced.
+    ///
+    /// # Arguments
+    /// * `input_path` - Path to the input TIFF file
+    /// * `output_path` - Path where to save the tensor
+    /// * `format` - Tensor format ("npy", "npz" or "safetensors")
+    /// * `method` - Normalization method ("minmax" or "zscore")
+    /// * `region` - Optional pixel region to extract (x, y, width, height)
+    ///
+    /// # Returns
+    /// Result indicating success or an error
+    pub fn extract_normalized_array(&self,
+                                    input_path: &str,
+                                    output_path: &str,
+                                    format: &str,
+                                    method: &str,
+                                    region: Option<(u32, u32, u32, u32)>) -> TiffResult<()> {
+        use crate::utils::normalize_utils::{self, NormalizationMethod};
+
+        let method = NormalizationMethod::from_string(method)?;
+        info!("Extracting {} normalized array from {} to {} in {} format",
+              method.name(), input_path, output_path, format);
+
+        let mut array = self.extract_array_data(input_path, region)?;
+        if array.bands.is_empty() {
+            array.bands = self.decode_bands(input_path, region)?;
+        }
+
+        // Multi-band rasters are normalized per band, in HWC order
+        let (height, width) = (array.height as usize, array.width as usize);
+        let (shape, data) = match array.bands.len() {
+            0 | 1 => (vec![height, width], array.data.to_f64()),
+            band_count => {
+                let bands: Vec<Vec<f64>> = array.bands.iter().map(|band| band.to_f64()).collect();
+                let data = (0..height * width).flat_map(|pixel| bands.iter().map(move |band| band[pixel])).collect();
+                (vec![height, width, band_count], data)
+            },
+        };
+        let band_count = shape.get(2).copied().unwrap_or(1);
+        normalize_utils::save_normalized(output_path, format, &shape, &data, band_count, array.nodata, method)?;
+
+        Ok(())
+    }
+
+    /// Extract elevations converted to another linear unit
+    ///
+    /// The source unit comes from the VerticalUnitsGeoKey, defaulting to
+    /// meters when the file does not declare one. Values are written as
+    /// float32 (CSV, NPY, NPZ or safetensors).
+    ///
+    /// # Arguments
+    /// * `input_path` - Path to the input DEM
+    /// * `output_path` - Path where to save the elevations
+    /// * `format` - Output format ("csv", "npy", "npz" or "safetensors")
+    /// * `units` - Target unit ("m", "ft" or "us-ft")
+    /// * `region` - Optional pixel region to extract (x, y, width, height)
+    ///
+    /// # Returns
+    /// Result indicating success or an error
+    pub fn extract_elevation_array(&self,
+                                   input_path: &str,
+                                   output_path: &str,
+                                   format: &str,
+                                   units: &str,
+                                   region: Option<(u32, u32, u32, u32)>) -> TiffResult<()> {
+        use crate::utils::elevation_utils::{self, LinearUnit};
+
+        let target = LinearUnit::from_string(units)?;
+
+        let vertical_units = self.dataset(input_path)?.geo_info.as_ref()
+            .map_or(0, |info| info.vertical_units_code);
+        let source = match vertical_units {
+            0 => {
+                log::warn!("{} declares no vertical units, assuming meters", input_path);
+                LinearUnit::Meter
+            },
+            code => LinearUnit::from_geokey(code).ok_or_else(|| crate::tiff::errors::TiffError::GenericError(
+                format!("Cannot convert elevations in vertical unit code {}", code)))?,
+        };
+
+        info!("Extracting elevations from {} to {} in {} format ({} -> {})",
+              input_path, output_path, format, source.name(), target.name());
+
+        let array = self.extract_array_data(input_path, region)?;
+        let values = elevation_utils::convert_elevations(&array.data.to_f64(), source, target, array.nodata);
+        let shape = [array.height as usize, array.width as usize];
+        elevation_utils::save_elevations(output_path, format, &shape, &values, target)
+    }
+
+    /// Read the radiometric scaling of a TIFF's first band
+    ///
+    /// The scaling comes from the `SCALE` and `OFFSET` items GDAL writes to
+    /// the GDAL_METADATA tag. Arrays hold the first band, so only its
+    /// scaling is returned.
+    ///
+    /// # Arguments
+    /// * `input_path` - Path to the input TIFF file
+    ///
+    /// # Returns
+    /// The scale and offset, or None if the file declares no scaling or
+    /// one that leaves values unchanged
+    pub fn radiometric_scaling(&self, input_path: &str) -> TiffResult<Option<(f64, f64)>> {
+        use crate::utils::{scaling_utils, tiff_extraction_utils};
+
+        let dataset = self.dataset(input_path)?;
+        let reader = dataset.reader(&self.logger);
+        let bands = dataset.tiff.ifds.first()
+            .and_then(|ifd| tiff_extraction_utils::extract_gdal_metadata(ifd, &reader))
+            .map(|xml| scaling_utils::scaling_from_gdal_metadata(&xml))
+            .unwrap_or_default();
+
+        if bands.len() > 1 {
+            info!("{} declares scaling for {} bands, using band 1", input_path, bands.len());
+        }
+        Ok(bands.first().filter(|band| !band.is_identity()).map(|band| (band.scale, band.offset)))
+    }
+
+    /// Extract array data scaled to physical units
+    ///
+    /// Stored values are mapped through `value * scale + offset` and
+    /// written as float32 (CSV, NPY, NPZ or safetensors); NoData cells keep
+    /// their stored value.
+    ///
+    /// # Arguments
+    /// * `input_path` - Path to the input TIFF file
+    /// * `output_path` - Path where to save the scaled values
+    /// * `format` - Output format ("csv", "npy", "npz" or "safetensors")
+    /// * `scale` - Factor applied to the stored values
+    /// * `offset` - Value added after scaling
+    /// * `region` - Optional pixel region to extract (x, y, width, height)
+    ///
+    /// # Returns
+    /// Result indicating success or an error
+    pub fn extract_scaled_array(&self,
+                                input_path: &str,
+                                output_path: &str,
+                                format: &str,
+                                scale: f64,
+                                offset: f64,
+                                region: Option<(u32, u32, u32, u32)>) -> TiffResult<()> {
+        use crate::utils::scaling_utils::{self, BandScaling};
+
+        info!("Extracting scaled values from {} to {} in {} format", input_path, output_path, format);
+
+        let scaling = BandScaling { scale, offset };
+        let array = self.extract_array_data(input_path, region)?;
+        let values = scaling_utils::apply_scaling(&array.data.to_f64(), scaling, array.nodata);
+        let shape = [array.height as usize, array.width as usize];
+        scaling_utils::save_scaled(output_path, format, &shape, &values, scaling)
+    }
+
+    /// Extract array data labelled with map coordinates
+    ///
+    /// Like `extract_to_array`, but CSV and JSON outputs carry the X/Y map
+    /// coordinates of the cell centers instead of bare row/column indices.
+    ///
+    /// # Arguments
+    /// * `input_path` - Path to the input GeoTIFF file
+    /// * `output_path` - Path where to save the extracted array
+    /// * `format` - Format for the output (csv or json)
+    /// * `region` - Optional pixel region to extract (x, y, width, height)
+    ///
+    /// # Returns
+    /// Result indicating success or an error
+    pub fn extract_to_array_with_coordinates(&self,
+                                             input_path: &str,
+                                             output_path: &str,
+                                             format: &str,
+                                             region: Option<(u32, u32, u32, u32)>) -> TiffResult<()> {
+        info!("Extracting georeferenced array data from {} to {} in {} format",
+              input_path, output_path, format);
+
+        let array = self.extract_array_data(input_path, region)?;
+        array.save_with_coordinates(output_path, format)
+    }
+
+    /// Extract pixel values into a SQLite database or GeoPackage
+    ///
+    /// Every pixel that is not NoData becomes a row of the `pixels` table,
+    /// ready to be queried with SQL. The optional spatial index is an
+    /// R*Tree table (`pixels_rtree`) over the pixel footprints.
+    ///
+    /// # Arguments
+    /// * `input_path` - Path to the input TIFF file
+    /// * `output_path` - Path of the database to write
+    /// * `geopackage` - Write a GeoPackage instead of a plain SQLite database
+    /// * `spatial_index` - Build the R*Tree spatial index
+    /// * `region` - Optional pixel region to extract (x, y, width, height)
+    ///
+    /// # Returns
+    /// The number of rows written, or an error
+    pub fn extract_to_database(&self,
+                               input_path: &str,
+                               output_path: &str,
+                               geopackage: bool,
+                               spatial_index: bool,
+                               region: Option<(u32, u32, u32, u32)>) -> TiffResult<usize> {
+        info!("Extracting pixel values from {} into {} {}",
+              input_path, if geopackage { "GeoPackage" } else { "SQLite database" }, output_path);
+
+        let array = self.extract_array_data(input_path, region)?;
+        array.save_as_sqlite(output_path, geopackage, spatial_index)
+    }
+
+    /// Extract pixel values as a PostGIS raster loading script
+    ///
+    /// Writes SQL compatible with `raster2pgsql` output that creates the
+    /// table (unless it is only appended to) and loads the raster as tiles.
+    /// Without an SRID in the options, the EPSG code of the input is used.
+    ///
+    /// # Arguments
+    /// * `input_path` - Path to the input TIFF file
+    /// * `output_path` - Path of the SQL script to write
+    /// * `options` - Target table, SRID, tiling, load mode and indexing
+    /// * `region` - Optional pixel region to extract (x, y, width, height)
+    ///
+    /// # Returns
+    /// The number of tiles written, or an error
+    pub fn extract_to_postgis(&self,
+                              input_path: &str,
+                              output_path: &str,
+                              options: &PostgisOptions,
+                              region: Option<(u32, u32, u32, u32)>) -> TiffResult<usize> {
+        info!("Extracting {} as PostGIS raster SQL for table {} to {}", input_path, options.table, output_path);
+
+        let array = self.extract_array_data(input_path, region)?;
+        array.save_as_postgis(output_path, options)
+    }
+
+    /// Extract pixel values to Parquet for analytical engines
+    ///
+    /// One row per valid pixel, split into row groups with min/max
+    /// statistics. Partitioning by tile or band writes a hive-style
+    /// directory tree (`tile_row=R/tile_col=C/part-0.parquet`) so that
+    /// DuckDB or Spark can prune whole files from partition filters.
+    ///
+    /// # Arguments
+    /// * `input_path` - Path to the input TIFF file
+    /// * `output_path` - Parquet file, or output directory when partitioned
+    /// * `partition_by` - "none", "tile" or "band"
+    /// * `tile_size` - Tile size for tile partitioning (default 256x256)
+    /// * `row_group_size` - Maximum number of rows per row group
+    /// * `region` - Optional pixel region to extract (x, y, width, height)
+    ///
+    /// # Returns
+    /// The number of rows written, or an error
+    pub fn extract_to_parquet(&self,
+                              input_path: &str,
+                              output_path: &str,
+                              partition_by: &str,
+                              tile_size: Option<(u32, u32)>,
+                              row_group_size: usize,
+                              region: Option<(u32, u32, u32, u32)>) -> TiffResult<usize> {
+        use crate::utils::parquet_utils::ParquetPartitioning;
+
+        let partitioning = ParquetPartitioning::from_string(partition_by, tile_size)?;
+        info!("Extracting pixel values from {} to Parquet {} ({:?}, {} rows per row group)",
+              input_path, output_path, partitioning, row_group_size);
+
+        let array = self.extract_array_data(input_path, region)?;
+        array.save_as_parquet(output_path, partitioning, row_group_size)
+    }
+}
@@ -11,19 +11,36 @@ use crate::tiff::TiffReader;
 use crate::tiff::errors::{TiffResult, TiffError};
 use crate::utils::logger::Logger;
 use crate::tiff::{is_geotiff_tag, get_tag_name, get_projected_cs_description};
-use crate::tiff::geo_key_parser::GeoKeyParser;
+use crate::tiff::geo_key_parser::{GeoKeyParser, GeoInfo};
 use crate::utils::tiff_code_translators::compression_code_to_name;
 use crate::compression::CompressionFactory;
 use crate::tiff::ifd::IFD;
 use crate::tiff::constants::{tags, geo_keys};
+use crate::utils::{acquisition_utils, exif_utils, footprint_utils, statistics_utils, tag_utils, tiff_extraction_utils};
+use crate::utils::footprint_utils::FootprintShape;
+use crate::utils::statistics_utils::BandReport;
 use crate::tiff::types::TIFF;
 
+/// Buckets of the histograms shown on the console
+const DISPLAYED_BUCKETS: usize = 16;
+
+/// Width of the longest bar of a displayed histogram
+const HISTOGRAM_BAR_WIDTH: usize = 40;
+
 /// Command for analyzing TIFF file structure
 pub struct AnalyzeCommand<'a> {
     /// Path to the input file
     input_file: String,
     /// Whether to enable verbose output
     verbose: bool,
+    /// Whether to compute per-band statistics
+    stats: bool,
+    /// Whether to save the statistics to the PAM sidecar
+    write_stats: bool,
+    /// Path of the GeoJSON footprint to write, if requested
+    footprint: Option<String>,
+    /// Rectangle or traced outline footprint
+    footprint_shape: FootprintShape,
     /// Logger for recording operations
     logger: &'a Logger,
 }
@@ -43,10 +60,16 @@ impl<'a> AnalyzeCommand<'a> {
             .clone();
 
         let verbose = args.get_flag("verbose");
+        let write_stats = args.get_flag("write-stats");
+        let stats = args.get_flag("stats") || write_stats;
 
         Ok(AnalyzeCommand {
             input_file,
             verbose,
+            stats,
+            write_stats,
+            footprint: args.get_one::<String>("footprint").cloned(),
+            footprint_shape: if args.get_flag("footprint-outline") { FootprintShape::Outline } else { FootprintShape::Rectangle },
             logger,
         })
     }
@@ -115,6 +138,40 @@ impl<'a> AnalyzeCommand<'a> {
             if entry.value_offset & 1 == 1 {
                 info!("    (Reduced resolution version)");
             }
+            if entry.value_offset & 4 == 4 {
+                info!("    (Transparency mask)");
+            }
+        }
+    }
+
+    /// Display the SubIFDs of an IFD
+    ///
+    /// Shows the child images (masks, overviews) that the SubIFDs tag points
+    /// to, with the index `--subifd` selects them by.
+    ///
+    /// # Arguments
+    /// * `ifd` - The parent IFD
+    /// * `index` - Index of the parent IFD in the TIFF file
+    fn display_sub_ifds(&self, ifd: &IFD, index: usize) {
+        if ifd.sub_ifds.is_empty() {
+            return;
+        }
+
+        info!("  SubIFDs: {}", ifd.sub_ifds.len());
+        for (sub_index, sub_ifd) in ifd.sub_ifds.iter().enumerate() {
+            let dimensions = sub_ifd.get_dimensions()
+                .map(|(width, height)| format!("{}x{}", width, height))
+                .unwrap_or_else(|| "unknown size".to_string());
+            let kind = match sub_ifd.get_tag_value(tags::NEW_SUBFILE_TYPE) {
+                Some(subfile_type) if subfile_type & 4 == 4 => "mask",
+                Some(subfile_type) if subfile_type & 1 == 1 => "reduced resolution",
+                _ => "image",
+            };
+            info!("    SubIFD #{}.{} (offset: {}): {}, {}, {} samples per pixel",
+                  index, sub_index, sub_ifd.offset, dimensions, kind, sub_ifd.get_samples_per_pixel());
+            if !sub_ifd.sub_ifds.is_empty() {
+                info!("      with {} nested SubIFDs", sub_ifd.sub_ifds.len());
+            }
         }
     }
 
@@ -160,12 +217,17 @@ impl<'a> AnalyzeCommand<'a> {
             self.display_tiepoint(ifd, byte_order_handler, file_path);
             self.display_geokey_directory(ifd, byte_order_handler, file_path);
             self.display_proj_string(ifd, byte_order_handler, file_path);
+            if let Ok(geo_info) = GeoKeyParser::extract_geo_info(ifd, byte_order_handler, file_path) {
+                self.display_vertical_crs(&geo_info);
+            }
         }
     }
 
     /// Display pixel scale information
     ///
-    /// Shows the pixel dimensions in map units from ModelPixelScale tag.
+    /// Shows the pixel dimensions from the ModelPixelScale tag in the units
+    /// declared by the GeoKeys, plus their size in meters when the units are
+    /// angular or non-metric.
     ///
     /// # Arguments
     /// * `ifd` - The IFD containing GeoTIFF information
@@ -174,10 +236,31 @@ impl<'a> AnalyzeCommand<'a> {
     fn display_pixel_scale(&self, ifd: &IFD,
                            byte_order_handler: &Box<dyn crate::io::byte_order::ByteOrderHandler>,
                            file_path: &str) {
-        if let Ok(pixel_scale) = GeoKeyParser::read_model_pixel_scale_values(ifd, byte_order_handler, file_path) {
-            if pixel_scale.len() >= 3 {
-                info!("  Pixel Size: X={:.6} Y={:.6} meters (Z={:.6})",
-                      pixel_scale[0], pixel_scale[1], pixel_scale[2]);
+        let Ok(pixel_scale) = GeoKeyParser::read_model_pixel_scale_values(ifd, byte_order_handler, file_path) else {
+            return;
+        };
+        if pixel_scale.len() < 3 {
+            return;
+        }
+
+        let Ok(geo_info) = GeoKeyParser::extract_geo_info(ifd, byte_order_handler, file_path) else {
+            info!("  Pixel Size: X={:.6} Y={:.6} (Z={:.6})", pixel_scale[0], pixel_scale[1], pixel_scale[2]);
+            return;
+        };
+
+        info!("  Pixel Size: X={:.6} Y={:.6} {} (Z={:.6})",
+              pixel_scale[0], pixel_scale[1], geo_info.pixel_size_units(), pixel_scale[2]);
+
+        // Ground size of angular or non-metric pixels, at the image center
+        let rows = ifd.get_dimensions().map(|(_, h)| h as f64).unwrap_or(0.0);
+        let center_latitude = geo_info.origin_y - rows / 2.0 * geo_info.pixel_size_y;
+        if geo_info.pixel_size_units() != "meters" {
+            if let Some((x_m, y_m)) = geo_info.pixel_size_meters(center_latitude) {
+                if geo_info.is_geographic() {
+                    info!("    ≈ {:.3} x {:.3} meters at latitude {:.4}", x_m, y_m, center_latitude);
+                } else {
+                    info!("    = {:.6} x {:.6} meters", x_m, y_m);
+                }
             }
         }
     }
@@ -245,12 +328,160 @@ impl<'a> AnalyzeCommand<'a> {
                            byte_order_handler: &Box<dyn crate::io::byte_order::ByteOrderHandler>,
                            file_path: &str) {
         if let Ok(geo_info) = GeoKeyParser::extract_geo_info(ifd, byte_order_handler, file_path) {
-            let proj_string = GeoKeyParser::format_projection_string(&geo_info);
+            let proj_string = GeoKeyParser::format_proj4_string(&geo_info)
+                .unwrap_or_else(|| GeoKeyParser::format_projection_string(&geo_info));
             info!("  PROJ.4 String:");
             info!("    {}", proj_string);
+
+            if self.verbose {
+                if let Some(projjson) = GeoKeyParser::format_projjson(&geo_info) {
+                    info!("  PROJJSON:");
+                    info!("    {}", projjson);
+                }
+            }
         }
     }
 
+    /// Display the vertical coordinate system
+    ///
+    /// Reports the vertical CRS, datum and units declared in the GeoKeys,
+    /// which tell how elevation values in a DEM are to be read.
+    ///
+    /// # Arguments
+    /// * `geo_info` - Geospatial information parsed from the GeoKeys
+    fn display_vertical_crs(&self, geo_info: &GeoInfo) {
+        if !geo_info.has_vertical_crs() {
+            return;
+        }
+
+        info!("  Vertical CRS:");
+        if let Some(description) = geo_info.vertical_cs_description() {
+            info!("    System: {}", description);
+        }
+        if geo_info.vertical_datum_code > 0 {
+            info!("    Datum: {}", geo_info.vertical_datum_code);
+        }
+        match geo_info.vertical_units_name() {
+            Some(units) => info!("    Units: {}", units),
+            None => info!("    Units: not declared (assumed meters)"),
+        }
+    }
+
+    /// Display capture metadata (camera, EXIF, GPS and XMP)
+    ///
+    /// Shows the camera make and model, the EXIF and GPS directories with
+    /// the decoded camera position, and the size of any XMP packet. The
+    /// XMP packet itself is printed in verbose mode.
+    ///
+    /// # Arguments
+    /// * `reader` - TIFF reader for accessing tag data
+    /// * `ifd` - The IFD to inspect
+    fn display_capture_metadata(&self, reader: &TiffReader, ifd: &IFD) {
+        for tag in [tags::MAKE, tags::MODEL] {
+            if let Some(text) = reader.read_tag::<String>(ifd, tag).ok().and_then(|strings| strings.into_iter().next()) {
+                info!("  {}: {}", tag_utils::get_tag_name(tag), text.trim());
+            }
+        }
+
+        for pointer_tag in [tags::EXIF_IFD, tags::GPS_IFD] {
+            match exif_utils::read_directory(reader, ifd, pointer_tag) {
+                Ok(Some(entries)) => {
+                    info!("  {} ({} entries):", tag_utils::get_tag_name(pointer_tag), entries.len());
+                    for entry in &entries {
+                        info!("    {} ({}): {}", exif_utils::tag_name(pointer_tag, entry.tag),
+                              entry.tag, exif_utils::format_value(entry));
+                    }
+
+                    if pointer_tag == tags::GPS_IFD {
+                        if let Some((lat, lon, alt)) = exif_utils::gps_position(&entries) {
+                            match alt {
+                                Some(alt) => info!("    → Position: {:.7}, {:.7} at {:.1} m", lat, lon, alt),
+                                None => info!("    → Position: {:.7}, {:.7}", lat, lon),
+                            }
+                        }
+                    }
+                },
+                Ok(None) => {},
+                Err(e) => info!("  {}: unreadable ({})", tag_utils::get_tag_name(pointer_tag), e),
+            }
+        }
+
+        if let Some(xmp) = exif_utils::read_xmp(reader, ifd) {
+            info!("  XMP packet: {} bytes", xmp.len());
+            if self.verbose {
+                info!("{}", String::from_utf8_lossy(&xmp).trim_end_matches('\0'));
+            }
+        }
+    }
+
+    /// Display the acquisition time
+    ///
+    /// Shows each recorded time with where it came from, and which of them
+    /// is taken as the acquisition time.
+    ///
+    /// # Arguments
+    /// * `reader` - TIFF reader for accessing tag data
+    /// * `ifd` - The IFD to inspect
+    fn display_acquisition(&self, reader: &TiffReader, ifd: &IFD) {
+        let times = acquisition_utils::read_acquisition(reader, ifd);
+        let Some(acquired) = times.acquired() else {
+            return;
+        };
+
+        info!("  Acquired: {}", acquired);
+        for (source, time) in [("GDAL ACQUISITIONDATETIME", times.acquisition_date_time),
+                               ("EXIF DateTimeOriginal", times.date_time_original),
+                               ("DateTime", times.date_time)] {
+            if let Some(time) = time {
+                info!("    {}: {}", source, time);
+            }
+        }
+    }
+
+    /// Display per-band statistics of the main image
+    ///
+    /// Shows the minimum, maximum, mean and standard deviation of each band
+    /// with a coarse histogram, all computed from the stored values with
+    /// NoData excluded. The full histogram is printed in verbose mode.
+    ///
+    /// # Arguments
+    /// * `reader` - TIFF reader for accessing tag data
+    /// * `ifd` - The main IFD
+    ///
+    /// # Returns
+    /// The statistics of each band
+    fn display_statistics(&self, reader: &TiffReader, ifd: &IFD) -> TiffResult<Vec<BandReport>> {
+        let nodata = if ifd.has_tag(tags::GDAL_NODATA) {
+            tiff_extraction_utils::extract_nodata_value(ifd, reader).trim().parse::<f64>().ok()
+        } else {
+            None
+        };
+        let reports = statistics_utils::compute_statistics(&self.input_file, nodata, self.logger)?;
+
+        let approximate = reports.iter().any(|report| report.approximate);
+        info!("\nBand Statistics{}:", if approximate { " (approximate, from sampled blocks)" } else { "" });
+        if let Some(nodata) = nodata {
+            info!("  NoData value {} excluded", nodata);
+        }
+
+        for report in &reports {
+            let stats = &report.statistics;
+            info!("  Band {}: min={} max={} mean={:.4} stddev={:.4} valid={:.2}%",
+                  report.band, stats.min, stats.max, stats.mean, stats.std, report.valid_percent);
+
+            let histogram = if self.verbose { report.histogram.clone() } else { report.histogram.coarsen(DISPLAYED_BUCKETS) };
+            let peak = histogram.counts.iter().copied().max().unwrap_or(0).max(1);
+            let width = (histogram.max - histogram.min) / histogram.counts.len() as f64;
+            for (bucket, &count) in histogram.counts.iter().enumerate() {
+                let lower = histogram.min + bucket as f64 * width;
+                let bar = "#".repeat((count as usize * HISTOGRAM_BAR_WIDTH).div_ceil(peak as usize));
+                info!("    {:>14.4} - {:<14.4} {:>10} {}", lower, lower + width, count, bar);
+            }
+        }
+
+        Ok(reports)
+    }
+
     /// Display a summary of the first few tags
     ///
     /// Shows detailed information for a subset of tags to avoid
@@ -301,6 +532,9 @@ impl<'a> Command for AnalyzeCommand<'a> {
             // Display subfile type
             self.display_subfile_type(ifd);
 
+            // Display child images referenced through SubIFDs
+            self.display_sub_ifds(ifd, i);
+
             // Check and display GeoTIFF tags
             let ifd_has_geotiff = self.display_geotiff_tags(ifd);
 
@@ -311,10 +545,32 @@ impl<'a> Command for AnalyzeCommand<'a> {
                 self.display_geotiff_details(&reader, ifd);
             }
 
+            // Display camera, EXIF/GPS and XMP metadata
+            self.display_capture_metadata(&reader, ifd);
+
+            // Display when the image was acquired
+            self.display_acquisition(&reader, ifd);
+
             // Display tag summary
             self.display_tag_summary(ifd);
         }
 
+        if self.stats {
+            let ifd = tiff.main_ifd()
+                .ok_or_else(|| TiffError::GenericError("No IFDs found in TIFF file".to_string()))?;
+            let reports = self.display_statistics(&reader, ifd)?;
+
+            if self.write_stats {
+                let aux_path = statistics_utils::write_pam_statistics(&self.input_file, &reports)?;
+                info!("  Statistics written to {}", aux_path);
+            }
+        }
+
+        if let Some(footprint) = &self.footprint {
+            footprint_utils::write_footprint(&self.input_file, footprint, self.footprint_shape, self.logger)?;
+            info!("\nFootprint written to {}", footprint);
+        }
+
         debug!("Analysis completed successfully");
         self.logger.log("Analysis completed successfully")?;
 
@@ -0,0 +1,102 @@
+//! Catalog extraction command
+//!
+//! This module implements the command that extracts a bounding box from a
+//! directory or index of rasters, mosaicking the rasters that cover it.
+
+use clap::ArgMatches;
+use log::info;
+
+use crate::commands::command_traits::Command;
+use crate::coordinate::{BoundingBox, CoordinateSystemFactory};
+use crate::extractor::OutputFormat;
+use crate::tiff::errors::{TiffResult, TiffError};
+use crate::tiff::proj_definition::ProjDefinition;
+use crate::utils::axis_order_utils::{self, AxisOrder};
+use crate::utils::catalog_extract_utils;
+use crate::utils::image_extraction_utils;
+use crate::utils::logger::Logger;
+use crate::utils::mosaic_utils::BlendMode;
+
+/// Command for extracting a bounding box from a catalog of rasters
+pub struct CatalogExtractCommand<'a> {
+    /// Directory of rasters or index file
+    catalog: String,
+    /// Area to extract, in `crs`
+    bbox: BoundingBox,
+    /// Coordinate system of the bounding box
+    crs: ProjDefinition,
+    /// Path of the GeoTIFF to write
+    output_file: String,
+    /// How overlapping rasters are combined
+    blend: BlendMode,
+    /// Logger for recording operations
+    logger: &'a Logger,
+}
+
+impl<'a> CatalogExtractCommand<'a> {
+    /// Create a new catalog extraction command
+    ///
+    /// # Arguments
+    /// * `args` - CLI argument matches from clap
+    /// * `logger` - Logger for recording operations
+    ///
+    /// # Returns
+    /// A new CatalogExtractCommand instance or an error
+    pub fn new(args: &ArgMatches, logger: &'a Logger) -> TiffResult<Self> {
+        let catalog = args.get_one::<String>("input")
+            .ok_or_else(|| TiffError::GenericError("Missing catalog to extract from".to_string()))?
+            .clone();
+
+        let output_file = args.get_one::<String>("output")
+            .ok_or_else(|| TiffError::GenericError("Missing output file path for extraction".to_string()))?
+            .clone();
+        if OutputFormat::resolve(&output_file, None) != OutputFormat::GeoTiff {
+            return Err(TiffError::GenericError(format!(
+                "Extraction from a catalog writes a GeoTIFF, not {}", output_file)));
+        }
+
+        let bbox_str = match args.get_many::<String>("bbox").map(|boxes| boxes.collect::<Vec<_>>()).as_deref() {
+            Some([bbox]) => (*bbox).clone(),
+            _ => return Err(TiffError::GenericError(
+                "Extraction from a catalog needs exactly one --bbox".to_string())),
+        };
+
+        let crs_str = args.get_one::<String>("crs").map_or("4326", String::as_str);
+        let crs = CoordinateSystemFactory::parse_definition(crs_str)
+            .map_err(|e| TiffError::GenericError(format!("Invalid CRS '{}': {}", crs_str, e)))?;
+        let crs = crs.definition.or_else(|| ProjDefinition::from_epsg(crs.epsg))
+            .ok_or_else(|| TiffError::GenericError(format!("Unsupported CRS '{}'", crs_str)))?;
+
+        // Bring the box into x,y order before parsing it
+        let axis_order = match args.get_one::<String>("axis-order") {
+            Some(name) => AxisOrder::from_name(name)?,
+            None => AxisOrder::Auto,
+        };
+        let bbox_str = axis_order_utils::normalize_axis_order(&bbox_str, axis_order, crs.is_geographic())?;
+        let bbox = image_extraction_utils::parse_bbox(&bbox_str)?;
+
+        let blend = BlendMode::from_string(args.get_one::<String>("blend").map(|s| s.as_str()).unwrap_or("last"))?;
+
+        info!("Extract {} from the catalog {} to {} ({:?})", bbox_str, catalog, output_file, blend);
+
+        Ok(CatalogExtractCommand {
+            catalog,
+            bbox,
+            crs,
+            output_file,
+            blend,
+            logger,
+        })
+    }
+}
+
+impl<'a> Command for CatalogExtractCommand<'a> {
+    fn execute(&self) -> TiffResult<()> {
+        let count = catalog_extract_utils::extract_from_catalog(
+            &self.catalog, &self.bbox, &self.crs, &self.output_file, self.blend, self.logger)?;
+
+        self.logger.log(&format!("Extracted the bounding box from {} raster(s) of {} to {}",
+                                 count, self.catalog, self.output_file))?;
+        Ok(())
+    }
+}
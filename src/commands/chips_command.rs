@@ -0,0 +1,90 @@
+//! Image chip export command
+//!
+//! This module implements the command for cutting a raster into
+//! fixed-size patches for machine learning datasets.
+
+use clap::ArgMatches;
+use log::info;
+
+use crate::commands::command_traits::Command;
+use crate::tiff::errors::{TiffResult, TiffError};
+use crate::utils::logger::Logger;
+use crate::utils::chip_utils::{self, ChipOptions};
+use crate::utils::normalize_utils::NormalizationMethod;
+
+/// Command for exporting image chips
+pub struct ChipsCommand<'a> {
+    /// Path to the input file
+    input_file: String,
+    /// Directory receiving the chips
+    output_dir: String,
+    /// Chip generation options
+    options: ChipOptions,
+    /// Logger for recording operations
+    logger: &'a Logger,
+}
+
+impl<'a> ChipsCommand<'a> {
+    /// Create a new chips command
+    ///
+    /// # Arguments
+    /// * `args` - CLI argument matches from clap
+    /// * `logger` - Logger for recording operations
+    ///
+    /// # Returns
+    /// A new ChipsCommand instance or an error
+    pub fn new(args: &ArgMatches, logger: &'a Logger) -> TiffResult<Self> {
+        let input_file = args.get_one::<String>("input")
+            .ok_or_else(|| TiffError::GenericError("Missing input file".to_string()))?
+            .clone();
+
+        let output_dir = args.get_one::<String>("output")
+            .ok_or_else(|| TiffError::GenericError("Missing output directory for chips".to_string()))?
+            .clone();
+
+        let mut options = ChipOptions::default();
+
+        if let Some(size_str) = args.get_one::<String>("chip-size") {
+            options.chip_size = size_str.parse::<u32>()
+                .map_err(|e| TiffError::GenericError(format!("Invalid chip size: {}", e)))?;
+        }
+
+        if let Some(overlap_str) = args.get_one::<String>("chip-overlap") {
+            options.overlap = overlap_str.parse::<u32>()
+                .map_err(|e| TiffError::GenericError(format!("Invalid chip overlap: {}", e)))?;
+        }
+
+        if let Some(format) = args.get_one::<String>("chip-format") {
+            options.format = format.clone();
+        }
+
+        options.label_path = args.get_one::<String>("label").cloned();
+        options.resume = args.get_flag("resume");
+        options.quality = crate::commands::output_quality(args)?;
+
+        if let Some(method) = args.get_one::<String>("normalize") {
+            options.normalize = Some(NormalizationMethod::from_string(method)?);
+        }
+
+        Ok(ChipsCommand {
+            input_file,
+            output_dir,
+            options,
+            logger,
+        })
+    }
+}
+
+impl<'a> Command for ChipsCommand<'a> {
+    fn execute(&self) -> TiffResult<()> {
+        info!("Exporting chips from {} to {}", self.input_file, self.output_dir);
+
+        let count = chip_utils::export_chips(
+            &self.input_file, &self.output_dir, &self.options, self.logger)?;
+
+        info!("Chip export successful: {} chips", count);
+        self.logger.log(&format!("Chip export successful: {} chips", count))?;
+
+        Ok(())
+    }
+}
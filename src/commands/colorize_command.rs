@@ -0,0 +1,69 @@
+//! Colorize command
+//!
+//! This module implements the command that applies a colormap to an
+//! entire single-band raster, without the region and shape handling of
+//! the extract pipeline.
+
+use clap::ArgMatches;
+use log::info;
+
+use crate::commands::command_traits::Command;
+use crate::tiff::errors::{TiffResult, TiffError};
+use crate::utils::logger::Logger;
+use crate::utils::colormap_utils;
+
+/// Command for colorizing a raster with a colormap
+pub struct ColorizeCommand<'a> {
+    /// Path to the input raster
+    input_file: String,
+    /// Path to the colorized output
+    output_file: String,
+    /// Path to the colormap (SLD, CSV or palette TIFF)
+    colormap_file: String,
+    /// Logger for recording operations
+    logger: &'a Logger,
+}
+
+impl<'a> ColorizeCommand<'a> {
+    /// Create a new colorize command
+    ///
+    /// # Arguments
+    /// * `args` - CLI argument matches from clap
+    /// * `logger` - Logger for recording operations
+    ///
+    /// # Returns
+    /// A new ColorizeCommand instance or an error
+    pub fn new(args: &ArgMatches, logger: &'a Logger) -> TiffResult<Self> {
+        let input_file = args.get_one::<String>("input")
+            .ok_or_else(|| TiffError::GenericError("Missing input file".to_string()))?
+            .clone();
+
+        let output_file = args.get_one::<String>("output")
+            .ok_or_else(|| TiffError::GenericError("Missing output file path for colorize".to_string()))?
+            .clone();
+
+        let colormap_file = args.get_one::<String>("colormap-input")
+            .ok_or_else(|| TiffError::GenericError(
+                "Colorize needs a colormap; pass it with --colormap-input".to_string()))?
+            .clone();
+
+        info!("Colorize {} with {} to {}", input_file, colormap_file, output_file);
+
+        Ok(ColorizeCommand {
+            input_file,
+            output_file,
+            colormap_file,
+            logger,
+        })
+    }
+}
+
+impl<'a> Command for ColorizeCommand<'a> {
+    fn execute(&self) -> TiffResult<()> {
+        let colormap = colormap_utils::load_colormap(&self.colormap_file, self.logger)?;
+        colormap_utils::colorize_file(&self.input_file, &self.output_file, &colormap, self.logger)?;
+
+        self.logger.log(&format!("Colorized {} written to {}", self.input_file, self.output_file))?;
+        Ok(())
+    }
+}
@@ -0,0 +1,92 @@
+//! Colormap transform command
+//!
+//! This module implements the command that rescales, offsets and merges
+//! colormap entry values so a style authored for one product can be
+//! reused for data in different units.
+
+use clap::ArgMatches;
+use log::info;
+
+use crate::commands::command_traits::Command;
+use crate::tiff::errors::{TiffResult, TiffError};
+use crate::utils::logger::Logger;
+use crate::utils::colormap_utils::{self, ColormapTransform};
+
+/// Command for editing the values of a colormap
+pub struct ColormapTransformCommand<'a> {
+    /// Path to the source colormap
+    colormap_file: String,
+    /// Path of the SLD to write
+    output_file: String,
+    /// Edits to apply
+    transform: ColormapTransform,
+    /// Logger for recording operations
+    logger: &'a Logger,
+}
+
+impl<'a> ColormapTransformCommand<'a> {
+    /// Create a new colormap transform command
+    ///
+    /// # Arguments
+    /// * `args` - CLI argument matches from clap
+    /// * `logger` - Logger for recording operations
+    ///
+    /// # Returns
+    /// A new ColormapTransformCommand instance or an error
+    pub fn new(args: &ArgMatches, logger: &'a Logger) -> TiffResult<Self> {
+        let colormap_file = args.get_one::<String>("input")
+            .ok_or_else(|| TiffError::GenericError("Missing colormap file".to_string()))?
+            .clone();
+
+        let output_file = args.get_one::<String>("output")
+            .ok_or_else(|| TiffError::GenericError("Missing output SLD path for the colormap".to_string()))?
+            .clone();
+
+        let rescale = match args.get_one::<String>("colormap-rescale") {
+            Some(spec) => Some(colormap_utils::parse_rescale_spec(spec)?),
+            None => None,
+        };
+
+        let offset = match args.get_one::<String>("colormap-offset") {
+            Some(value) => Some(value.trim().parse::<i32>().map_err(|_| TiffError::GenericError(
+                format!("Invalid colormap offset '{}'", value)))?),
+            None => None,
+        };
+
+        let transform = ColormapTransform {
+            rescale,
+            offset,
+            merge: args.get_one::<String>("colormap-merge").cloned(),
+        };
+
+        info!("Colormap transform of {} to {}: {:?}", colormap_file, output_file, transform);
+
+        Ok(ColormapTransformCommand {
+            colormap_file,
+            output_file,
+            transform,
+            logger,
+        })
+    }
+
+    /// Check whether the arguments request a colormap transform
+    ///
+    /// # Arguments
+    /// * `args` - CLI argument matches from clap
+    pub fn is_requested(args: &ArgMatches) -> bool {
+        ["colormap-rescale", "colormap-offset", "colormap-merge"]
+            .iter()
+            .any(|name| args.get_one::<String>(name).is_some())
+    }
+}
+
+impl<'a> Command for ColormapTransformCommand<'a> {
+    fn execute(&self) -> TiffResult<()> {
+        let colormap = colormap_utils::transform_colormap_file(
+            &self.colormap_file, &self.output_file, &self.transform, self.logger)?;
+        colormap.print();
+
+        self.logger.log(&format!("Transformed colormap {} written to {}", self.colormap_file, self.output_file))?;
+        Ok(())
+    }
+}
@@ -1,7 +1,8 @@
 //! Compression conversion command
 //!
 //! This module implements the command for converting TIFF files
-//! between different compression formats.
+//! between different compression formats, optionally expanding
+//! palette-color images to RGB or class indices on the way.
 
 use clap::ArgMatches;
 use log::{info, error};
@@ -10,6 +11,8 @@ use crate::commands::command_traits::Command;
 use crate::tiff::errors::{TiffResult, TiffError};
 use crate::utils::logger::Logger;
 use crate::compression::{CompressionFactory, CompressionConverter};
+use crate::utils::depalettize_utils::{self, DepalettizeMode};
+use crate::utils::icc_utils;
 
 /// Command for converting TIFF compression format
 pub struct ConvertCommand<'a> {
@@ -17,8 +20,14 @@ pub struct ConvertCommand<'a> {
     input_file: String,
     /// Path to the output file
     output_file: String,
-    /// Target compression code
-    target_compression: u64,
+    /// Target compression code; optional when depalettizing
+    target_compression: Option<u64>,
+    /// Palette expansion to apply before compressing
+    depalettize: Option<DepalettizeMode>,
+    /// ICC profile to embed in the output (optional)
+    icc_profile: Option<String>,
+    /// Continue an interrupted conversion from its journal
+    resume: bool,
     /// Logger for recording operations
     logger: &'a Logger,
 }
@@ -41,31 +50,47 @@ impl<'a> ConvertCommand<'a> {
             .ok_or_else(|| TiffError::GenericError("Missing output file path for conversion".to_string()))?
             .clone();
 
+        let depalettize = match args.get_one::<String>("depalettize") {
+            Some(mode) => Some(DepalettizeMode::from_string(mode)?),
+            None => None,
+        };
+
+        let icc_profile = args.get_one::<String>("icc-profile").cloned();
+        let resume = args.get_flag("resume");
+
         // Determine target compression
         let target_compression = if let Some(compression_str) = args.get_one::<String>("compression") {
             // Try to parse the compression code
-            compression_str.parse::<u64>()
-                .map_err(|_| TiffError::GenericError(format!("Invalid compression code: {}", compression_str)))?
+            Some(compression_str.parse::<u64>()
+                .map_err(|_| TiffError::GenericError(format!("Invalid compression code: {}", compression_str)))?)
         } else if let Some(compression_name) = args.get_one::<String>("compression-name") {
             // Try to get compression by name
             match CompressionFactory::get_handler_by_name(compression_name) {
-                Ok(handler) => handler.code(),
+                Ok(handler) => Some(handler.code()),
                 Err(_) => return Err(TiffError::GenericError(format!("Unknown compression name: {}", compression_name)))
             }
+        } else if depalettize.is_some() {
+            // Depalettized output is written uncompressed unless asked otherwise
+            None
         } else {
             return Err(TiffError::GenericError("Missing compression specification. Use --compression or --compression-name".to_string()));
         };
 
         // Validate the compression is supported
-        match CompressionFactory::create_handler(target_compression) {
-            Ok(handler) => info!("Using compression: {}", handler.name()),
-            Err(_) => return Err(TiffError::GenericError(format!("Unsupported compression code: {}", target_compression)))
+        if let Some(code) = target_compression {
+            match CompressionFactory::create_handler(code) {
+                Ok(handler) => info!("Using compression: {}", handler.name()),
+                Err(_) => return Err(TiffError::GenericError(format!("Unsupported compression code: {}", code)))
+            }
         }
 
         Ok(ConvertCommand {
             input_file,
             output_file,
             target_compression,
+            depalettize,
+            icc_profile,
+            resume,
             logger,
         })
     }
@@ -73,18 +98,75 @@ impl<'a> ConvertCommand<'a> {
 
 impl<'a> Command for ConvertCommand<'a> {
     fn execute(&self) -> TiffResult<()> {
+        if let Some(mode) = self.depalettize {
+            self.execute_depalettize(mode)?;
+            return self.embed_icc_profile();
+        }
+
+        let target_compression = self.target_compression
+            .ok_or_else(|| TiffError::GenericError("Missing compression specification".to_string()))?;
+
         info!("Converting file {} to {} with compression code {}",
-              self.input_file, self.output_file, self.target_compression);
+              self.input_file, self.output_file, target_compression);
 
         // Create compression converter
-        let mut converter = CompressionConverter::new(self.logger);
+        let mut converter = CompressionConverter::new(self.logger).with_resume(self.resume);
 
         // Convert the file
-        converter.convert_file(&self.input_file, &self.output_file, self.target_compression)?;
+        converter.convert_file(&self.input_file, &self.output_file, target_compression)?;
 
         info!("Compression conversion successful");
         self.logger.log("Compression conversion successful")?;
 
+        self.embed_icc_profile()
+    }
+}
+
+impl<'a> ConvertCommand<'a> {
+    /// Expand a palette image, then apply the target compression if one was given
+    ///
+    /// # Arguments
+    /// * `mode` - Expand to RGB or keep the class indices
+    ///
+    /// # Returns
+    /// Result indicating success or an error
+    fn execute_depalettize(&self, mode: DepalettizeMode) -> TiffResult<()> {
+        let Some(target_compression) = self.target_compression else {
+            depalettize_utils::depalettize_file(&self.input_file, &self.output_file, mode, self.logger)?;
+            self.logger.log(&format!("Depalettized {} to {}", self.input_file, self.output_file))?;
+            return Ok(());
+        };
+
+        // Write the expanded raster next to the output, then recompress it into place
+        let staging_file = format!("{}.depalettize.tmp", self.output_file);
+        let result = depalettize_utils::depalettize_file(&self.input_file, &staging_file, mode, self.logger)
+            .and_then(|_| {
+                let mut converter = CompressionConverter::new(self.logger);
+                converter.convert_file(&staging_file, &self.output_file, target_compression)
+            });
+
+        if let Err(e) = std::fs::remove_file(&staging_file) {
+            error!("Failed to remove temporary file {}: {}", staging_file, e);
+        }
+        result?;
+
+        info!("Depalettized {} to {} with compression code {}", self.input_file, self.output_file, target_compression);
+        self.logger.log(&format!("Depalettized {} to {}", self.input_file, self.output_file))?;
+        Ok(())
+    }
+
+    /// Embed the user-supplied ICC profile, replacing any profile copied from the source
+    ///
+    /// # Returns
+    /// Result indicating success or an error
+    fn embed_icc_profile(&self) -> TiffResult<()> {
+        let Some(profile_path) = &self.icc_profile else {
+            return Ok(());
+        };
+
+        let profile = icc_utils::load_icc_profile(profile_path)?;
+        icc_utils::embed_icc_profile(&self.output_file, &profile)?;
+        self.logger.log(&format!("Embedded ICC profile {} in {}", profile_path, self.output_file))?;
         Ok(())
     }
-}
\ No newline at end of file
+}
@@ -0,0 +1,116 @@
+//! TIFF tag dump command
+//!
+//! This module implements a `tiffinfo -d` style listing of every tag in
+//! every IFD, with the values decoded rather than shown as raw offsets.
+
+use clap::ArgMatches;
+use log::{info, warn};
+
+use crate::commands::command_traits::Command;
+use crate::tiff::TiffReader;
+use crate::tiff::errors::{TiffResult, TiffError};
+use crate::tiff::ifd::{IFD, IFDEntry};
+use crate::utils::logger::Logger;
+use crate::utils::tag_utils;
+
+/// Values shown per tag unless verbose output is requested
+const MAX_VALUES: usize = 32;
+
+/// Command for dumping all TIFF tags with decoded values
+pub struct DumpTagsCommand<'a> {
+    /// Path to the input file
+    input_file: String,
+    /// Whether to print every value of long arrays
+    verbose: bool,
+    /// Logger for recording operations
+    logger: &'a Logger,
+}
+
+impl<'a> DumpTagsCommand<'a> {
+    /// Create a new tag dump command
+    ///
+    /// # Arguments
+    /// * `args` - CLI argument matches from clap
+    /// * `logger` - Logger for recording operations
+    ///
+    /// # Returns
+    /// A new DumpTagsCommand instance or an error
+    pub fn new(args: &ArgMatches, logger: &'a Logger) -> TiffResult<Self> {
+        let input_file = args.get_one::<String>("input")
+            .ok_or_else(|| TiffError::GenericError("Missing input file".to_string()))?
+            .clone();
+
+        Ok(DumpTagsCommand {
+            input_file,
+            verbose: args.get_flag("verbose"),
+            logger,
+        })
+    }
+
+    /// Format one entry as a dump line
+    ///
+    /// Inline values show the type and count only; values stored elsewhere
+    /// in the file also show their offset.
+    ///
+    /// # Arguments
+    /// * `reader` - Reader the file was loaded with
+    /// * `entry` - The entry to format
+    fn format_entry(&self, reader: &TiffReader, entry: &IFDEntry) -> String {
+        let location = if entry.is_value_inline(reader.is_big_tiff()) {
+            format!("{}", entry.count)
+        } else {
+            format!("{} @ {}", entry.count, entry.value_offset)
+        };
+        let max_values = if self.verbose { None } else { Some(MAX_VALUES) };
+        let values = match reader.read_tag_value(entry) {
+            Ok(value) => value.format(max_values),
+            Err(e) => {
+                warn!("Failed to read tag {}: {}", entry.tag, e);
+                format!("<unreadable: {}>", e)
+            },
+        };
+
+        format!("  {} {} ({}, {}): {}", entry.tag, tag_utils::get_tag_name(entry.tag),
+                tag_utils::get_field_type_name(entry.field_type), location, values)
+    }
+
+    /// Print all entries of an IFD, followed by its SubIFDs
+    ///
+    /// # Arguments
+    /// * `reader` - Reader the file was loaded with
+    /// * `ifd` - The IFD to dump
+    /// * `index` - Index of the IFD in the file, such as "0", or "0.1"
+    ///   for the second SubIFD of IFD 0
+    fn dump_ifd(&self, reader: &TiffReader, ifd: &IFD, index: &str) {
+        let kind = if index.contains('.') { "SubIFD" } else { "IFD" };
+        println!("{} #{} at offset {} ({} entries)", kind, index, ifd.offset, ifd.entries.len());
+        for entry in &ifd.entries {
+            println!("{}", self.format_entry(reader, entry));
+        }
+
+        for (sub_index, sub_ifd) in ifd.sub_ifds.iter().enumerate() {
+            self.dump_ifd(reader, sub_ifd, &format!("{}.{}", index, sub_index));
+        }
+    }
+}
+
+impl<'a> Command for DumpTagsCommand<'a> {
+    fn execute(&self) -> TiffResult<()> {
+        info!("Dumping tags of {}", self.input_file);
+
+        let mut reader = TiffReader::new(self.logger);
+        let tiff = reader.load(&self.input_file)?;
+        let byte_order = reader.get_byte_order_handler()
+            .map(|handler| handler.byte_order().name())
+            .unwrap_or("unknown");
+
+        println!("{}: {}, {}, {} IFDs", self.input_file,
+                 if tiff.is_big_tiff { "BigTIFF" } else { "TIFF" }, byte_order, tiff.ifd_count());
+        for (index, ifd) in tiff.ifds.iter().enumerate() {
+            self.dump_ifd(&reader, ifd, &index.to_string());
+        }
+
+        self.logger.log(&format!("Dumped tags of {}", self.input_file))?;
+        Ok(())
+    }
+}
@@ -0,0 +1,80 @@
+//! Extent growing command
+//!
+//! This module implements the command for padding a raster out to a
+//! larger bounding box on its own pixel grid.
+
+use clap::ArgMatches;
+use log::info;
+
+use crate::commands::command_traits::Command;
+use crate::coordinate::BoundingBox;
+use crate::tiff::errors::{TiffResult, TiffError};
+use crate::utils::extend_utils;
+use crate::utils::logger::Logger;
+
+/// Command for extending a raster to a bounding box
+pub struct ExtendCommand<'a> {
+    /// Path to the raster to extend
+    input_file: String,
+    /// Path of the extended GeoTIFF
+    output_file: String,
+    /// Box the output must cover, in the raster's coordinate system
+    bbox: BoundingBox,
+    /// Value of the new pixels, None for the source NoData
+    nodata: Option<f64>,
+    /// Logger for recording operations
+    logger: &'a Logger,
+}
+
+impl<'a> ExtendCommand<'a> {
+    /// Create a new extend command
+    ///
+    /// # Arguments
+    /// * `args` - CLI argument matches from clap
+    /// * `logger` - Logger for recording operations
+    ///
+    /// # Returns
+    /// A new ExtendCommand instance or an error
+    pub fn new(args: &ArgMatches, logger: &'a Logger) -> TiffResult<Self> {
+        let input_file = args.get_one::<String>("input")
+            .ok_or_else(|| TiffError::GenericError("Missing input file".to_string()))?
+            .clone();
+
+        let output_file = args.get_one::<String>("output")
+            .ok_or_else(|| TiffError::GenericError("Missing output file path for extend".to_string()))?
+            .clone();
+
+        let bbox_str = args.get_one::<String>("extend")
+            .ok_or_else(|| TiffError::GenericError("Missing bounding box to extend to".to_string()))?;
+        let bbox = BoundingBox::from_string(bbox_str)
+            .map_err(|e| TiffError::GenericError(format!("Invalid bounding box '{}': {}", bbox_str, e)))?;
+
+        let nodata = match args.get_one::<String>("nodata") {
+            Some(nodata_str) => Some(nodata_str.parse::<f64>()
+                .map_err(|e| TiffError::GenericError(format!("Invalid NoData value: {}", e)))?),
+            None => None,
+        };
+
+        Ok(ExtendCommand {
+            input_file,
+            output_file,
+            bbox,
+            nodata,
+            logger,
+        })
+    }
+}
+
+impl<'a> Command for ExtendCommand<'a> {
+    fn execute(&self) -> TiffResult<()> {
+        info!("Extending {} to {:?}", self.input_file, self.bbox);
+
+        let padding = extend_utils::extend(
+            &self.input_file, &self.output_file, &self.bbox, self.nodata, self.logger)?;
+
+        println!("Wrote {}x{} raster to {}, source pixels from ({}, {})",
+                 padding.width, padding.height, self.output_file, padding.offset_x, padding.offset_y);
+
+        Ok(())
+    }
+}
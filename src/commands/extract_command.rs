@@ -1,48 +1,149 @@
 use clap::ArgMatches;
+use clap::parser::ValueSource;
 use log::{debug, info, warn, error};
+use std::collections::HashSet;
 use std::path::Path;
+use std::cell::{OnceCell, RefCell};
+use std::rc::Rc;
+use std::time::{Duration, Instant};
 use image::DynamicImage;
 use crate::commands::command_traits::Command;
 use crate::tiff::errors::{TiffResult, TiffError};
 use crate::utils::logger::Logger;
-use crate::extractor::{ImageExtractor, Region};
-use crate::coordinate::BoundingBox;
+use crate::extractor::{file_checksum, Coverage, ImageExtractor, OutputFormat, Padding, Provenance, Region};
+use crate::coordinate::{Affine, BoundingBox, CoordinateSystemFactory, CoordinateTransformer, Crs, Geocoder, NominatimGeocoder};
 use crate::tiff::TiffReader;
-use crate::tiff::constants::epsg;
+use crate::tiff::constants::{epsg, tags};
 use crate::tiff::types::TIFF;
+use crate::tiff::geo_key_parser::GeoKeyParser;
+use crate::tiff::proj_definition::{ProjDefinition, USER_DEFINED};
+use crate::coordinate::datum::NtV2Grid;
+use crate::coordinate::geocoder::DEFAULT_NOMINATIM_URL;
 use crate::utils::colormap_utils;
+use crate::utils::classification_utils::{self, ValueHistogram};
+use crate::tiff::colormap::ColorMap;
 use crate::utils::reference_utils;
 use crate::utils::image_extraction_utils;
-use crate::utils::coordinate_utils;
+use crate::utils::tiff_extraction_utils;
+use crate::utils::axis_order_utils::{self, AxisOrder};
+use crate::utils::cell_utils;
+use crate::utils::tile_utils::{self, TileScheme};
+use crate::utils::report_utils::{self, ReportEntry};
+use crate::utils::metrics;
+use crate::utils::memory_utils;
+use crate::utils::atomic_utils;
+use crate::utils::crop_utils;
+use crate::utils::scratch_utils;
+use crate::utils::coordinate_utils::{self, HumanCoordinate};
+use crate::utils::region_utils::{self, RegionSpec};
 use crate::utils::reprojection_utils;
+use crate::utils::warp_utils::{self, WarpOptions};
+use crate::utils::postgis_utils::{PostgisLoadMode, PostgisOptions};
 use crate::utils::filter_utils;
+use crate::utils::icc_utils;
+use crate::utils::elevation_utils::LinearUnit;
+use crate::utils::sidecar_utils::{self, SidecarGeoreference};
+
+/// Array formats that can hold values scaled to physical units
+const SCALED_FORMATS: [&str; 4] = ["csv", "npy", "npz", "safetensors"];
 
 /// Command for extracting image data from TIFF files
+#[derive(Clone)]
 pub struct ExtractCommand<'a> {
     /// Path to the input file
     input_file: String,
     /// Path to the output file
     output_file: String,
+    /// Output naming pattern, overriding the output path
+    out_pattern: Option<String>,
+    /// Output format given with --output-format, None to go by the extension
+    output_format: Option<OutputFormat>,
     /// Bounding box string for region extraction
     bbox_str: Option<String>,
     /// Coordinate string for point-based extraction
     coordinate_str: Option<String>,
+    /// Regions of a multi-region extraction, empty for a single region
+    regions: Rc<[RegionSpec]>,
     /// Radius in meters for point-based extraction
     radius: Option<f64>,
     /// Shape for coordinate-based extraction (circle or square)
     shape: String,
     /// CRS code for the bounding box/coordinate
     crs_code: Option<u32>,
+    /// Definition of --crs when given as a PROJ string or WKT
+    crs_definition: Option<ProjDefinition>,
     /// Target projection EPSG code for reprojection
     proj_code: Option<u32>,
+    /// Definition of a user-defined --proj given as a PROJ string or WKT
+    proj_definition: Option<ProjDefinition>,
+    /// Target grid of the reprojection
+    warp_options: WarpOptions,
+    /// NTv2 shift grid from the raster datum to WGS84 (optional)
+    datum_grid: Option<Rc<NtV2Grid>>,
+    /// Whether to expand the region to tile/strip boundaries
+    align_to_blocks: bool,
+    /// Whether to crop by copying the encoded blocks of a block-aligned window
+    copy_blocks: bool,
+    /// Whether to fail when the bounding box runs off the raster
+    require_full_coverage: bool,
+    /// Whether to pad the output to the full bounding box
+    pad_to_bbox: bool,
+    /// Value for padded pixels (defaults to the source NoData)
+    fill_value: Option<f64>,
     /// Path to save the colormap as SLD (optional)
     colormap_output: Option<String>,
     /// Path to a colormap file to apply (optional)
     colormap_input: Option<String>,
+    /// Automatic colormap specification "method:classes:scheme" (optional)
+    colormap_auto: Option<String>,
+    /// Whether to drop leading black entries from an extracted palette
+    colormap_trim_black: bool,
+    /// Path to an ICC profile to embed in TIFF output (optional)
+    icc_profile: Option<String>,
+    /// Whether to read world file / .aux.xml georeferencing for plain TIFFs
+    use_sidecars: bool,
+    /// Whether to write a provenance record into TIFF output
+    provenance: bool,
+    /// SubIFD of the main IFD to extract from (optional)
+    sub_ifd: Option<usize>,
+    /// Checksum of the input, computed once and shared by all regions
+    source_checksum: Rc<OnceCell<String>>,
+    /// Path of the JSON extraction report (optional)
+    report: Option<String>,
+    /// Report entries of the outputs written so far, shared by all regions
+    report_entries: Rc<RefCell<Vec<ReportEntry>>>,
     /// Whether to extract array data instead of image
     array_mode: bool,
     /// Format for array output
     array_format: String,
+    /// Path of an array output written beside the image output (optional)
+    array_output: Option<String>,
+    /// Normalization method for float32 tensor output (optional)
+    normalize: Option<String>,
+    /// Target units for elevation conversion during array extraction (optional)
+    elevation_units: Option<String>,
+    /// Scale factor to physical units, overriding the file's (optional)
+    scale: Option<f64>,
+    /// Offset to physical units, overriding the file's (optional)
+    offset: Option<f64>,
+    /// Whether to write stored values, ignoring the file's scaling
+    raw_values: bool,
+    /// Whether to label array output with map coordinates
+    array_coords: bool,
+    /// Whether to build a spatial index for database output
+    spatial_index: bool,
+    /// Target table for PostGIS SQL output
+    pg_table: String,
+    /// SRID override for PostGIS SQL output
+    pg_srid: Option<i32>,
+    /// Tile size for PostGIS SQL output
+    tile_size: Option<(u32, u32)>,
+    /// Whether PostGIS SQL output uses COPY instead of INSERT
+    pg_copy: bool,
+    /// Partitioning scheme for Parquet output
+    partition_by: String,
+    /// Maximum rows per Parquet row group
+    row_group_size: usize,
     /// Filter range to extract only specific pixel values (e.g., "15,160")
     filter_range: Option<String>,
     /// Whether to make filtered pixels transparent
@@ -68,18 +169,83 @@ impl<'a> ExtractCommand<'a> {
             .clone();
         info!("Input file: {}", input_file);
 
-        let output_file = args.get_one::<String>("output")
-            .ok_or_else(|| TiffError::GenericError("Missing output file path for extraction".to_string()))?
-            .clone();
-        info!("Output file: {}", output_file);
+        // An output pattern names the outputs itself
+        let out_pattern = args.get_one::<String>("out-pattern").cloned();
+        if let Some(pattern) = &out_pattern {
+            region_utils::validate_output_pattern(pattern)?;
+        }
+        let output_file = match (args.get_one::<String>("output"), &out_pattern) {
+            (Some(output), None) => output.clone(),
+            (_, Some(pattern)) => pattern.clone(),
+            (None, None) => return Err(TiffError::GenericError(
+                "Missing output file path for extraction".to_string())),
+        };
+        info!("Output file: {}, pattern: {:?}", output_file, out_pattern);
 
-        // Get bounding box string if provided
-        let bbox_str = args.get_one::<String>("bbox").cloned();
-        info!("Bounding box: {:?}", bbox_str);
+        // The output format is requested explicitly or implied by the extension
+        let output_format = match args.get_one::<String>("output-format") {
+            Some(name) => Some(OutputFormat::from_name(name)?),
+            None => None,
+        };
+        let resolved_format = OutputFormat::resolve(&output_file, output_format);
+        info!("Output format: {} ({})", resolved_format,
+              if output_format.is_some() { "requested" } else { "from extension" });
+
+        // Collect the bounding boxes and coordinates, from the command line
+        // and from a regions file
+        let mut regions: Vec<RegionSpec> = args.get_many::<String>("bbox")
+            .into_iter().flatten().cloned().map(RegionSpec::Bbox)
+            .chain(args.get_many::<String>("coordinate").into_iter().flatten().cloned().map(RegionSpec::Coordinate))
+            .chain(args.get_many::<String>("cell").into_iter().flatten().cloned().map(RegionSpec::Cell))
+            .collect();
+        if let Some(regions_file) = args.get_one::<String>("regions") {
+            regions.extend(region_utils::read_regions_file(regions_file)?);
+        }
 
-        // Get coordinate and radius if provided
-        let coordinate_str = args.get_one::<String>("coordinate").cloned();
+        // A tile is a region of its own, extracted alone
+        let tile = match args.get_one::<String>("tile") {
+            Some(tile_str) => {
+                if !regions.is_empty() {
+                    return Err(TiffError::GenericError(
+                        "--tile cannot be combined with other regions".to_string()));
+                }
+                let scheme = TileScheme::from_name(args.get_one::<String>("tile-scheme").map_or("webmercator", String::as_str))?;
+                Some((tile_utils::parse_tile(tile_str)?, scheme))
+            },
+            None => None,
+        };
+
+        // So is a named place, looked up before anything else
+        let place_position = match args.get_one::<String>("place") {
+            Some(place) => {
+                if !regions.is_empty() || tile.is_some() {
+                    return Err(TiffError::GenericError(
+                        "--place cannot be combined with other regions".to_string()));
+                }
+                let endpoint = args.get_one::<String>("geocoder-url").map_or(DEFAULT_NOMINATIM_URL, String::as_str);
+                let geocoder = NominatimGeocoder::with_endpoint(endpoint);
+                Some(geocoder.geocode(place)?)
+            },
+            None => None,
+        };
+
+        // A single region is handled exactly like before; several are
+        // extracted one after the other into numbered outputs
+        let (bbox_str, coordinate_str) = match (tile, regions.as_slice()) {
+            _ if place_position.is_some() => (None, place_position.map(|position| position.to_string())),
+            (Some((tile, scheme)), _) => (Some(tile_utils::tile_bbox(tile, scheme)?), None),
+            (None, [RegionSpec::Bbox(bbox)]) => (Some(bbox.clone()), None),
+            (None, [RegionSpec::Coordinate(coordinate)]) => (None, Some(coordinate.clone())),
+            (None, [RegionSpec::Cell(cell)]) => (Some(cell_utils::cell_bbox(cell)?), None),
+            _ => (None, None),
+        };
+        let single_cell = tile.is_some() || matches!(regions.as_slice(), [RegionSpec::Cell(_)]);
+        if regions.len() < 2 {
+            regions.clear();
+        }
+        info!("Bounding box: {:?}", bbox_str);
         info!("Coordinate: {:?}", coordinate_str);
+        info!("Regions: {}", regions.len());
 
         let radius = if let Some(radius_str) = args.get_one::<String>("radius") {
             match radius_str.parse::<f64>() {
@@ -103,24 +269,27 @@ impl<'a> ExtractCommand<'a> {
         info!("Shape: {}", shape);
 
         // Validate that if radius is specified, coordinate is also specified
-        if radius.is_some() && coordinate_str.is_none() {
+        let has_coordinates = coordinate_str.is_some()
+            || regions.iter().any(|region| matches!(region, RegionSpec::Coordinate(_)));
+        if radius.is_some() && !has_coordinates {
             return Err(TiffError::GenericError(
                 "Radius specified but no coordinate provided".to_string()));
         }
+        if radius.is_none() && regions.iter().any(|region| matches!(region, RegionSpec::Coordinate(_))) {
+            return Err(TiffError::GenericError(
+                "Coordinate regions need --radius".to_string()));
+        }
 
         // Get CRS code if provided
+        let mut crs_definition = None;
         let crs_code = if let Some(crs_str) = args.get_one::<String>("crs") {
-            // If a CRS was provided, parse it
-            info!("Parsing CRS code: {}", crs_str);
-            match crs_str.parse::<u32>() {
-                Ok(code) => {
-                    info!("Using CRS code: {}", code);
-                    Some(code)
-                },
-                Err(_) => {
-                    return Err(TiffError::GenericError(format!("Invalid CRS code: {}", crs_str)));
-                }
-            }
+            // If a CRS was provided, parse it (EPSG code, PROJ string or WKT)
+            info!("Parsing CRS: {}", crs_str);
+            let crs = CoordinateSystemFactory::parse_definition(crs_str)
+                .map_err(|e| TiffError::GenericError(format!("Invalid CRS '{}': {}", crs_str, e)))?;
+            info!("Using CRS code: {}", crs.epsg);
+            crs_definition = crs.definition;
+            Some(crs.epsg)
         } else if let Some(epsg_str) = args.get_one::<String>("epsg") {
             // For backward compatibility with --epsg
             info!("Using EPSG code from --epsg parameter: {}", epsg_str);
@@ -144,24 +313,184 @@ impl<'a> ExtractCommand<'a> {
 
         info!("CRS code: {:?}", crs_code);
 
+        // Bring coordinates and bounding boxes into x,y order
+        let axis_order = match args.get_one::<String>("axis-order") {
+            Some(name) => AxisOrder::from_name(name)?,
+            None => AxisOrder::Auto,
+        };
+        let geographic = match &crs_definition {
+            Some(definition) => definition.is_geographic(),
+            None => crs_code.and_then(|code| Crs::from_epsg(code).ok()).is_some_and(Crs::is_geographic),
+        };
+        info!("Axis order: {:?} ({} coordinates)", axis_order, if geographic { "geographic" } else { "projected" });
+        let normalize = |input: &str| axis_order_utils::normalize_axis_order(input, axis_order, geographic);
+        // Positions known to be WGS84 are projected into the CRS of the other coordinates
+        let lon_lat_coordinate = |lon: f64, lat: f64, input: &str| -> TiffResult<String> {
+            if geographic {
+                return Ok(format!("{},{}", lon, lat));
+            }
+            let target = crs_definition.clone().or_else(|| crs_code.and_then(ProjDefinition::from_epsg));
+            let point = target.zip(ProjDefinition::from_epsg(Crs::WGS84.epsg()))
+                .and_then(|(target, source)| CoordinateTransformer.geographic_to_definition(lon, lat, &source, &target, None))
+                .ok_or_else(|| TiffError::GenericError(format!(
+                    "Cannot project coordinate '{}' into CRS {:?}", input, crs_code)))?;
+            info!("Projected coordinate '{}' to {},{}", input, point.x, point.y);
+            Ok(format!("{},{}", point.x, point.y))
+        };
+        // Hemisphere letters and map URLs say themselves which value is the latitude
+        let normalize_coordinate = |input: &str| match coordinate_utils::parse_human_coordinate(input)? {
+            HumanCoordinate::LonLat(lon, lat) => lon_lat_coordinate(lon, lat, input),
+            HumanCoordinate::Plain(first, second) => normalize(&format!("{},{}", first, second)),
+        };
+        // Cells and tiles are resolved to WGS84 longitude and latitude
+        let has_cells = single_cell || regions.iter().any(|region| matches!(region, RegionSpec::Cell(_)));
+        if has_cells && crs_code != Some(Crs::WGS84.epsg()) {
+            return Err(TiffError::GenericError(format!(
+                "Cell and tile regions are in WGS84 and cannot be combined with CRS {:?}", crs_code)));
+        }
+        let bbox_str = if single_cell { bbox_str } else { bbox_str.as_deref().map(normalize).transpose()? };
+        let coordinate_str = match place_position {
+            Some(position) => Some(lon_lat_coordinate(position.lon(), position.lat(), &position.to_string())?),
+            None => coordinate_str.as_deref().map(normalize_coordinate).transpose()?,
+        };
+        for region in regions.iter_mut() {
+            match region {
+                RegionSpec::Bbox(value) => *value = normalize(value)?,
+                RegionSpec::Coordinate(value) => *value = normalize_coordinate(value)?,
+                RegionSpec::Cell(cell) => *region = RegionSpec::Bbox(cell_utils::cell_bbox(cell)?),
+            }
+        }
+
         // Get target projection code if provided
+        let mut proj_definition = None;
         let proj_code = if let Some(proj_str) = args.get_one::<String>("proj") {
-            info!("Parsing target projection code: {}", proj_str);
-            match proj_str.parse::<u32>() {
-                Ok(code) => {
-                    info!("Using target projection EPSG:{}", code);
-                    Some(code)
-                },
-                Err(_) => {
-                    return Err(TiffError::GenericError(format!("Invalid projection code: {}", proj_str)));
-                }
+            info!("Parsing target projection: {}", proj_str);
+            let proj = CoordinateSystemFactory::parse_definition(proj_str)
+                .map_err(|e| TiffError::GenericError(format!("Invalid projection '{}': {}", proj_str, e)))?;
+            info!("Using target projection EPSG:{}", proj.epsg);
+            if proj.is_user_defined() {
+                proj_definition = proj.definition;
             }
+            Some(proj.epsg)
         } else {
             None
         };
 
+        // A tile resampled to a fixed size is warped onto the tile's own grid
+        let tile_pixels = match args.get_one::<String>("tile-pixels") {
+            Some(pixels_str) => Some(pixels_str.trim().parse::<u32>()
+                .ok()
+                .filter(|pixels| *pixels > 0)
+                .ok_or_else(|| TiffError::GenericError(format!("Invalid tile size '{}', expected pixels", pixels_str)))?),
+            None => None,
+        };
+        let tile_grid = match (tile, tile_pixels) {
+            (Some((tile, scheme)), Some(pixels)) => {
+                let tile_crs = scheme.crs().epsg();
+                if proj_code.is_some_and(|code| code != tile_crs) || args.contains_id("tr") {
+                    return Err(TiffError::GenericError(format!(
+                        "--tile-pixels resamples onto the tile grid in EPSG:{} and cannot be combined with another --proj or --tr",
+                        tile_crs)));
+                }
+                let bounds = tile_utils::tile_bounds(tile, scheme)?;
+                info!("Resampling the tile to {}x{} pixels in EPSG:{}", pixels, pixels, tile_crs);
+                Some((tile_crs, bounds, bounds.width() / pixels as f64))
+            },
+            (None, Some(_)) => return Err(TiffError::GenericError("--tile-pixels needs --tile".to_string())),
+            _ => None,
+        };
+        let proj_code = tile_grid.map(|(tile_crs, _, _)| tile_crs).or(proj_code);
+
         info!("Target projection code: {:?}", proj_code);
 
+        // Target resolution and grid alignment of the reprojection
+        let resolution = match args.get_one::<String>("tr") {
+            Some(tr_str) => {
+                let values = tr_str.split(',')
+                    .map(|value| value.trim().parse::<f64>())
+                    .collect::<Result<Vec<f64>, _>>()
+                    .map_err(|e| TiffError::GenericError(format!("Invalid target resolution '{}': {}", tr_str, e)))?;
+                match values[..] {
+                    [resolution] => Some((resolution, resolution)),
+                    [x_resolution, y_resolution] => Some((x_resolution, y_resolution)),
+                    _ => return Err(TiffError::GenericError(format!(
+                        "Target resolution must be XRES or XRES,YRES, got '{}'", tr_str))),
+                }
+            },
+            None => tile_grid.map(|(_, _, resolution)| (resolution, resolution)),
+        };
+        let memory_budget = match args.get_one::<String>("warp-memory") {
+            Some(memory_str) => Some(memory_utils::parse_megabytes(memory_str, "warp memory")?),
+            None => None,
+        };
+        let nodata_option = |name: &str| -> TiffResult<Option<f64>> {
+            match args.get_one::<String>(name) {
+                Some(value_str) => value_str.trim().parse::<f64>()
+                    .map(Some)
+                    .map_err(|e| TiffError::GenericError(format!("Invalid --{} '{}': {}", name, value_str, e))),
+                None => Ok(None),
+            }
+        };
+        let error_threshold = match args.get_one::<String>("error-threshold") {
+            Some(threshold_str) => threshold_str.trim().parse::<f64>()
+                .ok()
+                .filter(|threshold| *threshold >= 0.0)
+                .ok_or_else(|| TiffError::GenericError(format!("Invalid error threshold '{}', expected pixels", threshold_str)))?,
+            None => warp_utils::DEFAULT_ERROR_THRESHOLD,
+        };
+        let warp_options = WarpOptions {
+            resolution,
+            target_aligned: args.get_flag("tap") && tile_grid.is_none(),
+            target_extent: tile_grid.map(|(_, bounds, _)| bounds),
+            // A circle is masked on the whole region, which the global
+            // budget then bounds as a single window
+            memory_budget: if shape.eq_ignore_ascii_case("circle") { memory_budget } else { memory_utils::warp_budget(memory_budget) },
+            source_nodata: nodata_option("src-nodata")?,
+            target_nodata: nodata_option("dst-nodata")?,
+            target_alpha: args.get_flag("dst-alpha"),
+            error_threshold,
+            gpu: args.get_flag("gpu"),
+        };
+        if proj_code.is_none() && (warp_options.resolution.is_some() || warp_options.target_aligned
+            || memory_budget.is_some() || warp_options.source_nodata.is_some()
+            || warp_options.target_nodata.is_some() || warp_options.target_alpha
+            || args.contains_id("error-threshold") || warp_options.gpu) {
+            warn!("--tr, --tap, --warp-memory, --src-nodata, --dst-nodata, --dst-alpha, --error-threshold and --gpu only apply when reprojecting with --proj");
+        }
+
+        // Get block alignment option
+        let align_to_blocks = args.get_flag("align-to-blocks");
+        info!("Align to blocks: {}", align_to_blocks);
+
+        // Copied blocks keep the source's encoding, which only a TIFF can hold
+        let copy_blocks = args.get_flag("copy-blocks");
+        info!("Copy blocks: {}", copy_blocks);
+        if copy_blocks && !resolved_format.is_tiff() {
+            return Err(TiffError::GenericError(format!(
+                "--copy-blocks writes TIFF output, not {}", resolved_format)));
+        }
+        if copy_blocks && shape.eq_ignore_ascii_case("circle") {
+            return Err(TiffError::GenericError(
+                "--copy-blocks cannot be combined with --shape circle, which masks the decoded pixels".to_string()));
+        }
+
+        let require_full_coverage = args.get_flag("require-full-coverage");
+        info!("Require full coverage: {}", require_full_coverage);
+
+        let pad_to_bbox = args.get_flag("pad-to-bbox");
+        let fill_value = match args.get_one::<String>("fill-value") {
+            Some(value) => Some(value.parse::<f64>()
+                .map_err(|_| TiffError::GenericError(format!("Invalid fill value: {}", value)))?),
+            None => None,
+        };
+        info!("Pad to bbox: {}, fill value: {:?}", pad_to_bbox, fill_value);
+
+        // Load the datum shift grid if provided
+        let datum_grid = match args.get_one::<String>("datum-grid") {
+            Some(path) => Some(Rc::new(NtV2Grid::load(path)?)),
+            None => None,
+        };
+
         // Get colormap options
         let colormap_output = args.get_one::<String>("colormap-output").cloned();
         info!("Colormap output: {:?}", colormap_output);
@@ -169,15 +498,177 @@ impl<'a> ExtractCommand<'a> {
         let colormap_input = args.get_one::<String>("colormap-input").cloned();
         info!("Colormap input: {:?}", colormap_input);
 
-        // Get array extraction options
-        let array_mode = args.get_flag("extract-array");
+        let colormap_auto = args.get_one::<String>("colormap-auto").cloned();
+        if let Some(spec) = &colormap_auto {
+            classification_utils::parse_auto_spec(spec)?;
+        }
+        info!("Automatic colormap: {:?}", colormap_auto);
+
+        // Padded windows have no pixel origin in the source, which the
+        // reprojection and colormap writers rely on for georeferencing
+        if memory_budget.is_some() && shape.eq_ignore_ascii_case("circle") {
+            return Err(TiffError::GenericError(
+                "--warp-memory cannot be combined with --shape circle, which masks the whole region at once".to_string()));
+        }
+
+        if pad_to_bbox && (proj_code.is_some() || colormap_input.is_some() || colormap_auto.is_some()) {
+            return Err(TiffError::GenericError(
+                "--pad-to-bbox cannot be combined with --proj, --colormap-input or --colormap-auto".to_string()));
+        }
+
+        let colormap_trim_black = args.get_flag("colormap-trim-black");
+
+        let icc_profile = args.get_one::<String>("icc-profile").cloned();
+        info!("ICC profile: {:?}", icc_profile);
+
+        let use_sidecars = args.get_flag("use-sidecars");
+        info!("Sidecar georeferencing: {}", use_sidecars);
+
+        let provenance = !args.get_flag("no-provenance");
+        info!("Provenance metadata: {}", provenance);
+
+        let report = args.get_one::<String>("report").cloned();
+        info!("Extraction report: {:?}", report);
+
+        let sub_ifd = match args.get_one::<String>("subifd") {
+            Some(value) => Some(value.parse::<usize>()
+                .map_err(|_| TiffError::GenericError(format!("Invalid SubIFD index: {}", value)))?),
+            None => None,
+        };
+        info!("SubIFD: {:?}", sub_ifd);
+
+        // Get array extraction options; array output formats imply array mode
+        let array_mode = args.get_flag("extract-array") || resolved_format.is_array();
         info!("Array extraction mode: {}", array_mode);
+        if let Some(format) = output_format.filter(|format| array_mode && !format.is_array()) {
+            return Err(TiffError::GenericError(format!(
+                "--output-format {} is an image format and cannot be used for array extraction", format)));
+        }
 
-        let array_format = args.get_one::<String>("array-format")
-            .cloned()
-            .unwrap_or_else(|| "csv".to_string());
+        // An image extraction can also write its window as an array
+        let array_output = args.get_one::<String>("array-output").cloned();
+        info!("Array output: {:?}", array_output);
+        if array_output.is_some() && array_mode {
+            return Err(TiffError::GenericError(
+                "--array-output adds an array to an image extraction; in array mode the output already is one".to_string()));
+        }
+        if array_output.is_some() && proj_code.is_some() {
+            return Err(TiffError::GenericError(
+                "--array-output cannot be combined with --proj, the array would not match the reprojected image".to_string()));
+        }
+
+        // An explicit --array-format must agree with an explicit --output-format,
+        // unless it is the format of a separate array output
+        let array_format = match args.get_one::<String>("array-format") {
+            Some(format) if args.value_source("array-format") == Some(ValueSource::CommandLine) => {
+                let conflicting = output_format
+                    .filter(|requested| array_output.is_none() && OutputFormat::from_name(format).ok() != Some(*requested));
+                if let Some(requested) = conflicting {
+                    return Err(TiffError::GenericError(format!(
+                        "--array-format {} conflicts with --output-format {}", format, requested)));
+                }
+                format.clone()
+            },
+            _ if resolved_format.is_array() => resolved_format.name().to_string(),
+            _ => match &array_output {
+                Some(path) => OutputFormat::from_extension(path)
+                    .filter(OutputFormat::is_array)
+                    .map(|format| format.name().to_string())
+                    .ok_or_else(|| TiffError::GenericError(format!(
+                        "Cannot tell the array format of {} from its extension, pass --array-format", path)))?,
+                None => "csv".to_string(),
+            },
+        };
         info!("Array format: {}", array_format);
 
+        let normalize = args.get_one::<String>("normalize").cloned();
+        if normalize.is_some() && !["npy", "npz", "safetensors"].contains(&array_format.to_lowercase().as_str()) {
+            return Err(TiffError::GenericError(format!(
+                "--normalize requires --array-format npy, npz or safetensors (got {})", array_format)));
+        }
+        info!("Normalization: {:?}", normalize);
+
+        let elevation_units = args.get_one::<String>("elevation-units").cloned();
+        if let Some(units) = &elevation_units {
+            LinearUnit::from_string(units)?;
+            if normalize.is_some() {
+                return Err(TiffError::GenericError(
+                    "--elevation-units cannot be combined with --normalize".to_string()));
+            }
+            if !["csv", "npy", "npz", "safetensors"].contains(&array_format.to_lowercase().as_str()) {
+                return Err(TiffError::GenericError(format!(
+                    "--elevation-units requires --array-format csv, npy, npz or safetensors (got {})", array_format)));
+            }
+        }
+        info!("Elevation units: {:?}", elevation_units);
+
+        let parse_number = |name: &str| -> TiffResult<Option<f64>> {
+            args.get_one::<String>(name)
+                .map(|value| value.parse::<f64>()
+                    .map_err(|_| TiffError::GenericError(format!("Invalid --{} value: {}", name, value))))
+                .transpose()
+        };
+        let scale = parse_number("scale")?;
+        let offset = parse_number("offset")?;
+        let raw_values = args.get_flag("raw");
+        if scale.is_some() || offset.is_some() {
+            if raw_values {
+                return Err(TiffError::GenericError(
+                    "--scale and --offset cannot be combined with --raw".to_string()));
+            }
+            if normalize.is_some() || elevation_units.is_some() {
+                return Err(TiffError::GenericError(
+                    "--scale and --offset cannot be combined with --normalize or --elevation-units".to_string()));
+            }
+            if !SCALED_FORMATS.contains(&array_format.to_lowercase().as_str()) {
+                return Err(TiffError::GenericError(format!(
+                    "--scale and --offset require --array-format csv, npy, npz or safetensors (got {})", array_format)));
+            }
+        }
+        info!("Scale: {:?}, offset: {:?}, raw values: {}", scale, offset, raw_values);
+
+        let array_coords = args.get_flag("array-coords");
+        info!("Array coordinates: {}", array_coords);
+
+        // Reprojection and coordinate arrays read georeferencing and pixels
+        // from the main IFD themselves
+        if sub_ifd.is_some() && (proj_code.is_some() || array_coords || pad_to_bbox) {
+            return Err(TiffError::GenericError(
+                "--subifd cannot be combined with --proj, --array-coords or --pad-to-bbox".to_string()));
+        }
+
+        let spatial_index = args.get_flag("spatial-index");
+        info!("Spatial index: {}", spatial_index);
+
+        // PostGIS raster options
+        let pg_table = args.get_one::<String>("pg-table")
+            .cloned()
+            .unwrap_or_else(|| "raster".to_string());
+        let pg_srid = match args.get_one::<String>("pg-srid") {
+            Some(srid_str) => Some(srid_str.parse::<i32>()
+                .map_err(|_| TiffError::GenericError(format!("Invalid SRID: {}", srid_str)))?),
+            None => None,
+        };
+        let tile_size = match args.get_one::<String>("tile-size") {
+            Some(size_str) => Some(crate::utils::postgis_utils::parse_tile_size(size_str)?),
+            None => None,
+        };
+        let pg_copy = args.get_flag("pg-copy");
+        info!("PostGIS table: {}, SRID: {:?}, tile size: {:?}, COPY: {}", pg_table, pg_srid, tile_size, pg_copy);
+
+        // Parquet options
+        let partition_by = args.get_one::<String>("partition-by")
+            .cloned()
+            .unwrap_or_else(|| "none".to_string());
+        let row_group_size = match args.get_one::<String>("row-group-size") {
+            Some(size_str) => match size_str.parse::<usize>() {
+                Ok(size) if size > 0 => size,
+                _ => return Err(TiffError::GenericError(format!("Invalid row group size: {}", size_str))),
+            },
+            None => crate::utils::parquet_utils::DEFAULT_ROW_GROUP_SIZE,
+        };
+        info!("Parquet partitioning: {}, row group size: {}", partition_by, row_group_size);
+
         // Get filter range if provided
         let filter_range = args.get_one::<String>("filter").cloned();
         info!("Filter range: {:?}", filter_range);
@@ -186,19 +677,61 @@ impl<'a> ExtractCommand<'a> {
         let filter_transparency = args.get_flag("filter-transparency");
         info!("Filter transparency: {}", filter_transparency);
 
+        // A quality travels with the lossy format it applies to, whether
+        // that format was requested or implied by the extension
+        let quality = crate::commands::output_quality(args)?;
+        let output_format = Some(resolved_format.with_quality(quality))
+            .filter(|format| *format != resolved_format)
+            .or(output_format);
+
         Ok(ExtractCommand {
             input_file,
             output_file,
+            out_pattern,
+            output_format,
             bbox_str,
             coordinate_str,
+            regions: regions.into(),
             radius,
             shape,
             crs_code,
+            crs_definition,
             proj_code,
+            proj_definition,
+            warp_options,
+            datum_grid,
+            align_to_blocks,
+            copy_blocks,
+            require_full_coverage,
+            pad_to_bbox,
+            fill_value,
             colormap_output,
             colormap_input,
+            colormap_auto,
+            colormap_trim_black,
+            icc_profile,
+            use_sidecars,
+            provenance,
+            sub_ifd,
+            source_checksum: Rc::new(OnceCell::new()),
+            report,
+            report_entries: Rc::new(RefCell::new(Vec::new())),
             array_mode,
             array_format,
+            array_output,
+            normalize,
+            elevation_units,
+            scale,
+            offset,
+            raw_values,
+            array_coords,
+            spatial_index,
+            pg_table,
+            pg_srid,
+            tile_size,
+            pg_copy,
+            partition_by,
+            row_group_size,
             filter_range,
             filter_transparency,
             logger,
@@ -219,6 +752,12 @@ impl<'a> ExtractCommand<'a> {
         // If coordinate and radius are specified, convert to bbox
         if let (Some(coord_str), Some(rad)) = (&self.coordinate_str, self.radius) {
             info!("Converting coordinate and radius to bounding box");
+            if let Some(definition) = self.crs_definition.as_ref().filter(|d| !d.is_geographic()) {
+                let radius_units = rad / definition.meters_per_unit();
+                let bbox_str = coordinate_utils::projected_coord_to_bbox(coord_str, radius_units)?;
+                info!("Calculated bounding box in the user-defined grid: {}", bbox_str);
+                return Ok(Some(bbox_str));
+            }
             let bbox_str = coordinate_utils::coord_to_bbox(
                 coord_str,
                 rad,
@@ -247,8 +786,9 @@ impl<'a> ExtractCommand<'a> {
     /// filter methods and coordinate reference systems.
     ///
     /// # Returns
-    /// An optional Region for extraction, or None to extract the entire image
-    fn determine_region(&self) -> TiffResult<Option<Region>> {
+    /// An optional Region for extraction, or None to extract the entire
+    /// image, and the padding back to the full bounding box if requested
+    fn determine_region(&self) -> TiffResult<(Option<Region>, Option<Padding>)> {
         info!("Determining extraction region");
 
         // Get the effective bounding box (either from bbox_str or calculated from coordinate+radius)
@@ -257,7 +797,7 @@ impl<'a> ExtractCommand<'a> {
         // If no spatial filter specified, use full image
         let Some(bbox_str) = effective_bbox else {
             info!("No spatial filter specified, will use full image");
-            return Ok(None);
+            return Ok((None, None));
         };
 
         info!("Using bounding box: {}", bbox_str);
@@ -278,16 +818,27 @@ impl<'a> ExtractCommand<'a> {
         info!("Loading TIFF file to determine region");
         let mut reader = TiffReader::new(self.logger);
         let tiff = reader.load(&self.input_file)?;
+        let sidecar = self.sidecar_georeference(&tiff)?;
+        self.match_user_defined_crs(&mut bbox, &tiff, &reader, sidecar.as_ref())?;
 
         // Determine extraction region based on the bounding box
         info!("Converting bounding box to pixel region");
-        let region = image_extraction_utils::determine_extraction_region(
-            bbox, &tiff, &reader, &self.input_file, self.logger)?;
+        let (region, coverage) = match &sidecar {
+            Some(sidecar) => image_extraction_utils::determine_sidecar_extraction_window(
+                &bbox, &tiff, sidecar, self.datum_grid.as_deref())?,
+            None => image_extraction_utils::determine_extraction_window(
+                bbox, &tiff, &reader, &self.input_file, self.logger, self.datum_grid.as_deref())?,
+        };
+        if let Some(coverage) = &coverage {
+            self.check_coverage(&region, coverage, &tiff, &reader, sidecar.as_ref())?;
+        }
+        let padding = self.padding(&region, coverage.as_ref(), &tiff, &reader)?;
+        let region = self.align_to_blocks(region, &tiff, &reader, sidecar.as_ref())?;
 
         info!("Determined extraction region: x={}, y={}, width={}, height={}",
               region.x, region.y, region.width, region.height);
 
-        Ok(Some(region))
+        Ok((Some(region), padding))
     }
 
     /// Extract colormap from input file if requested
@@ -305,9 +856,15 @@ impl<'a> ExtractCommand<'a> {
             return Ok(());
         };
 
+        if self.colormap_auto.is_some() {
+            info!("Colormap output will receive the generated colormap");
+            return Ok(());
+        }
+
         info!("Extracting colormap from {} to {}", self.input_file, colormap_path);
 
-        match colormap_utils::extract_colormap(&self.input_file, colormap_path, self.logger) {
+        match colormap_utils::extract_colormap(
+            &self.input_file, colormap_path, self.colormap_trim_black, self.logger) {
             Ok(_) => {
                 info!("Colormap extraction successful");
                 Ok(())
@@ -320,6 +877,52 @@ impl<'a> ExtractCommand<'a> {
         }
     }
 
+    /// Generate a classified colormap from the values of an extracted image
+    ///
+    /// The value 255 is left out of the histogram, as colormap application
+    /// renders it as white NoData. When a colormap output path is set, the
+    /// generated colormap is saved there as SLD.
+    ///
+    /// # Arguments
+    /// * `image` - The extracted image
+    ///
+    /// # Returns
+    /// The generated colormap or an error
+    fn build_auto_colormap(&self, image: &DynamicImage) -> TiffResult<ColorMap> {
+        let spec = self.colormap_auto.as_deref()
+            .ok_or_else(|| TiffError::GenericError("No automatic colormap requested".to_string()))?;
+        let (method, classes, scheme) = classification_utils::parse_auto_spec(spec)?;
+
+        info!("Generating colormap '{}' from the extracted values", spec);
+        let histogram = ValueHistogram::from_image(&image.to_luma8(), Some(255));
+        let colormap = ColorMap::from_statistics(&histogram, classes, method, scheme)?;
+
+        if let Some(colormap_path) = &self.colormap_output {
+            let layer_name = Path::new(&self.input_file)
+                .file_stem()
+                .map(|s| s.to_string_lossy().to_string())
+                .unwrap_or_else(|| "layer".to_string());
+            colormap.to_sld_file(colormap_path, &layer_name)?;
+            info!("Generated colormap saved to {}", colormap_path);
+        }
+
+        Ok(colormap)
+    }
+
+    /// Apply the --filter range to an extracted image, if one is given
+    fn filtered(&self, image: DynamicImage) -> DynamicImage {
+        let Some(filter_str) = &self.filter_range else {
+            return image;
+        };
+        match filter_utils::parse_filter_range(filter_str) {
+            Ok((min_value, max_value)) => {
+                info!("Filtering values from {} to {}", min_value, max_value);
+                filter_utils::filter_image_values(&image, min_value, max_value, 0, self.filter_transparency)
+            },
+            Err(_) => image,
+        }
+    }
+
     /// Extract image with colormap application
     ///
     /// Extracts an image and applies a colormap to it, transforming
@@ -328,12 +931,12 @@ impl<'a> ExtractCommand<'a> {
     /// # Arguments
     /// * `extractor` - Image extractor to use
     /// * `region` - Region to extract
-    /// * `colormap_path` - Path to the colormap file
+    /// * `colormap_path` - Path to the colormap file, or None to generate one
     ///
     /// # Returns
     /// Result indicating success or an error
-    fn extract_with_colormap(&self, extractor: &mut ImageExtractor, region: Option<Region>, colormap_path: &str) -> TiffResult<()> {
-        info!("Will apply colormap from {} when extracting", colormap_path);
+    fn extract_with_colormap(&self, extractor: &mut ImageExtractor, region: Option<Region>, colormap_path: Option<&str>) -> TiffResult<()> {
+        info!("Will apply colormap from {} when extracting", colormap_path.unwrap_or("the data distribution"));
 
         // First extract the image to memory for colormap application
         info!("Extracting image to memory for colormap application");
@@ -367,25 +970,32 @@ impl<'a> ExtractCommand<'a> {
             }
         }
 
-        // Load the colormap
-        info!("Loading colormap from {}", colormap_path);
-        let colormap = match colormap_utils::load_colormap(colormap_path, self.logger) {
-            Ok(cm) => {
-                info!("Colormap loaded with {} entries", cm.len());
-                cm
+        // Load the colormap, or generate it from the extracted values
+        let colormap = match colormap_path {
+            Some(colormap_path) => {
+                info!("Loading colormap from {}", colormap_path);
+                match colormap_utils::load_colormap(colormap_path, self.logger) {
+                    Ok(cm) => {
+                        info!("Colormap loaded with {} entries", cm.len());
+                        cm
+                    },
+                    Err(e) => {
+                        warn!("Failed to read colormap file: {:?}", e);
+                        warn!("Continuing with extraction without applying colormap");
+                        return extractor.extract_to_file(&self.input_file, &self.output_file, region, Some(&self.shape));
+                    }
+                }
             },
-            Err(e) => {
-                warn!("Failed to read colormap file: {:?}", e);
-                warn!("Continuing with extraction without applying colormap");
-                return extractor.extract_to_file(&self.input_file, &self.output_file, region, Some(&self.shape));
-            }
+            None => self.build_auto_colormap(&image)?,
         };
 
         info!("Applying colormap with {} entries", colormap.len());
 
-        // Convert to grayscale if not already
+        // Convert to grayscale if not already; the extracted image is no
+        // longer needed once the class values are taken from it
         info!("Converting image to grayscale");
-        let grayscale = image.to_luma8();
+        let grayscale = scratch_utils::luma_plane(&image, "the class values")?;
+        drop(image);
 
         // Apply colormap to transform image
         info!("Applying colormap to transform image");
@@ -398,7 +1008,8 @@ impl<'a> ExtractCommand<'a> {
             &self.input_file,
             region,
             self.logger,
-            Some(&self.shape)  // Pass the shape
+            Some(&self.shape),  // Pass the shape
+            self.output_format
         )
     }
 
@@ -416,13 +1027,9 @@ impl<'a> ExtractCommand<'a> {
         info!("Saving colorized image to {}", self.output_file);
 
         // Check output format
-        let is_tiff = Path::new(&self.output_file)
-            .extension()
-            .map(|ext| ext.to_string_lossy().to_lowercase())
-            .map(|ext| ext == "tif" || ext == "tiff")
-            .unwrap_or(false);
+        let format = OutputFormat::resolve(&self.output_file, self.output_format);
 
-        if is_tiff {
+        if format.is_tiff() {
             // Save as georeferenced TIFF
             info!("Saving as georeferenced TIFF");
             colormap_utils::save_colorized_tiff(
@@ -431,19 +1038,20 @@ impl<'a> ExtractCommand<'a> {
                 &self.input_file,
                 region,
                 self.logger,
-                Some(&self.shape)
+                Some(&self.shape),
+                self.output_format
             )
         } else {
             // For other formats, just save the RGB image
             info!("Saving as standard image format");
-            match rgb_image.save(&self.output_file) {
+            match format.write_image(&DynamicImage::ImageRgb8(rgb_image), &self.output_file) {
                 Ok(_) => {
                     info!("Image saved successfully");
                     Ok(())
                 },
                 Err(e) => {
                     error!("Failed to save colorized image: {}", e);
-                    Err(TiffError::GenericError(format!("Failed to save colorized image: {}", e)))
+                    Err(e)
                 }
             }
         }
@@ -452,20 +1060,30 @@ impl<'a> ExtractCommand<'a> {
     /// Extract array data from input file
     ///
     /// Extracts numeric array data from a TIFF file and saves it in the
-    /// specified format (CSV, JSON, or NPY).
+    /// specified format (CSV, JSON, NPY or safetensors), or as a normalized float32
+    /// tensor (NPY, NPZ or safetensors) when a normalization is set.
     ///
     /// # Arguments
+    /// * `extractor` - Array extractor for the plain array formats
     /// * `region` - Region to extract
+    /// * `padding` - Padding to the full bounding box, if requested
     ///
     /// # Returns
     /// Result indicating success or an error
-    fn extract_array_data(&self, region: Option<Region>) -> TiffResult<()> {
+    fn extract_array_data(&self, extractor: &mut ImageExtractor<'a>, region: Option<Region>,
+                          padding: Option<Padding>) -> TiffResult<()> {
         info!("Starting array data extraction from {} to {} in {} format",
               self.input_file, self.output_file, self.array_format);
 
-        // Test if output file is writable
+        // Test if output file is writable; partitioned Parquet output is a directory
         info!("Testing if output file is writable");
-        let test_file = std::fs::File::create(&self.output_file);
+        let partitioned = self.array_format.eq_ignore_ascii_case("parquet")
+            && !self.partition_by.eq_ignore_ascii_case("none");
+        let test_file = if partitioned {
+            std::fs::create_dir_all(&self.output_file).map_err(TiffError::from)
+        } else {
+            atomic_utils::check_writable(&self.output_file)
+        };
         match test_file {
             Ok(_) => info!("Output path is writable"),
             Err(e) => {
@@ -487,14 +1105,116 @@ impl<'a> ExtractCommand<'a> {
             }
         };
 
-        // Extract the array data to file
-        info!("Calling extract_to_array API method");
-        let result = api.extract_to_array(
-            &self.input_file,
-            &self.output_file,
-            &self.array_format,
-            region.map(|r| (r.x, r.y, r.width, r.height))
-        );
+        let scaling = self.radiometric_scaling(&api)?;
+
+        // Padding is applied by the plain array extractor only
+        let plain = self.elevation_units.is_none() && self.normalize.is_none() && scaling.is_none() && !self.spatial_index
+            && !self.array_coords && !["pgsql", "parquet"].contains(&self.array_format.to_lowercase().as_str());
+        if padding.is_some() && !plain {
+            warn!("--pad-to-bbox is not supported for {} output, writing the clipped window", self.array_format);
+        }
+
+        let pixel_region = region;
+        let region = region.map(|r| (r.x, r.y, r.width, r.height));
+
+        // Elevations converted to other units are written as float32
+        if let Some(units) = &self.elevation_units {
+            info!("Calling extract_elevation_array API method");
+            let result = api.extract_elevation_array(
+                &self.input_file,
+                &self.output_file,
+                &self.array_format,
+                units,
+                region
+            );
+            if let Err(e) = &result {
+                error!("Elevation extraction failed: {}", e);
+            }
+            return result;
+        }
+
+        // Values scaled to physical units are written as float32
+        if let Some((scale, offset)) = scaling {
+            info!("Calling extract_scaled_array API method");
+            let result = api.extract_scaled_array(
+                &self.input_file,
+                &self.output_file,
+                &self.array_format,
+                scale,
+                offset,
+                region
+            );
+            if let Err(e) = &result {
+                error!("Scaled extraction failed: {}", e);
+            }
+            return result;
+        }
+
+        // Extract the array data to file, normalized to float32 if requested
+        let result = match &self.normalize {
+            Some(method) => {
+                info!("Calling extract_normalized_array API method");
+                api.extract_normalized_array(
+                    &self.input_file,
+                    &self.output_file,
+                    &self.array_format,
+                    method,
+                    region
+                )
+            },
+            None if self.array_format.eq_ignore_ascii_case("pgsql") => {
+                info!("Calling extract_to_postgis API method");
+                let options = PostgisOptions {
+                    table: self.pg_table.clone(),
+                    srid: self.pg_srid,
+                    tile_size: self.tile_size,
+                    mode: if self.pg_copy { PostgisLoadMode::Copy } else { PostgisLoadMode::Insert },
+                    spatial_index: self.spatial_index,
+                    ..PostgisOptions::default()
+                };
+                api.extract_to_postgis(&self.input_file, &self.output_file, &options, region).map(|_| ())
+            },
+            None if self.array_format.eq_ignore_ascii_case("parquet") => {
+                info!("Calling extract_to_parquet API method");
+                api.extract_to_parquet(
+                    &self.input_file,
+                    &self.output_file,
+                    &self.partition_by,
+                    self.tile_size,
+                    self.row_group_size,
+                    region
+                ).map(|_| ())
+            },
+            None if self.spatial_index => {
+                info!("Calling extract_to_database API method");
+                api.extract_to_database(
+                    &self.input_file,
+                    &self.output_file,
+                    self.array_format.eq_ignore_ascii_case("gpkg"),
+                    true,
+                    region
+                ).map(|_| ())
+            },
+            None if self.array_coords => {
+                info!("Calling extract_to_array_with_coordinates API method");
+                api.extract_to_array_with_coordinates(
+                    &self.input_file,
+                    &self.output_file,
+                    &self.array_format,
+                    region
+                )
+            },
+            None => {
+                info!("Extracting array data");
+                extractor.set_padding(padding);
+                extractor.extract_to_array(
+                    &self.input_file,
+                    &self.output_file,
+                    &self.array_format,
+                    pixel_region
+                )
+            }
+        };
 
         // Check result
         match &result {
@@ -505,8 +1225,412 @@ impl<'a> ExtractCommand<'a> {
         result
     }
 
+    /// Write the extracted window as an array beside the image output
+    ///
+    /// Runs after the image output with the same extractor, whose block
+    /// cache still holds the window's decoded tiles or strips, so the
+    /// source is decoded once for both outputs.
+    ///
+    /// # Arguments
+    /// * `extractor` - Extractor that produced the image output
+    /// * `region` - Region that was extracted
+    /// * `array_output` - Path of the array output
+    ///
+    /// # Returns
+    /// Result indicating success or an error
+    fn write_array_output(&self, extractor: &mut ImageExtractor<'a>, region: Option<Region>,
+                          array_output: &str) -> TiffResult<()> {
+        info!("Writing the extracted window to {} as {}", array_output, self.array_format);
+        let array_data = extractor.extract_array_data(&self.input_file, region)?;
+        if self.array_coords {
+            array_data.save_with_coordinates(array_output, &self.array_format)
+        } else {
+            array_data.save_to_file(array_output, &self.array_format)
+        }
+    }
+
+    /// Determine the scaling of array values to physical units
+    ///
+    /// `--scale` and `--offset` override the scale and offset of the input's
+    /// GDAL metadata. Scaling is skipped with `--raw`, when normalizing or
+    /// converting elevations, and for non-TIFF inputs and formats that
+    /// can't hold fractional values unless it was requested explicitly.
+    ///
+    /// # Arguments
+    /// * `api` - API instance to read the input's metadata with
+    ///
+    /// # Returns
+    /// The scale and offset, or None to write the stored values
+    fn radiometric_scaling(&self, api: &crate::api::RasterKit) -> TiffResult<Option<(f64, f64)>> {
+        if self.raw_values || self.normalize.is_some() || self.elevation_units.is_some() {
+            return Ok(None);
+        }
+
+        let is_tiff = Path::new(&self.input_file).extension()
+            .and_then(|extension| extension.to_str())
+            .map(|extension| matches!(extension.to_lowercase().as_str(), "tif" | "tiff"))
+            .unwrap_or(false);
+        let declared = if is_tiff { api.radiometric_scaling(&self.input_file)? } else { None };
+
+        if self.scale.is_none() && self.offset.is_none() {
+            match declared {
+                Some((scale, offset)) => {
+                    if !SCALED_FORMATS.contains(&self.array_format.to_lowercase().as_str()) {
+                        warn!("{} output can't hold scaled values, writing stored values", self.array_format);
+                        return Ok(None);
+                    }
+                    info!("Scaling to physical units with the file's scale {} and offset {} (--raw to skip)",
+                          scale, offset);
+                },
+                None => return Ok(None),
+            }
+        }
+
+        let (declared_scale, declared_offset) = declared.unwrap_or((1.0, 0.0));
+        Ok(Some((self.scale.unwrap_or(declared_scale), self.offset.unwrap_or(declared_offset))))
+    }
+
+    /// Embed the requested ICC profile in the extracted file
+    ///
+    /// Only TIFF outputs can carry a profile; other formats are left as
+    /// written with a warning.
+    ///
+    /// # Returns
+    /// Result indicating success or an error
+    fn embed_icc_profile(&self) -> TiffResult<()> {
+        let Some(profile_path) = &self.icc_profile else {
+            return Ok(());
+        };
+
+        let output = Path::new(&self.output_file);
+        let is_tiff = OutputFormat::resolve(&self.output_file, self.output_format).is_tiff();
+        if !is_tiff || !output.exists() {
+            warn!("ICC profiles can only be embedded in TIFF output, not {}", self.output_file);
+            return Ok(());
+        }
+
+        let profile = icc_utils::load_icc_profile(profile_path)?;
+        icc_utils::embed_icc_profile(&self.output_file, &profile)?;
+        self.logger.log(&format!("Embedded ICC profile {} in {}", profile_path, self.output_file))?;
+        Ok(())
+    }
+
+    /// Provenance record for the current extraction
+    ///
+    /// The input checksum is computed on first use and reused for the other
+    /// regions of a multi-region run.
+    ///
+    /// # Returns
+    /// The record, or None if provenance metadata is disabled
+    fn provenance(&self) -> TiffResult<Option<Provenance>> {
+        if !self.provenance {
+            return Ok(None);
+        }
+
+        let checksum = match self.source_checksum.get() {
+            Some(checksum) => checksum.clone(),
+            None => {
+                let checksum = file_checksum(&self.input_file)?;
+                self.source_checksum.get_or_init(|| checksum).clone()
+            },
+        };
+        let crs = match (self.crs_code, &self.crs_definition) {
+            (Some(code), _) if code != USER_DEFINED => Some(format!("EPSG:{}", code)),
+            (_, Some(definition)) => Some(definition.to_proj_string()),
+            _ => None,
+        };
+        let parameters = std::env::args().skip(1)
+            .map(|arg| if arg.contains(char::is_whitespace) { format!("\"{}\"", arg) } else { arg })
+            .collect::<Vec<_>>()
+            .join(" ");
+
+        Ok(Some(Provenance {
+            source: self.input_file.clone(),
+            checksum,
+            bbox: self.determine_effective_bbox()?,
+            crs,
+            parameters,
+        }))
+    }
+
+    /// Sidecar georeferencing for the input, when requested and needed
+    ///
+    /// Sidecars are only consulted with `--use-sidecars` and only for
+    /// inputs without GeoTIFF tags of their own.
+    ///
+    /// # Arguments
+    /// * `tiff` - Parsed input TIFF
+    ///
+    /// # Returns
+    /// The sidecar georeferencing, or `None` if it should not or cannot be used
+    fn sidecar_georeference(&self, tiff: &TIFF) -> TiffResult<Option<SidecarGeoreference>> {
+        if !self.use_sidecars {
+            return Ok(None);
+        }
+        if sidecar_utils::has_internal_georeference(tiff) {
+            info!("{} has GeoTIFF tags, ignoring sidecar files", self.input_file);
+            return Ok(None);
+        }
+
+        let sidecar = sidecar_utils::find_sidecar_georeference(&self.input_file)?;
+        if sidecar.is_none() {
+            warn!("No .tfw, .wld or .aux.xml georeferencing found next to {}", self.input_file);
+        }
+        Ok(sidecar)
+    }
+
+    /// Match a --crs definition against the raster's own CRS
+    ///
+    /// Local grids have no EPSG code to compare, so the definitions are
+    /// compared instead. When they agree the bounding box is taken to be in
+    /// the raster CRS; transforming to or from a user-defined system is not
+    /// supported.
+    ///
+    /// # Arguments
+    /// * `bbox` - Bounding box whose CRS code is updated
+    /// * `tiff` - Parsed input TIFF
+    /// * `reader` - Reader that loaded the TIFF
+    /// * `sidecar` - Sidecar georeferencing, if the raster uses it
+    ///
+    /// # Returns
+    /// Result indicating success, or an error if the systems differ
+    fn match_user_defined_crs(&self, bbox: &mut BoundingBox, tiff: &TIFF, reader: &TiffReader,
+                              sidecar: Option<&SidecarGeoreference>) -> TiffResult<()> {
+        let Some(definition) = &self.crs_definition else {
+            return Ok(());
+        };
+
+        let (raster_definition, raster_code) = match sidecar {
+            Some(sidecar) => (
+                sidecar.wkt.as_deref().and_then(|wkt| ProjDefinition::from_wkt(wkt).ok()),
+                sidecar.epsg.unwrap_or(USER_DEFINED),
+            ),
+            None => {
+                let (Some(ifd), Some(handler)) = (tiff.ifds.first(), reader.get_byte_order_handler()) else {
+                    return Ok(());
+                };
+                let file_path = reader.get_file_path().unwrap_or(&self.input_file);
+                let geo_info = GeoKeyParser::extract_geo_info(ifd, handler, file_path)?;
+                (ProjDefinition::from_geo_info(&geo_info), geo_info.epsg_code)
+            }
+        };
+
+        let user_defined = bbox.epsg == Some(USER_DEFINED) || raster_code == USER_DEFINED;
+        if bbox.epsg == Some(raster_code) && !user_defined {
+            return Ok(());
+        }
+
+        match raster_definition {
+            Some(raster) if raster.is_equivalent(definition) => {
+                info!("CRS definition matches the raster CRS {}", raster.to_proj_string());
+                bbox.epsg = Some(raster_code);
+            },
+            Some(raster) if user_defined => {
+                return Err(TiffError::GenericError(format!(
+                    "The --crs definition '{}' differs from the raster CRS '{}'; \
+                     coordinates cannot be transformed between them",
+                    definition.to_proj_string(), raster.to_proj_string())));
+            },
+            None if user_defined => {
+                warn!("Cannot describe the raster CRS, assuming coordinates are in it");
+                bbox.epsg = Some(raster_code);
+            },
+            // Both systems are registered, the regular transformation applies
+            _ => {}
+        }
+        Ok(())
+    }
+
+    /// Write a `.prj` describing a user-defined target projection
+    ///
+    /// # Arguments
+    /// * `definition` - Target projection given with --proj
+    ///
+    /// # Returns
+    /// Result indicating success or an error
+    fn write_projection_file(&self, definition: &ProjDefinition) -> TiffResult<()> {
+        let prj_path = Path::new(&self.output_file).with_extension("prj");
+        std::fs::write(&prj_path, definition.to_wkt())?;
+        info!("Wrote user-defined projection {} to {}", definition.to_proj_string(), prj_path.display());
+        Ok(())
+    }
+
+    /// Write a world file next to the extracted image
+    ///
+    /// Extractions georeferenced through sidecars get the same treatment in
+    /// return: a world file for the extracted window, plus a `.prj` when the
+    /// CRS is known as WKT.
+    ///
+    /// # Arguments
+    /// * `region` - Extracted region, or `None` for the full image
+    /// * `padding` - Padding around the region, if the output was padded
+    ///
+    /// # Returns
+    /// Result indicating success or an error
+    fn write_output_sidecars(&self, region: Option<Region>, padding: Option<Padding>) -> TiffResult<()> {
+        if !self.use_sidecars || !Path::new(&self.output_file).exists() {
+            return Ok(());
+        }
+
+        let mut reader = TiffReader::new(self.logger);
+        let tiff = reader.load(&self.input_file)?;
+        let Some(sidecar) = self.sidecar_georeference(&tiff)? else {
+            return Ok(());
+        };
+
+        let (x, y) = region.map(|r| (r.x, r.y)).unwrap_or((0, 0));
+        let world_path = sidecar_utils::world_file_path(&self.output_file);
+        let geotransform = sidecar.shifted(x, y);
+        let geotransform = padding.map_or(geotransform, |padding| padding.shift(&geotransform));
+        sidecar_utils::write_world_file(&world_path, &geotransform)?;
+
+        if let Some(wkt) = &sidecar.wkt {
+            let prj_path = Path::new(&self.output_file).with_extension("prj");
+            std::fs::write(&prj_path, wkt)?;
+            info!("Wrote projection file {}", prj_path.display());
+        }
+
+        self.logger.log(&format!("Wrote sidecar georeferencing {} for {}", world_path, self.output_file))?;
+        Ok(())
+    }
+
+    /// Expand a region to the block grid if --align-to-blocks is set
+    ///
+    /// The geotransform of the aligned window is reported so that windows
+    /// extracted this way can be mosaicked without resampling.
+    ///
+    /// # Arguments
+    /// * `region` - Region computed from the spatial filter
+    /// * `tiff` - Parsed input file
+    /// * `reader` - Reader the file was loaded with
+    /// * `sidecar` - Sidecar georeferencing, if used
+    ///
+    /// # Returns
+    /// The aligned region, or the region unchanged when alignment is off
+    fn align_to_blocks(&self, region: Region, tiff: &TIFF, reader: &TiffReader,
+                       sidecar: Option<&SidecarGeoreference>) -> TiffResult<Region> {
+        if !self.align_to_blocks {
+            return Ok(region);
+        }
+        let ifd = tiff.main_ifd()
+            .ok_or_else(|| TiffError::GenericError("No IFDs found in input file".to_string()))?;
+        let aligned = tiff_extraction_utils::align_region_to_blocks(region, ifd)?;
+
+        if let Some(geotransform) = self.input_geotransform(tiff, reader, sidecar) {
+            info!("Aligned window geotransform: {}", geotransform.translated(aligned.x, aligned.y));
+        }
+
+        self.logger.log(&format!("Aligned extraction window to blocks: x={}, y={}, width={}, height={}",
+                                 aligned.x, aligned.y, aligned.width, aligned.height))?;
+        Ok(aligned)
+    }
+
+    /// Geotransform of the input, from the sidecar if one is used
+    fn input_geotransform(&self, tiff: &TIFF, reader: &TiffReader,
+                          sidecar: Option<&SidecarGeoreference>) -> Option<Affine> {
+        if let Some(sidecar) = sidecar {
+            return Some(sidecar.geotransform);
+        }
+        let ifd = tiff.main_ifd()?;
+        let handler = reader.get_byte_order_handler()?;
+        let file_path = reader.get_file_path().unwrap_or(&self.input_file);
+        image_extraction_utils::calculate_geotransform(ifd, handler, file_path).ok()
+    }
+
+    /// Report a bounding box that is only partly on the raster
+    ///
+    /// Prints the share of the box that will be extracted and the clipped
+    /// extent. Fails when the box misses the raster, unless the output is
+    /// padded to it, and when `--require-full-coverage` is set.
+    ///
+    /// # Arguments
+    /// * `region` - Clipped region that will be extracted
+    /// * `coverage` - Coverage of the requested window
+    /// * `tiff` - Parsed input file
+    /// * `reader` - Reader the file was loaded with
+    /// * `sidecar` - Sidecar georeferencing, if used
+    ///
+    /// # Returns
+    /// An error if the box misses the raster, or full coverage is required but missing
+    fn check_coverage(&self, region: &Region, coverage: &Coverage, tiff: &TIFF, reader: &TiffReader,
+                      sidecar: Option<&SidecarGeoreference>) -> TiffResult<()> {
+        if coverage.is_complete() {
+            info!("Bounding box lies entirely within the raster");
+            return Ok(());
+        }
+
+        let mut report = format!(
+            "Bounding box only partially covered: {:.2}% of {}x{} requested pixels lie on the raster, {} pixels outside",
+            coverage.percent(), coverage.requested_width, coverage.requested_height, coverage.outside_pixels());
+        match (coverage.clipped, self.input_geotransform(tiff, reader, sidecar)) {
+            (Some(_), Some(geotransform)) => {
+                let clipped = geotransform.translated(region.x, region.y).bounds(region.width, region.height);
+                report.push_str(&format!("; clipped bbox {},{},{},{}",
+                                         clipped.min_x, clipped.min_y, clipped.max_x, clipped.max_y));
+            },
+            (None, _) => report.push_str("; the bounding box does not intersect the raster"),
+            _ => {},
+        }
+
+        warn!("{}", report);
+        self.logger.log(&report)?;
+        println!("{}", report);
+
+        if coverage.clipped.is_none() && !self.pad_to_bbox {
+            return Err(TiffError::GenericError(
+                "Bounding box does not intersect the raster; add --pad-to-bbox for an output of fill values".to_string()));
+        }
+        if self.require_full_coverage {
+            return Err(TiffError::GenericError(format!(
+                "Bounding box is not fully covered by the raster ({:.2}% covered) and --require-full-coverage is set",
+                coverage.percent())));
+        }
+        Ok(())
+    }
+
+    /// Padding that restores the full bounding box, if requested
+    ///
+    /// The padded pixels take `--fill-value`, or the source NoData value
+    /// when it is omitted.
+    ///
+    /// # Arguments
+    /// * `region` - Region that will be extracted
+    /// * `coverage` - Coverage of the bounding box, if it could be measured
+    /// * `tiff` - The loaded input file
+    /// * `reader` - Reader the input was loaded with
+    ///
+    /// # Returns
+    /// The padding, None if not requested or not needed, or an error if
+    /// there is neither a fill value nor a source NoData value
+    fn padding(&self, region: &Region, coverage: Option<&Coverage>, tiff: &TIFF,
+               reader: &TiffReader) -> TiffResult<Option<Padding>> {
+        if !self.pad_to_bbox {
+            return Ok(None);
+        }
+        let Some(coverage) = coverage else {
+            warn!("Coverage of the bounding box is unknown, output will not be padded");
+            return Ok(None);
+        };
+        if coverage.clipped.is_none() {
+            warn!("Bounding box does not intersect the raster, the output is all fill");
+        }
+
+        let Some(padding) = coverage.padding(region, self.fill_value) else {
+            return Ok(None);
+        };
+        let nodata = tiff.main_ifd()
+            .filter(|ifd| ifd.has_tag(tags::GDAL_NODATA))
+            .and_then(|ifd| tiff_extraction_utils::extract_nodata_value(ifd, reader).trim().parse::<f64>().ok());
+        let fill = padding.fill_value(nodata).map_err(|_| TiffError::GenericError(format!(
+            "{} declares no NoData value; set --fill-value for the pixels padded to the bounding box", self.input_file)))?;
+
+        info!("Padding output to {}x{} with {}, extracted window at ({}, {})",
+              padding.width, padding.height, fill, padding.offset_x, padding.offset_y);
+        Ok(Some(Padding { fill: Some(fill), ..padding }))
+    }
+
     /// Determine region with radius information
-    fn determine_region_with_radius(&self, radius_meters: Option<f64>) -> TiffResult<Option<Region>> {
+    fn determine_region_with_radius(&self, radius_meters: Option<f64>) -> TiffResult<(Option<Region>, Option<Padding>)> {
         info!("Determining extraction region with radius information");
 
         // Get the effective bounding box (either from bbox_str or calculated from coordinate+radius)
@@ -515,7 +1639,7 @@ impl<'a> ExtractCommand<'a> {
         // If no spatial filter specified, use full image
         let Some(bbox_str) = effective_bbox else {
             info!("No spatial filter specified, will use full image");
-            return Ok(None);
+            return Ok((None, None));
         };
 
         info!("Using bounding box: {}", bbox_str);
@@ -542,37 +1666,62 @@ impl<'a> ExtractCommand<'a> {
         info!("Loading TIFF file to determine region");
         let mut reader = TiffReader::new(self.logger);
         let tiff = reader.load(&self.input_file)?;
+        let sidecar = self.sidecar_georeference(&tiff)?;
+        self.match_user_defined_crs(&mut bbox, &tiff, &reader, sidecar.as_ref())?;
 
         // Determine extraction region based on the bounding box
         info!("Converting bounding box to pixel region");
-        let region = image_extraction_utils::determine_extraction_region(
-            bbox, &tiff, &reader, &self.input_file, self.logger)?;
+        let (region, coverage) = match &sidecar {
+            Some(sidecar) => image_extraction_utils::determine_sidecar_extraction_window(
+                &bbox, &tiff, sidecar, self.datum_grid.as_deref())?,
+            None => image_extraction_utils::determine_extraction_window(
+                bbox, &tiff, &reader, &self.input_file, self.logger, self.datum_grid.as_deref())?,
+        };
+        if let Some(coverage) = &coverage {
+            self.check_coverage(&region, coverage, &tiff, &reader, sidecar.as_ref())?;
+        }
+        let padding = self.padding(&region, coverage.as_ref(), &tiff, &reader)?;
+        let region = self.align_to_blocks(region, &tiff, &reader, sidecar.as_ref())?;
 
         info!("Determined extraction region: x={}, y={}, width={}, height={}",
              region.x, region.y, region.width, region.height);
 
-        Ok(Some(region))
+        Ok((Some(region), padding))
     }
-}
 
-impl<'a> Command for ExtractCommand<'a> {
-    /// Execute the extract command
+    /// Extract the region of this command into its output file
     ///
-    /// This is the main entry point for the extract command. It determines
-    /// the extraction region, handles colormap extraction if requested, and
-    /// then performs either image or array extraction.
+    /// # Arguments
+    /// * `extractor` - Extractor shared by all regions of the invocation
     ///
     /// # Returns
     /// Result indicating success or an error
-    fn execute(&self) -> TiffResult<()> {
-        info!("Executing extract command with array_mode={}", self.array_mode);
+    fn run(&self, extractor: &mut ImageExtractor<'a>) -> TiffResult<()> {
+        let started = Instant::now();
+        let mut window = None;
+        let result = metrics::time("extract", || self.extract_output(extractor, &mut window));
+        if self.report.is_some() {
+            self.record_report(window, started.elapsed(), result.as_ref().err());
+        }
+        result
+    }
 
+    /// Extract the region to the output file
+    ///
+    /// # Arguments
+    /// * `extractor` - Extractor for the input file
+    /// * `window` - Set to the pixel window once it is known
+    ///
+    /// # Returns
+    /// Result indicating success or an error
+    fn extract_output(&self, extractor: &mut ImageExtractor<'a>, window: &mut Option<Region>) -> TiffResult<()> {
         // Determine region to extract
         info!("Determining extraction region");
-        let region = match self.determine_region() {
-            Ok(r) => {
+        let (region, padding) = match metrics::time("region", || self.determine_region()) {
+            Ok((r, padding)) => {
                 info!("Region determination successful: {:?}", r);
-                r
+                *window = r;
+                (r, padding)
             },
             Err(e) => {
                 error!("Failed to determine region: {}", e);
@@ -580,100 +1729,72 @@ impl<'a> Command for ExtractCommand<'a> {
             }
         };
 
-        // Handle colormap extraction if requested (for both image and array modes)
-        info!("Handling colormap extraction");
-        if let Err(e) = self.handle_colormap_extraction() {
-            error!("Colormap extraction failed: {}", e);
-            return Err(e);
-        }
-
         if self.array_mode {
             // Array extraction mode
             info!("Using array extraction mode");
-            self.extract_array_data(region)
+            self.extract_array_data(extractor, region, padding)
         } else {
             // Image extraction mode
             info!("Using image extraction mode");
             info!("Extracting image data from {} to {}", self.input_file, self.output_file);
 
-            // Create an extractor instance
-            let mut extractor = ImageExtractor::new(self.logger);
+            extractor.set_padding(padding);
+            extractor.set_provenance(self.provenance()?);
+
+            // The array output reads the blocks the image output decoded
+            if let Some(array_output) = &self.array_output {
+                atomic_utils::check_writable(array_output)?;
+                extractor.set_block_caching(true);
+            }
 
             // Check for reprojection requirement
-            if let Some(proj_code) = self.proj_code {
+            let result = if let Some(proj_code) = self.proj_code {
                 info!("Reprojection requested to EPSG:{}", proj_code);
 
-                // Handle extraction with or without colormap
-                if let Some(colormap_path) = &self.colormap_input {
-                    // Extract image data to memory first
-                    let mut image = extractor.extract_image(&self.input_file, region)?;
-
-                    // Apply filtering if specified
-                    if let Some(filter_str) = &self.filter_range {
-                        if let Ok((min_value, max_value)) = filter_utils::parse_filter_range(filter_str) {
-                            info!("Filtering values from {} to {}", min_value, max_value);
-                            image = filter_utils::filter_image_values(
-                                &image,
-                                min_value,
-                                max_value,
-                                0,
-                                self.filter_transparency
-                            );
+                // The colormap is set up once for all source windows; an
+                // automatic one is built from the whole region
+                let colormap = match (&self.colormap_input, self.colormap_auto.is_some()) {
+                    (Some(colormap_path), _) => Some(colormap_utils::load_colormap(colormap_path, self.logger)?),
+                    (None, true) => {
+                        if self.warp_options.memory_budget.is_some() {
+                            warn!("An automatic colormap reads the whole region, whatever the warp memory budget");
                         }
-                    }
-
-                    // Apply colormap to the extracted image
-                    let grayscale = image.to_luma8();
-                    let colormap = colormap_utils::load_colormap(colormap_path, self.logger)?;
-                    let rgb_image = colormap_utils::apply_colormap_to_image(&grayscale, &colormap);
-
-                    // Reproject and save image
-                    reprojection_utils::reproject_and_save(
-                        &DynamicImage::ImageRgb8(rgb_image),
-                        &self.input_file,
-                        &self.output_file,
-                        region,
-                        proj_code,
-                        self.logger,
-                        Some(&self.shape)
-                    )
-                } else {
-                    // Extract image first
-                    let mut image = extractor.extract_image(&self.input_file, region)?;
-
-                    // Apply filtering if specified
-                    if let Some(filter_str) = &self.filter_range {
-                        if let Ok((min_value, max_value)) = filter_utils::parse_filter_range(filter_str) {
-                            info!("Filtering values from {} to {}", min_value, max_value);
-                            image = filter_utils::filter_image_values(
-                                &image,
-                                min_value,
-                                max_value,
-                                0,
-                                self.filter_transparency
-                            );
-                        }
-                    }
-
-                    // Reproject and save without colormap
-                    reprojection_utils::reproject_and_save(
-                        &image,
-                        &self.input_file,
-                        &self.output_file,
-                        region,
-                        proj_code,
-                        self.logger,
-                        Some(&self.shape)
-                    )
-                }
+                        let image = self.filtered(extractor.extract_image(&self.input_file, region)?);
+                        Some(self.build_auto_colormap(&image)?)
+                    },
+                    (None, false) => None,
+                };
+
+                // Each source window is filtered and coloured as it is read
+                let mut read_window = |window: Region| -> TiffResult<DynamicImage> {
+                    let image = self.filtered(extractor.extract_image(&self.input_file, Some(window))?);
+                    Ok(match &colormap {
+                        Some(colormap) => DynamicImage::ImageRgb8(
+                            colormap_utils::apply_colormap_to_image(&image.to_luma8(), colormap)),
+                        None => image,
+                    })
+                };
+
+                reprojection_utils::reproject_and_save(
+                    &mut read_window,
+                    &self.input_file,
+                    &self.output_file,
+                    region,
+                    proj_code,
+                    self.proj_definition.as_ref(),
+                    &self.warp_options,
+                    self.logger,
+                    Some(&self.shape),
+                    self.output_format
+                )
             } else {
                 // No reprojection requested - use standard extraction
                 info!("No reprojection requested, using standard extraction");
 
                 // Handle extraction with or without colormap
-                if let Some(colormap_path) = &self.colormap_input {
+                if self.colormap_input.is_some() || self.colormap_auto.is_some() {
                     // Extract with colormap
-                    self.extract_with_colormap(&mut extractor, region, colormap_path)
+                    self.extract_with_colormap(extractor, region, self.colormap_input.as_deref())
                 } else {
                     // Check if we need to filter
                     if let Some(filter_str) = &self.filter_range {
@@ -700,13 +1821,286 @@ impl<'a> Command for ExtractCommand<'a> {
                         };
 
                         // Save the filtered image
-                        crate::utils::mask_utils::save_shaped_image(&filtered_image, &self.output_file, &self.shape)
+                        crate::utils::mask_utils::save_shaped_image(&filtered_image, &self.output_file, &self.shape, self.output_format)
                     } else {
                         // Simple extraction with shape masking
-                        extractor.extract_to_file(&self.input_file, &self.output_file, region, Some(&self.shape))
+                        match self.copy_blocks && self.copy_window(region)? {
+                            true => Ok(()),
+                            false => extractor.extract_to_file(&self.input_file, &self.output_file, region, Some(&self.shape)),
+                        }
                     }
                 }
+            };
+            result?;
+
+            if let Some(array_output) = &self.array_output {
+                self.write_array_output(extractor, region, array_output)?;
+            }
+
+            // Carry sidecar georeferencing over to the extracted window
+            if self.proj_code.is_none() {
+                self.write_output_sidecars(region, padding)?;
+            }
+
+            // User-defined target systems have no code for the GeoKeys
+            if let Some(definition) = &self.proj_definition {
+                self.write_projection_file(definition)?;
+            }
+
+            // Tag the output with a user-supplied ICC profile
+            self.embed_icc_profile()
+        }
+    }
+
+    /// Crop by copying the encoded blocks of the window, if it allows that
+    ///
+    /// # Arguments
+    /// * `region` - Window to crop, the whole image if None
+    ///
+    /// # Returns
+    /// Whether the output was written; false when the window has to be
+    /// decoded instead, for instance because it is not block-aligned
+    fn copy_window(&self, region: Option<Region>) -> TiffResult<bool> {
+        let mut reader = TiffReader::new(self.logger);
+        let tiff = match reader.load(&self.input_file) {
+            Ok(tiff) => tiff,
+            Err(e) => {
+                warn!("--copy-blocks only copies from TIFF files ({}), decoding the window instead", e);
+                return Ok(false);
+            }
+        };
+        let ifd = tiff.main_ifd()
+            .ok_or_else(|| TiffError::GenericError("No IFDs found in input file".to_string()))?;
+        let window = match (region, ifd.get_dimensions()) {
+            (Some(region), _) => region,
+            (None, Some((width, height))) => Region::new(0, 0, width as u32, height as u32),
+            (None, None) => return Ok(false),
+        };
+        if let Err(e) = crop_utils::check_copyable(&reader, ifd, &window) {
+            warn!("{}, decoding the window instead (--align-to-blocks makes it block-aligned)", e);
+            return Ok(false);
+        }
+
+        let blocks = crop_utils::crop_blocks(&self.input_file, &self.output_file, Some(window),
+                                             self.provenance()?.as_ref(), self.logger)?;
+        info!("Cropped {} by copying {} blocks", self.input_file, blocks);
+        Ok(true)
+    }
+
+    /// Add the outcome of the current extraction to the report
+    ///
+    /// GeoTIFF outputs are read back for their size, georeferencing and
+    /// statistics; other formats are reported with the request and window.
+    ///
+    /// # Arguments
+    /// * `window` - Pixel window read from the source, if it was resolved
+    /// * `elapsed` - Time the extraction took
+    /// * `error` - Why the extraction failed, None if it succeeded
+    fn record_report(&self, window: Option<Region>, elapsed: Duration, error: Option<&TiffError>) {
+        let is_tiff = OutputFormat::resolve(&self.output_file, self.output_format).is_tiff();
+        let summary = if error.is_none() && is_tiff && !self.array_mode {
+            report_utils::summarize_output(&self.output_file, self.logger)
+                .inspect_err(|e| warn!("Cannot describe {} in the report: {}", self.output_file, e))
+                .ok()
+        } else {
+            None
+        };
+        let request_crs = match (self.crs_code, &self.crs_definition) {
+            (Some(code), None) if code != USER_DEFINED => Some(format!("EPSG:{}", code)),
+            (_, Some(definition)) => Some(definition.name.clone()),
+            _ => None,
+        };
+
+        self.report_entries.borrow_mut().push(ReportEntry {
+            output: self.output_file.clone(),
+            error: error.map(|e| e.to_string()),
+            bbox: self.bbox_str.clone(),
+            coordinate: self.coordinate_str.clone(),
+            radius: self.radius,
+            request_crs: request_crs.filter(|_| self.bbox_str.is_some() || self.coordinate_str.is_some()),
+            window,
+            elapsed,
+            summary,
+        });
+    }
+
+    /// Output path of one region
+    ///
+    /// Fills in the output pattern if one is given. Without a pattern,
+    /// multi-region runs number the outputs and single regions use the
+    /// output path as given.
+    ///
+    /// # Arguments
+    /// * `index` - Zero-based region index
+    /// * `total` - Number of regions in the run
+    ///
+    /// # Returns
+    /// The output path, with its parent directories created
+    fn output_path(&self, index: usize, total: usize) -> TiffResult<String> {
+        let Some(pattern) = &self.out_pattern else {
+            return Ok(match total {
+                1 => self.output_file.clone(),
+                _ => region_utils::numbered_output_path(&self.output_file, index, total),
+            });
+        };
+
+        let stem = Path::new(&self.input_file).file_stem()
+            .map(|stem| stem.to_string_lossy().into_owned())
+            .unwrap_or_default();
+        let crs = match (self.crs_code, &self.crs_definition) {
+            (Some(code), _) if code != USER_DEFINED => code.to_string(),
+            (_, Some(_)) => "custom".to_string(),
+            _ => "pixel".to_string(),
+        };
+        let date: String = crate::utils::sqlite_utils::utc_timestamp().chars()
+            .take(10)
+            .filter(|c| *c != '-')
+            .collect();
+
+        let mut values = vec![
+            ("stem", stem),
+            ("index", format!("{:0width$}", index + 1, width = total.to_string().len())),
+            ("crs", crs),
+            ("date", date),
+        ];
+        let bbox = match self.determine_effective_bbox()? {
+            Some(bbox_str) => Some(image_extraction_utils::parse_bbox(&bbox_str)?),
+            None => None,
+        };
+        if let Some(bbox) = &bbox {
+            values.extend([
+                ("minx", region_utils::format_coordinate(bbox.min_x)),
+                ("miny", region_utils::format_coordinate(bbox.min_y)),
+                ("maxx", region_utils::format_coordinate(bbox.max_x)),
+                ("maxy", region_utils::format_coordinate(bbox.max_y)),
+            ]);
+        }
+        // {x}/{y} name the requested coordinate, or the centre of the bbox
+        let point = match &self.coordinate_str {
+            Some(coordinate) => {
+                let parts: Vec<f64> = coordinate.split(',').filter_map(|v| v.trim().parse().ok()).collect();
+                (parts.len() == 2).then(|| (parts[0], parts[1]))
+            },
+            None => bbox.map(|bbox| (bbox.center().x, bbox.center().y)),
+        };
+        if let Some((x, y)) = point {
+            values.extend([
+                ("x", region_utils::format_coordinate(x)),
+                ("y", region_utils::format_coordinate(y)),
+            ]);
+        }
+
+        let output = region_utils::render_output_pattern(pattern, &values)?;
+        if let Some(parent) = Path::new(&output).parent().filter(|p| !p.as_os_str().is_empty()) {
+            std::fs::create_dir_all(parent)?;
+        }
+        info!("Output for region {}/{}: {}", index + 1, total, output);
+        Ok(output)
+    }
+
+    /// Extract every region of a multi-region invocation
+    ///
+    /// The regions share one extractor with block caching, so each tile or
+    /// strip of the input is decoded at most once. A failing region is
+    /// reported and skipped; the command fails after the others are done.
+    ///
+    /// # Arguments
+    /// * `extractor` - Extractor for the input file
+    ///
+    /// # Returns
+    /// Result indicating success or an error listing the failed regions
+    fn run_regions(&self, extractor: &mut ImageExtractor<'a>) -> TiffResult<()> {
+        let total = self.regions.len();
+        info!("Extracting {} regions from {}", total, self.input_file);
+        extractor.set_block_caching(true);
+
+        let mut failed = Vec::new();
+        let mut outputs = HashSet::new();
+        for (index, spec) in self.regions.iter().enumerate() {
+            let mut job = self.clone();
+            job.regions = Rc::from([]);
+            (job.bbox_str, job.coordinate_str) = match spec {
+                RegionSpec::Bbox(bbox) => (Some(bbox.clone()), None),
+                RegionSpec::Coordinate(coordinate) => (None, Some(coordinate.clone())),
+                RegionSpec::Cell(cell) => (Some(cell_utils::cell_bbox(cell)?), None),
+            };
+
+            if let Some(array_output) = &self.array_output {
+                job.array_output = Some(match total {
+                    1 => array_output.clone(),
+                    _ => region_utils::numbered_output_path(array_output, index, total),
+                });
+            }
+
+            let result = job.output_path(index, total).and_then(|output_file| {
+                if !outputs.insert(output_file.clone()) {
+                    warn!("Region {}/{} overwrites {}, add {{index}} to the output pattern", index + 1, total, output_file);
+                }
+                job.output_file = output_file;
+                job.run(extractor)
+            });
+            match result {
+                Ok(()) => self.logger.log(&format!("Region {}/{} extracted to {}", index + 1, total, job.output_file))?,
+                Err(e) => {
+                    error!("Region {}/{} ({:?}) failed: {}", index + 1, total, spec, e);
+                    failed.push(index + 1);
+                },
             }
         }
+
+        println!("Extracted {} of {} regions", total - failed.len(), total);
+        if !failed.is_empty() {
+            return Err(TiffError::GenericError(format!(
+                "{} of {} regions failed: {:?}", failed.len(), total, failed)));
+        }
+        Ok(())
     }
-}
\ No newline at end of file
+}
+
+impl<'a> Command for ExtractCommand<'a> {
+    /// Execute the extract command
+    ///
+    /// This is the main entry point for the extract command. It determines
+    /// the extraction region, handles colormap extraction if requested, and
+    /// then performs either image or array extraction.
+    ///
+    /// # Returns
+    /// Result indicating success or an error
+    fn execute(&self) -> TiffResult<()> {
+        info!("Executing extract command with array_mode={}", self.array_mode);
+
+        // Handle colormap extraction if requested (for both image and array modes)
+        info!("Handling colormap extraction");
+        if let Err(e) = self.handle_colormap_extraction() {
+            error!("Colormap extraction failed: {}", e);
+            return Err(e);
+        }
+
+        let mut extractor = if self.array_mode {
+            ImageExtractor::new_array_extractor(self.logger)
+        } else {
+            ImageExtractor::new(self.logger)
+        };
+        extractor.set_sub_ifd(self.sub_ifd);
+        extractor.set_output_format(self.output_format);
+
+        let started = Instant::now();
+        let result = if !self.regions.is_empty() {
+            self.run_regions(&mut extractor)
+        } else if self.out_pattern.is_none() {
+            self.run(&mut extractor)
+        } else {
+            let mut job = self.clone();
+            self.output_path(0, 1).and_then(|output_file| {
+                job.output_file = output_file;
+                job.run(&mut extractor)
+            })
+        };
+
+        // The report also lists the extractions that failed
+        if let Some(report) = &self.report {
+            report_utils::write_report(report, &self.input_file, &self.report_entries.borrow(), started.elapsed())?;
+        }
+        result
+    }
+}
@@ -0,0 +1,85 @@
+//! NoData gap filling command
+//!
+//! This module implements the command for interpolating small NoData
+//! gaps in a raster, such as voids in a digital elevation model.
+
+use clap::ArgMatches;
+use log::info;
+
+use crate::commands::command_traits::Command;
+use crate::tiff::errors::{TiffResult, TiffError};
+use crate::utils::logger::Logger;
+use crate::utils::fillnodata_utils::{self, FillNodataOptions};
+
+/// Command for filling NoData gaps by interpolation
+pub struct FillNodataCommand<'a> {
+    /// Path to the input file
+    input_file: String,
+    /// Path to the output file
+    output_file: String,
+    /// Gap filling options
+    options: FillNodataOptions,
+    /// Logger for recording operations
+    logger: &'a Logger,
+}
+
+impl<'a> FillNodataCommand<'a> {
+    /// Create a new fill nodata command
+    ///
+    /// # Arguments
+    /// * `args` - CLI argument matches from clap
+    /// * `logger` - Logger for recording operations
+    ///
+    /// # Returns
+    /// A new FillNodataCommand instance or an error
+    pub fn new(args: &ArgMatches, logger: &'a Logger) -> TiffResult<Self> {
+        let input_file = args.get_one::<String>("input")
+            .ok_or_else(|| TiffError::GenericError("Missing input file".to_string()))?
+            .clone();
+
+        let output_file = args.get_one::<String>("output")
+            .ok_or_else(|| TiffError::GenericError("Missing output file path for fillnodata".to_string()))?
+            .clone();
+
+        let mut options = FillNodataOptions::default();
+
+        if let Some(distance_str) = args.get_one::<String>("max-search-distance") {
+            options.max_search_distance = distance_str.parse::<u32>()
+                .map_err(|e| TiffError::GenericError(format!("Invalid maximum search distance: {}", e)))?;
+        }
+
+        if let Some(iterations_str) = args.get_one::<String>("smoothing-iterations") {
+            options.smoothing_iterations = iterations_str.parse::<u32>()
+                .map_err(|e| TiffError::GenericError(format!("Invalid smoothing iterations: {}", e)))?;
+        }
+
+        if let Some(nodata_str) = args.get_one::<String>("nodata") {
+            options.nodata_value = Some(nodata_str.parse::<f64>()
+                .map_err(|e| TiffError::GenericError(format!("Invalid NoData value: {}", e)))?);
+        }
+
+        info!("Fill options: search distance {} px, {} smoothing iterations",
+              options.max_search_distance, options.smoothing_iterations);
+
+        Ok(FillNodataCommand {
+            input_file,
+            output_file,
+            options,
+            logger,
+        })
+    }
+}
+
+impl<'a> Command for FillNodataCommand<'a> {
+    fn execute(&self) -> TiffResult<()> {
+        info!("Filling NoData gaps from {} to {}", self.input_file, self.output_file);
+
+        fillnodata_utils::fill_nodata_file(
+            &self.input_file, &self.output_file, &self.options, self.logger)?;
+
+        info!("NoData filling successful");
+        self.logger.log("NoData filling successful")?;
+
+        Ok(())
+    }
+}
@@ -0,0 +1,85 @@
+//! Raster search command
+//!
+//! This module implements the command that scans the headers of the
+//! rasters under a directory and lists those matching a query.
+
+use std::fs;
+
+use clap::ArgMatches;
+use log::info;
+
+use crate::commands::command_traits::Command;
+use crate::tiff::errors::{TiffResult, TiffError};
+use crate::utils::catalog_utils;
+use crate::utils::logger::Logger;
+use crate::utils::query_utils::Query;
+
+/// Command for finding rasters by their header fields
+pub struct FindCommand<'a> {
+    /// File or directory to search
+    root: String,
+    /// Condition the rasters must meet, None to list them all
+    query: Option<Query>,
+    /// Whether to list the matches as JSON instead of a table
+    json: bool,
+    /// File to write the listing to instead of printing it
+    output_file: Option<String>,
+    /// Logger for recording operations
+    logger: &'a Logger,
+}
+
+impl<'a> FindCommand<'a> {
+    /// Create a new find command
+    ///
+    /// # Arguments
+    /// * `args` - CLI argument matches from clap
+    /// * `logger` - Logger for recording operations
+    ///
+    /// # Returns
+    /// A new FindCommand instance or an error
+    pub fn new(args: &ArgMatches, logger: &'a Logger) -> TiffResult<Self> {
+        let root = args.get_one::<String>("input")
+            .ok_or_else(|| TiffError::GenericError("Missing directory to search".to_string()))?
+            .clone();
+
+        // The query is parsed up front so a typo fails before the scan
+        let query = args.get_one::<String>("where")
+            .map(|expression| Query::parse(expression))
+            .transpose()?;
+
+        Ok(FindCommand {
+            root,
+            query,
+            json: args.get_flag("json"),
+            output_file: args.get_one::<String>("output").cloned(),
+            logger,
+        })
+    }
+}
+
+impl<'a> Command for FindCommand<'a> {
+    fn execute(&self) -> TiffResult<()> {
+        let paths = catalog_utils::collect_rasters(&self.root)?;
+        info!("Searching {} raster(s) under {}", paths.len(), self.root);
+
+        let matches: Vec<_> = catalog_utils::scan(&paths, self.logger).into_iter()
+            .filter(|record| self.query.as_ref().is_none_or(|query| query.matches(record)))
+            .collect();
+        info!("{} of {} raster(s) match", matches.len(), paths.len());
+
+        let listing = if self.json {
+            catalog_utils::records_json(&matches)
+        } else {
+            format!("{}\n", catalog_utils::format_table(&matches))
+        };
+        match &self.output_file {
+            Some(path) => {
+                fs::write(path, listing)?;
+                println!("Wrote {} matching raster(s) to {}", matches.len(), path);
+            },
+            None => print!("{}", listing),
+        }
+
+        Ok(())
+    }
+}
@@ -0,0 +1,69 @@
+//! Catalog index command
+//!
+//! This module implements the command that writes a spatial index of the
+//! rasters under a directory, for looking up which files cover an area.
+
+use clap::ArgMatches;
+use log::info;
+
+use crate::commands::command_traits::Command;
+use crate::tiff::errors::{TiffResult, TiffError};
+use crate::utils::index_utils;
+use crate::utils::logger::Logger;
+use crate::utils::query_utils::Query;
+
+/// Command for indexing a directory tree of rasters
+pub struct IndexCommand<'a> {
+    /// File or directory to index
+    root: String,
+    /// Path of the GeoJSON or GeoPackage index
+    output_file: String,
+    /// Condition the indexed rasters must meet, None for all
+    query: Option<Query>,
+    /// Logger for recording operations
+    logger: &'a Logger,
+}
+
+impl<'a> IndexCommand<'a> {
+    /// Create a new index command
+    ///
+    /// # Arguments
+    /// * `args` - CLI argument matches from clap
+    /// * `logger` - Logger for recording operations
+    ///
+    /// # Returns
+    /// A new IndexCommand instance or an error
+    pub fn new(args: &ArgMatches, logger: &'a Logger) -> TiffResult<Self> {
+        let root = args.get_one::<String>("input")
+            .ok_or_else(|| TiffError::GenericError("Missing directory to index".to_string()))?
+            .clone();
+
+        let output_file = args.get_one::<String>("output")
+            .ok_or_else(|| TiffError::GenericError(
+                "Missing output path for the index (.geojson or .gpkg)".to_string()))?
+            .clone();
+
+        let query = args.get_one::<String>("where")
+            .map(|expression| Query::parse(expression))
+            .transpose()?;
+
+        Ok(IndexCommand {
+            root,
+            output_file,
+            query,
+            logger,
+        })
+    }
+}
+
+impl<'a> Command for IndexCommand<'a> {
+    fn execute(&self) -> TiffResult<()> {
+        info!("Indexing the rasters under {}", self.root);
+
+        let count = index_utils::build_index(&self.root, &self.output_file, self.query.as_ref(), self.logger)?;
+
+        println!("Indexed {} raster(s) in {}", count, self.output_file);
+
+        Ok(())
+    }
+}
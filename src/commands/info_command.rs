@@ -0,0 +1,64 @@
+//! gdalinfo-style summary command
+//!
+//! This module implements the command printing a raster summary in the
+//! layout of `gdalinfo`, for scripts migrating from GDAL.
+
+use clap::ArgMatches;
+use log::info;
+
+use crate::commands::command_traits::Command;
+use crate::tiff::errors::{TiffResult, TiffError};
+use crate::utils::{footprint_utils, info_utils};
+use crate::utils::footprint_utils::FootprintShape;
+use crate::utils::logger::Logger;
+
+/// Command for printing a gdalinfo-style summary
+pub struct InfoCommand<'a> {
+    /// Path to the input file
+    input_file: String,
+    /// Path of the GeoJSON footprint to write, if requested
+    footprint: Option<String>,
+    /// Rectangle or traced outline footprint
+    footprint_shape: FootprintShape,
+    /// Logger for recording operations
+    logger: &'a Logger,
+}
+
+impl<'a> InfoCommand<'a> {
+    /// Create a new info command
+    ///
+    /// # Arguments
+    /// * `args` - CLI argument matches from clap
+    /// * `logger` - Logger for recording operations
+    ///
+    /// # Returns
+    /// A new InfoCommand instance or an error
+    pub fn new(args: &ArgMatches, logger: &'a Logger) -> TiffResult<Self> {
+        let input_file = args.get_one::<String>("input")
+            .ok_or_else(|| TiffError::GenericError("Missing input file".to_string()))?
+            .clone();
+
+        Ok(InfoCommand {
+            input_file,
+            footprint: args.get_one::<String>("footprint").cloned(),
+            footprint_shape: if args.get_flag("footprint-outline") { FootprintShape::Outline } else { FootprintShape::Rectangle },
+            logger,
+        })
+    }
+}
+
+impl<'a> Command for InfoCommand<'a> {
+    fn execute(&self) -> TiffResult<()> {
+        info!("Describing file: {}", self.input_file);
+
+        let summary = info_utils::raster_info(&self.input_file, self.logger)?;
+        println!("{}", summary);
+
+        if let Some(footprint) = &self.footprint {
+            footprint_utils::write_footprint(&self.input_file, footprint, self.footprint_shape, self.logger)?;
+        }
+
+        self.logger.log("Info summary completed successfully")?;
+        Ok(())
+    }
+}
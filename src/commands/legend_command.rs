@@ -0,0 +1,69 @@
+//! Colormap legend command
+//!
+//! This module implements the command for rendering a legend image from
+//! a colormap file (SLD, CSV or a palette TIFF).
+
+use clap::ArgMatches;
+use log::info;
+
+use crate::commands::command_traits::Command;
+use crate::tiff::errors::{TiffResult, TiffError};
+use crate::utils::logger::Logger;
+use crate::utils::colormap_utils;
+use crate::utils::legend_utils::{self, LegendStyle};
+
+/// Command for rendering a colormap legend image
+pub struct LegendCommand<'a> {
+    /// Path to the colormap source
+    colormap_file: String,
+    /// Path to the legend image
+    output_file: String,
+    /// Legend style, derived from the colormap type when not given
+    style: Option<LegendStyle>,
+    /// Logger for recording operations
+    logger: &'a Logger,
+}
+
+impl<'a> LegendCommand<'a> {
+    /// Create a new legend command
+    ///
+    /// # Arguments
+    /// * `args` - CLI argument matches from clap
+    /// * `logger` - Logger for recording operations
+    ///
+    /// # Returns
+    /// A new LegendCommand instance or an error
+    pub fn new(args: &ArgMatches, logger: &'a Logger) -> TiffResult<Self> {
+        let colormap_file = args.get_one::<String>("input")
+            .ok_or_else(|| TiffError::GenericError("Missing colormap file".to_string()))?
+            .clone();
+
+        let output_file = args.get_one::<String>("output")
+            .ok_or_else(|| TiffError::GenericError("Missing output file path for the legend".to_string()))?
+            .clone();
+
+        let style = match args.get_one::<String>("legend-style") {
+            Some(name) => Some(LegendStyle::from_string(name)?),
+            None => None,
+        };
+
+        info!("Legend from {} to {} (style: {:?})", colormap_file, output_file, style);
+
+        Ok(LegendCommand {
+            colormap_file,
+            output_file,
+            style,
+            logger,
+        })
+    }
+}
+
+impl<'a> Command for LegendCommand<'a> {
+    fn execute(&self) -> TiffResult<()> {
+        let colormap = colormap_utils::load_colormap(&self.colormap_file, self.logger)?;
+        legend_utils::save_legend(&colormap, &self.output_file, self.style)?;
+
+        self.logger.log(&format!("Legend for {} written to {}", self.colormap_file, self.output_file))?;
+        Ok(())
+    }
+}
@@ -0,0 +1,72 @@
+//! Histogram matching command
+//!
+//! This module implements the command that balances the colours of an
+//! image against a reference image, so neighbouring orthophotos match.
+
+use clap::ArgMatches;
+use log::info;
+
+use crate::commands::command_traits::Command;
+use crate::tiff::errors::{TiffResult, TiffError};
+use crate::utils::logger::Logger;
+use crate::utils::balance_utils;
+
+/// Command for matching the histograms of an image to a reference
+pub struct MatchHistogramCommand<'a> {
+    /// Path to the image to adjust
+    input_file: String,
+    /// Path to the reference image
+    reference_file: String,
+    /// Path to the adjusted output
+    output_file: String,
+    /// Quality of lossy image outputs, if any
+    quality: Option<u8>,
+    /// Logger for recording operations
+    logger: &'a Logger,
+}
+
+impl<'a> MatchHistogramCommand<'a> {
+    /// Create a new histogram matching command
+    ///
+    /// # Arguments
+    /// * `args` - CLI argument matches from clap
+    /// * `logger` - Logger for recording operations
+    ///
+    /// # Returns
+    /// A new MatchHistogramCommand instance or an error
+    pub fn new(args: &ArgMatches, logger: &'a Logger) -> TiffResult<Self> {
+        let input_file = args.get_one::<String>("input")
+            .ok_or_else(|| TiffError::GenericError("Missing input file".to_string()))?
+            .clone();
+
+        let reference_file = args.get_one::<String>("match-histogram")
+            .ok_or_else(|| TiffError::GenericError("Missing reference image".to_string()))?
+            .clone();
+
+        let output_file = args.get_one::<String>("output")
+            .ok_or_else(|| TiffError::GenericError("Missing output file path for histogram matching".to_string()))?
+            .clone();
+
+        let quality = crate::commands::output_quality(args)?;
+
+        info!("Match {} to {}, writing {}", input_file, reference_file, output_file);
+
+        Ok(MatchHistogramCommand {
+            input_file,
+            reference_file,
+            output_file,
+            quality,
+            logger,
+        })
+    }
+}
+
+impl<'a> Command for MatchHistogramCommand<'a> {
+    fn execute(&self) -> TiffResult<()> {
+        balance_utils::match_histograms(&self.input_file, &self.reference_file, &self.output_file,
+                                        self.quality, self.logger)?;
+
+        self.logger.log(&format!("Histogram-matched {} written to {}", self.input_file, self.output_file))?;
+        Ok(())
+    }
+}
@@ -5,17 +5,82 @@
 
 pub mod command_traits;
 pub mod analyze_command;
+pub mod dump_tags_command;
+pub mod info_command;
+pub mod repair_command;
 pub mod extract_command;
+pub mod catalog_extract_command;
 pub mod convert_command;
+pub mod proximity_command;
+pub mod fillnodata_command;
+pub mod valid_mask_command;
+pub mod qa_flags_command;
+pub mod split_bands_command;
+pub mod resize_command;
+pub mod overviews_command;
+pub mod extend_command;
+pub mod orient_command;
+pub mod find_command;
+pub mod index_command;
+pub mod chips_command;
+pub mod legend_command;
+pub mod colorize_command;
+pub mod render_command;
+pub mod pansharpen_command;
+pub mod match_histogram_command;
+pub mod mosaic_command;
+pub mod colormap_transform_command;
 
 pub use command_traits::{Command, CommandFactory};
 pub use analyze_command::AnalyzeCommand;
+pub use dump_tags_command::DumpTagsCommand;
+pub use info_command::InfoCommand;
+pub use repair_command::RepairCommand;
 pub use extract_command::ExtractCommand;
+pub use catalog_extract_command::CatalogExtractCommand;
 pub use convert_command::ConvertCommand;
+pub use proximity_command::ProximityCommand;
+pub use fillnodata_command::FillNodataCommand;
+pub use valid_mask_command::ValidMaskCommand;
+pub use qa_flags_command::QaFlagsCommand;
+pub use split_bands_command::SplitBandsCommand;
+pub use resize_command::ResizeCommand;
+pub use overviews_command::OverviewsCommand;
+pub use extend_command::ExtendCommand;
+pub use orient_command::OrientCommand;
+pub use find_command::FindCommand;
+pub use index_command::IndexCommand;
+pub use chips_command::ChipsCommand;
+pub use legend_command::LegendCommand;
+pub use colorize_command::ColorizeCommand;
+pub use render_command::RenderCommand;
+pub use pansharpen_command::PansharpenCommand;
+pub use match_histogram_command::MatchHistogramCommand;
+pub use mosaic_command::MosaicCommand;
+pub use colormap_transform_command::ColormapTransformCommand;
 
 use clap::ArgMatches;
 use crate::utils::logger::Logger;
-use crate::tiff::errors::TiffResult;
+use crate::utils::catalog_extract_utils;
+use crate::tiff::compliance;
+use crate::tiff::errors::{TiffError, TiffResult};
+
+/// Read the quality of lossy image outputs from `--quality`
+///
+/// # Arguments
+/// * `args` - CLI argument matches from clap
+///
+/// # Returns
+/// The quality from 1 to 100, None when not given, or an error
+pub(crate) fn output_quality(args: &ArgMatches) -> TiffResult<Option<u8>> {
+    match args.get_one::<String>("quality") {
+        Some(value) => match value.parse::<u8>() {
+            Ok(quality) if (1..=100).contains(&quality) => Ok(Some(quality)),
+            _ => Err(TiffError::GenericError(format!("Invalid quality: {} (expected 1 to 100)", value))),
+        },
+        None => Ok(None),
+    }
+}
 
 /// Factory for creating command instances based on CLI arguments
 ///
@@ -32,12 +97,67 @@ impl RasterkitCommandFactory {
 
 impl<'a> CommandFactory<'a> for RasterkitCommandFactory {
     fn create_command(&self, args: &ArgMatches, logger: &'a Logger) -> TiffResult<Box<dyn Command + 'a>> {
+        // Strict mode applies to every TIFF any command writes
+        compliance::set_strict(args.get_flag("strict"));
+
+        // An invalid quality is refused whichever command writes the images
+        output_quality(args)?;
+
         // Determine which command to run based on args
-        if args.get_flag("extract") || args.get_flag("extract-array") {
+        if args.get_flag("find") {
+            Ok(Box::new(FindCommand::new(args, logger)?))
+        } else if args.get_flag("index") {
+            Ok(Box::new(IndexCommand::new(args, logger)?))
+        } else if args.get_flag("extract") && args.get_one::<String>("input")
+            .is_some_and(|input| catalog_extract_utils::is_catalog(input)) {
+            // A directory or index of rasters is extracted from as one mosaic
+            Ok(Box::new(CatalogExtractCommand::new(args, logger)?))
+        } else if args.get_flag("extract") || args.get_flag("extract-array") {
             // Both regular extraction and array extraction use the ExtractCommand
             Ok(Box::new(ExtractCommand::new(args, logger)?))
         } else if args.get_flag("convert") {
             Ok(Box::new(ConvertCommand::new(args, logger)?))
+        } else if args.get_flag("proximity") {
+            Ok(Box::new(ProximityCommand::new(args, logger)?))
+        } else if args.get_flag("fillnodata") {
+            Ok(Box::new(FillNodataCommand::new(args, logger)?))
+        } else if args.get_flag("valid-mask") {
+            Ok(Box::new(ValidMaskCommand::new(args, logger)?))
+        } else if args.get_one::<String>("qa-flags").is_some() {
+            Ok(Box::new(QaFlagsCommand::new(args, logger)?))
+        } else if args.get_flag("split-bands") {
+            Ok(Box::new(SplitBandsCommand::new(args, logger)?))
+        } else if args.get_one::<String>("resize").is_some() || args.get_one::<String>("target-res").is_some() {
+            Ok(Box::new(ResizeCommand::new(args, logger)?))
+        } else if args.get_flag("build-overviews") {
+            Ok(Box::new(OverviewsCommand::new(args, logger)?))
+        } else if args.get_one::<String>("extend").is_some() {
+            Ok(Box::new(ExtendCommand::new(args, logger)?))
+        } else if args.get_one::<String>("orient").is_some() {
+            Ok(Box::new(OrientCommand::new(args, logger)?))
+        } else if args.get_flag("chips") {
+            Ok(Box::new(ChipsCommand::new(args, logger)?))
+        } else if args.get_flag("colorize") {
+            Ok(Box::new(ColorizeCommand::new(args, logger)?))
+        } else if args.get_one::<String>("pansharpen").is_some() {
+            Ok(Box::new(PansharpenCommand::new(args, logger)?))
+        } else if args.get_many::<String>("mosaic").is_some() {
+            Ok(Box::new(MosaicCommand::new(args, logger)?))
+        } else if args.get_one::<String>("match-histogram").is_some() {
+            Ok(Box::new(MatchHistogramCommand::new(args, logger)?))
+        } else if args.get_flag("render") {
+            Ok(Box::new(RenderCommand::new(args, logger)?))
+        } else if args.get_flag("colormap-legend") {
+            Ok(Box::new(LegendCommand::new(args, logger)?))
+        } else if args.get_flag("dump-tags") {
+            Ok(Box::new(DumpTagsCommand::new(args, logger)?))
+        } else if args.get_flag("info") {
+            Ok(Box::new(InfoCommand::new(args, logger)?))
+        } else if args.get_flag("repair") {
+            Ok(Box::new(RepairCommand::new(args, logger)?))
+        } else if ColormapTransformCommand::is_requested(args) {
+            // Colormap value edits (rescale, offset, merge) are enabled by their own flags
+            Ok(Box::new(ColormapTransformCommand::new(args, logger)?))
         } else {
             // Default to analyze command
             Ok(Box::new(AnalyzeCommand::new(args, logger)?))
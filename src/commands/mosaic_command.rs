@@ -0,0 +1,87 @@
+//! Mosaic command
+//!
+//! This module implements the command that combines the input raster with
+//! further rasters into one mosaic, blending them where they overlap.
+
+use clap::ArgMatches;
+use log::info;
+
+use crate::commands::command_traits::Command;
+use crate::tiff::errors::{TiffResult, TiffError};
+use crate::utils::acquisition_utils::{self, DateRange};
+use crate::utils::logger::Logger;
+use crate::utils::mosaic_utils::{self, BlendMode};
+
+/// Command for mosaicking rasters
+pub struct MosaicCommand<'a> {
+    /// Paths to the rasters, the input file first
+    input_files: Vec<String>,
+    /// Path to the mosaic
+    output_file: String,
+    /// How overlapping rasters are combined
+    blend: BlendMode,
+    /// Acquisition times of the rasters to use, None for all of them
+    acquired: Option<DateRange>,
+    /// Logger for recording operations
+    logger: &'a Logger,
+}
+
+impl<'a> MosaicCommand<'a> {
+    /// Create a new mosaic command
+    ///
+    /// # Arguments
+    /// * `args` - CLI argument matches from clap
+    /// * `logger` - Logger for recording operations
+    ///
+    /// # Returns
+    /// A new MosaicCommand instance or an error
+    pub fn new(args: &ArgMatches, logger: &'a Logger) -> TiffResult<Self> {
+        let input_file = args.get_one::<String>("input")
+            .ok_or_else(|| TiffError::GenericError("Missing input file".to_string()))?
+            .clone();
+
+        let mut input_files = vec![input_file];
+        input_files.extend(args.get_many::<String>("mosaic").into_iter().flatten()
+            .flat_map(|files| files.split(','))
+            .map(|file| file.trim().to_string())
+            .filter(|file| !file.is_empty()));
+
+        let output_file = args.get_one::<String>("output")
+            .ok_or_else(|| TiffError::GenericError("Missing output file path for the mosaic".to_string()))?
+            .clone();
+
+        let blend = BlendMode::from_string(args.get_one::<String>("blend").map(|s| s.as_str()).unwrap_or("last"))?;
+
+        let acquired = args.get_one::<String>("acquired")
+            .map(|range| DateRange::from_string(range))
+            .transpose()?;
+
+        info!("Mosaic {} rasters to {} ({:?})", input_files.len(), output_file, blend);
+
+        Ok(MosaicCommand {
+            input_files,
+            output_file,
+            blend,
+            acquired,
+            logger,
+        })
+    }
+}
+
+impl<'a> Command for MosaicCommand<'a> {
+    fn execute(&self) -> TiffResult<()> {
+        let input_files = match &self.acquired {
+            Some(range) => {
+                let kept = acquisition_utils::filter_by_acquisition(&self.input_files, range, self.logger)?;
+                info!("{} of {} rasters were acquired in the date range", kept.len(), self.input_files.len());
+                kept
+            },
+            None => self.input_files.clone(),
+        };
+
+        mosaic_utils::mosaic_files(&input_files, &self.output_file, self.blend, self.logger)?;
+
+        self.logger.log(&format!("Mosaic of {} rasters written to {}", input_files.len(), self.output_file))?;
+        Ok(())
+    }
+}
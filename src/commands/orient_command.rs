@@ -0,0 +1,67 @@
+//! Rotation and flip command
+//!
+//! This module implements the command for turning or mirroring a raster
+//! while keeping its pixels on the same ground positions.
+
+use clap::ArgMatches;
+use log::info;
+
+use crate::commands::command_traits::Command;
+use crate::tiff::errors::{TiffResult, TiffError};
+use crate::utils::logger::Logger;
+use crate::utils::orient_utils::{self, Orientation};
+
+/// Command for rotating or flipping a raster
+pub struct OrientCommand<'a> {
+    /// Path to the raster to rearrange
+    input_file: String,
+    /// Path of the rearranged GeoTIFF
+    output_file: String,
+    /// Rotation or flip to apply
+    orientation: Orientation,
+    /// Logger for recording operations
+    logger: &'a Logger,
+}
+
+impl<'a> OrientCommand<'a> {
+    /// Create a new orient command
+    ///
+    /// # Arguments
+    /// * `args` - CLI argument matches from clap
+    /// * `logger` - Logger for recording operations
+    ///
+    /// # Returns
+    /// A new OrientCommand instance or an error
+    pub fn new(args: &ArgMatches, logger: &'a Logger) -> TiffResult<Self> {
+        let input_file = args.get_one::<String>("input")
+            .ok_or_else(|| TiffError::GenericError("Missing input file".to_string()))?
+            .clone();
+
+        let output_file = args.get_one::<String>("output")
+            .ok_or_else(|| TiffError::GenericError("Missing output file path for orient".to_string()))?
+            .clone();
+
+        let orientation = Orientation::from_string(args.get_one::<String>("orient")
+            .ok_or_else(|| TiffError::GenericError("Missing orientation".to_string()))?)?;
+
+        Ok(OrientCommand {
+            input_file,
+            output_file,
+            orientation,
+            logger,
+        })
+    }
+}
+
+impl<'a> Command for OrientCommand<'a> {
+    fn execute(&self) -> TiffResult<()> {
+        info!("Applying {:?} to {}", self.orientation, self.input_file);
+
+        let (width, height) = orient_utils::reorient(
+            &self.input_file, &self.output_file, self.orientation, self.logger)?;
+
+        println!("Wrote {}x{} raster to {}", width, height, self.output_file);
+
+        Ok(())
+    }
+}
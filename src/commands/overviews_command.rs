@@ -0,0 +1,81 @@
+//! Overview building command
+//!
+//! This module implements the command for adding reduced-resolution
+//! overview IFDs to a TIFF, in place or in a copy.
+
+use clap::ArgMatches;
+use log::info;
+
+use crate::commands::command_traits::Command;
+use crate::tiff::errors::{TiffResult, TiffError};
+use crate::utils::logger::Logger;
+use crate::utils::overview_utils;
+use crate::utils::resize_utils::Resampling;
+
+/// Command for building internal overviews
+pub struct OverviewsCommand<'a> {
+    /// Path to the TIFF to add overviews to
+    input_file: String,
+    /// Path of the copy to write, None to update the input
+    output_file: Option<String>,
+    /// Reduction factors, empty for the defaults
+    levels: Vec<u32>,
+    /// How overview pixels take their value
+    resampling: Resampling,
+    /// Logger for recording operations
+    logger: &'a Logger,
+}
+
+impl<'a> OverviewsCommand<'a> {
+    /// Create a new overview building command
+    ///
+    /// # Arguments
+    /// * `args` - CLI argument matches from clap
+    /// * `logger` - Logger for recording operations
+    ///
+    /// # Returns
+    /// A new OverviewsCommand instance or an error
+    pub fn new(args: &ArgMatches, logger: &'a Logger) -> TiffResult<Self> {
+        let input_file = args.get_one::<String>("input")
+            .ok_or_else(|| TiffError::GenericError("Missing input file".to_string()))?
+            .clone();
+
+        let output_file = args.get_one::<String>("output").cloned();
+
+        let levels = match args.get_one::<String>("overview-levels") {
+            Some(levels) => levels.split(',')
+                .map(|level| level.trim().parse::<u32>())
+                .collect::<Result<Vec<u32>, _>>()
+                .map_err(|e| TiffError::GenericError(format!("Invalid overview levels '{}': {}", levels, e)))?,
+            None => Vec::new(),
+        };
+
+        let resampling = match args.get_one::<String>("resampling") {
+            Some(name) => Resampling::from_string(name)?,
+            None => Resampling::Nearest,
+        };
+
+        Ok(OverviewsCommand {
+            input_file,
+            output_file,
+            levels,
+            resampling,
+            logger,
+        })
+    }
+}
+
+impl<'a> Command for OverviewsCommand<'a> {
+    fn execute(&self) -> TiffResult<()> {
+        info!("Building {:?} overviews of {}", self.resampling, self.input_file);
+
+        let sizes = overview_utils::build_overviews(
+            &self.input_file, self.output_file.as_deref(), &self.levels, self.resampling, self.logger)?;
+
+        for (width, height) in &sizes {
+            println!("Overview {}x{}", width, height);
+        }
+
+        Ok(())
+    }
+}
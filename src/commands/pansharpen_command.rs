@@ -0,0 +1,86 @@
+//! Pan-sharpening command
+//!
+//! This module implements the command that fuses a panchromatic band
+//! with multispectral bands into a sharpened RGB image.
+
+use clap::ArgMatches;
+use log::info;
+
+use crate::commands::command_traits::Command;
+use crate::commands::render_command;
+use crate::tiff::errors::{TiffResult, TiffError};
+use crate::utils::logger::Logger;
+use crate::utils::pansharpen_utils::{self, PansharpenOptions};
+
+/// Command for pan-sharpening multispectral bands
+pub struct PansharpenCommand<'a> {
+    /// Path to the multispectral raster
+    input_file: String,
+    /// Path to the panchromatic raster
+    pan_file: String,
+    /// Path to the sharpened output
+    output_file: String,
+    /// Band weights and stretch
+    options: PansharpenOptions,
+    /// Logger for recording operations
+    logger: &'a Logger,
+}
+
+impl<'a> PansharpenCommand<'a> {
+    /// Create a new pan-sharpening command
+    ///
+    /// # Arguments
+    /// * `args` - CLI argument matches from clap
+    /// * `logger` - Logger for recording operations
+    ///
+    /// # Returns
+    /// A new PansharpenCommand instance or an error
+    pub fn new(args: &ArgMatches, logger: &'a Logger) -> TiffResult<Self> {
+        let input_file = args.get_one::<String>("input")
+            .ok_or_else(|| TiffError::GenericError("Missing input file".to_string()))?
+            .clone();
+
+        let pan_file = args.get_one::<String>("pansharpen")
+            .ok_or_else(|| TiffError::GenericError("Missing panchromatic band".to_string()))?
+            .clone();
+
+        let output_file = args.get_one::<String>("output")
+            .ok_or_else(|| TiffError::GenericError("Missing output file path for pan-sharpening".to_string()))?
+            .clone();
+
+        let weights = match args.get_one::<String>("pan-weights") {
+            Some(weights_str) => Some(weights_str.split(',')
+                .map(|weight| weight.trim().parse::<f64>())
+                .collect::<Result<Vec<f64>, _>>()
+                .map_err(|e| TiffError::GenericError(format!("Invalid pan-sharpening weights '{}': {}", weights_str, e)))?),
+            None => None,
+        };
+
+        let options = PansharpenOptions {
+            weights,
+            stretch: render_command::stretch_mode(args)?,
+            gpu: args.get_flag("gpu"),
+            quality: crate::commands::output_quality(args)?,
+        };
+
+        info!("Pan-sharpen {} with {} to {}", input_file, pan_file, output_file);
+
+        Ok(PansharpenCommand {
+            input_file,
+            pan_file,
+            output_file,
+            options,
+            logger,
+        })
+    }
+}
+
+impl<'a> Command for PansharpenCommand<'a> {
+    fn execute(&self) -> TiffResult<()> {
+        pansharpen_utils::pansharpen_file(
+            &self.input_file, &self.pan_file, &self.output_file, &self.options, self.logger)?;
+
+        self.logger.log(&format!("Pan-sharpened {} written to {}", self.input_file, self.output_file))?;
+        Ok(())
+    }
+}
@@ -0,0 +1,99 @@
+//! Proximity raster command
+//!
+//! This module implements the command for computing a distance-to-target
+//! raster from a classified or binary TIFF file.
+
+use clap::ArgMatches;
+use log::info;
+
+use crate::commands::command_traits::Command;
+use crate::tiff::errors::{TiffResult, TiffError};
+use crate::utils::logger::Logger;
+use crate::utils::proximity_utils::{self, ProximityOptions};
+
+/// Command for computing a proximity raster
+pub struct ProximityCommand<'a> {
+    /// Path to the input file
+    input_file: String,
+    /// Path to the output file
+    output_file: String,
+    /// Proximity computation options
+    options: ProximityOptions,
+    /// Logger for recording operations
+    logger: &'a Logger,
+}
+
+impl<'a> ProximityCommand<'a> {
+    /// Create a new proximity command
+    ///
+    /// # Arguments
+    /// * `args` - CLI argument matches from clap
+    /// * `logger` - Logger for recording operations
+    ///
+    /// # Returns
+    /// A new ProximityCommand instance or an error
+    pub fn new(args: &ArgMatches, logger: &'a Logger) -> TiffResult<Self> {
+        let input_file = args.get_one::<String>("input")
+            .ok_or_else(|| TiffError::GenericError("Missing input file".to_string()))?
+            .clone();
+
+        let output_file = args.get_one::<String>("output")
+            .ok_or_else(|| TiffError::GenericError("Missing output file path for proximity raster".to_string()))?
+            .clone();
+
+        // Target values default to every non-zero pixel
+        let target_values = match args.get_one::<String>("target-values") {
+            Some(values_str) => proximity_utils::parse_target_values(values_str)?,
+            None => Vec::new(),
+        };
+        info!("Target values: {:?}", target_values);
+
+        let band = match args.get_one::<String>("source-band") {
+            Some(value) => value.parse::<usize>().ok().filter(|&band| band >= 1)
+                .ok_or_else(|| TiffError::GenericError(format!("Invalid source band '{}' (bands count from 1)", value)))?,
+            None => 1,
+        };
+        info!("Source band: {}", band);
+
+        // Distance units: map units (default) or pixels
+        let use_map_units = match args.get_one::<String>("distance-units").map(|s| s.to_lowercase()) {
+            Some(units) if units == "pixel" || units == "pixels" => false,
+            Some(units) if units == "geo" || units == "map" => true,
+            Some(units) => return Err(TiffError::GenericError(
+                format!("Invalid distance units '{}' (expected 'geo' or 'pixel')", units))),
+            None => true,
+        };
+
+        let max_distance = match args.get_one::<String>("max-distance") {
+            Some(value) => Some(value.parse::<f64>()
+                .map_err(|e| TiffError::GenericError(format!("Invalid maximum distance: {}", e)))?),
+            None => None,
+        };
+
+        Ok(ProximityCommand {
+            input_file,
+            output_file,
+            options: ProximityOptions {
+                target_values,
+                band,
+                use_map_units,
+                max_distance,
+            },
+            logger,
+        })
+    }
+}
+
+impl<'a> Command for ProximityCommand<'a> {
+    fn execute(&self) -> TiffResult<()> {
+        info!("Computing proximity raster from {} to {}", self.input_file, self.output_file);
+
+        proximity_utils::create_proximity_raster(
+            &self.input_file, &self.output_file, &self.options, self.logger)?;
+
+        info!("Proximity computation successful");
+        self.logger.log("Proximity computation successful")?;
+
+        Ok(())
+    }
+}
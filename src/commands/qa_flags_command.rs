@@ -0,0 +1,71 @@
+//! QA flag decoding command
+//!
+//! This module implements the command for splitting the bit flags of a
+//! QA band into separate 0/1 rasters, as described by a bit-definition file.
+
+use clap::ArgMatches;
+use log::info;
+
+use crate::commands::command_traits::Command;
+use crate::tiff::errors::{TiffResult, TiffError};
+use crate::utils::logger::Logger;
+use crate::utils::qa_utils::{self, BitFlag};
+
+/// Command for decoding QA bit flags
+pub struct QaFlagsCommand<'a> {
+    /// Path to the QA band
+    input_file: String,
+    /// Output path, suffixed with each flag's name
+    output_file: String,
+    /// Flags to decode
+    flags: Vec<BitFlag>,
+    /// Logger for recording operations
+    logger: &'a Logger,
+}
+
+impl<'a> QaFlagsCommand<'a> {
+    /// Create a new QA flag decoding command
+    ///
+    /// # Arguments
+    /// * `args` - CLI argument matches from clap
+    /// * `logger` - Logger for recording operations
+    ///
+    /// # Returns
+    /// A new QaFlagsCommand instance or an error
+    pub fn new(args: &ArgMatches, logger: &'a Logger) -> TiffResult<Self> {
+        let input_file = args.get_one::<String>("input")
+            .ok_or_else(|| TiffError::GenericError("Missing input file".to_string()))?
+            .clone();
+
+        let output_file = args.get_one::<String>("output")
+            .ok_or_else(|| TiffError::GenericError("Missing output file path for QA flag rasters".to_string()))?
+            .clone();
+
+        let definitions = args.get_one::<String>("qa-flags")
+            .ok_or_else(|| TiffError::GenericError("Missing bit-definition file".to_string()))?;
+        let flags = qa_utils::read_bit_definitions(definitions)?;
+        info!("Decoding {} flag(s) defined in {}", flags.len(), definitions);
+
+        Ok(QaFlagsCommand {
+            input_file,
+            output_file,
+            flags,
+            logger,
+        })
+    }
+}
+
+impl<'a> Command for QaFlagsCommand<'a> {
+    fn execute(&self) -> TiffResult<()> {
+        info!("Decoding QA flags of {} to {}", self.input_file, self.output_file);
+
+        let written = qa_utils::decode_flags(&self.input_file, &self.output_file, &self.flags, self.logger)?;
+
+        for path in &written {
+            println!("{}", path);
+        }
+        self.logger.log(&format!("Decoded {} QA flag(s)", written.len()))?;
+
+        Ok(())
+    }
+}
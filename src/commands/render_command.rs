@@ -0,0 +1,86 @@
+//! Render command
+//!
+//! This module implements the command that stretches a raster's stored
+//! values to an 8-bit image for quicklooks.
+
+use clap::ArgMatches;
+use log::info;
+
+use crate::commands::command_traits::Command;
+use crate::tiff::errors::{TiffResult, TiffError};
+use crate::utils::logger::Logger;
+use crate::utils::render_utils::{self, StretchMode};
+
+/// Read the stretch mode from `--stretch` and `--clip-percent`
+///
+/// # Arguments
+/// * `args` - CLI argument matches from clap
+///
+/// # Returns
+/// The stretch mode or an error
+pub(crate) fn stretch_mode(args: &ArgMatches) -> TiffResult<StretchMode> {
+    let clip_str = args.get_one::<String>("clip-percent").map(String::as_str).unwrap_or("2,98");
+    let clip = clip_str.split_once(',')
+        .and_then(|(low, high)| Some((low.trim().parse::<f64>().ok()?, high.trim().parse::<f64>().ok()?)))
+        .ok_or_else(|| TiffError::GenericError(format!(
+            "Invalid clip percentiles '{}', expected LOW,HIGH such as 2,98", clip_str)))?;
+
+    let stretch = args.get_one::<String>("stretch").map(String::as_str).unwrap_or("linear");
+    StretchMode::from_string(stretch, clip)
+}
+
+/// Command for rendering a raster to an 8-bit image
+pub struct RenderCommand<'a> {
+    /// Path to the input raster
+    input_file: String,
+    /// Path to the rendered image
+    output_file: String,
+    /// How band values are mapped to display levels
+    mode: StretchMode,
+    /// Quality of lossy image outputs, if any
+    quality: Option<u8>,
+    /// Logger for recording operations
+    logger: &'a Logger,
+}
+
+impl<'a> RenderCommand<'a> {
+    /// Create a new render command
+    ///
+    /// # Arguments
+    /// * `args` - CLI argument matches from clap
+    /// * `logger` - Logger for recording operations
+    ///
+    /// # Returns
+    /// A new RenderCommand instance or an error
+    pub fn new(args: &ArgMatches, logger: &'a Logger) -> TiffResult<Self> {
+        let input_file = args.get_one::<String>("input")
+            .ok_or_else(|| TiffError::GenericError("Missing input file".to_string()))?
+            .clone();
+
+        let output_file = args.get_one::<String>("output")
+            .ok_or_else(|| TiffError::GenericError("Missing output file path for render".to_string()))?
+            .clone();
+
+        let mode = stretch_mode(args)?;
+        let quality = crate::commands::output_quality(args)?;
+
+        info!("Render {} to {} with the {} stretch", input_file, output_file, mode.name());
+
+        Ok(RenderCommand {
+            input_file,
+            output_file,
+            mode,
+            quality,
+            logger,
+        })
+    }
+}
+
+impl<'a> Command for RenderCommand<'a> {
+    fn execute(&self) -> TiffResult<()> {
+        render_utils::render_file(&self.input_file, &self.output_file, self.mode, self.quality, self.logger)?;
+
+        self.logger.log(&format!("Rendered {} written to {}", self.input_file, self.output_file))?;
+        Ok(())
+    }
+}
@@ -0,0 +1,85 @@
+//! TIFF repair command
+//!
+//! This module implements the tolerant read of damaged files: it reports
+//! structural problems together with what a repair would do about them,
+//! and with an output path writes the repaired copy.
+
+use clap::ArgMatches;
+use log::info;
+
+use crate::commands::command_traits::Command;
+use crate::tiff::TiffReader;
+use crate::tiff::errors::{TiffResult, TiffError};
+use crate::utils::logger::Logger;
+use crate::utils::repair_utils;
+
+/// Command for checking and salvaging damaged TIFF files
+pub struct RepairCommand<'a> {
+    /// Path to the input file
+    input_file: String,
+    /// Path of the repaired copy, None to only report problems
+    output_file: Option<String>,
+    /// Logger for recording operations
+    logger: &'a Logger,
+}
+
+impl<'a> RepairCommand<'a> {
+    /// Create a new repair command
+    ///
+    /// # Arguments
+    /// * `args` - CLI argument matches from clap
+    /// * `logger` - Logger for recording operations
+    ///
+    /// # Returns
+    /// A new RepairCommand instance or an error
+    pub fn new(args: &ArgMatches, logger: &'a Logger) -> TiffResult<Self> {
+        let input_file = args.get_one::<String>("input")
+            .ok_or_else(|| TiffError::GenericError("Missing input file".to_string()))?
+            .clone();
+
+        Ok(RepairCommand {
+            input_file,
+            output_file: args.get_one::<String>("output").cloned(),
+            logger,
+        })
+    }
+}
+
+impl<'a> Command for RepairCommand<'a> {
+    fn execute(&self) -> TiffResult<()> {
+        info!("Checking {} for structural problems", self.input_file);
+
+        let mut reader = TiffReader::new(self.logger);
+        let tiff = reader.load(&self.input_file)?;
+        let issues = repair_utils::check_file(&reader, &tiff)?;
+
+        println!("{}: {} IFDs readable", self.input_file, tiff.ifd_count());
+        if issues.is_empty() {
+            println!("No structural problems found");
+        }
+        for issue in &issues {
+            println!("IFD #{}: {}", issue.ifd, issue.problem);
+            println!("  -> {}", issue.suggestion);
+        }
+
+        let output_file = match &self.output_file {
+            Some(output_file) => output_file,
+            None => {
+                if !issues.is_empty() {
+                    println!("Run with --output to write a repaired copy");
+                }
+                return Ok(());
+            }
+        };
+
+        let results = repair_utils::write_repaired_copy(&reader, &tiff, output_file, self.logger)?;
+        for result in &results {
+            println!("IFD #{}: recovered {} of {} blocks ({} partially)",
+                     result.ifd, result.recovered, result.total, result.partial);
+        }
+        println!("Wrote repaired copy with {} of {} IFDs to {}", results.len(), tiff.ifd_count(), output_file);
+
+        self.logger.log(&format!("Repaired {} into {}", self.input_file, output_file))?;
+        Ok(())
+    }
+}
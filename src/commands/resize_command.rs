@@ -0,0 +1,94 @@
+//! Resize command
+//!
+//! This module implements the command for resampling a raster to a
+//! coarser or finer grid in its own coordinate system, by a factor or to
+//! a target resolution.
+
+use clap::ArgMatches;
+use log::info;
+
+use crate::commands::command_traits::Command;
+use crate::tiff::errors::{TiffResult, TiffError};
+use crate::utils::logger::Logger;
+use crate::utils::resize_utils::{self, Resampling, ResizeTarget};
+
+/// Command for resizing a raster
+pub struct ResizeCommand<'a> {
+    /// Path to the raster to resize
+    input_file: String,
+    /// Path of the resized GeoTIFF
+    output_file: String,
+    /// Scale factor or target resolution
+    target: ResizeTarget,
+    /// How output pixels take their value
+    resampling: Resampling,
+    /// Logger for recording operations
+    logger: &'a Logger,
+}
+
+impl<'a> ResizeCommand<'a> {
+    /// Create a new resize command
+    ///
+    /// # Arguments
+    /// * `args` - CLI argument matches from clap
+    /// * `logger` - Logger for recording operations
+    ///
+    /// # Returns
+    /// A new ResizeCommand instance or an error
+    pub fn new(args: &ArgMatches, logger: &'a Logger) -> TiffResult<Self> {
+        let input_file = args.get_one::<String>("input")
+            .ok_or_else(|| TiffError::GenericError("Missing input file".to_string()))?
+            .clone();
+
+        let output_file = args.get_one::<String>("output")
+            .ok_or_else(|| TiffError::GenericError("Missing output file path for resize".to_string()))?
+            .clone();
+
+        let target = match (args.get_one::<String>("resize"), args.get_one::<String>("target-res")) {
+            (Some(_), Some(_)) => return Err(TiffError::GenericError(
+                "Give either --resize or --target-res, not both".to_string())),
+            (Some(factor), None) => ResizeTarget::Factor(factor.trim().parse::<f64>()
+                .map_err(|e| TiffError::GenericError(format!("Invalid resize factor '{}': {}", factor, e)))?),
+            (None, Some(resolution)) => {
+                let values = resolution.split(',')
+                    .map(|value| value.trim().parse::<f64>())
+                    .collect::<Result<Vec<f64>, _>>()
+                    .map_err(|e| TiffError::GenericError(format!("Invalid target resolution '{}': {}", resolution, e)))?;
+                match values[..] {
+                    [resolution] => ResizeTarget::Resolution(resolution, resolution),
+                    [x_resolution, y_resolution] => ResizeTarget::Resolution(x_resolution, y_resolution),
+                    _ => return Err(TiffError::GenericError(format!(
+                        "Target resolution must be XRES or XRES,YRES, got '{}'", resolution))),
+                }
+            },
+            (None, None) => return Err(TiffError::GenericError(
+                "Resizing needs --resize FACTOR or --target-res RES".to_string())),
+        };
+
+        let resampling = match args.get_one::<String>("resampling") {
+            Some(name) => Resampling::from_string(name)?,
+            None => Resampling::Nearest,
+        };
+
+        Ok(ResizeCommand {
+            input_file,
+            output_file,
+            target,
+            resampling,
+            logger,
+        })
+    }
+}
+
+impl<'a> Command for ResizeCommand<'a> {
+    fn execute(&self) -> TiffResult<()> {
+        info!("Resizing {} to {} ({:?})", self.input_file, self.output_file, self.target);
+
+        let (width, height) = resize_utils::resize(
+            &self.input_file, &self.output_file, self.target, self.resampling, self.logger)?;
+
+        println!("Wrote {}x{} raster to {}", width, height, self.output_file);
+
+        Ok(())
+    }
+}
@@ -0,0 +1,63 @@
+//! Band splitting command
+//!
+//! This module implements the command for writing the bands of a
+//! multi-band raster to separate single-band GeoTIFFs.
+
+use clap::ArgMatches;
+use log::info;
+
+use crate::commands::command_traits::Command;
+use crate::tiff::errors::{TiffResult, TiffError};
+use crate::utils::logger::Logger;
+use crate::utils::split_utils;
+
+/// Command for splitting a raster into its bands
+pub struct SplitBandsCommand<'a> {
+    /// Path to the multi-band raster
+    input_file: String,
+    /// Output path, suffixed with each band number
+    output_file: String,
+    /// Logger for recording operations
+    logger: &'a Logger,
+}
+
+impl<'a> SplitBandsCommand<'a> {
+    /// Create a new band splitting command
+    ///
+    /// # Arguments
+    /// * `args` - CLI argument matches from clap
+    /// * `logger` - Logger for recording operations
+    ///
+    /// # Returns
+    /// A new SplitBandsCommand instance or an error
+    pub fn new(args: &ArgMatches, logger: &'a Logger) -> TiffResult<Self> {
+        let input_file = args.get_one::<String>("input")
+            .ok_or_else(|| TiffError::GenericError("Missing input file".to_string()))?
+            .clone();
+
+        let output_file = args.get_one::<String>("output")
+            .ok_or_else(|| TiffError::GenericError("Missing output file path for split bands".to_string()))?
+            .clone();
+
+        Ok(SplitBandsCommand {
+            input_file,
+            output_file,
+            logger,
+        })
+    }
+}
+
+impl<'a> Command for SplitBandsCommand<'a> {
+    fn execute(&self) -> TiffResult<()> {
+        info!("Splitting the bands of {} to {}", self.input_file, self.output_file);
+
+        let written = split_utils::split_bands(&self.input_file, &self.output_file, self.logger)?;
+
+        for path in &written {
+            println!("{}", path);
+        }
+        self.logger.log(&format!("Wrote {} band raster(s)", written.len()))?;
+
+        Ok(())
+    }
+}
@@ -0,0 +1,84 @@
+//! Valid-data mask command
+//!
+//! This module implements the command writing a mask of the pixels that
+//! hold data, either as a separate GeoTIFF or inside a copy of the raster.
+
+use clap::ArgMatches;
+use log::info;
+
+use crate::commands::command_traits::Command;
+use crate::tiff::errors::{TiffResult, TiffError};
+use crate::utils::logger::Logger;
+use crate::utils::valid_mask_utils::{self, ValidMaskOptions};
+
+/// Command for writing a valid-data mask
+pub struct ValidMaskCommand<'a> {
+    /// Path to the input file
+    input_file: String,
+    /// Path to the output file
+    output_file: String,
+    /// Mask options
+    options: ValidMaskOptions,
+    /// Logger for recording operations
+    logger: &'a Logger,
+}
+
+impl<'a> ValidMaskCommand<'a> {
+    /// Create a new valid mask command
+    ///
+    /// # Arguments
+    /// * `args` - CLI argument matches from clap
+    /// * `logger` - Logger for recording operations
+    ///
+    /// # Returns
+    /// A new ValidMaskCommand instance or an error
+    pub fn new(args: &ArgMatches, logger: &'a Logger) -> TiffResult<Self> {
+        let input_file = args.get_one::<String>("input")
+            .ok_or_else(|| TiffError::GenericError("Missing input file".to_string()))?
+            .clone();
+
+        let output_file = args.get_one::<String>("output")
+            .ok_or_else(|| TiffError::GenericError("Missing output file path for the valid-data mask".to_string()))?
+            .clone();
+
+        let mut options = ValidMaskOptions {
+            internal: args.get_flag("internal-mask"),
+            ..ValidMaskOptions::default()
+        };
+
+        if let Some(bits_str) = args.get_one::<String>("mask-bits") {
+            options.bits = bits_str.parse::<u16>()
+                .ok()
+                .filter(|bits| matches!(bits, 1 | 8))
+                .ok_or_else(|| TiffError::GenericError(format!("Invalid mask bits '{}', expected 1 or 8", bits_str)))?;
+        }
+
+        if let Some(nodata_str) = args.get_one::<String>("nodata") {
+            options.nodata = Some(nodata_str.parse::<f64>()
+                .map_err(|e| TiffError::GenericError(format!("Invalid NoData value: {}", e)))?);
+        }
+
+        Ok(ValidMaskCommand {
+            input_file,
+            output_file,
+            options,
+            logger,
+        })
+    }
+}
+
+impl<'a> Command for ValidMaskCommand<'a> {
+    fn execute(&self) -> TiffResult<()> {
+        info!("Writing the valid-data mask of {} to {}", self.input_file, self.output_file);
+
+        let mask = valid_mask_utils::write_valid_mask(
+            &self.input_file, &self.output_file, &self.options, self.logger)?;
+
+        let message = format!("Valid-data mask written: {} of {} pixels valid",
+                              mask.valid_count(), mask.valid.len());
+        info!("{}", message);
+        self.logger.log(&message)?;
+
+        Ok(())
+    }
+}
@@ -1,19 +1,41 @@
 //! Compression conversion functionality
 
-use std::fs::File;
+use std::collections::HashMap;
+use std::fs::{File, OpenOptions};
 use std::io::{BufReader, BufWriter, Read, Write, Seek, SeekFrom};
-use log::info;
+use std::path::{Path, PathBuf};
+use log::{info, warn};
 
 use crate::tiff::TiffReader;
+use crate::tiff::compliance;
+use crate::tiff::builders::geo_tags::GeoTagsBuilder;
+use crate::tiff::constants::{compression as compression_consts, field_types, tags};
+use crate::tiff::ifd::{IFD, IFDEntry};
+use crate::utils::{atomic_utils, exif_utils, memory_utils};
+use crate::utils::journal_utils::{self, Journal};
 use crate::tiff::errors::{TiffError, TiffResult};
 use crate::utils::logger::Logger;
 use super::factory::CompressionFactory;
 use super::handler::CompressionHandler;
 
+/// Tags rebuilt for the output rather than copied from the source
+const REPLACED_TAGS: [u16; 5] = [
+    tags::COMPRESSION,
+    tags::STRIP_OFFSETS,
+    tags::STRIP_BYTE_COUNTS,
+    tags::TILE_OFFSETS,
+    tags::TILE_BYTE_COUNTS,
+];
+
+/// Blocks converted between journal commits
+const JOURNAL_BATCH: usize = 64;
+
 /// Converter for changing compression formats
 pub struct CompressionConverter<'a> {
     logger: &'a Logger,
     reader: TiffReader<'a>,
+    /// Continue an interrupted conversion from its journal
+    resume: bool,
 }
 
 impl<'a> CompressionConverter<'a> {
@@ -22,9 +44,20 @@ impl<'a> CompressionConverter<'a> {
         CompressionConverter {
             logger,
             reader: TiffReader::new(logger),
+            resume: false,
         }
     }
 
+    /// Continue an interrupted conversion of the same file
+    ///
+    /// Every conversion journals its finished blocks in `<output>.journal`;
+    /// when resuming, blocks listed there are kept in the partial output as
+    /// written.
+    pub fn with_resume(mut self, resume: bool) -> Self {
+        self.resume = resume;
+        self
+    }
+
     /// Convert a single data block between compression formats
     pub fn convert_data(&self, data: &[u8],
                         source_compression: u64,
@@ -60,13 +93,38 @@ impl<'a> CompressionConverter<'a> {
             return Err(TiffError::GenericError("No IFDs found in TIFF file".to_string()));
         }
 
+        // Decoded JPEG blocks are RGB rather than the stored YCbCr, which
+        // the copied tags would misdescribe
+        if source_tiff.ifds.iter().any(|ifd| ifd.get_tag_value(tags::COMPRESSION) == Some(compression_consts::JPEG as u64)) {
+            return Err(TiffError::GenericError(
+                "JPEG-compressed images cannot be converted; extract them instead".to_string()));
+        }
+
         // Open the source file for reading binary data
         let source_file = File::open(input_path)?;
-        let mut source_reader = BufReader::with_capacity(1024 * 1024, source_file);
+        let mut source_reader = BufReader::with_capacity(memory_utils::io_buffer_size(), source_file);
+
+        // The output is written to a partial file and moved into place when
+        // complete. Finished blocks are journaled; a resumed run writes into
+        // the partial file of the interrupted one.
+        let partial_path = atomic_utils::partial_path(Path::new(output_path));
+        let journal_path = PathBuf::from(format!("{}.journal", output_path));
+        let resume = self.resume && partial_path.exists();
+        if self.resume && !resume && journal_path.exists() {
+            info!("{} is missing; starting the conversion over", partial_path.display());
+            std::fs::remove_file(&journal_path)?;
+        }
+        let job = format!("convert {} to compression {}", journal_utils::file_fingerprint(input_path)?, target_compression);
+        let mut journal = Journal::open(&journal_path, &job, resume)?;
 
         // Create the output file
-        let output_file = File::create(output_path)?;
-        let mut output_writer = BufWriter::with_capacity(1024 * 1024, output_file);
+        let output_file = if journal.is_resuming() {
+            OpenOptions::new().write(true).open(&partial_path)?
+        } else {
+            File::create(&partial_path)?
+        };
+        journal.sync_before_commit(output_file.try_clone()?);
+        let mut output_writer = BufWriter::with_capacity(memory_utils::io_buffer_size(), output_file);
 
         // Write TIFF header
         self.write_tiff_header(&mut output_writer, source_tiff.is_big_tiff)?;
@@ -80,6 +138,7 @@ impl<'a> CompressionConverter<'a> {
         // IFD chain information
         let mut ifd_offsets = Vec::new();
         let mut updated_ifds = Vec::new();
+        let mut written_tag_data = Vec::new();
 
         // Create a multi-progress display
         let multi_progress = indicatif::MultiProgress::new();
@@ -106,8 +165,23 @@ impl<'a> CompressionConverter<'a> {
             info!("Converting from {} to {} compression",
               source_handler.name(), target_handler.name());
 
-            // Create a new IFD that will hold updated entries
-            let mut new_ifd = ifd.clone();
+            // Build the output IFD from the source tags with their data,
+            // the block tables and compression replaced
+            let mut new_ifd = IFD::new(ifd_index, 0);
+            let mut external_data = HashMap::new();
+            GeoTagsBuilder::deep_copy_tags_from(&mut new_ifd, &mut external_data, ifd_index, ifd,
+                                                &REPLACED_TAGS, &self.reader, source_tiff.is_big_tiff);
+            let is_tiled = ifd.has_tag(tags::TILE_WIDTH) && ifd.has_tag(tags::TILE_LENGTH);
+            let (offsets_tag, counts_tag) = if is_tiled {
+                (tags::TILE_OFFSETS, tags::TILE_BYTE_COUNTS)
+            } else {
+                (tags::STRIP_OFFSETS, tags::STRIP_BYTE_COUNTS)
+            };
+            for tag in [offsets_tag, counts_tag] {
+                new_ifd.add_entry(IFDEntry::new(tag, field_types::LONG, 0, 0));
+            }
+            new_ifd.add_entry(IFDEntry::new(tags::COMPRESSION, field_types::SHORT, 1, target_compression));
+            new_ifd.entries.sort_by_key(|entry| entry.tag);
 
             // Record the new IFD offset
             ifd_offsets.push(current_offset);
@@ -116,32 +190,35 @@ impl<'a> CompressionConverter<'a> {
             current_offset += self.calculate_ifd_size(&new_ifd, source_tiff.is_big_tiff);
 
             // Process strips or tiles
-            if ifd.has_tag(322) && ifd.has_tag(323) {
-                // Tiled image
+            let (block_offsets, block_counts) = if is_tiled {
                 self.process_tiles(&mut source_reader, &mut output_writer, ifd,
                                    source_compression, target_compression,
-                                   &mut new_ifd, &mut current_offset, &multi_progress)?;
+                                   &mut new_ifd, &mut current_offset, &multi_progress,
+                                   ifd_index, &mut journal, source_tiff.is_big_tiff)?
             } else {
-                // Stripped image
                 self.process_strips(&mut source_reader, &mut output_writer, ifd,
                                     source_compression, target_compression,
-                                    &mut new_ifd, &mut current_offset, &multi_progress)?;
-            }
+                                    &mut new_ifd, &mut current_offset, &multi_progress,
+                                    ifd_index, &mut journal, source_tiff.is_big_tiff)?
+            };
 
-            // Update the compression tag to the new compression type
-            for entry in &mut new_ifd.entries {
-                if entry.tag == 259 { // Compression tag
-                    entry.value_offset = target_compression;
-                    break;
-                }
+            // Write the tag data too large for the entries, in tag order
+            let mut external_tags: Vec<u16> = external_data.keys().map(|(_, tag)| *tag).collect();
+            external_tags.sort_unstable();
+            for tag in external_tags {
+                let data = &external_data[&(ifd_index, tag)];
+                self.place_tag_data(&mut output_writer, &mut new_ifd, tag, data, &mut current_offset)?;
             }
-
-            // If there's no compression tag, add one
-            if !new_ifd.has_tag(259) {
-                let compression_entry = crate::tiff::ifd::IFDEntry::new(
-                    259, 3, 1, target_compression);
-                new_ifd.add_entry(compression_entry);
+            self.copy_exif_directories(&mut output_writer, ifd, &mut new_ifd, &mut current_offset)?;
+
+            // Keep the values written outside the entries for the strict checks
+            let mut tag_data: HashMap<u16, Vec<u8>> = external_data.into_iter()
+                .map(|((_, tag), data)| (tag, data))
+                .collect();
+            for (tag, values) in [(offsets_tag, block_offsets), (counts_tag, block_counts)] {
+                tag_data.insert(tag, values.iter().flat_map(|value| (*value as u32).to_le_bytes()).collect());
             }
+            written_tag_data.push(tag_data);
 
             updated_ifds.push(new_ifd);
         }
@@ -149,6 +226,25 @@ impl<'a> CompressionConverter<'a> {
         // Mark IFD processing as complete
         ifd_progress.finish_with_message("IFD processing complete");
 
+        // In strict mode, refuse to finish a file that violates the specifications,
+        // or whose tag data or blocks lie past its end
+        output_writer.flush()?;
+        let file_length = output_writer.seek(SeekFrom::End(0))?;
+        let tag_data = |ifd_index: usize, entry: &IFDEntry| {
+            if let Some(data) = written_tag_data[ifd_index].get(&entry.tag) {
+                return Some(data.clone());
+            }
+            let size = entry.count as usize * entry.get_field_type_size();
+            let bytes = entry.value_offset.to_le_bytes();
+            (entry.is_value_inline(source_tiff.is_big_tiff) && size <= bytes.len()).then(|| bytes[..size].to_vec())
+        };
+        if let Err(e) = compliance::enforce(&updated_ifds, source_tiff.is_big_tiff, &tag_data, Some(file_length)) {
+            drop(output_writer);
+            std::fs::remove_file(&partial_path)?;
+            journal.finish()?;
+            return Err(e);
+        }
+
         // Create progress bar for writing IFDs
         let write_progress = multi_progress.add(indicatif::ProgressBar::new(updated_ifds.len() as u64));
         write_progress.set_style(indicatif::ProgressStyle::default_bar()
@@ -184,8 +280,11 @@ impl<'a> CompressionConverter<'a> {
             output_writer.write_all(&(ifd_offsets[0] as u32).to_le_bytes())?;
         }
 
-        // Ensure all data is written
+        // Ensure all data is written, then put the output in place
         output_writer.flush()?;
+        drop(output_writer);
+        atomic_utils::finish(&partial_path, Path::new(output_path))?;
+        journal.finish()?;
 
         info!("Successfully converted TIFF file to {} compression",
           target_handler.name());
@@ -267,7 +366,70 @@ impl<'a> CompressionConverter<'a> {
         Ok(())
     }
 
-    // Process strips in a TIFF file
+    // Rebuild the EXIF and GPS sub-IFDs in the output
+    fn copy_exif_directories(&self, writer: &mut (impl Write + Seek),
+                             ifd: &crate::tiff::ifd::IFD,
+                             new_ifd: &mut crate::tiff::ifd::IFD,
+                             current_offset: &mut u64) -> TiffResult<()> {
+        for pointer_tag in [tags::EXIF_IFD, tags::GPS_IFD] {
+            // Damaged capture metadata is dropped rather than failing the conversion
+            let entries = match exif_utils::read_directory(&self.reader, ifd, pointer_tag) {
+                Ok(Some(entries)) => entries,
+                Ok(None) => continue,
+                Err(e) => {
+                    warn!("Dropping unreadable tag {} directory: {:?}", pointer_tag, e);
+                    new_ifd.remove_entry(pointer_tag);
+                    continue;
+                }
+            };
+
+            let directory = exif_utils::relocate_directory(
+                &exif_utils::pack_directory(&entries), *current_offset);
+            info!("Copying tag {} directory with {} entries", pointer_tag, entries.len());
+            self.place_tag_data(writer, new_ifd, pointer_tag, &directory, current_offset)?;
+        }
+
+        Ok(())
+    }
+
+    // Write tag data at the current position and point the tag's entry at it
+    fn place_tag_data(&self, writer: &mut (impl Write + Seek),
+                      new_ifd: &mut crate::tiff::ifd::IFD,
+                      tag: u16,
+                      data: &[u8],
+                      current_offset: &mut u64) -> TiffResult<()> {
+        writer.seek(SeekFrom::Start(*current_offset))?;
+        writer.write_all(data)?;
+        if let Some(entry) = new_ifd.get_entry(tag) {
+            new_ifd.replace_entry(IFDEntry { value_offset: *current_offset, ..entry.clone() });
+        }
+
+        // Keep following data word-aligned
+        *current_offset += data.len() as u64;
+        let padding = (4 - *current_offset % 4) % 4;
+        *current_offset += padding;
+        writer.write_all(&vec![0u8; padding as usize])?;
+
+        Ok(())
+    }
+
+    /// Refuse blocks too large for the memory budget
+    ///
+    /// Each block is held compressed, decompressed and recompressed at once;
+    /// the recompressed copy is assumed no larger than the decompressed one.
+    fn check_block_fits(&self, reader: &mut (impl Read + Seek + Send + Sync), ifd: &crate::tiff::ifd::IFD,
+                        kind: &str, pixels: u64) -> TiffResult<()> {
+        if memory_utils::memory_budget().is_none() {
+            return Ok(());
+        }
+        let bits_per_pixel: u64 = self.reader.read_tag_values(reader, ifd, tags::BITS_PER_SAMPLE)
+            .map(|bits| bits.iter().sum())
+            .unwrap_or(8 * ifd.get_samples_per_pixel());
+        let decoded = pixels * bits_per_pixel / 8;
+        memory_utils::check_fits(&format!("Converting a {} of {} pixels", kind, pixels), decoded * 3)
+    }
+
+    // Process strips in a TIFF file, returning their new offsets and byte counts
     fn process_strips(&self, reader: &mut (impl Read + Seek + Send + Sync),
                       writer: &mut (impl Write + Seek + Send + Sync),
                       ifd: &crate::tiff::ifd::IFD,
@@ -275,7 +437,10 @@ impl<'a> CompressionConverter<'a> {
                       target_compression: u64,
                       new_ifd: &mut crate::tiff::ifd::IFD,
                       current_offset: &mut u64,
-                      multi_progress: &indicatif::MultiProgress) -> TiffResult<()> {
+                      multi_progress: &indicatif::MultiProgress,
+                      ifd_index: usize,
+                      journal: &mut Journal,
+                      is_big_tiff: bool) -> TiffResult<(Vec<u64>, Vec<u64>)> {
         // Get strip offsets and byte counts
         let strip_offsets = self.reader.read_tag_values(reader, ifd, 273)?;
         let strip_byte_counts = self.reader.read_tag_values(reader, ifd, 279)?;
@@ -285,6 +450,10 @@ impl<'a> CompressionConverter<'a> {
                 "Mismatch between strip offsets and byte counts".to_string()));
         }
 
+        let (width, height) = ifd.get_dimensions().unwrap_or((0, 0));
+        let rows_per_strip = ifd.get_tag_value(tags::ROWS_PER_STRIP).unwrap_or(height).min(height);
+        self.check_block_fits(reader, ifd, "strip", width * rows_per_strip)?;
+
         // Create handlers
         let source_handler = CompressionFactory::create_handler(source_compression)?;
         let target_handler = CompressionFactory::create_handler(target_compression)?;
@@ -310,6 +479,16 @@ impl<'a> CompressionConverter<'a> {
 
         // Process each strip
         for i in 0..strip_offsets.len() {
+            // Blocks written by an interrupted run stay as they are
+            let key = format!("ifd{}/strip{}", ifd_index, i);
+            if let Some((written_offset, written_count)) = resumed_block(journal, &key, *current_offset) {
+                new_strip_offsets.push(written_offset);
+                new_strip_byte_counts.push(written_count);
+                *current_offset = (written_offset + written_count).next_multiple_of(4);
+                strip_progress.inc(1);
+                continue;
+            }
+
             let offset = strip_offsets[i];
             let byte_count = strip_byte_counts[i] as usize;
 
@@ -346,6 +525,7 @@ impl<'a> CompressionConverter<'a> {
             // Record new offset and byte count
             new_strip_offsets.push(*current_offset);
             new_strip_byte_counts.push(recompressed_data.len() as u64);
+            journal.record(key, format!("{} {}", *current_offset, recompressed_data.len()));
 
             // Update current offset
             *current_offset += recompressed_data.len() as u64;
@@ -358,9 +538,16 @@ impl<'a> CompressionConverter<'a> {
                 writer.write_all(&vec![0u8; padding as usize])?;
             }
 
+            if journal.pending() >= JOURNAL_BATCH {
+                writer.flush()?;
+                journal.commit()?;
+            }
+
             // Update progress
             strip_progress.inc(1);
         }
+        writer.flush()?;
+        journal.commit()?;
 
         strip_progress.finish_with_message("Strip conversion complete");
 
@@ -373,20 +560,16 @@ impl<'a> CompressionConverter<'a> {
             writer.write_all(&(*byte_count as u32).to_le_bytes())?;
         }
 
-        // Update IFD entries for strip offsets and byte counts
-        for entry in &mut new_ifd.entries {
-            if entry.tag == 273 {  // StripOffsets
-                entry.value_offset = strip_data_offset;
-            } else if entry.tag == 279 {  // StripByteCounts
-                entry.value_offset = strip_data_offset + (strips_count * 4);
-            }
-        }
+        // Point the IFD entries at the strip offsets and byte counts
+        set_block_table(new_ifd, tags::STRIP_OFFSETS, &new_strip_offsets, strip_data_offset, is_big_tiff);
+        set_block_table(new_ifd, tags::STRIP_BYTE_COUNTS, &new_strip_byte_counts,
+                        strip_data_offset + strips_count * 4, is_big_tiff);
 
-        Ok(())
+        Ok((new_strip_offsets, new_strip_byte_counts))
     }
 
 
-    // Process tiles in a TIFF file
+    // Process tiles in a TIFF file, returning their new offsets and byte counts
     fn process_tiles(&self, reader: &mut (impl Read + Seek + Send + Sync),
                      writer: &mut (impl Write + Seek + Send + Sync),
                      ifd: &crate::tiff::ifd::IFD,
@@ -394,7 +577,10 @@ impl<'a> CompressionConverter<'a> {
                      target_compression: u64,
                      new_ifd: &mut crate::tiff::ifd::IFD,
                      current_offset: &mut u64,
-                     multi_progress: &indicatif::MultiProgress) -> TiffResult<()> {
+                     multi_progress: &indicatif::MultiProgress,
+                     ifd_index: usize,
+                     journal: &mut Journal,
+                     is_big_tiff: bool) -> TiffResult<(Vec<u64>, Vec<u64>)> {
         // Get tile offsets and byte counts
         let tile_offsets = self.reader.read_tag_values(reader, ifd, 324)?;
         let tile_byte_counts = self.reader.read_tag_values(reader, ifd, 325)?;
@@ -404,6 +590,10 @@ impl<'a> CompressionConverter<'a> {
                 "Mismatch between tile offsets and byte counts".to_string()));
         }
 
+        let tile_width = ifd.get_tag_value(tags::TILE_WIDTH).unwrap_or(0);
+        let tile_length = ifd.get_tag_value(tags::TILE_LENGTH).unwrap_or(0);
+        self.check_block_fits(reader, ifd, "tile", tile_width * tile_length)?;
+
         // Create handlers
         let source_handler = CompressionFactory::create_handler(source_compression)?;
         let target_handler = CompressionFactory::create_handler(target_compression)?;
@@ -429,6 +619,16 @@ impl<'a> CompressionConverter<'a> {
 
         // Process each tile
         for i in 0..tile_offsets.len() {
+            // Blocks written by an interrupted run stay as they are
+            let key = format!("ifd{}/tile{}", ifd_index, i);
+            if let Some((written_offset, written_count)) = resumed_block(journal, &key, *current_offset) {
+                new_tile_offsets.push(written_offset);
+                new_tile_byte_counts.push(written_count);
+                *current_offset = (written_offset + written_count).next_multiple_of(4);
+                tile_progress.inc(1);
+                continue;
+            }
+
             let offset = tile_offsets[i];
             let byte_count = tile_byte_counts[i] as usize;
 
@@ -465,6 +665,7 @@ impl<'a> CompressionConverter<'a> {
             // Record new offset and byte count
             new_tile_offsets.push(*current_offset);
             new_tile_byte_counts.push(recompressed_data.len() as u64);
+            journal.record(key, format!("{} {}", *current_offset, recompressed_data.len()));
 
             // Update current offset
             *current_offset += recompressed_data.len() as u64;
@@ -477,9 +678,16 @@ impl<'a> CompressionConverter<'a> {
                 writer.write_all(&vec![0u8; padding as usize])?;
             }
 
+            if journal.pending() >= JOURNAL_BATCH {
+                writer.flush()?;
+                journal.commit()?;
+            }
+
             // Update progress
             tile_progress.inc(1);
         }
+        writer.flush()?;
+        journal.commit()?;
 
         tile_progress.finish_with_message("Tile conversion complete");
 
@@ -492,15 +700,35 @@ impl<'a> CompressionConverter<'a> {
             writer.write_all(&(*byte_count as u32).to_le_bytes())?;
         }
 
-        // Update IFD entries for tile offsets and byte counts
-        for entry in &mut new_ifd.entries {
-            if entry.tag == 324 {  // TileOffsets
-                entry.value_offset = tile_data_offset;
-            } else if entry.tag == 325 {  // TileByteCounts
-                entry.value_offset = tile_data_offset + (tiles_count * 4);
-            }
-        }
+        // Point the IFD entries at the tile offsets and byte counts
+        set_block_table(new_ifd, tags::TILE_OFFSETS, &new_tile_offsets, tile_data_offset, is_big_tiff);
+        set_block_table(new_ifd, tags::TILE_BYTE_COUNTS, &new_tile_byte_counts,
+                        tile_data_offset + tiles_count * 4, is_big_tiff);
 
-        Ok(())
+        Ok((new_tile_offsets, new_tile_byte_counts))
     }
-}
\ No newline at end of file
+}
+
+/// Offset and size of a block an interrupted run wrote at the same place
+///
+/// A journal entry written at another offset than the block would now go
+/// to is not trusted, and the block is converted again.
+fn resumed_block(journal: &Journal, key: &str, expected_offset: u64) -> Option<(u64, u64)> {
+    let (offset, count) = journal.completed(key)?.split_once(' ')?;
+    let (offset, count) = (offset.parse::<u64>().ok()?, count.parse::<u64>().ok()?);
+    (offset == expected_offset).then_some((offset, count))
+}
+
+/// Point a block table entry at the 32-bit values written at `table_offset`
+///
+/// Values that fit in the entry are stored there instead, as readers
+/// expect, leaving the reserved table space unused.
+fn set_block_table(ifd: &mut IFD, tag: u16, values: &[u64], table_offset: u64, is_big_tiff: bool) {
+    let inline_size = if is_big_tiff { 8 } else { 4 };
+    let value_offset = if values.len() * 4 <= inline_size {
+        values.iter().rev().fold(0, |packed, value| packed << 32 | (*value & 0xFFFF_FFFF))
+    } else {
+        table_offset
+    };
+    ifd.replace_entry(IFDEntry::new(tag, field_types::LONG, values.len() as u64, value_offset));
+}
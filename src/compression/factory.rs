@@ -1,29 +1,172 @@
 //! Factory for creating compression handlers
+//!
+//! Besides the built-in codecs, the factory hands out handlers that other
+//! crates registered at runtime, so proprietary or experimental codecs can
+//! be plugged in without changing rasterkit.
 
+use std::collections::HashMap;
+use std::sync::{Arc, RwLock};
+
+use lazy_static::lazy_static;
+use log::info;
+
+use crate::tiff::constants::{compression as compression_consts, tags};
 use crate::tiff::errors::{TiffError, TiffResult};
+use crate::tiff::ifd::IFD;
+use crate::tiff::TiffReader;
 use super::handler::CompressionHandler;
 use super::uncompressed::UncompressedHandler;
 use super::deflate::AdobeDeflateHandler;
+use super::lzw::LzwHandler;
+use super::jpeg::JpegHandler;
 use super::zstd::ZstdHandler;
 
+lazy_static! {
+    // Handlers registered at runtime, by compression code
+    static ref REGISTERED_HANDLERS: RwLock<HashMap<u64, Arc<dyn CompressionHandler>>> =
+        RwLock::new(HashMap::new());
+}
+
+/// A registered handler, shared by everything the factory creates for its code
+struct SharedHandler(Arc<dyn CompressionHandler>);
+
+impl CompressionHandler for SharedHandler {
+    fn decompress(&self, data: &[u8]) -> TiffResult<Vec<u8>> {
+        self.0.decompress(data)
+    }
+
+    fn compress(&self, data: &[u8]) -> TiffResult<Vec<u8>> {
+        self.0.compress(data)
+    }
+
+    fn name(&self) -> &'static str {
+        self.0.name()
+    }
+
+    fn code(&self) -> u64 {
+        self.0.code()
+    }
+}
+
 /// Factory for creating compression handlers
 pub struct CompressionFactory;
 
 impl CompressionFactory {
+    /// Register a handler for a compression code
+    ///
+    /// From then on the factory returns it for the code and its name, for
+    /// reading as well as writing. A handler registered for the code of a
+    /// built-in codec takes its place; registering a code again replaces
+    /// the earlier handler.
+    ///
+    /// # Arguments
+    /// * `code` - TIFF Compression tag value the handler is used for
+    /// * `handler` - The handler; its `code()` must return `code`
+    ///
+    /// # Returns
+    /// An error if the handler reports a different code
+    ///
+    /// # Example
+    /// ```
+    /// use rasterkit::compression::{CompressionFactory, CompressionHandler};
+    /// use rasterkit::tiff::errors::TiffResult;
+    ///
+    /// struct InvertHandler;
+    ///
+    /// impl CompressionHandler for InvertHandler {
+    ///     fn decompress(&self, data: &[u8]) -> TiffResult<Vec<u8>> {
+    ///         Ok(data.iter().map(|b| !b).collect())
+    ///     }
+    ///     fn compress(&self, data: &[u8]) -> TiffResult<Vec<u8>> {
+    ///         Ok(data.iter().map(|b| !b).collect())
+    ///     }
+    ///     fn name(&self) -> &'static str { "Invert" }
+    ///     fn code(&self) -> u64 { 65000 }
+    /// }
+    ///
+    /// CompressionFactory::register(65000, Box::new(InvertHandler)).unwrap();
+    /// let handler = CompressionFactory::create_handler(65000).unwrap();
+    /// assert_eq!(handler.decompress(&[0x0f]).unwrap(), vec![0xf0]);
+    /// ```
+    pub fn register(code: u64, handler: Box<dyn CompressionHandler>) -> TiffResult<()> {
+        if handler.code() != code {
+            return Err(TiffError::GenericError(format!(
+                "Handler {} reports compression code {}, not {}", handler.name(), handler.code(), code)));
+        }
+
+        info!("Registering {} compression handler for code {}", handler.name(), code);
+        let mut handlers = REGISTERED_HANDLERS.write()
+            .map_err(|_| TiffError::GenericError("Compression handler registry is poisoned".to_string()))?;
+        handlers.insert(code, Arc::from(handler));
+        Ok(())
+    }
+
+    /// Remove the handler registered for a compression code
+    ///
+    /// A built-in codec the handler replaced is used again afterwards.
+    ///
+    /// # Returns
+    /// Whether a handler was registered for the code
+    pub fn unregister(code: u64) -> bool {
+        REGISTERED_HANDLERS.write()
+            .map(|mut handlers| handlers.remove(&code).is_some())
+            .unwrap_or(false)
+    }
+
+    /// Handlers registered at runtime
+    fn registered_handlers() -> Vec<Arc<dyn CompressionHandler>> {
+        REGISTERED_HANDLERS.read()
+            .map(|handlers| handlers.values().cloned().collect())
+            .unwrap_or_default()
+    }
+
     /// Create a compression handler for the given compression code
     pub fn create_handler(compression: u64) -> TiffResult<Box<dyn CompressionHandler>> {
+        let registered = REGISTERED_HANDLERS.read().ok()
+            .and_then(|handlers| handlers.get(&compression).cloned());
+        if let Some(handler) = registered {
+            return Ok(Box::new(SharedHandler(handler)));
+        }
+
         match compression {
             1 => Ok(Box::new(UncompressedHandler)),
+            5 => Ok(Box::new(LzwHandler)),
+            7 => Ok(Box::new(JpegHandler::new())),
             8 => Ok(Box::new(AdobeDeflateHandler)),
             14 => Ok(Box::new(ZstdHandler::new())),
             _ => Err(TiffError::UnsupportedCompression(compression))
         }
     }
 
+    /// Create the compression handler for the blocks of an image
+    ///
+    /// The same as `create_handler` for the image's Compression tag, except
+    /// that the built-in JPEG handler also gets the tables and color model
+    /// the image's tags hold, without which its blocks cannot be decoded.
+    ///
+    /// # Arguments
+    /// * `reader` - Reader of the TIFF file
+    /// * `ifd` - The image
+    pub fn create_handler_for_ifd(reader: &TiffReader, ifd: &IFD) -> TiffResult<Box<dyn CompressionHandler>> {
+        let compression = ifd.get_tag_value(tags::COMPRESSION).unwrap_or(1);
+        let registered = REGISTERED_HANDLERS.read().is_ok_and(|handlers| handlers.contains_key(&compression));
+        if compression == compression_consts::JPEG as u64 && !registered {
+            return Ok(Box::new(JpegHandler::for_image(reader, ifd)?));
+        }
+        Self::create_handler(compression)
+    }
+
     /// Get a handler by name
     pub fn get_handler_by_name(name: &str) -> TiffResult<Box<dyn CompressionHandler>> {
+        let registered = Self::registered_handlers().into_iter()
+            .find(|handler| handler.name().eq_ignore_ascii_case(name));
+        if let Some(handler) = registered {
+            return Ok(Box::new(SharedHandler(handler)));
+        }
+
         match name.to_lowercase().as_str() {
             "uncompressed" | "none" => Ok(Box::new(UncompressedHandler)),
+            "lzw" => Ok(Box::new(LzwHandler)),
             "deflate" | "zip" | "adobe deflate" => Ok(Box::new(AdobeDeflateHandler)),
             "zstd" => Ok(Box::new(ZstdHandler::new())),
             _ => Err(TiffError::GenericError(format!("Unknown compression type: {}", name)))
@@ -31,11 +174,24 @@ impl CompressionFactory {
     }
 
     /// Get all available compression handlers
+    ///
+    /// Registered handlers are listed after the built-in ones, in place of
+    /// any built-in codec they replace.
     pub fn get_available_handlers() -> Vec<Box<dyn CompressionHandler>> {
-        vec![
+        let mut registered = Self::registered_handlers();
+        registered.sort_by_key(|handler| handler.code());
+        let built_in: Vec<Box<dyn CompressionHandler>> = vec![
             Box::new(UncompressedHandler),
+            Box::new(LzwHandler),
             Box::new(AdobeDeflateHandler),
             Box::new(ZstdHandler::new())
-        ]
+        ];
+
+        let mut handlers: Vec<Box<dyn CompressionHandler>> = built_in.into_iter()
+            .filter(|handler| !registered.iter().any(|r| r.code() == handler.code()))
+            .collect();
+        handlers.extend(registered.into_iter()
+            .map(|handler| Box::new(SharedHandler(handler)) as Box<dyn CompressionHandler>));
+        handlers
     }
-}
\ No newline at end of file
+}
@@ -0,0 +1,150 @@
+//! Handler for JPEG compressed data
+//!
+//! TIFF stores each tile or strip as a JPEG stream of its own (compression
+//! code 7, "new-style" JPEG). Writers such as GDAL leave the quantization
+//! and Huffman tables out of those streams and store them once in the
+//! JPEGTables tag, so a block can only be decoded together with the tables
+//! of its image. Color data is usually YCbCr (PhotometricInterpretation 6),
+//! often with subsampled chroma; it is decoded to interleaved RGB.
+
+use std::borrow::Cow;
+use std::io::Cursor;
+
+use jpeg_decoder::{ColorTransform, Decoder, PixelFormat};
+use log::debug;
+
+use crate::tiff::constants::{photometric, tags};
+use crate::tiff::errors::{TiffError, TiffResult};
+use crate::tiff::ifd::IFD;
+use crate::tiff::TiffReader;
+use super::handler::CompressionHandler;
+
+/// Start of image marker
+const SOI: [u8; 2] = [0xFF, 0xD8];
+
+/// End of image marker
+const EOI: [u8; 2] = [0xFF, 0xD9];
+
+/// JPEG compression handler (compression code 7)
+///
+/// Decodes 8-bit grayscale or color blocks; color blocks come out as RGB
+/// triples whether they were stored as YCbCr or RGB. Encoding is not
+/// supported, so JPEG is not offered as a target for conversions.
+#[derive(Default)]
+pub struct JpegHandler {
+    /// Content of the JPEGTables tag, a stream holding only tables
+    tables: Option<Vec<u8>>,
+    /// PhotometricInterpretation of the image, if known
+    photometric: Option<u16>,
+}
+
+impl JpegHandler {
+    /// Create a handler for self-contained JPEG blocks
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Use the tables an image keeps in its JPEGTables tag
+    ///
+    /// # Arguments
+    /// * `tables` - The tag's bytes, a JPEG stream without image data
+    pub fn with_tables(mut self, tables: Vec<u8>) -> Self {
+        self.tables = Some(tables);
+        self
+    }
+
+    /// Decode according to the image's color model
+    ///
+    /// # Arguments
+    /// * `photometric` - The image's PhotometricInterpretation
+    pub fn with_photometric(mut self, photometric: u16) -> Self {
+        self.photometric = Some(photometric);
+        self
+    }
+
+    /// Create the handler for the blocks of an image
+    ///
+    /// # Arguments
+    /// * `reader` - Reader of the TIFF file
+    /// * `ifd` - The image
+    ///
+    /// # Returns
+    /// A handler using the image's tables and color model
+    pub fn for_image(reader: &TiffReader, ifd: &IFD) -> TiffResult<Self> {
+        let mut handler = Self::new();
+        if ifd.has_tag(tags::JPEG_TABLES) {
+            let tables = reader.read_tag::<u8>(ifd, tags::JPEG_TABLES)?;
+            debug!("Using {} bytes of JPEG tables", tables.len());
+            handler = handler.with_tables(tables);
+        }
+        if let Some(value) = ifd.get_tag_value(tags::PHOTOMETRIC_INTERPRETATION) {
+            handler = handler.with_photometric(value as u16);
+        }
+        Ok(handler)
+    }
+
+    /// The block's stream, with the shared tables put in front of its data
+    fn complete_stream<'a>(&self, data: &'a [u8]) -> TiffResult<Cow<'a, [u8]>> {
+        let Some(tables) = self.tables.as_deref().filter(|tables| tables.len() > SOI.len()) else {
+            return Ok(data.into());
+        };
+        if !tables.starts_with(&SOI) || !data.starts_with(&SOI) {
+            return Err(TiffError::GenericError("JPEG data does not start with an SOI marker".to_string()));
+        }
+        // Tables SOI ... [EOI] followed by the block without its SOI
+        let tables = tables.strip_suffix(&EOI).unwrap_or(tables);
+        let mut stream = Vec::with_capacity(tables.len() + data.len());
+        stream.extend_from_slice(tables);
+        stream.extend_from_slice(&data[SOI.len()..]);
+        Ok(stream.into())
+    }
+
+    /// Color conversion the decoder should apply
+    fn color_transform(&self) -> Option<ColorTransform> {
+        match self.photometric? {
+            photometric::YCBCR => Some(ColorTransform::YCbCr),
+            photometric::RGB => Some(ColorTransform::RGB),
+            photometric::BLACK_IS_ZERO | photometric::WHITE_IS_ZERO => Some(ColorTransform::Grayscale),
+            photometric::CMYK => Some(ColorTransform::CMYK),
+            _ => None,
+        }
+    }
+}
+
+impl CompressionHandler for JpegHandler {
+    fn decompress(&self, data: &[u8]) -> TiffResult<Vec<u8>> {
+        debug!("JPEG decompressing {} bytes", data.len());
+        if data.is_empty() {
+            return Ok(Vec::new());
+        }
+
+        let stream = self.complete_stream(data)?;
+        let mut decoder = Decoder::new(Cursor::new(&*stream));
+        if let Some(transform) = self.color_transform() {
+            decoder.set_color_transform(transform);
+        }
+        let pixels = decoder.decode()
+            .map_err(|e| TiffError::GenericError(format!("JPEG decompression error: {}", e)))?;
+
+        match decoder.info().map(|info| info.pixel_format) {
+            Some(PixelFormat::L8) | Some(PixelFormat::RGB24) => {},
+            Some(format) => return Err(TiffError::GenericError(format!(
+                "Unsupported JPEG pixel format {:?}, only 8-bit grayscale and color are read", format))),
+            None => return Err(TiffError::GenericError("JPEG data without a frame".to_string())),
+        }
+        debug!("JPEG decompressed to {} bytes", pixels.len());
+        Ok(pixels)
+    }
+
+    fn compress(&self, _data: &[u8]) -> TiffResult<Vec<u8>> {
+        Err(TiffError::GenericError("JPEG compression is not supported, only decompression".to_string()))
+    }
+
+    fn name(&self) -> &'static str {
+        "JPEG"
+    }
+
+    fn code(&self) -> u64 {
+        7
+    }
+}
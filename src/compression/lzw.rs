@@ -0,0 +1,68 @@
+//! Handler for LZW compressed data
+//!
+//! TIFF's LZW uses codes written most significant bit first, growing one
+//! code early (the "size switch" of the TIFF 6.0 specification). Files
+//! written by libtiff before 5.0 use the GIF variant instead, least
+//! significant bit first; their data starts with a clear code in that bit
+//! order and is still read.
+
+use log::{debug, warn};
+use weezl::decode::Decoder;
+use weezl::encode::Encoder;
+use weezl::BitOrder;
+
+use crate::tiff::errors::{TiffError, TiffResult};
+use super::handler::CompressionHandler;
+
+/// Bits of an uncompressed symbol
+const SYMBOL_SIZE: u8 = 8;
+
+/// LZW compression handler (compression code 5)
+///
+/// Decoding yields the stored bytes; a horizontal differencing predictor
+/// is undone by the readers afterwards, as for every other codec.
+pub struct LzwHandler;
+
+impl LzwHandler {
+    /// Whether data was written by the old, GIF-style LZW encoder
+    fn is_old_style(data: &[u8]) -> bool {
+        data.len() >= 2 && data[0] == 0 && data[1] & 1 == 1
+    }
+}
+
+impl CompressionHandler for LzwHandler {
+    fn decompress(&self, data: &[u8]) -> TiffResult<Vec<u8>> {
+        debug!("LZW decompressing {} bytes", data.len());
+        if data.is_empty() {
+            return Ok(Vec::new());
+        }
+
+        let mut decoder = if Self::is_old_style(data) {
+            warn!("Reading old-style LZW data");
+            Decoder::new(BitOrder::Lsb, SYMBOL_SIZE)
+        } else {
+            Decoder::with_tiff_size_switch(BitOrder::Msb, SYMBOL_SIZE)
+        };
+        // Some writers omit the end code, so the data may simply run out
+        let mut decompressed = Vec::new();
+        decoder.into_vec(&mut decompressed).decode(data).status
+            .map_err(|e| TiffError::GenericError(format!("LZW decompression error: {}", e)))?;
+        debug!("LZW decompressed to {} bytes", decompressed.len());
+        Ok(decompressed)
+    }
+
+    fn compress(&self, data: &[u8]) -> TiffResult<Vec<u8>> {
+        debug!("LZW compressing {} bytes", data.len());
+        Encoder::with_tiff_size_switch(BitOrder::Msb, SYMBOL_SIZE)
+            .encode(data)
+            .map_err(|e| TiffError::GenericError(format!("LZW compression error: {}", e)))
+    }
+
+    fn name(&self) -> &'static str {
+        "LZW"
+    }
+
+    fn code(&self) -> u64 {
+        5
+    }
+}
@@ -5,6 +5,8 @@
 mod handler;
 mod uncompressed;
 mod deflate;
+mod lzw;
+mod jpeg;
 mod factory;
 mod zstd;
 mod converter;
@@ -12,6 +14,8 @@ mod converter;
 pub use handler::CompressionHandler;
 pub use uncompressed::UncompressedHandler;
 pub use deflate::AdobeDeflateHandler;
+pub use lzw::LzwHandler;
+pub use jpeg::JpegHandler;
 pub use factory::CompressionFactory;
 pub use zstd::ZstdHandler;
 pub use converter::CompressionConverter;
\ No newline at end of file
@@ -0,0 +1,304 @@
+//! Affine geotransform between pixel and map coordinates
+
+use std::fmt;
+
+use super::bbox::BoundingBox;
+
+/// Pixel (column, row) position paired with the map (x, y) position it shows
+pub type ControlPoint = ((f64, f64), (f64, f64));
+
+/// Affine transform from pixel (column, row) to map (x, y) coordinates
+///
+/// Follows GDAL's corner-based convention:
+/// `x = origin_x + col * pixel_width + row * row_rotation` and
+/// `y = origin_y + col * column_rotation + row * pixel_height`.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct Affine {
+    /// X of the top-left corner of the top-left pixel
+    pub origin_x: f64,
+    /// X step per column
+    pub pixel_width: f64,
+    /// X step per row (zero for north-up images)
+    pub row_rotation: f64,
+    /// Y of the top-left corner of the top-left pixel
+    pub origin_y: f64,
+    /// Y step per column (zero for north-up images)
+    pub column_rotation: f64,
+    /// Y step per row, usually negative
+    pub pixel_height: f64,
+}
+
+impl Affine {
+    /// Create a north-up transform
+    pub fn new(origin_x: f64, pixel_width: f64, origin_y: f64, pixel_height: f64) -> Self {
+        Affine { origin_x, pixel_width, row_rotation: 0.0, origin_y, column_rotation: 0.0, pixel_height }
+    }
+
+    /// Create a transform from GDAL's six coefficients
+    pub fn from_gdal(gt: [f64; 6]) -> Self {
+        Affine {
+            origin_x: gt[0],
+            pixel_width: gt[1],
+            row_rotation: gt[2],
+            origin_y: gt[3],
+            column_rotation: gt[4],
+            pixel_height: gt[5],
+        }
+    }
+
+    /// The six coefficients in GDAL order
+    pub fn to_gdal(&self) -> [f64; 6] {
+        [self.origin_x, self.pixel_width, self.row_rotation, self.origin_y, self.column_rotation, self.pixel_height]
+    }
+
+    /// Build a north-up transform from ModelPixelScale and ModelTiepoint values
+    ///
+    /// # Arguments
+    /// * `pixel_scale` - ModelPixelScaleTag values (sx, sy, sz)
+    /// * `tiepoint` - ModelTiepointTag values (i, j, k, x, y, z)
+    ///
+    /// # Returns
+    /// The transform, or None if either tag has too few values
+    pub fn from_scale_and_tiepoint(pixel_scale: &[f64], tiepoint: &[f64]) -> Option<Self> {
+        if pixel_scale.len() < 2 || tiepoint.len() < 6 {
+            return None;
+        }
+        let pixel_width = pixel_scale[0];
+        let pixel_height = -pixel_scale[1];
+        Some(Affine::new(
+            tiepoint[3] - tiepoint[0] * pixel_width,
+            pixel_width,
+            tiepoint[4] - tiepoint[1] * pixel_height,
+            pixel_height,
+        ))
+    }
+
+    /// Build a transform from ModelTransformationTag values
+    ///
+    /// # Arguments
+    /// * `matrix` - The 4x4 model transformation matrix in row-major order
+    ///
+    /// # Returns
+    /// The transform, or None if the tag has too few values
+    pub fn from_model_transformation(matrix: &[f64]) -> Option<Self> {
+        if matrix.len() < 8 {
+            return None;
+        }
+        Some(Affine::from_gdal([matrix[3], matrix[0], matrix[1], matrix[7], matrix[4], matrix[5]]))
+    }
+
+    /// Fit a transform to control points by least squares
+    ///
+    /// Rotation terms that are negligible next to the pixel size are
+    /// dropped, so points from a north-up grid give a north-up transform.
+    ///
+    /// # Arguments
+    /// * `points` - Pairs of pixel (column, row) and map (x, y) positions
+    ///
+    /// # Returns
+    /// The transform, or None for fewer than three points or collinear ones
+    pub fn from_control_points(points: &[ControlPoint]) -> Option<Self> {
+        if points.len() < 3 {
+            return None;
+        }
+
+        // Normal equations of v = a + b * col + c * row, shared by x and y
+        let mut normal = [[0.0f64; 3]; 3];
+        let (mut rhs_x, mut rhs_y) = ([0.0f64; 3], [0.0f64; 3]);
+        for &((col, row), (x, y)) in points {
+            let terms = [1.0, col, row];
+            for i in 0..3 {
+                for j in 0..3 {
+                    normal[i][j] += terms[i] * terms[j];
+                }
+                rhs_x[i] += terms[i] * x;
+                rhs_y[i] += terms[i] * y;
+            }
+        }
+
+        let det3 = |m: &[[f64; 3]; 3]| {
+            m[0][0] * (m[1][1] * m[2][2] - m[1][2] * m[2][1])
+                - m[0][1] * (m[1][0] * m[2][2] - m[1][2] * m[2][0])
+                + m[0][2] * (m[1][0] * m[2][1] - m[1][1] * m[2][0])
+        };
+        let det = det3(&normal);
+        if det.abs() < 1e-12 || !det.is_finite() {
+            return None;
+        }
+        let solve = |rhs: &[f64; 3]| {
+            let mut solution = [0.0f64; 3];
+            for (column, value) in solution.iter_mut().enumerate() {
+                let mut m = normal;
+                for (row, r) in m.iter_mut().enumerate() {
+                    r[column] = rhs[row];
+                }
+                *value = det3(&m) / det;
+            }
+            solution
+        };
+        let ([origin_x, pixel_width, mut row_rotation], [origin_y, mut column_rotation, pixel_height]) =
+            (solve(&rhs_x), solve(&rhs_y));
+
+        if row_rotation.abs() < 1e-9 * pixel_width.abs() {
+            row_rotation = 0.0;
+        }
+        if column_rotation.abs() < 1e-9 * pixel_height.abs() {
+            column_rotation = 0.0;
+        }
+
+        Some(Affine { origin_x, pixel_width, row_rotation, origin_y, column_rotation, pixel_height })
+    }
+
+    /// Whether the transform has no rotation terms
+    pub fn is_north_up(&self) -> bool {
+        self.row_rotation == 0.0 && self.column_rotation == 0.0
+    }
+
+    /// Map coordinates of a pixel position
+    ///
+    /// # Arguments
+    /// * `col` - Column, fractional values address inside the pixel
+    /// * `row` - Row, fractional values address inside the pixel
+    pub fn apply(&self, col: f64, row: f64) -> (f64, f64) {
+        (
+            self.origin_x + col * self.pixel_width + row * self.row_rotation,
+            self.origin_y + col * self.column_rotation + row * self.pixel_height,
+        )
+    }
+
+    /// Map coordinates of the centre of a pixel
+    pub fn cell_center(&self, col: u32, row: u32) -> (f64, f64) {
+        self.apply(col as f64 + 0.5, row as f64 + 0.5)
+    }
+
+    /// The inverse transform, from map to pixel coordinates
+    ///
+    /// # Returns
+    /// The inverse, or None if the transform is degenerate
+    pub fn inverse(&self) -> Option<Affine> {
+        let det = self.pixel_width * self.pixel_height - self.row_rotation * self.column_rotation;
+        if det == 0.0 || !det.is_finite() {
+            return None;
+        }
+        let (a, b, d, e) = (self.pixel_width / det, self.row_rotation / det,
+                            self.column_rotation / det, self.pixel_height / det);
+        Some(Affine {
+            origin_x: -self.origin_x * e + self.origin_y * b,
+            pixel_width: e,
+            row_rotation: -b,
+            origin_y: self.origin_x * d - self.origin_y * a,
+            column_rotation: -d,
+            pixel_height: a,
+        })
+    }
+
+    /// Fractional pixel position of a map coordinate
+    ///
+    /// North-up transforms divide directly, so coordinates on pixel edges
+    /// land exactly on whole pixels.
+    ///
+    /// # Returns
+    /// (column, row), or None if the transform is degenerate
+    pub fn to_pixel(&self, x: f64, y: f64) -> Option<(f64, f64)> {
+        if self.is_north_up() {
+            if self.pixel_width == 0.0 || self.pixel_height == 0.0 {
+                return None;
+            }
+            return Some(((x - self.origin_x) / self.pixel_width, (y - self.origin_y) / self.pixel_height));
+        }
+        self.inverse().map(|inverse| inverse.apply(x, y))
+    }
+
+    /// Compose two transforms
+    ///
+    /// # Arguments
+    /// * `inner` - Transform applied first
+    ///
+    /// # Returns
+    /// A transform equivalent to applying `inner`, then `self`
+    pub fn compose(&self, inner: &Affine) -> Affine {
+        let (origin_x, origin_y) = self.apply(inner.origin_x, inner.origin_y);
+        Affine {
+            origin_x,
+            pixel_width: self.pixel_width * inner.pixel_width + self.row_rotation * inner.column_rotation,
+            row_rotation: self.pixel_width * inner.row_rotation + self.row_rotation * inner.pixel_height,
+            origin_y,
+            column_rotation: self.column_rotation * inner.pixel_width + self.pixel_height * inner.column_rotation,
+            pixel_height: self.column_rotation * inner.row_rotation + self.pixel_height * inner.pixel_height,
+        }
+    }
+
+    /// Transform of a window whose top-left pixel is (col, row)
+    pub fn translated(&self, col: u32, row: u32) -> Affine {
+        let (origin_x, origin_y) = self.apply(col as f64, row as f64);
+        Affine { origin_x, origin_y, ..*self }
+    }
+
+    /// Transform of the same area sampled with coarser or finer pixels
+    ///
+    /// # Arguments
+    /// * `x_factor` - Source columns per new column (2.0 halves the resolution)
+    /// * `y_factor` - Source rows per new row
+    pub fn scaled(&self, x_factor: f64, y_factor: f64) -> Affine {
+        self.compose(&Affine::new(0.0, x_factor, 0.0, y_factor))
+    }
+
+    /// Pixel window covering a map-space bounding box
+    ///
+    /// The window is rounded outward to whole pixels, so it always covers
+    /// the box. It is not clamped to the image.
+    ///
+    /// # Returns
+    /// (min column, min row, max column, max row) with exclusive maxima,
+    /// or None if the transform is degenerate
+    pub fn pixel_window(&self, bbox: &BoundingBox) -> Option<(i64, i64, i64, i64)> {
+        let corners = [
+            self.to_pixel(bbox.min_x, bbox.min_y)?,
+            self.to_pixel(bbox.max_x, bbox.min_y)?,
+            self.to_pixel(bbox.min_x, bbox.max_y)?,
+            self.to_pixel(bbox.max_x, bbox.max_y)?,
+        ];
+        let (mut min_col, mut min_row) = (f64::INFINITY, f64::INFINITY);
+        let (mut max_col, mut max_row) = (f64::NEG_INFINITY, f64::NEG_INFINITY);
+        for (col, row) in corners {
+            min_col = min_col.min(col);
+            min_row = min_row.min(row);
+            max_col = max_col.max(col);
+            max_row = max_row.max(row);
+        }
+        Some((min_col.floor() as i64, min_row.floor() as i64, max_col.ceil() as i64, max_row.ceil() as i64))
+    }
+
+    /// Snap a bounding box outward to the pixel grid
+    ///
+    /// # Returns
+    /// The envelope of the covering pixel window, or None if the transform
+    /// is degenerate
+    pub fn snap_to_grid(&self, bbox: &BoundingBox) -> Option<BoundingBox> {
+        let (min_col, min_row, max_col, max_row) = self.pixel_window(bbox)?;
+        let origin = self.apply(min_col as f64, min_row as f64);
+        let window = Affine { origin_x: origin.0, origin_y: origin.1, ..*self };
+        Some(window.bounds((max_col - min_col) as u32, (max_row - min_row) as u32))
+    }
+
+    /// Map-space envelope of a raster of the given size
+    pub fn bounds(&self, width: u32, height: u32) -> BoundingBox {
+        let (w, h) = (width as f64, height as f64);
+        let corners = [self.apply(0.0, 0.0), self.apply(w, 0.0), self.apply(0.0, h), self.apply(w, h)];
+        let xs = corners.iter().map(|c| c.0);
+        let ys = corners.iter().map(|c| c.1);
+        BoundingBox::new(
+            xs.clone().fold(f64::INFINITY, f64::min),
+            ys.clone().fold(f64::INFINITY, f64::min),
+            xs.fold(f64::NEG_INFINITY, f64::max),
+            ys.fold(f64::NEG_INFINITY, f64::max),
+        )
+    }
+}
+
+impl fmt::Display for Affine {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        let gt = self.to_gdal();
+        write!(f, "[{}, {}, {}, {}, {}, {}]", gt[0], gt[1], gt[2], gt[3], gt[4], gt[5])
+    }
+}
@@ -1,5 +1,6 @@
 //! Bounding box structure for defining regions
 
+use super::affine::Affine;
 use super::point::Point;
 
 /// A bounding box in a coordinate system
@@ -103,27 +104,12 @@ impl BoundingBox {
     /// using the provided geotransform coefficients.
     ///
     /// # Arguments
-    /// * `geotransform` - Array of 6 coefficients: [origin_x, pixel_width, 0, origin_y, 0, pixel_height]
+    /// * `geotransform` - Affine transform of the image
     ///
     /// # Returns
     /// A Region object with pixel coordinates
-    pub fn to_pixel_region(&self, geotransform: &[f64]) -> crate::extractor::Region {
-        let origin_x = geotransform[0];
-        let pixel_width = geotransform[1];
-        let origin_y = geotransform[3];
-        let pixel_height = geotransform[5]; // This is typically negative
-
-        // Calculate pixel coordinates - use f64 for intermediate calculations to avoid overflow
-        let x_min_f = ((self.min_x - origin_x) / pixel_width).floor();
-        let y_max_f = ((self.min_y - origin_y) / pixel_height).floor();
-        let x_max_f = ((self.max_x - origin_x) / pixel_width).ceil();
-        let y_min_f = ((self.max_y - origin_y) / pixel_height).floor();
-
-        // Convert to i64 to handle possible negative values safely
-        let x_min = x_min_f as i64;
-        let y_min = y_min_f as i64;
-        let x_max = x_max_f as i64;
-        let y_max = y_max_f as i64;
+    pub fn to_pixel_region(&self, geotransform: &Affine) -> crate::extractor::Region {
+        let (x_min, y_min, x_max, y_max) = geotransform.pixel_window(self).unwrap_or((0, 0, 0, 0));
 
         // Ensure coordinates are positive or zero
         let start_x = x_min.max(0) as u32;
@@ -1,6 +1,9 @@
 //! Coordinate Reference System handling
 
+use log::info;
+
 use crate::tiff::errors::{TiffError, TiffResult};
+use crate::tiff::proj_definition::{ProjDefinition, USER_DEFINED};
 
 /// Identifier for common coordinate systems
 #[derive(Debug, Clone, Copy, PartialEq, Eq)]
@@ -49,6 +52,22 @@ impl CoordinateSystem {
     }
 }
 
+/// A CRS given by the user as an EPSG code, PROJ string or WKT
+#[derive(Debug, Clone, PartialEq)]
+pub struct CrsDefinition {
+    /// EPSG code, or 32767 (user-defined) for systems without a registration
+    pub epsg: u32,
+    /// Parsed definition, when the CRS was given as a PROJ string or WKT
+    pub definition: Option<ProjDefinition>,
+}
+
+impl CrsDefinition {
+    /// Whether the CRS has no EPSG registration
+    pub fn is_user_defined(&self) -> bool {
+        self.epsg == USER_DEFINED
+    }
+}
+
 /// Factory for creating coordinate systems
 pub struct CoordinateSystemFactory;
 
@@ -80,4 +99,51 @@ impl CoordinateSystemFactory {
             Err(TiffError::GenericError(format!("Unsupported CRS format: {}", crs_str)))
         }
     }
+
+    /// Parse a CRS given as an EPSG code, a PROJ string or WKT
+    ///
+    /// PROJ strings and WKT that match a built-in EPSG system resolve to its
+    /// code; anything else is kept as a user-defined (32767) definition, as
+    /// used for local engineering grids.
+    ///
+    /// # Arguments
+    /// * `input` - "4326", "EPSG:4326", "+proj=..." or a WKT string
+    ///
+    /// # Returns
+    /// The resolved CRS or an error if the input cannot be parsed
+    pub fn parse_definition(input: &str) -> TiffResult<CrsDefinition> {
+        let trimmed = input.trim();
+
+        if trimmed.starts_with('+') || trimmed.starts_with("proj=") {
+            let lower = trimmed.to_lowercase();
+            if let Some(code) = lower.split_whitespace()
+                .find_map(|token| token.trim_start_matches('+').strip_prefix("init=epsg:")) {
+                let epsg = code.parse::<u32>()
+                    .map_err(|_| TiffError::GenericError(format!("Invalid EPSG code in {}", trimmed)))?;
+                return Ok(CrsDefinition { epsg, definition: None });
+            }
+            return Ok(Self::resolve(ProjDefinition::from_proj_string(trimmed)?));
+        }
+
+        if trimmed.contains('[') {
+            return Ok(Self::resolve(ProjDefinition::from_wkt(trimmed)?));
+        }
+
+        let epsg = Self::from_string(trimmed)?.epsg_code();
+        Ok(CrsDefinition { epsg, definition: None })
+    }
+
+    /// Attach the EPSG code of an equivalent registered system, if any
+    fn resolve(definition: ProjDefinition) -> CrsDefinition {
+        match definition.equivalent_epsg() {
+            Some(epsg) => {
+                info!("CRS definition matches EPSG:{}", epsg);
+                CrsDefinition { epsg, definition: Some(definition) }
+            },
+            None => {
+                info!("Using user-defined CRS {}", definition.to_proj_string());
+                CrsDefinition { epsg: USER_DEFINED, definition: Some(definition) }
+            }
+        }
+    }
 }
\ No newline at end of file
@@ -0,0 +1,313 @@
+//! Datum shifts between geodetic datums
+//!
+//! Coordinates on historical datums such as NAD27 or OSGB36 sit tens to
+//! hundreds of meters away from the same numbers on WGS84. This module
+//! moves geographic coordinates between datums, either with the 3/7
+//! parameter Helmert transformation (PROJ's `+towgs84`) or, where a grid is
+//! available, with an NTv2 `.gsb` shift grid.
+
+use std::fs;
+
+use log::{debug, info};
+
+use crate::tiff::errors::{TiffError, TiffResult};
+use crate::tiff::proj_definition::Datum;
+
+/// Radians per arc second
+const ARC_SECOND: f64 = std::f64::consts::PI / (180.0 * 3600.0);
+
+/// WGS84 semi-major axis
+const WGS84_A: f64 = 6_378_137.0;
+
+/// WGS84 semi-minor axis
+const WGS84_B: f64 = 6_356_752.314_245_179;
+
+/// Convert geographic coordinates to earth-centred cartesian coordinates
+///
+/// # Arguments
+/// * `lon` - Longitude in degrees
+/// * `lat` - Latitude in degrees
+/// * `a` - Semi-major axis of the ellipsoid
+/// * `b` - Semi-minor axis of the ellipsoid
+///
+/// # Returns
+/// (X, Y, Z) in meters
+pub fn geodetic_to_geocentric(lon: f64, lat: f64, a: f64, b: f64) -> (f64, f64, f64) {
+    let e2 = 1.0 - (b * b) / (a * a);
+    let (phi, lambda) = (lat.to_radians(), lon.to_radians());
+    let n = a / (1.0 - e2 * phi.sin().powi(2)).sqrt();
+    (
+        n * phi.cos() * lambda.cos(),
+        n * phi.cos() * lambda.sin(),
+        n * (1.0 - e2) * phi.sin(),
+    )
+}
+
+/// Convert earth-centred cartesian coordinates to geographic coordinates
+///
+/// Uses Bowring's formula, accurate to well below a millimetre near the
+/// earth's surface.
+///
+/// # Returns
+/// (longitude, latitude) in degrees
+pub fn geocentric_to_geodetic(x: f64, y: f64, z: f64, a: f64, b: f64) -> (f64, f64) {
+    let e2 = 1.0 - (b * b) / (a * a);
+    let ep2 = (a * a) / (b * b) - 1.0;
+    let p = (x * x + y * y).sqrt();
+    let theta = (z * a).atan2(p * b);
+    let lat = (z + ep2 * b * theta.sin().powi(3)).atan2(p - e2 * a * theta.cos().powi(3));
+    (y.atan2(x).to_degrees(), lat.to_degrees())
+}
+
+/// Apply a 7-parameter Helmert transformation (position vector convention)
+///
+/// # Arguments
+/// * `xyz` - Geocentric coordinates
+/// * `params` - dx, dy, dz (m), rx, ry, rz (arc seconds), scale (ppm)
+/// * `inverse` - Apply the reverse transformation
+fn helmert(xyz: (f64, f64, f64), params: &[f64; 7], inverse: bool) -> (f64, f64, f64) {
+    let sign = if inverse { -1.0 } else { 1.0 };
+    let (dx, dy, dz) = (sign * params[0], sign * params[1], sign * params[2]);
+    let (rx, ry, rz) = (sign * params[3] * ARC_SECOND, sign * params[4] * ARC_SECOND, sign * params[5] * ARC_SECOND);
+    let scale = 1.0 + sign * params[6] * 1e-6;
+    let (x, y, z) = xyz;
+
+    if inverse {
+        // Undo the translation before rotating back
+        let (x, y, z) = (x + dx, y + dy, z + dz);
+        (
+            scale * (x - rz * y + ry * z),
+            scale * (rz * x + y - rx * z),
+            scale * (-ry * x + rx * y + z),
+        )
+    } else {
+        (
+            dx + scale * (x - rz * y + ry * z),
+            dy + scale * (rz * x + y - rx * z),
+            dz + scale * (-ry * x + rx * y + z),
+        )
+    }
+}
+
+/// Move a WGS84 longitude/latitude onto another datum
+///
+/// # Arguments
+/// * `lon` - WGS84 longitude in degrees
+/// * `lat` - WGS84 latitude in degrees
+/// * `datum` - Target datum
+/// * `grid` - Shift grid from the target datum to WGS84/NAD83, if any
+///
+/// # Returns
+/// (longitude, latitude) in degrees on the target datum
+pub fn wgs84_to_datum(lon: f64, lat: f64, datum: &Datum, grid: Option<&NtV2Grid>) -> (f64, f64) {
+    if let Some(shifted) = grid.and_then(|g| g.inverse_shift(lon, lat)) {
+        return shifted;
+    }
+
+    match datum.towgs84 {
+        Some(params) if params.iter().any(|p| *p != 0.0) => {
+            let xyz = geodetic_to_geocentric(lon, lat, WGS84_A, WGS84_B);
+            let (x, y, z) = helmert(xyz, &params, true);
+            geocentric_to_geodetic(x, y, z, datum.semi_major_axis, datum.semi_minor_axis)
+        },
+        _ => (lon, lat),
+    }
+}
+
+/// Move a longitude/latitude on a datum onto WGS84
+///
+/// # Arguments
+/// * `lon` - Longitude in degrees on the source datum
+/// * `lat` - Latitude in degrees on the source datum
+/// * `datum` - Source datum
+/// * `grid` - Shift grid from the source datum to WGS84/NAD83, if any
+///
+/// # Returns
+/// (longitude, latitude) in degrees on WGS84
+pub fn datum_to_wgs84(lon: f64, lat: f64, datum: &Datum, grid: Option<&NtV2Grid>) -> (f64, f64) {
+    if let Some(shifted) = grid.and_then(|g| g.shift(lon, lat)) {
+        return shifted;
+    }
+
+    match datum.towgs84 {
+        Some(params) if params.iter().any(|p| *p != 0.0) => {
+            let xyz = geodetic_to_geocentric(lon, lat, datum.semi_major_axis, datum.semi_minor_axis);
+            let (x, y, z) = helmert(xyz, &params, false);
+            geocentric_to_geodetic(x, y, z, WGS84_A, WGS84_B)
+        },
+        _ => (lon, lat),
+    }
+}
+
+/// One sub-grid of an NTv2 file
+#[derive(Debug, Clone)]
+struct SubGrid {
+    /// Southern edge in arc seconds
+    south: f64,
+    /// Northern edge in arc seconds
+    north: f64,
+    /// Eastern edge in arc seconds, positive west
+    east: f64,
+    /// Western edge in arc seconds, positive west
+    west: f64,
+    /// Node spacing in latitude (arc seconds)
+    lat_step: f64,
+    /// Node spacing in longitude (arc seconds)
+    lon_step: f64,
+    /// Number of nodes per row
+    columns: usize,
+    /// (latitude shift, longitude shift) per node in arc seconds, longitude positive west
+    shifts: Vec<(f32, f32)>,
+}
+
+impl SubGrid {
+    /// Whether the point (arc seconds, longitude positive west) lies inside
+    fn contains(&self, lat: f64, lon_west: f64) -> bool {
+        lat >= self.south && lat <= self.north && lon_west >= self.east && lon_west <= self.west
+    }
+
+    /// Bilinearly interpolated shift at a point inside the grid
+    fn interpolate(&self, lat: f64, lon_west: f64) -> (f64, f64) {
+        let rows = self.shifts.len() / self.columns;
+        let col_f = (lon_west - self.east) / self.lon_step;
+        let row_f = (lat - self.south) / self.lat_step;
+        let col = (col_f.floor() as usize).min(self.columns.saturating_sub(2));
+        let row = (row_f.floor() as usize).min(rows.saturating_sub(2));
+        let (fx, fy) = (col_f - col as f64, row_f - row as f64);
+
+        let node = |r: usize, c: usize| {
+            let (dlat, dlon) = self.shifts[(r * self.columns + c).min(self.shifts.len() - 1)];
+            (dlat as f64, dlon as f64)
+        };
+        let (a, b, c, d) = (node(row, col), node(row, col + 1), node(row + 1, col), node(row + 1, col + 1));
+        let blend = |a: f64, b: f64, c: f64, d: f64| {
+            a * (1.0 - fx) * (1.0 - fy) + b * fx * (1.0 - fy) + c * (1.0 - fx) * fy + d * fx * fy
+        };
+        (blend(a.0, b.0, c.0, d.0), blend(a.1, b.1, c.1, d.1))
+    }
+}
+
+/// An NTv2 horizontal shift grid
+///
+/// NTv2 grids (e.g. NADCON-derived `conus.gsb`, Canada's `ntv2_0.gsb`,
+/// OSTN-derived grids) shift coordinates from an older datum to a newer one
+/// much more accurately than a Helmert transformation.
+#[derive(Debug, Clone)]
+pub struct NtV2Grid {
+    /// Source datum name from the header
+    pub from_datum: String,
+    /// Target datum name from the header
+    pub to_datum: String,
+    sub_grids: Vec<SubGrid>,
+}
+
+impl NtV2Grid {
+    /// Load an NTv2 `.gsb` file
+    ///
+    /// # Arguments
+    /// * `path` - Path to the grid file
+    ///
+    /// # Returns
+    /// The loaded grid or an error if the file is not a valid NTv2 grid
+    pub fn load(path: &str) -> TiffResult<Self> {
+        let bytes = fs::read(path)
+            .map_err(|e| TiffError::GenericError(format!("Failed to read shift grid {}: {}", path, e)))?;
+        let invalid = || TiffError::GenericError(format!("{} is not an NTv2 grid", path));
+
+        if bytes.len() < 176 || &bytes[0..8] != b"NUM_OREC" {
+            return Err(invalid());
+        }
+        let little_endian = i32::from_le_bytes([bytes[8], bytes[9], bytes[10], bytes[11]]) == 11;
+
+        let record = |offset: usize| bytes.get(offset..offset + 16).ok_or_else(invalid);
+        let int_at = |offset: usize| -> TiffResult<i32> {
+            let r = record(offset)?;
+            let v = [r[8], r[9], r[10], r[11]];
+            Ok(if little_endian { i32::from_le_bytes(v) } else { i32::from_be_bytes(v) })
+        };
+        let double_at = |offset: usize| -> TiffResult<f64> {
+            let r = record(offset)?;
+            let v: [u8; 8] = r[8..16].try_into().map_err(|_| invalid())?;
+            Ok(if little_endian { f64::from_le_bytes(v) } else { f64::from_be_bytes(v) })
+        };
+        let text_at = |offset: usize| -> TiffResult<String> {
+            Ok(String::from_utf8_lossy(&record(offset)?[8..16]).trim().to_string())
+        };
+        let float_at = |offset: usize| -> f32 {
+            let v = [bytes[offset], bytes[offset + 1], bytes[offset + 2], bytes[offset + 3]];
+            if little_endian { f32::from_le_bytes(v) } else { f32::from_be_bytes(v) }
+        };
+
+        let header_records = int_at(0)? as usize;
+        let grid_count = int_at(32)? as usize;
+        if text_at(48)? != "SECONDS" {
+            return Err(TiffError::GenericError(format!("{} uses unsupported units {}", path, text_at(48)?)));
+        }
+        let from_datum = text_at(80)?;
+        let to_datum = text_at(96)?;
+
+        let mut sub_grids = Vec::with_capacity(grid_count);
+        let mut offset = header_records * 16;
+        for _ in 0..grid_count {
+            let (south, north) = (double_at(offset + 64)?, double_at(offset + 80)?);
+            let (east, west) = (double_at(offset + 96)?, double_at(offset + 112)?);
+            let (lat_step, lon_step) = (double_at(offset + 128)?, double_at(offset + 144)?);
+            let count = int_at(offset + 160)? as usize;
+            offset += 176;
+
+            if lat_step <= 0.0 || lon_step <= 0.0 || bytes.len() < offset + count * 16 {
+                return Err(invalid());
+            }
+            let columns = ((west - east) / lon_step).round() as usize + 1;
+            let shifts = (0..count)
+                .map(|i| (float_at(offset + i * 16), float_at(offset + i * 16 + 4)))
+                .collect();
+            offset += count * 16;
+
+            sub_grids.push(SubGrid { south, north, east, west, lat_step, lon_step, columns, shifts });
+        }
+
+        info!("Loaded NTv2 grid {} ({} to {}, {} sub-grids)", path, from_datum, to_datum, sub_grids.len());
+        Ok(NtV2Grid { from_datum, to_datum, sub_grids })
+    }
+
+    /// Shift a point from the grid's source datum to its target datum
+    ///
+    /// # Arguments
+    /// * `lon` - Longitude in degrees
+    /// * `lat` - Latitude in degrees
+    ///
+    /// # Returns
+    /// The shifted (longitude, latitude), or None outside the grid
+    pub fn shift(&self, lon: f64, lat: f64) -> Option<(f64, f64)> {
+        let (lat_s, lon_west) = (lat * 3600.0, -lon * 3600.0);
+        // The finest sub-grid covering the point wins
+        let grid = self.sub_grids.iter()
+            .filter(|g| g.contains(lat_s, lon_west))
+            .min_by(|a, b| a.lat_step.total_cmp(&b.lat_step))?;
+        let (dlat, dlon_west) = grid.interpolate(lat_s, lon_west);
+        Some((lon - dlon_west / 3600.0, lat + dlat / 3600.0))
+    }
+
+    /// Shift a point from the grid's target datum back to its source datum
+    ///
+    /// The grid is indexed by source coordinates, so the inverse is found by
+    /// fixed-point iteration.
+    ///
+    /// # Returns
+    /// The shifted (longitude, latitude), or None outside the grid
+    pub fn inverse_shift(&self, lon: f64, lat: f64) -> Option<(f64, f64)> {
+        let (mut guess_lon, mut guess_lat) = (lon, lat);
+        for _ in 0..10 {
+            let (shifted_lon, shifted_lat) = self.shift(guess_lon, guess_lat)?;
+            let (err_lon, err_lat) = (shifted_lon - lon, shifted_lat - lat);
+            guess_lon -= err_lon;
+            guess_lat -= err_lat;
+            if err_lon.abs() < 1e-10 && err_lat.abs() < 1e-10 {
+                break;
+            }
+        }
+        debug!("Grid inverse shift ({}, {}) -> ({}, {})", lon, lat, guess_lon, guess_lat);
+        Some((guess_lon, guess_lat))
+    }
+}
@@ -0,0 +1,143 @@
+//! Place name lookup
+//!
+//! Some extractions start from a named place rather than a coordinate
+//! ("Berlin, Germany"). A `Geocoder` resolves such a name to a WGS84
+//! position, which is then extracted like any `--coordinate`. Lookups go
+//! through a trait so that library users can bring their own service or
+//! an offline gazetteer; `NominatimGeocoder` queries an OpenStreetMap
+//! Nominatim server and is compiled in with the `geocoding` feature.
+
+use crate::tiff::errors::{TiffError, TiffResult};
+
+use super::typed::LonLat;
+
+/// Public Nominatim server of the OpenStreetMap Foundation
+pub const DEFAULT_NOMINATIM_URL: &str = "https://nominatim.openstreetmap.org";
+
+/// Resolves place names to positions
+pub trait Geocoder {
+    /// Position of a named place
+    ///
+    /// # Arguments
+    /// * `place` - Free-form place name or address, e.g. "Berlin, Germany"
+    ///
+    /// # Returns
+    /// The best match, or an error if nothing matches or the lookup fails
+    fn geocode(&self, place: &str) -> TiffResult<LonLat>;
+}
+
+/// Geocoder backed by a Nominatim server
+#[derive(Debug, Clone)]
+pub struct NominatimGeocoder {
+    /// Base URL of the server, without the /search path
+    endpoint: String,
+}
+
+impl NominatimGeocoder {
+    /// A geocoder querying the public OpenStreetMap server
+    ///
+    /// The public server allows about one request per second; batch jobs
+    /// should run their own instance and use `with_endpoint`.
+    pub fn new() -> Self {
+        Self::with_endpoint(DEFAULT_NOMINATIM_URL)
+    }
+
+    /// A geocoder querying the server at a base URL
+    ///
+    /// # Arguments
+    /// * `endpoint` - Base URL, e.g. "http://localhost:8080"
+    pub fn with_endpoint(endpoint: &str) -> Self {
+        NominatimGeocoder { endpoint: endpoint.trim_end_matches('/').to_string() }
+    }
+
+    /// Base URL the geocoder queries
+    pub fn endpoint(&self) -> &str {
+        &self.endpoint
+    }
+}
+
+impl Default for NominatimGeocoder {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+#[cfg(feature = "geocoding")]
+impl Geocoder for NominatimGeocoder {
+    fn geocode(&self, place: &str) -> TiffResult<LonLat> {
+        let url = format!("{}/search", self.endpoint);
+        log::info!("Looking up '{}' at {}", place, url);
+        // Nominatim's usage policy asks every client to identify itself
+        let response = ureq::get(&url)
+            .set("User-Agent", concat!("rasterkit/", env!("CARGO_PKG_VERSION")))
+            .query("q", place)
+            .query("format", "jsonv2")
+            .query("limit", "1")
+            .call()
+            .map_err(|e| TiffError::GenericError(format!("Geocoding '{}' failed: {}", place, e)))?;
+        let body = response.into_string()
+            .map_err(|e| TiffError::GenericError(format!("Reading the geocoder response failed: {}", e)))?;
+
+        let (position, name) = parse_nominatim_response(&body)?
+            .ok_or_else(|| TiffError::GenericError(format!("No place found for '{}'", place)))?;
+        log::info!("'{}' is {} at {}", place, name.as_deref().unwrap_or("a match"), position);
+        Ok(position)
+    }
+}
+
+#[cfg(not(feature = "geocoding"))]
+impl Geocoder for NominatimGeocoder {
+    fn geocode(&self, place: &str) -> TiffResult<LonLat> {
+        Err(TiffError::GenericError(format!(
+            "Looking up '{}' requires building rasterkit with --features geocoding", place)))
+    }
+}
+
+/// Read the first match of a Nominatim JSON search response
+///
+/// Nominatim gives latitude and longitude as strings, e.g.
+/// `[{"lat":"52.5170365","lon":"13.3888599","display_name":"Berlin, ..."}]`.
+///
+/// # Arguments
+/// * `body` - The response body
+///
+/// # Returns
+/// The position and display name of the first match, None for an empty
+/// result list, or an error for a body that is not a search response
+pub fn parse_nominatim_response(body: &str) -> TiffResult<Option<(LonLat, Option<String>)>> {
+    let body = body.trim();
+    if !body.starts_with('[') {
+        return Err(TiffError::GenericError(format!("Unexpected geocoder response: {}", truncate(body))));
+    }
+    let Some(start) = body.find('{') else { return Ok(None) };
+    let first = &body[start..];
+
+    let number = |key: &str| json_string_field(first, key)
+        .and_then(|value| value.parse::<f64>().ok())
+        .ok_or_else(|| TiffError::GenericError(format!("Geocoder match lacks '{}': {}", key, truncate(first))));
+    let position = LonLat::new(number("lon")?, number("lat")?)?;
+    Ok(Some((position, json_string_field(first, "display_name"))))
+}
+
+/// String value of the first `"key": "value"` pair in a JSON text
+fn json_string_field(json: &str, key: &str) -> Option<String> {
+    let pattern = format!("\"{}\"", key);
+    let after_key = &json[json.find(&pattern)? + pattern.len()..];
+    let after_colon = after_key.trim_start().strip_prefix(':')?.trim_start().strip_prefix('"')?;
+
+    let mut value = String::new();
+    let mut chars = after_colon.chars();
+    while let Some(c) = chars.next() {
+        match c {
+            '"' => return Some(value),
+            '\\' => value.push(chars.next()?),
+            c => value.push(c),
+        }
+    }
+    None
+}
+
+/// First characters of a response, for error messages
+fn truncate(text: &str) -> String {
+    text.chars().take(120).collect()
+}
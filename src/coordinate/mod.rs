@@ -3,13 +3,21 @@
 //! This module provides structures and functionality for handling
 //! different coordinate systems and transformations.
 
+mod affine;
 mod bbox;
 mod point;
 mod transform;
 mod crs;
+mod typed;
+pub mod datum;
+pub mod projection;
+pub mod geocoder;
 
 // Re-export key types
+pub use self::affine::{Affine, ControlPoint};
 pub use self::bbox::BoundingBox;
 pub use self::point::Point;
 pub use self::transform::CoordinateTransformer;
-pub use self::crs::{CoordinateSystem, CoordinateSystemFactory};
\ No newline at end of file
+pub use self::crs::{CoordinateSystem, CoordinateSystemFactory, CrsDefinition};
+pub use self::typed::{Crs, LonLat, ProjectedXY};
+pub use self::geocoder::{Geocoder, NominatimGeocoder};
@@ -0,0 +1,250 @@
+//! Forward map projections
+//!
+//! Projects longitude/latitude on a definition's own datum into the
+//! definition's projected coordinates, and back again by inverting the
+//! forward formulas numerically. The formulas follow Snyder, "Map
+//! Projections - A Working Manual" (USGS PP 1395), and cover the methods
+//! rasters are usually delivered in: Transverse Mercator (UTM, State Plane,
+//! national grids), Lambert Conic Conformal, Mercator, Albers and Lambert
+//! Azimuthal Equal Area.
+
+use std::f64::consts::FRAC_PI_4;
+
+use super::point::Point;
+use crate::tiff::proj_definition::ProjDefinition;
+
+/// Whether `forward` can project coordinates for a definition
+///
+/// # Arguments
+/// * `definition` - Target definition
+///
+/// # Returns
+/// `true` if the projection method is implemented
+pub fn is_supported(definition: &ProjDefinition) -> bool {
+    matches!(definition.proj, "longlat" | "tmerc" | "utm" | "lcc" | "merc" | "aea" | "laea")
+}
+
+/// Project a geographic coordinate
+///
+/// # Arguments
+/// * `definition` - Target definition
+/// * `lon` - Longitude in degrees on the definition's datum
+/// * `lat` - Latitude in degrees on the definition's datum
+///
+/// # Returns
+/// The projected point in the definition's units (degrees for geographic
+/// systems), or None for unsupported methods or points the projection
+/// cannot represent
+pub fn forward(definition: &ProjDefinition, lon: f64, lat: f64) -> Option<Point> {
+    if definition.is_geographic() {
+        return Some(Point::new(lon, lat));
+    }
+
+    let (a, b) = definition.projection_axes();
+    let ellipsoid = Ellipsoid::new(a, b);
+    let degrees = |key: &str| definition.parameter(key).unwrap_or(0.0).to_radians();
+    let scale = definition.parameter("k").or(definition.parameter("k_0")).unwrap_or(1.0);
+    let (lam0, phi0) = (degrees("lon_0"), degrees("lat_0"));
+    let (phi, dlam) = (lat.to_radians(), normalize_longitude(lon.to_radians() - lam0));
+
+    let (x, y) = match definition.proj {
+        "tmerc" | "utm" => transverse_mercator(&ellipsoid, phi, dlam, phi0, scale),
+        "merc" => {
+            let k0 = match definition.parameter("lat_ts") {
+                Some(lat_ts) => ellipsoid.m(lat_ts.to_radians()),
+                None => scale,
+            };
+            if phi.abs() >= 89.999f64.to_radians() {
+                return None;
+            }
+            (a * k0 * dlam, -a * k0 * ellipsoid.t(phi).ln())
+        },
+        "lcc" => {
+            let (n, f, k0) = match (definition.parameter("lat_1"), definition.parameter("lat_2")) {
+                (Some(lat_1), Some(lat_2)) if (lat_1 - lat_2).abs() > 1e-10 => {
+                    let (phi1, phi2) = (lat_1.to_radians(), lat_2.to_radians());
+                    let n = (ellipsoid.m(phi1).ln() - ellipsoid.m(phi2).ln())
+                        / (ellipsoid.t(phi1).ln() - ellipsoid.t(phi2).ln());
+                    (n, ellipsoid.m(phi1) / (n * ellipsoid.t(phi1).powf(n)), 1.0)
+                },
+                (lat_1, _) => {
+                    // One standard parallel: the latitude of origin unless given separately
+                    let phi1 = lat_1.map(f64::to_radians).unwrap_or(phi0);
+                    let n = phi1.sin();
+                    (n, ellipsoid.m(phi1) / (n * ellipsoid.t(phi1).powf(n)), scale)
+                },
+            };
+            if phi.abs() >= 89.999f64.to_radians() && phi.signum() != n.signum() {
+                return None;
+            }
+            let rho = a * f * k0 * ellipsoid.t(phi).powf(n);
+            let rho0 = a * f * k0 * ellipsoid.t(phi0).powf(n);
+            let theta = n * dlam;
+            (rho * theta.sin(), rho0 - rho * theta.cos())
+        },
+        "aea" => {
+            let (phi1, phi2) = (degrees("lat_1"), degrees("lat_2"));
+            let (m1, m2) = (ellipsoid.m(phi1), ellipsoid.m(phi2));
+            let (q1, q2) = (ellipsoid.q(phi1), ellipsoid.q(phi2));
+            let n = if (phi1 - phi2).abs() > 1e-10 { (m1 * m1 - m2 * m2) / (q2 - q1) } else { phi1.sin() };
+            let c = m1 * m1 + n * q1;
+            let rho = a * (c - n * ellipsoid.q(phi)).max(0.0).sqrt() / n;
+            let rho0 = a * (c - n * ellipsoid.q(phi0)).max(0.0).sqrt() / n;
+            let theta = n * dlam;
+            (rho * theta.sin(), rho0 - rho * theta.cos())
+        },
+        "laea" => lambert_azimuthal(&ellipsoid, phi, dlam, phi0)?,
+        _ => return None,
+    };
+
+    let to_units = definition.meters_per_unit();
+    let false_easting = definition.parameter("x_0").unwrap_or(0.0);
+    let false_northing = definition.parameter("y_0").unwrap_or(0.0);
+    Some(Point::new(x / to_units + false_easting, y / to_units + false_northing))
+}
+
+/// Unproject a coordinate to longitude and latitude
+///
+/// Newton's method runs the forward projection from the projection centre
+/// until it lands on the point, which takes a handful of steps anywhere
+/// the projection is well behaved.
+///
+/// # Arguments
+/// * `definition` - Definition the coordinate is in
+/// * `x` - Easting in the definition's units
+/// * `y` - Northing in the definition's units
+///
+/// # Returns
+/// Longitude and latitude in degrees on the definition's datum, or None
+/// for unsupported methods or when the iteration does not converge
+pub fn inverse(definition: &ProjDefinition, x: f64, y: f64) -> Option<Point> {
+    const STEP: f64 = 1e-6;
+    const TOLERANCE: f64 = 1e-6;
+
+    if definition.is_geographic() {
+        return Some(Point::new(x, y));
+    }
+
+    let mut lon = definition.parameter("lon_0").unwrap_or(0.0);
+    let mut lat = definition.parameter("lat_0").unwrap_or(0.0).clamp(-89.0, 89.0);
+    for _ in 0..50 {
+        let p = forward(definition, lon, lat)?;
+        let (dx, dy) = (x - p.x, y - p.y);
+        if dx.abs() < TOLERANCE && dy.abs() < TOLERANCE {
+            return Some(Point::new(lon, lat));
+        }
+
+        // Jacobian of the forward projection by finite differences
+        let east = forward(definition, lon + STEP, lat)?;
+        let north = forward(definition, lon, lat + STEP)?;
+        let (a, b) = ((east.x - p.x) / STEP, (north.x - p.x) / STEP);
+        let (c, d) = ((east.y - p.y) / STEP, (north.y - p.y) / STEP);
+        let determinant = a * d - b * c;
+        if determinant.abs() < 1e-12 {
+            return None;
+        }
+
+        lon += (d * dx - b * dy) / determinant;
+        lat = (lat + (a * dy - c * dx) / determinant).clamp(-90.0 + STEP, 90.0 - STEP);
+    }
+    None
+}
+
+/// Ellipsoid constants shared by the projection formulas
+struct Ellipsoid {
+    a: f64,
+    e: f64,
+    e2: f64,
+}
+
+impl Ellipsoid {
+    fn new(a: f64, b: f64) -> Self {
+        let e2 = (1.0 - (b * b) / (a * a)).max(0.0);
+        Ellipsoid { a, e: e2.sqrt(), e2 }
+    }
+
+    /// Snyder's m: cos φ / sqrt(1 - e² sin² φ)
+    fn m(&self, phi: f64) -> f64 {
+        phi.cos() / (1.0 - self.e2 * phi.sin().powi(2)).sqrt()
+    }
+
+    /// Snyder's t, the conformal latitude function
+    fn t(&self, phi: f64) -> f64 {
+        let es = self.e * phi.sin();
+        (FRAC_PI_4 - phi / 2.0).tan() / ((1.0 - es) / (1.0 + es)).powf(self.e / 2.0)
+    }
+
+    /// Snyder's q, the authalic latitude function
+    fn q(&self, phi: f64) -> f64 {
+        let sin = phi.sin();
+        if self.e < 1e-12 {
+            return 2.0 * sin;
+        }
+        let es = self.e * sin;
+        (1.0 - self.e2) * (sin / (1.0 - es * es) - (1.0 / (2.0 * self.e)) * ((1.0 - es) / (1.0 + es)).ln())
+    }
+
+    /// Meridional arc length from the equator
+    fn meridian_arc(&self, phi: f64) -> f64 {
+        let (e2, e4, e6) = (self.e2, self.e2 * self.e2, self.e2.powi(3));
+        self.a * ((1.0 - e2 / 4.0 - 3.0 * e4 / 64.0 - 5.0 * e6 / 256.0) * phi
+            - (3.0 * e2 / 8.0 + 3.0 * e4 / 32.0 + 45.0 * e6 / 1024.0) * (2.0 * phi).sin()
+            + (15.0 * e4 / 256.0 + 45.0 * e6 / 1024.0) * (4.0 * phi).sin()
+            - (35.0 * e6 / 3072.0) * (6.0 * phi).sin())
+    }
+}
+
+/// Transverse Mercator series, in meters without false origin
+fn transverse_mercator(ellipsoid: &Ellipsoid, phi: f64, dlam: f64, phi0: f64, k0: f64) -> (f64, f64) {
+    let ep2 = ellipsoid.e2 / (1.0 - ellipsoid.e2);
+    let (sin, cos, tan) = (phi.sin(), phi.cos(), phi.tan());
+    let n = ellipsoid.a / (1.0 - ellipsoid.e2 * sin * sin).sqrt();
+    let t = tan * tan;
+    let c = ep2 * cos * cos;
+    let a = dlam * cos;
+
+    let x = k0 * n * (a + (1.0 - t + c) * a.powi(3) / 6.0
+        + (5.0 - 18.0 * t + t * t + 72.0 * c - 58.0 * ep2) * a.powi(5) / 120.0);
+    let y = k0 * (ellipsoid.meridian_arc(phi) - ellipsoid.meridian_arc(phi0) + n * tan * (a * a / 2.0
+        + (5.0 - t + 9.0 * c + 4.0 * c * c) * a.powi(4) / 24.0
+        + (61.0 - 58.0 * t + t * t + 600.0 * c - 330.0 * ep2) * a.powi(6) / 720.0));
+    (x, y)
+}
+
+/// Lambert Azimuthal Equal Area, in meters without false origin
+fn lambert_azimuthal(ellipsoid: &Ellipsoid, phi: f64, dlam: f64, phi0: f64) -> Option<(f64, f64)> {
+    let a = ellipsoid.a;
+    let qp = ellipsoid.q(std::f64::consts::FRAC_PI_2);
+    let q = ellipsoid.q(phi);
+
+    // Polar aspects have their own, simpler form
+    if (phi0.abs() - std::f64::consts::FRAC_PI_2).abs() < 1e-10 {
+        let north = phi0 > 0.0;
+        let q_offset = if north { qp - q } else { qp + q };
+        let rho = a * q_offset.max(0.0).sqrt();
+        let y = if north { -rho * dlam.cos() } else { rho * dlam.cos() };
+        return Some((rho * dlam.sin(), y));
+    }
+
+    let beta = (q / qp).clamp(-1.0, 1.0).asin();
+    let beta1 = (ellipsoid.q(phi0) / qp).clamp(-1.0, 1.0).asin();
+    let rq = a * (qp / 2.0).sqrt();
+    let d = a * ellipsoid.m(phi0) / (rq * beta1.cos());
+    let denominator = 1.0 + beta1.sin() * beta.sin() + beta1.cos() * beta.cos() * dlam.cos();
+    if denominator <= 1e-12 {
+        // Antipode of the centre
+        return None;
+    }
+    let b = rq * (2.0 / denominator).sqrt();
+    Some((
+        b * d * beta.cos() * dlam.sin(),
+        (b / d) * (beta1.cos() * beta.sin() - beta1.sin() * beta.cos() * dlam.cos()),
+    ))
+}
+
+/// Wrap a longitude difference into [-π, π]
+fn normalize_longitude(lambda: f64) -> f64 {
+    use std::f64::consts::PI;
+    let wrapped = (lambda + PI).rem_euclid(2.0 * PI) - PI;
+    if wrapped == -PI && lambda > 0.0 { PI } else { wrapped }
+}
@@ -3,6 +3,9 @@
 use super::point::Point;
 use super::bbox::BoundingBox;
 use super::crs::CoordinateSystem;
+use super::datum::{self, NtV2Grid};
+use super::projection;
+use crate::tiff::proj_definition::ProjDefinition;
 use crate::tiff::errors::{TiffError, TiffResult};
 use std::f64::consts::PI;
 
@@ -48,6 +51,14 @@ impl CoordinateTransformer {
             (CoordinateSystem::WebMercator, CoordinateSystem::WGS84) => {
                 Ok(self.web_mercator_to_wgs84(point.x, point.y))
             },
+            (CoordinateSystem::UTM(..), CoordinateSystem::WGS84) => {
+                // UTM zones are WGS84 Transverse Mercator, so no datum shift is needed
+                ProjDefinition::from_epsg(from_crs.epsg_code())
+                    .and_then(|definition| projection::inverse(&definition, point.x, point.y))
+                    .ok_or_else(|| TiffError::GenericError(format!(
+                        "Cannot convert ({}, {}) from {} to longitude/latitude",
+                        point.x, point.y, from_crs.description())))
+            },
             _ => Err(TiffError::GenericError(format!(
                 "Unsupported coordinate transformation from {} to {}",
                 from_crs.description(), to_crs.description()
@@ -100,4 +111,70 @@ impl CoordinateTransformer {
             }
         }
     }
-}
\ No newline at end of file
+
+    /// Project a geographic coordinate into another system, across datums
+    ///
+    /// The point is moved from the source datum to WGS84 and then onto the
+    /// target datum (with the shift grid when one is given, otherwise with
+    /// the datum's Helmert parameters) before being projected.
+    ///
+    /// # Arguments
+    /// * `lon` - Longitude in degrees on the source datum
+    /// * `lat` - Latitude in degrees on the source datum
+    /// * `source` - Geographic definition of the input coordinates
+    /// * `target` - Definition to project into
+    /// * `grid` - Optional NTv2 grid from the target datum to WGS84
+    ///
+    /// # Returns
+    /// The point in target coordinates, or None if it cannot be projected
+    pub fn geographic_to_definition(&self, lon: f64, lat: f64, source: &ProjDefinition,
+                                    target: &ProjDefinition, grid: Option<&NtV2Grid>) -> Option<Point> {
+        let (lon, lat) = if source.datum == target.datum {
+            (lon, lat)
+        } else {
+            let (wgs_lon, wgs_lat) = datum::datum_to_wgs84(lon, lat, &source.datum, None);
+            datum::wgs84_to_datum(wgs_lon, wgs_lat, &target.datum, grid)
+        };
+        projection::forward(target, lon, lat)
+    }
+
+    /// Transform a geographic bounding box into another system, across datums
+    ///
+    /// Edges are sampled so the envelope stays correct when the projection
+    /// bends them.
+    ///
+    /// # Arguments
+    /// * `bbox` - Bounding box in degrees
+    /// * `source` - Geographic definition of the bounding box
+    /// * `target` - Definition to transform into
+    /// * `grid` - Optional NTv2 grid from the target datum to WGS84
+    ///
+    /// # Returns
+    /// The envelope in target coordinates, or None if the target cannot be
+    /// projected
+    pub fn transform_bbox_to_definition(&self, bbox: &BoundingBox, source: &ProjDefinition,
+                                        target: &ProjDefinition, grid: Option<&NtV2Grid>) -> Option<BoundingBox> {
+        const SAMPLES: usize = 20;
+
+        if !source.is_geographic() || !projection::is_supported(target) {
+            return None;
+        }
+
+        let mut envelope: Option<BoundingBox> = None;
+        for i in 0..=SAMPLES {
+            let f = i as f64 / SAMPLES as f64;
+            let lon = bbox.min_x + f * (bbox.max_x - bbox.min_x);
+            let lat = bbox.min_y + f * (bbox.max_y - bbox.min_y);
+            let edge_points = [(lon, bbox.min_y), (lon, bbox.max_y), (bbox.min_x, lat), (bbox.max_x, lat)];
+
+            for (x, y) in edge_points {
+                let Some(p) = self.geographic_to_definition(x, y, source, target, grid) else { continue };
+                envelope = Some(match envelope {
+                    Some(e) => BoundingBox::new(e.min_x.min(p.x), e.min_y.min(p.y), e.max_x.max(p.x), e.max_y.max(p.y)),
+                    None => BoundingBox::new(p.x, p.y, p.x, p.y),
+                });
+            }
+        }
+        envelope
+    }
+}
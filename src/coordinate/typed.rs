@@ -0,0 +1,203 @@
+//! Typed coordinate reference systems and positions
+//!
+//! Bare EPSG numbers and "x,y" strings leave it to the caller to know
+//! which axis comes first and whether values are degrees or meters; a
+//! swapped latitude and longitude only shows up as an empty or misplaced
+//! extraction. These types carry that meaning instead: a `LonLat` is
+//! always WGS84 degrees in longitude, latitude order and is range checked
+//! when built, a `ProjectedXY` is easting and northing in the units of a
+//! `Crs` given alongside it.
+
+use std::fmt;
+use std::str::FromStr;
+
+use crate::tiff::errors::{TiffError, TiffResult};
+
+use super::crs::{CoordinateSystem, CoordinateSystemFactory};
+
+/// A coordinate reference system identified by its EPSG code
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub struct Crs(u32);
+
+impl Crs {
+    /// WGS 84 longitude and latitude in degrees
+    pub const WGS84: Crs = Crs(4326);
+    /// Web Mercator in meters
+    pub const WEB_MERCATOR: Crs = Crs(3857);
+
+    /// A CRS from its EPSG code
+    ///
+    /// # Arguments
+    /// * `code` - EPSG code, e.g. 4326
+    ///
+    /// # Returns
+    /// The CRS, or an error for code 0
+    pub fn from_epsg(code: u32) -> TiffResult<Self> {
+        if code == 0 {
+            return Err(TiffError::GenericError("EPSG code 0 is not a coordinate system".to_string()));
+        }
+        Ok(Crs(code))
+    }
+
+    /// A CRS from "EPSG:4326" or a bare "4326"
+    pub fn parse(input: &str) -> TiffResult<Self> {
+        Self::from_epsg(CoordinateSystemFactory::from_string(input)?.epsg_code())
+    }
+
+    /// The WGS84 UTM zone for a zone number and hemisphere
+    ///
+    /// # Arguments
+    /// * `zone` - Zone number from 1 to 60
+    /// * `north` - Whether the zone is on the northern hemisphere
+    pub fn utm(zone: u8, north: bool) -> TiffResult<Self> {
+        if !(1..=60).contains(&zone) {
+            return Err(TiffError::GenericError(format!("UTM zone {} is not between 1 and 60", zone)));
+        }
+        Ok(CoordinateSystem::UTM(zone, north).into())
+    }
+
+    /// EPSG code of the CRS
+    pub fn epsg(self) -> u32 {
+        self.0
+    }
+
+    /// Whether coordinates are latitude and longitude in degrees
+    ///
+    /// EPSG registers geographic 2D systems in the 4000 to 4999 range.
+    pub fn is_geographic(self) -> bool {
+        (4000..5000).contains(&self.0)
+    }
+}
+
+impl From<CoordinateSystem> for Crs {
+    fn from(system: CoordinateSystem) -> Self {
+        Crs(system.epsg_code())
+    }
+}
+
+impl FromStr for Crs {
+    type Err = TiffError;
+
+    fn from_str(input: &str) -> TiffResult<Self> {
+        Self::parse(input)
+    }
+}
+
+impl fmt::Display for Crs {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "EPSG:{}", self.0)
+    }
+}
+
+/// A WGS84 position in degrees, longitude first
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct LonLat {
+    /// Longitude in degrees, -180 to 180
+    lon: f64,
+    /// Latitude in degrees, -90 to 90
+    lat: f64,
+}
+
+impl LonLat {
+    /// A position from its longitude and latitude
+    ///
+    /// # Arguments
+    /// * `lon` - Longitude in degrees
+    /// * `lat` - Latitude in degrees
+    ///
+    /// # Returns
+    /// The position, or an error if either value is out of range
+    pub fn new(lon: f64, lat: f64) -> TiffResult<Self> {
+        if !(-180.0..=180.0).contains(&lon) {
+            return Err(TiffError::GenericError(format!("Longitude {} is not between -180 and 180", lon)));
+        }
+        if !(-90.0..=90.0).contains(&lat) {
+            return Err(TiffError::GenericError(format!(
+                "Latitude {} is not between -90 and 90; were longitude and latitude swapped?", lat)));
+        }
+        Ok(LonLat { lon, lat })
+    }
+
+    /// A position given latitude first, as GPS devices and maps show it
+    pub fn from_lat_lon(lat: f64, lon: f64) -> TiffResult<Self> {
+        Self::new(lon, lat)
+    }
+
+    /// Parse "lon,lat"
+    pub fn parse(input: &str) -> TiffResult<Self> {
+        let (lon, lat) = parse_pair(input, "lon,lat")?;
+        Self::new(lon, lat)
+    }
+
+    /// Longitude in degrees
+    pub fn lon(self) -> f64 {
+        self.lon
+    }
+
+    /// Latitude in degrees
+    pub fn lat(self) -> f64 {
+        self.lat
+    }
+}
+
+impl FromStr for LonLat {
+    type Err = TiffError;
+
+    fn from_str(input: &str) -> TiffResult<Self> {
+        Self::parse(input)
+    }
+}
+
+impl fmt::Display for LonLat {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "{},{}", self.lon, self.lat)
+    }
+}
+
+/// Easting and northing in the linear unit of a projected CRS
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct ProjectedXY {
+    /// Easting
+    pub x: f64,
+    /// Northing
+    pub y: f64,
+}
+
+impl ProjectedXY {
+    /// A position from its easting and northing
+    pub fn new(x: f64, y: f64) -> Self {
+        ProjectedXY { x, y }
+    }
+
+    /// Parse "x,y"
+    pub fn parse(input: &str) -> TiffResult<Self> {
+        let (x, y) = parse_pair(input, "x,y")?;
+        Ok(ProjectedXY { x, y })
+    }
+}
+
+impl FromStr for ProjectedXY {
+    type Err = TiffError;
+
+    fn from_str(input: &str) -> TiffResult<Self> {
+        Self::parse(input)
+    }
+}
+
+impl fmt::Display for ProjectedXY {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "{},{}", self.x, self.y)
+    }
+}
+
+/// Split two finite comma-separated numbers
+fn parse_pair(input: &str, format: &str) -> TiffResult<(f64, f64)> {
+    let invalid = || TiffError::GenericError(format!("'{}' is not a coordinate in the format '{}'", input, format));
+    let (first, second) = input.split_once(',').ok_or_else(invalid)?;
+    let first: f64 = first.trim().parse().map_err(|_| invalid())?;
+    let second: f64 = second.trim().parse().map_err(|_| invalid())?;
+    if !first.is_finite() || !second.is_finite() {
+        return Err(invalid());
+    }
+    Ok((first, second))
+}
@@ -0,0 +1,237 @@
+//! Open dataset handle
+//!
+//! `RasterKit::open` parses a file's header once and hands out a `Dataset`
+//! that keeps it. Structure queries, pixel sampling and statistics then
+//! read through the parsed IFDs and GeoKeys instead of loading the file
+//! again on each call; extraction goes through the extractors, with
+//! bounding boxes resolved against the parsed header.
+
+use std::sync::Arc;
+
+use log::info;
+
+use crate::api::RasterKit;
+use crate::coordinate::Crs;
+use crate::extractor::{ArrayData, ExtractOptions, Region};
+use crate::tiff::constants::tags;
+use crate::tiff::dataset_cache::ParsedDataset;
+use crate::tiff::errors::{TiffError, TiffResult};
+use crate::tiff::ifd::IFD;
+use crate::tiff::proj_definition::USER_DEFINED;
+use crate::tiff::types::TIFF;
+use crate::utils::acquisition_utils::{self, AcquisitionMetadata};
+use crate::utils::{sample_utils, statistics_utils, tiff_code_translators, tiff_extraction_utils};
+
+/// Statistics of one band of a dataset
+#[derive(Debug, Clone, PartialEq)]
+pub struct BandSummary {
+    /// One-based band number
+    pub band: usize,
+    /// Smallest valid value
+    pub min: f64,
+    /// Largest valid value
+    pub max: f64,
+    /// Mean of the valid values
+    pub mean: f64,
+    /// Standard deviation of the valid values
+    pub std: f64,
+    /// Share of the pixels holding valid values, in percent
+    pub valid_percent: f64,
+    /// Whether only part of the blocks were sampled
+    pub approximate: bool,
+}
+
+/// A TIFF opened once for many operations
+pub struct Dataset<'a> {
+    /// Instance the dataset was opened with
+    kit: &'a RasterKit,
+    /// Parsed header, shared with the instance's cache
+    parsed: Arc<ParsedDataset>,
+}
+
+impl<'a> Dataset<'a> {
+    /// Wrap a parsed header
+    pub(crate) fn new(kit: &'a RasterKit, parsed: Arc<ParsedDataset>) -> Self {
+        Dataset { kit, parsed }
+    }
+
+    /// Path the dataset was opened from
+    pub fn path(&self) -> &str {
+        &self.parsed.path
+    }
+
+    /// First IFD of the file
+    fn main_ifd(&self) -> TiffResult<&IFD> {
+        self.parsed.tiff.main_ifd()
+            .ok_or_else(|| TiffError::GenericError(format!("No IFDs found in {}", self.parsed.path)))
+    }
+
+    /// Width and height of the image in pixels
+    pub fn dimensions(&self) -> TiffResult<(u32, u32)> {
+        self.main_ifd()?.get_dimensions()
+            .map(|(width, height)| (width as u32, height as u32))
+            .ok_or_else(|| TiffError::GenericError(format!("{} lacks image dimensions", self.parsed.path)))
+    }
+
+    /// Number of bands of the image
+    pub fn band_count(&self) -> TiffResult<usize> {
+        Ok(self.main_ifd()?.get_samples_per_pixel() as usize)
+    }
+
+    /// Coordinate system of the file, if it declares an EPSG code
+    pub fn crs(&self) -> Option<Crs> {
+        let info = self.parsed.geo_info.as_ref()?;
+        [info.epsg_code, info.geographic_cs_code].into_iter()
+            .filter(|&code| code != USER_DEFINED)
+            .find_map(|code| Crs::from_epsg(code).ok())
+    }
+
+    /// NoData value the file declares, if any
+    pub fn nodata(&self) -> Option<f64> {
+        let ifd = self.main_ifd().ok()?;
+        if !ifd.has_tag(tags::GDAL_NODATA) {
+            return None;
+        }
+        let reader = self.parsed.reader(self.kit.logger());
+        tiff_extraction_utils::extract_nodata_value(ifd, &reader).trim().parse().ok()
+    }
+
+    /// When the image was acquired, as recorded in the file
+    ///
+    /// # Returns
+    /// The TIFF DateTime, EXIF DateTimeOriginal and GDAL
+    /// ACQUISITIONDATETIME found, each None if absent or malformed
+    pub fn acquisition(&self) -> TiffResult<AcquisitionMetadata> {
+        let ifd = self.main_ifd()?;
+        let reader = self.parsed.reader(self.kit.logger());
+        Ok(acquisition_utils::read_acquisition(&reader, ifd))
+    }
+
+    /// Summarize the structure of the file
+    ///
+    /// # Returns
+    /// The same summary as `RasterKit::analyze`
+    pub fn analyze(&self) -> String {
+        structure_summary(&self.parsed.tiff)
+    }
+
+    /// Extract from the dataset to a file
+    ///
+    /// # Arguments
+    /// * `output_path` - Path where to save the extracted image
+    /// * `options` - What to extract and how
+    ///
+    /// # Returns
+    /// Result indicating success or an error
+    pub fn extract(&self, output_path: &str, options: &ExtractOptions) -> TiffResult<()> {
+        self.kit.extract(self.path(), output_path, options)
+    }
+
+    /// Extract a region as an array
+    ///
+    /// # Arguments
+    /// * `region` - Optional pixel region to extract (x, y, width, height)
+    ///
+    /// # Returns
+    /// The array data of the region
+    pub fn extract_array(&self, region: Option<(u32, u32, u32, u32)>) -> TiffResult<ArrayData> {
+        self.kit.extract_array_data(self.path(), region)
+    }
+
+    /// Read the values of every band at one pixel
+    ///
+    /// Only the tile or strip holding the pixel is decoded.
+    ///
+    /// # Arguments
+    /// * `column` - Column of the pixel
+    /// * `row` - Row of the pixel
+    ///
+    /// # Returns
+    /// One value per band
+    pub fn sample(&self, column: u32, row: u32) -> TiffResult<Vec<f64>> {
+        let (width, height) = self.dimensions()?;
+        if column >= width || row >= height {
+            return Err(TiffError::GenericError(format!(
+                "Pixel ({}, {}) is outside the {}x{} image", column, row, width, height)));
+        }
+
+        let pixel = Region::new(column, row, 1, 1);
+        let reader = self.parsed.reader(self.kit.logger());
+        let mut values = Vec::new();
+        sample_utils::read_ifd_blocks(
+            &reader, self.main_ifd()?, self.path(),
+            |layout, index| {
+                let position = index % layout.blocks_per_band();
+                let x = (position % layout.blocks_across()) as u32 * layout.block_width;
+                let y = (position / layout.blocks_across()) as u32 * layout.block_height;
+                (x..x + layout.block_width).contains(&pixel.x) && (y..y + layout.block_height).contains(&pixel.y)
+            },
+            |layout, block| {
+                values.resize(layout.bands, f64::NAN);
+                for band in block.bands.clone() {
+                    if let Some(raw) = block.sample(pixel.x - block.x, pixel.y - block.y, band) {
+                        values[band] = layout.value(raw);
+                    }
+                }
+            })?;
+
+        info!("Sampled {} band(s) of {} at ({}, {})", values.len(), self.path(), column, row);
+        Ok(values)
+    }
+
+    /// Compute the statistics of every band
+    ///
+    /// Pixels holding the file's NoData value are left out. Large images
+    /// are sampled, as with `--stats`.
+    ///
+    /// # Returns
+    /// One summary per band holding valid values
+    pub fn statistics(&self) -> TiffResult<Vec<BandSummary>> {
+        let reader = self.parsed.reader(self.kit.logger());
+        let reports = statistics_utils::compute_ifd_statistics(&reader, self.main_ifd()?, self.path(), self.nodata())?;
+
+        Ok(reports.into_iter().map(|report| BandSummary {
+            band: report.band,
+            min: report.statistics.min,
+            max: report.statistics.max,
+            mean: report.statistics.mean,
+            std: report.statistics.std,
+            valid_percent: report.valid_percent,
+            approximate: report.approximate,
+        }).collect())
+    }
+}
+
+/// Summary of the IFDs of a parsed file
+///
+/// # Arguments
+/// * `tiff` - The parsed file
+///
+/// # Returns
+/// Format, IFD count and the size, bands and compression of each IFD
+pub(crate) fn structure_summary(tiff: &TIFF) -> String {
+    let mut result = "TIFF Analysis Results:\n".to_string();
+    result.push_str(&format!("  Format: {}\n", if tiff.is_big_tiff { "BigTIFF" } else { "TIFF" }));
+    result.push_str(&format!("  Number of IFDs: {}\n", tiff.ifd_count()));
+
+    // Add details for each IFD
+    for (i, ifd) in tiff.ifds.iter().enumerate() {
+        result.push_str(&format!("\nIFD #{} (offset: {})\n", i, ifd.offset));
+        result.push_str(&format!("  Number of entries: {}\n", ifd.entries.len()));
+
+        if let Some((width, height)) = ifd.get_dimensions() {
+            result.push_str(&format!("  Dimensions: {}x{}\n", width, height));
+        }
+
+        result.push_str(&format!("  Samples per pixel: {}\n", ifd.get_samples_per_pixel()));
+
+        // Add compression info
+        if let Some(entry) = ifd.get_entry(tags::COMPRESSION) {
+            result.push_str(&format!("  Compression: {} ({})\n",
+                                     entry.value_offset,
+                                     tiff_code_translators::compression_code_to_name(entry.value_offset)));
+        }
+    }
+
+    result
+}
@@ -0,0 +1,236 @@
+//! Typed samples of extracted arrays
+//!
+//! An array keeps the sample type the raster stores, as given by its
+//! BitsPerSample and SampleFormat tags, so a UInt16 band or a Float32
+//! elevation model reaches CSV, JSON or NumPy output with its real values
+//! instead of being squeezed into bytes.
+
+use std::borrow::Cow;
+use std::fmt::Display;
+
+use crate::utils::image_extraction_utils;
+use crate::utils::tensor_utils::TensorDType;
+
+use super::buffer_decode::DecodedWindow;
+use super::region::Padding;
+
+/// Row-major samples of an array, in their native type
+#[derive(Debug, Clone, PartialEq)]
+pub enum ArraySamples {
+    /// Unsigned 8-bit samples
+    U8(Vec<u8>),
+    /// Unsigned 16-bit samples
+    U16(Vec<u16>),
+    /// Signed 16-bit samples, also holding signed bytes
+    I16(Vec<i16>),
+    /// Unsigned 32-bit samples
+    U32(Vec<u32>),
+    /// Signed 32-bit samples
+    I32(Vec<i32>),
+    /// 32-bit floats
+    F32(Vec<f32>),
+    /// 64-bit floats, also holding 64-bit integers
+    F64(Vec<f64>),
+}
+
+/// A sample type an array can hold
+trait Sample: Copy + Display {
+    /// The sample as a float
+    fn to_f64(self) -> f64;
+    /// A float as the sample type, rounded and clamped for integers
+    fn from_f64(value: f64) -> Self;
+    /// Append the little-endian bytes of the sample
+    fn put_le(self, bytes: &mut Vec<u8>);
+}
+
+macro_rules! impl_sample {
+    ($($type:ty => $convert:expr),*) => {$(
+        impl Sample for $type {
+            fn to_f64(self) -> f64 {
+                self as f64
+            }
+            fn from_f64(value: f64) -> Self {
+                // Float to integer casts saturate, and map NaN to 0
+                $convert(value) as $type
+            }
+            fn put_le(self, bytes: &mut Vec<u8>) {
+                bytes.extend_from_slice(&self.to_le_bytes());
+            }
+        }
+    )*};
+}
+
+impl_sample!(u8 => f64::round, u16 => f64::round, i16 => f64::round, u32 => f64::round,
+             i32 => f64::round, f32 => std::convert::identity, f64 => std::convert::identity);
+
+/// Run an expression on the samples, whatever their type
+macro_rules! each {
+    ($samples:expr, $values:ident => $body:expr) => {
+        match $samples {
+            ArraySamples::U8($values) => $body,
+            ArraySamples::U16($values) => $body,
+            ArraySamples::I16($values) => $body,
+            ArraySamples::U32($values) => $body,
+            ArraySamples::I32($values) => $body,
+            ArraySamples::F32($values) => $body,
+            ArraySamples::F64($values) => $body,
+        }
+    };
+}
+
+/// Build new samples of the same type from the samples
+macro_rules! map_each {
+    ($samples:expr, $values:ident => $body:expr) => {
+        match $samples {
+            ArraySamples::U8($values) => ArraySamples::U8($body),
+            ArraySamples::U16($values) => ArraySamples::U16($body),
+            ArraySamples::I16($values) => ArraySamples::I16($body),
+            ArraySamples::U32($values) => ArraySamples::U32($body),
+            ArraySamples::I32($values) => ArraySamples::I32($body),
+            ArraySamples::F32($values) => ArraySamples::F32($body),
+            ArraySamples::F64($values) => ArraySamples::F64($body),
+        }
+    };
+}
+
+/// Split native-endian bytes into samples of N bytes
+fn from_ne<T, const N: usize>(bytes: &[u8], convert: fn([u8; N]) -> T) -> Vec<T> {
+    bytes.chunks_exact(N).map(|chunk| convert(chunk.try_into().unwrap_or([0; N]))).collect()
+}
+
+impl ArraySamples {
+    /// Interpret decoded samples by their stored type
+    ///
+    /// Signed bytes widen to 16 bits, and 64-bit integers become floats.
+    ///
+    /// # Arguments
+    /// * `bytes` - Native-endian samples as decoded
+    /// * `shape` - Sample width and format of the window
+    ///
+    /// # Returns
+    /// The typed samples
+    pub fn from_native_bytes(bytes: Vec<u8>, shape: &DecodedWindow) -> Self {
+        match (shape.bytes_per_sample, shape.floating_point, shape.signed) {
+            (1, _, false) => ArraySamples::U8(bytes),
+            (1, _, true) => ArraySamples::I16(bytes.iter().map(|&byte| byte as i8 as i16).collect()),
+            (2, _, true) => ArraySamples::I16(from_ne(&bytes, i16::from_ne_bytes)),
+            (2, _, false) => ArraySamples::U16(from_ne(&bytes, u16::from_ne_bytes)),
+            (4, true, _) => ArraySamples::F32(from_ne(&bytes, f32::from_ne_bytes)),
+            (4, false, true) => ArraySamples::I32(from_ne(&bytes, i32::from_ne_bytes)),
+            (4, false, false) => ArraySamples::U32(from_ne(&bytes, u32::from_ne_bytes)),
+            (_, true, _) => ArraySamples::F64(from_ne(&bytes, f64::from_ne_bytes)),
+            (_, false, true) => ArraySamples::F64(from_ne(&bytes, |raw| i64::from_ne_bytes(raw) as f64)),
+            (_, false, false) => ArraySamples::F64(from_ne(&bytes, |raw| u64::from_ne_bytes(raw) as f64)),
+        }
+    }
+
+    /// Convert float values to samples of a type
+    ///
+    /// # Arguments
+    /// * `values` - The values, row by row
+    /// * `dtype` - Type to store them as
+    ///
+    /// # Returns
+    /// The samples, rounded and clamped for integer types
+    pub fn from_values(values: &[f64], dtype: TensorDType) -> Self {
+        fn convert<T: Sample>(values: &[f64]) -> Vec<T> {
+            values.iter().map(|&value| T::from_f64(value)).collect()
+        }
+        match dtype {
+            TensorDType::U8 => ArraySamples::U8(convert(values)),
+            TensorDType::U16 => ArraySamples::U16(convert(values)),
+            TensorDType::I16 => ArraySamples::I16(convert(values)),
+            TensorDType::U32 => ArraySamples::U32(convert(values)),
+            TensorDType::I32 => ArraySamples::I32(convert(values)),
+            TensorDType::F32 => ArraySamples::F32(convert(values)),
+            TensorDType::F64 => ArraySamples::F64(values.to_vec()),
+        }
+    }
+
+    /// Number of samples
+    pub fn len(&self) -> usize {
+        each!(self, values => values.len())
+    }
+
+    /// Whether there are no samples
+    pub fn is_empty(&self) -> bool {
+        self.len() == 0
+    }
+
+    /// Element type of the samples
+    pub fn dtype(&self) -> TensorDType {
+        match self {
+            ArraySamples::U8(_) => TensorDType::U8,
+            ArraySamples::U16(_) => TensorDType::U16,
+            ArraySamples::I16(_) => TensorDType::I16,
+            ArraySamples::U32(_) => TensorDType::U32,
+            ArraySamples::I32(_) => TensorDType::I32,
+            ArraySamples::F32(_) => TensorDType::F32,
+            ArraySamples::F64(_) => TensorDType::F64,
+        }
+    }
+
+    /// Whether the samples are floats
+    pub fn is_float(&self) -> bool {
+        matches!(self, ArraySamples::F32(_) | ArraySamples::F64(_))
+    }
+
+    /// Get a sample as a float
+    ///
+    /// # Arguments
+    /// * `index` - Position of the sample
+    ///
+    /// # Returns
+    /// The value, or None if the index is out of range
+    pub fn value(&self, index: usize) -> Option<f64> {
+        each!(self, values => values.get(index).map(|&value| value.to_f64()))
+    }
+
+    /// Format a sample as text, the way it is stored
+    ///
+    /// # Arguments
+    /// * `index` - Position of the sample
+    ///
+    /// # Returns
+    /// The formatted value, or None if the index is out of range
+    pub fn format_value(&self, index: usize) -> Option<String> {
+        each!(self, values => values.get(index).map(|value| value.to_string()))
+    }
+
+    /// All samples as floats
+    pub fn to_f64(&self) -> Vec<f64> {
+        each!(self, values => values.iter().map(|&value| value.to_f64()).collect())
+    }
+
+    /// All samples as bytes, rounded and clamped to 0-255
+    ///
+    /// # Returns
+    /// The samples themselves for 8-bit arrays, converted bytes otherwise
+    pub fn to_u8(&self) -> Cow<'_, [u8]> {
+        match self {
+            ArraySamples::U8(values) => Cow::Borrowed(values),
+            other => Cow::Owned(each!(other, values => values.iter().map(|&value| u8::from_f64(value.to_f64())).collect())),
+        }
+    }
+
+    /// The samples as little-endian bytes, as NumPy and ENVI store them
+    pub fn to_le_bytes(&self) -> Vec<u8> {
+        let mut bytes = Vec::with_capacity(self.len() * self.dtype().size());
+        each!(self, values => values.iter().for_each(|value| value.put_le(&mut bytes)));
+        bytes
+    }
+
+    /// Place the samples of a window in a larger one
+    ///
+    /// # Arguments
+    /// * `width` - Width of the window the samples cover
+    /// * `height` - Height of the window the samples cover
+    /// * `padding` - Output window and where the samples go in it
+    /// * `fill` - Value around the samples, converted to their type
+    ///
+    /// # Returns
+    /// The samples of the padded window
+    pub fn padded(&self, width: u32, height: u32, padding: &Padding, fill: f64) -> Self {
+        map_each!(self, values => image_extraction_utils::pad_band(values, width, height, padding, Sample::from_f64(fill)))
+    }
+}
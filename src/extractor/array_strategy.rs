@@ -10,15 +10,42 @@ use std::io::{BufWriter, Write, BufReader};
 use std::path::Path;
 use image::{DynamicImage, GenericImageView};
 
+use crate::io::storage;
 use crate::tiff::TiffReader;
 use crate::tiff::errors::{TiffError, TiffResult};
 use crate::tiff::constants::tags;
+use crate::tiff::geo_key_parser::GeoKeyParser;
 use crate::utils::logger::Logger;
+use crate::utils::{atomic_utils, npy_utils};
+use crate::utils::tensor_utils::{self, NamedTensor, TensorDType};
+use crate::utils::image_extraction_utils;
+use crate::utils::memory_utils;
+use crate::utils::tiff_extraction_utils;
+use crate::utils::gpkg_utils;
+use crate::utils::sqlite_utils::{SqlValue, SqliteWriter};
+use crate::utils::postgis_utils::{self, PostgisOptions};
+use crate::utils::parquet_utils::{self, ParquetColumn, ParquetPartitioning, ParquetValues};
+use crate::utils::envi_utils;
+use crate::coordinate::{datum, projection, Affine, Point};
+use crate::tiff::proj_definition::ProjDefinition;
 
-use super::region::Region;
+use super::array_samples::ArraySamples;
+use super::block_cache::BlockCache;
+use super::buffer_decode::{self, BufferLayout};
+use super::region::{Padding, Region};
 use super::tile_reader::TileReader;
 use super::strip_reader::StripReader;
 use super::extractor_strategy::ExtractorStrategy;
+use super::output_format::OutputFormat;
+
+/// Georeferencing information of an extracted array
+#[derive(Debug, Clone, PartialEq)]
+pub struct ArrayGeoreference {
+    /// Geotransform anchored at the array's top-left corner
+    pub geotransform: Affine,
+    /// EPSG code of the coordinate system, if known
+    pub epsg: Option<u32>,
+}
 
 /// Represents array data extracted from an image
 ///
@@ -30,8 +57,15 @@ pub struct ArrayData {
     pub width: u32,
     /// Height of the array (rows)
     pub height: u32,
-    /// Raw data values in row-major order
-    pub data: Vec<u8>,
+    /// Samples in row-major order, in the type the source stores them
+    pub data: ArraySamples,
+    /// Every band of a multi-band source in its stored type, for outputs
+    /// with a tensor per band; empty when only `data` was decoded
+    pub bands: Vec<ArraySamples>,
+    /// Georeferencing of the array, if the source was a GeoTIFF
+    pub georeference: Option<ArrayGeoreference>,
+    /// NoData value of the source, if one is declared
+    pub nodata: Option<f64>,
 }
 
 impl ArrayData {
@@ -51,7 +85,10 @@ impl ArrayData {
         ArrayData {
             width,
             height,
-            data,
+            data: ArraySamples::U8(data),
+            bands: Vec::new(),
+            georeference: None,
+            nodata: None,
         }
     }
 
@@ -63,58 +100,164 @@ impl ArrayData {
     ///
     /// # Returns
     /// The value at the specified position, or None if out of bounds
-    pub fn get(&self, x: u32, y: u32) -> Option<u8> {
+    pub fn get(&self, x: u32, y: u32) -> Option<f64> {
         if x >= self.width || y >= self.height {
             return None;
         }
 
         let idx = (y * self.width + x) as usize;
-        self.data.get(idx).copied()
+        self.data.value(idx)
+    }
+
+    /// Get a specific value as text, formatted in its sample type
+    ///
+    /// # Arguments
+    /// * `x` - Column index
+    /// * `y` - Row index
+    ///
+    /// # Returns
+    /// The formatted value, or None if out of bounds
+    fn value_text(&self, x: u32, y: u32) -> Option<String> {
+        if x >= self.width || y >= self.height {
+            return None;
+        }
+
+        self.data.format_value((y * self.width + x) as usize)
+    }
+
+    /// Check whether a value is the NoData value of the array
+    ///
+    /// A NaN NoData value matches NaN samples.
+    fn is_nodata(&self, value: f64) -> bool {
+        self.nodata.is_some_and(|nodata| value == nodata || (value.is_nan() && nodata.is_nan()))
+    }
+
+    /// Get the map coordinates of a cell center
+    ///
+    /// # Arguments
+    /// * `x` - Column index
+    /// * `y` - Row index
+    ///
+    /// # Returns
+    /// The (x, y) map coordinates, or None if the array is not georeferenced
+    pub fn cell_center(&self, x: u32, y: u32) -> Option<(f64, f64)> {
+        Some(self.georeference.as_ref()?.geotransform.cell_center(x, y))
+    }
+
+    /// Get the WGS84 longitude/latitude of a cell center
+    ///
+    /// # Arguments
+    /// * `x` - Column index
+    /// * `y` - Row index
+    ///
+    /// # Returns
+    /// The point (lon, lat), or None if the array is not georeferenced or
+    /// its CRS cannot be converted to WGS84
+    pub fn cell_center_lon_lat(&self, x: u32, y: u32) -> Option<Point> {
+        let (map_x, map_y) = self.cell_center(x, y)?;
+        let definition = ProjDefinition::from_epsg(self.georeference.as_ref()?.epsg?)?;
+        if !projection::is_supported(&definition) {
+            return None;
+        }
+        let point = projection::inverse(&definition, map_x, map_y)?;
+        let (lon, lat) = datum::datum_to_wgs84(point.x, point.y, &definition.datum, None);
+        Some(Point::new(lon, lat))
     }
 
     /// Save the array to a file in the specified format
     ///
     /// # Arguments
     /// * `path` - Path to save the file
-    /// * `format` - Format to use ("csv", "json", "npy")
+    /// * `format` - Format to use ("csv", "csv-long", "json", "npy", "safetensors", "sqlite", "gpkg", "pgsql", "parquet", "envi")
     ///
     /// # Returns
     /// Result indicating success or an error
     pub fn save_to_file(&self, path: &str, format: &str) -> TiffResult<()> {
-        match format.to_lowercase().as_str() {
-            "csv" => self.save_as_csv(path),
-            "json" => self.save_as_json(path),
-            "npy" => self.save_as_npy(path),
+        match OutputFormat::from_name(format)? {
+            // Outputs are written whole or not at all, through partial files
+            OutputFormat::Csv => atomic_utils::write_atomically(path, |partial| self.save_as_csv(partial, false)),
+            OutputFormat::Json => atomic_utils::write_atomically(path, |partial| self.save_as_json(partial, false)),
+            OutputFormat::CsvLong => atomic_utils::write_atomically(path, |partial| self.save_as_csv_long(partial)),
+            OutputFormat::Sqlite => self.save_as_sqlite(path, false, false).map(|_| ()),
+            OutputFormat::GeoPackage => self.save_as_sqlite(path, true, false).map(|_| ()),
+            OutputFormat::PgSql => self.save_as_postgis(path, &PostgisOptions::default()).map(|_| ()),
+            OutputFormat::Parquet => self.save_as_parquet(path, ParquetPartitioning::None, parquet_utils::DEFAULT_ROW_GROUP_SIZE)
+                .map(|_| ()),
+            OutputFormat::Npy => atomic_utils::write_atomically(path, |partial| self.save_as_npy(partial)),
+            OutputFormat::Safetensors => atomic_utils::write_atomically(path, |partial| self.save_as_safetensors(partial)),
+            OutputFormat::Envi => self.save_as_envi(path),
             _ => Err(TiffError::GenericError(format!("Unsupported array format: {}", format))),
         }
     }
 
+    /// Save the array with map coordinates instead of row/column indices
+    ///
+    /// CSV output labels columns and rows with the X and Y map coordinates
+    /// of the cell centers; JSON output gains `x_coordinates` and
+    /// `y_coordinates` arrays. Other formats are saved unchanged.
+    ///
+    /// # Arguments
+    /// * `path` - Path to save the file
+    /// * `format` - Format to use ("csv", "json", "npy", "safetensors")
+    ///
+    /// # Returns
+    /// Result indicating success or an error
+    pub fn save_with_coordinates(&self, path: &str, format: &str) -> TiffResult<()> {
+        if self.georeference.is_none() {
+            return Err(TiffError::GenericError(
+                "Array is not georeferenced, cannot write coordinates".to_string()));
+        }
+
+        match format.to_lowercase().as_str() {
+            "csv" => atomic_utils::write_atomically(path, |partial| self.save_as_csv(partial, true)),
+            "json" => atomic_utils::write_atomically(path, |partial| self.save_as_json(partial, true)),
+            _ => {
+                warn!("Coordinates are only written for CSV and JSON, saving {} unchanged", format);
+                self.save_to_file(path, format)
+            }
+        }
+    }
+
     /// Save the array as CSV
     ///
     /// # Arguments
     /// * `path` - Path to save the CSV file
+    /// * `coordinates` - Label rows and columns with map coordinates
     ///
     /// # Returns
     /// Result indicating success or an error
-    fn save_as_csv(&self, path: &str) -> TiffResult<()> {
+    fn save_as_csv(&self, path: &str, coordinates: bool) -> TiffResult<()> {
         let file = File::create(path)?;
         let mut writer = BufWriter::new(file);
 
-        // Write CSV header with column numbers (optional)
-        write!(writer, "row/col")?;
-        for x in 0..self.width {
-            write!(writer, ",{}", x)?;
+        // Write CSV header with column numbers or X coordinates
+        if coordinates {
+            write!(writer, "y/x")?;
+            for x in 0..self.width {
+                let (map_x, _) = self.cell_center(x, 0).unwrap_or_default();
+                write!(writer, ",{}", map_x)?;
+            }
+        } else {
+            write!(writer, "row/col")?;
+            for x in 0..self.width {
+                write!(writer, ",{}", x)?;
+            }
         }
         writeln!(writer)?;
 
         // Write data rows
         for y in 0..self.height {
-            // Row number as first column
-            write!(writer, "{}", y)?;
+            // Row number or Y coordinate as first column
+            if coordinates {
+                let (_, map_y) = self.cell_center(0, y).unwrap_or_default();
+                write!(writer, "{}", map_y)?;
+            } else {
+                write!(writer, "{}", y)?;
+            }
 
             // Write pixel values for this row
             for x in 0..self.width {
-                if let Some(value) = self.get(x, y) {
+                if let Some(value) = self.value_text(x, y) {
                     write!(writer, ",{}", value)?;
                 } else {
                     write!(writer, ",")?;
@@ -126,14 +269,298 @@ impl ArrayData {
         Ok(())
     }
 
+    /// Save the array as long-format (tidy) CSV
+    ///
+    /// Writes one row per valid pixel with the columns
+    /// `x,y,lon,lat,band,value`, where x/y are the column and row indices.
+    /// NoData pixels are skipped. Longitude and latitude are left empty when
+    /// the array is not georeferenced or its CRS cannot be converted to WGS84.
+    ///
+    /// # Arguments
+    /// * `path` - Path to save the CSV file
+    ///
+    /// # Returns
+    /// Result indicating success or an error
+    fn save_as_csv_long(&self, path: &str) -> TiffResult<()> {
+        let file = File::create(path)?;
+        let mut writer = BufWriter::new(file);
+
+        if self.width > 0 && self.height > 0 && self.cell_center_lon_lat(0, 0).is_none() {
+            warn!("Cannot derive longitude/latitude for this array, leaving those columns empty");
+        }
+
+        writeln!(writer, "x,y,lon,lat,band,value")?;
+
+        let mut skipped = 0usize;
+        for y in 0..self.height {
+            for x in 0..self.width {
+                let Some(value) = self.get(x, y) else { continue };
+                if self.is_nodata(value) {
+                    skipped += 1;
+                    continue;
+                }
+                let value = self.value_text(x, y).unwrap_or_default();
+
+                match self.cell_center_lon_lat(x, y) {
+                    Some(point) => writeln!(writer, "{},{},{},{},1,{}", x, y, point.x, point.y, value)?,
+                    None => writeln!(writer, "{},{},,,1,{}", x, y, value)?,
+                }
+            }
+        }
+
+        debug!("Skipped {} NoData pixels in long CSV output", skipped);
+        Ok(())
+    }
+
+    /// Save the valid pixels as rows of a SQLite database or GeoPackage
+    ///
+    /// The `pixels` table gets one row per pixel that is not NoData, with
+    /// the columns id, x, y, map_x, map_y, lon, lat, band and value. With a
+    /// spatial index, the R*Tree table `pixels_rtree` holds the footprint of
+    /// every cell in map coordinates and shares its ids with `pixels`.
+    ///
+    /// # Arguments
+    /// * `path` - Path of the database to write
+    /// * `geopackage` - Write a GeoPackage (pixels registered as attributes)
+    /// * `spatial_index` - Also build the R*Tree spatial index
+    ///
+    /// # Returns
+    /// The number of rows written, or an error
+    pub fn save_as_sqlite(&self, path: &str, geopackage: bool, spatial_index: bool) -> TiffResult<usize> {
+        if spatial_index && self.georeference.is_none() {
+            return Err(TiffError::GenericError(
+                "A spatial index needs a georeferenced array".to_string()));
+        }
+
+        let optional_real = |value: Option<f64>| value.map_or(SqlValue::Null, SqlValue::Real);
+
+        let mut rows = Vec::new();
+        let mut footprints = Vec::new();
+        for y in 0..self.height {
+            for x in 0..self.width {
+                let Some(value) = self.get(x, y) else { continue };
+                if self.is_nodata(value) {
+                    continue;
+                }
+
+                let id = rows.len() as i64 + 1;
+                let center = self.cell_center(x, y);
+                let lon_lat = self.cell_center_lon_lat(x, y);
+                rows.push((id, vec![
+                    SqlValue::Null,
+                    SqlValue::Integer(x as i64),
+                    SqlValue::Integer(y as i64),
+                    optional_real(center.map(|c| c.0)),
+                    optional_real(center.map(|c| c.1)),
+                    optional_real(lon_lat.map(|p| p.x)),
+                    optional_real(lon_lat.map(|p| p.y)),
+                    SqlValue::Integer(1),
+                    if self.data.is_float() { SqlValue::Real(value) } else { SqlValue::Integer(value as i64) },
+                ]));
+
+                if let Some(georeference) = self.georeference.as_ref().filter(|_| spatial_index) {
+                    let gt = georeference.geotransform;
+                    let (x0, y0) = gt.apply(x as f64, y as f64);
+                    let (x1, y1) = gt.apply(x as f64 + 1.0, y as f64 + 1.0);
+                    footprints.push((id, [x0.min(x1), x0.max(x1), y0.min(y1), y0.max(y1)]));
+                }
+            }
+        }
+
+        let row_count = rows.len();
+        info!("Writing {} pixel rows to {}", row_count, path);
+
+        let mut writer = SqliteWriter::new();
+        if geopackage {
+            gpkg_utils::add_geopackage_core(&mut writer, "pixels", "Pixel values exported by RasterKit")?;
+        }
+
+        writer.add_table(
+            "pixels",
+            "CREATE TABLE pixels (id INTEGER PRIMARY KEY, x INTEGER NOT NULL, y INTEGER NOT NULL, \
+             map_x REAL, map_y REAL, lon REAL, lat REAL, band INTEGER NOT NULL, value REAL)",
+            rows)?;
+
+        if spatial_index {
+            writer.add_rtree("pixels_rtree", ["id", "min_x", "max_x", "min_y", "max_y"], &footprints)?;
+        }
+
+        atomic_utils::write_atomically(path, |partial| writer.write(partial))?;
+        Ok(row_count)
+    }
+
+    /// Save the array as a SQL script loading it into a PostGIS raster table
+    ///
+    /// The script is what `raster2pgsql` would produce: the tiles carry the
+    /// array's geotransform and NoData, and keep its sample type as the
+    /// matching PostGIS pixel type (16BUI, 32BF and so on).
+    ///
+    /// # Arguments
+    /// * `path` - Path of the SQL file to write
+    /// * `options` - Table, SRID, tiling and statement options
+    ///
+    /// # Returns
+    /// The number of tiles written, or an error
+    pub(crate) fn save_as_postgis(&self, path: &str, options: &PostgisOptions) -> TiffResult<usize> {
+        let georeference = self.georeference.as_ref();
+
+        // Without an explicit SRID, use the EPSG code of the source
+        let mut options = options.clone();
+        if options.srid.is_none() {
+            options.srid = georeference.and_then(|g| g.epsg).map(|epsg| epsg as i32);
+        }
+
+        atomic_utils::write_atomically(path, |partial| postgis_utils::write_postgis_sql(
+            partial,
+            self.width,
+            self.height,
+            self.data.dtype(),
+            &self.data.to_le_bytes(),
+            georeference.map(|g| g.geotransform),
+            self.nodata,
+            &options,
+        ))
+    }
+
+    /// Save the valid pixels as Parquet, optionally hive-partitioned
+    ///
+    /// Rows have the columns x, y, value and band, plus map_x/map_y and
+    /// lon/lat when the array is georeferenced. NoData pixels are skipped.
+    /// With partitioning, `path` is a directory receiving one `part-0.parquet`
+    /// per partition under `key=value` subdirectories; partition keys are
+    /// not repeated as columns, as hive-aware readers add them back.
+    ///
+    /// # Arguments
+    /// * `path` - Path of the file (or directory, when partitioned) to write
+    /// * `partitioning` - How rows are split across files
+    /// * `row_group_size` - Maximum number of rows per row group
+    ///
+    /// # Returns
+    /// The number of rows written, or an error
+    pub(crate) fn save_as_parquet(
+        &self,
+        path: &str,
+        partitioning: ParquetPartitioning,
+        row_group_size: usize
+    ) -> TiffResult<usize> {
+        let partitions: Vec<(String, (u32, u32, u32, u32))> = match partitioning {
+            ParquetPartitioning::None => vec![(String::new(), (0, 0, self.width, self.height))],
+            ParquetPartitioning::Band => vec![("band=1".to_string(), (0, 0, self.width, self.height))],
+            ParquetPartitioning::Tile(tile_width, tile_height) => {
+                let mut tiles = Vec::new();
+                for (tile_row, y) in (0..self.height).step_by(tile_height as usize).enumerate() {
+                    for (tile_col, x) in (0..self.width).step_by(tile_width as usize).enumerate() {
+                        tiles.push((format!("tile_row={}/tile_col={}", tile_row, tile_col),
+                                    (x, y, tile_width.min(self.width - x), tile_height.min(self.height - y))));
+                    }
+                }
+                tiles
+            },
+        };
+
+        let with_band = partitioning != ParquetPartitioning::Band;
+        let mut total_rows = 0;
+        let mut file_count = 0;
+
+        for (partition, window) in partitions {
+            let columns = self.parquet_columns(window, with_band);
+            let rows = columns.first().map(|c| c.values.len()).unwrap_or(0);
+            total_rows += rows;
+
+            let file_path = if partitioning == ParquetPartitioning::None {
+                path.to_string()
+            } else if rows == 0 {
+                // Fully NoData partitions produce no file at all
+                continue;
+            } else {
+                let directory = Path::new(path).join(&partition);
+                std::fs::create_dir_all(&directory)?;
+                directory.join("part-0.parquet").to_string_lossy().to_string()
+            };
+
+            atomic_utils::write_atomically(&file_path, |partial| parquet_utils::write_parquet(partial, &columns, row_group_size))?;
+            file_count += 1;
+        }
+
+        info!("Exported {} pixel rows to {} Parquet file(s) under {}", total_rows, file_count, path);
+        Ok(total_rows)
+    }
+
+    /// Build the Parquet columns for the valid pixels of a window
+    ///
+    /// # Arguments
+    /// * `window` - Pixel window (x, y, width, height)
+    /// * `with_band` - Include the band column
+    ///
+    /// # Returns
+    /// The columns, all of equal length
+    fn parquet_columns(&self, window: (u32, u32, u32, u32), with_band: bool) -> Vec<ParquetColumn> {
+        let (x0, y0, width, height) = window;
+        let with_map = self.georeference.is_some();
+        let with_lon_lat = self.width > 0 && self.height > 0 && self.cell_center_lon_lat(0, 0).is_some();
+
+        let (mut xs, mut ys, mut values) = (Vec::new(), Vec::new(), Vec::new());
+        let (mut map_xs, mut map_ys, mut lons, mut lats) = (Vec::new(), Vec::new(), Vec::new(), Vec::new());
+
+        for y in y0..y0 + height {
+            for x in x0..x0 + width {
+                let Some(value) = self.get(x, y) else { continue };
+                if self.is_nodata(value) {
+                    continue;
+                }
+
+                xs.push(x as i32);
+                ys.push(y as i32);
+                values.push(value);
+                if let Some((map_x, map_y)) = self.cell_center(x, y) {
+                    map_xs.push(map_x);
+                    map_ys.push(map_y);
+                }
+                if with_lon_lat {
+                    let point = self.cell_center_lon_lat(x, y);
+                    lons.push(point.as_ref().map_or(f64::NAN, |p| p.x));
+                    lats.push(point.as_ref().map_or(f64::NAN, |p| p.y));
+                }
+            }
+        }
+
+        let row_count = values.len();
+        let mut columns = vec![
+            ParquetColumn::new("x", ParquetValues::Int32(xs)),
+            ParquetColumn::new("y", ParquetValues::Int32(ys)),
+        ];
+        if with_map {
+            columns.push(ParquetColumn::new("map_x", ParquetValues::Double(map_xs)));
+            columns.push(ParquetColumn::new("map_y", ParquetValues::Double(map_ys)));
+        }
+        if with_lon_lat {
+            columns.push(ParquetColumn::new("lon", ParquetValues::Double(lons)));
+            columns.push(ParquetColumn::new("lat", ParquetValues::Double(lats)));
+        }
+        if with_band {
+            columns.push(ParquetColumn::new("band", ParquetValues::Int32(vec![1; row_count])));
+        }
+        // Samples that fit in 32-bit integers stay integers
+        let values = match self.data.dtype() {
+            TensorDType::U8 | TensorDType::U16 | TensorDType::I16 | TensorDType::I32 =>
+                ParquetValues::Int32(values.into_iter().map(|value| value as i32).collect()),
+            _ => ParquetValues::Double(values),
+        };
+        columns.push(ParquetColumn::new("value", values));
+        columns
+    }
+
     /// Save the array as JSON
     ///
+    /// The geotransform and CRS are always included for georeferenced arrays.
+    ///
     /// # Arguments
     /// * `path` - Path to save the JSON file
+    /// * `coordinates` - Include the cell center coordinates of every column and row
     ///
     /// # Returns
     /// Result indicating success or an error
-    fn save_as_json(&self, path: &str) -> TiffResult<()> {
+    fn save_as_json(&self, path: &str, coordinates: bool) -> TiffResult<()> {
         let file = File::create(path)?;
         let mut writer = BufWriter::new(file);
 
@@ -141,6 +568,26 @@ impl ArrayData {
         writeln!(writer, "{{")?;
         writeln!(writer, "  \"width\": {},", self.width)?;
         writeln!(writer, "  \"height\": {},", self.height)?;
+
+        // Georeferencing block
+        if let Some(georeference) = &self.georeference {
+            writeln!(writer, "  \"geotransform\": {},", georeference.geotransform)?;
+            match georeference.epsg {
+                Some(epsg) => writeln!(writer, "  \"crs\": \"EPSG:{}\",", epsg)?,
+                None => writeln!(writer, "  \"crs\": null,")?,
+            }
+
+            if coordinates {
+                let x_coords: Vec<String> = (0..self.width)
+                    .filter_map(|x| self.cell_center(x, 0).map(|(map_x, _)| map_x.to_string()))
+                    .collect();
+                let y_coords: Vec<String> = (0..self.height)
+                    .filter_map(|y| self.cell_center(0, y).map(|(_, map_y)| map_y.to_string()))
+                    .collect();
+                writeln!(writer, "  \"x_coordinates\": [{}],", x_coords.join(", "))?;
+                writeln!(writer, "  \"y_coordinates\": [{}],", y_coords.join(", "))?;
+            }
+        }
         writeln!(writer, "  \"data\": [")?;
 
         // Write rows as nested arrays
@@ -148,10 +595,11 @@ impl ArrayData {
             write!(writer, "    [")?;
 
             for x in 0..self.width {
-                if let Some(value) = self.get(x, y) {
-                    write!(writer, "{}", value)?;
-                } else {
-                    write!(writer, "0")?;
+                // JSON has no NaN or infinity
+                match self.get(x, y) {
+                    Some(value) if !value.is_finite() => write!(writer, "null")?,
+                    Some(_) => write!(writer, "{}", self.value_text(x, y).unwrap_or_default())?,
+                    None => write!(writer, "0")?,
                 }
 
                 // Add comma if not the last element
@@ -177,38 +625,72 @@ impl ArrayData {
 
     /// Save the array as NumPy NPY file
     ///
+    /// The dtype of the file is the sample type of the array.
+    ///
     /// # Arguments
     /// * `path` - Path to save the NPY file
     ///
     /// # Returns
     /// Result indicating success or an error
     fn save_as_npy(&self, path: &str) -> TiffResult<()> {
-        let mut file = File::create(path)?;
-
-        // NPY format magic string and version
-        file.write_all(b"\x93NUMPY")?;  // Magic string
-        file.write_all(&[0x01, 0x00])?; // Version 1.0
-
-        // Create header string
-        let header_str = format!(
-            "{{'descr': '<u1', 'fortran_order': False, 'shape': ({}, {}), }}",
-            self.height, self.width
-        );
-
-        // Calculate padding to make header + length marker divisible by 64
-        let header_len = header_str.len() + 1; // +1 for newline
-        let padding_len = (64 - ((header_len + 10) % 64)) % 64;
-        let padded_header = format!("{}{}\n", header_str, " ".repeat(padding_len));
+        let shape = [self.height as usize, self.width as usize];
+        npy_utils::save_npy(path, self.data.dtype().npy_descr(), &shape, &self.data.to_le_bytes())
+    }
 
-        // Write header length and header
-        file.write_all(&[(padded_header.len() as u8) & 0xFF])?;
-        file.write_all(&[0x00])?; // For version 1.0, header length is 2 bytes
-        file.write_all(padded_header.as_bytes())?;
+    /// Save the array as an ENVI raster
+    ///
+    /// The samples are written band sequential next to a `.hdr` header
+    /// carrying the georeferencing and NoData value.
+    ///
+    /// # Arguments
+    /// * `path` - Path of the data file, or of the header
+    ///
+    /// # Returns
+    /// Result indicating success or an error
+    fn save_as_envi(&self, path: &str) -> TiffResult<()> {
+        let georeference = self.georeference.as_ref()
+            .map(|georeference| (&georeference.geotransform, georeference.epsg));
+        let data_type = envi_utils::data_type_code(self.data.dtype());
+        envi_utils::write_envi(path, self.width, self.height, data_type, &self.data.to_le_bytes(),
+                               georeference, self.nodata)
+            .map(|_| ())
+    }
 
-        // Write image data as raw bytes
-        file.write_all(&self.data)?;
+    /// Split the array into one named tensor per band
+    ///
+    /// Tensors are named `band_1`, `band_2`, ... with shape (height, width)
+    /// and keep the sample type of each band. An array without `bands`
+    /// gives a single tensor of its samples.
+    ///
+    /// # Returns
+    /// The band tensors
+    pub fn band_tensors(&self) -> Vec<NamedTensor> {
+        let shape = [self.height as usize, self.width as usize];
+        let bands = if self.bands.is_empty() { std::slice::from_ref(&self.data) } else { &self.bands };
+        bands.iter().enumerate()
+            .map(|(index, band)| NamedTensor::from_le_bytes(
+                &format!("band_{}", index + 1), band.dtype(), &shape, band.to_le_bytes()))
+            .collect()
+    }
 
-        Ok(())
+    /// Save the array as a safetensors file
+    ///
+    /// Each band becomes its own tensor; the raster dimensions are recorded
+    /// in the metadata section.
+    ///
+    /// # Arguments
+    /// * `path` - Path to save the safetensors file
+    ///
+    /// # Returns
+    /// Result indicating success or an error
+    fn save_as_safetensors(&self, path: &str) -> TiffResult<()> {
+        let tensors = self.band_tensors();
+        let metadata = vec![
+            ("width".to_string(), self.width.to_string()),
+            ("height".to_string(), self.height.to_string()),
+            ("bands".to_string(), tensors.len().to_string()),
+        ];
+        tensor_utils::save_safetensors(path, &tensors, &metadata)
     }
 }
 
@@ -221,6 +703,14 @@ pub struct ArrayExtractorStrategy<'a> {
     logger: &'a Logger,
     /// TIFF reader for parsing TIFF files
     reader: TiffReader<'a>,
+    /// Padding of extracted regions to the requested window
+    padding: Option<Padding>,
+    /// Decoded blocks kept between extractions, if caching is enabled
+    block_cache: Option<BlockCache>,
+    /// SubIFD of the main IFD to read from, if any
+    sub_ifd: Option<usize>,
+    /// Requested output format, None to go by the extension
+    output_format: Option<OutputFormat>,
 }
 
 impl<'a> ArrayExtractorStrategy<'a> {
@@ -235,8 +725,166 @@ impl<'a> ArrayExtractorStrategy<'a> {
         ArrayExtractorStrategy {
             logger,
             reader: TiffReader::new(logger),
+            padding: None,
+            block_cache: None,
+            sub_ifd: None,
+            output_format: None,
         }
     }
+
+    /// Decode every band of a window straight into typed samples
+    ///
+    /// The bands are read at their stored sample width into one buffer the
+    /// size of the window, instead of an RGB image, and padded if requested.
+    ///
+    /// # Arguments
+    /// * `source_path` - Path to the source TIFF file
+    /// * `region` - Optional region to extract (None for the full image)
+    ///
+    /// # Returns
+    /// The width and height of the (padded) window and the samples of each
+    /// band, or None for sub-byte samples, a selected SubIFD or cached
+    /// blocks, all of which go through the image path
+    fn decode_bands(&mut self, source_path: &str, region: Option<Region>) -> TiffResult<Option<(u32, u32, Vec<ArraySamples>)>> {
+        if self.sub_ifd.is_some() || self.block_cache.is_some() {
+            return Ok(None);
+        }
+        let shape = buffer_decode::describe_window(source_path, region, self.logger)?;
+        if shape.bytes_per_sample == 0 {
+            return Ok(None);
+        }
+
+        memory_utils::check_fits(&format!("The {}x{} window of {} band(s)", shape.width, shape.height, shape.bands),
+                                 shape.byte_len() as u64)?;
+        let mut samples = vec![0u8; shape.byte_len()];
+        buffer_decode::decode_into_bytes(source_path, region, BufferLayout::BandSequential, &mut samples, self.logger)?;
+        let band_len = shape.width as usize * shape.height as usize * shape.bytes_per_sample;
+        let bands: Vec<ArraySamples> = samples.chunks_exact(band_len)
+            .map(|band| ArraySamples::from_native_bytes(band.to_vec(), &shape))
+            .collect();
+        info!("Decoded a {}x{} window of {} band(s) of {} samples",
+              shape.width, shape.height, shape.bands, bands[0].dtype().npy_descr());
+
+        match self.padding {
+            Some(padding) => {
+                let fill = padding.fill_value(declared_nodata(&mut self.reader, source_path))?;
+                let bands = bands.iter().map(|band| band.padded(shape.width, shape.height, &padding, fill)).collect();
+                Ok(Some((padding.width, padding.height, bands)))
+            },
+            None => Ok(Some((shape.width, shape.height, bands))),
+        }
+    }
+
+    /// Decode a single-band window straight into an array
+    ///
+    /// Samples keep the type they are stored with.
+    ///
+    /// # Arguments
+    /// * `source_path` - Path to the source TIFF file
+    /// * `region` - Optional region to extract (None for the full image)
+    ///
+    /// # Returns
+    /// The array without georeferencing, or None if the image has several
+    /// bands or cannot be decoded natively
+    fn extract_band(&mut self, source_path: &str, region: Option<Region>) -> TiffResult<Option<ArrayData>> {
+        if buffer_decode::describe_window(source_path, region, self.logger)?.bands != 1 {
+            return Ok(None);
+        }
+        Ok(self.decode_bands(source_path, region)?.map(|(width, height, mut bands)| {
+            ArrayData { width, height, data: bands.remove(0), bands: Vec::new(), georeference: None, nodata: None }
+        }))
+    }
+
+    /// Decode a window with every band kept in its stored type
+    ///
+    /// # Arguments
+    /// * `source_path` - Path to the source TIFF file
+    /// * `region` - Optional region to extract (None for the full image)
+    ///
+    /// # Returns
+    /// The array of the first band holding every band in `bands`, or None
+    /// if the window cannot be decoded natively
+    fn extract_all_bands(&mut self, source_path: &str, region: Option<Region>) -> TiffResult<Option<ArrayData>> {
+        Ok(self.decode_bands(source_path, region)?.map(|(width, height, bands)| {
+            ArrayData { width, height, data: bands[0].clone(), bands, georeference: None, nodata: None }
+        }))
+    }
+}
+
+/// Read the declared NoData value of a file
+///
+/// # Arguments
+/// * `reader` - Reader to load the file with
+/// * `source_path` - Path to the source TIFF file
+///
+/// # Returns
+/// The NoData value, or None if the file does not declare one
+pub(super) fn declared_nodata(reader: &mut TiffReader, source_path: &str) -> Option<f64> {
+    let tiff = reader.load(source_path).ok()?;
+    let ifd = tiff.ifds.first()?;
+    if !ifd.has_tag(tags::GDAL_NODATA) {
+        return None;
+    }
+
+    tiff_extraction_utils::extract_nodata_value(ifd, reader).trim().parse::<f64>().ok()
+}
+
+/// Read the georeferencing of an extraction window
+///
+/// The geotransform is shifted so that its origin is the top-left
+/// corner of the extracted region, and rescaled when reading a SubIFD
+/// of another size than the main image.
+///
+/// # Arguments
+/// * `reader` - Reader to load the file with
+/// * `source_path` - Path to the source TIFF file
+/// * `region` - The extracted region (None for the full image)
+/// * `sub_ifd` - SubIFD the pixels were read from, if any
+///
+/// # Returns
+/// The georeferencing, or None if the file is not a GeoTIFF
+fn window_georeference(reader: &mut TiffReader, source_path: &str, region: Option<Region>,
+                       sub_ifd: Option<usize>) -> Option<ArrayGeoreference> {
+    let tiff = reader.load(source_path).ok()?;
+    let ifd = tiff.ifds.first()?;
+    let source_ifd = tiff_extraction_utils::select_source_ifd(&tiff, sub_ifd).ok()?;
+    let handler = reader.get_byte_order_handler()?;
+
+    let (x, y) = region.map(|r| tiff_extraction_utils::scale_region_to_ifd(r, ifd, source_ifd))
+        .map(|r| (r.x, r.y)).unwrap_or((0, 0));
+    let (factor_x, factor_y) = tiff_extraction_utils::ifd_scale_factors(ifd, source_ifd);
+    let geotransform = image_extraction_utils::calculate_geotransform(ifd, handler, source_path).ok()?
+        .scaled(factor_x, factor_y)
+        .translated(x, y);
+
+    let epsg = GeoKeyParser::extract_geo_info(ifd, handler, source_path).ok()
+        .and_then(|info| [info.epsg_code, info.geographic_cs_code].into_iter().find(|&code| code > 0));
+
+    Some(ArrayGeoreference { geotransform, epsg })
+}
+
+/// Attach the georeferencing and NoData value of an extraction window
+///
+/// # Arguments
+/// * `array_data` - Array decoded from the window
+/// * `reader` - Reader to load the source file with
+/// * `source_path` - Path to the source TIFF file
+/// * `region` - The extracted region (None for the full image)
+/// * `sub_ifd` - SubIFD the pixels were read from, if any
+/// * `padding` - Padding the array was extended with, if any
+pub(super) fn attach_window_metadata(array_data: &mut ArrayData, reader: &mut TiffReader, source_path: &str,
+                                     region: Option<Region>, sub_ifd: Option<usize>, padding: Option<Padding>) {
+    array_data.georeference = window_georeference(reader, source_path, region, sub_ifd);
+    array_data.nodata = declared_nodata(reader, source_path);
+    if let Some(padding) = padding {
+        if let Some(georeference) = array_data.georeference.as_mut() {
+            georeference.geotransform = padding.shift(&georeference.geotransform);
+        }
+        array_data.nodata = padding.fill.or(array_data.nodata);
+    }
+    if array_data.georeference.is_none() {
+        debug!("No georeferencing found for {}", source_path);
+    }
 }
 
 impl<'a> ExtractorStrategy for ArrayExtractorStrategy<'a> {
@@ -247,8 +895,11 @@ impl<'a> ExtractorStrategy for ArrayExtractorStrategy<'a> {
     /// with CSV format.
     fn extract_to_file(&mut self, source_path: &str, output_path: &str,
                        region: Option<Region>, shape: Option<&str>) -> TiffResult<()> {
-        // Default to CSV format for compatibility
-        self.extract_to_array(source_path, output_path, "csv", region)
+        // Array formats come from the request or the extension, CSV otherwise
+        let format = Some(OutputFormat::resolve(output_path, self.output_format))
+            .filter(|format| format.is_array())
+            .unwrap_or(OutputFormat::Csv);
+        self.extract_to_array(source_path, output_path, format.name(), region)
     }
 
     /// Extract an image from a file to memory
@@ -260,22 +911,24 @@ impl<'a> ExtractorStrategy for ArrayExtractorStrategy<'a> {
         // Load the TIFF file
         let tiff = self.reader.load(source_path)?;
 
-        if tiff.ifds.is_empty() {
-            return Err(TiffError::GenericError("No IFDs found in TIFF file".to_string()));
-        }
+        let main_ifd = tiff_extraction_utils::select_source_ifd(&tiff, None)?;
 
-        // Use the first IFD
-        let ifd = &tiff.ifds[0];
+        // Use the first IFD, or the requested SubIFD of it
+        let ifd = tiff_extraction_utils::select_source_ifd(&tiff, self.sub_ifd)?;
+        let region = region.map(|region| tiff_extraction_utils::scale_region_to_ifd(region, main_ifd, ifd));
 
         // Determine and validate the extraction region
         let region = crate::utils::tiff_extraction_utils::determine_extraction_region(region, ifd)?;
 
         info!("Extracting region: ({}, {}) with size {}x{}",
               region.x, region.y, region.width, region.height);
+        // Pixels are decoded to three bytes each before becoming arrays
+        memory_utils::check_fits(&format!("The {}x{} extraction window", region.width, region.height),
+                                 region.width as u64 * region.height as u64 * 3)?;
 
         // Open file for reading
-        let file = File::open(source_path)?;
-        let reader = BufReader::with_capacity(1024 * 1024, file);
+        let file = storage::open(source_path)?;
+        let reader = BufReader::with_capacity(memory_utils::io_buffer_size(), file);
 
         // Extract the pixel data
         let mut image = image::ImageBuffer::<image::Rgb<u8>, Vec<u8>>::new(region.width, region.height);
@@ -283,15 +936,31 @@ impl<'a> ExtractorStrategy for ArrayExtractorStrategy<'a> {
         // Check if we're using strips or tiles
         let is_tiled = ifd.has_tag(tags::TILE_WIDTH) && ifd.has_tag(tags::TILE_LENGTH);
 
+        if let Some(cache) = self.block_cache.as_mut() {
+            cache.use_file(source_path);
+        }
+
         if is_tiled {
             let mut tile_reader = TileReader::new(reader, ifd, &self.reader);
-            tile_reader.extract(&mut image, region)?;
+            tile_reader.extract(&mut image, region, self.block_cache.as_mut())?;
         } else {
             let mut strip_reader = StripReader::new(reader, ifd, &self.reader);
-            strip_reader.extract(&mut image, region)?;
+            strip_reader.extract(&mut image, region, self.block_cache.as_mut())?;
+        }
+
+        if let Some(cache) = &self.block_cache {
+            let (decoded, reused) = cache.stats();
+            debug!("Block cache: {} blocks decoded, {} reads reused, {} bytes held", decoded, reused, cache.cached_bytes());
         }
 
-        Ok(DynamicImage::ImageRgb8(image))
+        let image = DynamicImage::ImageRgb8(image);
+        match self.padding {
+            Some(padding) => {
+                let fill = padding.fill_value(declared_nodata(&mut self.reader, source_path))?;
+                Ok(image_extraction_utils::pad_image(&image, &padding, fill))
+            },
+            None => Ok(image),
+        }
     }
 
     /// Extract array data from a file to another file
@@ -302,7 +971,7 @@ impl<'a> ExtractorStrategy for ArrayExtractorStrategy<'a> {
     /// # Arguments
     /// * `source_path` - Path to the source TIFF file
     /// * `output_path` - Path where the extracted array should be saved
-    /// * `format` - Format for the output ("csv", "json", or "npy")
+    /// * `format` - Format for the output ("csv", "json", "npy" or "safetensors")
     /// * `region` - Optional region to extract (if None, extracts the entire image)
     ///
     /// # Returns
@@ -312,6 +981,18 @@ impl<'a> ExtractorStrategy for ArrayExtractorStrategy<'a> {
         info!("Extracting array data from {} to {} in {} format",
               source_path, output_path, format);
 
+        // Safetensors output keeps every band in its stored type
+        if OutputFormat::from_name(format)? == OutputFormat::Safetensors {
+            let native = self.extract_all_bands(source_path, region).unwrap_or_else(|e| {
+                warn!("Native decode of the bands of {} failed, decoding through an image: {}", source_path, e);
+                None
+            });
+            if let Some(mut array_data) = native {
+                attach_window_metadata(&mut array_data, &mut self.reader, source_path, region, self.sub_ifd, self.padding);
+                return array_data.save_to_file(output_path, format);
+            }
+        }
+
         // Extract the array data
         let array_data = self.extract_array_data(source_path, region)?;
 
@@ -334,10 +1015,43 @@ impl<'a> ExtractorStrategy for ArrayExtractorStrategy<'a> {
                           region: Option<Region>) -> TiffResult<ArrayData> {
         info!("Extracting array data from {} to memory", source_path);
 
-        // First extract the image
-        let image = self.extract_image(source_path, region)?;
+        // Single-band rasters are decoded natively, everything else through an image
+        let native = self.extract_band(source_path, region).unwrap_or_else(|e| {
+            warn!("Native band decode of {} failed, decoding through an image: {}", source_path, e);
+            None
+        });
+        let mut array_data = match native {
+            Some(array_data) => array_data,
+            None => ArrayData::from_image(&self.extract_image(source_path, region)?),
+        };
+
+        // Attach the georeferencing of the window
+        attach_window_metadata(&mut array_data, &mut self.reader, source_path, region, self.sub_ifd, self.padding);
+
+        Ok(array_data)
+    }
+
+    /// Pad extracted arrays to the requested window
+    fn set_padding(&mut self, padding: Option<Padding>) {
+        self.padding = padding;
+    }
+
+    /// Keep decoded blocks for later extractions from the same file
+    fn set_block_caching(&mut self, enabled: bool) {
+        match (enabled, self.block_cache.is_some()) {
+            (true, false) => self.block_cache = Some(BlockCache::new()),
+            (false, true) => self.block_cache = None,
+            _ => {},
+        }
+    }
+
+    /// Read pixels from a SubIFD of the main IFD
+    fn set_sub_ifd(&mut self, sub_ifd: Option<usize>) {
+        self.sub_ifd = sub_ifd;
+    }
 
-        // Convert to array data
-        Ok(ArrayData::from_image(&image))
+    /// Write arrays in the requested format
+    fn set_output_format(&mut self, format: Option<OutputFormat>) {
+        self.output_format = format;
     }
 }
\ No newline at end of file
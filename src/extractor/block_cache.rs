@@ -0,0 +1,113 @@
+//! Cache of decoded tiles and strips
+//!
+//! Extracting several regions from one file touches the same tiles or
+//! strips over and over. The cache keeps every decoded block of the current
+//! file, so each one is read and decompressed at most once per run. Under a
+//! memory budget it holds a share of the budget and drops the blocks it
+//! decoded first when full.
+
+use std::collections::{HashMap, VecDeque};
+
+use log::{debug, info};
+
+use crate::tiff::errors::TiffResult;
+use crate::utils::{memory_utils, metrics};
+
+/// Decoded blocks of one file, keyed by tile or strip index
+#[derive(Debug, Default)]
+pub struct BlockCache {
+    /// File the cached blocks belong to
+    path: String,
+    /// Decompressed block data, predictor already undone
+    blocks: HashMap<usize, Vec<u8>>,
+    /// Cached block indexes, oldest first
+    order: VecDeque<usize>,
+    /// Bytes of all cached blocks
+    cached_bytes: usize,
+    /// Most bytes to keep, None for no limit
+    limit: Option<usize>,
+    /// Number of blocks decoded
+    decoded: usize,
+    /// Number of block reads served from the cache
+    reused: usize,
+}
+
+impl BlockCache {
+    /// Create an empty cache, limited to its share of the memory budget
+    pub fn new() -> Self {
+        Self::with_limit(memory_utils::cache_limit())
+    }
+
+    /// Create an empty cache holding at most some bytes of decoded blocks
+    ///
+    /// # Arguments
+    /// * `limit` - Most bytes to keep, None for no limit; a single block
+    ///   larger than the limit is still kept until the next one is decoded
+    pub fn with_limit(limit: Option<usize>) -> Self {
+        BlockCache { limit, ..Self::default() }
+    }
+
+    /// Switch the cache to a file, dropping blocks of any other file
+    ///
+    /// # Arguments
+    /// * `path` - File the following reads come from
+    pub fn use_file(&mut self, path: &str) {
+        if self.path != path {
+            if !self.blocks.is_empty() {
+                info!("Dropping {} cached blocks of {}", self.blocks.len(), self.path);
+            }
+            self.blocks.clear();
+            self.order.clear();
+            self.cached_bytes = 0;
+            self.path = path.to_string();
+        }
+    }
+
+    /// Look up a block, decoding it on first use
+    ///
+    /// # Arguments
+    /// * `index` - Tile or strip index
+    /// * `decode` - Reads and decompresses the block
+    ///
+    /// # Returns
+    /// The decoded block, or the decoding error (which is not cached)
+    pub fn get_or_decode<F>(&mut self, index: usize, decode: F) -> TiffResult<&[u8]>
+    where
+        F: FnOnce() -> TiffResult<Vec<u8>>,
+    {
+        if self.blocks.contains_key(&index) {
+            self.reused += 1;
+            metrics::record_cache_hit();
+            return Ok(&self.blocks[&index]);
+        }
+
+        let data = decode()?;
+        self.decoded += 1;
+        self.make_room(data.len());
+        self.cached_bytes += data.len();
+        self.order.push_back(index);
+        Ok(self.blocks.entry(index).or_insert(data))
+    }
+
+    /// Drop the oldest blocks until another block fits under the limit
+    fn make_room(&mut self, bytes: usize) {
+        let Some(limit) = self.limit else { return };
+        while self.cached_bytes + bytes > limit {
+            let Some(oldest) = self.order.pop_front() else { break };
+            if let Some(block) = self.blocks.remove(&oldest) {
+                debug!("Dropping cached block {} to stay within {} bytes", oldest, limit);
+                self.cached_bytes -= block.len();
+            }
+        }
+    }
+
+    /// Number of blocks decoded and number of reads served from the cache
+    pub fn stats(&self) -> (usize, usize) {
+        (self.decoded, self.reused)
+    }
+
+    /// Bytes of the blocks currently cached
+    pub fn cached_bytes(&self) -> usize {
+        self.cached_bytes
+    }
+}
@@ -0,0 +1,238 @@
+//! Decoding into caller-provided buffers
+//!
+//! The extractors hand out `DynamicImage`s, which costs a full-size image
+//! buffer and usually a conversion copy before the caller sees a sample.
+//! Consumers that stream many windows (tiling services, training loaders)
+//! can instead pass a buffer of their own: every block the window touches
+//! is decompressed and its samples are written straight to their place in
+//! the buffer, in the requested layout. Blocks outside the window are not
+//! read at all.
+
+use log::info;
+
+use crate::io::storage;
+use crate::extractor::Region;
+use crate::tiff::errors::{TiffError, TiffResult};
+use crate::tiff::TiffReader;
+use crate::utils::logger::Logger;
+use crate::utils::sample_utils::{self, SampleFormat, SampleLayout};
+
+/// Order of the samples of a decoded window
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum BufferLayout {
+    /// Row-major pixels with their bands interleaved (BIP), as TIFF stores
+    /// chunky data
+    Interleaved,
+    /// One row-major plane per band (BSQ), as array libraries index
+    /// `[band][row][column]`
+    BandSequential,
+}
+
+/// Shape and sample type of a decoded window
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct DecodedWindow {
+    /// Width in pixels
+    pub width: u32,
+    /// Height in pixels
+    pub height: u32,
+    /// Bands per pixel
+    pub bands: usize,
+    /// Bytes of one sample as stored in the file
+    pub bytes_per_sample: usize,
+    /// Whether samples are IEEE floats
+    pub floating_point: bool,
+    /// Whether integer samples are signed
+    pub signed: bool,
+}
+
+impl DecodedWindow {
+    /// Number of samples in the window
+    pub fn samples(&self) -> usize {
+        self.width as usize * self.height as usize * self.bands
+    }
+
+    /// Number of bytes the window takes at its stored sample width
+    pub fn byte_len(&self) -> usize {
+        self.samples() * self.bytes_per_sample
+    }
+
+    /// Little-endian bytes of a value stored as a sample of the window
+    ///
+    /// Integer samples are rounded and saturate at the limits of their type.
+    pub fn sample_bytes(&self, value: f64) -> Vec<u8> {
+        // Float to integer casts saturate, and map NaN to 0
+        match (self.bytes_per_sample, self.floating_point, self.signed) {
+            (1, _, false) => vec![value.round() as u8],
+            (1, _, true) => (value.round() as i8).to_le_bytes().to_vec(),
+            (2, _, false) => (value.round() as u16).to_le_bytes().to_vec(),
+            (2, _, true) => (value.round() as i16).to_le_bytes().to_vec(),
+            (4, true, _) => (value as f32).to_le_bytes().to_vec(),
+            (4, false, false) => (value.round() as u32).to_le_bytes().to_vec(),
+            (4, false, true) => (value.round() as i32).to_le_bytes().to_vec(),
+            (_, true, _) => value.to_le_bytes().to_vec(),
+            (_, false, false) => (value.round() as u64).to_le_bytes().to_vec(),
+            (_, false, true) => (value.round() as i64).to_le_bytes().to_vec(),
+        }
+    }
+
+    /// Index in the buffer of a sample of the window
+    fn index(&self, layout: BufferLayout, column: u32, row: u32, band: usize) -> usize {
+        let pixel = row as usize * self.width as usize + column as usize;
+        match layout {
+            BufferLayout::Interleaved => pixel * self.bands + band,
+            BufferLayout::BandSequential => band * self.width as usize * self.height as usize + pixel,
+        }
+    }
+}
+
+/// Read the sample layout of a TIFF's first image and resolve a window in it
+///
+/// # Returns
+/// The window (the whole image if none was given) and its shape
+pub(super) fn open_window(input_path: &str, region: Option<Region>, logger: &Logger) -> TiffResult<(Region, DecodedWindow)> {
+    let mut reader = TiffReader::new(logger);
+    let tiff = reader.load(input_path)?;
+    let ifd = tiff.main_ifd()
+        .ok_or_else(|| TiffError::GenericError(format!("No IFDs found in {}", input_path)))?;
+    let mut file = std::io::BufReader::new(storage::open(input_path)?);
+    let layout = sample_utils::read_layout(&reader, ifd, &mut file)?;
+
+    let window = region.unwrap_or_else(|| Region::new(0, 0, layout.width, layout.height));
+    if window.width == 0 || window.height == 0 || window.end_x() > layout.width || window.end_y() > layout.height {
+        return Err(TiffError::GenericError(format!(
+            "Window {:?} is not inside the {}x{} image", window, layout.width, layout.height)));
+    }
+
+    let shape = DecodedWindow {
+        width: window.width,
+        height: window.height,
+        bands: layout.bands,
+        bytes_per_sample: (layout.bits / 8) as usize,
+        floating_point: layout.format == SampleFormat::Float,
+        signed: layout.format == SampleFormat::Signed,
+    };
+    Ok((window, shape))
+}
+
+/// Describe the window a decode would produce, without decoding it
+///
+/// # Arguments
+/// * `input_path` - Path to the TIFF
+/// * `region` - Window to decode, None for the whole image
+/// * `logger` - Logger for recording operations
+///
+/// # Returns
+/// The shape and sample type of the window, to size a buffer with
+pub fn describe_window(input_path: &str, region: Option<Region>, logger: &Logger) -> TiffResult<DecodedWindow> {
+    open_window(input_path, region, logger).map(|(_, shape)| shape)
+}
+
+/// Whether a block of an image overlaps a window
+///
+/// # Arguments
+/// * `layout` - Sample layout of the image
+/// * `index` - Index of the block
+/// * `window` - The window, in image pixels
+pub(super) fn block_touches(layout: &SampleLayout, index: usize, window: &Region) -> bool {
+    let position = index % layout.blocks_per_band();
+    let x = (position % layout.blocks_across()) as u32 * layout.block_width;
+    let y = (position / layout.blocks_across()) as u32 * layout.block_height;
+    x < window.end_x() && x + layout.block_width > window.x
+        && y < window.end_y() && y + layout.block_height > window.y
+}
+
+/// Write the bits of a sample into its bytes, in native byte order
+///
+/// # Arguments
+/// * `raw` - The sample bits
+/// * `target` - Bytes of the sample, 1, 2, 4 or 8 of them
+pub(super) fn store_native(raw: u64, target: &mut [u8]) {
+    match target.len() {
+        1 => target[0] = raw as u8,
+        2 => target.copy_from_slice(&(raw as u16).to_ne_bytes()),
+        4 => target.copy_from_slice(&(raw as u32).to_ne_bytes()),
+        _ => target.copy_from_slice(&raw.to_ne_bytes()),
+    }
+}
+
+/// Decode the blocks a window touches, handing each of its samples to `store`
+///
+/// # Returns
+/// The shape of the decoded window
+fn decode_window(input_path: &str, region: Option<Region>, layout: BufferLayout, buffer_len: usize,
+                 needed: fn(&DecodedWindow) -> usize, logger: &Logger,
+                 mut store: impl FnMut(&SampleLayout, usize, u64)) -> TiffResult<DecodedWindow> {
+    let (window, shape) = open_window(input_path, region, logger)?;
+    let needed = needed(&shape);
+    if buffer_len < needed {
+        return Err(TiffError::GenericError(format!(
+            "A {}x{} window of {} band(s) needs a buffer of {} elements, got {}",
+            shape.width, shape.height, shape.bands, needed, buffer_len)));
+    }
+    info!("Decoding a {}x{} window of {} into a {:?} buffer", window.width, window.height, input_path, layout);
+
+    sample_utils::read_blocks(
+        input_path, logger,
+        |layout, index| block_touches(layout, index, &window),
+        |sample_layout, block| {
+            let (first_column, last_column) = (block.x.max(window.x), (block.x + block.width).min(window.end_x()));
+            let (first_row, last_row) = (block.y.max(window.y), (block.y + block.height).min(window.end_y()));
+            for band in block.bands.clone() {
+                for row in first_row..last_row {
+                    for column in first_column..last_column {
+                        if let Some(raw) = block.sample(column - block.x, row - block.y, band) {
+                            store(sample_layout, shape.index(layout, column - window.x, row - window.y, band), raw);
+                        }
+                    }
+                }
+            }
+        })?;
+    Ok(shape)
+}
+
+/// Decode a window into a byte buffer at the stored sample width
+///
+/// Samples are written in native byte order, so a buffer of 16-bit data can
+/// be viewed as `u16`s in place. Pixels of blocks missing from the file are
+/// left as they were.
+///
+/// # Arguments
+/// * `input_path` - Path to the TIFF
+/// * `region` - Window to decode, None for the whole image
+/// * `layout` - Order of the samples in the buffer
+/// * `buffer` - Buffer of at least `DecodedWindow::byte_len` bytes
+/// * `logger` - Logger for recording operations
+///
+/// # Returns
+/// The shape and sample type of the decoded window
+pub fn decode_into_bytes(input_path: &str, region: Option<Region>, layout: BufferLayout,
+                         buffer: &mut [u8], logger: &Logger) -> TiffResult<DecodedWindow> {
+    let buffer_len = buffer.len();
+    decode_window(input_path, region, layout, buffer_len, DecodedWindow::byte_len, logger, |sample_layout, index, raw| {
+        let bytes = (sample_layout.bits / 8) as usize;
+        store_native(raw, &mut buffer[index * bytes..(index + 1) * bytes]);
+    })
+}
+
+/// Decode a window into a buffer of 32-bit floats
+///
+/// Integer samples are converted to their values, signed or not, and
+/// 64-bit floats are narrowed. Pixels of blocks missing from the file are
+/// left as they were.
+///
+/// # Arguments
+/// * `input_path` - Path to the TIFF
+/// * `region` - Window to decode, None for the whole image
+/// * `layout` - Order of the samples in the buffer
+/// * `buffer` - Buffer of at least `DecodedWindow::samples` values
+/// * `logger` - Logger for recording operations
+///
+/// # Returns
+/// The shape and sample type of the decoded window
+pub fn decode_into_f32(input_path: &str, region: Option<Region>, layout: BufferLayout,
+                       buffer: &mut [f32], logger: &Logger) -> TiffResult<DecodedWindow> {
+    let buffer_len = buffer.len();
+    decode_window(input_path, region, layout, buffer_len, DecodedWindow::samples, logger, |sample_layout, index, raw| {
+        buffer[index] = sample_layout.value(raw) as f32;
+    })
+}
@@ -0,0 +1,313 @@
+//! ENVI extractor strategy implementation
+//!
+//! Extracts ENVI rasters in image and array mode, given either the `.hdr`
+//! header or the binary data file next to it. GeoTIFF outputs and arrays
+//! are georeferenced from the header's `map info`.
+
+use std::path::{Path, PathBuf};
+
+use image::{DynamicImage, ImageBuffer, Luma, Rgb};
+use log::{info, warn};
+
+use crate::coordinate::Affine;
+use crate::tiff::TiffBuilder;
+use crate::tiff::errors::{TiffError, TiffResult};
+use crate::tiff::ifd::IFD;
+use crate::utils::envi_utils::{self, EnviHeader};
+use crate::utils::logger::Logger;
+use crate::utils::{mask_utils, tiff_extraction_utils};
+
+use super::array_samples::ArraySamples;
+use super::array_strategy::{ArrayData, ArrayGeoreference};
+use super::extractor_strategy::ExtractorStrategy;
+use super::output_format::OutputFormat;
+use super::region::Region;
+
+/// ENVI format extractor implementation
+///
+/// Shows the header's default bands as RGB when it names three, the first
+/// band otherwise. 8 and 16-bit unsigned samples are kept as they are;
+/// other types are stretched to 8 bits over their range.
+pub struct EnviExtractorStrategy<'a> {
+    /// Logger for recording operations
+    logger: &'a Logger,
+    /// Requested output format, None to go by the extension
+    output_format: Option<OutputFormat>,
+    /// Header and data path of the last raster read, by path
+    raster: Option<(String, EnviHeader, PathBuf)>,
+}
+
+impl<'a> EnviExtractorStrategy<'a> {
+    /// Create a new ENVI extractor strategy
+    ///
+    /// # Arguments
+    /// * `logger` - Logger for recording operations
+    pub fn new(logger: &'a Logger) -> Self {
+        EnviExtractorStrategy {
+            logger,
+            output_format: None,
+            raster: None,
+        }
+    }
+
+    /// Header and data path of a raster, parsing the header once per file
+    fn open(&mut self, source_path: &str) -> TiffResult<(EnviHeader, PathBuf)> {
+        if !matches!(&self.raster, Some((path, _, _)) if path == source_path) {
+            let (header_path, data_path) = envi_utils::resolve_paths(source_path)?;
+            let header = envi_utils::read_header(&header_path.to_string_lossy())?;
+            self.raster = Some((source_path.to_string(), header, data_path));
+        }
+
+        let (_, header, data_path) = self.raster.as_ref().expect("raster was just opened");
+        Ok((header.clone(), data_path.clone()))
+    }
+
+    /// Read the displayed bands of a region
+    ///
+    /// # Returns
+    /// The header and the samples of each displayed band
+    fn read_bands(&mut self, source_path: &str,
+                  region: &Region) -> TiffResult<(EnviHeader, Vec<Vec<f64>>)> {
+        let (header, data_path) = self.open(source_path)?;
+        let bands = display_bands(&header);
+
+        let names: Vec<String> = bands.iter()
+            .map(|&band| header.band_names.get(band as usize).cloned().unwrap_or_else(|| format!("Band {}", band + 1)))
+            .collect();
+        info!("Extracting ENVI region: ({}, {}) with size {}x{} from {}",
+              region.x, region.y, region.width, region.height, names.join(", "));
+
+        let samples = envi_utils::read_window(&header, &data_path, region, &bands)?;
+        Ok((header, samples))
+    }
+}
+
+/// Zero-based bands shown for a raster
+fn display_bands(header: &EnviHeader) -> Vec<u32> {
+    match header.default_bands.as_slice() {
+        [red, green, blue] if [red, green, blue].iter().all(|&&band| band >= 1 && band <= header.bands) => {
+            vec![red - 1, green - 1, blue - 1]
+        },
+        [] => {
+            if header.bands > 1 {
+                info!("ENVI header names no default bands, extracting band 1 of {}", header.bands);
+            }
+            vec![0]
+        },
+        other => {
+            warn!("Unusable ENVI default bands {:?}, extracting band 1", other);
+            vec![0]
+        },
+    }
+}
+
+/// Stretch samples to 8 bits over the range of the valid ones
+///
+/// NoData and NaN samples become 0; when the raster declares NoData the
+/// valid samples are stretched to 1-255 so they stay distinguishable.
+///
+/// # Arguments
+/// * `samples` - Samples of every band, stretched over their common range
+/// * `nodata` - NoData value of the raster, if any
+fn stretch_to_u8(samples: &[Vec<f64>], nodata: Option<f64>) -> Vec<Vec<u8>> {
+    let is_valid = |value: f64| value.is_finite() && Some(value) != nodata;
+    let (min, max) = samples.iter().flatten().copied().filter(|&value| is_valid(value))
+        .fold((f64::INFINITY, f64::NEG_INFINITY), |(min, max), value| (min.min(value), max.max(value)));
+    let low = if nodata.is_some() { 1.0 } else { 0.0 };
+    let scale = if max > min { (255.0 - low) / (max - min) } else { 0.0 };
+
+    info!("Stretching ENVI samples from {} - {} to 8 bits", min, max);
+    samples.iter()
+        .map(|band| band.iter()
+            .map(|&value| if is_valid(value) { (low + (value - min) * scale).round() as u8 } else { 0 })
+            .collect())
+        .collect()
+}
+
+/// Turn the samples of the displayed bands into an image
+///
+/// # Arguments
+/// * `header` - Header of the raster
+/// * `samples` - Samples of one or three bands
+/// * `width` - Width of the region
+/// * `height` - Height of the region
+///
+/// # Returns
+/// An RGB image for three bands, grayscale otherwise
+fn to_dynamic_image(header: &EnviHeader, samples: Vec<Vec<f64>>,
+                    width: u32, height: u32) -> TiffResult<DynamicImage> {
+    let buffer_error = || TiffError::GenericError("ENVI samples do not fit the image".to_string());
+    let pixel_count = width as usize * height as usize;
+    let interleave = |bands: &[Vec<f64>]| -> Vec<f64> {
+        (0..pixel_count).flat_map(|pixel| bands.iter().map(move |band| band[pixel])).collect()
+    };
+
+    Ok(match (header.data_type, samples.len()) {
+        (12, 1) => DynamicImage::ImageLuma16(ImageBuffer::<Luma<u16>, _>::from_raw(
+            width, height, interleave(&samples).into_iter().map(|v| v as u16).collect()).ok_or_else(buffer_error)?),
+        (12, _) => DynamicImage::ImageRgb16(ImageBuffer::<Rgb<u16>, _>::from_raw(
+            width, height, interleave(&samples).into_iter().map(|v| v as u16).collect()).ok_or_else(buffer_error)?),
+        (data_type, bands) => {
+            let bytes = if data_type == 1 {
+                samples.iter().map(|band| band.iter().map(|&v| v as u8).collect()).collect()
+            } else {
+                stretch_to_u8(&samples, header.nodata)
+            };
+            let data: Vec<u8> = (0..pixel_count).flat_map(|pixel| bytes.iter().map(move |band: &Vec<u8>| band[pixel])).collect();
+            if bands == 1 {
+                DynamicImage::ImageLuma8(ImageBuffer::<Luma<u8>, _>::from_raw(width, height, data).ok_or_else(buffer_error)?)
+            } else {
+                DynamicImage::ImageRgb8(ImageBuffer::<Rgb<u8>, _>::from_raw(width, height, data).ok_or_else(buffer_error)?)
+            }
+        },
+    })
+}
+
+/// Geotransform of a region of the raster, with the raster's EPSG code
+fn region_georeference(header: &EnviHeader, region: &Region) -> Option<(Affine, Option<u32>)> {
+    header.geotransform.map(|geotransform| (geotransform.translated(region.x, region.y), header.epsg))
+}
+
+impl<'a> ExtractorStrategy for EnviExtractorStrategy<'a> {
+    /// Extract an image from an ENVI raster to another file
+    ///
+    /// GeoTIFF outputs are georeferenced when the header has map info with
+    /// a known coordinate system.
+    ///
+    /// # Arguments
+    /// * `source_path` - Path to the ENVI header or data file
+    /// * `output_path` - Path where the extracted image should be saved
+    /// * `region` - Optional region to extract (if None, extracts the entire image)
+    /// * `shape` - Optional shape to use ("circle" or "square")
+    ///
+    /// # Returns
+    /// Result indicating success or an error with details
+    fn extract_to_file(&mut self, source_path: &str, output_path: &str,
+                       region: Option<Region>, shape: Option<&str>) -> TiffResult<()> {
+        info!("Extracting ENVI raster from {} to {}", source_path, output_path);
+
+        let format = OutputFormat::resolve(output_path, self.output_format);
+        if format == OutputFormat::Envi {
+            return self.extract_to_array(source_path, output_path, format.name(), region);
+        }
+
+        let image = self.extract_image(source_path, region)?;
+
+        if let Some(shape_str) = shape {
+            if shape_str.to_lowercase() == "circle" {
+                let masked = mask_utils::apply_shape_mask(&image, shape_str);
+                return mask_utils::save_shaped_image(&masked, output_path, shape_str, self.output_format);
+            }
+        }
+
+        if !format.is_tiff() {
+            return format.write_image(&image, output_path);
+        }
+
+        let (header, _) = self.open(source_path)?;
+        let extracted_region = region.unwrap_or_else(|| Region::new(0, 0, header.samples, header.lines));
+
+        let mut builder = TiffBuilder::new(self.logger, false);
+        let ifd_index = builder.add_ifd(IFD::new(0, 0));
+
+        match region_georeference(&header, &extracted_region) {
+            Some((geotransform, Some(epsg))) => builder.set_georeference(ifd_index, &geotransform, epsg)?,
+            Some((_, None)) => warn!("Coordinate system of {} is not known, writing the output without georeferencing", source_path),
+            None => info!("{} has no map info, writing the output without georeferencing", source_path),
+        }
+        tiff_extraction_utils::process_image_data(&image, &mut builder, ifd_index)?;
+
+        builder.write(output_path)?;
+
+        info!("Saved {}x{} image to {}", image.width(), image.height(), output_path);
+        Ok(())
+    }
+
+    /// Read an ENVI raster or a region of it to memory
+    ///
+    /// # Arguments
+    /// * `source_path` - Path to the ENVI header or data file
+    /// * `region` - Optional region to extract (if None, extracts the entire image)
+    ///
+    /// # Returns
+    /// Result containing the extracted image or an error
+    fn extract_image(&mut self, source_path: &str,
+                     region: Option<Region>) -> TiffResult<DynamicImage> {
+        let (header, _) = self.open(source_path)?;
+        let region = region.unwrap_or_else(|| Region::new(0, 0, header.samples, header.lines));
+
+        let (header, samples) = self.read_bands(source_path, &region)?;
+        to_dynamic_image(&header, samples, region.width, region.height)
+    }
+
+    /// Extract array data from an ENVI raster to another file
+    ///
+    /// # Arguments
+    /// * `source_path` - Path to the ENVI header or data file
+    /// * `output_path` - Path where the extracted array should be saved
+    /// * `format` - Format for the output (e.g., "csv", "json", "envi")
+    /// * `region` - Optional region to extract (if None, extracts the entire image)
+    ///
+    /// # Returns
+    /// Result indicating success or an error with details
+    fn extract_to_array(&mut self, source_path: &str, output_path: &str,
+                        format: &str, region: Option<Region>) -> TiffResult<()> {
+        info!("ENVI strategy: Converting raster to array format {}", format);
+        self.extract_array_data(source_path, region)?.save_to_file(output_path, format)
+    }
+
+    /// Extract the first band of an ENVI raster to memory
+    ///
+    /// Samples keep the raster's data type, with 64-bit integers read as
+    /// 64-bit floats.
+    ///
+    /// # Arguments
+    /// * `source_path` - Path to the ENVI header or data file
+    /// * `region` - Optional region to extract (if None, extracts the entire image)
+    ///
+    /// # Returns
+    /// Result containing the georeferenced array data or an error
+    fn extract_array_data(&mut self, source_path: &str,
+                          region: Option<Region>) -> TiffResult<ArrayData> {
+        let (header, data_path) = self.open(source_path)?;
+        let region = region.unwrap_or_else(|| Region::new(0, 0, header.samples, header.lines));
+        if header.bands > 1 {
+            info!("Extracting band 1 of {} as an array", header.bands);
+        }
+
+        let samples = envi_utils::read_window(&header, &data_path, &region, &[0])?;
+        let data = ArraySamples::from_values(&samples[0], envi_utils::sample_type(header.data_type));
+
+        Ok(ArrayData {
+            width: region.width,
+            height: region.height,
+            data,
+            bands: Vec::new(),
+            georeference: region_georeference(&header, &region)
+                .map(|(geotransform, epsg)| ArrayGeoreference { geotransform, epsg }),
+            nodata: header.nodata,
+        })
+    }
+
+    /// Write outputs in the requested format
+    fn set_output_format(&mut self, format: Option<OutputFormat>) {
+        self.output_format = format;
+    }
+
+    /// Check if this strategy supports the given file format
+    ///
+    /// # Arguments
+    /// * `file_path` - Path to check for ENVI format compatibility
+    ///
+    /// # Returns
+    /// `true` if this is an ENVI header or data file, `false` otherwise
+    fn supports_format(&self, file_path: &str) -> bool {
+        let extension = Path::new(file_path)
+            .extension()
+            .and_then(std::ffi::OsStr::to_str)
+            .unwrap_or("")
+            .to_lowercase();
+
+        matches!(extension.as_str(), "hdr" | "dat" | "envi")
+    }
+}
@@ -0,0 +1,152 @@
+//! Options of an extraction through the library API
+//!
+//! `RasterKit::extract` and `extract_to_buffer` take one `ExtractOptions`
+//! rather than a long list of positional parameters, most of them `None`.
+//! New options can be added as builder methods without breaking callers.
+//!
+//! Positions and systems are typed: a centre is either a `LonLat` in WGS84
+//! degrees or a `ProjectedXY` in the units of the options' `Crs`, so the
+//! axis order and units are settled when the options are built.
+
+use crate::coordinate::{BoundingBox, Crs, LonLat, ProjectedXY};
+use crate::utils::render_utils::StretchMode;
+
+use super::output_format::OutputFormat;
+
+/// Centre of a coordinate extraction
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub(crate) enum Centre {
+    /// WGS84 longitude and latitude
+    LonLat(LonLat),
+    /// Easting and northing in the options' CRS
+    Projected(ProjectedXY),
+}
+
+/// What to extract from a raster and how
+///
+/// Built with chained setters from `ExtractOptions::new()`, which extracts
+/// the whole image:
+///
+/// ```
+/// use rasterkit::coordinate::LonLat;
+/// use rasterkit::extractor::ExtractOptions;
+///
+/// let centre = LonLat::new(-109.22624, 56.13484).unwrap();
+/// let options = ExtractOptions::new()
+///     .coordinate(centre, 5000.0)
+///     .shape("circle")
+///     .colormap("colormap.sld");
+/// ```
+#[derive(Debug, Clone, Default)]
+pub struct ExtractOptions {
+    /// Pixel region (x, y, width, height)
+    pub(crate) region: Option<(u32, u32, u32, u32)>,
+    /// Bounding box in map coordinates
+    pub(crate) bbox: Option<BoundingBox>,
+    /// Centre position and radius in meters
+    pub(crate) coordinate: Option<(Centre, f64)>,
+    /// "circle" or "square"
+    pub(crate) shape: Option<String>,
+    /// System of the bounding box or projected centre
+    pub(crate) crs: Option<Crs>,
+    /// Path of a colormap applied to the values
+    pub(crate) colormap: Option<String>,
+    /// Value range kept, e.g. "15,160", and whether the rest turns transparent
+    pub(crate) filter: Option<(String, bool)>,
+    /// Stretch of the stored values to display levels
+    pub(crate) stretch: Option<StretchMode>,
+    /// One-based bands rendered, one for grayscale or three for RGB
+    pub(crate) bands: Vec<usize>,
+    /// Format of the output file instead of the one of its extension
+    pub(crate) output_format: Option<OutputFormat>,
+}
+
+impl ExtractOptions {
+    /// Options extracting the whole image as stored
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Extract a pixel region
+    ///
+    /// Takes precedence over a bounding box or coordinate.
+    pub fn region(mut self, x: u32, y: u32, width: u32, height: u32) -> Self {
+        self.region = Some((x, y, width, height));
+        self
+    }
+
+    /// Extract a bounding box
+    ///
+    /// The box is in the system set on it, or else the one of `crs`.
+    pub fn bbox(mut self, bbox: BoundingBox) -> Self {
+        self.bbox = Some(bbox);
+        self
+    }
+
+    /// Extract the area within a radius in meters of a WGS84 position
+    ///
+    /// Takes precedence over a bounding box. `crs` does not apply to it.
+    pub fn coordinate(mut self, centre: LonLat, radius: f64) -> Self {
+        self.coordinate = Some((Centre::LonLat(centre), radius));
+        self
+    }
+
+    /// Extract the area within a radius in meters of a projected position
+    ///
+    /// The position is in the system given with `crs`, which is required.
+    pub fn projected_coordinate(mut self, centre: ProjectedXY, radius: f64) -> Self {
+        self.coordinate = Some((Centre::Projected(centre), radius));
+        self
+    }
+
+    /// Cut the output to a "circle" or keep it "square"
+    pub fn shape(mut self, shape: &str) -> Self {
+        self.shape = Some(shape.to_string());
+        self
+    }
+
+    /// System of the bounding box or projected position, WGS84 by default
+    pub fn crs(mut self, crs: Crs) -> Self {
+        self.crs = Some(crs);
+        self
+    }
+
+    /// Colour the values with a colormap file (SLD, QML, ...)
+    pub fn colormap(mut self, path: &str) -> Self {
+        self.colormap = Some(path.to_string());
+        self
+    }
+
+    /// Keep only values in a range such as "15,160"
+    ///
+    /// Other pixels become black, or transparent if `transparent` is set.
+    pub fn filter(mut self, range: &str, transparent: bool) -> Self {
+        self.filter = Some((range.to_string(), transparent));
+        self
+    }
+
+    /// Render the stored values through a stretch
+    pub fn stretch(mut self, stretch: StretchMode) -> Self {
+        self.stretch = Some(stretch);
+        self
+    }
+
+    /// Render these one-based bands: one for grayscale, three for RGB
+    ///
+    /// Without a stretch, the bands are stretched linearly.
+    pub fn bands(mut self, bands: &[usize]) -> Self {
+        self.bands = bands.to_vec();
+        self
+    }
+
+    /// Write this format whatever the output extension
+    pub fn output_format(mut self, format: OutputFormat) -> Self {
+        self.output_format = Some(format);
+        self
+    }
+
+    /// Whether the output is rendered from the stored samples
+    pub(crate) fn renders(&self) -> bool {
+        self.stretch.is_some() || !self.bands.is_empty()
+    }
+}
@@ -3,14 +3,19 @@
 //! This module defines the strategy pattern for different image format extractors,
 //! allowing for extensible support of various file formats.
 
+use std::collections::HashMap;
 use std::path::Path;
+use std::sync::{Arc, RwLock};
 use image::DynamicImage;
+use lazy_static::lazy_static;
 use log::{info, debug, error};
 
 use crate::utils::logger::Logger;
 use crate::tiff::errors::{TiffError, TiffResult};
 
-use super::region::Region;
+use super::output_format::OutputFormat;
+use super::provenance::Provenance;
+use super::region::{Padding, Region};
 use super::array_strategy::ArrayData;
 
 /// Strategy for extracting images from different formats
@@ -66,6 +71,46 @@ pub trait ExtractorStrategy {
     fn extract_array_data(&mut self, source_path: &str,
                           region: Option<Region>) -> TiffResult<ArrayData>;
 
+    /// Pad extracted images back to the requested window
+    ///
+    /// Strategies that cannot pad their output leave this as a no-op.
+    ///
+    /// # Arguments
+    /// * `padding` - Padding for subsequent extractions, None to disable
+    fn set_padding(&mut self, _padding: Option<Padding>) {}
+
+    /// Keep decoded tiles and strips for later extractions from the same file
+    ///
+    /// Strategies without a block cache leave this as a no-op.
+    ///
+    /// # Arguments
+    /// * `enabled` - Whether to cache decoded blocks
+    fn set_block_caching(&mut self, _enabled: bool) {}
+
+    /// Record how subsequent outputs were produced
+    ///
+    /// Strategies whose outputs have no place for metadata ignore this.
+    ///
+    /// # Arguments
+    /// * `provenance` - Provenance to write, None to write none
+    fn set_provenance(&mut self, _provenance: Option<Provenance>) {}
+
+    /// Read pixels from a SubIFD of the main IFD instead of the main IFD
+    ///
+    /// Strategies for formats without SubIFDs leave this as a no-op.
+    ///
+    /// # Arguments
+    /// * `sub_ifd` - Index into the main IFD's SubIFDs, None for the main IFD
+    fn set_sub_ifd(&mut self, _sub_ifd: Option<usize>) {}
+
+    /// Write subsequent output files in a given format
+    ///
+    /// Strategies that only write one format leave this as a no-op.
+    ///
+    /// # Arguments
+    /// * `format` - Format to write, None to go by the output extension
+    fn set_output_format(&mut self, _format: Option<OutputFormat>) {}
+
     /// Check if this strategy supports the given file format
     ///
     /// # Arguments
@@ -85,10 +130,28 @@ pub trait ExtractorStrategy {
     }
 }
 
+/// Constructor of a registered strategy
+///
+/// Called with the logger to use and whether array extraction was
+/// requested, once for each source file of the format.
+type StrategyConstructor = Arc<dyn for<'l> Fn(&'l Logger, bool) -> Box<dyn ExtractorStrategy + 'l> + Send + Sync>;
+
+lazy_static! {
+    // Strategies registered at runtime, by lowercase file extension
+    static ref REGISTERED_STRATEGIES: RwLock<HashMap<String, StrategyConstructor>> =
+        RwLock::new(HashMap::new());
+}
+
+/// Normalize a file extension for lookups, such as ".IMG" to "img"
+fn normalize_extension(extension: &str) -> String {
+    extension.trim_start_matches('.').to_lowercase()
+}
+
 /// Factory for creating appropriate extractor strategies
 ///
 /// This factory examines file extensions and creates the appropriate
-/// strategy for handling that file format.
+/// strategy for handling that file format. Besides the built-in TIFF
+/// strategies, it creates strategies registered for other extensions.
 pub struct ExtractorStrategyFactory<'a> {
     /// Logger for recording operations
     logger: &'a Logger,
@@ -109,6 +172,79 @@ impl<'a> ExtractorStrategyFactory<'a> {
         }
     }
 
+    /// Register a strategy for additional file extensions
+    ///
+    /// Files with one of the extensions are then extracted with the
+    /// strategy everywhere extraction goes through the factory, including
+    /// `RasterKit::extract`. A registration takes precedence over the
+    /// built-in strategies and over earlier registrations of the extension.
+    ///
+    /// # Arguments
+    /// * `extensions` - File extensions the strategy handles, such as "img"
+    /// * `constructor` - Creates the strategy from the logger and whether
+    ///   array extraction was requested
+    ///
+    /// # Example
+    /// ```
+    /// use image::DynamicImage;
+    /// use rasterkit::extractor::{ArrayData, ExtractorStrategy, ExtractorStrategyFactory, Region};
+    /// use rasterkit::tiff::errors::{TiffError, TiffResult};
+    /// use rasterkit::utils::logger::Logger;
+    ///
+    /// struct BlankStrategy;
+    ///
+    /// impl ExtractorStrategy for BlankStrategy {
+    ///     fn extract_to_file(&mut self, source_path: &str, output_path: &str,
+    ///                        region: Option<Region>, _shape: Option<&str>) -> TiffResult<()> {
+    ///         let image = self.extract_image(source_path, region)?;
+    ///         image.save(output_path).map_err(|e| TiffError::GenericError(e.to_string()))
+    ///     }
+    ///     fn extract_image(&mut self, _source_path: &str, region: Option<Region>) -> TiffResult<DynamicImage> {
+    ///         let region = region.unwrap_or(Region::new(0, 0, 64, 64));
+    ///         Ok(DynamicImage::new_luma8(region.width, region.height))
+    ///     }
+    ///     fn extract_to_array(&mut self, _: &str, _: &str, _: &str, _: Option<Region>) -> TiffResult<()> {
+    ///         Err(TiffError::GenericError("No array data".to_string()))
+    ///     }
+    ///     fn extract_array_data(&mut self, _: &str, _: Option<Region>) -> TiffResult<ArrayData> {
+    ///         Err(TiffError::GenericError("No array data".to_string()))
+    ///     }
+    /// }
+    ///
+    /// ExtractorStrategyFactory::register(&["blank"], |_logger, _array| Box::new(BlankStrategy));
+    ///
+    /// let logger = Logger::new(std::env::temp_dir().join("blank.log").to_str().unwrap()).unwrap();
+    /// let factory = ExtractorStrategyFactory::new(&logger, false);
+    /// let mut strategy = factory.create_strategy("scene.BLANK").unwrap();
+    /// let image = strategy.extract_image("scene.BLANK", Some(Region::new(0, 0, 8, 4))).unwrap();
+    /// assert_eq!((image.width(), image.height()), (8, 4));
+    /// ```
+    pub fn register<F>(extensions: &[&str], constructor: F)
+    where
+        F: for<'l> Fn(&'l Logger, bool) -> Box<dyn ExtractorStrategy + 'l> + Send + Sync + 'static
+    {
+        let constructor: StrategyConstructor = Arc::new(constructor);
+        match REGISTERED_STRATEGIES.write() {
+            Ok(mut strategies) => {
+                for extension in extensions {
+                    info!("Registering extractor strategy for .{} files", normalize_extension(extension));
+                    strategies.insert(normalize_extension(extension), constructor.clone());
+                }
+            },
+            Err(_) => error!("Extractor strategy registry is poisoned, not registering {:?}", extensions),
+        }
+    }
+
+    /// Remove the strategy registered for a file extension
+    ///
+    /// # Returns
+    /// Whether a strategy was registered for the extension
+    pub fn unregister(extension: &str) -> bool {
+        REGISTERED_STRATEGIES.write()
+            .map(|mut strategies| strategies.remove(&normalize_extension(extension)).is_some())
+            .unwrap_or(false)
+    }
+
     /// Create an appropriate strategy for the given file path
     ///
     /// # Arguments
@@ -126,6 +262,13 @@ impl<'a> ExtractorStrategyFactory<'a> {
 
         debug!("Determining strategy for file extension: {}", extension);
 
+        let registered = REGISTERED_STRATEGIES.read().ok()
+            .and_then(|strategies| strategies.get(&extension).cloned());
+        if let Some(constructor) = registered {
+            info!("Using registered extractor strategy for {}", file_path);
+            return Ok(constructor(self.logger, self.use_array_extractor));
+        }
+
         // Create the appropriate strategy based on file extension and extraction mode
         match extension.as_str() {
             "tif" | "tiff" => {
@@ -137,7 +280,29 @@ impl<'a> ExtractorStrategyFactory<'a> {
                     Ok(Box::new(super::tiff_strategy::TiffExtractorStrategy::new(self.logger)))
                 }
             },
-            // Add more formats here as needed
+            // NITF decodes to an image in both modes
+            "ntf" | "nitf" | "nsf" => {
+                info!("Using NITF extractor strategy for {}", file_path);
+                Ok(Box::new(super::nitf_strategy::NitfExtractorStrategy::new(self.logger)))
+            },
+            // As does ENVI, given its header or its data file
+            "hdr" | "dat" | "envi" => {
+                info!("Using ENVI extractor strategy for {}", file_path);
+                Ok(Box::new(super::envi_strategy::EnviExtractorStrategy::new(self.logger)))
+            },
+            // So does JPEG2000
+            #[cfg(feature = "jpeg2000")]
+            "jp2" | "j2k" | "j2c" | "jpf" | "jpx" => {
+                info!("Using JPEG2000 extractor strategy for {}", file_path);
+                Ok(Box::new(super::jp2_strategy::Jpeg2000ExtractorStrategy::new(self.logger)))
+            },
+            #[cfg(not(feature = "jpeg2000"))]
+            "jp2" | "j2k" | "j2c" | "jpf" | "jpx" => {
+                error!("JPEG2000 support is not enabled");
+                Err(TiffError::GenericError(
+                    "JPEG2000 input requires building rasterkit with --features jpeg2000".to_string()))
+            },
+            // Other formats are handled by registered strategies
             _ => {
                 error!("Unsupported file format: {}", extension);
                 Err(TiffError::GenericError(format!("Unsupported file format: {}", extension)))
@@ -155,6 +320,18 @@ pub struct ImageExtractor<'a> {
     logger: &'a Logger,
     /// Factory for creating format-specific strategies
     factory: ExtractorStrategyFactory<'a>,
+    /// Padding applied to extracted regions, if any
+    padding: Option<Padding>,
+    /// Whether strategies keep decoded blocks between extractions
+    block_caching: bool,
+    /// Provenance written into outputs, if any
+    provenance: Option<Provenance>,
+    /// SubIFD of the main IFD to read from, if any
+    sub_ifd: Option<usize>,
+    /// Format of output files, None to go by the extension
+    output_format: Option<OutputFormat>,
+    /// Strategy of the last source file, reused while the file stays the same
+    strategy: Option<(String, Box<dyn ExtractorStrategy + 'a>)>,
 }
 
 impl<'a> ImageExtractor<'a> {
@@ -166,6 +343,12 @@ impl<'a> ImageExtractor<'a> {
         ImageExtractor {
             logger,
             factory: ExtractorStrategyFactory::new(logger, false),
+            padding: None,
+            block_caching: false,
+            provenance: None,
+            sub_ifd: None,
+            output_format: None,
+            strategy: None,
         }
     }
 
@@ -177,7 +360,85 @@ impl<'a> ImageExtractor<'a> {
         ImageExtractor {
             logger,
             factory: ExtractorStrategyFactory::new(logger, true),
+            padding: None,
+            block_caching: false,
+            provenance: None,
+            sub_ifd: None,
+            output_format: None,
+            strategy: None,
+        }
+    }
+
+    /// Pad extracted regions to a larger window
+    ///
+    /// Used when a bounding box runs off the image, so the output keeps the
+    /// requested size instead of shrinking to the overlap.
+    ///
+    /// # Arguments
+    /// * `padding` - Padding for subsequent extractions, None to disable
+    pub fn set_padding(&mut self, padding: Option<Padding>) {
+        self.padding = padding;
+    }
+
+    /// Decode each tile or strip at most once across extractions
+    ///
+    /// Meant for extracting many regions from one file; the decoded blocks
+    /// stay in memory until the extractor moves on to another file.
+    ///
+    /// # Arguments
+    /// * `enabled` - Whether to cache decoded blocks
+    pub fn set_block_caching(&mut self, enabled: bool) {
+        self.block_caching = enabled;
+    }
+
+    /// Record the provenance of subsequent outputs
+    ///
+    /// # Arguments
+    /// * `provenance` - Source, region and parameters of the extraction,
+    ///   None to leave outputs without a provenance record
+    pub fn set_provenance(&mut self, provenance: Option<Provenance>) {
+        self.provenance = provenance;
+    }
+
+    /// Extract from a SubIFD of the main IFD, such as a mask or an overview
+    ///
+    /// Regions stay in main-image pixels and are scaled to the SubIFD.
+    ///
+    /// # Arguments
+    /// * `sub_ifd` - Index into the main IFD's SubIFDs, None for the main IFD
+    pub fn set_sub_ifd(&mut self, sub_ifd: Option<usize>) {
+        self.sub_ifd = sub_ifd;
+    }
+
+    /// Override the output format otherwise taken from the extension
+    ///
+    /// # Arguments
+    /// * `format` - Format of subsequent output files, None to go by the extension
+    pub fn set_output_format(&mut self, format: Option<OutputFormat>) {
+        self.output_format = format;
+    }
+
+    /// Strategy for a source file, configured with the current options
+    ///
+    /// # Arguments
+    /// * `source_path` - Path to the source image file
+    ///
+    /// # Returns
+    /// The strategy, reused from the previous call for the same file
+    fn strategy_for(&mut self, source_path: &str) -> TiffResult<&mut (dyn ExtractorStrategy + 'a)> {
+        let reusable = matches!(&self.strategy, Some((path, _)) if path == source_path);
+        if !reusable {
+            self.strategy = Some((source_path.to_string(), self.factory.create_strategy(source_path)?));
         }
+
+        let (_, strategy) = self.strategy.as_mut()
+            .ok_or_else(|| TiffError::GenericError("No extraction strategy available".to_string()))?;
+        strategy.set_padding(self.padding);
+        strategy.set_block_caching(self.block_caching);
+        strategy.set_provenance(self.provenance.clone());
+        strategy.set_sub_ifd(self.sub_ifd);
+        strategy.set_output_format(self.output_format);
+        Ok(strategy.as_mut())
     }
 
     /// Extract an image region from a file to another file
@@ -194,8 +455,8 @@ impl<'a> ImageExtractor<'a> {
                            region: Option<Region>, shape: Option<&str>) -> TiffResult<()> {
         info!("Extracting from {} to {}", source_path, output_path);
 
-        // Create an appropriate strategy for this file format
-        let mut strategy = self.factory.create_strategy(source_path)?;
+        // Get an appropriate strategy for this file format
+        let strategy = self.strategy_for(source_path)?;
 
         // Delegate the extraction to the strategy
         strategy.extract_to_file(source_path, output_path, region, shape)
@@ -213,8 +474,8 @@ impl<'a> ImageExtractor<'a> {
                          region: Option<Region>) -> TiffResult<DynamicImage> {
         info!("Extracting image from {} to memory", source_path);
 
-        // Create an appropriate strategy for this file format
-        let mut strategy = self.factory.create_strategy(source_path)?;
+        // Get an appropriate strategy for this file format
+        let strategy = self.strategy_for(source_path)?;
 
         // Delegate the extraction to the strategy
         strategy.extract_image(source_path, region)
@@ -235,8 +496,8 @@ impl<'a> ImageExtractor<'a> {
         info!("Extracting array data from {} to {} in {} format",
               source_path, output_path, format);
 
-        // Create an appropriate strategy for this file format
-        let mut strategy = self.factory.create_strategy(source_path)?;
+        // Get an appropriate strategy for this file format
+        let strategy = self.strategy_for(source_path)?;
 
         // Delegate the extraction to the strategy
         strategy.extract_to_array(source_path, output_path, format, region)
@@ -254,8 +515,8 @@ impl<'a> ImageExtractor<'a> {
                               region: Option<Region>) -> TiffResult<ArrayData> {
         info!("Extracting array data from {} to memory", source_path);
 
-        // Create an appropriate strategy for this file format
-        let mut strategy = self.factory.create_strategy(source_path)?;
+        // Get an appropriate strategy for this file format
+        let strategy = self.strategy_for(source_path)?;
 
         // Delegate the extraction to the strategy
         strategy.extract_array_data(source_path, region)
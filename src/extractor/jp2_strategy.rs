@@ -0,0 +1,647 @@
+//! JPEG2000 extractor strategy implementation
+//!
+//! Decodes JP2 files and raw J2K codestreams with the OpenJPEG library,
+//! which has to be installed when rasterkit is built with the `jpeg2000`
+//! feature. Regions are decoded on their own, so OpenJPEG only reads the
+//! code-blocks covering them. Georeferencing stored in a GeoJP2 box is
+//! carried over to GeoTIFF outputs.
+
+use std::ffi::{c_char, c_void, CStr, CString};
+use std::fs::{self, File};
+use std::io::{Read, Seek, SeekFrom};
+use std::path::Path;
+use std::ptr;
+
+use image::{DynamicImage, ImageBuffer, Luma, LumaA, Rgb, Rgba};
+use log::{debug, info, warn};
+
+use crate::tiff::TiffBuilder;
+use crate::tiff::errors::{TiffError, TiffResult};
+use crate::tiff::ifd::IFD;
+use crate::utils::logger::Logger;
+use crate::utils::{mask_utils, reference_utils, tiff_extraction_utils};
+
+use super::array_strategy::ArrayData;
+use super::extractor_strategy::ExtractorStrategy;
+use super::output_format::OutputFormat;
+use super::region::Region;
+
+/// Signature box that starts every JP2 file
+const JP2_SIGNATURE: [u8; 12] = [0x00, 0x00, 0x00, 0x0C, 0x6A, 0x50, 0x20, 0x20, 0x0D, 0x0A, 0x87, 0x0A];
+
+/// SOC and SIZ markers that start a raw J2K codestream
+const J2K_SIGNATURE: [u8; 4] = [0xFF, 0x4F, 0xFF, 0x51];
+
+/// UUID of the GeoJP2 box, whose payload is a degenerate GeoTIFF
+const GEOJP2_UUID: [u8; 16] = [
+    0xB1, 0x4B, 0xF8, 0xBD, 0x08, 0x3D, 0x4B, 0x43,
+    0xA5, 0xAE, 0x8C, 0xD7, 0xD5, 0xA6, 0xCE, 0x03,
+];
+
+/// Bindings to the part of the OpenJPEG 2 API used for decoding
+mod ffi {
+    use std::ffi::{c_char, c_int, c_void};
+
+    pub const OPJ_CODEC_J2K: c_int = 0;
+    pub const OPJ_CODEC_JP2: c_int = 2;
+    pub const OPJ_CLRSPC_SYCC: c_int = 3;
+
+    /// Opaque `opj_dparameters_t`
+    ///
+    /// Its fields are never declared here, as their layout differs between
+    /// OpenJPEG releases; the library fills the structure in itself with
+    /// `opj_set_default_decoder_parameters`, whose defaults are what the
+    /// decoder needs.
+    #[repr(C)]
+    pub struct DecoderParameters {
+        _private: [u8; 0],
+    }
+
+    /// Bytes set aside for `opj_dparameters_t`, several times the size it
+    /// has in any OpenJPEG 2 release (about 8 KiB, mostly two file names)
+    pub const DECODER_PARAMETERS_SIZE: usize = 64 * 1024;
+
+    /// Zeroed, 8-byte aligned storage for the decoder parameters
+    pub fn decoder_parameters_storage() -> Vec<u64> {
+        vec![0; DECODER_PARAMETERS_SIZE / std::mem::size_of::<u64>()]
+    }
+
+    /// One component of a decoded image (`opj_image_comp_t`)
+    #[repr(C)]
+    pub struct ImageComponent {
+        pub dx: u32,
+        pub dy: u32,
+        pub w: u32,
+        pub h: u32,
+        pub x0: u32,
+        pub y0: u32,
+        pub prec: u32,
+        pub bpp: u32,
+        pub sgnd: u32,
+        pub resno_decoded: u32,
+        pub factor: u32,
+        pub data: *mut i32,
+        pub alpha: u16,
+    }
+
+    /// A decoded image (`opj_image_t`)
+    #[repr(C)]
+    pub struct Image {
+        pub x0: u32,
+        pub y0: u32,
+        pub x1: u32,
+        pub y1: u32,
+        pub numcomps: u32,
+        pub color_space: c_int,
+        pub comps: *mut ImageComponent,
+        pub icc_profile_buf: *mut u8,
+        pub icc_profile_len: u32,
+    }
+
+    /// Opaque `opj_stream_t`
+    #[repr(C)]
+    pub struct Stream {
+        _private: [u8; 0],
+    }
+
+    /// Opaque `opj_codec_t`
+    #[repr(C)]
+    pub struct Codec {
+        _private: [u8; 0],
+    }
+
+    pub type MessageCallback = extern "C" fn(*const c_char, *mut c_void);
+
+    #[link(name = "openjp2")]
+    extern "C" {
+        pub fn opj_stream_create_default_file_stream(fname: *const c_char, is_read_stream: c_int) -> *mut Stream;
+        pub fn opj_stream_destroy(stream: *mut Stream);
+        pub fn opj_create_decompress(format: c_int) -> *mut Codec;
+        pub fn opj_destroy_codec(codec: *mut Codec);
+        pub fn opj_set_default_decoder_parameters(parameters: *mut DecoderParameters);
+        pub fn opj_setup_decoder(codec: *mut Codec, parameters: *mut DecoderParameters) -> c_int;
+        pub fn opj_set_error_handler(codec: *mut Codec, handler: MessageCallback, client_data: *mut c_void) -> c_int;
+        pub fn opj_set_warning_handler(codec: *mut Codec, handler: MessageCallback, client_data: *mut c_void) -> c_int;
+        pub fn opj_read_header(stream: *mut Stream, codec: *mut Codec, image: *mut *mut Image) -> c_int;
+        pub fn opj_set_decoded_area(codec: *mut Codec, image: *mut Image,
+                                    start_x: i32, start_y: i32, end_x: i32, end_y: i32) -> c_int;
+        pub fn opj_decode(codec: *mut Codec, stream: *mut Stream, image: *mut Image) -> c_int;
+        pub fn opj_end_decompress(codec: *mut Codec, stream: *mut Stream) -> c_int;
+        pub fn opj_image_destroy(image: *mut Image);
+    }
+}
+
+/// Forward OpenJPEG errors to the log
+extern "C" fn log_error(message: *const c_char, _client_data: *mut c_void) {
+    if !message.is_null() {
+        let message = unsafe { CStr::from_ptr(message) };
+        warn!("OpenJPEG: {}", message.to_string_lossy().trim_end());
+    }
+}
+
+/// Forward OpenJPEG warnings to the log
+extern "C" fn log_warning(message: *const c_char, _client_data: *mut c_void) {
+    if !message.is_null() {
+        let message = unsafe { CStr::from_ptr(message) };
+        debug!("OpenJPEG: {}", message.to_string_lossy().trim_end());
+    }
+}
+
+/// OpenJPEG objects of one decode, released together
+struct Decoder {
+    stream: *mut ffi::Stream,
+    codec: *mut ffi::Codec,
+    image: *mut ffi::Image,
+}
+
+impl Drop for Decoder {
+    fn drop(&mut self) {
+        unsafe {
+            if !self.image.is_null() {
+                ffi::opj_image_destroy(self.image);
+            }
+            if !self.codec.is_null() {
+                ffi::opj_destroy_codec(self.codec);
+            }
+            if !self.stream.is_null() {
+                ffi::opj_stream_destroy(self.stream);
+            }
+        }
+    }
+}
+
+/// Components of a decoded image or region
+struct DecodedImage {
+    width: u32,
+    height: u32,
+    /// Samples of each component, row by row
+    components: Vec<Vec<i32>>,
+    /// Bits per sample
+    precision: u32,
+    /// Whether samples are signed
+    signed: bool,
+    /// Whether the components are YCbCr rather than RGB
+    sycc: bool,
+    /// Whether the last component is an alpha channel
+    has_alpha: bool,
+}
+
+/// Kind of JPEG2000 data a file holds
+#[derive(Clone, Copy, PartialEq)]
+enum Container {
+    /// JP2 file format, with boxes around the codestream
+    Jp2,
+    /// Raw J2K codestream
+    J2k,
+}
+
+/// Detect whether a file is a JP2 file or a raw codestream
+///
+/// # Arguments
+/// * `path` - Path to the file
+///
+/// # Returns
+/// The container, or an error if the file is not JPEG2000
+fn detect_container(path: &str) -> TiffResult<Container> {
+    let mut header = [0u8; 12];
+    let read = File::open(path)?.read(&mut header)?;
+
+    if read >= JP2_SIGNATURE.len() && header == JP2_SIGNATURE {
+        Ok(Container::Jp2)
+    } else if read >= J2K_SIGNATURE.len() && header[..4] == J2K_SIGNATURE {
+        Ok(Container::J2k)
+    } else {
+        Err(TiffError::GenericError(format!("{} is not a JPEG2000 file", path)))
+    }
+}
+
+/// Find the GeoTIFF embedded in a JP2 file's GeoJP2 box
+///
+/// Walks the top-level boxes, skipping over the codestream, so only box
+/// headers are read.
+///
+/// # Arguments
+/// * `path` - Path to the JP2 file
+///
+/// # Returns
+/// The embedded TIFF, or None if the file has no GeoJP2 box
+fn read_geojp2(path: &str) -> TiffResult<Option<Vec<u8>>> {
+    let mut file = File::open(path)?;
+    let file_length = file.metadata()?.len();
+    let mut position = 0u64;
+
+    while position + 8 <= file_length {
+        file.seek(SeekFrom::Start(position))?;
+        let mut header = [0u8; 8];
+        file.read_exact(&mut header)?;
+
+        let mut box_length = u32::from_be_bytes([header[0], header[1], header[2], header[3]]) as u64;
+        let box_type = [header[4], header[5], header[6], header[7]];
+        let mut header_length = 8u64;
+
+        if box_length == 1 {
+            // The length follows as a 64-bit value
+            let mut extended = [0u8; 8];
+            file.read_exact(&mut extended)?;
+            box_length = u64::from_be_bytes(extended);
+            header_length = 16;
+        } else if box_length == 0 {
+            // The last box runs to the end of the file
+            box_length = file_length - position;
+        }
+
+        if box_length < header_length || position + box_length > file_length {
+            warn!("Malformed JP2 box at offset {}, ignoring the rest of the file", position);
+            break;
+        }
+
+        if &box_type == b"uuid" && box_length >= header_length + 16 {
+            let mut uuid = [0u8; 16];
+            file.read_exact(&mut uuid)?;
+            if uuid == GEOJP2_UUID {
+                let mut payload = vec![0u8; (box_length - header_length - 16) as usize];
+                file.read_exact(&mut payload)?;
+                info!("Found GeoJP2 box with {} bytes of GeoTIFF", payload.len());
+                return Ok(Some(payload));
+            }
+        }
+
+        position += box_length;
+    }
+
+    Ok(None)
+}
+
+/// Decode an image or a region of it with OpenJPEG
+///
+/// # Arguments
+/// * `path` - Path to the JP2 or J2K file
+/// * `region` - Region to decode in image pixels, None for the whole image
+///
+/// # Returns
+/// The decoded components
+fn decode(path: &str, region: Option<Region>) -> TiffResult<DecodedImage> {
+    let codec_format = match detect_container(path)? {
+        Container::Jp2 => ffi::OPJ_CODEC_JP2,
+        Container::J2k => ffi::OPJ_CODEC_J2K,
+    };
+    let c_path = CString::new(path)
+        .map_err(|_| TiffError::GenericError(format!("Invalid path: {}", path)))?;
+
+    let mut decoder = Decoder { stream: ptr::null_mut(), codec: ptr::null_mut(), image: ptr::null_mut() };
+
+    unsafe {
+        decoder.stream = ffi::opj_stream_create_default_file_stream(c_path.as_ptr(), 1);
+        if decoder.stream.is_null() {
+            return Err(TiffError::GenericError(format!("OpenJPEG could not open {}", path)));
+        }
+
+        decoder.codec = ffi::opj_create_decompress(codec_format);
+        if decoder.codec.is_null() {
+            return Err(TiffError::GenericError("Failed to create OpenJPEG decoder".to_string()));
+        }
+        ffi::opj_set_error_handler(decoder.codec, log_error, ptr::null_mut());
+        ffi::opj_set_warning_handler(decoder.codec, log_warning, ptr::null_mut());
+
+        let mut storage = ffi::decoder_parameters_storage();
+        let parameters = storage.as_mut_ptr() as *mut ffi::DecoderParameters;
+        ffi::opj_set_default_decoder_parameters(parameters);
+        if ffi::opj_setup_decoder(decoder.codec, parameters) == 0 {
+            return Err(TiffError::GenericError("Failed to set up OpenJPEG decoder".to_string()));
+        }
+
+        if ffi::opj_read_header(decoder.stream, decoder.codec, &mut decoder.image) == 0 || decoder.image.is_null() {
+            return Err(TiffError::GenericError(format!("Failed to read JPEG2000 header of {}", path)));
+        }
+
+        let image = &*decoder.image;
+        let (full_width, full_height) = (image.x1 - image.x0, image.y1 - image.y0);
+        info!("JPEG2000 image: {}x{} with {} components", full_width, full_height, image.numcomps);
+
+        if let Some(region) = region {
+            if region.width == 0 || region.height == 0
+                || region.x.saturating_add(region.width) > full_width
+                || region.y.saturating_add(region.height) > full_height {
+                return Err(TiffError::GenericError(format!(
+                    "Region {}x{} at ({}, {}) is outside the {}x{} image",
+                    region.width, region.height, region.x, region.y, full_width, full_height)));
+            }
+
+            // The decoded area is given on the reference grid
+            let start_x = (image.x0 + region.x) as i32;
+            let start_y = (image.y0 + region.y) as i32;
+            if ffi::opj_set_decoded_area(decoder.codec, decoder.image, start_x, start_y,
+                                         start_x + region.width as i32, start_y + region.height as i32) == 0 {
+                return Err(TiffError::GenericError("OpenJPEG rejected the region to decode".to_string()));
+            }
+        }
+
+        if ffi::opj_decode(decoder.codec, decoder.stream, decoder.image) == 0
+            || ffi::opj_end_decompress(decoder.codec, decoder.stream) == 0 {
+            return Err(TiffError::GenericError(format!("Failed to decode JPEG2000 data of {}", path)));
+        }
+
+        let image = &*decoder.image;
+        if image.numcomps == 0 || image.comps.is_null() {
+            return Err(TiffError::GenericError("JPEG2000 image has no components".to_string()));
+        }
+        let components = std::slice::from_raw_parts(image.comps, image.numcomps as usize);
+
+        // Subsampled components would need resampling to a common grid
+        if components.iter().any(|component| component.dx != 1 || component.dy != 1) {
+            return Err(TiffError::GenericError(
+                "Subsampled JPEG2000 components are not supported".to_string()));
+        }
+
+        let (width, height) = (components[0].w, components[0].h);
+        let pixel_count = width as usize * height as usize;
+        let mut samples = Vec::with_capacity(components.len());
+        for component in components {
+            if component.w != width || component.h != height || component.data.is_null() {
+                return Err(TiffError::GenericError(
+                    "JPEG2000 components differ in size".to_string()));
+            }
+            samples.push(std::slice::from_raw_parts(component.data, pixel_count).to_vec());
+        }
+
+        Ok(DecodedImage {
+            width,
+            height,
+            components: samples,
+            precision: components[0].prec,
+            signed: components[0].sgnd != 0,
+            sycc: image.color_space == ffi::OPJ_CLRSPC_SYCC && components.len() >= 3,
+            has_alpha: components.last().map(|component| component.alpha != 0).unwrap_or(false),
+        })
+    }
+}
+
+/// Convert decoded components to an image
+///
+/// Samples of up to 8 bits become 8-bit images, deeper ones 16-bit images.
+/// Signed samples are shifted into the unsigned range. Components beyond
+/// RGB and alpha are dropped.
+///
+/// # Arguments
+/// * `decoded` - The decoded components
+///
+/// # Returns
+/// The image, or an error if the samples don't fit the buffer
+fn to_dynamic_image(mut decoded: DecodedImage) -> TiffResult<DynamicImage> {
+    let precision = decoded.precision.clamp(1, 31);
+    let offset = if decoded.signed { 1i64 << (precision - 1) } else { 0 };
+
+    if decoded.sycc {
+        info!("Converting sYCC components to RGB");
+        ycc_to_rgb(&mut decoded.components, precision, decoded.signed);
+    }
+
+    // Pick the components that make up the output bands
+    let band_count = match (decoded.components.len(), decoded.has_alpha) {
+        (1, _) => 1,
+        (2, true) => 2,
+        (2, false) => 1,
+        (3, _) => 3,
+        (_, true) => 4,
+        _ => 3,
+    };
+    if band_count < decoded.components.len() {
+        warn!("Keeping {} of {} JPEG2000 components", band_count, decoded.components.len());
+    }
+    if band_count == 4 {
+        let alpha = decoded.components.pop().unwrap_or_default();
+        decoded.components.truncate(3);
+        decoded.components.push(alpha);
+    } else {
+        decoded.components.truncate(band_count);
+    }
+
+    // Interleave the bands, shifting samples wider than 16 bits down
+    let shift = precision.saturating_sub(16);
+    let max_value = (1i64 << (precision - shift)) - 1;
+    let pixel_count = decoded.width as usize * decoded.height as usize;
+    let mut interleaved = Vec::with_capacity(pixel_count * band_count);
+    for pixel in 0..pixel_count {
+        for component in &decoded.components {
+            let value = ((component[pixel] as i64 + offset) >> shift).clamp(0, max_value);
+            interleaved.push(value as u16);
+        }
+    }
+
+    let (width, height) = (decoded.width, decoded.height);
+    let buffer_error = || TiffError::GenericError("Decoded JPEG2000 data does not fit the image".to_string());
+
+    if precision <= 8 {
+        let data: Vec<u8> = interleaved.into_iter().map(|value| value as u8).collect();
+        Ok(match band_count {
+            1 => DynamicImage::ImageLuma8(ImageBuffer::<Luma<u8>, _>::from_raw(width, height, data).ok_or_else(buffer_error)?),
+            2 => DynamicImage::ImageLumaA8(ImageBuffer::<LumaA<u8>, _>::from_raw(width, height, data).ok_or_else(buffer_error)?),
+            3 => DynamicImage::ImageRgb8(ImageBuffer::<Rgb<u8>, _>::from_raw(width, height, data).ok_or_else(buffer_error)?),
+            _ => DynamicImage::ImageRgba8(ImageBuffer::<Rgba<u8>, _>::from_raw(width, height, data).ok_or_else(buffer_error)?),
+        })
+    } else {
+        Ok(match band_count {
+            1 => DynamicImage::ImageLuma16(ImageBuffer::<Luma<u16>, _>::from_raw(width, height, interleaved).ok_or_else(buffer_error)?),
+            2 => DynamicImage::ImageLumaA16(ImageBuffer::<LumaA<u16>, _>::from_raw(width, height, interleaved).ok_or_else(buffer_error)?),
+            3 => DynamicImage::ImageRgb16(ImageBuffer::<Rgb<u16>, _>::from_raw(width, height, interleaved).ok_or_else(buffer_error)?),
+            _ => DynamicImage::ImageRgba16(ImageBuffer::<Rgba<u16>, _>::from_raw(width, height, interleaved).ok_or_else(buffer_error)?),
+        })
+    }
+}
+
+/// Convert the first three components from YCbCr to RGB in place
+///
+/// # Arguments
+/// * `components` - Y, Cb and Cr followed by any other components
+/// * `precision` - Bits per sample
+/// * `signed` - Whether samples are signed
+fn ycc_to_rgb(components: &mut [Vec<i32>], precision: u32, signed: bool) {
+    let (luma_offset, chroma_offset) = if signed {
+        (1i64 << (precision - 1), 0)
+    } else {
+        (0, 1i64 << (precision - 1))
+    };
+    let max_value = ((1i64 << precision) - 1 - luma_offset) as f64;
+    let min_value = -luma_offset as f64;
+
+    let (luma, rest) = components.split_at_mut(1);
+    let (cb, cr) = rest.split_at_mut(1);
+    for ((y, cb), cr) in luma[0].iter_mut().zip(cb[0].iter_mut()).zip(cr[0].iter_mut()) {
+        let (luma, blue, red) = (*y as f64, (*cb as i64 - chroma_offset) as f64, (*cr as i64 - chroma_offset) as f64);
+        *y = (luma + 1.402 * red).round().clamp(min_value, max_value) as i32;
+        *cb = (luma - 0.344136 * blue - 0.714136 * red).round().clamp(min_value, max_value) as i32;
+        *cr = (luma + 1.772 * blue).round().clamp(min_value, max_value) as i32;
+    }
+}
+
+/// JPEG2000 extractor implementation
+///
+/// Handles JP2 files and J2K codestreams in image as well as array mode.
+/// Outputs are written like TIFF extractions, with the GeoJP2
+/// georeferencing in place of the GeoTIFF tags of a TIFF source.
+pub struct Jpeg2000ExtractorStrategy<'a> {
+    /// Logger for recording operations
+    logger: &'a Logger,
+    /// Requested output format, None to go by the extension
+    output_format: Option<OutputFormat>,
+}
+
+impl<'a> Jpeg2000ExtractorStrategy<'a> {
+    /// Create a new JPEG2000 extractor strategy
+    ///
+    /// # Arguments
+    /// * `logger` - Logger for recording operations
+    pub fn new(logger: &'a Logger) -> Self {
+        Jpeg2000ExtractorStrategy {
+            logger,
+            output_format: None,
+        }
+    }
+
+    /// Copy the GeoJP2 georeferencing of the source to a TIFF being built
+    ///
+    /// The embedded GeoTIFF goes through a temporary file so it can be read
+    /// like any other georeferenced TIFF.
+    fn add_georeferencing(&self, source_path: &str, builder: &mut TiffBuilder,
+                          ifd_index: usize, region: &Region) -> TiffResult<()> {
+        if detect_container(source_path)? != Container::Jp2 {
+            return Ok(());
+        }
+        let geotiff = match read_geojp2(source_path)? {
+            Some(geotiff) => geotiff,
+            None => {
+                info!("No GeoJP2 box in {}, writing the output without georeferencing", source_path);
+                return Ok(());
+            }
+        };
+
+        let temp_path = std::env::temp_dir().join(format!("rasterkit_geojp2_{}.tif", std::process::id()));
+        let temp_path_str = temp_path.to_string_lossy().to_string();
+        fs::write(&temp_path, geotiff)?;
+        let result = reference_utils::add_georeferencing_to_builder(
+            builder, ifd_index, region, &temp_path_str, self.logger);
+        if let Err(e) = fs::remove_file(&temp_path) {
+            warn!("Failed to remove temporary file {}: {}", temp_path_str, e);
+        }
+
+        if let Err(e) = result {
+            warn!("Ignoring unreadable GeoJP2 box in {}: {}", source_path, e);
+        }
+        Ok(())
+    }
+}
+
+impl<'a> ExtractorStrategy for Jpeg2000ExtractorStrategy<'a> {
+    /// Extract an image from a JPEG2000 file to another file
+    ///
+    /// # Arguments
+    /// * `source_path` - Path to the JP2 or J2K file
+    /// * `output_path` - Path where the extracted image should be saved
+    /// * `region` - Optional region to extract (if None, extracts the entire image)
+    /// * `shape` - Optional shape to use ("circle" or "square")
+    ///
+    /// # Returns
+    /// Result indicating success or an error with details
+    fn extract_to_file(&mut self, source_path: &str, output_path: &str,
+                       region: Option<Region>, shape: Option<&str>) -> TiffResult<()> {
+        info!("Extracting JPEG2000 image from {} to {}", source_path, output_path);
+
+        let image = self.extract_image(source_path, region)?;
+
+        if let Some(shape_str) = shape {
+            if shape_str.to_lowercase() == "circle" {
+                let masked = mask_utils::apply_shape_mask(&image, shape_str);
+                return mask_utils::save_shaped_image(&masked, output_path, shape_str, self.output_format);
+            }
+        }
+
+        let format = OutputFormat::resolve(output_path, self.output_format);
+        if !format.is_tiff() {
+            return format.write_image(&image, output_path);
+        }
+
+        let extracted_region = region.unwrap_or_else(|| Region::new(0, 0, image.width(), image.height()));
+
+        let mut builder = TiffBuilder::new(self.logger, false);
+        let ifd_index = builder.add_ifd(IFD::new(0, 0));
+
+        self.add_georeferencing(source_path, &mut builder, ifd_index, &extracted_region)?;
+        tiff_extraction_utils::process_image_data(&image, &mut builder, ifd_index)?;
+
+        builder.write(output_path)?;
+
+        info!("Saved {}x{} image to {}", image.width(), image.height(), output_path);
+        Ok(())
+    }
+
+    /// Decode a JPEG2000 image or a region of it to memory
+    ///
+    /// # Arguments
+    /// * `source_path` - Path to the JP2 or J2K file
+    /// * `region` - Optional region to extract (if None, extracts the entire image)
+    ///
+    /// # Returns
+    /// Result containing the extracted image or an error
+    fn extract_image(&mut self, source_path: &str,
+                     region: Option<Region>) -> TiffResult<DynamicImage> {
+        if let Some(region) = region {
+            info!("Decoding JPEG2000 region: ({}, {}) with size {}x{}",
+                  region.x, region.y, region.width, region.height);
+        }
+
+        let decoded = decode(source_path, region)?;
+        to_dynamic_image(decoded)
+    }
+
+    /// Extract array data from a JPEG2000 file to another file
+    ///
+    /// # Arguments
+    /// * `source_path` - Path to the JP2 or J2K file
+    /// * `output_path` - Path where the extracted array should be saved
+    /// * `format` - Format for the output (e.g., "csv", "json", "npy")
+    /// * `region` - Optional region to extract (if None, extracts the entire image)
+    ///
+    /// # Returns
+    /// Result indicating success or an error with details
+    fn extract_to_array(&mut self, source_path: &str, output_path: &str,
+                        format: &str, region: Option<Region>) -> TiffResult<()> {
+        info!("JPEG2000 strategy: Converting image to array format {}", format);
+        self.extract_array_data(source_path, region)?.save_to_file(output_path, format)
+    }
+
+    /// Extract array data from a JPEG2000 file to memory
+    ///
+    /// # Arguments
+    /// * `source_path` - Path to the JP2 or J2K file
+    /// * `region` - Optional region to extract (if None, extracts the entire image)
+    ///
+    /// # Returns
+    /// Result containing the extracted array data or an error
+    fn extract_array_data(&mut self, source_path: &str,
+                          region: Option<Region>) -> TiffResult<ArrayData> {
+        let image = self.extract_image(source_path, region)?;
+        Ok(ArrayData::from_image(&image))
+    }
+
+    /// Write outputs in the requested format
+    fn set_output_format(&mut self, format: Option<OutputFormat>) {
+        self.output_format = format;
+    }
+
+    /// Check if this strategy supports the given file format
+    ///
+    /// # Arguments
+    /// * `file_path` - Path to check for JPEG2000 format compatibility
+    ///
+    /// # Returns
+    /// `true` if the extension is one of JPEG2000's, `false` otherwise
+    fn supports_format(&self, file_path: &str) -> bool {
+        let extension = Path::new(file_path)
+            .extension()
+            .and_then(std::ffi::OsStr::to_str)
+            .unwrap_or("")
+            .to_lowercase();
+
+        matches!(extension.as_str(), "jp2" | "j2k" | "j2c" | "jpf" | "jpx")
+    }
+}
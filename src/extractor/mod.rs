@@ -8,13 +8,39 @@ mod extractor_strategy;
 mod tiff_strategy;
 mod tile_reader;
 mod strip_reader;
+pub(crate) mod block_cache;
+mod provenance;
 mod array_strategy;
+mod array_samples;
+mod output_format;
+mod nitf_reader;
+mod nitf_strategy;
+mod envi_strategy;
+mod buffer_decode;
+mod windowed;
+mod extract_options;
+#[cfg(feature = "jpeg2000")]
+mod jp2_strategy;
 
 // Public exports
-pub use region::Region;
+pub use region::{Coverage, Padding, Region};
+pub use provenance::{file_checksum, Provenance};
 pub use extractor_strategy::{ExtractorStrategy, ExtractorStrategyFactory};
 pub use tiff_strategy::TiffExtractorStrategy;
-pub use array_strategy::{ArrayExtractorStrategy, ArrayData};
+pub use array_strategy::{ArrayExtractorStrategy, ArrayData, ArrayGeoreference};
+pub use array_samples::ArraySamples;
+pub use output_format::{ImageEncoder, OutputFormat};
+pub use nitf_strategy::NitfExtractorStrategy;
+pub use envi_strategy::EnviExtractorStrategy;
+pub use buffer_decode::{BufferLayout, DecodedWindow};
+pub use extract_options::ExtractOptions;
+pub(crate) use extract_options::Centre;
+pub use crate::utils::render_utils::StretchMode;
+pub(crate) use buffer_decode::{decode_into_bytes, decode_into_f32, describe_window};
+pub use windowed::WindowBlock;
+pub(crate) use windowed::extract_windowed;
+#[cfg(feature = "jpeg2000")]
+pub use jp2_strategy::Jpeg2000ExtractorStrategy;
 
 // Simple facade that delegates to the appropriate strategy
 pub use extractor_strategy::ImageExtractor;
\ No newline at end of file
@@ -0,0 +1,700 @@
+//! NITF container parsing
+//!
+//! Reads the file header and image subheaders of NITF 2.1 and NSIF 1.0
+//! files and decodes uncompressed image segments, masked or not, block by
+//! block. Georeferencing comes from the RPC00B (or older RPC00A) tagged
+//! record extension when a segment has one, and from the IGEOLO corner
+//! coordinates otherwise.
+
+use std::fs::File;
+use std::io::{Read, Seek, SeekFrom};
+use std::str::FromStr;
+
+use log::{debug, info, warn};
+
+use crate::coordinate::Affine;
+use crate::tiff::errors::{TiffError, TiffResult};
+
+use super::region::Region;
+
+/// Offset of the header length field (HL) in a NITF 2.1 file header
+const HEADER_LENGTH_OFFSET: usize = 354;
+
+/// Offset of the image segment count (NUMI) in a NITF 2.1 file header
+const IMAGE_COUNT_OFFSET: usize = 360;
+
+/// Block mask table entry of blocks that are not recorded
+const BLOCK_NOT_RECORDED: u32 = 0xFFFF_FFFF;
+
+/// EPSG code of WGS 84, the datum of NITF geographic coordinates
+const WGS84_EPSG: u32 = 4326;
+
+/// Sequential reader for the fixed-width fields of NITF headers
+struct FieldCursor<'d> {
+    data: &'d [u8],
+    position: usize,
+}
+
+impl<'d> FieldCursor<'d> {
+    fn new(data: &'d [u8]) -> Self {
+        FieldCursor { data, position: 0 }
+    }
+
+    /// Raw bytes of the next field
+    fn bytes(&mut self, length: usize) -> TiffResult<&'d [u8]> {
+        let end = self.position + length;
+        if end > self.data.len() {
+            return Err(TiffError::GenericError(format!(
+                "NITF header ends at byte {}, expected a field up to byte {}", self.data.len(), end)));
+        }
+        let field = &self.data[self.position..end];
+        self.position = end;
+        Ok(field)
+    }
+
+    /// Next field as text, without padding
+    fn text(&mut self, length: usize) -> TiffResult<String> {
+        Ok(String::from_utf8_lossy(self.bytes(length)?).trim().to_string())
+    }
+
+    /// Next field as a number
+    fn number<T: FromStr>(&mut self, length: usize, name: &str) -> TiffResult<T> {
+        let text = self.text(length)?;
+        text.parse().map_err(|_| TiffError::GenericError(format!("Invalid NITF {} field: '{}'", name, text)))
+    }
+
+    fn skip(&mut self, length: usize) -> TiffResult<()> {
+        self.bytes(length).map(|_| ())
+    }
+}
+
+/// Rational polynomial coefficients of a sensor model
+///
+/// Maps ground positions (longitude, latitude, height) to image lines and
+/// samples as ratios of cubic polynomials in normalized coordinates.
+#[derive(Debug, Clone)]
+pub struct RpcModel {
+    line_offset: f64,
+    sample_offset: f64,
+    latitude_offset: f64,
+    longitude_offset: f64,
+    height_offset: f64,
+    line_scale: f64,
+    sample_scale: f64,
+    latitude_scale: f64,
+    longitude_scale: f64,
+    height_scale: f64,
+    line_numerator: [f64; 20],
+    line_denominator: [f64; 20],
+    sample_numerator: [f64; 20],
+    sample_denominator: [f64; 20],
+    /// Whether the terms follow RPC00A rather than RPC00B order
+    order_a: bool,
+}
+
+impl RpcModel {
+    /// Parse the data of an RPC00A or RPC00B extension
+    ///
+    /// # Arguments
+    /// * `data` - The extension's data, without tag and length
+    /// * `order_a` - Whether the extension is RPC00A
+    fn parse(data: &[u8], order_a: bool) -> TiffResult<Self> {
+        let mut cursor = FieldCursor::new(data);
+        if cursor.text(1)? != "1" {
+            return Err(TiffError::GenericError("RPC extension is flagged as invalid".to_string()));
+        }
+        cursor.skip(14)?; // ERR_BIAS, ERR_RAND
+
+        let coefficients = |cursor: &mut FieldCursor| -> TiffResult<[f64; 20]> {
+            let mut values = [0.0; 20];
+            for value in values.iter_mut() {
+                *value = cursor.number(12, "RPC coefficient")?;
+            }
+            Ok(values)
+        };
+
+        Ok(RpcModel {
+            line_offset: cursor.number(6, "LINE_OFF")?,
+            sample_offset: cursor.number(5, "SAMP_OFF")?,
+            latitude_offset: cursor.number(8, "LAT_OFF")?,
+            longitude_offset: cursor.number(9, "LONG_OFF")?,
+            height_offset: cursor.number(5, "HEIGHT_OFF")?,
+            line_scale: cursor.number(6, "LINE_SCALE")?,
+            sample_scale: cursor.number(5, "SAMP_SCALE")?,
+            latitude_scale: cursor.number(8, "LAT_SCALE")?,
+            longitude_scale: cursor.number(9, "LONG_SCALE")?,
+            height_scale: cursor.number(5, "HEIGHT_SCALE")?,
+            line_numerator: coefficients(&mut cursor)?,
+            line_denominator: coefficients(&mut cursor)?,
+            sample_numerator: coefficients(&mut cursor)?,
+            sample_denominator: coefficients(&mut cursor)?,
+            order_a,
+        })
+    }
+
+    /// Evaluate one of the cubic polynomials
+    fn polynomial(&self, c: &[f64; 20], l: f64, p: f64, h: f64) -> f64 {
+        let mut value = c[0] + c[1] * l + c[2] * p + c[3] * h + c[4] * l * p + c[5] * l * h + c[6] * p * h;
+        if self.order_a {
+            value += c[7] * p * l * h + c[8] * l * l + c[9] * p * p + c[10] * h * h;
+        } else {
+            value += c[7] * l * l + c[8] * p * p + c[9] * h * h + c[10] * p * l * h;
+        }
+        value + c[11] * l * l * l + c[12] * l * p * p + c[13] * l * h * h + c[14] * l * l * p
+            + c[15] * p * p * p + c[16] * p * h * h + c[17] * l * l * h + c[18] * p * p * h + c[19] * h * h * h
+    }
+
+    /// Image position of a ground position
+    ///
+    /// # Returns
+    /// (sample, line) with (0, 0) at the centre of the first pixel
+    pub fn ground_to_image(&self, longitude: f64, latitude: f64, height: f64) -> (f64, f64) {
+        let l = (longitude - self.longitude_offset) / self.longitude_scale;
+        let p = (latitude - self.latitude_offset) / self.latitude_scale;
+        let h = (height - self.height_offset) / self.height_scale;
+
+        let line = self.polynomial(&self.line_numerator, l, p, h) / self.polynomial(&self.line_denominator, l, p, h);
+        let sample = self.polynomial(&self.sample_numerator, l, p, h) / self.polynomial(&self.sample_denominator, l, p, h);
+        (sample * self.sample_scale + self.sample_offset, line * self.line_scale + self.line_offset)
+    }
+
+    /// Ground position of an image position at the model's mean height
+    ///
+    /// Inverts the model with Newton's method, starting from the centre of
+    /// the model's ground area.
+    ///
+    /// # Returns
+    /// (longitude, latitude), or None if the iteration does not converge
+    pub fn image_to_ground(&self, sample: f64, line: f64) -> Option<(f64, f64)> {
+        let height = self.height_offset;
+        let (mut longitude, mut latitude) = (self.longitude_offset, self.latitude_offset);
+        let (step_longitude, step_latitude) = (self.longitude_scale * 1e-6, self.latitude_scale * 1e-6);
+
+        for _ in 0..30 {
+            let (s, l) = self.ground_to_image(longitude, latitude, height);
+            let (error_s, error_l) = (sample - s, line - l);
+            if error_s.abs() < 1e-8 && error_l.abs() < 1e-8 {
+                return Some((longitude, latitude));
+            }
+
+            let (s_lon, l_lon) = self.ground_to_image(longitude + step_longitude, latitude, height);
+            let (s_lat, l_lat) = self.ground_to_image(longitude, latitude + step_latitude, height);
+            let (ds_dlon, dl_dlon) = ((s_lon - s) / step_longitude, (l_lon - l) / step_longitude);
+            let (ds_dlat, dl_dlat) = ((s_lat - s) / step_latitude, (l_lat - l) / step_latitude);
+
+            let det = ds_dlon * dl_dlat - ds_dlat * dl_dlon;
+            if det == 0.0 || !det.is_finite() {
+                return None;
+            }
+            longitude += (error_s * dl_dlat - error_l * ds_dlat) / det;
+            latitude += (error_l * ds_dlon - error_s * dl_dlon) / det;
+        }
+
+        None
+    }
+}
+
+/// Band of an image segment
+#[derive(Debug, Clone)]
+pub struct NitfBand {
+    /// Band representation (IREPBAND), such as "R", "G", "B" or "M"
+    pub representation: String,
+    /// Lookup tables mapping sample values to output values, if any
+    pub luts: Vec<Vec<u8>>,
+}
+
+/// An image segment of a NITF file
+#[derive(Debug, Clone)]
+pub struct NitfImage {
+    /// Offset of the image data from the start of the file
+    data_offset: u64,
+    /// Number of rows (NROWS)
+    pub rows: u32,
+    /// Number of columns (NCOLS)
+    pub columns: u32,
+    /// Pixel value type (PVTYPE): INT, SI, R, C or B
+    pub pixel_type: String,
+    /// Image representation (IREP), such as MONO, RGB or MULTI
+    pub representation: String,
+    /// Compression (IC); only NC and NM are decoded
+    pub compression: String,
+    /// Bands in stored order
+    pub bands: Vec<NitfBand>,
+    /// Interleave mode (IMODE): B, P, R or S
+    pub mode: char,
+    /// Blocks per row (NBPR)
+    pub blocks_per_row: u32,
+    /// Blocks per column (NBPC)
+    pub blocks_per_column: u32,
+    /// Block width in pixels (NPPBH)
+    pub block_width: u32,
+    /// Block height in pixels (NPPBV)
+    pub block_height: u32,
+    /// Bits per stored sample (NBPP)
+    pub bits_per_pixel: u32,
+    /// Coordinate system of the corners (ICORDS), blank for none
+    pub corner_system: char,
+    /// Corner coordinates (IGEOLO)
+    pub corners: String,
+    /// Rational polynomial sensor model, if the segment has one
+    pub rpc: Option<RpcModel>,
+}
+
+impl NitfImage {
+    /// Parse an image subheader
+    ///
+    /// # Arguments
+    /// * `subheader` - The subheader bytes
+    /// * `data_offset` - Offset of the segment's image data in the file
+    fn parse(subheader: &[u8], data_offset: u64) -> TiffResult<Self> {
+        let mut cursor = FieldCursor::new(subheader);
+        if cursor.text(2)? != "IM" {
+            return Err(TiffError::GenericError("Image segment does not start with an IM subheader".to_string()));
+        }
+        // IID1, IDATIM, TGTID, IID2, ISCLAS and the security fields, ENCRYP, ISORCE
+        cursor.skip(10 + 14 + 17 + 80 + 1 + 166 + 1 + 42)?;
+
+        let rows: u32 = cursor.number(8, "NROWS")?;
+        let columns: u32 = cursor.number(8, "NCOLS")?;
+        let pixel_type = cursor.text(3)?;
+        let representation = cursor.text(8)?;
+        cursor.skip(8 + 2 + 1)?; // ICAT, ABPP, PJUST
+
+        let corner_system = cursor.bytes(1)?[0] as char;
+        let corners = if corner_system == ' ' {
+            String::new()
+        } else {
+            String::from_utf8_lossy(cursor.bytes(60)?).to_string()
+        };
+
+        let comment_count: usize = cursor.number(1, "NICOM")?;
+        cursor.skip(80 * comment_count)?;
+
+        let compression = cursor.text(2)?;
+        if compression != "NC" && compression != "NM" {
+            cursor.skip(4)?; // COMRAT
+        }
+
+        let mut band_count: usize = cursor.number(1, "NBANDS")?;
+        if band_count == 0 {
+            band_count = cursor.number(5, "XBANDS")?;
+        }
+        let mut bands = Vec::with_capacity(band_count);
+        for _ in 0..band_count {
+            let representation = cursor.text(2)?;
+            cursor.skip(6 + 1 + 3)?; // ISUBCAT, IFC, IMFLT
+            let lut_count: usize = cursor.number(1, "NLUTS")?;
+            let mut luts = Vec::with_capacity(lut_count);
+            if lut_count > 0 {
+                let entries: usize = cursor.number(5, "NELUT")?;
+                for _ in 0..lut_count {
+                    luts.push(cursor.bytes(entries)?.to_vec());
+                }
+            }
+            bands.push(NitfBand { representation, luts });
+        }
+
+        cursor.skip(1)?; // ISYNC
+        let mode = cursor.bytes(1)?[0] as char;
+        let blocks_per_row: u32 = cursor.number(4, "NBPR")?;
+        let blocks_per_column: u32 = cursor.number(4, "NBPC")?;
+        let mut block_width: u32 = cursor.number(4, "NPPBH")?;
+        let mut block_height: u32 = cursor.number(4, "NPPBV")?;
+        let bits_per_pixel: u32 = cursor.number(2, "NBPP")?;
+        cursor.skip(3 + 3 + 10 + 4)?; // IDLVL, IALVL, ILOC, IMAG
+
+        // Images wider or taller than 8192 pixels in one block store 0
+        if block_width == 0 {
+            block_width = columns;
+        }
+        if block_height == 0 {
+            block_height = rows;
+        }
+
+        let user_data_length: usize = cursor.number(5, "UDIDL")?;
+        if user_data_length > 0 {
+            cursor.skip(user_data_length)?;
+        }
+
+        let mut rpc = None;
+        let extension_length: usize = cursor.number(5, "IXSHDL")?;
+        if extension_length > 3 {
+            cursor.skip(3)?; // IXSOFL
+            let mut extensions = FieldCursor::new(cursor.bytes(extension_length - 3)?);
+            while extensions.position + 11 <= extensions.data.len() {
+                let tag = extensions.text(6)?;
+                let length: usize = extensions.number(5, "CEL")?;
+                let data = extensions.bytes(length)?;
+                debug!("Image subheader extension {} ({} bytes)", tag, length);
+                if tag == "RPC00B" || tag == "RPC00A" {
+                    match RpcModel::parse(data, tag == "RPC00A") {
+                        Ok(model) => rpc = Some(model),
+                        Err(e) => warn!("Ignoring {} extension: {}", tag, e),
+                    }
+                }
+            }
+        }
+
+        Ok(NitfImage {
+            data_offset,
+            rows,
+            columns,
+            pixel_type,
+            representation,
+            compression,
+            bands,
+            mode,
+            blocks_per_row,
+            blocks_per_column,
+            block_width,
+            block_height,
+            bits_per_pixel,
+            corner_system,
+            corners,
+            rpc,
+        })
+    }
+
+    /// Check that the image data can be decoded
+    fn check_supported(&self) -> TiffResult<()> {
+        if self.compression != "NC" && self.compression != "NM" {
+            return Err(TiffError::GenericError(format!(
+                "NITF compression {} is not supported, only uncompressed image segments are", self.compression)));
+        }
+        if self.bits_per_pixel != 8 && self.bits_per_pixel != 16 {
+            return Err(TiffError::GenericError(format!(
+                "NITF images with {} bits per pixel are not supported", self.bits_per_pixel)));
+        }
+        if !matches!(self.pixel_type.as_str(), "INT" | "SI") {
+            return Err(TiffError::GenericError(format!(
+                "NITF pixel type {} is not supported", self.pixel_type)));
+        }
+        if !matches!(self.mode, 'B' | 'P' | 'R' | 'S') {
+            return Err(TiffError::GenericError(format!("Unknown NITF interleave mode {}", self.mode)));
+        }
+        if self.bands.is_empty() || self.block_width == 0 || self.block_height == 0 {
+            return Err(TiffError::GenericError("NITF image segment has no bands or empty blocks".to_string()));
+        }
+        Ok(())
+    }
+
+    /// Size in bytes of one stored block, of one band in band sequential mode
+    fn block_size(&self) -> u64 {
+        let bands = if self.mode == 'S' { 1 } else { self.bands.len() as u64 };
+        self.block_width as u64 * self.block_height as u64 * bands * (self.bits_per_pixel / 8) as u64
+    }
+
+    /// Offsets of the stored blocks relative to the start of the blocks
+    ///
+    /// Masked images start with a table of block offsets, in which blocks
+    /// that were left out are marked; unmasked images store every block.
+    ///
+    /// # Returns
+    /// The offset of the first block and each block's offset, None for blocks
+    /// that are not recorded
+    fn block_offsets(&self, file: &mut File) -> TiffResult<(u64, Vec<Option<u64>>)> {
+        let block_count = self.blocks_per_row as usize * self.blocks_per_column as usize
+            * if self.mode == 'S' { self.bands.len() } else { 1 };
+
+        if self.compression == "NC" {
+            let offsets = (0..block_count as u64).map(|block| Some(block * self.block_size())).collect();
+            return Ok((self.data_offset, offsets));
+        }
+
+        file.seek(SeekFrom::Start(self.data_offset))?;
+        let mut table_header = [0u8; 10];
+        file.read_exact(&mut table_header)?;
+        let blocks_offset = u32::from_be_bytes([table_header[0], table_header[1], table_header[2], table_header[3]]);
+        let block_record_length = u16::from_be_bytes([table_header[4], table_header[5]]);
+        let pad_code_length = u16::from_be_bytes([table_header[8], table_header[9]]);
+        file.seek(SeekFrom::Current(pad_code_length.div_ceil(8) as i64))?;
+
+        if block_record_length == 0 {
+            // Only pad pixels are masked, every block is stored
+            let offsets = (0..block_count as u64).map(|block| Some(block * self.block_size())).collect();
+            return Ok((self.data_offset + blocks_offset as u64, offsets));
+        }
+
+        let mut table = vec![0u8; block_count * 4];
+        file.read_exact(&mut table)?;
+        let offsets = table.chunks_exact(4)
+            .map(|entry| u32::from_be_bytes([entry[0], entry[1], entry[2], entry[3]]))
+            .map(|offset| (offset != BLOCK_NOT_RECORDED).then_some(offset as u64))
+            .collect();
+        Ok((self.data_offset + blocks_offset as u64, offsets))
+    }
+
+    /// Read the samples of a region
+    ///
+    /// # Arguments
+    /// * `file` - The open NITF file
+    /// * `region` - Region to read, inside the image
+    ///
+    /// # Returns
+    /// The samples of each band, row by row; blocks that are not recorded
+    /// read as zeros
+    pub fn read_region(&self, file: &mut File, region: &Region) -> TiffResult<Vec<Vec<u16>>> {
+        self.check_supported()?;
+        if region.width == 0 || region.height == 0
+            || region.x.saturating_add(region.width) > self.columns
+            || region.y.saturating_add(region.height) > self.rows {
+            return Err(TiffError::GenericError(format!(
+                "Region {}x{} at ({}, {}) is outside the {}x{} image",
+                region.width, region.height, region.x, region.y, self.columns, self.rows)));
+        }
+
+        let (blocks_start, offsets) = self.block_offsets(file)?;
+        let band_count = self.bands.len();
+        let bytes_per_sample = (self.bits_per_pixel / 8) as usize;
+        let (block_width, block_height) = (self.block_width as usize, self.block_height as usize);
+        let blocks_per_band = self.blocks_per_row as usize * self.blocks_per_column as usize;
+        let pixel_count = region.width as usize * region.height as usize;
+        let mut samples = vec![vec![0u16; pixel_count]; band_count];
+
+        let first_block_column = region.x / self.block_width;
+        let last_block_column = (region.x + region.width - 1) / self.block_width;
+        let first_block_row = region.y / self.block_height;
+        let last_block_row = (region.y + region.height - 1) / self.block_height;
+        let mut block = vec![0u8; self.block_size() as usize];
+
+        for block_row in first_block_row..=last_block_row {
+            for block_column in first_block_column..=last_block_column {
+                let block_index = (block_row * self.blocks_per_row + block_column) as usize;
+                let stored_bands: Vec<usize> = if self.mode == 'S' { (0..band_count).collect() } else { vec![0] };
+
+                for stored_band in stored_bands {
+                    let Some(Some(offset)) = offsets.get(stored_band * blocks_per_band + block_index).copied() else {
+                        continue;
+                    };
+                    file.seek(SeekFrom::Start(blocks_start + offset))?;
+                    file.read_exact(&mut block)?;
+
+                    // Pixels of the block that fall inside the region
+                    let block_x = (block_column * self.block_width) as usize;
+                    let block_y = (block_row * self.block_height) as usize;
+                    let x_range = (region.x as usize).max(block_x)..((region.x + region.width) as usize).min(block_x + block_width);
+                    let y_range = (region.y as usize).max(block_y)..((region.y + region.height) as usize).min(block_y + block_height);
+
+                    for y in y_range {
+                        let (in_block_y, out_y) = (y - block_y, y - region.y as usize);
+                        for x in x_range.clone() {
+                            let (in_block_x, out_x) = (x - block_x, x - region.x as usize);
+                            let out_index = out_y * region.width as usize + out_x;
+
+                            for (band, band_samples) in samples.iter_mut().enumerate() {
+                                let sample_index = match self.mode {
+                                    'S' if band != stored_band => continue,
+                                    'S' => in_block_y * block_width + in_block_x,
+                                    'P' => (in_block_y * block_width + in_block_x) * band_count + band,
+                                    'R' => (in_block_y * band_count + band) * block_width + in_block_x,
+                                    _ => (band * block_height + in_block_y) * block_width + in_block_x,
+                                };
+                                let byte_index = sample_index * bytes_per_sample;
+                                band_samples[out_index] = if bytes_per_sample == 1 {
+                                    block[byte_index] as u16
+                                } else {
+                                    u16::from_be_bytes([block[byte_index], block[byte_index + 1]])
+                                };
+                            }
+                        }
+                    }
+                }
+            }
+        }
+
+        Ok(samples)
+    }
+
+    /// Geotransform and CRS of a region
+    ///
+    /// RPC models are approximated by the affine transform that fits them
+    /// best over the region, at the model's mean height; corner coordinates
+    /// give one transform for the whole image.
+    ///
+    /// # Arguments
+    /// * `region` - The extracted region
+    ///
+    /// # Returns
+    /// The geotransform anchored at the region's top-left corner and the
+    /// EPSG code of its CRS, or None if the segment is not georeferenced
+    pub fn georeference(&self, region: &Region) -> Option<(Affine, u32)> {
+        if let Some(rpc) = &self.rpc {
+            let mut points = Vec::with_capacity(25);
+            for i in 0..5 {
+                for j in 0..5 {
+                    let column = region.width as f64 * i as f64 / 4.0;
+                    let row = region.height as f64 * j as f64 / 4.0;
+                    // RPC positions have (0, 0) at the centre of the first pixel
+                    let sample = region.x as f64 + column - 0.5;
+                    let line = region.y as f64 + row - 0.5;
+                    if let Some(ground) = rpc.image_to_ground(sample, line) {
+                        points.push(((column, row), ground));
+                    }
+                }
+            }
+            match Affine::from_control_points(&points) {
+                Some(geotransform) => {
+                    info!("Approximating the RPC model with an affine transform at height {}", rpc.height_offset);
+                    return Some((geotransform, WGS84_EPSG));
+                },
+                None => warn!("RPC model could not be inverted, falling back to the corner coordinates"),
+            }
+        }
+
+        let (corners, epsg) = self.parse_corners()?;
+        let (columns, rows) = (self.columns as f64, self.rows as f64);
+
+        // IGEOLO locates the centres of the corner pixels
+        let pixels = [(0.5, 0.5), (columns - 0.5, 0.5), (columns - 0.5, rows - 0.5), (0.5, rows - 0.5)];
+        let points: Vec<_> = pixels.into_iter().zip(corners).collect();
+        let geotransform = Affine::from_control_points(&points)?;
+
+        // A transform through three corners that misses the fourth means
+        // the footprint is not a parallelogram
+        let (x, y) = geotransform.apply(pixels[2].0, pixels[2].1);
+        let tolerance = geotransform.pixel_width.abs().max(geotransform.pixel_height.abs()) * 2.0;
+        if (x - corners[2].0).abs() > tolerance || (y - corners[2].1).abs() > tolerance {
+            warn!("NITF corner coordinates don't form a parallelogram, the georeferencing is approximate");
+        }
+
+        Some((geotransform.translated(region.x, region.y), epsg))
+    }
+
+    /// Parse IGEOLO into map coordinates
+    ///
+    /// # Returns
+    /// The four corners as (x, y) in the order upper left, upper right,
+    /// lower right, lower left, and the EPSG code they are in
+    fn parse_corners(&self) -> Option<([(f64, f64); 4], u32)> {
+        if self.corner_system == ' ' || self.corners.len() < 60 {
+            return None;
+        }
+
+        let fields: Vec<&str> = (0..4).map(|i| &self.corners[i * 15..(i + 1) * 15]).collect();
+        let mut corners = [(0.0, 0.0); 4];
+        let mut epsg = WGS84_EPSG;
+
+        for (i, field) in fields.iter().enumerate() {
+            corners[i] = match self.corner_system {
+                'G' => {
+                    let latitude = parse_dms(&field[0..6], &field[6..7])?;
+                    let longitude = parse_dms(&field[7..14], &field[14..15])?;
+                    (longitude, latitude)
+                },
+                'D' => (field[7..15].trim().parse().ok()?, field[0..7].trim().parse().ok()?),
+                'N' | 'S' => {
+                    let zone: u32 = field[0..2].trim().parse().ok()?;
+                    let zone_epsg = if self.corner_system == 'N' { 32600 + zone } else { 32700 + zone };
+                    if i == 0 {
+                        epsg = zone_epsg;
+                    } else if zone_epsg != epsg {
+                        warn!("NITF corners span several UTM zones, using the zone of the first corner");
+                        return None;
+                    }
+                    (field[2..8].trim().parse().ok()?, field[8..15].trim().parse().ok()?)
+                },
+                other => {
+                    warn!("NITF corner coordinates of type '{}' are not supported", other);
+                    return None;
+                },
+            };
+        }
+
+        Some((corners, epsg))
+    }
+}
+
+/// Parse a degrees-minutes-seconds field such as "354530" with "N"
+///
+/// # Arguments
+/// * `digits` - ddmmss or dddmmss
+/// * `hemisphere` - N, S, E or W
+///
+/// # Returns
+/// Decimal degrees, negative south and west
+fn parse_dms(digits: &str, hemisphere: &str) -> Option<f64> {
+    let degree_digits = digits.len() - 4;
+    let degrees: f64 = digits[..degree_digits].parse().ok()?;
+    let minutes: f64 = digits[degree_digits..degree_digits + 2].parse().ok()?;
+    let seconds: f64 = digits[degree_digits + 2..].parse().ok()?;
+    let value = degrees + minutes / 60.0 + seconds / 3600.0;
+
+    match hemisphere {
+        "N" | "E" => Some(value),
+        "S" | "W" => Some(-value),
+        _ => None,
+    }
+}
+
+/// A parsed NITF file
+#[derive(Debug, Clone)]
+pub struct NitfFile {
+    /// Image segments in file order
+    pub images: Vec<NitfImage>,
+}
+
+impl NitfFile {
+    /// Read the headers of a NITF file
+    ///
+    /// # Arguments
+    /// * `path` - Path to the file
+    ///
+    /// # Returns
+    /// The parsed file, or an error if it is not a NITF 2.1 or NSIF 1.0 file
+    pub fn open(path: &str) -> TiffResult<Self> {
+        let mut file = File::open(path)?;
+        let file_length = file.metadata()?.len();
+
+        let mut start = [0u8; IMAGE_COUNT_OFFSET];
+        file.read_exact(&mut start).map_err(|_| TiffError::GenericError(format!(
+            "{} is too short to be a NITF file", path)))?;
+
+        let version = String::from_utf8_lossy(&start[0..9]).to_string();
+        match version.as_str() {
+            "NITF02.10" | "NSIF01.00" => {},
+            "NITF02.00" => return Err(TiffError::GenericError(
+                "NITF 2.0 files are not supported, only NITF 2.1 and NSIF 1.0".to_string())),
+            _ => return Err(TiffError::GenericError(format!("{} is not a NITF file", path))),
+        }
+
+        let header_length: u64 = FieldCursor::new(&start[HEADER_LENGTH_OFFSET..IMAGE_COUNT_OFFSET])
+            .number(6, "HL")?;
+        file.seek(SeekFrom::Start(0))?;
+        let mut header = vec![0u8; header_length as usize];
+        file.read_exact(&mut header)?;
+
+        let mut cursor = FieldCursor::new(&header);
+        cursor.skip(IMAGE_COUNT_OFFSET)?;
+        let image_count: usize = cursor.number(3, "NUMI")?;
+        let mut lengths = Vec::with_capacity(image_count);
+        for _ in 0..image_count {
+            let subheader_length: u64 = cursor.number(6, "LISH")?;
+            let data_length: u64 = cursor.number(10, "LI")?;
+            lengths.push((subheader_length, data_length));
+        }
+
+        let mut images = Vec::with_capacity(image_count);
+        let mut offset = header_length;
+        for (subheader_length, data_length) in lengths {
+            if offset + subheader_length + data_length > file_length {
+                warn!("Image segment at offset {} runs past the end of the file", offset);
+            }
+            file.seek(SeekFrom::Start(offset))?;
+            let mut subheader = vec![0u8; subheader_length as usize];
+            file.read_exact(&mut subheader)?;
+
+            let image = NitfImage::parse(&subheader, offset + subheader_length)?;
+            info!("NITF image segment {}: {}x{}, {} band(s), {} bits, IREP {}, IC {}, IMODE {}",
+                  images.len(), image.columns, image.rows, image.bands.len(), image.bits_per_pixel,
+                  image.representation, image.compression, image.mode);
+            images.push(image);
+            offset += subheader_length + data_length;
+        }
+
+        if images.is_empty() {
+            return Err(TiffError::GenericError(format!("{} has no image segments", path)));
+        }
+
+        Ok(NitfFile { images })
+    }
+}
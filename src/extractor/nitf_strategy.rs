@@ -0,0 +1,255 @@
+//! NITF extractor strategy implementation
+//!
+//! Extracts the first image segment of NITF 2.1 and NSIF 1.0 files in
+//! image and array mode. GeoTIFF outputs and arrays are georeferenced from
+//! the segment's RPC model or corner coordinates, see `nitf_reader`.
+
+use std::fs::File;
+use std::path::Path;
+
+use image::{DynamicImage, ImageBuffer, Luma, Rgb};
+use log::{info, warn};
+
+use crate::tiff::TiffBuilder;
+use crate::tiff::errors::{TiffError, TiffResult};
+use crate::tiff::ifd::IFD;
+use crate::utils::logger::Logger;
+use crate::utils::{mask_utils, tiff_extraction_utils};
+
+use super::array_strategy::{ArrayData, ArrayGeoreference};
+use super::extractor_strategy::ExtractorStrategy;
+use super::nitf_reader::{NitfFile, NitfImage};
+use super::output_format::OutputFormat;
+use super::region::Region;
+
+/// NITF format extractor implementation
+///
+/// Reads uncompressed image segments, picking the red, green and blue
+/// bands of multispectral imagery and applying lookup tables of
+/// palette images.
+pub struct NitfExtractorStrategy<'a> {
+    /// Logger for recording operations
+    logger: &'a Logger,
+    /// Requested output format, None to go by the extension
+    output_format: Option<OutputFormat>,
+    /// Headers of the last file read, by path
+    file: Option<(String, NitfFile)>,
+}
+
+impl<'a> NitfExtractorStrategy<'a> {
+    /// Create a new NITF extractor strategy
+    ///
+    /// # Arguments
+    /// * `logger` - Logger for recording operations
+    pub fn new(logger: &'a Logger) -> Self {
+        NitfExtractorStrategy {
+            logger,
+            output_format: None,
+            file: None,
+        }
+    }
+
+    /// The image segment to extract, parsing the headers once per file
+    fn image_segment(&mut self, source_path: &str) -> TiffResult<NitfImage> {
+        if !matches!(&self.file, Some((path, _)) if path == source_path) {
+            let file = NitfFile::open(source_path)?;
+            if file.images.len() > 1 {
+                info!("{} has {} image segments, extracting the first", source_path, file.images.len());
+            }
+            self.file = Some((source_path.to_string(), file));
+        }
+
+        self.file.as_ref()
+            .and_then(|(_, file)| file.images.first().cloned())
+            .ok_or_else(|| TiffError::GenericError(format!("{} has no image segments", source_path)))
+    }
+}
+
+/// Turn the samples of an image segment into an image
+///
+/// # Arguments
+/// * `image` - The image segment the samples were read from
+/// * `samples` - Samples of each band
+/// * `width` - Width of the region
+/// * `height` - Height of the region
+///
+/// # Returns
+/// An RGB image for color imagery and palette images, grayscale otherwise
+fn to_dynamic_image(image: &NitfImage, mut samples: Vec<Vec<u16>>,
+                    width: u32, height: u32) -> TiffResult<DynamicImage> {
+    // Shift signed samples into the unsigned range
+    if image.pixel_type == "SI" {
+        let sign_bit = 1u16 << (image.bits_per_pixel - 1);
+        samples.iter_mut().flatten().for_each(|sample| *sample ^= sign_bit);
+    }
+
+    let band_index = |name: &str| image.bands.iter().position(|band| band.representation == name);
+    let buffer_error = || TiffError::GenericError("NITF samples do not fit the image".to_string());
+
+    // Palette images map their single band through three lookup tables
+    if let [band] = image.bands.as_slice() {
+        if band.luts.len() >= 3 && image.bits_per_pixel == 8 {
+            info!("Applying NITF lookup tables");
+            let data = samples[0].iter()
+                .flat_map(|&index| band.luts[..3].iter().map(move |lut| lut.get(index as usize).copied().unwrap_or(0)))
+                .collect();
+            return Ok(DynamicImage::ImageRgb8(
+                ImageBuffer::<Rgb<u8>, _>::from_raw(width, height, data).ok_or_else(buffer_error)?));
+        }
+    }
+
+    let selected = match (band_index("R"), band_index("G"), band_index("B")) {
+        (Some(red), Some(green), Some(blue)) => vec![red, green, blue],
+        _ => {
+            if image.bands.len() > 1 {
+                warn!("No red, green and blue bands among the {} NITF bands, extracting the first", image.bands.len());
+            }
+            vec![0]
+        },
+    };
+
+    let pixel_count = width as usize * height as usize;
+    let interleaved: Vec<u16> = (0..pixel_count)
+        .flat_map(|pixel| selected.iter().map(|&band| samples[band][pixel]).collect::<Vec<_>>())
+        .collect();
+
+    Ok(match (image.bits_per_pixel, selected.len()) {
+        (8, 1) => DynamicImage::ImageLuma8(ImageBuffer::<Luma<u8>, _>::from_raw(
+            width, height, interleaved.into_iter().map(|v| v as u8).collect()).ok_or_else(buffer_error)?),
+        (8, _) => DynamicImage::ImageRgb8(ImageBuffer::<Rgb<u8>, _>::from_raw(
+            width, height, interleaved.into_iter().map(|v| v as u8).collect()).ok_or_else(buffer_error)?),
+        (_, 1) => DynamicImage::ImageLuma16(ImageBuffer::<Luma<u16>, _>::from_raw(
+            width, height, interleaved).ok_or_else(buffer_error)?),
+        _ => DynamicImage::ImageRgb16(ImageBuffer::<Rgb<u16>, _>::from_raw(
+            width, height, interleaved).ok_or_else(buffer_error)?),
+    })
+}
+
+impl<'a> ExtractorStrategy for NitfExtractorStrategy<'a> {
+    /// Extract an image from a NITF file to another file
+    ///
+    /// GeoTIFF outputs are georeferenced when the image segment is.
+    ///
+    /// # Arguments
+    /// * `source_path` - Path to the NITF file
+    /// * `output_path` - Path where the extracted image should be saved
+    /// * `region` - Optional region to extract (if None, extracts the entire image)
+    /// * `shape` - Optional shape to use ("circle" or "square")
+    ///
+    /// # Returns
+    /// Result indicating success or an error with details
+    fn extract_to_file(&mut self, source_path: &str, output_path: &str,
+                       region: Option<Region>, shape: Option<&str>) -> TiffResult<()> {
+        info!("Extracting NITF image from {} to {}", source_path, output_path);
+
+        let image = self.extract_image(source_path, region)?;
+
+        if let Some(shape_str) = shape {
+            if shape_str.to_lowercase() == "circle" {
+                let masked = mask_utils::apply_shape_mask(&image, shape_str);
+                return mask_utils::save_shaped_image(&masked, output_path, shape_str, self.output_format);
+            }
+        }
+
+        let format = OutputFormat::resolve(output_path, self.output_format);
+        if !format.is_tiff() {
+            return format.write_image(&image, output_path);
+        }
+
+        let segment = self.image_segment(source_path)?;
+        let extracted_region = region.unwrap_or_else(|| Region::new(0, 0, segment.columns, segment.rows));
+
+        let mut builder = TiffBuilder::new(self.logger, false);
+        let ifd_index = builder.add_ifd(IFD::new(0, 0));
+
+        match segment.georeference(&extracted_region) {
+            Some((geotransform, epsg)) => builder.set_georeference(ifd_index, &geotransform, epsg)?,
+            None => info!("{} is not georeferenced, writing the output without georeferencing", source_path),
+        }
+        tiff_extraction_utils::process_image_data(&image, &mut builder, ifd_index)?;
+
+        builder.write(output_path)?;
+
+        info!("Saved {}x{} image to {}", image.width(), image.height(), output_path);
+        Ok(())
+    }
+
+    /// Read a NITF image segment or a region of it to memory
+    ///
+    /// # Arguments
+    /// * `source_path` - Path to the NITF file
+    /// * `region` - Optional region to extract (if None, extracts the entire image)
+    ///
+    /// # Returns
+    /// Result containing the extracted image or an error
+    fn extract_image(&mut self, source_path: &str,
+                     region: Option<Region>) -> TiffResult<DynamicImage> {
+        let segment = self.image_segment(source_path)?;
+        let region = region.unwrap_or_else(|| Region::new(0, 0, segment.columns, segment.rows));
+
+        info!("Extracting NITF region: ({}, {}) with size {}x{}",
+              region.x, region.y, region.width, region.height);
+
+        let mut file = File::open(source_path)?;
+        let samples = segment.read_region(&mut file, &region)?;
+        to_dynamic_image(&segment, samples, region.width, region.height)
+    }
+
+    /// Extract array data from a NITF file to another file
+    ///
+    /// # Arguments
+    /// * `source_path` - Path to the NITF file
+    /// * `output_path` - Path where the extracted array should be saved
+    /// * `format` - Format for the output (e.g., "csv", "json", "npy")
+    /// * `region` - Optional region to extract (if None, extracts the entire image)
+    ///
+    /// # Returns
+    /// Result indicating success or an error with details
+    fn extract_to_array(&mut self, source_path: &str, output_path: &str,
+                        format: &str, region: Option<Region>) -> TiffResult<()> {
+        info!("NITF strategy: Converting image to array format {}", format);
+        self.extract_array_data(source_path, region)?.save_to_file(output_path, format)
+    }
+
+    /// Extract array data from a NITF file to memory
+    ///
+    /// # Arguments
+    /// * `source_path` - Path to the NITF file
+    /// * `region` - Optional region to extract (if None, extracts the entire image)
+    ///
+    /// # Returns
+    /// Result containing the georeferenced array data or an error
+    fn extract_array_data(&mut self, source_path: &str,
+                          region: Option<Region>) -> TiffResult<ArrayData> {
+        let image = self.extract_image(source_path, region)?;
+        let segment = self.image_segment(source_path)?;
+        let region = region.unwrap_or_else(|| Region::new(0, 0, segment.columns, segment.rows));
+
+        let mut array_data = ArrayData::from_image(&image);
+        array_data.georeference = segment.georeference(&region)
+            .map(|(geotransform, epsg)| ArrayGeoreference { geotransform, epsg: Some(epsg) });
+        Ok(array_data)
+    }
+
+    /// Write outputs in the requested format
+    fn set_output_format(&mut self, format: Option<OutputFormat>) {
+        self.output_format = format;
+    }
+
+    /// Check if this strategy supports the given file format
+    ///
+    /// # Arguments
+    /// * `file_path` - Path to check for NITF format compatibility
+    ///
+    /// # Returns
+    /// `true` if this is a NITF or NSIF file, `false` otherwise
+    fn supports_format(&self, file_path: &str) -> bool {
+        let extension = Path::new(file_path)
+            .extension()
+            .and_then(std::ffi::OsStr::to_str)
+            .unwrap_or("")
+            .to_lowercase();
+
+        matches!(extension.as_str(), "ntf" | "nitf" | "nsf")
+    }
+}
@@ -0,0 +1,379 @@
+//! Output formats of extraction
+//!
+//! Every extraction output is written in one of the formats listed here.
+//! The format is resolved in one place, from `--output-format` when given
+//! and from the output file extension otherwise, so image writers, array
+//! writers and the command line all agree on what a path will contain.
+
+use std::fmt;
+use std::fs::File;
+use std::io::{BufWriter, Write};
+use std::path::Path;
+
+use image::codecs::avif::AvifEncoder as AvifWriter;
+use image::codecs::jpeg::JpegEncoder;
+use image::{DynamicImage, ImageFormat};
+use log::info;
+
+use crate::tiff::errors::{TiffError, TiffResult};
+use crate::utils::atomic_utils;
+
+/// Quality AVIF is written at unless one is set
+const DEFAULT_AVIF_QUALITY: u8 = 80;
+
+/// Encoding speed of AVIF, from 1 (slowest, smallest) to 10
+const AVIF_SPEED: u8 = 4;
+
+/// Format of an extraction output
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub enum OutputFormat {
+    /// TIFF with the source georeferencing carried over
+    GeoTiff,
+    /// PNG image
+    Png,
+    /// JPEG image, at quality 75 unless one is set
+    Jpeg { quality: Option<u8> },
+    /// WebP image, lossless unless a quality is set
+    WebP { quality: Option<u8> },
+    /// AVIF image, at quality 80 unless one is set
+    Avif { quality: Option<u8> },
+    /// NumPy array
+    Npy,
+    /// NumPy archive (normalized tensors only)
+    Npz,
+    /// safetensors file
+    Safetensors,
+    /// CSV grid of values
+    Csv,
+    /// CSV with one row per pixel
+    CsvLong,
+    /// JSON array of values
+    Json,
+    /// Parquet table
+    Parquet,
+    /// SQLite database
+    Sqlite,
+    /// GeoPackage database
+    GeoPackage,
+    /// PostGIS raster SQL script
+    PgSql,
+    /// ENVI binary raster with a `.hdr` header
+    Envi,
+}
+
+impl OutputFormat {
+    /// All formats, in the order they are listed to users
+    pub const ALL: [OutputFormat; 16] = [
+        OutputFormat::GeoTiff, OutputFormat::Png, OutputFormat::Jpeg { quality: None },
+        OutputFormat::WebP { quality: None }, OutputFormat::Avif { quality: None }, OutputFormat::Npy,
+        OutputFormat::Npz, OutputFormat::Safetensors,
+        OutputFormat::Csv, OutputFormat::CsvLong, OutputFormat::Json, OutputFormat::Parquet,
+        OutputFormat::Sqlite, OutputFormat::GeoPackage, OutputFormat::PgSql, OutputFormat::Envi,
+    ];
+
+    /// Parse a format name such as "png", "geotiff" or "csv-long"
+    ///
+    /// # Arguments
+    /// * `name` - Format name or common alias, case-insensitive
+    ///
+    /// # Returns
+    /// The format, or an error listing the known names
+    pub fn from_name(name: &str) -> TiffResult<Self> {
+        let format = match name.trim().to_lowercase().as_str() {
+            "geotiff" | "tiff" | "tif" => OutputFormat::GeoTiff,
+            "png" => OutputFormat::Png,
+            "jpeg" | "jpg" => OutputFormat::Jpeg { quality: None },
+            "webp" => OutputFormat::WebP { quality: None },
+            "avif" => OutputFormat::Avif { quality: None },
+            "npy" => OutputFormat::Npy,
+            "npz" => OutputFormat::Npz,
+            "safetensors" => OutputFormat::Safetensors,
+            "csv" => OutputFormat::Csv,
+            "csv-long" => OutputFormat::CsvLong,
+            "json" => OutputFormat::Json,
+            "parquet" => OutputFormat::Parquet,
+            "sqlite" => OutputFormat::Sqlite,
+            "gpkg" | "geopackage" => OutputFormat::GeoPackage,
+            "pgsql" | "postgis" => OutputFormat::PgSql,
+            "envi" => OutputFormat::Envi,
+            _ => {
+                let names: Vec<&str> = Self::ALL.iter().map(|format| format.name()).collect();
+                return Err(TiffError::GenericError(format!(
+                    "Unknown output format '{}', expected one of {}", name, names.join(", "))));
+            }
+        };
+        Ok(format)
+    }
+
+    /// Format implied by the extension of an output path
+    ///
+    /// # Arguments
+    /// * `path` - Output path
+    ///
+    /// # Returns
+    /// The format, or None for missing and unknown extensions
+    pub fn from_extension(path: &str) -> Option<Self> {
+        let extension = Path::new(path).extension()?.to_str()?.to_lowercase();
+        match extension.as_str() {
+            "sql" => Some(OutputFormat::PgSql),
+            "db" | "sqlite3" => Some(OutputFormat::Sqlite),
+            "hdr" => Some(OutputFormat::Envi),
+            "csv-long" | "geotiff" | "postgis" | "geopackage" => None,
+            _ => Self::from_name(&extension).ok(),
+        }
+    }
+
+    /// Resolve the format an output is written in
+    ///
+    /// This is the single place output formats are decided: a requested
+    /// format wins, then the extension, and GeoTIFF is written otherwise.
+    ///
+    /// # Arguments
+    /// * `output_path` - Output path
+    /// * `requested` - Format given explicitly, if any
+    ///
+    /// # Returns
+    /// The format to write
+    pub fn resolve(output_path: &str, requested: Option<Self>) -> Self {
+        requested
+            .or_else(|| Self::from_extension(output_path))
+            .unwrap_or(OutputFormat::GeoTiff)
+    }
+
+    /// Canonical name, as accepted by `--output-format` and `--array-format`
+    pub fn name(&self) -> &'static str {
+        match self {
+            OutputFormat::GeoTiff => "geotiff",
+            OutputFormat::Png => "png",
+            OutputFormat::Jpeg { .. } => "jpeg",
+            OutputFormat::WebP { .. } => "webp",
+            OutputFormat::Avif { .. } => "avif",
+            OutputFormat::Npy => "npy",
+            OutputFormat::Npz => "npz",
+            OutputFormat::Safetensors => "safetensors",
+            OutputFormat::Csv => "csv",
+            OutputFormat::CsvLong => "csv-long",
+            OutputFormat::Json => "json",
+            OutputFormat::Parquet => "parquet",
+            OutputFormat::Sqlite => "sqlite",
+            OutputFormat::GeoPackage => "gpkg",
+            OutputFormat::PgSql => "pgsql",
+            OutputFormat::Envi => "envi",
+        }
+    }
+
+    /// Whether the format holds raw values rather than an image
+    pub fn is_array(&self) -> bool {
+        self.encoder().is_none()
+    }
+
+    /// Whether outputs in this format are TIFF files
+    pub fn is_tiff(&self) -> bool {
+        *self == OutputFormat::GeoTiff
+    }
+
+    /// Whether masked-out pixels can be stored as transparent
+    ///
+    /// TIFF is left out: shaped GeoTIFF outputs are written as PNG.
+    pub fn supports_transparency(&self) -> bool {
+        matches!(self, OutputFormat::Png | OutputFormat::WebP { .. } | OutputFormat::Avif { .. })
+    }
+
+    /// Whether written pixel values can differ from the extracted ones
+    pub fn is_lossy(&self) -> bool {
+        match self {
+            OutputFormat::Jpeg { .. } | OutputFormat::Avif { .. } => true,
+            OutputFormat::WebP { quality } => quality.is_some(),
+            _ => false,
+        }
+    }
+
+    /// The same format, written at another quality
+    ///
+    /// JPEG and AVIF use the quality in place of their defaults (75 and
+    /// 80), and WebP switches from lossless to lossy encoding at that
+    /// quality. Other formats are returned unchanged.
+    ///
+    /// # Arguments
+    /// * `quality` - Quality from 1 (smallest) to 100 (best), None for the defaults
+    ///
+    /// # Returns
+    /// The format with the quality clamped to 1..=100
+    pub fn with_quality(self, quality: Option<u8>) -> Self {
+        let quality = quality.map(|quality| quality.clamp(1, 100));
+        match self {
+            OutputFormat::Jpeg { .. } => OutputFormat::Jpeg { quality },
+            OutputFormat::WebP { .. } => OutputFormat::WebP { quality },
+            OutputFormat::Avif { .. } => OutputFormat::Avif { quality },
+            format => format,
+        }
+    }
+
+    /// Quality set for a lossy image format, if any
+    pub fn quality(&self) -> Option<u8> {
+        match self {
+            OutputFormat::Jpeg { quality } | OutputFormat::WebP { quality } | OutputFormat::Avif { quality } => *quality,
+            _ => None,
+        }
+    }
+
+    /// Encoder for image formats
+    ///
+    /// GeoTIFF outputs are normally written by the extractors, which carry
+    /// the georeferencing over; its encoder writes a plain TIFF for images
+    /// that have none.
+    ///
+    /// # Returns
+    /// The encoder, or None for array formats
+    pub fn encoder(&self) -> Option<Box<dyn ImageEncoder>> {
+        let format = match *self {
+            OutputFormat::GeoTiff => ImageFormat::Tiff,
+            OutputFormat::Png => ImageFormat::Png,
+            OutputFormat::Jpeg { .. } => ImageFormat::Jpeg,
+            OutputFormat::WebP { quality: Some(quality) } => return Some(Box::new(LossyWebPEncoder { quality })),
+            OutputFormat::WebP { quality: None } => ImageFormat::WebP,
+            OutputFormat::Avif { quality } => {
+                let quality = quality.unwrap_or(DEFAULT_AVIF_QUALITY);
+                return Some(Box::new(AvifEncoder { quality }));
+            },
+            _ => return None,
+        };
+        Some(Box::new(StandardEncoder { format, quality: self.quality() }))
+    }
+
+    /// Write an image in this format
+    ///
+    /// # Arguments
+    /// * `image` - The image to write
+    /// * `output_path` - Path of the output file
+    ///
+    /// # Returns
+    /// Result indicating success, or an error for array formats
+    pub fn write_image(&self, image: &DynamicImage, output_path: &str) -> TiffResult<()> {
+        let encoder = self.encoder().ok_or_else(|| TiffError::GenericError(format!(
+            "{} is an array format, images cannot be written as {}", self.name(), self.name())))?;
+        info!("Writing {}x{} image to {} as {}", image.width(), image.height(), output_path, self);
+        atomic_utils::write_atomically(output_path, |partial_path| encoder.encode(image, partial_path))
+    }
+}
+
+impl fmt::Display for OutputFormat {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.write_str(self.name())
+    }
+}
+
+/// Writes images to files in one output format
+pub trait ImageEncoder {
+    /// Encode an image into a file
+    ///
+    /// Images the format cannot hold as they are, such as 16-bit data for
+    /// JPEG, are converted first.
+    ///
+    /// # Arguments
+    /// * `image` - The image to encode
+    /// * `output_path` - Path of the output file
+    ///
+    /// # Returns
+    /// Result indicating success or an error
+    fn encode(&self, image: &DynamicImage, output_path: &str) -> TiffResult<()>;
+}
+
+/// Encoder for the formats the image crate writes
+struct StandardEncoder {
+    /// Format of the written files
+    format: ImageFormat,
+    /// Quality of JPEG files, None for the default
+    quality: Option<u8>,
+}
+
+impl StandardEncoder {
+    /// Convert an image to a color type the format can store
+    fn encodable(&self, image: &DynamicImage) -> Option<DynamicImage> {
+        let alpha = image.color().has_alpha();
+        match (self.format, image) {
+            (ImageFormat::Jpeg, DynamicImage::ImageLuma8(_)) => None,
+            (ImageFormat::Jpeg, DynamicImage::ImageLuma16(_)) => Some(DynamicImage::ImageLuma8(image.to_luma8())),
+            (ImageFormat::Jpeg, DynamicImage::ImageRgb8(_)) => None,
+            (ImageFormat::Jpeg, _) => Some(DynamicImage::ImageRgb8(image.to_rgb8())),
+            (ImageFormat::WebP, DynamicImage::ImageRgb8(_) | DynamicImage::ImageRgba8(_)) => None,
+            (ImageFormat::WebP, _) => Some(to_rgb8_or_rgba8(image)),
+            (_, DynamicImage::ImageRgb32F(_) | DynamicImage::ImageRgba32F(_)) if alpha =>
+                Some(DynamicImage::ImageRgba16(image.to_rgba16())),
+            (_, DynamicImage::ImageRgb32F(_)) => Some(DynamicImage::ImageRgb16(image.to_rgb16())),
+            _ => None,
+        }
+    }
+}
+
+impl ImageEncoder for StandardEncoder {
+    fn encode(&self, image: &DynamicImage, output_path: &str) -> TiffResult<()> {
+        let converted = self.encodable(image);
+        let image = converted.as_ref().unwrap_or(image);
+        match (self.format, self.quality) {
+            (ImageFormat::Jpeg, Some(quality)) => write_encoded(output_path, |writer| {
+                image.write_with_encoder(JpegEncoder::new_with_quality(writer, quality))
+                    .map_err(|e| TiffError::GenericError(e.to_string()))
+            }),
+            _ => image.save_with_format(output_path, self.format)
+                .map_err(|e| TiffError::GenericError(format!("Failed to save image to {}: {}", output_path, e))),
+        }
+    }
+}
+
+/// Lossy WebP encoder, used once a quality is set
+struct LossyWebPEncoder {
+    /// Quality from 1 to 100
+    quality: u8,
+}
+
+impl ImageEncoder for LossyWebPEncoder {
+    fn encode(&self, image: &DynamicImage, output_path: &str) -> TiffResult<()> {
+        let quality = self.quality;
+        let encoded = match to_rgb8_or_rgba8(image) {
+            DynamicImage::ImageRgba8(rgba) =>
+                webp::Encoder::from_rgba(rgba.as_raw(), rgba.width(), rgba.height()).encode(f32::from(quality)),
+            rgb => {
+                let rgb = rgb.to_rgb8();
+                webp::Encoder::from_rgb(rgb.as_raw(), rgb.width(), rgb.height()).encode(f32::from(quality))
+            }
+        };
+        write_encoded(output_path, |writer| writer.write_all(&encoded).map_err(TiffError::from))
+    }
+}
+
+/// AVIF encoder
+struct AvifEncoder {
+    /// Quality from 1 to 100
+    quality: u8,
+}
+
+impl ImageEncoder for AvifEncoder {
+    fn encode(&self, image: &DynamicImage, output_path: &str) -> TiffResult<()> {
+        let image = to_rgb8_or_rgba8(image);
+        write_encoded(output_path, |writer| {
+            image.write_with_encoder(AvifWriter::new_with_speed_quality(writer, AVIF_SPEED, self.quality))
+                .map_err(|e| TiffError::GenericError(e.to_string()))
+        })
+    }
+}
+
+/// Reduce an image to 8-bit RGB, or RGBA if it has transparency
+fn to_rgb8_or_rgba8(image: &DynamicImage) -> DynamicImage {
+    if image.color().has_alpha() {
+        DynamicImage::ImageRgba8(image.to_rgba8())
+    } else {
+        DynamicImage::ImageRgb8(image.to_rgb8())
+    }
+}
+
+/// Create an output file and write encoded data into it
+fn write_encoded<F>(output_path: &str, encode: F) -> TiffResult<()>
+where
+    F: FnOnce(&mut BufWriter<File>) -> TiffResult<()>
+{
+    let mut writer = BufWriter::new(File::create(output_path)?);
+    encode(&mut writer)
+        .and_then(|_| writer.flush().map_err(TiffError::from))
+        .map_err(|e| TiffError::GenericError(format!("Failed to save image to {}: {}", output_path, e)))
+}
@@ -0,0 +1,111 @@
+//! Extraction provenance
+//!
+//! Outputs carry a record of how they were produced: the source file and
+//! its checksum, the requested region, the CRS, the rasterkit version and
+//! the command line. It is written into the GDAL metadata and the
+//! ImageDescription tag so the record travels with the file.
+
+use std::io::{BufReader, Read};
+
+use log::info;
+
+use crate::io::storage;
+use crate::tiff::errors::TiffResult;
+use crate::utils::xml_utils;
+
+use super::region::Region;
+
+/// How an extracted output was produced
+#[derive(Debug, Clone, PartialEq)]
+pub struct Provenance {
+    /// Path of the source file
+    pub source: String,
+    /// Checksum of the source file, such as "crc32:1a2b3c4d"
+    pub checksum: String,
+    /// Requested bounding box, if the extraction had one
+    pub bbox: Option<String>,
+    /// CRS the bounding box is given in
+    pub crs: Option<String>,
+    /// Command-line parameters of the extraction
+    pub parameters: String,
+}
+
+impl Provenance {
+    /// Version of rasterkit that wrote the output
+    pub fn version() -> &'static str {
+        env!("CARGO_PKG_VERSION")
+    }
+
+    /// Name/value pairs describing an extraction
+    ///
+    /// # Arguments
+    /// * `window` - Pixel window read from the source
+    pub fn items(&self, window: &Region) -> Vec<(&'static str, String)> {
+        let mut items = vec![
+            ("RASTERKIT_VERSION", Self::version().to_string()),
+            ("RASTERKIT_SOURCE", self.source.clone()),
+            ("RASTERKIT_SOURCE_CHECKSUM", self.checksum.clone()),
+            ("RASTERKIT_WINDOW", format!("{},{},{},{}", window.x, window.y, window.width, window.height)),
+        ];
+        if let Some(bbox) = &self.bbox {
+            items.push(("RASTERKIT_BBOX", bbox.clone()));
+        }
+        if let Some(crs) = &self.crs {
+            items.push(("RASTERKIT_CRS", crs.clone()));
+        }
+        items.push(("RASTERKIT_PARAMETERS", self.parameters.clone()));
+        items
+    }
+
+    /// GDAL metadata `<Item>` elements for an extraction
+    ///
+    /// # Arguments
+    /// * `window` - Pixel window read from the source
+    pub fn metadata_items(&self, window: &Region) -> Vec<String> {
+        self.items(window).into_iter()
+            .map(|(name, value)| format!("<Item name=\"{}\">{}</Item>", name, xml_utils::escape_text(&value)))
+            .collect()
+    }
+
+    /// Human-readable summary for the ImageDescription tag
+    ///
+    /// # Arguments
+    /// * `window` - Pixel window read from the source
+    pub fn description(&self, window: &Region) -> String {
+        let mut lines = vec![format!("Extracted by rasterkit {} from {} ({})",
+                                     Self::version(), self.source, self.checksum)];
+        lines.push(format!("window: x={} y={} width={} height={}", window.x, window.y, window.width, window.height));
+        if let Some(bbox) = &self.bbox {
+            lines.push(format!("bbox: {}", bbox));
+        }
+        if let Some(crs) = &self.crs {
+            lines.push(format!("crs: {}", crs));
+        }
+        lines.push(format!("parameters: {}", self.parameters));
+        lines.join("\n")
+    }
+}
+
+/// CRC-32 checksum of a file
+///
+/// # Arguments
+/// * `path` - File to checksum
+///
+/// # Returns
+/// The checksum as "crc32:" followed by eight hex digits
+pub fn file_checksum(path: &str) -> TiffResult<String> {
+    let mut reader = BufReader::new(storage::open(path)?);
+    let mut hasher = crc32fast::Hasher::new();
+    let mut buffer = vec![0u8; 1 << 16];
+    loop {
+        let read = reader.read(&mut buffer)?;
+        if read == 0 {
+            break;
+        }
+        hasher.update(&buffer[..read]);
+    }
+
+    let checksum = format!("crc32:{:08x}", hasher.finalize());
+    info!("Checksum of {}: {}", path, checksum);
+    Ok(checksum)
+}
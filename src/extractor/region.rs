@@ -5,6 +5,9 @@
 //! follow the typical image coordinate system where (0,0) is the top-left
 //! corner of the image.
 
+use crate::coordinate::Affine;
+use crate::tiff::errors::{TiffError, TiffResult};
+
 /// Region for image extraction (in pixel coordinates)
 ///
 /// Represents a rectangular area defined by its top-left corner coordinates
@@ -61,4 +64,149 @@ impl Region {
     pub fn end_y(&self) -> u32 {
         self.y + self.height
     }
-}
\ No newline at end of file
+}
+
+/// Overlap between a requested pixel window and the image
+///
+/// Bounding boxes near the raster edge ask for pixels the image does not
+/// have. The window is clipped for extraction; this records how much of
+/// the request was actually served.
+#[derive(Debug, Clone, Copy)]
+pub struct Coverage {
+    /// Column of the requested window's left edge (may be negative)
+    pub requested_x: i64,
+    /// Row of the requested window's top edge (may be negative)
+    pub requested_y: i64,
+    /// Width of the requested window in pixels
+    pub requested_width: u64,
+    /// Height of the requested window in pixels
+    pub requested_height: u64,
+    /// Part of the window inside the image, None if they do not overlap
+    pub clipped: Option<Region>,
+}
+
+impl Coverage {
+    /// Clip a requested window to the image
+    ///
+    /// # Arguments
+    /// * `window` - (min column, min row, max column, max row), maxima exclusive
+    /// * `img_width` - Image width in pixels
+    /// * `img_height` - Image height in pixels
+    ///
+    /// # Returns
+    /// The coverage of the window
+    pub fn new(window: (i64, i64, i64, i64), img_width: u32, img_height: u32) -> Self {
+        let (min_x, min_y, max_x, max_y) = window;
+        let (x0, y0) = (min_x.clamp(0, img_width as i64), min_y.clamp(0, img_height as i64));
+        let (x1, y1) = (max_x.clamp(0, img_width as i64), max_y.clamp(0, img_height as i64));
+
+        let clipped = (x1 > x0 && y1 > y0)
+            .then(|| Region::new(x0 as u32, y0 as u32, (x1 - x0) as u32, (y1 - y0) as u32));
+
+        Coverage {
+            requested_x: min_x,
+            requested_y: min_y,
+            requested_width: (max_x - min_x).max(0) as u64,
+            requested_height: (max_y - min_y).max(0) as u64,
+            clipped,
+        }
+    }
+
+    /// Number of pixels in the requested window
+    pub fn requested_pixels(&self) -> u64 {
+        self.requested_width * self.requested_height
+    }
+
+    /// Number of requested pixels that lie on the image
+    pub fn covered_pixels(&self) -> u64 {
+        self.clipped.map_or(0, |r| r.width as u64 * r.height as u64)
+    }
+
+    /// Number of requested pixels outside the image (left as NoData)
+    pub fn outside_pixels(&self) -> u64 {
+        self.requested_pixels() - self.covered_pixels()
+    }
+
+    /// Share of the requested window on the image, in percent
+    pub fn percent(&self) -> f64 {
+        match self.requested_pixels() {
+            0 => 0.0,
+            total => self.covered_pixels() as f64 * 100.0 / total as f64,
+        }
+    }
+
+    /// Whether the whole requested window lies on the image
+    pub fn is_complete(&self) -> bool {
+        self.requested_pixels() > 0 && self.outside_pixels() == 0
+    }
+
+    /// Padding that restores the requested window around the extracted region
+    ///
+    /// When the window misses the image, the region read in its place lies
+    /// outside the window and the padded output is all fill.
+    ///
+    /// # Arguments
+    /// * `region` - Region that is extracted: the clipped window, or any
+    ///   region of the image if the window misses it
+    /// * `fill` - Value for the padded pixels, None for the source NoData
+    ///
+    /// # Returns
+    /// The padding, or None if the window is complete or empty
+    pub fn padding(&self, region: &Region, fill: Option<f64>) -> Option<Padding> {
+        if self.is_complete() || self.requested_pixels() == 0 {
+            return None;
+        }
+        Some(Padding {
+            offset_x: region.x as i64 - self.requested_x,
+            offset_y: region.y as i64 - self.requested_y,
+            width: self.requested_width as u32,
+            height: self.requested_height as u32,
+            fill,
+        })
+    }
+}
+
+/// Padding of an extracted region back to the requested window
+///
+/// The extracted pixels are placed at (`offset_x`, `offset_y`) in an output
+/// of `width` x `height` pixels; everything around them gets the fill value.
+/// The offsets may place the region partly or wholly outside the output,
+/// of which only the part inside is kept.
+#[derive(Debug, Clone, Copy)]
+pub struct Padding {
+    /// Column of the extracted region within the output
+    pub offset_x: i64,
+    /// Row of the extracted region within the output
+    pub offset_y: i64,
+    /// Output width in pixels
+    pub width: u32,
+    /// Output height in pixels
+    pub height: u32,
+    /// Fill value, None to use the source NoData value
+    pub fill: Option<f64>,
+}
+
+impl Padding {
+    /// Move a window geotransform to the padded output's corner
+    ///
+    /// # Arguments
+    /// * `geotransform` - Geotransform anchored at the extracted region
+    ///
+    /// # Returns
+    /// The geotransform anchored at the top-left of the padded output
+    pub fn shift(&self, geotransform: &Affine) -> Affine {
+        geotransform.compose(&Affine::new(-(self.offset_x as f64), 1.0, -(self.offset_y as f64), 1.0))
+    }
+
+    /// Value of the padded pixels
+    ///
+    /// # Arguments
+    /// * `nodata` - NoData value of the source, if it declares one
+    ///
+    /// # Returns
+    /// The fill value, else the source NoData, or an error if there is neither
+    pub fn fill_value(&self, nodata: Option<f64>) -> TiffResult<f64> {
+        self.fill.or(nodata).ok_or_else(|| TiffError::GenericError(
+            "The source declares no NoData value; set a fill value for the padded pixels".to_string()))
+    }
+}
@@ -7,6 +7,7 @@
 
 use log::{debug, info, warn};
 use std::io::SeekFrom;
+use std::time::Instant;
 use image::{ImageBuffer, Rgb};
 
 use crate::io::seekable::SeekableReader;
@@ -15,8 +16,11 @@ use crate::tiff::errors::TiffResult;
 use crate::tiff::ifd::IFD;
 use crate::tiff::constants::{tags, predictor as pred_consts};
 use crate::compression::CompressionFactory;
-use crate::utils::image_extraction_utils;
+use crate::utils::{image_extraction_utils, metrics};
 
+use std::borrow::Cow;
+
+use super::block_cache::BlockCache;
 use super::region::Region;
 
 /// Reads image data from stripped TIFF files
@@ -53,21 +57,25 @@ impl<'a, R: SeekableReader> StripReader<'a, R> {
 
     /// Get strip parameters from the IFD
     ///
-    /// Reads the rows per strip and image width from the IFD.
-    /// If RowsPerStrip is not specified, defaults to the entire image height.
+    /// Reads the rows per strip and image size from the IFD. If
+    /// RowsPerStrip is not specified, or larger than the image (writers
+    /// often store 2^32-1 for a single strip), it is the image height.
     ///
     /// # Returns
-    /// A tuple containing (rows_per_strip, image_width) or an error
-    fn get_strip_parameters(&self) -> TiffResult<(u32, u32)> {
+    /// A tuple containing (rows_per_strip, image_width, image_height) or an error
+    fn get_strip_parameters(&self) -> TiffResult<(u32, u32, u32)> {
         // Get image dimensions
-        let (img_width, _) = self.ifd.get_dimensions()
+        let (img_width, img_height) = self.ifd.get_dimensions()
             .ok_or_else(|| TiffError::GenericError("Missing image dimensions".to_string()))?;
+        let img_height = img_height as u32;
 
         // Get rows per strip, defaulting to the full image height
         let rows_per_strip = self.ifd.get_tag_value(tags::ROWS_PER_STRIP)
-            .unwrap_or(img_width) as u32;
+            .map(|rows| rows.min(img_height as u64) as u32)
+            .unwrap_or(img_height)
+            .max(1);
 
-        Ok((rows_per_strip, img_width as u32))
+        Ok((rows_per_strip, img_width as u32, img_height))
     }
 
     /// Read a single strip from the TIFF file
@@ -95,85 +103,98 @@ impl<'a, R: SeekableReader> StripReader<'a, R> {
         rows_per_strip: usize
     ) -> TiffResult<Vec<u8>> {
         // Read the compressed strip data
+        let started = Instant::now();
         self.reader.seek(SeekFrom::Start(offset))?;
         let mut compressed_data = vec![0u8; byte_count as usize];
         self.reader.read_exact(&mut compressed_data)?;
+        metrics::record_block_read(byte_count, started.elapsed());
 
         // Decompress the strip data
+        let started = Instant::now();
         let mut strip_data = compression_handler.decompress(&compressed_data)?;
 
         // Apply predictor if needed
         if predictor == pred_consts::HORIZONTAL_DIFFERENCING as usize {
             image_extraction_utils::apply_horizontal_predictor(&mut strip_data, width, rows_per_strip);
         }
+        metrics::record_decode(compression_handler.name(), byte_count, strip_data.len() as u64, started.elapsed());
 
         Ok(strip_data)
     }
 
     /// Extract image data to the provided buffer
     ///
-    /// Reads all strips that intersect with the specified region and
-    /// copies their pixel data to the output image.
+    /// Works out from RowsPerStrip which strips the region's rows fall in,
+    /// reads only their offsets and byte counts, and seeks straight to
+    /// each of them; the rest of the file is never touched. With a cache,
+    /// strips decoded by earlier extractions are reused.
     ///
     /// # Arguments
     /// * `image` - Output image buffer
     /// * `region` - Region of the image to extract
+    /// * `cache` - Cache of decoded strips, None to decode every strip
     ///
     /// # Returns
     /// Result indicating success or failure
     pub fn extract(
         &mut self,
         image: &mut ImageBuffer<Rgb<u8>, Vec<u8>>,
-        region: Region
+        region: Region,
+        mut cache: Option<&mut BlockCache>
     ) -> TiffResult<()> {
         // Get strip parameters
-        let (rows_per_strip, img_width) = self.get_strip_parameters()?;
+        let (rows_per_strip, img_width, img_height) = self.get_strip_parameters()?;
 
-        // Get compression type
-        let compression = self.ifd.get_tag_value(tags::COMPRESSION).unwrap_or(1);
-        let compression_handler = CompressionFactory::create_handler(compression)?;
+        // Get the compression handler, with the tables of JPEG images
+        let compression_handler = CompressionFactory::create_handler_for_ifd(self.tiff_reader, self.ifd)?;
         info!("Using compression: {}", compression_handler.name());
 
         // Get predictor
         let predictor = self.ifd.get_tag_value(tags::PREDICTOR).unwrap_or(1) as usize;
 
-        // Get strip offsets and byte counts
-        let strip_offsets = self.tiff_reader.read_tag_values(&mut self.reader, self.ifd, tags::STRIP_OFFSETS)?;
-        let strip_byte_counts = self.tiff_reader.read_tag_values(&mut self.reader, self.ifd, tags::STRIP_BYTE_COUNTS)?;
-
-        info!("Rows per strip: {}", rows_per_strip);
-        info!("Total strips: {}", strip_offsets.len());
-
-        // Calculate which strips we need
+        // Strips holding the first and past the last row of the region
         let start_strip = region.y / rows_per_strip;
-        let end_strip = (region.end_y() + rows_per_strip - 1) / rows_per_strip;
+        let end_strip = region.end_y().min(img_height).div_ceil(rows_per_strip);
+        let total_strips = self.ifd.get_entry(tags::STRIP_OFFSETS).map_or(0, |entry| entry.count);
 
-        info!("Processing strips from {} to {}", start_strip, end_strip - 1);
+        info!("Rows per strip: {}", rows_per_strip);
+        info!("Reading strips {} to {} of {}", start_strip, end_strip.saturating_sub(1), total_strips);
+
+        // Only the offsets and byte counts of those strips are read
+        let wanted = (end_strip - start_strip) as u64;
+        let strip_offsets = self.tiff_reader.read_tag_value_range(
+            &mut self.reader, self.ifd, tags::STRIP_OFFSETS, start_strip as u64, wanted)?;
+        let strip_byte_counts = self.tiff_reader.read_tag_value_range(
+            &mut self.reader, self.ifd, tags::STRIP_BYTE_COUNTS, start_strip as u64, wanted)?;
+        if (strip_offsets.len() as u64) < wanted || strip_byte_counts.len() < strip_offsets.len() {
+            warn!("The image has {} strips, the region needs strips up to {}", total_strips, end_strip - 1);
+        }
 
         // Process each strip
-        for strip_idx in start_strip..end_strip {
-            // Skip if strip index is out of bounds
-            if strip_idx as usize >= strip_offsets.len() {
-                warn!("Strip index {} out of bounds (max {})",
-                      strip_idx, strip_offsets.len() - 1);
-                continue;
-            }
+        for (i, (&offset, &byte_count)) in strip_offsets.iter().zip(&strip_byte_counts).enumerate() {
+            let strip_idx = start_strip + i as u32;
 
-            let offset = strip_offsets[strip_idx as usize];
-            let byte_count = strip_byte_counts[strip_idx as usize];
+            // Calculate strip position in pixels; the last strip may be short
+            let strip_start_y = strip_idx * rows_per_strip;
+            let rows_in_strip = rows_per_strip.min(img_height - strip_start_y) as usize;
 
             debug!("Reading strip {} at offset {} with {} bytes",
                   strip_idx, offset, byte_count);
 
             // Read and process the strip data
-            let strip_data = match self.read_strip(
+            let mut decode = || self.read_strip(
                 offset,
                 byte_count,
                 &*compression_handler,
                 predictor,
                 img_width as usize,
-                rows_per_strip as usize
-            ) {
+                rows_in_strip
+            );
+            let strip_data = match cache.as_deref_mut() {
+                Some(cache) => cache.get_or_decode(strip_idx as usize, decode).map(Cow::Borrowed),
+                None => decode().map(Cow::Owned),
+            };
+            let strip_data = match strip_data {
                 Ok(data) => data,
                 Err(e) => {
                     warn!("Error reading strip {}: {:?}", strip_idx, e);
@@ -181,15 +202,12 @@ impl<'a, R: SeekableReader> StripReader<'a, R> {
                 }
             };
 
-            // Calculate strip position in pixels
-            let strip_start_y = strip_idx * rows_per_strip;
-
             // Copy strip data to image buffer
             self.copy_strip_to_image(
                 &strip_data,
                 image,
                 img_width as usize,
-                rows_per_strip as usize,
+                rows_in_strip,
                 strip_start_y,
                 region
             );
@@ -219,16 +237,14 @@ impl<'a, R: SeekableReader> StripReader<'a, R> {
         strip_start_y: u32,
         region: Region
     ) {
-        // For each row in the strip
-        for row in 0..rows_in_strip {
-            let global_y = strip_start_y + row as u32;
-
-            // Skip rows outside our region
-            if global_y < region.y || global_y >= region.end_y() {
-                continue;
-            }
+        // Color images hold several bytes per pixel
+        let stride = image_extraction_utils::pixel_stride(self.ifd);
 
-            let row_start = row * width;
+        // For each row of the strip inside our region
+        let first_row = region.y.max(strip_start_y);
+        let last_row = region.end_y().min(strip_start_y + rows_in_strip as u32);
+        for global_y in first_row..last_row {
+            let row_start = (global_y - strip_start_y) as usize * width;
 
             // For each pixel in the row within our region
             for x in region.x..region.end_x() {
@@ -240,12 +256,13 @@ impl<'a, R: SeekableReader> StripReader<'a, R> {
                 let strip_idx = row_start + x as usize;
 
                 // Copy the pixel using the utility function
-                image_extraction_utils::copy_pixel(
+                image_extraction_utils::copy_interleaved_pixel(
                     strip_data,
                     image,
                     x,
                     global_y,
                     strip_idx,
+                    stride,
                     region
                 );
             }
@@ -3,20 +3,24 @@
 //! This module implements the extraction strategy for TIFF format images,
 //! handling both the standard TIFF format and GeoTIFF extensions.
 
-use log::info;
-use std::fs::File;
+use log::{debug, info};
 use std::io::BufReader;
 use std::path::Path;
 use image::{ImageBuffer, Rgb, DynamicImage};
-use crate::extractor::array_strategy::ArrayData;
+use crate::io::storage;
+use crate::extractor::array_strategy::{self, ArrayData};
 use crate::tiff::{TiffReader, TiffBuilder};
 use crate::tiff::errors::{TiffError, TiffResult};
 use crate::tiff::ifd::IFD;
 use crate::tiff::constants::{tags, photometric};
 use crate::utils::logger::Logger;
-use crate::utils::tiff_extraction_utils;
+use crate::utils::{grid_utils, image_extraction_utils, info_utils, memory_utils, tiff_extraction_utils, xml_utils};
 
-use super::region::Region;
+use super::block_cache::BlockCache;
+use super::buffer_decode::{self, BufferLayout};
+use super::output_format::OutputFormat;
+use super::provenance::Provenance;
+use super::region::{Padding, Region};
 use super::tile_reader::TileReader;
 use super::strip_reader::StripReader;
 use super::extractor_strategy::ExtractorStrategy;
@@ -30,6 +34,16 @@ pub struct TiffExtractorStrategy<'a> {
     logger: &'a Logger,
     /// TIFF reader for parsing TIFF files
     reader: TiffReader<'a>,
+    /// Padding of extracted regions to the requested window
+    padding: Option<Padding>,
+    /// Decoded blocks kept between extractions, if caching is enabled
+    block_cache: Option<BlockCache>,
+    /// Provenance record written into the output, if any
+    provenance: Option<Provenance>,
+    /// SubIFD of the main IFD to read from, if any
+    sub_ifd: Option<usize>,
+    /// Requested output format, None to go by the extension
+    output_format: Option<OutputFormat>,
 }
 
 impl<'a> TiffExtractorStrategy<'a> {
@@ -41,10 +55,85 @@ impl<'a> TiffExtractorStrategy<'a> {
         TiffExtractorStrategy {
             logger,
             reader: TiffReader::new(logger),
+            padding: None,
+            block_cache: None,
+            provenance: None,
+            sub_ifd: None,
+            output_format: None,
         }
     }
 }
 
+impl<'a> TiffExtractorStrategy<'a> {
+    /// Write a region padded to the requested window as a GeoTIFF
+    ///
+    /// The region is decoded at its stored sample type and band count and
+    /// padded with the fill value converted to that type, so integer and
+    /// floating-point rasters keep their values and NoData.
+    ///
+    /// # Arguments
+    /// * `tiff_path` - Path to the source TIFF file
+    /// * `output_path` - Path of the GeoTIFF to write
+    /// * `region` - Region of the main image to extract
+    /// * `padding` - Output window and where the region goes in it
+    ///
+    /// # Returns
+    /// Result indicating success or an error with details
+    fn write_padded(&mut self, tiff_path: &str, output_path: &str, region: Region, padding: &Padding) -> TiffResult<()> {
+        if self.sub_ifd.is_some() {
+            return Err(TiffError::GenericError("A SubIFD cannot be padded to the bounding box".to_string()));
+        }
+        let tiff = self.reader.load(tiff_path)?;
+        let source_ifd = tiff_extraction_utils::select_source_ifd(&tiff, None)?;
+
+        let shape = buffer_decode::describe_window(tiff_path, Some(region), self.logger)?;
+        if shape.bytes_per_sample == 0 {
+            return Err(TiffError::GenericError(format!(
+                "The sub-byte samples of {} cannot be padded", tiff_path)));
+        }
+        let output_len = padding.width as usize * padding.height as usize * shape.bands * shape.bytes_per_sample;
+        memory_utils::check_fits(&format!("Padding to {}x{}", padding.width, padding.height),
+                                 (shape.byte_len() + output_len) as u64)?;
+
+        let mut samples = vec![0u8; shape.byte_len()];
+        buffer_decode::decode_into_bytes(tiff_path, Some(region), BufferLayout::Interleaved, &mut samples, self.logger)?;
+        // Samples are decoded in the byte order of this machine, outputs are little-endian
+        if cfg!(target_endian = "big") {
+            samples.chunks_exact_mut(shape.bytes_per_sample).for_each(|sample| sample.reverse());
+        }
+        let fill = padding.fill_value(array_strategy::declared_nodata(&mut self.reader, tiff_path))?;
+        let padded = image_extraction_utils::pad_samples(&samples, &shape, padding, fill);
+
+        let mut builder = TiffBuilder::new(self.logger, tiff.is_big_tiff || output_len as u64 > u32::MAX as u64);
+        let ifd_index = builder.add_ifd(IFD::new(0, 0));
+        grid_utils::add_derived_image(&mut builder, ifd_index, &self.reader, source_ifd, (padding.width, padding.height),
+                                      padded, &[tags::GDAL_NODATA, tags::GDAL_METADATA, tags::IMAGE_DESCRIPTION]);
+        if let Some(georeferencing) = info_utils::read_georeferencing(&tiff, source_ifd, &self.reader, tiff_path) {
+            let geotransform = padding.shift(&georeferencing.geotransform.translated(region.x, region.y));
+            grid_utils::georeference_derived(&mut builder, ifd_index, source_ifd, &georeferencing, &geotransform)?;
+        }
+
+        // Padded pixels are marked as NoData
+        let nodata_value = fill.to_string();
+        let mut metadata_str = tiff_extraction_utils::extract_gdal_metadata(source_ifd, &self.reader);
+        if let Some(provenance) = &self.provenance {
+            info!("Writing provenance of {}", provenance.source);
+            let items = provenance.metadata_items(&region);
+            metadata_str = Some(items.iter().fold(
+                metadata_str.unwrap_or_else(|| "<GDALMetadata>\n</GDALMetadata>".to_string()),
+                |metadata, item| xml_utils::add_to_gdal_metadata(&metadata, item)));
+            builder.add_image_description(ifd_index, &provenance.description(&region));
+        }
+        builder.add_nodata_tag(ifd_index, &nodata_value);
+        builder.add_gdal_metadata_tag(ifd_index, metadata_str.as_deref(), &nodata_value);
+
+        builder.write(output_path)?;
+        info!("Saved {}x{} window of {} band(s) padded to {}x{} to {}",
+              region.width, region.height, shape.bands, padding.width, padding.height, output_path);
+        Ok(())
+    }
+}
+
 impl<'a> ExtractorStrategy for TiffExtractorStrategy<'a> {
     /// Extract an image from a TIFF file to another file
     ///
@@ -64,24 +153,31 @@ impl<'a> ExtractorStrategy for TiffExtractorStrategy<'a> {
 
         // Load the source TIFF
         let tiff = self.reader.load(tiff_path)?;
-        if tiff.ifds.is_empty() {
-            return Err(TiffError::GenericError("No IFDs found in TIFF file".to_string()));
-        }
+        let main_ifd = tiff_extraction_utils::select_source_ifd(&tiff, None)?;
 
-        // Use the first IFD
-        let original_ifd = &tiff.ifds[0];
+        // Use the first IFD, or the requested SubIFD of it
+        let original_ifd = tiff_extraction_utils::select_source_ifd(&tiff, self.sub_ifd)?;
 
         // Get basic image properties
         let (bits_per_sample, photometric, samples_per_pixel) =
             tiff_extraction_utils::get_tiff_image_properties(original_ifd);
 
-        // Get the file path and GeoTIFF information
+        // Get the file path and GeoTIFF information; SubIFDs without their
+        // own georeferencing share the main image's, at their own resolution
         let file_path = self.reader.get_file_path().unwrap_or(tiff_path);
-        let (pixel_scale, tiepoint) = tiff_extraction_utils::read_geotiff_info(
-            original_ifd, &self.reader, file_path);
+        let geo_ifd = if original_ifd.has_tag(tags::MODEL_PIXEL_SCALE_TAG) { original_ifd } else { main_ifd };
+        let (mut pixel_scale, mut tiepoint) = tiff_extraction_utils::read_geotiff_info(
+            geo_ifd, &self.reader, file_path);
+        if !std::ptr::eq(geo_ifd, original_ifd) && pixel_scale.len() >= 2 {
+            let (factor_x, factor_y) = tiff_extraction_utils::ifd_scale_factors(main_ifd, original_ifd);
+            pixel_scale[0] *= factor_x;
+            pixel_scale[1] *= factor_y;
+        }
 
         // Determine extraction region
-        let extracted_region = region.unwrap_or_else(|| {
+        let extracted_region = region.map(|region| {
+            tiff_extraction_utils::scale_region_to_ifd(region, main_ifd, original_ifd)
+        }).unwrap_or_else(|| {
             if let Some((width, height)) = original_ifd.get_dimensions() {
                 Region::new(0, 0, width as u32, height as u32)
             } else {
@@ -93,8 +189,27 @@ impl<'a> ExtractorStrategy for TiffExtractorStrategy<'a> {
           extracted_region.x, extracted_region.y,
           extracted_region.width, extracted_region.height);
 
+        // The decoded window, its RGB copy and the encoded strips are held
+        // together while writing, three bytes per pixel each
+        memory_utils::check_fits(
+            &format!("Writing the {}x{} region", extracted_region.width, extracted_region.height),
+            extracted_region.width as u64 * extracted_region.height as u64 * 9)?;
+
+        // Padded GeoTIFF output keeps the stored samples, whatever their type
+        let format = OutputFormat::resolve(output_path, self.output_format);
+        let masked = shape.is_some_and(|shape| shape.eq_ignore_ascii_case("circle"));
+        if let (Some(padding), true) = (self.padding, format.is_tiff() && !masked) {
+            return self.write_padded(tiff_path, output_path, extracted_region, &padding);
+        }
+
         // Extract the image data
-        let image = self.extract_image(tiff_path, Some(extracted_region))?;
+        let image = self.extract_image(tiff_path, region)?;
+
+        // A padded image starts up and left of the extracted region
+        if let (Some(padding), true) = (self.padding, pixel_scale.len() >= 2 && tiepoint.len() >= 6) {
+            tiepoint[3] -= padding.offset_x as f64 * pixel_scale[0];
+            tiepoint[4] += padding.offset_y as f64 * pixel_scale[1].abs();
+        }
 
         // Apply shape mask if needed
         let final_image = if let Some(shape_str) = shape {
@@ -110,10 +225,15 @@ impl<'a> ExtractorStrategy for TiffExtractorStrategy<'a> {
         // Check if we need to use PNG format for transparency
         if let Some(shape_str) = shape {
             if shape_str.to_lowercase() == "circle" {
-                return crate::utils::mask_utils::save_shaped_image(&final_image, output_path, shape_str);
+                return crate::utils::mask_utils::save_shaped_image(&final_image, output_path, shape_str, self.output_format);
             }
         }
 
+        // Other image formats have no place for the TIFF metadata
+        if !format.is_tiff() {
+            return format.write_image(&final_image, output_path);
+        }
+
         // Otherwise continue with normal TIFF saving
         let image_for_tiff = final_image.to_rgb8();
 
@@ -123,13 +243,15 @@ impl<'a> ExtractorStrategy for TiffExtractorStrategy<'a> {
         let ifd_index = builder.add_ifd(new_ifd);
 
         // Set up common TIFF tags
-        tiff_extraction_utils::setup_tiff_tags(&mut builder, ifd_index, original_ifd, &final_image)?;
+        tiff_extraction_utils::setup_tiff_tags(&mut builder, ifd_index, original_ifd, &self.reader, &final_image)?;
 
         // Copy statistics tags
         builder.copy_statistics_tags(ifd_index, original_ifd);
 
         // Copy and adjust GeoTIFF metadata
-        builder.copy_geotiff_tags(ifd_index, original_ifd, &mut self.reader)?;
+        builder.copy_geotiff_tags(ifd_index, geo_ifd, &mut self.reader)?;
+        builder.copy_icc_profile(ifd_index, original_ifd, &self.reader)?;
+        builder.copy_exif_metadata(ifd_index, original_ifd, &self.reader)?;
         builder.adjust_geotiff_for_region(ifd_index, &extracted_region, &pixel_scale, &tiepoint)?;
 
         // Process image data based on format
@@ -141,9 +263,22 @@ impl<'a> ExtractorStrategy for TiffExtractorStrategy<'a> {
             tiff_extraction_utils::process_rgb_image(&final_image, &mut builder, ifd_index)?;
         }
 
-        // Handle NoData value
-        let nodata_value = tiff_extraction_utils::extract_nodata_value(original_ifd, &self.reader);
-        let metadata_str = tiff_extraction_utils::extract_gdal_metadata(original_ifd, &self.reader);
+        // Handle NoData value; padded pixels are marked as NoData
+        let mut nodata_value = tiff_extraction_utils::extract_nodata_value(original_ifd, &self.reader);
+        if let Some(fill) = self.padding.and_then(|padding| padding.fill) {
+            nodata_value = fill.to_string();
+        }
+        let mut metadata_str = tiff_extraction_utils::extract_gdal_metadata(original_ifd, &self.reader);
+
+        // Record where the output came from
+        if let Some(provenance) = &self.provenance {
+            info!("Writing provenance of {}", provenance.source);
+            let items = provenance.metadata_items(&extracted_region);
+            metadata_str = Some(items.iter().fold(
+                metadata_str.unwrap_or_else(|| "<GDALMetadata>\n</GDALMetadata>".to_string()),
+                |metadata, item| xml_utils::add_to_gdal_metadata(&metadata, item)));
+            builder.add_image_description(ifd_index, &provenance.description(&extracted_region));
+        }
 
         // Set NoData tag and metadata
         info!("Setting NoData value: '{}'", nodata_value);
@@ -176,22 +311,23 @@ impl<'a> ExtractorStrategy for TiffExtractorStrategy<'a> {
         // Load the TIFF file
         let tiff = self.reader.load(tiff_path)?;
 
-        if tiff.ifds.is_empty() {
-            return Err(TiffError::GenericError("No IFDs found in TIFF file".to_string()));
-        }
+        let main_ifd = tiff_extraction_utils::select_source_ifd(&tiff, None)?;
 
-        // Use the first IFD
-        let ifd = &tiff.ifds[0];
+        // Use the first IFD, or the requested SubIFD of it
+        let ifd = tiff_extraction_utils::select_source_ifd(&tiff, self.sub_ifd)?;
+        let region = region.map(|region| tiff_extraction_utils::scale_region_to_ifd(region, main_ifd, ifd));
 
         // Determine and validate the extraction region
         let region = tiff_extraction_utils::determine_extraction_region(region, ifd)?;
 
         info!("Extracting region: ({}, {}) with size {}x{}",
               region.x, region.y, region.width, region.height);
+        memory_utils::check_fits(&format!("The {}x{} extraction window", region.width, region.height),
+                                 region.width as u64 * region.height as u64 * 3)?;
 
         // Open file for reading
-        let file = File::open(tiff_path)?;
-        let reader = BufReader::with_capacity(1024 * 1024, file);
+        let file = storage::open(tiff_path)?;
+        let reader = BufReader::with_capacity(memory_utils::io_buffer_size(), file);
 
         // Extract the pixel data
         let mut image = ImageBuffer::<Rgb<u8>, Vec<u8>>::new(region.width, region.height);
@@ -199,15 +335,31 @@ impl<'a> ExtractorStrategy for TiffExtractorStrategy<'a> {
         // Check if we're using strips or tiles
         let is_tiled = ifd.has_tag(tags::TILE_WIDTH) && ifd.has_tag(tags::TILE_LENGTH);
 
+        if let Some(cache) = self.block_cache.as_mut() {
+            cache.use_file(tiff_path);
+        }
+
         if is_tiled {
             let mut tile_reader = TileReader::new(reader, ifd, &self.reader);
-            tile_reader.extract(&mut image, region)?;
+            tile_reader.extract(&mut image, region, self.block_cache.as_mut())?;
         } else {
             let mut strip_reader = StripReader::new(reader, ifd, &self.reader);
-            strip_reader.extract(&mut image, region)?;
+            strip_reader.extract(&mut image, region, self.block_cache.as_mut())?;
+        }
+
+        if let Some(cache) = &self.block_cache {
+            let (decoded, reused) = cache.stats();
+            debug!("Block cache: {} blocks decoded, {} reads reused, {} bytes held", decoded, reused, cache.cached_bytes());
         }
 
-        Ok(DynamicImage::ImageRgb8(image))
+        let image = DynamicImage::ImageRgb8(image);
+        match self.padding {
+            Some(padding) => {
+                let fill = padding.fill_value(array_strategy::declared_nodata(&mut self.reader, tiff_path))?;
+                Ok(image_extraction_utils::pad_image(&image, &padding, fill))
+            },
+            None => Ok(image),
+        }
     }
 
     // Existing method implementations...
@@ -245,11 +397,44 @@ impl<'a> ExtractorStrategy for TiffExtractorStrategy<'a> {
                           region: Option<Region>) -> TiffResult<ArrayData> {
         info!("TIFF strategy: Extracting array data to memory");
 
-        // Extract image first
+        // Extract image first; with block caching this reuses the blocks
+        // of an image extracted from the same window
         let image = self.extract_image(source_path, region)?;
 
-        // Convert to array data
-        Ok(ArrayData::from_image(&image))
+        // Convert to array data, georeferenced like the array strategy's
+        let mut array_data = ArrayData::from_image(&image);
+        array_strategy::attach_window_metadata(&mut array_data, &mut self.reader, source_path,
+                                               region, self.sub_ifd, self.padding);
+        Ok(array_data)
+    }
+
+    /// Pad extracted images to the requested window
+    fn set_padding(&mut self, padding: Option<Padding>) {
+        self.padding = padding;
+    }
+
+    /// Keep decoded blocks for later extractions from the same file
+    fn set_block_caching(&mut self, enabled: bool) {
+        match (enabled, self.block_cache.is_some()) {
+            (true, false) => self.block_cache = Some(BlockCache::new()),
+            (false, true) => self.block_cache = None,
+            _ => {},
+        }
+    }
+
+    /// Write a provenance record into subsequent outputs
+    fn set_provenance(&mut self, provenance: Option<Provenance>) {
+        self.provenance = provenance;
+    }
+
+    /// Read pixels from a SubIFD of the main IFD
+    fn set_sub_ifd(&mut self, sub_ifd: Option<usize>) {
+        self.sub_ifd = sub_ifd;
+    }
+
+    /// Write outputs in the requested format
+    fn set_output_format(&mut self, format: Option<OutputFormat>) {
+        self.output_format = format;
     }
 
     /// Check if this strategy supports the given file format
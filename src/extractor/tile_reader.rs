@@ -7,6 +7,7 @@
 
 use log::{debug, info, warn};
 use std::io::SeekFrom;
+use std::time::Instant;
 use image::{ImageBuffer, Rgb};
 
 use crate::io::seekable::SeekableReader;
@@ -15,8 +16,11 @@ use crate::tiff::errors::TiffResult;
 use crate::tiff::ifd::IFD;
 use crate::tiff::constants::{tags, predictor as pred_consts};
 use crate::compression::CompressionFactory;
-use crate::utils::image_extraction_utils;
+use crate::utils::{image_extraction_utils, metrics};
 
+use std::borrow::Cow;
+
+use super::block_cache::BlockCache;
 use super::region::Region;
 
 /// Reads image data from tiled TIFF files
@@ -92,17 +96,21 @@ impl<'a, R: SeekableReader> TileReader<'a, R> {
         tile_height: usize
     ) -> TiffResult<Vec<u8>> {
         // Read the compressed tile data
+        let started = Instant::now();
         self.reader.seek(SeekFrom::Start(offset))?;
         let mut compressed_data = vec![0u8; byte_count as usize];
         self.reader.read_exact(&mut compressed_data)?;
+        metrics::record_block_read(byte_count, started.elapsed());
 
         // Decompress the tile data
+        let started = Instant::now();
         let mut tile_data = compression_handler.decompress(&compressed_data)?;
 
         // Apply predictor if needed
         if predictor == pred_consts::HORIZONTAL_DIFFERENCING as usize {
             image_extraction_utils::apply_horizontal_predictor(&mut tile_data, tile_width, tile_height);
         }
+        metrics::record_decode(compression_handler.name(), byte_count, tile_data.len() as u64, started.elapsed());
 
         Ok(tile_data)
     }
@@ -110,26 +118,28 @@ impl<'a, R: SeekableReader> TileReader<'a, R> {
     /// Extract image data to the provided buffer
     ///
     /// Reads all tiles that intersect with the specified region and
-    /// copies their pixel data to the output image.
+    /// copies their pixel data to the output image. With a cache, tiles
+    /// decoded by earlier extractions are reused.
     ///
     /// # Arguments
     /// * `image` - Output image buffer
     /// * `region` - Region of the image to extract
+    /// * `cache` - Cache of decoded tiles, None to decode every tile
     ///
     /// # Returns
     /// Result indicating success or failure
     pub fn extract(
         &mut self,
         image: &mut ImageBuffer<Rgb<u8>, Vec<u8>>,
-        region: Region
+        region: Region,
+        mut cache: Option<&mut BlockCache>
     ) -> TiffResult<()> {
         // Get tile dimensions
         let (tile_width, tile_height) = self.get_tile_dimensions();
         info!("Tile dimensions: {}x{}", tile_width, tile_height);
 
-        // Get compression type
-        let compression = self.ifd.get_tag_value(tags::COMPRESSION).unwrap_or(1);
-        let compression_handler = CompressionFactory::create_handler(compression)?;
+        // Get the compression handler, with the tables of JPEG images
+        let compression_handler = CompressionFactory::create_handler_for_ifd(self.tiff_reader, self.ifd)?;
         info!("Using compression: {}", compression_handler.name());
 
         // Get predictor
@@ -173,14 +183,19 @@ impl<'a, R: SeekableReader> TileReader<'a, R> {
                        tile_x, tile_y, offset, byte_count);
 
                 // Read and process the tile data
-                let tile_data = match self.read_tile(
+                let mut decode = || self.read_tile(
                     offset,
                     byte_count,
                     &*compression_handler,
                     predictor,
                     tile_width as usize,
                     tile_height as usize
-                ) {
+                );
+                let tile_data = match cache.as_deref_mut() {
+                    Some(cache) => cache.get_or_decode(tile_index, decode).map(Cow::Borrowed),
+                    None => decode().map(Cow::Owned),
+                };
+                let tile_data = match tile_data {
                     Ok(data) => data,
                     Err(e) => {
                         warn!("Error reading tile ({},{}): {:?}", tile_x, tile_y, e);
@@ -231,6 +246,9 @@ impl<'a, R: SeekableReader> TileReader<'a, R> {
         tile_start_y: u32,
         region: Region
     ) {
+        // Color images hold several bytes per pixel
+        let stride = image_extraction_utils::pixel_stride(self.ifd);
+
         // For each row in the tile
         for y in 0..tile_height {
             let global_y = tile_start_y + y as u32;
@@ -246,12 +264,13 @@ impl<'a, R: SeekableReader> TileReader<'a, R> {
                 let tile_idx = y * tile_width + x;
 
                 // Copy the pixel using the utility function
-                image_extraction_utils::copy_pixel(
+                image_extraction_utils::copy_interleaved_pixel(
                     tile_data,
                     image,
                     global_x,
                     global_y,
                     tile_idx,
+                    stride,
                     region
                 );
             }
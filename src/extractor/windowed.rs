@@ -0,0 +1,119 @@
+//! Block-by-block extraction of large windows
+//!
+//! `extract_image` and the buffer decoders hold the whole window in memory,
+//! which rules out windows of billions of pixels from 100k x 100k BigTIFFs.
+//! `extract_windowed` streams the window instead: every tile or strip it
+//! touches is decoded, clipped to the window and handed to a callback, then
+//! dropped before the next one is read. Memory stays at about one decoded
+//! block however large the window is, and the callback decides what to keep.
+
+use std::cell::Cell;
+use std::ops::Range;
+
+use log::info;
+
+use crate::tiff::errors::TiffResult;
+use crate::utils::logger::Logger;
+use crate::utils::sample_utils;
+
+use super::buffer_decode::{self, DecodedWindow};
+use super::region::Region;
+
+/// The part of a window one decoded block covers
+#[derive(Debug)]
+pub struct WindowBlock<'a> {
+    /// Pixels covered, in image coordinates; always inside the window
+    pub region: Region,
+    /// Bands held: all of them for chunky data, one for planar data
+    pub bands: Range<usize>,
+    /// Bytes of one sample
+    pub bytes_per_sample: usize,
+    /// Samples row by row with the bands of a pixel interleaved, at their
+    /// stored width in native byte order
+    pub data: &'a [u8],
+}
+
+impl WindowBlock<'_> {
+    /// Bytes of one sample of the block
+    ///
+    /// # Arguments
+    /// * `column` - Column in image coordinates, inside `region`
+    /// * `row` - Row in image coordinates, inside `region`
+    /// * `band` - Band, one of `bands`
+    pub fn sample(&self, column: u32, row: u32, band: usize) -> &[u8] {
+        let pixel = (row - self.region.y) as usize * self.region.width as usize + (column - self.region.x) as usize;
+        let index = pixel * self.bands.len() + band - self.bands.start;
+        &self.data[index * self.bytes_per_sample..(index + 1) * self.bytes_per_sample]
+    }
+}
+
+/// Stream a window of a TIFF's first image to a callback, block by block
+///
+/// Blocks arrive in file order, each clipped to the window; for planar
+/// data every band of a block position arrives separately. A block missing
+/// from the file is skipped. The first error returned by the callback stops
+/// the extraction before the next block is read.
+///
+/// # Arguments
+/// * `input_path` - Path to the TIFF
+/// * `region` - Window to stream, None for the whole image
+/// * `logger` - Logger for recording operations
+/// * `visit` - Receives the shape of the window and each of its blocks
+///
+/// # Returns
+/// The shape and sample type of the streamed window
+pub fn extract_windowed(input_path: &str, region: Option<Region>, logger: &Logger,
+                        mut visit: impl FnMut(&DecodedWindow, &WindowBlock) -> TiffResult<()>) -> TiffResult<DecodedWindow> {
+    let (window, shape) = buffer_decode::open_window(input_path, region, logger)?;
+    info!("Streaming a {}x{} window of {} block by block", window.width, window.height, input_path);
+
+    let stopped = Cell::new(false);
+    let mut failure = None;
+    let mut data = Vec::new();
+    let mut blocks = 0usize;
+
+    sample_utils::read_blocks(
+        input_path, logger,
+        |layout, index| !stopped.get() && buffer_decode::block_touches(layout, index, &window),
+        |_, block| {
+            let (first_column, last_column) = (block.x.max(window.x), (block.x + block.width).min(window.end_x()));
+            let (first_row, last_row) = (block.y.max(window.y), (block.y + block.height).min(window.end_y()));
+            if stopped.get() || first_column >= last_column || first_row >= last_row {
+                return;
+            }
+
+            // The buffer is reused, so only the largest block is ever held
+            let bytes = shape.bytes_per_sample;
+            data.clear();
+            data.resize((last_column - first_column) as usize * (last_row - first_row) as usize
+                            * block.bands.len() * bytes, 0);
+            let mut target = data.chunks_exact_mut(bytes);
+            for row in first_row..last_row {
+                for column in first_column..last_column {
+                    for band in block.bands.clone() {
+                        if let (Some(raw), Some(sample)) = (block.sample(column - block.x, row - block.y, band), target.next()) {
+                            buffer_decode::store_native(raw, sample);
+                        }
+                    }
+                }
+            }
+
+            let window_block = WindowBlock {
+                region: Region::new(first_column, first_row, last_column - first_column, last_row - first_row),
+                bands: block.bands.clone(),
+                bytes_per_sample: bytes,
+                data: &data,
+            };
+            blocks += 1;
+            if let Err(e) = visit(&shape, &window_block) {
+                failure = Some(e);
+                stopped.set(true);
+            }
+        })?;
+
+    if let Some(e) = failure {
+        return Err(e);
+    }
+    info!("Streamed {} block(s) of the window", blocks);
+    Ok(shape)
+}
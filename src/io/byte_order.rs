@@ -68,6 +68,9 @@ pub trait ByteOrderHandler: Send + Sync {
 
     /// Read a signed rational value (two i32 values as numerator/denominator)
     fn read_srational(&self, reader: &mut dyn SeekableReader) -> Result<(i32, i32)>;
+
+    /// The byte order this handler reads
+    fn byte_order(&self) -> ByteOrder;
 }
 
 /// Little-endian byte order handler
@@ -105,6 +108,10 @@ impl ByteOrderHandler for LittleEndianHandler {
         let denominator = reader.read_i32::<LittleEndian>()?;
         Ok((numerator, denominator))
     }
+
+    fn byte_order(&self) -> ByteOrder {
+        ByteOrder::LittleEndian
+    }
 }
 
 /// Big-endian byte order handler
@@ -142,4 +149,8 @@ impl ByteOrderHandler for BigEndianHandler {
         let denominator = reader.read_i32::<BigEndian>()?;
         Ok((numerator, denominator))
     }
+
+    fn byte_order(&self) -> ByteOrder {
+        ByteOrder::BigEndian
+    }
 }
\ No newline at end of file
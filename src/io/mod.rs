@@ -3,4 +3,7 @@
 //! This module provides traits and implementations for various I/O operations.
 
 pub mod seekable;
-pub mod byte_order;
\ No newline at end of file
+pub mod byte_order;
+pub mod storage;
+#[cfg(feature = "object-store")]
+pub mod object_storage;
@@ -0,0 +1,79 @@
+//! Cloud object storage backend
+//!
+//! Reads objects from Amazon S3, Google Cloud Storage and Azure Blob
+//! Storage through the `object_store` crate. Credentials, regions and
+//! endpoints come from the environment, as the cloud CLIs take them:
+//! `AWS_ACCESS_KEY_ID`, `AWS_SECRET_ACCESS_KEY`, `AWS_REGION` and
+//! `AWS_ENDPOINT` for S3, `GOOGLE_SERVICE_ACCOUNT` or
+//! `GOOGLE_APPLICATION_CREDENTIALS` for GCS, and
+//! `AZURE_STORAGE_ACCOUNT_NAME` with `AZURE_STORAGE_ACCOUNT_KEY` or
+//! `AZURE_STORAGE_SAS_KEY` for Azure.
+
+use std::io;
+
+use log::debug;
+use object_store::aws::AmazonS3Builder;
+use object_store::azure::MicrosoftAzureBuilder;
+use object_store::gcp::GoogleCloudStorageBuilder;
+use object_store::path::Path;
+use object_store::ObjectStore;
+use tokio::runtime::{Builder, Runtime};
+
+use crate::io::storage::{self, Storage};
+use crate::tiff::errors::{TiffError, TiffResult};
+
+/// One object in cloud storage
+pub struct ObjectStorage {
+    /// Store holding the object
+    store: Box<dyn ObjectStore>,
+    /// Key of the object in the store
+    location: Path,
+    /// Size of the object in bytes
+    size: u64,
+    /// Runtime driving the store's requests
+    runtime: Runtime,
+}
+
+impl ObjectStorage {
+    /// Open an object by its URI
+    ///
+    /// # Arguments
+    /// * `uri` - URI such as `s3://bucket/path/to/image.tif`
+    ///
+    /// # Returns
+    /// The storage, or an error if the store cannot be configured or the
+    /// object cannot be found
+    pub fn open(uri: &str) -> TiffResult<Self> {
+        let (scheme, bucket, key) = storage::parse_uri(uri)?;
+        let failed = |e: object_store::Error| TiffError::GenericError(format!("Cannot open {}: {}", uri, e));
+        let store: Box<dyn ObjectStore> = match scheme.as_str() {
+            "s3" => Box::new(AmazonS3Builder::from_env().with_bucket_name(bucket).build().map_err(failed)?),
+            "gs" => Box::new(GoogleCloudStorageBuilder::from_env().with_bucket_name(bucket).build().map_err(failed)?),
+            _ => Box::new(MicrosoftAzureBuilder::from_env().with_container_name(bucket).build().map_err(failed)?),
+        };
+
+        let runtime = Builder::new_current_thread().enable_all().build()?;
+        let location = Path::from(key);
+        let size = runtime.block_on(store.head(&location)).map_err(failed)?.size;
+        debug!("Opened {} of {} bytes", uri, size);
+
+        Ok(ObjectStorage { store, location, size, runtime })
+    }
+}
+
+impl Storage for ObjectStorage {
+    fn size(&self) -> io::Result<u64> {
+        Ok(self.size)
+    }
+
+    fn read_at(&self, offset: u64, buf: &mut [u8]) -> io::Result<usize> {
+        let end = self.size.min(offset + buf.len() as u64);
+        if offset >= end {
+            return Ok(0);
+        }
+        let bytes = self.runtime.block_on(self.store.get_range(&self.location, offset..end))
+            .map_err(io::Error::other)?;
+        buf[..bytes.len()].copy_from_slice(&bytes);
+        Ok(bytes.len())
+    }
+}
@@ -0,0 +1,182 @@
+//! Storage backends for reading rasters
+//!
+//! Readers reach their input through a `Storage`, a source of byte ranges
+//! that may be a local file or an object in cloud storage. Local paths
+//! open as plain files; with the `object-store` feature, `s3://`, `gs://`
+//! and `az://` URIs are read with ranged requests, so only the header,
+//! the IFDs and the blocks of the requested window are fetched.
+
+use std::collections::VecDeque;
+use std::fs::File;
+use std::io::{self, Read, Seek, SeekFrom};
+
+use log::debug;
+
+use crate::io::seekable::SeekableReader;
+use crate::tiff::errors::{TiffError, TiffResult};
+
+/// Bytes fetched from a storage at once for small reads
+pub const BLOCK_SIZE: usize = 64 * 1024;
+
+/// Blocks a `StorageReader` keeps, enough for an IFD chain and its tags
+const CACHED_BLOCKS: usize = 16;
+
+/// URI schemes of cloud object storage
+pub const REMOTE_SCHEMES: [&str; 3] = ["s3", "gs", "az"];
+
+/// A source of bytes addressed by offset
+///
+/// Implementations must be safe to share between threads. Reads of a
+/// range past the end return fewer bytes, as `Read` does.
+pub trait Storage: Send + Sync {
+    /// Total size in bytes
+    fn size(&self) -> io::Result<u64>;
+
+    /// Read bytes starting at an offset
+    ///
+    /// # Arguments
+    /// * `offset` - Position of the first byte
+    /// * `buf` - Buffer to fill
+    ///
+    /// # Returns
+    /// The number of bytes read, less than the buffer only at the end
+    fn read_at(&self, offset: u64, buf: &mut [u8]) -> io::Result<usize>;
+}
+
+/// Check whether a path names an object in cloud storage
+pub fn is_remote(path: &str) -> bool {
+    path.split_once("://").is_some_and(|(scheme, _)| REMOTE_SCHEMES.contains(&scheme.to_lowercase().as_str()))
+}
+
+/// Split an object storage URI into scheme, bucket and key
+///
+/// For `az://` the bucket is the container; the storage account comes
+/// from the environment.
+///
+/// # Arguments
+/// * `uri` - URI such as `s3://bucket/path/to/image.tif`
+///
+/// # Returns
+/// The parts, or an error for a URI without a bucket or a key
+pub fn parse_uri(uri: &str) -> TiffResult<(String, &str, &str)> {
+    let invalid = || TiffError::GenericError(format!(
+        "'{}' is not an object storage URI like s3://bucket/path/to/image.tif", uri));
+    let (scheme, rest) = uri.split_once("://").ok_or_else(invalid)?;
+    let scheme = scheme.to_lowercase();
+    if !REMOTE_SCHEMES.contains(&scheme.as_str()) {
+        return Err(invalid());
+    }
+    match rest.split_once('/') {
+        Some((bucket, key)) if !bucket.is_empty() && !key.is_empty() => Ok((scheme, bucket, key)),
+        _ => Err(invalid()),
+    }
+}
+
+/// Open a raster for reading from a local path or an object storage URI
+///
+/// # Arguments
+/// * `path` - Local path, or a `s3://`, `gs://` or `az://` URI
+///
+/// # Returns
+/// A reader positioned at the start of the data
+pub fn open(path: &str) -> TiffResult<Box<dyn SeekableReader>> {
+    if is_remote(path) {
+        return open_remote(path);
+    }
+    Ok(Box::new(File::open(path)?))
+}
+
+#[cfg(feature = "object-store")]
+fn open_remote(uri: &str) -> TiffResult<Box<dyn SeekableReader>> {
+    let storage = crate::io::object_storage::ObjectStorage::open(uri)?;
+    Ok(Box::new(StorageReader::new(storage)?))
+}
+
+#[cfg(not(feature = "object-store"))]
+fn open_remote(uri: &str) -> TiffResult<Box<dyn SeekableReader>> {
+    Err(TiffError::GenericError(format!(
+        "Reading {} requires building rasterkit with --features object-store", uri)))
+}
+
+/// Reader over a storage, caching the blocks of small reads
+///
+/// Parsing a header makes many reads of a few bytes each, which would be
+/// a request each against object storage. Reads smaller than a block are
+/// served from whole cached blocks instead; larger ones, such as tiles,
+/// go to the storage directly.
+pub struct StorageReader<S: Storage> {
+    /// Storage being read
+    storage: S,
+    /// Size of the storage in bytes
+    size: u64,
+    /// Position of the next read
+    position: u64,
+    /// Recently fetched blocks by starting offset, most recent last
+    blocks: VecDeque<(u64, Vec<u8>)>,
+}
+
+impl<S: Storage> StorageReader<S> {
+    /// Create a reader at the start of a storage
+    pub fn new(storage: S) -> io::Result<Self> {
+        let size = storage.size()?;
+        Ok(StorageReader { storage, size, position: 0, blocks: VecDeque::with_capacity(CACHED_BLOCKS) })
+    }
+
+    /// Index in the cache of the block holding an offset, fetching it if needed
+    fn block_at(&mut self, offset: u64) -> io::Result<usize> {
+        let start = offset - offset % BLOCK_SIZE as u64;
+        if let Some(index) = self.blocks.iter().position(|(block_start, _)| *block_start == start) {
+            return Ok(index);
+        }
+
+        let mut block = vec![0u8; BLOCK_SIZE.min((self.size - start) as usize)];
+        let mut filled = 0;
+        while filled < block.len() {
+            match self.storage.read_at(start + filled as u64, &mut block[filled..])? {
+                0 => break,
+                read => filled += read,
+            }
+        }
+        block.truncate(filled);
+        debug!("Fetched {} bytes from offset {}", filled, start);
+
+        if self.blocks.len() == CACHED_BLOCKS {
+            self.blocks.pop_front();
+        }
+        self.blocks.push_back((start, block));
+        Ok(self.blocks.len() - 1)
+    }
+}
+
+impl<S: Storage> Read for StorageReader<S> {
+    fn read(&mut self, buf: &mut [u8]) -> io::Result<usize> {
+        if buf.is_empty() || self.position >= self.size {
+            return Ok(0);
+        }
+        let read = if buf.len() >= BLOCK_SIZE {
+            self.storage.read_at(self.position, buf)?
+        } else {
+            let index = self.block_at(self.position)?;
+            let (start, block) = &self.blocks[index];
+            let available = &block[(self.position - start) as usize..];
+            let read = available.len().min(buf.len());
+            buf[..read].copy_from_slice(&available[..read]);
+            read
+        };
+        self.position += read as u64;
+        Ok(read)
+    }
+}
+
+impl<S: Storage> Seek for StorageReader<S> {
+    fn seek(&mut self, position: SeekFrom) -> io::Result<u64> {
+        let target = match position {
+            SeekFrom::Start(offset) => Some(offset),
+            SeekFrom::End(delta) => self.size.checked_add_signed(delta),
+            SeekFrom::Current(delta) => self.position.checked_add_signed(delta),
+        };
+        self.position = target.ok_or_else(|| io::Error::new(
+            io::ErrorKind::InvalidInput, "Seek to a negative or overflowing position"))?;
+        Ok(self.position)
+    }
+}
@@ -6,9 +6,15 @@ pub mod extractor;
 pub mod coordinate;
 pub mod commands;
 pub mod api;
+pub mod dataset;
 
 pub use crate::api::RasterKit;
+pub use crate::dataset::{BandSummary, Dataset};
+pub use crate::utils::acquisition_utils::{AcquisitionMetadata, DateRange, Timestamp};
+pub use crate::utils::chip_utils::ChipOptions;
+pub use crate::utils::normalize_utils::NormalizationMethod;
+pub use crate::utils::postgis_utils::{PostgisLoadMode, PostgisOptions};
 
 pub use tiff::TiffReader;
-pub use extractor::{ImageExtractor, Region};
-pub use coordinate::{BoundingBox, Point, CoordinateTransformer, CoordinateSystem};
\ No newline at end of file
+pub use extractor::{ExtractOptions, ImageExtractor, Region};
+pub use coordinate::{BoundingBox, Point, CoordinateTransformer, CoordinateSystem, Crs, LonLat, ProjectedXY};
\ No newline at end of file
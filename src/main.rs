@@ -1,9 +1,11 @@
-use clap::{Arg, Command as ClapCommand, ArgAction};
+use clap::{Arg, ArgMatches, Command as ClapCommand, ArgAction};
+use std::path::{Path, PathBuf};
 use std::process;
 use log::error;
 
 // Import from your library
 use rasterkit::utils::logger::Logger;
+use rasterkit::utils::{memory_utils, metrics, scratch_utils};
 use rasterkit::commands::{CommandFactory, RasterkitCommandFactory};
 
 fn main() {
@@ -24,11 +26,100 @@ fn main() {
                 .help("Enable verbose output")
                 .action(ArgAction::SetTrue),
         )
+        .arg(
+            Arg::new("metrics")
+                .long("metrics")
+                .help("Export read, decode and operation timings at the end of the run: JSON for a .json path, Prometheus text otherwise")
+                .value_name("FILE")
+                .required(false),
+        )
+        .arg(
+            Arg::new("max-memory")
+                .long("max-memory")
+                .help("Memory budget of the whole run in MB: sizes warp blocks, the block cache and file buffers, and refuses windows that would not fit")
+                .value_name("MB")
+                .required(false),
+        )
+        .arg(
+            Arg::new("temp-dir")
+                .long("temp-dir")
+                .help("Directory for scratch files of intermediate buffers spilled under --max-memory (default: the system's temporary directory)")
+                .value_name("DIR")
+                .required(false),
+        )
+        .arg(
+            Arg::new("stats")
+                .long("stats")
+                .help("Compute per-band min/max/mean/stddev and histograms from sampled blocks while analyzing")
+                .action(ArgAction::SetTrue),
+        )
+        .arg(
+            Arg::new("write-stats")
+                .long("write-stats")
+                .help("Compute band statistics (implies --stats) and save them as STATISTICS_* metadata in <input>.aux.xml")
+                .action(ArgAction::SetTrue),
+        )
+        .arg(
+            Arg::new("info")
+                .long("info")
+                .help("Print a gdalinfo-style summary: driver, size, CRS, origin, pixel size, corner coordinates, band types, blocks, NoData and overviews")
+                .action(ArgAction::SetTrue),
+        )
+        .arg(
+            Arg::new("footprint")
+                .long("footprint")
+                .help("Write the raster footprint as a GeoJSON polygon in WGS84 while analyzing (--info or the default analysis)")
+                .value_name("FILE")
+                .required(false),
+        )
+        .arg(
+            Arg::new("footprint-outline")
+                .long("footprint-outline")
+                .help("Trace the footprint around the pixels holding data (NoData-aware) instead of the bounding rectangle")
+                .action(ArgAction::SetTrue),
+        )
+        .arg(
+            Arg::new("find")
+                .long("find")
+                .help("Treat the input as a directory and list the rasters under it, reading only their headers")
+                .action(ArgAction::SetTrue),
+        )
+        .arg(
+            Arg::new("index")
+                .long("index")
+                .help("Write a spatial index of the rasters under the input directory to --output: footprints and header fields as GeoJSON, or a GeoPackage for a .gpkg path")
+                .action(ArgAction::SetTrue),
+        )
+        .arg(
+            Arg::new("where")
+                .long("where")
+                .help("Only list (--find) or index (--index) the rasters matching EXPR, e.g. 'epsg=3857 && compression=zstd && width>10000'")
+                .value_name("EXPR")
+                .required(false),
+        )
+        .arg(
+            Arg::new("json")
+                .long("json")
+                .help("List --find matches as JSON instead of a table (printed, or written to --output)")
+                .action(ArgAction::SetTrue),
+        )
+        .arg(
+            Arg::new("dump-tags")
+                .long("dump-tags")
+                .help("List every tag of every IFD with decoded values, like tiffinfo -d (all values with --verbose)")
+                .action(ArgAction::SetTrue),
+        )
+        .arg(
+            Arg::new("repair")
+                .long("repair")
+                .help("Report structural damage such as truncated strips or a broken IFD chain; with --output, write a repaired copy")
+                .action(ArgAction::SetTrue),
+        )
         .arg(
             Arg::new("extract")
                 .short('e')
                 .long("extract")
-                .help("Extract image data")
+                .help("Extract image data; from a directory or index (.geojson, .gpkg) input, mosaic the rasters covering --bbox")
                 .action(ArgAction::SetTrue),
         )
         .arg(
@@ -42,8 +133,37 @@ fn main() {
         .arg(
             Arg::new("bbox")
                 .long("bbox")
-                .help("Bounding box for extraction (minx,miny,maxx,maxy)")
+                .help("Bounding box for extraction (minx,miny,maxx,maxy); repeat for several regions")
                 .value_name("BBOX")
+                .action(ArgAction::Append)
+                .required(false),
+        )
+        .arg(
+            Arg::new("regions")
+                .long("regions")
+                .help("File with one bounding box or coordinate per line, extracted to numbered outputs")
+                .value_name("FILE")
+                .required(false),
+        )
+        .arg(
+            Arg::new("out-pattern")
+                .long("out-pattern")
+                .help("Output name template, e.g. \"{stem}_{minx}_{miny}.tif\"; tokens: {stem} {index} {minx} {miny} {maxx} {maxy} {x} {y} {crs} {date}")
+                .value_name("PATTERN")
+                .required(false),
+        )
+        .arg(
+            Arg::new("output-format")
+                .long("output-format")
+                .help("Format of the extracted output instead of the one implied by its extension (geotiff, png, jpeg, webp, avif, npy, npz, safetensors, csv, csv-long, json, parquet, sqlite, gpkg, pgsql, envi)")
+                .value_name("FORMAT")
+                .required(false),
+        )
+        .arg(
+            Arg::new("quality")
+                .long("quality")
+                .help("Quality of JPEG, WebP and AVIF output from 1 to 100; WebP is lossless without it")
+                .value_name("QUALITY")
                 .required(false),
         )
         .arg(
@@ -57,23 +177,142 @@ fn main() {
         .arg(
             Arg::new("crs")
                 .long("crs")
-                .help("EPSG code for coordinates (e.g., 4326 for WGS84, 3857 for Web Mercator)")
-                .value_name("CODE")
+                .help("CRS of the coordinates: EPSG code (e.g., 4326 for WGS84, 3857 for Web Mercator), PROJ string or WKT")
+                .value_name("CRS")
                 .default_value("4326")
                 .required(false),
         )
+        .arg(
+            Arg::new("axis-order")
+                .long("axis-order")
+                .help("Axis order of --coordinate, --bbox and region files in a geographic CRS: lonlat, latlon or auto (lon,lat unless a value only fits as latitude first)")
+                .value_name("ORDER")
+                .default_value("auto")
+                .required(false),
+        )
         .arg(
             Arg::new("proj")
                 .long("proj")
-                .help("Target projection EPSG code for reprojection")
-                .value_name("CODE")
+                .help("Target projection for reprojection: EPSG code, PROJ string or WKT")
+                .value_name("CRS")
+                .required(false),
+        )
+        .arg(
+            Arg::new("tr")
+                .long("tr")
+                .help("Target resolution of the reprojection in target units: XRES or XRES,YRES")
+                .value_name("RES")
+                .required(false),
+        )
+        .arg(
+            Arg::new("tap")
+                .long("tap")
+                .help("Align the reprojected grid to multiples of --tr, so outputs from different sources stack")
+                .action(ArgAction::SetTrue)
+                .required(false),
+        )
+        .arg(
+            Arg::new("warp-memory")
+                .long("warp-memory")
+                .help("Memory budget of the reprojection in MB; the output is warped in blocks that each read only their source window")
+                .value_name("MB")
+                .required(false),
+        )
+        .arg(
+            Arg::new("src-nodata")
+                .long("src-nodata")
+                .help("Source value treated as no data when reprojecting (default: the source NoData value)")
+                .value_name("VALUE")
+                .allow_hyphen_values(true)
+                .required(false),
+        )
+        .arg(
+            Arg::new("dst-nodata")
+                .long("dst-nodata")
+                .help("Value of reprojected pixels without data, also recorded as the output NoData (default: the source NoData)")
+                .value_name("VALUE")
+                .allow_hyphen_values(true)
+                .required(false),
+        )
+        .arg(
+            Arg::new("dst-alpha")
+                .long("dst-alpha")
+                .help("Add an alpha band to the reprojected output, transparent where it has no data")
+                .action(ArgAction::SetTrue)
+                .required(false),
+        )
+        .arg(
+            Arg::new("error-threshold")
+                .long("error-threshold")
+                .help("Largest error in source pixels of the approximate reprojection transform, 0 to project every pixel exactly (default: 0.125)")
+                .value_name("PIXELS")
+                .required(false),
+        )
+        .arg(
+            Arg::new("gpu")
+                .long("gpu")
+                .help("Resample on the GPU when reprojecting or pan-sharpening (builds with the gpu feature), falling back to the CPU")
+                .action(ArgAction::SetTrue)
+                .required(false),
+        )
+        .arg(
+            Arg::new("datum-grid")
+                .long("datum-grid")
+                .help("NTv2 grid (.gsb) shifting the raster datum to WGS84, used instead of the built-in Helmert shift")
+                .value_name("FILE")
                 .required(false),
         )
         .arg(
             Arg::new("coordinate")
                 .long("coordinate")
-                .help("Geographic coordinate for extraction in 'x,y' format")
+                .help("Geographic coordinate for extraction in 'x,y' format; repeat for several regions")
                 .value_name("COORDINATE")
+                .action(ArgAction::Append)
+                .required(false),
+        )
+        .arg(
+            Arg::new("place")
+                .long("place")
+                .help("Place name looked up by a geocoder and extracted like --coordinate, e.g. \"Berlin, Germany\" (needs the geocoding feature)")
+                .value_name("NAME")
+                .required(false),
+        )
+        .arg(
+            Arg::new("geocoder-url")
+                .long("geocoder-url")
+                .help("Base URL of the Nominatim server used by --place")
+                .value_name("URL")
+                .default_value("https://nominatim.openstreetmap.org")
+                .required(false),
+        )
+        .arg(
+            Arg::new("cell")
+                .long("cell")
+                .help("Geohash or H3 cell ID whose bounding box is extracted, e.g. u33dc0 or 8928308280fffff; repeat for several regions")
+                .value_name("CELL")
+                .action(ArgAction::Append)
+                .required(false),
+        )
+        .arg(
+            Arg::new("tile")
+                .long("tile")
+                .help("Slippy-map tile z/x/y whose extent is extracted, e.g. 12/2200/1343")
+                .value_name("Z/X/Y")
+                .required(false),
+        )
+        .arg(
+            Arg::new("tile-scheme")
+                .long("tile-scheme")
+                .help("Tiling of --tile: webmercator (XYZ, row 0 at the top), tms (row 0 at the bottom) or geodetic (WGS84)")
+                .value_name("SCHEME")
+                .default_value("webmercator")
+                .required(false),
+        )
+        .arg(
+            Arg::new("tile-pixels")
+                .long("tile-pixels")
+                .help("Resample the --tile extraction onto the tile grid at this many pixels square, e.g. 256 or 512")
+                .value_name("PIXELS")
                 .required(false),
         )
         .arg(
@@ -100,11 +339,30 @@ fn main() {
         .arg(
             Arg::new("array-format")
                 .long("array-format")
-                .help("Format for array output (csv, json, npy)")
+                .help("Format for array output (csv, csv-long, json, npy, safetensors, sqlite, gpkg, pgsql, parquet, envi; npz with --normalize)")
                 .value_name("FORMAT")
                 .default_value("csv")
                 .required(false),
         )
+        .arg(
+            Arg::new("array-output")
+                .long("array-output")
+                .help("Also write the extracted window as an array to this file (format from --array-format or the extension); the source is decoded once")
+                .value_name("FILE")
+                .required(false),
+        )
+        .arg(
+            Arg::new("array-coords")
+                .long("array-coords")
+                .help("Label CSV/JSON array output with map coordinates of the cell centers")
+                .action(ArgAction::SetTrue),
+        )
+        .arg(
+            Arg::new("spatial-index")
+                .long("spatial-index")
+                .help("Add a spatial index to sqlite/gpkg (R*Tree) or pgsql (GiST) array output")
+                .action(ArgAction::SetTrue),
+        )
         .arg(
             Arg::new("filter")
                 .long("filter")
@@ -112,6 +370,39 @@ fn main() {
                 .value_name("MIN,MAX")
                 .required(false),
         )
+        .arg(
+            Arg::new("align-to-blocks")
+                .long("align-to-blocks")
+                .help("Expand the extraction window to tile/strip boundaries")
+                .action(ArgAction::SetTrue),
+        )
+        .arg(
+            Arg::new("copy-blocks")
+                .long("copy-blocks")
+                .help("Crop a TIFF by copying the compressed tiles/strips of a block-aligned window as they are stored (falls back to decoding otherwise)")
+                .action(ArgAction::SetTrue)
+                .conflicts_with_all(["pad-to-bbox", "proj", "colormap-input", "colormap-auto", "filter", "extract-array", "icc-profile", "subifd"]),
+        )
+        .arg(
+            Arg::new("require-full-coverage")
+                .long("require-full-coverage")
+                .help("Fail if the bounding box extends beyond the raster")
+                .action(ArgAction::SetTrue),
+        )
+        .arg(
+            Arg::new("pad-to-bbox")
+                .long("pad-to-bbox")
+                .help("Pad the output to the full bounding box where it extends beyond the raster")
+                .action(ArgAction::SetTrue)
+                .conflicts_with("align-to-blocks"),
+        )
+        .arg(
+            Arg::new("fill-value")
+                .long("fill-value")
+                .value_name("VALUE")
+                .help("Value for padded pixels, required unless the source declares a NoData value")
+                .requires("pad-to-bbox"),
+        )
         .arg(
             Arg::new("filter-transparency")
                 .long("filter-transparency")
@@ -132,6 +423,58 @@ fn main() {
                 .value_name("FILE")
                 .required(false),
         )
+        .arg(
+            Arg::new("colormap-auto")
+                .long("colormap-auto")
+                .help("Generate a colormap from the data: METHOD[:CLASSES[:SCHEME]], e.g. quantile:7:viridis (methods: equal, quantile, jenks)")
+                .value_name("SPEC")
+                .conflicts_with("colormap-input")
+                .required(false),
+        )
+        .arg(
+            Arg::new("colormap-trim-black")
+                .long("colormap-trim-black")
+                .help("Drop leading black (0,0,0) palette entries when extracting a colormap (kept by default)")
+                .action(ArgAction::SetTrue),
+        )
+        .arg(
+            Arg::new("icc-profile")
+                .long("icc-profile")
+                .help("ICC color profile to embed in TIFF output of --extract or --convert")
+                .value_name("FILE")
+                .required(false),
+        )
+        .arg(
+            Arg::new("use-sidecars")
+                .long("use-sidecars")
+                .help("Read georeferencing from .tfw/.wld and .aux.xml sidecar files when the TIFF has none")
+                .action(ArgAction::SetTrue),
+        )
+        .arg(
+            Arg::new("subifd")
+                .long("subifd")
+                .value_name("INDEX")
+                .help("Extract from a SubIFD of the main image (mask or overview); see analyze for the list"),
+        )
+        .arg(
+            Arg::new("no-provenance")
+                .long("no-provenance")
+                .help("Don't record the source, region and parameters in the metadata of extracted TIFFs")
+                .action(ArgAction::SetTrue),
+        )
+        .arg(
+            Arg::new("report")
+                .long("report")
+                .help("Write a JSON report of each extraction: pixel window, output geotransform, CRS, NoData, band statistics and timing")
+                .value_name("FILE")
+                .required(false),
+        )
+        .arg(
+            Arg::new("strict")
+                .long("strict")
+                .help("Refuse to write any TIFF that violates the TIFF 6.0, BigTIFF or GeoTIFF specifications")
+                .action(ArgAction::SetTrue),
+        )
         .arg(
             Arg::new("convert")
                 .short('c')
@@ -142,17 +485,401 @@ fn main() {
         .arg(
             Arg::new("compression")
                 .long("compression")
-                .help("Target compression code (1=none, 8=deflate, 14=zstd)")
+                .help("Target compression code (1=none, 5=lzw, 8=deflate, 14=zstd)")
                 .value_name("CODE")
                 .required(false),
         )
         .arg(
             Arg::new("compression-name")
                 .long("compression-name")
-                .help("Target compression by name (none, deflate, zstd)")
+                .help("Target compression by name (none, lzw, deflate, zstd)")
                 .value_name("NAME")
                 .required(false),
         )
+        .arg(
+            Arg::new("depalettize")
+                .long("depalettize")
+                .help("With --convert, expand a palette TIFF to plain RGB (rgb) or to single-band class indices (index)")
+                .value_name("MODE")
+                .required(false),
+        )
+        .arg(
+            Arg::new("resume")
+                .long("resume")
+                .help("Continue an interrupted --convert or --chips run from its journal, skipping blocks and chips already written")
+                .action(ArgAction::SetTrue),
+        )
+        .arg(
+            Arg::new("proximity")
+                .long("proximity")
+                .help("Compute a proximity (distance to nearest target pixel) raster")
+                .action(ArgAction::SetTrue),
+        )
+        .arg(
+            Arg::new("target-values")
+                .long("target-values")
+                .help("Pixel values treated as targets for proximity (e.g., '1,3,1000-1999'); defaults to all non-zero pixels")
+                .value_name("VALUES")
+                .required(false),
+        )
+        .arg(
+            Arg::new("source-band")
+                .long("source-band")
+                .help("Band whose values are matched against --target-values for proximity (default: 1)")
+                .value_name("BAND")
+                .required(false),
+        )
+        .arg(
+            Arg::new("distance-units")
+                .long("distance-units")
+                .help("Units for proximity distances (geo or pixel)")
+                .value_name("UNITS")
+                .default_value("geo")
+                .required(false),
+        )
+        .arg(
+            Arg::new("max-distance")
+                .long("max-distance")
+                .help("Maximum proximity distance; farther pixels are written as NoData")
+                .value_name("DISTANCE")
+                .required(false),
+        )
+        .arg(
+            Arg::new("fillnodata")
+                .long("fillnodata")
+                .help("Fill NoData gaps by interpolating from surrounding pixels")
+                .action(ArgAction::SetTrue),
+        )
+        .arg(
+            Arg::new("valid-mask")
+                .long("valid-mask")
+                .help("Write a mask of the pixels holding data (not NoData or NaN) to the output file")
+                .action(ArgAction::SetTrue),
+        )
+        .arg(
+            Arg::new("mask-bits")
+                .long("mask-bits")
+                .help("Bits per pixel of the valid-data mask: 1, or 8 with 0/255 values")
+                .value_name("BITS")
+                .default_value("1")
+                .required(false),
+        )
+        .arg(
+            Arg::new("internal-mask")
+                .long("internal-mask")
+                .help("Store the valid-data mask inside an uncompressed copy of the raster, as a TIFF transparency mask")
+                .action(ArgAction::SetTrue),
+        )
+        .arg(
+            Arg::new("qa-flags")
+                .long("qa-flags")
+                .help("Decode the bit flags of a QA band into one 0/1 raster per flag, as defined in FILE ('name = bit', 'name = first-last' or 'name = first-last : value' per line)")
+                .value_name("FILE")
+                .required(false),
+        )
+        .arg(
+            Arg::new("split-bands")
+                .long("split-bands")
+                .help("Write each band of a multi-band raster to its own single-band GeoTIFF, named after the output with _band1, _band2, ... appended")
+                .action(ArgAction::SetTrue),
+        )
+        .arg(
+            Arg::new("resize")
+                .long("resize")
+                .help("Resize the raster by a factor in the same coordinate system (0.25 keeps a quarter of the width and height)")
+                .value_name("FACTOR")
+                .required(false),
+        )
+        .arg(
+            Arg::new("target-res")
+                .long("target-res")
+                .help("Resize the raster to a pixel size in map units (XRES or XRES,YRES)")
+                .value_name("RES")
+                .required(false),
+        )
+        .arg(
+            Arg::new("resampling")
+                .long("resampling")
+                .help("Resampling for --resize, --target-res and --build-overviews: nearest, bilinear or average")
+                .value_name("METHOD")
+                .default_value("nearest")
+                .required(false),
+        )
+        .arg(
+            Arg::new("extend")
+                .long("extend")
+                .help("Grow the raster on its own grid to cover a bounding box (minx,miny,maxx,maxy in its CRS), filling the new pixels with NoData (see --nodata)")
+                .value_name("BBOX")
+                .required(false),
+        )
+        .arg(
+            Arg::new("orient")
+                .long("orient")
+                .help("Rotate or flip the raster together with its georeferencing: rotate90, rotate180, rotate270 (clockwise), fliph or flipv")
+                .value_name("ORIENTATION")
+                .required(false),
+        )
+        .arg(
+            Arg::new("build-overviews")
+                .long("build-overviews")
+                .help("Add reduced-resolution overviews to the TIFF like gdaladdo, in place unless --output is given")
+                .action(ArgAction::SetTrue),
+        )
+        .arg(
+            Arg::new("overview-levels")
+                .long("overview-levels")
+                .help("Comma-separated overview reduction factors, e.g. 2,4,8,16 (default: halve until the overview fits in 256x256)")
+                .value_name("LEVELS")
+                .required(false),
+        )
+        .arg(
+            Arg::new("max-search-distance")
+                .long("max-search-distance")
+                .help("Maximum distance in pixels to search for valid values when filling NoData")
+                .value_name("PIXELS")
+                .default_value("100")
+                .required(false),
+        )
+        .arg(
+            Arg::new("smoothing-iterations")
+                .long("smoothing-iterations")
+                .help("Number of 3x3 smoothing passes applied to filled pixels")
+                .value_name("COUNT")
+                .default_value("0")
+                .required(false),
+        )
+        .arg(
+            Arg::new("nodata")
+                .long("nodata")
+                .help("NoData value to use instead of the one stored in the file")
+                .value_name("VALUE")
+                .required(false),
+        )
+        .arg(
+            Arg::new("chips")
+                .long("chips")
+                .help("Cut the raster into fixed-size chips for ML datasets (output is a directory)")
+                .action(ArgAction::SetTrue),
+        )
+        .arg(
+            Arg::new("chip-size")
+                .long("chip-size")
+                .help("Chip edge length in pixels")
+                .value_name("PIXELS")
+                .default_value("256")
+                .required(false),
+        )
+        .arg(
+            Arg::new("chip-overlap")
+                .long("chip-overlap")
+                .help("Overlap between neighbouring chips in pixels")
+                .value_name("PIXELS")
+                .default_value("0")
+                .required(false),
+        )
+        .arg(
+            Arg::new("chip-format")
+                .long("chip-format")
+                .help("Format for chips (png, webp, avif, npy, npz, safetensors)")
+                .value_name("FORMAT")
+                .default_value("png")
+                .required(false),
+        )
+        .arg(
+            Arg::new("label")
+                .long("label")
+                .help("Label raster aligned with the input, cut into matching chips")
+                .value_name("FILE")
+                .required(false),
+        )
+        .arg(
+            Arg::new("normalize")
+                .long("normalize")
+                .help("Export float32 tensors normalized per band (minmax, zscore)")
+                .value_name("METHOD")
+                .required(false),
+        )
+        .arg(
+            Arg::new("elevation-units")
+                .long("elevation-units")
+                .help("Convert extracted elevations to these units (m, ft, us-ft)")
+                .value_name("UNIT")
+                .required(false),
+        )
+        .arg(
+            Arg::new("scale")
+                .long("scale")
+                .help("Scale factor to physical units for array output, overriding the file's GDAL metadata")
+                .value_name("FACTOR")
+                .allow_hyphen_values(true)
+                .required(false),
+        )
+        .arg(
+            Arg::new("offset")
+                .long("offset")
+                .help("Offset to physical units for array output (added after scaling), overriding the file's GDAL metadata")
+                .value_name("VALUE")
+                .allow_hyphen_values(true)
+                .required(false),
+        )
+        .arg(
+            Arg::new("raw")
+                .long("raw")
+                .help("Write stored values in array output, ignoring the scale and offset in the file's GDAL metadata")
+                .action(ArgAction::SetTrue),
+        )
+        .arg(
+            Arg::new("pg-table")
+                .long("pg-table")
+                .help("Target table for pgsql array output (optionally schema.table)")
+                .value_name("TABLE")
+                .default_value("raster")
+                .required(false),
+        )
+        .arg(
+            Arg::new("pg-srid")
+                .long("pg-srid")
+                .help("SRID for pgsql array output (defaults to the input EPSG code)")
+                .value_name("SRID")
+                .required(false),
+        )
+        .arg(
+            Arg::new("tile-size")
+                .long("tile-size")
+                .help("Tile size for pgsql output and tile-partitioned parquet output (e.g. '256x256')")
+                .value_name("WxH")
+                .required(false),
+        )
+        .arg(
+            Arg::new("pg-copy")
+                .long("pg-copy")
+                .help("Load pgsql array output with COPY instead of INSERT statements")
+                .action(ArgAction::SetTrue),
+        )
+        .arg(
+            Arg::new("partition-by")
+                .long("partition-by")
+                .help("Hive-style partitioning of parquet array output (none, tile, band); output is a directory")
+                .value_name("SCHEME")
+                .default_value("none")
+                .required(false),
+        )
+        .arg(
+            Arg::new("row-group-size")
+                .long("row-group-size")
+                .help("Maximum rows per row group in parquet array output")
+                .value_name("ROWS")
+                .default_value("122880")
+                .required(false),
+        )
+        .arg(
+            Arg::new("colorize")
+                .long("colorize")
+                .help("Colorize the whole input raster with the colormap given by --colormap-input")
+                .action(ArgAction::SetTrue),
+        )
+        .arg(
+            Arg::new("render")
+                .long("render")
+                .help("Render the raster to an 8-bit image for viewing (RGB from the first three bands, else grayscale)")
+                .action(ArgAction::SetTrue),
+        )
+        .arg(
+            Arg::new("pansharpen")
+                .long("pansharpen")
+                .help("Pan-sharpen the input's first three bands with the panchromatic band in FILE (weighted Brovey) to an RGB image")
+                .value_name("FILE")
+                .required(false),
+        )
+        .arg(
+            Arg::new("pan-weights")
+                .long("pan-weights")
+                .help("Weights of the three multispectral bands in the pseudo-panchromatic band (default equal)")
+                .value_name("R,G,B")
+                .required(false),
+        )
+        .arg(
+            Arg::new("match-histogram")
+                .long("match-histogram")
+                .help("Match the colours of the input to the reference image in FILE (on their overlap when both are georeferenced), to hide mosaic seams")
+                .value_name("FILE")
+                .required(false),
+        )
+        .arg(
+            Arg::new("mosaic")
+                .long("mosaic")
+                .help("Mosaic the input with the rasters in FILES (comma-separated or repeated), in the same coordinate system")
+                .value_name("FILES")
+                .action(ArgAction::Append)
+                .required(false),
+        )
+        .arg(
+            Arg::new("blend")
+                .long("blend")
+                .help("How --mosaic and catalog extraction combine overlapping rasters: first, last, mean or feather:npixels (distance-weighted)")
+                .value_name("MODE")
+                .default_value("last")
+                .required(false),
+        )
+        .arg(
+            Arg::new("acquired")
+                .long("acquired")
+                .help("Only --mosaic the rasters acquired between START and END (START,END, dates like 2023-06-01 or 2023-06-01T10:00:00; either side may be empty)")
+                .value_name("START,END")
+                .required(false),
+        )
+        .arg(
+            Arg::new("stretch")
+                .long("stretch")
+                .help("How --render and --pansharpen map values to display levels: linear, percent-clip or equalize")
+                .value_name("MODE")
+                .default_value("linear")
+                .required(false),
+        )
+        .arg(
+            Arg::new("clip-percent")
+                .long("clip-percent")
+                .help("Lower and upper percentiles of the percent-clip stretch")
+                .value_name("LOW,HIGH")
+                .default_value("2,98")
+                .required(false),
+        )
+        .arg(
+            Arg::new("colormap-legend")
+                .long("colormap-legend")
+                .help("Render a legend image for the input colormap (SLD, CSV or palette TIFF)")
+                .action(ArgAction::SetTrue),
+        )
+        .arg(
+            Arg::new("legend-style")
+                .long("legend-style")
+                .help("Legend style (continuous, discrete); defaults to the colormap type")
+                .value_name("STYLE")
+                .required(false),
+        )
+        .arg(
+            Arg::new("colormap-rescale")
+                .long("colormap-rescale")
+                .help("Rescale the input colormap's values, e.g. 0:100:0:10000 (FROM_MIN:FROM_MAX:TO_MIN:TO_MAX)")
+                .value_name("SPEC")
+                .allow_hyphen_values(true)
+                .required(false),
+        )
+        .arg(
+            Arg::new("colormap-offset")
+                .long("colormap-offset")
+                .help("Add a constant to the input colormap's values (applied after rescaling)")
+                .value_name("DELTA")
+                .allow_hyphen_values(true)
+                .required(false),
+        )
+        .arg(
+            Arg::new("colormap-merge")
+                .long("colormap-merge")
+                .help("Merge the entries of another colormap into the input colormap; its entries win on equal values")
+                .value_name("FILE")
+                .required(false),
+        )
         .get_matches();
 
     let log_file = "rasterkit.log";
@@ -169,12 +896,32 @@ fn main() {
         process::exit(1);
     }
 
+    if let Some(megabytes) = matches.get_one::<String>("max-memory") {
+        match memory_utils::parse_megabytes(megabytes, "memory budget") {
+            Ok(budget) => memory_utils::set_memory_budget(Some(budget)),
+            Err(e) => {
+                eprintln!("Error: {}", e);
+                process::exit(1);
+            }
+        }
+    }
+
+    if let Some(dir) = matches.get_one::<String>("temp-dir") {
+        if !Path::new(dir).is_dir() {
+            eprintln!("Error: Temporary directory {} does not exist", dir);
+            process::exit(1);
+        }
+        scratch_utils::set_scratch_dir(Some(PathBuf::from(dir)));
+    }
+
     let factory = RasterkitCommandFactory::new();
 
     let command_result = factory.create_command(&matches, &logger);
     match command_result {
         Ok(command) => {
-            if let Err(e) = command.execute() {
+            let result = command.execute();
+            report_metrics(&matches);
+            if let Err(e) = result {
                 error!("Command execution error: {}", e);
                 eprintln!("Error: {}", e);
                 process::exit(1);
@@ -186,4 +933,19 @@ fn main() {
             process::exit(1);
         }
     };
-}
\ No newline at end of file
+}
+
+/// Print the metrics of the run in verbose mode and export them if asked to
+fn report_metrics(matches: &ArgMatches) {
+    if matches.get_flag("verbose") {
+        let recorded = metrics::snapshot();
+        if !recorded.is_empty() {
+            eprintln!("{}", recorded.summary());
+        }
+    }
+    if let Some(path) = matches.get_one::<String>("metrics") {
+        if let Err(e) = metrics::write_metrics(path) {
+            eprintln!("Error writing metrics to {}: {}", path, e);
+        }
+    }
+}
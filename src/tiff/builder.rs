@@ -9,7 +9,9 @@ use log::{info, error};
 use crate::tiff::errors::{TiffError, TiffResult};
 use crate::tiff::ifd::IFD;
 use crate::utils::logger::Logger;
+use crate::utils::metrics;
 use crate::extractor::Region;
+use crate::coordinate::Affine;
 
 use crate::tiff::builders::basic_tags::BasicTagsBuilder;
 use crate::tiff::builders::geo_tags::GeoTagsBuilder;
@@ -22,6 +24,7 @@ pub struct TiffBuilder<'a> {
     is_big_tiff: bool,
     pub ifds: Vec<IFD>,
     image_data: HashMap<usize, Vec<u8>>,
+    block_sizes: HashMap<usize, Vec<u64>>,
     external_data: HashMap<(usize, u16), Vec<u8>>,
 }
 
@@ -34,6 +37,7 @@ impl<'a> TiffBuilder<'a> {
             is_big_tiff,
             ifds: Vec::new(),
             image_data: HashMap::new(),
+            block_sizes: HashMap::new(),
             external_data: HashMap::new(),
         }
     }
@@ -100,6 +104,32 @@ impl<'a> TiffBuilder<'a> {
         );
     }
 
+    /// Set image data made of encoded blocks, written back to back
+    ///
+    /// `layout_tags` are the offset and byte count tags, those of strips or
+    /// of tiles; the tags describing the block size are left to the caller.
+    pub fn set_blocks(&mut self, ifd_index: usize, layout_tags: (u16, u16), blocks: Vec<Vec<u8>>) {
+        if ifd_index >= self.ifds.len() {
+            error!("Invalid IFD index {}, only have {} IFDs", ifd_index, self.ifds.len());
+            return;
+        }
+
+        let sizes: Vec<u64> = blocks.iter().map(|block| block.len() as u64).collect();
+        BasicTagsBuilder::setup_blocks(
+            &mut self.ifds[ifd_index],
+            &mut self.external_data,
+            ifd_index,
+            layout_tags,
+            &sizes,
+            self.is_big_tiff
+        );
+
+        if sizes.len() > 1 {
+            self.block_sizes.insert(ifd_index, sizes);
+        }
+        self.image_data.insert(ifd_index, blocks.concat());
+    }
+
     /// Add common tags for a basic RGB image
     pub fn add_basic_rgb_tags(&mut self, ifd_index: usize, width: u32, height: u32) {
         if ifd_index >= self.ifds.len() {
@@ -116,6 +146,53 @@ impl<'a> TiffBuilder<'a> {
         );
     }
 
+    /// Add common tags for an RGB image with unassociated alpha
+    pub fn add_basic_rgba_tags(&mut self, ifd_index: usize, width: u32, height: u32) {
+        if ifd_index >= self.ifds.len() {
+            error!("Invalid IFD index {}, only have {} IFDs", ifd_index, self.ifds.len());
+            return;
+        }
+
+        BasicTagsBuilder::add_basic_rgba_tags(
+            &mut self.ifds[ifd_index],
+            &mut self.external_data,
+            ifd_index,
+            width,
+            height
+        );
+    }
+
+    /// Add common tags for a grayscale image with an alpha channel
+    pub fn add_basic_gray_alpha_tags(&mut self, ifd_index: usize, width: u32, height: u32) {
+        if ifd_index >= self.ifds.len() {
+            error!("Invalid IFD index {}, only have {} IFDs", ifd_index, self.ifds.len());
+            return;
+        }
+
+        BasicTagsBuilder::add_basic_gray_alpha_tags(
+            &mut self.ifds[ifd_index],
+            &mut self.external_data,
+            ifd_index,
+            width,
+            height
+        );
+    }
+
+    /// Set the ExtraSamples tag; an empty slice removes it
+    pub fn add_extra_samples(&mut self, ifd_index: usize, extra: &[u16]) {
+        if ifd_index >= self.ifds.len() {
+            error!("Invalid IFD index {}, only have {} IFDs", ifd_index, self.ifds.len());
+            return;
+        }
+
+        BasicTagsBuilder::add_extra_samples(
+            &mut self.ifds[ifd_index],
+            &mut self.external_data,
+            ifd_index,
+            extra
+        );
+    }
+
     /// Add common tags for a grayscale image
     pub fn add_basic_gray_tags(&mut self, ifd_index: usize, width: u32, height: u32, bits_per_sample: u16) {
         if ifd_index >= self.ifds.len() {
@@ -158,7 +235,8 @@ impl<'a> TiffBuilder<'a> {
             &mut self.external_data,
             ifd_index,
             source_ifd,
-            reader
+            reader,
+            self.is_big_tiff
         )
     }
 
@@ -185,6 +263,37 @@ impl<'a> TiffBuilder<'a> {
         )
     }
 
+    /// Set the geotransform of an IFD, keeping its GeoKeys
+    pub fn set_geotransform(&mut self, ifd_index: usize, geotransform: &Affine) {
+        if ifd_index >= self.ifds.len() {
+            error!("Invalid IFD index {}, only have {} IFDs", ifd_index, self.ifds.len());
+            return;
+        }
+
+        GeoTagsBuilder::set_geotransform(
+            &mut self.ifds[ifd_index],
+            &mut self.external_data,
+            ifd_index,
+            geotransform
+        );
+    }
+
+    /// Georeference an IFD from a geotransform and an EPSG code
+    pub fn set_georeference(&mut self, ifd_index: usize, geotransform: &Affine, epsg: u32) -> TiffResult<()> {
+        if ifd_index >= self.ifds.len() {
+            return Err(TiffError::GenericError(format!(
+                "Invalid IFD index {}, only have {} IFDs", ifd_index, self.ifds.len())));
+        }
+
+        GeoTagsBuilder::set_georeference(
+            &mut self.ifds[ifd_index],
+            &mut self.external_data,
+            ifd_index,
+            geotransform,
+            epsg
+        )
+    }
+
     /// Copy appearance-related tags from source IFD
     pub fn copy_appearance_tags(&mut self, ifd_index: usize, source_ifd: &IFD) {
         if ifd_index >= self.ifds.len() {
@@ -212,6 +321,24 @@ impl<'a> TiffBuilder<'a> {
         );
     }
 
+    /// Copy tags from source IFD with the data they reference, excluding specified ones
+    pub fn deep_copy_tags_from(&mut self, ifd_index: usize, source_ifd: &IFD, exclude_tags: &[u16], reader: &crate::tiff::TiffReader) {
+        if ifd_index >= self.ifds.len() {
+            error!("Invalid IFD index {}, only have {} IFDs", ifd_index, self.ifds.len());
+            return;
+        }
+
+        GeoTagsBuilder::deep_copy_tags_from(
+            &mut self.ifds[ifd_index],
+            &mut self.external_data,
+            ifd_index,
+            source_ifd,
+            exclude_tags,
+            reader,
+            self.is_big_tiff
+        );
+    }
+
     /// Add a GDAL NoData tag to an IFD
     pub fn add_nodata_tag(&mut self, ifd_index: usize, nodata_value: &str) {
         if ifd_index >= self.ifds.len() {
@@ -243,6 +370,68 @@ impl<'a> TiffBuilder<'a> {
         );
     }
 
+    /// Add an ImageDescription tag to an IFD
+    pub fn add_image_description(&mut self, ifd_index: usize, description: &str) {
+        if ifd_index >= self.ifds.len() {
+            error!("Invalid IFD index {}, only have {} IFDs", ifd_index, self.ifds.len());
+            return;
+        }
+
+        MetadataBuilder::add_image_description(
+            &mut self.ifds[ifd_index],
+            &mut self.external_data,
+            ifd_index,
+            description
+        );
+    }
+
+    /// Embed an ICC color profile in an IFD
+    pub fn add_icc_profile(&mut self, ifd_index: usize, profile: Vec<u8>) {
+        if ifd_index >= self.ifds.len() {
+            error!("Invalid IFD index {}, only have {} IFDs", ifd_index, self.ifds.len());
+            return;
+        }
+
+        MetadataBuilder::add_icc_profile(
+            &mut self.ifds[ifd_index],
+            &mut self.external_data,
+            ifd_index,
+            profile
+        );
+    }
+
+    /// Copy the ICC color profile from source IFD
+    pub fn copy_icc_profile(&mut self, ifd_index: usize, source_ifd: &IFD, reader: &crate::tiff::TiffReader) -> TiffResult<()> {
+        if ifd_index >= self.ifds.len() {
+            return Err(TiffError::GenericError(format!(
+                "Invalid IFD index {}, only have {} IFDs", ifd_index, self.ifds.len())));
+        }
+
+        MetadataBuilder::copy_icc_profile(
+            &mut self.ifds[ifd_index],
+            &mut self.external_data,
+            ifd_index,
+            source_ifd,
+            reader
+        )
+    }
+
+    /// Copy EXIF, GPS and XMP metadata from source IFD
+    pub fn copy_exif_metadata(&mut self, ifd_index: usize, source_ifd: &IFD, reader: &crate::tiff::TiffReader) -> TiffResult<()> {
+        if ifd_index >= self.ifds.len() {
+            return Err(TiffError::GenericError(format!(
+                "Invalid IFD index {}, only have {} IFDs", ifd_index, self.ifds.len())));
+        }
+
+        MetadataBuilder::copy_exif_metadata(
+            &mut self.ifds[ifd_index],
+            &mut self.external_data,
+            ifd_index,
+            source_ifd,
+            reader
+        )
+    }
+
     /// Copy statistics tags from source IFD
     pub fn copy_statistics_tags(&mut self, ifd_index: usize, source_ifd: &IFD) {
         if ifd_index >= self.ifds.len() {
@@ -261,12 +450,13 @@ impl<'a> TiffBuilder<'a> {
         info!("Writing TIFF to {}", output_path);
         self.logger.log(&format!("Writing TIFF to {}", output_path))?;
 
-        WriterBuilder::write(
+        metrics::time("write", || WriterBuilder::write(
             self.is_big_tiff,
             &self.ifds,
             &self.image_data,
+            &self.block_sizes,
             &self.external_data,
             output_path
-        )
+        ))
     }
 }
\ No newline at end of file
@@ -4,7 +4,7 @@
 //! like dimensions, color spaces, and sample properties.
 
 use crate::tiff::ifd::{IFD, IFDEntry};
-use crate::tiff::constants::{tags, field_types, photometric, compression, planar_config};
+use crate::tiff::constants::{tags, field_types, photometric, compression, planar_config, extra_samples};
 use log::{debug, info, warn};
 
 /// Adds basic TIFF tags to an IFD
@@ -55,6 +55,46 @@ impl BasicTagsBuilder {
         }
     }
 
+    /// Set the ExtraSamples tag for an IFD
+    ///
+    /// ExtraSamples must list one value for every sample beyond the color
+    /// channels (e.g. the alpha of an RGBA image). Any ExtraSamples entry
+    /// already present, such as one copied from the source, is replaced; an
+    /// empty slice removes the tag for images without extra samples.
+    pub fn add_extra_samples(
+        ifd: &mut IFD,
+        external_data: &mut std::collections::HashMap<(usize, u16), Vec<u8>>,
+        ifd_index: usize,
+        extra: &[u16]
+    ) {
+        ifd.remove_entry(tags::EXTRA_SAMPLES);
+        external_data.remove(&(ifd_index, tags::EXTRA_SAMPLES));
+
+        if extra.is_empty() {
+            return;
+        }
+        debug!("Adding ExtraSamples: {:?}", extra);
+
+        if extra.len() == 1 {
+            ifd.add_entry(IFDEntry::new(
+                tags::EXTRA_SAMPLES,
+                field_types::SHORT,
+                1,
+                extra[0] as u64)
+            );
+        } else {
+            // Several extra samples (e.g. multiple alpha/mask channels) go to external data
+            let data: Vec<u8> = extra.iter().flat_map(|value| value.to_le_bytes()).collect();
+            ifd.add_entry(IFDEntry::new(
+                tags::EXTRA_SAMPLES,
+                field_types::SHORT,
+                extra.len() as u64,
+                0)
+            );
+            external_data.insert((ifd_index, tags::EXTRA_SAMPLES), data);
+        }
+    }
+
     /// Setup single strip for an IFD
     ///
     /// TIFF files store image data in strips. This function sets up a basic
@@ -101,6 +141,49 @@ impl BasicTagsBuilder {
         image_data.insert(ifd_index, strip_data);
     }
 
+    /// Set up the offset and byte count tags of image data in several blocks
+    ///
+    /// Used for strips or tiles that are already encoded, such as tiles
+    /// copied from another file. The offsets are only known once the file
+    /// is laid out, so the offset tag gets a placeholder array here that
+    /// the writer fills in. Values are 32-bit, or 64-bit in a BigTIFF.
+    ///
+    /// # Arguments
+    /// * `layout_tags` - The offset and byte count tags, for strips or for tiles
+    /// * `sizes` - Size of each block in bytes
+    pub fn setup_blocks(
+        ifd: &mut IFD,
+        external_data: &mut std::collections::HashMap<(usize, u16), Vec<u8>>,
+        ifd_index: usize,
+        layout_tags: (u16, u16),
+        sizes: &[u64],
+        is_big_tiff: bool
+    ) {
+        info!("Setting up {} blocks: {} bytes", sizes.len(), sizes.iter().sum::<u64>());
+        let (offsets_tag, byte_counts_tag) = layout_tags;
+        let (field_type, value_size) = if is_big_tiff { (field_types::LONG8, 8) } else { (field_types::LONG, 4) };
+        let count = sizes.len() as u64;
+
+        match sizes {
+            [size] => {
+                ifd.add_entry(IFDEntry::new(offsets_tag, field_type, 1, 0));
+                ifd.add_entry(IFDEntry::new(byte_counts_tag, field_type, 1, *size));
+            },
+            _ => {
+                let byte_counts: Vec<u8> = sizes.iter()
+                    .flat_map(|&size| match is_big_tiff {
+                        true => size.to_le_bytes().to_vec(),
+                        false => (size as u32).to_le_bytes().to_vec(),
+                    })
+                    .collect();
+                ifd.add_entry(IFDEntry::new(offsets_tag, field_type, count, 0));
+                ifd.add_entry(IFDEntry::new(byte_counts_tag, field_type, count, 0));
+                external_data.insert((ifd_index, offsets_tag), vec![0; sizes.len() * value_size]);
+                external_data.insert((ifd_index, byte_counts_tag), byte_counts);
+            }
+        }
+    }
+
     /// Add common tags for a basic RGB image
     ///
     /// Sets up all the required tags for an uncompressed RGB image.
@@ -175,6 +258,55 @@ impl BasicTagsBuilder {
         );
     }
 
+    /// Add common tags for an RGB image with an alpha channel
+    ///
+    /// Like `add_basic_rgb_tags`, but with four 8-bit samples per pixel
+    /// where the fourth is declared as unassociated alpha through the
+    /// ExtraSamples tag, as the TIFF specification requires.
+    pub fn add_basic_rgba_tags(
+        ifd: &mut IFD,
+        external_data: &mut std::collections::HashMap<(usize, u16), Vec<u8>>,
+        ifd_index: usize,
+        width: u32,
+        height: u32
+    ) {
+        Self::add_basic_rgb_tags(ifd, external_data, ifd_index, width, height);
+        info!("Adding alpha channel tags for {}x{} image", width, height);
+
+        Self::add_bits_per_sample(ifd, external_data, ifd_index, &[8, 8, 8, 8]);
+        ifd.add_entry(IFDEntry::new(
+            tags::SAMPLES_PER_PIXEL,
+            field_types::SHORT,
+            1,
+            4)
+        );
+        Self::add_extra_samples(ifd, external_data, ifd_index, &[extra_samples::UNASSOCIATED_ALPHA]);
+    }
+
+    /// Add common tags for a grayscale image with an alpha channel
+    ///
+    /// Two 8-bit samples per pixel, the second declared as unassociated
+    /// alpha through the ExtraSamples tag.
+    pub fn add_basic_gray_alpha_tags(
+        ifd: &mut IFD,
+        external_data: &mut std::collections::HashMap<(usize, u16), Vec<u8>>,
+        ifd_index: usize,
+        width: u32,
+        height: u32
+    ) {
+        Self::add_basic_gray_tags(ifd, width, height, 8);
+        info!("Adding alpha channel tags for {}x{} grayscale image", width, height);
+
+        Self::add_bits_per_sample(ifd, external_data, ifd_index, &[8, 8]);
+        ifd.add_entry(IFDEntry::new(
+            tags::SAMPLES_PER_PIXEL,
+            field_types::SHORT,
+            1,
+            2)
+        );
+        Self::add_extra_samples(ifd, external_data, ifd_index, &[extra_samples::UNASSOCIATED_ALPHA]);
+    }
+
     /// Add common tags for a grayscale image
     ///
     /// Creates a simple grayscale (black and white) image with
@@ -4,13 +4,14 @@
 //! a georeferenced image that can be accurately placed on a map. These functions
 //! handle coordinate systems, transformations, and other geo-spatial metadata.
 
-use crate::tiff::ifd::IFD;
+use crate::tiff::ifd::{IFD, IFDEntry};
 use crate::tiff::errors::{TiffError, TiffResult};
-use crate::tiff::constants::{tags, field_types};
+use crate::tiff::constants::{tags, field_types, geo_keys};
+use crate::coordinate::Affine;
+use crate::tiff::TiffReader;
 use crate::extractor::Region;
-use log::{debug, info, warn};
-use std::io::{Read, Seek, SeekFrom};
-use crate::utils::tiff_utils;
+use log::{debug, info, trace, warn};
+use crate::utils::{tag_utils, tiff_utils};
 use std::collections::HashMap;
 
 /// Handles GeoTIFF tags and transformations
@@ -27,7 +28,8 @@ impl GeoTagsBuilder {
         external_data: &mut HashMap<(usize, u16), Vec<u8>>,
         ifd_index: usize,
         source_ifd: &IFD,
-        reader: &mut crate::tiff::TiffReader
+        reader: &TiffReader,
+        is_big_tiff: bool
     ) -> TiffResult<()> {
         info!("Copying GeoTIFF tags");
 
@@ -38,16 +40,6 @@ impl GeoTagsBuilder {
             tags::GEO_ASCII_PARAMS_TAG,   // ASCII parameters referenced by the directory
         ];
 
-        // We need to open the file to read the actual tag data
-        // if it's stored externally (which is common for GeoTIFF tags)
-        let mut file = match reader.create_reader() {
-            Ok(f) => f,
-            Err(e) => {
-                warn!("Failed to create reader for GeoTIFF tag data: {:?}", e);
-                return Err(e);
-            }
-        };
-
         // Process each GeoTIFF tag
         for &tag in &geotiff_tags {
             // Skip tags that don't exist in the source
@@ -58,47 +50,11 @@ impl GeoTagsBuilder {
 
             debug!("Copying GeoTIFF tag {} (count: {})", tag, entry.count);
 
-            // Figure out if the data is stored inline or external to the IFD
-            // TIFF has a trick where small data can be stored directly in the tag's
-            // value field, but larger data needs to be stored elsewhere in the file
-            let type_size = tiff_utils::get_field_type_size(entry.field_type);
-            let data_size = type_size * entry.count as usize;
-
-            // The size threshold depends on whether this is a BigTIFF or not
-            let entry_size = if reader.is_big_tiff() { 8 } else { 4 };
-
-            // For inline values, just copy the entry directly
-            // These are simpler because the data is already in the tag
-            if data_size <= entry_size || data_size == 0 {
-                tiff_utils::update_ifd_tag(dest_ifd, tag, entry.clone());
-                continue;
-            }
-
-            // For externally stored data, we need to read it from the file
-            let mut data = vec![0u8; data_size];
-
-            // Seek to the location in the file where this data is stored
-            // and read the actual bytes
-            match file.seek(SeekFrom::Start(entry.value_offset))
-                .and_then(|_| file.read_exact(&mut data))
-            {
-                Ok(_) => {
-                    // Replace any existing tag with the same ID and store the external data
-                    tiff_utils::create_external_tag(
-                        dest_ifd,
-                        external_data,
-                        ifd_index,
-                        tag,
-                        entry.field_type,
-                        entry.count,
-                        data
-                    );
-                },
-                Err(e) => {
-                    // If we can't read this tag, log it but continue with others
-                    warn!("Failed to read data for GeoTIFF tag {}: {:?}", tag, e);
-                    continue;
-                }
+            // Read the values wherever they're stored and re-embed them, so
+            // the copy doesn't point into the source file
+            if let Err(e) = Self::deep_copy_entry(dest_ifd, external_data, ifd_index, entry, reader, is_big_tiff) {
+                // If we can't read this tag, log it but continue with others
+                warn!("Failed to read data for GeoTIFF tag {}: {:?}", tag, e);
             }
         }
 
@@ -200,6 +156,78 @@ impl GeoTagsBuilder {
         Ok(())
     }
 
+    /// Write the pixel-to-model transform of an image
+    ///
+    /// North-up transforms become ModelPixelScale and ModelTiepoint tags,
+    /// rotated ones a ModelTransformation matrix. Any GeoKeys are left as
+    /// they are.
+    pub fn set_geotransform(
+        ifd: &mut IFD,
+        external_data: &mut HashMap<(usize, u16), Vec<u8>>,
+        ifd_index: usize,
+        geotransform: &Affine
+    ) {
+        let doubles = |values: &[f64]| values.iter().flat_map(|v| v.to_le_bytes()).collect::<Vec<u8>>();
+
+        if geotransform.is_north_up() {
+            let tiepoint = [0.0, 0.0, 0.0, geotransform.origin_x, geotransform.origin_y, 0.0];
+            let pixel_scale = [geotransform.pixel_width, -geotransform.pixel_height, 0.0];
+            tiff_utils::create_external_tag(ifd, external_data, ifd_index,
+                tags::MODEL_TIEPOINT_TAG, field_types::DOUBLE, 6, doubles(&tiepoint));
+            tiff_utils::create_external_tag(ifd, external_data, ifd_index,
+                tags::MODEL_PIXEL_SCALE_TAG, field_types::DOUBLE, 3, doubles(&pixel_scale));
+        } else {
+            let g = geotransform;
+            let matrix = [
+                g.pixel_width, g.row_rotation, 0.0, g.origin_x,
+                g.column_rotation, g.pixel_height, 0.0, g.origin_y,
+                0.0, 0.0, 0.0, 0.0,
+                0.0, 0.0, 0.0, 1.0,
+            ];
+            tiff_utils::create_external_tag(ifd, external_data, ifd_index,
+                tags::MODEL_TRANSFORMATION_TAG, field_types::DOUBLE, 16, doubles(&matrix));
+        }
+    }
+
+    /// Georeference an image from a geotransform and an EPSG code
+    ///
+    /// For sources without GeoTIFF tags to copy, such as NITF files. The
+    /// transform is written as by `set_geotransform`. The GeoKey directory names the
+    /// CRS by its EPSG code, as geographic for codes in the 4000 range and
+    /// as projected otherwise.
+    pub fn set_georeference(
+        ifd: &mut IFD,
+        external_data: &mut HashMap<(usize, u16), Vec<u8>>,
+        ifd_index: usize,
+        geotransform: &Affine,
+        epsg: u32
+    ) -> TiffResult<()> {
+        info!("Georeferencing output with EPSG:{} and geotransform {:?}", epsg, geotransform.to_gdal());
+
+        let epsg_key = u16::try_from(epsg).map_err(|_| TiffError::GenericError(format!(
+            "EPSG:{} does not fit a GeoKey", epsg)))?;
+
+        Self::set_geotransform(ifd, external_data, ifd_index, geotransform);
+
+        // Header (version 1.1.0, three keys), then one key per row
+        let (model_type, crs_key) = if (4000..5000).contains(&epsg) {
+            (2u16, geo_keys::GEOGRAPHIC_TYPE)
+        } else {
+            (1u16, geo_keys::PROJECTED_CS_TYPE)
+        };
+        let directory: [u16; 16] = [
+            1, 1, 0, 3,
+            geo_keys::MODEL_TYPE, 0, 1, model_type,
+            geo_keys::RASTER_TYPE, 0, 1, 1,
+            crs_key, 0, 1, epsg_key,
+        ];
+        tiff_utils::create_external_tag(ifd, external_data, ifd_index,
+            tags::GEO_KEY_DIRECTORY_TAG, field_types::SHORT, directory.len() as u64,
+            directory.iter().flat_map(|v| v.to_le_bytes()).collect());
+
+        Ok(())
+    }
+
     /// Copy appearance-related tags from source IFD
     ///
     /// Some tags affect how image data is visually interpreted.
@@ -242,4 +270,72 @@ impl GeoTagsBuilder {
         // Loop through all entries in the source IFD
         tiff_utils::copy_tags_except(dest_ifd, source_ifd, exclude_tags);
     }
+
+    /// Copy tags from source IFD together with the data they reference
+    ///
+    /// `copy_tags_from` clones entries as they are, so a value stored
+    /// elsewhere in the source file ends up pointing at an unrelated spot of
+    /// the output. This reads every copied tag through the source reader and
+    /// stores its values again: inline when they fit in the entry, as
+    /// external data otherwise. Values are re-encoded little-endian, which
+    /// also makes copies from big-endian files come out right. Tags that
+    /// can't be read are dropped rather than copied broken.
+    pub fn deep_copy_tags_from(
+        dest_ifd: &mut IFD,
+        external_data: &mut HashMap<(usize, u16), Vec<u8>>,
+        ifd_index: usize,
+        source_ifd: &IFD,
+        exclude_tags: &[u16],
+        reader: &TiffReader,
+        is_big_tiff: bool
+    ) {
+        info!("Deep copying tags (excluding {} tags)", exclude_tags.len());
+
+        for entry in &source_ifd.entries {
+            if exclude_tags.contains(&entry.tag) {
+                continue;
+            }
+
+            if let Err(e) = Self::deep_copy_entry(dest_ifd, external_data, ifd_index, entry, reader, is_big_tiff) {
+                warn!("Dropping tag {} ({}): failed to read its data: {}",
+                      entry.tag, tag_utils::get_tag_name(entry.tag), e);
+            }
+        }
+    }
+
+    /// Read one entry's values and store them in the destination IFD
+    ///
+    /// `is_big_tiff` describes the output, whose entries hold 8 bytes of
+    /// data instead of 4 when it is a BigTIFF.
+    fn deep_copy_entry(
+        dest_ifd: &mut IFD,
+        external_data: &mut HashMap<(usize, u16), Vec<u8>>,
+        ifd_index: usize,
+        entry: &IFDEntry,
+        reader: &TiffReader,
+        is_big_tiff: bool
+    ) -> TiffResult<()> {
+        let data = reader.read_tag_value(entry)?.to_le_bytes();
+        let inline_size = if is_big_tiff { 8 } else { 4 };
+        trace!("Copying tag {} ({} bytes of data)", entry.tag, data.len());
+
+        if data.len() <= inline_size {
+            let mut value = [0u8; 8];
+            value[..data.len()].copy_from_slice(&data);
+            let inline = IFDEntry::new(entry.tag, entry.field_type, entry.count, u64::from_le_bytes(value));
+            tiff_utils::update_ifd_tag(dest_ifd, entry.tag, inline);
+            external_data.remove(&(ifd_index, entry.tag));
+        } else {
+            tiff_utils::create_external_tag(
+                dest_ifd,
+                external_data,
+                ifd_index,
+                entry.tag,
+                entry.field_type,
+                entry.count,
+                data
+            );
+        }
+        Ok(())
+    }
 }
\ No newline at end of file
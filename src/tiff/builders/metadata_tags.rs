@@ -1,15 +1,18 @@
-//! Metadata tag strategies (Nodata actually fails and should be rewritten)
+//! Metadata tag strategies
 //!
 //! This module handles special metadata tags in TIFF files, with a focus on
 //! GDAL-specific extensions. GDAL is a popular geospatial library that adds
 //! custom tags to store important information like no-data values and stats.
 
-use crate::tiff::ifd::{IFD, IFDEntry};
+use crate::tiff::ifd::IFD;
 use crate::tiff::constants::{tags, field_types};
 use log::{debug, info, warn};
 use crate::utils::tiff_utils;
 use crate::utils::xml_utils;
 use std::collections::HashMap;
+use std::io::{Read, Seek, SeekFrom};
+use crate::tiff::errors::TiffResult;
+use crate::utils::{exif_utils, tag_utils};
 
 /// Handles metadata tags in TIFF files
 pub struct MetadataBuilder;
@@ -57,19 +60,6 @@ impl MetadataBuilder {
             nodata_bytes.len() as u64,
             nodata_bytes
         );
-
-        // Also add the standard TIFF NODATA tag if possible
-        // Some applications look for this instead of the GDAL-specific tag
-        if let Ok(value) = final_nodata.parse::<u8>() {
-            // Set the standard TIFF tag for NoData if it's a simple numeric value
-            // This improves compatibility with non-GDAL software
-            ifd.add_entry(IFDEntry::new(
-                tags::GDAL_NODATA,
-                field_types::BYTE,
-                1,
-                value as u64
-            ));
-        }
     }
 
     /// Add or update GDAL metadata tag
@@ -109,8 +99,9 @@ impl MetadataBuilder {
             }
         };
 
-        // Add the new or updated metadata as an ASCII tag
-        let metadata_bytes = metadata.as_bytes().to_vec();
+        // Add the new or updated metadata as a NUL-terminated ASCII tag
+        let mut metadata_bytes = metadata.as_bytes().to_vec();
+        metadata_bytes.push(0);
         tiff_utils::create_external_tag(
             ifd,
             external_data,
@@ -143,4 +134,161 @@ impl MetadataBuilder {
         // Copy each tag if it exists
         tiff_utils::copy_tags(dest_ifd, source_ifd, &stats_tags);
     }
-}
\ No newline at end of file
+
+    /// Add an ImageDescription tag
+    ///
+    /// The description is free text that most viewers and `gdalinfo` show
+    /// as-is, stored as a NUL-terminated ASCII string.
+    pub fn add_image_description(
+        ifd: &mut IFD,
+        external_data: &mut HashMap<(usize, u16), Vec<u8>>,
+        ifd_index: usize,
+        description: &str
+    ) {
+        info!("Adding image description ({} characters)", description.len());
+
+        let mut bytes: Vec<u8> = description.chars()
+            .map(|c| if c.is_ascii() && c != '\0' { c as u8 } else { b'?' })
+            .collect();
+        bytes.push(0);
+
+        tiff_utils::create_external_tag(
+            ifd,
+            external_data,
+            ifd_index,
+            tags::IMAGE_DESCRIPTION,
+            field_types::ASCII,
+            bytes.len() as u64,
+            bytes
+        );
+    }
+
+    /// Embed an ICC color profile
+    ///
+    /// Color-managed imagery (orthophotos scanned or processed in a specific
+    /// color space) carries its profile in the InterColorProfile tag. The
+    /// profile is an opaque blob, so it is stored as UNDEFINED bytes.
+    pub fn add_icc_profile(
+        ifd: &mut IFD,
+        external_data: &mut HashMap<(usize, u16), Vec<u8>>,
+        ifd_index: usize,
+        profile: Vec<u8>
+    ) {
+        info!("Adding ICC profile ({} bytes)", profile.len());
+
+        tiff_utils::create_external_tag(
+            ifd,
+            external_data,
+            ifd_index,
+            tags::ICC_PROFILE,
+            field_types::UNDEFINED,
+            profile.len() as u64,
+            profile
+        );
+    }
+
+    /// Copy the ICC color profile from a source IFD
+    ///
+    /// The profile always lives outside the IFD, so copying the entry alone
+    /// would leave it pointing into the source file. The bytes are read from
+    /// the source and stored as external data for the new file instead.
+    /// Sources without a profile are left alone.
+    pub fn copy_icc_profile(
+        dest_ifd: &mut IFD,
+        external_data: &mut HashMap<(usize, u16), Vec<u8>>,
+        ifd_index: usize,
+        source_ifd: &IFD,
+        reader: &crate::tiff::TiffReader
+    ) -> TiffResult<()> {
+        let entry = match source_ifd.get_entry(tags::ICC_PROFILE) {
+            Some(e) => e,
+            None => return Ok(()),
+        };
+
+        let entry_size = if reader.is_big_tiff() { 8 } else { 4 };
+        if entry.count as usize <= entry_size {
+            warn!("ICC profile of {} bytes is too small to be valid, skipping", entry.count);
+            return Ok(());
+        }
+
+        debug!("Copying ICC profile ({} bytes at offset {})", entry.count, entry.value_offset);
+
+        let mut file = reader.create_reader()?;
+        let mut profile = vec![0u8; entry.count as usize];
+        file.seek(SeekFrom::Start(entry.value_offset))?;
+        file.read_exact(&mut profile)?;
+
+        Self::add_icc_profile(dest_ifd, external_data, ifd_index, profile);
+        Ok(())
+    }
+
+    /// Copy EXIF, GPS and XMP capture metadata from a source IFD
+    ///
+    /// Drone and camera imagery keeps its capture time, exposure and camera
+    /// position in EXIF/GPS sub-IFDs. The pointer tags can't be copied as-is,
+    /// so each directory is read and packed into external data, which the
+    /// writer places and relocates. The XMP packet and the camera make,
+    /// model and date/time of the main IFD are copied as plain bytes.
+    pub fn copy_exif_metadata(
+        dest_ifd: &mut IFD,
+        external_data: &mut HashMap<(usize, u16), Vec<u8>>,
+        ifd_index: usize,
+        source_ifd: &IFD,
+        reader: &crate::tiff::TiffReader
+    ) -> TiffResult<()> {
+        for pointer_tag in [tags::EXIF_IFD, tags::GPS_IFD] {
+            match exif_utils::read_directory(reader, source_ifd, pointer_tag) {
+                Ok(Some(entries)) => {
+                    info!("Copying {} directory ({} entries)", tag_utils::get_tag_name(pointer_tag), entries.len());
+                    tiff_utils::create_external_tag(
+                        dest_ifd,
+                        external_data,
+                        ifd_index,
+                        pointer_tag,
+                        field_types::LONG,
+                        1,
+                        exif_utils::pack_directory(&entries)
+                    );
+                },
+                Ok(None) => {},
+                // Damaged capture metadata shouldn't stop the extraction
+                Err(e) => warn!("Failed to read {} directory: {:?}", tag_utils::get_tag_name(pointer_tag), e),
+            }
+        }
+
+        // Camera make/model and capture time are usually too long to sit inline
+        for tag in [tags::MAKE, tags::MODEL, tags::DATE_TIME] {
+            let entry = match source_ifd.get_entry(tag) {
+                Some(e) if e.field_type == field_types::ASCII && !e.is_value_inline(reader.is_big_tiff()) => e,
+                Some(e) => {
+                    tiff_utils::update_ifd_tag(dest_ifd, tag, e.clone());
+                    continue;
+                },
+                None => continue,
+            };
+            match exif_utils::read_tag_bytes(reader, entry) {
+                Ok(text) => tiff_utils::create_external_tag(
+                    dest_ifd, external_data, ifd_index, tag, field_types::ASCII, entry.count, text),
+                Err(e) => {
+                    warn!("Failed to read {}: {:?}", tag_utils::get_tag_name(tag), e);
+                    dest_ifd.remove_entry(tag);
+                },
+            }
+        }
+
+        if let Some(xmp) = exif_utils::read_xmp(reader, source_ifd) {
+            info!("Copying XMP packet ({} bytes)", xmp.len());
+            tiff_utils::create_external_tag(
+                dest_ifd,
+                external_data,
+                ifd_index,
+                tags::XMP,
+                field_types::BYTE,
+                xmp.len() as u64,
+                xmp
+            );
+        }
+
+        Ok(())
+    }
+}
@@ -4,10 +4,11 @@
 //! Writing a valid TIFF requires careful management of offsets, ordering,
 //! and alignment to ensure the file can be read by other software.
 
+use crate::tiff::compliance;
 use crate::tiff::ifd::IFD;
 use crate::tiff::errors::{TiffError, TiffResult};
 use crate::tiff::constants::{header, tags};
-use crate::utils::write_utils;
+use crate::utils::{atomic_utils, exif_utils, memory_utils, write_utils};
 use log::info;
 use std::collections::HashMap;
 use std::fs::File;
@@ -26,42 +27,63 @@ impl WriterBuilder {
         is_big_tiff: bool,
         ifds: &[IFD],
         image_data: &HashMap<usize, Vec<u8>>,
+        block_sizes: &HashMap<usize, Vec<u64>>,
         external_data: &HashMap<(usize, u16), Vec<u8>>,
         output_path: &str
     ) -> TiffResult<()> {
         info!("Writing TIFF to {}", output_path);
 
-        // Create the output file and buffered writer
-        let file = File::create(output_path).map_err(TiffError::from)?;
-        let mut writer = BufWriter::with_capacity(1024 * 1024, file);
-
         // Sort IFDs by tag number as required by TIFF spec
-        let sorted_ifds = Self::prepare_sorted_ifds(ifds);
+        let mut sorted_ifds = Self::prepare_sorted_ifds(ifds);
+
+        // Values small enough for their entry are stored there, where readers look for them
+        let mut external_data = Self::inline_small_values(&mut sorted_ifds, external_data, is_big_tiff);
+
+        // In strict mode, check the result before anything is written
+        compliance::enforce(&sorted_ifds, is_big_tiff, &|ifd_index, entry| {
+            match external_data.get(&(ifd_index, entry.tag)) {
+                Some(data) => Some(data.clone()),
+                None => {
+                    let size = entry.count as usize * entry.get_field_type_size();
+                    let bytes = entry.value_offset.to_le_bytes();
+                    (size <= bytes.len()).then(|| bytes[..size].to_vec())
+                }
+            }
+        }, None)?;
 
-        // Write the TIFF header
-        Self::write_header(&mut writer, is_big_tiff)?;
+        // Write to a partial file that only becomes the output once complete
+        atomic_utils::write_atomically(output_path, |partial_path| {
+            let file = File::create(partial_path).map_err(TiffError::from)?;
+            let mut writer = BufWriter::with_capacity(memory_utils::io_buffer_size(), file);
 
-        // Calculate all offsets for IFDs and data
-        let header_size = if is_big_tiff { 16 } else { 8 };
-        let (ifd_offsets, tag_data_offsets) = Self::calculate_offsets(
-            &sorted_ifds, external_data, image_data, header_size, is_big_tiff);
+            // Write the TIFF header
+            Self::write_header(&mut writer, is_big_tiff)?;
 
-        // Write the offset to the first IFD in the header area
-        let first_ifd_offset = ifd_offsets.first().copied().unwrap_or(0);
-        Self::write_first_ifd_offset(&mut writer, first_ifd_offset, is_big_tiff)?;
+            // Calculate all offsets for IFDs and data
+            let header_size = if is_big_tiff { 16 } else { 8 };
+            let (ifd_offsets, tag_data_offsets, image_offsets) = Self::calculate_offsets(
+                &sorted_ifds, &external_data, image_data, block_sizes, header_size, is_big_tiff);
 
-        // Write all IFDs
-        Self::write_ifds(&mut writer, &sorted_ifds, &ifd_offsets, &tag_data_offsets, is_big_tiff)?;
+            // Image data of several blocks needs one offset per block
+            Self::fill_block_offsets(&mut external_data, &sorted_ifds, &image_offsets, block_sizes, is_big_tiff);
 
-        // Write all external tag data
-        Self::write_external_data(&mut writer, external_data, &tag_data_offsets)?;
+            // Write the offset to the first IFD in the header area
+            let first_ifd_offset = ifd_offsets.first().copied().unwrap_or(0);
+            Self::write_first_ifd_offset(&mut writer, first_ifd_offset, is_big_tiff)?;
 
-        // Write all image data
-        Self::write_image_data(&mut writer, image_data, &sorted_ifds, &tag_data_offsets)?;
+            // Write all IFDs
+            Self::write_ifds(&mut writer, &sorted_ifds, &ifd_offsets, &tag_data_offsets, is_big_tiff)?;
 
-        // Make sure everything is written to disk
-        writer.flush()?;
-        Ok(())
+            // Write all external tag data
+            Self::write_external_data(&mut writer, &external_data, &tag_data_offsets)?;
+
+            // Write all image data
+            Self::write_image_data(&mut writer, image_data, &image_offsets)?;
+
+            // Make sure everything is written to disk
+            writer.flush()?;
+            Ok(())
+        })
     }
 
     /// Prepare sorted IFDs with unique tags
@@ -73,17 +95,55 @@ impl WriterBuilder {
         }).collect()
     }
 
-    /// Calculate offsets for IFDs and external data
+    /// Move tag data that fits in the value field of its entry into the entry
+    ///
+    /// # Returns
+    /// The tag data that still has to be written outside the IFDs
+    fn inline_small_values(
+        sorted_ifds: &mut [IFD],
+        external_data: &HashMap<(usize, u16), Vec<u8>>,
+        is_big_tiff: bool
+    ) -> HashMap<(usize, u16), Vec<u8>> {
+        let inline_size = if is_big_tiff { 8 } else { 4 };
+        let mut remaining = HashMap::new();
+
+        for (&(ifd_index, tag), data) in external_data {
+            let entry = sorted_ifds.get_mut(ifd_index)
+                .and_then(|ifd| ifd.entries.iter_mut().find(|entry| entry.tag == tag));
+            match entry {
+                Some(entry) if data.len() <= inline_size => {
+                    let mut bytes = [0u8; 8];
+                    bytes[..data.len()].copy_from_slice(data);
+                    entry.value_offset = u64::from_le_bytes(bytes);
+                },
+                _ => {
+                    remaining.insert((ifd_index, tag), data.clone());
+                }
+            }
+        }
+
+        remaining
+    }
+
+    /// Calculate offsets for IFDs, external data and image data
+    ///
+    /// # Returns
+    /// The offsets of the IFDs, of the external tag data and of the image
+    /// data of each IFD. The offset tag of an IFD with a single block points
+    /// at its image data directly; one with several blocks points at an
+    /// array of block offsets kept in the external data.
     fn calculate_offsets(
         sorted_ifds: &[IFD],
         external_data: &HashMap<(usize, u16), Vec<u8>>,
         image_data: &HashMap<usize, Vec<u8>>,
+        block_sizes: &HashMap<usize, Vec<u64>>,
         header_size: u64,
         is_big_tiff: bool
-    ) -> (Vec<u64>, HashMap<(usize, u16), u64>) {
+    ) -> (Vec<u64>, HashMap<(usize, u16), u64>, HashMap<usize, u64>) {
         let mut current_offset = header_size;
         let mut ifd_offsets = Vec::with_capacity(sorted_ifds.len());
         let mut tag_data_offsets = HashMap::new();
+        let mut image_offsets = HashMap::new();
 
         // First pass: calculate IFD offsets
         for ifd in sorted_ifds {
@@ -106,17 +166,52 @@ impl WriterBuilder {
                 let offset_tags = [tags::STRIP_OFFSETS, tags::TILE_OFFSETS];
 
                 for &tag in &offset_tags {
-                    if ifd.has_tag(tag) {
+                    if ifd.has_tag(tag) && !block_sizes.contains_key(ifd_index) {
                         tag_data_offsets.insert((*ifd_index, tag), current_offset);
                     }
                 }
             }
 
+            image_offsets.insert(*ifd_index, current_offset);
             current_offset += data.len() as u64;
             current_offset = write_utils::align_to_4_bytes(current_offset);
         }
 
-        (ifd_offsets, tag_data_offsets)
+        (ifd_offsets, tag_data_offsets, image_offsets)
+    }
+
+    /// Store the file offset of every block of multi-block image data
+    ///
+    /// The blocks of an IFD are written back to back from the start of its
+    /// image data, so each offset follows from the sizes of the blocks
+    /// before it. The offsets replace the placeholder array of the IFD's
+    /// offset tag, which has the same size.
+    fn fill_block_offsets(
+        external_data: &mut HashMap<(usize, u16), Vec<u8>>,
+        sorted_ifds: &[IFD],
+        image_offsets: &HashMap<usize, u64>,
+        block_sizes: &HashMap<usize, Vec<u64>>,
+        is_big_tiff: bool
+    ) {
+        for (ifd_index, sizes) in block_sizes {
+            let (Some(ifd), Some(&start)) = (sorted_ifds.get(*ifd_index), image_offsets.get(ifd_index)) else {
+                continue;
+            };
+            let Some(tag) = [tags::STRIP_OFFSETS, tags::TILE_OFFSETS].into_iter().find(|&tag| ifd.has_tag(tag)) else {
+                continue;
+            };
+
+            let mut offset = start;
+            let mut data = Vec::with_capacity(sizes.len() * if is_big_tiff { 8 } else { 4 });
+            for size in sizes {
+                match is_big_tiff {
+                    true => data.extend_from_slice(&offset.to_le_bytes()),
+                    false => data.extend_from_slice(&(offset as u32).to_le_bytes()),
+                }
+                offset += size;
+            }
+            external_data.insert((*ifd_index, tag), data);
+        }
     }
 
     /// Write TIFF header
@@ -222,7 +317,13 @@ impl WriterBuilder {
             // Only process entries that have calculated offsets
             if let Some(offset) = tag_data_offsets.get(&key) {
                 writer.seek(SeekFrom::Start(*offset))?;
-                writer.write_all(data)?;
+
+                // Packed EXIF/GPS directories carry offsets relative to themselves
+                if *tag == tags::EXIF_IFD || *tag == tags::GPS_IFD {
+                    writer.write_all(&exif_utils::relocate_directory(data, *offset))?;
+                } else {
+                    writer.write_all(data)?;
+                }
                 write_utils::write_padding(writer, data.len())?;
             }
         }
@@ -234,21 +335,11 @@ impl WriterBuilder {
     fn write_image_data(
         writer: &mut (impl Write + Seek),
         image_data: &HashMap<usize, Vec<u8>>,
-        sorted_ifds: &[IFD],
-        tag_data_offsets: &HashMap<(usize, u16), u64>
+        image_offsets: &HashMap<usize, u64>
     ) -> TiffResult<()> {
         for (ifd_index, data) in image_data {
-            // Look for any offset tags that point to this image data
-            let possible_tags = [tags::STRIP_OFFSETS, tags::TILE_OFFSETS];
-
-            // Find the first applicable offset tag
-            let offset = possible_tags.iter()
-                .filter_map(|&tag| tag_data_offsets.get(&(*ifd_index, tag)))
-                .next()
-                .copied();
-
             // Write the data if we found a valid offset
-            if let Some(file_offset) = offset {
+            if let Some(&file_offset) = image_offsets.get(ifd_index) {
                 writer.seek(SeekFrom::Start(file_offset))?;
                 writer.write_all(data)?;
                 write_utils::write_padding(writer, data.len())?;
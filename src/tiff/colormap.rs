@@ -17,6 +17,8 @@ use crate::io::seekable::SeekableReader;
 use crate::tiff::TiffReader;
 use crate::tiff::TiffBuilder;
 use crate::utils::logger::Logger;
+use crate::utils::classification_utils::{self, ClassificationMethod, ColorScheme, ValueHistogram};
+use crate::utils::css_colors;
 
 /// Simple RGB color representation
 #[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
@@ -43,6 +45,23 @@ impl RgbColor {
         RgbColor { r, g, b }
     }
 
+    /// Create from a 16-bit TIFF palette color
+    ///
+    /// # Arguments
+    /// * `rgb16` - Red, green and blue components (0-65535)
+    ///
+    /// # Returns
+    /// The color reduced to 8 bits per component
+    pub fn from_rgb16(rgb16: [u16; 3]) -> Self {
+        // Convert 16-bit to 8-bit by dividing by 257 (approximately 65535/255)
+        RgbColor::new((rgb16[0] / 257) as u8, (rgb16[1] / 257) as u8, (rgb16[2] / 257) as u8)
+    }
+
+    /// Expand to 16 bits per component, as stored in a TIFF ColorMap
+    pub fn to_rgb16(self) -> [u16; 3] {
+        [self.r as u16 * 257, self.g as u16 * 257, self.b as u16 * 257]
+    }
+
     /// Convert to hex string (#RRGGBB format)
     ///
     /// # Returns
@@ -51,40 +70,71 @@ impl RgbColor {
         format!("#{:02x}{:02x}{:02x}", self.r, self.g, self.b)
     }
 
-    /// Create from hex string
+    /// Create from hex string, keeping the alpha component
+    ///
+    /// Accepts `#RGB`, `#RGBA`, `#RRGGBB` and `#RRGGBBAA`.
     ///
     /// # Arguments
-    /// * `hex` - Hex color string (with or without # prefix)
+    /// * `hex` - Hex color string with 3, 4, 6 or 8 digits (with or without # prefix)
     ///
     /// # Returns
-    /// A Result containing the parsed RgbColor or an error
-    pub fn from_hex(hex: &str) -> TiffResult<Self> {
+    /// A Result containing the parsed color and its alpha (255 when absent)
+    pub fn from_hex_rgba(hex: &str) -> TiffResult<(Self, u8)> {
         // Remove # prefix if present
-        let hex = hex.trim_start_matches('#');
+        let hex = hex.trim().trim_start_matches('#');
+
+        // Expand shorthand notation (#abc -> #aabbcc, #abcd -> #aabbccdd)
+        let expanded: String = match hex.len() {
+            3 | 4 => hex.chars().flat_map(|c| [c, c]).collect(),
+            6 | 8 => hex.to_string(),
+            _ => return Err(TiffError::GenericError(
+                format!("Invalid hex color code: {} - must be 3, 4, 6 or 8 hexadecimal digits", hex)
+            )),
+        };
 
-        // Validate hex string length
-        if hex.len() != 6 {
-            return Err(TiffError::GenericError(
-                format!("Invalid hex color code: {} - must be 6 hexadecimal digits", hex)
-            ));
-        }
+        // Parse RGB(A) components
+        let r = parse_hex_component(&expanded[0..2], hex)?;
+        let g = parse_hex_component(&expanded[2..4], hex)?;
+        let b = parse_hex_component(&expanded[4..6], hex)?;
+        let alpha = match expanded.len() {
+            8 => parse_hex_component(&expanded[6..8], hex)?,
+            _ => 255,
+        };
+
+        Ok((RgbColor { r, g, b }, alpha))
+    }
 
-        // Parse RGB components
-        let r = parse_hex_component(&hex[0..2], hex)?;
-        let g = parse_hex_component(&hex[2..4], hex)?;
-        let b = parse_hex_component(&hex[4..6], hex)?;
+    /// Parse a color given as hex or as a CSS color name
+    ///
+    /// # Arguments
+    /// * `text` - Hex color (3, 4, 6 or 8 digits) or a CSS keyword such as "steelblue"
+    ///
+    /// # Returns
+    /// A Result containing the parsed color and its alpha (255 when opaque)
+    pub fn parse_rgba(text: &str) -> TiffResult<(Self, u8)> {
+        let text = text.trim();
+        if !text.starts_with('#') {
+            if let Some((r, g, b, alpha)) = css_colors::lookup(text) {
+                return Ok((RgbColor { r, g, b }, alpha));
+            }
+        }
 
-        Ok(RgbColor { r, g, b })
+        Self::from_hex_rgba(text).map_err(|_| TiffError::GenericError(
+            format!("Invalid color: '{}' is neither a hex color nor a CSS color name", text)
+        ))
     }
 }
 
 /// Helper function to parse a hex color component
 fn parse_hex_component(hex_part: &str, full_hex: &str) -> TiffResult<u8> {
+    if !hex_part.bytes().all(|b| b.is_ascii_hexdigit()) {
+        return Err(TiffError::GenericError(format!("Invalid hex color: {}", full_hex)));
+    }
     u8::from_str_radix(hex_part, 16)
         .map_err(|_| TiffError::GenericError(format!("Invalid hex color: {}", full_hex)))
 }
 
-/// Represents a color map entry with a value and RGB color
+/// Represents a color map entry with a value, RGB color and opacity
 #[derive(Debug, Clone)]
 pub struct ColorMapEntry {
     /// The pixel value this entry applies to
@@ -93,6 +143,10 @@ pub struct ColorMapEntry {
     pub label: Option<String>,
     /// The RGB color for this value
     pub color: RgbColor,
+    /// Opacity of the color (0 transparent, 255 opaque)
+    pub alpha: u8,
+    /// Exact 16-bit color when read from a TIFF palette
+    pub color16: Option<[u16; 3]>,
 }
 
 impl ColorMapEntry {
@@ -108,7 +162,9 @@ impl ColorMapEntry {
         ColorMapEntry {
             value,
             label: None,
-            color
+            color,
+            alpha: 255,
+            color16: None,
         }
     }
 
@@ -126,6 +182,56 @@ impl ColorMapEntry {
             value,
             label: Some(label),
             color,
+            alpha: 255,
+            color16: None,
+        }
+    }
+
+    /// Create a color map entry from a 16-bit TIFF palette color
+    ///
+    /// The 16-bit components are kept so writing the entry back to a TIFF
+    /// ColorMap reproduces them exactly.
+    ///
+    /// # Arguments
+    /// * `value` - The pixel value this entry applies to
+    /// * `rgb16` - Red, green and blue components (0-65535)
+    ///
+    /// # Returns
+    /// A new ColorMapEntry instance
+    pub fn from_rgb16(value: u16, rgb16: [u16; 3]) -> Self {
+        ColorMapEntry {
+            value,
+            label: None,
+            color: RgbColor::from_rgb16(rgb16),
+            alpha: 255,
+            color16: Some(rgb16),
+        }
+    }
+
+    /// Get the 16-bit color to store in a TIFF ColorMap
+    ///
+    /// The exact palette color is used while it still matches `color`;
+    /// otherwise (e.g. the color was edited) the 8-bit color is expanded.
+    pub fn tiff_color(&self) -> [u16; 3] {
+        match self.color16 {
+            Some(rgb16) if RgbColor::from_rgb16(rgb16) == self.color => rgb16,
+            _ => self.color.to_rgb16(),
+        }
+    }
+
+    /// Get the opacity as a fraction between 0.0 and 1.0, as SLD expects it
+    pub fn opacity(&self) -> f64 {
+        self.alpha as f64 / 255.0
+    }
+
+    /// Get the color in hex format, appending alpha when not opaque (#RRGGBB or #RRGGBBAA)
+    ///
+    /// # Returns
+    /// A hex color string
+    pub fn to_rgba_hex(&self) -> String {
+        match self.alpha {
+            255 => self.color.to_hex(),
+            alpha => format!("{}{:02x}", self.color.to_hex(), alpha),
         }
     }
 
@@ -137,22 +243,24 @@ impl ColorMapEntry {
         self.color.to_hex()
     }
 
-    /// Create a color map entry from a hex color string
+    /// Create a color map entry from a hex color string or CSS color name
     ///
     /// # Arguments
     /// * `value` - The pixel value this entry applies to
-    /// * `hex` - Hex color string (with or without # prefix)
+    /// * `hex` - Hex color string (`#RGB`, `#RRGGBB`, `#RRGGBBAA`, ...) or CSS color name
     /// * `label` - Optional label for this entry
     ///
     /// # Returns
     /// A Result containing the new ColorMapEntry or an error
     pub fn from_hex_color(value: u16, hex: &str, label: Option<String>) -> TiffResult<Self> {
-        let color = RgbColor::from_hex(hex)?;
+        let (color, alpha) = RgbColor::parse_rgba(hex)?;
 
         Ok(ColorMapEntry {
             value,
             label,
             color,
+            alpha,
+            color16: None,
         })
     }
 }
@@ -212,7 +320,131 @@ impl ColorMap {
         self.entries.is_empty()
     }
 
-    /// Read a TIFF colormap from an IFD
+    /// Build a classified colormap from a value histogram
+    ///
+    /// Class breaks are derived with the given method and the classes are
+    /// colored by sampling the color scheme from low to high. The result is
+    /// an "intervals" colormap where, as in SLD, each entry colors the values
+    /// below its quantity; entries are labeled with the class range.
+    ///
+    /// # Arguments
+    /// * `stats` - Histogram of the values to classify
+    /// * `n_classes` - Requested number of classes
+    /// * `method` - Equal-interval, quantile or Jenks classification
+    /// * `scheme` - Color scheme for the classes
+    ///
+    /// # Returns
+    /// A Result containing the ColorMap or an error
+    pub fn from_statistics(
+        stats: &ValueHistogram,
+        n_classes: usize,
+        method: ClassificationMethod,
+        scheme: ColorScheme
+    ) -> TiffResult<Self> {
+        let breaks = classification_utils::class_breaks(stats, n_classes, method)?;
+        let colors = scheme.colors(breaks.len());
+        let min = stats.min().unwrap_or(0);
+
+        let mut colormap = ColorMap::new();
+        colormap.set_type("intervals");
+
+        let mut lower = min;
+        for (upper, color) in breaks.iter().zip(colors) {
+            let label = if lower == *upper { upper.to_string() } else { format!("{} - {}", lower, upper) };
+            colormap.add_entry(ColorMapEntry::with_label(upper.saturating_add(1), color, label));
+            lower = upper.saturating_add(1);
+        }
+
+        info!("Generated {}-class colormap ({:?}, {:?})", colormap.len(), method, scheme);
+        Ok(colormap)
+    }
+
+    /// Linearly rescale the entry values from one range to another
+    ///
+    /// Useful when a style authored for one product (e.g. percentages in
+    /// 0-100) must be applied to data in other units (e.g. 0-10000).
+    /// Values are rounded and clamped to the 16-bit range; entries that
+    /// collapse onto the same value keep only the first of them.
+    ///
+    /// # Arguments
+    /// * `from` - Source range (min, max) the entries were authored for
+    /// * `to` - Target range (min, max) of the data
+    ///
+    /// # Returns
+    /// A Result indicating success or an error for an empty source range
+    pub fn rescale(&mut self, from: (f64, f64), to: (f64, f64)) -> TiffResult<()> {
+        let span = from.1 - from.0;
+        if span == 0.0 || !span.is_finite() {
+            return Err(TiffError::GenericError(format!(
+                "Cannot rescale colormap from an empty range {}..{}", from.0, from.1)));
+        }
+
+        let factor = (to.1 - to.0) / span;
+        for entry in &mut self.entries {
+            let scaled = to.0 + (entry.value as f64 - from.0) * factor;
+            entry.value = scaled.round().clamp(0.0, u16::MAX as f64) as u16;
+        }
+
+        info!("Rescaled colormap values from {}..{} to {}..{}", from.0, from.1, to.0, to.1);
+        self.sort_and_dedup();
+        Ok(())
+    }
+
+    /// Shift all entry values by a constant offset
+    ///
+    /// Shifted values saturate at 0 and 65535.
+    ///
+    /// # Arguments
+    /// * `delta` - Amount added to every entry value (may be negative)
+    pub fn offset(&mut self, delta: i32) {
+        for entry in &mut self.entries {
+            entry.value = (entry.value as i32 + delta).clamp(0, u16::MAX as i32) as u16;
+        }
+
+        info!("Offset colormap values by {}", delta);
+        self.sort_and_dedup();
+    }
+
+    /// Merge the entries of another colormap into this one
+    ///
+    /// Where both colormaps define the same value, the entry from `other`
+    /// replaces the existing one. The colormap type of `self` is kept.
+    ///
+    /// # Arguments
+    /// * `other` - Colormap whose entries are added
+    pub fn merge(&mut self, other: &ColorMap) {
+        if other.map_type != self.map_type {
+            warn!("Merging a '{}' colormap into a '{}' colormap; keeping '{}'",
+                  other.map_type, self.map_type, self.map_type);
+        }
+
+        let before = self.entries.len();
+        self.entries.retain(|entry| !other.entries.iter().any(|o| o.value == entry.value));
+        let replaced = before - self.entries.len();
+
+        self.entries.extend(other.entries.iter().cloned());
+        self.sort_and_dedup();
+
+        info!("Merged {} entries ({} replaced), colormap now has {} entries",
+              other.entries.len(), replaced, self.entries.len());
+    }
+
+    /// Sort entries by value and drop entries that repeat a value
+    fn sort_and_dedup(&mut self) {
+        self.entries.sort_by_key(|e| e.value);
+
+        let before = self.entries.len();
+        self.entries.dedup_by_key(|e| e.value);
+        if self.entries.len() < before {
+            warn!("Dropped {} colormap entries that collapsed onto the same value",
+                  before - self.entries.len());
+        }
+    }
+
+    /// Read the raw palette of a TIFF IFD
+    ///
+    /// Unlike `from_tiff_ifd`, every palette slot is kept, so the result
+    /// can be indexed directly by pixel value.
     ///
     /// # Arguments
     /// * `ifd` - The IFD containing the colormap
@@ -220,13 +452,13 @@ impl ColorMap {
     /// * `byte_order_handler` - Handler for the file's byte order
     ///
     /// # Returns
-    /// A Result containing the ColorMap or an error
-    pub fn from_tiff_ifd<R: SeekableReader>(
+    /// A Result containing one 16-bit RGB color per palette index, or an error
+    pub fn read_tiff_palette<R: SeekableReader>(
         ifd: &IFD,
         reader: &mut R,
-        byte_order_handler: &Box<dyn ByteOrderHandler>
-    ) -> TiffResult<Self> {
-        debug!("Reading color map from TIFF IFD");
+        byte_order_handler: &dyn ByteOrderHandler
+    ) -> TiffResult<Vec<[u16; 3]>> {
+        debug!("Reading palette from TIFF IFD");
 
         // Verify it's a palette color image
         let photometric_interp = ifd.get_tag_value(tags::PHOTOMETRIC_INTERPRETATION)
@@ -240,9 +472,14 @@ impl ColorMap {
 
         // Get the bits per sample to determine the number of color map entries
         let bits_per_sample = ifd.get_tag_value(tags::BITS_PER_SAMPLE)
-            .unwrap_or(8) as u16;
+            .unwrap_or(8) as u32;
+        if bits_per_sample > 16 {
+            return Err(TiffError::GenericError(
+                format!("Unsupported palette depth: {} bits per sample", bits_per_sample)
+            ));
+        }
 
-        let num_entries = 1 << bits_per_sample; // 2^bits
+        let num_entries: u32 = 1 << bits_per_sample; // 2^bits
         debug!("Color map should have {} entries ({}-bit)", num_entries, bits_per_sample);
 
         // Get the color map entry
@@ -267,21 +504,36 @@ impl ColorMap {
             num_entries
         )?;
 
-        // Create a new color map
-        let mut colormap = ColorMap::new();
+        let palette = (0..num_entries as usize)
+            .map(|i| [r_values[i], g_values[i], b_values[i]])
+            .collect();
 
-        // Convert 16-bit values to 8-bit and add entries
-        for i in 0..num_entries {
-            // Convert 16-bit to 8-bit by dividing by 257 (approximately 65535/255)
-            let r = (r_values[i as usize] / 257) as u8;
-            let g = (g_values[i as usize] / 257) as u8;
-            let b = (b_values[i as usize] / 257) as u8;
+        Ok(palette)
+    }
 
-            colormap.add_entry(ColorMapEntry::new(i, RgbColor::new(r, g, b)));
-        }
+    /// Read a TIFF colormap from an IFD
+    ///
+    /// # Arguments
+    /// * `ifd` - The IFD containing the colormap
+    /// * `reader` - Reader for accessing the file
+    /// * `byte_order_handler` - Handler for the file's byte order
+    ///
+    /// # Returns
+    /// A Result containing the ColorMap or an error
+    pub fn from_tiff_ifd<R: SeekableReader>(
+        ifd: &IFD,
+        reader: &mut R,
+        byte_order_handler: &Box<dyn ByteOrderHandler>
+    ) -> TiffResult<Self> {
+        debug!("Reading color map from TIFF IFD");
 
-        // Remove entries that have pure black (0,0,0) at the beginning
-        colormap.remove_empty_entries();
+        let palette = Self::read_tiff_palette(ifd, reader, byte_order_handler.as_ref())?;
+
+        // Create a new color map, keeping every index (black entries included)
+        let mut colormap = ColorMap::new();
+        for (i, rgb16) in palette.into_iter().enumerate() {
+            colormap.add_entry(ColorMapEntry::from_rgb16(i as u16, rgb16));
+        }
 
         // Simplify if needed
         colormap.simplify_if_needed();
@@ -290,13 +542,18 @@ impl ColorMap {
     }
 
     /// Remove entries that have pure black (0,0,0) at the beginning
-    fn remove_empty_entries(&mut self) {
-        while !self.entries.is_empty() &&
-            self.entries[0].color.r == 0 &&
-            self.entries[0].color.g == 0 &&
-            self.entries[0].color.b == 0 {
-            self.entries.remove(0);
-        }
+    ///
+    /// Palettes are often padded with unused black slots, but value 0 can
+    /// also be a real black class (e.g. water), so callers must opt in.
+    ///
+    /// # Returns
+    /// The number of entries removed
+    pub fn remove_empty_entries(&mut self) -> usize {
+        let leading_black = self.entries.iter()
+            .take_while(|e| e.color.r == 0 && e.color.g == 0 && e.color.b == 0)
+            .count();
+        self.entries.drain(..leading_black);
+        leading_black
     }
 
     /// Simplify the color map if it has too many entries
@@ -310,7 +567,8 @@ impl ColorMap {
         let mut seen_colors = HashMap::new();
 
         for entry in &self.entries {
-            let color_key = (entry.color.r, entry.color.g, entry.color.b);
+            // Key on the exact palette color so distinct 16-bit entries survive
+            let color_key = entry.tiff_color();
             if !seen_colors.contains_key(&color_key) {
                 seen_colors.insert(color_key, true);
                 simplified.push(entry.clone());
@@ -433,19 +691,26 @@ impl ColorMap {
     /// Create a TIFF colormap suitable for writing to a file
     ///
     /// Converts the ColorMap structure to the raw data format required by TIFF.
+    /// Entries read from a TIFF palette keep their exact 16-bit colors.
     ///
     /// # Returns
     /// A tuple containing (num_entries, raw_data) where raw_data is the combined RGB data
-    pub fn to_tiff_colormap(&self) -> (u16, Vec<u16>) {
+    pub fn to_tiff_colormap(&self) -> (u32, Vec<u16>) {
         // Find the highest value in the color map to determine the size needed
         let max_value = self.entries.iter()
             .map(|e| e.value)
             .max()
             .unwrap_or(0);
 
-        // The number of entries should be a power of 2 and at least cover the max value
-        let bits_needed = ((max_value as f32).log2().ceil() as u32).max(1);
-        let num_entries = 1 << bits_needed; // 2^bits
+        // The number of entries must be 2^BitsPerSample for a valid palette depth (1, 2, 4, 8 or 16)
+        let bits_needed = match max_value {
+            0..=1 => 1,
+            2..=3 => 2,
+            4..=15 => 4,
+            16..=255 => 8,
+            _ => 16,
+        };
+        let num_entries: u32 = 1 << bits_needed; // 2^bits
 
         debug!("Creating TIFF colormap with {} entries (using {} bits)",
               num_entries, bits_needed);
@@ -459,10 +724,7 @@ impl ColorMap {
         for entry in &self.entries {
             let idx = entry.value as usize;
             if idx < num_entries as usize {
-                // Convert 8-bit colors to 16-bit (multiply by 257)
-                r_values[idx] = entry.color.r as u16 * 257;
-                g_values[idx] = entry.color.g as u16 * 257;
-                b_values[idx] = entry.color.b as u16 * 257;
+                [r_values[idx], g_values[idx], b_values[idx]] = entry.tiff_color();
             }
         }
 
@@ -471,13 +733,30 @@ impl ColorMap {
             self.interpolate_ramp_values(&mut r_values, &mut g_values, &mut b_values, num_entries);
         }
 
+        // Interval entries color the values from the previous entry up to their own (exclusive)
+        if self.map_type == "intervals" {
+            let mut lower = 0usize;
+            for entry in &self.entries {
+                let upper = (entry.value as usize).min(num_entries as usize);
+                for idx in lower..upper {
+                    [r_values[idx], g_values[idx], b_values[idx]] = entry.tiff_color();
+                }
+                lower = upper;
+            }
+            if let Some(last) = self.entries.last() {
+                for idx in lower..num_entries as usize {
+                    [r_values[idx], g_values[idx], b_values[idx]] = last.tiff_color();
+                }
+            }
+        }
+
         // Combine all values into a single vector in TIFF's expected order: all R, then all G, then all B
         let mut result = Vec::with_capacity(3 * num_entries as usize);
         result.extend_from_slice(&r_values);
         result.extend_from_slice(&g_values);
         result.extend_from_slice(&b_values);
 
-        (num_entries as u16, result)
+        (num_entries, result)
     }
 
     /// Interpolate missing values in a color ramp
@@ -513,10 +792,11 @@ impl ColorMap {
                     continue; // Skip indices beyond our array size
                 }
 
-                // Linear interpolation between colors
-                r_values[idx] = interpolate_color_component(prev.color.r, curr.color.r, t);
-                g_values[idx] = interpolate_color_component(prev.color.g, curr.color.g, t);
-                b_values[idx] = interpolate_color_component(prev.color.b, curr.color.b, t);
+                // Linear interpolation between the 16-bit colors
+                let (start, end) = (prev.tiff_color(), curr.tiff_color());
+                r_values[idx] = interpolate_color_component(start[0], end[0], t);
+                g_values[idx] = interpolate_color_component(start[1], end[1], t);
+                b_values[idx] = interpolate_color_component(start[2], end[2], t);
             }
         }
     }
@@ -561,8 +841,14 @@ impl ColorMap {
                 |s| s.clone()
             );
 
-            writeln!(writer, "              <sld:ColorMapEntry quantity=\"{}\" label=\"{}\" color=\"{}\"/>",
-                     entry.value, escape_xml(&label), entry.to_hex_color())?;
+            // SLD carries transparency in a separate opacity attribute
+            let opacity = match entry.alpha {
+                255 => String::new(),
+                _ => format!(" opacity=\"{:.3}\"", entry.opacity()),
+            };
+
+            writeln!(writer, "              <sld:ColorMapEntry quantity=\"{}\" label=\"{}\" color=\"{}\"{}/>",
+                     entry.value, escape_xml(&label), entry.to_hex_color(), opacity)?;
         }
 
         writeln!(writer, "            </sld:ColorMap>")?;
@@ -579,17 +865,17 @@ impl ColorMap {
     /// Print the color map to stdout in a human-readable format
     pub fn print(&self) {
         println!("Color Map with {} entries (type: {}):", self.entries.len(), self.map_type);
-        println!("{:^8} {:^20} {:^10}", "Value", "Color (RGB)", "Label");
-        println!("{:-^8} {:-^20} {:-^10}", "", "", "");
+        println!("{:^8} {:^24} {:^10}", "Value", "Color (RGB)", "Label");
+        println!("{:-^8} {:-^24} {:-^10}", "", "", "");
 
         for entry in &self.entries {
-            println!("{:^8} {:^20} {:^10}",
+            println!("{:^8} {:^24} {:^10}",
                      entry.value,
                      format!("({},{},{}) {}",
                              entry.color.r,
                              entry.color.g,
                              entry.color.b,
-                             entry.to_hex_color()
+                             entry.to_rgba_hex()
                      ),
                      entry.label.as_deref().unwrap_or("")
             );
@@ -667,6 +953,8 @@ impl ColorMap {
 pub struct ColorMapReader<'a> {
     /// Logger for recording operations
     logger: &'a Logger,
+    /// Drop leading pure-black entries from TIFF palettes
+    trim_leading_black: bool,
 }
 
 impl<'a> ColorMapReader<'a> {
@@ -679,10 +967,25 @@ impl<'a> ColorMapReader<'a> {
     /// A new ColorMapReader instance
     pub fn new(logger: &'a Logger) -> Self {
         ColorMapReader {
-            logger
+            logger,
+            trim_leading_black: false,
         }
     }
 
+    /// Drop leading pure-black entries when reading TIFF palettes
+    ///
+    /// Off by default so that the original index mapping is preserved.
+    ///
+    /// # Arguments
+    /// * `trim` - Whether to trim leading black entries
+    ///
+    /// # Returns
+    /// The reader with the option set
+    pub fn with_trim_leading_black(mut self, trim: bool) -> Self {
+        self.trim_leading_black = trim;
+        self
+    }
+
     /// Read a color map from a file based on its extension
     ///
     /// # Arguments
@@ -747,7 +1050,12 @@ impl<'a> ColorMapReader<'a> {
             .ok_or_else(|| TiffError::GenericError("No byte order handler available".to_string()))?;
 
         // Extract the color map
-        let colormap = ColorMap::from_tiff_ifd(ifd, &mut file_reader, byte_order_handler)?;
+        let mut colormap = ColorMap::from_tiff_ifd(ifd, &mut file_reader, byte_order_handler)?;
+
+        if self.trim_leading_black {
+            let removed = colormap.remove_empty_entries();
+            info!("Trimmed {} leading black entries from the palette", removed);
+        }
 
         info!("Successfully read color map with {} entries from TIFF", colormap.len());
         self.logger.log(&format!("Read color map with {} entries from {}", colormap.len(), file_path))?;
@@ -755,6 +1063,28 @@ impl<'a> ColorMapReader<'a> {
         Ok(colormap)
     }
 
+    /// Read the full 16-bit palette of a palette-color TIFF, one color per index
+    ///
+    /// # Arguments
+    /// * `file_path` - Path to the palette TIFF
+    ///
+    /// # Returns
+    /// A Result containing the palette colors or an error
+    pub fn read_palette_from_tiff(&self, file_path: &str) -> TiffResult<Vec<[u16; 3]>> {
+        info!("Reading palette from TIFF file: {}", file_path);
+
+        let mut reader = TiffReader::new(self.logger);
+        let tiff = reader.load(file_path)?;
+        let ifd = tiff.ifds.first()
+            .ok_or_else(|| TiffError::GenericError("No IFDs found in TIFF file".to_string()))?;
+
+        let mut file_reader = reader.create_reader()?;
+        let byte_order_handler = reader.get_byte_order_handler()
+            .ok_or_else(|| TiffError::GenericError("No byte order handler available".to_string()))?;
+
+        ColorMap::read_tiff_palette(ifd, &mut file_reader, byte_order_handler.as_ref())
+    }
+
     /// Try to guess the format of a color map file from its content
     ///
     /// # Arguments
@@ -803,8 +1133,8 @@ impl<'a> ColorMapReader<'a> {
 /// Read color map data from the reader
 fn read_colormap_data<R: SeekableReader>(
     reader: &mut R,
-    byte_order_handler: &Box<dyn ByteOrderHandler>,
-    num_entries: u16
+    byte_order_handler: &dyn ByteOrderHandler,
+    num_entries: u32
 ) -> TiffResult<(Vec<u16>, Vec<u16>, Vec<u16>)> {
     // Read the color map data
     let mut r_values = Vec::with_capacity(num_entries as usize);
@@ -846,12 +1176,18 @@ fn parse_sld_entry_attributes(colormap: &mut ColorMap, line: &str) {
         Err(_) => return, // Invalid quantity value, skip this entry
     };
 
-    // Parse the color
-    let rgb_color = match RgbColor::from_hex(&color_hex) {
+    // Parse the color (hex with optional alpha, or a CSS color name)
+    let (rgb_color, color_alpha) = match RgbColor::parse_rgba(&color_hex) {
         Ok(clr) => clr,
-        Err(_) => return, // Invalid color hex code, skip this entry
+        Err(_) => return, // Invalid color, skip this entry
     };
 
+    // The opacity attribute (0.0 - 1.0) scales any alpha given in the color
+    let opacity = extract_attribute(line, "opacity")
+        .and_then(|o| o.trim().parse::<f64>().ok())
+        .map_or(1.0, |o| o.clamp(0.0, 1.0));
+    let alpha = (color_alpha as f64 * opacity).round() as u8;
+
     // Get optional label
     let label = extract_attribute(line, "label");
 
@@ -859,7 +1195,9 @@ fn parse_sld_entry_attributes(colormap: &mut ColorMap, line: &str) {
     let entry = ColorMapEntry {
         value,
         label,
-        color: rgb_color
+        color: rgb_color,
+        alpha,
+        color16: None,
     };
 
     colormap.add_entry(entry);
@@ -879,20 +1217,16 @@ fn parse_csv_line(parts: &[&str]) -> Option<ColorMapEntry> {
 /// Parse a CSV line with format: value,hexcolor
 fn parse_csv_value_hex(parts: &[&str]) -> Option<ColorMapEntry> {
     let value = parts[0].parse::<f64>().ok()?;
-    let color = RgbColor::from_hex(parts[1]).ok()?;
-
-    Some(ColorMapEntry::new(value as u16, color))
+    ColorMapEntry::from_hex_color(value as u16, parts[1], None).ok()
 }
 
 /// Parse a CSV line with 3 parts
 fn parse_csv_three_parts(parts: &[&str]) -> Option<ColorMapEntry> {
     let value = parts[0].parse::<f64>().ok()?;
 
-    // Try to parse as hexcolor,label
-    if let Ok(color) = RgbColor::from_hex(parts[1]) {
-        return Some(ColorMapEntry::with_label(
-            value as u16, color, parts[2].to_string()
-        ));
+    // Try to parse as color,label
+    if let Ok(entry) = ColorMapEntry::from_hex_color(value as u16, parts[1], Some(parts[2].to_string())) {
+        return Some(entry);
     }
 
     // Try to parse as r,g
@@ -925,8 +1259,8 @@ fn parse_csv_value_rgb_label(parts: &[&str]) -> Option<ColorMapEntry> {
 }
 
 /// Helper function to interpolate between color components
-fn interpolate_color_component(start: u8, end: u8, t: f32) -> u16 {
-    ((start as f32 * (1.0 - t) + end as f32 * t) as u16 * 257)
+fn interpolate_color_component(start: u16, end: u16, t: f32) -> u16 {
+    (start as f32 * (1.0 - t) + end as f32 * t).round() as u16
 }
 
 /// Helper function to extract an attribute value from an XML element string
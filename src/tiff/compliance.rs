@@ -0,0 +1,365 @@
+//! Specification compliance of written files
+//!
+//! In strict mode every TIFF rasterkit writes is checked against the
+//! TIFF 6.0, BigTIFF and GeoTIFF specifications before it is written,
+//! and nothing is written if a rule is broken. The checks cover the
+//! required tags, the field types and value counts of the tags the specs
+//! define, the tag order, the strip or tile layout and the structure of
+//! the GeoTIFF tags.
+
+use std::sync::atomic::{AtomicBool, Ordering};
+
+use log::{info, warn};
+
+use crate::tiff::constants::{field_types, photometric, planar_config, tags};
+use crate::tiff::errors::{TiffError, TiffResult};
+use crate::tiff::ifd::{IFD, IFDEntry};
+use crate::utils::tag_utils;
+
+/// Whether written files must comply with the specifications
+static STRICT: AtomicBool = AtomicBool::new(false);
+
+/// Enable or disable strict mode for all files written from now on
+pub fn set_strict(enabled: bool) {
+    if enabled {
+        info!("Strict mode: output must comply with the TIFF 6.0, BigTIFF and GeoTIFF specifications");
+    }
+    STRICT.store(enabled, Ordering::Relaxed);
+}
+
+/// Whether strict mode is enabled
+pub fn is_strict() -> bool {
+    STRICT.load(Ordering::Relaxed)
+}
+
+/// Field types a tag may have, None for tags the specs leave open
+fn allowed_types(tag: u16, is_big_tiff: bool) -> Option<&'static [u16]> {
+    use field_types::*;
+
+    let allowed: &'static [u16] = match tag {
+        tags::IMAGE_WIDTH | tags::IMAGE_LENGTH | tags::ROWS_PER_STRIP
+        | tags::TILE_WIDTH | tags::TILE_LENGTH => &[SHORT, LONG],
+        tags::BITS_PER_SAMPLE | tags::COMPRESSION | tags::PHOTOMETRIC_INTERPRETATION
+        | tags::FILL_ORDER | tags::ORIENTATION | tags::SAMPLES_PER_PIXEL
+        | tags::MIN_SAMPLE_VALUE | tags::MAX_SAMPLE_VALUE | tags::PLANAR_CONFIGURATION
+        | tags::RESOLUTION_UNIT | tags::PREDICTOR | tags::COLOR_MAP | tags::EXTRA_SAMPLES
+        | tags::SAMPLE_FORMAT | tags::SUBFILE_TYPE | tags::GEO_KEY_DIRECTORY_TAG => &[SHORT],
+        tags::NEW_SUBFILE_TYPE => &[LONG],
+        tags::X_RESOLUTION | tags::Y_RESOLUTION => &[RATIONAL],
+        tags::IMAGE_DESCRIPTION | tags::MAKE | tags::MODEL | tags::SOFTWARE | tags::DATE_TIME
+        | tags::ARTIST | tags::HOST_COMPUTER | tags::COPYRIGHT | tags::GEO_ASCII_PARAMS_TAG
+        | tags::GDAL_METADATA | tags::GDAL_NODATA => &[ASCII],
+        tags::MODEL_PIXEL_SCALE_TAG | tags::MODEL_TIEPOINT_TAG | tags::MODEL_TRANSFORMATION_TAG
+        | tags::GEO_DOUBLE_PARAMS_TAG => &[DOUBLE],
+        tags::ICC_PROFILE => &[UNDEFINED],
+        tags::XMP => &[BYTE, UNDEFINED],
+        tags::STRIP_OFFSETS | tags::STRIP_BYTE_COUNTS | tags::TILE_BYTE_COUNTS if is_big_tiff => &[SHORT, LONG, LONG8],
+        tags::STRIP_OFFSETS | tags::STRIP_BYTE_COUNTS | tags::TILE_BYTE_COUNTS => &[SHORT, LONG],
+        tags::TILE_OFFSETS if is_big_tiff => &[LONG, LONG8],
+        tags::TILE_OFFSETS => &[LONG],
+        tags::SUB_IFDS | tags::EXIF_IFD | tags::GPS_IFD if is_big_tiff => &[LONG, IFD, LONG8, IFD8],
+        tags::SUB_IFDS | tags::EXIF_IFD | tags::GPS_IFD => &[LONG, IFD],
+        _ => return None,
+    };
+    Some(allowed)
+}
+
+/// Value count a tag must have, None if it varies
+fn required_count(tag: u16) -> Option<u64> {
+    match tag {
+        tags::IMAGE_WIDTH | tags::IMAGE_LENGTH | tags::COMPRESSION | tags::PHOTOMETRIC_INTERPRETATION
+        | tags::FILL_ORDER | tags::ORIENTATION | tags::SAMPLES_PER_PIXEL | tags::ROWS_PER_STRIP
+        | tags::PLANAR_CONFIGURATION | tags::RESOLUTION_UNIT | tags::PREDICTOR
+        | tags::X_RESOLUTION | tags::Y_RESOLUTION | tags::TILE_WIDTH | tags::TILE_LENGTH
+        | tags::NEW_SUBFILE_TYPE | tags::SUBFILE_TYPE => Some(1),
+        tags::DATE_TIME => Some(20),
+        tags::MODEL_PIXEL_SCALE_TAG => Some(3),
+        tags::MODEL_TRANSFORMATION_TAG => Some(16),
+        _ => None,
+    }
+}
+
+fn describe(tag: u16) -> String {
+    format!("tag {} ({})", tag, tag_utils::get_tag_name(tag))
+}
+
+/// Check the field type and count of one entry
+fn check_entry(entry: &IFDEntry, is_big_tiff: bool, data: Option<Vec<u8>>, violations: &mut Vec<String>) {
+    let name = describe(entry.tag);
+
+    match entry.field_type {
+        field_types::LONG8 | field_types::SLONG8 | field_types::IFD8 if !is_big_tiff => {
+            violations.push(format!("{} uses the BigTIFF-only type {}", name,
+                                    tag_utils::get_field_type_name(entry.field_type)));
+            return;
+        },
+        1..=13 | 16..=18 => {},
+        _ => {
+            violations.push(format!("{} has unknown field type {}", name, entry.field_type));
+            return;
+        },
+    }
+
+    if let Some(allowed) = allowed_types(entry.tag, is_big_tiff) {
+        if !allowed.contains(&entry.field_type) {
+            let expected: Vec<&str> = allowed.iter().map(|&t| tag_utils::get_field_type_name(t)).collect();
+            violations.push(format!("{} has type {}, expected {}", name,
+                                    tag_utils::get_field_type_name(entry.field_type), expected.join(" or ")));
+        }
+    }
+
+    if entry.count == 0 {
+        violations.push(format!("{} has no values", name));
+    } else if let Some(count) = required_count(entry.tag) {
+        if entry.count != count {
+            violations.push(format!("{} has {} values, expected {}", name, entry.count, count));
+        }
+    }
+    if entry.tag == tags::MODEL_TIEPOINT_TAG && !entry.count.is_multiple_of(6) {
+        violations.push(format!("{} has {} values, not a multiple of 6", name, entry.count));
+    }
+
+    if entry.field_type == field_types::ASCII {
+        if let Some(data) = data {
+            if data.get(entry.count as usize - 1).is_some_and(|&last| last != 0) {
+                violations.push(format!("{} is not NUL-terminated", name));
+            }
+        }
+    }
+}
+
+/// Check the strip or tile layout of an image IFD
+fn check_layout(ifd: &IFD, violations: &mut Vec<String>) {
+    let (width, height) = match ifd.get_dimensions() {
+        Some(dimensions) => dimensions,
+        None => return,
+    };
+    let samples = ifd.get_samples_per_pixel().max(1);
+    let planes = if ifd.get_tag_value(tags::PLANAR_CONFIGURATION) == Some(planar_config::PLANAR as u64) {
+        samples
+    } else {
+        1
+    };
+    let count = |tag: u16| ifd.get_entry(tag).map(|entry| entry.count);
+
+    let stripped = ifd.has_tag(tags::STRIP_OFFSETS) || ifd.has_tag(tags::STRIP_BYTE_COUNTS);
+    let tiled = [tags::TILE_WIDTH, tags::TILE_LENGTH, tags::TILE_OFFSETS, tags::TILE_BYTE_COUNTS]
+        .iter().any(|&tag| ifd.has_tag(tag));
+
+    let (blocks, offset_tag, count_tag) = if stripped && tiled {
+        violations.push("both strip and tile tags are present".to_string());
+        return;
+    } else if tiled {
+        let tile_width = ifd.get_tag_value(tags::TILE_WIDTH).unwrap_or(0);
+        let tile_length = ifd.get_tag_value(tags::TILE_LENGTH).unwrap_or(0);
+        if tile_width == 0 || tile_length == 0 {
+            violations.push("TileWidth and TileLength are required for tiled images".to_string());
+            return;
+        }
+        if !tile_width.is_multiple_of(16) || !tile_length.is_multiple_of(16) {
+            violations.push(format!("tile size {}x{} is not a multiple of 16", tile_width, tile_length));
+        }
+        (width.div_ceil(tile_width) * height.div_ceil(tile_length) * planes, tags::TILE_OFFSETS, tags::TILE_BYTE_COUNTS)
+    } else {
+        let rows_per_strip = ifd.get_tag_value(tags::ROWS_PER_STRIP).unwrap_or(u32::MAX as u64).clamp(1, height.max(1));
+        (height.div_ceil(rows_per_strip) * planes, tags::STRIP_OFFSETS, tags::STRIP_BYTE_COUNTS)
+    };
+
+    for tag in [offset_tag, count_tag] {
+        match count(tag) {
+            None => violations.push(format!("required {} is missing", describe(tag))),
+            Some(n) if n != blocks => violations.push(format!("{} has {} values for {} blocks", describe(tag), n, blocks)),
+            Some(_) => {},
+        }
+    }
+
+    if let Some(n) = count(tags::BITS_PER_SAMPLE) {
+        if n != samples {
+            violations.push(format!("{} has {} values for {} samples per pixel", describe(tags::BITS_PER_SAMPLE), n, samples));
+        }
+    }
+
+    if ifd.get_tag_value(tags::PHOTOMETRIC_INTERPRETATION) == Some(photometric::PALETTE as u64) {
+        let bits = ifd.get_tag_value(tags::BITS_PER_SAMPLE).unwrap_or(1).min(16);
+        match count(tags::COLOR_MAP) {
+            None => violations.push("palette image without a ColorMap".to_string()),
+            Some(n) if n != 3 << bits => violations.push(format!("ColorMap has {} values, expected {}", n, 3u64 << bits)),
+            Some(_) => {},
+        }
+    }
+}
+
+/// Check the GeoTIFF tags of an IFD
+fn check_geotiff(ifd: &IFD, tag_data: &dyn Fn(&IFDEntry) -> Option<Vec<u8>>, violations: &mut Vec<String>) {
+    let geo_tags = [tags::MODEL_PIXEL_SCALE_TAG, tags::MODEL_TIEPOINT_TAG, tags::MODEL_TRANSFORMATION_TAG,
+                    tags::GEO_DOUBLE_PARAMS_TAG, tags::GEO_ASCII_PARAMS_TAG];
+    let directory = match ifd.get_entry(tags::GEO_KEY_DIRECTORY_TAG) {
+        Some(entry) => entry,
+        None => {
+            for tag in geo_tags.iter().filter(|&&tag| ifd.has_tag(tag)) {
+                violations.push(format!("{} without a GeoKeyDirectory", describe(*tag)));
+            }
+            return;
+        }
+    };
+
+    if !ifd.has_tag(tags::MODEL_TIEPOINT_TAG) && !ifd.has_tag(tags::MODEL_TRANSFORMATION_TAG) {
+        violations.push("GeoKeyDirectory without ModelTiepoint or ModelTransformation".to_string());
+    }
+    if ifd.has_tag(tags::MODEL_TRANSFORMATION_TAG) && ifd.has_tag(tags::MODEL_PIXEL_SCALE_TAG) {
+        violations.push("ModelTransformation combined with ModelPixelScale".to_string());
+    }
+
+    let keys: Vec<u16> = match tag_data(directory) {
+        Some(data) => data.chunks_exact(2).map(|pair| u16::from_le_bytes([pair[0], pair[1]])).collect(),
+        None => return,
+    };
+    if keys.len() < 4 || keys[0] != 1 {
+        violations.push("GeoKeyDirectory has no version 1 header".to_string());
+        return;
+    }
+    let key_count = keys[3] as usize;
+    if keys.len() != 4 * (key_count + 1) {
+        violations.push(format!("GeoKeyDirectory has {} values for {} keys", keys.len(), key_count));
+        return;
+    }
+    let ids: Vec<u16> = keys[4..].chunks_exact(4).map(|key| key[0]).collect();
+    if ids.windows(2).any(|pair| pair[0] >= pair[1]) {
+        violations.push("GeoKeys are not sorted by key ID".to_string());
+    }
+}
+
+/// Find the specification violations of one IFD
+///
+/// # Arguments
+/// * `ifd` - The IFD as it will be written
+/// * `is_big_tiff` - Whether the file is a BigTIFF
+/// * `tag_data` - Little-endian bytes of an entry's values, None where
+///   they aren't known; checks that need the values are skipped then
+///
+/// # Returns
+/// A description of each violation
+pub fn check_ifd(ifd: &IFD, is_big_tiff: bool, tag_data: &dyn Fn(&IFDEntry) -> Option<Vec<u8>>) -> Vec<String> {
+    let mut violations = Vec::new();
+
+    for pair in ifd.entries.windows(2) {
+        if pair[0].tag == pair[1].tag {
+            violations.push(format!("{} appears more than once", describe(pair[0].tag)));
+        } else if pair[0].tag > pair[1].tag {
+            violations.push(format!("{} follows tag {}, tags must be sorted", describe(pair[1].tag), pair[0].tag));
+        }
+    }
+
+    for entry in &ifd.entries {
+        check_entry(entry, is_big_tiff, tag_data(entry), &mut violations);
+    }
+
+    for tag in [tags::IMAGE_WIDTH, tags::IMAGE_LENGTH, tags::PHOTOMETRIC_INTERPRETATION] {
+        if !ifd.has_tag(tag) {
+            violations.push(format!("required {} is missing", describe(tag)));
+        }
+    }
+    check_layout(ifd, &mut violations);
+    check_geotiff(ifd, tag_data, &mut violations);
+
+    violations
+}
+
+/// Unsigned values of a SHORT, LONG or LONG8 entry from its little-endian bytes
+fn unsigned_values(field_type: u16, data: &[u8]) -> Vec<u64> {
+    match field_type {
+        field_types::SHORT => data.chunks_exact(2).map(|value| u16::from_le_bytes([value[0], value[1]]) as u64).collect(),
+        field_types::LONG => data.chunks_exact(4).map(|value| u32::from_le_bytes(value.try_into().unwrap()) as u64).collect(),
+        field_types::LONG8 => data.chunks_exact(8).map(|value| u64::from_le_bytes(value.try_into().unwrap())).collect(),
+        _ => Vec::new(),
+    }
+}
+
+/// Find the data of an IFD that lies beyond the end of the file
+///
+/// Checks the out-of-line values of every entry and the byte range of
+/// every strip or tile.
+///
+/// # Arguments
+/// * `ifd` - The IFD as it will be written
+/// * `is_big_tiff` - Whether the file is a BigTIFF
+/// * `tag_data` - Little-endian bytes of an entry's values, None where
+///   they aren't known; block ranges are skipped then
+/// * `file_length` - Length of the file in bytes
+///
+/// # Returns
+/// A description of each violation
+pub fn check_ranges(ifd: &IFD, is_big_tiff: bool, tag_data: &dyn Fn(&IFDEntry) -> Option<Vec<u8>>,
+                    file_length: u64) -> Vec<String> {
+    let mut violations = Vec::new();
+
+    for entry in ifd.entries.iter().filter(|entry| !entry.is_value_inline(is_big_tiff)) {
+        let end = entry.value_offset.saturating_add(entry.count.saturating_mul(entry.get_field_type_size() as u64));
+        if end > file_length {
+            violations.push(format!("data of {} ends at byte {}, past the end of the file at {}",
+                                    describe(entry.tag), end, file_length));
+        }
+    }
+
+    for (kind, offset_tag, count_tag) in [("strip", tags::STRIP_OFFSETS, tags::STRIP_BYTE_COUNTS),
+                                          ("tile", tags::TILE_OFFSETS, tags::TILE_BYTE_COUNTS)] {
+        let values = |tag: u16| ifd.get_entry(tag)
+            .and_then(|entry| tag_data(entry).map(|data| unsigned_values(entry.field_type, &data)));
+        let (Some(offsets), Some(counts)) = (values(offset_tag), values(count_tag)) else {
+            continue;
+        };
+        for (index, (offset, count)) in offsets.iter().zip(&counts).enumerate() {
+            let end = offset.saturating_add(*count);
+            if end > file_length {
+                violations.push(format!("{} {} spans bytes {} to {}, past the end of the file at {}",
+                                        kind, index, offset, end, file_length));
+            }
+        }
+    }
+
+    violations
+}
+
+/// Refuse output that violates the specifications, if strict mode is on
+///
+/// # Arguments
+/// * `ifds` - The IFDs of the file, in the order they will be written
+/// * `is_big_tiff` - Whether the file is a BigTIFF
+/// * `tag_data` - Little-endian bytes of an entry's values given the
+///   index of its IFD, None where they aren't known
+/// * `file_length` - Length of the file when its data is already laid
+///   out, to check every value and block lies within it
+///
+/// # Returns
+/// Ok if strict mode is off or the file complies, an error listing the
+/// violations otherwise
+pub(crate) fn enforce(
+    ifds: &[IFD],
+    is_big_tiff: bool,
+    tag_data: &dyn Fn(usize, &IFDEntry) -> Option<Vec<u8>>,
+    file_length: Option<u64>
+) -> TiffResult<()> {
+    if !is_strict() {
+        return Ok(());
+    }
+
+    let mut violations = Vec::new();
+    for (index, ifd) in ifds.iter().enumerate() {
+        let mut ifd_violations = check_ifd(ifd, is_big_tiff, &|entry| tag_data(index, entry));
+        if let Some(file_length) = file_length {
+            ifd_violations.extend(check_ranges(ifd, is_big_tiff, &|entry| tag_data(index, entry), file_length));
+        }
+        for violation in ifd_violations {
+            warn!("IFD {}: {}", index, violation);
+            violations.push(format!("IFD {}: {}", index, violation));
+        }
+    }
+
+    if violations.is_empty() {
+        info!("Output complies with the specifications");
+        return Ok(());
+    }
+    Err(TiffError::GenericError(format!(
+        "Strict mode: refusing to write output that violates the specifications ({})",
+        violations.join("; "))))
+}
@@ -36,6 +36,7 @@ pub mod field_types {
     pub const SRATIONAL: u16 = 10; // Two SLONGs: numerator and denominator
     pub const FLOAT: u16 = 11;     // Single precision IEEE floating point
     pub const DOUBLE: u16 = 12;    // Double precision IEEE floating point
+    pub const IFD: u16 = 13;       // 32-bit IFD offset
     pub const LONG8: u16 = 16;     // BigTIFF 64-bit unsigned integer
     pub const SLONG8: u16 = 17;    // BigTIFF 64-bit signed integer
     pub const IFD8: u16 = 18;      // BigTIFF 64-bit IFD offset
@@ -59,7 +60,10 @@ pub mod tags {
     pub const PLANAR_CONFIGURATION: u16 = 284;     // How components are stored
     pub const COLOR_MAP: u16 = 320;                // Colormap for palette color images
     pub const SAMPLE_FORMAT: u16 = 339;            // Interpretation of sample data
+    pub const EXTRA_SAMPLES: u16 = 338;            // Meaning of samples beyond the color channels
     pub const PREDICTOR: u16 = 317;                // Prediction scheme used on image data
+    pub const JPEG_TABLES: u16 = 347;              // Quantization and Huffman tables of JPEG blocks
+    pub const YCBCR_SUBSAMPLING: u16 = 530;        // Chroma subsampling of YCbCr data
 
     // Other common tags
     pub const RESOLUTION_UNIT: u16 = 296;          // Unit of measurement for resolution
@@ -71,6 +75,14 @@ pub mod tags {
     pub const ARTIST: u16 = 315;                   // Person who created the image
     pub const HOST_COMPUTER: u16 = 316;            // Computer where the image was created
     pub const COPYRIGHT: u16 = 33432;              // Copyright notice
+    pub const ICC_PROFILE: u16 = 34675;            // Embedded ICC color profile (InterColorProfile)
+    pub const IMAGE_DESCRIPTION: u16 = 270;        // Free-text description of the image
+    pub const MAKE: u16 = 271;                     // Camera or scanner manufacturer
+    pub const MODEL: u16 = 272;                    // Camera or scanner model
+    pub const XMP: u16 = 700;                      // XMP metadata packet
+    pub const EXIF_IFD: u16 = 34665;               // Offset of the EXIF sub-IFD
+    pub const GPS_IFD: u16 = 34853;                // Offset of the GPS sub-IFD
+    pub const SUB_IFDS: u16 = 330;                 // Offsets of child IFDs (masks, overviews)
 
     pub const TILE_OFFSETS: u16 = 324;             // Offsets to the data tiles
     pub const TILE_BYTE_COUNTS: u16 = 325;         // Byte counts for tiles
@@ -186,11 +198,48 @@ pub mod new_subfile_type {
 
 /// GeoTIFF Key ID constants
 pub mod geo_keys {
+    pub const MODEL_TYPE: u16 = 1024;         // GTModelTypeGeoKey
+    pub const RASTER_TYPE: u16 = 1025;        // GTRasterTypeGeoKey
     pub const PROJECTED_CS_TYPE: u16 = 3072;  // ProjectedCSTypeGeoKey
     pub const PROJECTION: u16 = 3074;         // ProjectionGeoKey
     pub const GEOGRAPHIC_TYPE: u16 = 2048;    // GeographicTypeGeoKey
     pub const GEOG_LINEAR_UNITS: u16 = 2052;  // GeogLinearUnitsGeoKey
+    pub const GEOG_ANGULAR_UNITS: u16 = 2054; // GeogAngularUnitsGeoKey
     pub const PROJ_LINEAR_UNITS: u16 = 3076;  // ProjLinearUnitsGeoKey
+    pub const CITATION: u16 = 1026;           // GTCitationGeoKey
+    pub const GEOG_CITATION: u16 = 2049;      // GeogCitationGeoKey
+    pub const GEOG_GEODETIC_DATUM: u16 = 2050; // GeogGeodeticDatumGeoKey
+    pub const GEOG_PRIME_MERIDIAN: u16 = 2051; // GeogPrimeMeridianGeoKey
+    pub const GEOG_ELLIPSOID: u16 = 2056;     // GeogEllipsoidGeoKey
+    pub const GEOG_SEMI_MAJOR_AXIS: u16 = 2057; // GeogSemiMajorAxisGeoKey
+    pub const GEOG_SEMI_MINOR_AXIS: u16 = 2058; // GeogSemiMinorAxisGeoKey
+    pub const GEOG_INV_FLATTENING: u16 = 2059; // GeogInvFlatteningGeoKey
+    pub const GEOG_PRIME_MERIDIAN_LONG: u16 = 2061; // GeogPrimeMeridianLongGeoKey
+    pub const GEOG_TOWGS84: u16 = 2062;       // GeogTOWGS84GeoKey (GDAL extension)
+    pub const PCS_CITATION: u16 = 3073;       // PCSCitationGeoKey
+    pub const PROJ_COORD_TRANS: u16 = 3075;   // ProjCoordTransGeoKey
+    pub const PROJ_STD_PARALLEL_1: u16 = 3078; // ProjStdParallel1GeoKey
+    pub const PROJ_STD_PARALLEL_2: u16 = 3079; // ProjStdParallel2GeoKey
+    pub const PROJ_NAT_ORIGIN_LONG: u16 = 3080; // ProjNatOriginLongGeoKey
+    pub const PROJ_NAT_ORIGIN_LAT: u16 = 3081; // ProjNatOriginLatGeoKey
+    pub const PROJ_FALSE_EASTING: u16 = 3082; // ProjFalseEastingGeoKey
+    pub const PROJ_FALSE_NORTHING: u16 = 3083; // ProjFalseNorthingGeoKey
+    pub const PROJ_FALSE_ORIGIN_LONG: u16 = 3084; // ProjFalseOriginLongGeoKey
+    pub const PROJ_FALSE_ORIGIN_LAT: u16 = 3085; // ProjFalseOriginLatGeoKey
+    pub const PROJ_FALSE_ORIGIN_EASTING: u16 = 3086; // ProjFalseOriginEastingGeoKey
+    pub const PROJ_FALSE_ORIGIN_NORTHING: u16 = 3087; // ProjFalseOriginNorthingGeoKey
+    pub const PROJ_CENTER_LONG: u16 = 3088;   // ProjCenterLongGeoKey
+    pub const PROJ_CENTER_LAT: u16 = 3089;    // ProjCenterLatGeoKey
+    pub const PROJ_CENTER_EASTING: u16 = 3090; // ProjCenterEastingGeoKey
+    pub const PROJ_CENTER_NORTHING: u16 = 3091; // ProjCenterNorthingGeoKey
+    pub const PROJ_SCALE_AT_NAT_ORIGIN: u16 = 3092; // ProjScaleAtNatOriginGeoKey
+    pub const PROJ_SCALE_AT_CENTER: u16 = 3093; // ProjScaleAtCenterGeoKey
+    pub const PROJ_AZIMUTH_ANGLE: u16 = 3094; // ProjAzimuthAngleGeoKey
+    pub const PROJ_STRAIGHT_VERT_POLE_LONG: u16 = 3095; // ProjStraightVertPoleLongGeoKey
+    pub const VERTICAL_CS_TYPE: u16 = 4096;   // VerticalCSTypeGeoKey
+    pub const VERTICAL_CITATION: u16 = 4097;  // VerticalCitationGeoKey
+    pub const VERTICAL_DATUM: u16 = 4098;     // VerticalDatumGeoKey
+    pub const VERTICAL_UNITS: u16 = 4099;     // VerticalUnitsGeoKey
 }
 
 /// EPSG code constants for common projections
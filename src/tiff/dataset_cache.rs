@@ -0,0 +1,180 @@
+//! Cache of parsed TIFF headers shared between threads
+//!
+//! A server answering many requests against the same few rasters would
+//! otherwise read and parse every file's IFD chain and GeoKeys on each
+//! call. The cache keeps the parsed structure of recently opened files,
+//! keyed by path and checked against the file's modification time and
+//! length, so a file replaced on disk is parsed again on its next use.
+//!
+//! Files are parsed outside the lock: threads opening different files do
+//! not wait for each other, and two threads racing to open the same new
+//! file both parse it, the later one replacing the earlier entry.
+
+use std::collections::HashMap;
+use std::sync::{Arc, Mutex};
+use std::time::SystemTime;
+
+use log::{debug, info};
+
+use crate::io::byte_order::ByteOrder;
+use crate::io::storage;
+use crate::tiff::errors::{TiffError, TiffResult};
+use crate::tiff::geo_key_parser::{GeoInfo, GeoKeyParser};
+use crate::tiff::reader::TiffReader;
+use crate::tiff::types::TIFF;
+use crate::utils::logger::Logger;
+
+/// Number of files whose headers are kept by default
+pub const DEFAULT_CACHED_DATASETS: usize = 64;
+
+/// Parsed header of one file
+pub struct ParsedDataset {
+    /// Path the file was opened from
+    pub path: String,
+    /// IFDs of the file
+    pub tiff: TIFF,
+    /// Byte order of the file
+    pub byte_order: ByteOrder,
+    /// GeoKeys of the first IFD, None if the file is not a GeoTIFF
+    pub geo_info: Option<GeoInfo>,
+}
+
+impl ParsedDataset {
+    /// Parse a file's IFDs and GeoKeys
+    ///
+    /// # Arguments
+    /// * `path` - Path to the TIFF file
+    /// * `logger` - Logger for recording operations
+    ///
+    /// # Returns
+    /// The parsed header, or an error if the file is not a readable TIFF
+    pub fn parse(path: &str, logger: &Logger) -> TiffResult<Self> {
+        let mut reader = TiffReader::new(logger);
+        let tiff = reader.load(path)?;
+        let handler = reader.get_byte_order_handler()
+            .ok_or_else(|| TiffError::GenericError(format!("No byte order read from {}", path)))?;
+        let geo_info = tiff.ifds.first()
+            .and_then(|ifd| GeoKeyParser::extract_geo_info(ifd, handler, path).ok());
+
+        Ok(ParsedDataset {
+            path: path.to_string(),
+            byte_order: handler.byte_order(),
+            tiff,
+            geo_info,
+        })
+    }
+
+    /// A reader for tag values of the file that does not parse it again
+    ///
+    /// # Arguments
+    /// * `logger` - Logger for recording operations
+    ///
+    /// # Returns
+    /// A reader set up as if it had loaded the file
+    pub fn reader<'a>(&self, logger: &'a Logger) -> TiffReader<'a> {
+        let mut reader = TiffReader::new(logger);
+        reader.attach(&self.path, self.byte_order, self.tiff.is_big_tiff);
+        reader
+    }
+}
+
+/// A cached header and what it was parsed from
+struct CacheEntry {
+    /// Modification time and length of the file when it was parsed
+    stamp: (SystemTime, u64),
+    /// The parsed header
+    dataset: Arc<ParsedDataset>,
+    /// Value of the use counter at the last lookup, for eviction
+    last_used: u64,
+}
+
+/// Contents of the cache, behind its lock
+#[derive(Default)]
+struct CacheState {
+    /// Cached headers by path
+    entries: HashMap<String, CacheEntry>,
+    /// Incremented on every lookup
+    uses: u64,
+}
+
+/// Parsed headers of recently opened files
+pub struct DatasetCache {
+    /// Most files kept; the least recently used is dropped beyond it
+    capacity: usize,
+    /// Cached headers
+    state: Mutex<CacheState>,
+}
+
+impl DatasetCache {
+    /// Create an empty cache
+    ///
+    /// # Arguments
+    /// * `capacity` - Most files to keep, 0 to parse every file on each use
+    pub fn new(capacity: usize) -> Self {
+        DatasetCache { capacity, state: Mutex::new(CacheState::default()) }
+    }
+
+    /// Look up the parsed header of a file, parsing it if needed
+    ///
+    /// # Arguments
+    /// * `path` - Path to the TIFF file
+    /// * `logger` - Logger for recording operations
+    ///
+    /// # Returns
+    /// The parsed header, shared with other users of the cache
+    pub fn open(&self, path: &str, logger: &Logger) -> TiffResult<Arc<ParsedDataset>> {
+        // Objects in cloud storage have no modification time to check, so
+        // their headers are parsed on every use
+        if storage::is_remote(path) {
+            return Ok(Arc::new(ParsedDataset::parse(path, logger)?));
+        }
+
+        let metadata = std::fs::metadata(path)?;
+        let stamp = (metadata.modified()?, metadata.len());
+
+        {
+            let mut state = self.lock();
+            state.uses += 1;
+            let uses = state.uses;
+            if let Some(entry) = state.entries.get_mut(path).filter(|entry| entry.stamp == stamp) {
+                debug!("Using the cached header of {}", path);
+                entry.last_used = uses;
+                return Ok(Arc::clone(&entry.dataset));
+            }
+        }
+
+        let dataset = Arc::new(ParsedDataset::parse(path, logger)?);
+        if self.capacity == 0 {
+            return Ok(dataset);
+        }
+
+        let mut state = self.lock();
+        if !state.entries.contains_key(path) && state.entries.len() >= self.capacity {
+            let oldest = state.entries.iter()
+                .min_by_key(|(_, entry)| entry.last_used)
+                .map(|(oldest, _)| oldest.clone());
+            if let Some(oldest) = oldest {
+                info!("Dropping the cached header of {}", oldest);
+                state.entries.remove(&oldest);
+            }
+        }
+        let last_used = state.uses;
+        state.entries.insert(path.to_string(), CacheEntry { stamp, dataset: Arc::clone(&dataset), last_used });
+        Ok(dataset)
+    }
+
+    /// Number of files whose headers are cached
+    pub fn len(&self) -> usize {
+        self.lock().entries.len()
+    }
+
+    /// Drop every cached header
+    pub fn clear(&self) {
+        self.lock().entries.clear();
+    }
+
+    /// Lock the cache, recovering it if a thread panicked while holding it
+    fn lock(&self) -> std::sync::MutexGuard<'_, CacheState> {
+        self.state.lock().unwrap_or_else(|poisoned| poisoned.into_inner())
+    }
+}
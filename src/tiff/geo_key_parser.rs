@@ -3,15 +3,24 @@
 //! This module provides utilities for parsing and interpreting
 //! geographic metadata stored in TIFF files according to the GeoTIFF standard.
 
-use std::fs::File;
 use std::io::{Read, Seek, SeekFrom};
 use log::debug;
 
+use crate::io::storage;
 use crate::tiff::errors::{TiffError, TiffResult};
 use crate::tiff::ifd::IFD;
 use crate::tiff::{GeoKeyEntry, get_key_name};
+use crate::tiff::geotags::get_code_name;
 use crate::tiff::constants::{tags, geo_keys, epsg, proj_method};
 use crate::io::byte_order::ByteOrderHandler;
+use crate::tiff::proj_definition::ProjDefinition;
+
+/// GeoKey value marking a user-defined code
+const USER_DEFINED: u32 = 32767;
+
+/// GTModelTypeGeoKey values
+const MODEL_TYPE_PROJECTED: u16 = 1;
+const MODEL_TYPE_GEOGRAPHIC: u16 = 2;
 
 /// Parser for GeoTIFF geographic metadata
 pub struct GeoKeyParser;
@@ -49,7 +58,7 @@ impl GeoKeyParser {
             return Err(TiffError::GenericError("Invalid GeoKey directory header".to_string()));
         }
 
-        let file = File::open(file_path)?;
+        let file = storage::open(file_path)?;
         let mut reader = file;
         reader.seek(SeekFrom::Start(key_dir_offset))?;
 
@@ -112,7 +121,7 @@ impl GeoKeyParser {
         if key_entry.tiff_tag_location == tags::GEO_DOUBLE_PARAMS_TAG {
             if let Some(entry) = ifd.get_entry(tags::GEO_DOUBLE_PARAMS_TAG) {
                 let offset = entry.value_offset;
-                let file = File::open(file_path)?;
+                let file = storage::open(file_path)?;
                 let mut reader = file;
                 reader.seek(SeekFrom::Start(offset + (key_entry.value_offset as u64) * 8))?;
 
@@ -122,7 +131,7 @@ impl GeoKeyParser {
         } else if key_entry.tiff_tag_location == tags::GEO_ASCII_PARAMS_TAG {
             if let Some(entry) = ifd.get_entry(tags::GEO_ASCII_PARAMS_TAG) {
                 let offset = entry.value_offset;
-                let file = File::open(file_path)?;
+                let file = storage::open(file_path)?;
                 let mut reader = file;
                 reader.seek(SeekFrom::Start(offset + (key_entry.value_offset as u64)))?;
 
@@ -160,7 +169,7 @@ impl GeoKeyParser {
         file_path: &str
     ) -> TiffResult<Vec<f64>> {
         if let Some(entry) = ifd.get_entry(tags::MODEL_PIXEL_SCALE_TAG) {
-            let file = File::open(file_path)?;
+            let file = storage::open(file_path)?;
             let mut reader = file;
             reader.seek(SeekFrom::Start(entry.value_offset))?;
 
@@ -194,7 +203,7 @@ impl GeoKeyParser {
         file_path: &str
     ) -> TiffResult<Vec<f64>> {
         if let Some(entry) = ifd.get_entry(tags::MODEL_TIEPOINT_TAG) {
-            let file = File::open(file_path)?;
+            let file = storage::open(file_path)?;
             let mut reader = file;
             reader.seek(SeekFrom::Start(entry.value_offset))?;
 
@@ -209,6 +218,40 @@ impl GeoKeyParser {
         Err(TiffError::TagNotFound(tags::MODEL_TIEPOINT_TAG))
     }
 
+    /// Read model transformation values
+    ///
+    /// ModelTransformationTag (34264) holds a 4x4 matrix mapping raster to
+    /// model coordinates, used instead of tiepoint and scale for rotated
+    /// or sheared images.
+    ///
+    /// # Arguments
+    /// * `ifd` - The IFD containing the ModelTransformationTag
+    /// * `byte_order_handler` - Handler for the file's byte order
+    /// * `file_path` - Path to the TIFF file
+    ///
+    /// # Returns
+    /// * `TiffResult<Vec<f64>>` - The 16 matrix values in row-major order
+    pub fn read_model_transformation_values(
+        ifd: &IFD,
+        byte_order_handler: &dyn ByteOrderHandler,
+        file_path: &str
+    ) -> TiffResult<Vec<f64>> {
+        if let Some(entry) = ifd.get_entry(tags::MODEL_TRANSFORMATION_TAG) {
+            let file = storage::open(file_path)?;
+            let mut reader = file;
+            reader.seek(SeekFrom::Start(entry.value_offset))?;
+
+            let mut values = Vec::with_capacity(entry.count as usize);
+            for _ in 0..entry.count {
+                values.push(byte_order_handler.read_f64(&mut reader)?);
+            }
+
+            return Ok(values);
+        }
+
+        Err(TiffError::TagNotFound(tags::MODEL_TRANSFORMATION_TAG))
+    }
+
     /// Extract geospatial information from a TIFF IFD
     ///
     /// Interprets all the GeoTIFF tags and keys to build a comprehensive
@@ -250,11 +293,43 @@ impl GeoKeyParser {
                         geo_info.geographic_cs_code = key.value_offset as u32;
                     }
                 },
+                geo_keys::MODEL_TYPE if key.tiff_tag_location == 0 => {
+                    geo_info.model_type = key.value_offset;
+                },
+                geo_keys::GEOG_LINEAR_UNITS if key.tiff_tag_location == 0 => {
+                    geo_info.geog_linear_units_code = key.value_offset;
+                },
+                geo_keys::GEOG_ANGULAR_UNITS if key.tiff_tag_location == 0 => {
+                    geo_info.angular_units_code = key.value_offset;
+                },
+                geo_keys::PROJ_LINEAR_UNITS if key.tiff_tag_location == 0 => {
+                    geo_info.linear_units_code = key.value_offset;
+                },
+                geo_keys::VERTICAL_CS_TYPE if key.tiff_tag_location == 0 => {
+                    geo_info.vertical_cs_code = key.value_offset as u32;
+                },
+                geo_keys::VERTICAL_CITATION => {
+                    geo_info.vertical_citation =
+                        Self::get_geo_key_value_as_string(ifd, key, byte_order_handler, file_path).ok()
+                            .map(|text| text.trim_end_matches('|').to_string());
+                },
+                geo_keys::VERTICAL_DATUM if key.tiff_tag_location == 0 => {
+                    geo_info.vertical_datum_code = key.value_offset as u32;
+                },
+                geo_keys::VERTICAL_UNITS if key.tiff_tag_location == 0 => {
+                    geo_info.vertical_units_code = key.value_offset;
+                },
                 // Add more key interpretations as needed
                 _ => {}
             }
         }
 
+        // Projection parameters live in the double and ASCII parameter tags
+        let double_params = Self::read_geo_double_params(ifd, byte_order_handler.as_ref(), file_path)?;
+        let ascii_params = Self::read_geo_ascii_params(ifd, file_path)?;
+        geo_info.projection = ProjectionParameters::from_geo_keys(&geo_keys, &double_params, &ascii_params);
+        debug!("Projection parameters: {:?}", geo_info.projection);
+
         // Try to get pixel scale
         if let Ok(pixel_scale) = Self::read_model_pixel_scale_values(ifd, byte_order_handler, file_path) {
             if pixel_scale.len() >= 2 {
@@ -286,6 +361,61 @@ impl GeoKeyParser {
         Ok(geo_info)
     }
 
+    /// Read the GeoDoubleParamsTag values
+    ///
+    /// # Arguments
+    /// * `ifd` - The IFD containing the tag
+    /// * `byte_order_handler` - Handler for the file's byte order
+    /// * `file_path` - Path to the TIFF file
+    ///
+    /// # Returns
+    /// * `TiffResult<Vec<f64>>` - The doubles, empty if the tag is absent
+    pub fn read_geo_double_params(
+        ifd: &IFD,
+        byte_order_handler: &dyn ByteOrderHandler,
+        file_path: &str
+    ) -> TiffResult<Vec<f64>> {
+        let Some(entry) = ifd.get_entry(tags::GEO_DOUBLE_PARAMS_TAG) else {
+            return Ok(Vec::new());
+        };
+
+        let mut reader = storage::open(file_path)?;
+        reader.seek(SeekFrom::Start(entry.value_offset))?;
+
+        let mut values = Vec::with_capacity(entry.count as usize);
+        for _ in 0..entry.count {
+            values.push(byte_order_handler.read_f64(&mut reader)?);
+        }
+        Ok(values)
+    }
+
+    /// Read the GeoAsciiParamsTag text
+    ///
+    /// The text holds '|'-terminated strings that GeoKeys point into.
+    ///
+    /// # Arguments
+    /// * `ifd` - The IFD containing the tag
+    /// * `file_path` - Path to the TIFF file
+    ///
+    /// # Returns
+    /// * `TiffResult<String>` - The raw text, empty if the tag is absent
+    pub fn read_geo_ascii_params(ifd: &IFD, file_path: &str) -> TiffResult<String> {
+        let Some(entry) = ifd.get_entry(tags::GEO_ASCII_PARAMS_TAG) else {
+            return Ok(String::new());
+        };
+
+        // Up to four bytes sit inline in the entry, too short for any citation
+        if entry.count <= 4 {
+            return Ok(String::new());
+        }
+
+        let mut reader = storage::open(file_path)?;
+        reader.seek(SeekFrom::Start(entry.value_offset))?;
+        let mut buffer = vec![0u8; entry.count as usize];
+        reader.read_exact(&mut buffer)?;
+        Ok(String::from_utf8_lossy(&buffer).to_string())
+    }
+
     /// Format a human-readable GeoTIFF projection string
     ///
     /// Interprets the projection information in a GeoInfo structure
@@ -345,6 +475,32 @@ impl GeoKeyParser {
         projection
     }
 
+    /// Format a PROJ.4 definition string for the georeferencing
+    ///
+    /// Unlike `format_projection_string`, which only labels the system, this
+    /// expands it into a full `+proj=...` definition that proj-based tools
+    /// can consume directly.
+    ///
+    /// # Arguments
+    /// * `geo_info` - The GeoInfo structure containing projection information
+    ///
+    /// # Returns
+    /// * `Option<String>` - The PROJ.4 string, or None if the system cannot be described
+    pub fn format_proj4_string(geo_info: &GeoInfo) -> Option<String> {
+        ProjDefinition::from_geo_info(geo_info).map(|definition| definition.to_proj_string())
+    }
+
+    /// Format a PROJJSON definition for the georeferencing
+    ///
+    /// # Arguments
+    /// * `geo_info` - The GeoInfo structure containing projection information
+    ///
+    /// # Returns
+    /// * `Option<String>` - The PROJJSON document, or None if the system cannot be described
+    pub fn format_projjson(geo_info: &GeoInfo) -> Option<String> {
+        ProjDefinition::from_geo_info(geo_info).map(|definition| definition.to_projjson())
+    }
+
     /// Format GeoKeys for display
     ///
     /// Creates a vector of tuples containing all GeoKey information,
@@ -403,6 +559,24 @@ pub struct GeoInfo {
     pub origin_y: f64,
     /// Optional tie point (i,j,k,x,y,z)
     pub tie_point: Option<(f64, f64, f64, f64, f64, f64)>,
+    /// GTModelTypeGeoKey value (1 projected, 2 geographic, 0 if not set)
+    pub model_type: u16,
+    /// Linear unit code of the projected CRS (0 if not set)
+    pub linear_units_code: u16,
+    /// Linear unit code of the geographic CRS (0 if not set)
+    pub geog_linear_units_code: u16,
+    /// Angular unit code of the geographic CRS (0 if not set)
+    pub angular_units_code: u16,
+    /// Vertical coordinate system code (0 if not set)
+    pub vertical_cs_code: u32,
+    /// Vertical datum code (0 if not set)
+    pub vertical_datum_code: u32,
+    /// Linear unit code of the vertical axis (0 if not set)
+    pub vertical_units_code: u16,
+    /// Free-text description of the vertical CRS
+    pub vertical_citation: Option<String>,
+    /// Datum, ellipsoid and projection parameters from the GeoKeys
+    pub projection: ProjectionParameters,
 }
 
 impl GeoInfo {
@@ -417,6 +591,129 @@ impl GeoInfo {
             origin_x: 0.0,
             origin_y: 0.0,
             tie_point: None,
+            model_type: 0,
+            linear_units_code: 0,
+            geog_linear_units_code: 0,
+            angular_units_code: 0,
+            vertical_cs_code: 0,
+            vertical_datum_code: 0,
+            vertical_units_code: 0,
+            vertical_citation: None,
+            projection: ProjectionParameters::default(),
+        }
+    }
+
+    /// Check if the raster is in a geographic (lat/long) CRS
+    ///
+    /// Uses GTModelTypeGeoKey when present and falls back to the CRS codes.
+    pub fn is_geographic(&self) -> bool {
+        match self.model_type {
+            MODEL_TYPE_PROJECTED => false,
+            MODEL_TYPE_GEOGRAPHIC => true,
+            _ => (self.epsg_code == 0 && self.geographic_cs_code > 0)
+                || (4000..5000).contains(&self.epsg_code),
+        }
+    }
+
+    /// Units of the pixel size, e.g. "degrees", "meters" or "US survey feet"
+    ///
+    /// Geographic rasters use GeogAngularUnitsGeoKey (degrees by default),
+    /// projected ones ProjLinearUnitsGeoKey (meters by default).
+    pub fn pixel_size_units(&self) -> String {
+        if self.is_geographic() {
+            return match self.angular_units_code {
+                0 | 9102 => "degrees".to_string(),
+                9101 => "radians".to_string(),
+                9103 => "arc-minutes".to_string(),
+                9104 => "arc-seconds".to_string(),
+                9105 | 9106 => "grads".to_string(),
+                code => get_code_name("angular_unit", code),
+            };
+        }
+
+        match self.linear_units_code {
+            0 | 9001 => "meters".to_string(),
+            9002 => "feet".to_string(),
+            9003 => "US survey feet".to_string(),
+            9036 => "kilometers".to_string(),
+            code => get_code_name("linear_unit", code),
+        }
+    }
+
+    /// Pixel size converted to meters
+    ///
+    /// Angular sizes are converted on the WGS84 ellipsoid at the given
+    /// latitude, since a degree of longitude shrinks towards the poles.
+    ///
+    /// # Arguments
+    /// * `latitude` - Latitude in degrees at which to evaluate angular sizes
+    ///
+    /// # Returns
+    /// (x, y) pixel size in meters, or None for units without a known conversion
+    pub fn pixel_size_meters(&self, latitude: f64) -> Option<(f64, f64)> {
+        if self.is_geographic() {
+            let degrees_per_unit = match self.angular_units_code {
+                0 | 9102 => 1.0,
+                9101 => 180.0 / std::f64::consts::PI,
+                9103 => 1.0 / 60.0,
+                9104 => 1.0 / 3600.0,
+                9105 | 9106 => 0.9,
+                _ => return None,
+            };
+
+            // Meridional and parallel arc lengths of one degree on WGS84
+            let phi = latitude.to_radians();
+            let meters_per_degree_lat = 111_132.954 - 559.822 * (2.0 * phi).cos() + 1.175 * (4.0 * phi).cos();
+            let meters_per_degree_lon = 111_412.84 * phi.cos() - 93.5 * (3.0 * phi).cos();
+
+            return Some((
+                self.pixel_size_x * degrees_per_unit * meters_per_degree_lon,
+                self.pixel_size_y * degrees_per_unit * meters_per_degree_lat,
+            ));
+        }
+
+        let meters_per_unit = match self.linear_units_code {
+            0 | 9001 => 1.0,
+            9002 => 0.3048,
+            9003 => 1200.0 / 3937.0,
+            9036 => 1000.0,
+            _ => return None,
+        };
+        Some((self.pixel_size_x * meters_per_unit, self.pixel_size_y * meters_per_unit))
+    }
+
+    /// Check if a vertical CRS or vertical units are declared
+    pub fn has_vertical_crs(&self) -> bool {
+        self.vertical_cs_code > 0 || self.vertical_units_code > 0 || self.vertical_citation.is_some()
+    }
+
+    /// Describe the vertical CRS, e.g. "VertCS_NAVD88_height (EPSG:5703)"
+    ///
+    /// Returns None if the GeoKeys do not declare a vertical CRS.
+    pub fn vertical_cs_description(&self) -> Option<String> {
+        match (self.vertical_cs_code, &self.vertical_citation) {
+            (0, None) => None,
+            (0, Some(citation)) => Some(citation.clone()),
+            (USER_DEFINED, citation) => Some(format!("User-defined ({})",
+                citation.as_deref().unwrap_or("no citation"))),
+            (code, _) => {
+                let name = get_code_name("vertical_cs", code as u16);
+                if name == code.to_string() {
+                    Some(format!("EPSG:{}", code))
+                } else {
+                    Some(format!("{} (EPSG:{})", name, code))
+                }
+            }
+        }
+    }
+
+    /// Name of the vertical units, e.g. "Linear_Foot"
+    ///
+    /// Returns None if no VerticalUnitsGeoKey is present.
+    pub fn vertical_units_name(&self) -> Option<String> {
+        match self.vertical_units_code {
+            0 => None,
+            code => Some(get_code_name("linear_unit", code)),
         }
     }
 
@@ -444,4 +741,196 @@ impl GeoInfo {
 
         Some((min_x, min_y, max_x, max_y))
     }
-}
\ No newline at end of file
+}
+
+/// Projection parameters carried by the GeoKeys
+///
+/// Codes are 0 when the key is absent and 32767 when user-defined; in the
+/// latter case the explicit values (axes, origins, offsets) describe the
+/// system. Angles are in the file's angular units (normally degrees) and
+/// offsets in its linear units.
+#[derive(Debug, Clone, Default, PartialEq)]
+pub struct ProjectionParameters {
+    /// GTCitationGeoKey
+    pub citation: Option<String>,
+    /// GeogCitationGeoKey
+    pub geog_citation: Option<String>,
+    /// PCSCitationGeoKey
+    pub pcs_citation: Option<String>,
+    /// GeogGeodeticDatumGeoKey
+    pub datum_code: u16,
+    /// GeogPrimeMeridianGeoKey
+    pub prime_meridian_code: u16,
+    /// GeogPrimeMeridianLongGeoKey
+    pub prime_meridian_longitude: Option<f64>,
+    /// GeogEllipsoidGeoKey
+    pub ellipsoid_code: u16,
+    /// GeogSemiMajorAxisGeoKey
+    pub semi_major_axis: Option<f64>,
+    /// GeogSemiMinorAxisGeoKey
+    pub semi_minor_axis: Option<f64>,
+    /// GeogTOWGS84GeoKey: 3 or 7 Helmert parameters to WGS84 (GDAL extension)
+    pub towgs84: Option<Vec<f64>>,
+    /// GeogInvFlatteningGeoKey
+    pub inverse_flattening: Option<f64>,
+    /// ProjCoordTransGeoKey (coordinate transformation method)
+    pub coord_transform_code: u16,
+    /// ProjStdParallel1GeoKey
+    pub standard_parallel_1: Option<f64>,
+    /// ProjStdParallel2GeoKey
+    pub standard_parallel_2: Option<f64>,
+    /// ProjNatOriginLongGeoKey
+    pub natural_origin_longitude: Option<f64>,
+    /// ProjNatOriginLatGeoKey
+    pub natural_origin_latitude: Option<f64>,
+    /// ProjFalseEastingGeoKey
+    pub false_easting: Option<f64>,
+    /// ProjFalseNorthingGeoKey
+    pub false_northing: Option<f64>,
+    /// ProjFalseOriginLongGeoKey
+    pub false_origin_longitude: Option<f64>,
+    /// ProjFalseOriginLatGeoKey
+    pub false_origin_latitude: Option<f64>,
+    /// ProjFalseOriginEastingGeoKey
+    pub false_origin_easting: Option<f64>,
+    /// ProjFalseOriginNorthingGeoKey
+    pub false_origin_northing: Option<f64>,
+    /// ProjCenterLongGeoKey
+    pub center_longitude: Option<f64>,
+    /// ProjCenterLatGeoKey
+    pub center_latitude: Option<f64>,
+    /// ProjCenterEastingGeoKey
+    pub center_easting: Option<f64>,
+    /// ProjCenterNorthingGeoKey
+    pub center_northing: Option<f64>,
+    /// ProjScaleAtNatOriginGeoKey
+    pub scale_at_natural_origin: Option<f64>,
+    /// ProjScaleAtCenterGeoKey
+    pub scale_at_center: Option<f64>,
+    /// ProjAzimuthAngleGeoKey
+    pub azimuth: Option<f64>,
+    /// ProjStraightVertPoleLongGeoKey
+    pub straight_vertical_pole_longitude: Option<f64>,
+}
+
+impl ProjectionParameters {
+    /// Collect projection parameters from parsed GeoKeys
+    ///
+    /// # Arguments
+    /// * `geo_keys` - Entries of the GeoKey directory
+    /// * `double_params` - Values of the GeoDoubleParamsTag
+    /// * `ascii_params` - Text of the GeoAsciiParamsTag
+    ///
+    /// # Returns
+    /// The parameters found; absent keys stay at their defaults
+    pub fn from_geo_keys(geo_keys: &[GeoKeyEntry], double_params: &[f64], ascii_params: &str) -> Self {
+        let mut params = ProjectionParameters::default();
+
+        for key in geo_keys {
+            let short = (key.tiff_tag_location == 0).then_some(key.value_offset);
+            let double = (key.tiff_tag_location == tags::GEO_DOUBLE_PARAMS_TAG)
+                .then(|| double_params.get(key.value_offset as usize).copied())
+                .flatten();
+            let text = (key.tiff_tag_location == tags::GEO_ASCII_PARAMS_TAG)
+                .then(|| {
+                    let start = key.value_offset as usize;
+                    let end = (start + key.count as usize).min(ascii_params.len());
+                    ascii_params.get(start..end)
+                        .map(|t| t.trim_end_matches(['|', '\0']).to_string())
+                })
+                .flatten();
+
+            match key.key_id {
+                geo_keys::CITATION => params.citation = text,
+                geo_keys::GEOG_CITATION => params.geog_citation = text,
+                geo_keys::PCS_CITATION => params.pcs_citation = text,
+                geo_keys::GEOG_GEODETIC_DATUM => params.datum_code = short.unwrap_or(0),
+                geo_keys::GEOG_PRIME_MERIDIAN => params.prime_meridian_code = short.unwrap_or(0),
+                geo_keys::GEOG_PRIME_MERIDIAN_LONG => params.prime_meridian_longitude = double,
+                geo_keys::GEOG_ELLIPSOID => params.ellipsoid_code = short.unwrap_or(0),
+                geo_keys::GEOG_SEMI_MAJOR_AXIS => params.semi_major_axis = double,
+                geo_keys::GEOG_SEMI_MINOR_AXIS => params.semi_minor_axis = double,
+                geo_keys::GEOG_INV_FLATTENING => params.inverse_flattening = double,
+                geo_keys::GEOG_TOWGS84 if key.tiff_tag_location == tags::GEO_DOUBLE_PARAMS_TAG => {
+                    let start = key.value_offset as usize;
+                    params.towgs84 = double_params.get(start..start + key.count as usize).map(|v| v.to_vec());
+                },
+                geo_keys::PROJ_COORD_TRANS => params.coord_transform_code = short.unwrap_or(0),
+                geo_keys::PROJ_STD_PARALLEL_1 => params.standard_parallel_1 = double,
+                geo_keys::PROJ_STD_PARALLEL_2 => params.standard_parallel_2 = double,
+                geo_keys::PROJ_NAT_ORIGIN_LONG => params.natural_origin_longitude = double,
+                geo_keys::PROJ_NAT_ORIGIN_LAT => params.natural_origin_latitude = double,
+                geo_keys::PROJ_FALSE_EASTING => params.false_easting = double,
+                geo_keys::PROJ_FALSE_NORTHING => params.false_northing = double,
+                geo_keys::PROJ_FALSE_ORIGIN_LONG => params.false_origin_longitude = double,
+                geo_keys::PROJ_FALSE_ORIGIN_LAT => params.false_origin_latitude = double,
+                geo_keys::PROJ_FALSE_ORIGIN_EASTING => params.false_origin_easting = double,
+                geo_keys::PROJ_FALSE_ORIGIN_NORTHING => params.false_origin_northing = double,
+                geo_keys::PROJ_CENTER_LONG => params.center_longitude = double,
+                geo_keys::PROJ_CENTER_LAT => params.center_latitude = double,
+                geo_keys::PROJ_CENTER_EASTING => params.center_easting = double,
+                geo_keys::PROJ_CENTER_NORTHING => params.center_northing = double,
+                geo_keys::PROJ_SCALE_AT_NAT_ORIGIN => params.scale_at_natural_origin = double,
+                geo_keys::PROJ_SCALE_AT_CENTER => params.scale_at_center = double,
+                geo_keys::PROJ_AZIMUTH_ANGLE => params.azimuth = double,
+                geo_keys::PROJ_STRAIGHT_VERT_POLE_LONG => params.straight_vertical_pole_longitude = double,
+                _ => {}
+            }
+        }
+
+        params
+    }
+
+    /// Semi-major and semi-minor axes of the ellipsoid in meters
+    ///
+    /// Explicit axis keys win; otherwise the ellipsoid or datum code is
+    /// looked up among the common EPSG ellipsoids.
+    ///
+    /// # Returns
+    /// (a, b), or None if the ellipsoid cannot be determined
+    pub fn ellipsoid_axes(&self) -> Option<(f64, f64)> {
+        if let Some(a) = self.semi_major_axis {
+            if let Some(b) = self.semi_minor_axis {
+                return Some((a, b));
+            }
+            if let Some(inv_f) = self.inverse_flattening {
+                let b = if inv_f == 0.0 { a } else { a * (1.0 - 1.0 / inv_f) };
+                return Some((a, b));
+            }
+        }
+
+        let ellipsoid = match (self.ellipsoid_code, self.datum_code) {
+            (code, _) if code != 0 && code != USER_DEFINED as u16 => code,
+            (_, 6326) => 7030,
+            (_, 6258) | (_, 6269) | (_, 6283) => 7019,
+            (_, 6267) => 7008,
+            (_, 6230) => 7022,
+            (_, 6314) => 7004,
+            (_, 6277) => 7001,
+            _ => return None,
+        };
+        ellipsoid_axes_for_code(ellipsoid)
+    }
+}
+
+/// Axes of common EPSG ellipsoids
+///
+/// # Arguments
+/// * `code` - EPSG ellipsoid code
+///
+/// # Returns
+/// (a, b) in meters, or None for ellipsoids not in the table
+pub fn ellipsoid_axes_for_code(code: u16) -> Option<(f64, f64)> {
+    let (a, inv_f) = match code {
+        7030 => (6_378_137.0, 298.257_223_563),   // WGS 84
+        7019 => (6_378_137.0, 298.257_222_101),   // GRS 1980
+        7008 => (6_378_206.4, 294.978_698_213_9), // Clarke 1866
+        7022 => (6_378_388.0, 297.0),              // International 1924
+        7004 => (6_377_397.155, 299.152_812_8),   // Bessel 1841
+        7001 => (6_377_563.396, 299.324_964_6),   // Airy 1830
+        7024 => (6_378_245.0, 298.3),              // Krassowsky 1940
+        7043 => (6_378_135.0, 298.26),             // WGS 72
+        _ => return None,
+    };
+    Some((a, a * (1.0 - 1.0 / inv_f)))
+}
@@ -23,6 +23,8 @@ pub struct IFD {
     pub number: usize,
     /// Offset to this IFD in the file
     pub offset: u64,
+    /// Child IFDs referenced by the SubIFDs tag, such as masks and overviews
+    pub sub_ifds: Vec<IFD>,
     /// Cached tag values for quick lookup
     tag_map: HashMap<u16, IFDEntry>,
 }
@@ -73,7 +75,7 @@ impl IFDEntry {
         match self.field_type {
             field_types::BYTE | field_types::ASCII | field_types::SBYTE | field_types::UNDEFINED => 1,
             field_types::SHORT | field_types::SSHORT => 2,
-            field_types::LONG | field_types::SLONG | field_types::FLOAT => 4,
+            field_types::LONG | field_types::SLONG | field_types::FLOAT | field_types::IFD => 4,
             field_types::RATIONAL | field_types::SRATIONAL | field_types::DOUBLE => 8,
             field_types::LONG8 | field_types::SLONG8 | field_types::IFD8 => 8,
             _ => {
@@ -140,6 +142,7 @@ impl IFD {
             entries: Vec::new(),
             number,
             offset,
+            sub_ifds: Vec::new(),
             tag_map: HashMap::new(),
         }
     }
@@ -155,6 +158,29 @@ impl IFD {
         self.entries.push(entry);
     }
 
+    /// Replaces the entry of a tag in place, or adds it if the tag is absent
+    ///
+    /// Unlike removing and adding the entry, this keeps the order of the
+    /// entries, and unlike editing `entries` directly it keeps the lookup
+    /// cache current.
+    pub fn replace_entry(&mut self, entry: IFDEntry) {
+        match self.entries.iter_mut().find(|existing| existing.tag == entry.tag) {
+            Some(existing) => {
+                *existing = entry.clone();
+                self.tag_map.insert(entry.tag, entry);
+            },
+            None => self.add_entry(entry),
+        }
+    }
+
+    /// Removes all entries for a tag from this IFD
+    ///
+    /// Returns true if the tag was present.
+    pub fn remove_entry(&mut self, tag: u16) -> bool {
+        self.entries.retain(|entry| entry.tag != tag);
+        self.tag_map.remove(&tag).is_some()
+    }
+
     /// Gets a tag value (value_offset) directly
     ///
     /// This is a convenience method for quickly retrieving the value/offset
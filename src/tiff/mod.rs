@@ -7,19 +7,24 @@ pub mod errors;
 pub mod ifd;
 pub(crate) mod types;
 pub mod reader;
+pub mod tag_value;
 mod tests;
 pub mod geotags;
 pub mod builder;
-mod builders;
+pub(crate) mod builders;
 pub(crate) mod constants;
 pub mod geo_key_parser;
+pub mod proj_definition;
 pub(crate) mod validation;
+pub mod compliance;
 pub(crate) mod colormap;
+pub(crate) mod dataset_cache;
 
 pub use crate::io::byte_order::{BigEndianHandler, ByteOrder, ByteOrderHandler, LittleEndianHandler};
 pub use errors::{TiffError, TiffResult};
 pub use ifd::{IFD, IFDEntry};
 pub use reader::TiffReader;
+pub use tag_value::{TagType, TagValue};
 pub use types::TIFF;
 pub use geotags::{GeoKeyEntry, get_key_name, get_projected_cs_description, get_tag_name, is_geotiff_tag};
 pub use builder::TiffBuilder;
@@ -0,0 +1,1250 @@
+//! PROJ definitions built from GeoTIFF georeferencing
+//!
+//! Turns a parsed `GeoInfo` into a projection definition that PROJ-based
+//! tooling understands, either as a classic PROJ string (`+proj=...`) or as
+//! PROJJSON. Well-known EPSG systems (WGS84, Web Mercator, UTM, ...) are
+//! expanded from built-in parameters; user-defined systems are assembled
+//! from the projection GeoKeys.
+
+use std::collections::HashMap;
+
+use log::warn;
+
+use crate::tiff::errors::{TiffError, TiffResult};
+use crate::tiff::geo_key_parser::{GeoInfo, ProjectionParameters, ellipsoid_axes_for_code};
+use crate::utils::sidecar_utils;
+
+/// GeoKey value marking a user-defined code
+pub const USER_DEFINED: u32 = 32767;
+
+/// Conversion method of the one-parallel Lambert conic
+const LCC_1SP: &str = "Lambert Conic Conformal (1SP)";
+
+/// A single projection parameter
+#[derive(Debug, Clone, PartialEq)]
+pub struct ProjParameter {
+    /// PROJ string key, e.g. "lat_0"
+    pub key: &'static str,
+    /// EPSG parameter name used in PROJJSON
+    pub name: &'static str,
+    /// Value in degrees, meters or unitless
+    pub value: f64,
+    /// Unit kind of the value
+    pub unit: ParameterUnit,
+}
+
+/// Unit kind of a projection parameter
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum ParameterUnit {
+    /// Angle in degrees
+    Degree,
+    /// Length in the CRS linear unit
+    Linear,
+    /// Scale factor
+    Unity,
+}
+
+/// Geodetic datum of a definition
+#[derive(Debug, Clone, PartialEq)]
+pub struct Datum {
+    /// PROJ datum keyword, if PROJ knows the datum by name
+    pub proj_name: Option<&'static str>,
+    /// Datum name for PROJJSON
+    pub name: String,
+    /// Ellipsoid name for PROJJSON
+    pub ellipsoid_name: String,
+    /// Semi-major axis in meters
+    pub semi_major_axis: f64,
+    /// Semi-minor axis in meters
+    pub semi_minor_axis: f64,
+    /// Helmert parameters to WGS84 (dx, dy, dz in m, rx, ry, rz in arc
+    /// seconds, scale in ppm), None if unknown
+    pub towgs84: Option<[f64; 7]>,
+}
+
+impl Datum {
+    /// Datum for a well-known EPSG datum or ellipsoid code
+    fn from_code(code: u16) -> Option<Self> {
+        const NONE: [f64; 7] = [0.0; 7];
+        // NAD27 and OSGB36 use the EPSG average (CONUS / Great Britain) shifts
+        let (proj_name, name, ellipsoid_name, ellipsoid, towgs84) = match code {
+            6326 | 7030 => (Some("WGS84"), "World Geodetic System 1984", "WGS 84", 7030, Some(NONE)),
+            6269 => (Some("NAD83"), "North American Datum 1983", "GRS 1980", 7019, Some(NONE)),
+            6267 => (Some("NAD27"), "North American Datum 1927", "Clarke 1866", 7008,
+                     Some([-8.0, 160.0, 176.0, 0.0, 0.0, 0.0, 0.0])),
+            6258 => (None, "European Terrestrial Reference System 1989", "GRS 1980", 7019, Some(NONE)),
+            6277 | 7001 => (None, "Ordnance Survey of Great Britain 1936", "Airy 1830", 7001,
+                            Some([446.448, -125.157, 542.06, 0.15, 0.247, 0.842, -20.489])),
+            7019 => (None, "Unknown based on GRS 1980 ellipsoid", "GRS 1980", 7019, None),
+            _ => return None,
+        };
+        let (a, b) = ellipsoid_axes_for_code(ellipsoid)?;
+        Some(Datum {
+            proj_name,
+            name: name.to_string(),
+            ellipsoid_name: ellipsoid_name.to_string(),
+            semi_major_axis: a,
+            semi_minor_axis: b,
+            towgs84,
+        })
+    }
+
+    /// Datum for an ellipsoid code, falling back to an unnamed datum
+    fn for_ellipsoid(code: u16) -> Option<Self> {
+        if let Some(datum) = Datum::from_code(code) {
+            return Some(datum);
+        }
+        let (a, b) = ellipsoid_axes_for_code(code)?;
+        let mut datum = Datum::from_axes("Unknown", a, b);
+        if let Some(&(_, _, name)) = ELLIPSOIDS.iter().find(|(_, c, _)| *c == code) {
+            datum.ellipsoid_name = name.to_string();
+        }
+        Some(datum)
+    }
+
+    /// Unnamed datum on an ellipsoid given by its axes
+    fn from_axes(name: &str, semi_major_axis: f64, semi_minor_axis: f64) -> Self {
+        Datum {
+            proj_name: None,
+            name: name.to_string(),
+            ellipsoid_name: "Unknown".to_string(),
+            semi_major_axis,
+            semi_minor_axis,
+            towgs84: None,
+        }
+    }
+
+    /// Datum built from explicit GeoKey parameters
+    fn from_parameters(params: &ProjectionParameters) -> Option<Self> {
+        let mut datum = match Datum::from_code(params.datum_code)
+            .or_else(|| Datum::from_code(params.ellipsoid_code)) {
+            Some(datum) => datum,
+            None => {
+                let (a, b) = params.ellipsoid_axes()?;
+                let name = params.geog_citation.clone().unwrap_or_else(|| "Unknown".to_string());
+                Datum::from_axes(&name, a, b)
+            }
+        };
+        if let Some(towgs84) = params.towgs84.as_deref().and_then(helmert_parameters) {
+            datum.towgs84 = Some(towgs84);
+        }
+        Some(datum)
+    }
+
+    /// Inverse flattening (0 for a sphere)
+    fn inverse_flattening(&self) -> f64 {
+        if self.semi_major_axis == self.semi_minor_axis {
+            0.0
+        } else {
+            self.semi_major_axis / (self.semi_major_axis - self.semi_minor_axis)
+        }
+    }
+
+    /// PROJ string fragment describing the datum or ellipsoid
+    fn proj_fragment(&self) -> String {
+        if let Some(name) = self.proj_name {
+            return format!("+datum={}", name);
+        }
+        let known = ELLIPSOIDS.iter().find(|(_, code, _)| {
+            ellipsoid_axes_for_code(*code).is_some_and(|(a, b)|
+                (a - self.semi_major_axis).abs() < 1e-6 && (b - self.semi_minor_axis).abs() < 1e-6)
+        });
+        let ellipsoid = match known {
+            Some((proj_name, _, _)) => format!("+ellps={}", proj_name),
+            None => format!("+a={} +b={}", self.semi_major_axis, self.semi_minor_axis),
+        };
+        match self.towgs84 {
+            Some(towgs84) => {
+                let values: Vec<String> = towgs84.iter().map(|v| v.to_string()).collect();
+                format!("{} +towgs84={}", ellipsoid, values.join(","))
+            },
+            None => ellipsoid,
+        }
+    }
+}
+
+/// A complete projection definition
+#[derive(Debug, Clone, PartialEq)]
+pub struct ProjDefinition {
+    /// CRS name
+    pub name: String,
+    /// EPSG code, if the CRS is registered
+    pub epsg: Option<u32>,
+    /// PROJ projection name ("longlat" for geographic systems)
+    pub proj: &'static str,
+    /// PROJJSON conversion method name (empty for geographic systems)
+    pub method_name: &'static str,
+    /// Projection parameters
+    pub parameters: Vec<ProjParameter>,
+    /// Geodetic datum
+    pub datum: Datum,
+    /// PROJ unit keyword ("m", "ft", "us-ft")
+    pub units: &'static str,
+    /// Additional PROJ flags, e.g. "+south" or "+nadgrids=@null"
+    pub flags: Vec<&'static str>,
+}
+
+impl ProjDefinition {
+    /// Build a definition from GeoTIFF georeferencing
+    ///
+    /// # Arguments
+    /// * `geo_info` - Geospatial information parsed from the GeoKeys
+    ///
+    /// # Returns
+    /// The definition, or None if the system is neither a known EPSG code
+    /// nor fully described by its GeoKeys
+    pub fn from_geo_info(geo_info: &GeoInfo) -> Option<Self> {
+        if geo_info.epsg_code != 0 && geo_info.epsg_code != USER_DEFINED {
+            if let Some(definition) = Self::from_epsg(geo_info.epsg_code) {
+                return Some(definition);
+            }
+        }
+        if geo_info.epsg_code == 0 && geo_info.geographic_cs_code != 0
+            && geo_info.geographic_cs_code != USER_DEFINED {
+            if let Some(definition) = Self::from_epsg(geo_info.geographic_cs_code) {
+                return Some(definition);
+            }
+        }
+        Self::from_geo_keys(geo_info)
+    }
+
+    /// Definition of a well-known EPSG system
+    ///
+    /// # Arguments
+    /// * `code` - EPSG code
+    ///
+    /// # Returns
+    /// The definition, or None for codes without built-in parameters
+    pub fn from_epsg(code: u32) -> Option<Self> {
+        let geographic = |name: &str, datum: u16| Some(ProjDefinition {
+            name: name.to_string(),
+            epsg: Some(code),
+            proj: "longlat",
+            method_name: "",
+            parameters: Vec::new(),
+            datum: Datum::from_code(datum)?,
+            units: "m",
+            flags: Vec::new(),
+        });
+
+        match code {
+            4326 => geographic("WGS 84", 6326),
+            4269 => geographic("NAD83", 6269),
+            4267 => geographic("NAD27", 6267),
+            4258 => geographic("ETRS89", 6258),
+            3857 | 900913 => {
+                let mut definition = Self::projected("WGS 84 / Pseudo-Mercator", code, "merc",
+                    "Popular Visualisation Pseudo Mercator", 6326, vec![
+                        param("lat_0", "Latitude of natural origin", 0.0, ParameterUnit::Degree),
+                        param("lon_0", "Longitude of natural origin", 0.0, ParameterUnit::Degree),
+                        param("x_0", "False easting", 0.0, ParameterUnit::Linear),
+                        param("y_0", "False northing", 0.0, ParameterUnit::Linear),
+                    ])?;
+                // PROJ models Web Mercator as spherical Mercator on the WGS84 radius
+                definition.flags = vec!["+nadgrids=@null", "+wktext"];
+                Some(definition)
+            },
+            32601..=32660 => Self::utm("WGS 84", code, code - 32600, false, 6326),
+            32701..=32760 => Self::utm("WGS 84", code, code - 32700, true, 6326),
+            26901..=26923 => Self::utm("NAD83", code, code - 26900, false, 6269),
+            26701..=26722 => Self::utm("NAD27", code, code - 26700, false, 6267),
+            25828..=25838 => Self::utm("ETRS89", code, code - 25800, false, 6258),
+            3035 => Self::projected("ETRS89-extended / LAEA Europe", code, "laea",
+                "Lambert Azimuthal Equal Area", 6258, vec![
+                    param("lat_0", "Latitude of natural origin", 52.0, ParameterUnit::Degree),
+                    param("lon_0", "Longitude of natural origin", 10.0, ParameterUnit::Degree),
+                    param("x_0", "False easting", 4_321_000.0, ParameterUnit::Linear),
+                    param("y_0", "False northing", 3_210_000.0, ParameterUnit::Linear),
+                ]),
+            5070 => Self::projected("NAD83 / Conus Albers", code, "aea",
+                "Albers Equal Area", 6269, vec![
+                    param("lat_0", "Latitude of false origin", 23.0, ParameterUnit::Degree),
+                    param("lon_0", "Longitude of false origin", -96.0, ParameterUnit::Degree),
+                    param("lat_1", "Latitude of 1st standard parallel", 29.5, ParameterUnit::Degree),
+                    param("lat_2", "Latitude of 2nd standard parallel", 45.5, ParameterUnit::Degree),
+                    param("x_0", "Easting at false origin", 0.0, ParameterUnit::Linear),
+                    param("y_0", "Northing at false origin", 0.0, ParameterUnit::Linear),
+                ]),
+            27700 => Self::projected("OSGB36 / British National Grid", code, "tmerc",
+                "Transverse Mercator", 6277, vec![
+                    param("lat_0", "Latitude of natural origin", 49.0, ParameterUnit::Degree),
+                    param("lon_0", "Longitude of natural origin", -2.0, ParameterUnit::Degree),
+                    param("k", "Scale factor at natural origin", 0.999_601_271_7, ParameterUnit::Unity),
+                    param("x_0", "False easting", 400_000.0, ParameterUnit::Linear),
+                    param("y_0", "False northing", -100_000.0, ParameterUnit::Linear),
+                ]),
+            _ => None,
+        }
+    }
+
+    /// UTM zone on the given datum
+    fn utm(datum_name: &str, code: u32, zone: u32, south: bool, datum: u16) -> Option<Self> {
+        let name = format!("{} / UTM zone {}{}", datum_name, zone, if south { "S" } else { "N" });
+        Self::projected(&name, code, "tmerc", "Transverse Mercator", datum, vec![
+            param("lat_0", "Latitude of natural origin", 0.0, ParameterUnit::Degree),
+            param("lon_0", "Longitude of natural origin", zone as f64 * 6.0 - 183.0, ParameterUnit::Degree),
+            param("k", "Scale factor at natural origin", 0.9996, ParameterUnit::Unity),
+            param("x_0", "False easting", 500_000.0, ParameterUnit::Linear),
+            param("y_0", "False northing", if south { 10_000_000.0 } else { 0.0 }, ParameterUnit::Linear),
+        ])
+    }
+
+    /// Projected system in meters on a well-known datum
+    fn projected(name: &str, code: u32, proj: &'static str, method_name: &'static str,
+                 datum: u16, parameters: Vec<ProjParameter>) -> Option<Self> {
+        Some(ProjDefinition {
+            name: name.to_string(),
+            epsg: Some(code),
+            proj,
+            method_name,
+            parameters,
+            datum: Datum::from_code(datum)?,
+            units: "m",
+            flags: Vec::new(),
+        })
+    }
+
+    /// Definition assembled from user-defined projection GeoKeys
+    fn from_geo_keys(geo_info: &GeoInfo) -> Option<Self> {
+        let p = &geo_info.projection;
+        let datum = Datum::from_parameters(p)
+            .or_else(|| Datum::from_code(geo_info.geographic_cs_code.checked_sub(4000)
+                .map(|c| c as u16 + 6000).unwrap_or(0)))?;
+        let units = match geo_info.linear_units_code {
+            9002 => "ft",
+            9003 => "us-ft",
+            _ => "m",
+        };
+        let name = p.pcs_citation.clone()
+            .or_else(|| p.citation.clone())
+            .unwrap_or_else(|| "Unknown".to_string());
+
+        if geo_info.is_geographic() {
+            return Some(ProjDefinition {
+                name: p.geog_citation.clone().unwrap_or(name),
+                epsg: None,
+                proj: "longlat",
+                method_name: "",
+                parameters: Vec::new(),
+                datum,
+                units,
+                flags: Vec::new(),
+            });
+        }
+
+        let d = |value: Option<f64>| value.unwrap_or(0.0);
+        let natural_origin = |p: &ProjectionParameters| vec![
+            param("lat_0", "Latitude of natural origin", d(p.natural_origin_latitude), ParameterUnit::Degree),
+            param("lon_0", "Longitude of natural origin", d(p.natural_origin_longitude), ParameterUnit::Degree),
+        ];
+        let false_en = |p: &ProjectionParameters| vec![
+            param("x_0", "False easting", d(p.false_easting), ParameterUnit::Linear),
+            param("y_0", "False northing", d(p.false_northing), ParameterUnit::Linear),
+        ];
+        let false_origin = |p: &ProjectionParameters| vec![
+            param("lat_0", "Latitude of false origin",
+                  d(p.false_origin_latitude.or(p.natural_origin_latitude)), ParameterUnit::Degree),
+            param("lon_0", "Longitude of false origin",
+                  d(p.false_origin_longitude.or(p.natural_origin_longitude)), ParameterUnit::Degree),
+            param("lat_1", "Latitude of 1st standard parallel", d(p.standard_parallel_1), ParameterUnit::Degree),
+            param("lat_2", "Latitude of 2nd standard parallel", d(p.standard_parallel_2), ParameterUnit::Degree),
+            param("x_0", "Easting at false origin",
+                  d(p.false_origin_easting.or(p.false_easting)), ParameterUnit::Linear),
+            param("y_0", "Northing at false origin",
+                  d(p.false_origin_northing.or(p.false_northing)), ParameterUnit::Linear),
+        ];
+        let center = |p: &ProjectionParameters| vec![
+            param("lat_0", "Latitude of natural origin",
+                  d(p.center_latitude.or(p.natural_origin_latitude)), ParameterUnit::Degree),
+            param("lon_0", "Longitude of natural origin",
+                  d(p.center_longitude.or(p.natural_origin_longitude)), ParameterUnit::Degree),
+        ];
+        let scale = |p: &ProjectionParameters| param("k", "Scale factor at natural origin",
+            p.scale_at_natural_origin.or(p.scale_at_center).unwrap_or(1.0), ParameterUnit::Unity);
+
+        let (proj, method_name, parameters) = match p.coord_transform_code {
+            1 => ("tmerc", "Transverse Mercator",
+                  [natural_origin(p), vec![scale(p)], false_en(p)].concat()),
+            7 => ("merc", "Mercator (variant A)",
+                  [natural_origin(p), vec![scale(p)], false_en(p)].concat()),
+            8 => ("lcc", "Lambert Conic Conformal (2SP)", false_origin(p)),
+            9 => ("lcc", LCC_1SP, [natural_origin(p), vec![scale(p)], false_en(p)].concat()),
+            10 => ("laea", "Lambert Azimuthal Equal Area", [center(p), false_en(p)].concat()),
+            11 => ("aea", "Albers Equal Area", false_origin(p)),
+            12 => ("aeqd", "Azimuthal Equidistant", [center(p), false_en(p)].concat()),
+            14 => ("stere", "Stereographic", [center(p), vec![scale(p)], false_en(p)].concat()),
+            15 => {
+                let lat_ts = d(p.natural_origin_latitude);
+                let mut parameters = vec![
+                    param("lat_0", "Latitude of natural origin",
+                          if lat_ts < 0.0 { -90.0 } else { 90.0 }, ParameterUnit::Degree),
+                    param("lon_0", "Longitude of origin",
+                          d(p.straight_vertical_pole_longitude.or(p.natural_origin_longitude)), ParameterUnit::Degree),
+                    scale(p),
+                ];
+                parameters.extend(false_en(p));
+                ("stere", "Polar Stereographic (variant A)", parameters)
+            },
+            16 => ("sterea", "Oblique Stereographic", [natural_origin(p), vec![scale(p)], false_en(p)].concat()),
+            17 => ("eqc", "Equidistant Cylindrical",
+                   [vec![param("lat_ts", "Latitude of 1st standard parallel",
+                               d(p.standard_parallel_1), ParameterUnit::Degree)],
+                    center(p), false_en(p)].concat()),
+            18 => ("cass", "Cassini-Soldner", [natural_origin(p), false_en(p)].concat()),
+            24 => ("sinu", "Sinusoidal", [vec![center(p).remove(1)], false_en(p)].concat()),
+            _ => return None,
+        };
+
+        Some(ProjDefinition {
+            name,
+            epsg: None,
+            proj,
+            method_name,
+            parameters,
+            datum,
+            units,
+            flags: Vec::new(),
+        })
+    }
+
+    /// Whether the definition describes a geographic (lat/long) system
+    pub fn is_geographic(&self) -> bool {
+        self.proj == "longlat"
+    }
+
+    /// Render the definition as a PROJ string
+    ///
+    /// # Returns
+    /// A string such as "+proj=tmerc +lat_0=0 ... +datum=WGS84 +units=m +no_defs"
+    pub fn to_proj_string(&self) -> String {
+        let mut parts = vec![format!("+proj={}", self.proj)];
+        for p in &self.parameters {
+            // PROJ takes false easting/northing in meters whatever the units
+            let value = match p.unit {
+                ParameterUnit::Linear => p.value * self.meters_per_unit(),
+                _ => p.value,
+            };
+            parts.push(format!("+{}={}", p.key, value));
+        }
+        // PROJ expresses the one-parallel Lambert conic through lat_1
+        if self.method_name == LCC_1SP {
+            parts.push(format!("+lat_1={}", self.parameter("lat_0").unwrap_or(0.0)));
+        }
+
+        if self.proj == "merc" && self.flags.contains(&"+nadgrids=@null") {
+            parts.push(format!("+a={0} +b={0}", self.datum.semi_major_axis));
+        } else {
+            parts.push(self.datum.proj_fragment());
+        }
+
+        if !self.is_geographic() {
+            parts.push(format!("+units={}", self.units));
+        }
+        parts.extend(self.flags.iter().map(|f| f.to_string()));
+        parts.push("+no_defs".to_string());
+        parts.join(" ")
+    }
+
+    /// Render the definition as PROJJSON
+    ///
+    /// # Returns
+    /// A PROJJSON document (schema v0.7) for a GeographicCRS or ProjectedCRS
+    pub fn to_projjson(&self) -> String {
+        let geographic = format!(
+            "{{\"type\":\"GeographicCRS\",\"name\":{},\"datum\":{{\"type\":\"GeodeticReferenceFrame\",\
+             \"name\":{},\"ellipsoid\":{{\"name\":{},\"semi_major_axis\":{},\"inverse_flattening\":{}}}}},\
+             \"coordinate_system\":{{\"subtype\":\"ellipsoidal\",\"axis\":[\
+             {{\"name\":\"Geodetic latitude\",\"abbreviation\":\"Lat\",\"direction\":\"north\",\"unit\":\"degree\"}},\
+             {{\"name\":\"Geodetic longitude\",\"abbreviation\":\"Lon\",\"direction\":\"east\",\"unit\":\"degree\"}}]}}}}",
+            json_string(if self.is_geographic() { &self.name } else { &self.datum.name }),
+            json_string(&self.datum.name),
+            json_string(&self.datum.ellipsoid_name),
+            self.datum.semi_major_axis,
+            self.datum.inverse_flattening());
+
+        let id = self.epsg
+            .map(|code| format!(",\"id\":{{\"authority\":\"EPSG\",\"code\":{}}}", code))
+            .unwrap_or_default();
+
+        if self.is_geographic() {
+            return format!("{{\"$schema\":\"https://proj.org/schemas/v0.7/projjson.schema.json\",{}{}}}",
+                           &geographic[1..geographic.len() - 1], id);
+        }
+
+        let unit = match self.units {
+            "ft" => "{\"type\":\"LinearUnit\",\"name\":\"foot\",\"conversion_factor\":0.3048}".to_string(),
+            "us-ft" => "{\"type\":\"LinearUnit\",\"name\":\"US survey foot\",\"conversion_factor\":0.304800609601219}".to_string(),
+            _ => "\"metre\"".to_string(),
+        };
+        let parameters: Vec<String> = self.parameters.iter()
+            .map(|p| format!("{{\"name\":{},\"value\":{},\"unit\":{}}}", json_string(p.name), p.value,
+                             match p.unit {
+                                 ParameterUnit::Degree => "\"degree\"".to_string(),
+                                 ParameterUnit::Linear => unit.clone(),
+                                 ParameterUnit::Unity => "\"unity\"".to_string(),
+                             }))
+            .collect();
+
+        format!(
+            "{{\"$schema\":\"https://proj.org/schemas/v0.7/projjson.schema.json\",\"type\":\"ProjectedCRS\",\
+             \"name\":{},\"base_crs\":{},\"conversion\":{{\"name\":{},\"method\":{{\"name\":{}}},\"parameters\":[{}]}},\
+             \"coordinate_system\":{{\"subtype\":\"Cartesian\",\"axis\":[\
+             {{\"name\":\"Easting\",\"abbreviation\":\"E\",\"direction\":\"east\",\"unit\":{}}},\
+             {{\"name\":\"Northing\",\"abbreviation\":\"N\",\"direction\":\"north\",\"unit\":{}}}]}}{}}}",
+            json_string(&self.name),
+            geographic,
+            json_string(&format!("{} conversion", self.method_name)),
+            json_string(self.method_name),
+            parameters.join(","),
+            unit, unit,
+            id)
+    }
+
+    /// Parse a PROJ string such as "+proj=utm +zone=33 +datum=WGS84"
+    ///
+    /// # Arguments
+    /// * `definition` - PROJ.4 style definition
+    ///
+    /// # Returns
+    /// The definition, or an error for unsupported projections, datums or units
+    pub fn from_proj_string(definition: &str) -> TiffResult<Self> {
+        let mut values: HashMap<String, String> = HashMap::new();
+        let mut switches = Vec::new();
+        for token in definition.split_whitespace() {
+            let token = token.trim_start_matches('+');
+            match token.split_once('=') {
+                Some((key, value)) => { values.insert(key.to_lowercase(), value.to_string()); },
+                None => switches.push(token.to_lowercase()),
+            }
+        }
+
+        let number = |key: &str| -> TiffResult<Option<f64>> {
+            values.get(key)
+                .map(|v| v.parse::<f64>().map_err(|_| TiffError::GenericError(
+                    format!("Invalid value '{}' for +{} in PROJ string", v, key))))
+                .transpose()
+        };
+
+        let proj = values.get("proj")
+            .ok_or_else(|| TiffError::GenericError("PROJ string has no +proj= entry".to_string()))?
+            .to_lowercase();
+
+        let datum = match (values.get("datum"), values.get("ellps")) {
+            (Some(name), _) => datum_code_for_name(name).and_then(Datum::from_code)
+                .ok_or_else(|| TiffError::GenericError(format!("Unsupported PROJ datum '{}'", name)))?,
+            (None, Some(name)) => ELLIPSOIDS.iter()
+                .find(|(proj_name, _, _)| proj_name.eq_ignore_ascii_case(name))
+                .and_then(|&(_, code, _)| Datum::for_ellipsoid(code))
+                .ok_or_else(|| TiffError::GenericError(format!("Unsupported PROJ ellipsoid '{}'", name)))?,
+            (None, None) => match number("a")?.or(number("r")?) {
+                Some(a) => {
+                    let b = match (number("b")?, number("rf")?, number("f")?) {
+                        (Some(b), _, _) => b,
+                        (None, Some(rf), _) if rf != 0.0 => a * (1.0 - 1.0 / rf),
+                        (None, None, Some(f)) => a * (1.0 - f),
+                        _ => a,
+                    };
+                    Datum::from_axes("Unknown", a, b)
+                },
+                // PROJ falls back to GRS80 when no ellipsoid is given
+                None => Datum::for_ellipsoid(7019)
+                    .ok_or_else(|| TiffError::GenericError("GRS80 ellipsoid unavailable".to_string()))?,
+            },
+        };
+
+        let units = match (values.get("units").map(|u| u.to_lowercase()), number("to_meter")?) {
+            (Some(unit), _) => match unit.as_str() {
+                "m" => "m",
+                "ft" => "ft",
+                "us-ft" => "us-ft",
+                other => return Err(TiffError::GenericError(format!("Unsupported PROJ units '{}'", other))),
+            },
+            (None, Some(factor)) => units_for_factor(factor)?,
+            (None, None) => "m",
+        };
+
+        let mut datum = datum;
+        if let Some(text) = values.get("towgs84") {
+            let parsed = text.split(',')
+                .map(|v| v.trim().parse::<f64>())
+                .collect::<Result<Vec<f64>, _>>()
+                .ok()
+                .and_then(|v| helmert_parameters(&v))
+                .ok_or_else(|| TiffError::GenericError(format!("Invalid +towgs84={}", text)))?;
+            datum.towgs84 = Some(parsed);
+        }
+
+        let mut flags = Vec::new();
+        if values.get("nadgrids").map(|v| v.as_str()) == Some("@null") {
+            flags.push("+nadgrids=@null");
+        }
+        if switches.iter().any(|s| s == "wktext") {
+            flags.push("+wktext");
+        }
+
+        let name = values.get("title").cloned().unwrap_or_else(|| "Unknown".to_string());
+        match proj.as_str() {
+            "longlat" | "latlong" | "lonlat" | "latlon" => Ok(Self::geographic_from_datum(name, datum)),
+            "utm" => {
+                let zone = number("zone")?
+                    .filter(|z| z.fract() == 0.0 && (1.0..=60.0).contains(z))
+                    .ok_or_else(|| TiffError::GenericError("+proj=utm needs a +zone between 1 and 60".to_string()))?;
+                let south = switches.iter().any(|s| s == "south");
+                let parameters = HashMap::from([
+                    ("lon_0".to_string(), zone * 6.0 - 183.0),
+                    ("k".to_string(), 0.9996),
+                    ("x_0".to_string(), 500_000.0),
+                    ("y_0".to_string(), if south { 10_000_000.0 } else { 0.0 }),
+                ]);
+                let mut definition = Self::projected_from_values(name, "tmerc", &parameters, datum, units, flags)?;
+                definition.false_offsets_from_meters();
+                Ok(definition)
+            },
+            _ => {
+                let parameters = PARAMETER_KEYS.iter()
+                    .filter_map(|&key| number(key).transpose().map(|v| v.map(|v| (key.to_string(), v))))
+                    .collect::<TiffResult<HashMap<String, f64>>>()?;
+                let mut definition = Self::projected_from_values(name, &proj, &parameters, datum, units, flags)?;
+                definition.false_offsets_from_meters();
+                Ok(definition)
+            }
+        }
+    }
+
+    /// Parse a WKT (WKT1 or WKT2) CRS definition
+    ///
+    /// GDAL-style `EXTENSION["PROJ4", ...]` blocks are honoured, so Web
+    /// Mercator exported by GDAL comes back as the spherical definition.
+    ///
+    /// # Arguments
+    /// * `wkt` - WKT definition of a geographic or projected CRS
+    ///
+    /// # Returns
+    /// The definition, or an error for malformed WKT or unsupported methods
+    pub fn from_wkt(wkt: &str) -> TiffResult<Self> {
+        let root = WktNode::parse(wkt)?;
+        let keyword = root.keyword.to_uppercase();
+        let geographic = matches!(keyword.as_str(),
+            "GEOGCS" | "GEOGCRS" | "GEODCRS" | "GEOGRAPHICCRS" | "GEODETICCRS");
+        if !geographic && !matches!(keyword.as_str(), "PROJCS" | "PROJCRS" | "PROJECTEDCRS") {
+            return Err(TiffError::GenericError(format!("Unsupported WKT element {}", root.keyword)));
+        }
+
+        let name = root.text(0).unwrap_or("Unknown").to_string();
+        let epsg = sidecar_utils::epsg_from_wkt(wkt);
+
+        let proj4_extension = root.find_all("EXTENSION").into_iter()
+            .find(|node| node.text(0).is_some_and(|t| t.eq_ignore_ascii_case("PROJ4")))
+            .and_then(|node| node.text(1));
+        if let Some(proj4) = proj4_extension {
+            let mut definition = Self::from_proj_string(proj4)?;
+            definition.name = name;
+            definition.epsg = epsg;
+            return Ok(definition);
+        }
+
+        let datum_name = root.find(&["DATUM", "GEODETICDATUM", "TRF"]).and_then(|node| node.text(0));
+        let datum = match datum_name.and_then(datum_code_for_name).and_then(Datum::from_code) {
+            Some(datum) => datum,
+            None => {
+                let ellipsoid = root.find(&["SPHEROID", "ELLIPSOID"])
+                    .ok_or_else(|| TiffError::GenericError("WKT names no ellipsoid".to_string()))?;
+                let a = ellipsoid.number(1)
+                    .ok_or_else(|| TiffError::GenericError("WKT ellipsoid has no semi-major axis".to_string()))?;
+                let inv_f = ellipsoid.number(2).unwrap_or(0.0);
+                let mut datum = Datum::from_axes(datum_name.unwrap_or("Unknown"), a,
+                                                 if inv_f == 0.0 { a } else { a * (1.0 - 1.0 / inv_f) });
+                datum.ellipsoid_name = ellipsoid.text(0).unwrap_or("Unknown").to_string();
+                datum
+            }
+        };
+        let mut datum = datum;
+        if let Some(node) = root.find(&["TOWGS84"]) {
+            let values: Vec<f64> = (0..node.values.len()).filter_map(|i| node.number(i)).collect();
+            datum.towgs84 = helmert_parameters(&values);
+        }
+
+        if geographic {
+            let mut definition = Self::geographic_from_datum(name, datum);
+            definition.epsg = epsg;
+            return Ok(definition);
+        }
+
+        let unit_factor = root.child(&["UNIT", "LENGTHUNIT"])
+            .or_else(|| root.child(&["AXIS"]).and_then(|axis| axis.child(&["LENGTHUNIT", "UNIT"])))
+            .and_then(|unit| unit.number(1))
+            .unwrap_or(1.0);
+        let units = units_for_factor(unit_factor)?;
+
+        let method = root.find(&["PROJECTION", "METHOD"]).and_then(|node| node.text(0))
+            .ok_or_else(|| TiffError::GenericError("WKT has no projection method".to_string()))?;
+        let normalized_method = normalize_name(method);
+        let (proj, _) = METHOD_NAMES.iter()
+            .find(|(_, names)| names.contains(&normalized_method.as_str()))
+            .ok_or_else(|| TiffError::GenericError(format!("Unsupported projection method '{}'", method)))?;
+
+        let mut parameters = HashMap::new();
+        for node in root.find_all("PARAMETER") {
+            let (Some(param_name), Some(value)) = (node.text(0), node.number(1)) else { continue };
+            let normalized = normalize_name(param_name);
+            let Some((key, _)) = PARAMETER_NAMES.iter().find(|(_, names)| names.contains(&normalized.as_str())) else {
+                warn!("Ignoring unsupported WKT parameter '{}'", param_name);
+                continue;
+            };
+            // Mercator and plate carrée carry their standard parallel as lat_ts
+            let key = match (*key, *proj) {
+                ("lat_1", "merc") | ("lat_1", "eqc") => "lat_ts",
+                (key, _) => key,
+            };
+            parameters.insert(key.to_string(), value);
+        }
+
+        let mut flags = Vec::new();
+        if normalized_method == "popularvisualisationpseudomercator" {
+            flags = vec!["+nadgrids=@null", "+wktext"];
+        }
+
+        let mut definition = Self::projected_from_values(name, proj, &parameters, datum, units, flags)?;
+        definition.epsg = epsg;
+        Ok(definition)
+    }
+
+    /// Geographic definition on a datum
+    fn geographic_from_datum(name: String, datum: Datum) -> Self {
+        ProjDefinition {
+            name,
+            epsg: None,
+            proj: "longlat",
+            method_name: "",
+            parameters: Vec::new(),
+            datum,
+            units: "m",
+            flags: Vec::new(),
+        }
+    }
+
+    /// Projected definition from PROJ-keyed parameter values
+    ///
+    /// Missing parameters take PROJ's defaults (0, or 1 for the scale factor).
+    fn projected_from_values(name: String, proj: &str, values: &HashMap<String, f64>, datum: Datum,
+                             units: &'static str, flags: Vec<&'static str>) -> TiffResult<Self> {
+        let get = |key: &str| values.get(key).copied();
+        let (proj, method_name, layout) = parameter_layout(proj, &get)
+            .ok_or_else(|| TiffError::GenericError(format!("Unsupported projection '{}'", proj)))?;
+
+        let parameters = layout.iter()
+            .map(|&(key, name, unit)| {
+                let value = match key {
+                    "k" => get("k").or(get("k_0")).unwrap_or(1.0),
+                    "lat_0" if method_name == LCC_1SP => get("lat_0").or(get("lat_1")).unwrap_or(0.0),
+                    _ => get(key).unwrap_or(0.0),
+                };
+                param(key, name, value, unit)
+            })
+            .collect();
+
+        Ok(ProjDefinition { name, epsg: None, proj, method_name, parameters, datum, units, flags })
+    }
+
+    /// Convert false easting/northing read from a PROJ string to CRS units
+    ///
+    /// PROJ always takes x_0/y_0 in meters, while GeoTIFF, WKT and PROJJSON
+    /// express them in the CRS linear unit.
+    fn false_offsets_from_meters(&mut self) {
+        let factor = self.meters_per_unit();
+        for p in self.parameters.iter_mut().filter(|p| p.unit == ParameterUnit::Linear) {
+            p.value /= factor;
+        }
+    }
+
+    /// Value of a parameter by its PROJ key
+    pub fn parameter(&self, key: &str) -> Option<f64> {
+        self.parameters.iter().find(|p| p.key == key).map(|p| p.value)
+    }
+
+    /// Length of one CRS unit in meters
+    pub fn meters_per_unit(&self) -> f64 {
+        match self.units {
+            "ft" => 0.3048,
+            "us-ft" => 1200.0 / 3937.0,
+            _ => 1.0,
+        }
+    }
+
+    /// Ellipsoid axes the projection is actually computed on
+    ///
+    /// Web Mercator projects WGS84 coordinates on a sphere.
+    pub fn projection_axes(&self) -> (f64, f64) {
+        if self.proj == "merc" && self.flags.contains(&"+nadgrids=@null") {
+            (self.datum.semi_major_axis, self.datum.semi_major_axis)
+        } else {
+            (self.datum.semi_major_axis, self.datum.semi_minor_axis)
+        }
+    }
+
+    /// Check whether two definitions describe the same coordinate system
+    ///
+    /// Names and EPSG codes are ignored; the projection, its parameters,
+    /// the ellipsoid and the units have to agree. Named datums must match.
+    ///
+    /// # Arguments
+    /// * `other` - Definition to compare with
+    ///
+    /// # Returns
+    /// `true` if coordinates in one system are valid in the other
+    pub fn is_equivalent(&self, other: &ProjDefinition) -> bool {
+        if self.proj != other.proj || self.units != other.units {
+            return false;
+        }
+        if let (Some(a), Some(b)) = (self.datum.proj_name, other.datum.proj_name) {
+            if a != b {
+                return false;
+            }
+        }
+
+        let (a1, b1) = self.projection_axes();
+        let (a2, b2) = other.projection_axes();
+        if (a1 - a2).abs() > 1e-6 || (b1 - b2).abs() > 1e-6 {
+            return false;
+        }
+
+        let default = |key: &str| if key == "k" { 1.0 } else { 0.0 };
+        self.parameters.iter().chain(other.parameters.iter()).all(|p| {
+            let mine = self.parameter(p.key).unwrap_or_else(|| default(p.key));
+            let theirs = other.parameter(p.key).unwrap_or_else(|| default(p.key));
+            (mine - theirs).abs() < 1e-9
+        })
+    }
+
+    /// Look up the EPSG code of an equivalent built-in system
+    ///
+    /// # Returns
+    /// The EPSG code, or None if the definition matches no known system
+    pub fn equivalent_epsg(&self) -> Option<u32> {
+        if self.epsg.is_some() {
+            return self.epsg;
+        }
+        [4326, 4269, 4267, 4258, 3857, 3035, 5070, 27700].into_iter()
+            .chain(32601..=32660)
+            .chain(32701..=32760)
+            .chain(26901..=26923)
+            .chain(26701..=26722)
+            .chain(25828..=25838)
+            .filter_map(Self::from_epsg)
+            .find(|candidate| candidate.is_equivalent(self))
+            .and_then(|candidate| candidate.epsg)
+    }
+
+    /// Render the definition as WKT1, as read from `.prj` files
+    ///
+    /// # Returns
+    /// A GDAL-style `GEOGCS[...]` or `PROJCS[...]` string
+    pub fn to_wkt(&self) -> String {
+        let id = |code: Option<u32>| code
+            .map(|code| format!(",AUTHORITY[\"EPSG\",\"{}\"]", code))
+            .unwrap_or_default();
+
+        let datum = &self.datum;
+        let towgs84 = datum.towgs84
+            .map(|t| format!(",TOWGS84[{}]", t.iter().map(|v| v.to_string()).collect::<Vec<_>>().join(",")))
+            .unwrap_or_default();
+        let geogcs = format!(
+            "GEOGCS[{},DATUM[{},SPHEROID[{},{},{}]{}],PRIMEM[\"Greenwich\",0],UNIT[\"degree\",0.0174532925199433]{}]",
+            json_string(if self.is_geographic() { &self.name } else { &datum.name }),
+            json_string(&datum.name.replace(' ', "_")),
+            json_string(&datum.ellipsoid_name),
+            datum.semi_major_axis,
+            datum.inverse_flattening(),
+            towgs84,
+            if self.is_geographic() { id(self.epsg) } else { String::new() });
+        if self.is_geographic() {
+            return geogcs;
+        }
+
+        let projection = wkt1_method_name(self.proj, self.method_name);
+        let parameters: String = self.parameters.iter()
+            .map(|p| format!(",PARAMETER[\"{}\",{}]", wkt1_parameter_name(self.proj, p.key), p.value))
+            .collect();
+        let unit = match self.units {
+            "ft" => "UNIT[\"foot\",0.3048]".to_string(),
+            "us-ft" => "UNIT[\"US survey foot\",0.304800609601219]".to_string(),
+            _ => "UNIT[\"metre\",1]".to_string(),
+        };
+        let extension = if self.flags.is_empty() {
+            String::new()
+        } else {
+            format!(",EXTENSION[\"PROJ4\",{}]", json_string(&self.to_proj_string()))
+        };
+
+        format!("PROJCS[{},{},PROJECTION[\"{}\"]{},{}{}{}]",
+                json_string(&self.name), geogcs, projection, parameters, unit, extension, id(self.epsg))
+    }
+}
+
+/// Shorthand for building a parameter
+fn param(key: &'static str, name: &'static str, value: f64, unit: ParameterUnit) -> ProjParameter {
+    ProjParameter { key, name, value, unit }
+}
+
+/// Quote and escape a string for JSON
+fn json_string(value: &str) -> String {
+    let escaped = value.replace('\\', "\\\\").replace('"', "\\\"");
+    format!("\"{}\"", escaped)
+}
+
+/// PROJ ellipsoid names with their EPSG codes and full names
+const ELLIPSOIDS: &[(&str, u16, &str)] = &[
+    ("WGS84", 7030, "WGS 84"),
+    ("GRS80", 7019, "GRS 1980"),
+    ("clrk66", 7008, "Clarke 1866"),
+    ("intl", 7022, "International 1924"),
+    ("bessel", 7004, "Bessel 1841"),
+    ("airy", 7001, "Airy 1830"),
+    ("krass", 7024, "Krassowsky 1940"),
+    ("WGS72", 7043, "WGS 72"),
+];
+
+/// Numeric PROJ keys read as projection parameters
+const PARAMETER_KEYS: &[&str] = &["lat_0", "lon_0", "lat_1", "lat_2", "lat_ts", "k", "k_0", "x_0", "y_0"];
+
+/// Parameter layout entry: PROJ key, EPSG name and unit
+type Layout = (&'static str, &'static str, ParameterUnit);
+
+const NATURAL_ORIGIN: &[Layout] = &[
+    ("lat_0", "Latitude of natural origin", ParameterUnit::Degree),
+    ("lon_0", "Longitude of natural origin", ParameterUnit::Degree),
+    ("k", "Scale factor at natural origin", ParameterUnit::Unity),
+    ("x_0", "False easting", ParameterUnit::Linear),
+    ("y_0", "False northing", ParameterUnit::Linear),
+];
+
+const FALSE_ORIGIN: &[Layout] = &[
+    ("lat_0", "Latitude of false origin", ParameterUnit::Degree),
+    ("lon_0", "Longitude of false origin", ParameterUnit::Degree),
+    ("lat_1", "Latitude of 1st standard parallel", ParameterUnit::Degree),
+    ("lat_2", "Latitude of 2nd standard parallel", ParameterUnit::Degree),
+    ("x_0", "Easting at false origin", ParameterUnit::Linear),
+    ("y_0", "Northing at false origin", ParameterUnit::Linear),
+];
+
+const AZIMUTHAL: &[Layout] = &[
+    ("lat_0", "Latitude of natural origin", ParameterUnit::Degree),
+    ("lon_0", "Longitude of natural origin", ParameterUnit::Degree),
+    ("x_0", "False easting", ParameterUnit::Linear),
+    ("y_0", "False northing", ParameterUnit::Linear),
+];
+
+const MERCATOR_B: &[Layout] = &[
+    ("lat_ts", "Latitude of 1st standard parallel", ParameterUnit::Degree),
+    ("lon_0", "Longitude of natural origin", ParameterUnit::Degree),
+    ("x_0", "False easting", ParameterUnit::Linear),
+    ("y_0", "False northing", ParameterUnit::Linear),
+];
+
+const POLAR_STEREOGRAPHIC: &[Layout] = &[
+    ("lat_0", "Latitude of natural origin", ParameterUnit::Degree),
+    ("lon_0", "Longitude of origin", ParameterUnit::Degree),
+    ("k", "Scale factor at natural origin", ParameterUnit::Unity),
+    ("x_0", "False easting", ParameterUnit::Linear),
+    ("y_0", "False northing", ParameterUnit::Linear),
+];
+
+const EQUIDISTANT_CYLINDRICAL: &[Layout] = &[
+    ("lat_ts", "Latitude of 1st standard parallel", ParameterUnit::Degree),
+    ("lat_0", "Latitude of natural origin", ParameterUnit::Degree),
+    ("lon_0", "Longitude of natural origin", ParameterUnit::Degree),
+    ("x_0", "False easting", ParameterUnit::Linear),
+    ("y_0", "False northing", ParameterUnit::Linear),
+];
+
+const SINUSOIDAL: &[Layout] = &[
+    ("lon_0", "Longitude of natural origin", ParameterUnit::Degree),
+    ("x_0", "False easting", ParameterUnit::Linear),
+    ("y_0", "False northing", ParameterUnit::Linear),
+];
+
+/// Conversion method and parameter layout of a PROJ projection
+///
+/// Some PROJ projections cover several EPSG methods; the parameter values
+/// decide which one applies (e.g. one or two standard parallels).
+fn parameter_layout(proj: &str, get: &dyn Fn(&str) -> Option<f64>)
+    -> Option<(&'static str, &'static str, &'static [Layout])> {
+    let layout = match proj {
+        "tmerc" => ("tmerc", "Transverse Mercator", NATURAL_ORIGIN),
+        "merc" if get("lat_ts").unwrap_or(0.0) != 0.0 => ("merc", "Mercator (variant B)", MERCATOR_B),
+        "merc" => ("merc", "Mercator (variant A)", NATURAL_ORIGIN),
+        "lcc" if get("lat_2").is_some_and(|lat_2| Some(lat_2) != get("lat_1")) =>
+            ("lcc", "Lambert Conic Conformal (2SP)", FALSE_ORIGIN),
+        "lcc" => ("lcc", LCC_1SP, NATURAL_ORIGIN),
+        "aea" => ("aea", "Albers Equal Area", FALSE_ORIGIN),
+        "laea" => ("laea", "Lambert Azimuthal Equal Area", AZIMUTHAL),
+        "aeqd" => ("aeqd", "Azimuthal Equidistant", AZIMUTHAL),
+        "cass" => ("cass", "Cassini-Soldner", AZIMUTHAL),
+        "stere" if get("lat_0").is_some_and(|lat| lat.abs() == 90.0) =>
+            ("stere", "Polar Stereographic (variant A)", POLAR_STEREOGRAPHIC),
+        "stere" => ("stere", "Stereographic", NATURAL_ORIGIN),
+        "sterea" => ("sterea", "Oblique Stereographic", NATURAL_ORIGIN),
+        "eqc" => ("eqc", "Equidistant Cylindrical", EQUIDISTANT_CYLINDRICAL),
+        "sinu" => ("sinu", "Sinusoidal", SINUSOIDAL),
+        _ => return None,
+    };
+    Some(layout)
+}
+
+/// WKT method names (normalized) per PROJ projection
+const METHOD_NAMES: &[(&str, &[&str])] = &[
+    ("tmerc", &["transversemercator", "gausskruger"]),
+    ("merc", &["mercator", "mercator1sp", "mercator2sp", "mercatorvarianta", "mercatorvariantb",
+               "popularvisualisationpseudomercator"]),
+    ("lcc", &["lambertconformalconic", "lambertconformalconic1sp", "lambertconformalconic2sp",
+              "lambertconicconformal1sp", "lambertconicconformal2sp"]),
+    ("aea", &["albersconicequalarea", "albersequalarea"]),
+    ("laea", &["lambertazimuthalequalarea"]),
+    ("aeqd", &["azimuthalequidistant"]),
+    ("cass", &["cassinisoldner"]),
+    ("stere", &["stereographic", "polarstereographic", "polarstereographicvarianta"]),
+    ("sterea", &["obliquestereographic"]),
+    ("eqc", &["equirectangular", "equidistantcylindrical"]),
+    ("sinu", &["sinusoidal"]),
+];
+
+/// WKT1 and EPSG parameter names (normalized) per PROJ key
+const PARAMETER_NAMES: &[(&str, &[&str])] = &[
+    ("lat_0", &["latitudeoforigin", "latitudeofnaturalorigin", "latitudeoffalseorigin",
+                "latitudeofcenter", "latitudeofprojectioncentre"]),
+    ("lon_0", &["centralmeridian", "longitudeofnaturalorigin", "longitudeoffalseorigin",
+                "longitudeofcenter", "longitudeoforigin", "longitudeofprojectioncentre"]),
+    ("lat_1", &["standardparallel1", "latitudeof1ststandardparallel"]),
+    ("lat_2", &["standardparallel2", "latitudeof2ndstandardparallel"]),
+    ("k", &["scalefactor", "scalefactoratnaturalorigin"]),
+    ("x_0", &["falseeasting", "eastingatfalseorigin"]),
+    ("y_0", &["falsenorthing", "northingatfalseorigin"]),
+];
+
+/// WKT1 projection name as written by GDAL
+fn wkt1_method_name(proj: &str, method_name: &'static str) -> &'static str {
+    match (proj, method_name) {
+        ("tmerc", _) => "Transverse_Mercator",
+        ("merc", "Mercator (variant B)") => "Mercator_2SP",
+        ("merc", _) => "Mercator_1SP",
+        ("lcc", LCC_1SP) => "Lambert_Conformal_Conic_1SP",
+        ("lcc", _) => "Lambert_Conformal_Conic_2SP",
+        ("aea", _) => "Albers_Conic_Equal_Area",
+        ("laea", _) => "Lambert_Azimuthal_Equal_Area",
+        ("aeqd", _) => "Azimuthal_Equidistant",
+        ("cass", _) => "Cassini_Soldner",
+        ("stere", "Polar Stereographic (variant A)") => "Polar_Stereographic",
+        ("stere", _) => "Stereographic",
+        ("sterea", _) => "Oblique_Stereographic",
+        ("eqc", _) => "Equirectangular",
+        ("sinu", _) => "Sinusoidal",
+        _ => method_name,
+    }
+}
+
+/// WKT1 parameter name as written by GDAL
+fn wkt1_parameter_name(proj: &str, key: &str) -> &'static str {
+    let centered = matches!(proj, "aea" | "laea" | "aeqd");
+    match key {
+        "lat_0" if centered => "latitude_of_center",
+        "lon_0" if centered => "longitude_of_center",
+        "lat_0" => "latitude_of_origin",
+        "lon_0" => "central_meridian",
+        "lat_1" | "lat_ts" => "standard_parallel_1",
+        "lat_2" => "standard_parallel_2",
+        "k" => "scale_factor",
+        "x_0" => "false_easting",
+        "y_0" => "false_northing",
+        _ => "unknown",
+    }
+}
+
+/// EPSG datum code for a PROJ, WKT1 or WKT2 datum name
+fn datum_code_for_name(name: &str) -> Option<u16> {
+    match normalize_name(name).as_str() {
+        "wgs84" | "wgs1984" | "dwgs1984" | "worldgeodeticsystem1984" => Some(6326),
+        "nad83" | "northamericandatum1983" | "dnorthamerican1983" => Some(6269),
+        "nad27" | "northamericandatum1927" | "dnorthamerican1927" => Some(6267),
+        "etrs89" | "etrs1989" | "detrs1989" | "europeanterrestrialreferencesystem1989" => Some(6258),
+        "osgb36" | "osgb1936" | "dosgb1936" | "ordnancesurveyofgreatbritain1936" => Some(6277),
+        _ => None,
+    }
+}
+
+/// Normalize 3 or 7 TOWGS84 values to the 7-parameter form
+fn helmert_parameters(values: &[f64]) -> Option<[f64; 7]> {
+    match values.len() {
+        3 => Some([values[0], values[1], values[2], 0.0, 0.0, 0.0, 0.0]),
+        7 => Some([values[0], values[1], values[2], values[3], values[4], values[5], values[6]]),
+        _ => None,
+    }
+}
+
+/// PROJ unit keyword for a unit length in meters
+fn units_for_factor(factor: f64) -> TiffResult<&'static str> {
+    match factor {
+        f if (f - 1.0).abs() < 1e-9 => Ok("m"),
+        f if (f - 0.3048).abs() < 1e-9 => Ok("ft"),
+        f if (f - 1200.0 / 3937.0).abs() < 1e-9 => Ok("us-ft"),
+        f => Err(TiffError::GenericError(format!("Unsupported linear unit of {} m", f))),
+    }
+}
+
+/// Lowercase a name and drop everything but letters and digits
+fn normalize_name(name: &str) -> String {
+    name.chars().filter(|c| c.is_ascii_alphanumeric()).map(|c| c.to_ascii_lowercase()).collect()
+}
+
+/// A value inside a WKT element
+#[derive(Debug)]
+enum WktValue {
+    Text(String),
+    Number(f64),
+    Node(WktNode),
+}
+
+/// A parsed WKT element such as `PARAMETER["false_easting",500000]`
+#[derive(Debug)]
+struct WktNode {
+    keyword: String,
+    values: Vec<WktValue>,
+}
+
+impl WktNode {
+    /// Parse a complete WKT string
+    fn parse(wkt: &str) -> TiffResult<Self> {
+        let chars: Vec<char> = wkt.trim().chars().collect();
+        let mut pos = 0;
+        let node = Self::parse_node(&chars, &mut pos)?;
+        Ok(node)
+    }
+
+    /// Parse an element starting at `pos`
+    fn parse_node(chars: &[char], pos: &mut usize) -> TiffResult<Self> {
+        let keyword = Self::parse_word(chars, pos);
+        if keyword.is_empty() {
+            return Err(TiffError::GenericError(format!("Expected a WKT keyword at offset {}", pos)));
+        }
+        Self::skip_whitespace(chars, pos);
+        let close = match chars.get(*pos) {
+            Some('[') => ']',
+            Some('(') => ')',
+            _ => return Err(TiffError::GenericError(format!("Expected '[' after WKT keyword {}", keyword))),
+        };
+        *pos += 1;
+
+        let mut values = Vec::new();
+        loop {
+            Self::skip_whitespace(chars, pos);
+            match chars.get(*pos) {
+                Some(c) if *c == close => {
+                    *pos += 1;
+                    return Ok(WktNode { keyword, values });
+                },
+                Some(',') => *pos += 1,
+                Some('"') => {
+                    *pos += 1;
+                    let mut text = String::new();
+                    loop {
+                        match chars.get(*pos) {
+                            // A doubled quote is an escaped quote
+                            Some('"') if chars.get(*pos + 1) == Some(&'"') => { text.push('"'); *pos += 2; },
+                            Some('"') => { *pos += 1; break; },
+                            Some(c) => { text.push(*c); *pos += 1; },
+                            None => return Err(TiffError::GenericError("Unterminated string in WKT".to_string())),
+                        }
+                    }
+                    values.push(WktValue::Text(text));
+                },
+                Some(c) if c.is_ascii_alphabetic() => {
+                    let start = *pos;
+                    let word = Self::parse_word(chars, pos);
+                    Self::skip_whitespace(chars, pos);
+                    if matches!(chars.get(*pos), Some('[') | Some('(')) {
+                        *pos = start;
+                        values.push(WktValue::Node(Self::parse_node(chars, pos)?));
+                    } else {
+                        values.push(WktValue::Text(word));
+                    }
+                },
+                Some(_) => {
+                    let start = *pos;
+                    while chars.get(*pos).is_some_and(|c| !matches!(c, ',' | ']' | ')') && !c.is_whitespace()) {
+                        *pos += 1;
+                    }
+                    let token: String = chars[start..*pos].iter().collect();
+                    let number = token.parse::<f64>().map_err(|_| TiffError::GenericError(
+                        format!("Invalid number '{}' in WKT", token)))?;
+                    values.push(WktValue::Number(number));
+                },
+                None => return Err(TiffError::GenericError(format!("Unterminated WKT element {}", keyword))),
+            }
+        }
+    }
+
+    fn parse_word(chars: &[char], pos: &mut usize) -> String {
+        Self::skip_whitespace(chars, pos);
+        let start = *pos;
+        while chars.get(*pos).is_some_and(|c| c.is_ascii_alphanumeric() || *c == '_') {
+            *pos += 1;
+        }
+        chars[start..*pos].iter().collect()
+    }
+
+    fn skip_whitespace(chars: &[char], pos: &mut usize) {
+        while chars.get(*pos).is_some_and(|c| c.is_whitespace()) {
+            *pos += 1;
+        }
+    }
+
+    /// Text value at position `index`
+    fn text(&self, index: usize) -> Option<&str> {
+        match self.values.get(index) {
+            Some(WktValue::Text(text)) => Some(text),
+            _ => None,
+        }
+    }
+
+    /// Numeric value at position `index`
+    fn number(&self, index: usize) -> Option<f64> {
+        match self.values.get(index) {
+            Some(WktValue::Number(number)) => Some(*number),
+            _ => None,
+        }
+    }
+
+    fn children(&self) -> impl Iterator<Item = &WktNode> {
+        self.values.iter().filter_map(|value| match value {
+            WktValue::Node(node) => Some(node),
+            _ => None,
+        })
+    }
+
+    /// First direct child with one of the keywords
+    fn child(&self, keywords: &[&str]) -> Option<&WktNode> {
+        self.children().find(|node| keywords.iter().any(|k| node.keyword.eq_ignore_ascii_case(k)))
+    }
+
+    /// First descendant (depth first) with one of the keywords
+    fn find(&self, keywords: &[&str]) -> Option<&WktNode> {
+        self.children().find_map(|node| {
+            if keywords.iter().any(|k| node.keyword.eq_ignore_ascii_case(k)) {
+                Some(node)
+            } else {
+                node.find(keywords)
+            }
+        })
+    }
+
+    /// All descendants with the keyword, in document order
+    fn find_all(&self, keyword: &str) -> Vec<&WktNode> {
+        let mut found = Vec::new();
+        for node in self.children() {
+            if node.keyword.eq_ignore_ascii_case(keyword) {
+                found.push(node);
+            }
+            found.extend(node.find_all(keyword));
+        }
+        found
+    }
+}
@@ -4,21 +4,26 @@
 //! Strategy pattern to handle different byte orders.
 
 use log::{debug, info, warn};
-use std::fs::File;
-use std::io::{BufReader, Read, Seek, SeekFrom};
-use std::path::Path;
+use std::collections::HashSet;
+use std::io::{BufReader, Seek, SeekFrom};
 
 use crate::io::seekable::SeekableReader;
-use crate::io::byte_order::ByteOrderHandler;
+use crate::io::storage;
+use crate::io::byte_order::{ByteOrder, ByteOrderHandler};
+use crate::tiff::constants::tags;
 use crate::tiff::errors::{TiffError, TiffResult};
 use crate::tiff::ifd::{IFD, IFDEntry};
+use crate::tiff::tag_value::{TagType, TagValue};
 use crate::tiff::types::TIFF;
 use crate::tiff::validation;
 use crate::utils::format_utils;
 use crate::utils::ifd_utils;
-use crate::utils::tag_utils;
 use crate::utils::string_utils;
 use crate::utils::logger::Logger;
+use crate::utils::memory_utils;
+
+/// Deepest nesting of SubIFDs that is followed when reading
+const MAX_SUB_IFD_DEPTH: usize = 4;
 
 /// Builder for TiffReader
 ///
@@ -26,17 +31,27 @@ use crate::utils::logger::Logger;
 pub struct TiffReaderBuilder<'a> {
     /// Logger to use
     logger: &'a Logger,
+    /// Most IFDs to read from the main chain, None for all of them
+    max_ifds: Option<usize>,
 }
 
 impl<'a> TiffReaderBuilder<'a> {
     /// Create a new TiffReaderBuilder
     pub fn new(logger: &'a Logger) -> Self {
-        TiffReaderBuilder { logger }
+        TiffReaderBuilder { logger, max_ifds: None }
+    }
+
+    /// Stop reading the IFD chain after this many IFDs
+    pub fn max_ifds(mut self, max_ifds: usize) -> Self {
+        self.max_ifds = Some(max_ifds);
+        self
     }
 
     /// Build the TiffReader
     pub fn build(self) -> TiffReader<'a> {
-        TiffReader::new(self.logger)
+        let mut reader = TiffReader::new(self.logger);
+        reader.set_max_ifds(self.max_ifds);
+        reader
     }
 }
 
@@ -50,6 +65,8 @@ pub struct TiffReader<'a> {
     current_file: Option<String>,
     /// Whether currently reading BigTIFF format
     pub(crate) is_big_tiff: bool,
+    /// Most IFDs to read from the main chain, None for all of them
+    max_ifds: Option<usize>,
 }
 
 impl<'a> TiffReader<'a> {
@@ -60,19 +77,28 @@ impl<'a> TiffReader<'a> {
             logger,
             current_file: None,
             is_big_tiff: false,
+            max_ifds: None,
         }
     }
 
+    /// Limits how many IFDs of the main chain are read
+    ///
+    /// Without a limit the whole chain is read, however many pages it has;
+    /// chains that loop back on themselves are detected either way.
+    ///
+    /// # Arguments
+    /// * `max_ifds` - Most IFDs to read, None for all of them
+    pub fn set_max_ifds(&mut self, max_ifds: Option<usize>) {
+        self.max_ifds = max_ifds;
+    }
+
     /// Creates a file reader for the current file
     ///
     /// This is an internal utility to open the current file for reading.
     /// It's used by various methods that need to access file content.
-    pub(crate) fn create_reader(&self) -> TiffResult<File> {
+    pub(crate) fn create_reader(&self) -> TiffResult<Box<dyn SeekableReader>> {
         match &self.current_file {
-            Some(path) => {
-                let file = File::open(path)?;
-                Ok(file)
-            },
+            Some(path) => storage::open(path),
             None => Err(TiffError::GenericError("No file path specified".to_string()))
         }
     }
@@ -91,7 +117,7 @@ impl<'a> TiffReader<'a> {
     /// It opens the file and delegates to the read() method.
     ///
     /// # Arguments
-    /// * `filepath` - Path to the TIFF file to load
+    /// * `filepath` - Path to the TIFF file to load, or an object storage URI
     ///
     /// # Returns
     /// A TIFF structure containing the file's contents
@@ -99,13 +125,27 @@ impl<'a> TiffReader<'a> {
         info!("Loading TIFF file: {}", filepath);
         self.current_file = Some(filepath.to_string());
 
-        let path = Path::new(filepath);
-        let file = File::open(path)?;
-        let mut reader = BufReader::with_capacity(1024 * 1024, file); // 1MB buffer
+        let file = storage::open(filepath)?;
+        let mut reader = BufReader::with_capacity(memory_utils::io_buffer_size(), file);
 
         self.read(&mut reader)
     }
 
+    /// Points the reader at a file whose header was already parsed
+    ///
+    /// Sets up what `load` would have, without reading the file again, so
+    /// tag values of a cached `TIFF` can be read through this reader.
+    ///
+    /// # Arguments
+    /// * `filepath` - Path to the TIFF file
+    /// * `byte_order` - Byte order of the file
+    /// * `is_big_tiff` - Whether the file is a BigTIFF
+    pub(crate) fn attach(&mut self, filepath: &str, byte_order: ByteOrder, is_big_tiff: bool) {
+        self.current_file = Some(filepath.to_string());
+        self.byte_order_handler = Some(byte_order.create_handler());
+        self.is_big_tiff = is_big_tiff;
+    }
+
     /// Reads a TIFF file from the given reader
     ///
     /// This method handles the core process of reading a TIFF file:
@@ -152,6 +192,10 @@ impl<'a> TiffReader<'a> {
 
     /// Reads a chain of IFDs starting from the given offset
     ///
+    /// Follows next-IFD offsets until the chain ends, an offset repeats
+    /// (a corrupt or malicious file that loops), or the configured IFD
+    /// limit is reached.
+    ///
     /// # Arguments
     /// * `reader` - The seekable reader to use
     /// * `first_ifd_offset` - Offset of the first IFD in the chain
@@ -162,10 +206,18 @@ impl<'a> TiffReader<'a> {
         let mut ifds = Vec::new();
         let mut ifd_offset = first_ifd_offset;
         let mut ifd_number = 0;
-        let max_ifds = 100; // Reasonable limit to prevent infinite loops
+        let mut visited = HashSet::new();
         let handler = self.get_byte_order_handler_unwrapped()?;
 
-        while ifd_offset != 0 && ifd_number < max_ifds {
+        while ifd_offset != 0 {
+            if self.max_ifds.is_some_and(|max_ifds| ifd_number >= max_ifds) {
+                warn!("Stopping after {} IFDs, the configured limit", ifd_number);
+                break;
+            }
+            if !visited.insert(ifd_offset) {
+                warn!("IFD chain loops back to offset {}, stopping after {} IFDs", ifd_offset, ifd_number);
+                break;
+            }
             debug!("Reading IFD at offset: {}", ifd_offset);
 
             // Get the file size for validation
@@ -180,11 +232,13 @@ impl<'a> TiffReader<'a> {
 
             // Try to read the IFD
             match self.read_ifd(reader, ifd_offset, ifd_number) {
-                Ok(ifd) => {
+                Ok(mut ifd) => {
                     debug!("Successfully read IFD with {} entries", ifd.entries.len());
+                    self.read_sub_ifds(reader, &mut ifd, 0);
 
-                    // Get next IFD offset
-                    let next_offset_position = ifd_offset + ifd_utils::calculate_ifd_size(&ifd, self.is_big_tiff);
+                    // The next IFD offset is the last field of the IFD
+                    let offset_size = if self.is_big_tiff { 8 } else { 4 };
+                    let next_offset_position = ifd_offset + ifd_utils::calculate_ifd_size(&ifd, self.is_big_tiff) - offset_size;
 
                     // Validate next offset position
                     if next_offset_position >= file_size {
@@ -266,6 +320,58 @@ impl<'a> TiffReader<'a> {
         Ok(ifd)
     }
 
+    /// Reads the child IFDs an IFD points to through its SubIFDs tag
+    ///
+    /// Some producers keep masks and overviews there instead of in the main
+    /// chain. Each offset is read as one IFD, and nested SubIFDs are
+    /// followed up to `MAX_SUB_IFD_DEPTH` levels. Children that can't be
+    /// read are skipped with a warning, leaving the parent intact.
+    ///
+    /// # Arguments
+    /// * `reader` - The seekable reader to use
+    /// * `ifd` - The IFD whose children to read
+    /// * `depth` - Nesting level of `ifd`, 0 for the main chain
+    fn read_sub_ifds(&self, reader: &mut dyn SeekableReader, ifd: &mut IFD, depth: usize) {
+        if !ifd.has_tag(tags::SUB_IFDS) {
+            return;
+        }
+        if depth >= MAX_SUB_IFD_DEPTH {
+            warn!("SubIFDs nested deeper than {} levels, not following them", MAX_SUB_IFD_DEPTH);
+            return;
+        }
+
+        let offsets = match self.read_tag_values(reader, ifd, tags::SUB_IFDS) {
+            Ok(offsets) => offsets,
+            Err(e) => {
+                warn!("Error reading SubIFD offsets of IFD {}: {}", ifd.number, e);
+                return;
+            }
+        };
+        let file_size = match validation::get_file_size(reader) {
+            Ok(size) => size,
+            Err(e) => {
+                warn!("Error getting file size for SubIFDs: {}", e);
+                return;
+            }
+        };
+
+        for (number, offset) in offsets.into_iter().enumerate() {
+            if offset < 8 || offset >= file_size {
+                warn!("Invalid SubIFD offset {}, skipping", offset);
+                continue;
+            }
+            match self.read_ifd(reader, offset, number) {
+                Ok(mut sub_ifd) => {
+                    self.read_sub_ifds(reader, &mut sub_ifd, depth + 1);
+                    ifd.sub_ifds.push(sub_ifd);
+                },
+                Err(e) => warn!("Error reading SubIFD {} at offset {}: {}", number, offset, e),
+            }
+        }
+
+        info!("Read {} SubIFDs of IFD {}", ifd.sub_ifds.len(), ifd.number);
+    }
+
     /// Reads the entry count from an IFD
     fn read_ifd_entry_count(&self, reader: &mut dyn SeekableReader) -> TiffResult<u64> {
         let handler = self.get_byte_order_handler_unwrapped()?;
@@ -301,8 +407,8 @@ impl<'a> TiffReader<'a> {
 
     /// Reads a tag's value as a vector of u64
     ///
-    /// This is a utility method for extracting tag values from an IFD.
-    /// It handles different field types and automatically converts them to u64.
+    /// This is a utility method for extracting integer tag values, such as
+    /// strip and tile offsets, through an already open reader.
     ///
     /// # Arguments
     /// * `reader` - The seekable reader to use
@@ -314,19 +420,107 @@ impl<'a> TiffReader<'a> {
     pub fn read_tag_values(&self, reader: &mut dyn SeekableReader, ifd: &IFD, tag: u16) -> TiffResult<Vec<u64>> {
         let entry = ifd.get_entry(tag)
             .ok_or_else(|| TiffError::TagNotFound(tag))?;
+        let value = self.read_entry_value(reader, entry)?;
+        u64::from_tag_value(&value)
+    }
+
+    /// Reads a run of a tag's values as u64
+    ///
+    /// Only the bytes of the requested values are read from the file, so a
+    /// few entries of the offset arrays of huge images cost a few bytes
+    /// rather than the whole array.
+    ///
+    /// # Arguments
+    /// * `reader` - The seekable reader to use
+    /// * `ifd` - The IFD containing the tag
+    /// * `tag` - The tag number to read
+    /// * `start` - Index of the first value to read
+    /// * `count` - Number of values, cut short at the end of the tag
+    ///
+    /// # Returns
+    /// The values from `start` on
+    pub fn read_tag_value_range(&self, reader: &mut dyn SeekableReader, ifd: &IFD, tag: u16,
+                                start: u64, count: u64) -> TiffResult<Vec<u64>> {
+        let entry = ifd.get_entry(tag)
+            .ok_or(TiffError::TagNotFound(tag))?;
+        let count = count.min(entry.count.saturating_sub(start));
+        if count == 0 {
+            return Ok(Vec::new());
+        }
+        if entry.is_value_inline(self.is_big_tiff) {
+            let values = self.read_tag_values(reader, ifd, tag)?;
+            return Ok(values[start as usize..(start + count) as usize].to_vec());
+        }
+
+        let handler = self.get_byte_order_handler_unwrapped()?;
+        let size = TagValue::field_type_size(entry.field_type)
+            .ok_or(TiffError::UnsupportedFieldType(entry.field_type))? as u64;
+        let offset = entry.value_offset + start * size;
+        if offset + count * size > reader.seek(SeekFrom::End(0))? {
+            return Err(TiffError::GenericError(format!(
+                "Tag {} data at offset {} runs past the end of the file", entry.tag, entry.value_offset)));
+        }
+        let mut bytes = vec![0u8; (count * size) as usize];
+        reader.seek(SeekFrom::Start(offset))?;
+        reader.read_exact(&mut bytes)?;
+
+        u64::from_tag_value(&TagValue::decode(entry.field_type, count, &bytes, handler.as_ref())?)
+    }
+
+    /// Reads a tag's values as a given type
+    ///
+    /// One entry point for tag values of every field type and byte order,
+    /// stored in the entry or elsewhere in the file. Integers convert to any
+    /// integer type they fit in; numbers of any kind, rationals included,
+    /// convert to floats; ASCII reads as its NUL-separated strings.
+    ///
+    /// # Arguments
+    /// * `ifd` - The IFD containing the tag
+    /// * `tag` - The tag number to read
+    ///
+    /// # Returns
+    /// The values, or an error if the tag is missing or doesn't convert to `T`
+    pub fn read_tag<T: TagType>(&self, ifd: &IFD, tag: u16) -> TiffResult<Vec<T>> {
+        let entry = ifd.get_entry(tag).ok_or(TiffError::TagNotFound(tag))?;
+        T::from_tag_value(&self.read_tag_value(entry)?)
+    }
+
+    /// Reads and decodes the values of an IFD entry
+    ///
+    /// Works for every field type and byte order, whether the values are
+    /// stored in the entry itself or elsewhere in the file.
+    ///
+    /// # Arguments
+    /// * `entry` - The IFD entry to read
+    ///
+    /// # Returns
+    /// The typed values of the entry
+    pub fn read_tag_value(&self, entry: &IFDEntry) -> TiffResult<TagValue> {
+        let mut file = self.create_reader()?;
+        self.read_entry_value(&mut file, entry)
+    }
 
-        let mut values = Vec::with_capacity(entry.count as usize);
+    /// Reads and decodes an entry's values through the given reader
+    fn read_entry_value(&self, reader: &mut dyn SeekableReader, entry: &IFDEntry) -> TiffResult<TagValue> {
+        let handler = self.get_byte_order_handler_unwrapped()?;
+        let size = TagValue::field_type_size(entry.field_type)
+            .ok_or(TiffError::UnsupportedFieldType(entry.field_type))?;
 
-        // Check if the value is stored inline
-        if tag_utils::is_value_inline(entry, self.is_big_tiff) {
-            values.push(entry.value_offset);
+        let bytes = if entry.is_value_inline(self.is_big_tiff) {
+            TagValue::inline_bytes(entry, handler.byte_order(), self.is_big_tiff)
         } else {
+            let length = size as u64 * entry.count;
+            if entry.value_offset + length > reader.seek(SeekFrom::End(0))? {
+                return Err(TiffError::GenericError(format!(
+                    "Tag {} data at offset {} runs past the end of the file", entry.tag, entry.value_offset)));
+            }
+            let mut bytes = vec![0u8; length as usize];
             reader.seek(SeekFrom::Start(entry.value_offset))?;
-            let handler = self.get_byte_order_handler_unwrapped()?;
-            tag_utils::read_tag_value_array(reader, entry, handler, &mut values)?;
-        }
+            reader.read_exact(&mut bytes)?;
+            bytes
+        };
 
-        Ok(values)
+        TagValue::decode(entry.field_type, entry.count, &bytes, handler.as_ref())
     }
 
     /// Reads a rational value (numerator/denominator pair)
@@ -0,0 +1,338 @@
+//! Decoded TIFF tag values
+//!
+//! IFD entries only carry a type, a count and a value-or-offset field.
+//! This module turns an entry's raw bytes into typed values for every
+//! TIFF and BigTIFF field type, whichever byte order the file uses.
+
+use std::fmt::Write;
+use std::io::Cursor;
+
+use crate::io::byte_order::{ByteOrder, ByteOrderHandler};
+use crate::tiff::constants::field_types;
+use crate::tiff::errors::{TiffError, TiffResult};
+use crate::tiff::ifd::IFDEntry;
+
+/// Typed values of one IFD entry
+#[derive(Debug, Clone, PartialEq)]
+pub enum TagValue {
+    /// BYTE values
+    Byte(Vec<u8>),
+    /// ASCII text, NUL terminators included
+    Ascii(Vec<u8>),
+    /// SHORT values
+    Short(Vec<u16>),
+    /// LONG values
+    Long(Vec<u32>),
+    /// RATIONAL values as (numerator, denominator)
+    Rational(Vec<(u32, u32)>),
+    /// SBYTE values
+    SByte(Vec<i8>),
+    /// UNDEFINED bytes
+    Undefined(Vec<u8>),
+    /// SSHORT values
+    SShort(Vec<i16>),
+    /// SLONG values
+    SLong(Vec<i32>),
+    /// SRATIONAL values as (numerator, denominator)
+    SRational(Vec<(i32, i32)>),
+    /// FLOAT values
+    Float(Vec<f32>),
+    /// DOUBLE values
+    Double(Vec<f64>),
+    /// IFD offsets
+    Ifd(Vec<u32>),
+    /// LONG8 values (BigTIFF)
+    Long8(Vec<u64>),
+    /// SLONG8 values (BigTIFF)
+    SLong8(Vec<i64>),
+    /// IFD8 offsets (BigTIFF)
+    Ifd8(Vec<u64>),
+}
+
+impl TagValue {
+    /// Size in bytes of one value of a field type
+    ///
+    /// # Returns
+    /// The size, or None for field types TIFF does not define
+    pub fn field_type_size(field_type: u16) -> Option<usize> {
+        match field_type {
+            field_types::BYTE | field_types::ASCII | field_types::SBYTE | field_types::UNDEFINED => Some(1),
+            field_types::SHORT | field_types::SSHORT => Some(2),
+            field_types::LONG | field_types::SLONG | field_types::FLOAT | field_types::IFD => Some(4),
+            field_types::RATIONAL | field_types::SRATIONAL | field_types::DOUBLE |
+            field_types::LONG8 | field_types::SLONG8 | field_types::IFD8 => Some(8),
+            _ => None,
+        }
+    }
+
+    /// Raw bytes of a value stored inside the entry itself
+    ///
+    /// The value-or-offset field was read as one integer in the file's byte
+    /// order; writing it back the same way restores the bytes as stored.
+    ///
+    /// # Arguments
+    /// * `entry` - Entry whose value fits in the entry
+    /// * `byte_order` - Byte order of the file
+    /// * `is_big_tiff` - Whether the field is 8 bytes wide
+    pub fn inline_bytes(entry: &IFDEntry, byte_order: ByteOrder, is_big_tiff: bool) -> Vec<u8> {
+        match (byte_order, is_big_tiff) {
+            (ByteOrder::LittleEndian, true) => entry.value_offset.to_le_bytes().to_vec(),
+            (ByteOrder::BigEndian, true) => entry.value_offset.to_be_bytes().to_vec(),
+            (ByteOrder::LittleEndian, false) => (entry.value_offset as u32).to_le_bytes().to_vec(),
+            (ByteOrder::BigEndian, false) => (entry.value_offset as u32).to_be_bytes().to_vec(),
+        }
+    }
+
+    /// Decode raw bytes as values of a field type
+    ///
+    /// # Arguments
+    /// * `field_type` - TIFF field type code
+    /// * `count` - Number of values
+    /// * `bytes` - Raw bytes, at least `count` values long
+    /// * `handler` - Byte order handler of the file
+    ///
+    /// # Returns
+    /// The decoded values, or an error for unknown types or short data
+    pub fn decode(field_type: u16, count: u64, bytes: &[u8],
+                  handler: &dyn ByteOrderHandler) -> TiffResult<TagValue> {
+        let size = Self::field_type_size(field_type)
+            .ok_or(TiffError::UnsupportedFieldType(field_type))?;
+        let count = count as usize;
+        if bytes.len() < size * count {
+            return Err(TiffError::GenericError(format!(
+                "Tag data too short: {} bytes for {} values of type {}", bytes.len(), count, field_type)));
+        }
+
+        let mut cursor = Cursor::new(&bytes[..size * count]);
+        let cursor = &mut cursor;
+        let value = match field_type {
+            field_types::BYTE => TagValue::Byte(bytes[..count].to_vec()),
+            field_types::ASCII => TagValue::Ascii(bytes[..count].to_vec()),
+            field_types::UNDEFINED => TagValue::Undefined(bytes[..count].to_vec()),
+            field_types::SBYTE => TagValue::SByte(bytes[..count].iter().map(|b| *b as i8).collect()),
+            field_types::SHORT => TagValue::Short(read_n(count, || handler.read_u16(cursor))?),
+            field_types::SSHORT => TagValue::SShort(read_n(count, || handler.read_u16(cursor).map(|v| v as i16))?),
+            field_types::LONG => TagValue::Long(read_n(count, || handler.read_u32(cursor))?),
+            field_types::SLONG => TagValue::SLong(read_n(count, || handler.read_u32(cursor).map(|v| v as i32))?),
+            field_types::RATIONAL => TagValue::Rational(read_n(count, || handler.read_rational(cursor))?),
+            field_types::SRATIONAL => TagValue::SRational(read_n(count, || handler.read_srational(cursor))?),
+            field_types::FLOAT => TagValue::Float(read_n(count, || handler.read_f32(cursor))?),
+            field_types::DOUBLE => TagValue::Double(read_n(count, || handler.read_f64(cursor))?),
+            field_types::IFD => TagValue::Ifd(read_n(count, || handler.read_u32(cursor))?),
+            field_types::LONG8 => TagValue::Long8(read_n(count, || handler.read_u64(cursor))?),
+            field_types::SLONG8 => TagValue::SLong8(read_n(count, || handler.read_u64(cursor).map(|v| v as i64))?),
+            field_types::IFD8 => TagValue::Ifd8(read_n(count, || handler.read_u64(cursor))?),
+            _ => return Err(TiffError::UnsupportedFieldType(field_type)),
+        };
+        Ok(value)
+    }
+
+    /// Number of values
+    pub fn len(&self) -> usize {
+        match self {
+            TagValue::Byte(v) | TagValue::Ascii(v) | TagValue::Undefined(v) => v.len(),
+            TagValue::SByte(v) => v.len(),
+            TagValue::Short(v) => v.len(),
+            TagValue::SShort(v) => v.len(),
+            TagValue::Long(v) | TagValue::Ifd(v) => v.len(),
+            TagValue::SLong(v) => v.len(),
+            TagValue::Rational(v) => v.len(),
+            TagValue::SRational(v) => v.len(),
+            TagValue::Float(v) => v.len(),
+            TagValue::Double(v) => v.len(),
+            TagValue::Long8(v) | TagValue::Ifd8(v) => v.len(),
+            TagValue::SLong8(v) => v.len(),
+        }
+    }
+
+    /// Whether there are no values
+    pub fn is_empty(&self) -> bool {
+        self.len() == 0
+    }
+
+    /// Encode the values little-endian, as the TIFF writer stores them
+    ///
+    /// # Returns
+    /// The raw bytes, `len()` values of the entry's field type long
+    pub fn to_le_bytes(&self) -> Vec<u8> {
+        match self {
+            TagValue::Byte(v) | TagValue::Ascii(v) | TagValue::Undefined(v) => v.clone(),
+            TagValue::SByte(v) => v.iter().map(|b| *b as u8).collect(),
+            TagValue::Short(v) => v.iter().flat_map(|x| x.to_le_bytes()).collect(),
+            TagValue::SShort(v) => v.iter().flat_map(|x| x.to_le_bytes()).collect(),
+            TagValue::Long(v) | TagValue::Ifd(v) => v.iter().flat_map(|x| x.to_le_bytes()).collect(),
+            TagValue::SLong(v) => v.iter().flat_map(|x| x.to_le_bytes()).collect(),
+            TagValue::Rational(v) => v.iter()
+                .flat_map(|(n, d)| n.to_le_bytes().into_iter().chain(d.to_le_bytes()))
+                .collect(),
+            TagValue::SRational(v) => v.iter()
+                .flat_map(|(n, d)| n.to_le_bytes().into_iter().chain(d.to_le_bytes()))
+                .collect(),
+            TagValue::Float(v) => v.iter().flat_map(|x| x.to_le_bytes()).collect(),
+            TagValue::Double(v) => v.iter().flat_map(|x| x.to_le_bytes()).collect(),
+            TagValue::Long8(v) | TagValue::Ifd8(v) => v.iter().flat_map(|x| x.to_le_bytes()).collect(),
+            TagValue::SLong8(v) => v.iter().flat_map(|x| x.to_le_bytes()).collect(),
+        }
+    }
+
+    /// Render the values for display
+    ///
+    /// ASCII text is quoted with control characters escaped, UNDEFINED
+    /// bytes are shown as hex and rationals as "num/den".
+    ///
+    /// # Arguments
+    /// * `max_values` - Values to show before eliding the rest, None for all
+    pub fn format(&self, max_values: Option<usize>) -> String {
+        if let TagValue::Ascii(bytes) = self {
+            let text = String::from_utf8_lossy(bytes);
+            return format!("\"{}\"", text.trim_end_matches('\0').escape_default());
+        }
+
+        let limit = max_values.unwrap_or(usize::MAX).min(self.len());
+        let values: Vec<String> = match self {
+            TagValue::Byte(v) => v.iter().take(limit).map(u8::to_string).collect(),
+            TagValue::Undefined(v) => v.iter().take(limit).map(|b| format!("{:02x}", b)).collect(),
+            TagValue::SByte(v) => v.iter().take(limit).map(i8::to_string).collect(),
+            TagValue::Short(v) => v.iter().take(limit).map(u16::to_string).collect(),
+            TagValue::SShort(v) => v.iter().take(limit).map(i16::to_string).collect(),
+            TagValue::Long(v) | TagValue::Ifd(v) => v.iter().take(limit).map(u32::to_string).collect(),
+            TagValue::SLong(v) => v.iter().take(limit).map(i32::to_string).collect(),
+            TagValue::Rational(v) => v.iter().take(limit).map(|(n, d)| format!("{}/{}", n, d)).collect(),
+            TagValue::SRational(v) => v.iter().take(limit).map(|(n, d)| format!("{}/{}", n, d)).collect(),
+            TagValue::Float(v) => v.iter().take(limit).map(f32::to_string).collect(),
+            TagValue::Double(v) => v.iter().take(limit).map(f64::to_string).collect(),
+            TagValue::Long8(v) | TagValue::Ifd8(v) => v.iter().take(limit).map(u64::to_string).collect(),
+            TagValue::SLong8(v) => v.iter().take(limit).map(i64::to_string).collect(),
+            TagValue::Ascii(_) => Vec::new(),
+        };
+
+        let mut text = values.join(" ");
+        if limit < self.len() {
+            let _ = write!(text, " ... ({} more)", self.len() - limit);
+        }
+        text
+    }
+}
+
+/// Types tag values can be read as
+///
+/// Implemented for the integer types, `f32`, `f64` and `String`; see
+/// [`TiffReader::read_tag`](crate::tiff::TiffReader::read_tag).
+pub trait TagType: Sized {
+    /// Convert decoded values to this type
+    ///
+    /// # Returns
+    /// The converted values, or an error if the field type doesn't convert
+    fn from_tag_value(value: &TagValue) -> TiffResult<Vec<Self>>;
+}
+
+impl TagValue {
+    /// Integer values, widened so every integer field type fits
+    ///
+    /// # Returns
+    /// The values, or an error for non-integer field types
+    pub fn integers(&self) -> TiffResult<Vec<i128>> {
+        let values = match self {
+            TagValue::Byte(v) | TagValue::Undefined(v) => v.iter().map(|x| *x as i128).collect(),
+            TagValue::SByte(v) => v.iter().map(|x| *x as i128).collect(),
+            TagValue::Short(v) => v.iter().map(|x| *x as i128).collect(),
+            TagValue::SShort(v) => v.iter().map(|x| *x as i128).collect(),
+            TagValue::Long(v) | TagValue::Ifd(v) => v.iter().map(|x| *x as i128).collect(),
+            TagValue::SLong(v) => v.iter().map(|x| *x as i128).collect(),
+            TagValue::Long8(v) | TagValue::Ifd8(v) => v.iter().map(|x| *x as i128).collect(),
+            TagValue::SLong8(v) => v.iter().map(|x| *x as i128).collect(),
+            _ => return Err(TiffError::GenericError(format!("{} values are not integers", self.type_name()))),
+        };
+        Ok(values)
+    }
+
+    /// Numeric values as floats, rationals divided out
+    ///
+    /// # Returns
+    /// The values, or an error for ASCII
+    pub fn floats(&self) -> TiffResult<Vec<f64>> {
+        match self {
+            TagValue::Rational(v) => Ok(v.iter().map(|(n, d)| *n as f64 / *d as f64).collect()),
+            TagValue::SRational(v) => Ok(v.iter().map(|(n, d)| *n as f64 / *d as f64).collect()),
+            TagValue::Float(v) => Ok(v.iter().map(|x| *x as f64).collect()),
+            TagValue::Double(v) => Ok(v.clone()),
+            _ => Ok(self.integers()?.into_iter().map(|x| x as f64).collect()),
+        }
+    }
+
+    /// Name of the field type, for error messages
+    fn type_name(&self) -> &'static str {
+        match self {
+            TagValue::Byte(_) => "BYTE",
+            TagValue::Ascii(_) => "ASCII",
+            TagValue::Short(_) => "SHORT",
+            TagValue::Long(_) => "LONG",
+            TagValue::Rational(_) => "RATIONAL",
+            TagValue::SByte(_) => "SBYTE",
+            TagValue::Undefined(_) => "UNDEFINED",
+            TagValue::SShort(_) => "SSHORT",
+            TagValue::SLong(_) => "SLONG",
+            TagValue::SRational(_) => "SRATIONAL",
+            TagValue::Float(_) => "FLOAT",
+            TagValue::Double(_) => "DOUBLE",
+            TagValue::Ifd(_) => "IFD",
+            TagValue::Long8(_) => "LONG8",
+            TagValue::SLong8(_) => "SLONG8",
+            TagValue::Ifd8(_) => "IFD8",
+        }
+    }
+}
+
+/// Integer conversions, failing on values out of the target's range
+macro_rules! integer_tag_type {
+    ($($t:ty),*) => {$(
+        impl TagType for $t {
+            fn from_tag_value(value: &TagValue) -> TiffResult<Vec<Self>> {
+                value.integers()?.into_iter()
+                    .map(|x| <$t>::try_from(x).map_err(|_| TiffError::GenericError(
+                        format!("Tag value {} does not fit in {}", x, stringify!($t)))))
+                    .collect()
+            }
+        }
+    )*};
+}
+
+integer_tag_type!(u8, u16, u32, u64, i8, i16, i32, i64);
+
+impl TagType for f64 {
+    fn from_tag_value(value: &TagValue) -> TiffResult<Vec<Self>> {
+        value.floats()
+    }
+}
+
+impl TagType for f32 {
+    fn from_tag_value(value: &TagValue) -> TiffResult<Vec<Self>> {
+        Ok(value.floats()?.into_iter().map(|x| x as f32).collect())
+    }
+}
+
+impl TagType for String {
+    /// ASCII tags may hold several NUL-terminated strings
+    fn from_tag_value(value: &TagValue) -> TiffResult<Vec<Self>> {
+        let TagValue::Ascii(bytes) = value else {
+            return Err(TiffError::GenericError(format!("{} values are not text", value.type_name())));
+        };
+        let mut strings: Vec<String> = bytes.split(|b| *b == 0)
+            .map(|s| String::from_utf8_lossy(s).into_owned())
+            .collect();
+        // The terminator of the last string leaves an empty piece behind
+        if strings.len() > 1 && strings.last().is_some_and(|s| s.is_empty()) {
+            strings.pop();
+        }
+        Ok(strings)
+    }
+}
+
+/// Read `count` values with a reading closure
+fn read_n<T>(count: usize, mut read: impl FnMut() -> std::io::Result<T>) -> TiffResult<Vec<T>> {
+    let mut values = Vec::with_capacity(count);
+    for _ in 0..count {
+        values.push(read()?);
+    }
+    Ok(values)
+}
@@ -1,7 +1,240 @@
 //! Unit tests for the TIFF module
 
+#[cfg(test)]
 mod test_utils;
 #[cfg(test)]
 mod byte_order_tests;
 #[cfg(test)]
-mod types_tests;
\ No newline at end of file
+mod types_tests;
+#[cfg(test)]
+mod colormap_tests;
+#[cfg(test)]
+mod geo_key_tests;
+#[cfg(test)]
+mod reader_tests;
+#[cfg(test)]
+mod repair_tests;
+#[cfg(test)]
+mod compliance_tests;
+#[cfg(test)]
+mod output_format_tests;
+#[cfg(test)]
+mod nitf_tests;
+#[cfg(test)]
+mod envi_tests;
+#[cfg(test)]
+mod scaling_tests;
+#[cfg(test)]
+mod qa_tests;
+#[cfg(test)]
+mod statistics_tests;
+#[cfg(test)]
+mod footprint_tests;
+#[cfg(test)]
+mod valid_mask_tests;
+#[cfg(test)]
+mod render_tests;
+#[cfg(test)]
+mod pansharpen_tests;
+#[cfg(test)]
+mod balance_tests;
+
+#[cfg(test)]
+mod mosaic_tests;
+
+#[cfg(test)]
+mod warp_tests;
+
+#[cfg(test)]
+mod simd_tests;
+
+#[cfg(test)]
+mod buffer_decode_tests;
+
+#[cfg(test)]
+mod single_band_array_tests;
+
+#[cfg(test)]
+mod strip_reader_tests;
+
+#[cfg(test)]
+mod dataset_cache_tests;
+
+#[cfg(test)]
+mod dataset_tests;
+
+#[cfg(test)]
+mod extract_options_tests;
+
+#[cfg(test)]
+mod typed_coordinate_tests;
+
+#[cfg(test)]
+mod axis_order_tests;
+
+#[cfg(test)]
+mod human_coordinate_tests;
+
+#[cfg(test)]
+mod grid_reference_tests;
+
+#[cfg(test)]
+mod cell_region_tests;
+
+#[cfg(test)]
+mod tile_tests;
+
+#[cfg(test)]
+mod geocoder_tests;
+
+#[cfg(test)]
+mod report_tests;
+
+#[cfg(test)]
+mod metrics_tests;
+
+#[cfg(test)]
+mod memory_tests;
+
+#[cfg(test)]
+mod scratch_tests;
+
+#[cfg(test)]
+mod journal_tests;
+
+#[cfg(test)]
+mod atomic_tests;
+
+#[cfg(test)]
+mod lzw_tests;
+
+#[cfg(test)]
+mod jpeg_tests;
+
+#[cfg(test)]
+mod multi_output_tests;
+
+#[cfg(test)]
+mod crop_tests;
+
+#[cfg(test)]
+mod typed_array_tests;
+
+#[cfg(test)]
+mod split_bands_tests;
+
+#[cfg(test)]
+mod resize_tests;
+
+#[cfg(test)]
+mod overview_tests;
+
+#[cfg(test)]
+mod extend_tests;
+
+#[cfg(test)]
+mod orient_tests;
+
+#[cfg(test)]
+mod storage_tests;
+
+#[cfg(test)]
+mod acquisition_tests;
+
+#[cfg(test)]
+mod find_tests;
+
+#[cfg(test)]
+mod index_tests;
+
+#[cfg(test)]
+mod catalog_extract_tests;
+#[cfg(test)]
+mod convert_tests;
+
+#[cfg(test)]
+mod coverage_tests;
+
+#[cfg(test)]
+mod fillnodata_tests;
+
+#[cfg(test)]
+mod proximity_tests;
+
+#[cfg(test)]
+mod safetensors_tests;
+
+#[cfg(test)]
+mod postgis_tests;
+
+#[cfg(test)]
+mod sqlite_tests;
+
+#[cfg(test)]
+mod parquet_tests;
+
+#[cfg(test)]
+mod long_csv_tests;
+
+#[cfg(all(test, feature = "jpeg2000"))]
+mod jpeg2000_tests;
+
+#[cfg(test)]
+mod chips_tests;
+
+#[cfg(test)]
+mod normalize_tests;
+
+#[cfg(test)]
+mod array_coordinates_tests;
+
+#[cfg(test)]
+mod legend_tests;
+
+#[cfg(test)]
+mod classification_tests;
+
+#[cfg(test)]
+mod colorize_tests;
+
+#[cfg(test)]
+mod depalettize_tests;
+
+#[cfg(test)]
+mod extra_samples_tests;
+
+#[cfg(test)]
+mod icc_tests;
+
+#[cfg(test)]
+mod exif_tests;
+
+#[cfg(test)]
+mod sidecar_tests;
+
+#[cfg(test)]
+mod elevation_tests;
+
+#[cfg(test)]
+mod pixel_size_tests;
+
+#[cfg(test)]
+mod block_alignment_tests;
+
+#[cfg(test)]
+mod multi_region_tests;
+
+#[cfg(test)]
+mod output_pattern_tests;
+
+#[cfg(test)]
+mod provenance_tests;
+
+#[cfg(test)]
+mod sub_ifd_tests;
+
+#[cfg(test)]
+mod compression_registry_tests;
+
+#[cfg(test)]
+mod strategy_registry_tests;
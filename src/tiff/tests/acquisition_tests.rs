@@ -0,0 +1,50 @@
+//! Tests for reading acquisition times
+
+use crate::tiff::TiffReader;
+use crate::utils::acquisition_utils::{self, DateRange, Timestamp};
+use crate::utils::logger::Logger;
+use super::test_utils::TestTiff;
+
+/// 1x1 8-bit image written on 2024-01-05 with GDAL metadata saying it was
+/// acquired on 2023-06-14
+fn create_dated_file() -> Vec<u8> {
+    TestTiff::new(1, 1)
+        .ascii(306, "2024:01:05 08:00:00")
+        .ascii(42112, "<GDALMetadata>\n  <Item name=\"ACQUISITIONDATETIME\" domain=\"IMAGERY\">2023-06-14T10:32:05.25Z</Item>\n</GDALMetadata>")
+        .pixels(vec![7])
+        .build()
+}
+
+#[test]
+fn test_acquisition_prefers_gdal_metadata_over_date_time() {
+    let dir = std::env::temp_dir();
+    let logger = Logger::new(dir.join("rasterkit_acquisition_tests.log").to_str().unwrap()).unwrap();
+    let path = dir.join("rasterkit_acquisition.tif");
+    std::fs::write(&path, create_dated_file()).unwrap();
+    let path = path.to_str().unwrap().to_string();
+
+    let mut reader = TiffReader::new(&logger);
+    let tiff = reader.load(&path).unwrap();
+    let times = acquisition_utils::read_acquisition(&reader, tiff.main_ifd().unwrap());
+    assert_eq!(times.date_time.unwrap().to_string(), "2024-01-05T08:00:00");
+    assert_eq!(times.date_time_original, None);
+    assert_eq!(times.acquired().unwrap().to_string(), "2023-06-14T10:32:05");
+
+    let june = DateRange::from_string("2023-06-01,2023-06-14").unwrap();
+    let paths = vec![path];
+    assert_eq!(acquisition_utils::filter_by_acquisition(&paths, &june, &logger).unwrap(), paths);
+    let later = DateRange::from_string("2023-06-15,").unwrap();
+    assert!(acquisition_utils::filter_by_acquisition(&paths, &later, &logger).unwrap().is_empty());
+}
+
+#[test]
+fn test_timestamp_formats() {
+    let expected = Timestamp { year: 2023, month: 6, day: 14, hour: 10, minute: 32, second: 5 };
+    for text in ["2023:06:14 10:32:05", "2023-06-14T10:32:05", "2023-06-14T10:32:05.123+02:00", "2023-06-14 10:32:05-05:00\0"] {
+        assert_eq!(Timestamp::parse(text), Some(expected), "{}", text);
+    }
+    assert_eq!(Timestamp::parse("2023-06-14").unwrap().to_string(), "2023-06-14T00:00:00");
+    assert_eq!(Timestamp::parse("    :  :     :  :  "), None);
+    assert_eq!(Timestamp::parse("2023-13-01"), None);
+    assert!(DateRange::from_string("2023-06-30,2023-06-01").is_err());
+}
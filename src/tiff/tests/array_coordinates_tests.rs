@@ -0,0 +1,58 @@
+//! Tests for map coordinates in CSV and JSON array exports
+
+use crate::coordinate::Affine;
+use crate::extractor::{ArrayData, ArrayGeoreference, ArraySamples};
+
+/// A 3x2 UInt8 array in UTM with 10 m cells
+fn create_array(georeferenced: bool) -> ArrayData {
+    ArrayData {
+        width: 3,
+        height: 2,
+        data: ArraySamples::U8(vec![1, 2, 3, 4, 5, 6]),
+        bands: Vec::new(),
+        georeference: georeferenced.then(|| ArrayGeoreference {
+            geotransform: Affine::new(500000.0, 10.0, 4000000.0, -10.0),
+            epsg: Some(32633),
+        }),
+        nodata: None,
+    }
+}
+
+#[test]
+fn test_csv_labelled_with_cell_centers() {
+    let dir = std::env::temp_dir();
+    let path = dir.join("rasterkit_array_coordinates.csv");
+    create_array(true).save_with_coordinates(path.to_str().unwrap(), "csv").unwrap();
+    assert_eq!(std::fs::read_to_string(&path).unwrap(),
+               "y/x,500005,500015,500025\n3999995,1,2,3\n3999985,4,5,6\n");
+
+    // Without the option rows and columns keep their indices
+    create_array(true).save_to_file(path.to_str().unwrap(), "csv").unwrap();
+    assert_eq!(std::fs::read_to_string(&path).unwrap(), "row/col,0,1,2\n0,1,2,3\n1,4,5,6\n");
+
+    // Coordinates need a georeferenced array
+    let path = dir.join("rasterkit_array_coordinates_plain.csv");
+    assert!(create_array(false).save_with_coordinates(path.to_str().unwrap(), "csv").is_err());
+    assert!(!path.exists());
+}
+
+#[test]
+fn test_json_carries_geotransform_and_crs() {
+    let path = std::env::temp_dir().join("rasterkit_array_coordinates.json");
+
+    // The georeferencing block is always written for georeferenced arrays
+    create_array(true).save_to_file(path.to_str().unwrap(), "json").unwrap();
+    let json = std::fs::read_to_string(&path).unwrap();
+    assert!(json.contains("\"geotransform\": [500000, 10, 0, 4000000, 0, -10],"), "{}", json);
+    assert!(json.contains("\"crs\": \"EPSG:32633\","));
+    assert!(!json.contains("x_coordinates"));
+
+    create_array(true).save_with_coordinates(path.to_str().unwrap(), "json").unwrap();
+    let json = std::fs::read_to_string(&path).unwrap();
+    assert!(json.contains("\"x_coordinates\": [500005, 500015, 500025],"), "{}", json);
+    assert!(json.contains("\"y_coordinates\": [3999995, 3999985],"));
+    assert!(json.contains("[4, 5, 6]"));
+
+    create_array(false).save_to_file(path.to_str().unwrap(), "json").unwrap();
+    assert!(!std::fs::read_to_string(&path).unwrap().contains("geotransform"));
+}
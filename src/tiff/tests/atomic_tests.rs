@@ -0,0 +1,77 @@
+//! Tests for writing outputs through partial files
+
+use std::path::Path;
+
+use crate::tiff::errors::TiffError;
+use crate::utils::atomic_utils;
+
+#[test]
+fn test_partial_path_is_hidden_next_to_output() {
+    assert_eq!(atomic_utils::partial_path(Path::new("/data/out.tif")), Path::new("/data/.out.tif.part"));
+    assert_eq!(atomic_utils::partial_path(Path::new("out.tif")), Path::new(".out.tif.part"));
+}
+
+#[test]
+fn test_failed_write_keeps_previous_output() {
+    let output = std::env::temp_dir().join(format!("rasterkit-atomic-{}.txt", std::process::id()));
+    let output_str = output.to_str().unwrap();
+    let partial = atomic_utils::partial_path(&output);
+
+    atomic_utils::write_atomically(output_str, |path| {
+        assert_eq!(Path::new(path), partial);
+        std::fs::write(path, "first").map_err(TiffError::from)
+    }).unwrap();
+    assert_eq!(std::fs::read_to_string(&output).unwrap(), "first");
+    assert!(!partial.exists());
+
+    // A writer failing halfway leaves neither a partial file nor a truncated output
+    let result: Result<(), _> = atomic_utils::write_atomically(output_str, |path| {
+        std::fs::write(path, "sec")?;
+        Err(TiffError::GenericError("interrupted".to_string()))
+    });
+    assert!(result.is_err());
+    assert_eq!(std::fs::read_to_string(&output).unwrap(), "first");
+    assert!(!partial.exists());
+    std::fs::remove_file(&output).unwrap();
+}
+
+#[test]
+fn test_failed_array_outputs_leave_no_partial_file() {
+    use crate::extractor::{ArrayData, ArraySamples};
+
+    let dir = std::env::temp_dir().join(format!("rasterkit-atomic-arrays-{}", std::process::id()));
+    let _ = std::fs::remove_dir_all(&dir);
+    std::fs::create_dir_all(&dir).unwrap();
+    let array = ArrayData {
+        width: 2,
+        height: 1,
+        data: ArraySamples::U16(vec![100, 200]),
+        bands: Vec::new(),
+        georeference: None,
+        nodata: None,
+    };
+
+    // A directory in the way of the output fails the final rename
+    for (name, format) in [("pixels.sqlite", "sqlite"), ("pixels.gpkg", "gpkg"), ("tiles.sql", "pgsql"),
+                           ("pixels.parquet", "parquet"), ("band.dat", "envi")] {
+        let output = dir.join(name);
+        std::fs::create_dir_all(output.join("keep")).unwrap();
+        assert!(array.save_to_file(output.to_str().unwrap(), format).is_err(), "{}", format);
+        assert!(output.join("keep").is_dir());
+        let left: Vec<_> = std::fs::read_dir(&dir).unwrap().map(|entry| entry.unwrap().file_name()).collect();
+        assert_eq!(left, [std::ffi::OsString::from(name)], "{}", format);
+        std::fs::remove_dir_all(&output).unwrap();
+    }
+
+    // A header that cannot be moved into place leaves no partial file of the pair
+    let header = dir.join("band.hdr");
+    std::fs::create_dir_all(header.join("keep")).unwrap();
+    assert!(array.save_to_file(dir.join("band.dat").to_str().unwrap(), "envi").is_err());
+    assert!(!dir.join(".band.dat.part").exists() && !dir.join(".band.hdr.part").exists());
+    std::fs::remove_dir_all(&header).unwrap();
+
+    array.save_to_file(dir.join("band.dat").to_str().unwrap(), "envi").unwrap();
+    assert_eq!(std::fs::read(dir.join("band.dat")).unwrap(), [100, 0, 200, 0]);
+    assert!(std::fs::read_to_string(&header).unwrap().contains("data type = 12"));
+    std::fs::remove_dir_all(&dir).unwrap();
+}
@@ -0,0 +1,38 @@
+//! Tests for normalizing the axis order of user coordinates
+
+use crate::utils::axis_order_utils::{normalize_axis_order, AxisOrder};
+
+#[test]
+fn test_axis_order_names() {
+    assert_eq!(AxisOrder::from_name("lonlat").unwrap(), AxisOrder::LonLat);
+    assert_eq!(AxisOrder::from_name("Lat,Lon").unwrap(), AxisOrder::LatLon);
+    assert_eq!(AxisOrder::from_name("yx").unwrap(), AxisOrder::LatLon);
+    assert_eq!(AxisOrder::from_name("auto").unwrap(), AxisOrder::Auto);
+    assert!(AxisOrder::from_name("north-up").is_err());
+}
+
+#[test]
+fn test_explicit_axis_order() {
+    assert_eq!(normalize_axis_order("56.13484,-109.22624", AxisOrder::LatLon, true).unwrap(), "-109.22624,56.13484");
+    assert_eq!(normalize_axis_order("36.1,15.0,36.2,15.1", AxisOrder::LatLon, true).unwrap(), "15,36.1,15.1,36.2");
+    assert_eq!(normalize_axis_order("15.0, 36.1", AxisOrder::LonLat, true).unwrap(), "15,36.1");
+
+    // Latitude first by mistake, but told longitude first
+    assert!(normalize_axis_order("56.13484,-109.22624", AxisOrder::LonLat, true).is_err());
+}
+
+#[test]
+fn test_detected_axis_order() {
+    // Only fits latitude first
+    assert_eq!(normalize_axis_order("56.13484,-109.22624", AxisOrder::Auto, true).unwrap(), "-109.22624,56.13484");
+    // Fits both, read longitude first
+    assert_eq!(normalize_axis_order("36.14,15.006", AxisOrder::Auto, true).unwrap(), "36.14,15.006");
+    // Fits neither
+    assert!(normalize_axis_order("200,100", AxisOrder::Auto, true).is_err());
+
+    // Projected coordinates are left alone unless told
+    assert_eq!(normalize_axis_order("500000,4649776", AxisOrder::Auto, false).unwrap(), "500000,4649776");
+    assert_eq!(normalize_axis_order("4649776,500000", AxisOrder::LatLon, false).unwrap(), "500000,4649776");
+
+    assert!(normalize_axis_order("1,2,3", AxisOrder::Auto, true).is_err());
+}
@@ -0,0 +1,27 @@
+//! Tests for histogram matching
+
+use crate::utils::balance_utils;
+
+#[test]
+fn test_matching_lookup() {
+    // A dark image with half its pixels at 10 and half at 20
+    let mut source = vec![0u64; 256];
+    source[10] = 50;
+    source[20] = 50;
+
+    // A bright reference spread evenly over 100, 150 and 200
+    let mut reference = vec![0u64; 256];
+    reference[100] = 30;
+    reference[150] = 30;
+    reference[200] = 30;
+
+    let lookup = balance_utils::matching_lookup(&source, &reference);
+    assert_eq!(lookup.len(), 256);
+    assert_eq!(lookup[10], 150);
+    assert_eq!(lookup[20], 200);
+    assert_eq!(lookup[0], 0);
+
+    // Matching an image to itself changes nothing it holds
+    let identity = balance_utils::matching_lookup(&source, &source);
+    assert_eq!((identity[10], identity[20]), (10, 20));
+}
@@ -0,0 +1,54 @@
+//! Tests for snapping extraction windows to tile and strip boundaries
+
+use crate::extractor::Region;
+use crate::tiff::TiffReader;
+use crate::utils::logger::Logger;
+use crate::utils::tiff_extraction_utils;
+use super::test_utils::TestTiff;
+
+/// Position and size of a region
+fn bounds(region: Region) -> (u32, u32, u32, u32) {
+    (region.x, region.y, region.width, region.height)
+}
+
+#[test]
+fn test_regions_snap_to_tiles() {
+    let dir = std::env::temp_dir();
+    let logger = Logger::new(dir.join("rasterkit_block_alignment_tests.log").to_str().unwrap()).unwrap();
+    let path = dir.join("rasterkit_block_alignment_tiled.tif");
+    std::fs::write(&path, TestTiff::new(10, 6).tiles(4, 4, vec![vec![0; 16]; 6]).build()).unwrap();
+
+    let mut reader = TiffReader::new(&logger);
+    let tiff = reader.load(path.to_str().unwrap()).unwrap();
+    let ifd = tiff.main_ifd().unwrap();
+    assert_eq!(tiff_extraction_utils::block_size(ifd), Some((4, 4)));
+
+    // Expanded outwards to whole tiles
+    let aligned = tiff_extraction_utils::align_region_to_blocks(Region::new(5, 1, 2, 2), ifd).unwrap();
+    assert_eq!(bounds(aligned), (4, 0, 4, 4));
+    let aligned = tiff_extraction_utils::align_region_to_blocks(Region::new(3, 3, 2, 2), ifd).unwrap();
+    assert_eq!(bounds(aligned), (0, 0, 8, 6));
+
+    // Edge tiles are clipped to the image, and aligned regions stay put
+    let aligned = tiff_extraction_utils::align_region_to_blocks(Region::new(9, 5, 1, 1), ifd).unwrap();
+    assert_eq!(bounds(aligned), (8, 4, 2, 2));
+    let aligned = tiff_extraction_utils::align_region_to_blocks(Region::new(4, 0, 4, 4), ifd).unwrap();
+    assert_eq!(bounds(aligned), (4, 0, 4, 4));
+}
+
+#[test]
+fn test_regions_snap_to_strips() {
+    let dir = std::env::temp_dir();
+    let logger = Logger::new(dir.join("rasterkit_block_alignment_tests.log").to_str().unwrap()).unwrap();
+    let path = dir.join("rasterkit_block_alignment_stripped.tif");
+    std::fs::write(&path, TestTiff::new(5, 7).strips(3, vec![vec![0; 15], vec![0; 15], vec![0; 5]]).build()).unwrap();
+
+    let mut reader = TiffReader::new(&logger);
+    let tiff = reader.load(path.to_str().unwrap()).unwrap();
+    let ifd = tiff.main_ifd().unwrap();
+    assert_eq!(tiff_extraction_utils::block_size(ifd), Some((5, 3)));
+
+    // Strips span the full width
+    let aligned = tiff_extraction_utils::align_region_to_blocks(Region::new(1, 4, 2, 3), ifd).unwrap();
+    assert_eq!(bounds(aligned), (0, 3, 5, 4));
+}
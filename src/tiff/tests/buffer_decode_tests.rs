@@ -0,0 +1,79 @@
+//! Tests for decoding windows into caller-provided buffers and streaming them
+
+use crate::extractor::{self, BufferLayout, Region};
+use crate::utils::logger::Logger;
+use super::test_utils::TestTiff;
+
+/// 4x4 image of two 16-bit bands in two strips, sample = 100 * band + 10 * row + column
+fn create_two_band_file() -> Vec<u8> {
+    let strips = (0..2u16).map(|strip| {
+        (2 * strip..2 * strip + 2)
+            .flat_map(|row| (0..4u16).flat_map(move |column| (0..2u16).map(move |band| 100 * band + 10 * row + column)))
+            .flat_map(u16::to_le_bytes)
+            .collect()
+    }).collect();
+    TestTiff::new(4, 4).bits(&[16, 16]).planar(1).strips(2, strips).build()
+}
+
+#[test]
+fn test_decode_window_into_buffers() {
+    let dir = std::env::temp_dir();
+    let logger = Logger::new(dir.join("rasterkit_buffer_tests.log").to_str().unwrap()).unwrap();
+    let path = dir.join("rasterkit_buffer_decode.tif");
+    std::fs::write(&path, create_two_band_file()).unwrap();
+    let path = path.to_str().unwrap();
+
+    // A 2x3 window across both strips
+    let window = Some(Region::new(1, 1, 2, 3));
+    let shape = extractor::describe_window(path, window, &logger).unwrap();
+    assert_eq!((shape.width, shape.height, shape.bands, shape.bytes_per_sample), (2, 3, 2, 2));
+
+    let mut values = vec![0f32; shape.samples()];
+    extractor::decode_into_f32(path, window, BufferLayout::Interleaved, &mut values, &logger).unwrap();
+    assert_eq!(&values[..4], &[11.0, 111.0, 12.0, 112.0]);
+    assert_eq!(values[11], 132.0);
+
+    extractor::decode_into_f32(path, window, BufferLayout::BandSequential, &mut values, &logger).unwrap();
+    assert_eq!(values, [11.0, 12.0, 21.0, 22.0, 31.0, 32.0, 111.0, 112.0, 121.0, 122.0, 131.0, 132.0]);
+
+    let mut bytes = vec![0u8; shape.byte_len()];
+    extractor::decode_into_bytes(path, window, BufferLayout::BandSequential, &mut bytes, &logger).unwrap();
+    assert_eq!(u16::from_ne_bytes([bytes[14], bytes[15]]), 112);
+
+    // Buffers too small and windows outside the image are refused
+    assert!(extractor::decode_into_f32(path, window, BufferLayout::Interleaved, &mut values[..5], &logger).is_err());
+    assert!(extractor::describe_window(path, Some(Region::new(3, 0, 2, 1)), &logger).is_err());
+}
+
+#[test]
+fn test_extract_windowed_streams_clipped_blocks() {
+    let dir = std::env::temp_dir();
+    let logger = Logger::new(dir.join("rasterkit_buffer_tests.log").to_str().unwrap()).unwrap();
+    let path = dir.join("rasterkit_windowed.tif");
+    std::fs::write(&path, create_two_band_file()).unwrap();
+    let path = path.to_str().unwrap();
+
+    // The window spans both strips, so it arrives in two pieces
+    let mut regions = Vec::new();
+    let mut corner = 0;
+    let shape = extractor::extract_windowed(path, Some(Region::new(1, 1, 2, 3)), &logger, |shape, block| {
+        assert_eq!(shape.bytes_per_sample, block.bytes_per_sample);
+        regions.push((block.region.x, block.region.y, block.region.width, block.region.height));
+        if block.region.end_y() == 4 {
+            corner = u16::from_ne_bytes(block.sample(2, 3, 1).try_into().unwrap());
+        }
+        Ok(())
+    }).unwrap();
+    assert_eq!((shape.width, shape.height), (2, 3));
+    assert_eq!(regions, [(1, 1, 2, 1), (1, 2, 2, 2)]);
+    assert_eq!(corner, 132);
+
+    // An error from the callback stops the stream
+    let mut calls = 0;
+    let result = extractor::extract_windowed(path, None, &logger, |_, _| {
+        calls += 1;
+        Err(crate::tiff::errors::TiffError::GenericError("enough".to_string()))
+    });
+    assert!(result.is_err());
+    assert_eq!(calls, 1);
+}
@@ -5,6 +5,9 @@ extern crate std;
 use std::io::Cursor;
 use byteorder::{LittleEndian, BigEndian, WriteBytesExt};
 use crate::io::byte_order::{ByteOrder, ByteOrderHandler, LittleEndianHandler, BigEndianHandler};
+use crate::tiff::constants::field_types;
+use crate::tiff::ifd::IFDEntry;
+use crate::tiff::tag_value::{TagType, TagValue};
 
 #[test]
 fn test_byte_order_detection_little_endian() {
@@ -66,4 +69,27 @@ fn test_big_endian_handler() {
     std::assert_eq!(handler.read_u16(&mut cursor).unwrap(), 0x1234);
     std::assert_eq!(handler.read_u32(&mut cursor).unwrap(), 0x12345678);
     std::assert_eq!(handler.read_u64(&mut cursor).unwrap(), 0x1234567890ABCDEF);
-}
\ No newline at end of file
+}
+
+#[test]
+fn test_inline_tag_value_keeps_file_byte_order() {
+    // Two SSHORTs (-2, 7) stored inline in a big-endian classic TIFF, as read into value_offset
+    let entry = IFDEntry::new(33000, field_types::SSHORT, 2, 0xFFFE_0007);
+    let bytes = TagValue::inline_bytes(&entry, ByteOrder::BigEndian, false);
+    let value = TagValue::decode(entry.field_type, entry.count, &bytes, &BigEndianHandler).unwrap();
+    std::assert_eq!(value, TagValue::SShort(vec![-2, 7]));
+    std::assert_eq!(value.format(None), "-2 7");
+}
+
+#[test]
+fn test_tag_values_convert_to_requested_type() {
+    let shorts = TagValue::Short(vec![1, 300]);
+    std::assert_eq!(u64::from_tag_value(&shorts).unwrap(), vec![1, 300]);
+    std::assert!(u8::from_tag_value(&shorts).is_err());
+
+    let rationals = TagValue::Rational(vec![(72, 1), (1, 4)]);
+    std::assert_eq!(f64::from_tag_value(&rationals).unwrap(), vec![72.0, 0.25]);
+
+    let ascii = TagValue::Ascii(b"WGS 84|\0NAD83\0".to_vec());
+    std::assert_eq!(String::from_tag_value(&ascii).unwrap(), vec!["WGS 84|".to_string(), "NAD83".to_string()]);
+}
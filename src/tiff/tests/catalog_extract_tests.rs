@@ -0,0 +1,92 @@
+//! Tests for extracting a bounding box across a catalog of rasters
+
+use crate::coordinate::BoundingBox;
+use crate::tiff::proj_definition::ProjDefinition;
+use crate::utils::catalog_extract_utils;
+use crate::utils::grid_utils::Raster;
+use crate::utils::index_utils::{self, IndexEntry};
+use crate::utils::logger::Logger;
+use crate::utils::mosaic_utils::BlendMode;
+use crate::utils::sqlite_utils::{self, SqlValue};
+use super::test_utils::create_utm_tiff;
+
+#[test]
+fn test_catalog_extraction_mosaics_covering_tiles() {
+    let dir = std::env::temp_dir().join("rasterkit_catalog_extract");
+    let _ = std::fs::remove_dir_all(&dir);
+    std::fs::create_dir_all(dir.join("tiles")).unwrap();
+    let logger = Logger::new(dir.join("extract.log").to_str().unwrap()).unwrap();
+    std::fs::write(dir.join("tiles").join("west.tif"), create_utm_tiff(500000.0)).unwrap();
+    std::fs::write(dir.join("tiles").join("east.tif"), create_utm_tiff(500060.0)).unwrap();
+    let tiles = dir.join("tiles");
+    let index = dir.join("index.gpkg");
+    index_utils::build_index(tiles.to_str().unwrap(), index.to_str().unwrap(), None, &logger).unwrap();
+    assert!(catalog_extract_utils::is_catalog(index.to_str().unwrap()));
+    assert!(catalog_extract_utils::is_catalog(tiles.to_str().unwrap()));
+
+    // The box straddles the seam: the right column of the western tile
+    // and the left column of the eastern one
+    let utm = ProjDefinition::from_epsg(32633).unwrap();
+    let bbox = BoundingBox::new(500040.0, 3999950.0, 500080.0, 3999990.0);
+    for catalog in [&index, &tiles] {
+        let output = dir.join("seam.tif");
+        let count = catalog_extract_utils::extract_from_catalog(
+            catalog.to_str().unwrap(), &bbox, &utm, output.to_str().unwrap(), BlendMode::Last, &logger).unwrap();
+        assert_eq!(count, 2);
+        let raster = Raster::read(output.to_str().unwrap(), 1, &logger).unwrap();
+        assert_eq!((raster.layout.width, raster.layout.height), (2, 2));
+        assert_eq!(raster.bands[0], [2.0, 1.0, 4.0, 3.0]);
+        assert_eq!(raster.georeferencing.unwrap().geotransform.origin_x, 500030.0);
+    }
+
+    // A box entirely east of both tiles finds nothing
+    let far = BoundingBox::new(600000.0, 3999000.0, 601000.0, 4000000.0);
+    assert!(catalog_extract_utils::extract_from_catalog(
+        index.to_str().unwrap(), &far, &utm, dir.join("far.tif").to_str().unwrap(), BlendMode::Last, &logger).is_err());
+}
+
+#[test]
+fn test_catalog_selection_and_index_reading() {
+    let entry = |path: &str, epsg: u32, min_x: f64| IndexEntry {
+        path: path.to_string(),
+        epsg: Some(epsg),
+        bounds: Some((min_x, 3999940.0, min_x + 60.0, 4000000.0)),
+    };
+    let entries = [entry("a.tif", 32633, 500000.0), entry("b.tif", 32633, 500060.0),
+                   entry("c.tif", 32633, 700000.0), entry("d.tif", 32634, 500000.0)];
+
+    // A box in longitude/latitude near 15 degrees east falls in zone 33 only
+    let wgs84 = ProjDefinition::from_epsg(4326).unwrap();
+    let bbox = BoundingBox::new(15.0002, 36.1443, 15.0010, 36.1446);
+    let selection = catalog_extract_utils::select_covering(&entries, &bbox, &wgs84).unwrap().unwrap();
+    assert_eq!(selection.epsg, 32633);
+    assert_eq!(selection.paths, ["a.tif", "b.tif"]);
+    assert!(selection.window.0 > 500000.0 && selection.window.2 < 500120.0);
+
+    // Index properties are read whichever tool wrote them
+    let geojson = r#"{"type": "FeatureCollection", "features": [
+        {"type": "Feature", "properties": {"path": "tiles/a \"1\".tif", "epsg": 32633, "bounds": [1, 2, 3, 4]}, "geometry": null},
+        {"type": "Feature", "properties": {"location": "/data/b.tif"}, "geometry": null}]}"#;
+    let parsed = index_utils::parse_geojson_index(geojson);
+    assert_eq!(parsed, [
+        IndexEntry { path: "tiles/a \"1\".tif".to_string(), epsg: Some(32633), bounds: Some((1.0, 2.0, 3.0, 4.0)) },
+        IndexEntry { path: "/data/b.tif".to_string(), epsg: None, bounds: None },
+    ]);
+
+    // Tables written here read back, the rowid filling the key column
+    let dir = std::env::temp_dir().join("rasterkit_catalog_sqlite");
+    std::fs::create_dir_all(&dir).unwrap();
+    let path = dir.join("rows.sqlite");
+    let mut writer = sqlite_utils::SqliteWriter::new();
+    writer.add_table("rows", "CREATE TABLE rows (id INTEGER PRIMARY KEY, name TEXT, \"size\" REAL, CHECK (size > 0))",
+                     vec![(1, vec![SqlValue::Null, SqlValue::Text("one".to_string()), SqlValue::Real(1.5)]),
+                          (2, vec![SqlValue::Null, SqlValue::Null, SqlValue::Integer(-300)])]).unwrap();
+    writer.write(path.to_str().unwrap()).unwrap();
+    let (columns, rows) = sqlite_utils::read_table(path.to_str().unwrap(), "rows").unwrap();
+    assert_eq!(columns, ["id", "name", "size"]);
+    assert_eq!(rows, [
+        vec![SqlValue::Integer(1), SqlValue::Text("one".to_string()), SqlValue::Real(1.5)],
+        vec![SqlValue::Integer(2), SqlValue::Null, SqlValue::Integer(-300)],
+    ]);
+    assert!(sqlite_utils::read_table(path.to_str().unwrap(), "missing").is_err());
+}
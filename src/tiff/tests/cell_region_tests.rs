@@ -0,0 +1,50 @@
+//! Tests for geohash and H3 cell regions
+
+use crate::utils::cell_utils::{cell_bbox, geohash_bounds, h3_bounds};
+use crate::utils::region_utils::{read_regions_file, RegionSpec};
+
+#[test]
+fn test_geohash_bounds() {
+    let bounds = geohash_bounds("u").unwrap();
+    assert_eq!((bounds.min_x, bounds.min_y, bounds.max_x, bounds.max_y), (0.0, 45.0, 45.0, 90.0));
+    assert_eq!(bounds.epsg, Some(4326));
+
+    let bounds = geohash_bounds("ezs42").unwrap();
+    assert_eq!((bounds.min_x, bounds.min_y), (-5.625, 42.583_007_812_5));
+    assert_eq!((bounds.max_x, bounds.max_y), (-5.581_054_687_5, 42.626_953_125));
+
+    assert!(geohash_bounds("ezs4a").is_err());
+    assert!(geohash_bounds("").is_err());
+    assert!(geohash_bounds("ezs42ezs42ezs").is_err());
+}
+
+#[test]
+fn test_h3_bounds() {
+    let bounds = h3_bounds("8928308280fffff").unwrap();
+    // The cell holds its centre in San Francisco and is about 350 m across
+    assert!(bounds.min_x < -122.4185 && bounds.max_x > -122.4185);
+    assert!(bounds.min_y < 37.7767 && bounds.max_y > 37.7767);
+    assert!(bounds.width() < 0.01 && bounds.height() < 0.01);
+
+    assert!(h3_bounds("8928308280fffzz").is_err());
+    assert!(h3_bounds("0").is_err());
+}
+
+#[test]
+fn test_cell_ids() {
+    assert_eq!(cell_bbox("u").unwrap(), "0,45,45,90");
+    assert_eq!(cell_bbox("geohash:U").unwrap(), "0,45,45,90");
+    assert_eq!(cell_bbox("h3:8928308280fffff").unwrap(), cell_bbox("8928308280fffff").unwrap());
+}
+
+#[test]
+fn test_cells_in_regions_file() {
+    let path = std::env::temp_dir().join("rasterkit_cell_regions.txt");
+    std::fs::write(&path, "# work units\nu33dc0\n8928308280fffff\n1,2\n").unwrap();
+    let regions = read_regions_file(path.to_str().unwrap()).unwrap();
+    assert_eq!(regions, vec![
+        RegionSpec::Cell("u33dc0".to_string()),
+        RegionSpec::Cell("8928308280fffff".to_string()),
+        RegionSpec::Coordinate("1,2".to_string()),
+    ]);
+}
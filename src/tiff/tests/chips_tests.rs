@@ -0,0 +1,99 @@
+//! Tests for cutting a raster into training chips
+
+use std::path::Path;
+
+use crate::utils::chip_utils::{self, ChipOptions};
+use crate::utils::logger::Logger;
+use super::test_utils::TestTiff;
+
+/// Header dictionary and data of an NPY file
+pub(super) fn read_npy(path: &Path) -> (String, Vec<u8>) {
+    let bytes = std::fs::read(path).unwrap();
+    assert_eq!(&bytes[..6], b"\x93NUMPY");
+    let header_len = u16::from_le_bytes([bytes[8], bytes[9]]) as usize;
+    let header = std::str::from_utf8(&bytes[10..10 + header_len]).unwrap().trim_end().to_string();
+    (header, bytes[10 + header_len..].to_vec())
+}
+
+#[test]
+fn test_chip_offsets_cover_the_edge() {
+    assert_eq!(chip_utils::chip_offsets(10, 4, 4), [0, 4, 6]);
+    assert_eq!(chip_utils::chip_offsets(8, 4, 4), [0, 4]);
+    assert_eq!(chip_utils::chip_offsets(10, 4, 3), [0, 3, 6]);
+    assert_eq!(chip_utils::chip_offsets(3, 4, 4), [0]);
+}
+
+#[test]
+fn test_chips_with_labels_and_index() {
+    let dir = std::env::temp_dir();
+    let logger = Logger::new(dir.join("rasterkit_chips_tests.log").to_str().unwrap()).unwrap();
+    let input = dir.join("rasterkit_chips_image.tif");
+    std::fs::write(&input, TestTiff::new(5, 3).georeference(10.0, 10.0, 1000.0, 2000.0).epsg(32633)
+        .pixels((1..=15).collect()).build()).unwrap();
+    let label = dir.join("rasterkit_chips_label.tif");
+    std::fs::write(&label, TestTiff::new(5, 3).pixels((0..15).map(|index| (index % 3) as u8).collect()).build()).unwrap();
+
+    let output = dir.join("rasterkit_chips");
+    let _ = std::fs::remove_dir_all(&output);
+    let options = ChipOptions {
+        chip_size: 2,
+        format: "npy".to_string(),
+        label_path: Some(label.to_str().unwrap().to_string()),
+        ..Default::default()
+    };
+    let count = chip_utils::export_chips(input.to_str().unwrap(), output.to_str().unwrap(), &options, &logger).unwrap();
+    assert_eq!(count, 6);
+
+    // An extra chip is aligned with the right and bottom edges
+    let index = std::fs::read_to_string(output.join("index.csv")).unwrap();
+    let lines: Vec<&str> = index.lines().collect();
+    assert_eq!(lines[0], "chip_id,image_path,label_path,x,y,width,height,min_x,min_y,max_x,max_y");
+    assert_eq!(lines.len(), 7);
+    assert_eq!(lines[6], "chip_00001_00003,images/chip_00001_00003.npy,labels/chip_00001_00003.npy,3,1,2,2,1030,1970,1050,1990");
+
+    let (header, data) = read_npy(&output.join("images").join("chip_00001_00003.npy"));
+    assert!(header.contains("'descr': '<u1'") && header.contains("'shape': (2, 2)"), "{}", header);
+    assert_eq!(data, [9, 10, 14, 15]);
+    let (_, labels) = read_npy(&output.join("labels").join("chip_00001_00003.npy"));
+    assert_eq!(labels, [2, 0, 1, 2]);
+    std::fs::remove_dir_all(&output).unwrap();
+
+    // A raster smaller than a chip is padded with zeros
+    let options = ChipOptions { chip_size: 4, format: "npy".to_string(), ..Default::default() };
+    assert_eq!(chip_utils::export_chips(input.to_str().unwrap(), output.to_str().unwrap(), &options, &logger).unwrap(), 2);
+    let (_, data) = read_npy(&output.join("images").join("chip_00000_00001.npy"));
+    assert_eq!(data, [2, 3, 4, 5, 7, 8, 9, 10, 12, 13, 14, 15, 0, 0, 0, 0]);
+    assert!(!output.join("labels").exists());
+    std::fs::remove_dir_all(&output).unwrap();
+
+    // Overlap must leave a positive stride
+    let options = ChipOptions { chip_size: 2, overlap: 2, format: "npy".to_string(), ..Default::default() };
+    assert!(chip_utils::export_chips(input.to_str().unwrap(), output.to_str().unwrap(), &options, &logger).is_err());
+}
+
+#[test]
+fn test_lossy_chips_keep_lossless_labels() {
+    let dir = std::env::temp_dir();
+    let logger = Logger::new(dir.join("rasterkit_chips_tests.log").to_str().unwrap()).unwrap();
+    let input = dir.join("rasterkit_chips_webp_image.tif");
+    std::fs::write(&input, TestTiff::new(4, 4).pixels((0..16).map(|value| value * 16).collect()).build()).unwrap();
+    let label = dir.join("rasterkit_chips_webp_label.tif");
+    std::fs::write(&label, TestTiff::new(4, 4).pixels(vec![1; 16]).build()).unwrap();
+
+    let output = dir.join("rasterkit_chips_webp");
+    let _ = std::fs::remove_dir_all(&output);
+    let options = ChipOptions {
+        chip_size: 4,
+        format: "webp".to_string(),
+        label_path: Some(label.to_str().unwrap().to_string()),
+        quality: Some(60),
+        ..Default::default()
+    };
+    assert_eq!(chip_utils::export_chips(input.to_str().unwrap(), output.to_str().unwrap(), &options, &logger).unwrap(), 1);
+
+    // The quality makes image chips lossy, so labels are written as PNG
+    let chip = std::fs::read(output.join("images").join("chip_00000_00000.webp")).unwrap();
+    assert_eq!(&chip[8..16], b"WEBPVP8 ");
+    assert!(output.join("labels").join("chip_00000_00000.png").exists());
+    std::fs::remove_dir_all(&output).unwrap();
+}
@@ -0,0 +1,90 @@
+//! Tests for colormaps generated from the value distribution
+
+use crate::RasterKit;
+use crate::tiff::colormap::ColorMap;
+use crate::utils::classification_utils::{self, ClassificationMethod, ColorScheme, ValueHistogram};
+use super::test_utils::TestTiff;
+
+/// Histogram of the values, each repeated the given number of times
+fn histogram(values: &[(u8, usize)]) -> ValueHistogram {
+    let expanded: Vec<u8> = values.iter().flat_map(|&(value, count)| std::iter::repeat_n(value, count)).collect();
+    ValueHistogram::from_values(&expanded, None)
+}
+
+#[test]
+fn test_class_breaks_by_method() {
+    let uniform = ValueHistogram::from_values(&(0..=100).collect::<Vec<u8>>(), None);
+    assert_eq!(classification_utils::class_breaks(&uniform, 4, ClassificationMethod::EqualInterval).unwrap(),
+               [25, 50, 75, 100]);
+
+    // Quantiles follow the pixel counts, not the value range
+    let skewed = histogram(&[(1, 90), (200, 10)]);
+    assert_eq!(classification_utils::class_breaks(&skewed, 2, ClassificationMethod::Quantile).unwrap(), [1, 200]);
+    assert_eq!(classification_utils::class_breaks(&skewed, 2, ClassificationMethod::EqualInterval).unwrap(), [101, 200]);
+
+    // Jenks splits between the clusters
+    let clusters = histogram(&[(10, 5), (11, 3), (12, 5), (100, 2), (101, 4), (102, 2), (200, 6), (201, 6)]);
+    assert_eq!(classification_utils::class_breaks(&clusters, 3, ClassificationMethod::Jenks).unwrap(), [12, 102, 201]);
+
+    // Sparse data gives fewer classes, no data none at all
+    assert_eq!(classification_utils::class_breaks(&histogram(&[(7, 4)]), 5, ClassificationMethod::Quantile).unwrap(), [7]);
+    assert!(classification_utils::class_breaks(&ValueHistogram::from_values(&[0, 0], Some(0)), 3,
+                                               ClassificationMethod::Jenks).is_err());
+}
+
+#[test]
+fn test_colormap_from_statistics() {
+    let uniform = ValueHistogram::from_values(&(0..=100).collect::<Vec<u8>>(), None);
+    let colormap = ColorMap::from_statistics(&uniform, 2, ClassificationMethod::EqualInterval, ColorScheme::Greys).unwrap();
+
+    // Interval entries color the values below them and are labeled with the class range
+    assert_eq!(colormap.map_type, "intervals");
+    let entries: Vec<(u16, Option<&str>)> = colormap.entries.iter()
+        .map(|entry| (entry.value, entry.label.as_deref())).collect();
+    assert_eq!(entries, [(51, Some("0 - 50")), (101, Some("51 - 100"))]);
+    assert_eq!(colormap.entries[0].color, ColorScheme::Greys.color_at(0.0));
+    assert_eq!(colormap.entries[1].color, ColorScheme::Greys.color_at(1.0));
+}
+
+#[test]
+fn test_auto_colormap_specification() {
+    assert_eq!(classification_utils::parse_auto_spec("quantile:7:viridis").unwrap(),
+               (ClassificationMethod::Quantile, 7, ColorScheme::Viridis));
+    assert_eq!(classification_utils::parse_auto_spec("Jenks").unwrap(),
+               (ClassificationMethod::Jenks, 5, ColorScheme::Viridis));
+    assert_eq!(classification_utils::parse_auto_spec("equal:3:RdYlGn").unwrap(),
+               (ClassificationMethod::EqualInterval, 3, ColorScheme::RdYlGn));
+
+    for spec in ["quantile:0", "quantile:seven", "quantile:7:rainbow", "median", "quantile:7:viridis:x"] {
+        assert!(classification_utils::parse_auto_spec(spec).is_err(), "{}", spec);
+    }
+}
+
+#[test]
+fn test_generate_colormap_from_native_values() {
+    let dir = std::env::temp_dir();
+    let kit = RasterKit::new(Some(dir.join("rasterkit_classification_tests.log").to_str().unwrap())).unwrap();
+    let output = dir.join("rasterkit_classification_u16.sld");
+
+    // 16-bit values are classified as they are, not clamped to 255
+    let input = dir.join("rasterkit_classification_u16.tif");
+    std::fs::write(&input, TestTiff::new(4, 1).bits(&[16]).nodata("65535")
+        .pixels([100u16, 1000, 4000, 65535].iter().flat_map(|value| value.to_le_bytes()).collect()).build()).unwrap();
+    kit.generate_colormap(input.to_str().unwrap(), output.to_str().unwrap(), "equal:2:greys", None).unwrap();
+    let colormap = ColorMap::from_sld_file(&output).unwrap();
+    let values: Vec<u16> = colormap.entries.iter().map(|entry| entry.value).collect();
+    assert_eq!(values, [2051, 4001]);
+
+    // NoData outside 0..=255 is left out, fractional values cannot be classified
+    let floats = |values: [f32; 3]| values.iter().flat_map(|value| value.to_le_bytes()).collect();
+    let input = dir.join("rasterkit_classification_f32.tif");
+    std::fs::write(&input, TestTiff::new(3, 1).bits(&[32]).sample_format(3).nodata("-9999")
+        .pixels(floats([300.0, -9999.0, 700.0])).build()).unwrap();
+    kit.generate_colormap(input.to_str().unwrap(), output.to_str().unwrap(), "equal:2:greys", None).unwrap();
+    let colormap = ColorMap::from_sld_file(&output).unwrap();
+    assert_eq!(colormap.entries.iter().map(|entry| entry.value).collect::<Vec<_>>(), [501, 701]);
+
+    std::fs::write(&input, TestTiff::new(3, 1).bits(&[32]).sample_format(3)
+        .pixels(floats([0.25, 0.5, 0.75])).build()).unwrap();
+    assert!(kit.generate_colormap(input.to_str().unwrap(), output.to_str().unwrap(), "equal:2:greys", None).is_err());
+}
@@ -0,0 +1,50 @@
+//! Tests for colorizing a whole raster with a colormap
+
+use image::GenericImageView;
+
+use crate::RasterKit;
+use crate::extractor::ImageExtractor;
+use crate::tiff::TiffReader;
+use crate::utils::logger::Logger;
+use super::test_utils::TestTiff;
+
+#[test]
+fn test_colorize_keeps_georeferencing() {
+    let dir = std::env::temp_dir();
+    let log = dir.join("rasterkit_colorize_tests.log");
+    let kit = RasterKit::new(Some(log.to_str().unwrap())).unwrap();
+    let logger = Logger::new(log.to_str().unwrap()).unwrap();
+
+    let input = dir.join("rasterkit_colorize_classes.tif");
+    std::fs::write(&input, TestTiff::new(3, 2).georeference(30.0, 30.0, 500000.0, 4000000.0).epsg(32633)
+        .pixels(vec![0, 1, 2, 2, 1, 0]).build()).unwrap();
+    let colormap = dir.join("rasterkit_colorize_classes.csv");
+    std::fs::write(&colormap, "0,#ff0000\n1,#00ff00\n2,#0000ff\n").unwrap();
+
+    let output = dir.join("rasterkit_colorize_classes_rgb.tif");
+    kit.colorize(input.to_str().unwrap(), output.to_str().unwrap(), colormap.to_str().unwrap()).unwrap();
+
+    let image = ImageExtractor::new(&logger).extract_image(output.to_str().unwrap(), None).unwrap().to_rgb8();
+    assert_eq!(image.dimensions(), (3, 2));
+    let pixels: Vec<[u8; 3]> = image.pixels().map(|pixel| pixel.0).collect();
+    assert_eq!(pixels, [[255, 0, 0], [0, 255, 0], [0, 0, 255], [0, 0, 255], [0, 255, 0], [255, 0, 0]]);
+
+    let mut reader = TiffReader::new(&logger);
+    let tiff = reader.load(output.to_str().unwrap()).unwrap();
+    let ifd = tiff.main_ifd().unwrap();
+    assert_eq!(ifd.get_samples_per_pixel(), 3);
+    let tiepoint = reader.read_tag::<f64>(ifd, 33922).unwrap();
+    assert_eq!((tiepoint[3], tiepoint[4]), (500000.0, 4000000.0));
+    assert_eq!(reader.read_tag::<f64>(ifd, 33550).unwrap()[..2], [30.0, 30.0]);
+
+    // Other extensions are written as plain images
+    let png = dir.join("rasterkit_colorize_classes.png");
+    kit.colorize(input.to_str().unwrap(), png.to_str().unwrap(), colormap.to_str().unwrap()).unwrap();
+    let image = image::open(&png).unwrap();
+    assert_eq!(image.get_pixel(2, 0).0[..3], [0, 0, 255]);
+
+    // A missing colormap is reported before anything is written
+    let missing = dir.join("rasterkit_colorize_missing.tif");
+    assert!(kit.colorize(input.to_str().unwrap(), missing.to_str().unwrap(), "no_such_colormap.csv").is_err());
+    assert!(!missing.exists());
+}
@@ -0,0 +1,160 @@
+//! Tests for the TIFF colormap module
+
+extern crate std;
+
+use crate::RasterKit;
+use crate::tiff::colormap::{ColorMap, ColorMapEntry, RgbColor};
+use super::test_utils::TestTiff;
+
+#[test]
+fn test_tiff_colormap_keeps_16bit_palette_colors() {
+    let mut colormap = ColorMap::new();
+    colormap.set_type("values");
+    // Both collapse to the same 8-bit color but must stay distinct in the palette
+    colormap.add_entry(ColorMapEntry::from_rgb16(0, [0x1234, 0x5678, 0x9abc]));
+    colormap.add_entry(ColorMapEntry::from_rgb16(1, [0x1200, 0x5600, 0x9a00]));
+    colormap.add_entry(ColorMapEntry::new(2, RgbColor::new(255, 128, 0)));
+
+    let (num_entries, data) = colormap.to_tiff_colormap();
+    std::assert_eq!(num_entries, 4);
+
+    let n = num_entries as usize;
+    let color = |i: usize| [data[i], data[n + i], data[2 * n + i]];
+    std::assert_eq!(color(0), [0x1234, 0x5678, 0x9abc]);
+    std::assert_eq!(color(1), [0x1200, 0x5600, 0x9a00]);
+    std::assert_eq!(color(2), [255 * 257, 128 * 257, 0]);
+}
+
+#[test]
+fn test_edited_entry_falls_back_to_8bit_color() {
+    let mut entry = ColorMapEntry::from_rgb16(0, [0x1234, 0x5678, 0x9abc]);
+    entry.color = RgbColor::new(1, 2, 3);
+    std::assert_eq!(entry.tiff_color(), [257, 514, 771]);
+}
+
+#[test]
+fn test_extract_colormap_keeps_or_trims_leading_black() {
+    // 2x1 palette image whose first two entries are black, then red and green
+    let mut palette = std::vec![0u16; 3 * 256];
+    palette[2] = 65535;
+    palette[256 + 3] = 65535;
+    let file = TestTiff::new(2, 1).photometric(3).shorts(320, &palette).pixels(std::vec![2, 3]).build();
+
+    let dir = std::env::temp_dir();
+    let input = dir.join("rasterkit_colormap_palette.tif");
+    std::fs::write(&input, file).unwrap();
+    let kit = RasterKit::new(Some(dir.join("rasterkit_colormap_tests.log").to_str().unwrap())).unwrap();
+    let extract = |trim: Option<bool>| {
+        let output = dir.join("rasterkit_colormap_palette.sld");
+        match trim {
+            None => kit.extract_colormap(input.to_str().unwrap(), output.to_str().unwrap()).unwrap(),
+            Some(trim) => kit.extract_colormap_with_options(input.to_str().unwrap(), output.to_str().unwrap(), trim).unwrap(),
+        }
+        std::fs::read_to_string(output).unwrap()
+    };
+
+    // Every index is kept unless trimming is asked for
+    let kept = extract(None);
+    std::assert_eq!(kept, extract(Some(false)));
+    std::assert!(kept.contains("quantity=\"0\""));
+    let trimmed = extract(Some(true));
+    std::assert!(!trimmed.contains("quantity=\"0\""));
+    std::assert!(trimmed.contains("quantity=\"2\""));
+}
+
+#[test]
+fn test_colors_parse_with_alpha_shorthand_and_names() {
+    let parse = |text: &str| RgbColor::parse_rgba(text).unwrap();
+    std::assert_eq!(parse("#ff000080"), (RgbColor::new(255, 0, 0), 0x80));
+    std::assert_eq!(parse("#0F0"), (RgbColor::new(0, 255, 0), 255));
+    std::assert_eq!(parse("#00f8"), (RgbColor::new(0, 0, 255), 0x88));
+    std::assert_eq!(parse("a0b0c0"), (RgbColor::new(0xa0, 0xb0, 0xc0), 255));
+    std::assert_eq!(parse("SteelBlue"), (RgbColor::new(70, 130, 180), 255));
+    std::assert_eq!(parse("transparent").1, 0);
+    for invalid in ["#12345", "#ggg", "notacolor", ""] {
+        std::assert!(RgbColor::parse_rgba(invalid).is_err(), "{}", invalid);
+    }
+
+    let entry = ColorMapEntry::from_hex_color(3, "#11223344", None).unwrap();
+    std::assert_eq!((entry.alpha, entry.to_rgba_hex(), entry.to_hex_color()),
+                    (0x44, "#11223344".to_string(), "#112233".to_string()));
+}
+
+#[test]
+fn test_csv_and_sld_colormaps_keep_alpha() {
+    let csv = "0,#ff000080,Cloud\n1,forestgreen\n2,#abc\n3,notacolor\n";
+    let colormap = ColorMap::from_csv_reader(csv.as_bytes()).unwrap();
+    let entries: std::vec::Vec<(u16, RgbColor, u8)> = colormap.entries.iter()
+        .map(|entry| (entry.value, entry.color, entry.alpha)).collect();
+    std::assert_eq!(entries, [(0, RgbColor::new(255, 0, 0), 0x80), (1, RgbColor::new(34, 139, 34), 255),
+                              (2, RgbColor::new(0xaa, 0xbb, 0xcc), 255)]);
+
+    // SLD carries the alpha as opacity, which scales an alpha given in the color
+    let sld = "<ColorMap type=\"values\">\n\
+               <ColorMapEntry quantity=\"1\" color=\"navy\" opacity=\"0.5\"/>\n\
+               <ColorMapEntry quantity=\"2\" color=\"#ffffff80\" opacity=\"0.5\"/>\n\
+               </ColorMap>";
+    let colormap = ColorMap::from_sld_reader(sld.as_bytes()).unwrap();
+    std::assert_eq!(colormap.entries[0].color, RgbColor::new(0, 0, 128));
+    std::assert_eq!((colormap.entries[0].alpha, colormap.entries[1].alpha), (128, 64));
+
+    let path = std::env::temp_dir().join("rasterkit_colormap_alpha.sld");
+    colormap.to_sld_file(&path, "alpha").unwrap();
+    let written = std::fs::read_to_string(&path).unwrap();
+    std::assert!(written.contains("quantity=\"1\" label=\"1\" color=\"#000080\" opacity=\"0.502\""), "{}", written);
+    let reread = ColorMap::from_sld_file(&path).unwrap();
+    std::assert_eq!((reread.entries[0].alpha, reread.entries[1].alpha), (128, 64));
+}
+
+/// Values of the entries of a colormap
+fn values(colormap: &ColorMap) -> std::vec::Vec<u16> {
+    colormap.entries.iter().map(|entry| entry.value).collect()
+}
+
+#[test]
+fn test_colormap_rescale_offset_and_merge() {
+    let csv = "0,#000000\n25,#444444\n50,#888888\n100,#ffffff\n";
+    let mut colormap = ColorMap::from_csv_reader(csv.as_bytes()).unwrap();
+    colormap.rescale((0.0, 100.0), (0.0, 10000.0)).unwrap();
+    std::assert_eq!(values(&colormap), [0, 2500, 5000, 10000]);
+    std::assert_eq!(colormap.entries[1].color, RgbColor::new(0x44, 0x44, 0x44));
+    std::assert!(colormap.rescale((5.0, 5.0), (0.0, 1.0)).is_err());
+
+    // Values saturate at the 16-bit range and entries that collide keep the first
+    colormap.offset(-2500);
+    std::assert_eq!(values(&colormap), [0, 2500, 7500]);
+    std::assert_eq!(colormap.entries[0].color, RgbColor::new(0, 0, 0));
+    colormap.offset(60000);
+    std::assert_eq!(values(&colormap), [60000, 62500, 65535]);
+
+    // Merged entries are added in order and win on equal values
+    let mut other = ColorMap::new();
+    other.add_entry(ColorMapEntry::new(62500, RgbColor::new(255, 0, 0)));
+    other.add_entry(ColorMapEntry::new(1, RgbColor::new(0, 255, 0)));
+    colormap.merge(&other);
+    std::assert_eq!(values(&colormap), [1, 60000, 62500, 65535]);
+    std::assert_eq!(colormap.entries[2].color, RgbColor::new(255, 0, 0));
+}
+
+#[test]
+fn test_transform_colormap_file() {
+    let dir = std::env::temp_dir();
+    let kit = RasterKit::new(Some(dir.join("rasterkit_colormap_tests.log").to_str().unwrap())).unwrap();
+    let input = dir.join("rasterkit_colormap_percent.csv");
+    std::fs::write(&input, "0,#0000ff\n50,#00ff00\n100,#ff0000\n").unwrap();
+    let merge = dir.join("rasterkit_colormap_merge.csv");
+    std::fs::write(&merge, "5100,#ffff00,Flood\n").unwrap();
+    let output = dir.join("rasterkit_colormap_transformed.sld");
+
+    // Rescaled first, then offset, then merged
+    kit.transform_colormap(input.to_str().unwrap(), output.to_str().unwrap(),
+                           Some("0:100:0:10000"), Some(100), Some(merge.to_str().unwrap())).unwrap();
+    let colormap = ColorMap::from_sld_file(&output).unwrap();
+    std::assert_eq!(values(&colormap), [100, 5100, 10100]);
+    std::assert_eq!(colormap.entries[1].label.as_deref(), Some("Flood"));
+    std::assert_eq!(colormap.entries[2].color, RgbColor::new(255, 0, 0));
+
+    std::assert!(kit.transform_colormap(input.to_str().unwrap(), output.to_str().unwrap(), None, None, None).is_err());
+    std::assert!(kit.transform_colormap(input.to_str().unwrap(), output.to_str().unwrap(),
+                                        Some("0:100:0"), None, None).is_err());
+}
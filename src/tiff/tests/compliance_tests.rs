@@ -0,0 +1,86 @@
+//! Tests for the specification checks of strict mode
+
+extern crate std;
+
+use std::string::String;
+use std::vec::Vec;
+use crate::tiff::compliance;
+use crate::tiff::ifd::{IFD, IFDEntry};
+
+/// Minimal compliant 16x16 8-bit grayscale IFD in one strip
+fn create_minimal_ifd() -> IFD {
+    let mut ifd = IFD::new(0, 8);
+    for (tag, field_type, value) in [(256, 3, 16), (257, 3, 16), (258, 3, 8), (262, 3, 1),
+                                     (273, 4, 0), (278, 3, 16), (279, 4, 256)] {
+        ifd.add_entry(IFDEntry::new(tag, field_type, 1, value));
+    }
+    ifd
+}
+
+fn violations(ifd: &IFD, is_big_tiff: bool) -> Vec<String> {
+    compliance::check_ifd(ifd, is_big_tiff, &|_| None)
+}
+
+#[test]
+fn test_compliance_checks() {
+    std::assert!(violations(&create_minimal_ifd(), false).is_empty());
+
+    // Appending a tag out of order
+    let mut ifd = create_minimal_ifd();
+    ifd.add_entry(IFDEntry::new(259, 3, 1, 1));
+    std::assert_eq!(violations(&ifd, false), std::vec![
+        "tag 259 (Compression) follows tag 279, tags must be sorted".to_string()]);
+
+    // A wrong type, and an 8-byte type outside BigTIFF
+    let mut ifd = create_minimal_ifd();
+    ifd.remove_entry(279);
+    ifd.add_entry(IFDEntry::new(279, 16, 1, 256));
+    std::assert!(violations(&ifd, true).is_empty());
+    std::assert_eq!(violations(&ifd, false), std::vec![
+        "tag 279 (StripByteCounts) uses the BigTIFF-only type LONG8".to_string()]);
+
+    // Missing required tags
+    let mut ifd = create_minimal_ifd();
+    ifd.remove_entry(262);
+    ifd.remove_entry(279);
+    std::assert_eq!(violations(&ifd, false), std::vec![
+        "required tag 262 (PhotometricInterpretation) is missing".to_string(),
+        "required tag 279 (StripByteCounts) is missing".to_string()]);
+
+    // Georeferencing without a GeoKeyDirectory
+    let mut ifd = create_minimal_ifd();
+    ifd.add_entry(IFDEntry::new(33550, 12, 3, 0));
+    std::assert_eq!(violations(&ifd, false), std::vec![
+        "tag 33550 (ModelPixelScale) without a GeoKeyDirectory".to_string()]);
+}
+
+#[test]
+fn test_range_checks() {
+    // Two strips of 128 bytes from byte 300, and a description at byte 200
+    let mut ifd = create_minimal_ifd();
+    ifd.remove_entry(273);
+    ifd.remove_entry(278);
+    ifd.remove_entry(279);
+    for entry in [IFDEntry::new(270, 2, 40, 200), IFDEntry::new(273, 4, 2, 100), IFDEntry::new(278, 3, 1, 8),
+                  IFDEntry::new(279, 4, 2, 108)] {
+        ifd.add_entry(entry);
+    }
+    let tag_data = |entry: &IFDEntry| match entry.tag {
+        273 => Some([300u32, 428].iter().flat_map(|value| value.to_le_bytes()).collect()),
+        279 => Some([128u32, 128].iter().flat_map(|value| value.to_le_bytes()).collect()),
+        _ => None,
+    };
+    std::assert!(compliance::check_ranges(&ifd, false, &tag_data, 556).is_empty());
+
+    // The second strip ends past a shorter file
+    std::assert_eq!(compliance::check_ranges(&ifd, false, &tag_data, 500), std::vec![
+        "strip 1 spans bytes 428 to 556, past the end of the file at 500".to_string()]);
+
+    // So do the description and the offsets array
+    std::assert_eq!(compliance::check_ranges(&ifd, false, &tag_data, 104), std::vec![
+        "data of tag 270 (ImageDescription) ends at byte 240, past the end of the file at 104".to_string(),
+        "data of tag 273 (StripOffsets) ends at byte 108, past the end of the file at 104".to_string(),
+        "data of tag 279 (StripByteCounts) ends at byte 116, past the end of the file at 104".to_string(),
+        "strip 0 spans bytes 300 to 428, past the end of the file at 104".to_string(),
+        "strip 1 spans bytes 428 to 556, past the end of the file at 104".to_string()]);
+}
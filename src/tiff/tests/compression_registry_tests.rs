@@ -0,0 +1,79 @@
+//! Tests for compression handlers registered at runtime
+
+use crate::RasterKit;
+use crate::compression::{CompressionFactory, CompressionHandler};
+use crate::extractor::ImageExtractor;
+use crate::tiff::TiffReader;
+use crate::tiff::errors::{TiffError, TiffResult};
+use crate::utils::logger::Logger;
+use super::test_utils::TestTiff;
+
+/// Stores every byte inverted, under a private compression code
+struct InvertHandler(u64);
+
+impl CompressionHandler for InvertHandler {
+    fn decompress(&self, data: &[u8]) -> TiffResult<Vec<u8>> {
+        Ok(data.iter().map(|b| !b).collect())
+    }
+
+    fn compress(&self, data: &[u8]) -> TiffResult<Vec<u8>> {
+        Ok(data.iter().map(|b| !b).collect())
+    }
+
+    fn name(&self) -> &'static str {
+        match self.0 {
+            65010 => "InvertTest",
+            _ => "InvertRoundTrip",
+        }
+    }
+
+    fn code(&self) -> u64 {
+        self.0
+    }
+}
+
+#[test]
+fn test_register_and_unregister() {
+    // The handler must report the code it is registered for
+    assert!(CompressionFactory::register(65011, Box::new(InvertHandler(65010))).is_err());
+    assert!(matches!(CompressionFactory::create_handler(65010), Err(TiffError::UnsupportedCompression(65010))));
+
+    CompressionFactory::register(65010, Box::new(InvertHandler(65010))).unwrap();
+    assert_eq!(CompressionFactory::create_handler(65010).unwrap().decompress(&[0x0f]).unwrap(), [0xf0]);
+    assert_eq!(CompressionFactory::get_handler_by_name("inverttest").unwrap().code(), 65010);
+    assert!(CompressionFactory::get_available_handlers().iter().any(|handler| handler.name() == "InvertTest"));
+
+    assert!(CompressionFactory::unregister(65010));
+    assert!(!CompressionFactory::unregister(65010));
+    assert!(CompressionFactory::create_handler(65010).is_err());
+    assert!(CompressionFactory::get_handler_by_name("InvertTest").is_err());
+}
+
+#[test]
+fn test_registered_codec_reads_and_writes_images() {
+    let dir = std::env::temp_dir();
+    let log = dir.join("rasterkit_compression_registry_tests.log");
+    let kit = RasterKit::new(Some(log.to_str().unwrap())).unwrap();
+    let logger = Logger::new(log.to_str().unwrap()).unwrap();
+    CompressionFactory::register(65012, Box::new(InvertHandler(65012))).unwrap();
+
+    // Blocks in the private codec decode through the registered handler
+    let input = dir.join("rasterkit_compression_registry_inverted.tif");
+    std::fs::write(&input, TestTiff::new(3, 1).compression(65012).pixels(vec![!10, !20, !30]).build()).unwrap();
+    let image = ImageExtractor::new(&logger).extract_image(input.to_str().unwrap(), None).unwrap().to_luma8();
+    assert_eq!(image.into_raw(), [10, 20, 30]);
+
+    // And outputs can be written with it by name
+    let plain = dir.join("rasterkit_compression_registry_plain.tif");
+    std::fs::write(&plain, TestTiff::new(3, 1).pixels(vec![40, 50, 60]).build()).unwrap();
+    let output = dir.join("rasterkit_compression_registry_output.tif");
+    kit.convert_compression(plain.to_str().unwrap(), output.to_str().unwrap(), "InvertRoundTrip").unwrap();
+    let mut reader = TiffReader::new(&logger);
+    let tiff = reader.load(output.to_str().unwrap()).unwrap();
+    let ifd = tiff.main_ifd().unwrap();
+    assert_eq!(ifd.get_tag_value(259), Some(65012));
+    let offset = ifd.get_tag_value(273).unwrap() as usize;
+    assert_eq!(std::fs::read(&output).unwrap()[offset..offset + 3], [!40, !50, !60]);
+
+    CompressionFactory::unregister(65012);
+}
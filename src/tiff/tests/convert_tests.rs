@@ -0,0 +1,55 @@
+//! Tests for converting the compression of a file
+
+use crate::compression::CompressionConverter;
+use crate::extractor::{ArraySamples, ImageExtractor};
+use crate::utils::catalog_utils;
+use crate::utils::logger::Logger;
+use super::test_utils::TestTiff;
+
+/// 4x4 single-band 16-bit image, sample = 10 * row + column, in UTM zone
+/// 33N with 30 m pixels from (500000, 4000000) and NoData 0
+fn georeferenced_file() -> TestTiff {
+    TestTiff::new(4, 4)
+        .bits(&[16])
+        .georeference(30.0, 30.0, 500000.0, 4000000.0)
+        .epsg(32633)
+        .nodata("0")
+        .ascii(270, "converted elevation")
+}
+
+/// Samples of the image, row by row
+fn samples() -> Vec<u16> {
+    (0..4u16).flat_map(|row| (0..4u16).map(move |column| 10 * row + column)).collect()
+}
+
+#[test]
+fn test_convert_keeps_georeferencing() {
+    let dir = std::env::temp_dir();
+    let logger = Logger::new(dir.join("rasterkit_convert_tests.log").to_str().unwrap()).unwrap();
+    let bytes: Vec<u8> = samples().into_iter().flat_map(u16::to_le_bytes).collect();
+
+    // Two strips, one strip, and two tiles side by side
+    let strips = georeferenced_file().strips(2, bytes.chunks(16).map(<[u8]>::to_vec).collect()).build();
+    let strip = georeferenced_file().pixels(bytes.clone()).build();
+    let tile = |first_column: usize| (0..4).flat_map(|row| bytes[row * 8 + first_column * 2..][..4].to_vec()).collect();
+    let tiles = georeferenced_file().tiles(2, 4, vec![tile(0), tile(2)]).build();
+
+    for (name, file) in [("strips", strips), ("strip", strip), ("tiles", tiles)] {
+        let input = dir.join(format!("rasterkit_convert_{}.tif", name));
+        let output = dir.join(format!("rasterkit_converted_{}.tif", name));
+        std::fs::write(&input, file).unwrap();
+        CompressionConverter::new(&logger)
+            .convert_file(input.to_str().unwrap(), output.to_str().unwrap(), 8).unwrap();
+
+        // Tags stored outside the entries are copied with their data
+        let record = catalog_utils::read_record(output.to_str().unwrap(), &logger).unwrap();
+        assert_eq!(record.compression, "deflate", "{}", name);
+        assert_eq!(record.epsg, Some(32633), "{}", name);
+        assert_eq!(record.bounds, Some((500000.0, 3999880.0, 500120.0, 4000000.0)), "{}", name);
+        assert_eq!(record.nodata, Some(0.0), "{}", name);
+
+        let mut extractor = ImageExtractor::new_array_extractor(&logger);
+        let array = extractor.extract_array_data(output.to_str().unwrap(), None).unwrap();
+        assert_eq!(array.data, ArraySamples::U16(samples()), "{}", name);
+    }
+}
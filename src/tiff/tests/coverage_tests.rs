@@ -0,0 +1,103 @@
+//! Tests for bounding boxes that the raster covers partly or not at all
+
+use crate::extractor::{self, ArraySamples, BufferLayout, Coverage, DecodedWindow, ImageExtractor, Padding, Region};
+use crate::tiff::errors::TiffResult;
+use crate::utils::grid_utils::Raster;
+use crate::utils::image_extraction_utils;
+use crate::utils::logger::Logger;
+use super::test_utils::TestTiff;
+
+#[test]
+fn test_partial_window_is_padded_back() {
+    // A 4x4 window hanging two pixels off the top-left corner
+    let coverage = Coverage::new((-2, -2, 2, 2), 4, 4);
+    let region = coverage.clipped.unwrap();
+    assert_eq!((region.x, region.y, region.width, region.height), (0, 0, 2, 2));
+    assert_eq!((coverage.covered_pixels(), coverage.outside_pixels()), (4, 12));
+    let padding = coverage.padding(&region, Some(0.0)).unwrap();
+    assert_eq!((padding.offset_x, padding.offset_y, padding.width, padding.height), (2, 2, 4, 4));
+    assert_eq!(image_extraction_utils::pad_band(&[1, 2, 3, 4], 2, 2, &padding, 0),
+               [0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 1, 2, 0, 0, 3, 4]);
+
+    // Negative offsets keep only the part of the region inside the output
+    let padding = Padding { offset_x: -1, offset_y: -1, width: 2, height: 2, fill: None };
+    assert_eq!(image_extraction_utils::pad_band(&[1, 2, 3, 4, 5, 6, 7, 8, 9], 3, 3, &padding, 0), [5, 6, 8, 9]);
+
+    // A complete window needs no padding
+    let coverage = Coverage::new((1, 1, 3, 3), 4, 4);
+    assert!(coverage.padding(&coverage.clipped.unwrap(), Some(0.0)).is_none());
+}
+
+#[test]
+fn test_disjoint_window_pads_to_all_fill() {
+    // A 2x2 window well to the right of the 4x4 image
+    let coverage = Coverage::new((10, 1, 12, 3), 4, 4);
+    assert!(coverage.clipped.is_none());
+    assert_eq!(coverage.percent(), 0.0);
+    assert!(!coverage.is_complete());
+
+    // Whatever region is read in its place lands outside the window
+    let region = Region::new(3, 1, 1, 2);
+    let padding = coverage.padding(&region, Some(7.0)).unwrap();
+    assert_eq!((padding.offset_x, padding.offset_y, padding.width, padding.height), (-7, 0, 2, 2));
+
+    let dir = std::env::temp_dir();
+    let logger = Logger::new(dir.join("rasterkit_coverage_tests.log").to_str().unwrap()).unwrap();
+    let path = dir.join("rasterkit_coverage.tif");
+    std::fs::write(&path, TestTiff::new(4, 4).pixels((1..=16).collect()).build()).unwrap();
+
+    let mut extractor = ImageExtractor::new_array_extractor(&logger);
+    extractor.set_padding(Some(padding));
+    let array = extractor.extract_array_data(path.to_str().unwrap(), Some(region)).unwrap();
+    assert_eq!((array.width, array.height), (2, 2));
+    assert_eq!(array.data, ArraySamples::U8(vec![7; 4]));
+    assert_eq!(array.nodata, Some(7.0));
+}
+
+/// Extract the whole of a raster padded by one pixel on each side
+fn extract_padded(name: &str, file: Vec<u8>, fill: Option<f64>, logger: &Logger) -> TiffResult<(DecodedWindow, Vec<u8>)> {
+    let dir = std::env::temp_dir();
+    let (input, output) = (dir.join(format!("rasterkit_{}.tif", name)), dir.join(format!("rasterkit_{}_padded.tif", name)));
+    std::fs::write(&input, file).unwrap();
+
+    let mut extractor = ImageExtractor::new(logger);
+    extractor.set_padding(Some(Padding { offset_x: 1, offset_y: 1, width: 4, height: 3, fill }));
+    extractor.extract_to_file(input.to_str().unwrap(), output.to_str().unwrap(), Some(Region::new(0, 0, 2, 1)), None)?;
+
+    let shape = extractor::describe_window(output.to_str().unwrap(), None, logger)?;
+    let mut samples = vec![0u8; shape.byte_len()];
+    extractor::decode_into_bytes(output.to_str().unwrap(), None, BufferLayout::Interleaved, &mut samples, logger)?;
+    Ok((shape, samples))
+}
+
+#[test]
+fn test_padded_geotiff_keeps_sample_type() {
+    let dir = std::env::temp_dir();
+    let logger = Logger::new(dir.join("rasterkit_coverage_tests.log").to_str().unwrap()).unwrap();
+
+    // Float32 elevations padded with their NoData value of -9999
+    let elevations = TestTiff::new(2, 1).bits(&[32]).sample_format(3)
+        .georeference(10.0, 10.0, 500000.0, 4000000.0).epsg(32633).nodata("-9999")
+        .pixels([12.5f32, -3.25].iter().flat_map(|value| value.to_le_bytes()).collect()).build();
+    let (shape, samples) = extract_padded("padded_float", elevations, None, &logger).unwrap();
+    assert_eq!((shape.width, shape.height, shape.bands, shape.floating_point), (4, 3, 1, true));
+    let values: Vec<f32> = samples.chunks_exact(4).map(|bytes| f32::from_le_bytes(bytes.try_into().unwrap())).collect();
+    assert_eq!(values, [-9999.0, -9999.0, -9999.0, -9999.0,
+                        -9999.0, 12.5, -3.25, -9999.0,
+                        -9999.0, -9999.0, -9999.0, -9999.0]);
+    let raster = Raster::read(dir.join("rasterkit_padded_float_padded.tif").to_str().unwrap(), 1, &logger).unwrap();
+    let geotransform = raster.georeferencing.unwrap().geotransform;
+    assert_eq!((geotransform.origin_x, geotransform.origin_y), (499990.0, 4000010.0));
+
+    // Two UInt16 bands, with a fill value above the 8-bit range
+    let bands = || TestTiff::new(2, 1).bits(&[16, 16]).photometric(1)
+        .pixels([1000u16, 2000, 3000, 4000].iter().flat_map(|value| value.to_le_bytes()).collect()).build();
+    let (shape, samples) = extract_padded("padded_u16", bands(), Some(65535.0), &logger).unwrap();
+    assert_eq!((shape.bands, shape.bytes_per_sample), (2, 2));
+    let values: Vec<u16> = samples.chunks_exact(2).map(|bytes| u16::from_le_bytes([bytes[0], bytes[1]])).collect();
+    assert_eq!(&values[8..16], [65535, 65535, 1000, 2000, 3000, 4000, 65535, 65535]);
+    assert!(values[..8].iter().chain(&values[16..]).all(|&value| value == 65535));
+
+    // Without NoData there is no fill value to guess
+    assert!(extract_padded("padded_u16", bands(), None, &logger).is_err());
+}
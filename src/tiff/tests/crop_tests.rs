@@ -0,0 +1,89 @@
+//! Tests for cropping by copying encoded tiles
+
+use image::GenericImageView;
+use crate::compression::CompressionFactory;
+use crate::extractor::{ImageExtractor, Region};
+use crate::tiff::TiffReader;
+use crate::utils::crop_utils;
+use crate::utils::logger::Logger;
+use super::test_utils::TestTiff;
+
+/// Deflate-compressed 4x4 tiles of a 10x6 image, value = 10 * row + column
+fn encode_tiles() -> Vec<Vec<u8>> {
+    let deflate = CompressionFactory::create_handler(8).unwrap();
+    let mut tiles = Vec::new();
+    for tile_row in 0..2 {
+        for tile_column in 0..3 {
+            let mut pixels = Vec::new();
+            for y in tile_row * 4..tile_row * 4 + 4 {
+                for x in tile_column * 4..tile_column * 4 + 4 {
+                    pixels.push(if x < 10 && y < 6 { (10 * y + x) as u8 } else { 0 });
+                }
+            }
+            tiles.push(deflate.compress(&pixels).unwrap());
+        }
+    }
+    tiles
+}
+
+/// The tiled image, georeferenced with 30 m pixels from (500000, 4000000)
+fn create_tiled_file(tiles: &[Vec<u8>]) -> Vec<u8> {
+    TestTiff::new(10, 6)
+        .compression(8)
+        .georeference(30.0, 30.0, 500000.0, 4000000.0)
+        .tiles(4, 4, tiles.to_vec())
+        .build()
+}
+
+#[test]
+fn test_crop_copies_tiles_unchanged() {
+    let dir = std::env::temp_dir();
+    let logger = Logger::new(dir.join("rasterkit_crop_tests.log").to_str().unwrap()).unwrap();
+    let tiles = encode_tiles();
+    let input = dir.join("rasterkit_crop_tiled.tif");
+    std::fs::write(&input, create_tiled_file(&tiles)).unwrap();
+    let output = dir.join("rasterkit_crop_copied.tif");
+    let (input, output) = (input.to_str().unwrap(), output.to_str().unwrap());
+
+    // Right column of tiles, which ends at the edge of the image
+    let window = Region::new(4, 0, 6, 6);
+    assert_eq!(crop_utils::crop_blocks(input, output, Some(window), None, &logger).unwrap(), 4);
+
+    let mut reader = TiffReader::new(&logger);
+    let tiff = reader.load(output).unwrap();
+    let ifd = tiff.main_ifd().unwrap();
+    assert_eq!(ifd.get_dimensions(), Some((6, 6)));
+    assert_eq!(ifd.get_tag_value(259), Some(8));
+    let offsets = reader.read_tag::<u64>(ifd, 324).unwrap();
+    let counts = reader.read_tag::<u64>(ifd, 325).unwrap();
+    let data = std::fs::read(output).unwrap();
+    for (copy, source) in [(0, 1), (1, 2), (2, 4), (3, 5)] {
+        let start = offsets[copy] as usize;
+        assert_eq!(&data[start..start + counts[copy] as usize], &tiles[source][..]);
+    }
+
+    let mut extractor = ImageExtractor::new(&logger);
+    let image = extractor.extract_image(output, None).unwrap();
+    assert_eq!(image.dimensions(), (6, 6));
+    assert_eq!(image.get_pixel(0, 0).0[0], 4);
+    assert_eq!(image.get_pixel(5, 5).0[0], 59);
+
+    // The tiepoint moves to the window
+    let tiepoint = reader.read_tag::<f64>(ifd, 33922).unwrap();
+    assert_eq!((tiepoint[3], tiepoint[4]), (500120.0, 4000000.0));
+}
+
+#[test]
+fn test_unaligned_window_is_not_copyable() {
+    let dir = std::env::temp_dir();
+    let logger = Logger::new(dir.join("rasterkit_crop_tests.log").to_str().unwrap()).unwrap();
+    let input = dir.join("rasterkit_crop_unaligned.tif");
+    std::fs::write(&input, create_tiled_file(&encode_tiles())).unwrap();
+
+    let mut reader = TiffReader::new(&logger);
+    let tiff = reader.load(input.to_str().unwrap()).unwrap();
+    let ifd = tiff.main_ifd().unwrap();
+    assert!(crop_utils::check_copyable(&reader, ifd, &Region::new(0, 4, 8, 2)).is_ok());
+    assert!(crop_utils::check_copyable(&reader, ifd, &Region::new(2, 0, 4, 4)).is_err());
+    assert!(crop_utils::check_copyable(&reader, ifd, &Region::new(0, 0, 6, 4)).is_err());
+}
@@ -0,0 +1,40 @@
+//! Tests for sharing a RasterKit between threads and its header cache
+
+use std::sync::Arc;
+use crate::RasterKit;
+use super::test_utils::TestTiff;
+
+/// Single-strip 8-bit image of `width` x 2 pixels
+fn create_file(width: u32) -> Vec<u8> {
+    TestTiff::new(width, 2).pixels(vec![7; width as usize * 2]).build()
+}
+
+fn assert_send_sync<T: Send + Sync>() {}
+
+#[test]
+fn test_shared_rasterkit_caches_headers() {
+    assert_send_sync::<RasterKit>();
+
+    let dir = std::env::temp_dir();
+    let path = dir.join("rasterkit_dataset_cache.tif");
+    std::fs::write(&path, create_file(4)).unwrap();
+    let path = path.to_str().unwrap().to_string();
+    let kit = Arc::new(RasterKit::new(Some(dir.join("rasterkit_dataset_cache.log").to_str().unwrap())).unwrap());
+
+    let handles: Vec<_> = (0..4).map(|_| {
+        let (kit, path) = (Arc::clone(&kit), path.clone());
+        std::thread::spawn(move || kit.analyze(&path).unwrap())
+    }).collect();
+    for handle in handles {
+        assert!(handle.join().unwrap().contains("Dimensions: 4x2"));
+    }
+    assert_eq!(kit.cached_datasets(), 1);
+
+    // A file changed on disk is parsed again
+    std::fs::write(&path, create_file(6)).unwrap();
+    assert!(kit.analyze(&path).unwrap().contains("Dimensions: 6x2"));
+    assert_eq!(kit.cached_datasets(), 1);
+
+    kit.clear_dataset_cache();
+    assert_eq!(kit.cached_datasets(), 0);
+}
@@ -0,0 +1,26 @@
+//! Tests for the open dataset handle
+
+use crate::RasterKit;
+use super::test_utils::create_two_band_tiff;
+
+#[test]
+fn test_dataset_operations() {
+    let dir = std::env::temp_dir();
+    let path = dir.join("rasterkit_dataset.tif");
+    std::fs::write(&path, create_two_band_tiff()).unwrap();
+    let kit = RasterKit::new(Some(dir.join("rasterkit_dataset.log").to_str().unwrap())).unwrap();
+
+    let dataset = kit.open(path.to_str().unwrap()).unwrap();
+    assert_eq!(dataset.dimensions().unwrap(), (3, 2));
+    assert_eq!(dataset.band_count().unwrap(), 2);
+    assert_eq!(dataset.crs(), None);
+    assert!(dataset.analyze().contains("Samples per pixel: 2"));
+
+    assert_eq!(dataset.sample(2, 1).unwrap(), [5.0, 15.0]);
+    assert!(dataset.sample(3, 0).is_err());
+
+    let statistics = dataset.statistics().unwrap();
+    assert_eq!(statistics.len(), 2);
+    assert_eq!((statistics[1].min, statistics[1].max, statistics[1].mean), (10.0, 15.0, 12.5));
+    assert!(!statistics[0].approximate);
+}
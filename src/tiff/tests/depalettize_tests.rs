@@ -0,0 +1,67 @@
+//! Tests for expanding palette TIFFs to RGB or class indices
+
+use crate::RasterKit;
+use crate::extractor::ImageExtractor;
+use crate::tiff::TiffReader;
+use crate::utils::logger::Logger;
+use crate::utils::tiff_extraction_utils;
+use super::test_utils::TestTiff;
+
+/// 3x1 georeferenced palette image of indices 0, 1, 2; index 2 is NoData
+fn create_palette_tiff() -> Vec<u8> {
+    let mut palette = vec![0u16; 3 * 256];
+    palette[0] = 65535;                     // 0: red
+    palette[256 + 1] = 0x8080;              // 1: half green
+    palette[2] = 0x4040;                    // 2: grey
+    palette[256 + 2] = 0x4040;
+    palette[512 + 2] = 0x4040;
+    TestTiff::new(3, 1).photometric(3).shorts(320, &palette)
+        .georeference(10.0, 10.0, 600000.0, 5000000.0).epsg(32633).nodata("2")
+        .pixels(vec![0, 1, 2]).build()
+}
+
+#[test]
+fn test_depalettize_to_rgb_and_indices() {
+    let dir = std::env::temp_dir();
+    let log = dir.join("rasterkit_depalettize_tests.log");
+    let kit = RasterKit::new(Some(log.to_str().unwrap())).unwrap();
+    let logger = Logger::new(log.to_str().unwrap()).unwrap();
+    let input = dir.join("rasterkit_depalettize_palette.tif");
+    std::fs::write(&input, create_palette_tiff()).unwrap();
+
+    // Every index becomes its palette color
+    let rgb = dir.join("rasterkit_depalettize_rgb.tif");
+    kit.depalettize(input.to_str().unwrap(), rgb.to_str().unwrap(), "rgb").unwrap();
+    let image = ImageExtractor::new(&logger).extract_image(rgb.to_str().unwrap(), None).unwrap().to_rgb8();
+    let pixels: Vec<[u8; 3]> = image.pixels().map(|pixel| pixel.0).collect();
+    assert_eq!(pixels, [[255, 0, 0], [0, 128, 0], [64, 64, 64]]);
+
+    // Class indices keep their values and NoData, without the ColorMap
+    let indices = dir.join("rasterkit_depalettize_indices.tif");
+    kit.depalettize(input.to_str().unwrap(), indices.to_str().unwrap(), "classes").unwrap();
+    let mut reader = TiffReader::new(&logger);
+    let tiff = reader.load(indices.to_str().unwrap()).unwrap();
+    let ifd = tiff.main_ifd().unwrap();
+    assert_eq!(ifd.get_tag_value(262), Some(1));
+    assert!(!ifd.has_tag(320));
+    assert_eq!(ifd.get_samples_per_pixel(), 1);
+    assert_eq!(reader.read_tag::<f64>(ifd, 33922).unwrap()[3..5], [600000.0, 5000000.0]);
+    assert_eq!(tiff_extraction_utils::extract_nodata_value(ifd, &reader).trim(), "2");
+    let image = ImageExtractor::new(&logger).extract_image(indices.to_str().unwrap(), None).unwrap().to_luma8();
+    assert_eq!(image.into_raw(), [0, 1, 2]);
+}
+
+#[test]
+fn test_depalettize_refuses_other_inputs() {
+    let dir = std::env::temp_dir();
+    let kit = RasterKit::new(Some(dir.join("rasterkit_depalettize_tests.log").to_str().unwrap())).unwrap();
+    let grey = dir.join("rasterkit_depalettize_grey.tif");
+    std::fs::write(&grey, TestTiff::new(2, 1).pixels(vec![0, 1]).build()).unwrap();
+    let output = dir.join("rasterkit_depalettize_refused.tif");
+
+    assert!(kit.depalettize(grey.to_str().unwrap(), output.to_str().unwrap(), "rgb").is_err());
+    let palette = dir.join("rasterkit_depalettize_mode.tif");
+    std::fs::write(&palette, create_palette_tiff()).unwrap();
+    assert!(kit.depalettize(palette.to_str().unwrap(), output.to_str().unwrap(), "cmyk").is_err());
+    assert!(!output.exists());
+}
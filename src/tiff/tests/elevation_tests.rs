@@ -0,0 +1,86 @@
+//! Tests for vertical CRS reporting and elevation unit conversion
+
+use crate::RasterKit;
+use crate::tiff::TiffReader;
+use crate::tiff::geo_key_parser::GeoKeyParser;
+use crate::utils::elevation_utils::{self, LinearUnit};
+use crate::utils::logger::Logger;
+use super::safetensors_tests::read_safetensors;
+use super::test_utils::TestTiff;
+
+/// 2x2 DEM in UTM 33N over NAVD88 with the given vertical units; 255 is NoData
+fn create_dem(vertical_units: u16) -> Vec<u8> {
+    TestTiff::new(2, 2).georeference(10.0, 10.0, 500000.0, 4000000.0)
+        .shorts(34735, &[1, 1, 0, 4, 1024, 0, 1, 1, 3072, 0, 1, 32633, 4096, 0, 1, 5703, 4099, 0, 1, vertical_units])
+        .nodata("255").pixels(vec![10, 100, 0, 255]).build()
+}
+
+#[test]
+fn test_vertical_crs_is_parsed() {
+    let dir = std::env::temp_dir();
+    let logger = Logger::new(dir.join("rasterkit_elevation_tests.log").to_str().unwrap()).unwrap();
+    let path = dir.join("rasterkit_elevation_navd88.tif");
+    std::fs::write(&path, create_dem(9002)).unwrap();
+    let path = path.to_str().unwrap();
+
+    let mut reader = TiffReader::new(&logger);
+    let tiff = reader.load(path).unwrap();
+    let handler = reader.get_byte_order_handler().unwrap();
+    let geo_info = GeoKeyParser::extract_geo_info(tiff.main_ifd().unwrap(), handler, path).unwrap();
+    assert!(geo_info.has_vertical_crs());
+    assert_eq!(geo_info.vertical_cs_code, 5703);
+    assert_eq!(geo_info.vertical_cs_description().as_deref(), Some("VertCS_NAVD88_height (EPSG:5703)"));
+    assert_eq!(geo_info.vertical_units_name().as_deref(), Some("Linear_Foot"));
+    assert_eq!(LinearUnit::from_geokey(geo_info.vertical_units_code), Some(LinearUnit::Foot));
+
+    // Horizontal-only files declare nothing vertical
+    let flat = dir.join("rasterkit_elevation_flat.tif");
+    std::fs::write(&flat, TestTiff::new(1, 1).georeference(1.0, 1.0, 0.0, 0.0).epsg(32633).pixels(vec![0]).build()).unwrap();
+    let flat = flat.to_str().unwrap();
+    let mut reader = TiffReader::new(&logger);
+    let tiff = reader.load(flat).unwrap();
+    let handler = reader.get_byte_order_handler().unwrap();
+    let geo_info = GeoKeyParser::extract_geo_info(tiff.main_ifd().unwrap(), handler, flat).unwrap();
+    assert!(!geo_info.has_vertical_crs());
+    assert_eq!((geo_info.vertical_cs_description(), geo_info.vertical_units_name()), (None, None));
+}
+
+#[test]
+fn test_convert_elevations() {
+    assert_eq!(LinearUnit::from_string("US-FT").unwrap(), LinearUnit::UsSurveyFoot);
+    assert!(LinearUnit::from_string("yards").is_err());
+
+    let meters = elevation_utils::convert_elevations(&[1000.0, -9999.0], LinearUnit::Foot, LinearUnit::Meter, Some(-9999.0));
+    assert_eq!(meters, [304.8, -9999.0]);
+    let feet = elevation_utils::convert_elevations(&[1200.0], LinearUnit::Meter, LinearUnit::UsSurveyFoot, None);
+    assert!((feet[0] - 3937.0).abs() < 1e-3);
+}
+
+#[test]
+fn test_extract_elevation_array_in_other_units() {
+    let dir = std::env::temp_dir();
+    let kit = RasterKit::new(Some(dir.join("rasterkit_elevation_tests.log").to_str().unwrap())).unwrap();
+    let input = dir.join("rasterkit_elevation_feet.tif");
+    std::fs::write(&input, create_dem(9002)).unwrap();
+    let input = input.to_str().unwrap();
+
+    // Feet to meters, with NoData left alone
+    let csv = dir.join("rasterkit_elevation_meters.csv");
+    kit.extract_elevation_array(input, csv.to_str().unwrap(), "csv", "m", None).unwrap();
+    assert_eq!(std::fs::read_to_string(&csv).unwrap(), "3.048,30.48\n0,255\n");
+
+    // Tensor formats are float32 and record the unit
+    let tensors = dir.join("rasterkit_elevation_meters.safetensors");
+    kit.extract_elevation_array(input, tensors.to_str().unwrap(), "safetensors", "m", Some((1, 0, 1, 2))).unwrap();
+    let (header, tensors) = read_safetensors(&tensors);
+    assert!(header.contains(r#""units":"m""#));
+    let (name, dtype, shape, data) = &tensors[0];
+    assert_eq!((name.as_str(), dtype.as_str(), shape.as_slice()), ("data", "F32", &[2, 1][..]));
+    assert_eq!(data[..], [30.48f32.to_le_bytes(), 255f32.to_le_bytes()].concat());
+
+    // Vertical units without a conversion are refused
+    let unknown = dir.join("rasterkit_elevation_unknown.tif");
+    std::fs::write(&unknown, create_dem(9036)).unwrap();
+    let output = dir.join("rasterkit_elevation_unknown.csv");
+    assert!(kit.extract_elevation_array(unknown.to_str().unwrap(), output.to_str().unwrap(), "csv", "m", None).is_err());
+}
@@ -0,0 +1,62 @@
+//! Tests for reading and writing ENVI rasters
+
+use image::GenericImageView;
+
+use crate::extractor::{ArraySamples, ExtractorStrategy, EnviExtractorStrategy, Region};
+use crate::utils::logger::Logger;
+
+/// Write a 5x4 raster of three 16-bit bands, interleaved by line, where
+/// sample = 100 * band + 10 * y + x
+fn create_envi_raster(dir: &std::path::Path) -> String {
+    let (samples, lines, bands) = (5u16, 4u16, 3u16);
+    let mut data = Vec::new();
+    for y in 0..lines {
+        for band in 0..bands {
+            for x in 0..samples {
+                data.extend((100 * band + 10 * y + x).to_le_bytes());
+            }
+        }
+    }
+    std::fs::write(dir.join("rasterkit_envi_test.dat"), data).unwrap();
+
+    let header = dir.join("rasterkit_envi_test.hdr");
+    std::fs::write(&header, "ENVI\n\
+        samples = 5\nlines = 4\nbands = 3\nheader offset = 0\n\
+        data type = 12\ninterleave = bil\nbyte order = 0\n\
+        map info = {UTM, 1.0, 1.0, 500000.0, 4000000.0,\n 30.0, 30.0, 33, North, WGS-84}\n\
+        default bands = {3, 2, 1}\n").unwrap();
+    header.to_str().unwrap().to_string()
+}
+
+#[test]
+fn test_envi_read_and_write() {
+    let dir = std::env::temp_dir();
+    let logger = Logger::new(dir.join("rasterkit_envi_tests.log").to_str().unwrap()).unwrap();
+    let path = create_envi_raster(&dir);
+
+    let mut strategy = EnviExtractorStrategy::new(&logger);
+    assert!(strategy.supports_format(&path));
+
+    // Default bands give an RGB image of bands 3, 2 and 1
+    let region = Region::new(1, 2, 3, 2);
+    let image = strategy.extract_image(&path, Some(region)).unwrap();
+    assert_eq!(image.dimensions(), (3, 2));
+    let pixel = image.to_rgb16().get_pixel(0, 0).0;
+    assert_eq!(pixel, [221, 121, 21]);
+
+    let array = strategy.extract_array_data(&path, Some(region)).unwrap();
+    let georeference = array.georeference.clone().unwrap();
+    assert_eq!(georeference.epsg, Some(32633));
+    assert_eq!(georeference.geotransform.to_gdal(), [500030.0, 30.0, 0.0, 3999940.0, 0.0, -30.0]);
+
+    assert_eq!(array.data, ArraySamples::U16(vec![21, 22, 23, 31, 32, 33]));
+
+    // Written rasters read back with the same samples, type and georeferencing
+    let output = dir.join("rasterkit_envi_output.hdr");
+    array.save_to_file(output.to_str().unwrap(), "envi").unwrap();
+    assert!(dir.join("rasterkit_envi_output.dat").is_file());
+
+    let written = strategy.extract_array_data(output.to_str().unwrap(), None).unwrap();
+    assert_eq!(written.data, array.data);
+    assert_eq!(written.georeference.unwrap().geotransform, georeference.geotransform);
+}
@@ -0,0 +1,118 @@
+//! Tests for reading and carrying EXIF, GPS and XMP metadata
+
+use crate::RasterKit;
+use crate::extractor::ExtractOptions;
+use crate::tiff::TiffReader;
+use crate::tiff::constants::tags;
+use crate::utils::exif_utils::{self, ExifEntry};
+use crate::utils::logger::Logger;
+use super::test_utils::TestTiff;
+
+const XMP: &[u8] = b"<x:xmpmeta xmlns:x=\"adobe:ns:meta/\"><rdf:RDF/></x:xmpmeta>";
+
+/// An entry of unsigned rationals
+fn rationals(tag: u16, values: &[(u32, u32)]) -> ExifEntry {
+    let data = values.iter().flat_map(|&(num, den)| [num.to_le_bytes(), den.to_le_bytes()].concat()).collect();
+    ExifEntry { tag, field_type: 5, count: values.len() as u64, data }
+}
+
+/// An entry of ASCII text, NUL-terminated
+fn ascii(tag: u16, text: &str) -> ExifEntry {
+    let data = [text.as_bytes(), &[0]].concat();
+    ExifEntry { tag, field_type: 2, count: data.len() as u64, data }
+}
+
+/// EXIF entries in tag order, as directories store them
+fn exif_entries() -> Vec<ExifEntry> {
+    vec![
+        rationals(33437, &[(28, 10)]),
+        ExifEntry { tag: 34855, field_type: 3, count: 1, data: 100u16.to_le_bytes().to_vec() },
+        ascii(36867, "2024:05:01 10:00:00"),
+    ]
+}
+
+/// GPS entries in tag order: 52° 30' 36" N, 4° 54' W at 120.5 m
+fn gps_entries() -> Vec<ExifEntry> {
+    vec![
+        ascii(1, "N"),
+        rationals(2, &[(52, 1), (30, 1), (36, 1)]),
+        ascii(3, "W"),
+        rationals(4, &[(4, 1), (54, 1), (0, 1)]),
+        ExifEntry { tag: 5, field_type: 1, count: 1, data: vec![0] },
+        rationals(6, &[(1205, 10)]),
+    ]
+}
+
+/// A 2x2 image with EXIF and GPS directories appended after the image and an XMP packet
+fn create_drone_tiff() -> Vec<u8> {
+    let build = |exif: u32, gps: u32| TestTiff::new(2, 2).bits(&[8, 8, 8]).photometric(2)
+        .longs(tags::EXIF_IFD, &[exif]).longs(tags::GPS_IFD, &[gps])
+        .tag(tags::XMP, 1, XMP.len() as u32, XMP.to_vec())
+        .pixels((0..12).collect()).build();
+
+    // The pointers are inline, so the file length does not depend on them
+    let end = build(0, 0).len() as u64;
+    let exif_offset = end + end % 2;
+    let exif = exif_utils::relocate_directory(&exif_utils::pack_directory(&exif_entries()), exif_offset);
+    let gps_offset = exif_offset + exif.len() as u64;
+    let gps = exif_utils::relocate_directory(&exif_utils::pack_directory(&gps_entries()), gps_offset);
+
+    let mut file = build(exif_offset as u32, gps_offset as u32);
+    file.resize(exif_offset as usize, 0);
+    file.extend(exif);
+    file.extend(gps);
+    file
+}
+
+/// The EXIF and GPS directories and the XMP packet of a file
+fn read_metadata(path: &str, logger: &Logger) -> (Vec<ExifEntry>, Vec<ExifEntry>, Option<Vec<u8>>) {
+    let mut reader = TiffReader::new(logger);
+    let tiff = reader.load(path).unwrap();
+    let ifd = tiff.main_ifd().unwrap();
+    (exif_utils::read_directory(&reader, ifd, tags::EXIF_IFD).unwrap().unwrap(),
+     exif_utils::read_directory(&reader, ifd, tags::GPS_IFD).unwrap().unwrap(),
+     exif_utils::read_xmp(&reader, ifd))
+}
+
+#[test]
+fn test_exif_gps_and_xmp_are_read() {
+    let dir = std::env::temp_dir();
+    let logger = Logger::new(dir.join("rasterkit_exif_tests.log").to_str().unwrap()).unwrap();
+    let path = dir.join("rasterkit_exif_drone.tif");
+    std::fs::write(&path, create_drone_tiff()).unwrap();
+
+    let (exif, gps, xmp) = read_metadata(path.to_str().unwrap(), &logger);
+    assert_eq!(exif, exif_entries());
+    assert_eq!(xmp.as_deref(), Some(XMP));
+    let shown: Vec<(&str, String)> = exif.iter()
+        .map(|entry| (exif_utils::tag_name(tags::EXIF_IFD, entry.tag), exif_utils::format_value(entry))).collect();
+    assert_eq!(shown, [("FNumber", "2.8".to_string()), ("ISOSpeedRatings", "100".to_string()),
+                       ("DateTimeOriginal", "2024:05:01 10:00:00".to_string())]);
+
+    // Degrees, minutes and seconds with the hemisphere references
+    let (latitude, longitude, altitude) = exif_utils::gps_position(&gps).unwrap();
+    assert!((latitude - 52.51).abs() < 1e-9 && (longitude + 4.9).abs() < 1e-9);
+    assert_eq!(altitude, Some(120.5));
+    assert_eq!(exif_utils::tag_name(tags::GPS_IFD, 2), "GPSLatitude");
+}
+
+#[test]
+fn test_metadata_survives_extract_and_convert() {
+    let dir = std::env::temp_dir();
+    let log = dir.join("rasterkit_exif_tests.log");
+    let kit = RasterKit::new(Some(log.to_str().unwrap())).unwrap();
+    let logger = Logger::new(log.to_str().unwrap()).unwrap();
+    let input = dir.join("rasterkit_exif_source.tif");
+    std::fs::write(&input, create_drone_tiff()).unwrap();
+    let input = input.to_str().unwrap();
+
+    let converted = dir.join("rasterkit_exif_converted.tif");
+    kit.convert_compression(input, converted.to_str().unwrap(), "deflate").unwrap();
+    let (exif, gps, xmp) = read_metadata(converted.to_str().unwrap(), &logger);
+    assert_eq!((exif, gps, xmp.as_deref()), (exif_entries(), gps_entries(), Some(XMP)));
+
+    let extracted = dir.join("rasterkit_exif_extracted.tif");
+    kit.extract(input, extracted.to_str().unwrap(), &ExtractOptions::new().region(0, 0, 1, 2)).unwrap();
+    let (exif, gps, xmp) = read_metadata(extracted.to_str().unwrap(), &logger);
+    assert_eq!((exif, gps, xmp.as_deref()), (exif_entries(), gps_entries(), Some(XMP)));
+}
@@ -0,0 +1,41 @@
+//! Tests for extending a raster to a larger bounding box
+
+use crate::coordinate::BoundingBox;
+use crate::extractor::{ArraySamples, ImageExtractor};
+use crate::utils::extend_utils;
+use crate::utils::logger::Logger;
+use super::test_utils::TestTiff;
+
+/// 2x2 single-band 16-bit image in one strip holding 1 to 4, with 30 m
+/// pixels from (500000, 4000000) and NoData 9
+fn create_small_file() -> Vec<u8> {
+    TestTiff::new(2, 2)
+        .bits(&[16])
+        .georeference(30.0, 30.0, 500000.0, 4000000.0)
+        .nodata("9")
+        .pixels((1..=4u16).flat_map(u16::to_le_bytes).collect())
+        .build()
+}
+
+#[test]
+fn test_extend_pads_with_nodata_on_the_same_grid() {
+    let dir = std::env::temp_dir();
+    let logger = Logger::new(dir.join("rasterkit_extend_tests.log").to_str().unwrap()).unwrap();
+    let input = dir.join("rasterkit_extend.tif");
+    std::fs::write(&input, create_small_file()).unwrap();
+    let output = dir.join("rasterkit_extended.tif");
+
+    // One pixel more to the west and, partly covered, one more to the south;
+    // the box ends inside the raster to the east
+    let bbox = BoundingBox::new(499970.0, 3999915.0, 500040.0, 4000000.0);
+    let padding = extend_utils::extend(input.to_str().unwrap(), output.to_str().unwrap(), &bbox, None, &logger).unwrap();
+    assert_eq!((padding.width, padding.height, padding.offset_x, padding.offset_y), (3, 3, 1, 0));
+
+    let mut extractor = ImageExtractor::new_array_extractor(&logger);
+    let array = extractor.extract_array_data(output.to_str().unwrap(), None).unwrap();
+    assert_eq!(array.data, ArraySamples::U16(vec![9, 1, 2, 9, 3, 4, 9, 9, 9]));
+    assert_eq!(array.nodata, Some(9.0));
+    let geotransform = array.georeference.expect("the output is georeferenced").geotransform;
+    assert_eq!(geotransform.apply(0.0, 0.0), (499970.0, 4000000.0));
+    assert_eq!(geotransform.pixel_width, 30.0);
+}
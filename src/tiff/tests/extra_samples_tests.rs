@@ -0,0 +1,80 @@
+//! Tests for the ExtraSamples tag of written images
+
+use image::{DynamicImage, RgbImage, RgbaImage};
+
+use crate::tiff::{IFDEntry, TiffBuilder, TiffReader};
+use crate::tiff::ifd::IFD;
+use crate::utils::logger::Logger;
+use crate::utils::tiff_extraction_utils;
+use super::test_utils::TestTiff;
+
+/// Write an image with the tags of a source IFD, as extraction does
+fn write_like_extraction(source: &str, image: &DynamicImage, output: &str, logger: &Logger) {
+    let mut reader = TiffReader::new(logger);
+    let tiff = reader.load(source).unwrap();
+    let mut builder = TiffBuilder::new(logger, false);
+    let ifd_index = builder.add_ifd(IFD::new(0, 0));
+    tiff_extraction_utils::setup_tiff_tags(&mut builder, ifd_index, tiff.main_ifd().unwrap(), &reader, image).unwrap();
+    tiff_extraction_utils::process_image_data(image, &mut builder, ifd_index).unwrap();
+    builder.write(output).unwrap();
+}
+
+#[test]
+fn test_extra_samples_follow_the_written_image() {
+    let dir = std::env::temp_dir();
+    let logger = Logger::new(dir.join("rasterkit_extra_samples_tests.log").to_str().unwrap()).unwrap();
+    let source = dir.join("rasterkit_extra_samples_rgba.tif");
+    std::fs::write(&source, TestTiff::new(2, 1).bits(&[8, 8, 8, 8]).photometric(2).shorts(338, &[2])
+        .pixels(vec![255, 0, 0, 255, 0, 0, 255, 0]).build()).unwrap();
+    let source = source.to_str().unwrap();
+    let output = dir.join("rasterkit_extra_samples_out.tif");
+    let output = output.to_str().unwrap();
+
+    // RGB output does not inherit the source's alpha declaration
+    let rgb = DynamicImage::ImageRgb8(RgbImage::from_raw(2, 1, vec![255, 0, 0, 0, 0, 255]).unwrap());
+    write_like_extraction(source, &rgb, output, &logger);
+    let mut reader = TiffReader::new(&logger);
+    let tiff = reader.load(output).unwrap();
+    let ifd = tiff.main_ifd().unwrap();
+    assert_eq!(ifd.get_samples_per_pixel(), 3);
+    assert!(!ifd.has_tag(338));
+
+    // RGBA output declares its fourth sample as unassociated alpha
+    let rgba = DynamicImage::ImageRgba8(RgbaImage::from_raw(2, 1, vec![255, 0, 0, 255, 0, 0, 255, 0]).unwrap());
+    write_like_extraction(source, &rgba, output, &logger);
+    let mut reader = TiffReader::new(&logger);
+    let tiff = reader.load(output).unwrap();
+    let ifd = tiff.main_ifd().unwrap();
+    assert_eq!(ifd.get_samples_per_pixel(), 4);
+    assert_eq!(reader.read_tag::<u16>(ifd, 258).unwrap(), [8, 8, 8, 8]);
+    assert_eq!(reader.read_tag::<u16>(ifd, 338).unwrap(), [2]);
+    let offset = ifd.get_tag_value(273).unwrap() as usize;
+    assert_eq!(std::fs::read(output).unwrap()[offset..offset + 8], [255, 0, 0, 255, 0, 0, 255, 0]);
+}
+
+#[test]
+fn test_several_extra_samples_and_removal() {
+    let dir = std::env::temp_dir();
+    let logger = Logger::new(dir.join("rasterkit_extra_samples_tests.log").to_str().unwrap()).unwrap();
+    let output = dir.join("rasterkit_extra_samples_masks.tif");
+    let output = output.to_str().unwrap();
+
+    // RGB plus an alpha and an unspecified mask channel
+    let mut builder = TiffBuilder::new(&logger, false);
+    let ifd_index = builder.add_ifd(IFD::new(0, 0));
+    builder.add_basic_rgba_tags(ifd_index, 1, 1);
+    builder.add_extra_samples(ifd_index, &[2, 0]);
+    builder.ifds[ifd_index].add_entry(IFDEntry::new(277, 3, 1, 5));
+    builder.add_bits_per_sample(ifd_index, &[8, 8, 8, 8, 8]);
+    builder.setup_single_strip(ifd_index, vec![1, 2, 3, 4, 5]);
+    builder.write(output).unwrap();
+
+    let mut reader = TiffReader::new(&logger);
+    let tiff = reader.load(output).unwrap();
+    let ifd = tiff.main_ifd().unwrap();
+    assert_eq!(reader.read_tag::<u16>(ifd, 338).unwrap(), [2, 0]);
+
+    // An empty list removes the tag and its external data
+    builder.add_extra_samples(ifd_index, &[]);
+    assert!(!builder.ifds[ifd_index].has_tag(338));
+}
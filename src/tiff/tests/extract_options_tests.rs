@@ -0,0 +1,29 @@
+//! Tests for extracting through ExtractOptions
+
+use image::GenericImageView;
+use crate::extractor::{ExtractOptions, StretchMode};
+use crate::RasterKit;
+use super::test_utils::create_two_band_tiff;
+
+#[test]
+fn test_extract_with_options() {
+    let dir = std::env::temp_dir();
+    let path = dir.join("rasterkit_extract_options.tif");
+    std::fs::write(&path, create_two_band_tiff()).unwrap();
+    let path = path.to_str().unwrap();
+    let kit = RasterKit::new(Some(dir.join("rasterkit_extract_options.log").to_str().unwrap())).unwrap();
+
+    // The second band of a region, stretched linearly over the band's 10-15
+    let options = ExtractOptions::new().region(1, 0, 2, 2).bands(&[2]).stretch(StretchMode::Linear);
+    let image = kit.extract_to_buffer(path, &options).unwrap();
+    assert_eq!(image.dimensions(), (2, 2));
+    assert_eq!(image.to_luma8().into_raw(), [52, 103, 204, 255]);
+
+    assert!(kit.extract_to_buffer(path, &ExtractOptions::new().bands(&[3])).is_err());
+    assert!(kit.extract_to_buffer(path, &ExtractOptions::new().bands(&[1, 2])).is_err());
+
+    // Rendered output is written in the requested format
+    let output = dir.join("rasterkit_extract_options.png");
+    kit.extract(path, output.to_str().unwrap(), &ExtractOptions::new().bands(&[1])).unwrap();
+    assert_eq!(image::open(&output).unwrap().dimensions(), (3, 2));
+}
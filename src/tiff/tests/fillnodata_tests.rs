@@ -0,0 +1,70 @@
+//! Tests for filling NoData gaps on the stored sample values
+
+use crate::utils::fillnodata_utils::{self, FillNodataOptions};
+use crate::utils::logger::Logger;
+use crate::utils::sample_utils::{self, SampleFormat};
+use super::test_utils::TestTiff;
+
+#[test]
+fn test_fill_band_within_search_distance() {
+    // A plane with a hole in the middle is filled with the plane's value
+    let mut plane: Vec<f64> = (0..25).map(|index| 100.0 * (index % 5) as f64 + 1000.0 * (index / 5) as f64).collect();
+    plane[12] = -9999.0;
+    let (filled, count) = fillnodata_utils::fill_nodata(&plane, 5, 5, -9999.0, &FillNodataOptions::default());
+    assert_eq!(count, 1);
+    assert!((filled[12] - 2200.0).abs() < 1e-9);
+
+    // Gaps further than the search distance from any valid pixel remain
+    let row = [5.0, 0.0, 0.0, 0.0, 0.0, 0.0, 7.0];
+    let options = FillNodataOptions { max_search_distance: 2, ..FillNodataOptions::default() };
+    let (filled, count) = fillnodata_utils::fill_nodata(&row, 7, 1, 0.0, &options);
+    assert_eq!(count, 4);
+    assert_eq!(filled, [5.0, 5.0, 5.0, 0.0, 7.0, 7.0, 7.0]);
+
+    // NaN samples are gaps whatever the NoData value
+    let (filled, count) = fillnodata_utils::fill_nodata(&[1.0, f64::NAN, 3.0], 3, 1, -1.0, &options);
+    assert_eq!((count, filled[1]), (1, 2.0));
+}
+
+#[test]
+fn test_fill_file_keeps_sample_type() {
+    let dir = std::env::temp_dir();
+    let logger = Logger::new(dir.join("rasterkit_fillnodata_tests.log").to_str().unwrap()).unwrap();
+    let fill = |name: &str, file: Vec<u8>, nodata: Option<f64>| {
+        let (input, output) = (dir.join(format!("rasterkit_{}.tif", name)), dir.join(format!("rasterkit_{}_filled.tif", name)));
+        std::fs::write(&input, file).unwrap();
+        let options = FillNodataOptions { nodata_value: nodata, ..FillNodataOptions::default() };
+        fillnodata_utils::fill_nodata_file(input.to_str().unwrap(), output.to_str().unwrap(), &options, &logger)
+            .map(|_| sample_utils::read_bands(output.to_str().unwrap(), 4, &logger).unwrap())
+    };
+
+    // Float32 elevations with a NoData of -9999, georeferencing kept
+    let elevations = [812.5f32, 813.5, 814.5, 812.5, -9999.0, 814.5, 812.5, 813.5, 814.5];
+    let file = TestTiff::new(3, 3).bits(&[32]).sample_format(3).nodata("-9999")
+        .georeference(30.0, 30.0, 500000.0, 4000000.0).epsg(32633)
+        .pixels(elevations.iter().flat_map(|value| value.to_le_bytes()).collect()).build();
+    let (bands, layout) = fill("fill_float", file, None).unwrap();
+    assert_eq!((layout.bits, layout.format), (32, SampleFormat::Float));
+    assert_eq!(bands[0][4], 813.5);
+    assert_eq!(bands[0][0], 812.5);
+    let record = crate::utils::catalog_utils::read_record(
+        dir.join("rasterkit_fill_float_filled.tif").to_str().unwrap(), &logger).unwrap();
+    assert_eq!((record.epsg, record.nodata), (Some(32633), Some(-9999.0)));
+
+    // Two Int16 bands with values outside the 8-bit range, and gaps of their own
+    let samples: [i16; 6] = [-500, 0, 0, 1500, 700, -300];
+    let file = TestTiff::new(3, 1).bits(&[16, 16]).sample_format(2).photometric(1).nodata("0")
+        .pixels(samples.iter().flat_map(|value| value.to_le_bytes()).collect()).build();
+    let (bands, layout) = fill("fill_i16", file, None).unwrap();
+    assert_eq!((layout.bands, layout.format), (2, SampleFormat::Signed));
+    assert_eq!(bands[0], [-500.0, 100.0, 700.0]);
+    assert_eq!(bands[1], [1500.0, 1500.0, -300.0]);
+
+    // A UInt16 gap value given on the command line
+    let samples: [u16; 3] = [60000, 65535, 62000];
+    let file = TestTiff::new(3, 1).bits(&[16])
+        .pixels(samples.iter().flat_map(|value| value.to_le_bytes()).collect()).build();
+    assert!(fill("fill_u16", file.clone(), None).is_err());
+    let (bands, _) = fill("fill_u16", file, Some(65535.0)).unwrap();
+    assert_eq!(bands[0], [60000.0, 61000.0, 62000.0]);
+}
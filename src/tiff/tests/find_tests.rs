@@ -0,0 +1,79 @@
+//! Tests for scanning raster headers and querying them
+
+use crate::utils::acquisition_utils::Timestamp;
+use crate::utils::catalog_utils::{self, RasterRecord};
+use crate::utils::logger::Logger;
+use crate::utils::query_utils::Query;
+use super::test_utils::create_utm_tiff;
+
+/// A record of a large compressed scene acquired in June 2023
+fn scene_record() -> RasterRecord {
+    RasterRecord {
+        path: "/data/landsat/LC08_scene.tif".to_string(),
+        file_size: Some(52_000_000),
+        width: 12000,
+        height: 11000,
+        bands: 1,
+        data_type: "UInt16",
+        compression: "zstd".to_string(),
+        epsg: Some(3857),
+        resolution: Some(30.0),
+        bounds: None,
+        footprint: None,
+        nodata: None,
+        overviews: 4,
+        tiled: true,
+        big_tiff: false,
+        acquired: Timestamp::parse("2023-06-14T10:32:05"),
+    }
+}
+
+#[test]
+fn test_query_matches_record_fields() {
+    let record = scene_record();
+    let matches = |text: &str| Query::parse(text).unwrap().matches(&record);
+
+    assert!(matches("epsg=3857 && compression=zstd && width>10000"));
+    assert!(matches("compression=ZSTD && path~landsat && name~lc08_"));
+    assert!(!matches("epsg=3857 && width>20000"));
+    assert!(matches("width>20000 || tiled=true"));
+    assert!(matches("!(bands>1) && overviews>=4"));
+    // A date stands for its whole day, and a missing field never matches
+    assert!(matches("acquired=2023-06-14 && acquired<2023-07-01"));
+    assert!(!matches("acquired>2023-06-14"));
+    assert!(!matches("nodata=0") && !matches("nodata!=0"));
+
+    assert!(Query::parse("colour=red").is_err());
+    assert!(Query::parse("width>wide").is_err());
+    assert!(Query::parse("compression<zstd").is_err());
+    assert!(Query::parse("(width>1").is_err());
+    assert!(Query::parse("width>1 &&").is_err());
+}
+
+#[test]
+fn test_find_scans_headers_under_a_directory() {
+    let dir = std::env::temp_dir().join("rasterkit_find");
+    let _ = std::fs::remove_dir_all(&dir);
+    std::fs::create_dir_all(dir.join("nested")).unwrap();
+    let logger = Logger::new(dir.join("find.log").to_str().unwrap()).unwrap();
+    std::fs::write(dir.join("nested").join("utm.TIF"), create_utm_tiff(500000.0)).unwrap();
+    std::fs::write(dir.join("broken.tif"), b"not a tiff").unwrap();
+    std::fs::write(dir.join("notes.txt"), b"not a raster").unwrap();
+
+    let paths = catalog_utils::collect_rasters(dir.to_str().unwrap()).unwrap();
+    assert_eq!(paths.len(), 2);
+
+    // The unreadable file is skipped
+    let records = catalog_utils::scan(&paths, &logger);
+    assert_eq!(records.len(), 1);
+    let record = &records[0];
+    assert!(record.path.ends_with("utm.TIF"));
+    assert_eq!((record.width, record.height, record.bands), (2, 2, 1));
+    assert_eq!((record.data_type, record.compression.as_str()), ("Byte", "none"));
+    assert_eq!(record.epsg, Some(32633));
+    assert_eq!(record.bounds, Some((500000.0, 3999940.0, 500060.0, 4000000.0)));
+    assert!(Query::parse("epsg=32633 && resolution=30 && tiled=false").unwrap().matches(record));
+
+    let json = catalog_utils::records_json(&records);
+    assert!(json.contains("\"epsg\": 32633") && json.contains("\"bounds\": [500000, 3999940, 500060, 4000000]"));
+}
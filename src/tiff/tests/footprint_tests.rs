@@ -0,0 +1,35 @@
+//! Tests for footprint outline tracing
+
+use crate::utils::footprint_utils;
+
+#[test]
+fn test_trace_outline() {
+    // A ring of cells around a hole, and a cell touching it only at a corner
+    let rows = [
+        "###.",
+        "#.#.",
+        "###.",
+        "...#",
+    ];
+    let mask: Vec<bool> = rows.iter().flat_map(|row| row.chars().map(|c| c == '#')).collect();
+
+    let mut polygons = footprint_utils::trace_outline(&mask, 4, 4);
+    polygons.sort_by_key(|polygon| polygon.len());
+    assert_eq!(polygons.len(), 2);
+
+    let corner = &polygons[0];
+    assert_eq!(corner.len(), 1);
+    let mut vertices = corner[0].clone();
+    vertices.sort_by(|a, b| a.partial_cmp(b).unwrap());
+    assert_eq!(vertices, vec![(3.0, 3.0), (3.0, 4.0), (4.0, 3.0), (4.0, 4.0)]);
+
+    let ring = &polygons[1];
+    assert_eq!(ring.len(), 2);
+    assert_eq!(ring[0].len(), 4);
+    assert_eq!(ring[1].len(), 4);
+    assert!(ring[1].contains(&(1.0, 1.0)) && ring[1].contains(&(2.0, 2.0)));
+
+    // A staircase simplifies to its diagonal within a cell
+    let staircase = vec![(0.0, 0.0), (1.0, 0.0), (1.0, 1.0), (2.0, 1.0), (2.0, 2.0), (3.0, 2.0), (3.0, 3.0), (0.0, 3.0)];
+    assert_eq!(footprint_utils::simplify_ring(&staircase, 1.0).len(), 3);
+}
@@ -0,0 +1,146 @@
+//! Tests for GeoKey projection parameter parsing
+
+extern crate std;
+
+use crate::tiff::GeoKeyEntry;
+use crate::tiff::constants::{tags, geo_keys};
+use crate::tiff::geo_key_parser::{GeoInfo, ProjectionParameters};
+use crate::tiff::proj_definition::ProjDefinition;
+use crate::coordinate::{Affine, BoundingBox, CoordinateSystemFactory, CoordinateTransformer};
+
+#[test]
+fn test_projection_parameters_from_geo_keys() {
+    let keys = vec![
+        GeoKeyEntry::new(geo_keys::CITATION, tags::GEO_ASCII_PARAMS_TAG, 10, 0),
+        GeoKeyEntry::new(geo_keys::GEOG_GEODETIC_DATUM, 0, 1, 6326),
+        GeoKeyEntry::new(geo_keys::PROJ_COORD_TRANS, 0, 1, 1),
+        GeoKeyEntry::new(geo_keys::PROJ_NAT_ORIGIN_LONG, tags::GEO_DOUBLE_PARAMS_TAG, 1, 0),
+        GeoKeyEntry::new(geo_keys::PROJ_FALSE_EASTING, tags::GEO_DOUBLE_PARAMS_TAG, 1, 1),
+        GeoKeyEntry::new(geo_keys::PROJ_SCALE_AT_NAT_ORIGIN, tags::GEO_DOUBLE_PARAMS_TAG, 1, 2),
+    ];
+    let doubles = [9.0, 500000.0, 0.9996];
+
+    let params = ProjectionParameters::from_geo_keys(&keys, &doubles, "Custom TM|");
+
+    std::assert_eq!(params.citation.as_deref(), Some("Custom TM"));
+    std::assert_eq!(params.datum_code, 6326);
+    std::assert_eq!(params.coord_transform_code, 1);
+    std::assert_eq!(params.natural_origin_longitude, Some(9.0));
+    std::assert_eq!(params.false_easting, Some(500000.0));
+    std::assert_eq!(params.scale_at_natural_origin, Some(0.9996));
+    std::assert_eq!(params.false_northing, None);
+}
+
+#[test]
+fn test_ellipsoid_axes_from_datum_and_flattening() {
+    let wgs84 = ProjectionParameters { datum_code: 6326, ..Default::default() };
+    let (a, b) = wgs84.ellipsoid_axes().unwrap();
+    std::assert_eq!(a, 6378137.0);
+    std::assert!((b - 6356752.314245).abs() < 1e-3);
+
+    let sphere = ProjectionParameters {
+        semi_major_axis: Some(6371000.0),
+        inverse_flattening: Some(0.0),
+        ..Default::default()
+    };
+    std::assert_eq!(sphere.ellipsoid_axes(), Some((6371000.0, 6371000.0)));
+}
+
+#[test]
+fn test_proj_definition_strings() {
+    let utm = ProjDefinition::from_epsg(32733).unwrap();
+    std::assert_eq!(utm.to_proj_string(),
+                    "+proj=tmerc +lat_0=0 +lon_0=15 +k=0.9996 +x_0=500000 +y_0=10000000 +datum=WGS84 +units=m +no_defs");
+    std::assert!(utm.to_projjson().contains("\"id\":{\"authority\":\"EPSG\",\"code\":32733}"));
+
+    let mut geo_info = GeoInfo::new();
+    geo_info.epsg_code = 32767;
+    geo_info.projection = ProjectionParameters {
+        datum_code: 6269,
+        coord_transform_code: 8,
+        standard_parallel_1: Some(33.0),
+        standard_parallel_2: Some(45.0),
+        false_origin_latitude: Some(23.0),
+        false_origin_longitude: Some(-96.0),
+        ..Default::default()
+    };
+    std::assert_eq!(ProjDefinition::from_geo_info(&geo_info).unwrap().to_proj_string(),
+                    "+proj=lcc +lat_0=23 +lon_0=-96 +lat_1=33 +lat_2=45 +x_0=0 +y_0=0 +datum=NAD83 +units=m +no_defs");
+}
+
+#[test]
+fn test_crs_definition_from_proj_string_and_wkt() {
+    let utm = CoordinateSystemFactory::parse_definition("+proj=utm +zone=33 +south +datum=WGS84 +units=m").unwrap();
+    std::assert_eq!(utm.epsg, 32733);
+
+    let mercator = CoordinateSystemFactory::parse_definition(
+        "+proj=merc +a=6378137 +b=6378137 +lat_ts=0 +lon_0=0 +x_0=0 +y_0=0 +k=1 +units=m +nadgrids=@null +wktext +no_defs").unwrap();
+    std::assert_eq!(mercator.epsg, 3857);
+
+    let local = CoordinateSystemFactory::parse_definition(
+        "PROJCRS[\"Site grid\",BASEGEOGCRS[\"ETRS89\",DATUM[\"European Terrestrial Reference System 1989\",\
+         ELLIPSOID[\"GRS 1980\",6378137,298.257222101]]],CONVERSION[\"Site TM\",METHOD[\"Transverse Mercator\"],\
+         PARAMETER[\"Latitude of natural origin\",0],PARAMETER[\"Longitude of natural origin\",12.5],\
+         PARAMETER[\"Scale factor at natural origin\",1],PARAMETER[\"False easting\",1000],\
+         PARAMETER[\"False northing\",-5000000]],CS[Cartesian,2],AXIS[\"easting\",east],AXIS[\"northing\",north],\
+         LENGTHUNIT[\"metre\",1]]").unwrap();
+    std::assert!(local.is_user_defined());
+    std::assert_eq!(local.definition.unwrap().to_proj_string(),
+                    "+proj=tmerc +lat_0=0 +lon_0=12.5 +k=1 +x_0=1000 +y_0=-5000000 +ellps=GRS80 +towgs84=0,0,0,0,0,0,0 +units=m +no_defs");
+}
+
+#[test]
+fn test_datum_shift_projection() {
+    let transformer = CoordinateTransformer;
+    let wgs84 = ProjDefinition::from_epsg(4326).unwrap();
+
+    // Latitude 45 on the central meridian of UTM zone 15
+    let utm = transformer.geographic_to_definition(-93.0, 45.0, &wgs84,
+        &ProjDefinition::from_epsg(32615).unwrap(), None).unwrap();
+    std::assert!((utm.x - 500_000.0).abs() < 0.01);
+    std::assert!((utm.y - 4_982_950.40).abs() < 0.01);
+
+    // The same position lies about 200 m further south in NAD27 coordinates
+    let nad27 = transformer.geographic_to_definition(-93.0, 45.0, &wgs84,
+        &ProjDefinition::from_epsg(26715).unwrap(), None).unwrap();
+    std::assert!((nad27.x - utm.x - 16.4).abs() < 1.0);
+    std::assert!((utm.y - nad27.y - 216.4).abs() < 1.0);
+}
+
+#[test]
+fn test_affine_geotransform() {
+    let gt = Affine::from_scale_and_tiepoint(&[10.0, 10.0, 0.0], &[0.0, 0.0, 0.0, 500_000.0, 4_000_000.0, 0.0]).unwrap();
+    std::assert_eq!(gt.to_gdal(), [500_000.0, 10.0, 0.0, 4_000_000.0, 0.0, -10.0]);
+    std::assert_eq!(gt.to_pixel(500_100.0, 3_999_800.0), Some((10.0, 20.0)));
+
+    // A rotated transform round-trips through its inverse
+    let rotated = Affine::from_gdal([100.0, 2.0, 0.5, 200.0, 0.25, -3.0]);
+    let (x, y) = rotated.inverse().unwrap().compose(&rotated).apply(7.0, 9.0);
+    std::assert!((x - 7.0).abs() < 1e-9 && (y - 9.0).abs() < 1e-9);
+
+    // Windows round outward; overview transforms double the pixel size
+    let bbox = BoundingBox::new(500_105.0, 3_999_695.0, 500_295.0, 3_999_795.0);
+    std::assert_eq!(gt.pixel_window(&bbox), Some((10, 20, 30, 31)));
+    std::assert_eq!(gt.translated(10, 20).origin_x, 500_100.0);
+    std::assert_eq!(gt.scaled(2.0, 2.0).pixel_height, -20.0);
+    let snapped = gt.snap_to_grid(&bbox).unwrap();
+    std::assert_eq!((snapped.min_x, snapped.max_y), (500_100.0, 3_999_800.0));
+}
+
+#[test]
+fn test_inverse_projection() {
+    use crate::coordinate::projection;
+    use crate::utils::info_utils::format_dms;
+
+    let utm = ProjDefinition::from_epsg(32615).unwrap();
+    let point = projection::inverse(&utm, 500_000.0, 4_982_950.40).unwrap();
+    std::assert!((point.x + 93.0).abs() < 1e-7 && (point.y - 45.0).abs() < 1e-7);
+
+    let mercator = ProjDefinition::from_epsg(3857).unwrap();
+    let forward = projection::forward(&mercator, 12.5, -33.25).unwrap();
+    let back = projection::inverse(&mercator, forward.x, forward.y).unwrap();
+    std::assert!((back.x - 12.5).abs() < 1e-9 && (back.y + 33.25).abs() < 1e-9);
+
+    std::assert_eq!(format_dms(-117.641169, 'E', 'W'), "117d38'28.21\"W");
+    std::assert_eq!(format_dms(33.9999999, 'N', 'S'), " 34d 0' 0.00\"N");
+}
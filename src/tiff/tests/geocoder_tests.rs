@@ -0,0 +1,41 @@
+//! Tests for place name lookup
+
+use crate::coordinate::geocoder::parse_nominatim_response;
+use crate::coordinate::{Geocoder, LonLat, NominatimGeocoder};
+use crate::tiff::errors::{TiffError, TiffResult};
+
+/// Fixed gazetteer standing in for a lookup service
+struct Gazetteer;
+
+impl Geocoder for Gazetteer {
+    fn geocode(&self, place: &str) -> TiffResult<LonLat> {
+        match place {
+            "Berlin" => LonLat::new(13.3888599, 52.5170365),
+            _ => Err(TiffError::GenericError(format!("No place found for '{}'", place))),
+        }
+    }
+}
+
+#[test]
+fn test_parse_nominatim_response() {
+    let body = r#"[{"place_id":1,"lat":"52.5170365","lon": "13.3888599","display_name":"Berlin, \"Deutschland\""},
+                   {"lat":"0","lon":"0"}]"#;
+    let (position, name) = parse_nominatim_response(body).unwrap().unwrap();
+    assert_eq!((position.lon(), position.lat()), (13.3888599, 52.5170365));
+    assert_eq!(name.as_deref(), Some("Berlin, \"Deutschland\""));
+
+    assert!(parse_nominatim_response("[]").unwrap().is_none());
+    assert!(parse_nominatim_response(r#"{"error":"Bad request"}"#).is_err());
+    assert!(parse_nominatim_response(r#"[{"lat":"95","lon":"13"}]"#).is_err());
+    assert!(parse_nominatim_response(r#"[{"lat":"52.5"}]"#).is_err());
+}
+
+#[test]
+fn test_geocoders() {
+    let geocoder: &dyn Geocoder = &Gazetteer;
+    assert_eq!(geocoder.geocode("Berlin").unwrap().lat(), 52.5170365);
+    assert!(geocoder.geocode("Atlantis").is_err());
+
+    assert_eq!(NominatimGeocoder::with_endpoint("http://localhost:8080/").endpoint(), "http://localhost:8080");
+    assert_eq!(NominatimGeocoder::new().endpoint(), "https://nominatim.openstreetmap.org");
+}
@@ -0,0 +1,50 @@
+//! Tests for reading UTM coordinates and MGRS references
+
+use crate::utils::coordinate_utils::{parse_human_coordinate, HumanCoordinate};
+use crate::utils::grid_reference_utils::{parse_mgrs, parse_utm, GridReference};
+
+#[test]
+fn test_utm_coordinates() {
+    let expected = GridReference { zone: 33, north: true, easting: 500100.0, northing: 3999950.0 };
+    assert_eq!(parse_utm("33N 500100 3999950").unwrap(), Some(expected));
+    assert_eq!(parse_utm("33 N, 500100mE, 3999950mN").unwrap(), Some(expected));
+    // A latitude band instead of the hemisphere
+    assert_eq!(parse_utm("33S 500100 3999950").unwrap().map(|r| r.north), Some(false));
+    assert_eq!(parse_utm("33T 500100 3999950").unwrap().map(|r| r.north), Some(true));
+
+    // Not UTM at all, or UTM that cannot be
+    assert_eq!(parse_utm("52.52N 13.41E").unwrap(), None);
+    assert!(parse_utm("33N 50 3999950").is_err());
+    assert!(parse_utm("61N 500100 3999950").unwrap().is_none());
+}
+
+#[test]
+fn test_mgrs_references() {
+    // Centre of the referenced 1 m square
+    let reference = parse_mgrs("33SWV0010099950").unwrap().unwrap();
+    assert_eq!((reference.zone, reference.north), (33, true));
+    assert_eq!((reference.easting, reference.northing), (500100.5, 3999950.5));
+    assert_eq!(parse_mgrs("33s wv 00100 99950").unwrap(), Some(reference));
+
+    // Lower precision refers to larger squares
+    let coarse = parse_mgrs("33SWV0099").unwrap().unwrap();
+    assert_eq!((coarse.easting, coarse.northing), (500500.0, 3999500.0));
+
+    // Even zones start their rows at F
+    let even = parse_mgrs("32UPU0000050000").unwrap().unwrap();
+    assert_eq!(even.northing.floor(), 5_350_000.0);
+
+    assert_eq!(parse_mgrs("33N 500100 3999950").unwrap(), None);
+    assert!(parse_mgrs("33SWV001009995").is_err());
+    assert!(parse_mgrs("33SAV0010099950").is_err());
+    assert!(parse_mgrs("33PWV0010099950").is_err());
+}
+
+#[test]
+fn test_grid_references_as_coordinates() {
+    let HumanCoordinate::LonLat(lon, lat) = parse_human_coordinate("33N 500100 3999950").unwrap() else {
+        panic!("UTM coordinate not read as longitude and latitude");
+    };
+    assert!((lon - 15.001112).abs() < 1e-6 && (lat - 36.144267).abs() < 1e-6, "{} {}", lon, lat);
+    assert!(matches!(parse_human_coordinate("33SWV0010099950").unwrap(), HumanCoordinate::LonLat(..)));
+}
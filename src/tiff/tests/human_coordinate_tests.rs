@@ -0,0 +1,42 @@
+//! Tests for parsing coordinates written by hand
+
+use crate::utils::coordinate_utils::{parse_human_coordinate, HumanCoordinate};
+
+/// Longitude and latitude of a coordinate whose order the input fixes
+fn lon_lat(input: &str) -> (f64, f64) {
+    match parse_human_coordinate(input).unwrap() {
+        HumanCoordinate::LonLat(lon, lat) => ((lon * 1e6).round() / 1e6, (lat * 1e6).round() / 1e6),
+        other => panic!("'{}' parsed as {:?}", input, other),
+    }
+}
+
+#[test]
+fn test_degrees_minutes_seconds() {
+    assert_eq!(lon_lat("52°31'12\"N 13°24'36\"E"), (13.41, 52.52));
+    assert_eq!(lon_lat("N52 31 12, E13 24 36"), (13.41, 52.52));
+    assert_eq!(lon_lat("13d24'36.00\"E, 52d31'12.00\"N"), (13.41, 52.52));
+    assert_eq!(lon_lat("33°52′4″S 151°12′26″E"), (151.207222, -33.867778));
+}
+
+#[test]
+fn test_hemisphere_letters() {
+    assert_eq!(lon_lat("52.52N,13.41E"), (13.41, 52.52));
+    assert_eq!(lon_lat("13.41e 52.52n"), (13.41, 52.52));
+    assert_eq!(lon_lat("56.13484N, 109.22624W"), (-109.22624, 56.13484));
+    // One letter is enough to tell the other value apart
+    assert_eq!(lon_lat("52.52N, 13.41"), (13.41, 52.52));
+}
+
+#[test]
+fn test_map_urls_and_plain_numbers() {
+    assert_eq!(lon_lat("https://www.google.com/maps/@52.5200066,13.404954,14z"), (13.404954, 52.520007));
+    assert_eq!(parse_human_coordinate("-109.22624, 56.13484").unwrap(), HumanCoordinate::Plain(-109.22624, 56.13484));
+    assert_eq!(parse_human_coordinate("52°, 13°").unwrap(), HumanCoordinate::Plain(52.0, 13.0));
+}
+
+#[test]
+fn test_unreadable_coordinates() {
+    for input in ["52.52N 13.41N", "52.52", "1,2,3", "52°75'N 13E", "-52.5S 13E", "52.52Q 13.41E", "95N 13E"] {
+        assert!(parse_human_coordinate(input).is_err(), "'{}' was accepted", input);
+    }
+}
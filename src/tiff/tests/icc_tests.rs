@@ -0,0 +1,96 @@
+//! Tests for carrying and embedding ICC color profiles
+
+use crate::RasterKit;
+use crate::extractor::{ExtractOptions, ImageExtractor};
+use crate::tiff::TiffReader;
+use crate::utils::icc_utils;
+use crate::utils::logger::Logger;
+use super::test_utils::TestTiff;
+
+/// A minimal profile: header with size, color space and signature, then a filler byte
+fn create_profile(filler: u8) -> Vec<u8> {
+    let mut profile = vec![0u8; 132];
+    profile[..4].copy_from_slice(&132u32.to_be_bytes());
+    profile[16..20].copy_from_slice(b"RGB ");
+    profile[36..40].copy_from_slice(b"acsp");
+    profile[128..].fill(filler);
+    profile
+}
+
+/// The ICC profile of the first IFD of a file, if it has one
+fn read_profile(path: &str, logger: &Logger) -> Option<Vec<u8>> {
+    let mut reader = TiffReader::new(logger);
+    let tiff = reader.load(path).unwrap();
+    let ifd = tiff.main_ifd().unwrap();
+    ifd.has_tag(34675).then(|| reader.read_tag::<u8>(ifd, 34675).unwrap())
+}
+
+#[test]
+fn test_icc_profile_survives_extract_and_convert() {
+    let dir = std::env::temp_dir();
+    let log = dir.join("rasterkit_icc_tests.log");
+    let kit = RasterKit::new(Some(log.to_str().unwrap())).unwrap();
+    let logger = Logger::new(log.to_str().unwrap()).unwrap();
+    let profile = create_profile(7);
+    let input = dir.join("rasterkit_icc_source.tif");
+    std::fs::write(&input, TestTiff::new(4, 2).bits(&[8, 8, 8]).photometric(2)
+        .tag(34675, 7, profile.len() as u32, profile.clone())
+        .pixels((0..24).collect()).build()).unwrap();
+    let input = input.to_str().unwrap();
+
+    let converted = dir.join("rasterkit_icc_converted.tif");
+    kit.convert_compression(input, converted.to_str().unwrap(), "deflate").unwrap();
+    assert_eq!(read_profile(converted.to_str().unwrap(), &logger), Some(profile.clone()));
+
+    let extracted = dir.join("rasterkit_icc_extracted.tif");
+    kit.extract(input, extracted.to_str().unwrap(), &ExtractOptions::new().region(1, 0, 2, 2)).unwrap();
+    assert_eq!(read_profile(extracted.to_str().unwrap(), &logger), Some(profile));
+}
+
+#[test]
+fn test_embedding_replaces_the_profile() {
+    let dir = std::env::temp_dir();
+    let log = dir.join("rasterkit_icc_tests.log");
+    let kit = RasterKit::new(Some(log.to_str().unwrap())).unwrap();
+    let logger = Logger::new(log.to_str().unwrap()).unwrap();
+    let tiff = dir.join("rasterkit_icc_embedded.tif");
+    std::fs::write(&tiff, TestTiff::new(3, 1).georeference(1.0, 1.0, 10.0, 20.0).pixels(vec![5, 6, 7]).build()).unwrap();
+    let tiff = tiff.to_str().unwrap();
+
+    let first = dir.join("rasterkit_icc_first.icc");
+    std::fs::write(&first, create_profile(1)).unwrap();
+    kit.embed_icc_profile(tiff, first.to_str().unwrap()).unwrap();
+    assert_eq!(read_profile(tiff, &logger), Some(create_profile(1)));
+
+    // A second profile replaces the first, and the pixels and other tags are untouched
+    let second = dir.join("rasterkit_icc_second.icc");
+    std::fs::write(&second, create_profile(2)).unwrap();
+    kit.embed_icc_profile(tiff, second.to_str().unwrap()).unwrap();
+    let mut reader = TiffReader::new(&logger);
+    let loaded = reader.load(tiff).unwrap();
+    let ifd = loaded.main_ifd().unwrap();
+    assert_eq!(ifd.entries.iter().filter(|entry| entry.tag == 34675).count(), 1);
+    assert_eq!(reader.read_tag::<u8>(ifd, 34675).unwrap(), create_profile(2));
+    assert_eq!(reader.read_tag::<f64>(ifd, 33922).unwrap()[3..5], [10.0, 20.0]);
+    let image = ImageExtractor::new(&logger).extract_image(tiff, None).unwrap().to_luma8();
+    assert_eq!(image.into_raw(), [5, 6, 7]);
+}
+
+#[test]
+fn test_invalid_profiles_are_refused() {
+    let dir = std::env::temp_dir();
+    let path = dir.join("rasterkit_icc_invalid.icc");
+
+    let mut unsigned = create_profile(0);
+    unsigned[36..40].copy_from_slice(b"xxxx");
+    std::fs::write(&path, unsigned).unwrap();
+    assert!(icc_utils::load_icc_profile(path.to_str().unwrap()).is_err());
+
+    let mut truncated = create_profile(0);
+    truncated.truncate(130);
+    std::fs::write(&path, truncated).unwrap();
+    assert!(icc_utils::load_icc_profile(path.to_str().unwrap()).is_err());
+
+    std::fs::write(&path, create_profile(0)).unwrap();
+    assert_eq!(icc_utils::load_icc_profile(path.to_str().unwrap()).unwrap().len(), 132);
+}
@@ -0,0 +1,53 @@
+//! Tests for writing catalog indexes
+
+use crate::utils::gpkg_utils;
+use crate::utils::index_utils::{self, IndexFormat};
+use crate::utils::logger::Logger;
+use crate::utils::query_utils::Query;
+use super::test_utils::create_utm_tiff;
+
+#[test]
+fn test_index_writes_footprints_and_fields() {
+    let dir = std::env::temp_dir().join("rasterkit_index");
+    let _ = std::fs::remove_dir_all(&dir);
+    std::fs::create_dir_all(&dir).unwrap();
+    let logger = Logger::new(dir.join("index.log").to_str().unwrap()).unwrap();
+    std::fs::write(dir.join("west.tif"), create_utm_tiff(500000.0)).unwrap();
+    std::fs::write(dir.join("east.tif"), create_utm_tiff(500060.0)).unwrap();
+    let root = dir.to_str().unwrap();
+
+    let geojson = dir.join("index.geojson");
+    assert_eq!(index_utils::build_index(root, geojson.to_str().unwrap(), None, &logger).unwrap(), 2);
+    let text = std::fs::read_to_string(&geojson).unwrap();
+    assert_eq!(text.matches("\"type\": \"Polygon\"").count(), 2);
+    assert!(text.contains("\"epsg\": 32633") && text.contains("\"bounds\": [500060, 3999940, 500120, 4000000]"));
+    // Near 15 degrees east, where zone 33's central meridian runs
+    assert!(text.contains("[15.00000000,36.14471810]"));
+
+    // The query keeps the eastern raster only
+    let gpkg = dir.join("index.gpkg");
+    let query = Query::parse("name~east").unwrap();
+    assert_eq!(index_utils::build_index(root, gpkg.to_str().unwrap(), Some(&query), &logger).unwrap(), 1);
+    let bytes = std::fs::read(&gpkg).unwrap();
+    assert!(bytes.starts_with(b"SQLite format 3\0"));
+    assert_eq!(&bytes[68..72], b"GPKG");
+}
+
+#[test]
+fn test_index_format_and_geometry_encoding() {
+    assert_eq!(IndexFormat::from_path("catalog.GPKG"), IndexFormat::GeoPackage);
+    assert_eq!(IndexFormat::from_path("catalog.geojson"), IndexFormat::GeoJson);
+
+    let blob = gpkg_utils::polygon_blob(&[vec![(0.0, 0.0), (2.0, 0.0), (2.0, 1.0), (0.0, 1.0)]]);
+    assert_eq!(&blob[..4], &[b'G', b'P', 0, 3]);
+    assert_eq!(i32::from_le_bytes(blob[4..8].try_into().unwrap()), 4326);
+    // Envelope as min x, max x, min y, max y
+    let envelope: Vec<f64> = blob[8..40].chunks(8).map(|bytes| f64::from_le_bytes(bytes.try_into().unwrap())).collect();
+    assert_eq!(envelope, [0.0, 2.0, 0.0, 1.0]);
+    // Little-endian WKB polygon of one ring, closed to five points
+    assert_eq!(blob[40], 1);
+    assert_eq!(u32::from_le_bytes(blob[41..45].try_into().unwrap()), 3);
+    assert_eq!(u32::from_le_bytes(blob[45..49].try_into().unwrap()), 1);
+    assert_eq!(u32::from_le_bytes(blob[49..53].try_into().unwrap()), 5);
+    assert_eq!(blob.len(), 53 + 5 * 16);
+}
@@ -0,0 +1,49 @@
+//! Tests for journals of interrupted jobs
+
+use crate::utils::journal_utils::Journal;
+
+fn journal_path(name: &str) -> std::path::PathBuf {
+    std::env::temp_dir().join(format!("rasterkit-{}-{}.journal", name, std::process::id()))
+}
+
+#[test]
+fn test_resume_sees_committed_entries_only() {
+    let path = journal_path("resume");
+    let mut journal = Journal::open(&path, "convert a.tif", false).unwrap();
+    assert!(!journal.is_resuming());
+    journal.record("ifd0/strip0".to_string(), "8 100".to_string());
+    journal.record("ifd0/strip1".to_string(), "108 90".to_string());
+    assert_eq!(journal.pending(), 2);
+    journal.commit().unwrap();
+    assert_eq!(journal.pending(), 0);
+    journal.record("ifd0/strip2".to_string(), "200 90".to_string());
+    drop(journal);
+
+    let journal = Journal::open(&path, "convert a.tif", true).unwrap();
+    assert!(journal.is_resuming());
+    assert_eq!(journal.completed("ifd0/strip1"), Some("108 90"));
+    assert_eq!(journal.completed("ifd0/strip2"), None);
+    journal.finish().unwrap();
+    assert!(!path.exists());
+}
+
+#[test]
+fn test_journal_of_another_job_or_cut_short_is_not_honored() {
+    let path = journal_path("other");
+    std::fs::write(&path, "rasterkit-journal 1 convert a.tif\nifd0/strip0\t8 100\nifd0/strip1\t108 9").unwrap();
+
+    let journal = Journal::open(&path, "convert a.tif", true).unwrap();
+    assert_eq!(journal.completed("ifd0/strip0"), Some("8 100"));
+    assert_eq!(journal.completed("ifd0/strip1"), None);
+    drop(journal);
+
+    let journal = Journal::open(&path, "convert b.tif", true).unwrap();
+    assert!(!journal.is_resuming());
+    drop(journal);
+
+    // Without resuming, an existing journal starts over
+    std::fs::write(&path, "rasterkit-journal 1 convert a.tif\nifd0/strip0\t8 100\n").unwrap();
+    let journal = Journal::open(&path, "convert a.tif", false).unwrap();
+    assert_eq!(journal.completed("ifd0/strip0"), None);
+    journal.finish().unwrap();
+}
@@ -0,0 +1,48 @@
+//! Tests for decoding JPEG2000 through OpenJPEG (`--features jpeg2000`)
+
+use crate::extractor::{ArraySamples, ExtractorStrategy, Jpeg2000ExtractorStrategy, Region};
+use crate::utils::logger::Logger;
+
+/// A 4x4 single-tile, 8-bit grey J2K codestream whose one packet is empty
+///
+/// With no coded data every coefficient is zero, so after the DC level
+/// shift each pixel decodes to 128.
+fn create_codestream() -> Vec<u8> {
+    let mut codestream = vec![0xFF, 0x4F];                                   // SOC
+    codestream.extend_from_slice(&[0xFF, 0x51, 0x00, 0x29, 0x00, 0x00]);      // SIZ, Rsiz
+    for value in [4u32, 4, 0, 0, 4, 4, 0, 0] {                               // image, offset, tile, tile offset
+        codestream.extend_from_slice(&value.to_be_bytes());
+    }
+    codestream.extend_from_slice(&[0x00, 0x01, 0x07, 0x01, 0x01]);          // one unsigned 8-bit component
+    codestream.extend_from_slice(&[0xFF, 0x52, 0x00, 0x0C, 0x00,             // COD
+                                   0x00, 0x00, 0x01, 0x00,                   // LRCP, one layer, no MCT
+                                   0x00, 0x04, 0x04, 0x00, 0x01]);           // no decomposition, 64x64 blocks, 5/3
+    codestream.extend_from_slice(&[0xFF, 0x5C, 0x00, 0x04, 0x40, 0x40]);    // QCD, no quantization
+    codestream.extend_from_slice(&[0xFF, 0x90, 0x00, 0x0A, 0x00, 0x00,       // SOT of tile 0
+                                   0x00, 0x00, 0x00, 0x0F, 0x00, 0x01]);     // 15 bytes, one tile-part
+    codestream.extend_from_slice(&[0xFF, 0x93, 0x00]);                      // SOD and an empty packet
+    codestream.extend_from_slice(&[0xFF, 0xD9]);                            // EOC
+    codestream
+}
+
+#[test]
+fn test_jpeg2000_decodes_image_and_region() {
+    let dir = std::env::temp_dir();
+    let logger = Logger::new(dir.join("rasterkit_jpeg2000_tests.log").to_str().unwrap()).unwrap();
+    let path = dir.join("rasterkit_empty.j2k");
+    std::fs::write(&path, create_codestream()).unwrap();
+
+    let mut strategy = Jpeg2000ExtractorStrategy::new(&logger);
+    let array = strategy.extract_array_data(path.to_str().unwrap(), None).unwrap();
+    assert_eq!((array.width, array.height), (4, 4));
+    assert_eq!(array.data, ArraySamples::U8(vec![128; 16]));
+
+    let region = strategy.extract_array_data(path.to_str().unwrap(), Some(Region::new(1, 2, 3, 2))).unwrap();
+    assert_eq!((region.width, region.height), (3, 2));
+
+    // Regions off the image and files that are not JPEG2000 are refused
+    assert!(strategy.extract_array_data(path.to_str().unwrap(), Some(Region::new(2, 2, 4, 4))).is_err());
+    let not_jpeg2000 = dir.join("rasterkit_not_jpeg2000.j2k");
+    std::fs::write(&not_jpeg2000, b"not a codestream").unwrap();
+    assert!(strategy.extract_array_data(not_jpeg2000.to_str().unwrap(), None).is_err());
+}
@@ -0,0 +1,78 @@
+//! Tests for reading JPEG-compressed TIFF tiles
+
+use image::codecs::jpeg::JpegEncoder;
+use image::{ExtendedColorType, GenericImageView, RgbImage};
+use crate::compression::{CompressionFactory, CompressionHandler, JpegHandler};
+use crate::extractor::{ImageExtractor, Region};
+use crate::utils::logger::Logger;
+use super::test_utils::TestTiff;
+
+/// A 16x16 tile, red on the left half and blue on the right, as JPEG
+fn encode_tile() -> Vec<u8> {
+    let tile = RgbImage::from_fn(16, 16, |x, _| if x < 8 { image::Rgb([200, 30, 30]) } else { image::Rgb([30, 30, 200]) });
+    let mut jpeg = Vec::new();
+    JpegEncoder::new_with_quality(&mut jpeg, 95).encode(tile.as_raw(), 16, 16, ExtendedColorType::Rgb8).unwrap();
+    jpeg
+}
+
+/// Split a JPEG stream the way TIFF writers do: the quantization and
+/// Huffman tables into a tables-only stream, the rest into an abbreviated one
+fn split_tables(jpeg: &[u8]) -> (Vec<u8>, Vec<u8>) {
+    let (mut tables, mut block) = (vec![0xFF, 0xD8], vec![0xFF, 0xD8]);
+    let mut at = 2;
+    while jpeg[at + 1] != 0xDA {
+        let length = u16::from_be_bytes([jpeg[at + 2], jpeg[at + 3]]) as usize;
+        let segment = &jpeg[at..at + 2 + length];
+        if matches!(jpeg[at + 1], 0xDB | 0xC4) { tables.extend_from_slice(segment) } else { block.extend_from_slice(segment) }
+        at += 2 + length;
+    }
+    block.extend_from_slice(&jpeg[at..]);
+    tables.extend_from_slice(&[0xFF, 0xD9]);
+    (tables, block)
+}
+
+/// A 16x16 YCbCr image in a single JPEG tile with its tables in JPEGTables
+fn create_jpeg_tiff() -> Vec<u8> {
+    let (tables, block) = split_tables(&encode_tile());
+    TestTiff::new(16, 16)
+        .bits(&[8, 8, 8])
+        .compression(7)
+        .photometric(6)
+        .planar(1)
+        .tag(347, 7, tables.len() as u32, tables)
+        .tiles(16, 16, vec![block])
+        .build()
+}
+
+/// Whether two colors differ by no more than JPEG's losses
+fn close(actual: &[u8], expected: [u8; 3]) -> bool {
+    actual.iter().zip(expected).all(|(a, e)| (*a as i32 - e as i32).abs() <= 12)
+}
+
+#[test]
+fn test_jpeg_block_with_separate_tables() {
+    let (tables, block) = split_tables(&encode_tile());
+    // The abbreviated block cannot be decoded on its own
+    assert!(CompressionFactory::create_handler(7).unwrap().decompress(&block).is_err());
+
+    let handler = JpegHandler::new().with_tables(tables).with_photometric(6);
+    let pixels = handler.decompress(&block).unwrap();
+    assert_eq!(pixels.len(), 16 * 16 * 3);
+    assert!(close(&pixels[..3], [200, 30, 30]));
+    assert!(close(&pixels[(16 * 15 + 15) * 3..], [30, 30, 200]));
+    assert!(handler.compress(&pixels).is_err());
+}
+
+#[test]
+fn test_extract_rgb_from_jpeg_tiles() {
+    let dir = std::env::temp_dir();
+    let logger = Logger::new(dir.join("rasterkit_jpeg_tests.log").to_str().unwrap()).unwrap();
+    let path = dir.join("rasterkit_jpeg_tiles.tif");
+    std::fs::write(&path, create_jpeg_tiff()).unwrap();
+
+    let mut extractor = ImageExtractor::new(&logger);
+    let image = extractor.extract_image(path.to_str().unwrap(), Some(Region::new(2, 2, 12, 12))).unwrap();
+    assert_eq!(image.dimensions(), (12, 12));
+    assert!(close(&image.get_pixel(0, 0).0[..3], [200, 30, 30]));
+    assert!(close(&image.get_pixel(11, 11).0[..3], [30, 30, 200]));
+}
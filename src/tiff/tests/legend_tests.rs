@@ -0,0 +1,71 @@
+//! Tests for colormap legend images
+
+use image::Rgb;
+
+use crate::tiff::colormap::{ColorMap, ColorMapEntry, RgbColor};
+use crate::utils::legend_utils::{self, LegendStyle};
+
+/// A colormap of the given type and entries
+fn create_colormap(map_type: &str, entries: Vec<ColorMapEntry>) -> ColorMap {
+    let mut colormap = ColorMap::new();
+    colormap.set_type(map_type);
+    for entry in entries {
+        colormap.add_entry(entry);
+    }
+    colormap
+}
+
+#[test]
+fn test_discrete_legend_draws_labeled_swatches() {
+    let mut translucent = ColorMapEntry::with_label(3, RgbColor::new(0, 0, 0), "Urban".to_string());
+    translucent.alpha = 0;
+    let colormap = create_colormap("values", vec![
+        ColorMapEntry::with_label(1, RgbColor::new(0, 0, 255), "Water".to_string()),
+        ColorMapEntry::new(2, RgbColor::new(0, 200, 0)),
+        translucent,
+    ]);
+    assert_eq!(LegendStyle::for_colormap(&colormap), LegendStyle::Discrete);
+
+    let legend = legend_utils::render_legend(&colormap, LegendStyle::Discrete).unwrap();
+    // Margins, swatch, gap and the widest label, "Water"; three 20 px rows 6 px apart
+    assert_eq!(legend.dimensions(), (10 + 28 + 8 + 58 + 10, 10 + 3 * 20 + 2 * 6 + 10));
+
+    // Swatches are filled inside a black outline, transparent ones show the background
+    assert_eq!(*legend.get_pixel(10, 10), Rgb([0, 0, 0]));
+    assert_eq!(*legend.get_pixel(20, 20), Rgb([0, 0, 255]));
+    assert_eq!(*legend.get_pixel(20, 46), Rgb([0, 200, 0]));
+    assert_eq!(*legend.get_pixel(20, 72), Rgb([255, 255, 255]));
+
+    // Labels are drawn to the right of each swatch, entries without one show their value
+    let inked = |top: u32| (46..legend.width()).any(|x| (top..top + 20).any(|y| *legend.get_pixel(x, y) == Rgb([0, 0, 0])));
+    assert!(inked(10) && inked(36) && inked(62));
+}
+
+#[test]
+fn test_continuous_legend_runs_from_high_to_low() {
+    let colormap = create_colormap("ramp", vec![
+        ColorMapEntry::new(0, RgbColor::new(0, 0, 255)),
+        ColorMapEntry::new(100, RgbColor::new(255, 0, 0)),
+    ]);
+    assert_eq!(LegendStyle::for_colormap(&colormap), LegendStyle::Continuous);
+
+    let legend = legend_utils::render_legend(&colormap, LegendStyle::Continuous).unwrap();
+    assert_eq!(legend.height(), 10 + 7 + 256 + 7 + 10);
+
+    // The highest value is at the top of the bar, interpolated colors in between
+    let Rgb([r, _, b]) = *legend.get_pixel(20, 17 + 1);
+    assert!(r >= 250 && b <= 5);
+    let Rgb([r, _, b]) = *legend.get_pixel(20, 17 + 254);
+    assert!(r <= 5 && b >= 250);
+    let Rgb([r, _, b]) = *legend.get_pixel(20, 17 + 128);
+    assert!((120..=135).contains(&r) && (120..=135).contains(&b));
+
+    // Saved through the image encoder picked by the extension
+    let path = std::env::temp_dir().join("rasterkit_legend.png");
+    legend_utils::save_legend(&colormap, path.to_str().unwrap(), None).unwrap();
+    assert_eq!(image::open(&path).unwrap().to_rgb8(), legend);
+
+    assert!(legend_utils::render_legend(&ColorMap::new(), LegendStyle::Discrete).is_err());
+    assert_eq!(LegendStyle::from_string("Ramp").unwrap(), LegendStyle::Continuous);
+    assert!(LegendStyle::from_string("pie").is_err());
+}
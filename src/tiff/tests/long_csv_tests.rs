@@ -0,0 +1,48 @@
+//! Tests for long-format (tidy) CSV array output
+
+use crate::coordinate::{projection, Affine};
+use crate::extractor::{ArrayData, ArrayGeoreference, ArraySamples};
+use crate::tiff::proj_definition::ProjDefinition;
+
+/// A 2x2 array of the given samples on a 10 m grid centred on 15°E in UTM zone 33N
+fn create_array(epsg: Option<u32>) -> ArrayData {
+    ArrayData {
+        width: 2,
+        height: 2,
+        data: ArraySamples::I16(vec![-5, 0, 12, 300]),
+        bands: Vec::new(),
+        georeference: Some(ArrayGeoreference {
+            geotransform: Affine::new(499995.0, 10.0, 4000005.0, -10.0),
+            epsg,
+        }),
+        nodata: Some(0.0),
+    }
+}
+
+#[test]
+fn test_long_csv_rows_carry_lon_lat() {
+    let path = std::env::temp_dir().join("rasterkit_long_csv.csv");
+    create_array(Some(32633)).save_to_file(path.to_str().unwrap(), "csv-long").unwrap();
+    let csv = std::fs::read_to_string(&path).unwrap();
+    let rows: Vec<Vec<&str>> = csv.lines().map(|line| line.split(',').collect()).collect();
+    assert_eq!(rows[0], ["x", "y", "lon", "lat", "band", "value"]);
+    assert_eq!(rows.len(), 4);
+
+    // The NoData pixel is skipped and the others keep their values
+    let cells: Vec<(&str, &str, &str)> = rows[1..].iter().map(|row| (row[0], row[1], row[5])).collect();
+    assert_eq!(cells, [("0", "0", "-5"), ("0", "1", "12"), ("1", "1", "300")]);
+
+    // Cell centres unproject onto the central meridian and back onto the grid
+    let utm = ProjDefinition::from_epsg(32633).unwrap();
+    let (lon, lat): (f64, f64) = (rows[1][2].parse().unwrap(), rows[1][3].parse().unwrap());
+    assert!((lon - 15.0).abs() < 1e-9);
+    let northing = projection::forward(&utm, lon, lat).unwrap().y;
+    assert!((northing - 4000000.0).abs() < 1e-3);
+    let east: f64 = rows[3][2].parse().unwrap();
+    assert!(east > 15.0 && east < 15.001);
+
+    // Without a known CRS the coordinates are left empty
+    create_array(None).save_to_file(path.to_str().unwrap(), "csv-long").unwrap();
+    let csv = std::fs::read_to_string(&path).unwrap();
+    assert_eq!(csv.lines().nth(1), Some("0,0,,,1,-5"));
+}
@@ -0,0 +1,23 @@
+//! Tests for the LZW compression handler
+
+use crate::compression::{CompressionFactory, CompressionHandler, LzwHandler};
+
+#[test]
+fn test_lzw_round_trip() {
+    let data: Vec<u8> = (0..5000u32).map(|i| ((i / 7) % 256) as u8).collect();
+    let handler = CompressionFactory::create_handler(5).unwrap();
+    assert_eq!(handler.name(), "LZW");
+    let compressed = handler.compress(&data).unwrap();
+    assert!(compressed.len() < data.len());
+    // New-style data starts with a clear code written most significant bit first
+    assert_eq!(compressed[0], 0x80);
+    assert_eq!(handler.decompress(&compressed).unwrap(), data);
+    assert_eq!(CompressionFactory::get_handler_by_name("lzw").unwrap().code(), 5);
+}
+
+#[test]
+fn test_lzw_reads_old_style_data() {
+    let data = b"TOBEORNOTTOBEORTOBEORNOT".repeat(20);
+    let compressed = weezl::encode::Encoder::new(weezl::BitOrder::Lsb, 8).encode(&data).unwrap();
+    assert_eq!(LzwHandler.decompress(&compressed).unwrap(), data);
+}
@@ -0,0 +1,34 @@
+//! Tests for the memory budget
+
+use crate::extractor::block_cache::BlockCache;
+use crate::utils::memory_utils::{parse_megabytes, MEGABYTE};
+
+#[test]
+fn test_parse_megabytes() {
+    assert_eq!(parse_megabytes("512", "memory budget").unwrap(), 512 * MEGABYTE);
+    assert_eq!(parse_megabytes(" 0.5 ", "memory budget").unwrap(), MEGABYTE / 2);
+    for invalid in ["0", "-1", "lots", "inf"] {
+        let error = parse_megabytes(invalid, "memory budget").unwrap_err().to_string();
+        assert!(error.contains("Invalid memory budget"), "{}", error);
+    }
+}
+
+#[test]
+fn test_block_cache_stays_within_limit() {
+    let mut cache = BlockCache::with_limit(Some(250));
+    cache.use_file("a.tif");
+    for index in 0..3 {
+        assert_eq!(cache.get_or_decode(index, || Ok(vec![index as u8; 100])).unwrap()[0], index as u8);
+    }
+    // The first block made room for the third
+    assert_eq!(cache.cached_bytes(), 200);
+    cache.get_or_decode(2, || panic!("block 2 is cached")).unwrap();
+    assert_eq!(cache.get_or_decode(0, || Ok(vec![7; 100])).unwrap()[0], 7);
+    assert_eq!(cache.stats(), (4, 1));
+
+    // A block over the limit is kept alone
+    cache.get_or_decode(9, || Ok(vec![0; 300])).unwrap();
+    assert_eq!(cache.cached_bytes(), 300);
+    cache.use_file("b.tif");
+    assert_eq!(cache.cached_bytes(), 0);
+}
@@ -0,0 +1,57 @@
+//! Tests for operation metrics
+
+use std::time::Duration;
+
+use crate::utils::metrics::{self, CodecMetrics, Metrics, OperationTiming};
+
+/// Metrics of a run that decoded two Deflate tiles and warped once
+fn sample_metrics() -> Metrics {
+    let mut recorded = Metrics {
+        blocks_read: 2,
+        bytes_read: 1000,
+        read_time: Duration::from_millis(5),
+        cache_hits: 3,
+        ..Default::default()
+    };
+    recorded.codecs.insert("Deflate", CodecMetrics {
+        blocks: 2,
+        compressed_bytes: 1000,
+        decoded_bytes: 4000,
+        decode_time: Duration::from_millis(20),
+    });
+    recorded.operations.insert("warp", OperationTiming { count: 1, total: Duration::from_millis(250) });
+    recorded
+}
+
+#[test]
+fn test_metrics_formats() {
+    let recorded = sample_metrics();
+    assert_eq!(recorded.compression_ratio(), Some(4.0));
+    assert!(!recorded.is_empty());
+    assert!(Metrics::default().is_empty());
+    assert_eq!(Metrics::default().compression_ratio(), None);
+
+    let json = recorded.to_json();
+    assert!(json.contains("\"cache_hits\": 3"));
+    assert!(json.contains("\"Deflate\": {\"blocks\": 2, \"compressed_bytes\": 1000, \"decoded_bytes\": 4000, \"compression_ratio\": 4,"));
+    assert!(json.contains("\"warp\": {\"count\": 1, \"seconds\": 0.25}"));
+
+    let text = recorded.to_prometheus();
+    assert!(text.contains("# TYPE rasterkit_blocks_read_total counter\nrasterkit_blocks_read_total 2\n"));
+    assert!(text.contains("rasterkit_decoded_bytes_total{codec=\"Deflate\"} 4000\n"));
+    assert!(text.contains("rasterkit_operation_seconds_total{operation=\"warp\"} 0.25\n"));
+
+    assert!(recorded.summary().contains("Decoded 2 Deflate block(s): 1000 -> 4000 bytes (ratio 4.00)"));
+}
+
+#[test]
+fn test_timed_operations() {
+    // Other tests record into the same registry, so only growth is checked
+    let before = metrics::snapshot().operations.get("metrics test").map_or(0, |timing| timing.count);
+    assert_eq!(metrics::time("metrics test", || 42), 42);
+    metrics::record_decode("metrics test codec", 10, 30, Duration::from_millis(1));
+
+    let after = metrics::snapshot();
+    assert_eq!(after.operations["metrics test"].count, before + 1);
+    assert!(after.codecs["metrics test codec"].decoded_bytes >= 30);
+}
@@ -0,0 +1,32 @@
+//! Tests for mosaic blending
+
+use crate::utils::mosaic_utils::{self, BlendMode};
+
+#[test]
+fn test_feather_weights() {
+    // A 9x1 strip with data everywhere but its last pixel
+    let mut valid = vec![true; 9];
+    valid[8] = false;
+
+    let weights = mosaic_utils::feather_weights(&valid, 9, 1, 4);
+    assert_eq!(weights[8], 0.0);
+    // The strip is one pixel high, so every pixel touches the border
+    assert!(weights.iter().take(8).all(|&weight| (weight - 0.25).abs() < 1e-9));
+
+    // In a wide block the weight ramps up away from the edges
+    let weights = mosaic_utils::feather_weights(&[true; 100], 10, 10, 3);
+    let row: Vec<f64> = (0..10).map(|column| weights[50 + column]).collect();
+    assert!((row[0] - 1.0 / 3.0).abs() < 1e-9);
+    assert!((row[1] - 2.0 / 3.0).abs() < 1e-9);
+    assert_eq!(row[4], 1.0);
+    assert!((row[9] - 1.0 / 3.0).abs() < 1e-9);
+}
+
+#[test]
+fn test_blend_mode_parsing() {
+    assert_eq!(BlendMode::from_string("feather:20").unwrap(), BlendMode::Feather(20));
+    assert_eq!(BlendMode::from_string("Mean").unwrap(), BlendMode::Mean);
+    assert!(BlendMode::from_string("feather").is_err());
+    assert!(BlendMode::from_string("feather:0").is_err());
+    assert!(BlendMode::from_string("median").is_err());
+}
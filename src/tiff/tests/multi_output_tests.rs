@@ -0,0 +1,39 @@
+//! Tests for writing an image and an array output from one decode
+
+use crate::extractor::{ImageExtractor, Region};
+use crate::utils::logger::Logger;
+use crate::utils::metrics;
+use super::test_utils::TestTiff;
+
+/// Single-strip 8x8 image, value = 10 * row + column, with 30 m pixels
+/// whose top-left corner is at (500000, 4000000)
+fn create_georeferenced_file() -> Vec<u8> {
+    let pixels = (0..8u8).flat_map(|row| (0..8u8).map(move |column| 10 * row + column)).collect();
+    TestTiff::new(8, 8).georeference(30.0, 30.0, 500000.0, 4000000.0).pixels(pixels).build()
+}
+
+#[test]
+fn test_array_reuses_the_blocks_of_the_image_output() {
+    let dir = std::env::temp_dir();
+    let logger = Logger::new(dir.join("rasterkit_multi_output_tests.log").to_str().unwrap()).unwrap();
+    let input = dir.join("rasterkit_multi_output.tif");
+    std::fs::write(&input, create_georeferenced_file()).unwrap();
+    let input = input.to_str().unwrap();
+    let region = Some(Region::new(2, 3, 4, 4));
+
+    let mut extractor = ImageExtractor::new(&logger);
+    extractor.set_block_caching(true);
+    let image_output = dir.join("rasterkit_multi_output_image.tif");
+    extractor.extract_to_file(input, image_output.to_str().unwrap(), region, None).unwrap();
+
+    let hits = metrics::snapshot().cache_hits;
+    let array = extractor.extract_array_data(input, region).unwrap();
+    assert!(metrics::snapshot().cache_hits > hits);
+    assert_eq!((array.width, array.height), (4, 4));
+    assert_eq!(array.get(0, 0), Some(32.0));
+    assert_eq!(array.get(3, 3), Some(65.0));
+
+    // The array is georeferenced to its window like array extractions are
+    let georeference = array.georeference.expect("the window is georeferenced");
+    assert_eq!(georeference.geotransform.apply(0.0, 0.0), (500060.0, 3999910.0));
+}
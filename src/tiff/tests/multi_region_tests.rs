@@ -0,0 +1,55 @@
+//! Tests for extracting several regions in one run
+
+use crate::extractor::{ImageExtractor, Region};
+use crate::utils::logger::Logger;
+use crate::utils::metrics;
+use crate::utils::region_utils::{self, RegionSpec};
+use super::test_utils::TestTiff;
+
+#[test]
+fn test_regions_file() {
+    let path = std::env::temp_dir().join("rasterkit_multi_region_regions.txt");
+    std::fs::write(&path, "# bboxes, coordinates and cells\n500000,4000000 500100,4000100\n\n\
+                           8.5 47.25\nu0m6\n").unwrap();
+    assert_eq!(region_utils::read_regions_file(path.to_str().unwrap()).unwrap(), [
+        RegionSpec::Bbox("500000,4000000,500100,4000100".to_string()),
+        RegionSpec::Coordinate("8.5,47.25".to_string()),
+        RegionSpec::Cell("u0m6".to_string()),
+    ]);
+
+    // The first bad line is reported by number
+    std::fs::write(&path, "1,2\n1,2,3\n").unwrap();
+    let error = region_utils::read_regions_file(path.to_str().unwrap()).unwrap_err();
+    assert!(format!("{:?}", error).contains(":2:"));
+}
+
+#[test]
+fn test_numbered_outputs() {
+    assert_eq!(region_utils::numbered_output_path("out.tif", 0, 3), "out_1.tif");
+    assert_eq!(region_utils::numbered_output_path("dir/out.tif", 9, 12), "dir/out_10.tif");
+    assert_eq!(region_utils::numbered_output_path("out", 1, 10), "out_02");
+}
+
+#[test]
+fn test_block_cache_reuses_decoded_tiles() {
+    let dir = std::env::temp_dir();
+    let logger = Logger::new(dir.join("rasterkit_multi_region_tests.log").to_str().unwrap()).unwrap();
+    let path = dir.join("rasterkit_multi_region_tiled.tif");
+    std::fs::write(&path, TestTiff::new(8, 4).tiles(4, 4, vec![(0..16).collect(), (16..32).collect()]).build()).unwrap();
+    let path = path.to_str().unwrap();
+    let regions = [Region::new(0, 0, 2, 2), Region::new(1, 1, 2, 2), Region::new(3, 2, 2, 2)];
+
+    let mut plain = ImageExtractor::new(&logger);
+    let expected: Vec<Vec<u8>> = regions.iter()
+        .map(|region| plain.extract_image(path, Some(*region)).unwrap().to_luma8().into_raw()).collect();
+    assert_eq!(expected[2], [11, 24, 15, 28]);
+
+    // The same pixels, with the first tile decoded once and reused
+    let mut cached = ImageExtractor::new(&logger);
+    cached.set_block_caching(true);
+    let hits_before = metrics::snapshot().cache_hits;
+    for (region, pixels) in regions.iter().zip(&expected) {
+        assert_eq!(&cached.extract_image(path, Some(*region)).unwrap().to_luma8().into_raw(), pixels);
+    }
+    assert!(metrics::snapshot().cache_hits - hits_before >= 2);
+}
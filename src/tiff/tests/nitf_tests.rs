@@ -0,0 +1,108 @@
+//! Tests for reading NITF image segments
+
+use image::GenericImageView;
+
+use crate::extractor::{ExtractorStrategy, NitfExtractorStrategy, Region};
+use crate::utils::logger::Logger;
+
+/// Pad a NITF field to its width, numbers with leading zeros
+fn field(value: &str, width: usize, numeric: bool) -> String {
+    if numeric { format!("{:0>width$}", value) } else { format!("{:<width$}", value) }
+}
+
+/// 20x12 8-bit NITF 2.1 file in 8x8 blocks, pixel value = x + 10 * y,
+/// with corners in UTM zone 33N on a 10 m grid
+fn create_nitf_file() -> Vec<u8> {
+    let (columns, rows, block) = (20u32, 12u32, 8u32);
+    let (blocks_per_row, blocks_per_column) = (columns.div_ceil(block), rows.div_ceil(block));
+
+    let mut data = Vec::new();
+    for block_row in 0..blocks_per_column {
+        for block_column in 0..blocks_per_row {
+            for y in block_row * block..(block_row + 1) * block {
+                for x in block_column * block..(block_column + 1) * block {
+                    data.push(if x < columns && y < rows { (x + 10 * y) as u8 } else { 0 });
+                }
+            }
+        }
+    }
+
+    // Centres of the corner pixels
+    let corner = |x: u32, y: u32| format!("33{:06}{:07}", x, y);
+    let corners = [corner(500005, 3999995), corner(500195, 3999995),
+                   corner(500195, 3999885), corner(500005, 3999885)].concat();
+
+    let mut subheader = String::from("IM");
+    subheader += &field("chip", 10, false);
+    subheader += &field("20240101000000", 14, false);
+    subheader += &" ".repeat(17 + 80);
+    subheader += "U";
+    subheader += &" ".repeat(166);
+    subheader += "0";
+    subheader += &" ".repeat(42);
+    subheader += &field(&rows.to_string(), 8, true);
+    subheader += &field(&columns.to_string(), 8, true);
+    subheader += "INTMONO    VIS     08RN";
+    subheader += &corners;
+    // No comments, uncompressed, one band without lookup tables
+    subheader += &["0", "NC", "1", "M ", "      ", "N", "   ", "0"].concat();
+    subheader += "0B";
+    subheader += &[blocks_per_row, blocks_per_column, block, block].map(|v| field(&v.to_string(), 4, true)).concat();
+    subheader += &["08", "001", "000", "0000000000", "1.0 ", "00000", "00000"].concat();
+
+    let mut header = String::from("NITF02.1003BF01");
+    header += &" ".repeat(10);
+    header += "20240101000000";
+    header += &" ".repeat(80);
+    header += "U";
+    header += &" ".repeat(166);
+    header += "00000000000";
+    header += "   ";
+    header += &" ".repeat(24 + 18);
+    let header_length = header.len() + 12 + 6 + 3 + 16 + 5 * 3 + 2 * 5;
+    let file_length = header_length + subheader.len() + data.len();
+    header += &field(&file_length.to_string(), 12, true);
+    header += &field(&header_length.to_string(), 6, true);
+    header += "001";
+    header += &field(&subheader.len().to_string(), 6, true);
+    header += &field(&data.len().to_string(), 10, true);
+    header += "000000000000000";
+    header += "0000000000";
+
+    let mut bytes = header.into_bytes();
+    bytes.extend(subheader.into_bytes());
+    bytes.extend(data);
+    bytes
+}
+
+#[test]
+fn test_nitf_region_extraction() {
+    let dir = std::env::temp_dir();
+    let logger = Logger::new(dir.join("rasterkit_nitf_tests.log").to_str().unwrap()).unwrap();
+    let path = dir.join("rasterkit_nitf_test.ntf");
+    std::fs::write(&path, create_nitf_file()).unwrap();
+    let path = path.to_str().unwrap();
+
+    let mut strategy = NitfExtractorStrategy::new(&logger);
+    assert!(strategy.supports_format(path));
+
+    // A region spanning four blocks
+    let region = Region::new(5, 3, 10, 6);
+    let image = strategy.extract_image(path, Some(region)).unwrap();
+    assert_eq!(image.dimensions(), (10, 6));
+    for (x, y, pixel) in image.pixels() {
+        assert_eq!(pixel[0] as u32, (x + 5) + 10 * (y + 3));
+    }
+
+    // Corner coordinates locate pixel centres, the geotransform pixel edges
+    let array = strategy.extract_array_data(path, Some(region)).unwrap();
+    let georeference = array.georeference.unwrap();
+    assert_eq!(georeference.epsg, Some(32633));
+    let [origin_x, pixel_width, row_rotation, origin_y, column_rotation, pixel_height] =
+        georeference.geotransform.to_gdal();
+    assert!((origin_x - 500050.0).abs() < 1e-6 && (origin_y - 3999970.0).abs() < 1e-6);
+    assert!((pixel_width - 10.0).abs() < 1e-9 && (pixel_height + 10.0).abs() < 1e-9);
+    assert_eq!((row_rotation, column_rotation), (0.0, 0.0));
+
+    assert!(strategy.extract_image(path, Some(Region::new(15, 0, 10, 4))).is_err());
+}
@@ -0,0 +1,128 @@
+//! Tests for normalized float32 tensor export
+
+use crate::RasterKit;
+use crate::utils::chip_utils::{self, ChipOptions};
+use crate::utils::logger::Logger;
+use crate::utils::normalize_utils::{self, NormalizationMethod};
+use super::chips_tests::read_npy;
+use super::safetensors_tests::read_safetensors;
+use super::test_utils::TestTiff;
+
+/// Little-endian float32 values of a tensor
+fn floats(data: &[u8]) -> Vec<f32> {
+    data.chunks_exact(4).map(|bytes| f32::from_le_bytes(bytes.try_into().unwrap())).collect()
+}
+
+#[test]
+fn test_normalize_bands_per_band() {
+    // Two interleaved bands, the second one constant
+    let data = [0.0, 7.0, 50.0, 7.0, 100.0, 7.0, 200.0, 7.0];
+    let stats = normalize_utils::compute_band_statistics(&data, 2, None);
+    assert_eq!((stats[0].min, stats[0].max, stats[0].mean), (0.0, 200.0, 87.5));
+    assert_eq!((stats[1].min, stats[1].max, stats[1].std), (7.0, 7.0, 0.0));
+
+    let minmax = normalize_utils::normalize_bands(&data, &stats, NormalizationMethod::MinMax, None);
+    assert_eq!(minmax, [0.0, 0.0, 0.25, 0.0, 0.5, 0.0, 1.0, 0.0]);
+
+    let zscore = normalize_utils::normalize_bands(&data, &stats, NormalizationMethod::ZScore, None);
+    let first_band: Vec<f32> = zscore.iter().step_by(2).copied().collect();
+    let mean = first_band.iter().sum::<f32>() / 4.0;
+    let variance = first_band.iter().map(|value| value * value).sum::<f32>() / 4.0;
+    assert!(mean.abs() < 1e-6 && (variance - 1.0).abs() < 1e-5);
+    assert!(zscore.iter().skip(1).step_by(2).all(|&value| value == 0.0));
+
+    // NoData and NaN samples are left out and come out as NaN
+    let data = [-9999.0, 10.0, f64::NAN, 30.0];
+    let stats = normalize_utils::compute_band_statistics(&data, 1, Some(-9999.0));
+    assert_eq!((stats[0].min, stats[0].max, stats[0].mean, stats[0].std), (10.0, 30.0, 20.0, 10.0));
+    let minmax = normalize_utils::normalize_bands(&data, &stats, NormalizationMethod::MinMax, Some(-9999.0));
+    assert!(minmax[0].is_nan() && minmax[2].is_nan());
+    assert_eq!((minmax[1], minmax[3]), (0.0, 1.0));
+
+    assert_eq!(NormalizationMethod::from_string("Z-Score").unwrap(), NormalizationMethod::ZScore);
+    assert!(NormalizationMethod::from_string("log").is_err());
+}
+
+#[test]
+fn test_save_normalized_safetensors_carries_statistics() {
+    let path = std::env::temp_dir().join("rasterkit_normalized.safetensors");
+    normalize_utils::save_normalized(path.to_str().unwrap(), "safetensors", &[2, 2], &[0.0, 50.0, 100.0, 200.0], 1, None,
+                                     NormalizationMethod::MinMax).unwrap();
+
+    let (header, tensors) = read_safetensors(&path);
+    assert!(header.contains(r#""normalization":"minmax""#), "{}", header);
+    let (name, dtype, shape, data) = &tensors[0];
+    assert_eq!((name.as_str(), dtype.as_str(), shape.as_slice()), ("data", "F32", [2, 2].as_slice()));
+    assert_eq!(floats(data), [0.0, 0.25, 0.5, 1.0]);
+    let band_max = tensors.iter().find(|tensor| tensor.0 == "band_max").unwrap();
+    assert_eq!(floats(&band_max.3), [200.0]);
+}
+
+#[test]
+fn test_normalize_16_bit_values() {
+    let dir = std::env::temp_dir();
+    let kit = RasterKit::new(Some(dir.join("rasterkit_normalize_tests.log").to_str().unwrap())).unwrap();
+    let input = dir.join("rasterkit_normalize_u16.tif");
+    std::fs::write(&input, TestTiff::new(2, 2).bits(&[16])
+        .pixels([1000u16, 2000, 3000, 5000].iter().flat_map(|value| value.to_le_bytes()).collect()).build()).unwrap();
+
+    // Values above 255 keep their spread instead of all clamping to 1.0
+    let output = dir.join("rasterkit_normalize_u16.safetensors");
+    kit.extract_normalized_array(input.to_str().unwrap(), output.to_str().unwrap(), "safetensors", "minmax", None).unwrap();
+    let (_, tensors) = read_safetensors(&output);
+    assert_eq!(floats(&tensors[0].3), [0.0, 0.25, 0.5, 1.0]);
+    let band_max = tensors.iter().find(|tensor| tensor.0 == "band_max").unwrap();
+    assert_eq!(floats(&band_max.3), [5000.0]);
+}
+
+#[test]
+fn test_normalize_each_band_of_a_raster() {
+    let dir = std::env::temp_dir();
+    let kit = RasterKit::new(Some(dir.join("rasterkit_normalize_tests.log").to_str().unwrap())).unwrap();
+    let input = dir.join("rasterkit_normalize_rgb16.tif");
+    let samples: [u16; 12] = [0, 0, 0, 1000, 10, 500, 3000, 30, 700, 2000, 20, 600];
+    std::fs::write(&input, TestTiff::new(4, 1).bits(&[16, 16, 16]).photometric(2).nodata("0")
+        .pixels(samples.iter().flat_map(|value| value.to_le_bytes()).collect()).build()).unwrap();
+
+    let output = dir.join("rasterkit_normalize_rgb16.safetensors");
+    kit.extract_normalized_array(input.to_str().unwrap(), output.to_str().unwrap(), "safetensors", "minmax", None).unwrap();
+    let (_, tensors) = read_safetensors(&output);
+    let (_, _, shape, data) = &tensors[0];
+    assert_eq!(shape, &[1, 4, 3]);
+    let values = floats(data);
+    assert!(values[..3].iter().all(|value| value.is_nan()));
+    assert_eq!(values[3..], [0.0, 0.0, 0.0, 1.0, 1.0, 1.0, 0.5, 0.5, 0.5]);
+    let band_min = tensors.iter().find(|tensor| tensor.0 == "band_min").unwrap();
+    assert_eq!(floats(&band_min.3), [1000.0, 10.0, 500.0]);
+}
+
+#[test]
+fn test_normalized_chips_share_raster_statistics() {
+    let dir = std::env::temp_dir();
+    let logger = Logger::new(dir.join("rasterkit_normalize_tests.log").to_str().unwrap()).unwrap();
+    let input = dir.join("rasterkit_normalize_chips.tif");
+    std::fs::write(&input, TestTiff::new(4, 2).pixels(vec![0, 20, 40, 60, 80, 100, 120, 200]).build()).unwrap();
+
+    let output = dir.join("rasterkit_normalize_chips");
+    let _ = std::fs::remove_dir_all(&output);
+    let options = ChipOptions {
+        chip_size: 2,
+        format: "npy".to_string(),
+        normalize: Some(NormalizationMethod::MinMax),
+        ..Default::default()
+    };
+    let kit = RasterKit::new(Some(dir.join("rasterkit_normalize_tests.log").to_str().unwrap())).unwrap();
+    assert_eq!(kit.export_chips(input.to_str().unwrap(), output.to_str().unwrap(), &options).unwrap(), 2);
+
+    // Scaled by the minimum and maximum of the whole raster, not of the chip
+    let (header, data) = read_npy(&output.join("images").join("chip_00000_00002.npy"));
+    assert!(header.contains("'descr': '<f4'"), "{}", header);
+    assert_eq!(floats(&data), [0.2, 0.3, 0.6, 1.0]);
+    let json = std::fs::read_to_string(output.join("normalization.json")).unwrap();
+    assert!(json.contains("\"normalization\": \"minmax\"") && json.contains("\"min\": 0, \"max\": 200"), "{}", json);
+    std::fs::remove_dir_all(&output).unwrap();
+
+    // Image formats cannot hold float values
+    let options = ChipOptions { format: "png".to_string(), ..options };
+    assert!(chip_utils::export_chips(input.to_str().unwrap(), output.to_str().unwrap(), &options, &logger).is_err());
+}
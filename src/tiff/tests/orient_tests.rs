@@ -0,0 +1,49 @@
+//! Tests for rotating and flipping rasters
+
+use crate::extractor::{ArraySamples, ImageExtractor};
+use crate::utils::logger::Logger;
+use crate::utils::orient_utils::{self, Orientation};
+use super::test_utils::TestTiff;
+
+/// 2x2 single-band 16-bit north-down image holding 1 to 4, with 30 m
+/// pixels whose top edge is at y 3999940
+fn create_north_down_file() -> Vec<u8> {
+    TestTiff::new(2, 2)
+        .bits(&[16])
+        .georeference(30.0, -30.0, 500000.0, 3999940.0)
+        .pixels((1..=4u16).flat_map(u16::to_le_bytes).collect())
+        .build()
+}
+
+#[test]
+fn test_orient_keeps_pixels_on_the_ground() {
+    let dir = std::env::temp_dir();
+    let logger = Logger::new(dir.join("rasterkit_orient_tests.log").to_str().unwrap()).unwrap();
+    let input = dir.join("rasterkit_orient.tif");
+    std::fs::write(&input, create_north_down_file()).unwrap();
+    let mut extractor = ImageExtractor::new_array_extractor(&logger);
+
+    // Flipping vertically normalizes the image to north-up
+    let flipped = dir.join("rasterkit_orient_flipv.tif");
+    orient_utils::reorient(input.to_str().unwrap(), flipped.to_str().unwrap(), Orientation::FlipVertical, &logger).unwrap();
+    let array = extractor.extract_array_data(flipped.to_str().unwrap(), None).unwrap();
+    assert_eq!(array.data, ArraySamples::U16(vec![3, 4, 1, 2]));
+    let geotransform = array.georeference.expect("the output is georeferenced").geotransform;
+    assert_eq!(geotransform.to_gdal(), [500000.0, 30.0, 0.0, 4000000.0, 0.0, -30.0]);
+
+    // A quarter turn clockwise ends up with a rotated geotransform
+    let rotated = dir.join("rasterkit_orient_rotate90.tif");
+    orient_utils::reorient(input.to_str().unwrap(), rotated.to_str().unwrap(), Orientation::Rotate90, &logger).unwrap();
+    let array = extractor.extract_array_data(rotated.to_str().unwrap(), None).unwrap();
+    assert_eq!(array.data, ArraySamples::U16(vec![3, 1, 4, 2]));
+    let geotransform = array.georeference.expect("the output is georeferenced").geotransform;
+    // The centre of the pixel holding 1 stays at (500015, 3999955)
+    assert_eq!(geotransform.apply(1.5, 0.5), (500015.0, 3999955.0));
+}
+
+#[test]
+fn test_orientation_names() {
+    assert_eq!(Orientation::from_string("rotate270").unwrap(), Orientation::Rotate270);
+    assert_eq!(Orientation::from_string("FlipH").unwrap(), Orientation::FlipHorizontal);
+    assert!(Orientation::from_string("sideways").is_err());
+}
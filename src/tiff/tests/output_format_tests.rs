@@ -0,0 +1,68 @@
+//! Tests for resolving the output format of extractions
+
+use image::GenericImageView;
+
+use crate::extractor::OutputFormat;
+
+const WEBP: OutputFormat = OutputFormat::WebP { quality: None };
+const AVIF: OutputFormat = OutputFormat::Avif { quality: None };
+
+#[test]
+fn test_output_format_resolution() {
+    // Extensions decide unless a format is requested
+    assert_eq!(OutputFormat::resolve("out/chip.PNG", None), OutputFormat::Png);
+    assert_eq!(OutputFormat::resolve("chip.jpg", None), OutputFormat::Jpeg { quality: None });
+    assert_eq!(OutputFormat::resolve("chip.avif", None), AVIF);
+    assert_eq!(OutputFormat::resolve("dem.sql", None), OutputFormat::PgSql);
+    assert_eq!(OutputFormat::resolve("chip.png", Some(WEBP)), WEBP);
+
+    // Missing and unknown extensions fall back to GeoTIFF
+    assert_eq!(OutputFormat::resolve("chip", None), OutputFormat::GeoTiff);
+    assert_eq!(OutputFormat::resolve("chip.bin", None), OutputFormat::GeoTiff);
+
+    // Names round-trip, aliases included
+    for format in OutputFormat::ALL {
+        assert_eq!(OutputFormat::from_name(format.name()).unwrap(), format);
+    }
+    assert_eq!(OutputFormat::from_name("TIF").unwrap(), OutputFormat::GeoTiff);
+    assert!(OutputFormat::from_name("bmp").is_err());
+
+    assert!(OutputFormat::Parquet.is_array());
+    assert!(!OutputFormat::Jpeg { quality: None }.is_array());
+    assert!(AVIF.is_lossy() && !OutputFormat::Png.is_lossy());
+    assert!(OutputFormat::Npy.write_image(&image::DynamicImage::new_luma8(1, 1), "chip.npy").is_err());
+}
+
+#[test]
+fn test_webp_and_avif_outputs() {
+    let dir = std::env::temp_dir();
+    let gradient = image::RgbImage::from_fn(16, 16, |x, y| image::Rgb([x as u8 * 16, y as u8 * 16, 128]));
+    let image = image::DynamicImage::ImageRgb8(gradient);
+
+    // WebP is lossless until a quality is set
+    let lossless = dir.join("rasterkit_output_format_lossless.webp");
+    WEBP.write_image(&image, lossless.to_str().unwrap()).unwrap();
+    assert!(!WEBP.is_lossy());
+    let bytes = std::fs::read(&lossless).unwrap();
+    assert_eq!((&bytes[..4], &bytes[8..16]), (&b"RIFF"[..], &b"WEBPVP8L"[..]));
+    assert_eq!(image::open(&lossless).unwrap().to_rgb8(), image.to_rgb8());
+
+    let lossy_webp = WEBP.with_quality(Some(60));
+    assert!(lossy_webp.is_lossy());
+    let lossy = dir.join("rasterkit_output_format_lossy.webp");
+    lossy_webp.write_image(&image, lossy.to_str().unwrap()).unwrap();
+    assert_eq!(&std::fs::read(&lossy).unwrap()[8..16], b"WEBPVP8 ");
+    assert_eq!(image::open(&lossy).unwrap().dimensions(), (16, 16));
+
+    // AVIF is written as an ISO base media file of brand avif
+    let avif = dir.join("rasterkit_output_format_chip.avif");
+    AVIF.write_image(&image, avif.to_str().unwrap()).unwrap();
+    assert_eq!(&std::fs::read(&avif).unwrap()[4..12], b"ftypavif");
+
+    // Qualities are clamped to 1..=100 and only held by lossy formats
+    assert_eq!(AVIF.with_quality(Some(0)).quality(), Some(1));
+    assert_eq!(OutputFormat::Jpeg { quality: None }.with_quality(Some(250)).quality(), Some(100));
+    assert_eq!(lossy_webp.with_quality(None), WEBP);
+    assert_eq!(OutputFormat::Png.with_quality(Some(60)), OutputFormat::Png);
+    assert_eq!(lossy_webp.name(), "webp");
+}
@@ -0,0 +1,38 @@
+//! Tests for templated output names
+
+use crate::utils::region_utils;
+
+#[test]
+fn test_output_patterns_are_validated() {
+    region_utils::validate_output_pattern("{stem}_{minx}_{miny}.tif").unwrap();
+    region_utils::validate_output_pattern("{crs}/{date}/{stem}_{index}_{x}_{y}_{maxx}_{maxy}.tif").unwrap();
+    region_utils::validate_output_pattern("plain.tif").unwrap();
+
+    // Unknown and unterminated tokens are refused up front
+    assert!(region_utils::validate_output_pattern("{stem}_{zoom}.tif").is_err());
+    assert!(region_utils::validate_output_pattern("{stem.tif").is_err());
+}
+
+#[test]
+fn test_output_patterns_are_rendered() {
+    let values = [
+        ("stem", "dem".to_string()),
+        ("index", "07".to_string()),
+        ("minx", region_utils::format_coordinate(500000.0)),
+        ("miny", region_utils::format_coordinate(-8.250)),
+    ];
+    assert_eq!(region_utils::render_output_pattern("{stem}_{minx}_{miny}.tif", &values).unwrap(),
+               "dem_500000_-8.25.tif");
+    assert_eq!(region_utils::render_output_pattern("{stem}/{index}_{stem}.tif", &values).unwrap(), "dem/07_dem.tif");
+
+    // Tokens without a value for this region are an error
+    assert!(region_utils::render_output_pattern("{stem}_{x}.tif", &values).is_err());
+}
+
+#[test]
+fn test_format_coordinate() {
+    assert_eq!(region_utils::format_coordinate(8.25), "8.25");
+    assert_eq!(region_utils::format_coordinate(12.1234567), "12.123457");
+    assert_eq!(region_utils::format_coordinate(-0.0000001), "0");
+    assert_eq!(region_utils::format_coordinate(-3.5), "-3.5");
+}
@@ -0,0 +1,49 @@
+//! Tests for building internal overviews
+
+use crate::extractor::{ArraySamples, ImageExtractor};
+use crate::tiff::TiffReader;
+use crate::utils::logger::Logger;
+use crate::utils::overview_utils;
+use crate::utils::resize_utils::Resampling;
+use super::test_utils::TestTiff;
+
+/// 4x4 single-band 8-bit image in one strip, sample = 10 * row + column
+fn create_gray_file() -> Vec<u8> {
+    let pixels = (0..4u8).flat_map(|row| (0..4u8).map(move |column| 10 * row + column)).collect();
+    TestTiff::new(4, 4).pixels(pixels).build()
+}
+
+#[test]
+fn test_overviews_follow_the_main_image() {
+    let dir = std::env::temp_dir();
+    let logger = Logger::new(dir.join("rasterkit_overview_tests.log").to_str().unwrap()).unwrap();
+    let input = dir.join("rasterkit_overviews.tif");
+    std::fs::write(&input, create_gray_file()).unwrap();
+    let output = dir.join("rasterkit_overviews_out.tif");
+
+    let sizes = overview_utils::build_overviews(input.to_str().unwrap(), Some(output.to_str().unwrap()),
+                                                &[4, 2], Resampling::Average, &logger).unwrap();
+    assert_eq!(sizes, [(2, 2), (1, 1)]);
+
+    let mut reader = TiffReader::new(&logger);
+    let tiff = reader.load(output.to_str().unwrap()).unwrap();
+    assert_eq!(tiff.ifds.len(), 3);
+    assert_eq!(tiff.ifds[1].get_tag_value(254), Some(1));
+    assert_eq!(tiff.ifds[1].get_dimensions(), Some((2, 2)));
+
+    // The 2x2 overview holds the rounded mean of each 2x2 block
+    let strip_offset = tiff.ifds[1].get_tag_value(273).unwrap() as usize;
+    let bytes = std::fs::read(&output).unwrap();
+    assert_eq!(&bytes[strip_offset..strip_offset + 4], [6, 8, 26, 28]);
+
+    // The main image is unchanged
+    let mut extractor = ImageExtractor::new_array_extractor(&logger);
+    let array = extractor.extract_array_data(output.to_str().unwrap(), None).unwrap();
+    assert_eq!(array.data, ArraySamples::U8((0..4).flat_map(|row| (0..4).map(move |column| 10 * row + column)).collect()));
+}
+
+#[test]
+fn test_default_levels_reach_256_pixels() {
+    assert_eq!(overview_utils::default_levels(1000, 600), [2, 4]);
+    assert_eq!(overview_utils::default_levels(100, 100), [2]);
+}
@@ -0,0 +1,17 @@
+//! Tests for pan-sharpening
+
+use crate::utils::pansharpen_utils;
+
+#[test]
+fn test_brovey() {
+    let weights = [0.25, 0.25, 0.5];
+
+    // Pseudo-pan of 60 against a pan of 90 brightens every band by half
+    let fused = pansharpen_utils::brovey(90.0, &[40.0, 80.0, 60.0], &weights).unwrap();
+    assert_eq!(fused, vec![60.0, 120.0, 90.0]);
+
+    // Band ratios are kept
+    assert_eq!(fused[1] / fused[0], 2.0);
+
+    assert!(pansharpen_utils::brovey(90.0, &[0.0, 0.0, 0.0], &weights).is_none());
+}
@@ -0,0 +1,91 @@
+//! Tests reading the Parquet exports back with the parquet crate
+
+use std::fs::File;
+
+use parquet::file::reader::{FileReader, SerializedFileReader};
+use parquet::record::RowAccessor;
+
+use crate::coordinate::Affine;
+use crate::extractor::{ArrayData, ArrayGeoreference, ArraySamples};
+use crate::utils::parquet_utils::ParquetPartitioning;
+
+/// A georeferenced array of the given samples, NoData being 0
+fn create_array(width: u32, height: u32, data: ArraySamples) -> ArrayData {
+    ArrayData {
+        width,
+        height,
+        data,
+        bands: Vec::new(),
+        georeference: Some(ArrayGeoreference {
+            geotransform: Affine::new(500000.0, 10.0, 4000000.0, -10.0),
+            epsg: Some(32633),
+        }),
+        nodata: Some(0.0),
+    }
+}
+
+/// Open a Parquet file with the reference reader
+fn open(path: &std::path::Path) -> SerializedFileReader<File> {
+    SerializedFileReader::new(File::open(path).unwrap()).unwrap()
+}
+
+#[test]
+fn test_parquet_export_reads_back() {
+    let path = std::env::temp_dir().join("rasterkit_parquet_pixels.parquet");
+    let data = (0..50 * 40u32).map(|index| if index % 7 == 0 { 0 } else { index as u16 }).collect();
+    let array = create_array(50, 40, ArraySamples::U16(data));
+    let rows = array.save_as_parquet(path.to_str().unwrap(), ParquetPartitioning::None, 500).unwrap();
+    assert_eq!(rows, 2000 - 286);
+
+    let reader = open(&path);
+    let metadata = reader.metadata();
+    assert_eq!(metadata.file_metadata().num_rows() as usize, rows);
+    assert_eq!(metadata.num_row_groups(), 4);
+    assert_eq!(metadata.row_groups().iter().map(|group| group.num_rows()).collect::<Vec<_>>(), [500, 500, 500, 214]);
+    let names: Vec<&str> = metadata.file_metadata().schema_descr().columns().iter().map(|column| column.name()).collect();
+    assert_eq!(names, ["x", "y", "map_x", "map_y", "lon", "lat", "band", "value"]);
+
+    // Rows come back in pixel order with the NoData pixels skipped
+    let rows: Vec<_> = reader.get_row_iter(None).unwrap().map(Result::unwrap).collect();
+    assert_eq!((rows[0].get_int(0).unwrap(), rows[0].get_int(1).unwrap(), rows[0].get_int(7).unwrap()), (1, 0, 1));
+    let row = rows.iter().find(|row| row.get_int(0).unwrap() == 13 && row.get_int(1).unwrap() == 21).unwrap();
+    assert_eq!(row.get_int(7).unwrap(), 21 * 50 + 13);
+    assert_eq!((row.get_double(2).unwrap(), row.get_double(3).unwrap()), (500135.0, 3999785.0));
+    assert_eq!(row.get_int(6).unwrap(), 1);
+    assert!(rows.iter().all(|row| row.get_int(7).unwrap() % 7 != 0));
+}
+
+#[test]
+fn test_parquet_float_values_and_empty_regions() {
+    let dir = std::env::temp_dir();
+
+    // Float samples are stored as doubles
+    let path = dir.join("rasterkit_parquet_float.parquet");
+    let array = create_array(2, 1, ArraySamples::F32(vec![-12.25, 8848.5]));
+    array.save_as_parquet(path.to_str().unwrap(), ParquetPartitioning::None, 1000).unwrap();
+    let values: Vec<f64> = open(&path).get_row_iter(None).unwrap().map(|row| row.unwrap().get_double(7).unwrap()).collect();
+    assert_eq!(values, [-12.25, 8848.5]);
+
+    // A region of NoData only still gives a readable file without rows
+    let path = dir.join("rasterkit_parquet_empty.parquet");
+    let array = create_array(3, 2, ArraySamples::U8(vec![0; 6]));
+    assert_eq!(array.save_as_parquet(path.to_str().unwrap(), ParquetPartitioning::None, 1000).unwrap(), 0);
+    let reader = open(&path);
+    assert_eq!(reader.metadata().file_metadata().num_rows(), 0);
+    assert_eq!(reader.metadata().file_metadata().schema_descr().num_columns(), 8);
+    assert_eq!(reader.get_row_iter(None).unwrap().count(), 0);
+
+    // Partitioned by tile, tiles of NoData only get no file
+    let directory = dir.join("rasterkit_parquet_tiles");
+    let _ = std::fs::remove_dir_all(&directory);
+    let array = create_array(4, 2, ArraySamples::U8(vec![0, 0, 3, 4, 0, 0, 7, 8]));
+    let rows = array.save_as_parquet(directory.to_str().unwrap(), ParquetPartitioning::Tile(2, 2), 1000).unwrap();
+    assert_eq!(rows, 4);
+    assert!(!directory.join("tile_row=0").join("tile_col=0").exists());
+    let reader = open(&directory.join("tile_row=0").join("tile_col=1").join("part-0.parquet"));
+    let names: Vec<&str> = reader.metadata().file_metadata().schema_descr().columns().iter().map(|column| column.name()).collect();
+    assert_eq!(names, ["x", "y", "map_x", "map_y", "lon", "lat", "band", "value"]);
+    let values: Vec<i32> = reader.get_row_iter(None).unwrap().map(|row| row.unwrap().get_int(7).unwrap()).collect();
+    assert_eq!(values, [3, 4, 7, 8]);
+    std::fs::remove_dir_all(&directory).unwrap();
+}
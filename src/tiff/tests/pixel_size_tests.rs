@@ -0,0 +1,56 @@
+//! Tests for pixel sizes reported in the declared angular or linear units
+
+use crate::tiff::TiffReader;
+use crate::tiff::geo_key_parser::{GeoInfo, GeoKeyParser};
+use crate::utils::logger::Logger;
+use super::test_utils::TestTiff;
+
+/// Parse the GeoKeys of a 1x1 raster with the given pixel size and keys
+fn parse_geo_info(name: &str, pixel_size: f64, geo_keys: &[u16]) -> GeoInfo {
+    let dir = std::env::temp_dir();
+    let logger = Logger::new(dir.join("rasterkit_pixel_size_tests.log").to_str().unwrap()).unwrap();
+    let path = dir.join(name);
+    std::fs::write(&path, TestTiff::new(1, 1).georeference(pixel_size, pixel_size, 0.0, 0.0)
+        .shorts(34735, geo_keys).pixels(vec![0]).build()).unwrap();
+    let path = path.to_str().unwrap();
+
+    let mut reader = TiffReader::new(&logger);
+    let tiff = reader.load(path).unwrap();
+    GeoKeyParser::extract_geo_info(tiff.main_ifd().unwrap(), reader.get_byte_order_handler().unwrap(), path).unwrap()
+}
+
+#[test]
+fn test_projected_pixel_size_units() {
+    // NAD83 / Colorado North (ftUS) declares US survey feet
+    let geo_info = parse_geo_info("rasterkit_pixel_size_ftus.tif", 3937.0,
+                                  &[1, 1, 0, 3, 1024, 0, 1, 1, 3072, 0, 1, 2231, 3076, 0, 1, 9003]);
+    assert!(!geo_info.is_geographic());
+    assert_eq!(geo_info.pixel_size_units(), "US survey feet");
+    let (x, y) = geo_info.pixel_size_meters(0.0).unwrap();
+    assert!((x - 1200.0).abs() < 1e-9 && (y - 1200.0).abs() < 1e-9);
+
+    // Without ProjLinearUnitsGeoKey a projected CRS is in meters
+    let geo_info = parse_geo_info("rasterkit_pixel_size_meters.tif", 30.0, &[1, 1, 0, 2, 1024, 0, 1, 1, 3072, 0, 1, 32633]);
+    assert_eq!(geo_info.pixel_size_units(), "meters");
+    assert_eq!(geo_info.pixel_size_meters(0.0), Some((30.0, 30.0)));
+}
+
+#[test]
+fn test_geographic_pixel_size_units() {
+    let geo_info = parse_geo_info("rasterkit_pixel_size_degrees.tif", 0.001, &[1, 1, 0, 2, 1024, 0, 1, 2, 2048, 0, 1, 4326]);
+    assert!(geo_info.is_geographic());
+    assert_eq!(geo_info.pixel_size_units(), "degrees");
+
+    // A degree of longitude shrinks towards the poles, one of latitude grows slightly
+    let (x, y) = geo_info.pixel_size_meters(0.0).unwrap();
+    assert!((x - 111.32).abs() < 0.01 && (y - 110.57).abs() < 0.01);
+    let (x, y) = geo_info.pixel_size_meters(60.0).unwrap();
+    assert!((x - 55.80).abs() < 0.01 && (y - 111.41).abs() < 0.01);
+
+    // Arc-seconds declared through GeogAngularUnitsGeoKey
+    let geo_info = parse_geo_info("rasterkit_pixel_size_arcsec.tif", 1.0,
+                                  &[1, 1, 0, 3, 1024, 0, 1, 2, 2048, 0, 1, 4326, 2054, 0, 1, 9104]);
+    assert_eq!(geo_info.pixel_size_units(), "arc-seconds");
+    let (x, _) = geo_info.pixel_size_meters(0.0).unwrap();
+    assert!((x - 30.92).abs() < 0.01);
+}
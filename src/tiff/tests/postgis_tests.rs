@@ -0,0 +1,104 @@
+//! Tests for PostGIS raster SQL output
+
+use crate::{PostgisLoadMode, PostgisOptions, RasterKit};
+use crate::coordinate::Affine;
+use crate::extractor::ImageExtractor;
+use crate::utils::logger::Logger;
+use crate::utils::postgis_utils;
+use crate::utils::tensor_utils::TensorDType;
+use super::test_utils::TestTiff;
+
+/// Decode the upper-case hex of a tile back to bytes
+fn from_hex(hex: &str) -> Vec<u8> {
+    (0..hex.len()).step_by(2).map(|i| u8::from_str_radix(&hex[i..i + 2], 16).unwrap()).collect()
+}
+
+#[test]
+fn test_wkb_bytes_of_a_float_tile() {
+    let geotransform = Affine::new(500000.0, 10.0, 4000000.0, -10.0);
+    let data: Vec<u8> = [12.5f32, -9999.0].iter().flat_map(|value| value.to_le_bytes()).collect();
+    let wkb = postgis_utils::raster_wkb(2, 1, &geotransform, 32633, TensorDType::F32, Some(-9999.0), &data);
+
+    let mut expected = vec![1u8, 0, 0, 1, 0];
+    for value in [10.0f64, -10.0, 500000.0, 4000000.0, 0.0, 0.0] {
+        expected.extend_from_slice(&value.to_le_bytes());
+    }
+    expected.extend_from_slice(&32633i32.to_le_bytes());
+    expected.extend_from_slice(&[2, 0, 1, 0]);
+    expected.push(0x40 | 10); // has NoData, 32BF
+    expected.extend_from_slice(&(-9999.0f32).to_le_bytes());
+    expected.extend_from_slice(&data);
+    assert_eq!(wkb, expected);
+}
+
+#[test]
+fn test_wkb_pixel_types_and_nodata_flag() {
+    let geotransform = Affine::new(0.0, 1.0, 0.0, -1.0);
+    let band = |dtype: TensorDType, nodata: Option<f64>| {
+        let wkb = postgis_utils::raster_wkb(1, 1, &geotransform, 0, dtype, nodata, &vec![0; dtype.size()]);
+        (wkb[61], wkb[62..62 + dtype.size()].to_vec(), wkb.len())
+    };
+
+    assert_eq!(band(TensorDType::U8, Some(255.0)), (0x44, vec![255], 64));
+    assert_eq!(band(TensorDType::I16, Some(-32768.0)), (0x45, (-32768i16).to_le_bytes().to_vec(), 66));
+    assert_eq!(band(TensorDType::U16, Some(65535.0)), (0x46, vec![255, 255], 66));
+    assert_eq!(band(TensorDType::I32, Some(-1.0)), (0x47, vec![255; 4], 70));
+    assert_eq!(band(TensorDType::U32, None), (0x08, vec![0; 4], 70));
+    assert_eq!(band(TensorDType::F64, Some(-9999.5)), (0x4B, (-9999.5f64).to_le_bytes().to_vec(), 78));
+
+    // A NoData value the pixel type cannot hold clears the flag
+    assert_eq!(band(TensorDType::U16, Some(-9999.0)), (0x06, vec![0, 0], 66));
+    assert_eq!(band(TensorDType::U8, Some(0.5)), (0x04, vec![0], 64));
+}
+
+#[test]
+fn test_postgis_sql_keeps_sample_type() {
+    let dir = std::env::temp_dir();
+    let logger = Logger::new(dir.join("rasterkit_postgis_tests.log").to_str().unwrap()).unwrap();
+    let path = dir.join("rasterkit_postgis_int16.tif");
+    let values = [-200i16, 1500, -9999, 32000];
+    std::fs::write(&path, TestTiff::new(2, 2).bits(&[16]).sample_format(2)
+        .georeference(30.0, 30.0, 600000.0, 5000000.0).epsg(32633).nodata("-9999")
+        .pixels(values.iter().flat_map(|value| value.to_le_bytes()).collect()).build()).unwrap();
+
+    let mut extractor = ImageExtractor::new_array_extractor(&logger);
+    let array = extractor.extract_array_data(path.to_str().unwrap(), None).unwrap();
+    let sql = dir.join("rasterkit_postgis_int16.sql");
+    array.save_to_file(sql.to_str().unwrap(), "pgsql").unwrap();
+
+    let sql = std::fs::read_to_string(sql).unwrap();
+    let insert = sql.lines().find(|line| line.starts_with("INSERT")).unwrap();
+    let hex = insert.split('\'').nth(1).unwrap();
+    let wkb = from_hex(hex);
+    assert_eq!(&wkb[57..61], &[2, 0, 2, 0]);
+    assert_eq!(i32::from_le_bytes(wkb[53..57].try_into().unwrap()), 32633);
+    assert_eq!(wkb[61], 0x40 | 5); // has NoData, 16BSI
+    assert_eq!(&wkb[62..64], &(-9999i16).to_le_bytes());
+    let samples: Vec<i16> = wkb[64..].chunks_exact(2).map(|bytes| i16::from_le_bytes([bytes[0], bytes[1]])).collect();
+    assert_eq!(samples, values);
+}
+
+#[test]
+fn test_extract_to_postgis_with_options() {
+    let dir = std::env::temp_dir();
+    let kit = RasterKit::new(Some(dir.join("rasterkit_postgis_tests.log").to_str().unwrap())).unwrap();
+    let path = dir.join("rasterkit_postgis_tiled.tif");
+    std::fs::write(&path, TestTiff::new(4, 2).georeference(30.0, 30.0, 600000.0, 5000000.0).epsg(32633)
+        .pixels((0..8).collect()).build()).unwrap();
+
+    let options = PostgisOptions {
+        table: "public.scene".to_string(),
+        tile_size: Some((2, 2)),
+        mode: PostgisLoadMode::Copy,
+        spatial_index: true,
+        ..PostgisOptions::default()
+    };
+    let sql = dir.join("rasterkit_postgis_tiled.sql");
+    let tiles = kit.extract_to_postgis(path.to_str().unwrap(), sql.to_str().unwrap(), &options, None).unwrap();
+    assert_eq!(tiles, 2);
+
+    let sql = std::fs::read_to_string(sql).unwrap();
+    assert!(sql.contains("COPY \"public\".\"scene\" (\"rast\") FROM stdin;"), "{}", sql);
+    assert!(sql.contains("USING gist"), "{}", sql);
+    assert!(!sql.contains("INSERT"), "{}", sql);
+}
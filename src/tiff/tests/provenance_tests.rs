@@ -0,0 +1,68 @@
+//! Tests for provenance records written into extracted outputs
+
+use crate::extractor::{file_checksum, ImageExtractor, Provenance, Region};
+use crate::tiff::TiffReader;
+use crate::utils::logger::Logger;
+use crate::utils::tiff_extraction_utils;
+use super::test_utils::TestTiff;
+
+/// Provenance of a bbox extraction from a source file
+fn create_provenance(source: &str, checksum: String) -> Provenance {
+    Provenance {
+        source: source.to_string(),
+        checksum,
+        bbox: Some("500010,3999980,500030,4000000".to_string()),
+        crs: Some("EPSG:32633".to_string()),
+        parameters: "extract --bbox 500010,3999980,500030,4000000 --epsg 32633 & more".to_string(),
+    }
+}
+
+#[test]
+fn test_provenance_items_and_checksum() {
+    let path = std::env::temp_dir().join("rasterkit_provenance_checksum.bin");
+    std::fs::write(&path, b"123456789").unwrap();
+    // The CRC-32 check value
+    assert_eq!(file_checksum(path.to_str().unwrap()).unwrap(), "crc32:cbf43926");
+
+    let provenance = create_provenance("in.tif", "crc32:cbf43926".to_string());
+    let window = Region::new(1, 0, 2, 2);
+    let items = provenance.metadata_items(&window);
+    assert_eq!(items.len(), 7);
+    assert_eq!(items[0], format!("<Item name=\"RASTERKIT_VERSION\">{}</Item>", Provenance::version()));
+    assert_eq!(items[3], "<Item name=\"RASTERKIT_WINDOW\">1,0,2,2</Item>");
+    assert!(items[6].contains("--epsg 32633 &amp; more"));
+
+    // Without a bbox the record only names the pixel window
+    let pixels = Provenance { bbox: None, crs: None, ..provenance };
+    assert!(pixels.items(&window).iter().all(|(name, _)| *name != "RASTERKIT_BBOX" && *name != "RASTERKIT_CRS"));
+    assert!(pixels.description(&window).contains("window: x=1 y=0 width=2 height=2"));
+}
+
+#[test]
+fn test_provenance_is_written_into_outputs() {
+    let dir = std::env::temp_dir();
+    let logger = Logger::new(dir.join("rasterkit_provenance_tests.log").to_str().unwrap()).unwrap();
+    let input = dir.join("rasterkit_provenance_source.tif");
+    std::fs::write(&input, TestTiff::new(4, 2).georeference(10.0, 10.0, 500000.0, 4000000.0).epsg(32633)
+        .pixels((0..8).collect()).build()).unwrap();
+    let input = input.to_str().unwrap();
+    let checksum = file_checksum(input).unwrap();
+
+    let output = dir.join("rasterkit_provenance_clip.tif");
+    let mut extractor = ImageExtractor::new(&logger);
+    extractor.set_provenance(Some(create_provenance(input, checksum.clone())));
+    extractor.extract_to_file(input, output.to_str().unwrap(), Some(Region::new(1, 0, 2, 2)), None).unwrap();
+
+    let mut reader = TiffReader::new(&logger);
+    let tiff = reader.load(output.to_str().unwrap()).unwrap();
+    let ifd = tiff.main_ifd().unwrap();
+    let metadata = tiff_extraction_utils::extract_gdal_metadata(ifd, &reader).unwrap();
+    assert!(metadata.contains(&format!("<Item name=\"RASTERKIT_SOURCE_CHECKSUM\">{}</Item>", checksum)));
+    assert!(metadata.contains("<Item name=\"RASTERKIT_WINDOW\">1,0,2,2</Item>"));
+    assert!(metadata.contains("<Item name=\"RASTERKIT_CRS\">EPSG:32633</Item>"));
+
+    let description = reader.read_tag::<String>(ifd, 270).unwrap().remove(0);
+    assert!(description.starts_with(&format!("Extracted by rasterkit {} from {} ({})",
+                                             Provenance::version(), input, checksum)));
+    assert!(description.contains("\nbbox: 500010,3999980,500030,4000000\n"));
+}
@@ -0,0 +1,50 @@
+//! Tests for proximity rasters computed on the stored band values
+
+use crate::utils::logger::Logger;
+use crate::utils::proximity_utils::{self, ProximityOptions, PROXIMITY_NODATA};
+use crate::utils::sample_utils;
+use super::test_utils::TestTiff;
+
+#[test]
+fn test_target_values_and_distances() {
+    assert_eq!(proximity_utils::parse_target_values("1, 3,1000-1999,-5,-10--2,0.5").unwrap(),
+               [(1.0, 1.0), (3.0, 3.0), (1000.0, 1999.0), (-5.0, -5.0), (-10.0, -2.0), (0.5, 0.5)]);
+    assert!(proximity_utils::parse_target_values("5-1").is_err());
+    assert!(proximity_utils::parse_target_values("road").is_err());
+
+    // A code above 255 is a target only where it is listed; NaN never is
+    let values = [0.0, 1300.0, 0.0, f64::NAN, 44.0];
+    let options = ProximityOptions { target_values: vec![(1000.0, 1999.0)], ..ProximityOptions::default() };
+    assert_eq!(proximity_utils::compute_proximity(&values, 5, 1, (2.0, 2.0), &options), [2.0, 0.0, 2.0, 4.0, 6.0]);
+    let options = ProximityOptions { max_distance: Some(3.0), ..ProximityOptions::default() };
+    assert_eq!(proximity_utils::compute_proximity(&values, 5, 1, (1.0, 1.0), &options),
+               [1.0, 0.0, 1.0, 1.0, 0.0]);
+    let options = ProximityOptions { target_values: vec![(7.0, 7.0)], ..ProximityOptions::default() };
+    assert!(proximity_utils::compute_proximity(&values, 5, 1, (1.0, 1.0), &options).iter().all(|&d| d == PROXIMITY_NODATA));
+}
+
+#[test]
+fn test_proximity_file_reads_native_band() {
+    let dir = std::env::temp_dir();
+    let logger = Logger::new(dir.join("rasterkit_proximity_tests.log").to_str().unwrap()).unwrap();
+    let input = dir.join("rasterkit_proximity_bands.tif");
+    let output = dir.join("rasterkit_proximity_bands_distance.tif");
+
+    // Band 1 holds 16-bit class codes, band 2 a single target of its own;
+    // 9999 is NoData, which is never a target
+    let pixels: [(u16, u16); 4] = [(300, 0), (9999, 0), (0, 0), (0, 1)];
+    std::fs::write(&input, TestTiff::new(4, 1).bits(&[16, 16]).photometric(1).nodata("9999")
+        .georeference(10.0, 10.0, 500000.0, 4000000.0).epsg(32633)
+        .pixels(pixels.iter().flat_map(|&(a, b)| [a.to_le_bytes(), b.to_le_bytes()]).flatten().collect()).build()).unwrap();
+
+    let distances = |options: &ProximityOptions| {
+        proximity_utils::create_proximity_raster(input.to_str().unwrap(), output.to_str().unwrap(), options, &logger)
+            .map(|_| sample_utils::read_bands(output.to_str().unwrap(), 1, &logger).unwrap().0.remove(0))
+    };
+
+    let options = ProximityOptions { target_values: vec![(256.0, 511.0)], ..ProximityOptions::default() };
+    assert_eq!(distances(&options).unwrap(), [0.0, 10.0, 20.0, 30.0]);
+    let options = ProximityOptions { band: 2, use_map_units: false, ..ProximityOptions::default() };
+    assert_eq!(distances(&options).unwrap(), [3.0, 2.0, 1.0, 0.0]);
+    assert!(distances(&ProximityOptions { band: 3, ..ProximityOptions::default() }).is_err());
+}
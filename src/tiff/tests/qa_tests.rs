@@ -0,0 +1,28 @@
+//! Tests for QA bit flag definitions
+
+use crate::utils::qa_utils::{self, BitFlag};
+
+#[test]
+fn test_bit_definitions() {
+    let flags = qa_utils::parse_bit_definitions("\
+        # Landsat QA_PIXEL\n\
+        fill = 0\n\
+        cloud = 3  # dilated clouds are bit 1\n\
+        \n\
+        cloud_confidence = 8-9\n\
+        high_cloud_confidence = 8-9 : 3\n").unwrap();
+
+    assert_eq!(flags.len(), 4);
+    assert_eq!(flags[3], BitFlag { name: "high_cloud_confidence".to_string(), first_bit: 8, last_bit: 9, value: Some(3) });
+
+    // Cloud with high confidence and clear with low confidence
+    let (cloudy, clear) = (0b11_0000_1000u32, 0b01_0100_0000u32);
+    assert!(!flags[0].is_set(cloudy));
+    assert!(flags[1].is_set(cloudy) && !flags[1].is_set(clear));
+    assert_eq!((flags[2].field(cloudy), flags[2].field(clear)), (3, 1));
+    assert!(flags[3].is_set(cloudy) && !flags[3].is_set(clear));
+
+    assert!(qa_utils::parse_bit_definitions("cloud = 32").is_err());
+    assert!(qa_utils::parse_bit_definitions("cloud = 3\ncloud = 4").is_err());
+    assert!(qa_utils::parse_bit_definitions("# nothing").is_err());
+}
@@ -0,0 +1,47 @@
+//! Tests for reading IFD chains
+
+extern crate std;
+
+use std::io::Cursor;
+use byteorder::{LittleEndian, WriteBytesExt};
+use crate::tiff::reader::{TiffReader, TiffReaderBuilder};
+use crate::utils::logger::Logger;
+
+/// Little-endian TIFF with `pages` one-entry IFDs; the last one points to `last_next`
+fn create_chain_buffer(pages: u32, last_next: u32) -> Cursor<Vec<u8>> {
+    let mut buffer = Vec::new();
+    buffer.write_u16::<LittleEndian>(0x4949).unwrap();
+    buffer.write_u16::<LittleEndian>(42).unwrap();
+    buffer.write_u32::<LittleEndian>(8).unwrap();
+
+    // Each IFD: count (2) + one entry (12) + next offset (4)
+    for page in 0..pages {
+        let next = if page + 1 == pages { last_next } else { 8 + (page + 1) * 18 };
+        buffer.write_u16::<LittleEndian>(1).unwrap();
+        buffer.write_u16::<LittleEndian>(256).unwrap();
+        buffer.write_u16::<LittleEndian>(4).unwrap();
+        buffer.write_u32::<LittleEndian>(1).unwrap();
+        buffer.write_u32::<LittleEndian>(page + 1).unwrap();
+        buffer.write_u32::<LittleEndian>(next).unwrap();
+    }
+
+    Cursor::new(buffer)
+}
+
+#[test]
+fn test_ifd_chain_limits_and_cycles() {
+    let log_path = std::env::temp_dir().join("rasterkit_reader_tests.log");
+    let logger = Logger::new(log_path.to_str().unwrap()).unwrap();
+
+    // Deep stacks are read in full
+    let tiff = TiffReader::new(&logger).read(&mut create_chain_buffer(150, 0)).unwrap();
+    std::assert_eq!(tiff.ifd_count(), 150);
+
+    // A chain looping back to an earlier IFD ends at the repeat
+    let tiff = TiffReader::new(&logger).read(&mut create_chain_buffer(5, 8 + 2 * 18)).unwrap();
+    std::assert_eq!(tiff.ifd_count(), 5);
+
+    // An explicit limit still applies
+    let mut reader = TiffReaderBuilder::new(&logger).max_ifds(10).build();
+    std::assert_eq!(reader.read(&mut create_chain_buffer(150, 0)).unwrap().ifd_count(), 10);
+}
@@ -0,0 +1,33 @@
+//! Tests for rendering stretches
+
+use crate::utils::normalize_utils::BandStatistics;
+use crate::utils::render_utils::{Stretch, StretchMode};
+use crate::utils::statistics_utils::{BandReport, Histogram};
+
+#[test]
+fn test_stretch_modes() {
+    // 100 pixels over 0-100, half of them in the lowest tenth
+    let report = BandReport {
+        band: 1,
+        statistics: BandStatistics { min: 0.0, max: 100.0, mean: 30.0, std: 30.0 },
+        valid_percent: 100.0,
+        histogram: Histogram { min: 0.0, max: 100.0, counts: vec![50, 10, 10, 10, 10, 0, 0, 0, 0, 10] },
+        approximate: false,
+    };
+
+    let linear = Stretch::new(StretchMode::Linear, &report);
+    assert_eq!((linear.apply(0.0), linear.apply(50.0), linear.apply(100.0)), (1, 128, 255));
+
+    let clip = Stretch::new(StretchMode::PercentClip { low: 25.0, high: 90.0 }, &report);
+    assert!((clip.percentile(25.0) - 5.0).abs() < 1e-9);
+    assert!((clip.percentile(90.0) - 50.0).abs() < 1e-9);
+    assert_eq!((clip.apply(5.0), clip.apply(60.0)), (1, 255));
+
+    // The crowded lowest tenth spreads over half the levels
+    let equalize = Stretch::new(StretchMode::Equalize, &report);
+    assert_eq!(equalize.apply(10.0), 128);
+    assert_eq!(equalize.apply(100.0), 255);
+
+    assert!(StretchMode::from_string("percent-clip", (98.0, 2.0)).is_err());
+    assert!(StretchMode::from_string("gamma", (2.0, 98.0)).is_err());
+}
@@ -0,0 +1,48 @@
+//! Tests for salvaging truncated files
+
+extern crate std;
+
+use crate::tiff::reader::TiffReader;
+use crate::utils::logger::Logger;
+use crate::utils::repair_utils;
+use super::test_utils::TestTiff;
+
+/// 4x8 8-bit image in four strips of two rows, pixel value = index + 1
+fn create_striped_file() -> Vec<u8> {
+    let pixels: Vec<u8> = (1..=32).collect();
+    TestTiff::new(4, 8).strips(2, pixels.chunks(8).map(<[u8]>::to_vec).collect()).build()
+}
+
+#[test]
+fn test_repair_truncated_strips() {
+    let dir = std::env::temp_dir();
+    let logger = Logger::new(dir.join("rasterkit_repair_tests.log").to_str().unwrap()).unwrap();
+
+    // Cut the file three bytes into the third strip
+    let input = dir.join("rasterkit_repair_truncated.tif");
+    let file = create_striped_file();
+    std::fs::write(&input, &file[..file.len() - 13]).unwrap();
+
+    let mut reader = TiffReader::new(&logger);
+    let tiff = reader.load(input.to_str().unwrap()).unwrap();
+    let problems: std::vec::Vec<std::string::String> = repair_utils::check_file(&reader, &tiff).unwrap()
+        .into_iter().map(|issue| issue.problem).collect();
+    std::assert_eq!(problems, std::vec![
+        "strip 2 is truncated: 3 of 8 bytes present".to_string(),
+        "data of strip 3 lies beyond the end of the file".to_string(),
+    ]);
+
+    let output = dir.join("rasterkit_repair_fixed.tif");
+    let results = repair_utils::write_repaired_copy(&reader, &tiff, output.to_str().unwrap(), &logger).unwrap();
+    std::assert_eq!((results[0].recovered, results[0].partial, results[0].total), (2, 1, 4));
+
+    // The copy is one strip: two intact strips, three present bytes, zeros
+    let mut reader = TiffReader::new(&logger);
+    let tiff = reader.load(output.to_str().unwrap()).unwrap();
+    let ifd = tiff.main_ifd().unwrap();
+    let offset = ifd.get_tag_value(273).unwrap() as usize;
+    let data = std::fs::read(&output).unwrap();
+    let mut expected: std::vec::Vec<u8> = (1..=19).collect();
+    expected.resize(32, 0);
+    std::assert_eq!(&data[offset..offset + 32], &expected[..]);
+}
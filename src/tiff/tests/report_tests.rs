@@ -0,0 +1,58 @@
+//! Tests for extraction reports
+
+use std::time::Duration;
+
+use crate::extractor::Region;
+use crate::utils::logger::Logger;
+use crate::utils::report_utils::{report_json, summarize_output, ReportEntry};
+use super::test_utils::TestTiff;
+
+/// 2x2 single-band 8-bit image in one strip, with the values 1 to 4
+fn create_small_file() -> Vec<u8> {
+    TestTiff::new(2, 2).pixels(vec![1, 2, 3, 4]).build()
+}
+
+#[test]
+fn test_summarize_output() {
+    let dir = std::env::temp_dir();
+    let path = dir.join("rasterkit_report.tif");
+    std::fs::write(&path, create_small_file()).unwrap();
+    let logger = Logger::new(dir.join("rasterkit_report_tests.log").to_str().unwrap()).unwrap();
+
+    let summary = summarize_output(path.to_str().unwrap(), &logger).unwrap();
+    assert_eq!((summary.width, summary.height, summary.bands), (2, 2, 1));
+    assert!(summary.geotransform.is_none());
+    assert_eq!(summary.nodata, None);
+    assert_eq!(summary.statistics.len(), 1);
+    assert_eq!((summary.statistics[0].statistics.min, summary.statistics[0].statistics.max), (1.0, 4.0));
+    assert_eq!(summary.statistics[0].statistics.mean, 2.5);
+}
+
+#[test]
+fn test_report_json() {
+    let entries = [
+        ReportEntry {
+            output: "chips/a \"1\".tif".to_string(),
+            bbox: Some("15,36,15.1,36.1".to_string()),
+            request_crs: Some("EPSG:4326".to_string()),
+            window: Some(Region::new(10, 20, 30, 40)),
+            elapsed: Duration::from_millis(250),
+            ..Default::default()
+        },
+        ReportEntry {
+            output: "b.tif".to_string(),
+            error: Some("no overlap".to_string()),
+            radius: Some(f64::NAN),
+            ..Default::default()
+        },
+    ];
+    let json = report_json("in.tif", &entries, Duration::from_secs(1));
+    assert!(json.contains("\"input\": \"in.tif\""));
+    assert!(json.contains("\"output\": \"chips/a \\\"1\\\".tif\""));
+    assert!(json.contains("\"window\": {\"x\": 10, \"y\": 20, \"width\": 30, \"height\": 40}"));
+    assert!(json.contains("\"elapsed_seconds\": 0.25"));
+    assert!(json.contains("\"status\": \"failed\",\n      \"error\": \"no overlap\""));
+    assert!(json.contains("\"request\": {\"radius\": null}"));
+    assert!(json.contains("\"window\": null"));
+    assert_eq!(json.matches('{').count(), json.matches('}').count());
+}
@@ -0,0 +1,53 @@
+//! Tests for resizing a raster in its own coordinate system
+
+use crate::coordinate::Affine;
+use crate::extractor::{ArraySamples, ImageExtractor};
+use crate::utils::logger::Logger;
+use crate::utils::resize_utils::{self, Resampling, ResizeTarget};
+use super::test_utils::TestTiff;
+
+/// 4x4 single-band 16-bit image in one strip, sample = 10 * row + column,
+/// with 30 m pixels from (500000, 4000000) and NoData 0
+fn create_georeferenced_file() -> Vec<u8> {
+    let pixels = (0..4u16).flat_map(|row| (0..4u16).map(move |column| 10 * row + column))
+        .flat_map(u16::to_le_bytes)
+        .collect();
+    TestTiff::new(4, 4)
+        .bits(&[16])
+        .georeference(30.0, 30.0, 500000.0, 4000000.0)
+        .nodata("0")
+        .pixels(pixels)
+        .build()
+}
+
+#[test]
+fn test_average_resize_skips_nodata_and_rescales_geotransform() {
+    let dir = std::env::temp_dir();
+    let logger = Logger::new(dir.join("rasterkit_resize_tests.log").to_str().unwrap()).unwrap();
+    let input = dir.join("rasterkit_resize.tif");
+    std::fs::write(&input, create_georeferenced_file()).unwrap();
+    let output = dir.join("rasterkit_resized.tif");
+
+    let size = resize_utils::resize(input.to_str().unwrap(), output.to_str().unwrap(),
+                                    ResizeTarget::Factor(0.5), Resampling::Average, &logger).unwrap();
+    assert_eq!(size, (2, 2));
+
+    let mut extractor = ImageExtractor::new_array_extractor(&logger);
+    let array = extractor.extract_array_data(output.to_str().unwrap(), None).unwrap();
+    // The NoData pixel at (0, 0) is left out of the top-left mean
+    assert_eq!(array.data, ArraySamples::U16(vec![7, 8, 26, 28]));
+    assert_eq!(array.nodata, Some(0.0));
+    let geotransform = array.georeference.expect("the output is georeferenced").geotransform;
+    assert_eq!((geotransform.pixel_width, geotransform.pixel_height), (60.0, -60.0));
+    assert_eq!(geotransform.apply(2.0, 2.0), (500120.0, 3999880.0));
+}
+
+#[test]
+fn test_target_resolution_keeps_pixel_size() {
+    let geotransform = Affine::new(500000.0, 30.0, 4000000.0, -30.0);
+    let grid = resize_utils::resize_grid(1000, 500, Some(&geotransform), ResizeTarget::Resolution(45.0, 45.0)).unwrap();
+    assert_eq!(grid, (667, 333, 1.5, 1.5));
+
+    assert!(resize_utils::resize_grid(1000, 500, None, ResizeTarget::Resolution(45.0, 45.0)).is_err());
+    assert_eq!(resize_utils::resize_grid(3, 3, None, ResizeTarget::Factor(0.1)).unwrap().0, 1);
+}
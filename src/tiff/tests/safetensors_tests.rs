@@ -0,0 +1,65 @@
+//! Tests for safetensors output with a tensor per band
+
+use regex::Regex;
+
+use crate::extractor::{ImageExtractor, Region};
+use crate::utils::logger::Logger;
+use super::test_utils::TestTiff;
+
+/// A tensor read back as (name, dtype, shape, data)
+type Tensor = (String, String, Vec<usize>, Vec<u8>);
+
+/// Header of a safetensors file and its tensors
+pub(super) fn read_safetensors(path: &std::path::Path) -> (String, Vec<Tensor>) {
+    let bytes = std::fs::read(path).unwrap();
+    let header_len = u64::from_le_bytes(bytes[..8].try_into().unwrap()) as usize;
+    assert_eq!(header_len % 8, 0);
+    let header = std::str::from_utf8(&bytes[8..8 + header_len]).unwrap().trim_end().to_string();
+    let data = &bytes[8 + header_len..];
+
+    let entry = Regex::new(r#""(\w+)":\{"dtype":"(\w+)","shape":\[([\d,]*)\],"data_offsets":\[(\d+),(\d+)\]\}"#).unwrap();
+    let tensors = entry.captures_iter(&header).map(|captures| {
+        let shape = captures[3].split(',').map(|dimension| dimension.parse().unwrap()).collect();
+        let (start, end): (usize, usize) = (captures[4].parse().unwrap(), captures[5].parse().unwrap());
+        (captures[1].to_string(), captures[2].to_string(), shape, data[start..end].to_vec())
+    }).collect();
+    (header, tensors)
+}
+
+#[test]
+fn test_safetensors_keeps_every_band() {
+    let dir = std::env::temp_dir();
+    let logger = Logger::new(dir.join("rasterkit_safetensors_tests.log").to_str().unwrap()).unwrap();
+    let mut extractor = ImageExtractor::new_array_extractor(&logger);
+
+    // Three UInt16 bands, values above the 8-bit range
+    let input = dir.join("rasterkit_safetensors_rgb.tif");
+    let pixels: [u16; 9] = [1000, 2000, 3000, 1001, 2001, 3001, 1002, 2002, 3002];
+    std::fs::write(&input, TestTiff::new(3, 1).bits(&[16, 16, 16]).photometric(2)
+        .pixels(pixels.iter().flat_map(|value| value.to_le_bytes()).collect()).build()).unwrap();
+    let output = dir.join("rasterkit_safetensors_rgb.safetensors");
+    extractor.extract_to_array(input.to_str().unwrap(), output.to_str().unwrap(), "safetensors",
+                               Some(Region::new(1, 0, 2, 1))).unwrap();
+
+    let (header, tensors) = read_safetensors(&output);
+    assert!(header.contains(r#""__metadata__":{"width":"2","height":"1","bands":"3"}"#));
+    let le = |values: &[u16]| values.iter().flat_map(|value| value.to_le_bytes()).collect::<Vec<u8>>();
+    assert_eq!(tensors, [
+        ("band_1".to_string(), "U16".to_string(), vec![1, 2], le(&[1001, 1002])),
+        ("band_2".to_string(), "U16".to_string(), vec![1, 2], le(&[2001, 2002])),
+        ("band_3".to_string(), "U16".to_string(), vec![1, 2], le(&[3001, 3002])),
+    ]);
+
+    // A single Float32 band stays one float tensor
+    let input = dir.join("rasterkit_safetensors_dem.tif");
+    std::fs::write(&input, TestTiff::new(2, 2).bits(&[32]).sample_format(3)
+        .pixels([1.5f32, -2.0, 0.25, 9999.0].iter().flat_map(|value| value.to_le_bytes()).collect()).build()).unwrap();
+    let output = dir.join("rasterkit_safetensors_dem.safetensors");
+    extractor.extract_to_array(input.to_str().unwrap(), output.to_str().unwrap(), "safetensors", None).unwrap();
+
+    let (_, tensors) = read_safetensors(&output);
+    assert_eq!(tensors.len(), 1);
+    let (name, dtype, shape, data) = &tensors[0];
+    assert_eq!((name.as_str(), dtype.as_str(), shape.as_slice()), ("band_1", "F32", [2, 2].as_slice()));
+    assert_eq!(f32::from_le_bytes(data[12..16].try_into().unwrap()), 9999.0);
+}
@@ -0,0 +1,25 @@
+//! Tests for radiometric scaling from GDAL metadata
+
+use crate::utils::scaling_utils::{self, BandScaling};
+
+#[test]
+fn test_scaling_from_gdal_metadata() {
+    let xml = "<GDALMetadata>\n\
+        <Item name=\"STATISTICS_MEAN\" sample=\"0\">12.5</Item>\n\
+        <Item name=\"OFFSET\" sample=\"0\" role=\"offset\">-0.1</Item>\n\
+        <Item name=\"SCALE\" sample=\"0\" role=\"scale\">2.75e-05</Item>\n\
+        <Item name=\"SCALE\" sample=\"2\" role=\"scale\">0.01</Item>\n\
+        </GDALMetadata>";
+
+    let bands = scaling_utils::scaling_from_gdal_metadata(xml);
+    assert_eq!(bands, vec![
+        BandScaling { scale: 2.75e-05, offset: -0.1 },
+        BandScaling::IDENTITY,
+        BandScaling { scale: 0.01, offset: 0.0 },
+    ]);
+    assert!(scaling_utils::scaling_from_gdal_metadata("<GDALMetadata></GDALMetadata>").is_empty());
+
+    // NoData keeps its stored value
+    let values = scaling_utils::apply_scaling(&[0.0, 100.0, 200.0], BandScaling { scale: 0.5, offset: -10.0 }, Some(0.0));
+    assert_eq!(values, vec![0.0, 40.0, 90.0]);
+}
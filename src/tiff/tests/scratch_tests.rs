@@ -0,0 +1,42 @@
+//! Tests for scratch buffers spilled to disk
+
+use image::{DynamicImage, RgbImage};
+
+use crate::utils::scratch_utils::{self, ScratchBuffer};
+
+#[test]
+fn test_spilled_buffer_is_removed_when_dropped() {
+    let dir = std::env::temp_dir().join(format!("rasterkit-scratch-test-{}", std::process::id()));
+    std::fs::create_dir_all(&dir).unwrap();
+    scratch_utils::set_scratch_dir(Some(dir.clone()));
+
+    let mut values = ScratchBuffer::<f64>::spilled(1000, "test values").unwrap();
+    assert!(values.is_spilled());
+    assert_eq!(values.len(), 1000);
+    assert!(values.iter().all(|&value| value == 0.0));
+    values[999] = 2.5;
+    values[0] += 1.0;
+    assert_eq!((values[0], values[999]), (1.0, 2.5));
+    assert_eq!(std::fs::read_dir(&dir).unwrap().count(), 1);
+
+    drop(values);
+    assert_eq!(std::fs::read_dir(&dir).unwrap().count(), 0);
+    scratch_utils::set_scratch_dir(None);
+    std::fs::remove_dir(&dir).unwrap();
+}
+
+#[test]
+fn test_buffers_stay_in_memory_without_budget() {
+    let values = ScratchBuffer::<u8>::zeroed(16, "small values").unwrap();
+    assert!(!values.is_spilled());
+    assert_eq!(&values[..], &[0u8; 16]);
+}
+
+#[test]
+fn test_luma_plane_matches_to_luma8() {
+    let rgb = RgbImage::from_fn(7, 3, |x, y| image::Rgb([x as u8 * 30, y as u8 * 80, 200 - x as u8 * 10]));
+    let image = DynamicImage::ImageRgb8(rgb);
+    let plane = scratch_utils::luma_plane(&image, "test plane").unwrap();
+    assert_eq!(plane.dimensions(), (7, 3));
+    assert_eq!(&plane.as_raw()[..], image.to_luma8().as_raw().as_slice());
+}
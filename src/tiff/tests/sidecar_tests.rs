@@ -0,0 +1,96 @@
+//! Tests for georeferencing read from world files, .aux.xml and .prj sidecars
+
+use std::path::PathBuf;
+
+use crate::coordinate::Affine;
+use crate::tiff::TiffReader;
+use crate::utils::logger::Logger;
+use crate::utils::sidecar_utils;
+use super::test_utils::TestTiff;
+
+const UTM_33N: &str = "PROJCS[\"WGS 84 / UTM zone 33N\",GEOGCS[\"WGS 84\",AUTHORITY[\"EPSG\",\"4326\"]],\
+                       AUTHORITY[\"EPSG\",\"32633\"]]";
+
+/// An empty directory holding a plain 2x2 TIFF without GeoTIFF tags
+fn create_plain_tiff(name: &str) -> (PathBuf, String) {
+    let dir = std::env::temp_dir().join(name);
+    let _ = std::fs::remove_dir_all(&dir);
+    std::fs::create_dir_all(&dir).unwrap();
+    let image = dir.join("scan.tif");
+    std::fs::write(&image, TestTiff::new(2, 2).pixels(vec![1, 2, 3, 4]).build()).unwrap();
+    let path = image.to_str().unwrap().to_string();
+    (dir, path)
+}
+
+#[test]
+fn test_world_file_and_prj() {
+    let (dir, image) = create_plain_tiff("rasterkit_sidecar_world");
+    assert!(sidecar_utils::find_sidecar_georeference(&image).unwrap().is_none());
+
+    // World files locate the centre of the top-left pixel
+    std::fs::write(dir.join("scan.tfw"), "10\n0\n0\n-10\n500005\n3999995\n").unwrap();
+    let sidecar = sidecar_utils::find_sidecar_georeference(&image).unwrap().unwrap();
+    assert_eq!(sidecar.geotransform, Affine::new(500000.0, 10.0, 4000000.0, -10.0));
+    assert_eq!((sidecar.epsg, sidecar.wkt.as_deref()), (None, None));
+    assert!(sidecar.source.ends_with("scan.tfw"));
+    assert_eq!(sidecar.shifted(1, 1).to_gdal(), [500010.0, 10.0, 0.0, 3999990.0, 0.0, -10.0]);
+
+    std::fs::write(dir.join("scan.prj"), UTM_33N).unwrap();
+    let sidecar = sidecar_utils::find_sidecar_georeference(&image).unwrap().unwrap();
+    assert_eq!(sidecar.epsg, Some(32633));
+    assert_eq!(sidecar.wkt.as_deref(), Some(UTM_33N));
+
+    // A world file that cannot be parsed is an error, not a silent miss
+    std::fs::write(dir.join("scan.tfw"), "10\n0\n0\n-10\n500005\n").unwrap();
+    assert!(sidecar_utils::find_sidecar_georeference(&image).is_err());
+}
+
+#[test]
+fn test_aux_xml_takes_precedence() {
+    let (dir, image) = create_plain_tiff("rasterkit_sidecar_aux");
+    std::fs::write(dir.join("scan.wld"), "10\n0\n0\n-10\n500005\n3999995\n").unwrap();
+    std::fs::write(dir.join("scan.prj"), "EPSG:4326").unwrap();
+    let escaped = UTM_33N.replace('"', "&quot;");
+    std::fs::write(format!("{}.aux.xml", image), format!(
+        "<PAMDataset>\n  <SRS dataAxisToSRSAxisMapping=\"1,2\">{}</SRS>\n  \
+         <GeoTransform> 6.0e+05, 2.0, 0.0, 5.0e+06, 0.0, -2.0</GeoTransform>\n</PAMDataset>\n", escaped)).unwrap();
+
+    let sidecar = sidecar_utils::find_sidecar_georeference(&image).unwrap().unwrap();
+    assert_eq!(sidecar.geotransform.to_gdal(), [600000.0, 2.0, 0.0, 5000000.0, 0.0, -2.0]);
+    assert_eq!(sidecar.epsg, Some(32633));
+    assert_eq!(sidecar.wkt.as_deref(), Some(UTM_33N));
+    assert!(sidecar.source.ends_with("scan.tif.aux.xml"));
+}
+
+#[test]
+fn test_world_file_round_trip() {
+    let (dir, image) = create_plain_tiff("rasterkit_sidecar_round_trip");
+    assert_eq!(sidecar_utils::world_file_path(&image), dir.join("scan.tfw").to_str().unwrap());
+    assert!(sidecar_utils::world_file_path("map.png").ends_with("map.pgw"));
+
+    let rotated = Affine::from_gdal([1000.0, 4.0, 1.0, 2000.0, 0.5, -4.0]);
+    let world = sidecar_utils::world_file_path(&image);
+    sidecar_utils::write_world_file(&world, &rotated).unwrap();
+    assert_eq!(sidecar_utils::read_world_file(&world).unwrap(), rotated);
+}
+
+#[test]
+fn test_epsg_from_wkt_and_internal_georeference() {
+    assert_eq!(sidecar_utils::epsg_from_wkt("epsg:4326"), Some(4326));
+    assert_eq!(sidecar_utils::epsg_from_wkt(UTM_33N), Some(32633));
+    assert_eq!(sidecar_utils::epsg_from_wkt("PROJCRS[\"ETRS89 / UTM 32N\",BASEGEOGCRS[\"ETRS89\",ID[\"EPSG\",4258]],\
+                                            ID[\"EPSG\",25832]]"), Some(25832));
+    assert_eq!(sidecar_utils::epsg_from_wkt("LOCAL_CS[\"Arbitrary\"]"), None);
+
+    let dir = std::env::temp_dir();
+    let logger = Logger::new(dir.join("rasterkit_sidecar_tests.log").to_str().unwrap()).unwrap();
+    let (_, plain) = create_plain_tiff("rasterkit_sidecar_internal");
+    let georeferenced = dir.join("rasterkit_sidecar_georeferenced.tif");
+    std::fs::write(&georeferenced, TestTiff::new(2, 2).georeference(10.0, 10.0, 0.0, 0.0)
+        .pixels(vec![1, 2, 3, 4]).build()).unwrap();
+
+    let mut reader = TiffReader::new(&logger);
+    assert!(!sidecar_utils::has_internal_georeference(&reader.load(&plain).unwrap()));
+    let mut reader = TiffReader::new(&logger);
+    assert!(sidecar_utils::has_internal_georeference(&reader.load(georeferenced.to_str().unwrap()).unwrap()));
+}
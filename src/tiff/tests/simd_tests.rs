@@ -0,0 +1,45 @@
+//! Tests for the SIMD predictor against the scalar version
+
+use std::time::Instant;
+
+use crate::utils::simd_utils;
+
+/// Bytes varying enough to exercise wrapping sums
+fn noise(len: usize) -> Vec<u8> {
+    (0..len).map(|i| (i.wrapping_mul(2_654_435_761) >> 7) as u8).collect()
+}
+
+#[test]
+fn test_predictor_matches_scalar() {
+    for stride in 1..=5 {
+        for len in [0, 1, 3, 15, 16, 17, 47, 48, 100, 1027] {
+            let mut simd = noise(len);
+            let mut scalar = simd.clone();
+            simd_utils::undo_horizontal_differencing(&mut simd, stride);
+            simd_utils::undo_horizontal_differencing_scalar(&mut scalar, stride);
+            assert_eq!(simd, scalar, "stride {} length {}", stride, len);
+        }
+    }
+}
+
+/// Timings of the SIMD and scalar predictor: `cargo test --release bench_simd -- --ignored --nocapture`
+#[test]
+#[ignore]
+fn bench_simd_predictor() {
+    let data = noise(64 * 1024 * 1024);
+    let time = |name: &str, run: &mut dyn FnMut()| {
+        let start = Instant::now();
+        run();
+        println!("{:<28} {:>8.1} ms", name, start.elapsed().as_secs_f64() * 1000.0);
+    };
+
+    for stride in [1, 3, 4] {
+        let mut buffer = data.clone();
+        time(&format!("predictor stride {} simd", stride), &mut || {
+            buffer.chunks_mut(4096).for_each(|row| simd_utils::undo_horizontal_differencing(row, stride))
+        });
+        time(&format!("predictor stride {} scalar", stride), &mut || {
+            buffer.chunks_mut(4096).for_each(|row| simd_utils::undo_horizontal_differencing_scalar(row, stride))
+        });
+    }
+}
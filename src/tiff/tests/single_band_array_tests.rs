@@ -0,0 +1,34 @@
+//! Tests for extracting single-band arrays without an RGB image
+
+use crate::extractor::{ArraySamples, ImageExtractor, Padding, Region};
+use crate::utils::logger::Logger;
+use super::test_utils::TestTiff;
+
+/// 4x4 single-band 16-bit image in one strip, sample = 100 * row + column
+fn create_single_band_file() -> Vec<u8> {
+    let pixels = (0..4u16).flat_map(|row| (0..4u16).map(move |column| 100 * row + column))
+        .flat_map(u16::to_le_bytes)
+        .collect();
+    TestTiff::new(4, 4).bits(&[16]).planar(1).pixels(pixels).build()
+}
+
+#[test]
+fn test_single_band_array_extraction() {
+    let dir = std::env::temp_dir();
+    let logger = Logger::new(dir.join("rasterkit_single_band_tests.log").to_str().unwrap()).unwrap();
+    let path = dir.join("rasterkit_single_band.tif");
+    std::fs::write(&path, create_single_band_file()).unwrap();
+    let path = path.to_str().unwrap();
+
+    // 16-bit samples are read as values, not split into bytes
+    let mut extractor = ImageExtractor::new_array_extractor(&logger);
+    let array = extractor.extract_array_data(path, Some(Region::new(1, 1, 3, 2))).unwrap();
+    assert_eq!((array.width, array.height), (3, 2));
+    assert_eq!(array.data, ArraySamples::U16(vec![101, 102, 103, 201, 202, 203]));
+
+    // Padding places the window in a larger array around the fill value
+    extractor.set_padding(Some(Padding { offset_x: 1, offset_y: 0, width: 3, height: 2, fill: Some(9.0) }));
+    let array = extractor.extract_array_data(path, Some(Region::new(2, 2, 2, 2))).unwrap();
+    assert_eq!(array.data, ArraySamples::U16(vec![9, 202, 203, 9, 302, 303]));
+    assert_eq!(array.nodata, Some(9.0));
+}
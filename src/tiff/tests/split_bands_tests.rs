@@ -0,0 +1,52 @@
+//! Tests for splitting a multi-band raster into single-band files
+
+use crate::extractor::{ArraySamples, ImageExtractor};
+use crate::tiff::TiffReader;
+use crate::utils::logger::Logger;
+use crate::utils::split_utils;
+use super::test_utils::TestTiff;
+
+/// 4x3 image of three 16-bit bands in one strip, sample = 1000 * band +
+/// 10 * row + column, with 30 m pixels from (500000, 4000000) and NoData -1
+fn create_multi_band_file() -> Vec<u8> {
+    let pixels = (0..3u16)
+        .flat_map(|row| (0..4u16).flat_map(move |column| (0..3u16).map(move |band| 1000 * band + 10 * row + column)))
+        .flat_map(u16::to_le_bytes)
+        .collect();
+    TestTiff::new(4, 3)
+        .bits(&[16, 16, 16])
+        .photometric(2)
+        .planar(1)
+        .georeference(30.0, 30.0, 500000.0, 4000000.0)
+        .nodata("-1")
+        .pixels(pixels)
+        .build()
+}
+
+#[test]
+fn test_split_bands_keeps_samples_and_georeferencing() {
+    let dir = std::env::temp_dir();
+    let logger = Logger::new(dir.join("rasterkit_split_bands_tests.log").to_str().unwrap()).unwrap();
+    let input = dir.join("rasterkit_split_bands.tif");
+    std::fs::write(&input, create_multi_band_file()).unwrap();
+    let output = dir.join("rasterkit_split.tif");
+
+    let written = split_utils::split_bands(input.to_str().unwrap(), output.to_str().unwrap(), &logger).unwrap();
+    let expected: Vec<String> = (1..=3).map(|band| dir.join(format!("rasterkit_split_band{}.tif", band))
+        .to_string_lossy().into_owned()).collect();
+    assert_eq!(written, expected);
+
+    let mut extractor = ImageExtractor::new_array_extractor(&logger);
+    let array = extractor.extract_array_data(&written[2], None).unwrap();
+    assert_eq!((array.width, array.height), (4, 3));
+    assert_eq!(array.data, ArraySamples::U16((0..3).flat_map(|row| (0..4).map(move |column| 2000 + 10 * row + column)).collect()));
+    assert_eq!(array.nodata, Some(-1.0));
+    let georeference = array.georeference.expect("the band is georeferenced");
+    assert_eq!(georeference.geotransform.apply(0.0, 0.0), (500000.0, 4000000.0));
+
+    let mut reader = TiffReader::new(&logger);
+    let tiff = reader.load(&written[0]).unwrap();
+    let ifd = tiff.main_ifd().unwrap();
+    assert_eq!(ifd.get_samples_per_pixel(), 1);
+    assert_eq!(ifd.get_tag_value(258), Some(16));
+}
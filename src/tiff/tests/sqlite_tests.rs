@@ -0,0 +1,110 @@
+//! Tests reading the SQLite and GeoPackage exports back with SQLite itself
+
+use rusqlite::{Connection, OpenFlags};
+
+use crate::coordinate::Affine;
+use crate::extractor::{ArrayData, ArrayGeoreference, ArraySamples};
+use crate::utils::sqlite_utils::{SqlValue, SqliteWriter};
+
+/// A 120x80 UInt16 array in UTM with NoData on every tenth pixel
+fn create_array() -> ArrayData {
+    let data = (0..120 * 80u32).map(|index| if index % 10 == 0 { 0 } else { (index % 1000) as u16 + 1 }).collect();
+    ArrayData {
+        width: 120,
+        height: 80,
+        data: ArraySamples::U16(data),
+        bands: Vec::new(),
+        georeference: Some(ArrayGeoreference {
+            geotransform: Affine::new(500000.0, 10.0, 4000000.0, -10.0),
+            epsg: Some(32633),
+        }),
+        nodata: Some(0.0),
+    }
+}
+
+/// Open a database read-only and check SQLite finds it consistent
+fn open_checked(path: &std::path::Path) -> Connection {
+    let connection = Connection::open_with_flags(path, OpenFlags::SQLITE_OPEN_READ_ONLY).unwrap();
+    let check: String = connection.query_row("PRAGMA integrity_check", [], |row| row.get(0)).unwrap();
+    assert_eq!(check, "ok");
+    connection
+}
+
+#[test]
+fn test_sqlite_export_reads_back() {
+    let dir = std::env::temp_dir();
+    let path = dir.join("rasterkit_sqlite_pixels.sqlite");
+    let array = create_array();
+    let rows = array.save_as_sqlite(path.to_str().unwrap(), false, true).unwrap();
+    assert_eq!(rows, 120 * 80 - 960);
+
+    let connection = open_checked(&path);
+    let tables: Vec<String> = connection
+        .prepare("SELECT name FROM sqlite_schema WHERE type = 'table' ORDER BY name").unwrap()
+        .query_map([], |row| row.get(0)).unwrap()
+        .map(Result::unwrap).collect();
+    assert!(tables.contains(&"pixels".to_string()));
+    assert!(tables.contains(&"pixels_rtree".to_string()));
+
+    // The rows span many pages and keep the pixel values
+    let page_count: i64 = connection.query_row("PRAGMA page_count", [], |row| row.get(0)).unwrap();
+    assert!(page_count > 10);
+    let count: i64 = connection.query_row("SELECT count(*) FROM pixels", [], |row| row.get(0)).unwrap();
+    assert_eq!(count as usize, rows);
+    let (x, y, value, map_x): (i64, i64, f64, f64) = connection.query_row(
+        "SELECT x, y, value, map_x FROM pixels WHERE x = 37 AND y = 51", [], |row| Ok((row.get(0)?, row.get(1)?, row.get(2)?, row.get(3)?))).unwrap();
+    assert_eq!((x, y, value, map_x), (37, 51, 158.0, 500375.0));
+    let skipped: i64 = connection.query_row("SELECT count(*) FROM pixels WHERE x % 10 = 0", [], |row| row.get(0)).unwrap();
+    assert_eq!(skipped, 0);
+
+    // The R*Tree answers a window query with the pixels inside it
+    let hits: Vec<(i64, i64)> = connection.prepare(
+        "SELECT p.x, p.y FROM pixels_rtree r JOIN pixels p ON p.id = r.id \
+         WHERE r.min_x >= 500100 AND r.max_x <= 500130 AND r.min_y >= 3999950 AND r.max_y <= 3999970 ORDER BY p.y, p.x").unwrap()
+        .query_map([], |row| Ok((row.get(0)?, row.get(1)?))).unwrap()
+        .map(Result::unwrap).collect();
+    assert_eq!(hits, [(11, 3), (12, 3), (11, 4), (12, 4)]);
+}
+
+#[test]
+fn test_geopackage_export_registers_contents() {
+    let path = std::env::temp_dir().join("rasterkit_sqlite_pixels.gpkg");
+    create_array().save_as_sqlite(path.to_str().unwrap(), true, false).unwrap();
+
+    let connection = open_checked(&path);
+    let application_id: i64 = connection.query_row("PRAGMA application_id", [], |row| row.get(0)).unwrap();
+    assert_eq!(application_id, 0x47504B47);
+    let (table, data_type): (String, String) = connection.query_row(
+        "SELECT table_name, data_type FROM gpkg_contents", [], |row| Ok((row.get(0)?, row.get(1)?))).unwrap();
+    assert_eq!((table.as_str(), data_type.as_str()), ("pixels", "attributes"));
+    let srs_ids: Vec<i64> = connection.prepare("SELECT srs_id FROM gpkg_spatial_ref_sys ORDER BY srs_id").unwrap()
+        .query_map([], |row| row.get(0)).unwrap()
+        .map(Result::unwrap).collect();
+    assert_eq!(srs_ids, [-1, 0, 4326]);
+    let value: f64 = connection.query_row("SELECT value FROM pixels WHERE x = 1 AND y = 0", [], |row| row.get(0)).unwrap();
+    assert_eq!(value, 2.0);
+}
+
+#[test]
+fn test_large_rows_fill_pages_without_overflow() {
+    let path = std::env::temp_dir().join("rasterkit_sqlite_large_rows.sqlite");
+    let text = |length: usize, index: i64| SqlValue::Text(format!("{:0>width$}", index, width = length));
+
+    // Rows close to the largest payload a page holds, many pages deep
+    let mut writer = SqliteWriter::new();
+    writer.add_table("notes", "CREATE TABLE notes (id INTEGER PRIMARY KEY, body TEXT)",
+                     (1..=300).map(|id| (id, vec![SqlValue::Null, text(4000, id)]))).unwrap();
+    writer.write(path.to_str().unwrap()).unwrap();
+
+    let connection = open_checked(&path);
+    let (count, length): (i64, i64) = connection.query_row(
+        "SELECT count(*), sum(length(body)) FROM notes", [], |row| Ok((row.get(0)?, row.get(1)?))).unwrap();
+    assert_eq!((count, length), (300, 300 * 4000));
+    let body: String = connection.query_row("SELECT body FROM notes WHERE id = 257", [], |row| row.get(0)).unwrap();
+    assert!(body.ends_with("0257") && body.len() == 4000);
+
+    // A row that would need an overflow page is refused rather than corrupted
+    let mut writer = SqliteWriter::new();
+    assert!(writer.add_table("notes", "CREATE TABLE notes (id INTEGER PRIMARY KEY, body TEXT)",
+                             vec![(1, vec![SqlValue::Null, text(5000, 1)])]).is_err());
+}
@@ -0,0 +1,42 @@
+//! Tests for band statistics and their PAM sidecar
+
+use crate::utils::normalize_utils::BandStatistics;
+use crate::utils::statistics_utils::{self, BandReport, Histogram};
+
+#[test]
+fn test_describe_and_histogram() {
+    let values = [2.0, 4.0, 4.0, 4.0, 5.0, 5.0, 7.0, 9.0];
+    let stats = statistics_utils::describe(&values).unwrap();
+    assert_eq!((stats.min, stats.max, stats.mean, stats.std), (2.0, 9.0, 5.0, 2.0));
+    assert!(statistics_utils::describe(&[]).is_none());
+
+    let histogram = Histogram::build(&values, 2.0, 10.0, 8);
+    assert_eq!(histogram.counts, vec![1, 0, 3, 2, 0, 1, 0, 1]);
+    assert_eq!(histogram.coarsen(2).counts, vec![6, 2]);
+}
+
+#[test]
+fn test_pam_statistics_keep_other_content() {
+    let image = std::env::temp_dir().join("rasterkit_statistics_test.tif");
+    let image = image.to_str().unwrap();
+    let aux = format!("{}.aux.xml", image);
+    std::fs::write(&aux, "<PAMDataset>\n  <SRS>EPSG:4326</SRS>\n</PAMDataset>\n").unwrap();
+
+    let report = |mean: f64| BandReport {
+        band: 1,
+        statistics: BandStatistics { min: 0.0, max: 10.0, mean, std: 1.0 },
+        valid_percent: 100.0,
+        histogram: Histogram { min: 0.0, max: 10.0, counts: vec![3, 4] },
+        approximate: true,
+    };
+    statistics_utils::write_pam_statistics(image, &[report(4.0)]).unwrap();
+    statistics_utils::write_pam_statistics(image, &[report(5.0)]).unwrap();
+
+    let xml = std::fs::read_to_string(&aux).unwrap();
+    std::fs::remove_file(&aux).ok();
+    assert!(xml.contains("<SRS>EPSG:4326</SRS>"));
+    assert_eq!(xml.matches("<PAMRasterBand band=\"1\">").count(), 1);
+    assert!(xml.contains("<MDI key=\"STATISTICS_MEAN\">5</MDI>"));
+    assert!(xml.contains("<MDI key=\"STATISTICS_APPROXIMATE\">YES</MDI>"));
+    assert!(xml.contains("<HistCounts>3|4</HistCounts>"));
+}
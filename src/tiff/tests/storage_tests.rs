@@ -0,0 +1,72 @@
+//! Tests for reading rasters through storage backends
+
+use std::io::{self, Read, Seek, SeekFrom};
+use std::sync::atomic::{AtomicUsize, Ordering};
+use std::sync::Arc;
+
+use crate::io::storage::{self, Storage, StorageReader, BLOCK_SIZE};
+
+/// Bytes held in memory, counting the reads made against them
+struct MemoryStorage {
+    bytes: Vec<u8>,
+    reads: Arc<AtomicUsize>,
+}
+
+impl Storage for MemoryStorage {
+    fn size(&self) -> io::Result<u64> {
+        Ok(self.bytes.len() as u64)
+    }
+
+    fn read_at(&self, offset: u64, buf: &mut [u8]) -> io::Result<usize> {
+        self.reads.fetch_add(1, Ordering::Relaxed);
+        let available = self.bytes.get(offset as usize..).unwrap_or(&[]);
+        let read = available.len().min(buf.len());
+        buf[..read].copy_from_slice(&available[..read]);
+        Ok(read)
+    }
+}
+
+#[test]
+fn test_storage_reader_caches_small_reads() {
+    let bytes: Vec<u8> = (0..3 * BLOCK_SIZE).map(|i| (i % 251) as u8).collect();
+    let reads = Arc::new(AtomicUsize::new(0));
+    let mut reader = StorageReader::new(MemoryStorage { bytes: bytes.clone(), reads: Arc::clone(&reads) }).unwrap();
+
+    // Reads of a few bytes each, as header parsing makes, share one fetch
+    let mut small = [0u8; 4];
+    for offset in [8u64, 100, 12, 2000] {
+        reader.seek(SeekFrom::Start(offset)).unwrap();
+        reader.read_exact(&mut small).unwrap();
+        assert_eq!(small, bytes[offset as usize..offset as usize + 4]);
+    }
+    // A read across a block boundary, then one of a whole tile
+    reader.seek(SeekFrom::Start(BLOCK_SIZE as u64 - 2)).unwrap();
+    reader.read_exact(&mut small).unwrap();
+    assert_eq!(small, bytes[BLOCK_SIZE - 2..BLOCK_SIZE + 2]);
+    let mut tile = vec![0u8; BLOCK_SIZE];
+    reader.seek(SeekFrom::Start(10)).unwrap();
+    reader.read_exact(&mut tile).unwrap();
+    assert_eq!(tile, bytes[10..BLOCK_SIZE + 10]);
+
+    // Two blocks for the small reads and one direct read for the tile
+    assert_eq!(reads.load(Ordering::Relaxed), 3);
+
+    assert_eq!(reader.seek(SeekFrom::End(-1)).unwrap(), bytes.len() as u64 - 1);
+    let mut rest = Vec::new();
+    assert_eq!(reader.read_to_end(&mut rest).unwrap(), 1);
+    assert_eq!(rest, [bytes[bytes.len() - 1]]);
+    assert!(reader.seek(SeekFrom::Current(-(bytes.len() as i64) - 1)).is_err());
+}
+
+#[test]
+fn test_object_storage_uris() {
+    assert!(storage::is_remote("s3://bucket/scenes/image.tif"));
+    assert!(storage::is_remote("GS://bucket/image.tif"));
+    assert!(!storage::is_remote("/data/image.tif"));
+    assert!(!storage::is_remote("C:\\data\\image.tif"));
+
+    let (scheme, bucket, key) = storage::parse_uri("az://container/scenes/image.tif").unwrap();
+    assert_eq!((scheme.as_str(), bucket, key), ("az", "container", "scenes/image.tif"));
+    assert!(storage::parse_uri("s3://bucket").is_err());
+    assert!(storage::parse_uri("s3:///image.tif").is_err());
+}
@@ -0,0 +1,67 @@
+//! Tests for extractor strategies registered at runtime
+
+use image::{DynamicImage, GrayImage};
+
+use crate::RasterKit;
+use crate::extractor::{ArrayData, ExtractOptions, ExtractorStrategy, ExtractorStrategyFactory, Region};
+use crate::tiff::errors::{TiffError, TiffResult};
+use crate::utils::logger::Logger;
+
+/// Reads a toy container: one byte of width, then 8-bit grey pixels row by row
+struct GridStrategy;
+
+impl ExtractorStrategy for GridStrategy {
+    fn extract_to_file(&mut self, source_path: &str, output_path: &str,
+                       region: Option<Region>, _shape: Option<&str>) -> TiffResult<()> {
+        let image = self.extract_image(source_path, region)?;
+        image.save(output_path).map_err(|e| TiffError::GenericError(e.to_string()))
+    }
+
+    fn extract_image(&mut self, source_path: &str, region: Option<Region>) -> TiffResult<DynamicImage> {
+        let bytes = std::fs::read(source_path)?;
+        let width = bytes[0] as u32;
+        let grid = GrayImage::from_raw(width, (bytes.len() as u32 - 1) / width, bytes[1..].to_vec())
+            .ok_or_else(|| TiffError::GenericError("Truncated grid".to_string()))?;
+        let image = DynamicImage::ImageLuma8(grid);
+        Ok(match region {
+            Some(region) => image.crop_imm(region.x, region.y, region.width, region.height),
+            None => image,
+        })
+    }
+
+    fn extract_to_array(&mut self, _: &str, _: &str, _: &str, _: Option<Region>) -> TiffResult<()> {
+        Err(TiffError::GenericError("Grids have no array data".to_string()))
+    }
+
+    fn extract_array_data(&mut self, _: &str, _: Option<Region>) -> TiffResult<ArrayData> {
+        Err(TiffError::GenericError("Grids have no array data".to_string()))
+    }
+}
+
+#[test]
+fn test_registered_strategy_extracts_through_the_api() {
+    let dir = std::env::temp_dir();
+    let log = dir.join("rasterkit_strategy_registry_tests.log");
+    let kit = RasterKit::new(Some(log.to_str().unwrap())).unwrap();
+    let logger = Logger::new(log.to_str().unwrap()).unwrap();
+    let input = dir.join("rasterkit_strategy_registry_scene.GRIDTEST");
+    std::fs::write(&input, [3, 1, 2, 3, 4, 5, 6]).unwrap();
+    let input = input.to_str().unwrap();
+
+    // Unknown extensions have no strategy until one is registered
+    let factory = ExtractorStrategyFactory::new(&logger, false);
+    assert!(factory.create_strategy(input).is_err());
+    ExtractorStrategyFactory::register(&[".GridTest", "gridtest2"], |_logger, _array| Box::new(GridStrategy));
+    assert!(factory.create_strategy(input).is_ok());
+
+    let output = dir.join("rasterkit_strategy_registry_clip.png");
+    kit.extract(input, output.to_str().unwrap(), &ExtractOptions::new().region(1, 0, 2, 2)).unwrap();
+    assert_eq!(image::open(&output).unwrap().to_luma8().into_raw(), [2, 3, 5, 6]);
+
+    // Each extension is removed on its own
+    assert!(ExtractorStrategyFactory::unregister("gridtest"));
+    assert!(factory.create_strategy(input).is_err());
+    assert!(factory.create_strategy("scene.gridtest2").is_ok());
+    assert!(ExtractorStrategyFactory::unregister(".gridtest2"));
+    assert!(!ExtractorStrategyFactory::unregister("gridtest2"));
+}
@@ -0,0 +1,37 @@
+//! Tests for reading regions of stripped images
+
+use image::GenericImageView;
+use crate::extractor::{ImageExtractor, Region};
+use crate::utils::logger::Logger;
+use super::test_utils::TestTiff;
+
+/// Single-band 8-bit image of `height` rows of 8 pixels, value = row + column
+///
+/// The pixels are split into strips of `rows_per_strip` rows, the last one
+/// short; the RowsPerStrip tag holds `tag_rows`.
+fn create_striped_file(height: u32, rows_per_strip: u32, tag_rows: u32) -> Vec<u8> {
+    let pixels: Vec<u8> = (0..height).flat_map(|row| (0..8).map(move |column| (row + column) as u8)).collect();
+    let strips = pixels.chunks(8 * rows_per_strip as usize).map(<[u8]>::to_vec).collect();
+    TestTiff::new(8, height).planar(1).strips(rows_per_strip, strips).longs(278, &[tag_rows]).build()
+}
+
+#[test]
+fn test_region_reads_only_its_strips() {
+    let dir = std::env::temp_dir();
+    let logger = Logger::new(dir.join("rasterkit_strip_reader_tests.log").to_str().unwrap()).unwrap();
+
+    // 100 rows in strips of 7; the region spans strips 5 to 14, the last of them short
+    let path = dir.join("rasterkit_strips.tif");
+    std::fs::write(&path, create_striped_file(100, 7, 7)).unwrap();
+    let mut extractor = ImageExtractor::new(&logger);
+    let image = extractor.extract_image(path.to_str().unwrap(), Some(Region::new(2, 40, 5, 60))).unwrap();
+    assert_eq!(image.dimensions(), (5, 60));
+    assert_eq!(image.get_pixel(0, 0)[0], 42);
+    assert_eq!(image.get_pixel(4, 59)[0], 105);
+
+    // Writers often declare a single strip with RowsPerStrip = 2^32-1
+    let path = dir.join("rasterkit_single_strip.tif");
+    std::fs::write(&path, create_striped_file(20, 20, u32::MAX)).unwrap();
+    let image = extractor.extract_image(path.to_str().unwrap(), Some(Region::new(0, 15, 8, 5))).unwrap();
+    assert_eq!(image.get_pixel(7, 4)[0], 26);
+}
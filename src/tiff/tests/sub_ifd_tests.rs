@@ -0,0 +1,92 @@
+//! Tests for reading and extracting from SubIFDs
+
+use crate::extractor::{ImageExtractor, Region};
+use crate::tiff::TiffReader;
+use crate::utils::logger::Logger;
+use crate::utils::tiff_extraction_utils;
+use super::test_utils::TestTiff;
+
+/// An 8-bit grey reduced-resolution IFD at `offset`, with its single strip right after it
+fn create_sub_ifd(offset: u32, width: u16, height: u16, pixels: &[u8]) -> Vec<u8> {
+    let entries: [(u16, u16, u32); 10] = [
+        (254, 4, 1), (256, 3, width as u32), (257, 3, height as u32), (258, 3, 8), (259, 3, 1),
+        (262, 3, 1), (273, 4, offset + 2 + 12 * 10 + 4), (277, 3, 1), (278, 3, height as u32),
+        (279, 4, pixels.len() as u32),
+    ];
+    let mut ifd = (entries.len() as u16).to_le_bytes().to_vec();
+    for (tag, field_type, value) in entries {
+        ifd.extend([tag.to_le_bytes(), field_type.to_le_bytes()].concat());
+        ifd.extend(1u32.to_le_bytes());
+        ifd.extend(value.to_le_bytes());
+    }
+    ifd.extend(0u32.to_le_bytes());
+    ifd.extend(pixels);
+    ifd
+}
+
+/// A 4x4 image with a 2x2 overview in a SubIFD
+fn create_tiff_with_overview() -> Vec<u8> {
+    let build = |sub_ifd: u32| TestTiff::new(4, 4).tag(330, 13, 1, sub_ifd.to_le_bytes().to_vec())
+        .pixels((0..16).collect()).build();
+
+    // The pointer is inline, so the file length does not depend on it
+    let end = build(0).len() as u32;
+    let offset = end + end % 2;
+    let mut file = build(offset);
+    file.resize(offset as usize, 0);
+    file.extend(create_sub_ifd(offset, 2, 2, &[100, 101, 102, 103]));
+    file
+}
+
+#[test]
+fn test_sub_ifds_are_read() {
+    let dir = std::env::temp_dir();
+    let logger = Logger::new(dir.join("rasterkit_sub_ifd_tests.log").to_str().unwrap()).unwrap();
+    let path = dir.join("rasterkit_sub_ifd_overview.tif");
+    std::fs::write(&path, create_tiff_with_overview()).unwrap();
+
+    let mut reader = TiffReader::new(&logger);
+    let tiff = reader.load(path.to_str().unwrap()).unwrap();
+    assert_eq!(tiff.ifds.len(), 1);
+    let main_ifd = tiff.main_ifd().unwrap();
+    let overview = tiff.sub_ifd(0).unwrap();
+    assert_eq!(overview.get_dimensions(), Some((2, 2)));
+    assert!(tiff.sub_ifd(1).is_none());
+
+    assert_eq!(tiff_extraction_utils::ifd_scale_factors(main_ifd, overview), (2.0, 2.0));
+    assert!(tiff_extraction_utils::select_source_ifd(&tiff, Some(1)).is_err());
+    assert_eq!(tiff_extraction_utils::select_source_ifd(&tiff, None).unwrap().get_dimensions(), Some((4, 4)));
+
+    // Main-image regions cover every overview pixel they touch
+    let scaled = tiff_extraction_utils::scale_region_to_ifd(Region::new(1, 1, 2, 1), main_ifd, overview);
+    assert_eq!((scaled.x, scaled.y, scaled.width, scaled.height), (0, 0, 2, 1));
+    let scaled = tiff_extraction_utils::scale_region_to_ifd(Region::new(1, 1, 2, 1), main_ifd, main_ifd);
+    assert_eq!((scaled.x, scaled.y, scaled.width, scaled.height), (1, 1, 2, 1));
+}
+
+#[test]
+fn test_extract_from_a_sub_ifd() {
+    let dir = std::env::temp_dir();
+    let logger = Logger::new(dir.join("rasterkit_sub_ifd_tests.log").to_str().unwrap()).unwrap();
+    let path = dir.join("rasterkit_sub_ifd_source.tif");
+    std::fs::write(&path, create_tiff_with_overview()).unwrap();
+    let path = path.to_str().unwrap();
+
+    let mut extractor = ImageExtractor::new(&logger);
+    let main = extractor.extract_image(path, Some(Region::new(2, 2, 2, 2))).unwrap().to_luma8();
+    assert_eq!(main.into_raw(), [10, 11, 14, 15]);
+
+    extractor.set_sub_ifd(Some(0));
+    let overview = extractor.extract_image(path, Some(Region::new(2, 2, 2, 2))).unwrap().to_luma8();
+    assert_eq!(overview.into_raw(), [103]);
+    let whole = extractor.extract_image(path, None).unwrap().to_luma8();
+    assert_eq!(whole.into_raw(), [100, 101, 102, 103]);
+
+    // The output holds no child images, so it gets no SubIFDs tag
+    let output = dir.join("rasterkit_sub_ifd_clip.tif");
+    extractor.set_sub_ifd(None);
+    extractor.extract_to_file(path, output.to_str().unwrap(), Some(Region::new(0, 0, 2, 2)), None).unwrap();
+    let mut reader = TiffReader::new(&logger);
+    let tiff = reader.load(output.to_str().unwrap()).unwrap();
+    assert!(!tiff.main_ifd().unwrap().has_tag(330));
+}
@@ -69,4 +69,222 @@ pub fn create_test_bigtiff_buffer() -> Cursor<Vec<u8>> {
 
     // Return cursor at position 0
     Cursor::new(buffer)
-}
\ No newline at end of file
+}
+
+/// Pixel data of a test image and the tags that locate it
+enum Blocks {
+    /// Strips, described by RowsPerStrip
+    Strips(Vec<Vec<u8>>),
+    /// Tiles, described by TileWidth and TileLength
+    Tiles(Vec<Vec<u8>>),
+}
+
+/// Builder of small little-endian classic TIFF files for tests
+///
+/// Tags are set by value and laid out on `build`: the IFD follows the
+/// header, tag data too large for an entry follows the IFD in tag order,
+/// and the strips or tiles come last. By default the image is one band of
+/// 8-bit unsigned samples, uncompressed, black-is-zero, in a single strip.
+pub struct TestTiff {
+    /// Field type, count and little-endian bytes of each tag
+    tags: std::collections::BTreeMap<u16, (u16, u32, Vec<u8>)>,
+    /// Pixel data, None for an image without any
+    blocks: Option<Blocks>,
+}
+
+impl TestTiff {
+    /// An image of the given size with the default tags
+    pub fn new(width: u32, height: u32) -> Self {
+        TestTiff { tags: std::collections::BTreeMap::new(), blocks: None }
+            .longs(256, &[width]).longs(257, &[height]).shorts(259, &[1]).shorts(262, &[1]).bits(&[8])
+    }
+
+    /// Set a tag to raw little-endian bytes of a field type
+    pub fn tag(mut self, tag: u16, field_type: u16, count: u32, bytes: Vec<u8>) -> Self {
+        self.tags.insert(tag, (field_type, count, bytes));
+        self
+    }
+
+    /// Set a SHORT tag
+    pub fn shorts(self, tag: u16, values: &[u16]) -> Self {
+        self.tag(tag, 3, values.len() as u32, values.iter().flat_map(|value| value.to_le_bytes()).collect())
+    }
+
+    /// Set a LONG tag
+    pub fn longs(self, tag: u16, values: &[u32]) -> Self {
+        self.tag(tag, 4, values.len() as u32, values.iter().flat_map(|value| value.to_le_bytes()).collect())
+    }
+
+    /// Set a DOUBLE tag
+    pub fn doubles(self, tag: u16, values: &[f64]) -> Self {
+        self.tag(tag, 12, values.len() as u32, values.iter().flat_map(|value| value.to_le_bytes()).collect())
+    }
+
+    /// Set an ASCII tag, adding the terminating NUL
+    pub fn ascii(self, tag: u16, text: &str) -> Self {
+        let mut bytes = text.as_bytes().to_vec();
+        bytes.push(0);
+        self.tag(tag, 2, bytes.len() as u32, bytes)
+    }
+
+    /// Set the bits of each sample, and with them the number of bands
+    pub fn bits(self, bits: &[u16]) -> Self {
+        self.shorts(258, bits).shorts(277, &[bits.len() as u16])
+    }
+
+    /// Set the sample format: 1 unsigned, 2 signed, 3 floating point
+    pub fn sample_format(self, format: u16) -> Self {
+        self.shorts(339, &[format])
+    }
+
+    /// Set the photometric interpretation
+    pub fn photometric(self, photometric: u16) -> Self {
+        self.shorts(262, &[photometric])
+    }
+
+    /// Set the compression scheme
+    pub fn compression(self, compression: u16) -> Self {
+        self.shorts(259, &[compression])
+    }
+
+    /// Set the planar configuration: 1 interleaved, 2 band by band
+    pub fn planar(self, planar: u16) -> Self {
+        self.shorts(284, &[planar])
+    }
+
+    /// Georeference with a pixel scale and the map position of the top-left corner
+    ///
+    /// A negative `scale_y` makes rows run northward.
+    pub fn georeference(self, scale_x: f64, scale_y: f64, origin_x: f64, origin_y: f64) -> Self {
+        self.doubles(33550, &[scale_x, scale_y, 0.0])
+            .doubles(33922, &[0.0, 0.0, 0.0, origin_x, origin_y, 0.0])
+    }
+
+    /// Declare the EPSG code of the coordinate system, pixel-is-area
+    pub fn epsg(self, epsg: u16) -> Self {
+        let (model, key) = if epsg == 4326 { (2, 2048) } else { (1, 3072) };
+        self.shorts(34735, &[1, 1, 0, 3, 1024, 0, 1, model, 1025, 0, 1, 1, key, 0, 1, epsg])
+    }
+
+    /// Declare the NoData value
+    pub fn nodata(self, nodata: &str) -> Self {
+        self.ascii(42113, nodata)
+    }
+
+    /// Store all pixels, little-endian and interleaved, in one strip
+    pub fn pixels(self, pixels: Vec<u8>) -> Self {
+        let height = self.tag_value(257);
+        self.strips(height, vec![pixels])
+    }
+
+    /// Store the pixels in strips of `rows_per_strip` rows
+    pub fn strips(mut self, rows_per_strip: u32, strips: Vec<Vec<u8>>) -> Self {
+        self = self.longs(278, &[rows_per_strip]);
+        self.blocks = Some(Blocks::Strips(strips));
+        self
+    }
+
+    /// Store the pixels in tiles
+    pub fn tiles(mut self, tile_width: u32, tile_height: u32, tiles: Vec<Vec<u8>>) -> Self {
+        self = self.shorts(322, &[tile_width as u16]).shorts(323, &[tile_height as u16]);
+        self.tags.remove(&278);
+        self.blocks = Some(Blocks::Tiles(tiles));
+        self
+    }
+
+    /// First value of a SHORT or LONG tag
+    fn tag_value(&self, tag: u16) -> u32 {
+        match &self.tags[&tag] {
+            (3, _, bytes) => u16::from_le_bytes([bytes[0], bytes[1]]) as u32,
+            (_, _, bytes) => u32::from_le_bytes([bytes[0], bytes[1], bytes[2], bytes[3]]),
+        }
+    }
+
+    /// Lay out the file
+    pub fn build(self) -> Vec<u8> {
+        let mut tags = self.tags;
+        let blocks = match self.blocks {
+            Some(Blocks::Strips(strips)) => {
+                tags.insert(273, (4, strips.len() as u32, vec![0; 4 * strips.len()]));
+                tags.insert(279, (4, strips.len() as u32, strips.iter().flat_map(|s| (s.len() as u32).to_le_bytes()).collect()));
+                strips
+            },
+            Some(Blocks::Tiles(tiles)) => {
+                tags.insert(324, (4, tiles.len() as u32, vec![0; 4 * tiles.len()]));
+                tags.insert(325, (4, tiles.len() as u32, tiles.iter().flat_map(|t| (t.len() as u32).to_le_bytes()).collect()));
+                tiles
+            },
+            None => Vec::new(),
+        };
+
+        // Tag data that does not fit in an entry goes after the IFD,
+        // each value starting on a word boundary
+        let mut at = 8 + 2 + 12 * tags.len() + 4;
+        let mut positions = std::collections::BTreeMap::new();
+        for (tag, (_, _, bytes)) in &tags {
+            if bytes.len() > 4 {
+                positions.insert(*tag, at);
+                at += bytes.len() + bytes.len() % 2;
+            }
+        }
+        let mut offset = at as u32;
+        let block_offsets: Vec<u8> = blocks.iter()
+            .flat_map(|block| {
+                let start = offset;
+                offset += block.len() as u32;
+                start.to_le_bytes()
+            })
+            .collect();
+        for tag in [273, 324] {
+            if let Some(entry) = tags.get_mut(&tag) {
+                entry.2 = block_offsets.clone();
+            }
+        }
+
+        let mut buffer = Vec::new();
+        buffer.write_u16::<LittleEndian>(0x4949).unwrap();
+        buffer.write_u16::<LittleEndian>(42).unwrap();
+        buffer.write_u32::<LittleEndian>(8).unwrap();
+        buffer.write_u16::<LittleEndian>(tags.len() as u16).unwrap();
+        for (tag, (field_type, count, bytes)) in &tags {
+            buffer.write_u16::<LittleEndian>(*tag).unwrap();
+            buffer.write_u16::<LittleEndian>(*field_type).unwrap();
+            buffer.write_u32::<LittleEndian>(*count).unwrap();
+            match positions.get(tag) {
+                Some(position) => buffer.write_u32::<LittleEndian>(*position as u32).unwrap(),
+                None => {
+                    let mut inline = bytes.clone();
+                    inline.resize(4, 0);
+                    buffer.extend_from_slice(&inline);
+                },
+            }
+        }
+        buffer.write_u32::<LittleEndian>(0).unwrap();
+        for (_, _, bytes) in tags.values().filter(|(_, _, bytes)| bytes.len() > 4) {
+            buffer.extend_from_slice(bytes);
+            if bytes.len() % 2 == 1 {
+                buffer.push(0);
+            }
+        }
+        for block in &blocks {
+            buffer.extend_from_slice(block);
+        }
+        buffer
+    }
+}
+
+/// 3x2 image of two 8-bit bands in one strip, sample = 10 * band + 3 * row + column
+pub fn create_two_band_tiff() -> Vec<u8> {
+    let pixels = (0..6u8).flat_map(|pixel| [pixel, 10 + pixel]).collect();
+    TestTiff::new(3, 2).bits(&[8, 8]).pixels(pixels).build()
+}
+
+/// 2x2 8-bit image holding 1, 2, 3, 4 in UTM zone 33N with 30 m pixels,
+/// top left at (origin_x, 4000000)
+pub fn create_utm_tiff(origin_x: f64) -> Vec<u8> {
+    TestTiff::new(2, 2)
+        .georeference(30.0, 30.0, origin_x, 4000000.0)
+        .epsg(32633)
+        .pixels(vec![1, 2, 3, 4])
+        .build()
+}
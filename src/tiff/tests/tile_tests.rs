@@ -0,0 +1,55 @@
+//! Tests for slippy-map tile extents
+
+use crate::utils::tile_utils::{parse_tile, tile_bbox, tile_bounds, Tile, TileScheme};
+use crate::utils::warp_utils::{target_grid, WarpOptions};
+
+const HALF_WORLD: f64 = 20037508.342789244;
+
+#[test]
+fn test_parse_tile() {
+    assert_eq!(parse_tile("12/2200/1343").unwrap(), Tile { z: 12, x: 2200, y: 1343 });
+    assert!(parse_tile("12/2200").is_err());
+    assert!(parse_tile("12/-1/3").is_err());
+    assert!(parse_tile("31/0/0").is_err());
+    assert!(TileScheme::from_name("xyz").unwrap() == TileScheme::WebMercator);
+    assert!(TileScheme::from_name("mercator").is_err());
+}
+
+#[test]
+fn test_web_mercator_tiles() {
+    let world = tile_bounds(Tile { z: 0, x: 0, y: 0 }, TileScheme::WebMercator).unwrap();
+    assert_eq!((world.min_x, world.min_y, world.max_x, world.max_y), (-HALF_WORLD, -HALF_WORLD, HALF_WORLD, HALF_WORLD));
+    assert_eq!(world.epsg, Some(3857));
+
+    // Row 0 is the top in XYZ and the bottom in TMS
+    let top_left = tile_bounds(Tile { z: 1, x: 0, y: 0 }, TileScheme::WebMercator).unwrap();
+    assert_eq!((top_left.min_x, top_left.min_y, top_left.max_y), (-HALF_WORLD, 0.0, HALF_WORLD));
+    let bottom_left = tile_bounds(Tile { z: 1, x: 0, y: 0 }, TileScheme::Tms).unwrap();
+    assert_eq!((bottom_left.min_y, bottom_left.max_y), (-HALF_WORLD, 0.0));
+
+    assert!(tile_bounds(Tile { z: 1, x: 2, y: 0 }, TileScheme::WebMercator).is_err());
+}
+
+#[test]
+fn test_tile_lon_lat() {
+    let bbox = tile_bbox(Tile { z: 1, x: 1, y: 0 }, TileScheme::WebMercator).unwrap();
+    let values: Vec<f64> = bbox.split(',').map(|value| value.parse().unwrap()).collect();
+    assert_eq!(values[0], 0.0);
+    assert_eq!(values[1], 0.0);
+    assert_eq!(values[2], 180.0);
+    assert!((values[3] - 85.051_128_78).abs() < 1e-6);
+
+    // The geodetic scheme has two tiles at zoom 0
+    assert_eq!(tile_bbox(Tile { z: 0, x: 1, y: 0 }, TileScheme::Geodetic).unwrap(), "0,-90,180,90");
+    assert!(tile_bbox(Tile { z: 0, x: 2, y: 0 }, TileScheme::Geodetic).is_err());
+}
+
+#[test]
+fn test_tile_grid() {
+    let bounds = tile_bounds(Tile { z: 16, x: 35499, y: 25703 }, TileScheme::WebMercator).unwrap();
+    let resolution = bounds.width() / 256.0;
+    let options = WarpOptions { resolution: Some((resolution, resolution)), target_extent: Some(bounds), ..Default::default() };
+    let (geotransform, width, height) = target_grid(&bounds, 100, 100, &options).unwrap();
+    assert_eq!((width, height), (256, 256));
+    assert_eq!(geotransform.apply(0.0, 0.0), (bounds.min_x, bounds.max_y));
+}
@@ -0,0 +1,50 @@
+//! Tests for arrays that keep the sample type of the raster
+
+use crate::extractor::{ArraySamples, ImageExtractor};
+use crate::utils::logger::Logger;
+use crate::utils::tensor_utils::TensorDType;
+use super::test_utils::TestTiff;
+
+const ELEVATIONS: [f32; 6] = [1.5, -2.25, 100.125, 0.0, 8848.5, -10.75];
+
+/// 3x2 single-band Float32 image in one strip
+fn create_float_file() -> Vec<u8> {
+    let pixels = ELEVATIONS.iter().flat_map(|value| value.to_le_bytes()).collect();
+    TestTiff::new(3, 2).bits(&[32]).sample_format(3).pixels(pixels).build()
+}
+
+#[test]
+fn test_float_array_keeps_its_values() {
+    let dir = std::env::temp_dir();
+    let logger = Logger::new(dir.join("rasterkit_typed_array_tests.log").to_str().unwrap()).unwrap();
+    let path = dir.join("rasterkit_typed_array.tif");
+    std::fs::write(&path, create_float_file()).unwrap();
+
+    let mut extractor = ImageExtractor::new_array_extractor(&logger);
+    let array = extractor.extract_array_data(path.to_str().unwrap(), None).unwrap();
+    assert_eq!(array.data, ArraySamples::F32(ELEVATIONS.to_vec()));
+    assert_eq!(array.get(1, 1), Some(8848.5));
+
+    // The NPY dtype is the sample type, followed by the samples as stored
+    let npy = dir.join("rasterkit_typed_array.npy");
+    array.save_to_file(npy.to_str().unwrap(), "npy").unwrap();
+    let npy = std::fs::read(npy).unwrap();
+    let header = String::from_utf8_lossy(&npy[..npy.len() - 24]).to_string();
+    assert!(header.contains("'descr': '<f4'"));
+    assert!(header.contains("'shape': (2, 3)"));
+    assert_eq!(&npy[npy.len() - 4..], &(-10.75f32).to_le_bytes());
+
+    let csv = dir.join("rasterkit_typed_array.csv");
+    array.save_to_file(csv.to_str().unwrap(), "csv").unwrap();
+    let csv = std::fs::read_to_string(csv).unwrap();
+    assert_eq!(csv.lines().collect::<Vec<_>>(), ["row/col,0,1,2", "0,1.5,-2.25,100.125", "1,0,8848.5,-10.75"]);
+}
+
+#[test]
+fn test_samples_convert_between_types() {
+    let samples = ArraySamples::from_values(&[-3.0, 70000.0, 2.6, 300.0], TensorDType::U16);
+    assert_eq!(samples, ArraySamples::U16(vec![0, 65535, 3, 300]));
+    assert_eq!(samples.dtype().npy_descr(), "<u2");
+    assert_eq!(samples.to_le_bytes(), [0, 0, 255, 255, 3, 0, 44, 1]);
+    assert_eq!(&samples.to_u8()[..], [0, 255, 3, 255]);
+}
@@ -0,0 +1,57 @@
+//! Tests for the typed CRS and coordinate types
+
+use crate::coordinate::{CoordinateSystem, Crs, LonLat, ProjectedXY};
+use crate::extractor::ExtractOptions;
+use crate::tiff::errors::TiffError;
+use crate::RasterKit;
+
+#[test]
+fn test_crs_parsing() {
+    assert_eq!(Crs::parse("EPSG:4326").unwrap(), Crs::WGS84);
+    assert_eq!("epsg:3857".parse::<Crs>().unwrap(), Crs::WEB_MERCATOR);
+    assert_eq!(Crs::parse(" 32633 ").unwrap().epsg(), 32633);
+    assert_eq!(Crs::utm(33, true).unwrap(), Crs::from_epsg(32633).unwrap());
+    assert_eq!(Crs::from(CoordinateSystem::UTM(18, false)).to_string(), "EPSG:32718");
+
+    assert!(Crs::parse("WGS84").is_err());
+    assert!(Crs::from_epsg(0).is_err());
+    assert!(Crs::utm(61, true).is_err());
+
+    assert!(Crs::WGS84.is_geographic());
+    assert!(!Crs::WEB_MERCATOR.is_geographic());
+}
+
+#[test]
+fn test_lon_lat_checks_ranges() {
+    let position = LonLat::parse("-109.22624, 56.13484").unwrap();
+    assert_eq!((position.lon(), position.lat()), (-109.22624, 56.13484));
+    assert_eq!(LonLat::from_lat_lon(56.13484, -109.22624).unwrap(), position);
+    assert_eq!(position.to_string().parse::<LonLat>().unwrap(), position);
+
+    // Latitude first by mistake
+    assert!(LonLat::parse("56.13484,-109.22624").is_err());
+    assert!(LonLat::new(181.0, 0.0).is_err());
+    assert!(LonLat::new(f64::NAN, 0.0).is_err());
+    assert!(LonLat::parse("1.0").is_err());
+}
+
+#[test]
+fn test_projected_xy_parsing() {
+    assert_eq!(ProjectedXY::parse("500000,4649776.2").unwrap(), ProjectedXY::new(500000.0, 4649776.2));
+    assert!("500000;4649776".parse::<ProjectedXY>().is_err());
+    assert!(ProjectedXY::parse("inf,0").is_err());
+}
+
+#[test]
+fn test_projected_centre_needs_projected_crs() {
+    let dir = std::env::temp_dir();
+    let kit = RasterKit::new(Some(dir.join("rasterkit_typed_coordinates.log").to_str().unwrap())).unwrap();
+    let centre = ProjectedXY::new(500000.0, 4649776.0);
+
+    let message = |options: ExtractOptions| match kit.extract_to_buffer("missing.tif", &options) {
+        Err(TiffError::GenericError(message)) => message,
+        other => panic!("unexpected result {:?}", other.map(|_| ())),
+    };
+    assert!(message(ExtractOptions::new().projected_coordinate(centre, 100.0)).contains("needs the CRS"));
+    assert!(message(ExtractOptions::new().projected_coordinate(centre, 100.0).crs(Crs::WGS84)).contains("LonLat"));
+}
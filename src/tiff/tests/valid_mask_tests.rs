@@ -0,0 +1,17 @@
+//! Tests for valid-data masks
+
+use crate::utils::valid_mask_utils::ValidMask;
+
+#[test]
+fn test_pack_bits() {
+    // Rows of ten cells take two bytes each, padded with zero bits
+    let rows = [
+        "#........#",
+        ".##.......",
+    ];
+    let valid: Vec<bool> = rows.iter().flat_map(|row| row.chars().map(|c| c == '#')).collect();
+    let mask = ValidMask { width: 10, height: 2, cell: 1, valid };
+
+    assert_eq!(mask.valid_count(), 4);
+    assert_eq!(mask.pack_bits(), vec![0b1000_0000, 0b0100_0000, 0b0110_0000, 0b0000_0000]);
+}
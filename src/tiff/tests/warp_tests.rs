@@ -0,0 +1,106 @@
+//! Tests for the warp target grid
+
+use crate::coordinate::BoundingBox;
+use crate::utils::warp_utils::{self, WarpOptions};
+
+#[test]
+fn test_target_aligned_grid() {
+    let extent = BoundingBox::new(500_012.0, 3_999_087.0, 501_203.0, 4_000_004.0);
+
+    // Snapped outwards to multiples of 30 m
+    let options = WarpOptions { resolution: Some((30.0, 30.0)), target_aligned: true, ..Default::default() };
+    let (geotransform, width, height) = warp_utils::target_grid(&extent, 40, 30, &options).unwrap();
+    assert_eq!((geotransform.origin_x, geotransform.origin_y), (500_010.0, 4_000_020.0));
+    assert_eq!((geotransform.pixel_width, geotransform.pixel_height), (30.0, -30.0));
+    assert_eq!((width, height), (40, 32));
+
+    // Without alignment the grid starts at the extent
+    let options = WarpOptions { resolution: Some((30.0, 30.0)), ..Default::default() };
+    let (geotransform, width, _) = warp_utils::target_grid(&extent, 40, 30, &options).unwrap();
+    assert_eq!(geotransform.origin_x, 500_012.0);
+    assert_eq!(width, 40);
+
+    // Aligning needs a resolution
+    let options = WarpOptions { target_aligned: true, ..Default::default() };
+    assert!(warp_utils::target_grid(&extent, 40, 30, &options).is_err());
+}
+
+#[test]
+fn test_block_size_budget() {
+    // Without a budget the output is one block
+    assert_eq!(warp_utils::block_size(1000, 800, 500_000, 1, None), 1000);
+
+    // A budget that leaves 1 MB after the 800 kB output, with one source
+    // pixel of 1 byte per block pixel and 16 bytes of lookup
+    let side = warp_utils::block_size(1000, 800, 800_000, 1, Some(800_000 + 1_048_576));
+    assert_eq!(side, (1_048_576f64 / 17.0).sqrt() as u32);
+
+    // Budgets too small for the output fall back to the smallest blocks
+    assert_eq!(warp_utils::block_size(1000, 800, 800_000, 1, Some(1000)), 64);
+}
+
+#[test]
+fn test_source_nodata_pixels() {
+    use image::{LumaA, Rgb};
+
+    // A pixel is NoData only when every colour channel matches
+    assert!(!warp_utils::has_data(&Rgb([0u8, 0, 0]), Some(0.0)));
+    assert!(warp_utils::has_data(&Rgb([0u8, 7, 0]), Some(0.0)));
+    assert!(warp_utils::has_data(&Rgb([0u8, 0, 0]), None));
+
+    // A cleared alpha channel hides the pixel whatever its value
+    assert!(!warp_utils::has_data(&LumaA([120u8, 0]), None));
+    assert!(warp_utils::has_data(&LumaA([120u8, 255]), Some(0.0)));
+}
+
+#[test]
+fn test_approximate_pixel_mapping() {
+    use crate::coordinate::Affine;
+    use crate::tiff::proj_definition::ProjDefinition;
+    use crate::utils::warp_utils::{PixelMapping, WarpTransformer};
+
+    // A 30 m UTM raster mapped onto a geographic grid
+    let utm = ProjDefinition::from_epsg(32633).unwrap();
+    let wgs84 = ProjDefinition::from_epsg(4326).unwrap();
+    let transformer = WarpTransformer::new(&utm, &wgs84).unwrap();
+    let source = Affine::new(500_000.0, 30.0, 4_000_000.0, -30.0);
+    let target = Affine::new(15.0, 0.0003, 36.14, -0.0003);
+
+    let exact = PixelMapping::new(target, &transformer, &source, 0.0).unwrap();
+    let approximate = PixelMapping::new(target, &transformer, &source, 0.125).unwrap();
+    let (exact_positions, exact_count) = exact.positions(0, 0, 200, 150);
+    let (approximate_positions, approximate_count) = approximate.positions(0, 0, 200, 150);
+    assert_eq!(exact_count, 200 * 150);
+    assert!(approximate_count < exact_count / 100);
+
+    for (exact, approximate) in exact_positions.iter().zip(&approximate_positions) {
+        let ((x, y), (ax, ay)) = (exact.unwrap(), approximate.unwrap());
+        assert!((x - ax).abs() <= 0.2 && (y - ay).abs() <= 0.2);
+    }
+}
+
+#[test]
+fn test_gpu_warp_matches_cpu() {
+    use image::{DynamicImage, GrayImage, Luma};
+    use crate::coordinate::Affine;
+    use crate::extractor::Region;
+    use crate::tiff::proj_definition::ProjDefinition;
+    use crate::utils::warp_utils::WarpTransformer;
+
+    // Without a GPU (or the gpu feature) the warp falls back to the CPU
+    let utm = ProjDefinition::from_epsg(32633).unwrap();
+    let transformer = WarpTransformer::new(&utm, &utm).unwrap();
+    let source = GrayImage::from_fn(16, 12, |x, y| Luma([(x * 12 + y) as u8]));
+    let geotransform = Affine::new(500_000.0, 30.0, 4_000_000.0, -30.0);
+
+    let warp = |gpu: bool| {
+        let options = WarpOptions { resolution: Some((20.0, 20.0)), source_nodata: Some(0.0), gpu, ..Default::default() };
+        warp_utils::warp_windows(Region::new(0, 0, 16, 12), &geotransform, &transformer, &options, 1,
+                                 |window| Ok(DynamicImage::ImageLuma8(source.clone()).crop_imm(window.x, window.y, window.width, window.height)))
+            .unwrap()
+    };
+    let ((cpu, cpu_geotransform), (gpu, gpu_geotransform)) = (warp(false), warp(true));
+    assert_eq!(cpu_geotransform, gpu_geotransform);
+    assert_eq!((cpu.width(), cpu.height()), (24, 18));
+    assert_eq!(cpu.as_bytes(), gpu.as_bytes());
+}
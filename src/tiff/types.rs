@@ -31,6 +31,11 @@ impl TIFF {
         self.ifds.len()
     }
 
+    /// Returns a SubIFD of the main IFD, by its position in the SubIFDs tag
+    pub fn sub_ifd(&self, index: usize) -> Option<&IFD> {
+        self.main_ifd()?.sub_ifds.get(index)
+    }
+
     /// Returns a reference to all overview IFDs (subfile type 1)
     pub fn overviews(&self) -> Vec<&IFD> {
         self.ifds.iter()
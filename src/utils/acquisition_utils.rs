@@ -0,0 +1,236 @@
+//! Acquisition time utilities
+//!
+//! When an image was taken can be recorded in three places: the TIFF
+//! DateTime tag (306), the EXIF DateTimeOriginal of camera and drone
+//! imagery, and the ACQUISITIONDATETIME item GDAL keeps in the IMAGERY
+//! metadata domain of satellite products. DateTime is often the time the
+//! file was written rather than captured, so it is only used when neither
+//! of the others is present.
+//!
+//! Times are compared as recorded: fractions of a second and time zone
+//! offsets are dropped.
+
+use std::fmt;
+
+use log::{debug, info, warn};
+
+use crate::tiff::constants::tags;
+use crate::tiff::errors::{TiffError, TiffResult};
+use crate::tiff::ifd::IFD;
+use crate::tiff::TiffReader;
+use crate::utils::logger::Logger;
+use crate::utils::{exif_utils, tiff_extraction_utils};
+
+/// EXIF tag of the time the image was captured
+pub const DATE_TIME_ORIGINAL: u16 = 36867;
+
+/// GDAL metadata items holding the acquisition time, most specific first
+const ACQUISITION_ITEMS: [&str; 3] = ["ACQUISITIONDATETIME", "ACQUISITION_DATETIME", "DATE_ACQUIRED"];
+
+/// Date and time to the second
+///
+/// Fields are ordered from the year down, so comparing timestamps
+/// compares them chronologically.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord, Hash)]
+pub struct Timestamp {
+    /// Year, e.g. 2023
+    pub year: u16,
+    /// Month, 1 to 12
+    pub month: u8,
+    /// Day of the month, from 1
+    pub day: u8,
+    /// Hour, 0 to 23
+    pub hour: u8,
+    /// Minute, 0 to 59
+    pub minute: u8,
+    /// Second, 0 to 60 for leap seconds
+    pub second: u8,
+}
+
+impl Timestamp {
+    /// Parse a date with an optional time of day
+    ///
+    /// Accepts the TIFF and EXIF layout `2023:06:14 10:32:05` as well as
+    /// ISO 8601, e.g. `2023-06-14T10:32:05.25Z` or `2023-06-14`. A missing
+    /// time of day is midnight.
+    ///
+    /// # Arguments
+    /// * `text` - The date and time
+    ///
+    /// # Returns
+    /// The timestamp, or None if the text is not a valid date
+    pub fn parse(text: &str) -> Option<Self> {
+        let text = text.trim().trim_end_matches('\0');
+        let (date, time) = match text.split_once(['T', ' ']) {
+            Some((date, time)) => (date, time.trim()),
+            None => (text, ""),
+        };
+
+        let mut date_parts = date.split([':', '-']).map(|part| part.parse::<u16>().ok());
+        let (year, month, day) = (date_parts.next()??, date_parts.next()??, date_parts.next()??);
+        if date_parts.next().is_some() || !(1..=12).contains(&month) || !(1..=31).contains(&day) {
+            return None;
+        }
+
+        // Drop the zone and the fraction of a second: "10:32:05.25+02:00" is 10:32:05
+        let time = time.split(['Z', 'z', '+', '-', '.']).next().unwrap_or("");
+        let mut time_parts = time.split(':').filter(|part| !part.is_empty()).map(|part| part.parse::<u8>().ok());
+        let hour = time_parts.next().unwrap_or(Some(0))?;
+        let minute = time_parts.next().unwrap_or(Some(0))?;
+        let second = time_parts.next().unwrap_or(Some(0))?;
+        if time_parts.next().is_some() || hour > 23 || minute > 59 || second > 60 {
+            return None;
+        }
+
+        Some(Timestamp { year, month: month as u8, day: day as u8, hour, minute, second })
+    }
+
+    /// The last second of the same day
+    pub fn end_of_day(&self) -> Self {
+        Timestamp { hour: 23, minute: 59, second: 59, ..*self }
+    }
+}
+
+impl fmt::Display for Timestamp {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "{:04}-{:02}-{:02}T{:02}:{:02}:{:02}",
+               self.year, self.month, self.day, self.hour, self.minute, self.second)
+    }
+}
+
+/// Times recorded in a file
+#[derive(Debug, Clone, Copy, Default, PartialEq)]
+pub struct AcquisitionMetadata {
+    /// TIFF DateTime, often when the file was written
+    pub date_time: Option<Timestamp>,
+    /// EXIF DateTimeOriginal, when a camera took the image
+    pub date_time_original: Option<Timestamp>,
+    /// GDAL ACQUISITIONDATETIME, when a satellite scene was acquired
+    pub acquisition_date_time: Option<Timestamp>,
+}
+
+impl AcquisitionMetadata {
+    /// Best estimate of when the image was taken
+    pub fn acquired(&self) -> Option<Timestamp> {
+        self.acquisition_date_time.or(self.date_time_original).or(self.date_time)
+    }
+}
+
+/// Read the acquisition time from GDAL_METADATA XML
+///
+/// # Arguments
+/// * `xml` - Content of the GDAL_METADATA tag
+///
+/// # Returns
+/// The time of the first acquisition item that parses, if any
+pub fn acquisition_from_gdal_metadata(xml: &str) -> Option<Timestamp> {
+    let items: Vec<(String, &str)> = xml.split("<Item").skip(1)
+        .filter_map(|item| item.split_once('>'))
+        .filter_map(|(tag, rest)| {
+            let name = tag.split("name=\"").nth(1)?.split('"').next()?;
+            Some((name.to_uppercase(), rest.split("</Item>").next()?))
+        })
+        .collect();
+
+    ACQUISITION_ITEMS.iter()
+        .find_map(|wanted| items.iter().find(|(name, _)| name == wanted)
+            .and_then(|(_, value)| Timestamp::parse(value)))
+}
+
+/// Read the times recorded in an IFD
+///
+/// Unreadable or malformed values are skipped.
+///
+/// # Arguments
+/// * `reader` - Reader that loaded the file
+/// * `ifd` - The image IFD
+///
+/// # Returns
+/// The times found
+pub fn read_acquisition(reader: &TiffReader, ifd: &IFD) -> AcquisitionMetadata {
+    let date_time = reader.read_tag::<String>(ifd, tags::DATE_TIME).ok()
+        .and_then(|strings| strings.into_iter().next())
+        .and_then(|text| Timestamp::parse(&text));
+
+    let date_time_original = exif_utils::read_directory(reader, ifd, tags::EXIF_IFD).ok().flatten()
+        .and_then(|entries| entries.into_iter().find(|entry| entry.tag == DATE_TIME_ORIGINAL))
+        .and_then(|entry| Timestamp::parse(&String::from_utf8_lossy(&entry.data)));
+
+    let acquisition_date_time = tiff_extraction_utils::extract_gdal_metadata(ifd, reader)
+        .and_then(|xml| acquisition_from_gdal_metadata(&xml));
+
+    AcquisitionMetadata { date_time, date_time_original, acquisition_date_time }
+}
+
+/// Inclusive range of acquisition times, open on either side
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct DateRange {
+    /// Earliest time in the range
+    pub start: Option<Timestamp>,
+    /// Latest time in the range
+    pub end: Option<Timestamp>,
+}
+
+impl DateRange {
+    /// Parse a range as `START,END`
+    ///
+    /// Either side may be left empty. An end given as a date alone takes
+    /// in the whole day, so `2023-06-01,2023-06-30` covers all of June.
+    pub fn from_string(text: &str) -> TiffResult<Self> {
+        let (start, end) = text.split_once(',').ok_or_else(|| TiffError::GenericError(format!(
+            "Invalid date range '{}', expected START,END such as 2023-06-01,2023-06-30", text)))?;
+        let bound = |bound: &str| -> TiffResult<Option<Timestamp>> {
+            let bound = bound.trim();
+            if bound.is_empty() {
+                return Ok(None);
+            }
+            Timestamp::parse(bound).map(Some).ok_or_else(|| TiffError::GenericError(format!(
+                "Invalid date '{}' in range '{}'", bound, text)))
+        };
+
+        let start = bound(start)?;
+        let end = bound(end)?.map(|time| if end.trim().len() <= 10 { time.end_of_day() } else { time });
+        if let (Some(start), Some(end)) = (start, end) {
+            if start > end {
+                return Err(TiffError::GenericError(format!("Date range '{}' ends before it starts", text)));
+            }
+        }
+        Ok(DateRange { start, end })
+    }
+
+    /// Whether a time lies in the range
+    pub fn contains(&self, time: Timestamp) -> bool {
+        self.start.is_none_or(|start| time >= start) && self.end.is_none_or(|end| time <= end)
+    }
+}
+
+/// Keep the files acquired within a date range
+///
+/// Files without any recorded time are dropped with a warning.
+///
+/// # Arguments
+/// * `paths` - Files to filter, in order
+/// * `range` - Acquisition times to keep
+/// * `logger` - Logger for recording operations
+///
+/// # Returns
+/// The files in range, in their original order
+pub fn filter_by_acquisition(paths: &[String], range: &DateRange, logger: &Logger) -> TiffResult<Vec<String>> {
+    let mut kept = Vec::with_capacity(paths.len());
+    for path in paths {
+        let mut reader = TiffReader::new(logger);
+        let tiff = reader.load(path)?;
+        let ifd = tiff.main_ifd()
+            .ok_or_else(|| TiffError::GenericError(format!("No IFDs found in {}", path)))?;
+
+        match read_acquisition(&reader, ifd).acquired() {
+            Some(time) if range.contains(time) => {
+                debug!("{} was acquired at {}, in range", path, time);
+                kept.push(path.clone());
+            },
+            Some(time) => info!("Skipping {}, acquired at {}", path, time),
+            None => warn!("Skipping {}, which records no acquisition time", path),
+        }
+    }
+    Ok(kept)
+}
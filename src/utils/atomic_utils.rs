@@ -0,0 +1,118 @@
+//! Atomic output files
+//!
+//! A run killed halfway through writing leaves a truncated file behind,
+//! and a downstream job polling the output directory has no way to tell
+//! it from a finished one. Writers therefore put their data into a partial
+//! file next to the output (`.<name>.part`, hidden and on the same file
+//! system) and only rename it to the output once it is complete and synced.
+//! The rename replaces any earlier output in one step, so readers see
+//! either the old file or the new one.
+
+use std::fs::{self, File};
+use std::path::{Path, PathBuf};
+
+use log::{debug, warn};
+
+use crate::tiff::errors::{TiffError, TiffResult};
+
+/// Extension of partial files
+const PARTIAL_EXTENSION: &str = "part";
+
+/// Path of the partial file an output is written to
+///
+/// # Arguments
+/// * `output` - Path of the finished output
+///
+/// # Returns
+/// A hidden file in the same directory as the output
+pub fn partial_path(output: &Path) -> PathBuf {
+    let name = output.file_name().map_or_else(|| "output".into(), |name| name.to_string_lossy());
+    output.with_file_name(format!(".{}.{}", name, PARTIAL_EXTENSION))
+}
+
+/// Move a complete partial file into place
+///
+/// The partial file is synced first, so a crash right after the rename
+/// cannot leave an output whose data never reached the disk.
+///
+/// # Arguments
+/// * `partial` - The complete partial file
+/// * `output` - Path of the output it becomes
+pub fn finish(partial: &Path, output: &Path) -> TiffResult<()> {
+    File::open(partial)?.sync_all()?;
+    fs::rename(partial, output).map_err(|e| TiffError::GenericError(format!(
+        "Failed to move {} to {}: {}", partial.display(), output.display(), e)))?;
+    // Make the rename itself durable where directories can be synced
+    #[cfg(unix)]
+    if let Some(dir) = output.parent().filter(|dir| !dir.as_os_str().is_empty()) {
+        if let Err(e) = File::open(dir).and_then(|dir| dir.sync_all()) {
+            debug!("Could not sync directory {}: {}", dir.display(), e);
+        }
+    }
+    Ok(())
+}
+
+/// Write an output through a partial file
+///
+/// # Arguments
+/// * `output_path` - Path of the output
+/// * `write` - Writes the whole output to the path it is given
+///
+/// # Returns
+/// What `write` returned; on an error the partial file is removed and the
+/// output left as it was
+pub fn write_atomically<T>(output_path: &str, write: impl FnOnce(&str) -> TiffResult<T>) -> TiffResult<T> {
+    let output = Path::new(output_path);
+    let partial = partial_path(output);
+    let result = write(&partial.to_string_lossy()).and_then(|value| finish(&partial, output).map(|_| value));
+    if result.is_err() && partial.exists() {
+        if let Err(e) = fs::remove_file(&partial) {
+            warn!("Failed to remove partial output {}: {}", partial.display(), e);
+        }
+    }
+    result
+}
+
+/// Write a set of outputs that belong together through partial files
+///
+/// Every output is written to its partial file before any is moved into
+/// place, in the order given, so for a data file and its header listed
+/// last a reader never finds the header without the complete data.
+///
+/// # Arguments
+/// * `output_paths` - Paths of the outputs
+/// * `write` - Writes every output to the partial paths it is given, in the same order
+///
+/// # Returns
+/// What `write` returned; on an error the partial files are removed
+pub fn write_all_atomically<T>(output_paths: &[&Path], write: impl FnOnce(&[PathBuf]) -> TiffResult<T>) -> TiffResult<T> {
+    let partials: Vec<PathBuf> = output_paths.iter().map(|output| partial_path(output)).collect();
+    let result = write(&partials).and_then(|value| {
+        for (partial, output) in partials.iter().zip(output_paths) {
+            finish(partial, output)?;
+        }
+        Ok(value)
+    });
+    if result.is_err() {
+        for partial in partials.iter().filter(|partial| partial.exists()) {
+            if let Err(e) = fs::remove_file(partial) {
+                warn!("Failed to remove partial output {}: {}", partial.display(), e);
+            }
+        }
+    }
+    result
+}
+
+/// Check that an output can be written, without touching the output
+///
+/// # Arguments
+/// * `output_path` - Path of the output
+///
+/// # Returns
+/// Ok if its partial file could be created
+pub fn check_writable(output_path: &str) -> TiffResult<()> {
+    let partial = partial_path(Path::new(output_path));
+    File::create(&partial)?;
+    fs::remove_file(&partial)?;
+    Ok(())
+}
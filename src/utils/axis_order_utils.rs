@@ -0,0 +1,109 @@
+//! Axis order of geographic coordinates
+//!
+//! EPSG:4326 formally lists latitude before longitude, GPS devices and web
+//! maps show "lat, lon", while GeoTIFF and most GIS tools work in x,y,
+//! i.e. "lon, lat". Coordinates and bounding boxes given in the wrong
+//! order extract a mirrored area, or nothing at all. Everything inside
+//! RasterKit is x,y; these utilities bring user input into that order,
+//! either as told or by spotting values that can only be a latitude in
+//! the wrong place.
+
+use log::{info, warn};
+
+use crate::tiff::errors::{TiffError, TiffResult};
+
+/// Order of the axes in user-supplied geographic coordinates
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum AxisOrder {
+    /// Longitude first (x,y)
+    LonLat,
+    /// Latitude first (y,x)
+    LatLon,
+    /// Longitude first unless the values show otherwise
+    Auto,
+}
+
+impl AxisOrder {
+    /// Parse an axis order name
+    ///
+    /// # Arguments
+    /// * `name` - "lonlat" (or "xy"), "latlon" (or "yx"), or "auto"
+    ///
+    /// # Returns
+    /// The axis order, or an error naming the accepted values
+    pub fn from_name(name: &str) -> TiffResult<Self> {
+        match name.trim().to_lowercase().replace(['-', '_', ','], "").as_str() {
+            "lonlat" | "xy" => Ok(AxisOrder::LonLat),
+            "latlon" | "yx" => Ok(AxisOrder::LatLon),
+            "auto" => Ok(AxisOrder::Auto),
+            _ => Err(TiffError::GenericError(format!(
+                "Unknown axis order '{}', expected lonlat, latlon or auto", name))),
+        }
+    }
+}
+
+/// Bring a coordinate or bounding box into x,y order
+///
+/// Works on "x,y" coordinates and "minx,miny,maxx,maxy" boxes alike, each
+/// pair being swapped when the input is latitude first. With `Auto`, a
+/// geographic input is read latitude first only when its values fit
+/// that order but not the other, i.e. a second value lies beyond ±90;
+/// the swap is logged as a warning. Coordinates of projected systems are swapped
+/// only when asked to.
+///
+/// # Arguments
+/// * `input` - Comma-separated coordinate or bounding box
+/// * `order` - Axis order of the input
+/// * `geographic` - Whether the values are degrees of a geographic CRS
+///
+/// # Returns
+/// The input in x,y order, or an error for values outside ±180/±90
+pub fn normalize_axis_order(input: &str, order: AxisOrder, geographic: bool) -> TiffResult<String> {
+    let values = input.split(',')
+        .map(|value| value.trim().parse::<f64>())
+        .collect::<Result<Vec<f64>, _>>()
+        .map_err(|_| TiffError::GenericError(format!("'{}' is not a list of numbers", input)))?;
+    if values.len() != 2 && values.len() != 4 {
+        return Err(TiffError::GenericError(format!(
+            "'{}' is neither a coordinate (x,y) nor a bounding box (minx,miny,maxx,maxy)", input)));
+    }
+    let pairs: Vec<(f64, f64)> = values.chunks(2).map(|pair| (pair[0], pair[1])).collect();
+
+    let swap = match order {
+        AxisOrder::LonLat => false,
+        AxisOrder::LatLon => true,
+        AxisOrder::Auto if !geographic => false,
+        AxisOrder::Auto => {
+            let fits_latitude_first = pairs.iter().all(|&(first, second)| first.abs() <= 90.0 && second.abs() <= 180.0);
+            let fits_longitude_first = pairs.iter().all(|&(first, second)| first.abs() <= 180.0 && second.abs() <= 90.0);
+            let latitude_first = fits_latitude_first && !fits_longitude_first;
+            if latitude_first {
+                warn!("'{}' has a second value beyond ±90, reading it as latitude first \
+                       (pass --axis-order lonlat or latlon to be explicit)", input);
+            }
+            latitude_first
+        },
+    };
+
+    let pairs: Vec<(f64, f64)> = if swap {
+        pairs.into_iter().map(|(lat, lon)| (lon, lat)).collect()
+    } else {
+        pairs
+    };
+
+    if geographic {
+        if let Some(&(lon, lat)) = pairs.iter().find(|&&(lon, lat)| lon.abs() > 180.0 || lat.abs() > 90.0) {
+            return Err(TiffError::GenericError(format!(
+                "'{}' is out of range as longitude {}, latitude {}; check --axis-order and --crs", input, lon, lat)));
+        }
+    }
+
+    let normalized = pairs.iter()
+        .map(|(x, y)| format!("{},{}", x, y))
+        .collect::<Vec<_>>()
+        .join(",");
+    if swap {
+        info!("Read '{}' latitude first, as x,y: {}", input, normalized);
+    }
+    Ok(normalized)
+}
@@ -0,0 +1,152 @@
+//! Colour balancing utilities
+//!
+//! Orthophotos flown on different days or with different exposures show
+//! visible seams when mosaicked. Histogram matching remaps the levels of
+//! each band of an image so that its distribution follows that of a
+//! reference image. Where the two overlap, only the overlapping pixels
+//! are compared, which is what makes the seam between them disappear;
+//! otherwise the whole images are.
+
+use image::{DynamicImage, GrayImage, RgbImage};
+use log::info;
+
+use crate::tiff::errors::{TiffError, TiffResult};
+use crate::utils::grid_utils::{self, Raster};
+use crate::utils::logger::Logger;
+use crate::utils::render_utils;
+use crate::utils::sample_utils::SampleFormat;
+
+/// Levels of 8-bit imagery
+const LEVELS: usize = 256;
+
+/// Fewest overlapping pixels worth matching on
+const MIN_OVERLAP_PIXELS: u64 = 100;
+
+/// Build the lookup table matching one histogram to another
+///
+/// Each source level is mapped to the lowest reference level whose
+/// cumulative share of pixels reaches that of the source level.
+///
+/// # Arguments
+/// * `source` - Pixels per level of the image to adjust
+/// * `reference` - Pixels per level of the reference
+///
+/// # Returns
+/// The new level of each source level
+pub fn matching_lookup(source: &[u64], reference: &[u64]) -> Vec<u8> {
+    let cumulative = |counts: &[u64]| -> Vec<f64> {
+        let total = counts.iter().sum::<u64>().max(1) as f64;
+        counts.iter()
+            .scan(0u64, |sum, &count| {
+                *sum += count;
+                Some(*sum as f64 / total)
+            })
+            .collect()
+    };
+    let (source_cdf, reference_cdf) = (cumulative(source), cumulative(reference));
+
+    (0..LEVELS)
+        .map(|level| {
+            let share = source_cdf.get(level).copied().unwrap_or(1.0);
+            reference_cdf.iter()
+                .position(|&reference_share| reference_share >= share - 1e-12)
+                .unwrap_or(LEVELS - 1) as u8
+        })
+        .collect()
+}
+
+/// Level of an 8-bit value
+fn level(value: f64) -> usize {
+    (value.round().max(0.0) as usize).min(LEVELS - 1)
+}
+
+/// Fail unless a raster holds 8-bit unsigned samples
+fn require_8bit(raster: &Raster) -> TiffResult<()> {
+    if raster.layout.bits != 8 || raster.layout.format != SampleFormat::Unsigned {
+        return Err(TiffError::GenericError(format!(
+            "{} holds {}-bit samples; histogram matching works on 8-bit imagery (see --render)",
+            raster.path, raster.layout.bits)));
+    }
+    Ok(())
+}
+
+/// Match the histograms of an image to those of a reference image
+///
+/// Images of three or more bands are matched band by band on their first
+/// three bands and written as RGB, others on their first band. NoData
+/// pixels are written as 0, which matched levels never take.
+///
+/// # Arguments
+/// * `input_path` - Image to adjust
+/// * `reference_path` - Image whose colours it should take
+/// * `output_path` - Path of the adjusted image
+/// * `quality` - Quality of lossy image formats, if any
+/// * `logger` - Logger for recording operations
+///
+/// # Returns
+/// Result indicating success or an error
+pub fn match_histograms(input_path: &str, reference_path: &str, output_path: &str,
+                        quality: Option<u8>, logger: &Logger) -> TiffResult<()> {
+    let source = Raster::read(input_path, 3, logger)?;
+    require_8bit(&source)?;
+    let channels = if source.bands.len() >= 3 { 3 } else { 1 };
+    let reference = Raster::read(reference_path, channels, logger)?;
+    require_8bit(&reference)?;
+    if reference.bands.len() < channels {
+        return Err(TiffError::GenericError(format!(
+            "{} has {} band(s), {} are needed to match {}", reference_path, reference.bands.len(), channels, input_path)));
+    }
+
+    // Histograms of the pixels the two images share
+    let mapping = grid_utils::pixel_mapping(&source, &reference)?;
+    let (width, height) = (source.layout.width as usize, source.layout.height as usize);
+    let mut source_counts = vec![vec![0u64; LEVELS]; channels];
+    let mut reference_counts = vec![vec![0u64; LEVELS]; channels];
+    for row in 0..height {
+        for column in 0..width {
+            let (x, y) = mapping.apply(column as f64 + 0.5, row as f64 + 0.5);
+            for band in 0..channels {
+                let value = source.bands[band][row * width + column];
+                if let (false, Some(reference_value)) = (value.is_nan(), reference.nearest(band, x, y)) {
+                    source_counts[band][level(value)] += 1;
+                    reference_counts[band][level(reference_value)] += 1;
+                }
+            }
+        }
+    }
+
+    let overlap = source_counts.iter().map(|counts| counts.iter().sum::<u64>()).min().unwrap_or(0);
+    if overlap >= MIN_OVERLAP_PIXELS {
+        info!("Matching on the {} pixels where {} overlaps {}", overlap, input_path, reference_path);
+    } else {
+        info!("{} barely overlaps {}, matching the whole images", input_path, reference_path);
+        let whole = |raster: &Raster, band: usize| {
+            let mut counts = vec![0u64; LEVELS];
+            raster.bands[band].iter().filter(|value| !value.is_nan()).for_each(|&value| counts[level(value)] += 1);
+            counts
+        };
+        source_counts = (0..channels).map(|band| whole(&source, band)).collect();
+        reference_counts = (0..channels).map(|band| whole(&reference, band)).collect();
+    }
+
+    let lookups: Vec<Vec<u8>> = source_counts.iter().zip(&reference_counts)
+        .map(|(source, reference)| matching_lookup(source, reference))
+        .collect();
+
+    let mut levels = vec![0u8; width * height * channels];
+    for (band, lookup) in lookups.iter().enumerate() {
+        for (index, &value) in source.bands[band].iter().enumerate().filter(|(_, value)| !value.is_nan()) {
+            levels[index * channels + band] = lookup[level(value)].max(1);
+        }
+    }
+
+    let image = if channels == 3 {
+        RgbImage::from_raw(width as u32, height as u32, levels).map(DynamicImage::ImageRgb8)
+    } else {
+        GrayImage::from_raw(width as u32, height as u32, levels).map(DynamicImage::ImageLuma8)
+    }.ok_or_else(|| TiffError::GenericError("Matched image does not fit the raster".to_string()))?;
+    render_utils::save_rendered(&image, output_path, input_path, quality, logger)?;
+
+    info!("Histogram-matched {} to {}, written to {}", input_path, reference_path, output_path);
+    Ok(())
+}
@@ -0,0 +1,187 @@
+//! Extraction across a raster catalog
+//!
+//! Treats a directory of rasters, or an index of them written by `--index`,
+//! as one virtual mosaic: a bounding box is answered by picking the rasters
+//! whose extent overlaps it, sampling each onto a grid laid over the box
+//! and blending them into a single GeoTIFF, with no VRT prepared beforehand.
+//!
+//! The rasters are selected on the extents in their own coordinate systems,
+//! with the box projected into each. A mosaic has one coordinate system,
+//! so when the covering rasters are in several, those of the system most
+//! of them share are used and the others skipped with a warning.
+
+use std::collections::BTreeMap;
+use std::path::Path;
+
+use log::{info, warn};
+
+use crate::coordinate::{Affine, BoundingBox};
+use crate::io::storage;
+use crate::tiff::errors::{TiffError, TiffResult};
+use crate::tiff::proj_definition::ProjDefinition;
+use crate::utils::catalog_utils;
+use crate::utils::index_utils::{self, IndexEntry};
+use crate::utils::logger::Logger;
+use crate::utils::mosaic_utils::{self, BlendMode};
+use crate::utils::warp_utils::{self, WarpTransformer};
+
+/// Extensions of index files
+const INDEX_EXTENSIONS: [&str; 3] = ["geojson", "json", "gpkg"];
+
+/// Rasters picked from a catalog for a bounding box
+#[derive(Debug, Clone, PartialEq)]
+pub struct Selection {
+    /// EPSG code of the rasters and the output
+    pub epsg: u32,
+    /// The bounding box in that system as (min_x, min_y, max_x, max_y)
+    pub window: (f64, f64, f64, f64),
+    /// Paths of the rasters, in catalog order
+    pub paths: Vec<String>,
+}
+
+/// Whether a path names a catalog rather than a single raster
+///
+/// A directory is a catalog, and so is a file with an index extension
+/// (`.geojson`, `.json` or `.gpkg`).
+pub fn is_catalog(path: &str) -> bool {
+    if !storage::is_remote(path) && Path::new(path).is_dir() {
+        return true;
+    }
+    Path::new(path).extension()
+        .and_then(|extension| extension.to_str())
+        .is_some_and(|extension| INDEX_EXTENSIONS.contains(&extension.to_lowercase().as_str()))
+}
+
+/// Locate a raster listed in an index
+///
+/// Relative paths are taken from the working directory when the raster is
+/// found there, as `--index` writes them, and else from the directory of
+/// the index.
+fn resolve_path(path: &str, index_path: &str) -> String {
+    if storage::is_remote(path) || Path::new(path).is_absolute() || Path::new(path).exists() {
+        return path.to_string();
+    }
+    match Path::new(index_path).parent() {
+        Some(dir) if !dir.as_os_str().is_empty() => dir.join(path).to_string_lossy().into_owned(),
+        _ => path.to_string(),
+    }
+}
+
+/// List the rasters of a catalog with their coordinate systems and extents
+///
+/// A directory is scanned; an index is read, and rasters it lists without
+/// an EPSG code or extent have their header read for them.
+///
+/// # Arguments
+/// * `catalog` - Directory of rasters or index file
+/// * `logger` - Logger for reading raster headers
+///
+/// # Returns
+/// The rasters in catalog order
+pub fn load_catalog(catalog: &str, logger: &Logger) -> TiffResult<Vec<IndexEntry>> {
+    if !storage::is_remote(catalog) && Path::new(catalog).is_dir() {
+        let paths = catalog_utils::collect_rasters(catalog)?;
+        return Ok(catalog_utils::scan(&paths, logger).into_iter()
+            .map(|record| IndexEntry { path: record.path, epsg: record.epsg, bounds: record.bounds })
+            .collect());
+    }
+
+    let mut entries = index_utils::read_index(catalog)?;
+    for entry in entries.iter_mut() {
+        entry.path = resolve_path(&entry.path, catalog);
+        if entry.epsg.is_none() || entry.bounds.is_none() {
+            match catalog_utils::read_record(&entry.path, logger) {
+                Ok(record) => {
+                    entry.epsg = entry.epsg.or(record.epsg);
+                    entry.bounds = entry.bounds.or(record.bounds);
+                },
+                Err(e) => warn!("Skipping {}: {}", entry.path, e),
+            }
+        }
+    }
+    Ok(entries)
+}
+
+/// Pick the rasters of a catalog that overlap a bounding box
+///
+/// # Arguments
+/// * `entries` - Rasters of the catalog
+/// * `bbox` - Area to extract
+/// * `crs` - Coordinate system of the bounding box
+///
+/// # Returns
+/// The covering rasters of one coordinate system, or None if no raster overlaps the box
+pub fn select_covering(entries: &[IndexEntry], bbox: &BoundingBox, crs: &ProjDefinition) -> TiffResult<Option<Selection>> {
+    // The box projected into each coordinate system of the catalog
+    let box_transform = Affine::new(bbox.min_x, bbox.max_x - bbox.min_x, bbox.max_y, bbox.min_y - bbox.max_y);
+    let mut windows: BTreeMap<u32, Option<(f64, f64, f64, f64)>> = BTreeMap::new();
+    let mut covering: BTreeMap<u32, Vec<String>> = BTreeMap::new();
+    let mut order = Vec::new();
+    for entry in entries {
+        let (Some(epsg), Some((min_x, min_y, max_x, max_y))) = (entry.epsg, entry.bounds) else {
+            warn!("Skipping {}: its coordinate system or extent is unknown", entry.path);
+            continue;
+        };
+        let window = *windows.entry(epsg).or_insert_with(|| {
+            let target = ProjDefinition::from_epsg(epsg)?;
+            let transformer = WarpTransformer::new(crs, &target).ok()?;
+            let extent = warp_utils::target_extent(&transformer, &box_transform, 1, 1)?;
+            Some((extent.min_x, extent.min_y, extent.max_x, extent.max_y))
+        });
+        let Some(window) = window else {
+            warn!("Skipping {}: the bounding box cannot be projected into EPSG:{}", entry.path, epsg);
+            continue;
+        };
+        if min_x < window.2 && max_x > window.0 && min_y < window.3 && max_y > window.1 {
+            if !covering.contains_key(&epsg) {
+                order.push(epsg);
+            }
+            covering.entry(epsg).or_default().push(entry.path.clone());
+        }
+    }
+
+    // The system most covering rasters share, the first one met on a tie
+    let Some(epsg) = order.iter().copied().reduce(|best, epsg| {
+        if covering[&epsg].len() > covering[&best].len() { epsg } else { best }
+    }) else {
+        return Ok(None);
+    };
+    for other in order.iter().filter(|other| **other != epsg) {
+        warn!("Skipping {} covering raster(s) in EPSG:{}; the mosaic is in EPSG:{}",
+              covering[other].len(), other, epsg);
+    }
+
+    let window = windows[&epsg].ok_or_else(|| TiffError::GenericError(format!(
+        "The bounding box cannot be projected into EPSG:{}", epsg)))?;
+    Ok(Some(Selection { epsg, window, paths: covering.remove(&epsg).unwrap_or_default() }))
+}
+
+/// Extract a bounding box from a catalog into one GeoTIFF
+///
+/// # Arguments
+/// * `catalog` - Directory of rasters or index file
+/// * `bbox` - Area to extract
+/// * `crs` - Coordinate system of the bounding box
+/// * `output_path` - Path of the GeoTIFF to write
+/// * `mode` - How overlapping rasters are combined
+/// * `logger` - Logger for recording operations
+///
+/// # Returns
+/// The number of rasters the output was assembled from
+pub fn extract_from_catalog(catalog: &str, bbox: &BoundingBox, crs: &ProjDefinition, output_path: &str,
+                            mode: BlendMode, logger: &Logger) -> TiffResult<usize> {
+    let entries = load_catalog(catalog, logger)?;
+    info!("Catalog {} lists {} raster(s)", catalog, entries.len());
+
+    let selection = select_covering(&entries, bbox, crs)?
+        .ok_or_else(|| TiffError::GenericError(format!(
+            "No raster in {} overlaps the bounding box {},{},{},{}",
+            catalog, bbox.min_x, bbox.min_y, bbox.max_x, bbox.max_y)))?;
+    info!("{} raster(s) in EPSG:{} cover the window {:?}", selection.paths.len(), selection.epsg, selection.window);
+    for path in &selection.paths {
+        info!("  {}", path);
+    }
+
+    mosaic_utils::mosaic_window(&selection.paths, selection.window, output_path, mode, logger)?;
+    Ok(selection.paths.len())
+}
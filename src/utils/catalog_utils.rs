@@ -0,0 +1,273 @@
+//! Raster catalog scanning
+//!
+//! Describes many rasters from their headers alone: only the TIFF header
+//! and the IFD chain are read, never a block of pixels, so a directory
+//! tree of large files is scanned in about the time it takes to list it.
+//! Each file becomes a `RasterRecord` of the facts catalogs filter on,
+//! which can be printed as a table or as JSON.
+
+use std::fs;
+use std::io::BufReader;
+use std::path::Path;
+
+use log::{debug, info, warn};
+
+use crate::io::storage;
+use crate::tiff::constants::tags;
+use crate::tiff::errors::{TiffError, TiffResult};
+use crate::tiff::TiffReader;
+use crate::utils::acquisition_utils::{self, Timestamp};
+use crate::utils::logger::Logger;
+use crate::utils::tensor_utils::escape_json;
+use crate::utils::footprint_utils::{self, Ring};
+use crate::utils::{info_utils, sample_utils, tiff_extraction_utils};
+
+/// File extensions scanned in directories, compared case-insensitively
+const RASTER_EXTENSIONS: [&str; 3] = ["tif", "tiff", "gtiff"];
+
+/// Header facts of one raster
+#[derive(Debug, Clone, PartialEq)]
+pub struct RasterRecord {
+    /// Path the raster was read from
+    pub path: String,
+    /// Size of the file in bytes, None for object storage
+    pub file_size: Option<u64>,
+    /// Width in pixels
+    pub width: u32,
+    /// Height in pixels
+    pub height: u32,
+    /// Number of bands
+    pub bands: usize,
+    /// GDAL data type name, e.g. `Byte` or `Float32`
+    pub data_type: &'static str,
+    /// Compression in lower case, `none` when uncompressed
+    pub compression: String,
+    /// EPSG code of the coordinate system, if it has one
+    pub epsg: Option<u32>,
+    /// Ground size of a pixel along x, in CRS units
+    pub resolution: Option<f64>,
+    /// Extent as (min x, min y, max x, max y) in the raster's CRS
+    pub bounds: Option<(f64, f64, f64, f64)>,
+    /// Edge of the raster in WGS84 longitude/latitude, when its CRS can
+    /// be unprojected
+    pub footprint: Option<Ring>,
+    /// NoData value
+    pub nodata: Option<f64>,
+    /// Number of reduced-resolution images
+    pub overviews: usize,
+    /// Whether the pixels are stored in tiles rather than strips
+    pub tiled: bool,
+    /// Whether the file is a BigTIFF
+    pub big_tiff: bool,
+    /// Best estimate of when the image was acquired
+    pub acquired: Option<Timestamp>,
+}
+
+/// Collect the rasters under a path
+///
+/// A file, or an object storage URI, is returned as it is; a directory is
+/// walked recursively for files with a TIFF extension.
+///
+/// # Arguments
+/// * `root` - File or directory to scan
+///
+/// # Returns
+/// The raster paths, sorted so scans are repeatable
+pub fn collect_rasters(root: &str) -> TiffResult<Vec<String>> {
+    if storage::is_remote(root) || Path::new(root).is_file() {
+        return Ok(vec![root.to_string()]);
+    }
+    if !Path::new(root).is_dir() {
+        return Err(TiffError::GenericError(format!("{} is neither a file nor a directory", root)));
+    }
+
+    let mut paths = Vec::new();
+    let mut pending = vec![Path::new(root).to_path_buf()];
+    while let Some(dir) = pending.pop() {
+        for entry in fs::read_dir(&dir)? {
+            let path = entry?.path();
+            if path.is_dir() {
+                pending.push(path);
+            } else if path.extension().and_then(|extension| extension.to_str())
+                .is_some_and(|extension| RASTER_EXTENSIONS.contains(&extension.to_lowercase().as_str())) {
+                paths.push(path.to_string_lossy().into_owned());
+            }
+        }
+    }
+    paths.sort();
+    debug!("Found {} raster(s) under {}", paths.len(), root);
+    Ok(paths)
+}
+
+/// Describe a raster from its header
+///
+/// # Arguments
+/// * `path` - Path or object storage URI of the raster
+/// * `logger` - Logger for the reader
+///
+/// # Returns
+/// The record, or an error if the file is not a readable TIFF
+pub fn read_record(path: &str, logger: &Logger) -> TiffResult<RasterRecord> {
+    let mut reader = TiffReader::new(logger);
+    let tiff = reader.load(path)?;
+    let ifd = tiff.main_ifd()
+        .ok_or_else(|| TiffError::GenericError(format!("No IFDs found in {}", path)))?;
+    let mut file = BufReader::new(storage::open(path)?);
+    let layout = sample_utils::read_layout(&reader, ifd, &mut file)?;
+
+    let georeferencing = info_utils::read_georeferencing(&tiff, ifd, &reader, path);
+    let epsg = georeferencing.as_ref()
+        .and_then(|georeferencing| georeferencing.definition.as_ref())
+        .and_then(|definition| definition.epsg);
+    let footprint = georeferencing.as_ref().and_then(|georeferencing| {
+        footprint_utils::rectangle_ring(layout.width as f64, layout.height as f64).into_iter()
+            .map(|(column, row)| footprint_utils::to_wgs84(georeferencing, georeferencing.geotransform.apply(column, row)))
+            .collect::<Option<Ring>>()
+    });
+    let geotransform = georeferencing.map(|georeferencing| georeferencing.geotransform);
+    let bounds = geotransform.map(|geotransform| {
+        let (width, height) = (layout.width as f64, layout.height as f64);
+        let corners = [(0.0, 0.0), (width, 0.0), (0.0, height), (width, height)]
+            .map(|(column, row)| geotransform.apply(column, row));
+        corners.iter().fold((f64::MAX, f64::MAX, f64::MIN, f64::MIN), |(min_x, min_y, max_x, max_y), (x, y)| {
+            (min_x.min(*x), min_y.min(*y), max_x.max(*x), max_y.max(*y))
+        })
+    });
+
+    let nodata = if ifd.has_tag(tags::GDAL_NODATA) {
+        tiff_extraction_utils::extract_nodata_value(ifd, &reader).trim().parse::<f64>().ok()
+    } else {
+        None
+    };
+    let compression = info_utils::gdal_compression_name(ifd.get_tag_value(tags::COMPRESSION).unwrap_or(1))
+        .unwrap_or("NONE").to_lowercase();
+
+    Ok(RasterRecord {
+        path: path.to_string(),
+        file_size: fs::metadata(path).ok().map(|metadata| metadata.len()),
+        width: layout.width,
+        height: layout.height,
+        bands: layout.bands,
+        data_type: info_utils::gdal_type_name(&layout),
+        compression,
+        epsg,
+        resolution: geotransform.map(|geotransform| geotransform.pixel_width.hypot(geotransform.column_rotation)),
+        bounds,
+        footprint,
+        nodata,
+        overviews: info_utils::overview_sizes(&tiff, ifd).len(),
+        tiled: ifd.has_tag(tags::TILE_OFFSETS),
+        big_tiff: tiff.is_big_tiff,
+        acquired: acquisition_utils::read_acquisition(&reader, ifd).acquired(),
+    })
+}
+
+/// Describe many rasters, skipping those that cannot be read
+///
+/// # Arguments
+/// * `paths` - Rasters to describe
+/// * `logger` - Logger for the readers
+///
+/// # Returns
+/// The records of the readable rasters, in the order given
+pub fn scan(paths: &[String], logger: &Logger) -> Vec<RasterRecord> {
+    let records: Vec<RasterRecord> = paths.iter()
+        .filter_map(|path| match read_record(path, logger) {
+            Ok(record) => Some(record),
+            Err(e) => {
+                warn!("Skipping {}: {}", path, e);
+                None
+            },
+        })
+        .collect();
+    info!("Scanned the headers of {} of {} raster(s)", records.len(), paths.len());
+    records
+}
+
+/// Format records as an aligned text table
+///
+/// # Arguments
+/// * `records` - Rasters to list
+///
+/// # Returns
+/// The table with a header line, one raster per line after it
+pub fn format_table(records: &[RasterRecord]) -> String {
+    let optional = |value: Option<String>| value.unwrap_or_else(|| "-".to_string());
+    let header = ["PATH", "SIZE", "BANDS", "TYPE", "COMPRESSION", "EPSG", "RESOLUTION", "ACQUIRED"];
+    let rows: Vec<[String; 8]> = records.iter()
+        .map(|record| [
+            record.path.clone(),
+            format!("{}x{}", record.width, record.height),
+            record.bands.to_string(),
+            record.data_type.to_string(),
+            record.compression.clone(),
+            optional(record.epsg.map(|epsg| epsg.to_string())),
+            optional(record.resolution.map(short_number)),
+            optional(record.acquired.map(|time| time.to_string())),
+        ])
+        .collect();
+
+    let widths: Vec<usize> = (0..header.len())
+        .map(|column| rows.iter().map(|row| row[column].len()).chain([header[column].len()]).max().unwrap_or(0))
+        .collect();
+    let line = |cells: Vec<&str>| cells.iter().zip(&widths)
+        .map(|(cell, width)| format!("{:<width$}", cell, width = width))
+        .collect::<Vec<_>>().join("  ").trim_end().to_string();
+
+    std::iter::once(line(header.to_vec()))
+        .chain(rows.iter().map(|row| line(row.iter().map(String::as_str).collect())))
+        .collect::<Vec<_>>().join("\n")
+}
+
+/// A number to at most six decimals, in exponent form if that rounds it to zero
+fn short_number(value: f64) -> String {
+    let fixed = format!("{:.6}", value);
+    let fixed = fixed.trim_end_matches('0').trim_end_matches('.');
+    if fixed == "0" && value != 0.0 {
+        format!("{:.3e}", value)
+    } else {
+        fixed.to_string()
+    }
+}
+
+/// Format records as a JSON array
+///
+/// # Arguments
+/// * `records` - Rasters to list
+///
+/// # Returns
+/// The JSON text, one object per raster
+pub fn records_json(records: &[RasterRecord]) -> String {
+    let objects: Vec<String> = records.iter().map(|record| format!("  {}", record_json(record))).collect();
+    if objects.is_empty() {
+        "[]\n".to_string()
+    } else {
+        format!("[\n{}\n]\n", objects.join(",\n"))
+    }
+}
+
+/// One record as a single-line JSON object
+pub(crate) fn record_json(record: &RasterRecord) -> String {
+    let number = |value: Option<f64>| value.filter(|value| value.is_finite())
+        .map_or("null".to_string(), |value| value.to_string());
+    let fields = [
+        ("path", format!("\"{}\"", escape_json(&record.path))),
+        ("file_size", record.file_size.map_or("null".to_string(), |size| size.to_string())),
+        ("width", record.width.to_string()),
+        ("height", record.height.to_string()),
+        ("bands", record.bands.to_string()),
+        ("type", format!("\"{}\"", record.data_type)),
+        ("compression", format!("\"{}\"", record.compression)),
+        ("epsg", record.epsg.map_or("null".to_string(), |epsg| epsg.to_string())),
+        ("resolution", number(record.resolution)),
+        ("bounds", record.bounds.map_or("null".to_string(), |(min_x, min_y, max_x, max_y)| format!(
+            "[{}, {}, {}, {}]", number(Some(min_x)), number(Some(min_y)), number(Some(max_x)), number(Some(max_y))))),
+        ("nodata", number(record.nodata)),
+        ("overviews", record.overviews.to_string()),
+        ("tiled", record.tiled.to_string()),
+        ("bigtiff", record.big_tiff.to_string()),
+        ("acquired", record.acquired.map_or("null".to_string(), |time| format!("\"{}\"", time))),
+    ];
+    let body: Vec<String> = fields.iter().map(|(key, value)| format!("\"{}\": {}", key, value)).collect();
+    format!("{{{}}}", body.join(", "))
+}
@@ -0,0 +1,111 @@
+//! Geohash and H3 cell regions
+//!
+//! Processing jobs partitioned on a discrete global grid name their work
+//! units by cell ID. This module turns a geohash ("u33dc0") or an H3
+//! cell index ("8928308280fffff") into the WGS84 bounding box of the cell,
+//! which is then extracted like any other bounding box.
+
+use h3o::CellIndex;
+use log::info;
+
+use crate::coordinate::BoundingBox;
+use crate::tiff::errors::{TiffError, TiffResult};
+
+/// Characters of the geohash alphabet, five bits each
+const GEOHASH_ALPHABET: &str = "0123456789bcdefghjkmnpqrstuvwxyz";
+
+/// Longest geohash accepted, finer than a centimeter
+const MAX_GEOHASH_LENGTH: usize = 12;
+
+/// Bounding box of a geohash cell
+///
+/// # Arguments
+/// * `hash` - Geohash of 1 to 12 characters
+///
+/// # Returns
+/// The cell in WGS84 longitude and latitude, or an error for characters
+/// outside the geohash alphabet
+pub fn geohash_bounds(hash: &str) -> TiffResult<BoundingBox> {
+    let hash = hash.trim().to_lowercase();
+    if hash.is_empty() || hash.len() > MAX_GEOHASH_LENGTH {
+        return Err(TiffError::GenericError(format!(
+            "Geohash '{}' must have 1 to {} characters", hash, MAX_GEOHASH_LENGTH)));
+    }
+
+    let (mut lon, mut lat) = ((-180.0, 180.0), (-90.0, 90.0));
+    let mut even = true;
+    for c in hash.chars() {
+        let bits = GEOHASH_ALPHABET.find(c).ok_or_else(|| TiffError::GenericError(format!(
+            "Geohash '{}' holds '{}', which is not a geohash character", hash, c)))?;
+        // Bits alternate between longitude and latitude, longitude first
+        for shift in (0..5).rev() {
+            let range: &mut (f64, f64) = if even { &mut lon } else { &mut lat };
+            let middle = (range.0 + range.1) / 2.0;
+            if bits >> shift & 1 == 1 {
+                range.0 = middle;
+            } else {
+                range.1 = middle;
+            }
+            even = !even;
+        }
+    }
+
+    Ok(BoundingBox::new_with_crs(lon.0, lat.0, lon.1, lat.1, 4326))
+}
+
+/// Bounding box of an H3 cell
+///
+/// # Arguments
+/// * `index` - Cell index as 15 hexadecimal digits
+///
+/// # Returns
+/// The envelope of the cell's boundary in WGS84, or an error for invalid
+/// indexes and cells crossing the antimeridian
+pub fn h3_bounds(index: &str) -> TiffResult<BoundingBox> {
+    let cell: CellIndex = index.trim().parse()
+        .map_err(|e| TiffError::GenericError(format!("Invalid H3 cell '{}': {}", index, e)))?;
+
+    let boundary = cell.boundary();
+    let (mut min_lon, mut min_lat) = (f64::MAX, f64::MAX);
+    let (mut max_lon, mut max_lat) = (f64::MIN, f64::MIN);
+    for vertex in boundary.iter() {
+        min_lon = min_lon.min(vertex.lng());
+        max_lon = max_lon.max(vertex.lng());
+        min_lat = min_lat.min(vertex.lat());
+        max_lat = max_lat.max(vertex.lat());
+    }
+    if max_lon - min_lon > 180.0 {
+        return Err(TiffError::GenericError(format!("H3 cell {} crosses the antimeridian", index)));
+    }
+
+    info!("H3 cell {} at resolution {} spans {},{} to {},{}",
+          index, u8::from(cell.resolution()), min_lon, min_lat, max_lon, max_lat);
+    Ok(BoundingBox::new_with_crs(min_lon, min_lat, max_lon, max_lat, 4326))
+}
+
+/// Bounding box string of a geohash or H3 cell
+///
+/// Fifteen hexadecimal digits starting with 8 are read as an H3 index,
+/// anything else as a geohash; a `geohash:` or `h3:` prefix settles it.
+///
+/// # Arguments
+/// * `cell` - The cell ID
+///
+/// # Returns
+/// "minlon,minlat,maxlon,maxlat" in WGS84, or an error naming the problem
+pub fn cell_bbox(cell: &str) -> TiffResult<String> {
+    let cell = cell.trim();
+    let bounds = if let Some(hash) = cell.strip_prefix("geohash:") {
+        geohash_bounds(hash)?
+    } else if let Some(index) = cell.strip_prefix("h3:") {
+        h3_bounds(index)?
+    } else if cell.len() == 15 && cell.starts_with('8') && cell.chars().all(|c| c.is_ascii_hexdigit()) {
+        h3_bounds(cell)?
+    } else {
+        geohash_bounds(cell)?
+    };
+
+    let bbox = format!("{},{},{},{}", bounds.min_x, bounds.min_y, bounds.max_x, bounds.max_y);
+    info!("Cell {} covers {}", cell, bbox);
+    Ok(bbox)
+}
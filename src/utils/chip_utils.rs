@@ -0,0 +1,378 @@
+//! Image chip (patch) export utilities
+//!
+//! This module cuts a raster into fixed-size patches for deep-learning
+//! dataset preparation. Each chip is written as PNG, WebP, AVIF, NPY, NPZ or
+//! safetensors, optionally together with the matching patch of an aligned
+//! label raster, and an index CSV lists every chip with its pixel window and
+//! map bounds. Labels of lossy image chips are kept as PNG.
+//! Tensor chips can be normalized to float32 using statistics of the whole
+//! raster, so every chip of a dataset shares the same scaling.
+//! Written chips are journaled, so an interrupted export can be resumed
+//! without cutting them again.
+
+use std::fs::{self, File};
+use std::io::{BufWriter, Write};
+use std::path::Path;
+use image::{DynamicImage, GenericImageView};
+use log::{info, warn};
+
+use crate::coordinate::Affine;
+use crate::tiff::errors::{TiffError, TiffResult};
+use crate::tiff::TiffReader;
+use crate::extractor::{ImageExtractor, OutputFormat, Region};
+use crate::utils::logger::Logger;
+use crate::utils::{atomic_utils, image_extraction_utils};
+use crate::utils::journal_utils::{self, Journal};
+use crate::utils::normalize_utils::{self, BandStatistics, NormalizationMethod};
+use crate::utils::tensor_utils::{self, NamedTensor};
+
+/// Chip formats written as images rather than tensors
+const IMAGE_FORMATS: [&str; 3] = ["png", "webp", "avif"];
+
+/// Chips written between journal commits
+const JOURNAL_BATCH: usize = 64;
+
+/// Options controlling chip generation
+#[derive(Debug, Clone)]
+pub struct ChipOptions {
+    /// Edge length of a (square) chip in pixels
+    pub chip_size: u32,
+    /// Number of pixels shared by neighbouring chips
+    pub overlap: u32,
+    /// Output format for chips ("png", "webp", "avif", "npy", "npz" or "safetensors")
+    pub format: String,
+    /// Optional label raster aligned with the input
+    pub label_path: Option<String>,
+    /// Normalize image chips to float32 (tensor formats only)
+    pub normalize: Option<NormalizationMethod>,
+    /// Quality of WebP and AVIF chips; WebP chips are lossless without one
+    pub quality: Option<u8>,
+    /// Keep the chips an interrupted export of the same input already wrote
+    pub resume: bool,
+}
+
+impl Default for ChipOptions {
+    fn default() -> Self {
+        ChipOptions {
+            chip_size: 256,
+            overlap: 0,
+            format: "png".to_string(),
+            label_path: None,
+            normalize: None,
+            quality: None,
+            resume: false,
+        }
+    }
+}
+
+/// Compute the chip start offsets along one axis
+///
+/// Chips are placed every `chip_size - overlap` pixels. When the last chip
+/// would run past the edge, an extra chip aligned with the edge is added so
+/// every pixel is covered and all chips keep the full size.
+///
+/// # Arguments
+/// * `length` - Raster size along the axis
+/// * `chip_size` - Chip size along the axis
+/// * `stride` - Step between chip origins
+///
+/// # Returns
+/// The chip start offsets
+pub fn chip_offsets(length: u32, chip_size: u32, stride: u32) -> Vec<u32> {
+    if length <= chip_size {
+        return vec![0];
+    }
+
+    let mut offsets: Vec<u32> = (0..=(length - chip_size)).step_by(stride as usize).collect();
+    if let Some(&last) = offsets.last() {
+        if last + chip_size < length {
+            offsets.push(length - chip_size);
+        }
+    }
+
+    offsets
+}
+
+/// Cut a chip out of an image, padding with zeros beyond the image edge
+///
+/// # Arguments
+/// * `image` - The source image
+/// * `region` - The chip window
+///
+/// # Returns
+/// The chip image with exactly the region size
+fn crop_padded(image: &DynamicImage, region: &Region) -> DynamicImage {
+    let (width, height) = image.dimensions();
+    let crop_width = region.width.min(width - region.x);
+    let crop_height = region.height.min(height - region.y);
+    let cropped = image.crop_imm(region.x, region.y, crop_width, crop_height);
+
+    if crop_width == region.width && crop_height == region.height {
+        return cropped;
+    }
+
+    // Raster smaller than a chip: place it in the top-left corner of a blank chip
+    let mut padded = match image {
+        DynamicImage::ImageLuma8(_) => DynamicImage::new_luma8(region.width, region.height),
+        _ => DynamicImage::new_rgb8(region.width, region.height),
+    };
+    image::imageops::overlay(&mut padded, &cropped, 0, 0);
+    padded
+}
+
+/// Widen 8-bit chip values for normalization
+fn to_f64(values: &[u8]) -> Vec<f64> {
+    values.iter().map(|&value| value as f64).collect()
+}
+
+/// Save a chip in the requested format
+///
+/// Tensor formats store the chip as `data` with shape (h, w) for grayscale
+/// or (h, w, 3) for RGB chips. With a normalization, values are written as
+/// float32, otherwise as unsigned 8-bit.
+///
+/// # Arguments
+/// * `chip` - The chip image (grayscale or RGB)
+/// * `path` - Output path
+/// * `format` - "png", "webp", "avif", "npy", "npz" or "safetensors"
+/// * `quality` - Quality of lossy image formats, if any
+/// * `normalization` - Optional normalization method and raster statistics
+///
+/// # Returns
+/// Result indicating success or an error
+fn save_chip(
+    chip: &DynamicImage,
+    path: &Path,
+    format: &str,
+    quality: Option<u8>,
+    normalization: Option<(NormalizationMethod, &[BandStatistics])>
+) -> TiffResult<()> {
+    let path_str = path.to_string_lossy();
+
+    if IMAGE_FORMATS.contains(&format) {
+        return OutputFormat::from_name(format)?.with_quality(quality).write_image(chip, &path_str)
+            .map_err(|e| TiffError::GenericError(format!("Failed to save chip {}: {}", path_str, e)));
+    }
+
+    let (shape, values) = match chip {
+        DynamicImage::ImageLuma8(gray) => (
+            vec![gray.height() as usize, gray.width() as usize],
+            gray.as_raw().clone()),
+        _ => {
+            let rgb = chip.to_rgb8();
+            (vec![rgb.height() as usize, rgb.width() as usize, 3], rgb.into_raw())
+        }
+    };
+
+    let tensor = match normalization {
+        Some((method, stats)) => NamedTensor::from_f32(
+            "data", &shape, &normalize_utils::normalize_bands(&to_f64(&values), stats, method, None)),
+        None => NamedTensor::from_u8("data", &shape, &values),
+    };
+
+    tensor_utils::save_tensors(&path_str, format, &[tensor], &[])
+}
+
+/// Read an image and reduce it to its natural band layout
+///
+/// Single-band rasters are returned as grayscale, everything else as RGB.
+///
+/// # Arguments
+/// * `path` - The raster to read
+/// * `logger` - Logger for recording operations
+///
+/// # Returns
+/// The image, and the geotransform if the raster is georeferenced
+fn read_raster(path: &str, logger: &Logger) -> TiffResult<(DynamicImage, Option<Affine>)> {
+    let mut reader = TiffReader::new(logger);
+    let tiff = reader.load(path)?;
+    let ifd = tiff.main_ifd()
+        .ok_or_else(|| TiffError::GenericError(format!("No IFDs found in {}", path)))?;
+
+    let samples_per_pixel = ifd.get_samples_per_pixel();
+    let geotransform = reader.get_byte_order_handler().and_then(|handler| {
+        let file_path = reader.get_file_path().unwrap_or(path);
+        image_extraction_utils::calculate_geotransform(ifd, handler, file_path).ok()
+    });
+
+    let mut extractor = ImageExtractor::new(logger);
+    let image = extractor.extract_image(path, None)?;
+
+    let image = if samples_per_pixel == 1 {
+        DynamicImage::ImageLuma8(image.to_luma8())
+    } else {
+        image
+    };
+
+    Ok((image, geotransform))
+}
+
+/// Cut a raster into chips and write them with an index CSV
+///
+/// # Arguments
+/// * `input_path` - The raster to cut
+/// * `output_dir` - Directory receiving the chips and `index.csv`
+/// * `options` - Chip options
+/// * `logger` - Logger for recording operations
+///
+/// # Returns
+/// The number of chips written, or an error
+pub fn export_chips(
+    input_path: &str,
+    output_dir: &str,
+    options: &ChipOptions,
+    logger: &Logger
+) -> TiffResult<usize> {
+    if options.chip_size == 0 {
+        return Err(TiffError::GenericError("Chip size must be greater than zero".to_string()));
+    }
+    if options.overlap >= options.chip_size {
+        return Err(TiffError::GenericError(format!(
+            "Chip overlap ({}) must be smaller than the chip size ({})",
+            options.overlap, options.chip_size)));
+    }
+
+    let format = options.format.to_lowercase();
+    if !IMAGE_FORMATS.contains(&format.as_str()) && !["npy", "npz", "safetensors"].contains(&format.as_str()) {
+        return Err(TiffError::GenericError(format!("Unsupported chip format: {}", options.format)));
+    }
+    if options.normalize.is_some() && IMAGE_FORMATS.contains(&format.as_str()) {
+        return Err(TiffError::GenericError(
+            "Normalized chips need a tensor format (npy, npz or safetensors)".to_string()));
+    }
+
+    // Class values of labels must survive encoding
+    let label_format = match OutputFormat::from_name(&format) {
+        Ok(image_format) if image_format.with_quality(options.quality).is_lossy() => {
+            warn!("{} chips are lossy, writing labels as PNG", format);
+            "png".to_string()
+        },
+        _ => format.clone(),
+    };
+
+    info!("Cutting {} into {}x{} chips (overlap {}) as {}",
+          input_path, options.chip_size, options.chip_size, options.overlap, format);
+
+    let (image, geotransform) = read_raster(input_path, logger)?;
+    let (width, height) = image.dimensions();
+
+    // Load and validate the label raster
+    let label = match &options.label_path {
+        Some(label_path) => {
+            let (label_image, _) = read_raster(label_path, logger)?;
+            if label_image.dimensions() != (width, height) {
+                return Err(TiffError::GenericError(format!(
+                    "Label raster is {}x{} but input is {}x{}",
+                    label_image.width(), label_image.height(), width, height)));
+            }
+            Some(DynamicImage::ImageLuma8(label_image.to_luma8()))
+        },
+        None => None,
+    };
+
+    if geotransform.is_none() {
+        warn!("Input is not georeferenced, chip bounds will be left empty");
+    }
+
+    // Statistics over the whole raster keep the scaling identical across chips
+    let statistics = options.normalize.map(|method| {
+        let (values, band_count) = match &image {
+            DynamicImage::ImageLuma8(gray) => (gray.as_raw().clone(), 1),
+            _ => (image.to_rgb8().into_raw(), 3),
+        };
+        (method, normalize_utils::compute_band_statistics(&to_f64(&values), band_count, None))
+    });
+
+    // Prepare output directories
+    let output_dir = Path::new(output_dir);
+    let image_dir = output_dir.join("images");
+    fs::create_dir_all(&image_dir)?;
+    let label_dir = output_dir.join("labels");
+    if label.is_some() {
+        fs::create_dir_all(&label_dir)?;
+    }
+
+    if let Some((method, stats)) = &statistics {
+        fs::write(output_dir.join("normalization.json"), normalize_utils::statistics_to_json(*method, stats))?;
+        info!("Normalizing chips with {} statistics of the full raster", method.name());
+    }
+
+    // The job covers everything that changes the contents of a chip
+    let job = format!("chips {} label {} size {} overlap {} format {} normalize {}",
+                      journal_utils::file_fingerprint(input_path)?,
+                      match &options.label_path {
+                          Some(label_path) => journal_utils::file_fingerprint(label_path)?,
+                          None => "none".to_string(),
+                      },
+                      options.chip_size, options.overlap, format,
+                      options.normalize.map_or("none", |method| method.name()));
+    let mut journal = Journal::open(&output_dir.join("chips.journal"), &job, options.resume)?;
+    let mut resumed = 0;
+
+    let index_path = output_dir.join("index.csv");
+    let index_partial = atomic_utils::partial_path(&index_path);
+    let mut index = BufWriter::new(File::create(&index_partial)?);
+    writeln!(index, "chip_id,image_path,label_path,x,y,width,height,min_x,min_y,max_x,max_y")?;
+
+    let stride = options.chip_size - options.overlap;
+    let mut chip_count = 0;
+
+    for y in chip_offsets(height, options.chip_size, stride) {
+        for x in chip_offsets(width, options.chip_size, stride) {
+            let region = Region::new(x, y, options.chip_size, options.chip_size);
+            let chip_id = format!("chip_{:05}_{:05}", y, x);
+            let file_name = format!("{}.{}", chip_id, format);
+
+            let label_name = format!("{}.{}", chip_id, label_format);
+
+            // Chips of an interrupted run are kept if their files survived
+            let written = journal.completed(&chip_id).is_some()
+                && image_dir.join(&file_name).exists()
+                && (label.is_none() || label_dir.join(&label_name).exists());
+            if written {
+                resumed += 1;
+            } else {
+                let normalization = statistics.as_ref().map(|(method, stats)| (*method, stats.as_slice()));
+                save_chip(&crop_padded(&image, &region), &image_dir.join(&file_name), &format, options.quality, normalization)?;
+                if let Some(label_image) = &label {
+                    // Labels keep their class values and are never normalized
+                    save_chip(&crop_padded(label_image, &region), &label_dir.join(&label_name), &label_format, None, None)?;
+                }
+                journal.record(chip_id.clone(), file_name.clone());
+                if journal.pending() >= JOURNAL_BATCH {
+                    journal.commit()?;
+                }
+            }
+
+            let label_rel = match &label {
+                Some(_) => format!("labels/{}", label_name),
+                None => String::new(),
+            };
+
+            // Map bounds of the chip window
+            let bounds = match geotransform {
+                Some(gt) => {
+                    let b = gt.translated(x, y).bounds(region.width, region.height);
+                    format!("{},{},{},{}", b.min_x, b.min_y, b.max_x, b.max_y)
+                },
+                None => ",,,".to_string(),
+            };
+
+            writeln!(index, "{},images/{},{},{},{},{},{},{}",
+                     chip_id, file_name, label_rel, x, y, region.width, region.height, bounds)?;
+            chip_count += 1;
+        }
+    }
+
+    index.flush()?;
+    drop(index);
+    atomic_utils::finish(&index_partial, &index_path)?;
+    journal.commit()?;
+    journal.finish()?;
+    if resumed > 0 {
+        info!("Kept {} chips written by the interrupted run", resumed);
+    }
+    info!("Wrote {} chips to {}", chip_count, output_dir.display());
+
+    Ok(chip_count)
+}
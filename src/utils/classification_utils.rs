@@ -0,0 +1,386 @@
+//! Data classification utilities
+//!
+//! Builds value histograms and derives class breaks from them using
+//! equal-interval, quantile or Jenks natural-breaks classification, and
+//! provides the named color schemes used to color the resulting classes.
+
+use image::GrayImage;
+use log::{debug, info};
+
+use crate::tiff::colormap::RgbColor;
+use crate::tiff::errors::{TiffError, TiffResult};
+
+/// Maximum number of bins the Jenks optimization works on
+const JENKS_MAX_BINS: usize = 1024;
+
+/// Histogram of integer pixel values
+#[derive(Debug, Clone)]
+pub struct ValueHistogram {
+    /// Pixel count per value, indexed by value
+    pub counts: Vec<u64>,
+}
+
+impl ValueHistogram {
+    /// Build a histogram from a grayscale image
+    ///
+    /// # Arguments
+    /// * `image` - The image to count
+    /// * `ignore` - Value to leave out (e.g. NoData), if any
+    ///
+    /// # Returns
+    /// The value histogram
+    pub fn from_image(image: &GrayImage, ignore: Option<u8>) -> Self {
+        Self::from_values(image.as_raw(), ignore)
+    }
+
+    /// Build a histogram from 8-bit values
+    ///
+    /// # Arguments
+    /// * `values` - The values to count
+    /// * `ignore` - Value to leave out (e.g. NoData), if any
+    ///
+    /// # Returns
+    /// The value histogram
+    pub fn from_values(values: &[u8], ignore: Option<u8>) -> Self {
+        let mut counts = vec![0u64; 256];
+        for &value in values {
+            if Some(value) != ignore {
+                counts[value as usize] += 1;
+            }
+        }
+        ValueHistogram { counts }
+    }
+
+    /// Build a histogram from native sample values
+    ///
+    /// NaN samples and samples equal to `ignore` are left out. Colormap
+    /// entries hold 16-bit quantities, so the remaining samples must be
+    /// integers in 0..=65535.
+    ///
+    /// # Arguments
+    /// * `values` - The values to count
+    /// * `ignore` - Value to leave out (e.g. NoData), if any
+    ///
+    /// # Returns
+    /// The value histogram or an error for values a colormap cannot hold
+    pub fn from_samples(values: &[f64], ignore: Option<f64>) -> TiffResult<Self> {
+        let mut counts = Vec::new();
+        for &value in values {
+            if value.is_nan() || Some(value) == ignore {
+                continue;
+            }
+            if value.fract() != 0.0 || !(0.0..=u16::MAX as f64).contains(&value) {
+                return Err(TiffError::GenericError(format!(
+                    "Cannot classify value {}: colormaps hold integer values in 0..=65535", value)));
+            }
+
+            let index = value as usize;
+            if index >= counts.len() {
+                counts.resize(index + 1, 0);
+            }
+            counts[index] += 1;
+        }
+        Ok(ValueHistogram { counts })
+    }
+
+    /// Total number of counted pixels
+    pub fn total(&self) -> u64 {
+        self.counts.iter().sum()
+    }
+
+    /// Smallest value with a non-zero count
+    pub fn min(&self) -> Option<u16> {
+        self.counts.iter().position(|&c| c > 0).map(|v| v as u16)
+    }
+
+    /// Largest value with a non-zero count
+    pub fn max(&self) -> Option<u16> {
+        self.counts.iter().rposition(|&c| c > 0).map(|v| v as u16)
+    }
+}
+
+/// How class breaks are derived from the histogram
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum ClassificationMethod {
+    /// Classes of equal value range between minimum and maximum
+    EqualInterval,
+    /// Classes holding roughly the same number of pixels
+    Quantile,
+    /// Jenks natural breaks, minimizing the variance within classes
+    Jenks,
+}
+
+impl ClassificationMethod {
+    /// Parse a classification method name
+    ///
+    /// # Arguments
+    /// * `name` - "equal", "quantile" or "jenks" (case-insensitive)
+    ///
+    /// # Returns
+    /// The classification method or an error
+    pub fn from_string(name: &str) -> TiffResult<Self> {
+        match name.to_lowercase().as_str() {
+            "equal" | "equal-interval" | "equalinterval" => Ok(ClassificationMethod::EqualInterval),
+            "quantile" => Ok(ClassificationMethod::Quantile),
+            "jenks" | "natural" | "natural-breaks" => Ok(ClassificationMethod::Jenks),
+            _ => Err(TiffError::GenericError(format!(
+                "Unknown classification '{}' (expected 'equal', 'quantile' or 'jenks')", name))),
+        }
+    }
+}
+
+/// Named color schemes for generated colormaps
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum ColorScheme {
+    Viridis,
+    Magma,
+    Inferno,
+    Plasma,
+    Cividis,
+    Greys,
+    Blues,
+    Greens,
+    Reds,
+    Spectral,
+    RdYlGn,
+    Terrain,
+}
+
+impl ColorScheme {
+    /// Parse a color scheme name (case-insensitive)
+    ///
+    /// # Arguments
+    /// * `name` - Scheme name, e.g. "viridis" or "rdylgn"
+    ///
+    /// # Returns
+    /// The color scheme or an error
+    pub fn from_string(name: &str) -> TiffResult<Self> {
+        let scheme = match name.to_lowercase().as_str() {
+            "viridis" => ColorScheme::Viridis,
+            "magma" => ColorScheme::Magma,
+            "inferno" => ColorScheme::Inferno,
+            "plasma" => ColorScheme::Plasma,
+            "cividis" => ColorScheme::Cividis,
+            "greys" | "grays" => ColorScheme::Greys,
+            "blues" => ColorScheme::Blues,
+            "greens" => ColorScheme::Greens,
+            "reds" => ColorScheme::Reds,
+            "spectral" => ColorScheme::Spectral,
+            "rdylgn" => ColorScheme::RdYlGn,
+            "terrain" => ColorScheme::Terrain,
+            _ => return Err(TiffError::GenericError(format!(
+                "Unknown color scheme '{}' (expected viridis, magma, inferno, plasma, cividis, \
+                 greys, blues, greens, reds, spectral, rdylgn or terrain)", name))),
+        };
+        Ok(scheme)
+    }
+
+    /// Evenly spaced color stops of the scheme, from low to high values
+    fn stops(&self) -> &'static [[u8; 3]] {
+        match self {
+            ColorScheme::Viridis => &[[68, 1, 84], [72, 40, 120], [62, 73, 137], [49, 104, 142], [38, 130, 142],
+                                      [31, 158, 137], [53, 183, 121], [110, 206, 88], [181, 222, 43], [253, 231, 37]],
+            ColorScheme::Magma => &[[0, 0, 4], [24, 15, 61], [68, 15, 118], [114, 31, 129], [158, 47, 127],
+                                    [205, 64, 113], [241, 96, 93], [253, 150, 104], [254, 202, 141], [252, 253, 191]],
+            ColorScheme::Inferno => &[[0, 0, 4], [27, 12, 65], [74, 12, 107], [120, 28, 109], [165, 44, 96],
+                                      [207, 68, 70], [237, 105, 37], [251, 155, 6], [247, 209, 61], [252, 255, 164]],
+            ColorScheme::Plasma => &[[13, 8, 135], [70, 3, 159], [114, 1, 168], [156, 23, 158], [189, 55, 134],
+                                     [216, 87, 107], [237, 121, 83], [251, 159, 58], [253, 202, 38], [240, 249, 33]],
+            ColorScheme::Cividis => &[[0, 34, 78], [18, 53, 112], [59, 73, 108], [87, 93, 109], [112, 113, 115],
+                                      [138, 135, 121], [166, 157, 117], [196, 181, 108], [228, 207, 91], [254, 232, 56]],
+            ColorScheme::Greys => &[[255, 255, 255], [0, 0, 0]],
+            ColorScheme::Blues => &[[247, 251, 255], [198, 219, 239], [107, 174, 214], [33, 113, 181], [8, 48, 107]],
+            ColorScheme::Greens => &[[247, 252, 245], [199, 233, 192], [116, 196, 118], [35, 139, 69], [0, 68, 27]],
+            ColorScheme::Reds => &[[255, 245, 240], [252, 187, 161], [251, 106, 74], [203, 24, 29], [103, 0, 13]],
+            ColorScheme::Spectral => &[[158, 1, 66], [213, 62, 79], [244, 109, 67], [253, 174, 97], [254, 224, 139],
+                                       [255, 255, 191], [230, 245, 152], [171, 221, 164], [102, 194, 165],
+                                       [50, 136, 189], [94, 79, 162]],
+            ColorScheme::RdYlGn => &[[165, 0, 38], [215, 48, 39], [244, 109, 67], [253, 174, 97], [254, 224, 139],
+                                     [255, 255, 191], [217, 239, 139], [166, 217, 106], [102, 189, 99],
+                                     [26, 152, 80], [0, 104, 55]],
+            ColorScheme::Terrain => &[[51, 51, 153], [2, 148, 250], [36, 211, 109], [254, 254, 152],
+                                      [130, 94, 85], [255, 255, 255]],
+        }
+    }
+
+    /// Color of the scheme at a position between 0.0 (low) and 1.0 (high)
+    ///
+    /// # Arguments
+    /// * `t` - Position along the scheme, clamped to [0, 1]
+    ///
+    /// # Returns
+    /// The interpolated color
+    pub fn color_at(&self, t: f64) -> RgbColor {
+        let stops = self.stops();
+        let position = t.clamp(0.0, 1.0) * (stops.len() - 1) as f64;
+        let index = (position.floor() as usize).min(stops.len() - 2);
+        let frac = position - index as f64;
+        let (a, b) = (stops[index], stops[index + 1]);
+        let mix = |i: usize| (a[i] as f64 + (b[i] as f64 - a[i] as f64) * frac).round() as u8;
+        RgbColor::new(mix(0), mix(1), mix(2))
+    }
+
+    /// Sample `count` evenly spaced colors from the scheme
+    ///
+    /// # Arguments
+    /// * `count` - Number of colors
+    ///
+    /// # Returns
+    /// The colors, from low to high values
+    pub fn colors(&self, count: usize) -> Vec<RgbColor> {
+        match count {
+            0 => Vec::new(),
+            1 => vec![self.color_at(0.5)],
+            _ => (0..count).map(|i| self.color_at(i as f64 / (count - 1) as f64)).collect(),
+        }
+    }
+}
+
+/// Parse an automatic colormap specification "method[:classes[:scheme]]"
+///
+/// Defaults are 5 classes and the viridis scheme, e.g. "quantile:7:viridis".
+///
+/// # Arguments
+/// * `spec` - The specification string
+///
+/// # Returns
+/// The classification method, class count and color scheme, or an error
+pub fn parse_auto_spec(spec: &str) -> TiffResult<(ClassificationMethod, usize, ColorScheme)> {
+    let mut parts = spec.split(':').map(str::trim);
+
+    let method = ClassificationMethod::from_string(parts.next().unwrap_or(""))?;
+    let classes = match parts.next() {
+        Some(n) => n.parse::<usize>().ok().filter(|&n| (1..=256).contains(&n))
+            .ok_or_else(|| TiffError::GenericError(format!(
+                "Invalid class count '{}' (expected 1 to 256)", n)))?,
+        None => 5,
+    };
+    let scheme = match parts.next() {
+        Some(name) => ColorScheme::from_string(name)?,
+        None => ColorScheme::Viridis,
+    };
+
+    if parts.next().is_some() {
+        return Err(TiffError::GenericError(format!(
+            "Invalid colormap specification '{}' (expected method:classes:scheme)", spec)));
+    }
+
+    Ok((method, classes, scheme))
+}
+
+/// Compute class breaks from a histogram
+///
+/// Each break is the inclusive upper value of its class; the last break is
+/// always the histogram maximum. Duplicate breaks are removed, so sparse
+/// data may yield fewer classes than requested.
+///
+/// # Arguments
+/// * `histogram` - The value histogram
+/// * `classes` - Requested number of classes
+/// * `method` - Classification method
+///
+/// # Returns
+/// The class upper bounds in ascending order, or an error for an empty histogram
+pub fn class_breaks(histogram: &ValueHistogram, classes: usize, method: ClassificationMethod) -> TiffResult<Vec<u16>> {
+    let (min, max) = histogram.min().zip(histogram.max())
+        .ok_or_else(|| TiffError::GenericError("Cannot classify an empty histogram".to_string()))?;
+    let classes = classes.max(1);
+
+    let mut breaks = match method {
+        ClassificationMethod::EqualInterval => {
+            let width = (max - min) as f64 / classes as f64;
+            (1..=classes).map(|i| (min as f64 + width * i as f64).round() as u16).collect()
+        },
+        ClassificationMethod::Quantile => quantile_breaks(histogram, classes),
+        ClassificationMethod::Jenks => jenks_breaks(histogram, classes),
+    };
+
+    breaks.push(max);
+    breaks.retain(|&b| b >= min && b <= max);
+    breaks.sort_unstable();
+    breaks.dedup();
+
+    info!("{:?} classification into {} classes: {:?}", method, breaks.len(), breaks);
+    Ok(breaks)
+}
+
+/// Quantile breaks: the value at which each cumulative share is reached
+fn quantile_breaks(histogram: &ValueHistogram, classes: usize) -> Vec<u16> {
+    let total = histogram.total() as f64;
+    let mut breaks = Vec::with_capacity(classes);
+    let mut cumulative = 0u64;
+    let mut next = 1;
+
+    for (value, &count) in histogram.counts.iter().enumerate() {
+        cumulative += count;
+        while next < classes && cumulative as f64 >= total * next as f64 / classes as f64 {
+            breaks.push(value as u16);
+            next += 1;
+        }
+    }
+
+    breaks
+}
+
+/// Jenks natural breaks over the (binned) histogram
+///
+/// Uses the Fisher-Jenks dynamic program on weighted values, which is
+/// exact for 8-bit data and works on at most `JENKS_MAX_BINS` bins.
+fn jenks_breaks(histogram: &ValueHistogram, classes: usize) -> Vec<u16> {
+    // Bins of (upper value, weight, weighted sum, weighted sum of squares)
+    let occupied: Vec<(usize, u64)> = histogram.counts.iter().copied().enumerate().filter(|&(_, c)| c > 0).collect();
+    let per_bin = occupied.len().div_ceil(JENKS_MAX_BINS).max(1);
+    let bins: Vec<(u16, f64, f64, f64)> = occupied.chunks(per_bin)
+        .map(|chunk| chunk.iter().fold((0u16, 0.0, 0.0, 0.0), |acc, &(value, count)| {
+            let (v, w) = (value as f64, count as f64);
+            (value as u16, acc.1 + w, acc.2 + w * v, acc.3 + w * v * v)
+        }))
+        .collect();
+
+    let n = bins.len();
+    let k = classes.min(n);
+    debug!("Jenks optimization over {} bins into {} classes", n, k);
+    if k <= 1 {
+        return Vec::new();
+    }
+
+    // Prefix sums for O(1) within-class sum of squared deviations
+    let mut prefix = vec![(0.0, 0.0, 0.0); n + 1];
+    for (i, bin) in bins.iter().enumerate() {
+        let p = prefix[i];
+        prefix[i + 1] = (p.0 + bin.1, p.1 + bin.2, p.2 + bin.3);
+    }
+    let cost = |from: usize, to: usize| {
+        let (w, s, sq) = (prefix[to].0 - prefix[from].0, prefix[to].1 - prefix[from].1, prefix[to].2 - prefix[from].2);
+        if w > 0.0 { sq - s * s / w } else { 0.0 }
+    };
+
+    // best[c][i]: minimal cost of splitting the first i bins into c + 1 classes
+    let mut best = vec![vec![f64::INFINITY; n + 1]; k];
+    let mut split = vec![vec![0usize; n + 1]; k];
+    for (i, first) in best[0].iter_mut().enumerate().skip(1) {
+        *first = cost(0, i);
+    }
+    for c in 1..k {
+        for i in c + 1..=n {
+            for j in c..i {
+                let candidate = best[c - 1][j] + cost(j, i);
+                if candidate < best[c][i] {
+                    best[c][i] = candidate;
+                    split[c][i] = j;
+                }
+            }
+        }
+    }
+
+    // Walk the splits back: each split j ends a class at bin j - 1
+    let mut breaks = Vec::with_capacity(k - 1);
+    let mut end = n;
+    for c in (1..k).rev() {
+        let j = split[c][end];
+        breaks.push(bins[j - 1].0);
+        end = j;
+    }
+    breaks.reverse();
+    breaks
+}
@@ -10,10 +10,11 @@ use std::path::Path;
 use crate::tiff::errors::{TiffResult, TiffError};
 use crate::tiff::colormap::{ColorMap, ColorMapReader, RgbColor, ColorMapEntry};
 use crate::utils::logger::Logger;
-use crate::extractor::Region;
+use crate::extractor::{OutputFormat, Region};
 use crate::tiff::TiffReader;
 use crate::tiff::geo_key_parser::GeoKeyParser;
 use crate::utils::reference_utils::add_georeferencing_to_builder;
+use crate::utils::scratch_utils;
 
 /// Find the appropriate color for a pixel value using a colormap
 ///
@@ -46,6 +47,14 @@ pub fn find_color_for_value(colormap: &ColorMap, value: u16) -> RgbColor {
         return interpolate_color(colormap, value);
     }
 
+    // Interval entries color the values below their quantity; values beyond the last one take its color
+    if colormap.map_type == "intervals" {
+        return colormap.entries.iter()
+            .find(|entry| value < entry.value)
+            .unwrap_or(&colormap.entries[colormap.entries.len() - 1])
+            .color;
+    }
+
     // For non-ramp colormaps, find the nearest entry
     find_nearest_color(colormap, value)
 }
@@ -143,26 +152,23 @@ pub fn find_nearest_color(colormap: &ColorMap, value: u16) -> RgbColor {
 ///
 /// # Returns
 /// A new RGB image with the colormap applied
-pub fn apply_colormap_to_image(
-    grayscale: &image::GrayImage,
+pub fn apply_colormap_to_image<C: std::ops::Deref<Target = [u8]>>(
+    grayscale: &image::ImageBuffer<image::Luma<u8>, C>,
     colormap: &ColorMap
 ) -> image::RgbImage {
     let width = grayscale.width();
     let height = grayscale.height();
     let mut rgb_image = image::RgbImage::new(width, height);
 
-    // Apply the colormap to each pixel
-    for y in 0..height {
-        for x in 0..width {
-            let pixel = grayscale.get_pixel(x, y);
-            let value = pixel[0] as u16; // Value is in the first channel
-
-            // Find the right color for this value
-            let color = find_color_for_value(colormap, value);
-
-            // Set the pixel in the output image
-            rgb_image.put_pixel(x, y, image::Rgb([color.r, color.g, color.b]));
-        }
+    // Find the color of each 8-bit value once, then look the pixels up
+    let mut table = [[0u8; 3]; 256];
+    for (value, entry) in table.iter_mut().enumerate() {
+        let color = find_color_for_value(colormap, value as u16);
+        *entry = [color.r, color.g, color.b];
+    }
+    // A byte-shuffle lookup benchmarked at half the speed of this table walk
+    for (&value, pixel) in grayscale.as_raw().iter().zip(rgb_image.chunks_exact_mut(3)) {
+        pixel.copy_from_slice(&table[value as usize]);
     }
 
     rgb_image
@@ -173,14 +179,20 @@ pub fn apply_colormap_to_image(
 /// # Arguments
 /// * `tiff_path` - Path to the TIFF file
 /// * `output_path` - Path where to save the colormap
+/// * `trim_leading_black` - Drop leading pure-black palette entries
 /// * `logger` - Logger for recording operations
 ///
 /// # Returns
 /// Result indicating success or an error
-pub fn extract_colormap(tiff_path: &str, output_path: &str, logger: &Logger) -> TiffResult<()> {
+pub fn extract_colormap(
+    tiff_path: &str,
+    output_path: &str,
+    trim_leading_black: bool,
+    logger: &Logger
+) -> TiffResult<()> {
     info!("Extracting colormap from {} to {}", tiff_path, output_path);
 
-    let colormap_reader = ColorMapReader::new(logger);
+    let colormap_reader = ColorMapReader::new(logger).with_trim_leading_black(trim_leading_black);
     let colormap = colormap_reader.read_from_tiff(tiff_path)?;
 
     // Determine output format and layer name
@@ -216,6 +228,8 @@ pub fn extract_colormap(tiff_path: &str, output_path: &str, logger: &Logger) ->
 /// * `input_path` - Path to the input file (for georeference info)
 /// * `region` - Optional region that was extracted
 /// * `logger` - Logger for recording operations
+/// * `shape` - Optional shape to use ("circle" or "square")
+/// * `format` - Requested output format, None to go by the extension
 ///
 /// # Returns
 /// Result indicating success or an error
@@ -226,13 +240,11 @@ pub fn save_colorized_tiff(
     input_path: &str,
     region: Option<Region>,
     logger: &Logger,
-    shape: Option<&str>
+    shape: Option<&str>,
+    format: Option<OutputFormat>
 ) -> TiffResult<()> {
     // Check if the output should be a non-TIFF format
-    let path = std::path::Path::new(output_path);
-    let extension = path.extension()
-        .map(|ext| ext.to_string_lossy().to_lowercase())
-        .unwrap_or_default();
+    let output_format = OutputFormat::resolve(output_path, format);
 
     // Convert to DynamicImage for shape masking
     let dynamic_image = image::DynamicImage::ImageRgb8(rgb_image);
@@ -249,16 +261,13 @@ pub fn save_colorized_tiff(
     };
 
     // If output is PNG, JPG, or any other supported non-TIFF format
-    if extension != "tif" && extension != "tiff" {
-        info!("Saving colorized image to {} format", extension);
+    if !output_format.is_tiff() {
+        info!("Saving colorized image to {} format", output_format);
         // Use the mask_utils to handle format selection and transparency
         if let Some(shape_str) = shape {
-            return crate::utils::mask_utils::save_shaped_image(&final_image, output_path, shape_str);
+            return crate::utils::mask_utils::save_shaped_image(&final_image, output_path, shape_str, format);
         } else {
-            return match final_image.save(output_path) {
-                Ok(_) => Ok(()),
-                Err(e) => Err(TiffError::GenericError(format!("Failed to save image: {}", e)))
-            };
+            return output_format.write_image(&final_image, output_path);
         }
     }
 
@@ -266,20 +275,20 @@ pub fn save_colorized_tiff(
     let width = final_image.width();
     let height = final_image.height();
 
-    // Get the RGB data from the final image
-    let rgb_data = final_image.to_rgb8().into_raw();
-
     // Create a new TIFF builder for an RGB image
     let mut builder = crate::tiff::TiffBuilder::new(logger, false);
 
     // Add a new IFD
     let ifd_index = builder.add_ifd(crate::tiff::ifd::IFD::new(0, 0));
 
-    // Set basic RGB tags
-    builder.add_basic_rgb_tags(ifd_index, width, height);
-
-    // Set up the strip data
-    builder.setup_single_strip(ifd_index, rgb_data);
+    // Shape-masked images keep their transparency as an alpha channel
+    if final_image.color().has_alpha() {
+        builder.add_basic_rgba_tags(ifd_index, width, height);
+        builder.setup_single_strip(ifd_index, final_image.to_rgba8().into_raw());
+    } else {
+        builder.add_basic_rgb_tags(ifd_index, width, height);
+        builder.setup_single_strip(ifd_index, final_image.to_rgb8().into_raw());
+    }
 
     // If we have a region, add geotransform for it
     if let Some(extract_region) = region {
@@ -304,4 +313,121 @@ pub fn save_colorized_tiff(
 pub fn load_colormap(colormap_path: &str, logger: &Logger) -> TiffResult<ColorMap> {
     let colormap_reader = ColorMapReader::new(logger);
     colormap_reader.read_file(colormap_path)
-}
\ No newline at end of file
+}
+/// Value edits applied to a colormap, in the order rescale, offset, merge
+#[derive(Debug, Clone, Default)]
+pub struct ColormapTransform {
+    /// Rescale entry values from the first range to the second
+    pub rescale: Option<((f64, f64), (f64, f64))>,
+    /// Constant added to every entry value after rescaling
+    pub offset: Option<i32>,
+    /// Path of a colormap whose entries are merged in last
+    pub merge: Option<String>,
+}
+
+impl ColormapTransform {
+    /// Check whether the transform changes anything
+    pub fn is_empty(&self) -> bool {
+        self.rescale.is_none() && self.offset.is_none() && self.merge.is_none()
+    }
+}
+
+/// Parse a rescale specification of the form "FROM_MIN:FROM_MAX:TO_MIN:TO_MAX"
+///
+/// # Arguments
+/// * `spec` - Rescale specification, e.g. "0:100:0:10000"
+///
+/// # Returns
+/// The source and target ranges, or an error
+pub fn parse_rescale_spec(spec: &str) -> TiffResult<((f64, f64), (f64, f64))> {
+    let values: Vec<f64> = spec.split(':')
+        .map(|part| part.trim().parse::<f64>())
+        .collect::<Result<_, _>>()
+        .map_err(|_| TiffError::GenericError(format!("Invalid rescale value in '{}'", spec)))?;
+
+    match values.as_slice() {
+        [from_min, from_max, to_min, to_max] => Ok(((*from_min, *from_max), (*to_min, *to_max))),
+        _ => Err(TiffError::GenericError(format!(
+            "Invalid rescale '{}' (expected FROM_MIN:FROM_MAX:TO_MIN:TO_MAX)", spec))),
+    }
+}
+
+/// Load a colormap, apply value edits and save the result as SLD
+///
+/// # Arguments
+/// * `colormap_path` - Path to the source colormap (SLD, CSV or palette TIFF)
+/// * `output_path` - Path of the SLD file to write
+/// * `transform` - Rescale, offset and merge operations to apply
+/// * `logger` - Logger for recording operations
+///
+/// # Returns
+/// The transformed ColorMap or an error
+pub fn transform_colormap_file(
+    colormap_path: &str,
+    output_path: &str,
+    transform: &ColormapTransform,
+    logger: &Logger
+) -> TiffResult<ColorMap> {
+    let mut colormap = load_colormap(colormap_path, logger)?;
+    info!("Transforming colormap {} ({} entries)", colormap_path, colormap.len());
+
+    if let Some((from, to)) = transform.rescale {
+        colormap.rescale(from, to)?;
+    }
+    if let Some(delta) = transform.offset {
+        colormap.offset(delta);
+    }
+    if let Some(merge_path) = &transform.merge {
+        let other = load_colormap(merge_path, logger)?;
+        colormap.merge(&other);
+    }
+
+    let layer_name = Path::new(colormap_path)
+        .file_stem()
+        .map(|s| s.to_string_lossy().to_string())
+        .unwrap_or_else(|| "layer".to_string());
+    colormap.to_sld_file(output_path, &layer_name)?;
+
+    info!("Transformed colormap saved to {}", output_path);
+    Ok(colormap)
+}
+
+/// Colorize a whole single-band raster with a colormap
+///
+/// Reads the full raster (no region or shape handling), maps every value
+/// through the colormap and writes an RGB image. TIFF output keeps the
+/// georeferencing of the source; other extensions are written as plain images.
+///
+/// # Arguments
+/// * `input_path` - Path to the single-band source raster
+/// * `output_path` - Path of the colorized output (format from extension)
+/// * `colormap` - The colormap to apply
+/// * `logger` - Logger for recording operations
+///
+/// # Returns
+/// Result indicating success or an error
+pub fn colorize_file(
+    input_path: &str,
+    output_path: &str,
+    colormap: &ColorMap,
+    logger: &Logger
+) -> TiffResult<()> {
+    info!("Colorizing {} with a {}-entry '{}' colormap", input_path, colormap.len(), colormap.map_type);
+
+    let mut extractor = crate::extractor::ImageExtractor::new(logger);
+    let image = extractor.extract_image(input_path, None)?;
+    if image.color().channel_count() > 1 {
+        warn!("{} has {} channels; colorizing its luminance", input_path, image.color().channel_count());
+    }
+
+    // Only the class values are needed from here on
+    let grayscale = scratch_utils::luma_plane(&image, "the class values")?;
+    drop(image);
+    let rgb_image = apply_colormap_to_image(&grayscale, colormap);
+
+    let full_extent = Region::new(0, 0, grayscale.width(), grayscale.height());
+    save_colorized_tiff(rgb_image, output_path, input_path, Some(full_extent), logger, None, None)?;
+
+    info!("Colorized raster written to {}", output_path);
+    Ok(())
+}
@@ -6,6 +6,7 @@
 
 use crate::tiff::errors::{TiffError, TiffResult};
 use crate::coordinate::BoundingBox;
+use crate::utils::grid_reference_utils;
 use std::f64::consts::PI;
 use log::{debug, info};
 
@@ -32,18 +33,7 @@ pub fn coord_to_bbox(coord_str: &str, radius: f64, shape: &str, epsg: Option<u32
            coord_str, radius, shape);
 
     // Parse the coordinate
-    let parts: Vec<&str> = coord_str.split(',').collect();
-    if parts.len() != 2 {
-        return Err(TiffError::GenericError(
-            "Coordinate must be in format 'x,y' or 'lon,lat' for EPSG:4326".to_string()));
-    }
-
-    let x = parts[0].trim().parse::<f64>()
-        .map_err(|_| TiffError::GenericError("Invalid x/longitude coordinate".to_string()))?;
-    let y = parts[1].trim().parse::<f64>()
-        .map_err(|_| TiffError::GenericError("Invalid y/latitude coordinate".to_string()))?;
-
-    debug!("Parsed coordinates: x/lon={}, y/lat={}", x, y);
+    let (x, y) = parse_coordinate(coord_str)?;
 
     // Calculate bounding box based on shape and EPSG
     match shape.to_lowercase().as_str() {
@@ -104,6 +94,210 @@ pub fn coord_to_bbox(coord_str: &str, radius: f64, shape: &str, epsg: Option<u32
     }
 }
 
+/// Convert a coordinate in a projected CRS to a bounding box
+///
+/// Used for user-defined grids, where the radius is already expressed in
+/// the grid's linear unit. Circles and squares share the same bounding box.
+///
+/// # Arguments
+/// * `coord_str` - Coordinate string in format "x,y"
+/// * `radius` - Radius in CRS units
+///
+/// # Returns
+/// A string representation of the bounding box or an error
+pub fn projected_coord_to_bbox(coord_str: &str, radius: f64) -> TiffResult<String> {
+    let (x, y) = parse_coordinate(coord_str)?;
+    Ok(format!("{},{},{},{}", x - radius, y - radius, x + radius, y + radius))
+}
+
+/// Parse an "x,y" coordinate string
+fn parse_coordinate(coord_str: &str) -> TiffResult<(f64, f64)> {
+    let parts: Vec<&str> = coord_str.split(',').collect();
+    if parts.len() != 2 {
+        return Err(TiffError::GenericError(
+            "Coordinate must be in format 'x,y' or 'lon,lat' for EPSG:4326".to_string()));
+    }
+
+    let x = parts[0].trim().parse::<f64>()
+        .map_err(|_| TiffError::GenericError("Invalid x/longitude coordinate".to_string()))?;
+    let y = parts[1].trim().parse::<f64>()
+        .map_err(|_| TiffError::GenericError("Invalid y/latitude coordinate".to_string()))?;
+
+    debug!("Parsed coordinates: x/lon={}, y/lat={}", x, y);
+    Ok((x, y))
+}
+
+/// A coordinate as a person typed or copied it
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum HumanCoordinate {
+    /// Two bare numbers, in the order of `--axis-order`
+    Plain(f64, f64),
+    /// Longitude and latitude, the order being fixed by the input itself
+    LonLat(f64, f64),
+}
+
+/// One part of a coordinate: degrees, minutes, seconds and a hemisphere
+#[derive(Default)]
+struct CoordinatePart {
+    /// Up to three numbers: degrees, then minutes and seconds
+    numbers: Vec<f64>,
+    /// Hemisphere letter before or after the numbers
+    hemisphere: Option<char>,
+}
+
+impl CoordinatePart {
+    /// Decimal degrees of the part, negative for S and W
+    fn degrees(&self, input: &str) -> TiffResult<f64> {
+        let invalid = |reason: &str| TiffError::GenericError(format!("Invalid coordinate '{}': {}", input, reason));
+        let (degrees, rest) = self.numbers.split_first().ok_or_else(|| invalid("missing degrees"))?;
+        if rest.len() > 2 {
+            return Err(invalid("more than degrees, minutes and seconds in one part"));
+        }
+        if rest.iter().any(|&value| !(0.0..60.0).contains(&value)) {
+            return Err(invalid("minutes and seconds must be between 0 and 60"));
+        }
+        if !rest.is_empty() && degrees.fract() != 0.0 {
+            return Err(invalid("degrees with minutes must be whole"));
+        }
+
+        let magnitude = degrees.abs() + rest.iter().zip([60.0, 3600.0]).map(|(value, unit)| value / unit).sum::<f64>();
+        let negative = degrees.is_sign_negative();
+        match self.hemisphere {
+            Some('S') | Some('W') if negative => Err(invalid("a negative value with an S or W hemisphere")),
+            Some('S') | Some('W') => Ok(-magnitude),
+            _ if negative => Ok(-magnitude),
+            _ => Ok(magnitude),
+        }
+    }
+}
+
+/// Parse a coordinate written the way people write them
+///
+/// Besides "x,y", accepts degrees-minutes-seconds and hemisphere letters
+/// such as `52°31'12"N 13°24'36"E`, `N52 31 12, E13 24 36`, `52.52N,13.41E`
+/// or gdalinfo's `13d24'36.00"E`, Google Maps URLs holding `@lat,lon,zoom`,
+/// UTM coordinates ("33N 389000 5819000") and MGRS references
+/// ("33UUU8900019000"). Letters, URLs and grid references settle which
+/// value is the latitude; bare numbers, with or without degree signs, are
+/// left to the axis order.
+///
+/// # Arguments
+/// * `input` - The coordinate as given on the command line
+///
+/// # Returns
+/// The parsed coordinate, or an error saying what could not be read
+pub fn parse_human_coordinate(input: &str) -> TiffResult<HumanCoordinate> {
+    let invalid = |reason: String| TiffError::GenericError(format!("Invalid coordinate '{}': {}", input, reason));
+
+    // Google Maps URLs hold latitude, longitude and zoom after an @
+    if let Some((_, location)) = input.split_once('@') {
+        let values: Vec<f64> = location.split(',').take(2)
+            .map(|value| value.trim().parse::<f64>())
+            .collect::<Result<_, _>>()
+            .map_err(|_| invalid("expected @latitude,longitude".to_string()))?;
+        if values.len() != 2 {
+            return Err(invalid("expected @latitude,longitude".to_string()));
+        }
+        return checked_lon_lat(input, values[1], values[0]);
+    }
+
+    // UTM coordinates and MGRS references name their zone
+    let reference = match grid_reference_utils::parse_mgrs(input)? {
+        Some(reference) => Some(reference),
+        None => grid_reference_utils::parse_utm(input)?,
+    };
+    if let Some(reference) = reference {
+        let point = reference.to_lon_lat()?;
+        return checked_lon_lat(input, point.x, point.y);
+    }
+
+    let mut parts: Vec<CoordinatePart> = Vec::new();
+    let mut current = CoordinatePart::default();
+    let mut chars = input.trim().chars().peekable();
+    while let Some(&c) = chars.peek() {
+        if c.is_ascii_digit() || c == '.' || ((c == '-' || c == '+') && current.numbers.is_empty()) {
+            let mut number = String::new();
+            while let Some(&d) = chars.peek() {
+                if d.is_ascii_digit() || d == '.' || (number.is_empty() && (d == '-' || d == '+')) {
+                    number.push(d);
+                    chars.next();
+                } else {
+                    break;
+                }
+            }
+            let value = number.parse::<f64>().map_err(|_| invalid(format!("'{}' is not a number", number)))?;
+            current.numbers.push(value);
+            continue;
+        }
+
+        chars.next();
+        match c.to_ascii_uppercase() {
+            hemisphere @ ('N' | 'S' | 'E' | 'W') => {
+                if current.numbers.is_empty() {
+                    // Prefix form, e.g. N52 31 12
+                    if current.hemisphere.is_some() {
+                        return Err(invalid(format!("two hemisphere letters before '{}'", c)));
+                    }
+                    current.hemisphere = Some(hemisphere);
+                } else if current.hemisphere.is_some() {
+                    // The letter opens the next part
+                    parts.push(std::mem::take(&mut current));
+                    current.hemisphere = Some(hemisphere);
+                } else {
+                    current.hemisphere = Some(hemisphere);
+                    parts.push(std::mem::take(&mut current));
+                }
+            },
+            ',' | ';' => {
+                if !current.numbers.is_empty() {
+                    parts.push(std::mem::take(&mut current));
+                }
+            },
+            '°' | 'º' | 'D' | '\'' | '′' | '’' | '"' | '″' | '”' => {},
+            c if c.is_whitespace() => {},
+            _ => return Err(invalid(format!("unexpected '{}'", c))),
+        }
+    }
+    if !current.numbers.is_empty() || current.hemisphere.is_some() {
+        parts.push(current);
+    }
+
+    let [first, second] = parts.as_slice() else {
+        return Err(invalid(format!("expected two values, found {}", parts.len())));
+    };
+    let is_latitude = |part: &CoordinatePart| part.hemisphere.map(|h| h == 'N' || h == 'S');
+    let first_value = first.degrees(input)?;
+    let second_value = second.degrees(input)?;
+
+    let latitude_first = match (is_latitude(first), is_latitude(second)) {
+        (None, None) => {
+            debug!("Parsed coordinate '{}' as {},{}", input, first_value, second_value);
+            return Ok(HumanCoordinate::Plain(first_value, second_value));
+        },
+        (Some(a), Some(b)) if a == b => {
+            return Err(invalid("both values have a latitude or both a longitude hemisphere".to_string()));
+        },
+        (Some(a), _) => a,
+        (None, Some(b)) => !b,
+    };
+
+    if latitude_first {
+        checked_lon_lat(input, second_value, first_value)
+    } else {
+        checked_lon_lat(input, first_value, second_value)
+    }
+}
+
+/// A longitude and latitude checked against their ranges
+fn checked_lon_lat(input: &str, lon: f64, lat: f64) -> TiffResult<HumanCoordinate> {
+    if !(-180.0..=180.0).contains(&lon) || !(-90.0..=90.0).contains(&lat) {
+        return Err(TiffError::GenericError(format!(
+            "Invalid coordinate '{}': longitude {} or latitude {} out of range", input, lon, lat)));
+    }
+    info!("Read coordinate '{}' as longitude {}, latitude {}", input, lon, lat);
+    Ok(HumanCoordinate::LonLat(lon, lat))
+}
+
 /// Calculate a bounding box that surrounds a circle centered at a point
 ///
 /// This function computes the corners of a bounding box that fully contains
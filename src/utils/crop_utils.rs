@@ -0,0 +1,219 @@
+//! Cropping by copying encoded blocks
+//!
+//! Extracting a window normally decodes every strip or tile it touches and
+//! encodes the result again. When the window starts on a block boundary
+//! and ends on one (or at the edge of the image), the output consists of
+//! whole source blocks, so they can be copied as they are stored: the
+//! compressed bytes, the compression and predictor, JPEG tables and every
+//! other tag stay untouched, only the dimensions, block offsets and
+//! georeferencing change. For a cloud-optimized GeoTIFF this clips without
+//! any loss in about the time it takes to read the blocks.
+
+use std::fs::File;
+use std::io::{BufReader, Read, Seek, SeekFrom};
+use std::time::Instant;
+
+use log::info;
+
+use crate::extractor::{Provenance, Region};
+use crate::io::byte_order::ByteOrder;
+use crate::tiff::builder::TiffBuilder;
+use crate::tiff::constants::{field_types, planar_config, tags};
+use crate::tiff::errors::{TiffError, TiffResult};
+use crate::tiff::ifd::{IFD, IFDEntry};
+use crate::tiff::TiffReader;
+use crate::utils::logger::Logger;
+use crate::utils::{memory_utils, metrics, tiff_extraction_utils, tiff_utils, xml_utils};
+
+/// Tags that are rewritten for the output, or point at parts of the source
+/// file that a deep copy cannot follow
+const REPLACED_TAGS: [u16; 9] = [
+    tags::IMAGE_WIDTH, tags::IMAGE_LENGTH,
+    tags::STRIP_OFFSETS, tags::STRIP_BYTE_COUNTS, tags::TILE_OFFSETS, tags::TILE_BYTE_COUNTS,
+    tags::SUB_IFDS, tags::EXIF_IFD, tags::GPS_IFD,
+];
+
+/// Check whether a window of an image can be cropped by copying blocks
+///
+/// # Arguments
+/// * `reader` - Reader the file was loaded with
+/// * `ifd` - The image
+/// * `region` - Window to crop, in pixels of the image
+///
+/// # Returns
+/// Ok if the window starts on a strip or tile boundary and ends on one or
+/// at the edge of the image, and the blocks can be stored unchanged in a
+/// little-endian output; otherwise an error saying why not
+pub fn check_copyable(reader: &TiffReader, ifd: &IFD, region: &Region) -> TiffResult<()> {
+    let (width, height) = ifd.get_dimensions()
+        .ok_or_else(|| TiffError::GenericError("Missing image dimensions".to_string()))?;
+    let (block_width, block_height) = tiff_extraction_utils::block_size(ifd)
+        .ok_or_else(|| TiffError::GenericError("Cannot determine block size".to_string()))?;
+
+    if region.width == 0 || region.height == 0
+        || region.end_x() as u64 > width || region.end_y() as u64 > height {
+        return Err(TiffError::GenericError(format!(
+            "Window ({}, {}) {}x{} is not inside the {}x{} image",
+            region.x, region.y, region.width, region.height, width, height)));
+    }
+
+    let aligned = |start: u32, end: u32, block: u32, size: u64| {
+        start.is_multiple_of(block) && (end.is_multiple_of(block) || end as u64 == size)
+    };
+    if !aligned(region.x, region.end_x(), block_width, width)
+        || !aligned(region.y, region.end_y(), block_height, height) {
+        return Err(TiffError::GenericError(format!(
+            "Window ({}, {}) {}x{} does not fall on the {}x{} blocks of the image",
+            region.x, region.y, region.width, region.height, block_width, block_height)));
+    }
+
+    // Samples wider than a byte are stored in the source's byte order,
+    // while outputs are always little-endian
+    let (bits_per_sample, _, _) = tiff_extraction_utils::get_tiff_image_properties(ifd);
+    let byte_order = reader.get_byte_order_handler().map(|handler| handler.byte_order());
+    if bits_per_sample > 8 && byte_order == Some(ByteOrder::BigEndian) {
+        return Err(TiffError::GenericError(format!(
+            "The {}-bit samples of a big-endian file cannot be copied as they are stored", bits_per_sample)));
+    }
+    Ok(())
+}
+
+/// Indices of the blocks covering a block-aligned window
+///
+/// Blocks are listed row by row, one band after the other for planar
+/// images, which is also their order in the output.
+fn window_blocks(ifd: &IFD, region: &Region) -> TiffResult<Vec<usize>> {
+    let (width, height) = ifd.get_dimensions()
+        .ok_or_else(|| TiffError::GenericError("Missing image dimensions".to_string()))?;
+    let (block_width, block_height) = tiff_extraction_utils::block_size(ifd)
+        .ok_or_else(|| TiffError::GenericError("Cannot determine block size".to_string()))?;
+
+    let across = (width as usize).div_ceil(block_width as usize);
+    let down = (height as usize).div_ceil(block_height as usize);
+    let planes = match ifd.get_tag_value(tags::PLANAR_CONFIGURATION) {
+        Some(value) if value == planar_config::PLANAR as u64 => ifd.get_samples_per_pixel() as usize,
+        _ => 1,
+    };
+
+    let columns = (region.x / block_width) as usize..region.end_x().div_ceil(block_width) as usize;
+    let rows = (region.y / block_height) as usize..region.end_y().div_ceil(block_height) as usize;
+    let mut blocks = Vec::with_capacity(planes * columns.len() * rows.len());
+    for plane in 0..planes {
+        for row in rows.clone() {
+            blocks.extend(columns.clone().map(|column| plane * across * down + row * across + column));
+        }
+    }
+    Ok(blocks)
+}
+
+/// Fill an IFD of a builder with the encoded blocks of a window of an image
+///
+/// The window must pass `check_copyable`. Every tag but the dimensions and
+/// block layout is copied from the source; georeferencing is left as the
+/// source has it.
+///
+/// # Arguments
+/// * `builder` - Builder of the output
+/// * `ifd_index` - IFD of the builder to fill
+/// * `reader` - Reader the source was loaded with
+/// * `ifd` - The source image
+/// * `input_path` - Path of the source TIFF
+/// * `region` - Block-aligned window to copy
+///
+/// # Returns
+/// The number of blocks copied
+pub(crate) fn copy_blocks(builder: &mut TiffBuilder, ifd_index: usize, reader: &TiffReader, ifd: &IFD,
+                          input_path: &str, region: &Region) -> TiffResult<usize> {
+    let layout_tags = if ifd.has_tag(tags::TILE_OFFSETS) {
+        (tags::TILE_OFFSETS, tags::TILE_BYTE_COUNTS)
+    } else {
+        (tags::STRIP_OFFSETS, tags::STRIP_BYTE_COUNTS)
+    };
+    let mut file = BufReader::new(File::open(input_path)?);
+    let offsets = reader.read_tag_values(&mut file, ifd, layout_tags.0)?;
+    let byte_counts = reader.read_tag_values(&mut file, ifd, layout_tags.1)?;
+
+    let indices = window_blocks(ifd, region)?;
+    let total: u64 = indices.iter().filter_map(|&index| byte_counts.get(index)).sum();
+    memory_utils::check_fits(&format!("Copying {} blocks", indices.len()), total)?;
+    info!("Copying {} blocks of window ({}, {}) {}x{} from {}",
+          indices.len(), region.x, region.y, region.width, region.height, input_path);
+    let mut blocks = Vec::with_capacity(indices.len());
+    for index in indices {
+        let (Some(&offset), Some(&byte_count)) = (offsets.get(index), byte_counts.get(index)) else {
+            return Err(TiffError::GenericError(format!("Block {} is missing from {}", index, input_path)));
+        };
+        let started = Instant::now();
+        file.seek(SeekFrom::Start(offset))?;
+        let mut block = vec![0u8; byte_count as usize];
+        file.read_exact(&mut block)?;
+        metrics::record_block_read(byte_count, started.elapsed());
+        blocks.push(block);
+    }
+    let count = blocks.len();
+
+    builder.deep_copy_tags_from(ifd_index, ifd, &REPLACED_TAGS, reader);
+    builder.copy_exif_metadata(ifd_index, ifd, reader)?;
+    builder.ifds[ifd_index].add_entry(IFDEntry::new(tags::IMAGE_WIDTH, field_types::LONG, 1, region.width as u64));
+    builder.ifds[ifd_index].add_entry(IFDEntry::new(tags::IMAGE_LENGTH, field_types::LONG, 1, region.height as u64));
+    builder.set_blocks(ifd_index, layout_tags, blocks);
+    Ok(count)
+}
+
+/// Crop the main image of a TIFF file by copying its encoded blocks
+///
+/// # Arguments
+/// * `input_path` - Path of the source TIFF
+/// * `output_path` - Path of the TIFF to write
+/// * `region` - Block-aligned window to crop, the whole image if None
+/// * `provenance` - Where the output came from, recorded in its metadata
+/// * `logger` - Logger for recording operations
+///
+/// # Returns
+/// The number of blocks copied
+pub fn crop_blocks(input_path: &str, output_path: &str, region: Option<Region>,
+                   provenance: Option<&Provenance>, logger: &Logger) -> TiffResult<usize> {
+    let mut reader = TiffReader::new(logger);
+    let tiff = reader.load(input_path)?;
+    let ifd = tiff.main_ifd()
+        .ok_or_else(|| TiffError::GenericError("No IFDs found in input file".to_string()))?;
+    let region = match region {
+        Some(region) => region,
+        None => {
+            let (width, height) = ifd.get_dimensions()
+                .ok_or_else(|| TiffError::GenericError("Missing image dimensions".to_string()))?;
+            Region::new(0, 0, width as u32, height as u32)
+        },
+    };
+    check_copyable(&reader, ifd, &region)?;
+
+    // Everything but the layout is the source's
+    let mut builder = TiffBuilder::new(logger, reader.is_big_tiff());
+    let ifd_index = builder.add_ifd(IFD::new(0, 0));
+    let count = copy_blocks(&mut builder, ifd_index, &reader, ifd, input_path, &region)?;
+
+    if ifd.has_tag(tags::MODEL_TIEPOINT_TAG) && ifd.has_tag(tags::MODEL_PIXEL_SCALE_TAG) {
+        let (pixel_scale, tiepoint) = tiff_extraction_utils::read_geotiff_info(
+            ifd, &reader, reader.get_file_path().unwrap_or(input_path));
+        builder.adjust_geotiff_for_region(ifd_index, &region, &pixel_scale, &tiepoint)?;
+    }
+
+    if let Some(provenance) = provenance {
+        info!("Writing provenance of {}", provenance.source);
+        let metadata = provenance.metadata_items(&region).iter().fold(
+            tiff_extraction_utils::extract_gdal_metadata(ifd, &reader)
+                .unwrap_or_else(|| "<GDALMetadata>\n</GDALMetadata>".to_string()),
+            |metadata, item| xml_utils::add_to_gdal_metadata(&metadata, item));
+        let mut metadata = metadata.into_bytes();
+        metadata.push(0);
+        tiff_utils::update_ifd_tag(&mut builder.ifds[ifd_index], tags::GDAL_METADATA,
+            IFDEntry::new(tags::GDAL_METADATA, field_types::ASCII, metadata.len() as u64, 0));
+        builder.set_external_data(ifd_index, tags::GDAL_METADATA, metadata);
+        builder.add_image_description(ifd_index, &provenance.description(&region));
+    }
+
+    builder.write(output_path)?;
+    logger.log(&format!("Copied {} blocks of window ({}, {}) {}x{} to {}",
+                        count, region.x, region.y, region.width, region.height, output_path))?;
+    Ok(count)
+}
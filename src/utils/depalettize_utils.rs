@@ -0,0 +1,131 @@
+//! Palette expansion utilities
+//!
+//! Converts palette-color (indexed) TIFFs into plain rasters that analysis
+//! tools can read: either RGB, with every index replaced by its palette
+//! color, or a single band holding the class indices without the ColorMap.
+
+use image::RgbImage;
+use log::{info, warn};
+
+use crate::tiff::errors::{TiffError, TiffResult};
+use crate::tiff::ifd::IFD;
+use crate::tiff::TiffReader;
+use crate::tiff::TiffBuilder;
+use crate::tiff::colormap::{ColorMapReader, RgbColor};
+use crate::tiff::constants::{tags, field_types, photometric};
+use crate::extractor::{ImageExtractor, Region};
+use crate::utils::logger::Logger;
+use crate::utils::{colormap_utils, reference_utils, tiff_extraction_utils};
+
+/// Target representation for a depalettized raster
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum DepalettizeMode {
+    /// Three-band RGB with the palette colors expanded
+    Rgb,
+    /// Single band holding the palette indices (class values)
+    Index,
+}
+
+impl DepalettizeMode {
+    /// Parse a depalettize mode from a string
+    ///
+    /// # Arguments
+    /// * `mode` - "rgb" or "index" (also "indices", "classes")
+    ///
+    /// # Returns
+    /// The parsed mode or an error
+    pub fn from_string(mode: &str) -> TiffResult<Self> {
+        match mode.to_lowercase().as_str() {
+            "rgb" => Ok(DepalettizeMode::Rgb),
+            "index" | "indices" | "classes" => Ok(DepalettizeMode::Index),
+            _ => Err(TiffError::GenericError(format!(
+                "Unknown depalettize mode '{}' (expected rgb or index)", mode))),
+        }
+    }
+}
+
+/// Check that an IFD holds an 8-bit palette-color image
+fn check_palette_ifd(ifd: &IFD) -> TiffResult<()> {
+    let photometric_interp = ifd.get_tag_value(tags::PHOTOMETRIC_INTERPRETATION).unwrap_or(0);
+    if photometric_interp != photometric::PALETTE as u64 {
+        return Err(TiffError::GenericError(
+            "Input is not a palette-color TIFF (PhotometricInterpretation is not 3)".to_string()));
+    }
+
+    let bits_per_sample = ifd.get_tag_value(tags::BITS_PER_SAMPLE).unwrap_or(8);
+    if bits_per_sample != 8 {
+        return Err(TiffError::GenericError(format!(
+            "Only 8-bit palette images can be depalettized (found {} bits per sample)", bits_per_sample)));
+    }
+
+    Ok(())
+}
+
+/// Convert a palette-color TIFF to RGB or to single-band class indices
+///
+/// The output is an uncompressed GeoTIFF carrying the georeferencing of the
+/// source. Index output keeps the source NoData value when it has one.
+///
+/// # Arguments
+/// * `input_path` - Path to the palette TIFF
+/// * `output_path` - Path of the TIFF to write
+/// * `mode` - Expand to RGB or keep the indices
+/// * `logger` - Logger for recording operations
+///
+/// # Returns
+/// Result indicating success or an error
+pub fn depalettize_file(
+    input_path: &str,
+    output_path: &str,
+    mode: DepalettizeMode,
+    logger: &Logger
+) -> TiffResult<()> {
+    info!("Depalettizing {} to {} ({:?})", input_path, output_path, mode);
+
+    let mut reader = TiffReader::new(logger);
+    let tiff = reader.load(input_path)?;
+    let ifd = tiff.main_ifd()
+        .ok_or_else(|| TiffError::GenericError("No IFDs found in TIFF file".to_string()))?;
+    check_palette_ifd(ifd)?;
+
+    let nodata = ifd.get_entry(tags::GDAL_NODATA)
+        .filter(|entry| entry.field_type == field_types::ASCII)
+        .map(|_| tiff_extraction_utils::extract_nodata_value(ifd, &reader));
+
+    let mut extractor = ImageExtractor::new(logger);
+    let indices = extractor.extract_image(input_path, None)?.to_luma8();
+    let (width, height) = indices.dimensions();
+    let full_extent = Region::new(0, 0, width, height);
+
+    match mode {
+        DepalettizeMode::Rgb => {
+            let palette = ColorMapReader::new(logger).read_palette_from_tiff(input_path)?;
+            info!("Expanding {} palette entries to RGB", palette.len());
+
+            let mut rgb = RgbImage::new(width, height);
+            for (index, pixel) in indices.pixels().zip(rgb.pixels_mut()) {
+                let color = RgbColor::from_rgb16(palette[index[0] as usize]);
+                *pixel = image::Rgb([color.r, color.g, color.b]);
+            }
+            if nodata.is_some() {
+                warn!("NoData is not carried over to RGB output; NoData pixels take their palette color");
+            }
+
+            colormap_utils::save_colorized_tiff(rgb, output_path, input_path, Some(full_extent), logger, None, None)
+        },
+        DepalettizeMode::Index => {
+            let mut builder = TiffBuilder::new(logger, false);
+            let ifd_index = builder.add_ifd(IFD::new(0, 0));
+
+            let image = image::DynamicImage::ImageLuma8(indices);
+            tiff_extraction_utils::process_grayscale_image(&image, &mut builder, ifd_index, 8)?;
+            reference_utils::add_georeferencing_to_builder(&mut builder, ifd_index, &full_extent, input_path, logger)?;
+            if let Some(nodata) = &nodata {
+                builder.add_nodata_tag(ifd_index, nodata);
+            }
+
+            info!("Writing single-band class indices to {}", output_path);
+            builder.write(output_path)
+        },
+    }
+}
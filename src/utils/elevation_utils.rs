@@ -0,0 +1,137 @@
+//! Elevation unit utilities
+//!
+//! DEMs come in meters, international feet or US survey feet, declared by
+//! the VerticalUnitsGeoKey. These helpers convert extracted elevations
+//! between those units and write them as float32 arrays, so the converted
+//! values keep their fractional part.
+
+use std::fs::File;
+use std::io::{BufWriter, Write};
+
+use log::info;
+
+use crate::tiff::errors::{TiffError, TiffResult};
+use crate::utils::tensor_utils::{self, NamedTensor};
+
+/// A linear unit usable for elevations
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum LinearUnit {
+    /// Meters (EPSG 9001)
+    Meter,
+    /// International feet (EPSG 9002)
+    Foot,
+    /// US survey feet (EPSG 9003)
+    UsSurveyFoot,
+}
+
+impl LinearUnit {
+    /// Parse a unit name
+    ///
+    /// # Arguments
+    /// * `name` - "m", "ft" or "us-ft" (or their long forms)
+    ///
+    /// # Returns
+    /// The unit or an error for unknown names
+    pub fn from_string(name: &str) -> TiffResult<Self> {
+        match name.to_lowercase().as_str() {
+            "m" | "meter" | "meters" | "metre" | "metres" => Ok(LinearUnit::Meter),
+            "ft" | "foot" | "feet" => Ok(LinearUnit::Foot),
+            "us-ft" | "us-foot" | "us-feet" | "ftus" | "us-survey-foot" => Ok(LinearUnit::UsSurveyFoot),
+            _ => Err(TiffError::GenericError(format!(
+                "Unknown unit '{}' (expected 'm', 'ft' or 'us-ft')", name))),
+        }
+    }
+
+    /// Map a GeoTIFF linear unit code to a unit
+    ///
+    /// # Arguments
+    /// * `code` - Value of a linear units GeoKey
+    ///
+    /// # Returns
+    /// The unit, or None for codes without a conversion
+    pub fn from_geokey(code: u16) -> Option<Self> {
+        match code {
+            9001 => Some(LinearUnit::Meter),
+            9002 => Some(LinearUnit::Foot),
+            9003 => Some(LinearUnit::UsSurveyFoot),
+            _ => None,
+        }
+    }
+
+    /// Length of one unit in meters
+    pub fn meters_per_unit(&self) -> f64 {
+        match self {
+            LinearUnit::Meter => 1.0,
+            LinearUnit::Foot => 0.3048,
+            LinearUnit::UsSurveyFoot => 1200.0 / 3937.0,
+        }
+    }
+
+    /// Canonical short name of the unit
+    pub fn name(&self) -> &'static str {
+        match self {
+            LinearUnit::Meter => "m",
+            LinearUnit::Foot => "ft",
+            LinearUnit::UsSurveyFoot => "us-ft",
+        }
+    }
+}
+
+/// Convert elevation values from one unit to another
+///
+/// NoData cells keep their value so they can still be recognized.
+///
+/// # Arguments
+/// * `data` - Elevation values as extracted
+/// * `from` - Unit of the source values
+/// * `to` - Desired unit
+/// * `nodata` - NoData value of the source, if any
+///
+/// # Returns
+/// The converted values as float32
+pub fn convert_elevations(data: &[f64], from: LinearUnit, to: LinearUnit, nodata: Option<f64>) -> Vec<f32> {
+    let factor = from.meters_per_unit() / to.meters_per_unit();
+    info!("Converting elevations from {} to {} (factor {})", from.name(), to.name(), factor);
+
+    data.iter()
+        .map(|&value| {
+            if nodata == Some(value) { value as f32 } else { (value * factor) as f32 }
+        })
+        .collect()
+}
+
+/// Save converted elevations
+///
+/// Tensor formats record the unit in their metadata; CSV is written as a
+/// plain grid of values.
+///
+/// # Arguments
+/// * `path` - Path of the file to write
+/// * `format` - "csv", "npy", "npz" or "safetensors"
+/// * `shape` - Array shape (rows, columns)
+/// * `values` - Elevations in row-major order
+/// * `unit` - Unit of the values
+///
+/// # Returns
+/// Result indicating success or an error
+pub fn save_elevations(path: &str, format: &str, shape: &[usize], values: &[f32], unit: LinearUnit) -> TiffResult<()> {
+    match format.to_lowercase().as_str() {
+        "csv" => {
+            let columns = shape.get(1).copied().unwrap_or(values.len()).max(1);
+            let mut writer = BufWriter::new(File::create(path)?);
+            for row in values.chunks(columns) {
+                let line: Vec<String> = row.iter().map(|v| v.to_string()).collect();
+                writeln!(writer, "{}", line.join(","))?;
+            }
+            writer.flush()?;
+        },
+        _ => {
+            let tensors = [NamedTensor::from_f32("data", shape, values)];
+            let metadata = [("units".to_string(), unit.name().to_string())];
+            tensor_utils::save_tensors(path, format, &tensors, &metadata)?;
+        }
+    }
+
+    info!("Saved {:?} elevations in {} to {}", shape, unit.name(), path);
+    Ok(())
+}
@@ -0,0 +1,489 @@
+//! ENVI raster utilities
+//!
+//! An ENVI raster is a headerless binary file of samples next to a plain
+//! text `.hdr` file describing it: dimensions, sample type, byte order,
+//! how bands are interleaved (BSQ, BIL or BIP) and, in `map info`, where
+//! the image lies on the map. This module parses and writes those headers
+//! and reads windows of samples from the binary file.
+
+use std::collections::HashMap;
+use std::fs::{self, File};
+use std::io::{BufWriter, Read, Seek, SeekFrom, Write};
+use std::path::{Path, PathBuf};
+
+use log::{debug, info, warn};
+
+use crate::coordinate::Affine;
+use crate::extractor::Region;
+use crate::tiff::errors::{TiffError, TiffResult};
+use crate::tiff::proj_definition::ProjDefinition;
+use crate::utils::{atomic_utils, sidecar_utils};
+use crate::utils::tensor_utils::TensorDType;
+
+/// Extensions ENVI data files commonly have, tried when resolving a header
+const DATA_EXTENSIONS: [&str; 5] = ["dat", "img", "bin", "raw", "bsq"];
+
+/// How the bands of an ENVI raster are laid out
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Interleave {
+    /// Band sequential: every row of band 1, then of band 2, ...
+    Bsq,
+    /// Band interleaved by line: row 1 of every band, then row 2, ...
+    Bil,
+    /// Band interleaved by pixel: all bands of a pixel together
+    Bip,
+}
+
+/// Parsed ENVI header
+#[derive(Debug, Clone)]
+pub struct EnviHeader {
+    /// Columns (`samples`)
+    pub samples: u32,
+    /// Rows (`lines`)
+    pub lines: u32,
+    /// Number of bands
+    pub bands: u32,
+    /// Bytes to skip at the start of the data file
+    pub header_offset: u64,
+    /// ENVI data type code
+    pub data_type: u32,
+    /// Band layout
+    pub interleave: Interleave,
+    /// Whether samples are big-endian
+    pub big_endian: bool,
+    /// Geotransform from `map info`, if present
+    pub geotransform: Option<Affine>,
+    /// EPSG code from `map info` or `coordinate system string`, if known
+    pub epsg: Option<u32>,
+    /// NoData value (`data ignore value`)
+    pub nodata: Option<f64>,
+    /// One-based red, green and blue bands for display (`default bands`)
+    pub default_bands: Vec<u32>,
+    /// Band names, if given
+    pub band_names: Vec<String>,
+}
+
+impl EnviHeader {
+    /// Bytes per sample of the data type
+    ///
+    /// # Returns
+    /// The size, or an error for complex and unknown types
+    pub fn bytes_per_sample(&self) -> TiffResult<usize> {
+        match self.data_type {
+            1 => Ok(1),
+            2 | 12 => Ok(2),
+            3 | 4 | 13 => Ok(4),
+            5 | 14 | 15 => Ok(8),
+            6 | 9 => Err(TiffError::GenericError("Complex ENVI data is not supported".to_string())),
+            other => Err(TiffError::GenericError(format!("Unknown ENVI data type {}", other))),
+        }
+    }
+
+    /// Decode one sample
+    fn decode(&self, bytes: &[u8]) -> f64 {
+        macro_rules! read {
+            ($type:ty) => {{
+                let raw = bytes.try_into().unwrap_or([0; std::mem::size_of::<$type>()]);
+                if self.big_endian { <$type>::from_be_bytes(raw) } else { <$type>::from_le_bytes(raw) }
+            }};
+        }
+        match self.data_type {
+            1 => bytes[0] as f64,
+            2 => read!(i16) as f64,
+            3 => read!(i32) as f64,
+            4 => read!(f32) as f64,
+            5 => read!(f64),
+            12 => read!(u16) as f64,
+            13 => read!(u32) as f64,
+            14 => read!(i64) as f64,
+            15 => read!(u64) as f64,
+            _ => f64::NAN,
+        }
+    }
+}
+
+/// Split a header into its keys and values
+///
+/// Values in braces may span several lines; keys are lowercased.
+fn parse_fields(text: &str) -> HashMap<String, String> {
+    let mut fields = HashMap::new();
+    let mut lines = text.lines();
+
+    while let Some(line) = lines.next() {
+        let Some((key, value)) = line.split_once('=') else {
+            continue;
+        };
+        let mut value = value.trim().to_string();
+        if value.starts_with('{') {
+            while !value.contains('}') {
+                match lines.next() {
+                    Some(next) => {
+                        value.push(' ');
+                        value.push_str(next.trim());
+                    },
+                    None => break,
+                }
+            }
+            value = value.trim_start_matches('{').trim_end_matches('}').trim().to_string();
+        }
+        fields.insert(key.trim().to_lowercase(), value);
+    }
+
+    fields
+}
+
+/// Split a braced list value into its items
+fn list_items(value: &str) -> Vec<String> {
+    value.split(',').map(|item| item.trim().to_string()).collect()
+}
+
+/// Parse `map info` into a geotransform and, where the projection is
+/// named, an EPSG code
+///
+/// # Arguments
+/// * `map_info` - The value without braces
+///
+/// # Returns
+/// The geotransform and EPSG code, or None if the value can't be used
+fn parse_map_info(map_info: &str) -> Option<(Affine, Option<u32>)> {
+    let items = list_items(map_info);
+    if items.len() < 7 {
+        warn!("Ignoring incomplete ENVI map info: {}", map_info);
+        return None;
+    }
+
+    let number = |index: usize| items.get(index).and_then(|item| item.parse::<f64>().ok());
+    let (reference_x, reference_y) = (number(1)?, number(2)?);
+    let (easting, northing) = (number(3)?, number(4)?);
+    let (pixel_x, pixel_y) = (number(5)?, number(6)?);
+
+    let rotation = items.iter()
+        .find_map(|item| item.to_lowercase().strip_prefix("rotation=").and_then(|v| v.trim().parse::<f64>().ok()))
+        .unwrap_or(0.0);
+    if rotation != 0.0 {
+        warn!("Rotated ENVI map info is not supported, ignoring the georeferencing");
+        return None;
+    }
+
+    // The reference pixel is one-based and addresses the pixel's top-left corner
+    let geotransform = Affine::new(
+        easting - (reference_x - 1.0) * pixel_x,
+        pixel_x,
+        northing + (reference_y - 1.0) * pixel_y,
+        -pixel_y,
+    );
+
+    let projection = items[0].to_lowercase();
+    let datum = items.iter().skip(7)
+        .find(|item| !item.contains('=') && item.parse::<f64>().is_err()
+            && !item.eq_ignore_ascii_case("north") && !item.eq_ignore_ascii_case("south"))
+        .map(|item| item.to_uppercase())
+        .unwrap_or_default();
+    let epsg = match projection.as_str() {
+        "utm" => {
+            let zone = number(7)? as u32;
+            let south = items.get(8).map(|item| item.eq_ignore_ascii_case("south")).unwrap_or(false);
+            match (datum.as_str(), south) {
+                ("WGS-84", false) => Some(32600 + zone),
+                ("WGS-84", true) => Some(32700 + zone),
+                ("NORTH AMERICA 1983", false) => Some(26900 + zone),
+                _ => None,
+            }
+        },
+        "geographic lat/lon" if datum == "WGS-84" => Some(4326),
+        _ => None,
+    };
+
+    Some((geotransform, epsg))
+}
+
+/// Parse an ENVI header file
+///
+/// # Arguments
+/// * `path` - Path to the `.hdr` file
+///
+/// # Returns
+/// The header, or an error if the file is not an ENVI header
+pub fn read_header(path: &str) -> TiffResult<EnviHeader> {
+    let text = fs::read_to_string(path)?;
+    if !text.trim_start().starts_with("ENVI") {
+        return Err(TiffError::GenericError(format!("{} is not an ENVI header", path)));
+    }
+    let fields = parse_fields(&text);
+
+    let required = |key: &str| -> TiffResult<u64> {
+        fields.get(key)
+            .and_then(|value| value.parse().ok())
+            .ok_or_else(|| TiffError::GenericError(format!("ENVI header {} lacks a valid '{}'", path, key)))
+    };
+    let optional = |key: &str| fields.get(key).and_then(|value| value.parse::<f64>().ok());
+
+    let interleave = match fields.get("interleave").map(|value| value.to_lowercase()).as_deref() {
+        Some("bil") => Interleave::Bil,
+        Some("bip") => Interleave::Bip,
+        Some("bsq") | None => Interleave::Bsq,
+        Some(other) => return Err(TiffError::GenericError(format!("Unknown ENVI interleave '{}'", other))),
+    };
+
+    let (geotransform, mut epsg) = match fields.get("map info").and_then(|value| parse_map_info(value)) {
+        Some((geotransform, epsg)) => (Some(geotransform), epsg),
+        None => (None, None),
+    };
+    if let Some(wkt) = fields.get("coordinate system string") {
+        epsg = sidecar_utils::epsg_from_wkt(wkt)
+            .or_else(|| ProjDefinition::from_wkt(wkt).ok().and_then(|definition| definition.equivalent_epsg()))
+            .or(epsg);
+    }
+
+    let header = EnviHeader {
+        samples: required("samples")? as u32,
+        lines: required("lines")? as u32,
+        bands: required("bands")? as u32,
+        header_offset: required("header offset").unwrap_or(0),
+        data_type: required("data type")? as u32,
+        interleave,
+        big_endian: optional("byte order") == Some(1.0),
+        geotransform,
+        epsg,
+        nodata: optional("data ignore value"),
+        default_bands: fields.get("default bands")
+            .map(|value| list_items(value).iter().filter_map(|item| item.parse().ok()).collect())
+            .unwrap_or_default(),
+        band_names: fields.get("band names").map(|value| list_items(value)).unwrap_or_default(),
+    };
+
+    info!("ENVI raster: {}x{} with {} band(s), data type {}, {:?}",
+          header.samples, header.lines, header.bands, header.data_type, header.interleave);
+    Ok(header)
+}
+
+/// Find the header and data file of an ENVI raster
+///
+/// Accepts either file: a `.hdr` path looks for a data file with the same
+/// stem, any other path for `<stem>.hdr` or `<path>.hdr`.
+///
+/// # Arguments
+/// * `path` - Path to the header or the data file
+///
+/// # Returns
+/// The header path and the data path
+pub fn resolve_paths(path: &str) -> TiffResult<(PathBuf, PathBuf)> {
+    let given = Path::new(path);
+    let is_header = given.extension()
+        .map(|extension| extension.eq_ignore_ascii_case("hdr"))
+        .unwrap_or(false);
+
+    if is_header {
+        let candidates = DATA_EXTENSIONS.iter()
+            .map(|extension| given.with_extension(extension))
+            .chain(std::iter::once(given.with_extension("")));
+        for candidate in candidates {
+            if candidate.is_file() {
+                debug!("ENVI data file: {}", candidate.display());
+                return Ok((given.to_path_buf(), candidate));
+            }
+        }
+        return Err(TiffError::GenericError(format!("No data file found next to ENVI header {}", path)));
+    }
+
+    let candidates = [given.with_extension("hdr"), PathBuf::from(format!("{}.hdr", path))];
+    candidates.into_iter()
+        .find(|candidate| candidate.is_file())
+        .map(|header| (header, given.to_path_buf()))
+        .ok_or_else(|| TiffError::GenericError(format!("No ENVI header found for {}", path)))
+}
+
+/// Read a window of samples of some bands
+///
+/// # Arguments
+/// * `header` - Header of the raster
+/// * `data_path` - Path to the data file
+/// * `region` - Window to read, inside the raster
+/// * `bands` - Zero-based bands to read
+///
+/// # Returns
+/// The samples of each requested band, row by row
+pub fn read_window(header: &EnviHeader, data_path: &Path, region: &Region,
+                   bands: &[u32]) -> TiffResult<Vec<Vec<f64>>> {
+    if region.width == 0 || region.height == 0
+        || region.x.saturating_add(region.width) > header.samples
+        || region.y.saturating_add(region.height) > header.lines {
+        return Err(TiffError::GenericError(format!(
+            "Region {}x{} at ({}, {}) is outside the {}x{} raster",
+            region.width, region.height, region.x, region.y, header.samples, header.lines)));
+    }
+    if let Some(band) = bands.iter().find(|&&band| band >= header.bands) {
+        return Err(TiffError::GenericError(format!(
+            "Band {} does not exist, the raster has {}", band + 1, header.bands)));
+    }
+
+    let size = header.bytes_per_sample()?;
+    let (samples, lines, band_count) = (header.samples as u64, header.lines as u64, header.bands as u64);
+    let width = region.width as usize;
+    let mut file = File::open(data_path)?;
+    let mut output = vec![Vec::with_capacity(width * region.height as usize); bands.len()];
+
+    // BIP rows hold every band, so they are read once for all bands
+    let row_bytes = if header.interleave == Interleave::Bip { width * band_count as usize * size } else { width * size };
+    let mut row = vec![0u8; row_bytes];
+
+    for y in region.y as u64..(region.y + region.height) as u64 {
+        match header.interleave {
+            Interleave::Bip => {
+                let offset = ((y * samples + region.x as u64) * band_count) * size as u64;
+                file.seek(SeekFrom::Start(header.header_offset + offset))?;
+                file.read_exact(&mut row)?;
+                for (out, &band) in output.iter_mut().zip(bands) {
+                    out.extend(row.chunks_exact(size).skip(band as usize).step_by(band_count as usize)
+                        .map(|sample| header.decode(sample)));
+                }
+            },
+            interleave => {
+                for (out, &band) in output.iter_mut().zip(bands) {
+                    let row_index = if interleave == Interleave::Bsq {
+                        band as u64 * lines + y
+                    } else {
+                        y * band_count + band as u64
+                    };
+                    let offset = (row_index * samples + region.x as u64) * size as u64;
+                    file.seek(SeekFrom::Start(header.header_offset + offset))?;
+                    file.read_exact(&mut row)?;
+                    out.extend(row.chunks_exact(size).map(|sample| header.decode(sample)));
+                }
+            },
+        }
+    }
+
+    Ok(output)
+}
+
+/// Format `map info` and, when the CRS is known, `coordinate system string`
+/// entries for a geotransform
+///
+/// # Arguments
+/// * `geotransform` - Geotransform of the raster, north-up
+/// * `epsg` - EPSG code of its CRS, if known
+///
+/// # Returns
+/// Header lines, empty for rotated geotransforms
+fn georeference_lines(geotransform: &Affine, epsg: Option<u32>) -> Vec<String> {
+    if !geotransform.is_north_up() {
+        warn!("ENVI map info can't hold a rotated geotransform, writing no georeferencing");
+        return Vec::new();
+    }
+
+    let (x, y) = (geotransform.origin_x, geotransform.origin_y);
+    let (pixel_x, pixel_y) = (geotransform.pixel_width, -geotransform.pixel_height);
+    let projection = match epsg {
+        Some(code @ 32601..=32660) => format!("UTM, 1, 1, {}, {}, {}, {}, {}, North, WGS-84, units=Meters",
+                                              x, y, pixel_x, pixel_y, code - 32600),
+        Some(code @ 32701..=32760) => format!("UTM, 1, 1, {}, {}, {}, {}, {}, South, WGS-84, units=Meters",
+                                              x, y, pixel_x, pixel_y, code - 32700),
+        Some(4326) => format!("Geographic Lat/Lon, 1, 1, {}, {}, {}, {}, WGS-84, units=Degrees",
+                              x, y, pixel_x, pixel_y),
+        _ => format!("Arbitrary, 1, 1, {}, {}, {}, {}, 0, North", x, y, pixel_x, pixel_y),
+    };
+
+    let mut lines = vec![format!("map info = {{{}}}", projection)];
+    if let Some(definition) = epsg.and_then(ProjDefinition::from_epsg) {
+        lines.push(format!("coordinate system string = {{{}}}", definition.to_wkt()));
+    }
+    lines
+}
+
+/// ENVI data type code of a sample type
+///
+/// # Arguments
+/// * `dtype` - Sample type
+///
+/// # Returns
+/// The code for the header's `data type` field
+pub fn data_type_code(dtype: TensorDType) -> u32 {
+    match dtype {
+        TensorDType::U8 => 1,
+        TensorDType::I16 => 2,
+        TensorDType::I32 => 3,
+        TensorDType::F32 => 4,
+        TensorDType::F64 => 5,
+        TensorDType::U16 => 12,
+        TensorDType::U32 => 13,
+    }
+}
+
+/// Sample type that holds the values of an ENVI data type
+///
+/// # Arguments
+/// * `data_type` - ENVI data type code
+///
+/// # Returns
+/// The sample type; 64-bit integers are held as 64-bit floats
+pub fn sample_type(data_type: u32) -> TensorDType {
+    match data_type {
+        1 => TensorDType::U8,
+        2 => TensorDType::I16,
+        3 => TensorDType::I32,
+        4 => TensorDType::F32,
+        12 => TensorDType::U16,
+        13 => TensorDType::U32,
+        _ => TensorDType::F64,
+    }
+}
+
+/// Write a single-band ENVI raster
+///
+/// The samples go to the given path, or next to it with a `.dat`
+/// extension when the path names the header; the header is written with
+/// the data file's stem and a `.hdr` extension. Both go through partial
+/// files, the header moved into place last.
+///
+/// # Arguments
+/// * `path` - Output path, of the data file or the header
+/// * `width` - Columns
+/// * `height` - Rows
+/// * `data_type` - ENVI data type code of the samples
+/// * `data` - Little-endian samples, row by row
+/// * `georeference` - Geotransform and EPSG code, if known
+/// * `nodata` - NoData value, if any
+///
+/// # Returns
+/// The paths of the header and data file
+pub fn write_envi(path: &str, width: u32, height: u32, data_type: u32, data: &[u8],
+                  georeference: Option<(&Affine, Option<u32>)>,
+                  nodata: Option<f64>) -> TiffResult<(PathBuf, PathBuf)> {
+    let given = Path::new(path);
+    let data_path = match given.extension() {
+        Some(extension) if extension.eq_ignore_ascii_case("hdr") => given.with_extension("dat"),
+        _ => given.to_path_buf(),
+    };
+    let header_path = data_path.with_extension("hdr");
+
+    atomic_utils::write_all_atomically(&[&data_path, &header_path], |partials| {
+        fs::write(&partials[0], data)?;
+
+        let mut header = BufWriter::new(File::create(&partials[1])?);
+        writeln!(header, "ENVI")?;
+        writeln!(header, "description = {{Extracted by rasterkit}}")?;
+        writeln!(header, "samples = {}", width)?;
+        writeln!(header, "lines = {}", height)?;
+        writeln!(header, "bands = 1")?;
+        writeln!(header, "header offset = 0")?;
+        writeln!(header, "file type = ENVI Standard")?;
+        writeln!(header, "data type = {}", data_type)?;
+        writeln!(header, "interleave = bsq")?;
+        writeln!(header, "byte order = 0")?;
+        if let Some((geotransform, epsg)) = georeference {
+            for line in georeference_lines(geotransform, epsg) {
+                writeln!(header, "{}", line)?;
+            }
+        }
+        if let Some(nodata) = nodata {
+            writeln!(header, "data ignore value = {}", nodata)?;
+        }
+        writeln!(header, "band names = {{Band 1}}")?;
+        header.flush()?;
+        Ok(())
+    })?;
+
+    info!("Wrote ENVI raster {} with header {}", data_path.display(), header_path.display());
+    Ok((header_path, data_path))
+}
@@ -0,0 +1,386 @@
+//! EXIF and XMP metadata utilities
+//!
+//! Drone and camera imagery records its capture metadata (time, exposure,
+//! camera, GPS position) in an EXIF sub-IFD referenced from the main IFD,
+//! and often an XMP packet alongside it. This module reads both, formats
+//! them for display, and packs EXIF directories into self-contained blobs
+//! so the TIFF writer can place them in new files.
+//!
+//! Blobs use the classic little-endian IFD layout with value offsets
+//! relative to the start of the blob; `relocate_directory` turns them into
+//! absolute offsets once the writer knows where the blob lands.
+
+use std::io::{Read, Seek, SeekFrom};
+
+use log::{debug, info, warn};
+
+use crate::io::seekable::SeekableReader;
+use crate::tiff::TiffReader;
+use crate::tiff::constants::{tags, field_types};
+use crate::tiff::errors::TiffResult;
+use crate::tiff::ifd::{IFD, IFDEntry};
+use crate::utils::tiff_utils;
+
+/// Interoperability IFD pointer inside the EXIF directory (not carried over)
+const INTEROPERABILITY_IFD: u16 = 40965;
+
+/// A single EXIF/GPS entry with its value bytes in little-endian order
+#[derive(Debug, Clone, PartialEq)]
+pub struct ExifEntry {
+    /// Tag number (EXIF and GPS tags share numbers, so keep the directory in mind)
+    pub tag: u16,
+    /// TIFF field type
+    pub field_type: u16,
+    /// Number of values
+    pub count: u64,
+    /// Raw value bytes, little-endian
+    pub data: Vec<u8>,
+}
+
+/// Read the EXIF or GPS directory referenced by a pointer tag
+///
+/// Nested pointers (the interoperability IFD) are skipped, since their
+/// targets are not read.
+///
+/// # Arguments
+/// * `reader` - Reader that loaded the file containing `ifd`
+/// * `ifd` - The main image IFD
+/// * `pointer_tag` - `tags::EXIF_IFD` or `tags::GPS_IFD`
+///
+/// # Returns
+/// The directory entries, None when the pointer is absent, or an error
+pub fn read_directory(reader: &TiffReader, ifd: &IFD, pointer_tag: u16) -> TiffResult<Option<Vec<ExifEntry>>> {
+    let offset = match ifd.get_entry(pointer_tag) {
+        Some(entry) => entry.value_offset,
+        None => return Ok(None),
+    };
+
+    let mut file = reader.create_reader()?;
+    let big_endian = is_big_endian(&mut file)?;
+    let sub_ifd = reader.read_ifd(&mut file, offset, 0)?;
+    let inline_size = if reader.is_big_tiff() { 8 } else { 4 };
+
+    let mut entries = Vec::with_capacity(sub_ifd.entries.len());
+    for entry in &sub_ifd.entries {
+        if entry.tag == INTEROPERABILITY_IFD {
+            debug!("Skipping interoperability IFD pointer in tag {} directory", pointer_tag);
+            continue;
+        }
+
+        let unit = tiff_utils::get_field_type_size(entry.field_type);
+        let size = unit * entry.count as usize;
+        let mut data = if size <= inline_size {
+            // Inline values were decoded as one integer; recover the raw bytes
+            let raw = match (big_endian, inline_size) {
+                (false, 4) => (entry.value_offset as u32).to_le_bytes().to_vec(),
+                (true, 4) => (entry.value_offset as u32).to_be_bytes().to_vec(),
+                (false, _) => entry.value_offset.to_le_bytes().to_vec(),
+                (true, _) => entry.value_offset.to_be_bytes().to_vec(),
+            };
+            raw[..size].to_vec()
+        } else {
+            let mut bytes = vec![0u8; size];
+            file.seek(SeekFrom::Start(entry.value_offset))?;
+            file.read_exact(&mut bytes)?;
+            bytes
+        };
+
+        if big_endian {
+            swap_to_little_endian(&mut data, entry.field_type);
+        }
+
+        entries.push(ExifEntry { tag: entry.tag, field_type: entry.field_type, count: entry.count, data });
+    }
+
+    info!("Read {} entries from tag {} directory at offset {}", entries.len(), pointer_tag, offset);
+    Ok(Some(entries))
+}
+
+/// Read the out-of-line bytes of a byte-sized tag (ASCII, BYTE, UNDEFINED)
+///
+/// # Arguments
+/// * `reader` - Reader that loaded the file containing the entry
+/// * `entry` - Entry whose data is stored at its offset
+///
+/// # Returns
+/// The raw bytes or an error
+pub fn read_tag_bytes(reader: &TiffReader, entry: &IFDEntry) -> TiffResult<Vec<u8>> {
+    let mut data = vec![0u8; entry.count as usize];
+    let mut file = reader.create_reader()?;
+    file.seek(SeekFrom::Start(entry.value_offset))?;
+    file.read_exact(&mut data)?;
+    Ok(data)
+}
+
+/// Read the XMP packet of an IFD
+///
+/// # Arguments
+/// * `reader` - Reader that loaded the file containing `ifd`
+/// * `ifd` - The IFD to read from
+///
+/// # Returns
+/// The raw XMP bytes if the tag is present and readable
+pub fn read_xmp(reader: &TiffReader, ifd: &IFD) -> Option<Vec<u8>> {
+    let entry = ifd.get_entry(tags::XMP)?;
+    if entry.count <= 4 {
+        return None;
+    }
+
+    match read_tag_bytes(reader, entry) {
+        Ok(data) => Some(data),
+        Err(e) => {
+            warn!("Failed to read XMP packet: {:?}", e);
+            None
+        }
+    }
+}
+
+/// Check the byte order marker of an open TIFF file
+fn is_big_endian(file: &mut dyn SeekableReader) -> TiffResult<bool> {
+    let mut marker = [0u8; 2];
+    file.seek(SeekFrom::Start(0))?;
+    file.read_exact(&mut marker)?;
+    Ok(&marker == b"MM")
+}
+
+/// Reverse each numeric component of big-endian value bytes in place
+fn swap_to_little_endian(data: &mut [u8], field_type: u16) {
+    // Rationals are pairs of 32-bit integers, so they swap in 4-byte units
+    let unit = match field_type {
+        field_types::RATIONAL | field_types::SRATIONAL => 4,
+        _ => tiff_utils::get_field_type_size(field_type),
+    };
+    if unit > 1 {
+        for chunk in data.chunks_mut(unit) {
+            chunk.reverse();
+        }
+    }
+}
+
+/// Pack a directory into a classic little-endian IFD blob
+///
+/// Entries are sorted by tag. Values larger than four bytes follow the
+/// IFD, with offsets relative to the start of the blob.
+///
+/// # Arguments
+/// * `entries` - Directory entries to pack
+///
+/// # Returns
+/// The packed directory
+pub fn pack_directory(entries: &[ExifEntry]) -> Vec<u8> {
+    let mut sorted: Vec<&ExifEntry> = entries.iter().collect();
+    sorted.sort_by_key(|e| e.tag);
+
+    let ifd_size = 2 + 12 * sorted.len() + 4;
+    let mut blob = Vec::with_capacity(ifd_size);
+    let mut values = Vec::new();
+
+    blob.extend_from_slice(&(sorted.len() as u16).to_le_bytes());
+    for entry in &sorted {
+        blob.extend_from_slice(&entry.tag.to_le_bytes());
+        blob.extend_from_slice(&entry.field_type.to_le_bytes());
+        blob.extend_from_slice(&(entry.count as u32).to_le_bytes());
+
+        if entry.data.len() <= 4 {
+            let mut inline = [0u8; 4];
+            inline[..entry.data.len()].copy_from_slice(&entry.data);
+            blob.extend_from_slice(&inline);
+        } else {
+            let offset = (ifd_size + values.len()) as u32;
+            blob.extend_from_slice(&offset.to_le_bytes());
+            values.extend_from_slice(&entry.data);
+            if values.len() % 2 != 0 {
+                values.push(0);
+            }
+        }
+    }
+    blob.extend_from_slice(&0u32.to_le_bytes());
+    blob.extend_from_slice(&values);
+    blob
+}
+
+/// Turn the relative value offsets of a packed directory into file offsets
+///
+/// # Arguments
+/// * `blob` - Directory produced by `pack_directory`
+/// * `base` - File offset the blob will be written at
+///
+/// # Returns
+/// The blob with absolute offsets
+pub fn relocate_directory(blob: &[u8], base: u64) -> Vec<u8> {
+    let mut relocated = blob.to_vec();
+    if blob.len() < 2 {
+        return relocated;
+    }
+
+    let count = u16::from_le_bytes([blob[0], blob[1]]) as usize;
+    for i in 0..count {
+        let start = 2 + 12 * i;
+        let Some(raw) = blob.get(start..start + 12) else { break };
+        let field_type = u16::from_le_bytes([raw[2], raw[3]]);
+        let count = u32::from_le_bytes([raw[4], raw[5], raw[6], raw[7]]) as usize;
+
+        if tiff_utils::get_field_type_size(field_type) * count > 4 {
+            let relative = u32::from_le_bytes([raw[8], raw[9], raw[10], raw[11]]) as u64;
+            relocated[start + 8..start + 12].copy_from_slice(&((relative + base) as u32).to_le_bytes());
+        }
+    }
+    relocated
+}
+
+/// Human-readable name of an EXIF or GPS tag
+///
+/// # Arguments
+/// * `pointer_tag` - Directory the tag came from (`tags::EXIF_IFD` or `tags::GPS_IFD`)
+/// * `tag` - Tag number within that directory
+///
+/// # Returns
+/// The tag name, or "Unknown"
+pub fn tag_name(pointer_tag: u16, tag: u16) -> &'static str {
+    if pointer_tag == tags::GPS_IFD {
+        return match tag {
+            0 => "GPSVersionID",
+            1 => "GPSLatitudeRef",
+            2 => "GPSLatitude",
+            3 => "GPSLongitudeRef",
+            4 => "GPSLongitude",
+            5 => "GPSAltitudeRef",
+            6 => "GPSAltitude",
+            7 => "GPSTimeStamp",
+            12 => "GPSSpeedRef",
+            13 => "GPSSpeed",
+            16 => "GPSImgDirectionRef",
+            17 => "GPSImgDirection",
+            18 => "GPSMapDatum",
+            29 => "GPSDateStamp",
+            _ => "Unknown",
+        };
+    }
+
+    match tag {
+        33434 => "ExposureTime",
+        33437 => "FNumber",
+        34850 => "ExposureProgram",
+        34855 => "ISOSpeedRatings",
+        36864 => "ExifVersion",
+        36867 => "DateTimeOriginal",
+        36868 => "DateTimeDigitized",
+        36880 => "OffsetTime",
+        37377 => "ShutterSpeedValue",
+        37378 => "ApertureValue",
+        37380 => "ExposureBiasValue",
+        37383 => "MeteringMode",
+        37385 => "Flash",
+        37386 => "FocalLength",
+        37500 => "MakerNote",
+        37510 => "UserComment",
+        37520 => "SubSecTime",
+        40961 => "ColorSpace",
+        40962 => "PixelXDimension",
+        40963 => "PixelYDimension",
+        41986 => "ExposureMode",
+        41987 => "WhiteBalance",
+        41989 => "FocalLengthIn35mmFilm",
+        42016 => "ImageUniqueID",
+        42033 => "BodySerialNumber",
+        42034 => "LensSpecification",
+        42035 => "LensMake",
+        42036 => "LensModel",
+        _ => "Unknown",
+    }
+}
+
+/// Decode the values of an entry as floating point numbers
+///
+/// # Arguments
+/// * `entry` - The entry to decode
+///
+/// # Returns
+/// The numeric values; empty for text and opaque data
+pub fn numeric_values(entry: &ExifEntry) -> Vec<f64> {
+    let d = &entry.data;
+    let u32_at = |i: usize| u32::from_le_bytes([d[i], d[i + 1], d[i + 2], d[i + 3]]);
+
+    match entry.field_type {
+        field_types::BYTE => d.iter().map(|&v| v as f64).collect(),
+        field_types::SHORT => d.chunks_exact(2).map(|c| u16::from_le_bytes([c[0], c[1]]) as f64).collect(),
+        field_types::SSHORT => d.chunks_exact(2).map(|c| i16::from_le_bytes([c[0], c[1]]) as f64).collect(),
+        field_types::LONG => (0..d.len() / 4).map(|i| u32_at(i * 4) as f64).collect(),
+        field_types::SLONG => (0..d.len() / 4).map(|i| u32_at(i * 4) as i32 as f64).collect(),
+        field_types::RATIONAL => (0..d.len() / 8)
+            .map(|i| (u32_at(i * 8), u32_at(i * 8 + 4)))
+            .map(|(num, den)| if den == 0 { 0.0 } else { num as f64 / den as f64 })
+            .collect(),
+        field_types::SRATIONAL => (0..d.len() / 8)
+            .map(|i| (u32_at(i * 8) as i32, u32_at(i * 8 + 4) as i32))
+            .map(|(num, den)| if den == 0 { 0.0 } else { num as f64 / den as f64 })
+            .collect(),
+        field_types::FLOAT => (0..d.len() / 4).map(|i| f32::from_bits(u32_at(i * 4)) as f64).collect(),
+        field_types::DOUBLE => d.chunks_exact(8)
+            .map(|c| f64::from_le_bytes([c[0], c[1], c[2], c[3], c[4], c[5], c[6], c[7]]))
+            .collect(),
+        _ => Vec::new(),
+    }
+}
+
+/// Format the value of an entry for display
+///
+/// # Arguments
+/// * `entry` - The entry to format
+///
+/// # Returns
+/// The value as text; long numeric lists and binary blobs are summarized
+pub fn format_value(entry: &ExifEntry) -> String {
+    match entry.field_type {
+        field_types::ASCII => String::from_utf8_lossy(&entry.data).trim_end_matches('\0').trim().to_string(),
+        field_types::UNDEFINED => {
+            // Short printable values such as ExifVersion ("0231") read best as text
+            if entry.data.len() <= 8 && entry.data.iter().all(|b| b.is_ascii_graphic()) {
+                String::from_utf8_lossy(&entry.data).to_string()
+            } else {
+                format!("<{} bytes>", entry.data.len())
+            }
+        },
+        _ => {
+            let values = numeric_values(entry);
+            if values.is_empty() {
+                return format!("<{} bytes>", entry.data.len());
+            }
+            let shown: Vec<String> = values.iter().take(8).map(|v| format!("{}", v)).collect();
+            let suffix = if values.len() > 8 { format!(", ... ({} values)", values.len()) } else { String::new() };
+            format!("{}{}", shown.join(", "), suffix)
+        }
+    }
+}
+
+/// Decode the camera position from a GPS directory
+///
+/// # Arguments
+/// * `gps` - Entries of the GPS directory
+///
+/// # Returns
+/// (latitude, longitude, altitude) in decimal degrees and meters, if present
+pub fn gps_position(gps: &[ExifEntry]) -> Option<(f64, f64, Option<f64>)> {
+    let find = |tag: u16| gps.iter().find(|e| e.tag == tag);
+    let degrees = |tag: u16| {
+        let values = numeric_values(find(tag)?);
+        (values.len() == 3).then(|| values[0] + values[1] / 60.0 + values[2] / 3600.0)
+    };
+    let is_ref = |tag: u16, negative: u8| find(tag).is_some_and(|e| e.data.first() == Some(&negative));
+
+    let mut latitude = degrees(2)?;
+    let mut longitude = degrees(4)?;
+    if is_ref(1, b'S') {
+        latitude = -latitude;
+    }
+    if is_ref(3, b'W') {
+        longitude = -longitude;
+    }
+
+    // Altitude reference 1 means below sea level
+    let altitude = find(6)
+        .and_then(|e| numeric_values(e).first().copied())
+        .map(|alt| if is_ref(5, 1) { -alt } else { alt });
+
+    Some((latitude, longitude, altitude))
+}
@@ -0,0 +1,110 @@
+//! Raster extent utilities
+//!
+//! Mosaics and band math line rasters up pixel for pixel, which goes
+//! wrong when one covers less ground than the others. Extending grows a
+//! raster to a larger bounding box on its own pixel grid: the samples are
+//! copied unchanged and the new border is filled with NoData, so the
+//! result covers the box without any resampling.
+
+use log::{info, warn};
+
+use crate::coordinate::BoundingBox;
+use crate::extractor::{self, BufferLayout, Padding};
+use crate::tiff::builder::TiffBuilder;
+use crate::tiff::constants::tags;
+use crate::tiff::errors::{TiffError, TiffResult};
+use crate::tiff::ifd::IFD;
+use crate::tiff::TiffReader;
+use crate::utils::logger::Logger;
+use crate::utils::{grid_utils, image_extraction_utils, info_utils, memory_utils, tiff_extraction_utils};
+
+/// Extend a raster to cover a bounding box
+///
+/// The output grid is the source grid grown outward to whole pixels
+/// around the box. Sides of the source already beyond the box are kept,
+/// so nothing is cropped. Samples are written uncompressed in one strip.
+///
+/// # Arguments
+/// * `input_path` - Georeferenced raster to extend
+/// * `output_path` - Path of the GeoTIFF to write
+/// * `bbox` - Box to cover, in the coordinate system of the raster
+/// * `nodata` - Value of the new pixels, instead of the source NoData
+/// * `logger` - Logger for recording operations
+///
+/// # Returns
+/// The size of the output and where the source pixels are in it
+pub fn extend(input_path: &str, output_path: &str, bbox: &BoundingBox, nodata: Option<f64>,
+              logger: &Logger) -> TiffResult<Padding> {
+    let mut reader = TiffReader::new(logger);
+    let tiff = reader.load(input_path)?;
+    let source_ifd = tiff.main_ifd()
+        .ok_or_else(|| TiffError::GenericError("No IFDs found in input file".to_string()))?;
+    let georeferencing = info_utils::read_georeferencing(&tiff, source_ifd, &reader, input_path)
+        .ok_or_else(|| TiffError::GenericError(format!("{} is not georeferenced, so it cannot be extended to a bounding box", input_path)))?;
+
+    let shape = extractor::describe_window(input_path, None, logger)?;
+    if shape.bytes_per_sample == 0 {
+        return Err(TiffError::GenericError(format!(
+            "The sub-byte samples of {} cannot be extended", input_path)));
+    }
+    let (width, height) = (shape.width, shape.height);
+
+    let (min_x, min_y, max_x, max_y) = georeferencing.geotransform.pixel_window(bbox)
+        .ok_or_else(|| TiffError::GenericError(format!("The geotransform of {} is degenerate", input_path)))?;
+    let (min_x, min_y) = (min_x.min(0), min_y.min(0));
+    let (max_x, max_y) = (max_x.max(width as i64), max_y.max(height as i64));
+    if (max_x - min_x, max_y - min_y) == (width as i64, height as i64) {
+        warn!("{} already covers the bounding box, copying it unchanged", input_path);
+    }
+    let padding = Padding {
+        offset_x: -min_x,
+        offset_y: -min_y,
+        width: u32::try_from(max_x - min_x).map_err(|_| TiffError::GenericError(
+            "The extended raster would be too wide".to_string()))?,
+        height: u32::try_from(max_y - min_y).map_err(|_| TiffError::GenericError(
+            "The extended raster would be too tall".to_string()))?,
+        fill: nodata,
+    };
+
+    let source_nodata = source_ifd.has_tag(tags::GDAL_NODATA)
+        .then(|| tiff_extraction_utils::extract_nodata_value(source_ifd, &reader).trim().to_string());
+    let nodata_text = match (nodata, &source_nodata) {
+        (Some(value), _) => value.to_string(),
+        (None, Some(text)) => text.clone(),
+        (None, None) => {
+            warn!("{} declares no NoData value, the new pixels are 0", input_path);
+            "0".to_string()
+        },
+    };
+    let fill = nodata_text.parse::<f64>().map_err(|e| TiffError::GenericError(format!(
+        "NoData value '{}' of {} is not a number: {}", nodata_text, input_path, e)))?;
+
+    let pixel_bytes = shape.bands * shape.bytes_per_sample;
+    let output_len = padding.width as usize * padding.height as usize * pixel_bytes;
+    memory_utils::check_fits(&format!("Extending to {}x{}", padding.width, padding.height),
+                             (shape.byte_len() + output_len) as u64)?;
+    info!("Extending {} from {}x{} to {}x{}, its pixels at ({}, {})",
+          input_path, width, height, padding.width, padding.height, padding.offset_x, padding.offset_y);
+
+    let mut samples = vec![0u8; shape.byte_len()];
+    extractor::decode_into_bytes(input_path, None, BufferLayout::Interleaved, &mut samples, logger)?;
+    // Samples are decoded in the byte order of this machine, outputs are little-endian
+    if cfg!(target_endian = "big") {
+        samples.chunks_exact_mut(shape.bytes_per_sample).for_each(|sample| sample.reverse());
+    }
+
+    let extended = image_extraction_utils::pad_samples(&samples, &shape, &padding, fill);
+
+    let mut builder = TiffBuilder::new(logger, tiff.is_big_tiff || output_len as u64 > u32::MAX as u64);
+    let ifd_index = builder.add_ifd(IFD::new(0, 0));
+    grid_utils::add_derived_image(&mut builder, ifd_index, &reader, source_ifd,
+                                  (padding.width, padding.height), extended, &[tags::GDAL_NODATA]);
+    builder.add_nodata_tag(ifd_index, &nodata_text);
+
+    let geotransform = padding.shift(&georeferencing.geotransform);
+    grid_utils::georeference_derived(&mut builder, ifd_index, source_ifd, &georeferencing, &geotransform)?;
+
+    builder.write(output_path)?;
+    logger.log(&format!("Extended {} to {}x{} in {}", input_path, padding.width, padding.height, output_path))?;
+    Ok(padding)
+}
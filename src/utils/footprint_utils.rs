@@ -0,0 +1,386 @@
+//! Raster footprint utilities
+//!
+//! Writes where a raster lies as a GeoJSON polygon for catalogs, either
+//! its bounding rectangle or an outline traced around the pixels that hold
+//! data. The outline follows the pixel edges of a valid-data mask (cells
+//! are merged on large rasters so the mask stays under `MAX_TRACE_CELLS`
+//! a side), keeps holes and separate islands, and is simplified before
+//! the vertices are moved to WGS84 longitude/latitude as RFC 7946 asks.
+
+use std::collections::HashMap;
+use std::fs::File;
+use std::io::{BufReader, BufWriter, Write};
+
+use log::{debug, info, warn};
+
+use crate::coordinate::{datum, projection};
+use crate::tiff::constants::tags;
+use crate::tiff::errors::{TiffError, TiffResult};
+use crate::tiff::TiffReader;
+use crate::utils::info_utils::{self, Georeferencing};
+use crate::utils::logger::Logger;
+use crate::utils::sample_utils::{self, SampleFormat};
+use crate::utils::tiff_extraction_utils;
+use crate::utils::valid_mask_utils;
+
+/// Longest side of the valid-data mask, in cells
+pub const MAX_TRACE_CELLS: usize = 1024;
+
+/// Points per side of a rectangle reprojected to longitude/latitude
+const RECTANGLE_EDGE_POINTS: usize = 16;
+
+/// Largest distance, in mask cells, simplification may move the outline
+const SIMPLIFY_TOLERANCE: f64 = 1.0;
+
+/// A ring of (x, y) vertices, not repeating the first one at the end
+pub type Ring = Vec<(f64, f64)>;
+
+/// A polygon as its outer ring followed by its holes
+pub type Polygon = Vec<Ring>;
+
+/// Shape of the footprint to write
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum FootprintShape {
+    /// The raster's bounding rectangle
+    Rectangle,
+    /// The outline of the pixels holding data
+    Outline,
+}
+
+/// Twice the signed area of a ring, positive when counterclockwise in a
+/// y-up system
+fn signed_area(ring: &[(f64, f64)]) -> f64 {
+    ring.iter().zip(ring.iter().cycle().skip(1))
+        .map(|(&(x1, y1), &(x2, y2))| x1 * y2 - x2 * y1)
+        .sum()
+}
+
+/// Whether a point lies inside a ring, by ray casting
+fn contains(ring: &[(f64, f64)], (x, y): (f64, f64)) -> bool {
+    let mut inside = false;
+    for (&(x1, y1), &(x2, y2)) in ring.iter().zip(ring.iter().cycle().skip(1)) {
+        if (y1 > y) != (y2 > y) && x < x1 + (y - y1) / (y2 - y1) * (x2 - x1) {
+            inside = !inside;
+        }
+    }
+    inside
+}
+
+/// Drop vertices in the middle of straight runs
+fn remove_collinear(ring: Ring) -> Ring {
+    let count = ring.len();
+    (0..count)
+        .filter(|&i| {
+            let (px, py) = ring[(i + count - 1) % count];
+            let (x, y) = ring[i];
+            let (nx, ny) = ring[(i + 1) % count];
+            (x - px) * (ny - y) - (y - py) * (nx - x) != 0.0
+        })
+        .map(|i| ring[i])
+        .collect()
+}
+
+/// Douglas-Peucker simplification of an open chain, endpoints kept
+fn simplify_chain(points: &[(f64, f64)], tolerance: f64, kept: &mut Vec<(f64, f64)>) {
+    let (Some(&(x1, y1)), Some(&(x2, y2))) = (points.first(), points.last()) else {
+        return;
+    };
+    let length = ((x2 - x1).powi(2) + (y2 - y1).powi(2)).sqrt();
+    let distance = |&(x, y): &(f64, f64)| if length == 0.0 {
+        ((x - x1).powi(2) + (y - y1).powi(2)).sqrt()
+    } else {
+        ((x2 - x1) * (y1 - y) - (x1 - x) * (y2 - y1)).abs() / length
+    };
+
+    let farthest = (1..points.len().saturating_sub(1))
+        .max_by(|&a, &b| distance(&points[a]).total_cmp(&distance(&points[b])));
+    match farthest {
+        Some(index) if distance(&points[index]) > tolerance => {
+            simplify_chain(&points[..=index], tolerance, kept);
+            simplify_chain(&points[index..], tolerance, kept);
+        },
+        _ => kept.push(points[0]),
+    }
+}
+
+/// Simplify a closed ring, keeping it a polygon
+///
+/// # Arguments
+/// * `ring` - Ring to simplify
+/// * `tolerance` - Largest distance a vertex may be moved by
+///
+/// # Returns
+/// The simplified ring, or the ring itself if simplifying would collapse it
+pub fn simplify_ring(ring: &[(f64, f64)], tolerance: f64) -> Ring {
+    if ring.len() <= 4 {
+        return ring.to_vec();
+    }
+
+    // Split the ring at the vertex farthest from the first one
+    let first = ring[0];
+    let split = (1..ring.len())
+        .max_by(|&a, &b| {
+            let distance = |(x, y): (f64, f64)| (x - first.0).powi(2) + (y - first.1).powi(2);
+            distance(ring[a]).total_cmp(&distance(ring[b]))
+        })
+        .unwrap_or(ring.len() / 2);
+    let mut closed = ring.to_vec();
+    closed.push(first);
+
+    let mut kept = Vec::new();
+    simplify_chain(&closed[..=split], tolerance, &mut kept);
+    simplify_chain(&closed[split..], tolerance, &mut kept);
+
+    if kept.len() < 3 || signed_area(&kept).abs() < f64::EPSILON {
+        ring.to_vec()
+    } else {
+        kept
+    }
+}
+
+/// Trace the outline of the set cells of a mask
+///
+/// Rings follow the cell edges. Cells touching only at a corner belong to
+/// separate rings, and a polygon's holes are listed after its outer ring.
+///
+/// # Arguments
+/// * `mask` - Cells in row-major order, true where the raster holds data
+/// * `width` - Columns of the mask
+/// * `height` - Rows of the mask
+///
+/// # Returns
+/// The polygons in cell coordinates, y growing downwards
+pub fn trace_outline(mask: &[bool], width: usize, height: usize) -> Vec<Polygon> {
+    let set = |x: isize, y: isize| x >= 0 && y >= 0 && (x as usize) < width && (y as usize) < height
+        && mask[y as usize * width + x as usize];
+    let vertex = |x: usize, y: usize| y * (width + 1) + x;
+
+    // Directed edges with the set cell on their left when drawn y-down
+    let mut edges: HashMap<usize, Vec<usize>> = HashMap::new();
+    for y in 0..height {
+        for x in 0..width {
+            if !mask[y * width + x] {
+                continue;
+            }
+            let (cx, cy) = (x as isize, y as isize);
+            let mut add = |from: usize, to: usize| edges.entry(from).or_default().push(to);
+            if !set(cx, cy - 1) { add(vertex(x + 1, y), vertex(x, y)); }
+            if !set(cx - 1, cy) { add(vertex(x, y), vertex(x, y + 1)); }
+            if !set(cx, cy + 1) { add(vertex(x, y + 1), vertex(x + 1, y + 1)); }
+            if !set(cx + 1, cy) { add(vertex(x + 1, y + 1), vertex(x + 1, y)); }
+        }
+    }
+
+    let point = |v: usize| ((v % (width + 1)) as isize, (v / (width + 1)) as isize);
+    // Starting away from corners shared by two rings keeps each ring whole
+    let mut starts: Vec<usize> = edges.keys().copied().collect();
+    starts.sort_unstable_by_key(|start| (edges[start].len(), *start));
+
+    let mut rings: Vec<Vec<(isize, isize)>> = Vec::new();
+    for start in starts {
+        while let Some(first) = edges.get_mut(&start).and_then(|outgoing| outgoing.pop()) {
+            let mut ring = vec![point(start)];
+            let (mut previous, mut current) = (start, first);
+            while current != start {
+                ring.push(point(current));
+                let Some(outgoing) = edges.get_mut(&current).filter(|outgoing| !outgoing.is_empty()) else {
+                    break;
+                };
+                // Where two rings meet at a corner, always take the same turn
+                let (px, py) = point(previous);
+                let (x, y) = point(current);
+                let turn = |next: &usize| {
+                    let (nx, ny) = point(*next);
+                    (x - px) * (ny - y) - (y - py) * (nx - x)
+                };
+                let index = (0..outgoing.len()).min_by_key(|&i| turn(&outgoing[i])).unwrap_or(0);
+                previous = current;
+                current = outgoing.swap_remove(index);
+            }
+            rings.push(ring);
+        }
+    }
+
+    // Outer rings wind one way and holes the other
+    let as_float = |ring: &[(isize, isize)]| -> Ring { ring.iter().map(|&(x, y)| (x as f64, y as f64)).collect() };
+    let (outers, holes): (Vec<Ring>, Vec<Ring>) = rings.iter()
+        .map(|ring| as_float(ring))
+        .partition(|ring| signed_area(ring) < 0.0);
+
+    let mut polygons: Vec<Polygon> = outers.into_iter().map(|outer| vec![outer]).collect();
+    for hole in holes {
+        // The middle of a vertical edge never lies on another ring's vertex
+        let probe = hole.iter().zip(hole.iter().cycle().skip(1))
+            .find(|(a, b)| a.0 == b.0)
+            .map(|(a, b)| (a.0, (a.1 + b.1) / 2.0))
+            .unwrap_or(hole[0]);
+        let owner = polygons.iter_mut()
+            .filter(|polygon| contains(&polygon[0], probe))
+            .min_by(|a, b| signed_area(&a[0]).abs().total_cmp(&signed_area(&b[0]).abs()));
+        match owner {
+            Some(polygon) => polygon.push(hole),
+            None => warn!("Dropping a footprint hole outside every outline"),
+        }
+    }
+
+    polygons.into_iter()
+        .map(|polygon| polygon.into_iter().map(remove_collinear).collect())
+        .collect()
+}
+
+/// The edge of a raster in pixel coordinates
+///
+/// Sides are densified so they can bend once unprojected.
+pub(crate) fn rectangle_ring(width: f64, height: f64) -> Ring {
+    let corners = [(0.0, 0.0), (width, 0.0), (width, height), (0.0, height)];
+    (0..corners.len())
+        .flat_map(|side| {
+            let ((x1, y1), (x2, y2)) = (corners[side], corners[(side + 1) % corners.len()]);
+            (0..RECTANGLE_EDGE_POINTS).map(move |i| {
+                let f = i as f64 / RECTANGLE_EDGE_POINTS as f64;
+                (x1 + f * (x2 - x1), y1 + f * (y2 - y1))
+            })
+        })
+        .collect()
+}
+
+/// Move map coordinates to WGS84 longitude/latitude
+pub(crate) fn to_wgs84(georeferencing: &Georeferencing, (x, y): (f64, f64)) -> Option<(f64, f64)> {
+    let definition = georeferencing.definition.as_ref()?;
+    if !projection::is_supported(definition) {
+        return None;
+    }
+    let point = projection::inverse(definition, x, y)?;
+    Some(datum::datum_to_wgs84(point.x, point.y, &definition.datum, None))
+}
+
+/// Format a ring as GeoJSON, closed and wound as RFC 7946 asks
+pub(crate) fn ring_json(ring: &[(f64, f64)], outer: bool, precision: usize) -> String {
+    let mut ring = ring.to_vec();
+    if (signed_area(&ring) > 0.0) != outer {
+        ring.reverse();
+    }
+    ring.push(ring[0]);
+    let points: Vec<String> = ring.iter()
+        .map(|(x, y)| format!("[{:.*},{:.*}]", precision, x, precision, y))
+        .collect();
+    format!("[{}]", points.join(","))
+}
+
+/// Write a raster's footprint as a GeoJSON feature collection
+///
+/// Coordinates are WGS84 longitude/latitude when the raster's coordinate
+/// system can be unprojected; otherwise they stay in map units and the
+/// file names the CRS, as GeoJSON did before RFC 7946.
+///
+/// # Arguments
+/// * `input_path` - Path to the raster
+/// * `output_path` - Path of the GeoJSON file to write
+/// * `shape` - Bounding rectangle or traced outline
+/// * `logger` - Logger for recording operations
+///
+/// # Returns
+/// The number of polygons written
+pub fn write_footprint(input_path: &str, output_path: &str, shape: FootprintShape, logger: &Logger) -> TiffResult<usize> {
+    let mut reader = TiffReader::new(logger);
+    let tiff = reader.load(input_path)?;
+    let ifd = tiff.main_ifd()
+        .ok_or_else(|| TiffError::GenericError("No IFDs found in TIFF file".to_string()))?;
+    let georeferencing = info_utils::read_georeferencing(&tiff, ifd, &reader, input_path)
+        .ok_or_else(|| TiffError::GenericError(format!(
+            "{} is not georeferenced, a footprint needs map coordinates", input_path)))?;
+    let mut file = BufReader::new(File::open(input_path)?);
+    let layout = sample_utils::read_layout(&reader, ifd, &mut file)?;
+    let (width, height) = (layout.width as f64, layout.height as f64);
+    let nodata = if ifd.has_tag(tags::GDAL_NODATA) {
+        tiff_extraction_utils::extract_nodata_value(ifd, &reader).trim().parse::<f64>().ok()
+    } else {
+        None
+    };
+
+    let traceable = nodata.is_some() || layout.format == SampleFormat::Float;
+    if shape == FootprintShape::Outline && !traceable {
+        warn!("{} declares no NoData value, its outline is the full rectangle", input_path);
+    }
+
+    // Polygons in pixel coordinates
+    let pixel_polygons: Vec<Polygon> = if shape == FootprintShape::Outline && traceable {
+        let mask = valid_mask_utils::read_valid_mask(input_path, nodata, Some(MAX_TRACE_CELLS), logger)?;
+        if mask.cell > 1 {
+            info!("Tracing the footprint on {}x{} cells of {} pixels a side", mask.width, mask.height, mask.cell);
+        }
+        let cell = mask.cell as f64;
+        let scale = |(x, y): (f64, f64)| ((x * cell).min(width), (y * cell).min(height));
+        trace_outline(&mask.valid, mask.width, mask.height).into_iter()
+            .map(|polygon| polygon.iter()
+                .map(|ring| simplify_ring(ring, SIMPLIFY_TOLERANCE).into_iter().map(scale).collect())
+                .collect())
+            .collect()
+    } else {
+        vec![vec![rectangle_ring(width, height)]]
+    };
+    if pixel_polygons.is_empty() {
+        return Err(TiffError::GenericError(format!("{} holds no valid pixels", input_path)));
+    }
+
+    let map_polygons: Vec<Polygon> = pixel_polygons.iter()
+        .map(|polygon| polygon.iter()
+            .map(|ring| ring.iter().map(|&(column, row)| georeferencing.geotransform.apply(column, row)).collect())
+            .collect())
+        .collect();
+    let wgs84: Option<Vec<Polygon>> = map_polygons.iter()
+        .map(|polygon| polygon.iter()
+            .map(|ring| ring.iter().map(|&point| to_wgs84(&georeferencing, point)).collect::<Option<Ring>>())
+            .collect::<Option<Polygon>>())
+        .collect();
+
+    let epsg = georeferencing.definition.as_ref().and_then(|definition| definition.epsg);
+    let (polygons, precision, crs) = match wgs84 {
+        Some(polygons) => (polygons, 8, String::new()),
+        None => {
+            warn!("Cannot convert the footprint of {} to longitude/latitude, writing map coordinates", input_path);
+            let crs = epsg
+                .map(|code| format!(",\n  \"crs\": {{\"type\": \"name\", \"properties\": {{\"name\": \"urn:ogc:def:crs:EPSG::{}\"}}}}", code))
+                .unwrap_or_default();
+            (map_polygons, 3, crs)
+        },
+    };
+
+    let points = || polygons.iter().flat_map(|polygon| polygon.iter().flatten());
+    let bbox = [
+        points().map(|p| p.0).fold(f64::INFINITY, f64::min),
+        points().map(|p| p.1).fold(f64::INFINITY, f64::min),
+        points().map(|p| p.0).fold(f64::NEG_INFINITY, f64::max),
+        points().map(|p| p.1).fold(f64::NEG_INFINITY, f64::max),
+    ];
+    let coordinates: Vec<String> = polygons.iter()
+        .map(|polygon| {
+            let rings: Vec<String> = polygon.iter().enumerate()
+                .map(|(index, ring)| ring_json(ring, index == 0, precision))
+                .collect();
+            format!("[{}]", rings.join(","))
+        })
+        .collect();
+    let geometry = if coordinates.len() == 1 {
+        format!("{{\"type\": \"Polygon\", \"coordinates\": {}}}", coordinates[0])
+    } else {
+        format!("{{\"type\": \"MultiPolygon\", \"coordinates\": [{}]}}", coordinates.join(","))
+    };
+    let source = input_path.replace('\\', "\\\\").replace('"', "\\\"");
+    let kind = if shape == FootprintShape::Outline && traceable { "outline" } else { "rectangle" };
+
+    let mut writer = BufWriter::new(File::create(output_path)?);
+    write!(writer, "{{\n  \"type\": \"FeatureCollection\"{},\n  \"features\": [{{\n", crs)?;
+    writeln!(writer, "    \"type\": \"Feature\",")?;
+    writeln!(writer, "    \"bbox\": [{:.*}, {:.*}, {:.*}, {:.*}],",
+             precision, bbox[0], precision, bbox[1], precision, bbox[2], precision, bbox[3])?;
+    writeln!(writer, "    \"properties\": {{\"source\": \"{}\", \"footprint\": \"{}\", \"width\": {}, \"height\": {}}},",
+             source, kind, layout.width, layout.height)?;
+    writeln!(writer, "    \"geometry\": {}", geometry)?;
+    writeln!(writer, "  }}]\n}}")?;
+    writer.flush()?;
+
+    debug!("Footprint bbox: {:?}", bbox);
+    info!("Saved the {} footprint of {} ({} polygon(s)) to {}", kind, input_path, polygons.len(), output_path);
+    Ok(polygons.len())
+}
@@ -0,0 +1,183 @@
+//! GeoPackage utilities
+//!
+//! A GeoPackage is a SQLite database with a fixed set of metadata tables.
+//! This module adds the mandatory core tables (`gpkg_spatial_ref_sys` and
+//! `gpkg_contents`) to a database built with the SQLite writer, and for
+//! feature tables the geometry column registry and geometry encoding.
+
+use crate::tiff::errors::TiffResult;
+use crate::utils::sqlite_utils::{self, SqlValue, SqliteWriter};
+
+/// Application id marking a SQLite file as GeoPackage ("GPKG")
+pub const GPKG_APPLICATION_ID: u32 = 0x4750_4B47;
+
+/// GeoPackage version 1.3 as stored in the user version
+pub const GPKG_USER_VERSION: u32 = 10300;
+
+/// srs_id of WGS 84 longitude/latitude
+pub const WGS84_SRS_ID: i64 = 4326;
+
+/// WKT of WGS 84, the geographic SRS every GeoPackage must contain
+const WGS84_WKT: &str = "GEOGCS[\"WGS 84\",DATUM[\"WGS_1984\",SPHEROID[\"WGS 84\",6378137,298.257223563,\
+AUTHORITY[\"EPSG\",\"7030\"]],AUTHORITY[\"EPSG\",\"6326\"]],PRIMEM[\"Greenwich\",0,\
+AUTHORITY[\"EPSG\",\"8901\"]],UNIT[\"degree\",0.0174532925199433,AUTHORITY[\"EPSG\",\"9122\"]],\
+AUTHORITY[\"EPSG\",\"4326\"]]";
+
+/// Turn a SQLite database into a GeoPackage with one attributes table
+///
+/// Sets the GeoPackage header fields and writes the spatial reference
+/// system table with its three required rows plus a contents entry
+/// registering `table_name` as an attributes table.
+///
+/// # Arguments
+/// * `writer` - The database being built
+/// * `table_name` - Name of the attributes table to register
+/// * `description` - Human-readable description of the table
+///
+/// # Returns
+/// Result indicating success or an error
+pub fn add_geopackage_core(writer: &mut SqliteWriter, table_name: &str, description: &str) -> TiffResult<()> {
+    writer.set_application_id(GPKG_APPLICATION_ID, GPKG_USER_VERSION);
+    add_spatial_ref_sys(writer)?;
+    add_contents(writer, table_name, "attributes", description, None)
+}
+
+/// Turn a SQLite database into a GeoPackage with one WGS 84 features table
+///
+/// Like `add_geopackage_core`, but the table is registered as features
+/// with its geometry column, and its R*Tree index is declared when the
+/// caller adds one with `add_rtree` under the name `rtree_<table>_<column>`.
+///
+/// # Arguments
+/// * `writer` - The database being built
+/// * `table_name` - Name of the features table to register
+/// * `column_name` - Geometry column of the table
+/// * `geometry_type` - Geometry type name, e.g. `POLYGON`
+/// * `description` - Human-readable description of the table
+/// * `extent` - Bounds of the features as [min_x, min_y, max_x, max_y]
+/// * `spatial_index` - Whether the table has an R*Tree index
+///
+/// # Returns
+/// Result indicating success or an error
+#[allow(clippy::too_many_arguments)]
+pub fn add_geopackage_features(writer: &mut SqliteWriter, table_name: &str, column_name: &str, geometry_type: &str,
+                               description: &str, extent: Option<[f64; 4]>, spatial_index: bool) -> TiffResult<()> {
+    writer.set_application_id(GPKG_APPLICATION_ID, GPKG_USER_VERSION);
+    add_spatial_ref_sys(writer)?;
+    add_contents(writer, table_name, "features", description, extent)?;
+
+    let text = |s: &str| SqlValue::Text(s.to_string());
+    writer.add_table(
+        "gpkg_geometry_columns",
+        "CREATE TABLE gpkg_geometry_columns (table_name TEXT NOT NULL, column_name TEXT NOT NULL, \
+         geometry_type_name TEXT NOT NULL, srs_id INTEGER NOT NULL, z TINYINT NOT NULL, m TINYINT NOT NULL, \
+         CONSTRAINT pk_geom_cols PRIMARY KEY (table_name, column_name), \
+         CONSTRAINT uk_gc_table_name UNIQUE (table_name), \
+         CONSTRAINT fk_gc_tn FOREIGN KEY (table_name) REFERENCES gpkg_contents(table_name), \
+         CONSTRAINT fk_gc_srs FOREIGN KEY (srs_id) REFERENCES gpkg_spatial_ref_sys (srs_id))",
+        vec![(1, vec![text(table_name), text(column_name), text(geometry_type),
+                      SqlValue::Integer(WGS84_SRS_ID), SqlValue::Integer(0), SqlValue::Integer(0)])])?;
+    writer.add_auto_index("gpkg_geometry_columns", 1, &[(vec![text(table_name), text(column_name)], 1)])?;
+    writer.add_auto_index("gpkg_geometry_columns", 2, &[(vec![text(table_name)], 1)])?;
+
+    if spatial_index {
+        writer.add_table(
+            "gpkg_extensions",
+            "CREATE TABLE gpkg_extensions (table_name TEXT, column_name TEXT, extension_name TEXT NOT NULL, \
+             definition TEXT NOT NULL, scope TEXT NOT NULL, \
+             CONSTRAINT ge_tce UNIQUE (table_name, column_name, extension_name))",
+            vec![(1, vec![text(table_name), text(column_name), text("gpkg_rtree_index"),
+                          text("http://www.geopackage.org/spec120/#extension_rtree"), text("write-only")])])?;
+        writer.add_auto_index("gpkg_extensions", 1,
+                              &[(vec![text(table_name), text(column_name), text("gpkg_rtree_index")], 1)])?;
+    }
+
+    Ok(())
+}
+
+/// Write the spatial reference system table with its required rows
+fn add_spatial_ref_sys(writer: &mut SqliteWriter) -> TiffResult<()> {
+    let text = |s: &str| SqlValue::Text(s.to_string());
+
+    // Required spatial reference systems, keyed by srs_id (the rowid)
+    let srs_rows = vec![
+        (-1, vec![text("Undefined cartesian SRS"), SqlValue::Null, text("NONE"), SqlValue::Integer(-1),
+                  text("undefined"), text("undefined cartesian coordinate reference system")]),
+        (0, vec![text("Undefined geographic SRS"), SqlValue::Null, text("NONE"), SqlValue::Integer(0),
+                 text("undefined"), text("undefined geographic coordinate reference system")]),
+        (WGS84_SRS_ID, vec![text("WGS 84 geodetic"), SqlValue::Null, text("EPSG"), SqlValue::Integer(WGS84_SRS_ID),
+                            text(WGS84_WKT), text("longitude/latitude coordinates in decimal degrees on the WGS 84 spheroid")]),
+    ];
+    writer.add_table(
+        "gpkg_spatial_ref_sys",
+        "CREATE TABLE gpkg_spatial_ref_sys (srs_name TEXT NOT NULL, srs_id INTEGER NOT NULL PRIMARY KEY, \
+         organization TEXT NOT NULL, organization_coordsys_id INTEGER NOT NULL, definition TEXT NOT NULL, \
+         description TEXT)",
+        srs_rows)
+}
+
+/// Write the contents table registering one table
+fn add_contents(writer: &mut SqliteWriter, table_name: &str, data_type: &str, description: &str,
+                extent: Option<[f64; 4]>) -> TiffResult<()> {
+    let text = |s: &str| SqlValue::Text(s.to_string());
+    let mut bounds = match extent {
+        Some(extent) => extent.iter().map(|value| SqlValue::Real(*value)).collect(),
+        None => vec![SqlValue::Null; 4],
+    };
+    // Features are in WGS 84; attributes have no spatial reference
+    bounds.push(if data_type == "features" { SqlValue::Integer(WGS84_SRS_ID) } else { SqlValue::Null });
+
+    writer.add_table(
+        "gpkg_contents",
+        "CREATE TABLE gpkg_contents (table_name TEXT NOT NULL PRIMARY KEY, data_type TEXT NOT NULL, \
+         identifier TEXT UNIQUE, description TEXT DEFAULT '', \
+         last_change DATETIME NOT NULL DEFAULT (strftime('%Y-%m-%dT%H:%M:%fZ','now')), \
+         min_x DOUBLE, min_y DOUBLE, max_x DOUBLE, max_y DOUBLE, srs_id INTEGER, \
+         CONSTRAINT fk_gc_r_srs_id FOREIGN KEY (srs_id) REFERENCES gpkg_spatial_ref_sys(srs_id))",
+        vec![(1, [text(table_name), text(data_type), text(table_name), text(description),
+                  text(&sqlite_utils::utc_timestamp())].into_iter().chain(bounds).collect())])?;
+
+    // Indexes backing the PRIMARY KEY and UNIQUE constraints of gpkg_contents
+    writer.add_auto_index("gpkg_contents", 1, &[(vec![text(table_name)], 1)])?;
+    writer.add_auto_index("gpkg_contents", 2, &[(vec![text(table_name)], 1)])?;
+
+    Ok(())
+}
+
+/// Encode a WGS 84 polygon as a GeoPackage geometry blob
+///
+/// The blob is the GeoPackage header, with the polygon's envelope, followed
+/// by the polygon as little-endian WKB.
+///
+/// # Arguments
+/// * `rings` - Outer ring followed by any holes, not closed
+///
+/// # Returns
+/// The geometry blob
+pub fn polygon_blob(rings: &[Vec<(f64, f64)>]) -> Vec<u8> {
+    let points = || rings.iter().flatten();
+    let envelope = [
+        points().map(|p| p.0).fold(f64::INFINITY, f64::min),
+        points().map(|p| p.0).fold(f64::NEG_INFINITY, f64::max),
+        points().map(|p| p.1).fold(f64::INFINITY, f64::min),
+        points().map(|p| p.1).fold(f64::NEG_INFINITY, f64::max),
+    ];
+
+    // "GP", version 0, little-endian with an [min_x, max_x, min_y, max_y] envelope
+    let mut blob = vec![b'G', b'P', 0, 0b0000_0011];
+    blob.extend_from_slice(&(WGS84_SRS_ID as i32).to_le_bytes());
+    envelope.iter().for_each(|value| blob.extend_from_slice(&value.to_le_bytes()));
+
+    // WKB polygon, each ring closed by repeating its first point
+    blob.push(1);
+    blob.extend_from_slice(&3u32.to_le_bytes());
+    blob.extend_from_slice(&(rings.len() as u32).to_le_bytes());
+    for ring in rings {
+        blob.extend_from_slice(&(ring.len() as u32 + 1).to_le_bytes());
+        for (x, y) in ring.iter().chain(ring.first()) {
+            blob.extend_from_slice(&x.to_le_bytes());
+            blob.extend_from_slice(&y.to_le_bytes());
+        }
+    }
+    blob
+}
@@ -0,0 +1,308 @@
+//! GPU resampling utilities
+//!
+//! Resampling a band onto another grid reads every output pixel's value
+//! from the source independently of all the others, which maps directly
+//! onto a compute shader: one invocation per output pixel, each sampling
+//! the source at the fractional pixel position it was given. The source
+//! band, the positions and the output live in storage buffers; positions
+//! are sent in chunks so that none of them exceeds the binding limits of
+//! the device.
+//!
+//! The backend is compiled in with the `gpu` feature and runs on whatever
+//! wgpu finds (Vulkan, Metal, DX12). Without the feature, or without a
+//! usable adapter, `GpuResampler::new` returns None and callers keep to
+//! their CPU loops.
+
+use crate::tiff::errors::{TiffError, TiffResult};
+
+/// Value marking samples without data, in both directions
+pub const NO_DATA: f32 = f32::MIN;
+
+/// Invocations per workgroup of the resampling shader
+#[cfg(feature = "gpu")]
+const WORKGROUP_SIZE: u32 = 64;
+
+/// Most workgroups along one dispatch dimension
+#[cfg(feature = "gpu")]
+const MAX_WORKGROUPS: u32 = 65_535;
+
+/// Nearest and bilinear sampling of one band at fractional pixel positions
+///
+/// Pixel centres sit at half-pixel positions. Bilinear sampling leaves out
+/// neighbours without data and rescales the weights of the others, like
+/// the CPU sampling of `grid_utils::Raster`.
+#[cfg(feature = "gpu")]
+const RESAMPLE_SHADER: &str = r#"
+struct Params {
+    width: u32,
+    height: u32,
+    count: u32,
+    bilinear: u32,
+    row_stride: u32,
+    nodata: f32,
+    padding0: u32,
+    padding1: u32,
+}
+
+@group(0) @binding(0) var<uniform> params: Params;
+@group(0) @binding(1) var<storage, read> source: array<f32>;
+@group(0) @binding(2) var<storage, read> positions: array<vec2<f32>>;
+@group(0) @binding(3) var<storage, read_write> output: array<f32>;
+
+@compute @workgroup_size(64)
+fn main(@builtin(global_invocation_id) id: vec3<u32>) {
+    let index = id.x + id.y * params.row_stride;
+    if (index >= params.count) {
+        return;
+    }
+    let position = positions[index];
+    let size = vec2<f32>(f32(params.width), f32(params.height));
+    output[index] = params.nodata;
+
+    if (params.bilinear == 0u) {
+        if (position.x >= 0.0 && position.y >= 0.0 && position.x < size.x && position.y < size.y) {
+            output[index] = source[u32(position.y) * params.width + u32(position.x)];
+        }
+        return;
+    }
+
+    if (position.x < 0.0 || position.y < 0.0 || position.x > size.x || position.y > size.y) {
+        return;
+    }
+    let p = clamp(position - vec2<f32>(0.5, 0.5), vec2<f32>(0.0, 0.0), size - vec2<f32>(1.0, 1.0));
+    let x0 = u32(floor(p.x));
+    let y0 = u32(floor(p.y));
+    let x1 = min(x0 + 1u, params.width - 1u);
+    let y1 = min(y0 + 1u, params.height - 1u);
+    let f = p - vec2<f32>(f32(x0), f32(y0));
+
+    var columns = array<u32, 4>(x0, x1, x0, x1);
+    var rows = array<u32, 4>(y0, y0, y1, y1);
+    var weights = array<f32, 4>((1.0 - f.x) * (1.0 - f.y), f.x * (1.0 - f.y), (1.0 - f.x) * f.y, f.x * f.y);
+    var sum = 0.0;
+    var total = 0.0;
+    for (var i = 0u; i < 4u; i = i + 1u) {
+        let value = source[rows[i] * params.width + columns[i]];
+        if (value != params.nodata && weights[i] > 0.0) {
+            sum = sum + value * weights[i];
+            total = total + weights[i];
+        }
+    }
+    if (total > 0.0) {
+        output[index] = sum / total;
+    }
+}
+"#;
+
+/// How a band is sampled between its pixel centres
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Sampling {
+    /// Value of the pixel containing the position
+    Nearest,
+    /// Distance-weighted value of the four nearest pixel centres
+    Bilinear,
+}
+
+/// Resamples bands on the GPU
+#[cfg(feature = "gpu")]
+pub struct GpuResampler {
+    /// Device the shader runs on
+    device: wgpu::Device,
+    /// Queue of the device
+    queue: wgpu::Queue,
+    /// Compiled resampling shader
+    pipeline: wgpu::ComputePipeline,
+    /// Largest storage buffer the device binds, in bytes
+    max_binding: u64,
+}
+
+/// Resamples bands on the GPU (unavailable: built without the `gpu` feature)
+#[cfg(not(feature = "gpu"))]
+pub struct GpuResampler;
+
+#[cfg(not(feature = "gpu"))]
+impl GpuResampler {
+    /// Open the GPU backend
+    ///
+    /// # Returns
+    /// Always None, as this build has no GPU backend
+    pub fn new() -> Option<Self> {
+        log::warn!("rasterkit was built without the gpu feature, resampling on the CPU");
+        None
+    }
+
+    /// Sample a band at fractional pixel positions
+    ///
+    /// # Returns
+    /// Always an error, as this build has no GPU backend
+    pub fn resample(&self, _band: &[f32], _width: u32, _height: u32, _positions: &[[f32; 2]],
+                    _sampling: Sampling) -> TiffResult<Vec<f32>> {
+        Err(TiffError::GenericError("GPU resampling requires building rasterkit with --features gpu".to_string()))
+    }
+}
+
+#[cfg(feature = "gpu")]
+impl GpuResampler {
+    /// Open the GPU backend on the best adapter available
+    ///
+    /// # Returns
+    /// The resampler, or None (with a warning) if no adapter can be used
+    pub fn new() -> Option<Self> {
+        let instance = wgpu::Instance::new(&wgpu::InstanceDescriptor::default());
+        let adapter = match pollster::block_on(instance.request_adapter(&wgpu::RequestAdapterOptions {
+            power_preference: wgpu::PowerPreference::HighPerformance,
+            ..Default::default()
+        })) {
+            Ok(adapter) => adapter,
+            Err(e) => {
+                log::warn!("No GPU adapter available ({}), resampling on the CPU", e);
+                return None;
+            },
+        };
+        let limits = adapter.limits();
+        let (device, queue) = match pollster::block_on(adapter.request_device(&wgpu::DeviceDescriptor {
+            label: Some("rasterkit"),
+            required_limits: limits.clone(),
+            ..Default::default()
+        })) {
+            Ok(device) => device,
+            Err(e) => {
+                log::warn!("Could not open the GPU ({}), resampling on the CPU", e);
+                return None;
+            },
+        };
+
+        let module = device.create_shader_module(wgpu::ShaderModuleDescriptor {
+            label: Some("resample"),
+            source: wgpu::ShaderSource::Wgsl(RESAMPLE_SHADER.into()),
+        });
+        let pipeline = device.create_compute_pipeline(&wgpu::ComputePipelineDescriptor {
+            label: Some("resample"),
+            layout: None,
+            module: &module,
+            entry_point: Some("main"),
+            compilation_options: Default::default(),
+            cache: None,
+        });
+
+        let info = adapter.get_info();
+        log::info!("Resampling on the GPU: {} ({:?})", info.name, info.backend);
+        Some(GpuResampler {
+            device,
+            queue,
+            pipeline,
+            max_binding: (limits.max_storage_buffer_binding_size as u64).min(limits.max_buffer_size),
+        })
+    }
+
+    /// Sample a band at fractional pixel positions
+    ///
+    /// # Arguments
+    /// * `band` - Band values row by row, `NO_DATA` where there is no data
+    /// * `width` - Band width in pixels
+    /// * `height` - Band height in pixels
+    /// * `positions` - Column and row to sample at for each output value
+    /// * `sampling` - Nearest or bilinear sampling
+    ///
+    /// # Returns
+    /// One value per position, `NO_DATA` outside the band or where it has no data
+    pub fn resample(&self, band: &[f32], width: u32, height: u32, positions: &[[f32; 2]],
+                    sampling: Sampling) -> TiffResult<Vec<f32>> {
+        use wgpu::util::DeviceExt;
+
+        let band_bytes = std::mem::size_of_val(band) as u64;
+        if band_bytes > self.max_binding {
+            return Err(TiffError::GenericError(format!(
+                "A {}x{} band exceeds the {} bytes the GPU binds at once", width, height, self.max_binding)));
+        }
+        let source = self.device.create_buffer_init(&wgpu::util::BufferInitDescriptor {
+            label: Some("source"),
+            contents: &band.iter().flat_map(|value| value.to_ne_bytes()).collect::<Vec<u8>>(),
+            usage: wgpu::BufferUsages::STORAGE,
+        });
+
+        let chunk_len = (self.max_binding / 8) as usize;
+        let mut output = Vec::with_capacity(positions.len());
+        for chunk in positions.chunks(chunk_len) {
+            output.extend(self.resample_chunk(&source, width, height, chunk, sampling)?);
+        }
+        Ok(output)
+    }
+
+    /// Sample the bound source band at one chunk of positions
+    fn resample_chunk(&self, source: &wgpu::Buffer, width: u32, height: u32, positions: &[[f32; 2]],
+                      sampling: Sampling) -> TiffResult<Vec<f32>> {
+        use wgpu::util::DeviceExt;
+
+        let count = positions.len() as u32;
+        let groups = count.div_ceil(WORKGROUP_SIZE).max(1);
+        let (groups_x, groups_y) = (groups.min(MAX_WORKGROUPS), groups.div_ceil(MAX_WORKGROUPS));
+        let params: Vec<u8> = [width, height, count, u32::from(sampling == Sampling::Bilinear),
+                               groups_x * WORKGROUP_SIZE, NO_DATA.to_bits(), 0, 0]
+            .iter()
+            .flat_map(|value| value.to_ne_bytes())
+            .collect();
+
+        let params = self.device.create_buffer_init(&wgpu::util::BufferInitDescriptor {
+            label: Some("params"),
+            contents: &params,
+            usage: wgpu::BufferUsages::UNIFORM,
+        });
+        let positions = self.device.create_buffer_init(&wgpu::util::BufferInitDescriptor {
+            label: Some("positions"),
+            contents: &positions.iter().flatten().flat_map(|value| value.to_ne_bytes()).collect::<Vec<u8>>(),
+            usage: wgpu::BufferUsages::STORAGE,
+        });
+        let output_size = count as u64 * 4;
+        let output = self.device.create_buffer(&wgpu::BufferDescriptor {
+            label: Some("output"),
+            size: output_size,
+            usage: wgpu::BufferUsages::STORAGE | wgpu::BufferUsages::COPY_SRC,
+            mapped_at_creation: false,
+        });
+        let staging = self.device.create_buffer(&wgpu::BufferDescriptor {
+            label: Some("staging"),
+            size: output_size,
+            usage: wgpu::BufferUsages::MAP_READ | wgpu::BufferUsages::COPY_DST,
+            mapped_at_creation: false,
+        });
+
+        let bind_group = self.device.create_bind_group(&wgpu::BindGroupDescriptor {
+            label: Some("resample"),
+            layout: &self.pipeline.get_bind_group_layout(0),
+            entries: &[
+                wgpu::BindGroupEntry { binding: 0, resource: params.as_entire_binding() },
+                wgpu::BindGroupEntry { binding: 1, resource: source.as_entire_binding() },
+                wgpu::BindGroupEntry { binding: 2, resource: positions.as_entire_binding() },
+                wgpu::BindGroupEntry { binding: 3, resource: output.as_entire_binding() },
+            ],
+        });
+
+        let mut encoder = self.device.create_command_encoder(&wgpu::CommandEncoderDescriptor { label: Some("resample") });
+        {
+            let mut pass = encoder.begin_compute_pass(&wgpu::ComputePassDescriptor { label: Some("resample"), timestamp_writes: None });
+            pass.set_pipeline(&self.pipeline);
+            pass.set_bind_group(0, &bind_group, &[]);
+            pass.dispatch_workgroups(groups_x, groups_y, 1);
+        }
+        encoder.copy_buffer_to_buffer(&output, 0, &staging, 0, output_size);
+        self.queue.submit(Some(encoder.finish()));
+
+        // Wait for the results and copy them back
+        let slice = staging.slice(..);
+        let (sender, receiver) = std::sync::mpsc::channel();
+        slice.map_async(wgpu::MapMode::Read, move |result| { let _ = sender.send(result); });
+        self.device.poll(wgpu::PollType::Wait)
+            .map_err(|e| TiffError::GenericError(format!("GPU resampling failed: {}", e)))?;
+        receiver.recv()
+            .map_err(|e| TiffError::GenericError(format!("GPU resampling failed: {}", e)))?
+            .map_err(|e| TiffError::GenericError(format!("Could not read back GPU results: {}", e)))?;
+
+        let values = slice.get_mapped_range()
+            .chunks_exact(4)
+            .map(|bytes| f32::from_ne_bytes([bytes[0], bytes[1], bytes[2], bytes[3]]))
+            .collect();
+        staging.unmap();
+        Ok(values)
+    }
+}
@@ -0,0 +1,177 @@
+//! UTM and MGRS grid references
+//!
+//! Field teams and GPS receivers often record locations as UTM
+//! coordinates ("33N 389000 5819000") or as MGRS grid references
+//! ("33UUU8900019000"), which name a UTM zone and a position in it. This
+//! module reads both into a zone, easting and northing, and hands them to
+//! the coordinate transformer for longitude and latitude.
+
+use log::{debug, info};
+
+use crate::coordinate::{CoordinateSystem, CoordinateTransformer, Point};
+use crate::tiff::errors::{TiffError, TiffResult};
+
+/// MGRS column letters, eight per zone set (I and O are skipped)
+const COLUMN_LETTERS: &str = "ABCDEFGHJKLMNPQRSTUVWXYZ";
+
+/// MGRS row letters, repeating every 2000 km of northing
+const ROW_LETTERS: &str = "ABCDEFGHJKLMNPQRSTUV";
+
+/// Latitude band letters, 8° each from 80°S (X spans 12°)
+const BAND_LETTERS: &str = "CDEFGHJKLMNPQRSTUVWX";
+
+/// A position in a WGS84 UTM zone
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct GridReference {
+    /// Zone number from 1 to 60
+    pub zone: u8,
+    /// Whether the zone is on the northern hemisphere
+    pub north: bool,
+    /// Easting in meters, 500000 on the central meridian
+    pub easting: f64,
+    /// Northing in meters, from the equator or 10000000 m south of it
+    pub northing: f64,
+}
+
+impl GridReference {
+    /// Longitude and latitude of the position
+    pub fn to_lon_lat(self) -> TiffResult<Point> {
+        CoordinateTransformer.transform_point(
+            &Point::new(self.easting, self.northing),
+            &CoordinateSystem::UTM(self.zone, self.north),
+            &CoordinateSystem::WGS84)
+    }
+}
+
+/// Read a UTM coordinate such as "33N 389000 5819000"
+///
+/// The zone is followed by N or S for the hemisphere, or by a latitude
+/// band letter; easting and northing may carry "mE" and "mN" suffixes and
+/// be separated by spaces or commas.
+///
+/// # Arguments
+/// * `input` - The coordinate as given
+///
+/// # Returns
+/// The grid reference, None if the input is not written as a UTM
+/// coordinate, or an error if it is but cannot be valid
+pub fn parse_utm(input: &str) -> TiffResult<Option<GridReference>> {
+    let tokens: Vec<&str> = input.split(|c: char| c == ',' || c.is_whitespace())
+        .filter(|token| !token.is_empty())
+        .collect();
+    let (zone_token, rest) = match tokens.as_slice() {
+        [zone, letter, rest @ ..] if zone.chars().all(|c| c.is_ascii_digit()) && letter.len() == 1 =>
+            (format!("{}{}", zone, letter), rest),
+        [zone, rest @ ..] => (zone.to_string(), rest),
+        [] => return Ok(None),
+    };
+    let [easting, northing] = rest else { return Ok(None) };
+    let Some((zone, letter)) = split_zone(&zone_token) else { return Ok(None) };
+
+    let number = |token: &str, suffix: &str| {
+        let lower = token.to_lowercase();
+        lower.strip_suffix(suffix).or(lower.strip_suffix('m')).unwrap_or(&lower).parse::<f64>().ok()
+    };
+    let (Some(easting), Some(northing)) = (number(easting, "me"), number(northing, "mn")) else {
+        return Ok(None);
+    };
+
+    let invalid = |reason: &str| TiffError::GenericError(format!("Invalid UTM coordinate '{}': {}", input, reason));
+    let north = match letter {
+        'N' => true,
+        'S' => false,
+        band => {
+            let index = BAND_LETTERS.find(band).ok_or_else(|| invalid("expected N, S or a latitude band after the zone"))?;
+            index >= BAND_LETTERS.find('N').unwrap_or(10)
+        },
+    };
+    if !(100_000.0..=900_000.0).contains(&easting) {
+        return Err(invalid("easting must be between 100000 and 900000 meters"));
+    }
+    if !(0.0..=10_000_000.0).contains(&northing) {
+        return Err(invalid("northing must be between 0 and 10000000 meters"));
+    }
+
+    debug!("Read UTM zone {}{} easting {} northing {}", zone, if north { 'N' } else { 'S' }, easting, northing);
+    Ok(Some(GridReference { zone, north, easting, northing }))
+}
+
+/// Read an MGRS grid reference such as "33UUU8900019000" or "33U UU 89000 19000"
+///
+/// The position is the centre of the referenced square, so "33UUU8919"
+/// stands for the middle of a 1 km square.
+///
+/// # Arguments
+/// * `input` - The grid reference as given
+///
+/// # Returns
+/// The grid reference, None if the input is not written as MGRS, or an
+/// error if it is but names no valid square
+pub fn parse_mgrs(input: &str) -> TiffResult<Option<GridReference>> {
+    let compact: String = input.chars().filter(|c| !c.is_whitespace()).collect::<String>().to_uppercase();
+    let zone_length = compact.chars().take_while(|c| c.is_ascii_digit()).count();
+    if !(1..=2).contains(&zone_length) || compact.len() < zone_length + 3 {
+        return Ok(None);
+    }
+    let (zone_part, rest) = compact.split_at(zone_length);
+    let mut letters = rest.chars();
+    let (Some(band), Some(column), Some(row)) = (letters.next(), letters.next(), letters.next()) else {
+        return Ok(None);
+    };
+    let digits = letters.as_str();
+    if ![band, column, row].iter().all(|c| c.is_ascii_alphabetic()) || !digits.chars().all(|c| c.is_ascii_digit()) {
+        return Ok(None);
+    }
+
+    let invalid = |reason: &str| TiffError::GenericError(format!("Invalid MGRS reference '{}': {}", input, reason));
+    let zone: u8 = zone_part.parse().map_err(|_| invalid("bad zone"))?;
+    if !(1..=60).contains(&zone) {
+        return Err(invalid("zone must be between 1 and 60"));
+    }
+    let band_index = BAND_LETTERS.find(band)
+        .ok_or_else(|| invalid("latitude band must be C to X; polar UPS references are not supported"))?;
+    if digits.len() % 2 != 0 || digits.len() > 10 {
+        return Err(invalid("expected an even number of up to 10 digits"));
+    }
+
+    // Column letters cycle through three sets of eight, one per zone
+    let column_index = COLUMN_LETTERS.find(column).ok_or_else(|| invalid("bad 100 km column letter"))?;
+    if column_index / 8 != (zone as usize - 1) % 3 {
+        return Err(invalid(&format!("column letter {} is not used in zone {}", column, zone)));
+    }
+    let square_easting = (column_index % 8 + 1) as f64 * 100_000.0;
+
+    // Row letters start at A on the equator in odd zones and at F in even ones
+    let row_index = ROW_LETTERS.find(row).ok_or_else(|| invalid("bad 100 km row letter"))?;
+    let offset = if zone.is_multiple_of(2) { 5 } else { 0 };
+    let square_northing = ((row_index + 20 - offset) % 20) as f64 * 100_000.0;
+
+    let precision = digits.len() / 2;
+    let cell = 10f64.powi(5 - precision as i32);
+    let (easting_digits, northing_digits) = digits.split_at(precision);
+    let value = |digits: &str| if digits.is_empty() { 0.0 } else { digits.parse::<f64>().unwrap_or(0.0) * cell };
+    let easting = square_easting + value(easting_digits) + cell / 2.0;
+    let northing_in_cycle = square_northing + value(northing_digits) + cell / 2.0;
+
+    // The row letters repeat every 2000 km; the band picks the repetition
+    let north = band_index >= BAND_LETTERS.find('N').unwrap_or(10);
+    let band_south = -80.0 + 8.0 * band_index as f64;
+    let band_north = if band == 'X' { 84.0 } else { band_south + 8.0 };
+    let reference = (0..5)
+        .map(|cycle| GridReference { zone, north, easting, northing: northing_in_cycle + cycle as f64 * 2_000_000.0 })
+        .find(|candidate| candidate.to_lon_lat()
+            .is_ok_and(|point| point.y >= band_south - 0.5 && point.y <= band_north + 0.5))
+        .ok_or_else(|| invalid(&format!("square {}{} does not lie in latitude band {}", column, row, band)))?;
+
+    info!("Read MGRS reference {} as UTM zone {}{} easting {} northing {}",
+          input, zone, if north { 'N' } else { 'S' }, reference.easting, reference.northing);
+    Ok(Some(reference))
+}
+
+/// Split "33N" into the zone number and the letter after it
+fn split_zone(token: &str) -> Option<(u8, char)> {
+    let upper = token.to_uppercase();
+    let letter = upper.chars().last()?;
+    let zone = upper[..upper.len() - letter.len_utf8()].parse::<u8>().ok()?;
+    ((1..=60).contains(&zone) && letter.is_ascii_alphabetic()).then_some((zone, letter))
+}
@@ -0,0 +1,204 @@
+//! Pixel grid utilities
+//!
+//! Operations that combine two rasters (pan-sharpening, histogram
+//! matching) read both onto their own pixel grids and relate them through
+//! their geotransforms. This module holds the raster they read and the
+//! mapping from the pixels of one grid to those of the other, and writes
+//! images on grids derived from a source's (resized, extended, rotated)
+//! with the source's tags and coordinate system.
+
+use log::warn;
+
+use crate::coordinate::Affine;
+use crate::tiff::builder::TiffBuilder;
+use crate::tiff::constants::{compression, field_types, photometric, tags};
+use crate::tiff::errors::{TiffError, TiffResult};
+use crate::tiff::ifd::{IFD, IFDEntry};
+use crate::tiff::TiffReader;
+use crate::utils::info_utils::{self, Georeferencing};
+use crate::utils::logger::Logger;
+use crate::utils::sample_utils::{self, SampleLayout};
+use crate::utils::{tiff_extraction_utils, tiff_utils};
+
+/// Tags describing the stored layout or position of a source image, which
+/// an image derived from its samples writes anew
+const DERIVED_IMAGE_TAGS: [u16; 20] = [
+    tags::IMAGE_WIDTH, tags::IMAGE_LENGTH, tags::COMPRESSION,
+    tags::STRIP_OFFSETS, tags::STRIP_BYTE_COUNTS, tags::ROWS_PER_STRIP,
+    tags::TILE_WIDTH, tags::TILE_LENGTH, tags::TILE_OFFSETS, tags::TILE_BYTE_COUNTS,
+    tags::PLANAR_CONFIGURATION, tags::PREDICTOR, tags::JPEG_TABLES, tags::YCBCR_SUBSAMPLING,
+    tags::SUB_IFDS, tags::EXIF_IFD, tags::GPS_IFD,
+    tags::MODEL_TIEPOINT_TAG, tags::MODEL_PIXEL_SCALE_TAG, tags::MODEL_TRANSFORMATION_TAG,
+];
+
+/// A raster read with its values and georeferencing
+pub struct Raster {
+    /// Path the raster was read from
+    pub path: String,
+    /// Values of each band, NaN where there is no data
+    pub bands: Vec<Vec<f64>>,
+    /// Sample layout
+    pub layout: SampleLayout,
+    /// Georeferencing, if any
+    pub georeferencing: Option<Georeferencing>,
+}
+
+impl Raster {
+    /// Read the first bands of a raster, NoData values replaced by NaN
+    ///
+    /// # Arguments
+    /// * `path` - Path to the raster
+    /// * `max_bands` - Most bands to read
+    /// * `logger` - Logger for recording operations
+    ///
+    /// # Returns
+    /// The raster
+    pub fn read(path: &str, max_bands: usize, logger: &Logger) -> TiffResult<Raster> {
+        let mut reader = TiffReader::new(logger);
+        let tiff = reader.load(path)?;
+        let ifd = tiff.main_ifd()
+            .ok_or_else(|| TiffError::GenericError(format!("No IFDs found in {}", path)))?;
+        let nodata = if ifd.has_tag(tags::GDAL_NODATA) {
+            tiff_extraction_utils::extract_nodata_value(ifd, &reader).trim().parse::<f64>().ok()
+        } else {
+            None
+        };
+        let georeferencing = info_utils::read_georeferencing(&tiff, ifd, &reader, path);
+
+        let (mut bands, layout) = sample_utils::read_bands(path, max_bands, logger)?;
+        if let Some(nodata) = nodata {
+            bands.iter_mut().flatten().filter(|value| **value == nodata).for_each(|value| *value = f64::NAN);
+        }
+        Ok(Raster { path: path.to_string(), bands, layout, georeferencing })
+    }
+
+    /// Value of the pixel containing a fractional pixel position
+    ///
+    /// # Returns
+    /// The value, or None outside the raster or where there is no data
+    pub fn nearest(&self, band: usize, column: f64, row: f64) -> Option<f64> {
+        if column < 0.0 || row < 0.0 || column >= self.layout.width as f64 || row >= self.layout.height as f64 {
+            return None;
+        }
+        let value = self.bands[band][row as usize * self.layout.width as usize + column as usize];
+        (!value.is_nan()).then_some(value)
+    }
+
+    /// Bilinear value of a band at a fractional pixel position
+    ///
+    /// Neighbours without data are left out and the weights of the others
+    /// rescaled; positions outside the raster have no value.
+    pub fn bilinear(&self, band: usize, column: f64, row: f64) -> Option<f64> {
+        let (width, height) = (self.layout.width as f64, self.layout.height as f64);
+        if column < 0.0 || row < 0.0 || column > width || row > height {
+            return None;
+        }
+
+        // Pixel centres sit at half-pixel positions
+        let (x, y) = ((column - 0.5).clamp(0.0, width - 1.0), (row - 0.5).clamp(0.0, height - 1.0));
+        let (x0, y0) = (x.floor() as usize, y.floor() as usize);
+        let (x1, y1) = ((x0 + 1).min(self.layout.width as usize - 1), (y0 + 1).min(self.layout.height as usize - 1));
+        let (fx, fy) = (x - x0 as f64, y - y0 as f64);
+
+        let values = &self.bands[band];
+        let width = self.layout.width as usize;
+        let (sum, weight) = [(x0, y0, (1.0 - fx) * (1.0 - fy)), (x1, y0, fx * (1.0 - fy)),
+                             (x0, y1, (1.0 - fx) * fy), (x1, y1, fx * fy)]
+            .iter()
+            .map(|&(x, y, weight)| (values[y * width + x], weight))
+            .filter(|(value, weight)| !value.is_nan() && *weight > 0.0)
+            .fold((0.0, 0.0), |(sum, total), (value, weight)| (sum + value * weight, total + weight));
+        (weight > 0.0).then(|| sum / weight)
+    }
+}
+
+/// Transform from the pixel positions of one raster to those of another
+///
+/// Rasters that are not both georeferenced are assumed to cover the same
+/// area.
+///
+/// # Arguments
+/// * `from` - Raster whose pixel positions are transformed
+/// * `to` - Raster whose pixel positions they become
+///
+/// # Returns
+/// The transform, or an error if the rasters are in different coordinate systems
+pub fn pixel_mapping(from: &Raster, to: &Raster) -> TiffResult<Affine> {
+    match (&from.georeferencing, &to.georeferencing) {
+        (Some(from_reference), Some(to_reference)) => {
+            let from_epsg = from_reference.definition.as_ref().and_then(|definition| definition.epsg);
+            let to_epsg = to_reference.definition.as_ref().and_then(|definition| definition.epsg);
+            if let (Some(from_epsg), Some(to_epsg)) = (from_epsg, to_epsg) {
+                if from_epsg != to_epsg {
+                    return Err(TiffError::GenericError(format!(
+                        "{} is in EPSG:{} and {} in EPSG:{}; reproject one first",
+                        from.path, from_epsg, to.path, to_epsg)));
+                }
+            }
+            let to_pixels = to_reference.geotransform.inverse()
+                .ok_or_else(|| TiffError::GenericError(format!("The geotransform of {} is degenerate", to.path)))?;
+            Ok(to_pixels.compose(&from_reference.geotransform))
+        },
+        _ => {
+            warn!("Without georeferencing on both rasters, they are assumed to cover the same area");
+            Ok(Affine::new(0.0, to.layout.width as f64 / from.layout.width as f64,
+                           0.0, to.layout.height as f64 / from.layout.height as f64))
+        },
+    }
+}
+
+/// Georeference an output whose grid is derived from a source raster
+///
+/// GeoKeys copied from the source stay as they are and only the
+/// geotransform is written. A source georeferenced by a sidecar file has
+/// no GeoKeys to copy, so they are written from its EPSG code if known.
+///
+/// # Arguments
+/// * `builder` - Builder of the output
+/// * `ifd_index` - IFD of the output image
+/// * `source_ifd` - Image of the source raster
+/// * `georeferencing` - Georeferencing of the source raster
+/// * `geotransform` - Geotransform of the output grid
+pub fn georeference_derived(builder: &mut TiffBuilder, ifd_index: usize, source_ifd: &IFD,
+                            georeferencing: &Georeferencing, geotransform: &Affine) -> TiffResult<()> {
+    let epsg = georeferencing.definition.as_ref().and_then(|definition| definition.epsg);
+    match epsg {
+        Some(epsg) if !source_ifd.has_tag(tags::GEO_KEY_DIRECTORY_TAG) =>
+            builder.set_georeference(ifd_index, geotransform, epsg),
+        _ => {
+            builder.set_geotransform(ifd_index, geotransform);
+            Ok(())
+        },
+    }
+}
+
+/// Fill an IFD with the decoded samples of an image derived from a source
+///
+/// The samples keep the source's type and are stored uncompressed in one
+/// strip. Other tags are copied from the source image, except those
+/// describing its layout and geotransform; georeference the output with
+/// `georeference_derived`.
+///
+/// # Arguments
+/// * `builder` - Builder of the output
+/// * `ifd_index` - IFD of the builder to fill
+/// * `reader` - Reader the source was loaded with
+/// * `source_ifd` - The source image
+/// * `size` - Width and height of the derived image
+/// * `samples` - Little-endian samples, bands interleaved
+/// * `exclude` - Further source tags to leave out
+pub fn add_derived_image(builder: &mut TiffBuilder, ifd_index: usize, reader: &TiffReader, source_ifd: &IFD,
+                         size: (u32, u32), samples: Vec<u8>, exclude: &[u16]) {
+    let excluded: Vec<u16> = DERIVED_IMAGE_TAGS.iter().chain(exclude).copied().collect();
+    builder.deep_copy_tags_from(ifd_index, source_ifd, &excluded, reader);
+    builder.ifds[ifd_index].add_entry(IFDEntry::new(tags::IMAGE_WIDTH, field_types::LONG, 1, size.0 as u64));
+    builder.ifds[ifd_index].add_entry(IFDEntry::new(tags::IMAGE_LENGTH, field_types::LONG, 1, size.1 as u64));
+    builder.ifds[ifd_index].add_entry(IFDEntry::new(
+        tags::COMPRESSION, field_types::SHORT, 1, compression::NONE as u64));
+    // JPEG-compressed YCbCr is decoded to RGB
+    if source_ifd.get_tag_value(tags::PHOTOMETRIC_INTERPRETATION) == Some(photometric::YCBCR as u64) {
+        tiff_utils::update_ifd_tag(&mut builder.ifds[ifd_index], tags::PHOTOMETRIC_INTERPRETATION,
+            IFDEntry::new(tags::PHOTOMETRIC_INTERPRETATION, field_types::SHORT, 1, photometric::RGB as u64));
+    }
+    builder.setup_single_strip(ifd_index, samples);
+}
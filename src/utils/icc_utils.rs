@@ -0,0 +1,153 @@
+//! ICC color profile utilities
+//!
+//! Loads ICC profiles from disk and embeds them in existing TIFF files, so
+//! color-managed orthophotos can be tagged with the profile they were
+//! produced in. Embedding appends the profile and a rewritten first IFD to
+//! the end of the file and repoints the header, leaving the pixel data and
+//! every other tag untouched.
+
+use std::fs::{self, OpenOptions};
+use std::io::{Read, Seek, SeekFrom, Write};
+
+use log::info;
+
+use crate::tiff::constants::{tags, field_types};
+use crate::tiff::errors::{TiffError, TiffResult};
+
+/// Size of the fixed ICC profile header
+const ICC_HEADER_SIZE: usize = 128;
+
+/// Load an ICC profile and check that it looks like one
+///
+/// # Arguments
+/// * `path` - Path to an `.icc` / `.icm` profile
+///
+/// # Returns
+/// The raw profile bytes or an error if the file is not an ICC profile
+pub fn load_icc_profile(path: &str) -> TiffResult<Vec<u8>> {
+    let profile = fs::read(path)
+        .map_err(|e| TiffError::GenericError(format!("Failed to read ICC profile {}: {}", path, e)))?;
+
+    // The header starts with the big-endian profile size and carries the
+    // 'acsp' signature at byte 36
+    if profile.len() < ICC_HEADER_SIZE || &profile[36..40] != b"acsp" {
+        return Err(TiffError::GenericError(format!("{} is not an ICC profile", path)));
+    }
+    let declared = u32::from_be_bytes([profile[0], profile[1], profile[2], profile[3]]) as usize;
+    if declared != profile.len() {
+        return Err(TiffError::GenericError(format!(
+            "ICC profile {} declares {} bytes but holds {}", path, declared, profile.len())));
+    }
+
+    let color_space = String::from_utf8_lossy(&profile[16..20]).trim().to_string();
+    info!("Loaded ICC profile {} ({} bytes, {} color space)", path, profile.len(), color_space);
+    Ok(profile)
+}
+
+/// Embed an ICC profile in the first IFD of an existing TIFF file
+///
+/// Any profile already present is replaced. Both byte orders and BigTIFF
+/// are supported; the old IFD is left in place as unreferenced bytes.
+///
+/// # Arguments
+/// * `tiff_path` - TIFF file to update in place
+/// * `profile` - Raw ICC profile bytes
+///
+/// # Returns
+/// Result indicating success or an error
+pub fn embed_icc_profile(tiff_path: &str, profile: &[u8]) -> TiffResult<()> {
+    let mut file = OpenOptions::new().read(true).write(true).open(tiff_path)?;
+
+    let mut header = [0u8; 16];
+    file.read_exact(&mut header[..8])?;
+    let little_endian = match &header[..2] {
+        b"II" => true,
+        b"MM" => false,
+        _ => return Err(TiffError::GenericError(format!("{} is not a TIFF file", tiff_path))),
+    };
+
+    let u16_at = |b: &[u8]| if little_endian { u16::from_le_bytes([b[0], b[1]]) } else { u16::from_be_bytes([b[0], b[1]]) };
+    let u32_at = |b: &[u8]| {
+        let bytes = [b[0], b[1], b[2], b[3]];
+        if little_endian { u32::from_le_bytes(bytes) } else { u32::from_be_bytes(bytes) }
+    };
+    let u64_at = |b: &[u8]| {
+        let bytes: [u8; 8] = b[..8].try_into().unwrap_or_default();
+        if little_endian { u64::from_le_bytes(bytes) } else { u64::from_be_bytes(bytes) }
+    };
+
+    let is_big_tiff = u16_at(&header[2..4]) == 43;
+    let (count_size, entry_size, offset_size) = if is_big_tiff { (8, 20, 8) } else { (2, 12, 4) };
+    let ifd_offset = if is_big_tiff {
+        file.read_exact(&mut header[8..16])?;
+        u64_at(&header[8..16])
+    } else {
+        u32_at(&header[4..8]) as u64
+    };
+
+    // Read the first IFD as raw entries, so existing values keep their encoding
+    file.seek(SeekFrom::Start(ifd_offset))?;
+    let mut count_bytes = vec![0u8; count_size];
+    file.read_exact(&mut count_bytes)?;
+    let entry_count = if is_big_tiff { u64_at(&count_bytes) as usize } else { u16_at(&count_bytes) as usize };
+
+    let mut raw_entries = vec![0u8; entry_count * entry_size];
+    file.read_exact(&mut raw_entries)?;
+    let mut next_ifd = vec![0u8; offset_size];
+    file.read_exact(&mut next_ifd)?;
+
+    let mut entries: Vec<(u16, Vec<u8>)> = raw_entries.chunks(entry_size)
+        .map(|chunk| (u16_at(chunk), chunk.to_vec()))
+        .filter(|(tag, _)| *tag != tags::ICC_PROFILE)
+        .collect();
+
+    // Append the profile at the end of the file, word-aligned, then the IFD
+    let mut end = file.seek(SeekFrom::End(0))?;
+    let profile_offset = end + end % 2;
+    let new_ifd_offset = profile_offset + profile.len() as u64 + profile.len() as u64 % 2;
+    if !is_big_tiff && new_ifd_offset > u32::MAX as u64 {
+        return Err(TiffError::GenericError(
+            "Embedding the ICC profile would exceed the 4 GB classic TIFF limit".to_string()));
+    }
+
+    let mut icc_entry = encode(tags::ICC_PROFILE as u64, 2, little_endian);
+    icc_entry.extend(encode(field_types::UNDEFINED as u64, 2, little_endian));
+    icc_entry.extend(encode(profile.len() as u64, offset_size, little_endian));
+    icc_entry.extend(encode(profile_offset, offset_size, little_endian));
+    entries.push((tags::ICC_PROFILE, icc_entry));
+    entries.sort_by_key(|(tag, _)| *tag);
+
+    while end < profile_offset {
+        file.write_all(&[0])?;
+        end += 1;
+    }
+    file.write_all(profile)?;
+    end += profile.len() as u64;
+    while end < new_ifd_offset {
+        file.write_all(&[0])?;
+        end += 1;
+    }
+
+    // Write the rewritten IFD and repoint the header at it
+    file.write_all(&encode(entries.len() as u64, count_size, little_endian))?;
+    for (_, entry) in &entries {
+        file.write_all(entry)?;
+    }
+    file.write_all(&next_ifd)?;
+
+    file.seek(SeekFrom::Start(if is_big_tiff { 8 } else { 4 }))?;
+    file.write_all(&encode(new_ifd_offset, offset_size, little_endian))?;
+    file.flush()?;
+
+    info!("Embedded {} byte ICC profile in {}", profile.len(), tiff_path);
+    Ok(())
+}
+
+/// Encode the low `size` bytes of a value in the file's byte order
+fn encode(value: u64, size: usize, little_endian: bool) -> Vec<u8> {
+    if little_endian {
+        value.to_le_bytes()[..size].to_vec()
+    } else {
+        value.to_be_bytes()[8 - size..].to_vec()
+    }
+}
@@ -12,15 +12,20 @@ use image::{DynamicImage, ImageBuffer, Rgb};
 
 use crate::tiff::errors::{TiffResult, TiffError};
 use crate::utils::logger::Logger;
-use crate::extractor::Region;
-use crate::coordinate::BoundingBox;
+use crate::extractor::{Coverage, DecodedWindow, Padding, Region};
+use crate::coordinate::{Affine, BoundingBox, CoordinateTransformer};
+use crate::coordinate::datum::NtV2Grid;
 use crate::tiff::TiffReader;
 use crate::tiff::is_geotiff_tag;
 use crate::tiff::geo_key_parser::GeoKeyParser;
+use crate::tiff::proj_definition::ProjDefinition;
 use crate::tiff::types::TIFF;
 use crate::tiff::ifd::IFD;
+use crate::tiff::constants::{planar_config, tags};
 use crate::io::byte_order::ByteOrderHandler;
 use crate::utils::coordinate_transformer;
+use crate::utils::sidecar_utils::SidecarGeoreference;
+use crate::utils::simd_utils;
 
 /// Parse bounding box from string
 ///
@@ -39,7 +44,8 @@ pub fn parse_bbox(bbox_str: &str) -> TiffResult<BoundingBox> {
 /// Calculate geotransform from GeoTIFF information
 ///
 /// Extracts pixel scale and tiepoint information from GeoTIFF tags
-/// and constructs a geotransform array.
+/// and constructs the north-up affine transform they describe, or reads
+/// the ModelTransformation matrix of rotated images.
 ///
 /// # Arguments
 /// * `ifd` - The IFD containing GeoTIFF information
@@ -47,40 +53,29 @@ pub fn parse_bbox(bbox_str: &str) -> TiffResult<BoundingBox> {
 /// * `file_path` - Path to the TIFF file
 ///
 /// # Returns
-/// A 6-element geotransform array [origin_x, pixel_width, 0, origin_y, 0, pixel_height]
+/// The affine transform of the image
 pub fn calculate_geotransform(
     ifd: &IFD,
     byte_order_handler: &Box<dyn ByteOrderHandler>,
     file_path: &str
-) -> TiffResult<[f64; 6]> {
+) -> TiffResult<Affine> {
+    // Rotated images carry a transformation matrix instead of scale and tiepoint
+    if ifd.has_tag(tags::MODEL_TRANSFORMATION_TAG) {
+        let matrix = GeoKeyParser::read_model_transformation_values(ifd, byte_order_handler.as_ref(), file_path)?;
+        let geotransform = Affine::from_model_transformation(&matrix)
+            .ok_or_else(|| TiffError::GenericError("Incomplete GeoTIFF information".to_string()))?;
+        debug!("Read geotransform from the transformation matrix: {}", geotransform);
+        return Ok(geotransform);
+    }
+
     // Get pixel scale and tiepoint values
     let pixel_scale = GeoKeyParser::read_model_pixel_scale_values(ifd, byte_order_handler, file_path)?;
     let tiepoint = GeoKeyParser::read_model_tiepoint_values(ifd, byte_order_handler, file_path)?;
 
-    // Verify we have enough values
-    if pixel_scale.len() < 2 || tiepoint.len() < 6 {
-        return Err(TiffError::GenericError("Incomplete GeoTIFF information".to_string()));
-    }
+    let geotransform = Affine::from_scale_and_tiepoint(&pixel_scale, &tiepoint)
+        .ok_or_else(|| TiffError::GenericError("Incomplete GeoTIFF information".to_string()))?;
 
-    // Calculate geotransform components
-    let pixel_width = pixel_scale[0];
-    let pixel_height = -pixel_scale[1]; // Y scale is negative in geotransform
-    let origin_x = tiepoint[3] - tiepoint[0] * pixel_width;
-    let origin_y = tiepoint[4] + tiepoint[1] * -pixel_height;
-
-    // Construct geotransform array
-    let geotransform = [
-        origin_x,
-        pixel_width,
-        0.0,
-        origin_y,
-        0.0,
-        pixel_height
-    ];
-
-    debug!("Calculated geotransform: [{:.1}, {:.1}, {:.1}, {:.1}, {:.1}, {:.1}]",
-           geotransform[0], geotransform[1], geotransform[2],
-           geotransform[3], geotransform[4], geotransform[5]);
+    debug!("Calculated geotransform: {}", geotransform);
 
     Ok(geotransform)
 }
@@ -92,7 +87,7 @@ pub fn calculate_geotransform(
 ///
 /// # Arguments
 /// * `bbox` - Bounding box in source CRS
-/// * `geotransform` - Geotransform array from the GeoTIFF
+/// * `geotransform` - Affine transform of the GeoTIFF
 /// * `img_width` - Image width in pixels
 /// * `img_height` - Image height in pixels
 /// * `source_epsg` - Source CRS EPSG code
@@ -100,27 +95,30 @@ pub fn calculate_geotransform(
 /// * `radius_meters` - Optional radius in meters for fallback sizing
 ///
 /// # Returns
-/// A Region for extraction
+/// A Region for extraction, with its coverage when the conversion is exact
+/// enough to measure it
 pub fn generic_crs_to_pixel_region(
     bbox: &BoundingBox,
-    geotransform: &[f64],
+    geotransform: &Affine,
     img_width: u32,
     img_height: u32,
     source_epsg: u32,
     target_epsg: u32,
     radius_meters: Option<f64>
-) -> Region {
+) -> (Region, Option<Coverage>) {
     info!("Converting coordinates from EPSG:{} to EPSG:{}", source_epsg, target_epsg);
 
     // Special case for WGS84 to Web Mercator (EPSG:4326 to EPSG:3857)
     // This is a common case and we have optimized code for it
     if source_epsg == 4326 && target_epsg == 3857 {
-        return convert_wgs84_to_web_mercator(bbox, geotransform, img_width, img_height);
+        let (region, coverage) = convert_wgs84_to_web_mercator(bbox, geotransform, img_width, img_height);
+        return (region, Some(coverage));
     }
 
     // For same CRS, simple conversion
     if source_epsg == target_epsg {
-        return convert_same_crs_to_pixels(bbox, geotransform, img_width, img_height);
+        let (region, coverage) = convert_same_crs_to_pixels(bbox, geotransform, img_width, img_height);
+        return (region, Some(coverage));
     }
 
     // For other CRS combinations, we need more sophisticated transformation
@@ -129,7 +127,8 @@ pub fn generic_crs_to_pixel_region(
 
     // Different CRSes but we'll do our best to transform
     let transformed_bbox = try_transform_bbox(bbox, source_epsg, target_epsg);
-    let region = convert_same_crs_to_pixels(&transformed_bbox, geotransform, img_width, img_height);
+    // The approximate transform is too coarse to say how much of the box is covered
+    let (region, _) = convert_same_crs_to_pixels(&transformed_bbox, geotransform, img_width, img_height);
 
     // Check if region is reasonable and adjust if necessary
     let adjusted_region = adjust_region_to_image_bounds(
@@ -143,7 +142,7 @@ pub fn generic_crs_to_pixel_region(
     info!("Generic CRS conversion result: ({}, {}) with size {}x{}",
         adjusted_region.x, adjusted_region.y, adjusted_region.width, adjusted_region.height);
 
-    adjusted_region
+    (adjusted_region, None)
 }
 
 /// Try to transform a bounding box between coordinate systems
@@ -184,42 +183,38 @@ fn try_transform_bbox(bbox: &BoundingBox, source_epsg: u32, target_epsg: u32) ->
 ///
 /// # Arguments
 /// * `bbox` - Bounding box in the CRS
-/// * `geotransform` - Geotransform array from the GeoTIFF
+/// * `geotransform` - Affine transform of the GeoTIFF
 /// * `img_width` - Image width in pixels
 /// * `img_height` - Image height in pixels
 ///
 /// # Returns
-/// A Region for extraction
+/// The part of the window on the image, and how much of the window that is
 fn convert_same_crs_to_pixels(
     bbox: &BoundingBox,
-    geotransform: &[f64],
+    geotransform: &Affine,
     img_width: u32,
     img_height: u32
-) -> Region {
+) -> (Region, Coverage) {
     debug!("Converting coordinates to pixels using direct geotransform");
 
-    // Extract geotransform components
-    let origin_x = geotransform[0];
-    let pixel_width = geotransform[1];
-    let origin_y = geotransform[3];
-    let pixel_height = geotransform[5]; // Usually negative
-
-    // Calculate pixel coordinates
-    let min_x_pixel = ((bbox.min_x - origin_x) / pixel_width).floor() as i64;
-    let max_y_pixel = ((bbox.min_y - origin_y) / pixel_height).floor() as i64;
-    let max_x_pixel = ((bbox.max_x - origin_x) / pixel_width).ceil() as i64;
-    let min_y_pixel = ((bbox.max_y - origin_y) / pixel_height).floor() as i64;
+    let (min_x_pixel, min_y_pixel, max_x_pixel, max_y_pixel) =
+        geotransform.pixel_window(bbox).unwrap_or((0, 0, 1, 1));
 
     debug!("Pixel region: ({}, {}) to ({}, {})",
         min_x_pixel, min_y_pixel, max_x_pixel, max_y_pixel);
 
-    // Create a region, ensuring it's within bounds
+    let coverage = Coverage::new((min_x_pixel, min_y_pixel, max_x_pixel, max_y_pixel), img_width, img_height);
+    if let Some(clipped) = coverage.clipped {
+        return (clipped, coverage);
+    }
+
+    // No overlap: keep a minimal region at the nearest edge
     let x = min_x_pixel.max(0).min(img_width as i64 - 1) as u32;
     let y = min_y_pixel.max(0).min(img_height as i64 - 1) as u32;
     let width = ((max_x_pixel - min_x_pixel).max(1) as u32).min(img_width - x);
     let height = ((max_y_pixel - min_y_pixel).max(1) as u32).min(img_height - y);
 
-    Region::new(x, y, width, height)
+    (Region::new(x, y, width, height), coverage)
 }
 
 /// Convert WGS84 coordinates to Web Mercator pixels
@@ -229,18 +224,18 @@ fn convert_same_crs_to_pixels(
 ///
 /// # Arguments
 /// * `bbox` - The bounding box in WGS84 coordinates
-/// * `geotransform` - The geotransform array from the GeoTIFF
+/// * `geotransform` - The affine transform of the GeoTIFF
 /// * `img_width` - Image width in pixels
 /// * `img_height` - Image height in pixels
 ///
 /// # Returns
-/// A Region for extraction
+/// A Region for extraction and the coverage of the requested window
 fn convert_wgs84_to_web_mercator(
     bbox: &BoundingBox,
-    geotransform: &[f64],
+    geotransform: &Affine,
     img_width: u32,
     img_height: u32
-) -> Region {
+) -> (Region, Coverage) {
     info!("Converting WGS84 coordinates to Web Mercator for extraction");
 
     use std::f64::consts::PI;
@@ -271,21 +266,16 @@ fn convert_wgs84_to_web_mercator(
     debug!("Web Mercator bbox: x_min={}, y_min={}, x_max={}, y_max={}",
            x_min, y_min, x_max, y_max);
 
-    // Calculate pixel coordinates
-    let origin_x = geotransform[0];
-    let pixel_width = geotransform[1];
-    let origin_y = geotransform[3];
-    let pixel_height = geotransform[5]; // Usually negative
-
     // Convert to pixel coordinates - handle min/max ordering
-    let min_x_pixel = ((x_min - origin_x) / pixel_width).floor() as i64;
-    let max_y_pixel = ((y_min - origin_y) / pixel_height).floor() as i64;
-    let max_x_pixel = ((x_max - origin_x) / pixel_width).ceil() as i64;
-    let min_y_pixel = ((y_max - origin_y) / pixel_height).floor() as i64;
+    let mercator_bbox = BoundingBox::new(x_min, y_min, x_max, y_max);
+    let (min_x_pixel, min_y_pixel, max_x_pixel, max_y_pixel) =
+        geotransform.pixel_window(&mercator_bbox).unwrap_or((0, 0, 1, 1));
 
     debug!("Raw pixel coordinates: ({}, {}) to ({}, {})",
            min_x_pixel, min_y_pixel, max_x_pixel, max_y_pixel);
 
+    let coverage = Coverage::new((min_x_pixel, min_y_pixel, max_x_pixel, max_y_pixel), img_width, img_height);
+
     // Check if the region is within image bounds
     let x_in_bounds = min_x_pixel < img_width as i64 && max_x_pixel >= 0;
     let y_in_bounds = min_y_pixel < img_height as i64 && max_y_pixel >= 0;
@@ -295,7 +285,7 @@ fn convert_wgs84_to_web_mercator(
         // Calculate a sensible region size based on the radius if available
         let size = if let Some(radius) = bbox.radius_meters {
             // Convert radius from meters to pixels
-            ((radius * 2.0) / pixel_width.abs() as f64) as u32
+            ((radius * 2.0) / geotransform.pixel_width.abs()) as u32
         } else {
             1000 // Default size
         };
@@ -306,25 +296,27 @@ fn convert_wgs84_to_web_mercator(
 
         debug!("Region outside image bounds, using centered region of size {}", size);
 
-        return Region::new(
+        let region = Region::new(
             center_x.saturating_sub(size / 2),
             center_y.saturating_sub(size / 2),
             size.min(img_width),
             size.min(img_height)
         );
+        return (region, coverage);
     }
 
-    // Ensure coordinates are within bounds
-    let x = min_x_pixel.max(0).min(img_width as i64 - 1) as u32;
-    let y = min_y_pixel.max(0).min(img_height as i64 - 1) as u32;
-    let width = ((max_x_pixel - min_x_pixel).max(1) as u32).min(img_width - x);
-    let height = ((max_y_pixel - min_y_pixel).max(1) as u32).min(img_height - y);
+    // Keep the part of the window that lies on the image
+    let region = coverage.clipped.unwrap_or_else(|| Region::new(
+        min_x_pixel.clamp(0, img_width as i64 - 1) as u32,
+        min_y_pixel.clamp(0, img_height as i64 - 1) as u32,
+        1,
+        1
+    ));
 
     debug!("Adjusted pixel region: x={}, y={}, width={}, height={}",
-           x, y, width, height);
+           region.x, region.y, region.width, region.height);
 
-    // Create and return the region
-    Region::new(x, y, width, height)
+    (region, coverage)
 }
 
 /// Adjust a region to fit within image bounds
@@ -334,7 +326,7 @@ fn convert_wgs84_to_web_mercator(
 /// * `img_width` - Image width in pixels
 /// * `img_height` - Image height in pixels
 /// * `radius_meters` - Optional radius in meters for fallback sizing
-/// * `geotransform` - Geotransform array for converting meters to pixels
+/// * `geotransform` - Affine transform for converting meters to pixels
 ///
 /// # Returns
 /// An adjusted region that fits within the image bounds
@@ -343,7 +335,7 @@ fn adjust_region_to_image_bounds(
     img_width: u32,
     img_height: u32,
     radius_meters: Option<f64>,
-    geotransform: &[f64]
+    geotransform: &Affine
 ) -> Region {
     // If region is completely outside the image, return a reasonable default
     if region.x >= img_width || region.y >= img_height || region.width == 0 || region.height == 0 {
@@ -360,7 +352,7 @@ fn adjust_region_to_image_bounds(
         // Calculate size based on radius in meters if provided
         let size = if let Some(radius) = radius_meters {
             // Convert radius from meters to pixels using the geotransform
-            let pixel_width = geotransform[1].abs();  // Pixel width in map units (meters)
+            let pixel_width = geotransform.pixel_width.abs();  // Pixel width in map units (meters)
 
             // Calculate radius in pixels (each side of the square is 2*radius)
             let size_in_pixels = ((radius * 2.0) / pixel_width as f64).ceil() as u32;
@@ -413,10 +405,48 @@ fn adjust_region_to_image_bounds(
     Region::new(x, y, width, height)
 }
 
+/// Reproject a geographic bounding box into the image CRS
+///
+/// Used whenever the image CRS can be projected exactly, so that datum
+/// differences (e.g. WGS84 input on a NAD27 map) are accounted for. The
+/// fast Web Mercator path and same-CRS extractions are left alone.
+///
+/// # Arguments
+/// * `bbox` - Bounding box in the source CRS
+/// * `source_epsg` - EPSG code of the bounding box
+/// * `target_epsg` - EPSG code of the image
+/// * `target` - Definition of the image CRS, if known
+/// * `datum_grid` - Optional NTv2 grid from the image datum to WGS84
+///
+/// # Returns
+/// The bounding box in image coordinates, or None to fall back to the
+/// approximate conversion
+fn reproject_geographic_bbox(
+    bbox: &BoundingBox,
+    source_epsg: u32,
+    target_epsg: u32,
+    target: Option<&ProjDefinition>,
+    datum_grid: Option<&NtV2Grid>
+) -> Option<BoundingBox> {
+    if source_epsg == target_epsg || (source_epsg == 4326 && target_epsg == 3857) {
+        return None;
+    }
+
+    let source = ProjDefinition::from_epsg(source_epsg).filter(|d| d.is_geographic())?;
+    let target = target?;
+    let transformed = CoordinateTransformer.transform_bbox_to_definition(bbox, &source, target, datum_grid)?;
+
+    info!("Reprojected bbox from EPSG:{} ({}) to {}: [{:.3}, {:.3}, {:.3}, {:.3}]",
+        source_epsg, source.datum.name, target.name,
+        transformed.min_x, transformed.min_y, transformed.max_x, transformed.max_y);
+    Some(transformed)
+}
+
 /// Determine extraction region
 ///
 /// Based on the bounding box and GeoTIFF information, determines
-/// the region to extract in pixel coordinates.
+/// the region to extract in pixel coordinates. See
+/// `determine_extraction_window` for how much of the box the region covers.
 ///
 /// # Arguments
 /// * `bbox` - The bounding box in geographic or pixel coordinates
@@ -424,6 +454,7 @@ fn adjust_region_to_image_bounds(
 /// * `reader` - TIFF reader for accessing data
 /// * `input_file` - Path to the input file (fallback for file path)
 /// * `logger` - Logger for recording operations
+/// * `datum_grid` - Optional NTv2 grid from the image datum to WGS84
 ///
 /// # Returns
 /// A Region for extraction or an error
@@ -432,8 +463,38 @@ pub fn determine_extraction_region(
     tiff: &TIFF,
     reader: &TiffReader,
     input_file: &str,
-    logger: &Logger
+    logger: &Logger,
+    datum_grid: Option<&NtV2Grid>
 ) -> TiffResult<Region> {
+    determine_extraction_window(bbox, tiff, reader, input_file, logger, datum_grid)
+        .map(|(region, _)| region)
+}
+
+/// Determine the extraction region and its coverage of the bounding box
+///
+/// Bounding boxes that run off the image are clipped to it. The coverage
+/// records the requested window so callers can report or reject partial
+/// extractions. It is None for pixel-coordinate boxes and for conversions
+/// too approximate to measure.
+///
+/// # Arguments
+/// * `bbox` - The bounding box in geographic or pixel coordinates
+/// * `tiff` - The TIFF file structure
+/// * `reader` - TIFF reader for accessing data
+/// * `input_file` - Path to the input file (fallback for file path)
+/// * `logger` - Logger for recording operations
+/// * `datum_grid` - Optional NTv2 grid from the image datum to WGS84
+///
+/// # Returns
+/// The clipped region and its coverage, or an error
+pub fn determine_extraction_window(
+    bbox: BoundingBox,
+    tiff: &TIFF,
+    reader: &TiffReader,
+    input_file: &str,
+    logger: &Logger,
+    datum_grid: Option<&NtV2Grid>
+) -> TiffResult<(Region, Option<Coverage>)> {
     info!("Determining extraction region");
 
     // Create a direct conversion region as fallback
@@ -453,7 +514,7 @@ pub fn determine_extraction_region(
         epsg_code
     } else {
         info!("No source EPSG code specified, assuming direct pixel coordinates");
-        return Ok(direct_region);
+        return Ok((direct_region, None));
     };
 
     // Check for necessary conditions for geotransform
@@ -462,7 +523,7 @@ pub fn determine_extraction_region(
 
     if !has_geotiff_tags || tiff.ifds.is_empty() {
         info!("No GeoTIFF tags found, using bounding box as pixel coordinates");
-        return Ok(direct_region);
+        return Ok((direct_region, None));
     }
 
     let ifd = &tiff.ifds[0];
@@ -472,7 +533,7 @@ pub fn determine_extraction_region(
         Some(handler) => handler,
         None => {
             info!("No byte order handler available, using direct coordinate conversion");
-            return Ok(direct_region);
+            return Ok((direct_region, None));
         }
     };
 
@@ -483,7 +544,7 @@ pub fn determine_extraction_region(
         Some((w, h)) => (w as u32, h as u32),
         None => {
             warn!("Could not determine image dimensions");
-            return Ok(direct_region);
+            return Ok((direct_region, None));
         }
     };
 
@@ -502,36 +563,195 @@ pub fn determine_extraction_region(
                 },
                 Err(e) => {
                     warn!("Failed to extract GeoTIFF info: {}, using fallback", e);
-                    return Ok(direct_region);
+                    return Ok((direct_region, None));
                 }
             };
 
             let target_epsg = geo_info.epsg_code;
             info!("Image CRS is EPSG:{}", target_epsg);
 
-            // Use our more generic coordinate conversion function
-            let region = generic_crs_to_pixel_region(
-                &bbox,
-                &geotransform,
-                img_width,
-                img_height,
-                source_epsg,
-                target_epsg,
-                radius_meters
-            );
+            let target = ProjDefinition::from_geo_info(&geo_info);
+            let (region, coverage) = match reproject_geographic_bbox(&bbox, source_epsg, target_epsg, target.as_ref(), datum_grid) {
+                Some(projected) => {
+                    let (region, coverage) = convert_same_crs_to_pixels(&projected, &geotransform, img_width, img_height);
+                    (region, Some(coverage))
+                },
+                // Use our more generic coordinate conversion function
+                None => generic_crs_to_pixel_region(
+                    &bbox,
+                    &geotransform,
+                    img_width,
+                    img_height,
+                    source_epsg,
+                    target_epsg,
+                    radius_meters
+                ),
+            };
 
             info!("Final extraction region: x={}, y={}, width={}, height={}",
                 region.x, region.y, region.width, region.height);
 
-            Ok(region)
+            Ok((region, coverage))
         },
         Err(e) => {
             info!("GeoTIFF conversion failed: {}, using direct coordinate conversion", e);
-            Ok(direct_region)
+            Ok((direct_region, None))
         }
     }
 }
 
+/// Determine the extraction region from sidecar georeferencing
+///
+/// Counterpart of `determine_extraction_window` for images whose
+/// georeferencing lives in a world file or `.aux.xml` instead of GeoTIFF
+/// tags. When the sidecars do not name a CRS, the image is assumed to be
+/// in the bounding box CRS.
+///
+/// # Arguments
+/// * `bbox` - Bounding box with its EPSG code
+/// * `tiff` - Parsed TIFF structure
+/// * `sidecar` - Georeferencing read from the sidecar files
+/// * `datum_grid` - Optional NTv2 grid from the image datum to WGS84
+///
+/// # Returns
+/// The clipped pixel region and, when measurable, its coverage of the box
+pub fn determine_sidecar_extraction_window(
+    bbox: &BoundingBox,
+    tiff: &TIFF,
+    sidecar: &SidecarGeoreference,
+    datum_grid: Option<&NtV2Grid>
+) -> TiffResult<(Region, Option<Coverage>)> {
+    let source_epsg = bbox.epsg.ok_or_else(|| TiffError::GenericError(
+        "Sidecar georeferencing needs the bounding box CRS (--epsg or --crs)".to_string()))?;
+
+    let (img_width, img_height) = tiff.ifds.first()
+        .and_then(|ifd| ifd.get_dimensions())
+        .map(|(w, h)| (w as u32, h as u32))
+        .ok_or_else(|| TiffError::GenericError("Could not determine image dimensions".to_string()))?;
+
+    let target_epsg = sidecar.epsg.unwrap_or_else(|| {
+        warn!("Sidecar {} names no CRS, assuming EPSG:{}", sidecar.source, source_epsg);
+        source_epsg
+    });
+
+    let target = ProjDefinition::from_epsg(target_epsg);
+    let (region, coverage) = match reproject_geographic_bbox(bbox, source_epsg, target_epsg, target.as_ref(), datum_grid) {
+        Some(projected) => {
+            let (region, coverage) = convert_same_crs_to_pixels(&projected, &sidecar.geotransform, img_width, img_height);
+            (region, Some(coverage))
+        },
+        None => generic_crs_to_pixel_region(
+            bbox,
+            &sidecar.geotransform,
+            img_width,
+            img_height,
+            source_epsg,
+            target_epsg,
+            bbox.radius_meters
+        ),
+    };
+
+    info!("Sidecar extraction region: x={}, y={}, width={}, height={}",
+        region.x, region.y, region.width, region.height);
+
+    Ok((region, coverage))
+}
+
+/// Pad an extracted image to the requested window
+///
+/// For 8-bit renderings (PNG, JPEG, masked shapes) of the extracted image;
+/// GeoTIFF output is padded on the stored samples with `pad_samples`.
+///
+/// # Arguments
+/// * `image` - Extracted (clipped) image
+/// * `padding` - Placement of the image within the output
+/// * `fill` - Value of the padded pixels, clamped to the 8-bit range
+///
+/// # Returns
+/// An RGB image of the padded size
+pub fn pad_image(image: &DynamicImage, padding: &Padding, fill: f64) -> DynamicImage {
+    let value = fill.round().clamp(0.0, 255.0) as u8;
+    let mut padded = ImageBuffer::from_pixel(padding.width, padding.height, Rgb([value; 3]));
+    image::imageops::replace(&mut padded, &image.to_rgb8(), padding.offset_x, padding.offset_y);
+
+    info!("Padded {}x{} image to {}x{} with fill value {}",
+        image.width(), image.height(), padding.width, padding.height, value);
+    DynamicImage::ImageRgb8(padded)
+}
+
+/// Columns and rows of a region that land inside its padded output
+///
+/// # Returns
+/// The first column, the number of columns and the range of rows, in
+/// region pixels; no columns if the region lies outside the output
+fn padded_span(width: u32, height: u32, padding: &Padding) -> (i64, usize, std::ops::Range<i64>) {
+    let first_column = (-padding.offset_x).clamp(0, width as i64);
+    let end_column = (padding.width as i64 - padding.offset_x).clamp(first_column, width as i64);
+    let first_row = (-padding.offset_y).clamp(0, height as i64);
+    let end_row = (padding.height as i64 - padding.offset_y).clamp(first_row, height as i64);
+    (first_column, (end_column - first_column) as usize, first_row..end_row)
+}
+
+/// Pad a single-band buffer to a larger window
+///
+/// The counterpart of `pad_image` for bands decoded without an image,
+/// for samples of any type.
+///
+/// # Arguments
+/// * `data` - Row-major samples of the extracted region
+/// * `width` - Width of the extracted region
+/// * `height` - Height of the extracted region
+/// * `padding` - Output window and where the region goes in it
+/// * `fill` - Value of the pixels around the region
+///
+/// # Returns
+/// The samples of the padded window
+pub fn pad_band<T: Copy + std::fmt::Display>(data: &[T], width: u32, height: u32, padding: &Padding, fill: T) -> Vec<T> {
+    let mut padded = vec![fill; padding.width as usize * padding.height as usize];
+
+    let (first_column, columns, rows) = padded_span(width, height, padding);
+    for row in rows.filter(|_| columns > 0) {
+        let source = (row * width as i64 + first_column) as usize;
+        let target = ((row + padding.offset_y) * padding.width as i64 + first_column + padding.offset_x) as usize;
+        padded[target..target + columns].copy_from_slice(&data[source..source + columns]);
+    }
+
+    info!("Padded {}x{} band to {}x{} with fill value {}",
+        width, height, padding.width, padding.height, fill);
+    padded
+}
+
+/// Pad decoded samples of any type and band count to a larger window
+///
+/// The samples stay as stored; the fill value is converted to their type,
+/// so a Float32 window can be padded with -9999 or a UInt16 one with 65535.
+///
+/// # Arguments
+/// * `samples` - Little-endian samples of the extracted region, bands interleaved
+/// * `shape` - Shape and sample type of the extracted region
+/// * `padding` - Output window and where the region goes in it
+/// * `fill` - Value of the samples around the region
+///
+/// # Returns
+/// The little-endian samples of the padded window
+pub fn pad_samples(samples: &[u8], shape: &DecodedWindow, padding: &Padding, fill: f64) -> Vec<u8> {
+    let pixel_bytes = shape.bands * shape.bytes_per_sample;
+    let fill_pixel = shape.sample_bytes(fill).repeat(shape.bands);
+    let mut padded = fill_pixel.repeat(padding.width as usize * padding.height as usize);
+
+    let (first_column, columns, rows) = padded_span(shape.width, shape.height, padding);
+    let row_bytes = columns * pixel_bytes;
+    for row in rows.filter(|_| columns > 0) {
+        let source = (row * shape.width as i64 + first_column) as usize * pixel_bytes;
+        let target = ((row + padding.offset_y) * padding.width as i64 + first_column + padding.offset_x) as usize * pixel_bytes;
+        padded[target..target + row_bytes].copy_from_slice(&samples[source..source + row_bytes]);
+    }
+
+    info!("Padded {}x{} window of {} band(s) to {}x{} with fill value {}",
+        shape.width, shape.height, shape.bands, padding.width, padding.height, fill);
+    padded
+}
+
 /// Apply horizontal differencing predictor
 ///
 /// Reverses the horizontal differencing applied during compression,
@@ -544,12 +764,10 @@ pub fn determine_extraction_region(
 /// * `height` - Height in pixels
 pub fn apply_horizontal_predictor(data: &mut [u8], width: usize, height: usize) {
     for row in 0..height {
-        let start = row * width;
+        let start = min(row * width, data.len());
         let end = min(start + width, data.len());
 
-        for i in (start + 1)..end {
-            data[i] = data[i].wrapping_add(data[i - 1]);
-        }
+        simd_utils::undo_horizontal_differencing(&mut data[start..end], 1);
     }
 }
 
@@ -598,6 +816,60 @@ pub fn copy_pixel(
     true
 }
 
+/// Bytes each pixel takes in the decoded blocks of an image
+///
+/// Chunky images keep a pixel's samples side by side, so a block of an
+/// RGB image holds three bytes per pixel. Planar images are read from
+/// their first band only, one byte per pixel.
+///
+/// # Arguments
+/// * `ifd` - The image
+pub fn pixel_stride(ifd: &IFD) -> usize {
+    let planar = ifd.get_tag_value(tags::PLANAR_CONFIGURATION).unwrap_or(1) == planar_config::PLANAR as u64;
+    if planar { 1 } else { ifd.get_samples_per_pixel().max(1) as usize }
+}
+
+/// Copy a pixel of a block with interleaved samples to the output image
+///
+/// Like `copy_pixel`, but for blocks holding `stride` bytes per pixel.
+/// With three or more samples the first three are taken as red, green
+/// and blue; otherwise the first sample is copied as gray.
+///
+/// # Arguments
+/// * `data` - Source block data
+/// * `image` - Output image buffer
+/// * `global_x` - Global X coordinate in the original image
+/// * `global_y` - Global Y coordinate in the original image
+/// * `pixel_idx` - Index of the pixel within the block
+/// * `stride` - Bytes per pixel, as `pixel_stride` gives them
+/// * `region` - Region being extracted
+///
+/// # Returns
+/// `true` if the pixel was copied, `false` if it was outside the region or data
+pub fn copy_interleaved_pixel(
+    data: &[u8],
+    image: &mut ImageBuffer<Rgb<u8>, Vec<u8>>,
+    global_x: u32,
+    global_y: u32,
+    pixel_idx: usize,
+    stride: usize,
+    region: Region
+) -> bool {
+    if stride < 3 {
+        return copy_pixel(data, image, global_x, global_y, pixel_idx * stride, region);
+    }
+    if !is_in_region(global_x, global_y, &region) {
+        return false;
+    }
+    let start = pixel_idx * stride;
+    let Some(rgb) = data.get(start..start + 3) else {
+        return false;
+    };
+    let (buf_x, buf_y) = calc_buffer_coords(global_x, global_y, &region);
+    image.put_pixel(buf_x, buf_y, Rgb([rgb[0], rgb[1], rgb[2]]));
+    true
+}
+
 /// Check if a given point is within an extraction region
 ///
 /// A simple utility to check if a pixel is within the extraction region.
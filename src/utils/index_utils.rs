@@ -0,0 +1,353 @@
+//! Raster catalog indexes
+//!
+//! Writes a spatial index of a directory tree of rasters: one feature per
+//! file, its footprint as a WGS84 polygon and its header facts (size,
+//! bands, data type, compression, EPSG code, resolution, extent in its own
+//! CRS, acquisition time) as attributes. GIS tools then answer which files
+//! cover an area without opening any of them. The index is GeoJSON, one
+//! feature per line, or a GeoPackage with an R*Tree on the footprints.
+//!
+//! Indexes are read back for catalog extraction, which needs no more than
+//! the path, EPSG code and native extent of each raster. Indexes written by
+//! other tools that name the path `location`, as gdaltindex does, are read
+//! too; what they lack is taken from the raster headers later.
+
+use std::fs;
+use std::path::Path;
+
+use log::{info, warn};
+
+use crate::tiff::errors::{TiffError, TiffResult};
+use crate::utils::catalog_utils::{self, RasterRecord};
+use crate::utils::footprint_utils;
+use crate::utils::gpkg_utils;
+use crate::utils::logger::Logger;
+use crate::utils::query_utils::Query;
+use crate::utils::sqlite_utils::{self, SqlValue, SqliteWriter};
+
+/// Name of the features table of a GeoPackage index
+pub const INDEX_TABLE: &str = "footprints";
+
+/// Geometry column of the features table
+const GEOMETRY_COLUMN: &str = "geom";
+
+/// Attributes that may hold the raster path: ours, then gdaltindex's
+const PATH_FIELDS: [&str; 2] = ["path", "location"];
+
+/// Decimal places of longitudes and latitudes, about a millimetre
+const COORDINATE_PRECISION: usize = 8;
+
+/// File format of an index
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum IndexFormat {
+    /// GeoJSON feature collection
+    GeoJson,
+    /// GeoPackage with a features table
+    GeoPackage,
+}
+
+impl IndexFormat {
+    /// Pick the format from a file extension: `.gpkg` is a GeoPackage,
+    /// anything else GeoJSON
+    pub fn from_path(path: &str) -> Self {
+        match Path::new(path).extension().and_then(|extension| extension.to_str()) {
+            Some(extension) if extension.eq_ignore_ascii_case("gpkg") => IndexFormat::GeoPackage,
+            _ => IndexFormat::GeoJson,
+        }
+    }
+}
+
+/// A raster listed in an index
+#[derive(Debug, Clone, PartialEq)]
+pub struct IndexEntry {
+    /// Path of the raster as the index records it
+    pub path: String,
+    /// EPSG code of the raster's coordinate system, if recorded
+    pub epsg: Option<u32>,
+    /// Extent in the raster's own coordinate system as
+    /// (min_x, min_y, max_x, max_y), if recorded
+    pub bounds: Option<(f64, f64, f64, f64)>,
+}
+
+/// Longitude/latitude bounds of a footprint as [min_x, min_y, max_x, max_y]
+fn footprint_extent(ring: &[(f64, f64)]) -> [f64; 4] {
+    ring.iter().fold([f64::MAX, f64::MAX, f64::MIN, f64::MIN], |[min_x, min_y, max_x, max_y], (x, y)| {
+        [min_x.min(*x), min_y.min(*y), max_x.max(*x), max_y.max(*y)]
+    })
+}
+
+/// Bounds of all footprints, None if no raster has one
+fn union_extent(records: &[RasterRecord]) -> Option<[f64; 4]> {
+    records.iter()
+        .filter_map(|record| record.footprint.as_deref().map(footprint_extent))
+        .reduce(|a, b| [a[0].min(b[0]), a[1].min(b[1]), a[2].max(b[2]), a[3].max(b[3])])
+}
+
+/// Format an index as a GeoJSON feature collection
+///
+/// Rasters whose footprint cannot be placed in longitude/latitude are
+/// kept with a null geometry.
+///
+/// # Arguments
+/// * `records` - Rasters to index
+///
+/// # Returns
+/// The GeoJSON text, one feature per line
+pub fn index_geojson(records: &[RasterRecord]) -> String {
+    let bbox = |extent: [f64; 4]| format!("[{:.*}, {:.*}, {:.*}, {:.*}]",
+                                          COORDINATE_PRECISION, extent[0], COORDINATE_PRECISION, extent[1],
+                                          COORDINATE_PRECISION, extent[2], COORDINATE_PRECISION, extent[3]);
+    let features: Vec<String> = records.iter()
+        .map(|record| {
+            let properties = catalog_utils::record_json(record);
+            match &record.footprint {
+                Some(ring) => format!(
+                    "    {{\"type\": \"Feature\", \"bbox\": {}, \"properties\": {}, \
+                     \"geometry\": {{\"type\": \"Polygon\", \"coordinates\": [{}]}}}}",
+                    bbox(footprint_extent(ring)), properties,
+                    footprint_utils::ring_json(ring, true, COORDINATE_PRECISION)),
+                None => format!("    {{\"type\": \"Feature\", \"properties\": {}, \"geometry\": null}}", properties),
+            }
+        })
+        .collect();
+
+    format!("{{\n  \"type\": \"FeatureCollection\",{}\n  \"features\": [\n{}\n  ]\n}}\n",
+            union_extent(records).map(|extent| format!("\n  \"bbox\": {},", bbox(extent))).unwrap_or_default(),
+            features.join(",\n"))
+}
+
+/// Write an index as a GeoPackage
+///
+/// # Arguments
+/// * `records` - Rasters to index
+/// * `path` - Path of the GeoPackage to write
+pub fn write_geopackage_index(records: &[RasterRecord], path: &str) -> TiffResult<()> {
+    let text = |value: &str| SqlValue::Text(value.to_string());
+    let real = |value: Option<f64>| value.filter(|value| value.is_finite()).map_or(SqlValue::Null, SqlValue::Real);
+    let integer = |value: Option<i64>| value.map_or(SqlValue::Null, SqlValue::Integer);
+
+    let mut rows = Vec::with_capacity(records.len());
+    let mut footprints = Vec::new();
+    for (index, record) in records.iter().enumerate() {
+        let fid = index as i64 + 1;
+        if let Some(ring) = &record.footprint {
+            let [min_x, min_y, max_x, max_y] = footprint_extent(ring);
+            footprints.push((fid, [min_x, max_x, min_y, max_y]));
+        }
+        let bounds = record.bounds.map(|(min_x, min_y, max_x, max_y)| [min_x, min_y, max_x, max_y]);
+        let bound = |corner: usize| real(bounds.map(|bounds| bounds[corner]));
+        rows.push((fid, vec![
+            SqlValue::Null,
+            record.footprint.as_ref().map_or(SqlValue::Null, |ring| SqlValue::Blob(gpkg_utils::polygon_blob(std::slice::from_ref(ring)))),
+            text(&record.path),
+            SqlValue::Integer(record.width as i64),
+            SqlValue::Integer(record.height as i64),
+            SqlValue::Integer(record.bands as i64),
+            text(record.data_type),
+            text(&record.compression),
+            integer(record.epsg.map(i64::from)),
+            real(record.resolution),
+            real(record.nodata),
+            SqlValue::Integer(record.overviews as i64),
+            SqlValue::Integer(record.tiled as i64),
+            SqlValue::Integer(record.big_tiff as i64),
+            integer(record.file_size.map(|size| size as i64)),
+            record.acquired.map_or(SqlValue::Null, |time| text(&time.to_string())),
+            bound(0), bound(1), bound(2), bound(3),
+        ]));
+    }
+
+    let mut writer = SqliteWriter::new();
+    gpkg_utils::add_geopackage_features(&mut writer, INDEX_TABLE, GEOMETRY_COLUMN, "POLYGON",
+                                        "Raster footprints indexed by RasterKit", union_extent(records), true)?;
+    writer.add_table(
+        INDEX_TABLE,
+        &format!("CREATE TABLE {} (fid INTEGER PRIMARY KEY, {} POLYGON, path TEXT NOT NULL, \
+                  width INTEGER, height INTEGER, bands INTEGER, type TEXT, compression TEXT, epsg INTEGER, \
+                  resolution REAL, nodata REAL, overviews INTEGER, tiled BOOLEAN, bigtiff BOOLEAN, \
+                  file_size INTEGER, acquired DATETIME, min_x REAL, min_y REAL, max_x REAL, max_y REAL)",
+                 INDEX_TABLE, GEOMETRY_COLUMN),
+        rows)?;
+    writer.add_rtree(&format!("rtree_{}_{}", INDEX_TABLE, GEOMETRY_COLUMN),
+                     ["id", "minx", "maxx", "miny", "maxy"], &footprints)?;
+    writer.write(path)
+}
+
+/// Index the rasters under a directory
+///
+/// # Arguments
+/// * `root` - File or directory to index
+/// * `output_path` - Path of the index, `.gpkg` for a GeoPackage, else GeoJSON
+/// * `query` - Condition the indexed rasters must meet, None for all
+/// * `logger` - Logger for recording operations
+///
+/// # Returns
+/// The number of rasters indexed
+pub fn build_index(root: &str, output_path: &str, query: Option<&Query>, logger: &Logger) -> TiffResult<usize> {
+    let paths = catalog_utils::collect_rasters(root)?;
+    let records: Vec<RasterRecord> = catalog_utils::scan(&paths, logger).into_iter()
+        .filter(|record| query.is_none_or(|query| query.matches(record)))
+        .collect();
+
+    let unplaced = records.iter().filter(|record| record.footprint.is_none()).count();
+    if unplaced > 0 {
+        warn!("{} raster(s) cannot be placed in longitude/latitude and are indexed without a footprint", unplaced);
+    }
+
+    let format = IndexFormat::from_path(output_path);
+    match format {
+        IndexFormat::GeoJson => fs::write(output_path, index_geojson(&records))?,
+        IndexFormat::GeoPackage => write_geopackage_index(&records, output_path)?,
+    }
+
+    info!("Indexed {} raster(s) under {} as {:?}", records.len(), root, format);
+    logger.log(&format!("Wrote an index of {} raster(s) under {} to {}", records.len(), root, output_path))?;
+    Ok(records.len())
+}
+
+/// Read the rasters listed in an index
+///
+/// # Arguments
+/// * `path` - GeoJSON or GeoPackage index
+///
+/// # Returns
+/// The listed rasters in index order
+pub fn read_index(path: &str) -> TiffResult<Vec<IndexEntry>> {
+    let entries = match IndexFormat::from_path(path) {
+        IndexFormat::GeoJson => parse_geojson_index(&fs::read_to_string(path)?),
+        IndexFormat::GeoPackage => read_geopackage_index(path)?,
+    };
+    info!("Read {} raster(s) from the index {}", entries.len(), path);
+    Ok(entries)
+}
+
+/// Parse the features of a GeoJSON index
+///
+/// Each feature's properties are read for the raster path, EPSG code and
+/// bounds; features without a path are skipped.
+pub fn parse_geojson_index(text: &str) -> Vec<IndexEntry> {
+    let mut entries = Vec::new();
+    let mut rest = text;
+    while let Some(start) = rest.find("\"properties\"") {
+        rest = &rest[start + "\"properties\"".len()..];
+        let Some(properties) = json_object(rest) else { continue };
+        let Some(path) = PATH_FIELDS.iter().find_map(|field| json_string(properties, field)) else { continue };
+        let bounds = json_numbers(properties, "bounds")
+            .filter(|bounds| bounds.len() == 4)
+            .map(|bounds| (bounds[0], bounds[1], bounds[2], bounds[3]));
+        let epsg = json_numbers(properties, "epsg")
+            .and_then(|values| values.first().copied())
+            .filter(|epsg| *epsg > 0.0 && epsg.fract() == 0.0)
+            .map(|epsg| epsg as u32);
+        entries.push(IndexEntry { path, epsg, bounds });
+    }
+    entries
+}
+
+/// Read the features table of a GeoPackage index
+fn read_geopackage_index(path: &str) -> TiffResult<Vec<IndexEntry>> {
+    // Ours is called `footprints`; another tool's index names its table in
+    // gpkg_contents
+    let table = match sqlite_utils::read_table(path, INDEX_TABLE) {
+        Ok(table) => table,
+        Err(_) => {
+            let (columns, rows) = sqlite_utils::read_table(path, "gpkg_contents")?;
+            let column = |name: &str| columns.iter().position(|column| column == name);
+            let name = column("table_name").zip(column("data_type"))
+                .and_then(|(name, data_type)| rows.iter().find_map(|row| match (&row[name], &row[data_type]) {
+                    (SqlValue::Text(name), SqlValue::Text(data_type)) if data_type == "features" => Some(name.clone()),
+                    _ => None,
+                }))
+                .ok_or_else(|| TiffError::GenericError(format!("{} has no features table", path)))?;
+            sqlite_utils::read_table(path, &name)?
+        },
+    };
+
+    let (columns, rows) = table;
+    let column = |name: &str| columns.iter().position(|column| column.eq_ignore_ascii_case(name));
+    let path_column = PATH_FIELDS.iter().find_map(|field| column(field))
+        .ok_or_else(|| TiffError::GenericError(format!("The index {} has no path column", path)))?;
+    let epsg_column = column("epsg");
+    let bound_columns: Option<Vec<usize>> = ["min_x", "min_y", "max_x", "max_y"].iter().map(|name| column(name)).collect();
+
+    let number = |value: &SqlValue| match value {
+        SqlValue::Integer(value) => Some(*value as f64),
+        SqlValue::Real(value) => Some(*value),
+        _ => None,
+    };
+    Ok(rows.iter()
+        .filter_map(|row| {
+            let SqlValue::Text(raster) = &row[path_column] else { return None };
+            let epsg = epsg_column.and_then(|column| number(&row[column])).map(|epsg| epsg as u32);
+            let bounds = bound_columns.as_ref()
+                .and_then(|columns| columns.iter().map(|&column| number(&row[column])).collect::<Option<Vec<f64>>>())
+                .map(|bounds| (bounds[0], bounds[1], bounds[2], bounds[3]));
+            Some(IndexEntry { path: raster.clone(), epsg, bounds })
+        })
+        .collect())
+}
+
+/// The JSON object starting after a key, up to its closing brace
+fn json_object(text: &str) -> Option<&str> {
+    let start = text.find(|c: char| !c.is_whitespace() && c != ':')?;
+    if !text[start..].starts_with('{') {
+        return None;
+    }
+    let (mut depth, mut in_string, mut escaped) = (0, false, false);
+    for (offset, c) in text[start..].char_indices() {
+        match c {
+            _ if escaped => escaped = false,
+            '\\' if in_string => escaped = true,
+            '"' => in_string = !in_string,
+            '{' if !in_string => depth += 1,
+            '}' if !in_string => {
+                depth -= 1;
+                if depth == 0 {
+                    return Some(&text[start..start + offset + 1]);
+                }
+            },
+            _ => {},
+        }
+    }
+    None
+}
+
+/// Text after `"key":` in a JSON object
+fn json_value<'a>(object: &'a str, key: &str) -> Option<&'a str> {
+    let pattern = format!("\"{}\"", key);
+    let after_key = &object[object.find(&pattern)? + pattern.len()..];
+    Some(after_key.trim_start().strip_prefix(':')?.trim_start())
+}
+
+/// String value of a key, with escapes resolved
+fn json_string(object: &str, key: &str) -> Option<String> {
+    let mut chars = json_value(object, key)?.strip_prefix('"')?.chars();
+    let mut value = String::new();
+    while let Some(c) = chars.next() {
+        match c {
+            '"' => return Some(value),
+            '\\' => match chars.next()? {
+                'n' => value.push('\n'),
+                't' => value.push('\t'),
+                'r' => value.push('\r'),
+                'u' => {
+                    let code: String = chars.by_ref().take(4).collect();
+                    value.push(u32::from_str_radix(&code, 16).ok().and_then(char::from_u32)?);
+                },
+                escaped => value.push(escaped),
+            },
+            c => value.push(c),
+        }
+    }
+    None
+}
+
+/// A number, or an array of numbers, as the value of a key
+fn json_numbers(object: &str, key: &str) -> Option<Vec<f64>> {
+    let value = json_value(object, key)?;
+    let text = match value.strip_prefix('[') {
+        Some(array) => &array[..array.find(']')?],
+        None => &value[..value.find([',', '}']).unwrap_or(value.len())],
+    };
+    text.split(',').map(|number| number.trim().parse::<f64>().ok()).collect()
+}
@@ -0,0 +1,252 @@
+//! gdalinfo-style raster summaries
+//!
+//! Formats the facts scripts usually scrape from `gdalinfo` (driver, size,
+//! coordinate system, origin and pixel size, corner coordinates, band
+//! types and blocks, NoData, overviews) in the same layout, so that
+//! existing parsing keeps working against rasterkit's output.
+
+use std::io::BufReader;
+use std::path::Path;
+
+use log::{debug, info};
+
+use crate::io::storage;
+use crate::coordinate::{projection, Affine};
+use crate::tiff::constants::{compression, photometric, tags};
+use crate::tiff::errors::{TiffError, TiffResult};
+use crate::tiff::geo_key_parser::GeoKeyParser;
+use crate::tiff::ifd::IFD;
+use crate::tiff::proj_definition::ProjDefinition;
+use crate::tiff::types::TIFF;
+use crate::tiff::TiffReader;
+use crate::utils::logger::Logger;
+use crate::utils::sample_utils::{self, SampleFormat, SampleLayout};
+use crate::utils::{image_extraction_utils, scaling_utils, sidecar_utils, tiff_extraction_utils};
+
+/// Georeferencing of the raster being described
+pub struct Georeferencing {
+    /// Corner-based geotransform
+    pub geotransform: Affine,
+    /// Coordinate system, when it can be described
+    pub definition: Option<ProjDefinition>,
+}
+
+/// GDAL data type name of a sample layout
+///
+/// Odd bit depths map to the smallest type holding them, as GDAL reads
+/// 12-bit samples as UInt16.
+pub(crate) fn gdal_type_name(layout: &SampleLayout) -> &'static str {
+    match (layout.format, layout.bits) {
+        (SampleFormat::Float, bits) if bits <= 32 => "Float32",
+        (SampleFormat::Float, _) => "Float64",
+        (SampleFormat::Signed, bits) if bits <= 8 => "Int8",
+        (SampleFormat::Signed, bits) if bits <= 16 => "Int16",
+        (SampleFormat::Signed, bits) if bits <= 32 => "Int32",
+        (SampleFormat::Signed, _) => "Int64",
+        (SampleFormat::Unsigned, bits) if bits <= 8 => "Byte",
+        (SampleFormat::Unsigned, bits) if bits <= 16 => "UInt16",
+        (SampleFormat::Unsigned, bits) if bits <= 32 => "UInt32",
+        (SampleFormat::Unsigned, _) => "UInt64",
+    }
+}
+
+/// GDAL name of a TIFF compression, None when uncompressed
+pub(crate) fn gdal_compression_name(code: u64) -> Option<&'static str> {
+    match code as u16 {
+        compression::NONE => None,
+        compression::CCITT_RLE => Some("CCITTRLE"),
+        compression::CCITT_FAX3 => Some("CCITTFAX3"),
+        compression::CCITT_FAX4 => Some("CCITTFAX4"),
+        compression::LZW => Some("LZW"),
+        compression::JPEG_OLD => Some("OJPEG"),
+        compression::JPEG => Some("JPEG"),
+        compression::DEFLATE | 32946 => Some("DEFLATE"),
+        compression::ZSTD => Some("ZSTD"),
+        compression::PACKBITS => Some("PACKBITS"),
+        _ => Some("UNKNOWN"),
+    }
+}
+
+/// GDAL colour interpretation of each band
+fn color_interpretations(ifd: &IFD, bands: usize) -> Vec<&'static str> {
+    let names: &[&'static str] = match ifd.get_tag_value(tags::PHOTOMETRIC_INTERPRETATION).map(|value| value as u16) {
+        Some(photometric::RGB) => &["Red", "Green", "Blue"],
+        Some(photometric::YCBCR) if ifd.get_tag_value(tags::COMPRESSION) == Some(compression::JPEG as u64) => &["Red", "Green", "Blue"],
+        Some(photometric::YCBCR) => &["YCbCr_Y", "YCbCr_Cb", "YCbCr_Cr"],
+        Some(photometric::CMYK) => &["Cyan", "Magenta", "Yellow", "Black"],
+        Some(photometric::PALETTE) => &["Palette"],
+        _ => &["Gray"],
+    };
+    // A single extra sample flagged as associated or unassociated alpha
+    let alpha = matches!(ifd.get_tag_value(tags::EXTRA_SAMPLES), Some(1) | Some(2));
+
+    (0..bands)
+        .map(|band| match names.get(band) {
+            Some(name) => name,
+            None if alpha && band == names.len() => "Alpha",
+            None => "Undefined",
+        })
+        .collect()
+}
+
+/// Read the georeferencing from GeoTIFF tags or, failing that, sidecars
+///
+/// # Arguments
+/// * `tiff` - Parsed TIFF structure
+/// * `ifd` - The IFD to georeference
+/// * `reader` - Reader the file was loaded with
+/// * `input_path` - Path to the TIFF, for its tag data and sidecars
+///
+/// # Returns
+/// The geotransform with the coordinate system, or None if neither the
+/// tags nor any sidecar place the raster
+pub fn read_georeferencing(tiff: &TIFF, ifd: &IFD, reader: &TiffReader, input_path: &str) -> Option<Georeferencing> {
+    if sidecar_utils::has_internal_georeference(tiff) {
+        let handler = reader.get_byte_order_handler()?;
+        let geotransform = image_extraction_utils::calculate_geotransform(ifd, handler, input_path).ok()?;
+        let definition = GeoKeyParser::extract_geo_info(ifd, handler, input_path).ok()
+            .and_then(|geo_info| ProjDefinition::from_geo_info(&geo_info));
+        return Some(Georeferencing { geotransform, definition });
+    }
+
+    let sidecar = sidecar_utils::find_sidecar_georeference(input_path).ok().flatten()?;
+    debug!("Georeferencing of {} read from {}", input_path, sidecar.source);
+    let definition = sidecar.wkt.as_deref().and_then(|wkt| ProjDefinition::from_wkt(wkt).ok())
+        .or_else(|| sidecar.epsg.and_then(ProjDefinition::from_epsg));
+    Some(Georeferencing { geotransform: sidecar.geotransform, definition })
+}
+
+/// Format an angle as gdalinfo does, e.g. `117d38'28.21"W`
+///
+/// # Arguments
+/// * `degrees` - Angle in decimal degrees
+/// * `positive` - Hemisphere letter of positive angles
+/// * `negative` - Hemisphere letter of negative angles
+///
+/// # Returns
+/// The angle in degrees, minutes and seconds
+pub fn format_dms(degrees: f64, positive: char, negative: char) -> String {
+    let hemisphere = if degrees < 0.0 { negative } else { positive };
+    // Round to hundredths of a second first so 59.999" carries over
+    let hundredths = (degrees.abs() * 360_000.0).round() as u64;
+    let (whole, minutes, seconds) = (hundredths / 360_000, hundredths / 6_000 % 60, (hundredths % 6_000) as f64 / 100.0);
+    format!("{:3}d{:2}'{:5.2}\"{}", whole, minutes, seconds, hemisphere)
+}
+
+/// One line of the corner coordinate listing
+fn corner_line(label: &str, column: f64, row: f64, georeferencing: Option<&Georeferencing>) -> String {
+    let Some(georeferencing) = georeferencing else {
+        return format!("{}({:>7.1},{:>7.1})", label, column, row);
+    };
+
+    let (x, y) = georeferencing.geotransform.apply(column, row);
+    let Some(definition) = &georeferencing.definition else {
+        return format!("{}({:>12.3},{:>12.3})", label, x, y);
+    };
+    let coordinates = if definition.is_geographic() {
+        format!("{}({:>12.7},{:>12.7})", label, x, y)
+    } else {
+        format!("{}({:>12.3},{:>12.3})", label, x, y)
+    };
+
+    if !projection::is_supported(definition) {
+        return coordinates;
+    }
+    match projection::inverse(definition, x, y) {
+        Some(point) => format!("{} ({},{})", coordinates, format_dms(point.x, 'E', 'W'), format_dms(point.y, 'N', 'S')),
+        None => coordinates,
+    }
+}
+
+/// Sizes of the reduced-resolution images of the main image
+pub(crate) fn overview_sizes(tiff: &TIFF, ifd: &IFD) -> Vec<String> {
+    let is_overview = |candidate: &&IFD| candidate.get_tag_value(tags::NEW_SUBFILE_TYPE).unwrap_or(0) & 5 == 1;
+
+    tiff.ifds.iter().skip(1).filter(is_overview)
+        .chain(ifd.sub_ifds.iter().filter(is_overview))
+        .filter_map(|overview| overview.get_dimensions())
+        .map(|(width, height)| format!("{}x{}", width, height))
+        .collect()
+}
+
+/// Describe a TIFF in gdalinfo's layout
+///
+/// # Arguments
+/// * `input_path` - Path to the TIFF
+/// * `logger` - Logger for recording operations
+///
+/// # Returns
+/// The description, one field per line
+pub fn raster_info(input_path: &str, logger: &Logger) -> TiffResult<String> {
+    let mut reader = TiffReader::new(logger);
+    let tiff = reader.load(input_path)?;
+    let ifd = tiff.main_ifd()
+        .ok_or_else(|| TiffError::GenericError("No IFDs found in TIFF file".to_string()))?;
+    let mut file = BufReader::new(storage::open(input_path)?);
+    let layout = sample_utils::read_layout(&reader, ifd, &mut file)?;
+    let georeferencing = read_georeferencing(&tiff, ifd, &reader, input_path);
+
+    let mut lines = vec!["Driver: GTiff/GeoTIFF".to_string(), format!("Files: {}", input_path)];
+    let sidecars = [format!("{}.aux.xml", input_path), sidecar_utils::world_file_path(input_path)];
+    for sidecar in sidecars.iter().filter(|sidecar| Path::new(sidecar).exists()) {
+        lines.push(format!("       {}", sidecar));
+    }
+    lines.push(format!("Size is {}, {}", layout.width, layout.height));
+
+    if let Some(georeferencing) = &georeferencing {
+        if let Some(definition) = &georeferencing.definition {
+            lines.push("Coordinate System is:".to_string());
+            lines.push(definition.to_wkt());
+        }
+        let [origin_x, pixel_width, row_rotation, origin_y, column_rotation, pixel_height] =
+            georeferencing.geotransform.to_gdal();
+        lines.push(format!("Origin = ({:.15},{:.15})", origin_x, origin_y));
+        lines.push(format!("Pixel Size = ({:.15},{:.15})", pixel_width, pixel_height));
+        if row_rotation != 0.0 || column_rotation != 0.0 {
+            lines.push(format!("GeoTransform rotation = ({:.15},{:.15})", row_rotation, column_rotation));
+        }
+    }
+
+    lines.push("Image Structure Metadata:".to_string());
+    if let Some(name) = gdal_compression_name(ifd.get_tag_value(tags::COMPRESSION).unwrap_or(1)) {
+        lines.push(format!("  COMPRESSION={}", name));
+    }
+    lines.push(format!("  INTERLEAVE={}", if layout.bands > 1 && !layout.planar { "PIXEL" } else { "BAND" }));
+
+    lines.push("Corner Coordinates:".to_string());
+    let (width, height) = (layout.width as f64, layout.height as f64);
+    for (label, column, row) in [("Upper Left  ", 0.0, 0.0), ("Lower Left  ", 0.0, height),
+                                 ("Upper Right ", width, 0.0), ("Lower Right ", width, height),
+                                 ("Center      ", width / 2.0, height / 2.0)] {
+        lines.push(corner_line(label, column, row, georeferencing.as_ref()));
+    }
+
+    let nodata = ifd.has_tag(tags::GDAL_NODATA)
+        .then(|| tiff_extraction_utils::extract_nodata_value(ifd, &reader).trim().to_string());
+    let scaling = tiff_extraction_utils::extract_gdal_metadata(ifd, &reader)
+        .map(|xml| scaling_utils::scaling_from_gdal_metadata(&xml))
+        .unwrap_or_default();
+    let overviews = overview_sizes(&tiff, ifd);
+    let has_mask = tiff.ifds.iter().chain(ifd.sub_ifds.iter())
+        .any(|candidate| candidate.get_tag_value(tags::NEW_SUBFILE_TYPE).unwrap_or(0) & 4 == 4);
+
+    for (band, interpretation) in color_interpretations(ifd, layout.bands).iter().enumerate() {
+        lines.push(format!("Band {} Block={}x{} Type={}, ColorInterp={}",
+                           band + 1, layout.block_width, layout.block_height, gdal_type_name(&layout), interpretation));
+        if let Some(nodata) = &nodata {
+            lines.push(format!("  NoData Value={}", nodata));
+        }
+        if !overviews.is_empty() {
+            lines.push(format!("  Overviews: {}", overviews.join(", ")));
+        }
+        if has_mask {
+            lines.push("  Mask Flags: PER_DATASET".to_string());
+        }
+        if let Some(band_scaling) = scaling.get(band).filter(|band_scaling| !band_scaling.is_identity()) {
+            lines.push(format!("  Offset: {},   Scale:{}", band_scaling.offset, band_scaling.scale));
+        }
+    }
+
+    info!("Described {} ({} band(s), {} overview(s))", input_path, layout.bands, overviews.len());
+    Ok(lines.join("\n"))
+}
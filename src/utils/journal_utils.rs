@@ -0,0 +1,172 @@
+//! Journals of completed work
+//!
+//! Converting a large file or cutting it into thousands of chips can take
+//! hours, and a crash or a preempted machine would otherwise mean starting
+//! over. Such jobs keep a small journal next to their output, one line per
+//! completed block or chip. A re-run with `--resume` reads it back and
+//! skips what is already written; a successful run removes it.
+//!
+//! The first line identifies the job (input size and modification time,
+//! output settings), so a journal is only honored for the same job. Entries
+//! are committed in batches, each after the output data they describe has
+//! been synced to disk.
+
+use std::collections::HashMap;
+use std::fs::{self, File, OpenOptions};
+use std::io::Write;
+use std::path::{Path, PathBuf};
+use std::time::UNIX_EPOCH;
+
+use log::{info, warn};
+
+use crate::tiff::errors::{TiffError, TiffResult};
+
+/// First word of every journal, followed by the format version
+const MAGIC: &str = "rasterkit-journal 1";
+
+/// Journal of one job
+pub struct Journal {
+    /// Path of the journal file
+    path: PathBuf,
+    /// The journal file, open for appending
+    file: File,
+    /// Entries of earlier runs, by key
+    completed: HashMap<String, String>,
+    /// Entries recorded since the last commit
+    pending: Vec<(String, String)>,
+    /// Output file synced before each commit, if any
+    output: Option<File>,
+}
+
+/// Identify an input file by path, size and modification time
+///
+/// # Arguments
+/// * `path` - The input file
+///
+/// # Returns
+/// A string that changes whenever the file is replaced or modified
+pub fn file_fingerprint(path: &str) -> TiffResult<String> {
+    let metadata = fs::metadata(path)?;
+    let modified = metadata.modified().ok()
+        .and_then(|time| time.duration_since(UNIX_EPOCH).ok())
+        .map_or(0, |since| since.as_nanos());
+    Ok(format!("{} {} {}", path, metadata.len(), modified))
+}
+
+impl Journal {
+    /// Open the journal of a job
+    ///
+    /// # Arguments
+    /// * `path` - Path of the journal file
+    /// * `job` - Description of the job; a journal of another job is never resumed
+    /// * `resume` - Whether to honor the entries of an earlier run
+    ///
+    /// # Returns
+    /// The journal, holding the earlier entries when resuming the same job
+    pub fn open(path: &Path, job: &str, resume: bool) -> TiffResult<Self> {
+        let header = format!("{} {}", MAGIC, job.replace('\n', " "));
+        let completed = match fs::read_to_string(path) {
+            Ok(text) if resume => Self::parse(path, &text, &header),
+            Ok(_) => {
+                warn!("Found the journal {} of an interrupted run; starting over (pass --resume to continue it)",
+                      path.display());
+                HashMap::new()
+            },
+            Err(_) => {
+                if resume {
+                    info!("No journal at {}; starting from the beginning", path.display());
+                }
+                HashMap::new()
+            },
+        };
+
+        // Rewrite the journal with only the entries that will be honored
+        let mut file = File::create(path)?;
+        writeln!(file, "{}", header)?;
+        for (key, value) in &completed {
+            writeln!(file, "{}\t{}", key, value)?;
+        }
+        file.sync_data()?;
+        let file = OpenOptions::new().append(true).open(path)?;
+
+        Ok(Journal { path: path.to_path_buf(), file, completed, pending: Vec::new(), output: None })
+    }
+
+    /// Entries of a journal file written for the same job
+    fn parse(path: &Path, text: &str, header: &str) -> HashMap<String, String> {
+        let mut lines = text.lines();
+        if lines.next() != Some(header) {
+            warn!("The journal {} belongs to another job or input; starting over", path.display());
+            return HashMap::new();
+        }
+        // A last line cut short by a crash lacks its newline and is ignored
+        let complete = if text.ends_with('\n') { text.lines().count() } else { text.lines().count() - 1 };
+        let completed: HashMap<String, String> = lines
+            .take(complete.saturating_sub(1))
+            .filter_map(|line| line.split_once('\t'))
+            .map(|(key, value)| (key.to_string(), value.to_string()))
+            .collect();
+        info!("Resuming from {}: {} entries already done", path.display(), completed.len());
+        completed
+    }
+
+    /// Sync this file before committing entries
+    ///
+    /// # Arguments
+    /// * `output` - A handle of the output file the entries describe
+    pub fn sync_before_commit(&mut self, output: File) {
+        self.output = Some(output);
+    }
+
+    /// Whether the job is continuing an earlier run
+    pub fn is_resuming(&self) -> bool {
+        !self.completed.is_empty()
+    }
+
+    /// Value recorded for a key by an earlier run
+    ///
+    /// # Arguments
+    /// * `key` - Key of the block or item, e.g. "ifd0/strip12"
+    pub fn completed(&self, key: &str) -> Option<&str> {
+        self.completed.get(key).map(String::as_str)
+    }
+
+    /// Record a completed block or item, written out at the next commit
+    ///
+    /// # Arguments
+    /// * `key` - Key of the block or item
+    /// * `value` - What a resumed run needs to know about it
+    pub fn record(&mut self, key: String, value: String) {
+        self.pending.push((key, value));
+    }
+
+    /// Number of entries recorded since the last commit
+    pub fn pending(&self) -> usize {
+        self.pending.len()
+    }
+
+    /// Write the recorded entries out, after syncing the output
+    ///
+    /// Buffered output must be flushed before calling this.
+    pub fn commit(&mut self) -> TiffResult<()> {
+        if self.pending.is_empty() {
+            return Ok(());
+        }
+        if let Some(output) = &self.output {
+            output.sync_data()?;
+        }
+        let mut lines = String::new();
+        for (key, value) in self.pending.drain(..) {
+            lines.push_str(&format!("{}\t{}\n", key, value));
+        }
+        self.file.write_all(lines.as_bytes())?;
+        self.file.sync_data()?;
+        Ok(())
+    }
+
+    /// Remove the journal of a finished job
+    pub fn finish(self) -> TiffResult<()> {
+        fs::remove_file(&self.path)
+            .map_err(|e| TiffError::GenericError(format!("Failed to remove journal {}: {}", self.path.display(), e)))
+    }
+}
@@ -0,0 +1,311 @@
+//! Colormap legend rendering
+//!
+//! Renders a colormap as a small legend image: a vertical color bar with
+//! tick labels for ramp colormaps, or a column of labeled swatches for
+//! value and interval colormaps. Labels use a built-in 5x7 bitmap font so
+//! no font files are needed.
+
+use image::{Rgb, RgbImage};
+use log::info;
+
+use crate::tiff::colormap::{ColorMap, RgbColor};
+use crate::tiff::errors::{TiffError, TiffResult};
+
+/// Pixel scale applied to the bitmap font
+const FONT_SCALE: u32 = 2;
+/// Glyph width in font pixels
+const GLYPH_WIDTH: u32 = 5;
+/// Glyph height in font pixels
+const GLYPH_HEIGHT: u32 = 7;
+/// Horizontal advance per character in font pixels
+const GLYPH_ADVANCE: u32 = 6;
+/// Outer margin in pixels
+const MARGIN: u32 = 10;
+/// Width of the color bar or swatches in pixels
+const SWATCH_WIDTH: u32 = 28;
+/// Height of a discrete swatch in pixels
+const SWATCH_HEIGHT: u32 = 20;
+/// Vertical gap between swatches in pixels
+const SWATCH_GAP: u32 = 6;
+/// Height of a continuous color bar in pixels
+const BAR_HEIGHT: u32 = 256;
+/// Gap between the bar/swatches and the labels in pixels
+const LABEL_GAP: u32 = 8;
+
+const BACKGROUND: Rgb<u8> = Rgb([255, 255, 255]);
+const FOREGROUND: Rgb<u8> = Rgb([0, 0, 0]);
+
+/// How a colormap is drawn in the legend
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum LegendStyle {
+    /// A continuous color bar with ticks at the colormap entries
+    Continuous,
+    /// One labeled swatch per colormap entry
+    Discrete,
+}
+
+impl LegendStyle {
+    /// Parse a legend style name
+    ///
+    /// # Arguments
+    /// * `name` - "continuous"/"ramp" or "discrete"/"classes" (case-insensitive)
+    ///
+    /// # Returns
+    /// The legend style or an error
+    pub fn from_string(name: &str) -> TiffResult<Self> {
+        match name.to_lowercase().as_str() {
+            "continuous" | "ramp" => Ok(LegendStyle::Continuous),
+            "discrete" | "classes" => Ok(LegendStyle::Discrete),
+            _ => Err(TiffError::GenericError(format!(
+                "Unknown legend style '{}' (expected 'continuous' or 'discrete')", name))),
+        }
+    }
+
+    /// Pick the natural style for a colormap (ramps are continuous)
+    ///
+    /// # Arguments
+    /// * `colormap` - The colormap to be drawn
+    ///
+    /// # Returns
+    /// The legend style matching the colormap type
+    pub fn for_colormap(colormap: &ColorMap) -> Self {
+        if colormap.map_type == "ramp" && colormap.len() > 1 {
+            LegendStyle::Continuous
+        } else {
+            LegendStyle::Discrete
+        }
+    }
+}
+
+/// 5x7 bitmap of a character, one byte per row, bit 4 being the leftmost pixel
+fn glyph(c: char) -> [u8; 7] {
+    match c.to_ascii_uppercase() {
+        '0' => [0x0E, 0x11, 0x13, 0x15, 0x19, 0x11, 0x0E],
+        '1' => [0x04, 0x0C, 0x04, 0x04, 0x04, 0x04, 0x0E],
+        '2' => [0x0E, 0x11, 0x01, 0x02, 0x04, 0x08, 0x1F],
+        '3' => [0x1F, 0x02, 0x04, 0x02, 0x01, 0x11, 0x0E],
+        '4' => [0x02, 0x06, 0x0A, 0x12, 0x1F, 0x02, 0x02],
+        '5' => [0x1F, 0x10, 0x1E, 0x01, 0x01, 0x11, 0x0E],
+        '6' => [0x06, 0x08, 0x10, 0x1E, 0x11, 0x11, 0x0E],
+        '7' => [0x1F, 0x01, 0x02, 0x04, 0x08, 0x08, 0x08],
+        '8' => [0x0E, 0x11, 0x11, 0x0E, 0x11, 0x11, 0x0E],
+        '9' => [0x0E, 0x11, 0x11, 0x0F, 0x01, 0x02, 0x0C],
+        'A' => [0x0E, 0x11, 0x11, 0x1F, 0x11, 0x11, 0x11],
+        'B' => [0x1E, 0x11, 0x11, 0x1E, 0x11, 0x11, 0x1E],
+        'C' => [0x0E, 0x11, 0x10, 0x10, 0x10, 0x11, 0x0E],
+        'D' => [0x1C, 0x12, 0x11, 0x11, 0x11, 0x12, 0x1C],
+        'E' => [0x1F, 0x10, 0x10, 0x1E, 0x10, 0x10, 0x1F],
+        'F' => [0x1F, 0x10, 0x10, 0x1E, 0x10, 0x10, 0x10],
+        'G' => [0x0E, 0x11, 0x10, 0x17, 0x11, 0x11, 0x0F],
+        'H' => [0x11, 0x11, 0x11, 0x1F, 0x11, 0x11, 0x11],
+        'I' => [0x0E, 0x04, 0x04, 0x04, 0x04, 0x04, 0x0E],
+        'J' => [0x07, 0x02, 0x02, 0x02, 0x02, 0x12, 0x0C],
+        'K' => [0x11, 0x12, 0x14, 0x18, 0x14, 0x12, 0x11],
+        'L' => [0x10, 0x10, 0x10, 0x10, 0x10, 0x10, 0x1F],
+        'M' => [0x11, 0x1B, 0x15, 0x15, 0x11, 0x11, 0x11],
+        'N' => [0x11, 0x11, 0x19, 0x15, 0x13, 0x11, 0x11],
+        'O' => [0x0E, 0x11, 0x11, 0x11, 0x11, 0x11, 0x0E],
+        'P' => [0x1E, 0x11, 0x11, 0x1E, 0x10, 0x10, 0x10],
+        'Q' => [0x0E, 0x11, 0x11, 0x11, 0x15, 0x12, 0x0D],
+        'R' => [0x1E, 0x11, 0x11, 0x1E, 0x14, 0x12, 0x11],
+        'S' => [0x0F, 0x10, 0x10, 0x0E, 0x01, 0x01, 0x1E],
+        'T' => [0x1F, 0x04, 0x04, 0x04, 0x04, 0x04, 0x04],
+        'U' => [0x11, 0x11, 0x11, 0x11, 0x11, 0x11, 0x0E],
+        'V' => [0x11, 0x11, 0x11, 0x11, 0x11, 0x0A, 0x04],
+        'W' => [0x11, 0x11, 0x11, 0x15, 0x15, 0x15, 0x0A],
+        'X' => [0x11, 0x11, 0x0A, 0x04, 0x0A, 0x11, 0x11],
+        'Y' => [0x11, 0x11, 0x11, 0x0A, 0x04, 0x04, 0x04],
+        'Z' => [0x1F, 0x01, 0x02, 0x04, 0x08, 0x10, 0x1F],
+        '.' => [0x00, 0x00, 0x00, 0x00, 0x00, 0x0C, 0x0C],
+        ',' => [0x00, 0x00, 0x00, 0x00, 0x0C, 0x04, 0x08],
+        '-' => [0x00, 0x00, 0x00, 0x1F, 0x00, 0x00, 0x00],
+        '+' => [0x00, 0x04, 0x04, 0x1F, 0x04, 0x04, 0x00],
+        '%' => [0x18, 0x19, 0x02, 0x04, 0x08, 0x13, 0x03],
+        '(' => [0x02, 0x04, 0x08, 0x08, 0x08, 0x04, 0x02],
+        ')' => [0x08, 0x04, 0x02, 0x02, 0x02, 0x04, 0x08],
+        '/' => [0x00, 0x01, 0x02, 0x04, 0x08, 0x10, 0x00],
+        ':' => [0x00, 0x0C, 0x0C, 0x00, 0x0C, 0x0C, 0x00],
+        '_' => [0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x1F],
+        '<' => [0x02, 0x04, 0x08, 0x10, 0x08, 0x04, 0x02],
+        '>' => [0x08, 0x04, 0x02, 0x01, 0x02, 0x04, 0x08],
+        '=' => [0x00, 0x00, 0x1F, 0x00, 0x1F, 0x00, 0x00],
+        ' ' => [0x00; 7],
+        _ => [0x0E, 0x11, 0x01, 0x02, 0x04, 0x00, 0x04],
+    }
+}
+
+/// Width of a rendered text in pixels
+fn text_width(text: &str) -> u32 {
+    let chars = text.chars().count() as u32;
+    if chars == 0 { 0 } else { (chars * GLYPH_ADVANCE - (GLYPH_ADVANCE - GLYPH_WIDTH)) * FONT_SCALE }
+}
+
+/// Height of a rendered line of text in pixels
+fn text_height() -> u32 {
+    GLYPH_HEIGHT * FONT_SCALE
+}
+
+/// Draw text with its top-left corner at (x, y), clipped to the image
+fn draw_text(image: &mut RgbImage, x: u32, y: u32, text: &str) {
+    for (i, c) in text.chars().enumerate() {
+        let origin_x = x + i as u32 * GLYPH_ADVANCE * FONT_SCALE;
+        for (row, bits) in glyph(c).iter().enumerate() {
+            for col in 0..GLYPH_WIDTH {
+                if bits & (0x10 >> col) == 0 {
+                    continue;
+                }
+                for dy in 0..FONT_SCALE {
+                    for dx in 0..FONT_SCALE {
+                        let px = origin_x + col * FONT_SCALE + dx;
+                        let py = y + row as u32 * FONT_SCALE + dy;
+                        if px < image.width() && py < image.height() {
+                            image.put_pixel(px, py, FOREGROUND);
+                        }
+                    }
+                }
+            }
+        }
+    }
+}
+
+/// Draw a filled rectangle with a one-pixel outline
+fn draw_box(image: &mut RgbImage, x: u32, y: u32, width: u32, height: u32, fill: Rgb<u8>) {
+    for py in y..y + height {
+        for px in x..x + width {
+            let edge = px == x || py == y || px == x + width - 1 || py == y + height - 1;
+            image.put_pixel(px, py, if edge { FOREGROUND } else { fill });
+        }
+    }
+}
+
+/// Label shown for a colormap entry: its label, or its value
+fn entry_label(colormap: &ColorMap, index: usize) -> String {
+    let entry = &colormap.entries[index];
+    entry.label.clone().unwrap_or_else(|| entry.value.to_string())
+}
+
+/// Color of a ramp at a fractional value, interpolated between entries
+fn ramp_color(colormap: &ColorMap, value: f64) -> RgbColor {
+    let entries = &colormap.entries;
+    let first = &entries[0];
+    let last = &entries[entries.len() - 1];
+    if value <= first.value as f64 {
+        return first.color;
+    }
+    if value >= last.value as f64 {
+        return last.color;
+    }
+
+    let upper = entries.iter().position(|e| e.value as f64 >= value).unwrap_or(entries.len() - 1);
+    let (lo, hi) = (&entries[upper.saturating_sub(1)], &entries[upper]);
+    let span = (hi.value as f64 - lo.value as f64).max(f64::EPSILON);
+    let t = (value - lo.value as f64) / span;
+    let mix = |a: u8, b: u8| (a as f64 + (b as f64 - a as f64) * t).round() as u8;
+
+    RgbColor::new(mix(lo.color.r, hi.color.r), mix(lo.color.g, hi.color.g), mix(lo.color.b, hi.color.b))
+}
+
+/// Render a colormap legend
+///
+/// # Arguments
+/// * `colormap` - The colormap to draw
+/// * `style` - Continuous color bar or discrete swatches
+///
+/// # Returns
+/// The legend image, or an error for an empty colormap
+pub fn render_legend(colormap: &ColorMap, style: LegendStyle) -> TiffResult<RgbImage> {
+    if colormap.is_empty() {
+        return Err(TiffError::GenericError("Cannot draw a legend for an empty colormap".to_string()));
+    }
+
+    let labels: Vec<String> = (0..colormap.len()).map(|i| entry_label(colormap, i)).collect();
+    let label_width = labels.iter().map(|l| text_width(l)).max().unwrap_or(0);
+    let width = MARGIN * 2 + SWATCH_WIDTH + LABEL_GAP + label_width;
+    let label_x = MARGIN + SWATCH_WIDTH + LABEL_GAP;
+
+    match style {
+        LegendStyle::Discrete => {
+            let row_height = SWATCH_HEIGHT + SWATCH_GAP;
+            let height = MARGIN * 2 + row_height * colormap.len() as u32 - SWATCH_GAP;
+            let mut image = RgbImage::from_pixel(width, height, BACKGROUND);
+
+            for (i, entry) in colormap.entries.iter().enumerate() {
+                let y = MARGIN + i as u32 * row_height;
+                // Translucent entries are shown blended over the background
+                let blend = |c: u8, bg: u8| {
+                    ((c as u32 * entry.alpha as u32 + bg as u32 * (255 - entry.alpha as u32)) / 255) as u8
+                };
+                let color = Rgb([
+                    blend(entry.color.r, BACKGROUND[0]),
+                    blend(entry.color.g, BACKGROUND[1]),
+                    blend(entry.color.b, BACKGROUND[2]),
+                ]);
+                draw_box(&mut image, MARGIN, y, SWATCH_WIDTH, SWATCH_HEIGHT, color);
+                draw_text(&mut image, label_x, y + (SWATCH_HEIGHT - text_height()) / 2, &labels[i]);
+            }
+
+            Ok(image)
+        },
+        LegendStyle::Continuous => {
+            let half_text = text_height() / 2;
+            let height = MARGIN * 2 + BAR_HEIGHT + half_text * 2;
+            let bar_top = MARGIN + half_text;
+            let mut image = RgbImage::from_pixel(width, height, BACKGROUND);
+
+            // Highest value at the top, as on a map legend
+            let min = colormap.entries[0].value as f64;
+            let max = colormap.entries[colormap.len() - 1].value as f64;
+            let row_value = |row: u32| max - (max - min) * row as f64 / (BAR_HEIGHT - 1) as f64;
+
+            draw_box(&mut image, MARGIN, bar_top, SWATCH_WIDTH, BAR_HEIGHT, FOREGROUND);
+            for row in 1..BAR_HEIGHT - 1 {
+                let color = ramp_color(colormap, row_value(row));
+                for x in MARGIN + 1..MARGIN + SWATCH_WIDTH - 1 {
+                    image.put_pixel(x, bar_top + row, Rgb([color.r, color.g, color.b]));
+                }
+            }
+
+            // Ticks at the entries, skipping labels that would overlap the previous one
+            let mut last_label_y: Option<u32> = None;
+            for (i, entry) in colormap.entries.iter().enumerate().rev() {
+                let fraction = if max > min { (max - entry.value as f64) / (max - min) } else { 0.0 };
+                let tick_y = bar_top + (fraction * (BAR_HEIGHT - 1) as f64).round() as u32;
+
+                for x in MARGIN + SWATCH_WIDTH..label_x - 2 {
+                    image.put_pixel(x, tick_y, FOREGROUND);
+                }
+
+                let text_y = tick_y - half_text;
+                if last_label_y.is_none_or(|last| text_y >= last + text_height() + 2) {
+                    draw_text(&mut image, label_x, text_y, &labels[i]);
+                    last_label_y = Some(text_y);
+                }
+            }
+
+            Ok(image)
+        },
+    }
+}
+
+/// Render a colormap legend and save it as an image
+///
+/// The output format follows the file extension (PNG, JPEG, ...).
+///
+/// # Arguments
+/// * `colormap` - The colormap to draw
+/// * `output_path` - Path of the legend image
+/// * `style` - Legend style, or None to derive it from the colormap type
+///
+/// # Returns
+/// Result indicating success or an error
+pub fn save_legend(colormap: &ColorMap, output_path: &str, style: Option<LegendStyle>) -> TiffResult<()> {
+    let style = style.unwrap_or_else(|| LegendStyle::for_colormap(colormap));
+    info!("Rendering {:?} legend for {} colormap entries", style, colormap.len());
+
+    let image = render_legend(colormap, style)?;
+    image.save(output_path)
+        .map_err(|e| TiffError::GenericError(format!("Failed to save legend image: {}", e)))?;
+
+    info!("Legend ({}x{}) saved to {}", image.width(), image.height(), output_path);
+    Ok(())
+}
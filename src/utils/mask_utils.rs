@@ -6,7 +6,8 @@
 use image::{DynamicImage, GenericImageView, Rgba, RgbaImage};
 use log::info;
 use std::path::Path;
-use crate::tiff::errors::{TiffError, TiffResult};
+use crate::tiff::errors::TiffResult;
+use crate::extractor::OutputFormat;
 
 /// Apply a shape mask to an image
 ///
@@ -93,24 +94,23 @@ pub fn ensure_png_extension(file_path: &str) -> String {
 /// * `image` - The image to save
 /// * `output_path` - Path where to save the output
 /// * `shape` - The shape that was used ("circle" or "square")
+/// * `format` - Requested output format, None to go by the extension
 ///
 /// # Returns
 /// Result indicating success or an error
-pub fn save_shaped_image(image: &DynamicImage, output_path: &str, shape: &str) -> TiffResult<()> {
-    // For circles, we need PNG to support transparency
-    let final_path = if shape.to_lowercase() == "circle" {
+pub fn save_shaped_image(image: &DynamicImage, output_path: &str, shape: &str,
+                         format: Option<OutputFormat>) -> TiffResult<()> {
+    let format = OutputFormat::resolve(output_path, format);
+
+    // For circles, we need a format with transparency, PNG unless requested
+    if shape.to_lowercase() == "circle" && !format.supports_transparency() {
         let png_path = ensure_png_extension(output_path);
         if png_path != output_path {
             info!("Changed output extension to PNG for transparency support: {}", png_path);
         }
-        png_path
-    } else {
-        output_path.to_string()
-    };
+        return OutputFormat::Png.write_image(image, &png_path);
+    }
 
     // Save the image
-    match image.save(&final_path) {
-        Ok(_) => Ok(()),
-        Err(e) => Err(TiffError::GenericError(format!("Failed to save image: {}", e)))
-    }
-}
\ No newline at end of file
+    format.write_image(image, output_path)
+}
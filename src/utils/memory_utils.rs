@@ -0,0 +1,115 @@
+//! Global memory budget
+//!
+//! Containers kill processes that outgrow their memory limit, usually
+//! without a useful message. `--max-memory` sets one budget for the whole
+//! run: the subsystems that hold large buffers size them from it (warp
+//! blocks, the block cache, I/O buffers) and refuse work whose unavoidable
+//! buffers would not fit, with an error naming the culprit, before
+//! allocating anything.
+
+use std::sync::atomic::{AtomicUsize, Ordering};
+
+use log::info;
+
+use crate::tiff::errors::{TiffError, TiffResult};
+
+/// Bytes in a megabyte, as budgets are given
+pub const MEGABYTE: usize = 1024 * 1024;
+
+/// Share of the budget the block cache may hold, as a divisor
+const CACHE_SHARE: usize = 4;
+
+/// Size of file buffers without a budget
+const DEFAULT_IO_BUFFER: usize = MEGABYTE;
+
+/// Smallest file buffer used under a tight budget
+const MIN_IO_BUFFER: usize = 64 * 1024;
+
+/// The budget in bytes, 0 when there is none
+static BUDGET: AtomicUsize = AtomicUsize::new(0);
+
+/// Read a memory size given in megabytes
+///
+/// # Arguments
+/// * `value` - Megabytes, fractions allowed, e.g. "512" or "0.5"
+/// * `option` - Name of the option, for the error message
+///
+/// # Returns
+/// The size in bytes, or an error for anything but a positive number
+pub fn parse_megabytes(value: &str, option: &str) -> TiffResult<usize> {
+    value.trim().parse::<f64>()
+        .ok()
+        .filter(|megabytes| megabytes.is_finite() && *megabytes > 0.0)
+        .map(|megabytes| (megabytes * MEGABYTE as f64) as usize)
+        .ok_or_else(|| TiffError::GenericError(format!("Invalid {} '{}', expected megabytes", option, value)))
+}
+
+/// Set or clear the budget of the process
+///
+/// # Arguments
+/// * `budget` - Bytes the run may use, None for no limit
+pub fn set_memory_budget(budget: Option<usize>) {
+    BUDGET.store(budget.unwrap_or(0), Ordering::Relaxed);
+    if let Some(budget) = budget {
+        info!("Memory budget: {:.1} MB", budget as f64 / MEGABYTE as f64);
+    }
+}
+
+/// The budget of the process, None without one
+pub fn memory_budget() -> Option<usize> {
+    match BUDGET.load(Ordering::Relaxed) {
+        0 => None,
+        budget => Some(budget),
+    }
+}
+
+/// Refuse a buffer that would not fit in the budget
+///
+/// # Arguments
+/// * `what` - Description of the buffer, e.g. "The 4000x3000 window"
+/// * `bytes` - Size the buffer needs
+///
+/// # Returns
+/// Ok if there is no budget or the buffer fits, otherwise an error giving
+/// both sizes
+pub fn check_fits(what: &str, bytes: u64) -> TiffResult<()> {
+    match memory_budget() {
+        Some(budget) if bytes > budget as u64 => Err(TiffError::GenericError(format!(
+            "{} needs {:.1} MB, over the {:.1} MB memory budget",
+            what, bytes as f64 / MEGABYTE as f64, budget as f64 / MEGABYTE as f64))),
+        _ => Ok(()),
+    }
+}
+
+/// Bytes of decoded blocks a block cache may keep, None for no limit
+pub fn cache_limit() -> Option<usize> {
+    memory_budget().map(|budget| budget / CACHE_SHARE)
+}
+
+/// Size of the buffer for reading or writing a file
+///
+/// A megabyte, shrunk to a small share of a tight budget.
+pub fn io_buffer_size() -> usize {
+    match memory_budget() {
+        Some(budget) => (budget / 16).clamp(MIN_IO_BUFFER, DEFAULT_IO_BUFFER),
+        None => DEFAULT_IO_BUFFER,
+    }
+}
+
+/// Memory budget of a reprojection
+///
+/// # Arguments
+/// * `requested` - Budget given for the warp alone, if any
+///
+/// # Returns
+/// The smaller of the warp budget and the global one, or whichever is set
+pub fn warp_budget(requested: Option<usize>) -> Option<usize> {
+    match (requested, memory_budget()) {
+        (Some(requested), Some(budget)) if requested > budget => {
+            info!("Warp memory capped at the {:.1} MB memory budget", budget as f64 / MEGABYTE as f64);
+            Some(budget)
+        },
+        (Some(requested), _) => Some(requested),
+        (None, budget) => budget,
+    }
+}
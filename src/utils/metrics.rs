@@ -0,0 +1,250 @@
+//! Operation metrics
+//!
+//! Slow extractions from large COGs are hard to diagnose from timings of
+//! the whole command: the time may go into reading, decompressing, warping
+//! or writing, and a bad block layout shows up only as many small reads.
+//! The readers and the main operations record what they do here, in one
+//! process-wide registry; `--verbose` prints a summary at the end of a
+//! run and `--metrics` exports it as JSON or Prometheus text.
+
+use std::collections::BTreeMap;
+use std::fs;
+use std::sync::{Mutex, MutexGuard};
+use std::time::{Duration, Instant};
+
+use log::info;
+
+use crate::tiff::errors::TiffResult;
+
+/// Blocks of one compression method that were decompressed
+#[derive(Debug, Clone, Copy, Default, PartialEq)]
+pub struct CodecMetrics {
+    /// Number of blocks decompressed
+    pub blocks: u64,
+    /// Bytes before decompression
+    pub compressed_bytes: u64,
+    /// Bytes after decompression
+    pub decoded_bytes: u64,
+    /// Time spent decompressing and undoing predictors
+    pub decode_time: Duration,
+}
+
+impl CodecMetrics {
+    /// Decoded bytes per compressed byte, None before anything was decoded
+    pub fn compression_ratio(&self) -> Option<f64> {
+        (self.compressed_bytes > 0).then(|| self.decoded_bytes as f64 / self.compressed_bytes as f64)
+    }
+}
+
+/// Runs of one operation
+#[derive(Debug, Clone, Copy, Default, PartialEq)]
+pub struct OperationTiming {
+    /// Number of runs
+    pub count: u64,
+    /// Time of all runs together
+    pub total: Duration,
+}
+
+/// What the process has done so far
+#[derive(Debug, Clone, Default, PartialEq)]
+pub struct Metrics {
+    /// Tiles and strips read from disk
+    pub blocks_read: u64,
+    /// Bytes of those blocks
+    pub bytes_read: u64,
+    /// Time spent seeking and reading them
+    pub read_time: Duration,
+    /// Block reads served from a cache of decoded blocks
+    pub cache_hits: u64,
+    /// Decompression by compression method name
+    pub codecs: BTreeMap<&'static str, CodecMetrics>,
+    /// Timed operations by name
+    pub operations: BTreeMap<&'static str, OperationTiming>,
+}
+
+/// The process-wide registry
+static REGISTRY: Mutex<Metrics> = Mutex::new(Metrics {
+    blocks_read: 0,
+    bytes_read: 0,
+    read_time: Duration::ZERO,
+    cache_hits: 0,
+    codecs: BTreeMap::new(),
+    operations: BTreeMap::new(),
+});
+
+/// Lock the registry, recovering it from a panicked holder
+fn registry() -> MutexGuard<'static, Metrics> {
+    REGISTRY.lock().unwrap_or_else(|poisoned| poisoned.into_inner())
+}
+
+/// Record a tile or strip read from disk
+///
+/// # Arguments
+/// * `bytes` - Size of the block as stored
+/// * `elapsed` - Time the seek and read took
+pub fn record_block_read(bytes: u64, elapsed: Duration) {
+    let mut metrics = registry();
+    metrics.blocks_read += 1;
+    metrics.bytes_read += bytes;
+    metrics.read_time += elapsed;
+}
+
+/// Record a decompressed block
+///
+/// # Arguments
+/// * `codec` - Name of the compression method
+/// * `compressed_bytes` - Size of the block as stored
+/// * `decoded_bytes` - Size of the decoded block
+/// * `elapsed` - Time the decoding took
+pub fn record_decode(codec: &'static str, compressed_bytes: u64, decoded_bytes: u64, elapsed: Duration) {
+    let mut metrics = registry();
+    let entry = metrics.codecs.entry(codec).or_default();
+    entry.blocks += 1;
+    entry.compressed_bytes += compressed_bytes;
+    entry.decoded_bytes += decoded_bytes;
+    entry.decode_time += elapsed;
+}
+
+/// Record a block read served from a cache
+pub fn record_cache_hit() {
+    registry().cache_hits += 1;
+}
+
+/// Run an operation and record how long it took
+///
+/// # Arguments
+/// * `operation` - Name of the operation, e.g. "warp"
+/// * `run` - The operation
+///
+/// # Returns
+/// What the operation returned
+pub fn time<T>(operation: &'static str, run: impl FnOnce() -> T) -> T {
+    let started = Instant::now();
+    let result = run();
+    let elapsed = started.elapsed();
+    let mut metrics = registry();
+    let entry = metrics.operations.entry(operation).or_default();
+    entry.count += 1;
+    entry.total += elapsed;
+    result
+}
+
+/// Copy of everything recorded so far
+pub fn snapshot() -> Metrics {
+    registry().clone()
+}
+
+/// Forget everything recorded so far
+pub fn reset() {
+    *registry() = Metrics::default();
+}
+
+impl Metrics {
+    /// Whether nothing has been recorded
+    pub fn is_empty(&self) -> bool {
+        self.blocks_read == 0 && self.cache_hits == 0 && self.codecs.is_empty() && self.operations.is_empty()
+    }
+
+    /// Decoded bytes per compressed byte over all compression methods
+    pub fn compression_ratio(&self) -> Option<f64> {
+        let total = self.codecs.values().fold(CodecMetrics::default(), |total, codec| CodecMetrics {
+            blocks: total.blocks + codec.blocks,
+            compressed_bytes: total.compressed_bytes + codec.compressed_bytes,
+            decoded_bytes: total.decoded_bytes + codec.decoded_bytes,
+            decode_time: total.decode_time + codec.decode_time,
+        });
+        total.compression_ratio()
+    }
+
+    /// Human-readable summary, one line per figure
+    pub fn summary(&self) -> String {
+        let mut lines = vec![
+            "Metrics:".to_string(),
+            format!("  Blocks read: {} ({} bytes in {:.3} s)", self.blocks_read, self.bytes_read, self.read_time.as_secs_f64()),
+            format!("  Cache hits: {}", self.cache_hits),
+        ];
+        for (codec, metrics) in &self.codecs {
+            lines.push(format!("  Decoded {} {} block(s): {} -> {} bytes (ratio {}) in {:.3} s",
+                               metrics.blocks, codec, metrics.compressed_bytes, metrics.decoded_bytes,
+                               metrics.compression_ratio().map_or("n/a".to_string(), |ratio| format!("{:.2}", ratio)),
+                               metrics.decode_time.as_secs_f64()));
+        }
+        for (operation, timing) in &self.operations {
+            lines.push(format!("  {}: {} run(s) in {:.3} s", operation, timing.count, timing.total.as_secs_f64()));
+        }
+        lines.join("\n")
+    }
+
+    /// The metrics as a JSON document
+    pub fn to_json(&self) -> String {
+        let codecs: Vec<String> = self.codecs.iter()
+            .map(|(codec, metrics)| format!(
+                "    \"{}\": {{\"blocks\": {}, \"compressed_bytes\": {}, \"decoded_bytes\": {}, \
+                 \"compression_ratio\": {}, \"decode_seconds\": {}}}",
+                codec, metrics.blocks, metrics.compressed_bytes, metrics.decoded_bytes,
+                metrics.compression_ratio().map_or("null".to_string(), |ratio| ratio.to_string()),
+                metrics.decode_time.as_secs_f64()))
+            .collect();
+        let operations: Vec<String> = self.operations.iter()
+            .map(|(operation, timing)| format!("    \"{}\": {{\"count\": {}, \"seconds\": {}}}",
+                                                operation, timing.count, timing.total.as_secs_f64()))
+            .collect();
+        let object = |entries: &[String]| if entries.is_empty() {
+            "{}".to_string()
+        } else {
+            format!("{{\n{}\n  }}", entries.join(",\n"))
+        };
+
+        format!("{{\n  \"blocks_read\": {},\n  \"bytes_read\": {},\n  \"read_seconds\": {},\n  \
+                 \"cache_hits\": {},\n  \"compression_ratio\": {},\n  \"codecs\": {},\n  \"operations\": {}\n}}\n",
+                self.blocks_read, self.bytes_read, self.read_time.as_secs_f64(), self.cache_hits,
+                self.compression_ratio().map_or("null".to_string(), |ratio| ratio.to_string()),
+                object(&codecs), object(&operations))
+    }
+
+    /// The metrics in the Prometheus text exposition format
+    pub fn to_prometheus(&self) -> String {
+        let mut text = String::new();
+        let mut family = |name: &str, help: &str, samples: Vec<(String, String)>| {
+            text.push_str(&format!("# HELP rasterkit_{} {}\n# TYPE rasterkit_{} counter\n", name, help, name));
+            for (labels, value) in samples {
+                text.push_str(&format!("rasterkit_{}{} {}\n", name, labels, value));
+            }
+        };
+        let unlabelled = |value: String| vec![(String::new(), value)];
+        let by_codec = |value: &dyn Fn(&CodecMetrics) -> String| self.codecs.iter()
+            .map(|(codec, metrics)| (format!("{{codec=\"{}\"}}", codec), value(metrics)))
+            .collect::<Vec<_>>();
+        let by_operation = |value: &dyn Fn(&OperationTiming) -> String| self.operations.iter()
+            .map(|(operation, timing)| (format!("{{operation=\"{}\"}}", operation), value(timing)))
+            .collect::<Vec<_>>();
+
+        family("blocks_read_total", "Tiles and strips read from disk", unlabelled(self.blocks_read.to_string()));
+        family("bytes_read_total", "Bytes of the tiles and strips read", unlabelled(self.bytes_read.to_string()));
+        family("read_seconds_total", "Time spent reading tiles and strips", unlabelled(self.read_time.as_secs_f64().to_string()));
+        family("block_cache_hits_total", "Block reads served from the cache", unlabelled(self.cache_hits.to_string()));
+        family("decoded_blocks_total", "Blocks decompressed", by_codec(&|metrics| metrics.blocks.to_string()));
+        family("compressed_bytes_total", "Bytes before decompression", by_codec(&|metrics| metrics.compressed_bytes.to_string()));
+        family("decoded_bytes_total", "Bytes after decompression", by_codec(&|metrics| metrics.decoded_bytes.to_string()));
+        family("decode_seconds_total", "Time spent decompressing", by_codec(&|metrics| metrics.decode_time.as_secs_f64().to_string()));
+        family("operations_total", "Runs of each operation", by_operation(&|timing| timing.count.to_string()));
+        family("operation_seconds_total", "Time spent in each operation", by_operation(&|timing| timing.total.as_secs_f64().to_string()));
+        text
+    }
+}
+
+/// Write everything recorded so far to a file
+///
+/// # Arguments
+/// * `path` - Output path; a `.json` extension writes JSON, anything else
+///   Prometheus text
+///
+/// # Returns
+/// Result indicating success or an error
+pub fn write_metrics(path: &str) -> TiffResult<()> {
+    let metrics = snapshot();
+    let json = path.to_lowercase().ends_with(".json");
+    fs::write(path, if json { metrics.to_json() } else { metrics.to_prometheus() })?;
+    info!("Wrote metrics to {} as {}", path, if json { "JSON" } else { "Prometheus text" });
+    Ok(())
+}
@@ -16,9 +16,66 @@ pub(crate) mod tag_utils;
 pub(crate) mod tiff_extraction_utils;
 pub(crate) mod image_extraction_utils;
 pub(crate) mod colormap_utils;
+pub(crate) mod css_colors;
+pub(crate) mod depalettize_utils;
+pub(crate) mod icc_utils;
+pub(crate) mod exif_utils;
+pub(crate) mod sidecar_utils;
+pub(crate) mod legend_utils;
+pub(crate) mod classification_utils;
 pub(crate) mod reference_utils;
 pub(crate) mod coordinate_utils;
+pub(crate) mod region_utils;
 pub(crate) mod mask_utils;
 mod coordinate_transformer;
 pub(crate) mod reprojection_utils;
+pub(crate) mod warp_utils;
+pub(crate) mod gpu_utils;
+pub(crate) mod simd_utils;
+pub(crate) mod proximity_utils;
+pub(crate) mod fillnodata_utils;
+pub(crate) mod sample_utils;
+pub(crate) mod qa_utils;
+pub(crate) mod split_utils;
+pub(crate) mod resize_utils;
+pub(crate) mod overview_utils;
+pub(crate) mod extend_utils;
+pub(crate) mod orient_utils;
+pub(crate) mod acquisition_utils;
+pub(crate) mod catalog_utils;
+pub(crate) mod query_utils;
+pub(crate) mod index_utils;
+pub(crate) mod catalog_extract_utils;
+pub(crate) mod statistics_utils;
+pub(crate) mod render_utils;
+pub(crate) mod grid_utils;
+pub(crate) mod pansharpen_utils;
+pub(crate) mod balance_utils;
+pub(crate) mod mosaic_utils;
+pub(crate) mod info_utils;
+pub(crate) mod footprint_utils;
+pub(crate) mod valid_mask_utils;
+pub(crate) mod repair_utils;
+pub(crate) mod npy_utils;
+pub(crate) mod chip_utils;
+pub(crate) mod tensor_utils;
+pub(crate) mod normalize_utils;
+pub(crate) mod elevation_utils;
+pub(crate) mod sqlite_utils;
+pub(crate) mod gpkg_utils;
+pub(crate) mod postgis_utils;
+pub(crate) mod parquet_utils;
+pub(crate) mod envi_utils;
+pub(crate) mod scaling_utils;
 pub mod filter_utils;
+pub(crate) mod axis_order_utils;
+pub(crate) mod grid_reference_utils;
+pub(crate) mod cell_utils;
+pub(crate) mod tile_utils;
+pub(crate) mod report_utils;
+pub mod metrics;
+pub mod memory_utils;
+pub mod scratch_utils;
+pub(crate) mod journal_utils;
+pub(crate) mod atomic_utils;
+pub(crate) mod crop_utils;
@@ -0,0 +1,340 @@
+//! Mosaicking utilities
+//!
+//! Combines georeferenced rasters in one coordinate system into a single
+//! raster covering all of them. The output grid spans the union of the
+//! inputs, or a requested window, at the pixel size of the first one, and
+//! every input is sampled onto it by nearest neighbour. Where inputs
+//! overlap, the blend mode decides the value: the first or last input with
+//! data wins, all are averaged, or they are feathered, each weighted by its
+//! distance to the edge of its own data so that seams fade out over a
+//! number of pixels.
+
+use log::{info, warn};
+
+use crate::coordinate::Affine;
+use crate::tiff::builder::TiffBuilder;
+use crate::tiff::constants::tags;
+use crate::tiff::errors::{TiffError, TiffResult};
+use crate::tiff::ifd::IFD;
+use crate::tiff::TiffReader;
+use crate::utils::grid_utils::Raster;
+use crate::utils::logger::Logger;
+use crate::utils::sample_utils::SampleFormat;
+use crate::utils::scratch_utils::ScratchBuffer;
+use crate::utils::tiff_extraction_utils;
+
+/// How overlapping inputs are combined
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum BlendMode {
+    /// The first input with data wins
+    First,
+    /// The last input with data wins
+    Last,
+    /// Inputs with data are averaged
+    Mean,
+    /// Inputs are averaged, weighted by their distance in pixels to the
+    /// edge of their data, up to the given distance
+    Feather(u32),
+}
+
+impl BlendMode {
+    /// Parse a blend mode name: first, last, mean or feather:npixels
+    pub fn from_string(name: &str) -> TiffResult<Self> {
+        let name = name.trim().to_lowercase();
+        match name.split_once(':') {
+            Some(("feather", pixels)) => match pixels.trim().parse::<u32>() {
+                Ok(pixels) if pixels > 0 => Ok(BlendMode::Feather(pixels)),
+                _ => Err(TiffError::GenericError(format!(
+                    "Feather distance must be a positive number of pixels, got '{}'", pixels))),
+            },
+            None if name == "first" => Ok(BlendMode::First),
+            None if name == "last" => Ok(BlendMode::Last),
+            None if name == "mean" => Ok(BlendMode::Mean),
+            None if name == "feather" => Err(TiffError::GenericError(
+                "Feathering needs a distance, e.g. feather:20".to_string())),
+            _ => Err(TiffError::GenericError(format!(
+                "Unknown blend mode '{}'. Supported: first, last, mean, feather:npixels", name))),
+        }
+    }
+}
+
+/// Feathering weight of each pixel of a raster
+///
+/// The weight grows linearly from the edge of the valid data, where it is
+/// `1 / distance`, to 1 at `distance` pixels inside it. Pixels beyond the
+/// image border count as invalid, so image edges are feathered too.
+/// Distances are chamfer distances with steps of 1 and √2.
+///
+/// # Arguments
+/// * `valid` - Whether each pixel has data, in row-major order
+/// * `width` - Raster width in pixels
+/// * `height` - Raster height in pixels
+/// * `distance` - Distance in pixels over which the weight ramps up
+///
+/// # Returns
+/// The weight of each pixel, 0 where there is no data
+pub fn feather_weights(valid: &[bool], width: usize, height: usize, distance: u32) -> Vec<f64> {
+    let limit = distance as f64;
+    let mut reach: Vec<f64> = valid.iter().map(|&valid| if valid { limit } else { 0.0 }).collect();
+    let diagonal = std::f64::consts::SQRT_2;
+
+    // Distance to the nearest invalid pixel, a border counting as one
+    // pixel away, in a forward and a backward pass
+    let at = |reach: &[f64], column: isize, row: isize| -> f64 {
+        if column < 0 || row < 0 || column >= width as isize || row >= height as isize {
+            0.0
+        } else {
+            reach[row as usize * width + column as usize]
+        }
+    };
+    for row in 0..height as isize {
+        for column in 0..width as isize {
+            let index = row as usize * width + column as usize;
+            if reach[index] == 0.0 {
+                continue;
+            }
+            let nearest = (at(&reach, column - 1, row) + 1.0)
+                .min(at(&reach, column, row - 1) + 1.0)
+                .min(at(&reach, column - 1, row - 1) + diagonal)
+                .min(at(&reach, column + 1, row - 1) + diagonal);
+            reach[index] = reach[index].min(nearest);
+        }
+    }
+    for row in (0..height as isize).rev() {
+        for column in (0..width as isize).rev() {
+            let index = row as usize * width + column as usize;
+            if reach[index] == 0.0 {
+                continue;
+            }
+            let nearest = (at(&reach, column + 1, row) + 1.0)
+                .min(at(&reach, column, row + 1) + 1.0)
+                .min(at(&reach, column + 1, row + 1) + diagonal)
+                .min(at(&reach, column - 1, row + 1) + diagonal);
+            reach[index] = reach[index].min(nearest);
+        }
+    }
+
+    reach.iter().map(|&reach| reach.min(limit) / limit).collect()
+}
+
+/// NoData value declared by a raster, as written in its tag
+fn declared_nodata(path: &str, logger: &Logger) -> TiffResult<Option<String>> {
+    let mut reader = TiffReader::new(logger);
+    let tiff = reader.load(path)?;
+    let ifd = tiff.main_ifd()
+        .ok_or_else(|| TiffError::GenericError(format!("No IFDs found in {}", path)))?;
+    Ok(if ifd.has_tag(tags::GDAL_NODATA) {
+        Some(tiff_extraction_utils::extract_nodata_value(ifd, &reader).trim().to_string())
+    } else {
+        None
+    })
+}
+
+/// Mosaic rasters into one
+///
+/// All inputs must be north-up and georeferenced in the same EPSG
+/// coordinate system. When all of them hold 8-bit samples and at least
+/// three bands, the output is RGB; 8-bit inputs with fewer bands give an
+/// 8-bit grayscale output, and anything else a float32 raster of the first
+/// band. The output takes the NoData value of the first input declaring
+/// one, or 0 (8-bit) or NaN (float32).
+///
+/// # Arguments
+/// * `input_paths` - Rasters to combine, in order of precedence for `first`/`last`
+/// * `output_path` - Path of the GeoTIFF to write
+/// * `mode` - How overlapping inputs are combined
+/// * `logger` - Logger for recording operations
+///
+/// # Returns
+/// Result indicating success or an error
+pub fn mosaic_files(input_paths: &[String], output_path: &str, mode: BlendMode, logger: &Logger) -> TiffResult<()> {
+    if input_paths.len() < 2 {
+        return Err(TiffError::GenericError("A mosaic needs at least two inputs".to_string()));
+    }
+    mosaic(input_paths, None, output_path, mode, logger)
+}
+
+/// Mosaic rasters onto a window
+///
+/// Like `mosaic_files`, but the output covers the window instead of the
+/// union of the inputs, grown outwards to whole pixels of the first
+/// input's grid so no input is shifted. Parts of the window no input
+/// covers hold NoData, and a single input is simply clipped.
+///
+/// # Arguments
+/// * `input_paths` - Rasters to combine, in order of precedence for `first`/`last`
+/// * `window` - Area to cover as (min_x, min_y, max_x, max_y) in the inputs' coordinate system
+/// * `output_path` - Path of the GeoTIFF to write
+/// * `mode` - How overlapping inputs are combined
+/// * `logger` - Logger for recording operations
+///
+/// # Returns
+/// Result indicating success or an error
+pub fn mosaic_window(input_paths: &[String], window: (f64, f64, f64, f64), output_path: &str,
+                     mode: BlendMode, logger: &Logger) -> TiffResult<()> {
+    if input_paths.is_empty() {
+        return Err(TiffError::GenericError("No rasters to mosaic onto the window".to_string()));
+    }
+    mosaic(input_paths, Some(window), output_path, mode, logger)
+}
+
+/// Grow a window outwards to the pixel edges of a grid
+fn snap_to_grid((min_x, min_y, max_x, max_y): (f64, f64, f64, f64), grid: &Affine) -> (f64, f64, f64, f64) {
+    let (step_x, step_y) = (grid.pixel_width.abs(), grid.pixel_height.abs());
+    let down = |value: f64, origin: f64, step: f64| origin + ((value - origin) / step + 1e-6).floor() * step;
+    let up = |value: f64, origin: f64, step: f64| origin + ((value - origin) / step - 1e-6).ceil() * step;
+    (down(min_x, grid.origin_x, step_x), down(min_y, grid.origin_y, step_y),
+     up(max_x, grid.origin_x, step_x), up(max_y, grid.origin_y, step_y))
+}
+
+/// Mosaic rasters onto a window, or onto their union when there is none
+fn mosaic(input_paths: &[String], window: Option<(f64, f64, f64, f64)>, output_path: &str,
+          mode: BlendMode, logger: &Logger) -> TiffResult<()> {
+    let mut inputs = Vec::with_capacity(input_paths.len());
+    for path in input_paths {
+        inputs.push(Raster::read(path, 3, logger)?);
+    }
+
+    // Every input must sit in the same coordinate system
+    let mut epsg = None;
+    let mut geotransforms = Vec::with_capacity(inputs.len());
+    for input in &inputs {
+        let georeferencing = input.georeferencing.as_ref()
+            .ok_or_else(|| TiffError::GenericError(format!("{} is not georeferenced", input.path)))?;
+        let input_epsg = georeferencing.definition.as_ref().and_then(|definition| definition.epsg)
+            .ok_or_else(|| TiffError::GenericError(format!(
+                "The coordinate system of {} has no EPSG code", input.path)))?;
+        if !georeferencing.geotransform.is_north_up() {
+            return Err(TiffError::GenericError(format!(
+                "{} is rotated; reproject it to a north-up grid first", input.path)));
+        }
+        match epsg {
+            Some(epsg) if epsg != input_epsg => return Err(TiffError::GenericError(format!(
+                "{} is in EPSG:{} and {} in EPSG:{}; reproject one first",
+                inputs[0].path, epsg, input.path, input_epsg))),
+            _ => epsg = Some(input_epsg),
+        }
+        geotransforms.push(georeferencing.geotransform);
+    }
+    let epsg = epsg.unwrap_or_default();
+
+    // The window or the union of the input extents, on the pixel size of
+    // the first input
+    let (pixel_width, pixel_height) = (geotransforms[0].pixel_width, geotransforms[0].pixel_height);
+    let (min_x, min_y, max_x, max_y) = match window {
+        Some(window) => snap_to_grid(window, &geotransforms[0]),
+        None => {
+            let mut extent = (f64::MAX, f64::MAX, f64::MIN, f64::MIN);
+            for (input, geotransform) in inputs.iter().zip(&geotransforms) {
+                for (column, row) in [(0.0, 0.0), (input.layout.width as f64, input.layout.height as f64)] {
+                    let (x, y) = geotransform.apply(column, row);
+                    extent = (extent.0.min(x), extent.1.min(y), extent.2.max(x), extent.3.max(y));
+                }
+            }
+            extent
+        },
+    };
+    let width = ((max_x - min_x) / pixel_width.abs() - 1e-6).ceil().max(1.0) as usize;
+    let height = ((max_y - min_y) / pixel_height.abs() - 1e-6).ceil().max(1.0) as usize;
+    let origin_x = if pixel_width > 0.0 { min_x } else { max_x };
+    let origin_y = if pixel_height < 0.0 { max_y } else { min_y };
+    let output_geotransform = Affine::new(origin_x, pixel_width, origin_y, pixel_height);
+    info!("Mosaicking {} inputs onto a {}x{} grid in EPSG:{} ({:?})", inputs.len(), width, height, epsg, mode);
+
+    let byte_output = inputs.iter()
+        .all(|input| input.layout.bits == 8 && input.layout.format == SampleFormat::Unsigned);
+    let channels = if byte_output && inputs.iter().all(|input| input.bands.len() >= 3) { 3 } else { 1 };
+    if !byte_output && inputs.iter().any(|input| input.bands.len() > 1) {
+        warn!("Inputs are not all 8-bit; only their first band is mosaicked");
+    }
+
+    // The accumulators take 8 bytes per pixel and band and spill to disk
+    // under a tight memory budget
+    let mut sums = (0..channels)
+        .map(|band| ScratchBuffer::<f64>::zeroed(width * height, &format!("the mosaic sums of band {}", band + 1)))
+        .collect::<TiffResult<Vec<_>>>()?;
+    let mut weights = ScratchBuffer::<f64>::zeroed(width * height, "the mosaic weights")?;
+    for (input, geotransform) in inputs.iter().zip(&geotransforms) {
+        let to_input = geotransform.inverse()
+            .ok_or_else(|| TiffError::GenericError(format!("The geotransform of {} is degenerate", input.path)))?
+            .compose(&output_geotransform);
+        let (input_width, input_height) = (input.layout.width as usize, input.layout.height as usize);
+        let input_weights = match mode {
+            BlendMode::Feather(distance) => {
+                let valid: Vec<bool> = (0..input_width * input_height)
+                    .map(|index| (0..channels).all(|band| !input.bands[band][index].is_nan()))
+                    .collect();
+                Some(feather_weights(&valid, input_width, input_height, distance))
+            },
+            _ => None,
+        };
+
+        for row in 0..height {
+            for column in 0..width {
+                let index = row * width + column;
+                if mode == BlendMode::First && weights[index] > 0.0 {
+                    continue;
+                }
+                let (x, y) = to_input.apply(column as f64 + 0.5, row as f64 + 0.5);
+                let values: Option<Vec<f64>> = (0..channels).map(|band| input.nearest(band, x, y)).collect();
+                let Some(values) = values else {
+                    continue;
+                };
+                let weight = match &input_weights {
+                    Some(input_weights) => input_weights[y as usize * input_width + x as usize],
+                    None => 1.0,
+                };
+                if mode == BlendMode::Last {
+                    weights[index] = 0.0;
+                    sums.iter_mut().for_each(|sum| sum[index] = 0.0);
+                }
+                weights[index] += weight;
+                for (band, value) in values.into_iter().enumerate() {
+                    sums[band][index] += value * weight;
+                }
+            }
+        }
+    }
+
+    let mut nodata = None;
+    for path in input_paths {
+        if let Some(value) = declared_nodata(path, logger)? {
+            nodata = Some(value);
+            break;
+        }
+    }
+    let nodata = nodata.unwrap_or_else(|| if byte_output { "0" } else { "nan" }.to_string());
+    let fill = nodata.parse::<f64>().unwrap_or(f64::NAN);
+    let covered = weights.iter().filter(|&&weight| weight > 0.0).count();
+    info!("{} of {} mosaic pixels have data", covered, width * height);
+
+    let value = |band: usize, index: usize| -> f64 {
+        if weights[index] > 0.0 { sums[band][index] / weights[index] } else { fill }
+    };
+
+    let mut builder = TiffBuilder::new(logger, false);
+    let ifd_index = builder.add_ifd(IFD::new(0, 0));
+    if byte_output {
+        let mut levels = vec![0u8; width * height * channels];
+        for index in 0..width * height {
+            for band in 0..channels {
+                levels[index * channels + band] = value(band, index).round().clamp(0.0, 255.0) as u8;
+            }
+        }
+        if channels == 3 {
+            builder.add_basic_rgb_tags(ifd_index, width as u32, height as u32);
+        } else {
+            builder.add_basic_gray_tags(ifd_index, width as u32, height as u32, 8);
+        }
+        builder.setup_single_strip(ifd_index, levels);
+    } else {
+        let data: Vec<f32> = (0..width * height).map(|index| value(0, index) as f32).collect();
+        tiff_extraction_utils::process_float32_image(&data, width as u32, height as u32, &mut builder, ifd_index)?;
+    }
+    builder.set_georeference(ifd_index, &output_geotransform, epsg)?;
+    builder.add_nodata_tag(ifd_index, &nodata);
+    builder.write(output_path)?;
+
+    info!("Mosaic of {} inputs written to {}", inputs.len(), output_path);
+    Ok(())
+}
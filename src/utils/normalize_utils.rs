@@ -0,0 +1,245 @@
+//! Normalization utilities for ML exports
+//!
+//! Converts pixel values of any sample type into normalized float32 tensors, either scaled
+//! to [0, 1] with per-band min/max or standardized with per-band mean and
+//! standard deviation. NoData and NaN samples are left out of the
+//! statistics and written as NaN. The statistics used are returned so they
+//! can be stored next to the tensors and reused at inference time.
+
+use log::info;
+
+use crate::tiff::errors::{TiffError, TiffResult};
+use crate::utils::tensor_utils::{self, NamedTensor};
+
+/// How pixel values are normalized
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum NormalizationMethod {
+    /// Scale each band to [0, 1] using its minimum and maximum
+    MinMax,
+    /// Standardize each band to zero mean and unit variance
+    ZScore,
+}
+
+impl NormalizationMethod {
+    /// Parse a normalization method name
+    ///
+    /// # Arguments
+    /// * `name` - "minmax" or "zscore" (case-insensitive)
+    ///
+    /// # Returns
+    /// The normalization method or an error
+    pub fn from_string(name: &str) -> TiffResult<Self> {
+        match name.to_lowercase().as_str() {
+            "minmax" | "min-max" => Ok(NormalizationMethod::MinMax),
+            "zscore" | "z-score" | "standard" => Ok(NormalizationMethod::ZScore),
+            _ => Err(TiffError::GenericError(format!(
+                "Unknown normalization '{}' (expected 'minmax' or 'zscore')", name))),
+        }
+    }
+
+    /// Canonical name of the method
+    pub fn name(&self) -> &'static str {
+        match self {
+            NormalizationMethod::MinMax => "minmax",
+            NormalizationMethod::ZScore => "zscore",
+        }
+    }
+}
+
+/// Statistics of a single band
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct BandStatistics {
+    pub min: f64,
+    pub max: f64,
+    pub mean: f64,
+    pub std: f64,
+}
+
+/// Whether a sample holds a value, i.e. is neither NaN nor NoData
+fn is_valid(value: f64, nodata: Option<f64>) -> bool {
+    !value.is_nan() && Some(value) != nodata
+}
+
+/// Compute per-band statistics of band-interleaved data
+///
+/// # Arguments
+/// * `data` - Pixel values, bands interleaved (HWC order)
+/// * `band_count` - Number of bands
+/// * `nodata` - NoData value to leave out, if any
+///
+/// # Returns
+/// One set of statistics per band
+pub fn compute_band_statistics(data: &[f64], band_count: usize, nodata: Option<f64>) -> Vec<BandStatistics> {
+    let band_count = band_count.max(1);
+
+    (0..band_count)
+        .map(|band| {
+            let mut min = f64::INFINITY;
+            let mut max = f64::NEG_INFINITY;
+            let mut sum = 0.0;
+            let mut sum_sq = 0.0;
+            let mut count = 0usize;
+
+            for &value in data.iter().skip(band).step_by(band_count).filter(|&&value| is_valid(value, nodata)) {
+                min = min.min(value);
+                max = max.max(value);
+                sum += value;
+                sum_sq += value * value;
+                count += 1;
+            }
+
+            let pixel_count = count.max(1) as f64;
+            let mean = sum / pixel_count;
+            let variance = (sum_sq / pixel_count - mean * mean).max(0.0);
+            BandStatistics {
+                min: if min <= max { min } else { 0.0 },
+                max: if min <= max { max } else { 0.0 },
+                mean,
+                std: variance.sqrt(),
+            }
+        })
+        .collect()
+}
+
+/// Normalize band-interleaved data to float32
+///
+/// Constant bands (zero range or zero deviation) map to 0.0, NoData and
+/// NaN samples to NaN.
+///
+/// # Arguments
+/// * `data` - Pixel values, bands interleaved (HWC order)
+/// * `stats` - Per-band statistics, one entry per band
+/// * `method` - Normalization method
+/// * `nodata` - NoData value to leave out, if any
+///
+/// # Returns
+/// The normalized values in the same layout
+pub fn normalize_bands(data: &[f64], stats: &[BandStatistics], method: NormalizationMethod,
+                       nodata: Option<f64>) -> Vec<f32> {
+    let band_count = stats.len().max(1);
+
+    // Precompute scale and offset per band: out = (value - offset) * scale
+    let transforms: Vec<(f64, f64)> = stats.iter()
+        .map(|s| match method {
+            NormalizationMethod::MinMax => {
+                let range = s.max - s.min;
+                (s.min, if range > 0.0 { 1.0 / range } else { 0.0 })
+            },
+            NormalizationMethod::ZScore => (s.mean, if s.std > 0.0 { 1.0 / s.std } else { 0.0 }),
+        })
+        .collect();
+
+    data.iter()
+        .enumerate()
+        .map(|(i, &value)| {
+            if !is_valid(value, nodata) {
+                return f32::NAN;
+            }
+            let (offset, scale) = transforms[i % band_count];
+            ((value - offset) * scale) as f32
+        })
+        .collect()
+}
+
+/// Build the metadata describing a normalization
+///
+/// # Arguments
+/// * `method` - Normalization method used
+/// * `stats` - Per-band statistics used
+///
+/// # Returns
+/// Key/value pairs suitable for safetensors metadata
+pub fn normalization_metadata(method: NormalizationMethod, stats: &[BandStatistics]) -> Vec<(String, String)> {
+    let join = |f: fn(&BandStatistics) -> f64| {
+        stats.iter().map(|s| f(s).to_string()).collect::<Vec<_>>().join(",")
+    };
+
+    vec![
+        ("normalization".to_string(), method.name().to_string()),
+        ("band_min".to_string(), join(|s| s.min)),
+        ("band_max".to_string(), join(|s| s.max)),
+        ("band_mean".to_string(), join(|s| s.mean)),
+        ("band_std".to_string(), join(|s| s.std)),
+    ]
+}
+
+/// Build the statistics as small float32 tensors (one value per band)
+///
+/// # Arguments
+/// * `stats` - Per-band statistics
+///
+/// # Returns
+/// Tensors named band_min, band_max, band_mean and band_std
+pub fn statistics_tensors(stats: &[BandStatistics]) -> Vec<NamedTensor> {
+    let shape = [stats.len()];
+    let column = |f: fn(&BandStatistics) -> f64| stats.iter().map(|s| f(s) as f32).collect::<Vec<_>>();
+
+    vec![
+        NamedTensor::from_f32("band_min", &shape, &column(|s| s.min)),
+        NamedTensor::from_f32("band_max", &shape, &column(|s| s.max)),
+        NamedTensor::from_f32("band_mean", &shape, &column(|s| s.mean)),
+        NamedTensor::from_f32("band_std", &shape, &column(|s| s.std)),
+    ]
+}
+
+/// Format statistics as a small JSON document
+///
+/// # Arguments
+/// * `method` - Normalization method used
+/// * `stats` - Per-band statistics used
+///
+/// # Returns
+/// The JSON text
+pub fn statistics_to_json(method: NormalizationMethod, stats: &[BandStatistics]) -> String {
+    let bands: Vec<String> = stats.iter()
+        .enumerate()
+        .map(|(i, s)| format!(
+            "    {{\"band\": {}, \"min\": {}, \"max\": {}, \"mean\": {}, \"std\": {}}}",
+            i + 1, s.min, s.max, s.mean, s.std))
+        .collect();
+
+    format!("{{\n  \"normalization\": \"{}\",\n  \"bands\": [\n{}\n  ]\n}}\n",
+            method.name(), bands.join(",\n"))
+}
+
+/// Normalize an array and save it as a float32 tensor
+///
+/// The data tensor is named `data`. NPZ archives additionally carry the
+/// statistics as arrays, safetensors files carry them as metadata.
+///
+/// # Arguments
+/// * `path` - Path of the file to write
+/// * `format` - "npy", "npz" or "safetensors"
+/// * `shape` - Array shape, last axis being the bands for multi-band data
+/// * `data` - Pixel values, bands interleaved
+/// * `band_count` - Number of bands
+/// * `nodata` - NoData value to leave out, if any
+/// * `method` - Normalization method
+///
+/// # Returns
+/// The statistics used for normalization, or an error
+pub fn save_normalized(
+    path: &str,
+    format: &str,
+    shape: &[usize],
+    data: &[f64],
+    band_count: usize,
+    nodata: Option<f64>,
+    method: NormalizationMethod
+) -> TiffResult<Vec<BandStatistics>> {
+    let stats = compute_band_statistics(data, band_count, nodata);
+    for (i, s) in stats.iter().enumerate() {
+        info!("Band {}: min={} max={} mean={:.4} std={:.4}", i + 1, s.min, s.max, s.mean, s.std);
+    }
+
+    let normalized = normalize_bands(data, &stats, method, nodata);
+
+    let mut tensors = vec![NamedTensor::from_f32("data", shape, &normalized)];
+    tensors.extend(statistics_tensors(&stats));
+
+    tensor_utils::save_tensors(
+        path, format, &tensors, &normalization_metadata(method, &stats))?;
+
+    info!("Saved {} normalized tensor {:?} to {}", method.name(), shape, path);
+    Ok(stats)
+}
@@ -0,0 +1,84 @@
+//! NumPy NPY format utilities
+//!
+//! Helpers for writing arrays in the NumPy `.npy` format (version 1.0).
+//! The format is a magic string, a little-endian header length, a Python
+//! dict literal describing dtype and shape, and the raw data. The header is
+//! padded with spaces so the data starts on a 64-byte boundary.
+
+use std::fs::File;
+use std::io::{BufWriter, Write};
+
+use crate::tiff::errors::{TiffError, TiffResult};
+
+/// Build the NPY header (magic, version, length and dict) for an array
+///
+/// # Arguments
+/// * `descr` - NumPy dtype descriptor (e.g. "<u1", "<f4")
+/// * `shape` - Array shape
+///
+/// # Returns
+/// The complete header bytes, or an error if the header is too long
+pub fn build_npy_header(descr: &str, shape: &[usize]) -> TiffResult<Vec<u8>> {
+    // Python tuple syntax: a single dimension needs a trailing comma
+    let shape_str = match shape.len() {
+        1 => format!("({},)", shape[0]),
+        _ => format!("({})", shape.iter().map(|d| d.to_string()).collect::<Vec<_>>().join(", ")),
+    };
+
+    let dict = format!(
+        "{{'descr': '{}', 'fortran_order': False, 'shape': {}, }}",
+        descr, shape_str
+    );
+
+    // magic (6) + version (2) + header length (2) + dict + newline, padded to 64 bytes
+    let unpadded_len = 10 + dict.len() + 1;
+    let padding_len = (64 - unpadded_len % 64) % 64;
+    let header_len = dict.len() + padding_len + 1;
+
+    if header_len > u16::MAX as usize {
+        return Err(TiffError::GenericError("NPY header is too long".to_string()));
+    }
+
+    let mut header = Vec::with_capacity(10 + header_len);
+    header.extend_from_slice(b"\x93NUMPY");
+    header.extend_from_slice(&[0x01, 0x00]);
+    header.extend_from_slice(&(header_len as u16).to_le_bytes());
+    header.extend_from_slice(dict.as_bytes());
+    header.resize(header.len() + padding_len, b' ');
+    header.push(b'\n');
+
+    Ok(header)
+}
+
+/// Write an NPY array to any writer
+///
+/// # Arguments
+/// * `writer` - Destination writer
+/// * `descr` - NumPy dtype descriptor
+/// * `shape` - Array shape
+/// * `data` - Raw little-endian element data in C order
+///
+/// # Returns
+/// Result indicating success or an error
+pub fn write_npy<W: Write>(writer: &mut W, descr: &str, shape: &[usize], data: &[u8]) -> TiffResult<()> {
+    writer.write_all(&build_npy_header(descr, shape)?)?;
+    writer.write_all(data)?;
+    Ok(())
+}
+
+/// Save an NPY array to a file
+///
+/// # Arguments
+/// * `path` - Path of the file to write
+/// * `descr` - NumPy dtype descriptor
+/// * `shape` - Array shape
+/// * `data` - Raw little-endian element data in C order
+///
+/// # Returns
+/// Result indicating success or an error
+pub fn save_npy(path: &str, descr: &str, shape: &[usize], data: &[u8]) -> TiffResult<()> {
+    let mut writer = BufWriter::new(File::create(path)?);
+    write_npy(&mut writer, descr, shape, data)?;
+    writer.flush()?;
+    Ok(())
+}
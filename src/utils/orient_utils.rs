@@ -0,0 +1,157 @@
+//! Raster rotation and flipping utilities
+//!
+//! Some sensors deliver imagery north-down or mirrored, with a geotransform
+//! that says so. Rotating by quarter turns or flipping rearranges the
+//! pixels and composes the geotransform with the same change of pixel
+//! coordinates, so every pixel keeps its position on the ground: flipping
+//! a north-down image vertically makes it north-up, while a quarter turn
+//! of a north-up image gives it a rotated geotransform.
+
+use log::info;
+
+use crate::coordinate::Affine;
+use crate::extractor::{self, BufferLayout};
+use crate::tiff::builder::TiffBuilder;
+use crate::tiff::errors::{TiffError, TiffResult};
+use crate::tiff::ifd::IFD;
+use crate::tiff::TiffReader;
+use crate::utils::logger::Logger;
+use crate::utils::{grid_utils, info_utils, memory_utils};
+
+/// A rearrangement of the pixels of a raster
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Orientation {
+    /// Quarter turn clockwise
+    Rotate90,
+    /// Half turn
+    Rotate180,
+    /// Quarter turn counter-clockwise
+    Rotate270,
+    /// Mirror left to right
+    FlipHorizontal,
+    /// Mirror top to bottom
+    FlipVertical,
+}
+
+impl Orientation {
+    /// Parse an orientation name: rotate90, rotate180, rotate270, fliph or flipv
+    pub fn from_string(name: &str) -> TiffResult<Self> {
+        match name.trim().to_lowercase().as_str() {
+            "rotate90" | "90" => Ok(Orientation::Rotate90),
+            "rotate180" | "180" => Ok(Orientation::Rotate180),
+            "rotate270" | "270" | "-90" => Ok(Orientation::Rotate270),
+            "fliph" | "horizontal" => Ok(Orientation::FlipHorizontal),
+            "flipv" | "vertical" => Ok(Orientation::FlipVertical),
+            other => Err(TiffError::GenericError(format!(
+                "Unknown orientation '{}'. Supported: rotate90, rotate180, rotate270, fliph, flipv", other))),
+        }
+    }
+
+    /// Size of the rearranged raster
+    pub fn output_size(&self, width: u32, height: u32) -> (u32, u32) {
+        match self {
+            Orientation::Rotate90 | Orientation::Rotate270 => (height, width),
+            _ => (width, height),
+        }
+    }
+
+    /// Transform from output pixel positions to source pixel positions
+    ///
+    /// Positions are corner-based, so the output geotransform is the
+    /// source geotransform composed with this transform.
+    ///
+    /// # Arguments
+    /// * `width` - Source width in pixels
+    /// * `height` - Source height in pixels
+    pub fn pixel_transform(&self, width: u32, height: u32) -> Affine {
+        let (width, height) = (width as f64, height as f64);
+        // In GDAL order: origin x, x per column, x per row, origin y, y per column, y per row
+        Affine::from_gdal(match self {
+            // Output column c shows source row height - c, output row r source column r
+            Orientation::Rotate90 => [0.0, 0.0, 1.0, height, -1.0, 0.0],
+            Orientation::Rotate180 => [width, -1.0, 0.0, height, 0.0, -1.0],
+            Orientation::Rotate270 => [width, 0.0, -1.0, 0.0, 1.0, 0.0],
+            Orientation::FlipHorizontal => [width, -1.0, 0.0, 0.0, 0.0, 1.0],
+            Orientation::FlipVertical => [0.0, 1.0, 0.0, height, 0.0, -1.0],
+        })
+    }
+}
+
+/// Rearrange interleaved pixels
+///
+/// # Arguments
+/// * `samples` - Row-major pixels of the source
+/// * `width` - Source width in pixels
+/// * `height` - Source height in pixels
+/// * `pixel_bytes` - Bytes of one pixel, all bands
+/// * `orientation` - Rearrangement to apply
+///
+/// # Returns
+/// Row-major pixels of the output
+pub fn reorient_pixels(samples: &[u8], width: u32, height: u32, pixel_bytes: usize,
+                       orientation: Orientation) -> Vec<u8> {
+    let (output_width, output_height) = orientation.output_size(width, height);
+    let to_source = orientation.pixel_transform(width, height);
+    let mut output = Vec::with_capacity(samples.len());
+    for row in 0..output_height {
+        for column in 0..output_width {
+            // The source pixel under the output pixel centre
+            let (x, y) = to_source.apply(column as f64 + 0.5, row as f64 + 0.5);
+            let index = (y as usize * width as usize + x as usize) * pixel_bytes;
+            output.extend_from_slice(&samples[index..index + pixel_bytes]);
+        }
+    }
+    output
+}
+
+/// Rotate or flip a raster and its georeferencing
+///
+/// Samples are written uncompressed in one strip; other tags are kept.
+///
+/// # Arguments
+/// * `input_path` - Raster to rearrange
+/// * `output_path` - Path of the GeoTIFF to write
+/// * `orientation` - Rotation or flip to apply
+/// * `logger` - Logger for recording operations
+///
+/// # Returns
+/// The width and height of the output
+pub fn reorient(input_path: &str, output_path: &str, orientation: Orientation,
+                logger: &Logger) -> TiffResult<(u32, u32)> {
+    let shape = extractor::describe_window(input_path, None, logger)?;
+    if shape.bytes_per_sample == 0 {
+        return Err(TiffError::GenericError(format!(
+            "The sub-byte samples of {} cannot be rotated or flipped", input_path)));
+    }
+    memory_utils::check_fits(&format!("Rearranging the {}x{} raster", shape.width, shape.height),
+                             shape.byte_len() as u64 * 2)?;
+
+    let mut samples = vec![0u8; shape.byte_len()];
+    extractor::decode_into_bytes(input_path, None, BufferLayout::Interleaved, &mut samples, logger)?;
+    // Samples are decoded in the byte order of this machine, outputs are little-endian
+    if cfg!(target_endian = "big") {
+        samples.chunks_exact_mut(shape.bytes_per_sample).for_each(|sample| sample.reverse());
+    }
+    let pixels = reorient_pixels(&samples, shape.width, shape.height,
+                                 shape.bands * shape.bytes_per_sample, orientation);
+    let size = orientation.output_size(shape.width, shape.height);
+    info!("Applied {:?} to {}, {}x{} pixels", orientation, input_path, size.0, size.1);
+
+    let mut reader = TiffReader::new(logger);
+    let tiff = reader.load(input_path)?;
+    let source_ifd = tiff.main_ifd()
+        .ok_or_else(|| TiffError::GenericError("No IFDs found in input file".to_string()))?;
+    let mut builder = TiffBuilder::new(logger, tiff.is_big_tiff);
+    let ifd_index = builder.add_ifd(IFD::new(0, 0));
+    grid_utils::add_derived_image(&mut builder, ifd_index, &reader, source_ifd, size, pixels, &[]);
+
+    if let Some(georeferencing) = info_utils::read_georeferencing(&tiff, source_ifd, &reader, input_path) {
+        let geotransform = georeferencing.geotransform.compose(&orientation.pixel_transform(shape.width, shape.height));
+        grid_utils::georeference_derived(&mut builder, ifd_index, source_ifd, &georeferencing, &geotransform)?;
+        info!("Geotransform changes from {:?} to {:?}", georeferencing.geotransform.to_gdal(), geotransform.to_gdal());
+    }
+
+    builder.write(output_path)?;
+    logger.log(&format!("Wrote {:?} of {} to {}", orientation, input_path, output_path))?;
+    Ok(size)
+}
@@ -0,0 +1,133 @@
+//! Internal overview utilities
+//!
+//! Viewers and tile servers read a reduced-resolution copy of a large
+//! raster instead of decimating the full image on every zoomed-out
+//! request. Like `gdaladdo`, this computes such copies at 2x, 4x, 8x ...
+//! reduction and stores them after the main image as IFDs with
+//! NewSubfileType 1, where GDAL, QGIS and this tool's `--info` find them.
+//! The main image is kept as it is stored whenever its blocks can be
+//! copied; overviews already in the file are replaced.
+
+use log::{info, warn};
+
+use crate::extractor::Region;
+use crate::tiff::builder::TiffBuilder;
+use crate::tiff::constants::{compression, field_types, tags};
+use crate::tiff::errors::{TiffError, TiffResult};
+use crate::tiff::ifd::{IFD, IFDEntry};
+use crate::tiff::{validation, TiffReader};
+use crate::utils::grid_utils::Raster;
+use crate::utils::logger::Logger;
+use crate::utils::resize_utils::{self, Resampling};
+use crate::utils::{crop_utils, memory_utils, repair_utils};
+
+/// NewSubfileType of a reduced-resolution image
+const SUBFILE_REDUCED: u64 = 1;
+
+/// Largest width or height the smallest default overview is reduced to
+const SMALLEST_OVERVIEW: u32 = 256;
+
+/// Source tags left out of overviews, which describe the full image only
+const FULL_IMAGE_TAGS: [u16; 5] = [
+    tags::NEW_SUBFILE_TYPE, tags::GEO_KEY_DIRECTORY_TAG, tags::GEO_DOUBLE_PARAMS_TAG,
+    tags::GEO_ASCII_PARAMS_TAG, tags::GDAL_METADATA,
+];
+
+/// Overview levels GDAL would pick for an image
+///
+/// # Arguments
+/// * `width` - Image width in pixels
+/// * `height` - Image height in pixels
+///
+/// # Returns
+/// Powers of two, up to the first whose overview fits in 256x256 pixels
+pub fn default_levels(width: u32, height: u32) -> Vec<u32> {
+    let mut levels = vec![2];
+    while let Some(&level) = levels.last() {
+        if width.div_ceil(level) <= SMALLEST_OVERVIEW && height.div_ceil(level) <= SMALLEST_OVERVIEW {
+            break;
+        }
+        levels.push(level * 2);
+    }
+    levels
+}
+
+/// Add overviews to a TIFF
+///
+/// # Arguments
+/// * `input_path` - TIFF whose main image gets overviews
+/// * `output_path` - Path to write, or None to update the input in place
+/// * `levels` - Reduction factors, or empty to pick them as GDAL does
+/// * `resampling` - How overview pixels take their value
+/// * `logger` - Logger for recording operations
+///
+/// # Returns
+/// The width and height of each overview written, largest first
+pub fn build_overviews(input_path: &str, output_path: Option<&str>, levels: &[u32],
+                       resampling: Resampling, logger: &Logger) -> TiffResult<Vec<(u32, u32)>> {
+    let mut reader = TiffReader::new(logger);
+    let tiff = reader.load(input_path)?;
+    let ifd = tiff.main_ifd()
+        .ok_or_else(|| TiffError::GenericError("No IFDs found in input file".to_string()))?;
+    let (width, height) = ifd.get_dimensions()
+        .ok_or_else(|| TiffError::GenericError("Missing image dimensions".to_string()))?;
+    let (width, height) = (width as u32, height as u32);
+
+    let mut levels = if levels.is_empty() { default_levels(width, height) } else { levels.to_vec() };
+    if let Some(&level) = levels.iter().find(|&&level| level < 2) {
+        return Err(TiffError::GenericError(format!("Overview levels must be 2 or more, got {}", level)));
+    }
+    levels.sort_unstable();
+    levels.dedup();
+
+    let replaced = tiff.ifds.iter().skip(1)
+        .filter(|other| other.get_tag_value(tags::NEW_SUBFILE_TYPE).unwrap_or(0) & 5 == SUBFILE_REDUCED)
+        .count();
+    if replaced > 0 {
+        info!("Replacing the {} overview(s) of {}", replaced, input_path);
+    }
+    if tiff.ifds.len() > 1 + replaced {
+        warn!("Leaving {} other image(s) of {} out of the output", tiff.ifds.len() - 1 - replaced, input_path);
+    }
+
+    // The main image keeps its encoding when its blocks can be copied
+    let mut builder = TiffBuilder::new(logger, tiff.is_big_tiff);
+    let main_index = builder.add_ifd(IFD::new(0, 0));
+    let full_image = Region::new(0, 0, width, height);
+    match crop_utils::check_copyable(&reader, ifd, &full_image) {
+        Ok(()) => {
+            crop_utils::copy_blocks(&mut builder, main_index, &reader, ifd, input_path, &full_image)?;
+        },
+        Err(e) => {
+            warn!("Rewriting the main image uncompressed: {}", e);
+            let file_size = validation::get_file_size(&mut reader.create_reader()?)?;
+            let (raster, _) = repair_utils::salvage_image(&reader, ifd, file_size)
+                .map_err(|e| TiffError::GenericError(format!("Cannot copy the image of {}: {}", input_path, e)))?;
+            builder.deep_copy_tags_from(main_index, ifd, &repair_utils::REBUILT_TAGS, &reader);
+            builder.ifds[main_index].add_entry(IFDEntry::new(
+                tags::COMPRESSION, field_types::SHORT, 1, compression::NONE as u64));
+            builder.setup_single_strip(main_index, raster);
+        },
+    }
+
+    memory_utils::check_fits(&format!("Reading the {}x{} image", width, height),
+                             width as u64 * height as u64 * ifd.get_samples_per_pixel() * 8)?;
+    let source = Raster::read(input_path, usize::MAX, logger)?;
+    let mut sizes = Vec::with_capacity(levels.len());
+    for level in levels {
+        let size = (width.div_ceil(level), height.div_ceil(level));
+        let steps = (width as f64 / size.0 as f64, height as f64 / size.1 as f64);
+        let overview_index = builder.add_ifd(IFD::new(builder.ifds.len(), 0));
+        resize_utils::add_resampled_image(&mut builder, overview_index, &reader, ifd, &source,
+                                          size, steps, resampling, &FULL_IMAGE_TAGS)?;
+        builder.ifds[overview_index].add_entry(IFDEntry::new(
+            tags::NEW_SUBFILE_TYPE, field_types::LONG, 1, SUBFILE_REDUCED));
+        info!("Computed the 1:{} overview of {}x{} pixels", level, size.0, size.1);
+        sizes.push(size);
+    }
+
+    let output_path = output_path.unwrap_or(input_path);
+    builder.write(output_path)?;
+    logger.log(&format!("Wrote {} overview(s) of {} to {}", sizes.len(), input_path, output_path))?;
+    Ok(sizes)
+}
@@ -0,0 +1,194 @@
+//! Pan-sharpening utilities
+//!
+//! Fuses a high-resolution panchromatic band with lower-resolution
+//! multispectral bands using the weighted Brovey transform, as
+//! `gdal_pansharpen.py` does. The multispectral bands are resampled
+//! bilinearly onto the panchromatic grid, a pseudo-panchromatic value is
+//! formed as the weighted mean of the resampled bands, and each band is
+//! multiplied by the ratio of the real to the pseudo-panchromatic value.
+//! The sharpened bands are stretched to an 8-bit RGB image.
+//!
+//! The bilinear resampling can run on the GPU (`gpu_utils`), which returns
+//! the same values up to single-precision rounding.
+
+use image::{DynamicImage, RgbImage};
+use log::{info, warn};
+
+use crate::coordinate::Affine;
+use crate::tiff::errors::{TiffError, TiffResult};
+use crate::utils::gpu_utils::{self, GpuResampler, Sampling};
+use crate::utils::grid_utils::{self, Raster};
+use crate::utils::logger::Logger;
+use crate::utils::render_utils::{self, Stretch, StretchMode};
+use crate::utils::statistics_utils::{self, BandReport, Histogram};
+
+/// Multispectral bands fused into the RGB output
+const FUSED_BANDS: usize = 3;
+
+/// Pan-sharpening options
+#[derive(Debug, Clone)]
+pub struct PansharpenOptions {
+    /// Weight of each multispectral band in the pseudo-panchromatic value,
+    /// None for equal weights
+    pub weights: Option<Vec<f64>>,
+    /// How the sharpened values are stretched to 8 bits
+    pub stretch: StretchMode,
+    /// Resample the multispectral bands on the GPU when one is available
+    pub gpu: bool,
+    /// Quality of lossy image outputs, None for the format default
+    pub quality: Option<u8>,
+}
+
+impl Default for PansharpenOptions {
+    fn default() -> Self {
+        PansharpenOptions {
+            weights: None,
+            stretch: StretchMode::Linear,
+            gpu: false,
+            quality: None,
+        }
+    }
+}
+
+/// Apply the weighted Brovey transform to one pixel
+///
+/// # Arguments
+/// * `pan` - Panchromatic value
+/// * `spectral` - Multispectral values resampled to the pixel
+/// * `weights` - Weight of each multispectral value, summing to 1
+///
+/// # Returns
+/// The sharpened multispectral values, or None when the pseudo-panchromatic
+/// value is not positive
+pub fn brovey(pan: f64, spectral: &[f64], weights: &[f64]) -> Option<Vec<f64>> {
+    let pseudo_pan: f64 = spectral.iter().zip(weights).map(|(value, weight)| value * weight).sum();
+    if pseudo_pan <= 0.0 {
+        return None;
+    }
+    let ratio = pan / pseudo_pan;
+    Some(spectral.iter().map(|value| value * ratio).collect())
+}
+
+/// Resample the fused multispectral bands onto the panchromatic grid on the GPU
+///
+/// # Returns
+/// Each band's value under every panchromatic pixel, `NO_DATA` where it has none
+fn resample_on_gpu(resampler: &GpuResampler, spectral: &Raster, mapping: &Affine,
+                   width: usize, height: usize) -> TiffResult<Vec<Vec<f32>>> {
+    let positions: Vec<[f32; 2]> = (0..width * height)
+        .map(|index| {
+            let (x, y) = mapping.apply((index % width) as f64 + 0.5, (index / width) as f64 + 0.5);
+            [x as f32, y as f32]
+        })
+        .collect();
+    spectral.bands[..FUSED_BANDS].iter()
+        .map(|values| {
+            let band: Vec<f32> = values.iter()
+                .map(|&value| if value.is_nan() { gpu_utils::NO_DATA } else { value as f32 })
+                .collect();
+            resampler.resample(&band, spectral.layout.width, spectral.layout.height, &positions, Sampling::Bilinear)
+        })
+        .collect()
+}
+
+/// Pan-sharpen multispectral bands to an RGB image
+///
+/// The first three multispectral bands become red, green and blue; the
+/// output is on the grid of the panchromatic band and georeferenced like it.
+///
+/// # Arguments
+/// * `spectral_path` - Raster with the multispectral bands
+/// * `pan_path` - Raster with the panchromatic band
+/// * `output_path` - Path of the image to write
+/// * `options` - Band weights and stretch
+/// * `logger` - Logger for recording operations
+///
+/// # Returns
+/// Result indicating success or an error
+pub fn pansharpen_file(spectral_path: &str, pan_path: &str, output_path: &str,
+                       options: &PansharpenOptions, logger: &Logger) -> TiffResult<()> {
+    let spectral = Raster::read(spectral_path, FUSED_BANDS, logger)?;
+    if spectral.bands.len() < FUSED_BANDS {
+        return Err(TiffError::GenericError(format!(
+            "{} has {} band(s), pan-sharpening needs {}", spectral_path, spectral.bands.len(), FUSED_BANDS)));
+    }
+    let pan = Raster::read(pan_path, 1, logger)?;
+    if pan.layout.bands > 1 {
+        info!("{} has {} bands, using the first as panchromatic", pan_path, pan.layout.bands);
+    }
+
+    let weights = options.weights.clone().unwrap_or_else(|| vec![1.0; FUSED_BANDS]);
+    let total: f64 = weights.iter().sum();
+    if weights.len() != FUSED_BANDS || weights.iter().any(|&weight| weight < 0.0) || total <= 0.0 {
+        return Err(TiffError::GenericError(format!(
+            "Pan-sharpening needs {} non-negative weights, got {:?}", FUSED_BANDS, weights)));
+    }
+    let weights: Vec<f64> = weights.iter().map(|weight| weight / total).collect();
+
+    let mapping = grid_utils::pixel_mapping(&pan, &spectral)?;
+    let (width, height) = (pan.layout.width as usize, pan.layout.height as usize);
+    info!("Sharpening {}x{} multispectral pixels to {}x{} with weights {:?}",
+          spectral.layout.width, spectral.layout.height, width, height, weights);
+
+    let resampled = if options.gpu {
+        GpuResampler::new().and_then(|resampler| {
+            resample_on_gpu(&resampler, &spectral, &mapping, width, height)
+                .inspect_err(|e| warn!("{}, resampling on the CPU", e))
+                .ok()
+        })
+    } else {
+        None
+    };
+
+    let mut sharpened = vec![vec![f64::NAN; width * height]; FUSED_BANDS];
+    for row in 0..height {
+        for column in 0..width {
+            let index = row * width + column;
+            let pan_value = pan.bands[0][index];
+            if pan_value.is_nan() {
+                continue;
+            }
+            let values: Option<Vec<f64>> = match &resampled {
+                Some(bands) => bands.iter()
+                    .map(|band| (band[index] != gpu_utils::NO_DATA).then_some(band[index] as f64))
+                    .collect(),
+                None => {
+                    let (x, y) = mapping.apply(column as f64 + 0.5, row as f64 + 0.5);
+                    (0..FUSED_BANDS).map(|band| spectral.bilinear(band, x, y)).collect()
+                },
+            };
+            if let Some(fused) = values.and_then(|values| brovey(pan_value, &values, &weights)) {
+                for (band, value) in fused.into_iter().enumerate() {
+                    sharpened[band][index] = value;
+                }
+            }
+        }
+    }
+
+    // Stretch each band over the distribution of its sharpened values
+    let mut levels = vec![0u8; width * height * FUSED_BANDS];
+    for (band, values) in sharpened.iter().enumerate() {
+        let valid: Vec<f64> = values.iter().copied().filter(|value| !value.is_nan()).collect();
+        let Some(statistics) = statistics_utils::describe(&valid) else {
+            return Err(TiffError::GenericError("The rasters share no pixels with data".to_string()));
+        };
+        let report = BandReport {
+            band: band + 1,
+            statistics,
+            valid_percent: 100.0 * valid.len() as f64 / values.len() as f64,
+            histogram: Histogram::build(&valid, statistics.min, statistics.max, statistics_utils::HISTOGRAM_BUCKETS),
+            approximate: false,
+        };
+        let stretch = Stretch::new(options.stretch, &report);
+        for (index, &value) in values.iter().enumerate().filter(|(_, value)| !value.is_nan()) {
+            levels[index * FUSED_BANDS + band] = stretch.apply(value);
+        }
+    }
+
+    let image = RgbImage::from_raw(width as u32, height as u32, levels)
+        .ok_or_else(|| TiffError::GenericError("Sharpened image does not fit the raster".to_string()))?;
+    render_utils::save_rendered(&DynamicImage::ImageRgb8(image), output_path, pan_path, options.quality, logger)?;
+
+    info!("Pan-sharpened image written to {}", output_path);
+    Ok(())
+}
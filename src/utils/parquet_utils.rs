@@ -0,0 +1,399 @@
+//! Apache Parquet utilities
+//!
+//! A small writer for flat Parquet files: every column is REQUIRED, values
+//! are PLAIN encoded and pages are ZSTD compressed. Rows are split into row
+//! groups of a configurable size and every column chunk carries min/max
+//! statistics, so engines such as DuckDB or Spark can skip row groups (and,
+//! with hive-style partitioning, whole files) that cannot match a filter.
+//!
+//! Parquet metadata is serialized with the Thrift compact protocol, which is
+//! implemented here for the handful of structures a writer needs.
+
+use std::fs::File;
+use std::io::{BufWriter, Write};
+
+use log::info;
+
+use crate::compression::{CompressionHandler, ZstdHandler};
+use crate::tiff::errors::{TiffError, TiffResult};
+
+/// Magic bytes at the start and end of every Parquet file
+const PARQUET_MAGIC: &[u8; 4] = b"PAR1";
+
+/// Default number of rows per row group (DuckDB's default)
+pub const DEFAULT_ROW_GROUP_SIZE: usize = 122_880;
+
+// Parquet enum values used by the writer
+const PAGE_TYPE_DATA: i32 = 0;
+const ENCODING_PLAIN: i32 = 0;
+const ENCODING_RLE: i32 = 3;
+const CODEC_ZSTD: i32 = 6;
+const REPETITION_REQUIRED: i32 = 0;
+
+// Thrift compact protocol type ids
+const CT_I32: u8 = 5;
+const CT_I64: u8 = 6;
+const CT_BINARY: u8 = 8;
+const CT_LIST: u8 = 9;
+const CT_STRUCT: u8 = 12;
+
+/// PLAIN-encoded minimum and maximum of a column chunk
+type MinMax = (Vec<u8>, Vec<u8>);
+
+/// Values of a single column
+#[derive(Debug, Clone)]
+pub enum ParquetValues {
+    /// 32-bit signed integers
+    Int32(Vec<i32>),
+    /// 64-bit floating point numbers
+    Double(Vec<f64>),
+}
+
+impl ParquetValues {
+    /// Number of values in the column
+    pub fn len(&self) -> usize {
+        match self {
+            ParquetValues::Int32(v) => v.len(),
+            ParquetValues::Double(v) => v.len(),
+        }
+    }
+
+    /// Parquet physical type id
+    fn physical_type(&self) -> i32 {
+        match self {
+            ParquetValues::Int32(_) => 1,
+            ParquetValues::Double(_) => 5,
+        }
+    }
+
+    /// PLAIN-encode a range of values and compute their min/max statistics
+    fn encode_range(&self, start: usize, end: usize) -> (Vec<u8>, Option<MinMax>) {
+        match self {
+            ParquetValues::Int32(values) => {
+                let slice = &values[start..end];
+                let data = slice.iter().flat_map(|v| v.to_le_bytes()).collect();
+                let stats = slice.iter().min().zip(slice.iter().max())
+                    .map(|(min, max)| (min.to_le_bytes().to_vec(), max.to_le_bytes().to_vec()));
+                (data, stats)
+            },
+            ParquetValues::Double(values) => {
+                let slice = &values[start..end];
+                let data = slice.iter().flat_map(|v| v.to_le_bytes()).collect();
+                let finite = slice.iter().copied().filter(|v| !v.is_nan());
+                let stats = finite.clone().reduce(f64::min).zip(finite.reduce(f64::max))
+                    .map(|(min, max)| (min.to_le_bytes().to_vec(), max.to_le_bytes().to_vec()));
+                (data, stats)
+            },
+        }
+    }
+}
+
+/// A named column of a Parquet file
+#[derive(Debug, Clone)]
+pub struct ParquetColumn {
+    /// Column name
+    pub name: String,
+    /// Column values, one per row
+    pub values: ParquetValues,
+}
+
+impl ParquetColumn {
+    /// Create a new column
+    ///
+    /// # Arguments
+    /// * `name` - Column name
+    /// * `values` - Column values
+    ///
+    /// # Returns
+    /// A new ParquetColumn
+    pub fn new(name: &str, values: ParquetValues) -> Self {
+        ParquetColumn { name: name.to_string(), values }
+    }
+}
+
+/// How exported rows are split across files
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum ParquetPartitioning {
+    /// A single Parquet file
+    None,
+    /// One file per tile, in `tile_row=R/tile_col=C/` directories
+    Tile(u32, u32),
+    /// One file per band, in `band=B/` directories
+    Band,
+}
+
+impl ParquetPartitioning {
+    /// Parse a partitioning scheme name
+    ///
+    /// # Arguments
+    /// * `name` - "none", "tile" or "band" (case-insensitive)
+    /// * `tile_size` - Tile size used by tile partitioning
+    ///
+    /// # Returns
+    /// The partitioning scheme or an error
+    pub fn from_string(name: &str, tile_size: Option<(u32, u32)>) -> TiffResult<Self> {
+        match name.to_lowercase().as_str() {
+            "none" => Ok(ParquetPartitioning::None),
+            "band" => Ok(ParquetPartitioning::Band),
+            "tile" => {
+                let (width, height) = tile_size.unwrap_or((256, 256));
+                Ok(ParquetPartitioning::Tile(width, height))
+            },
+            _ => Err(TiffError::GenericError(format!(
+                "Unknown partitioning '{}' (expected 'none', 'tile' or 'band')", name))),
+        }
+    }
+}
+
+/// Minimal Thrift compact protocol encoder
+struct CompactWriter {
+    buf: Vec<u8>,
+    /// Last field id written, per nesting level
+    last_field: Vec<i16>,
+}
+
+impl CompactWriter {
+    fn new() -> Self {
+        CompactWriter { buf: Vec::new(), last_field: vec![0] }
+    }
+
+    fn varint(&mut self, mut value: u64) {
+        while value >= 0x80 {
+            self.buf.push((value as u8) | 0x80);
+            value >>= 7;
+        }
+        self.buf.push(value as u8);
+    }
+
+    fn zigzag(&mut self, value: i64) {
+        self.varint(((value << 1) ^ (value >> 63)) as u64);
+    }
+
+    fn field(&mut self, id: i16, type_id: u8) {
+        let last = self.last_field.last_mut().expect("field outside of a struct");
+        let delta = id - std::mem::replace(last, id);
+        if (1..=15).contains(&delta) {
+            self.buf.push(((delta as u8) << 4) | type_id);
+        } else {
+            self.buf.push(type_id);
+            self.zigzag(id as i64);
+        }
+    }
+
+    fn i32_field(&mut self, id: i16, value: i32) {
+        self.field(id, CT_I32);
+        self.zigzag(value as i64);
+    }
+
+    fn i64_field(&mut self, id: i16, value: i64) {
+        self.field(id, CT_I64);
+        self.zigzag(value);
+    }
+
+    fn binary_field(&mut self, id: i16, value: &[u8]) {
+        self.field(id, CT_BINARY);
+        self.binary(value);
+    }
+
+    fn binary(&mut self, value: &[u8]) {
+        self.varint(value.len() as u64);
+        self.buf.extend_from_slice(value);
+    }
+
+    fn list_field(&mut self, id: i16, element_type: u8, size: usize) {
+        self.field(id, CT_LIST);
+        self.list_header(element_type, size);
+    }
+
+    fn list_header(&mut self, element_type: u8, size: usize) {
+        if size < 15 {
+            self.buf.push(((size as u8) << 4) | element_type);
+        } else {
+            self.buf.push(0xF0 | element_type);
+            self.varint(size as u64);
+        }
+    }
+
+    fn begin_struct_field(&mut self, id: i16) {
+        self.field(id, CT_STRUCT);
+        self.begin_struct();
+    }
+
+    fn begin_struct(&mut self) {
+        self.last_field.push(0);
+    }
+
+    fn end_struct(&mut self) {
+        self.buf.push(0);
+        self.last_field.pop();
+    }
+}
+
+/// Metadata of a written column chunk
+struct ChunkInfo {
+    physical_type: i32,
+    name: String,
+    num_values: usize,
+    data_page_offset: u64,
+    uncompressed_size: usize,
+    compressed_size: usize,
+    stats: Option<MinMax>,
+}
+
+/// Write a column chunk (a single data page) and describe it
+fn write_chunk<W: Write>(
+    writer: &mut W,
+    offset: u64,
+    column: &ParquetColumn,
+    start: usize,
+    end: usize,
+    compressor: &ZstdHandler
+) -> TiffResult<ChunkInfo> {
+    let (data, stats) = column.values.encode_range(start, end);
+    let compressed = compressor.compress(&data)?;
+
+    let mut header = CompactWriter::new();
+    header.i32_field(1, PAGE_TYPE_DATA);
+    header.i32_field(2, data.len() as i32);
+    header.i32_field(3, compressed.len() as i32);
+    header.begin_struct_field(5);
+    header.i32_field(1, (end - start) as i32);
+    header.i32_field(2, ENCODING_PLAIN);
+    header.i32_field(3, ENCODING_RLE);
+    header.i32_field(4, ENCODING_RLE);
+    header.end_struct();
+    header.buf.push(0);
+
+    writer.write_all(&header.buf)?;
+    writer.write_all(&compressed)?;
+
+    Ok(ChunkInfo {
+        physical_type: column.values.physical_type(),
+        name: column.name.clone(),
+        num_values: end - start,
+        data_page_offset: offset,
+        uncompressed_size: header.buf.len() + data.len(),
+        compressed_size: header.buf.len() + compressed.len(),
+        stats,
+    })
+}
+
+/// Serialize the file footer (FileMetaData)
+fn encode_footer(columns: &[ParquetColumn], num_rows: usize, row_groups: &[(usize, Vec<ChunkInfo>)]) -> Vec<u8> {
+    let mut meta = CompactWriter::new();
+    meta.i32_field(1, 1);
+
+    // Schema: a root group followed by one leaf per column
+    meta.list_field(2, CT_STRUCT, columns.len() + 1);
+    meta.begin_struct();
+    meta.binary_field(4, b"schema");
+    meta.i32_field(5, columns.len() as i32);
+    meta.end_struct();
+    for column in columns {
+        meta.begin_struct();
+        meta.i32_field(1, column.values.physical_type());
+        meta.i32_field(3, REPETITION_REQUIRED);
+        meta.binary_field(4, column.name.as_bytes());
+        meta.end_struct();
+    }
+
+    meta.i64_field(3, num_rows as i64);
+
+    meta.list_field(4, CT_STRUCT, row_groups.len());
+    for (rows, chunks) in row_groups {
+        meta.begin_struct();
+        meta.list_field(1, CT_STRUCT, chunks.len());
+        for chunk in chunks {
+            meta.begin_struct();
+            meta.i64_field(2, chunk.data_page_offset as i64);
+            meta.begin_struct_field(3);
+            meta.i32_field(1, chunk.physical_type);
+            meta.list_field(2, CT_I32, 2);
+            meta.zigzag(ENCODING_PLAIN as i64);
+            meta.zigzag(ENCODING_RLE as i64);
+            meta.list_field(3, CT_BINARY, 1);
+            meta.binary(chunk.name.as_bytes());
+            meta.i32_field(4, CODEC_ZSTD);
+            meta.i64_field(5, chunk.num_values as i64);
+            meta.i64_field(6, chunk.uncompressed_size as i64);
+            meta.i64_field(7, chunk.compressed_size as i64);
+            meta.i64_field(9, chunk.data_page_offset as i64);
+            meta.begin_struct_field(12);
+            meta.i64_field(3, 0);
+            if let Some((min, max)) = &chunk.stats {
+                meta.binary_field(5, max);
+                meta.binary_field(6, min);
+            }
+            meta.end_struct();
+            meta.end_struct();
+            meta.end_struct();
+        }
+        let byte_size: usize = chunks.iter().map(|c| c.uncompressed_size).sum();
+        meta.i64_field(2, byte_size as i64);
+        meta.i64_field(3, *rows as i64);
+        meta.end_struct();
+    }
+
+    meta.binary_field(6, concat!("rasterkit version ", env!("CARGO_PKG_VERSION")).as_bytes());
+
+    // Column orders: plain type-defined ordering, so min/max statistics apply
+    meta.list_field(7, CT_STRUCT, columns.len());
+    for _ in columns {
+        meta.begin_struct();
+        meta.begin_struct_field(1);
+        meta.end_struct();
+        meta.end_struct();
+    }
+
+    meta.buf.push(0);
+    meta.buf
+}
+
+/// Write columns to a Parquet file
+///
+/// All columns must have the same length. Rows are written in order and
+/// split into row groups of at most `row_group_size` rows.
+///
+/// # Arguments
+/// * `path` - Path of the file to write
+/// * `columns` - Columns to store
+/// * `row_group_size` - Maximum number of rows per row group
+///
+/// # Returns
+/// Result indicating success or an error
+pub fn write_parquet(path: &str, columns: &[ParquetColumn], row_group_size: usize) -> TiffResult<()> {
+    let num_rows = columns.first().map(|c| c.values.len()).unwrap_or(0);
+    if let Some(column) = columns.iter().find(|c| c.values.len() != num_rows) {
+        return Err(TiffError::GenericError(format!(
+            "Column '{}' has {} values, expected {}", column.name, column.values.len(), num_rows)));
+    }
+    if row_group_size == 0 {
+        return Err(TiffError::GenericError("Row group size must be at least 1".to_string()));
+    }
+
+    let compressor = ZstdHandler::new();
+    let mut writer = BufWriter::new(File::create(path)?);
+    writer.write_all(PARQUET_MAGIC)?;
+    let mut offset = PARQUET_MAGIC.len() as u64;
+
+    let mut row_groups = Vec::new();
+    for start in (0..num_rows).step_by(row_group_size) {
+        let end = (start + row_group_size).min(num_rows);
+        let mut chunks = Vec::with_capacity(columns.len());
+        for column in columns {
+            let chunk = write_chunk(&mut writer, offset, column, start, end, &compressor)?;
+            offset += chunk.compressed_size as u64;
+            chunks.push(chunk);
+        }
+        row_groups.push((end - start, chunks));
+    }
+
+    let footer = encode_footer(columns, num_rows, &row_groups);
+    writer.write_all(&footer)?;
+    writer.write_all(&(footer.len() as u32).to_le_bytes())?;
+    writer.write_all(PARQUET_MAGIC)?;
+    writer.flush()?;
+
+    info!("Wrote {} rows in {} row groups to {}", num_rows, row_groups.len(), path);
+    Ok(())
+}
@@ -0,0 +1,297 @@
+//! PostGIS raster utilities
+//!
+//! Produces SQL scripts in the style of `raster2pgsql` for loading pixel
+//! data into a PostGIS `raster` column. Each tile is serialized in the
+//! PostGIS raster WKB format and emitted as a hex literal, either as one
+//! `INSERT` statement per tile or as rows of a single `COPY ... FROM stdin`
+//! block, which loads considerably faster through `psql`.
+//!
+//! Bands keep the sample type of the source: each maps to the PostGIS
+//! pixel type of the same width and signedness (8BUI, 16BSI, 16BUI, 32BSI,
+//! 32BUI, 32BF or 64BF), with the NoData value stored in that type.
+
+use std::fs::File;
+use std::io::{BufWriter, Write};
+
+use log::{info, warn};
+
+use crate::coordinate::Affine;
+use crate::tiff::errors::{TiffError, TiffResult};
+use crate::utils::tensor_utils::TensorDType;
+
+/// Band flag set when the band declares a NoData value
+const BAND_FLAG_HAS_NODATA: u8 = 0x40;
+
+/// PostGIS pixel type code of a sample type
+///
+/// # Arguments
+/// * `dtype` - Sample type of the band
+///
+/// # Returns
+/// The pixel type code and its name, as `ST_BandPixelType` reports it
+pub fn pixel_type(dtype: TensorDType) -> (u8, &'static str) {
+    match dtype {
+        TensorDType::U8 => (4, "8BUI"),
+        TensorDType::I16 => (5, "16BSI"),
+        TensorDType::U16 => (6, "16BUI"),
+        TensorDType::I32 => (7, "32BSI"),
+        TensorDType::U32 => (8, "32BUI"),
+        TensorDType::F32 => (10, "32BF"),
+        TensorDType::F64 => (11, "64BF"),
+    }
+}
+
+/// A NoData value as little-endian bytes of a sample type
+///
+/// # Arguments
+/// * `value` - The NoData value
+/// * `dtype` - Sample type of the band
+///
+/// # Returns
+/// The bytes, or None when the type cannot hold the value exactly
+fn nodata_bytes(value: f64, dtype: TensorDType) -> Option<Vec<u8>> {
+    let integral = |min: f64, max: f64| value.fract() == 0.0 && (min..=max).contains(&value);
+    match dtype {
+        TensorDType::U8 if integral(0.0, u8::MAX as f64) => Some(vec![value as u8]),
+        TensorDType::I16 if integral(i16::MIN as f64, i16::MAX as f64) => Some((value as i16).to_le_bytes().to_vec()),
+        TensorDType::U16 if integral(0.0, u16::MAX as f64) => Some((value as u16).to_le_bytes().to_vec()),
+        TensorDType::I32 if integral(i32::MIN as f64, i32::MAX as f64) => Some((value as i32).to_le_bytes().to_vec()),
+        TensorDType::U32 if integral(0.0, u32::MAX as f64) => Some((value as u32).to_le_bytes().to_vec()),
+        TensorDType::F32 => Some((value as f32).to_le_bytes().to_vec()),
+        TensorDType::F64 => Some(value.to_le_bytes().to_vec()),
+        _ => None,
+    }
+}
+
+/// How tile rows are written to the SQL script
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum PostgisLoadMode {
+    /// One `INSERT` statement per tile (raster2pgsql default)
+    Insert,
+    /// A `COPY ... FROM stdin` block (raster2pgsql `-Y`)
+    Copy,
+}
+
+/// Options controlling the generated PostGIS script
+#[derive(Debug, Clone)]
+pub struct PostgisOptions {
+    /// Target table, optionally schema-qualified ("schema.table")
+    pub table: String,
+    /// Name of the raster column
+    pub column: String,
+    /// SRID to assign; 0 (unknown) when not set
+    pub srid: Option<i32>,
+    /// Tile size in pixels (width, height); None stores one tile
+    pub tile_size: Option<(u32, u32)>,
+    /// Statement style used for the tile rows
+    pub mode: PostgisLoadMode,
+    /// Emit a `CREATE TABLE` statement before the data
+    pub create_table: bool,
+    /// Emit a GiST index on the convex hull of the tiles
+    pub spatial_index: bool,
+}
+
+impl Default for PostgisOptions {
+    fn default() -> Self {
+        PostgisOptions {
+            table: "raster".to_string(),
+            column: "rast".to_string(),
+            srid: None,
+            tile_size: None,
+            mode: PostgisLoadMode::Insert,
+            create_table: true,
+            spatial_index: false,
+        }
+    }
+}
+
+/// Parse a tile size given as "WIDTHxHEIGHT" or a single edge length
+///
+/// # Arguments
+/// * `value` - Tile size string, e.g. "256x256" or "100"
+///
+/// # Returns
+/// The (width, height) tile size or an error
+pub fn parse_tile_size(value: &str) -> TiffResult<(u32, u32)> {
+    let parse = |s: &str| s.trim().parse::<u32>().ok().filter(|&v| v > 0 && v <= u16::MAX as u32);
+
+    let size = match value.to_lowercase().split_once('x') {
+        Some((w, h)) => parse(w).zip(parse(h)),
+        None => parse(value).map(|v| (v, v)),
+    };
+
+    size.ok_or_else(|| TiffError::GenericError(format!(
+        "Invalid tile size '{}' (expected WIDTHxHEIGHT between 1 and 65535)", value)))
+}
+
+/// Quote an identifier, splitting off an optional schema
+fn quote_table(table: &str) -> String {
+    let quote = |s: &str| format!("\"{}\"", s.replace('"', "\"\""));
+    match table.split_once('.') {
+        Some((schema, name)) => format!("{}.{}", quote(schema), quote(name)),
+        None => quote(table),
+    }
+}
+
+/// Serialize a single-band tile of any sample type as PostGIS raster WKB
+///
+/// The band gets the pixel type of `dtype`; a NoData value the type cannot
+/// hold is left out, with the band's NoData flag cleared.
+///
+/// # Arguments
+/// * `width` - Tile width in pixels
+/// * `height` - Tile height in pixels
+/// * `geotransform` - Geotransform of the tile's top-left corner
+/// * `srid` - Spatial reference id
+/// * `dtype` - Sample type of the band
+/// * `nodata` - NoData value of the band, if any
+/// * `data` - Little-endian samples in row-major order
+///
+/// # Returns
+/// The WKB bytes
+pub fn raster_wkb(
+    width: u16,
+    height: u16,
+    geotransform: &Affine,
+    srid: i32,
+    dtype: TensorDType,
+    nodata: Option<f64>,
+    data: &[u8]
+) -> Vec<u8> {
+    let gt = geotransform;
+    let mut wkb = Vec::with_capacity(61 + 1 + dtype.size() + data.len());
+
+    wkb.push(1);                                     // NDR (little-endian)
+    wkb.extend_from_slice(&0u16.to_le_bytes());      // format version
+    wkb.extend_from_slice(&1u16.to_le_bytes());      // band count
+    for value in [gt.pixel_width, gt.pixel_height, gt.origin_x, gt.origin_y, gt.row_rotation, gt.column_rotation] {
+        wkb.extend_from_slice(&value.to_le_bytes()); // scale, upper-left, skew
+    }
+    wkb.extend_from_slice(&srid.to_le_bytes());
+    wkb.extend_from_slice(&width.to_le_bytes());
+    wkb.extend_from_slice(&height.to_le_bytes());
+
+    // The NoData slot is always present, zero when the band has none
+    let nodata = nodata.and_then(|value| nodata_bytes(value, dtype));
+    let flags = pixel_type(dtype).0 | if nodata.is_some() { BAND_FLAG_HAS_NODATA } else { 0 };
+    wkb.push(flags);
+    wkb.extend_from_slice(&nodata.unwrap_or_else(|| vec![0; dtype.size()]));
+    wkb.extend_from_slice(data);
+
+    wkb
+}
+
+/// Encode bytes as upper-case hex, as raster2pgsql does
+fn to_hex(bytes: &[u8]) -> String {
+    const DIGITS: &[u8; 16] = b"0123456789ABCDEF";
+    let mut hex = String::with_capacity(bytes.len() * 2);
+    for &b in bytes {
+        hex.push(DIGITS[(b >> 4) as usize] as char);
+        hex.push(DIGITS[(b & 0x0F) as usize] as char);
+    }
+    hex
+}
+
+/// Write a raster2pgsql-compatible SQL script for a single band of any type
+///
+/// The raster is split into tiles of the requested size; tiles on the
+/// right and bottom edges are smaller rather than padded. Without a
+/// geotransform, tiles get a unit pixel size with a negative Y scale.
+///
+/// # Arguments
+/// * `path` - Path of the SQL file to write
+/// * `width` - Raster width in pixels
+/// * `height` - Raster height in pixels
+/// * `dtype` - Sample type of the band
+/// * `data` - Little-endian samples in row-major order
+/// * `geotransform` - Geotransform of the raster, if georeferenced
+/// * `nodata` - NoData value of the source, if any
+/// * `options` - Table, SRID, tiling and statement options
+///
+/// # Returns
+/// The number of tiles written, or an error
+#[allow(clippy::too_many_arguments)]
+pub fn write_postgis_sql(
+    path: &str,
+    width: u32,
+    height: u32,
+    dtype: TensorDType,
+    data: &[u8],
+    geotransform: Option<Affine>,
+    nodata: Option<f64>,
+    options: &PostgisOptions
+) -> TiffResult<usize> {
+    let sample_size = dtype.size();
+    if data.len() != (width as usize) * (height as usize) * sample_size {
+        return Err(TiffError::GenericError(format!(
+            "Pixel buffer holds {} bytes but the {}x{} raster of {} samples needs {}",
+            data.len(), width, height, pixel_type(dtype).1, width as usize * height as usize * sample_size)));
+    }
+
+    let (tile_width, tile_height) = options.tile_size.unwrap_or((width, height));
+    if tile_width > u16::MAX as u32 || tile_height > u16::MAX as u32 {
+        return Err(TiffError::GenericError(format!(
+            "PostGIS rasters are limited to 65535 pixels per side; use a tile size for {}x{}",
+            width, height)));
+    }
+
+    let srid = options.srid.unwrap_or(0);
+    let gt = geotransform.unwrap_or(Affine::new(0.0, 1.0, 0.0, -1.0));
+    if let Some(value) = nodata.filter(|&value| nodata_bytes(value, dtype).is_none()) {
+        warn!("NoData value {} does not fit {} pixels; the bands are written without NoData",
+              value, pixel_type(dtype).1);
+    }
+
+    let table = quote_table(&options.table);
+    let column = format!("\"{}\"", options.column.replace('"', "\"\""));
+
+    info!("Writing {} PostGIS raster SQL for {} to {} (SRID {}, {}x{} tiles, {:?})",
+          pixel_type(dtype).1, table, path, srid, tile_width, tile_height, options.mode);
+
+    let mut writer = BufWriter::new(File::create(path)?);
+    writeln!(writer, "BEGIN;")?;
+    if options.create_table {
+        writeln!(writer, "CREATE TABLE {} (\"rid\" serial PRIMARY KEY,{} raster);", table, column)?;
+    }
+    if options.mode == PostgisLoadMode::Copy {
+        writeln!(writer, "COPY {} ({}) FROM stdin;", table, column)?;
+    }
+
+    let mut tile_count = 0;
+    let mut tile = Vec::with_capacity(tile_width as usize * tile_height as usize * sample_size);
+    for tile_y in (0..height).step_by(tile_height as usize) {
+        for tile_x in (0..width).step_by(tile_width as usize) {
+            let w = tile_width.min(width - tile_x);
+            let h = tile_height.min(height - tile_y);
+
+            tile.clear();
+            for row in tile_y..tile_y + h {
+                let start = (row as usize * width as usize + tile_x as usize) * sample_size;
+                tile.extend_from_slice(&data[start..start + w as usize * sample_size]);
+            }
+
+            let tile_gt = gt.translated(tile_x, tile_y);
+            let hex = to_hex(&raster_wkb(w as u16, h as u16, &tile_gt, srid, dtype, nodata, &tile));
+
+            match options.mode {
+                PostgisLoadMode::Insert =>
+                    writeln!(writer, "INSERT INTO {} ({}) VALUES ('{}'::raster);", table, column, hex)?,
+                PostgisLoadMode::Copy => writeln!(writer, "{}", hex)?,
+            }
+            tile_count += 1;
+        }
+    }
+
+    if options.mode == PostgisLoadMode::Copy {
+        writeln!(writer, "\\.")?;
+    }
+    if options.spatial_index {
+        writeln!(writer, "CREATE INDEX ON {} USING gist (st_convexhull({}));", table, column)?;
+    }
+    writeln!(writer, "ANALYZE {};", table)?;
+    writeln!(writer, "END;")?;
+    writer.flush()?;
+
+    info!("Wrote {} raster tiles to {}", tile_count, path);
+    Ok(tile_count)
+}
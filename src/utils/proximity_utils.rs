@@ -0,0 +1,276 @@
+//! Proximity (distance transform) utilities
+//!
+//! This module computes, for every pixel of a raster, the Euclidean distance
+//! to the nearest "target" pixel. The result is the classic proximity raster
+//! used for buffering and suitability analysis (e.g. distance to roads or
+//! water bodies). Distances can be expressed in pixels or in map units.
+//!
+//! Targets are picked on the stored values of one band, so class codes
+//! above 255 and bands other than the first of a multi-band raster work.
+
+use log::{info, warn};
+
+use crate::tiff::constants::tags;
+use crate::tiff::errors::{TiffError, TiffResult};
+use crate::tiff::TiffReader;
+use crate::utils::logger::Logger;
+use crate::utils::{sample_utils, tiff_extraction_utils};
+
+/// NoData value written for pixels beyond the maximum distance
+pub const PROXIMITY_NODATA: f32 = -1.0;
+
+/// Stand-in for "infinitely far away" that keeps the parabola math finite
+const FAR_AWAY: f64 = 1e20;
+
+/// Options controlling a proximity computation
+#[derive(Debug, Clone)]
+pub struct ProximityOptions {
+    /// Inclusive ranges of pixel values treated as targets (empty means
+    /// every non-zero pixel)
+    pub target_values: Vec<(f64, f64)>,
+    /// Band whose values are compared with the targets, from 1
+    pub band: usize,
+    /// Whether distances are reported in map units instead of pixels
+    pub use_map_units: bool,
+    /// Distances above this value are written as NoData
+    pub max_distance: Option<f64>,
+}
+
+impl Default for ProximityOptions {
+    fn default() -> Self {
+        ProximityOptions {
+            target_values: Vec::new(),
+            band: 1,
+            use_map_units: true,
+            max_distance: None,
+        }
+    }
+}
+
+/// Parse a list of target values
+///
+/// Accepts comma separated values and inclusive ranges, e.g. "1,3,10-12"
+/// or "-5,1000-1999"; values may be negative or fractional.
+///
+/// # Arguments
+/// * `values_str` - The string to parse
+///
+/// # Returns
+/// The inclusive ranges of target values or an error
+pub fn parse_target_values(values_str: &str) -> TiffResult<Vec<(f64, f64)>> {
+    let mut values = Vec::new();
+
+    for part in values_str.split(',').map(str::trim).filter(|p| !p.is_empty()) {
+        let parse = |s: &str| s.trim().parse::<f64>().ok().filter(|value| value.is_finite())
+            .ok_or_else(|| TiffError::GenericError(format!("Invalid target value '{}'", s)));
+
+        // A leading minus sign belongs to the first value, not to a range
+        match part.char_indices().skip(1).find(|&(_, c)| c == '-') {
+            Some((split, _)) => {
+                let (start, end) = (parse(&part[..split])?, parse(&part[split + 1..])?);
+                if start > end {
+                    return Err(TiffError::GenericError(
+                        format!("Invalid target range '{}': start is greater than end", part)));
+                }
+                values.push((start, end));
+            },
+            None => {
+                let value = parse(part)?;
+                values.push((value, value));
+            },
+        }
+    }
+
+    Ok(values)
+}
+
+/// Compute the squared distance transform of a 1-D sampled function
+///
+/// Implements the lower envelope of parabolas algorithm by Felzenszwalb and
+/// Huttenlocher, which gives exact results in linear time. `spacing` is the
+/// distance between two neighbouring samples.
+///
+/// # Arguments
+/// * `f` - Input squared distances
+/// * `spacing` - Distance between neighbouring samples
+///
+/// # Returns
+/// Squared distances after the transform
+fn distance_transform_1d(f: &[f64], spacing: f64) -> Vec<f64> {
+    let n = f.len();
+    let mut d = vec![0.0; n];
+    if n == 0 {
+        return d;
+    }
+
+    let s2 = spacing * spacing;
+    let mut v = vec![0usize; n];      // Locations of parabolas in the envelope
+    let mut z = vec![0.0f64; n + 1];  // Boundaries between parabolas
+    let mut k = 0usize;
+
+    z[0] = f64::NEG_INFINITY;
+    z[1] = f64::INFINITY;
+
+    // Intersection of the parabolas rooted at q and p
+    let intersect = |q: usize, p: usize| {
+        ((f[q] + s2 * (q * q) as f64) - (f[p] + s2 * (p * p) as f64))
+            / (2.0 * s2 * (q as f64 - p as f64))
+    };
+
+    for q in 1..n {
+        let mut s = intersect(q, v[k]);
+        while s <= z[k] {
+            k -= 1;
+            s = intersect(q, v[k]);
+        }
+        k += 1;
+        v[k] = q;
+        z[k] = s;
+        z[k + 1] = f64::INFINITY;
+    }
+
+    k = 0;
+    for (q, out) in d.iter_mut().enumerate() {
+        while z[k + 1] < q as f64 {
+            k += 1;
+        }
+        let offset = (q as f64 - v[k] as f64) * spacing;
+        *out = offset * offset + f[v[k]];
+    }
+
+    d
+}
+
+/// Compute a proximity raster
+///
+/// Runs an exact Euclidean distance transform over the raster. Pixel sizes
+/// may differ in X and Y, which is common for geographic rasters.
+///
+/// # Arguments
+/// * `values` - Source pixel values in row-major order, NaN where there is no data
+/// * `width` - Raster width in pixels
+/// * `height` - Raster height in pixels
+/// * `pixel_size` - Pixel size (x, y); use (1.0, 1.0) for pixel distances
+/// * `options` - Proximity options
+///
+/// # Returns
+/// Distance for every pixel, with NoData beyond the maximum distance
+pub fn compute_proximity(
+    values: &[f64],
+    width: u32,
+    height: u32,
+    pixel_size: (f64, f64),
+    options: &ProximityOptions
+) -> Vec<f32> {
+    let (w, h) = (width as usize, height as usize);
+
+    // NoData is never a target, even among every non-zero pixel
+    let is_target = |value: f64| !value.is_nan() && match options.target_values.is_empty() {
+        true => value != 0.0,
+        false => options.target_values.iter().any(|&(start, end)| (start..=end).contains(&value)),
+    };
+
+    // Seed: zero distance on target pixels, "infinity" elsewhere
+    let mut grid: Vec<f64> = values.iter()
+        .map(|&v| if is_target(v) { 0.0 } else { FAR_AWAY })
+        .collect();
+
+    let target_count = grid.iter().filter(|&&d| d == 0.0).count();
+    info!("Proximity: {} target pixels out of {}", target_count, grid.len());
+
+    if target_count == 0 {
+        warn!("No target pixels found, the proximity raster will be entirely NoData");
+        return vec![PROXIMITY_NODATA; grid.len()];
+    }
+
+    let (size_x, size_y) = (pixel_size.0.abs(), pixel_size.1.abs());
+
+    // Pass 1: columns
+    let mut column = vec![0.0; h];
+    for x in 0..w {
+        for (y, value) in column.iter_mut().enumerate() {
+            *value = grid[y * w + x];
+        }
+        for (y, value) in distance_transform_1d(&column, size_y).into_iter().enumerate() {
+            grid[y * w + x] = value;
+        }
+    }
+
+    // Pass 2: rows
+    for y in 0..h {
+        let row = &mut grid[y * w..(y + 1) * w];
+        let transformed = distance_transform_1d(row, size_x);
+        row.copy_from_slice(&transformed);
+    }
+
+    // Convert squared distances and apply the distance cut-off
+    grid.iter()
+        .map(|&d2| {
+            let distance = d2.sqrt();
+            match options.max_distance {
+                Some(max) if distance > max => PROXIMITY_NODATA,
+                _ if d2 >= FAR_AWAY => PROXIMITY_NODATA,
+                _ => distance as f32,
+            }
+        })
+        .collect()
+}
+
+/// Compute a proximity raster for a file and save it as a GeoTIFF
+///
+/// # Arguments
+/// * `input_path` - Source raster
+/// * `output_path` - Float32 GeoTIFF to write
+/// * `options` - Proximity options
+/// * `logger` - Logger for recording operations
+///
+/// # Returns
+/// Result indicating success or an error
+pub fn create_proximity_raster(
+    input_path: &str,
+    output_path: &str,
+    options: &ProximityOptions,
+    logger: &Logger
+) -> TiffResult<()> {
+    info!("Computing proximity raster for {} -> {}", input_path, output_path);
+
+    let mut reader = TiffReader::new(logger);
+    let tiff = reader.load(input_path)?;
+    let ifd = tiff.main_ifd()
+        .ok_or_else(|| TiffError::GenericError("No IFDs found in TIFF file".to_string()))?;
+
+    // Read the stored values of the band, NoData as NaN
+    let (mut bands, layout) = sample_utils::read_bands(input_path, options.band, logger)?;
+    let mut values = match options.band {
+        band if band >= 1 && band <= bands.len() => bands.swap_remove(band - 1),
+        band => return Err(TiffError::GenericError(format!(
+            "{} has {} band(s), there is no band {}", input_path, layout.bands, band))),
+    };
+    if ifd.has_tag(tags::GDAL_NODATA) {
+        if let Ok(nodata) = tiff_extraction_utils::extract_nodata_value(ifd, &reader).trim().parse::<f64>() {
+            values.iter_mut().filter(|value| **value == nodata).for_each(|value| *value = f64::NAN);
+        }
+    }
+    let (width, height) = (layout.width, layout.height);
+
+    // Determine the pixel size for map-unit distances
+    let pixel_size = if options.use_map_units {
+        let file_path = reader.get_file_path().unwrap_or(input_path);
+        let (pixel_scale, _) = tiff_extraction_utils::read_geotiff_info(ifd, &reader, file_path);
+        (pixel_scale[0], pixel_scale[1])
+    } else {
+        (1.0, 1.0)
+    };
+
+    info!("Using pixel size {:?} ({})", pixel_size,
+          if options.use_map_units { "map units" } else { "pixels" });
+
+    let distances = compute_proximity(&values, width, height, pixel_size, options);
+
+    tiff_extraction_utils::save_float32_tiff(
+        &distances, width, height, output_path, input_path,
+        &PROXIMITY_NODATA.to_string(), logger)?;
+
+    info!("Proximity raster saved to {}", output_path);
+    Ok(())
+}
@@ -0,0 +1,207 @@
+//! QA band decoding utilities
+//!
+//! Quality assessment bands such as Landsat's QA_PIXEL pack several flags
+//! into the bits of each sample. This module reads a bit-definition file
+//! naming those flags, reads the QA band with its full sample width and
+//! writes one 0/1 raster per flag.
+//!
+//! A bit-definition file has one flag per line, `name = bits` where bits
+//! is a single bit (`3`) or an inclusive range (`8-9`). A flag is set
+//! where its bits are not all zero, or, with `name = bits : value`, where
+//! the bits hold exactly that value. `#` starts a comment.
+
+use std::fs;
+use std::path::Path;
+
+use image::GrayImage;
+use log::{debug, info};
+
+use crate::tiff::errors::{TiffError, TiffResult};
+use crate::utils::logger::Logger;
+use crate::utils::sample_utils::{self, SampleFormat};
+use crate::utils::tiff_extraction_utils;
+
+/// NoData value recorded in flag rasters, which only hold 0 and 1
+const FLAG_NODATA: &str = "255";
+
+/// A flag packed into the bits of a QA band
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct BitFlag {
+    /// Name of the flag, used in output file names
+    pub name: String,
+    /// Lowest bit of the flag, 0 being the least significant
+    pub first_bit: u32,
+    /// Highest bit of the flag
+    pub last_bit: u32,
+    /// Value the bits must hold for the flag to be set, None for any non-zero value
+    pub value: Option<u32>,
+}
+
+impl BitFlag {
+    /// Value of the flag's bits in a sample
+    pub fn field(&self, sample: u32) -> u32 {
+        let width = self.last_bit - self.first_bit + 1;
+        let mask = if width >= 32 { u32::MAX } else { (1u32 << width) - 1 };
+        (sample >> self.first_bit) & mask
+    }
+
+    /// Whether the flag is set in a sample
+    pub fn is_set(&self, sample: u32) -> bool {
+        match self.value {
+            Some(value) => self.field(sample) == value,
+            None => self.field(sample) != 0,
+        }
+    }
+}
+
+/// Parse bit definitions
+///
+/// # Arguments
+/// * `text` - Content of a bit-definition file
+///
+/// # Returns
+/// The flags in file order, or an error naming the offending line
+pub fn parse_bit_definitions(text: &str) -> TiffResult<Vec<BitFlag>> {
+    let mut flags: Vec<BitFlag> = Vec::new();
+
+    for (number, line) in text.lines().enumerate() {
+        let line = line.split('#').next().unwrap_or("").trim();
+        if line.is_empty() {
+            continue;
+        }
+        let invalid = |reason: &str| TiffError::GenericError(format!(
+            "Invalid bit definition on line {}: '{}' ({})", number + 1, line, reason));
+
+        let (name, definition) = line.split_once('=').ok_or_else(|| invalid("expected name = bits"))?;
+        let name = name.trim();
+        if name.is_empty() || !name.chars().all(|c| c.is_ascii_alphanumeric() || c == '_' || c == '-') {
+            return Err(invalid("names may only use letters, digits, '_' and '-'"));
+        }
+        if flags.iter().any(|flag| flag.name == name) {
+            return Err(invalid("duplicate name"));
+        }
+
+        let (bits, value) = match definition.split_once(':') {
+            Some((bits, value)) => (bits, Some(value.trim().parse::<u32>().map_err(|_| invalid("bad value"))?)),
+            None => (definition, None),
+        };
+        let parse_bit = |bit: &str| bit.trim().parse::<u32>().ok().filter(|&bit| bit < 32);
+        let (first_bit, last_bit) = match bits.split_once('-') {
+            Some((first, last)) => (parse_bit(first), parse_bit(last)),
+            None => (parse_bit(bits), parse_bit(bits)),
+        };
+        let (first_bit, last_bit) = first_bit.zip(last_bit)
+            .filter(|(first, last)| first <= last)
+            .ok_or_else(|| invalid("bits must be 0-31, ranges written low-high"))?;
+
+        let flag = BitFlag { name: name.to_string(), first_bit, last_bit, value };
+        if let Some(value) = value {
+            if flag.field(value << first_bit) != value {
+                return Err(invalid("value does not fit in the bits"));
+            }
+        }
+        debug!("Bit flag {}: bits {}-{}, value {:?}", flag.name, first_bit, last_bit, value);
+        flags.push(flag);
+    }
+
+    if flags.is_empty() {
+        return Err(TiffError::GenericError("The bit-definition file defines no flags".to_string()));
+    }
+    Ok(flags)
+}
+
+/// Read a bit-definition file
+///
+/// # Arguments
+/// * `path` - Path to the file
+///
+/// # Returns
+/// The flags it defines
+pub fn read_bit_definitions(path: &str) -> TiffResult<Vec<BitFlag>> {
+    let text = fs::read_to_string(path)
+        .map_err(|e| TiffError::GenericError(format!("Cannot read bit definitions {}: {}", path, e)))?;
+    parse_bit_definitions(&text)
+}
+
+/// Read the first band of a TIFF with its full sample width
+///
+/// # Arguments
+/// * `input_path` - Path to the TIFF
+/// * `logger` - Logger for recording operations
+///
+/// # Returns
+/// The samples in row-major order with the width and height
+pub fn read_band_samples(input_path: &str, logger: &Logger) -> TiffResult<(Vec<u32>, u32, u32)> {
+    let mut samples = Vec::new();
+    let layout = sample_utils::read_blocks(
+        input_path, logger,
+        |layout, index| !layout.planar || index < layout.blocks_per_band(),
+        |layout, block| {
+            if samples.is_empty() {
+                samples = vec![0u32; layout.width as usize * layout.height as usize];
+            }
+            for row in 0..block.height {
+                for column in 0..block.width {
+                    let index = (block.y + row) as usize * layout.width as usize + (block.x + column) as usize;
+                    samples[index] = block.sample(column, row, 0).unwrap_or(0) as u32;
+                }
+            }
+        })?;
+
+    if layout.format == SampleFormat::Float {
+        return Err(TiffError::GenericError("QA bands must hold integers, not floating point samples".to_string()));
+    }
+    if layout.bands > 1 {
+        info!("{} has {} bands, decoding the first", input_path, layout.bands);
+    }
+    samples.resize(layout.width as usize * layout.height as usize, 0);
+
+    Ok((samples, layout.width, layout.height))
+}
+
+/// Path of the raster written for a flag
+///
+/// # Arguments
+/// * `output_path` - Output path given by the user
+/// * `flag` - The flag
+///
+/// # Returns
+/// The output path with `_<flag name>` appended to its stem
+pub fn flag_output_path(output_path: &str, flag: &BitFlag) -> String {
+    let path = Path::new(output_path);
+    let stem = path.file_stem().and_then(|stem| stem.to_str()).unwrap_or("qa");
+    let extension = path.extension().and_then(|extension| extension.to_str()).unwrap_or("tif");
+    path.with_file_name(format!("{}_{}.{}", stem, flag.name, extension))
+        .to_string_lossy()
+        .into_owned()
+}
+
+/// Decode the flags of a QA band into one 0/1 GeoTIFF each
+///
+/// # Arguments
+/// * `input_path` - QA band
+/// * `output_path` - Output path, suffixed with each flag's name
+/// * `flags` - Flags to decode
+/// * `logger` - Logger for recording operations
+///
+/// # Returns
+/// The paths of the written rasters
+pub fn decode_flags(input_path: &str, output_path: &str, flags: &[BitFlag],
+                    logger: &Logger) -> TiffResult<Vec<String>> {
+    let (samples, width, height) = read_band_samples(input_path, logger)?;
+
+    let mut written = Vec::new();
+    for flag in flags {
+        let data: Vec<u8> = samples.iter().map(|&sample| flag.is_set(sample) as u8).collect();
+        let count = data.iter().filter(|&&value| value == 1).count();
+        let image = GrayImage::from_raw(width, height, data)
+            .ok_or_else(|| TiffError::GenericError("Flag raster does not fit the image".to_string()))?;
+
+        let path = flag_output_path(output_path, flag);
+        tiff_extraction_utils::save_grayscale_tiff(&image, &path, input_path, FLAG_NODATA, logger)?;
+        info!("Flag {} is set on {} of {} pixels, written to {}", flag.name, count, samples.len(), path);
+        written.push(path);
+    }
+
+    Ok(written)
+}
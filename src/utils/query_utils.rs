@@ -0,0 +1,352 @@
+//! Catalog query expressions
+//!
+//! Parses the `--where` expressions that select rasters from a catalog
+//! scan, such as `epsg=3857 && compression=zstd && width>10000`.
+//! Comparisons join with `&&` and `||`, negate with `!` and group with
+//! parentheses; `&&` binds tighter than `||`. Text compares without
+//! regard to case and `~` tests whether it contains the value. A date
+//! without a time of day stands for the whole day, so `acquired=2023-06-14`
+//! matches any time on that day. A comparison against a field the raster
+//! does not record, such as the EPSG code of an unreferenced image, is
+//! false whatever the operator.
+
+use std::fmt;
+
+use crate::tiff::errors::{TiffError, TiffResult};
+use crate::utils::acquisition_utils::Timestamp;
+use crate::utils::catalog_utils::RasterRecord;
+
+/// What a field holds
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+enum FieldKind {
+    Number,
+    Text,
+    Time,
+}
+
+/// Fields a query can compare, with what they hold
+const FIELDS: [(&str, FieldKind); 15] = [
+    ("path", FieldKind::Text),
+    ("name", FieldKind::Text),
+    ("width", FieldKind::Number),
+    ("height", FieldKind::Number),
+    ("bands", FieldKind::Number),
+    ("type", FieldKind::Text),
+    ("compression", FieldKind::Text),
+    ("epsg", FieldKind::Number),
+    ("resolution", FieldKind::Number),
+    ("nodata", FieldKind::Number),
+    ("overviews", FieldKind::Number),
+    ("tiled", FieldKind::Text),
+    ("bigtiff", FieldKind::Text),
+    ("size", FieldKind::Number),
+    ("acquired", FieldKind::Time),
+];
+
+/// Comparison operators
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Operator {
+    /// `=` or `==`
+    Equal,
+    /// `!=`
+    NotEqual,
+    /// `<`
+    Less,
+    /// `<=`
+    LessOrEqual,
+    /// `>`
+    Greater,
+    /// `>=`
+    GreaterOrEqual,
+    /// `~`, text containing the value
+    Contains,
+}
+
+impl fmt::Display for Operator {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.write_str(match self {
+            Operator::Equal => "=",
+            Operator::NotEqual => "!=",
+            Operator::Less => "<",
+            Operator::LessOrEqual => "<=",
+            Operator::Greater => ">",
+            Operator::GreaterOrEqual => ">=",
+            Operator::Contains => "~",
+        })
+    }
+}
+
+/// Value a field is compared with
+#[derive(Debug, Clone, PartialEq)]
+pub enum Value {
+    /// Number, for sizes, codes and resolutions
+    Number(f64),
+    /// Text in lower case
+    Text(String),
+    /// First and last second the value stands for
+    Time(Timestamp, Timestamp),
+}
+
+/// A parsed query expression
+#[derive(Debug, Clone, PartialEq)]
+pub enum Query {
+    /// Both sides hold
+    And(Box<Query>, Box<Query>),
+    /// Either side holds
+    Or(Box<Query>, Box<Query>),
+    /// The inner query does not hold
+    Not(Box<Query>),
+    /// A field compared with a value
+    Compare {
+        /// Field name, one of the supported fields
+        field: &'static str,
+        /// How the field is compared
+        operator: Operator,
+        /// Value on the right-hand side
+        value: Value,
+    },
+}
+
+/// Pieces of an expression
+#[derive(Debug, Clone, PartialEq)]
+enum Token {
+    And,
+    Or,
+    Not,
+    Open,
+    Close,
+    Operator(Operator),
+    Word(String),
+}
+
+/// Split an expression into tokens
+fn tokenize(text: &str) -> TiffResult<Vec<Token>> {
+    let mut tokens = Vec::new();
+    let mut chars = text.chars().peekable();
+    while let Some(&next) = chars.peek() {
+        let token = match next {
+            c if c.is_whitespace() => {
+                chars.next();
+                continue;
+            },
+            '&' | '|' => {
+                chars.next();
+                if chars.next() != Some(next) {
+                    return Err(TiffError::GenericError(format!(
+                        "Expected '{}{}' in query '{}'", next, next, text)));
+                }
+                if next == '&' { Token::And } else { Token::Or }
+            },
+            '(' | ')' => {
+                chars.next();
+                if next == '(' { Token::Open } else { Token::Close }
+            },
+            '=' | '!' | '<' | '>' | '~' => {
+                chars.next();
+                let equals = chars.next_if_eq(&'=').is_some();
+                match (next, equals) {
+                    ('=', _) => Token::Operator(Operator::Equal),
+                    ('!', true) => Token::Operator(Operator::NotEqual),
+                    ('!', false) => Token::Not,
+                    ('<', true) => Token::Operator(Operator::LessOrEqual),
+                    ('<', false) => Token::Operator(Operator::Less),
+                    ('>', true) => Token::Operator(Operator::GreaterOrEqual),
+                    ('>', false) => Token::Operator(Operator::Greater),
+                    _ => Token::Operator(Operator::Contains),
+                }
+            },
+            '"' | '\'' => {
+                chars.next();
+                let word: String = chars.by_ref().take_while(|&c| c != next).collect();
+                Token::Word(word)
+            },
+            _ => {
+                let mut word = String::new();
+                while let Some(c) = chars.next_if(|c| !c.is_whitespace() && !"&|()=!<>~\"'".contains(*c)) {
+                    word.push(c);
+                }
+                Token::Word(word)
+            },
+        };
+        tokens.push(token);
+    }
+    Ok(tokens)
+}
+
+/// Recursive descent over the tokens of one expression
+struct Parser<'a> {
+    tokens: Vec<Token>,
+    position: usize,
+    text: &'a str,
+}
+
+impl Parser<'_> {
+    fn error(&self, message: &str) -> TiffError {
+        TiffError::GenericError(format!("{} in query '{}'", message, self.text))
+    }
+
+    fn next_if(&mut self, token: &Token) -> bool {
+        let found = self.tokens.get(self.position) == Some(token);
+        if found {
+            self.position += 1;
+        }
+        found
+    }
+
+    fn or(&mut self) -> TiffResult<Query> {
+        let mut query = self.and()?;
+        while self.next_if(&Token::Or) {
+            query = Query::Or(Box::new(query), Box::new(self.and()?));
+        }
+        Ok(query)
+    }
+
+    fn and(&mut self) -> TiffResult<Query> {
+        let mut query = self.unary()?;
+        while self.next_if(&Token::And) {
+            query = Query::And(Box::new(query), Box::new(self.unary()?));
+        }
+        Ok(query)
+    }
+
+    fn unary(&mut self) -> TiffResult<Query> {
+        if self.next_if(&Token::Not) {
+            return Ok(Query::Not(Box::new(self.unary()?)));
+        }
+        if self.next_if(&Token::Open) {
+            let query = self.or()?;
+            if !self.next_if(&Token::Close) {
+                return Err(self.error("Missing ')'"));
+            }
+            return Ok(query);
+        }
+        self.comparison()
+    }
+
+    fn comparison(&mut self) -> TiffResult<Query> {
+        let tokens = self.tokens.get(self.position..self.position + 3).map(|tokens| tokens.to_vec());
+        let Some([Token::Word(name), Token::Operator(operator), Token::Word(text)]) = tokens.as_deref() else {
+            return Err(self.error("Expected a comparison such as width>10000"));
+        };
+        self.position += 3;
+
+        let (field, kind) = FIELDS.iter().find(|(field, _)| field.eq_ignore_ascii_case(name)).copied()
+            .ok_or_else(|| self.error(&format!("Unknown field '{}' (supported: {})", name,
+                FIELDS.iter().map(|(field, _)| *field).collect::<Vec<_>>().join(", "))))?;
+        let operator = *operator;
+        let value = match kind {
+            FieldKind::Number => text.parse::<f64>().map(Value::Number)
+                .map_err(|_| self.error(&format!("'{}' compares with numbers, not '{}'", field, text)))?,
+            FieldKind::Text => Value::Text(text.to_lowercase()),
+            FieldKind::Time => {
+                let time = Timestamp::parse(text)
+                    .ok_or_else(|| self.error(&format!("Invalid date '{}'", text)))?;
+                // A date alone stands for its whole day
+                Value::Time(time, if text.trim().len() <= 10 { time.end_of_day() } else { time })
+            },
+        };
+        let supported = match kind {
+            FieldKind::Text => matches!(operator, Operator::Equal | Operator::NotEqual | Operator::Contains),
+            _ => operator != Operator::Contains,
+        };
+        if !supported {
+            return Err(self.error(&format!("'{}' cannot be compared with {}", field, operator)));
+        }
+        Ok(Query::Compare { field, operator, value })
+    }
+}
+
+impl Query {
+    /// Parse a query expression
+    ///
+    /// # Arguments
+    /// * `text` - Expression such as `epsg=3857 && width>10000`
+    ///
+    /// # Returns
+    /// The query, or an error naming what could not be parsed
+    pub fn parse(text: &str) -> TiffResult<Self> {
+        let mut parser = Parser { tokens: tokenize(text)?, position: 0, text };
+        let query = parser.or()?;
+        if parser.position < parser.tokens.len() {
+            return Err(parser.error("Unexpected text after the expression"));
+        }
+        Ok(query)
+    }
+
+    /// Check whether a raster satisfies the query
+    pub fn matches(&self, record: &RasterRecord) -> bool {
+        match self {
+            Query::And(left, right) => left.matches(record) && right.matches(record),
+            Query::Or(left, right) => left.matches(record) || right.matches(record),
+            Query::Not(inner) => !inner.matches(record),
+            Query::Compare { field, operator, value } => compare(record, field, *operator, value),
+        }
+    }
+}
+
+/// Evaluate one comparison against a record
+fn compare(record: &RasterRecord, field: &str, operator: Operator, value: &Value) -> bool {
+    match value {
+        Value::Number(wanted) => {
+            let Some(actual) = number_field(record, field) else { return false };
+            match operator {
+                Operator::Equal => actual == *wanted,
+                Operator::NotEqual => actual != *wanted,
+                Operator::Less => actual < *wanted,
+                Operator::LessOrEqual => actual <= *wanted,
+                Operator::Greater => actual > *wanted,
+                Operator::GreaterOrEqual => actual >= *wanted,
+                Operator::Contains => false,
+            }
+        },
+        Value::Text(wanted) => {
+            let actual = text_field(record, field).to_lowercase();
+            match operator {
+                Operator::Equal => actual == *wanted,
+                Operator::NotEqual => actual != *wanted,
+                Operator::Contains => actual.contains(wanted.as_str()),
+                _ => false,
+            }
+        },
+        Value::Time(first, last) => {
+            let Some(actual) = record.acquired else { return false };
+            match operator {
+                Operator::Equal => actual >= *first && actual <= *last,
+                Operator::NotEqual => actual < *first || actual > *last,
+                Operator::Less => actual < *first,
+                Operator::LessOrEqual => actual <= *last,
+                Operator::Greater => actual > *last,
+                Operator::GreaterOrEqual => actual >= *first,
+                Operator::Contains => false,
+            }
+        },
+    }
+}
+
+/// A numeric field of a record, None when the raster lacks it
+fn number_field(record: &RasterRecord, field: &str) -> Option<f64> {
+    match field {
+        "width" => Some(record.width as f64),
+        "height" => Some(record.height as f64),
+        "bands" => Some(record.bands as f64),
+        "epsg" => record.epsg.map(|epsg| epsg as f64),
+        "resolution" => record.resolution,
+        "nodata" => record.nodata,
+        "overviews" => Some(record.overviews as f64),
+        "size" => record.file_size.map(|size| size as f64),
+        _ => None,
+    }
+}
+
+/// A text field of a record
+fn text_field(record: &RasterRecord, field: &str) -> String {
+    match field {
+        "path" => record.path.clone(),
+        "name" => record.path.rsplit(['/', '\\']).next().unwrap_or_default().to_string(),
+        "type" => record.data_type.to_string(),
+        "compression" => record.compression.clone(),
+        "tiled" => record.tiled.to_string(),
+        "bigtiff" => record.big_tiff.to_string(),
+        _ => String::new(),
+    }
+}
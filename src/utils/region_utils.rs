@@ -0,0 +1,161 @@
+//! Region list utilities
+//!
+//! Support for extracting many regions in one invocation: reading region
+//! lists from files, numbering the outputs and filling in output naming
+//! patterns.
+
+use std::fs;
+use std::path::Path;
+
+use log::info;
+
+use crate::tiff::errors::{TiffError, TiffResult};
+
+/// One region of a multi-region extraction
+#[derive(Debug, Clone, PartialEq)]
+pub enum RegionSpec {
+    /// Bounding box string "minx,miny,maxx,maxy"
+    Bbox(String),
+    /// Coordinate string "x,y", sized by --radius
+    Coordinate(String),
+    /// Geohash or H3 cell ID, extracted as its WGS84 bounding box
+    Cell(String),
+}
+
+/// Read a regions file
+///
+/// Each non-empty line holds one region: four numbers for a bounding box
+/// (minx,miny,maxx,maxy), two for a coordinate (x,y), or a geohash or H3
+/// cell ID. Numbers may be separated by commas or whitespace; lines
+/// starting with `#` are comments.
+///
+/// # Arguments
+/// * `path` - Path to the regions file
+///
+/// # Returns
+/// The regions in file order, or an error naming the first bad line
+pub fn read_regions_file(path: &str) -> TiffResult<Vec<RegionSpec>> {
+    let content = fs::read_to_string(path)
+        .map_err(|e| TiffError::GenericError(format!("Failed to read regions file {}: {}", path, e)))?;
+
+    let mut regions = Vec::new();
+    for (line_number, line) in content.lines().enumerate() {
+        let line = line.trim();
+        if line.is_empty() || line.starts_with('#') {
+            continue;
+        }
+
+        let values: Vec<&str> = line.split(|c: char| c == ',' || c.is_whitespace())
+            .filter(|value| !value.is_empty())
+            .collect();
+        if let [cell] = values.as_slice() {
+            if cell.parse::<f64>().is_err() {
+                regions.push(RegionSpec::Cell(cell.to_string()));
+                continue;
+            }
+        }
+        if values.iter().any(|value| value.parse::<f64>().is_err()) {
+            return Err(TiffError::GenericError(format!(
+                "{}:{}: expected numbers, got '{}'", path, line_number + 1, line)));
+        }
+
+        match values.len() {
+            4 => regions.push(RegionSpec::Bbox(values.join(","))),
+            2 => regions.push(RegionSpec::Coordinate(values.join(","))),
+            n => return Err(TiffError::GenericError(format!(
+                "{}:{}: expected 4 values (bbox) or 2 (coordinate), got {}", path, line_number + 1, n))),
+        }
+    }
+
+    info!("Read {} regions from {}", regions.len(), path);
+    Ok(regions)
+}
+
+/// Output path for one region of a multi-region extraction
+///
+/// Inserts a 1-based, zero-padded region number before the extension, so
+/// `out.tif` becomes `out_01.tif`, `out_02.tif`, ... for ten or more regions.
+///
+/// # Arguments
+/// * `output` - Output path given on the command line
+/// * `index` - Zero-based region index
+/// * `total` - Number of regions
+///
+/// # Returns
+/// The numbered output path
+pub fn numbered_output_path(output: &str, index: usize, total: usize) -> String {
+    let width = total.to_string().len();
+    let path = Path::new(output);
+    let stem = path.file_stem().and_then(|s| s.to_str()).unwrap_or("output");
+    let name = match path.extension().and_then(|e| e.to_str()) {
+        Some(extension) => format!("{}_{:0width$}.{}", stem, index + 1, extension, width = width),
+        None => format!("{}_{:0width$}", stem, index + 1, width = width),
+    };
+    path.with_file_name(name).to_string_lossy().into_owned()
+}
+
+/// Tokens understood by output naming patterns
+pub const PATTERN_TOKENS: [&str; 10] = ["stem", "index", "minx", "miny", "maxx", "maxy", "x", "y", "crs", "date"];
+
+/// Check an output naming pattern
+///
+/// # Arguments
+/// * `pattern` - Pattern such as "{stem}_{minx}_{miny}.tif"
+///
+/// # Returns
+/// An error naming the first unknown or unterminated token
+pub fn validate_output_pattern(pattern: &str) -> TiffResult<()> {
+    for token in pattern_tokens(pattern)? {
+        if !PATTERN_TOKENS.contains(&token) {
+            return Err(TiffError::GenericError(format!(
+                "Unknown token {{{}}} in output pattern (known: {})", token, PATTERN_TOKENS.join(", "))));
+        }
+    }
+    Ok(())
+}
+
+/// Fill in an output naming pattern
+///
+/// # Arguments
+/// * `pattern` - Validated pattern
+/// * `values` - Token values; tokens without a value are an error
+///
+/// # Returns
+/// The output path
+pub fn render_output_pattern(pattern: &str, values: &[(&str, String)]) -> TiffResult<String> {
+    let mut output = pattern.to_string();
+    for token in pattern_tokens(pattern)? {
+        let value = values.iter().find(|(name, _)| *name == token)
+            .map(|(_, value)| value)
+            .ok_or_else(|| TiffError::GenericError(format!(
+                "Output pattern uses {{{}}}, which is not available for this region", token)))?;
+        output = output.replace(&format!("{{{}}}", token), value);
+    }
+    Ok(output)
+}
+
+/// Format a coordinate for a file name
+///
+/// Rounds to six decimals and drops trailing zeros, so 500000.0 becomes
+/// "500000" and 8.25 stays "8.25".
+pub fn format_coordinate(value: f64) -> String {
+    let formatted = format!("{:.6}", value);
+    let trimmed = formatted.trim_end_matches('0').trim_end_matches('.');
+    match trimmed {
+        "-0" => "0".to_string(),
+        _ => trimmed.to_string(),
+    }
+}
+
+/// Names of the `{token}`s in a pattern, in order of appearance
+fn pattern_tokens(pattern: &str) -> TiffResult<Vec<&str>> {
+    let mut tokens = Vec::new();
+    let mut rest = pattern;
+    while let Some(start) = rest.find('{') {
+        let end = rest[start..].find('}').ok_or_else(|| TiffError::GenericError(format!(
+            "Unterminated token in output pattern '{}'", pattern)))?;
+        tokens.push(&rest[start + 1..start + end]);
+        rest = &rest[start + end + 1..];
+    }
+    Ok(tokens)
+}
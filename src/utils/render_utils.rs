@@ -0,0 +1,355 @@
+//! Rendering utilities
+//!
+//! Turns the stored values of a raster into an 8-bit image for viewing,
+//! such as a quicklook. Each band is stretched to 1-255 (0 is kept for
+//! NoData) with a transfer function built from its statistics: a linear
+//! stretch over the full value range, a linear stretch between two
+//! percentiles of the histogram, or histogram equalization.
+
+use image::{DynamicImage, GrayImage, RgbImage};
+use log::info;
+
+use crate::extractor::{OutputFormat, Region};
+use crate::tiff::builder::TiffBuilder;
+use crate::tiff::constants::tags;
+use crate::tiff::errors::{TiffError, TiffResult};
+use crate::tiff::ifd::IFD;
+use crate::tiff::TiffReader;
+use crate::utils::logger::Logger;
+use crate::utils::reference_utils;
+use crate::utils::sample_utils::{self, SampleLayout};
+use crate::utils::statistics_utils::{self, BandReport, Histogram};
+use crate::utils::tiff_extraction_utils;
+
+/// Value written for NoData pixels
+const RENDER_NODATA: &str = "0";
+
+/// How band values are mapped to display levels
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum StretchMode {
+    /// Linear between the band's minimum and maximum
+    Linear,
+    /// Linear between two percentiles, values beyond them saturate
+    PercentClip { low: f64, high: f64 },
+    /// Levels spread so that each holds about as many pixels
+    Equalize,
+}
+
+impl StretchMode {
+    /// Parse a stretch mode name
+    ///
+    /// # Arguments
+    /// * `name` - "linear", "percent-clip" or "equalize" (case-insensitive)
+    /// * `clip` - Lower and upper percentile used by percent-clip
+    ///
+    /// # Returns
+    /// The stretch mode or an error
+    pub fn from_string(name: &str, clip: (f64, f64)) -> TiffResult<Self> {
+        match name.to_lowercase().as_str() {
+            "linear" | "minmax" => Ok(StretchMode::Linear),
+            "percent-clip" | "clip" => {
+                let (low, high) = clip;
+                if !(0.0..high).contains(&low) || high > 100.0 {
+                    return Err(TiffError::GenericError(format!(
+                        "Invalid clip percentiles {}-{} (expected 0 <= low < high <= 100)", low, high)));
+                }
+                Ok(StretchMode::PercentClip { low, high })
+            },
+            "equalize" | "histeq" => Ok(StretchMode::Equalize),
+            _ => Err(TiffError::GenericError(format!(
+                "Unknown stretch '{}' (expected 'linear', 'percent-clip' or 'equalize')", name))),
+        }
+    }
+
+    /// Canonical name of the mode
+    pub fn name(&self) -> &'static str {
+        match self {
+            StretchMode::Linear => "linear",
+            StretchMode::PercentClip { .. } => "percent-clip",
+            StretchMode::Equalize => "equalize",
+        }
+    }
+}
+
+/// Transfer function of one band
+#[derive(Debug, Clone)]
+pub struct Stretch {
+    /// How values are mapped
+    mode: StretchMode,
+    /// Value shown as the darkest level
+    low: f64,
+    /// Value shown as the brightest level
+    high: f64,
+    /// Histogram of the band
+    histogram: Histogram,
+    /// Pixels counted before each bucket
+    cumulative: Vec<u64>,
+}
+
+impl Stretch {
+    /// Build the transfer function of a band
+    ///
+    /// # Arguments
+    /// * `mode` - How values are mapped
+    /// * `report` - Statistics and histogram of the band
+    ///
+    /// # Returns
+    /// The transfer function
+    pub fn new(mode: StretchMode, report: &BandReport) -> Stretch {
+        let histogram = report.histogram.clone();
+        let cumulative: Vec<u64> = histogram.counts.iter()
+            .scan(0u64, |total, &count| {
+                let before = *total;
+                *total += count;
+                Some(before)
+            })
+            .collect();
+
+        let mut stretch = Stretch {
+            mode,
+            low: report.statistics.min,
+            high: report.statistics.max,
+            histogram,
+            cumulative,
+        };
+        if let StretchMode::PercentClip { low, high } = mode {
+            stretch.low = stretch.percentile(low).max(report.statistics.min);
+            stretch.high = stretch.percentile(high).min(report.statistics.max);
+        }
+        stretch
+    }
+
+    /// Pixels in the histogram
+    fn total(&self) -> u64 {
+        self.cumulative.last().copied().unwrap_or(0) + self.histogram.counts.last().copied().unwrap_or(0)
+    }
+
+    /// Width of a histogram bucket
+    fn bucket_width(&self) -> f64 {
+        (self.histogram.max - self.histogram.min) / self.histogram.counts.len().max(1) as f64
+    }
+
+    /// Value below which a share of the pixels lie
+    ///
+    /// Values are assumed to be spread evenly within a bucket.
+    ///
+    /// # Arguments
+    /// * `percent` - Share of the pixels, 0 to 100
+    pub fn percentile(&self, percent: f64) -> f64 {
+        let target = percent / 100.0 * self.total() as f64;
+        let width = self.bucket_width();
+        for (bucket, &count) in self.histogram.counts.iter().enumerate() {
+            let before = self.cumulative[bucket] as f64;
+            if count > 0 && before + count as f64 >= target {
+                let fraction = ((target - before) / count as f64).clamp(0.0, 1.0);
+                return self.histogram.min + width * (bucket as f64 + fraction);
+            }
+        }
+        self.histogram.max
+    }
+
+    /// Display level of a valid value, from 1 to 255
+    pub fn apply(&self, value: f64) -> u8 {
+        let fraction = match self.mode {
+            StretchMode::Equalize => {
+                let width = self.bucket_width();
+                let position = if width > 0.0 { (value - self.histogram.min) / width } else { 0.0 };
+                let last = self.histogram.counts.len() - 1;
+                let bucket = (position.max(0.0) as usize).min(last);
+                let within = (position - bucket as f64).clamp(0.0, 1.0);
+                let below = self.cumulative[bucket] as f64 + self.histogram.counts[bucket] as f64 * within;
+                below / self.total().max(1) as f64
+            },
+            _ if self.high > self.low => (value - self.low) / (self.high - self.low),
+            _ => 0.5,
+        };
+        1 + (fraction.clamp(0.0, 1.0) * 254.0).round() as u8
+    }
+}
+
+/// Save a rendered image
+///
+/// GeoTIFF output takes the georeferencing of a source raster and records
+/// 0 as NoData; other formats are written by extension.
+///
+/// # Arguments
+/// * `image` - Grayscale or RGB image, 0 marking NoData
+/// * `output_path` - Path of the image to write
+/// * `source_path` - Raster on the same pixel grid, for georeferencing
+/// * `quality` - Quality of lossy image formats, if any
+/// * `logger` - Logger for recording operations
+///
+/// # Returns
+/// Result indicating success or an error
+pub fn save_rendered(image: &DynamicImage, output_path: &str, source_path: &str, quality: Option<u8>,
+                     logger: &Logger) -> TiffResult<()> {
+    let format = OutputFormat::resolve(output_path, None).with_quality(quality);
+    if !format.is_tiff() {
+        return format.write_image(image, output_path);
+    }
+    if let DynamicImage::ImageLuma8(gray) = image {
+        return tiff_extraction_utils::save_grayscale_tiff(gray, output_path, source_path, RENDER_NODATA, logger);
+    }
+
+    let (width, height) = (image.width(), image.height());
+    let mut builder = TiffBuilder::new(logger, false);
+    let ifd_index = builder.add_ifd(IFD::new(0, 0));
+    builder.add_basic_rgb_tags(ifd_index, width, height);
+    builder.setup_single_strip(ifd_index, image.to_rgb8().into_raw());
+    let region = Region::new(0, 0, width, height);
+    reference_utils::add_georeferencing_to_builder(&mut builder, ifd_index, &region, source_path, logger)?;
+    builder.add_nodata_tag(ifd_index, RENDER_NODATA);
+    builder.write(output_path)
+}
+
+/// Bands rendered: three for RGB, one for grayscale
+fn channels(layout: &SampleLayout) -> usize {
+    if layout.bands >= 3 { 3 } else { 1 }
+}
+
+/// Render a raster to an 8-bit image
+///
+/// Rasters of three or more bands are rendered as RGB from their first
+/// three bands, others as grayscale from the first band.
+///
+/// # Arguments
+/// * `input_path` - Path to the raster
+/// * `output_path` - Path of the image to write
+/// * `mode` - How band values are mapped
+/// * `quality` - Quality of lossy image formats, if any
+/// * `logger` - Logger for recording operations
+///
+/// # Returns
+/// Result indicating success or an error
+pub fn render_file(input_path: &str, output_path: &str, mode: StretchMode, quality: Option<u8>,
+                   logger: &Logger) -> TiffResult<()> {
+    let mut reader = TiffReader::new(logger);
+    let tiff = reader.load(input_path)?;
+    let ifd = tiff.main_ifd()
+        .ok_or_else(|| TiffError::GenericError("No IFDs found in TIFF file".to_string()))?;
+    let nodata = if ifd.has_tag(tags::GDAL_NODATA) {
+        tiff_extraction_utils::extract_nodata_value(ifd, &reader).trim().parse::<f64>().ok()
+    } else {
+        None
+    };
+
+    let reports = statistics_utils::compute_statistics(input_path, nodata, logger)?;
+    let stretches: Vec<Option<Stretch>> = (1..=3)
+        .map(|band| reports.iter().find(|report| report.band == band).map(|report| Stretch::new(mode, report)))
+        .collect();
+    for (band, stretch) in stretches.iter().enumerate() {
+        if let Some(stretch) = stretch {
+            info!("Band {}: {} stretch from {} to {}", band + 1, mode.name(), stretch.low, stretch.high);
+        }
+    }
+
+    let mut levels: Vec<u8> = Vec::new();
+    let layout = sample_utils::read_blocks(
+        input_path, logger,
+        |layout, index| !layout.planar || index / layout.blocks_per_band() < channels(layout),
+        |layout, block| {
+            let channels = channels(layout);
+            if levels.is_empty() {
+                levels = vec![0u8; layout.width as usize * layout.height as usize * channels];
+            }
+            for band in block.bands.clone().filter(|&band| band < channels) {
+                let Some(stretch) = &stretches[band] else {
+                    continue;
+                };
+                for row in 0..block.height {
+                    for column in 0..block.width {
+                        let Some(value) = block.sample(column, row, band).map(|raw| layout.value(raw)) else {
+                            continue;
+                        };
+                        if value.is_nan() || nodata == Some(value) {
+                            continue;
+                        }
+                        let pixel = (block.y + row) as usize * layout.width as usize + (block.x + column) as usize;
+                        levels[pixel * channels + band] = stretch.apply(value);
+                    }
+                }
+            }
+        })?;
+    let channels = channels(&layout);
+    levels.resize(layout.width as usize * layout.height as usize * channels, 0);
+
+    let (width, height) = (layout.width, layout.height);
+    let image = if channels == 3 {
+        RgbImage::from_raw(width, height, levels).map(DynamicImage::ImageRgb8)
+    } else {
+        GrayImage::from_raw(width, height, levels).map(DynamicImage::ImageLuma8)
+    }.ok_or_else(|| TiffError::GenericError("Rendered image does not fit the raster".to_string()))?;
+
+    save_rendered(&image, output_path, input_path, quality, logger)?;
+
+    info!("Rendered {}x{} {} image with the {} stretch to {}", width, height,
+          if channels == 3 { "RGB" } else { "grayscale" }, mode.name(), output_path);
+    Ok(())
+}
+
+/// Render a window of chosen bands to an 8-bit image
+///
+/// Unlike `render_file`, only the window is decoded, and any bands can be
+/// picked. The stretch of each band is still fitted to the statistics of
+/// the whole band, so neighbouring windows render alike.
+///
+/// # Arguments
+/// * `input_path` - Path to the raster
+/// * `region` - Window to render, None for the whole image
+/// * `bands` - One-based bands: one for grayscale, three for RGB, none for
+///   the default of `render_file`
+/// * `mode` - How band values are mapped
+/// * `logger` - Logger for recording operations
+///
+/// # Returns
+/// The rendered image, 0 marking NoData
+pub fn render_window(input_path: &str, region: Option<Region>, bands: &[usize],
+                     mode: StretchMode, logger: &Logger) -> TiffResult<DynamicImage> {
+    let shape = crate::extractor::describe_window(input_path, region, logger)?;
+    let bands: Vec<usize> = match bands.len() {
+        0 if shape.bands >= 3 => vec![1, 2, 3],
+        0 => vec![1],
+        1 | 3 => bands.to_vec(),
+        count => return Err(TiffError::GenericError(format!(
+            "Render one band or three, not {}", count))),
+    };
+    if let Some(&band) = bands.iter().find(|&&band| band == 0 || band > shape.bands) {
+        return Err(TiffError::GenericError(format!(
+            "Band {} does not exist, {} has {} band(s)", band, input_path, shape.bands)));
+    }
+
+    let mut reader = TiffReader::new(logger);
+    let tiff = reader.load(input_path)?;
+    let ifd = tiff.main_ifd()
+        .ok_or_else(|| TiffError::GenericError("No IFDs found in TIFF file".to_string()))?;
+    let nodata = if ifd.has_tag(tags::GDAL_NODATA) {
+        tiff_extraction_utils::extract_nodata_value(ifd, &reader).trim().parse::<f64>().ok()
+    } else {
+        None
+    };
+
+    let reports = statistics_utils::compute_ifd_statistics(&reader, ifd, input_path, nodata)?;
+    let stretches: Vec<Option<Stretch>> = bands.iter()
+        .map(|&band| reports.iter().find(|report| report.band == band).map(|report| Stretch::new(mode, report)))
+        .collect();
+
+    let mut values = vec![f32::NAN; shape.samples()];
+    crate::extractor::decode_into_f32(input_path, region, crate::extractor::BufferLayout::Interleaved, &mut values, logger)?;
+    let levels: Vec<u8> = values.chunks_exact(shape.bands)
+        .flat_map(|pixel| bands.iter().zip(&stretches).map(|(&band, stretch)| {
+            let value = pixel[band - 1] as f64;
+            match stretch {
+                Some(stretch) if !value.is_nan() && nodata != Some(value) => stretch.apply(value),
+                _ => 0,
+            }
+        }))
+        .collect();
+
+    info!("Rendered a {}x{} window of band(s) {:?} with the {} stretch", shape.width, shape.height, bands, mode.name());
+    let image = if bands.len() == 3 {
+        RgbImage::from_raw(shape.width, shape.height, levels).map(DynamicImage::ImageRgb8)
+    } else {
+        GrayImage::from_raw(shape.width, shape.height, levels).map(DynamicImage::ImageLuma8)
+    };
+    image.ok_or_else(|| TiffError::GenericError("Rendered image does not fit the window".to_string()))
+}
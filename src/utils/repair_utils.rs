@@ -0,0 +1,522 @@
+//! Structural repair of damaged TIFF files
+//!
+//! Partially downloaded or otherwise damaged rasters usually keep most of
+//! their structure: the directories are readable, but the next-IFD pointer
+//! of the last complete IFD points past the end of the file, trailing
+//! strips or tiles are cut short, or their data is missing altogether.
+//! This module lists such problems with a suggestion for each, and
+//! rebuilds a clean copy from whatever image data is still readable,
+//! filling the gaps with zeros.
+
+use std::io::{Seek, SeekFrom};
+
+use log::{info, warn};
+
+use crate::compression::CompressionFactory;
+use crate::io::byte_order::ByteOrder;
+use crate::io::seekable::SeekableReader;
+use crate::tiff::builder::TiffBuilder;
+use crate::tiff::constants::{compression, field_types, planar_config, predictor, tags};
+use crate::tiff::errors::{TiffError, TiffResult};
+use crate::tiff::ifd::{IFD, IFDEntry};
+use crate::tiff::validation;
+use crate::tiff::{TiffReader, TIFF};
+use crate::utils::ifd_utils;
+use crate::utils::logger::Logger;
+use crate::utils::simd_utils;
+use crate::utils::tag_utils;
+
+/// Tags that describe the block layout, the data encoding or point at
+/// other parts of the source file, none of which survive a rebuild
+pub(crate) const REBUILT_TAGS: [u16; 12] = [
+    tags::STRIP_OFFSETS, tags::STRIP_BYTE_COUNTS, tags::ROWS_PER_STRIP,
+    tags::TILE_WIDTH, tags::TILE_LENGTH, tags::TILE_OFFSETS, tags::TILE_BYTE_COUNTS,
+    tags::COMPRESSION, tags::PREDICTOR, tags::SUB_IFDS,
+    tags::EXIF_IFD, tags::GPS_IFD,
+];
+
+/// A structural problem found in a file
+#[derive(Debug, Clone, PartialEq)]
+pub(crate) struct RepairIssue {
+    /// Index of the IFD the problem was found in
+    pub ifd: usize,
+    /// What is wrong
+    pub problem: String,
+    /// What a repaired copy does about it
+    pub suggestion: String,
+}
+
+impl RepairIssue {
+    fn new(ifd: usize, problem: String, suggestion: &str) -> Self {
+        RepairIssue { ifd, problem, suggestion: suggestion.to_string() }
+    }
+}
+
+/// Outcome of rebuilding the image data of one IFD
+#[derive(Debug, Clone, PartialEq)]
+pub(crate) struct SalvageResult {
+    /// Index of the IFD in the source file
+    pub ifd: usize,
+    /// Blocks decoded completely
+    pub recovered: usize,
+    /// Blocks only partially present, kept with a zero-filled tail
+    pub partial: usize,
+    /// Blocks the image should have
+    pub total: usize,
+}
+
+/// Strip or tile layout of one IFD
+struct BlockLayout {
+    width: usize,
+    height: usize,
+    block_width: usize,
+    block_height: usize,
+    tiled: bool,
+    samples_per_pixel: usize,
+    bits_per_sample: usize,
+    planes: usize,
+    offsets: Vec<u64>,
+    byte_counts: Option<Vec<u64>>,
+}
+
+impl BlockLayout {
+    /// Read the layout tags of an IFD
+    ///
+    /// Missing byte counts are tolerated here, since they can be rebuilt.
+    fn read(reader: &TiffReader, ifd: &IFD) -> TiffResult<Self> {
+        let (width, height) = ifd.get_dimensions()
+            .ok_or_else(|| TiffError::GenericError("missing image dimensions".to_string()))?;
+        let tiled = ifd.has_tag(tags::TILE_OFFSETS);
+        let (offset_tag, count_tag) = if tiled {
+            (tags::TILE_OFFSETS, tags::TILE_BYTE_COUNTS)
+        } else {
+            (tags::STRIP_OFFSETS, tags::STRIP_BYTE_COUNTS)
+        };
+
+        let offsets = reader.read_tag::<u64>(ifd, offset_tag)
+            .map_err(|e| TiffError::GenericError(format!("unreadable {}: {}", tag_utils::get_tag_name(offset_tag), e)))?;
+        let byte_counts = reader.read_tag::<u64>(ifd, count_tag).ok();
+
+        let (block_width, block_height) = if tiled {
+            let tile_width = ifd.get_tag_value(tags::TILE_WIDTH)
+                .ok_or_else(|| TiffError::GenericError("missing TileWidth".to_string()))?;
+            let tile_length = ifd.get_tag_value(tags::TILE_LENGTH)
+                .ok_or_else(|| TiffError::GenericError("missing TileLength".to_string()))?;
+            (tile_width as usize, tile_length as usize)
+        } else {
+            let rows_per_strip = ifd.get_tag_value(tags::ROWS_PER_STRIP).unwrap_or(height).min(height);
+            (width as usize, rows_per_strip as usize)
+        };
+        if width == 0 || height == 0 || block_width == 0 || block_height == 0 {
+            return Err(TiffError::GenericError("zero image or block dimensions".to_string()));
+        }
+
+        let samples_per_pixel = ifd.get_tag_value(tags::SAMPLES_PER_PIXEL).unwrap_or(1).max(1) as usize;
+        let bits_per_sample = reader.read_tag::<u64>(ifd, tags::BITS_PER_SAMPLE).ok()
+            .and_then(|bits| bits.first().copied())
+            .unwrap_or(1) as usize;
+        let planes = if ifd.get_tag_value(tags::PLANAR_CONFIGURATION) == Some(planar_config::PLANAR as u64) {
+            samples_per_pixel
+        } else {
+            1
+        };
+
+        Ok(BlockLayout {
+            width: width as usize,
+            height: height as usize,
+            block_width,
+            block_height,
+            tiled,
+            samples_per_pixel,
+            bits_per_sample,
+            planes,
+            offsets,
+            byte_counts,
+        })
+    }
+
+    fn block_name(&self) -> &'static str {
+        if self.tiled { "tile" } else { "strip" }
+    }
+
+    fn blocks_across(&self) -> usize {
+        self.width.div_ceil(self.block_width)
+    }
+
+    fn blocks_down(&self) -> usize {
+        self.height.div_ceil(self.block_height)
+    }
+
+    /// Number of blocks the image dimensions call for
+    fn expected_blocks(&self) -> usize {
+        self.blocks_across() * self.blocks_down() * self.planes
+    }
+
+    /// Bits of one pixel within a block
+    fn bits_per_pixel(&self) -> usize {
+        self.bits_per_sample * self.samples_per_pixel / self.planes
+    }
+
+    /// Bytes of one row of a block of the given width
+    fn row_bytes(&self, width: usize) -> usize {
+        (width * self.bits_per_pixel()).div_ceil(8)
+    }
+
+    /// Rows stored in a block; the last strip of an image is usually shorter
+    fn block_rows(&self, index: usize) -> usize {
+        if self.tiled {
+            return self.block_height;
+        }
+        let first_row = (index % self.blocks_down()) * self.block_height;
+        self.block_height.min(self.height.saturating_sub(first_row))
+    }
+
+    /// Decoded size of a block in bytes
+    fn block_size(&self, index: usize) -> usize {
+        self.row_bytes(self.block_width) * self.block_rows(index)
+    }
+
+    /// Stored byte count of a block
+    ///
+    /// Without a byte count tag, uncompressed blocks are assumed to have
+    /// their decoded size and compressed ones to run up to the next block.
+    fn byte_count(&self, index: usize, uncompressed: bool, file_size: u64) -> u64 {
+        if let Some(count) = self.byte_counts.as_ref().and_then(|counts| counts.get(index)) {
+            return *count;
+        }
+        if uncompressed {
+            return self.block_size(index) as u64;
+        }
+        let offset = self.offsets[index];
+        let next = self.offsets.iter().copied().filter(|&o| o > offset).min().unwrap_or(file_size);
+        next.min(file_size).saturating_sub(offset)
+    }
+}
+
+/// Name a list of blocks with their indices as ranges, such as "strips 3, 7-12"
+fn describe_blocks(name: &str, indices: &[usize]) -> String {
+    let mut ranges: Vec<String> = Vec::new();
+    let mut start = 0;
+    while start < indices.len() {
+        let mut end = start;
+        while end + 1 < indices.len() && indices[end + 1] == indices[end] + 1 {
+            end += 1;
+        }
+        if end == start {
+            ranges.push(indices[start].to_string());
+        } else {
+            ranges.push(format!("{}-{}", indices[start], indices[end]));
+        }
+        start = end + 1;
+    }
+    if indices.len() == 1 {
+        format!("{} {}", name, ranges.join(", "))
+    } else {
+        format!("{}s {}", name, ranges.join(", "))
+    }
+}
+
+/// Check the block layout of one IFD against the file size
+fn check_blocks(reader: &TiffReader, ifd: &IFD, file_size: u64) -> Vec<RepairIssue> {
+    let mut issues = Vec::new();
+    let layout = match BlockLayout::read(reader, ifd) {
+        Ok(layout) => layout,
+        Err(e) => {
+            issues.push(RepairIssue::new(ifd.number, format!("no usable image layout ({})", e),
+                                         "the IFD is left out of a repaired copy"));
+            return issues;
+        }
+    };
+    let name = layout.block_name();
+    let uncompressed = ifd.get_tag_value(tags::COMPRESSION).unwrap_or(1) == compression::NONE as u64;
+
+    let expected = layout.expected_blocks();
+    if layout.offsets.len() < expected {
+        issues.push(RepairIssue::new(ifd.number,
+                                     format!("{} {} offsets for an image of {} {}s", layout.offsets.len(), name, expected, name),
+                                     "the missing blocks are filled with zeros"));
+    }
+    match &layout.byte_counts {
+        None => issues.push(RepairIssue::new(ifd.number, format!("{} byte counts are missing or unreadable", name),
+                                             if uncompressed {
+                                                 "byte counts are rebuilt from the block size"
+                                             } else {
+                                                 "byte counts are rebuilt from the distance to the next block"
+                                             })),
+        Some(counts) if counts.len() != layout.offsets.len() => {
+            issues.push(RepairIssue::new(ifd.number,
+                                         format!("{} {} offsets but {} byte counts", layout.offsets.len(), name, counts.len()),
+                                         "blocks without a byte count are rebuilt from their size"));
+        },
+        Some(_) => {},
+    }
+
+    let mut beyond_end = Vec::new();
+    let mut empty = Vec::new();
+    for (index, &offset) in layout.offsets.iter().enumerate().take(expected) {
+        let byte_count = layout.byte_count(index, uncompressed, file_size);
+        if byte_count == 0 {
+            empty.push(index);
+        } else if offset >= file_size {
+            beyond_end.push(index);
+        } else if offset + byte_count > file_size {
+            issues.push(RepairIssue::new(ifd.number,
+                                         format!("{} {} is truncated: {} of {} bytes present",
+                                                 name, index, file_size - offset, byte_count),
+                                         if uncompressed {
+                                             "the present rows are kept and the rest is filled with zeros"
+                                         } else {
+                                             "the block is decoded as far as possible and filled with zeros"
+                                         }));
+        }
+    }
+    if !beyond_end.is_empty() {
+        issues.push(RepairIssue::new(ifd.number,
+                                     format!("data of {} lies beyond the end of the file", describe_blocks(name, &beyond_end)),
+                                     "the missing blocks are filled with zeros"));
+    }
+    if !empty.is_empty() {
+        issues.push(RepairIssue::new(ifd.number, format!("zero byte count for {}", describe_blocks(name, &empty)),
+                                     "the empty blocks are filled with zeros"));
+    }
+
+    issues
+}
+
+/// Check that the next-IFD pointer of the last IFD read ends the chain
+fn check_chain(reader: &TiffReader, tiff: &TIFF, file_size: u64) -> TiffResult<Option<RepairIssue>> {
+    let last = match tiff.ifds.last() {
+        Some(ifd) => ifd,
+        None => return Ok(None),
+    };
+    let handler = reader.get_byte_order_handler()
+        .ok_or_else(|| TiffError::GenericError("Byte order not determined".to_string()))?;
+
+    let offset_size = if tiff.is_big_tiff { 8 } else { 4 };
+    let position = last.offset + ifd_utils::calculate_ifd_size(last, tiff.is_big_tiff) - offset_size;
+    if position + offset_size > file_size {
+        return Ok(Some(RepairIssue::new(last.number,
+                                        format!("IFD is cut off at the end of the file ({} bytes missing)",
+                                                position + offset_size - file_size),
+                                        "the chain is ended after this IFD")));
+    }
+
+    let mut file = reader.create_reader()?;
+    file.seek(SeekFrom::Start(position))?;
+    let next_offset = ifd_utils::read_next_ifd_offset(&mut file, tiff.is_big_tiff, handler)?;
+    if next_offset == 0 {
+        return Ok(None);
+    }
+
+    let problem = if validation::validate_ifd_offset(next_offset, file_size).is_err() {
+        format!("next IFD offset {} points outside the file", next_offset)
+    } else {
+        format!("next IFD at offset {} could not be read", next_offset)
+    };
+    Ok(Some(RepairIssue::new(last.number, problem, "the chain is ended after this IFD")))
+}
+
+/// Find structural problems in a loaded file
+///
+/// # Arguments
+/// * `reader` - Reader the file was loaded with
+/// * `tiff` - The loaded file
+///
+/// # Returns
+/// The problems found, in file order; empty for a sound file
+pub(crate) fn check_file(reader: &TiffReader, tiff: &TIFF) -> TiffResult<Vec<RepairIssue>> {
+    let mut file = reader.create_reader()?;
+    let file_size = validation::get_file_size(&mut file)?;
+    let mut issues = Vec::new();
+
+    for ifd in &tiff.ifds {
+        for entry in &ifd.entries {
+            if let Err(e) = reader.read_tag_value(entry) {
+                issues.push(RepairIssue::new(ifd.number,
+                                             format!("tag {} ({}) is unreadable: {}", entry.tag, tag_utils::get_tag_name(entry.tag), e),
+                                             "the tag is dropped"));
+            }
+        }
+        issues.extend(check_blocks(reader, ifd, file_size));
+    }
+    issues.extend(check_chain(reader, tiff, file_size)?);
+
+    info!("Found {} structural problems", issues.len());
+    Ok(issues)
+}
+
+/// Read the bytes of a block that are present in the file
+fn read_present_bytes(file: &mut dyn SeekableReader, offset: u64, byte_count: u64, file_size: u64) -> TiffResult<Vec<u8>> {
+    if offset >= file_size {
+        return Ok(Vec::new());
+    }
+    let available = byte_count.min(file_size - offset);
+    let mut data = vec![0u8; available as usize];
+    file.seek(SeekFrom::Start(offset))?;
+    file.read_exact(&mut data)?;
+    Ok(data)
+}
+
+/// Undo 8-bit horizontal differencing
+///
+/// # Arguments
+/// * `data` - Decoded block data
+/// * `row_bytes` - Bytes per row of the block
+/// * `stride` - Samples per pixel; each sample is predicted from the
+///   same sample of the previous pixel
+fn undo_horizontal_differencing(data: &mut [u8], row_bytes: usize, stride: usize) {
+    for row in data.chunks_mut(row_bytes) {
+        simd_utils::undo_horizontal_differencing(row, stride);
+    }
+}
+
+/// Rebuild the image data of one IFD as a single chunky raster
+///
+/// Every block is decoded if it can be; truncated uncompressed blocks keep
+/// their present rows, and everything that can't be read stays zero.
+/// Multi-byte samples of big-endian files are swapped to little-endian,
+/// the byte order the builder writes.
+///
+/// # Arguments
+/// * `reader` - Reader the file was loaded with
+/// * `ifd` - The IFD to rebuild
+/// * `file_size` - Size of the source file
+///
+/// # Returns
+/// The raster and the salvage statistics
+pub(crate) fn salvage_image(reader: &TiffReader, ifd: &IFD, file_size: u64) -> TiffResult<(Vec<u8>, SalvageResult)> {
+    let layout = BlockLayout::read(reader, ifd)?;
+    if layout.planes > 1 {
+        return Err(TiffError::GenericError("planar images can't be rebuilt".to_string()));
+    }
+    if layout.tiled && layout.bits_per_pixel() % 8 != 0 {
+        return Err(TiffError::GenericError("tiled images with sub-byte pixels can't be rebuilt".to_string()));
+    }
+    let prediction = ifd.get_tag_value(tags::PREDICTOR).unwrap_or(1);
+    if prediction != predictor::NONE as u64
+        && !(prediction == predictor::HORIZONTAL_DIFFERENCING as u64 && layout.bits_per_sample == 8) {
+        return Err(TiffError::GenericError(format!("predictor {} with {}-bit samples can't be undone",
+                                                   prediction, layout.bits_per_sample)));
+    }
+
+    let compression_code = ifd.get_tag_value(tags::COMPRESSION).unwrap_or(1);
+    let uncompressed = compression_code == compression::NONE as u64;
+    let handler = CompressionFactory::create_handler(compression_code)?;
+
+    let raster_row_bytes = layout.row_bytes(layout.width);
+    let block_row_bytes = layout.row_bytes(layout.block_width);
+    let pixel_bytes = layout.bits_per_pixel() / 8;
+    let mut raster = vec![0u8; raster_row_bytes * layout.height];
+    let mut result = SalvageResult { ifd: ifd.number, recovered: 0, partial: 0, total: layout.expected_blocks() };
+    let mut file = reader.create_reader()?;
+
+    for index in 0..layout.offsets.len().min(result.total) {
+        let byte_count = layout.byte_count(index, uncompressed, file_size);
+        let stored = read_present_bytes(&mut file, layout.offsets[index], byte_count, file_size)?;
+        if stored.is_empty() {
+            continue;
+        }
+        let truncated = (stored.len() as u64) < byte_count;
+
+        let mut block = if uncompressed {
+            stored
+        } else {
+            match handler.decompress(&stored) {
+                Ok(data) => data,
+                Err(e) => {
+                    warn!("Could not decode {} {} of IFD {}: {}", layout.block_name(), index, ifd.number, e);
+                    continue;
+                }
+            }
+        };
+        let block_size = layout.block_size(index);
+        if truncated || block.len() < block_size {
+            result.partial += 1;
+        } else {
+            result.recovered += 1;
+        }
+        block.resize(block_size, 0);
+
+        if prediction == predictor::HORIZONTAL_DIFFERENCING as u64 {
+            undo_horizontal_differencing(&mut block, block_row_bytes, layout.samples_per_pixel);
+        }
+
+        if layout.tiled {
+            let x = (index % layout.blocks_across()) * layout.block_width;
+            let y = (index / layout.blocks_across()) * layout.block_height;
+            let copy_bytes = layout.block_width.min(layout.width - x) * pixel_bytes;
+            let rows = layout.block_height.min(layout.height - y);
+            for row in 0..rows {
+                let source = row * block_row_bytes;
+                let target = (y + row) * raster_row_bytes + x * pixel_bytes;
+                raster[target..target + copy_bytes].copy_from_slice(&block[source..source + copy_bytes]);
+            }
+        } else {
+            let start = index * layout.block_height * raster_row_bytes;
+            raster[start..start + block.len()].copy_from_slice(&block);
+        }
+    }
+
+    let sample_bytes = layout.bits_per_sample / 8;
+    let big_endian = reader.get_byte_order_handler()
+        .is_some_and(|handler| handler.byte_order() == ByteOrder::BigEndian);
+    if big_endian && sample_bytes > 1 && layout.bits_per_sample % 8 == 0 {
+        raster.chunks_exact_mut(sample_bytes).for_each(|sample| sample.reverse());
+    }
+
+    info!("IFD {}: recovered {} of {} {}s, {} partially",
+          ifd.number, result.recovered, result.total, layout.block_name(), result.partial);
+    Ok((raster, result))
+}
+
+/// Write a repaired copy of a file
+///
+/// Each IFD whose image can be rebuilt is written with its image data as
+/// a single uncompressed strip, keeping all readable tags; IFDs that
+/// can't be rebuilt are left out. The IFD chain of the copy is always
+/// well formed.
+///
+/// # Arguments
+/// * `reader` - Reader the file was loaded with
+/// * `tiff` - The loaded file
+/// * `output_path` - Where to write the copy
+/// * `logger` - Logger for recording operations
+///
+/// # Returns
+/// Salvage statistics for each IFD written
+pub(crate) fn write_repaired_copy(
+    reader: &TiffReader,
+    tiff: &TIFF,
+    output_path: &str,
+    logger: &Logger
+) -> TiffResult<Vec<SalvageResult>> {
+    let mut file = reader.create_reader()?;
+    let file_size = validation::get_file_size(&mut file)?;
+
+    let mut builder = TiffBuilder::new(logger, tiff.is_big_tiff);
+    let mut results = Vec::new();
+    for ifd in &tiff.ifds {
+        let (raster, result) = match salvage_image(reader, ifd, file_size) {
+            Ok(salvaged) => salvaged,
+            Err(e) => {
+                warn!("Leaving IFD {} out of the repaired copy: {}", ifd.number, e);
+                continue;
+            }
+        };
+
+        let ifd_index = builder.add_ifd(IFD::new(results.len(), 0));
+        builder.deep_copy_tags_from(ifd_index, ifd, &REBUILT_TAGS, reader);
+        builder.ifds[ifd_index].add_entry(IFDEntry::new(
+            tags::COMPRESSION, field_types::SHORT, 1, compression::NONE as u64));
+        builder.setup_single_strip(ifd_index, raster);
+        results.push(result);
+    }
+
+    if results.is_empty() {
+        return Err(TiffError::GenericError("No IFD could be rebuilt".to_string()));
+    }
+
+    builder.write(output_path)?;
+    info!("Wrote repaired copy with {} IFDs to {}", results.len(), output_path);
+    Ok(results)
+}
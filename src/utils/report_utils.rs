@@ -0,0 +1,222 @@
+//! Extraction reports
+//!
+//! Pipelines that index or validate extraction results need the pixel
+//! window that was read, where the output lies and what it holds. With
+//! `--report`, the extract command gathers this for every output while it
+//! still knows the resolved parameters and writes it as one JSON document,
+//! so nobody has to open the outputs again to find out.
+
+use std::fs;
+use std::time::Duration;
+
+use log::{info, warn};
+
+use crate::coordinate::Affine;
+use crate::extractor::Region;
+use crate::tiff::constants::tags;
+use crate::tiff::errors::{TiffError, TiffResult};
+use crate::tiff::TiffReader;
+use crate::utils::info_utils;
+use crate::utils::logger::Logger;
+use crate::utils::statistics_utils::{self, BandReport};
+use crate::utils::tensor_utils::escape_json;
+use crate::utils::tiff_extraction_utils;
+
+/// What an extracted GeoTIFF holds
+#[derive(Debug, Clone)]
+pub struct OutputSummary {
+    /// Width in pixels
+    pub width: u32,
+    /// Height in pixels
+    pub height: u32,
+    /// Number of bands
+    pub bands: usize,
+    /// Geotransform of the output, if it is georeferenced
+    pub geotransform: Option<Affine>,
+    /// Coordinate system of the output, "EPSG:n" or its name
+    pub crs: Option<String>,
+    /// NoData value of the output
+    pub nodata: Option<f64>,
+    /// Statistics of each band holding valid values
+    pub statistics: Vec<BandReport>,
+}
+
+/// Outcome of one extraction
+#[derive(Debug, Clone, Default)]
+pub struct ReportEntry {
+    /// Path of the output
+    pub output: String,
+    /// Why the extraction failed, None if it succeeded
+    pub error: Option<String>,
+    /// Requested bounding box
+    pub bbox: Option<String>,
+    /// Requested centre coordinate
+    pub coordinate: Option<String>,
+    /// Radius around the coordinate
+    pub radius: Option<f64>,
+    /// CRS of the requested bounding box or coordinate
+    pub request_crs: Option<String>,
+    /// Pixel window read from the source
+    pub window: Option<Region>,
+    /// Time the extraction took
+    pub elapsed: Duration,
+    /// Contents of the output, for GeoTIFF outputs
+    pub summary: Option<OutputSummary>,
+}
+
+/// Describe an extracted GeoTIFF
+///
+/// The statistics are computed as `--stats` does, sampling the blocks of
+/// large outputs.
+///
+/// # Arguments
+/// * `path` - Path of the output
+/// * `logger` - Logger for the reader
+///
+/// # Returns
+/// Size, georeferencing, NoData and band statistics of the output
+pub fn summarize_output(path: &str, logger: &Logger) -> TiffResult<OutputSummary> {
+    let mut reader = TiffReader::new(logger);
+    let tiff = reader.load(path)?;
+    let ifd = tiff.main_ifd()
+        .ok_or_else(|| TiffError::GenericError(format!("No IFDs found in {}", path)))?;
+    let (width, height) = ifd.get_dimensions()
+        .ok_or_else(|| TiffError::GenericError(format!("{} lacks image dimensions", path)))?;
+
+    let georeferencing = info_utils::read_georeferencing(&tiff, ifd, &reader, path);
+    let crs = georeferencing.as_ref()
+        .and_then(|georeferencing| georeferencing.definition.as_ref())
+        .map(|definition| match definition.epsg {
+            Some(code) => format!("EPSG:{}", code),
+            None => definition.name.clone(),
+        });
+    let nodata = if ifd.has_tag(tags::GDAL_NODATA) {
+        tiff_extraction_utils::extract_nodata_value(ifd, &reader).trim().parse::<f64>().ok()
+    } else {
+        None
+    };
+    // An output without valid values is still described, with no statistics
+    let statistics = statistics_utils::compute_ifd_statistics(&reader, ifd, path, nodata)
+        .unwrap_or_else(|e| {
+            warn!("No statistics for {}: {}", path, e);
+            Vec::new()
+        });
+
+    Ok(OutputSummary {
+        width: width as u32,
+        height: height as u32,
+        bands: ifd.get_samples_per_pixel() as usize,
+        geotransform: georeferencing.map(|georeferencing| georeferencing.geotransform),
+        crs,
+        nodata,
+        statistics,
+    })
+}
+
+/// Format a report as JSON
+///
+/// # Arguments
+/// * `input` - Path of the source
+/// * `entries` - One entry per output
+/// * `elapsed` - Time the whole command took
+///
+/// # Returns
+/// The JSON text
+pub fn report_json(input: &str, entries: &[ReportEntry], elapsed: Duration) -> String {
+    let outputs: Vec<String> = entries.iter().map(entry_json).collect();
+    format!("{{\n  \"rasterkit_version\": \"{}\",\n  \"created\": \"{}\",\n  \"input\": \"{}\",\n  \
+             \"elapsed_seconds\": {},\n  \"outputs\": [{}]\n}}\n",
+            env!("CARGO_PKG_VERSION"), crate::utils::sqlite_utils::utc_timestamp(), escape_json(input),
+            number(elapsed.as_secs_f64()),
+            if outputs.is_empty() { String::new() } else { format!("\n{}\n  ", outputs.join(",\n")) })
+}
+
+/// Write a report to a file
+///
+/// # Arguments
+/// * `path` - Path of the report
+/// * `input` - Path of the source
+/// * `entries` - One entry per output
+/// * `elapsed` - Time the whole command took
+pub fn write_report(path: &str, input: &str, entries: &[ReportEntry], elapsed: Duration) -> TiffResult<()> {
+    fs::write(path, report_json(input, entries, elapsed))?;
+    let failed = entries.iter().filter(|entry| entry.error.is_some()).count();
+    if failed > 0 {
+        warn!("Report {} lists {} failed extraction(s)", path, failed);
+    }
+    info!("Wrote extraction report for {} output(s) to {}", entries.len(), path);
+    Ok(())
+}
+
+/// One output as a JSON object
+fn entry_json(entry: &ReportEntry) -> String {
+    let mut fields = vec![
+        ("output", string(&entry.output)),
+        ("status", string(if entry.error.is_some() { "failed" } else { "ok" })),
+    ];
+    if let Some(error) = &entry.error {
+        fields.push(("error", string(error)));
+    }
+
+    let mut request = Vec::new();
+    if let Some(bbox) = &entry.bbox {
+        request.push(format!("\"bbox\": {}", string(bbox)));
+    }
+    if let Some(coordinate) = &entry.coordinate {
+        request.push(format!("\"coordinate\": {}", string(coordinate)));
+    }
+    if let Some(radius) = entry.radius {
+        request.push(format!("\"radius\": {}", number(radius)));
+    }
+    if let Some(crs) = &entry.request_crs {
+        request.push(format!("\"crs\": {}", string(crs)));
+    }
+    fields.push(("request", format!("{{{}}}", request.join(", "))));
+
+    fields.push(("window", match entry.window {
+        Some(window) => format!("{{\"x\": {}, \"y\": {}, \"width\": {}, \"height\": {}}}",
+                                window.x, window.y, window.width, window.height),
+        None => "null".to_string(),
+    }));
+    fields.push(("elapsed_seconds", number(entry.elapsed.as_secs_f64())));
+
+    if let Some(summary) = &entry.summary {
+        fields.push(("width", summary.width.to_string()));
+        fields.push(("height", summary.height.to_string()));
+        fields.push(("bands", summary.bands.to_string()));
+        fields.push(("crs", summary.crs.as_deref().map_or("null".to_string(), string)));
+        fields.push(("geotransform", match summary.geotransform {
+            Some(gt) => format!("[{}]", [gt.origin_x, gt.pixel_width, gt.row_rotation,
+                                         gt.origin_y, gt.column_rotation, gt.pixel_height]
+                .iter().map(|value| number(*value)).collect::<Vec<_>>().join(", ")),
+            None => "null".to_string(),
+        }));
+        fields.push(("nodata", summary.nodata.map_or("null".to_string(), number)));
+        let bands: Vec<String> = summary.statistics.iter()
+            .map(|report| format!(
+                "        {{\"band\": {}, \"min\": {}, \"max\": {}, \"mean\": {}, \"std\": {}, \
+                 \"valid_percent\": {}, \"approximate\": {}}}",
+                report.band, number(report.statistics.min), number(report.statistics.max),
+                number(report.statistics.mean), number(report.statistics.std),
+                number(report.valid_percent), report.approximate))
+            .collect();
+        fields.push(("statistics", if bands.is_empty() {
+            "[]".to_string()
+        } else {
+            format!("[\n{}\n      ]", bands.join(",\n"))
+        }));
+    }
+
+    let body: Vec<String> = fields.iter().map(|(key, value)| format!("      \"{}\": {}", key, value)).collect();
+    format!("    {{\n{}\n    }}", body.join(",\n"))
+}
+
+/// Quote a JSON string
+fn string(value: &str) -> String {
+    format!("\"{}\"", escape_json(value))
+}
+
+/// A JSON number, null for values JSON cannot hold
+fn number(value: f64) -> String {
+    if value.is_finite() { value.to_string() } else { "null".to_string() }
+}
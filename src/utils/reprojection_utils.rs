@@ -1,148 +1,151 @@
 //! Image reprojection utilities
 //!
 //! This module provides functionality for reprojecting images between different
-//! coordinate reference systems during extraction.
+//! coordinate reference systems during extraction. The pixels are warped by
+//! `warp_utils`; this module handles the surrounding metadata and output.
 
 use image::DynamicImage;
-use log::{info, debug, warn};
-use std::path::Path;
+use log::{info, warn};
 
 use crate::tiff::errors::{TiffError, TiffResult};
 use crate::tiff::TiffReader;
 use crate::tiff::TiffBuilder;
-use crate::tiff::geo_key_parser::GeoKeyParser;
-use crate::tiff::constants::{tags, field_types, photometric};
-use crate::extractor::Region;
+use crate::tiff::constants::{tags, field_types};
+use crate::tiff::ifd::IFD;
+use crate::tiff::proj_definition::ProjDefinition;
+use crate::extractor::{OutputFormat, Region};
+use crate::utils::info_utils::{self, Georeferencing};
 use crate::utils::logger::Logger;
+use crate::utils::metrics;
 use crate::utils::reference_utils;
 use crate::utils::tiff_extraction_utils;
+use crate::utils::warp_utils::{self, WarpOptions, WarpTransformer};
 
 /// Reproject and save an image
 ///
-/// Takes an extracted image and reprojected it to the target projection
-/// before saving. This maintains geospatial reference information.
+/// Warps the extracted region onto a north-up grid in the target
+/// projection (see `warp_utils`) and saves it georeferenced in that
+/// projection. The pixels are pulled through `read_window`, one source
+/// window per output block, so a memory budget in the warp options keeps
+/// large regions from being read whole.
 ///
 /// # Arguments
-/// * `image` - The extracted image to reproject
+/// * `read_window` - Reads a window of the input, in its pixels, as an image
 /// * `input_path` - Path to the original input file (for metadata)
 /// * `output_path` - Path where to save the reprojected output
-/// * `region` - Region that was extracted
+/// * `region` - Region that was extracted, None for the whole image
 /// * `target_epsg` - Target EPSG code for reprojection
+/// * `target_definition` - Target definition when the system has no EPSG code
+/// * `warp_options` - Target grid, memory budget and NoData handling
 /// * `logger` - Logger for recording operations
 /// * `shape` - Optional shape to use ("circle" or "square")
+/// * `format` - Requested output format, None to go by the extension
 ///
 /// # Returns
 /// Result indicating success or an error
+#[allow(clippy::too_many_arguments)]
 pub fn reproject_and_save(
-    image: &DynamicImage,
+    read_window: &mut dyn FnMut(Region) -> TiffResult<DynamicImage>,
     input_path: &str,
     output_path: &str,
     region: Option<Region>,
     target_epsg: u32,
+    target_definition: Option<&ProjDefinition>,
+    warp_options: &WarpOptions,
     logger: &Logger,
-    shape: Option<&str>
+    shape: Option<&str>,
+    format: Option<OutputFormat>
 ) -> TiffResult<()> {
     info!("Reprojecting image to EPSG:{}", target_epsg);
 
-    // If it's a non-TIFF output format, just save directly (no reprojection possible)
-    let extension = Path::new(output_path)
-        .extension()
-        .map(|ext| ext.to_string_lossy().to_lowercase())
-        .unwrap_or_default();
+    // Get source georeferencing and metadata from input file
+    let mut tiff_reader = TiffReader::new(logger);
+    let tiff = tiff_reader.load(input_path)?;
 
-    if extension != "tif" && extension != "tiff" {
+    if tiff.ifds.is_empty() {
+        return Err(TiffError::GenericError("No IFDs found in input file".to_string()));
+    }
+
+    let source_ifd = &tiff.ifds[0];
+    let (width, height) = source_ifd.get_dimensions()
+        .ok_or_else(|| TiffError::GenericError("Could not determine image dimensions".to_string()))?;
+    let full_region = region.unwrap_or_else(|| Region::new(0, 0, width as u32, height as u32));
+
+    // If it's a non-TIFF output format, just save directly (no reprojection possible)
+    let output_format = OutputFormat::resolve(output_path, format);
+    if !output_format.is_tiff() {
         warn!("Reprojection only supported for TIFF output, saving without reprojection");
+        let image = read_window(full_region)?;
         // For non-TIFF formats with shape masking
         if let Some(shape_str) = shape {
             if shape_str.to_lowercase() == "circle" {
-                let masked_image = crate::utils::mask_utils::apply_shape_mask(image, shape_str);
-                return crate::utils::mask_utils::save_shaped_image(&masked_image, output_path, shape_str);
+                let masked_image = crate::utils::mask_utils::apply_shape_mask(&image, shape_str);
+                return crate::utils::mask_utils::save_shaped_image(&masked_image, output_path, shape_str, format);
             }
         }
-        return match image.save(output_path) {
-            Ok(_) => Ok(()),
-            Err(e) => Err(TiffError::GenericError(format!("Failed to save image: {}", e)))
-        };
+        return output_format.write_image(&image, output_path);
     }
 
-    // Get source EPSG code and metadata from input file
-    let mut tiff_reader = TiffReader::new(logger);
-    let tiff = tiff_reader.load(input_path)?;
-
-    if tiff.ifds.is_empty() {
-        return Err(TiffError::GenericError("No IFDs found in input file".to_string()));
-    }
-
-    let source_ifd = &tiff.ifds[0];
+    let georeferencing = info_utils::read_georeferencing(&tiff, source_ifd, &tiff_reader, input_path);
+    let Some(Georeferencing { geotransform, definition: Some(source_definition) }) = georeferencing else {
+        warn!("Source projection not found, saving without reprojection");
+        let image = read_window(full_region)?;
+        return save_without_reprojection(&image, output_path, region, input_path, logger, shape, format);
+    };
 
-    // Get byte order handler
-    let byte_order_handler = match tiff_reader.get_byte_order_handler() {
-        Some(handler) => handler,
-        None => return Err(TiffError::GenericError("Byte order handler not available".to_string())),
+    let target_definition = match target_definition {
+        Some(definition) => definition.clone(),
+        None => ProjDefinition::from_epsg(target_epsg).ok_or_else(|| TiffError::GenericError(format!(
+            "EPSG:{} is not a built-in system; give it as a PROJ string or WKT", target_epsg)))?,
     };
 
-    // Get the file path
-    let file_path = tiff_reader.get_file_path().unwrap_or(input_path);
+    info!("Reprojecting from {} to {}", source_definition.name, target_definition.name);
 
-    // Extract geospatial information
-    let geo_info = match GeoKeyParser::extract_geo_info(source_ifd, byte_order_handler, file_path) {
-        Ok(info) => info,
-        Err(e) => {
-            warn!("Failed to extract GeoTIFF info: {}, continuing with limited metadata", e);
-            return save_without_reprojection(image, output_path, region, input_path, logger, shape);
-        }
+    // The circle is cut from the whole region, which is read in one window
+    // unless a memory budget splits it
+    let circle = shape.is_some_and(|shape_str| shape_str.eq_ignore_ascii_case("circle"));
+    let mut read_masked = |window: Region| -> TiffResult<DynamicImage> {
+        let image = read_window(window)?;
+        let whole = window.x == full_region.x && window.y == full_region.y
+            && window.width == full_region.width && window.height == full_region.height;
+        Ok(if circle && whole { crate::utils::mask_utils::apply_shape_mask(&image, "circle") } else { image })
     };
 
-    // Get the source EPSG code
-    let source_epsg = geo_info.epsg_code;
-    if source_epsg == 0 {
-        warn!("Source EPSG code not found, saving without reprojection");
-        return save_without_reprojection(image, output_path, region, input_path, logger, shape);
+    let transformer = WarpTransformer::new(&source_definition, &target_definition)?;
+    // NoData defaults to the source's, on both sides of the warp
+    let mut warp_options = warp_options.clone();
+    if warp_options.source_nodata.is_none() && source_ifd.has_tag(tags::GDAL_NODATA) {
+        warp_options.source_nodata = tiff_extraction_utils::extract_nodata_value(source_ifd, &tiff_reader).parse::<f64>().ok();
     }
+    warp_options.target_nodata = warp_options.target_nodata.or(warp_options.source_nodata);
+    info!("Source NoData {:?}, target NoData {:?}, alpha: {}",
+          warp_options.source_nodata, warp_options.target_nodata, warp_options.target_alpha);
 
-    info!("Reprojecting from EPSG:{} to EPSG:{}", source_epsg, target_epsg);
-
-    // Apply shape mask if needed
-    let masked_image = if let Some(shape_str) = shape {
-        if shape_str.to_lowercase() == "circle" {
-            crate::utils::mask_utils::apply_shape_mask(image, shape_str)
-        } else {
-            image.clone()
-        }
-    } else {
-        image.clone()
-    };
+    let (warped_image, target_geotransform) = metrics::time("warp", || warp_utils::warp_windows(
+        full_region, &geotransform, &transformer, &warp_options, estimated_pixel_bytes(source_ifd), &mut read_masked))?;
 
     // Set up the TIFF builder
     let mut builder = TiffBuilder::new(logger, false);
     let ifd_index = builder.add_ifd(crate::tiff::ifd::IFD::new(0, 0));
 
     // Set basic tags
-    tiff_extraction_utils::setup_tiff_tags(&mut builder, ifd_index, source_ifd, &masked_image)?;
-
-    // Process image data
-    if masked_image.color().has_color() {
-        // RGB image
-        tiff_extraction_utils::process_rgb_image(&masked_image, &mut builder, ifd_index)?;
-    } else {
-        // Grayscale image
-        tiff_extraction_utils::process_grayscale_image(&masked_image, &mut builder, ifd_index, 8)?;
-    }
+    tiff_extraction_utils::setup_tiff_tags(&mut builder, ifd_index, source_ifd, &tiff_reader, &warped_image)?;
+    builder.ifds[ifd_index].entries.retain(|entry| entry.tag != tags::MODEL_TRANSFORMATION_TAG);
 
-    // Copy GeoTIFF tags for source projection
-    builder.copy_geotiff_tags(ifd_index, source_ifd, &mut tiff_reader)?;
+    // Process image data (RGBA masks keep their alpha channel)
+    tiff_extraction_utils::process_image_data(&warped_image, &mut builder, ifd_index)?;
 
-    // Add georeferencing, preserving source projection info
-    if let Some(extracted_region) = region {
-        reference_utils::add_georeferencing_to_builder(&mut builder, ifd_index, &extracted_region, input_path, logger)?;
-    }
+    builder.copy_icc_profile(ifd_index, source_ifd, &tiff_reader)?;
+    builder.copy_exif_metadata(ifd_index, source_ifd, &tiff_reader)?;
 
-    // Update the projection info to use the target EPSG code
-    // This is the core of the reprojection - updating the EPSG code in the GeoKey directory
-    update_projection_code(&mut builder, ifd_index, target_epsg);
+    // Georeference the warped grid in the target system
+    builder.set_georeference(ifd_index, &target_geotransform, target_epsg)?;
 
     // Set NoData tag and other important metadata
-    let nodata_value = tiff_extraction_utils::extract_nodata_value(source_ifd, &tiff_reader);
+    let nodata_value = match warp_options.target_nodata {
+        Some(nodata) => nodata.to_string(),
+        None => tiff_extraction_utils::extract_nodata_value(source_ifd, &tiff_reader),
+    };
     let metadata_str = tiff_extraction_utils::extract_gdal_metadata(source_ifd, &tiff_reader);
 
     builder.add_nodata_tag(ifd_index, &nodata_value);
@@ -155,6 +158,19 @@ pub fn reproject_and_save(
     Ok(())
 }
 
+/// Bytes an extracted pixel of an image may take
+///
+/// Errs on the high side: colormaps turn single bands into RGB, so at
+/// least three samples are counted.
+fn estimated_pixel_bytes(ifd: &IFD) -> usize {
+    let samples = ifd.get_samples_per_pixel().max(1) as usize;
+    let bytes_per_sample = match ifd.get_tag_value(tags::BITS_PER_SAMPLE) {
+        Some(bits) if samples == 1 => bits.div_ceil(8).max(1) as usize,
+        _ => 1,
+    };
+    samples.max(3) * bytes_per_sample
+}
+
 /// Save image without reprojection as a fallback
 ///
 /// This is used when reprojection isn't possible due to missing source projection info.
@@ -166,6 +182,7 @@ pub fn reproject_and_save(
 /// * `input_path` - Path to the original input file (for metadata)
 /// * `logger` - Logger for recording operations
 /// * `shape` - Optional shape to use ("circle" or "square")
+/// * `format` - Requested output format, None to go by the extension
 ///
 /// # Returns
 /// Result indicating success or an error
@@ -175,7 +192,8 @@ fn save_without_reprojection(
     region: Option<Region>,
     input_path: &str,
     logger: &Logger,
-    shape: Option<&str>
+    shape: Option<&str>,
+    format: Option<OutputFormat>
 ) -> TiffResult<()> {
     warn!("Saving without reprojection");
 
@@ -191,19 +209,12 @@ fn save_without_reprojection(
     };
 
     // For non-TIFF formats
-    let extension = Path::new(output_path)
-        .extension()
-        .map(|ext| ext.to_string_lossy().to_lowercase())
-        .unwrap_or_default();
-
-    if extension != "tif" && extension != "tiff" {
+    let output_format = OutputFormat::resolve(output_path, format);
+    if !output_format.is_tiff() {
         if let Some(shape_str) = shape {
-            return crate::utils::mask_utils::save_shaped_image(&masked_image, output_path, shape_str);
+            return crate::utils::mask_utils::save_shaped_image(&masked_image, output_path, shape_str, format);
         } else {
-            return match masked_image.save(output_path) {
-                Ok(_) => Ok(()),
-                Err(e) => Err(TiffError::GenericError(format!("Failed to save image: {}", e)))
-            };
+            return output_format.write_image(&masked_image, output_path);
         }
     }
 
@@ -217,7 +228,9 @@ fn save_without_reprojection(
 
     if !source_tiff.ifds.is_empty() {
         let source_ifd = &source_tiff.ifds[0];
-        tiff_extraction_utils::setup_tiff_tags(&mut builder, ifd_index, source_ifd, &masked_image)?;
+        tiff_extraction_utils::setup_tiff_tags(&mut builder, ifd_index, source_ifd, &reader, &masked_image)?;
+        builder.copy_icc_profile(ifd_index, source_ifd, &reader)?;
+        builder.copy_exif_metadata(ifd_index, source_ifd, &reader)?;
     } else {
         // Basic image dimensions if no source IFD
         builder.ifds[ifd_index].add_entry(crate::tiff::ifd::IFDEntry::new(
@@ -226,14 +239,8 @@ fn save_without_reprojection(
             tags::IMAGE_LENGTH, field_types::LONG, 1, masked_image.height() as u64));
     }
 
-    // Process image data
-    if masked_image.color().has_color() {
-        // RGB image
-        tiff_extraction_utils::process_rgb_image(&masked_image, &mut builder, ifd_index)?;
-    } else {
-        // Grayscale image
-        tiff_extraction_utils::process_grayscale_image(&masked_image, &mut builder, ifd_index, 8)?;
-    }
+    // Process image data (RGBA masks keep their alpha channel)
+    tiff_extraction_utils::process_image_data(&masked_image, &mut builder, ifd_index)?;
 
     // Try to copy georeference information
     if !source_tiff.ifds.is_empty() {
@@ -252,35 +259,3 @@ fn save_without_reprojection(
 
     Ok(())
 }
-
-/// Update the projection code in a TIFF IFD
-///
-/// Updates the EPSG code in the GeoKey directory tag to change
-/// the projection of the output file.
-///
-/// # Arguments
-/// * `builder` - The TIFF builder to modify
-/// * `ifd_index` - Index of the IFD to update
-/// * `target_epsg` - The target EPSG code
-fn update_projection_code(
-    builder: &mut TiffBuilder,
-    ifd_index: usize,
-    target_epsg: u32
-) {
-    info!("Updating projection code to EPSG:{}", target_epsg);
-
-    // In a real implementation, we would modify the GeoKeyDirectoryTag to update
-    // the ProjectedCSTypeGeoKey with the new EPSG code.
-    // For now, this is a placeholder that would be expanded in a full implementation
-    // to properly modify the GeoKey directory structure.
-
-    // This would require parsing the existing GeoKeyDirectoryTag,
-    // finding the ProjectedCSTypeGeoKey entry, and updating its value.
-    // Then rewriting the entire GeoKeyDirectoryTag.
-
-    // For a complete solution, GDAL or PROJ libraries would be used to
-    // properly transform the coordinates during reprojection.
-
-    debug!("Note: This is a metadata-only reprojection that changes the projection code");
-    debug!("      without actually transforming the coordinates");
-}
\ No newline at end of file
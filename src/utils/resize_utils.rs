@@ -0,0 +1,294 @@
+//! Raster resizing utilities
+//!
+//! Resamples a raster onto a coarser (or finer) grid covering the same
+//! area, in the same coordinate system: by a scale factor, or to a target
+//! pixel size in map units. The output keeps the sample type, band layout
+//! and GeoKeys of the source, and its geotransform is rescaled so every
+//! pixel still lands where it belongs. This is the quick way to produce
+//! thumbnails or analysis-resolution copies without a full reprojection.
+
+use log::{info, warn};
+
+use crate::coordinate::Affine;
+use crate::extractor::{self, ArraySamples};
+use crate::tiff::builder::TiffBuilder;
+use crate::tiff::constants::{field_types, photometric, sample_format, tags};
+use crate::tiff::errors::{TiffError, TiffResult};
+use crate::tiff::ifd::{IFD, IFDEntry};
+use crate::tiff::TiffReader;
+use crate::utils::grid_utils::{self, Raster};
+use crate::utils::logger::Logger;
+use crate::utils::sample_utils::{SampleFormat, SampleLayout};
+use crate::utils::tensor_utils::TensorDType;
+use crate::utils::{memory_utils, tiff_extraction_utils};
+
+/// Tags describing the samples of the source, which are written anew
+/// for their output type
+const SAMPLE_TAGS: [u16; 4] = [
+    tags::BITS_PER_SAMPLE, tags::SAMPLE_FORMAT, tags::MIN_SAMPLE_VALUE, tags::MAX_SAMPLE_VALUE,
+];
+
+/// How output pixels take their value from the source
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Resampling {
+    /// The source pixel under the output pixel centre
+    Nearest,
+    /// Bilinear interpolation of the four source pixels around the centre
+    Bilinear,
+    /// Mean of the source pixels whose centres fall in the output pixel
+    Average,
+}
+
+impl Resampling {
+    /// Parse a resampling name: nearest, bilinear or average
+    pub fn from_string(name: &str) -> TiffResult<Self> {
+        match name.trim().to_lowercase().as_str() {
+            "nearest" => Ok(Resampling::Nearest),
+            "bilinear" => Ok(Resampling::Bilinear),
+            "average" | "mean" => Ok(Resampling::Average),
+            other => Err(TiffError::GenericError(format!(
+                "Unknown resampling '{}'. Supported: nearest, bilinear, average", other))),
+        }
+    }
+}
+
+/// Size of the resized raster
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum ResizeTarget {
+    /// Multiply the width and height by a factor (0.25 keeps a quarter of each)
+    Factor(f64),
+    /// Pixel width and height in map units
+    Resolution(f64, f64),
+}
+
+/// Output size and source pixels per output pixel for a resize
+///
+/// # Arguments
+/// * `width` - Source width in pixels
+/// * `height` - Source height in pixels
+/// * `geotransform` - Source geotransform, needed for a target resolution
+/// * `target` - Requested size
+///
+/// # Returns
+/// (width, height, x step, y step), where the steps are the source
+/// columns and rows spanned by one output pixel
+pub fn resize_grid(width: u32, height: u32, geotransform: Option<&Affine>,
+                   target: ResizeTarget) -> TiffResult<(u32, u32, f64, f64)> {
+    let (columns, rows) = match target {
+        ResizeTarget::Factor(factor) => {
+            if !(factor.is_finite() && factor > 0.0) {
+                return Err(TiffError::GenericError(format!(
+                    "The resize factor must be a positive number, got {}", factor)));
+            }
+            (width as f64 * factor, height as f64 * factor)
+        },
+        ResizeTarget::Resolution(x_resolution, y_resolution) => {
+            if !(x_resolution > 0.0 && y_resolution > 0.0) {
+                return Err(TiffError::GenericError(format!(
+                    "The target resolution must be positive, got {},{}", x_resolution, y_resolution)));
+            }
+            let geotransform = geotransform.ok_or_else(|| TiffError::GenericError(
+                "A target resolution needs a georeferenced raster; use a resize factor instead".to_string()))?;
+            if !geotransform.is_north_up() {
+                return Err(TiffError::GenericError(
+                    "A target resolution needs a north-up raster; use a resize factor instead".to_string()));
+            }
+            let x_step = x_resolution / geotransform.pixel_width.abs();
+            let y_step = y_resolution / geotransform.pixel_height.abs();
+            // The pixel size is kept exact, so the extent may grow or
+            // shrink by a fraction of an output pixel
+            let columns = (width as f64 / x_step).round().max(1.0);
+            let rows = (height as f64 / y_step).round().max(1.0);
+            return Ok((columns as u32, rows as u32, x_step, y_step));
+        },
+    };
+
+    let (columns, rows) = (columns.round().max(1.0), rows.round().max(1.0));
+    if columns > u32::MAX as f64 || rows > u32::MAX as f64 {
+        return Err(TiffError::GenericError(format!(
+            "The resized raster would be {}x{} pixels", columns, rows)));
+    }
+    Ok((columns as u32, rows as u32, width as f64 / columns, height as f64 / rows))
+}
+
+/// Mean of the source pixels whose centres fall in an output pixel
+///
+/// # Returns
+/// The mean of the pixels with data, NaN if none have any, or None when
+/// the output pixel is smaller than a source pixel and covers no centre
+fn footprint_mean(raster: &Raster, band: usize, column: u32, row: u32, x_step: f64, y_step: f64) -> Option<f64> {
+    let span = |start: f64, step: f64, size: u32| {
+        let first = (start * step - 0.5).ceil().max(0.0) as u32;
+        let end = (((start + 1.0) * step - 0.5).ceil().max(0.0) as u32).min(size);
+        first..end
+    };
+    let (columns, rows) = (span(column as f64, x_step, raster.layout.width), span(row as f64, y_step, raster.layout.height));
+    if columns.is_empty() || rows.is_empty() {
+        return None;
+    }
+
+    let width = raster.layout.width as usize;
+    let values = &raster.bands[band];
+    let (sum, count) = rows
+        .flat_map(|y| columns.clone().map(move |x| values[y as usize * width + x as usize]))
+        .filter(|value| !value.is_nan())
+        .fold((0.0, 0usize), |(sum, count), value| (sum + value, count + 1));
+    Some(if count > 0 { sum / count as f64 } else { f64::NAN })
+}
+
+/// Resample every band of a raster onto a regular grid over its extent
+///
+/// # Arguments
+/// * `raster` - Source raster
+/// * `width` - Output width in pixels
+/// * `height` - Output height in pixels
+/// * `x_step` - Source columns per output column
+/// * `y_step` - Source rows per output row
+/// * `resampling` - How output pixels take their value
+///
+/// # Returns
+/// The values of each output band in row-major order, NaN where there is no data
+pub fn resample_bands(raster: &Raster, width: u32, height: u32, x_step: f64, y_step: f64,
+                      resampling: Resampling) -> Vec<Vec<f64>> {
+    (0..raster.bands.len()).map(|band| {
+        let mut values = Vec::with_capacity(width as usize * height as usize);
+        for row in 0..height {
+            let source_row = (row as f64 + 0.5) * y_step;
+            for column in 0..width {
+                let source_column = (column as f64 + 0.5) * x_step;
+                let value = match resampling {
+                    Resampling::Nearest => raster.nearest(band, source_column, source_row),
+                    Resampling::Bilinear => raster.bilinear(band, source_column, source_row),
+                    Resampling::Average => match footprint_mean(raster, band, column, row, x_step, y_step) {
+                        Some(mean) => Some(mean),
+                        None => raster.nearest(band, source_column, source_row),
+                    },
+                };
+                values.push(value.unwrap_or(f64::NAN));
+            }
+        }
+        values
+    }).collect()
+}
+
+/// Type the samples of a layout are written as
+///
+/// Signed bytes widen to 16 bits and 64-bit integers become doubles, as
+/// in extracted arrays.
+fn output_dtype(layout: &SampleLayout) -> TiffResult<TensorDType> {
+    match (layout.bits, layout.format) {
+        (8, SampleFormat::Unsigned) => Ok(TensorDType::U8),
+        (8, SampleFormat::Signed) | (16, SampleFormat::Signed) => Ok(TensorDType::I16),
+        (16, SampleFormat::Unsigned) => Ok(TensorDType::U16),
+        (32, SampleFormat::Unsigned) => Ok(TensorDType::U32),
+        (32, SampleFormat::Signed) => Ok(TensorDType::I32),
+        (32, SampleFormat::Float) => Ok(TensorDType::F32),
+        (64, _) => Ok(TensorDType::F64),
+        (bits, format) => Err(TiffError::GenericError(format!(
+            "Cannot resize {}-bit {:?} samples", bits, format))),
+    }
+}
+
+/// Fill an IFD of a builder with a resampled copy of a raster
+///
+/// Samples keep the type of the source, widened where an array would be,
+/// and are written as `grid_utils::add_derived_image` writes them.
+///
+/// # Arguments
+/// * `builder` - Builder of the output
+/// * `ifd_index` - IFD of the builder to fill
+/// * `reader` - Reader the source was loaded with
+/// * `source_ifd` - The source image
+/// * `raster` - Values of the source image
+/// * `size` - Output width and height in pixels
+/// * `steps` - Source columns and rows per output pixel
+/// * `resampling` - How output pixels take their value
+/// * `exclude` - Further source tags to leave out
+#[allow(clippy::too_many_arguments)]
+pub(crate) fn add_resampled_image(builder: &mut TiffBuilder, ifd_index: usize, reader: &TiffReader,
+                                  source_ifd: &IFD, raster: &Raster, size: (u32, u32), steps: (f64, f64),
+                                  resampling: Resampling, exclude: &[u16]) -> TiffResult<()> {
+    let dtype = output_dtype(&raster.layout)?;
+    let ((width, height), bands) = (size, raster.bands.len());
+    memory_utils::check_fits(&format!("Resampling to {}x{}", width, height),
+                             width as u64 * height as u64 * bands as u64 * (8 + dtype.size() as u64))?;
+
+    let photometric_value = source_ifd.get_tag_value(tags::PHOTOMETRIC_INTERPRETATION);
+    if resampling != Resampling::Nearest && photometric_value == Some(photometric::PALETTE as u64) {
+        warn!("Palette indices of {} are interpolated; nearest resampling keeps them valid", raster.path);
+    }
+
+    // Pixels without data take the NoData value, or stay NaN for floats
+    let fill = source_ifd.has_tag(tags::GDAL_NODATA)
+        .then(|| tiff_extraction_utils::extract_nodata_value(source_ifd, reader).trim().parse::<f64>().ok())
+        .flatten();
+    let resampled = resample_bands(raster, width, height, steps.0, steps.1, resampling);
+    let interleaved: Vec<f64> = (0..width as usize * height as usize)
+        .flat_map(|pixel| resampled.iter().map(move |band| band[pixel]))
+        .map(|value| if value.is_nan() { fill.unwrap_or(value) } else { value })
+        .collect();
+    let samples = ArraySamples::from_values(&interleaved, dtype);
+
+    let excluded: Vec<u16> = SAMPLE_TAGS.iter().chain(exclude).copied().collect();
+    grid_utils::add_derived_image(builder, ifd_index, reader, source_ifd, size, samples.to_le_bytes(), &excluded);
+    builder.add_bits_per_sample(ifd_index, &vec![(dtype.size() * 8) as u16; bands]);
+    let format = match dtype {
+        TensorDType::F32 | TensorDType::F64 => sample_format::IEEEFP,
+        TensorDType::I16 | TensorDType::I32 => sample_format::SIGNED,
+        _ => sample_format::UNSIGNED,
+    };
+    if format != sample_format::UNSIGNED {
+        builder.ifds[ifd_index].add_entry(IFDEntry::new(tags::SAMPLE_FORMAT, field_types::SHORT, 1, format as u64));
+    }
+    Ok(())
+}
+
+/// Resize a raster within its coordinate system
+///
+/// The output is written as `add_resampled_image` fills it, with the
+/// geotransform rescaled.
+///
+/// # Arguments
+/// * `input_path` - Raster to resize
+/// * `output_path` - Path of the GeoTIFF to write
+/// * `target` - Scale factor or target resolution
+/// * `resampling` - How output pixels take their value
+/// * `logger` - Logger for recording operations
+///
+/// # Returns
+/// The width and height of the output
+pub fn resize(input_path: &str, output_path: &str, target: ResizeTarget, resampling: Resampling,
+              logger: &Logger) -> TiffResult<(u32, u32)> {
+    let shape = extractor::describe_window(input_path, None, logger)?;
+    memory_utils::check_fits(&format!("Reading the {}x{} raster", shape.width, shape.height),
+                             shape.samples() as u64 * 8)?;
+    let raster = Raster::read(input_path, usize::MAX, logger)?;
+    let geotransform = raster.georeferencing.as_ref().map(|georeferencing| georeferencing.geotransform);
+
+    let (width, height, x_step, y_step) = resize_grid(
+        raster.layout.width, raster.layout.height, geotransform.as_ref(), target)?;
+    info!("Resizing {} from {}x{} to {}x{} with {:?} resampling",
+          input_path, raster.layout.width, raster.layout.height, width, height, resampling);
+
+    let mut reader = TiffReader::new(logger);
+    let tiff = reader.load(input_path)?;
+    let source_ifd = tiff.main_ifd()
+        .ok_or_else(|| TiffError::GenericError("No IFDs found in input file".to_string()))?;
+
+    let mut builder = TiffBuilder::new(logger, false);
+    let ifd_index = builder.add_ifd(IFD::new(0, 0));
+    add_resampled_image(&mut builder, ifd_index, &reader, source_ifd, &raster,
+                        (width, height), (x_step, y_step), resampling, &[])?;
+
+    if let Some(georeferencing) = &raster.georeferencing {
+        let resized = georeferencing.geotransform.scaled(x_step, y_step);
+        grid_utils::georeference_derived(&mut builder, ifd_index, source_ifd, georeferencing, &resized)?;
+        info!("Pixel size changes from {} x {} to {} x {}",
+              georeferencing.geotransform.pixel_width, georeferencing.geotransform.pixel_height,
+              resized.pixel_width, resized.pixel_height);
+    }
+
+    builder.write(output_path)?;
+    logger.log(&format!("Resized {} to {}x{} in {}", input_path, width, height, output_path))?;
+    Ok((width, height))
+}
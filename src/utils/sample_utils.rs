@@ -0,0 +1,333 @@
+//! Raw sample reading utilities
+//!
+//! The extractors decode pixels to 8-bit images for display. Operations
+//! that need the stored values themselves (bit flags of QA bands, band
+//! statistics) read them here instead: blocks are decompressed and their
+//! samples kept at full width, for integer and floating point data, in
+//! chunky or planar layout.
+
+use std::fs::File;
+use std::io::{BufReader, Read, Seek, SeekFrom};
+use std::ops::Range;
+use std::time::Instant;
+
+use log::{info, warn};
+
+use crate::compression::CompressionFactory;
+use crate::io::byte_order::ByteOrder;
+use crate::io::seekable::SeekableReader;
+use crate::tiff::constants::{predictor as pred_consts, tags};
+use crate::tiff::errors::{TiffError, TiffResult};
+use crate::tiff::ifd::IFD;
+use crate::tiff::TiffReader;
+use crate::utils::logger::Logger;
+use crate::utils::metrics;
+
+/// How the bits of a sample are interpreted
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum SampleFormat {
+    /// Unsigned integer
+    Unsigned,
+    /// Two's complement signed integer
+    Signed,
+    /// IEEE floating point
+    Float,
+}
+
+/// Layout of the samples of a TIFF image
+#[derive(Debug, Clone)]
+pub struct SampleLayout {
+    /// Width in pixels
+    pub width: u32,
+    /// Height in pixels
+    pub height: u32,
+    /// Samples per pixel
+    pub bands: usize,
+    /// Bits per sample
+    pub bits: u32,
+    /// Interpretation of the samples
+    pub format: SampleFormat,
+    /// Whether each band is stored in blocks of its own
+    pub planar: bool,
+    /// Width of a tile, or the image width for strips
+    pub block_width: u32,
+    /// Height of a tile or strip
+    pub block_height: u32,
+}
+
+impl SampleLayout {
+    /// Blocks in a row of blocks
+    pub fn blocks_across(&self) -> usize {
+        self.width.div_ceil(self.block_width) as usize
+    }
+
+    /// Blocks of one band, or of all bands for chunky data
+    pub fn blocks_per_band(&self) -> usize {
+        self.blocks_across() * self.height.div_ceil(self.block_height) as usize
+    }
+
+    /// Total number of blocks
+    pub fn block_count(&self) -> usize {
+        if self.planar { self.blocks_per_band() * self.bands } else { self.blocks_per_band() }
+    }
+
+    /// Interpret the bits of a sample as a number
+    pub fn value(&self, raw: u64) -> f64 {
+        match (self.format, self.bits) {
+            (SampleFormat::Float, 32) => f32::from_bits(raw as u32) as f64,
+            (SampleFormat::Float, _) => f64::from_bits(raw),
+            (SampleFormat::Signed, bits) => {
+                let shift = 64 - bits;
+                ((raw << shift) as i64 >> shift) as f64
+            },
+            (SampleFormat::Unsigned, _) => raw as f64,
+        }
+    }
+}
+
+/// Samples of one decoded block
+#[derive(Debug, Clone)]
+pub struct Block {
+    /// Column of the block's top-left pixel
+    pub x: u32,
+    /// Row of the block's top-left pixel
+    pub y: u32,
+    /// Columns of the block inside the image
+    pub width: u32,
+    /// Rows of the block inside the image
+    pub height: u32,
+    /// Bands the block holds
+    pub bands: Range<usize>,
+    /// Samples in storage order, padding columns included
+    samples: Vec<u64>,
+    /// Stored width of a row, padding included
+    row_length: usize,
+}
+
+impl Block {
+    /// Raw sample of a pixel inside the block
+    ///
+    /// # Arguments
+    /// * `column` - Column relative to the block
+    /// * `row` - Row relative to the block
+    /// * `band` - Band, one of `bands`
+    ///
+    /// # Returns
+    /// The sample bits, or None if the block's data was short
+    pub fn sample(&self, column: u32, row: u32, band: usize) -> Option<u64> {
+        let stride = self.bands.len();
+        let index = (row as usize * self.row_length + column as usize) * stride + (band - self.bands.start);
+        self.samples.get(index).copied()
+    }
+}
+
+/// Read the sample layout of an IFD
+///
+/// # Arguments
+/// * `reader` - Reader the file was loaded with
+/// * `ifd` - The IFD to describe
+/// * `file` - Open handle on the file, for tag values stored out of line
+///
+/// # Returns
+/// The layout, whatever its bit depth
+pub fn read_layout(reader: &TiffReader, ifd: &IFD, file: &mut dyn SeekableReader) -> TiffResult<SampleLayout> {
+    let (width, height) = ifd.get_dimensions()
+        .ok_or_else(|| TiffError::GenericError("TIFF lacks image dimensions".to_string()))?;
+    let bands = ifd.get_tag_value(tags::SAMPLES_PER_PIXEL).unwrap_or(1).max(1) as usize;
+    let mut first_value = |tag: u16, default: u64| reader.read_tag_values(file, ifd, tag)
+        .ok().and_then(|values| values.first().copied()).unwrap_or(default);
+    let bits = first_value(tags::BITS_PER_SAMPLE, 1) as u32;
+    let format = match first_value(tags::SAMPLE_FORMAT, 1) {
+        2 => SampleFormat::Signed,
+        3 => SampleFormat::Float,
+        _ => SampleFormat::Unsigned,
+    };
+
+    let tiled = ifd.has_tag(tags::TILE_OFFSETS);
+    let (block_width, block_height) = if tiled {
+        (ifd.get_tag_value(tags::TILE_WIDTH).unwrap_or(256) as u32,
+         ifd.get_tag_value(tags::TILE_LENGTH).unwrap_or(256) as u32)
+    } else {
+        (width as u32, (ifd.get_tag_value(tags::ROWS_PER_STRIP).unwrap_or(height) as u32).clamp(1, (height as u32).max(1)))
+    };
+
+    Ok(SampleLayout {
+        width: width as u32,
+        height: height as u32,
+        bands,
+        bits,
+        format,
+        planar: ifd.get_tag_value(tags::PLANAR_CONFIGURATION).unwrap_or(1) == 2 && bands > 1,
+        block_width,
+        block_height,
+    })
+}
+
+/// Decode the blocks of a TIFF's first image at full sample width
+///
+/// # Arguments
+/// * `input_path` - Path to the TIFF
+/// * `logger` - Logger for recording operations
+/// * `select` - Decides by block index which blocks to decode
+/// * `visit` - Receives each decoded block
+///
+/// # Returns
+/// The sample layout of the image
+pub fn read_blocks(input_path: &str, logger: &Logger,
+                   select: impl FnMut(&SampleLayout, usize) -> bool,
+                   visit: impl FnMut(&SampleLayout, &Block)) -> TiffResult<SampleLayout> {
+    let mut reader = TiffReader::new(logger);
+    let tiff = reader.load(input_path)?;
+    let ifd = tiff.main_ifd()
+        .ok_or_else(|| TiffError::GenericError("No IFDs found in TIFF file".to_string()))?;
+    read_ifd_blocks(&reader, ifd, input_path, select, visit)
+}
+
+/// Decode the blocks of an already parsed image at full sample width
+///
+/// # Arguments
+/// * `reader` - Reader that loaded the image's header
+/// * `ifd` - IFD of the image
+/// * `input_path` - Path to the TIFF
+/// * `select` - Decides by block index which blocks to decode
+/// * `visit` - Receives each decoded block
+///
+/// # Returns
+/// The sample layout of the image
+pub fn read_ifd_blocks(reader: &TiffReader, ifd: &IFD, input_path: &str,
+                       mut select: impl FnMut(&SampleLayout, usize) -> bool,
+                       mut visit: impl FnMut(&SampleLayout, &Block)) -> TiffResult<SampleLayout> {
+    let byte_order = reader.get_byte_order_handler()
+        .map(|handler| handler.byte_order())
+        .unwrap_or(ByteOrder::LittleEndian);
+
+    let mut file = BufReader::new(File::open(input_path)?);
+    let layout = read_layout(reader, ifd, &mut file)?;
+
+    let supported = match layout.format {
+        SampleFormat::Float => matches!(layout.bits, 32 | 64),
+        _ => matches!(layout.bits, 8 | 16 | 32),
+    };
+    if !supported {
+        return Err(TiffError::GenericError(format!("Unsupported {}-bit {:?} samples", layout.bits, layout.format)));
+    }
+
+    let (offset_tag, count_tag) = if ifd.has_tag(tags::TILE_OFFSETS) {
+        (tags::TILE_OFFSETS, tags::TILE_BYTE_COUNTS)
+    } else {
+        (tags::STRIP_OFFSETS, tags::STRIP_BYTE_COUNTS)
+    };
+    let offsets = reader.read_tag_values(&mut file, ifd, offset_tag)?;
+    let byte_counts = reader.read_tag_values(&mut file, ifd, count_tag)?;
+
+    let compression = CompressionFactory::create_handler_for_ifd(reader, ifd)?;
+    let predictor = ifd.get_tag_value(tags::PREDICTOR).unwrap_or(1);
+    if predictor == pred_consts::FLOATING_POINT as u64 {
+        return Err(TiffError::GenericError("The floating point predictor is not supported".to_string()));
+    }
+    info!("Reading {}x{} raster of {} band(s) with {}-bit {:?} samples ({})",
+          layout.width, layout.height, layout.bands, layout.bits, layout.format, compression.name());
+
+    let bytes = (layout.bits / 8) as usize;
+    let stride = if layout.planar { 1 } else { layout.bands };
+    let blocks_across = layout.blocks_across();
+    let mask = if layout.bits >= 64 { u64::MAX } else { (1u64 << layout.bits) - 1 };
+
+    for index in 0..layout.block_count() {
+        if !select(&layout, index) {
+            continue;
+        }
+        let (Some(&offset), Some(&byte_count)) = (offsets.get(index), byte_counts.get(index)) else {
+            warn!("Block {} is missing from {}", index, input_path);
+            continue;
+        };
+        let started = Instant::now();
+        file.seek(SeekFrom::Start(offset))?;
+        let mut compressed = vec![0u8; byte_count as usize];
+        file.read_exact(&mut compressed)?;
+        metrics::record_block_read(byte_count, started.elapsed());
+        let started = Instant::now();
+        let data = compression.decompress(&compressed)?;
+        metrics::record_decode(compression.name(), byte_count, data.len() as u64, started.elapsed());
+
+        let mut samples: Vec<u64> = data.chunks_exact(bytes)
+            .map(|sample| {
+                let mut word = [0u8; 8];
+                match byte_order {
+                    ByteOrder::LittleEndian => {
+                        word[..bytes].copy_from_slice(sample);
+                        u64::from_le_bytes(word)
+                    },
+                    ByteOrder::BigEndian => {
+                        word[8 - bytes..].copy_from_slice(sample);
+                        u64::from_be_bytes(word)
+                    },
+                }
+            })
+            .collect();
+
+        // Each sample is differenced against the same sample of the previous pixel
+        if predictor == pred_consts::HORIZONTAL_DIFFERENCING as u64 {
+            let row_samples = layout.block_width as usize * stride;
+            for row in samples.chunks_mut(row_samples) {
+                for i in stride..row.len() {
+                    row[i] = row[i].wrapping_add(row[i - stride]) & mask;
+                }
+            }
+        }
+
+        let position = index % layout.blocks_per_band();
+        let band = index / layout.blocks_per_band();
+        let (x, y) = ((position % blocks_across) as u32 * layout.block_width,
+                      (position / blocks_across) as u32 * layout.block_height);
+        let block = Block {
+            x,
+            y,
+            width: layout.block_width.min(layout.width - x),
+            height: layout.block_height.min(layout.height - y),
+            bands: if layout.planar { band..band + 1 } else { 0..layout.bands },
+            samples,
+            row_length: layout.block_width as usize,
+        };
+        visit(&layout, &block);
+    }
+
+    Ok(layout)
+}
+
+/// Read the values of the first bands of a TIFF's first image
+///
+/// # Arguments
+/// * `input_path` - Path to the TIFF
+/// * `max_bands` - Most bands to read
+/// * `logger` - Logger for recording operations
+///
+/// # Returns
+/// The values of each band in row-major order, with the sample layout
+pub fn read_bands(input_path: &str, max_bands: usize, logger: &Logger) -> TiffResult<(Vec<Vec<f64>>, SampleLayout)> {
+    let mut bands: Vec<Vec<f64>> = Vec::new();
+    let layout = read_blocks(
+        input_path, logger,
+        |layout, index| !layout.planar || index / layout.blocks_per_band() < max_bands,
+        |layout, block| {
+            if bands.is_empty() {
+                let pixels = layout.width as usize * layout.height as usize;
+                bands = vec![vec![f64::NAN; pixels]; layout.bands.min(max_bands)];
+            }
+            for band in block.bands.clone().filter(|&band| band < max_bands) {
+                for row in 0..block.height {
+                    for column in 0..block.width {
+                        let index = (block.y + row) as usize * layout.width as usize + (block.x + column) as usize;
+                        if let Some(raw) = block.sample(column, row, band) {
+                            bands[band][index] = layout.value(raw);
+                        }
+                    }
+                }
+            }
+        })?;
+
+    // Blocks that were missing leave their pixels NaN
+    let pixels = layout.width as usize * layout.height as usize;
+    bands.resize(layout.bands.min(max_bands), vec![f64::NAN; pixels]);
+    Ok((bands, layout))
+}
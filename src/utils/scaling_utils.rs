@@ -0,0 +1,153 @@
+//! Radiometric scaling utilities
+//!
+//! Many rasters store physical quantities (reflectance, temperature, ...)
+//! as integer DNs together with a per-band linear scaling,
+//! `value = DN * scale + offset`. GDAL records it in the GDAL_METADATA tag
+//! as `SCALE` and `OFFSET` items with the `scale` and `offset` roles.
+//! These helpers read that scaling, apply it and write the scaled values
+//! as float32 arrays.
+
+use std::fs::File;
+use std::io::{BufWriter, Write};
+
+use log::{debug, info};
+
+use crate::tiff::errors::TiffResult;
+use crate::utils::tensor_utils::{self, NamedTensor};
+
+/// Linear scaling of one band, `value = DN * scale + offset`
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct BandScaling {
+    /// Factor applied to the stored values
+    pub scale: f64,
+    /// Value added after scaling
+    pub offset: f64,
+}
+
+impl BandScaling {
+    /// Scaling that leaves values unchanged
+    pub const IDENTITY: BandScaling = BandScaling { scale: 1.0, offset: 0.0 };
+
+    /// Whether the scaling leaves values unchanged
+    pub fn is_identity(&self) -> bool {
+        *self == Self::IDENTITY
+    }
+
+    /// Scale one stored value
+    pub fn apply(&self, value: f64) -> f64 {
+        value * self.scale + self.offset
+    }
+}
+
+/// Value of an attribute in the opening tag of an XML element
+fn attribute<'a>(tag: &'a str, name: &str) -> Option<&'a str> {
+    let pattern = format!("{}=\"", name);
+    let start = tag.find(&pattern)? + pattern.len();
+    let end = tag[start..].find('"')?;
+    Some(&tag[start..start + end])
+}
+
+/// Read per-band scale and offset from GDAL_METADATA XML
+///
+/// Items are matched by their `role` attribute, or by their name when it
+/// is missing; the `sample` attribute selects the zero-based band. Bands
+/// without items get the identity scaling.
+///
+/// # Arguments
+/// * `xml` - Content of the GDAL_METADATA tag
+///
+/// # Returns
+/// One scaling per band up to the last band with an item, empty if there
+/// are none
+pub fn scaling_from_gdal_metadata(xml: &str) -> Vec<BandScaling> {
+    let mut bands: Vec<BandScaling> = Vec::new();
+
+    for item in xml.split("<Item").skip(1) {
+        let Some((tag, rest)) = item.split_once('>') else {
+            continue;
+        };
+        let Some(value) = rest.split("</Item>").next().and_then(|text| text.trim().parse::<f64>().ok()) else {
+            continue;
+        };
+        let role = attribute(tag, "role")
+            .or_else(|| attribute(tag, "name"))
+            .map(|role| role.to_lowercase())
+            .unwrap_or_default();
+        if role != "scale" && role != "offset" {
+            continue;
+        }
+
+        let band = attribute(tag, "sample").and_then(|sample| sample.parse::<usize>().ok()).unwrap_or(0);
+        if bands.len() <= band {
+            bands.resize(band + 1, BandScaling::IDENTITY);
+        }
+        if role == "scale" {
+            bands[band].scale = value;
+        } else {
+            bands[band].offset = value;
+        }
+        debug!("GDAL metadata {} of band {}: {}", role, band + 1, value);
+    }
+
+    bands
+}
+
+/// Scale stored values to physical units
+///
+/// NoData cells keep their stored value so they can still be recognized.
+///
+/// # Arguments
+/// * `data` - Values as extracted
+/// * `scaling` - Scaling of the band
+/// * `nodata` - NoData value of the source, if any
+///
+/// # Returns
+/// The scaled values as float32
+pub fn apply_scaling(data: &[f64], scaling: BandScaling, nodata: Option<f64>) -> Vec<f32> {
+    info!("Scaling values by {} with offset {}", scaling.scale, scaling.offset);
+
+    data.iter()
+        .map(|&value| {
+            if nodata == Some(value) { value as f32 } else { scaling.apply(value) as f32 }
+        })
+        .collect()
+}
+
+/// Save scaled values
+///
+/// Tensor formats record the scaling in their metadata; CSV is written as
+/// a plain grid of values.
+///
+/// # Arguments
+/// * `path` - Path of the file to write
+/// * `format` - "csv", "npy", "npz" or "safetensors"
+/// * `shape` - Array shape (rows, columns)
+/// * `values` - Scaled values in row-major order
+/// * `scaling` - Scaling that was applied
+///
+/// # Returns
+/// Result indicating success or an error
+pub fn save_scaled(path: &str, format: &str, shape: &[usize], values: &[f32], scaling: BandScaling) -> TiffResult<()> {
+    match format.to_lowercase().as_str() {
+        "csv" => {
+            let columns = shape.get(1).copied().unwrap_or(values.len()).max(1);
+            let mut writer = BufWriter::new(File::create(path)?);
+            for row in values.chunks(columns) {
+                let line: Vec<String> = row.iter().map(|v| v.to_string()).collect();
+                writeln!(writer, "{}", line.join(","))?;
+            }
+            writer.flush()?;
+        },
+        _ => {
+            let tensors = [NamedTensor::from_f32("data", shape, values)];
+            let metadata = [
+                ("scale".to_string(), scaling.scale.to_string()),
+                ("offset".to_string(), scaling.offset.to_string()),
+            ];
+            tensor_utils::save_tensors(path, format, &tensors, &metadata)?;
+        }
+    }
+
+    info!("Saved {:?} scaled values to {}", shape, path);
+    Ok(())
+}
@@ -0,0 +1,229 @@
+//! Scratch buffers that spill to disk
+//!
+//! Some operations need intermediate buffers as large as their output, or
+//! several times larger: a mosaic accumulates weighted sums per band, the
+//! colormap writers keep the class values beside the colored image. Under
+//! `--max-memory` such buffers would push a run over its budget. A
+//! `ScratchBuffer` stays in memory while intermediates use at most half of
+//! the budget; beyond that it lives in a memory-mapped file in the scratch
+//! directory (`--temp-dir`, the system's temporary directory by default),
+//! which the operating system pages in and out as needed. The file is
+//! removed when the buffer is dropped.
+
+use std::fs::{self, OpenOptions};
+use std::ops::{Deref, DerefMut};
+use std::path::PathBuf;
+use std::sync::atomic::{AtomicUsize, Ordering};
+use std::sync::Mutex;
+
+use image::{DynamicImage, ImageBuffer, Luma, Pixel};
+use log::{debug, info, warn};
+use memmap2::MmapMut;
+
+use crate::tiff::errors::{TiffError, TiffResult};
+use crate::utils::memory_utils::{self, MEGABYTE};
+
+/// Share of the memory budget intermediates may hold in memory, as a divisor
+const MEMORY_SHARE: usize = 2;
+
+/// Directory for scratch files, None for the system's temporary directory
+static SCRATCH_DIR: Mutex<Option<PathBuf>> = Mutex::new(None);
+
+/// Bytes of scratch buffers currently held in memory
+static IN_MEMORY: AtomicUsize = AtomicUsize::new(0);
+
+/// Numbers scratch files so that buffers never share one
+static NEXT_FILE: AtomicUsize = AtomicUsize::new(0);
+
+/// Sample types a scratch buffer can hold
+///
+/// Implemented only for plain numbers, whose all-zero bit pattern is zero
+/// and which may be read from any suitably aligned bytes.
+///
+/// # Safety
+///
+/// File-backed buffers hand out the bytes of a zero-filled mapping as
+/// values of the type, so every bit pattern, all zeros included, must be
+/// a valid value. References, `bool`, `char` and enums are not.
+pub unsafe trait ScratchElement: Copy + Default + 'static {}
+
+// SAFETY: plain numbers are valid for any bit pattern
+unsafe impl ScratchElement for u8 {}
+unsafe impl ScratchElement for u16 {}
+unsafe impl ScratchElement for f32 {}
+unsafe impl ScratchElement for f64 {}
+
+/// Set the directory scratch files are created in
+///
+/// # Arguments
+/// * `dir` - Directory, None for the system's temporary directory
+pub fn set_scratch_dir(dir: Option<PathBuf>) {
+    *SCRATCH_DIR.lock().unwrap_or_else(|poisoned| poisoned.into_inner()) = dir;
+}
+
+/// The directory scratch files are created in
+pub fn scratch_dir() -> PathBuf {
+    SCRATCH_DIR.lock().unwrap_or_else(|poisoned| poisoned.into_inner()).clone()
+        .unwrap_or_else(std::env::temp_dir)
+}
+
+/// A zero-filled buffer, in memory or in a memory-mapped file
+pub struct ScratchBuffer<T: ScratchElement> {
+    /// Where the values are kept
+    storage: Storage<T>,
+}
+
+/// Backing of a scratch buffer
+enum Storage<T> {
+    /// An ordinary vector, counted against the budget
+    Memory(Vec<T>),
+    /// A mapped scratch file
+    Mapped(MappedFile),
+}
+
+/// A scratch file and its mapping
+struct MappedFile {
+    /// The mapping, taken out before the file is removed
+    map: Option<MmapMut>,
+    /// Path of the file
+    path: PathBuf,
+    /// Number of values in the file
+    len: usize,
+}
+
+impl<T: ScratchElement> ScratchBuffer<T> {
+    /// Allocate a zero-filled buffer, spilling it to disk if it would
+    /// exceed the memory budget
+    ///
+    /// # Arguments
+    /// * `len` - Number of values
+    /// * `what` - Description of the buffer, for the log
+    ///
+    /// # Returns
+    /// The buffer, or an error if a scratch file could not be created
+    pub fn zeroed(len: usize, what: &str) -> TiffResult<Self> {
+        let bytes = len * std::mem::size_of::<T>();
+        let held = IN_MEMORY.load(Ordering::Relaxed);
+        let fits = match memory_utils::memory_budget() {
+            Some(budget) => held + bytes <= budget / MEMORY_SHARE,
+            None => true,
+        };
+        if fits || bytes == 0 {
+            IN_MEMORY.fetch_add(bytes, Ordering::Relaxed);
+            return Ok(ScratchBuffer { storage: Storage::Memory(vec![T::default(); len]) });
+        }
+        Self::spilled(len, what)
+    }
+
+    /// Allocate a zero-filled buffer in a scratch file, whatever the budget
+    ///
+    /// # Arguments
+    /// * `len` - Number of values
+    /// * `what` - Description of the buffer, for the log
+    ///
+    /// # Returns
+    /// The buffer, or an error if the file could not be created or mapped
+    pub fn spilled(len: usize, what: &str) -> TiffResult<Self> {
+        let bytes = len * std::mem::size_of::<T>();
+        let dir = scratch_dir();
+        let path = dir.join(format!("rasterkit-scratch-{}-{}.bin",
+                                    std::process::id(), NEXT_FILE.fetch_add(1, Ordering::Relaxed)));
+        let scratch_error = |e: std::io::Error| TiffError::GenericError(format!(
+            "Cannot spill {} ({:.1} MB) to {}: {}", what, bytes as f64 / MEGABYTE as f64, path.display(), e));
+
+        let file = OpenOptions::new().read(true).write(true).create_new(true).open(&path).map_err(scratch_error)?;
+        // A file grown with set_len reads as zeros, usually without using disk
+        let map = file.set_len(bytes.max(1) as u64)
+            // SAFETY: the file was just created under a unique name and no
+            // one else writes to it while it is mapped
+            .and_then(|_| unsafe { MmapMut::map_mut(&file) });
+        let map = match map {
+            Ok(map) => map,
+            Err(e) => {
+                let _ = fs::remove_file(&path);
+                return Err(scratch_error(e));
+            },
+        };
+
+        info!("Spilled {} ({:.1} MB) to {}", what, bytes as f64 / MEGABYTE as f64, path.display());
+        Ok(ScratchBuffer { storage: Storage::Mapped(MappedFile { map: Some(map), path, len }) })
+    }
+
+    /// Whether the buffer lives in a scratch file
+    pub fn is_spilled(&self) -> bool {
+        matches!(self.storage, Storage::Mapped(_))
+    }
+}
+
+impl<T: ScratchElement> Deref for ScratchBuffer<T> {
+    type Target = [T];
+
+    fn deref(&self) -> &[T] {
+        match &self.storage {
+            Storage::Memory(values) => values,
+            Storage::Mapped(MappedFile { map: Some(map), len, .. }) => {
+                // SAFETY: the mapping is page-aligned and holds `len` values
+                // of a plain number type, for which any bytes are valid
+                unsafe { std::slice::from_raw_parts(map.as_ptr() as *const T, *len) }
+            },
+            Storage::Mapped(MappedFile { map: None, .. }) => &[],
+        }
+    }
+}
+
+impl<T: ScratchElement> DerefMut for ScratchBuffer<T> {
+    fn deref_mut(&mut self) -> &mut [T] {
+        match &mut self.storage {
+            Storage::Memory(values) => values,
+            Storage::Mapped(MappedFile { map: Some(map), len, .. }) => {
+                // SAFETY: as in deref, and the mapping is borrowed mutably
+                unsafe { std::slice::from_raw_parts_mut(map.as_mut_ptr() as *mut T, *len) }
+            },
+            Storage::Mapped(MappedFile { map: None, .. }) => &mut [],
+        }
+    }
+}
+
+impl<T: ScratchElement> Drop for ScratchBuffer<T> {
+    fn drop(&mut self) {
+        if let Storage::Memory(values) = &self.storage {
+            IN_MEMORY.fetch_sub(values.len() * std::mem::size_of::<T>(), Ordering::Relaxed);
+        }
+    }
+}
+
+impl Drop for MappedFile {
+    fn drop(&mut self) {
+        // Unmap first; some systems refuse to remove a mapped file
+        self.map.take();
+        match fs::remove_file(&self.path) {
+            Ok(()) => debug!("Removed scratch file {}", self.path.display()),
+            Err(e) => warn!("Failed to remove scratch file {}: {}", self.path.display(), e),
+        }
+    }
+}
+
+/// Luminance of an image as a scratch-backed 8-bit image
+///
+/// # Arguments
+/// * `image` - The image, usually an extracted window
+/// * `what` - Description of the plane, for the log
+///
+/// # Returns
+/// The 8-bit luminance, converted as `DynamicImage::to_luma8` does
+pub fn luma_plane(image: &DynamicImage, what: &str) -> TiffResult<ImageBuffer<Luma<u8>, ScratchBuffer<u8>>> {
+    let (width, height) = (image.width(), image.height());
+    let mut plane = ScratchBuffer::zeroed(width as usize * height as usize, what)?;
+    match image {
+        // Extracted windows are RGB; convert them without a second copy
+        DynamicImage::ImageRgb8(rgb) => {
+            for (pixel, value) in rgb.pixels().zip(plane.iter_mut()) {
+                *value = pixel.to_luma()[0];
+            }
+        },
+        DynamicImage::ImageLuma8(luma) => plane.copy_from_slice(luma.as_raw()),
+        other => plane.copy_from_slice(other.to_luma8().as_raw()),
+    }
+    ImageBuffer::from_raw(width, height, plane)
+        .ok_or_else(|| TiffError::GenericError(format!("{} does not match its {}x{} size", what, width, height)))
+}
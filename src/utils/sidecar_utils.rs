@@ -0,0 +1,289 @@
+//! Sidecar georeferencing utilities
+//!
+//! Scanned maps and plain TIFFs often carry their georeferencing next to the
+//! image instead of inside it: an ESRI world file (`.tfw`, `.tifw`, `.wld`)
+//! with the affine transform, a GDAL `.aux.xml` (PAM dataset) with the
+//! transform and the CRS as WKT, or a `.prj` holding just the WKT. This
+//! module looks those files up and turns them into the corner-based
+//! `Affine` geotransform used throughout the extractor.
+
+use std::fs;
+use std::path::{Path, PathBuf};
+
+use log::{debug, info, warn};
+
+use crate::coordinate::Affine;
+use crate::tiff::errors::{TiffError, TiffResult};
+use crate::tiff::is_geotiff_tag;
+use crate::tiff::types::TIFF;
+
+/// Georeferencing recovered from files next to an image
+#[derive(Debug, Clone)]
+pub struct SidecarGeoreference {
+    /// Corner-based geotransform
+    pub geotransform: Affine,
+    /// EPSG code of the CRS, when the sidecars name one
+    pub epsg: Option<u32>,
+    /// CRS as WKT, when a sidecar provides it
+    pub wkt: Option<String>,
+    /// Path of the sidecar that supplied the geotransform
+    pub source: String,
+}
+
+impl SidecarGeoreference {
+    /// Geotransform of a window starting at pixel (x, y)
+    ///
+    /// # Arguments
+    /// * `x` - Column of the window origin
+    /// * `y` - Row of the window origin
+    ///
+    /// # Returns
+    /// The geotransform with its origin moved to the window corner
+    pub fn shifted(&self, x: u32, y: u32) -> Affine {
+        self.geotransform.translated(x, y)
+    }
+}
+
+/// Check whether a TIFF carries its own GeoTIFF tags
+///
+/// # Arguments
+/// * `tiff` - Parsed TIFF structure
+///
+/// # Returns
+/// `true` if any IFD contains a GeoTIFF tag
+pub fn has_internal_georeference(tiff: &TIFF) -> bool {
+    tiff.ifds.iter().any(|ifd| ifd.entries.iter().any(|entry| is_geotiff_tag(entry.tag)))
+}
+
+/// Look for sidecar georeferencing next to an image
+///
+/// A geotransform from `<image>.aux.xml` takes precedence over a world
+/// file. The CRS comes from the `.aux.xml` SRS element, falling back to a
+/// `.prj` file.
+///
+/// # Arguments
+/// * `image_path` - Path of the image the sidecars belong to
+///
+/// # Returns
+/// The recovered georeferencing, `None` if no usable sidecar exists, or an
+/// error if a sidecar exists but cannot be parsed
+pub fn find_sidecar_georeference(image_path: &str) -> TiffResult<Option<SidecarGeoreference>> {
+    let aux_path = format!("{}.aux.xml", image_path);
+    let (aux_transform, aux_wkt) = if Path::new(&aux_path).exists() {
+        info!("Reading PAM sidecar {}", aux_path);
+        read_aux_xml(&aux_path)?
+    } else {
+        (None, None)
+    };
+
+    let (geotransform, source) = match aux_transform {
+        Some(gt) => (gt, aux_path),
+        None => match find_world_file(image_path) {
+            Some(world_path) => {
+                let source = world_path.to_string_lossy().to_string();
+                info!("Reading world file {}", source);
+                (read_world_file(&source)?, source)
+            },
+            None => {
+                debug!("No geotransform sidecar found for {}", image_path);
+                return Ok(None);
+            }
+        }
+    };
+
+    let wkt = aux_wkt.or_else(|| {
+        let prj_path = Path::new(image_path).with_extension("prj");
+        fs::read_to_string(&prj_path).ok().map(|text| text.trim().to_string())
+    });
+    let epsg = wkt.as_deref().and_then(epsg_from_wkt);
+
+    match epsg {
+        Some(code) => info!("Sidecar georeferencing from {} in EPSG:{}", source, code),
+        None => info!("Sidecar georeferencing from {} without a known EPSG code", source),
+    }
+
+    Ok(Some(SidecarGeoreference { geotransform, epsg, wkt, source }))
+}
+
+/// Find the world file belonging to an image
+///
+/// Tries the short (`.tfw`), long (`.tifw`) and generic (`.wld`) forms in
+/// both lower and upper case.
+fn find_world_file(image_path: &str) -> Option<PathBuf> {
+    let path = Path::new(image_path);
+    let ext = path.extension().and_then(|e| e.to_str()).unwrap_or("");
+
+    let mut candidates = Vec::new();
+    if let Some(short) = world_file_extension(ext) {
+        candidates.push(short);
+    }
+    candidates.push(format!("{}w", ext));
+    candidates.push("wld".to_string());
+
+    candidates.iter()
+        .flat_map(|c| [c.to_lowercase(), c.to_uppercase()])
+        .map(|c| path.with_extension(c))
+        .find(|candidate| candidate.exists())
+}
+
+/// Short world file extension for an image extension (tif -> tfw, png -> pgw)
+fn world_file_extension(ext: &str) -> Option<String> {
+    let chars: Vec<char> = ext.chars().collect();
+    if chars.len() < 2 {
+        return None;
+    }
+    Some(format!("{}{}w", chars[0], chars[chars.len() - 1]))
+}
+
+/// Read an ESRI world file
+///
+/// World files list A, D, B, E, C, F on six lines, where C/F locate the
+/// centre of the top-left pixel. The result is shifted to the pixel corner.
+///
+/// # Arguments
+/// * `path` - Path of the world file
+///
+/// # Returns
+/// The corner-based geotransform or an error if the file is malformed
+pub fn read_world_file(path: &str) -> TiffResult<Affine> {
+    let text = fs::read_to_string(path)
+        .map_err(|e| TiffError::GenericError(format!("Failed to read world file {}: {}", path, e)))?;
+
+    let values = text.split_whitespace()
+        .take(6)
+        .map(|v| v.parse::<f64>()
+            .map_err(|_| TiffError::GenericError(format!("Invalid value '{}' in world file {}", v, path))))
+        .collect::<TiffResult<Vec<f64>>>()?;
+    if values.len() < 6 {
+        return Err(TiffError::GenericError(format!("World file {} needs six values", path)));
+    }
+
+    let (a, d, b, e, c, f) = (values[0], values[1], values[2], values[3], values[4], values[5]);
+    Ok(Affine::from_gdal([c - a / 2.0 - b / 2.0, a, b, f - d / 2.0 - e / 2.0, d, e]))
+}
+
+/// Write an ESRI world file for a geotransform
+///
+/// # Arguments
+/// * `path` - Destination path
+/// * `geotransform` - Corner-based geotransform
+///
+/// # Returns
+/// Result indicating success or an error
+pub fn write_world_file(path: &str, geotransform: &Affine) -> TiffResult<()> {
+    let gt = geotransform;
+    let (center_x, center_y) = gt.cell_center(0, 0);
+    let text = format!("{}\n{}\n{}\n{}\n{}\n{}\n",
+                       gt.pixel_width, gt.column_rotation, gt.row_rotation, gt.pixel_height, center_x, center_y);
+
+    fs::write(path, text)
+        .map_err(|e| TiffError::GenericError(format!("Failed to write world file {}: {}", path, e)))?;
+    info!("Wrote world file {}", path);
+    Ok(())
+}
+
+/// World file path for an image, using the short extension form
+///
+/// # Arguments
+/// * `image_path` - Path of the image
+///
+/// # Returns
+/// The sidecar path, e.g. `out.tfw` for `out.tif`
+pub fn world_file_path(image_path: &str) -> String {
+    let path = Path::new(image_path);
+    let ext = path.extension().and_then(|e| e.to_str()).unwrap_or("");
+    let world_ext = world_file_extension(ext).unwrap_or_else(|| "wld".to_string());
+    path.with_extension(world_ext).to_string_lossy().to_string()
+}
+
+/// Read the geotransform and SRS from a GDAL `.aux.xml` file
+///
+/// # Arguments
+/// * `path` - Path of the `.aux.xml` file
+///
+/// # Returns
+/// The geotransform and WKT, each `None` when the element is missing
+fn read_aux_xml(path: &str) -> TiffResult<(Option<Affine>, Option<String>)> {
+    let xml = fs::read_to_string(path)
+        .map_err(|e| TiffError::GenericError(format!("Failed to read {}: {}", path, e)))?;
+
+    let geotransform = match element_text(&xml, "GeoTransform") {
+        Some(text) => {
+            let values = text.split(',')
+                .map(|v| v.trim().parse::<f64>())
+                .collect::<Result<Vec<f64>, _>>()
+                .map_err(|_| TiffError::GenericError(format!("Invalid GeoTransform in {}", path)))?;
+            if values.len() != 6 {
+                return Err(TiffError::GenericError(format!(
+                    "GeoTransform in {} has {} values, expected 6", path, values.len())));
+            }
+            Some(Affine::from_gdal([values[0], values[1], values[2], values[3], values[4], values[5]]))
+        },
+        None => None,
+    };
+
+    let wkt = element_text(&xml, "SRS").map(|text| unescape_xml(text.trim()));
+    if geotransform.is_none() && wkt.is_none() {
+        warn!("{} holds neither a GeoTransform nor an SRS", path);
+    }
+    Ok((geotransform, wkt))
+}
+
+/// Text content of the first `<name ...>...</name>` element
+fn element_text<'x>(xml: &'x str, name: &str) -> Option<&'x str> {
+    let open = format!("<{}", name);
+    let mut search_from = 0;
+    while let Some(pos) = xml[search_from..].find(&open) {
+        let start = search_from + pos;
+        let after_name = start + open.len();
+        // Skip longer element names sharing the prefix (e.g. <SRSName>)
+        match xml[after_name..].chars().next() {
+            Some('>') | Some(' ') | Some('\t') | Some('\n') | Some('\r') => {
+                let content_start = after_name + xml[after_name..].find('>')? + 1;
+                let close = format!("</{}>", name);
+                let content_end = content_start + xml[content_start..].find(&close)?;
+                return Some(&xml[content_start..content_end]);
+            },
+            _ => search_from = after_name,
+        }
+    }
+    None
+}
+
+/// Undo the XML entity escaping GDAL applies to WKT
+fn unescape_xml(text: &str) -> String {
+    text.replace("&quot;", "\"")
+        .replace("&apos;", "'")
+        .replace("&lt;", "<")
+        .replace("&gt;", ">")
+        .replace("&amp;", "&")
+}
+
+/// Extract the EPSG code of the outermost CRS in a WKT string
+///
+/// Handles WKT1 `AUTHORITY["EPSG","N"]`, WKT2 `ID["EPSG",N]` and plain
+/// `EPSG:N` definitions. The outermost authority is the last one listed.
+///
+/// # Arguments
+/// * `wkt` - CRS definition
+///
+/// # Returns
+/// The EPSG code, if one can be found
+pub fn epsg_from_wkt(wkt: &str) -> Option<u32> {
+    let upper = wkt.to_uppercase();
+    if let Some(code) = upper.trim().strip_prefix("EPSG:") {
+        return code.trim().parse().ok();
+    }
+
+    let compact: String = upper.chars().filter(|c| !c.is_whitespace()).collect();
+    ["AUTHORITY[\"EPSG\",", "ID[\"EPSG\","].iter()
+        .filter_map(|marker| compact.rfind(marker).map(|pos| pos + marker.len()))
+        .max()
+        .and_then(|start| {
+            let digits: String = compact[start..].chars()
+                .skip_while(|c| *c == '"')
+                .take_while(|c| c.is_ascii_digit())
+                .collect();
+            digits.parse().ok()
+        })
+}
@@ -0,0 +1,115 @@
+//! Band splitting utilities
+//!
+//! Writes every band of a multi-band raster to a single-band GeoTIFF of
+//! its own, keeping the sample type, georeferencing and NoData value of
+//! the source, so each band can be processed or published on its own.
+
+use std::path::Path;
+
+use log::{info, warn};
+
+use crate::extractor::{self, BufferLayout, Region};
+use crate::tiff::builder::TiffBuilder;
+use crate::tiff::constants::{field_types, sample_format, tags};
+use crate::tiff::errors::{TiffError, TiffResult};
+use crate::tiff::ifd::{IFD, IFDEntry};
+use crate::tiff::TiffReader;
+use crate::utils::logger::Logger;
+use crate::utils::{memory_utils, tiff_extraction_utils};
+
+/// Path of the raster written for a band
+///
+/// # Arguments
+/// * `output_path` - Output path given by the user
+/// * `band` - One-based band number
+///
+/// # Returns
+/// The output path with `_band<N>` appended to its stem
+pub fn band_output_path(output_path: &str, band: usize) -> String {
+    let path = Path::new(output_path);
+    let stem = path.file_stem().and_then(|stem| stem.to_str()).unwrap_or("band");
+    let extension = path.extension().and_then(|extension| extension.to_str()).unwrap_or("tif");
+    path.with_file_name(format!("{}_band{}.{}", stem, band, extension))
+        .to_string_lossy()
+        .into_owned()
+}
+
+/// Split a raster into one single-band GeoTIFF per band
+///
+/// Samples are written uncompressed at their stored width and format.
+///
+/// # Arguments
+/// * `input_path` - Multi-band TIFF to split
+/// * `output_path` - Output path, suffixed with each band number
+/// * `logger` - Logger for recording operations
+///
+/// # Returns
+/// The paths of the written rasters, in band order
+pub fn split_bands(input_path: &str, output_path: &str, logger: &Logger) -> TiffResult<Vec<String>> {
+    let shape = extractor::describe_window(input_path, None, logger)?;
+    if shape.bytes_per_sample == 0 {
+        return Err(TiffError::GenericError(format!(
+            "The sub-byte samples of {} cannot be split into bands", input_path)));
+    }
+    if shape.bands < 2 {
+        warn!("{} has a single band, writing it unchanged", input_path);
+    }
+
+    memory_utils::check_fits(&format!("Splitting the {}x{} raster", shape.width, shape.height),
+                             shape.byte_len() as u64)?;
+    let mut samples = vec![0u8; shape.byte_len()];
+    extractor::decode_into_bytes(input_path, None, BufferLayout::BandSequential, &mut samples, logger)?;
+
+    // Samples are decoded in the byte order of this machine, outputs are little-endian
+    if cfg!(target_endian = "big") {
+        samples.chunks_exact_mut(shape.bytes_per_sample).for_each(|sample| sample.reverse());
+    }
+
+    let mut reader = TiffReader::new(logger);
+    let tiff = reader.load(input_path)?;
+    let source_ifd = tiff.main_ifd()
+        .ok_or_else(|| TiffError::GenericError("No IFDs found in input file".to_string()))?;
+    let nodata = source_ifd.has_tag(tags::GDAL_NODATA)
+        .then(|| tiff_extraction_utils::extract_nodata_value(source_ifd, &reader));
+    let geotransform = (source_ifd.has_tag(tags::MODEL_TIEPOINT_TAG) && source_ifd.has_tag(tags::MODEL_PIXEL_SCALE_TAG))
+        .then(|| tiff_extraction_utils::read_geotiff_info(
+            source_ifd, &reader, reader.get_file_path().unwrap_or(input_path)));
+    let extent = Region::new(0, 0, shape.width, shape.height);
+
+    let format = match (shape.floating_point, shape.signed) {
+        (true, _) => sample_format::IEEEFP,
+        (false, true) => sample_format::SIGNED,
+        (false, false) => sample_format::UNSIGNED,
+    };
+    let band_len = shape.byte_len() / shape.bands;
+
+    let mut written = Vec::with_capacity(shape.bands);
+    for (band, plane) in samples.chunks_exact(band_len).enumerate() {
+        let mut builder = TiffBuilder::new(logger, reader.is_big_tiff());
+        let ifd_index = builder.add_ifd(IFD::new(0, 0));
+        builder.add_basic_gray_tags(ifd_index, shape.width, shape.height, (shape.bytes_per_sample * 8) as u16);
+        if format != sample_format::UNSIGNED {
+            // The default sample range only holds for unsigned integers
+            builder.ifds[ifd_index].entries.retain(|e|
+                e.tag != tags::MIN_SAMPLE_VALUE && e.tag != tags::MAX_SAMPLE_VALUE);
+            builder.ifds[ifd_index].add_entry(IFDEntry::new(
+                tags::SAMPLE_FORMAT, field_types::SHORT, 1, format as u64));
+        }
+        builder.setup_single_strip(ifd_index, plane.to_vec());
+
+        if let Some((pixel_scale, tiepoint)) = &geotransform {
+            builder.adjust_geotiff_for_region(ifd_index, &extent, pixel_scale, tiepoint)?;
+        }
+        builder.copy_geotiff_tags(ifd_index, source_ifd, &mut reader)?;
+        if let Some(nodata) = &nodata {
+            builder.add_nodata_tag(ifd_index, nodata);
+        }
+
+        let path = band_output_path(output_path, band + 1);
+        builder.write(&path)?;
+        info!("Wrote band {} of {} to {}", band + 1, shape.bands, path);
+        written.push(path);
+    }
+
+    Ok(written)
+}
@@ -0,0 +1,909 @@
+//! Minimal SQLite database writer and reader
+//!
+//! Writes a complete SQLite 3 database file in a single pass, without linking
+//! against SQLite. Only what the exporters need is supported: rowid tables
+//! filled once, small single-page indexes (for PRIMARY KEY/UNIQUE columns of
+//! metadata tables) and R*Tree virtual tables, which are stored through their
+//! shadow tables exactly as SQLite's rtree module lays them out. Rows must
+//! fit in a page, so overflow pages are never produced.
+//!
+//! Reading is limited to scanning the rows of one table, which is all the
+//! catalog queries need from a GeoPackage index.
+
+use std::fs::File;
+use std::io::{BufWriter, Write};
+use std::time::{SystemTime, UNIX_EPOCH};
+
+use crate::tiff::errors::{TiffError, TiffResult};
+
+/// Page size of the written database
+const PAGE_SIZE: usize = 4096;
+
+/// Size of the database header at the start of page 1
+const DB_HEADER_SIZE: usize = 100;
+
+/// Largest payload stored without overflow pages in a table leaf (U - 35)
+const MAX_TABLE_PAYLOAD: usize = PAGE_SIZE - 35;
+
+/// Node size SQLite's rtree module picks for a new table (page size - 64)
+const RTREE_NODE_SIZE: usize = PAGE_SIZE - 64;
+
+/// Size of one 2-D R*Tree cell: 64-bit id and four 32-bit floats
+const RTREE_CELL_SIZE: usize = 24;
+
+/// B-tree page types
+const PAGE_TABLE_INTERIOR: u8 = 0x05;
+const PAGE_TABLE_LEAF: u8 = 0x0D;
+const PAGE_INDEX_LEAF: u8 = 0x0A;
+
+/// An R*Tree cell: entry id (or child index) and [min_x, max_x, min_y, max_y]
+type RtreeCell = (i64, [f64; 4]);
+
+/// A value stored in a table column
+#[derive(Debug, Clone, PartialEq)]
+pub enum SqlValue {
+    Null,
+    Integer(i64),
+    Real(f64),
+    Text(String),
+    Blob(Vec<u8>),
+}
+
+/// An entry of the sqlite_schema table
+struct SchemaEntry {
+    kind: &'static str,
+    name: String,
+    table_name: String,
+    root_page: u32,
+    sql: Option<String>,
+}
+
+/// Write-once SQLite database builder
+///
+/// Tables are encoded into pages as they are added; `write` then builds the
+/// schema table on page 1 and saves the file.
+pub struct SqliteWriter {
+    /// Encoded pages; index 0 is page 1, filled in by `write`
+    pages: Vec<Vec<u8>>,
+    /// Schema entries in creation order
+    schema: Vec<SchemaEntry>,
+    /// Application id stored in the header (e.g. "GPKG")
+    application_id: u32,
+    /// User version stored in the header
+    user_version: u32,
+}
+
+impl Default for SqliteWriter {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl SqliteWriter {
+    /// Create an empty database
+    pub fn new() -> Self {
+        SqliteWriter {
+            pages: vec![Vec::new()],
+            schema: Vec::new(),
+            application_id: 0,
+            user_version: 0,
+        }
+    }
+
+    /// Set the application id and user version of the header
+    ///
+    /// # Arguments
+    /// * `application_id` - Application id (GeoPackage uses 0x47504B47)
+    /// * `user_version` - User version (GeoPackage stores its version here)
+    pub fn set_application_id(&mut self, application_id: u32, user_version: u32) {
+        self.application_id = application_id;
+        self.user_version = user_version;
+    }
+
+    /// Add a rowid table with its rows
+    ///
+    /// Rows must be given in ascending rowid order. A column declared as
+    /// INTEGER PRIMARY KEY aliases the rowid and must be passed as `Null`.
+    ///
+    /// # Arguments
+    /// * `name` - Table name
+    /// * `sql` - The CREATE TABLE statement
+    /// * `rows` - (rowid, column values) pairs
+    ///
+    /// # Returns
+    /// Result indicating success or an error
+    pub fn add_table<I>(&mut self, name: &str, sql: &str, rows: I) -> TiffResult<()>
+    where
+        I: IntoIterator<Item = (i64, Vec<SqlValue>)>,
+    {
+        let mut cells = Vec::new();
+        let mut previous = None;
+
+        for (rowid, values) in rows {
+            if previous.is_some_and(|p| p >= rowid) {
+                return Err(TiffError::GenericError(format!(
+                    "Rows of table {} are not in ascending rowid order", name)));
+            }
+            previous = Some(rowid);
+
+            let payload = encode_record(&values);
+            if payload.len() > MAX_TABLE_PAYLOAD {
+                return Err(TiffError::GenericError(format!(
+                    "Row {} of table {} is too large ({} bytes)", rowid, name, payload.len())));
+            }
+
+            let mut cell = Vec::with_capacity(payload.len() + 18);
+            put_varint(&mut cell, payload.len() as u64);
+            put_varint(&mut cell, rowid as u64);
+            cell.extend_from_slice(&payload);
+            cells.push((rowid, cell));
+        }
+
+        let root_page = self.build_table_btree(cells, false)?;
+        self.schema.push(SchemaEntry {
+            kind: "table",
+            name: name.to_string(),
+            table_name: name.to_string(),
+            root_page,
+            sql: Some(sql.to_string()),
+        });
+
+        Ok(())
+    }
+
+    /// Add the automatic index SQLite keeps for a PRIMARY KEY or UNIQUE constraint
+    ///
+    /// Only indexes fitting in a single page are supported, which covers the
+    /// small metadata tables this writer is used for.
+    ///
+    /// # Arguments
+    /// * `table` - Table the index belongs to
+    /// * `number` - Index number (1 for the first constraint, 2 for the next...)
+    /// * `entries` - (key columns, rowid) pairs
+    ///
+    /// # Returns
+    /// Result indicating success or an error
+    pub fn add_auto_index(&mut self, table: &str, number: u32, entries: &[(Vec<SqlValue>, i64)]) -> TiffResult<()> {
+        let mut records: Vec<(Vec<Vec<u8>>, Vec<u8>)> = entries.iter()
+            .map(|(key, rowid)| {
+                let values: Vec<SqlValue> = key.iter().cloned().chain([SqlValue::Integer(*rowid)]).collect();
+                (key.iter().map(sort_key).collect(), encode_record(&values))
+            })
+            .collect();
+        records.sort_by(|a, b| a.0.cmp(&b.0));
+
+        let cells: Vec<Vec<u8>> = records.into_iter()
+            .map(|(_, payload)| {
+                let mut cell = Vec::with_capacity(payload.len() + 9);
+                put_varint(&mut cell, payload.len() as u64);
+                cell.extend_from_slice(&payload);
+                cell
+            })
+            .collect();
+
+        if page_fill(&cells, 8) > PAGE_SIZE {
+            return Err(TiffError::GenericError(format!("Index on {} does not fit in one page", table)));
+        }
+
+        let root_page = self.push_page(build_page(PAGE_INDEX_LEAF, &cells, None, 0));
+        self.schema.push(SchemaEntry {
+            kind: "index",
+            name: format!("sqlite_autoindex_{}_{}", table, number),
+            table_name: table.to_string(),
+            root_page,
+            sql: None,
+        });
+
+        Ok(())
+    }
+
+    /// Add a 2-D R*Tree virtual table, bulk loaded with sort-tile-recursive packing
+    ///
+    /// # Arguments
+    /// * `name` - Name of the virtual table
+    /// * `columns` - Names of the id and the four bound columns
+    /// * `entries` - (id, [min_x, max_x, min_y, max_y]) pairs
+    ///
+    /// # Returns
+    /// Result indicating success or an error
+    pub fn add_rtree(&mut self, name: &str, columns: [&str; 5], entries: &[RtreeCell]) -> TiffResult<()> {
+        let capacity = (RTREE_NODE_SIZE - 4) / RTREE_CELL_SIZE;
+
+        // Build the levels bottom-up; a cell refers to an entry id (leaves)
+        // or to a node index of the level below
+        let mut levels: Vec<Vec<Vec<RtreeCell>>> = vec![str_pack(entries.to_vec(), capacity)];
+
+        while levels.last().map_or(0, |l| l.len()) > 1 {
+            let parents: Vec<RtreeCell> = levels.last().unwrap().iter()
+                .enumerate()
+                .map(|(index, node)| (index as i64, union_bounds(node)))
+                .collect();
+            levels.push(str_pack(parents, capacity));
+        }
+
+        // Number the nodes top-down so the root is node 1
+        let mut node_numbers: Vec<Vec<i64>> = vec![Vec::new(); levels.len()];
+        let mut next_number = 1i64;
+        for (level, nodes) in levels.iter().enumerate().rev() {
+            for _ in nodes {
+                node_numbers[level].push(next_number);
+                next_number += 1;
+            }
+        }
+
+        let depth = (levels.len() - 1) as u16;
+        let mut node_rows = Vec::new();
+        let mut parent_rows = Vec::new();
+        let mut rowid_rows = Vec::new();
+
+        for (level, nodes) in levels.iter().enumerate() {
+            for (index, node) in nodes.iter().enumerate() {
+                let number = node_numbers[level][index];
+
+                let mut blob = vec![0u8; RTREE_NODE_SIZE];
+                if number == 1 {
+                    blob[0..2].copy_from_slice(&depth.to_be_bytes());
+                }
+                blob[2..4].copy_from_slice(&(node.len() as u16).to_be_bytes());
+
+                for (i, (id, bounds)) in node.iter().enumerate() {
+                    let id = if level == 0 {
+                        rowid_rows.push((*id, vec![SqlValue::Null, SqlValue::Integer(number)]));
+                        *id
+                    } else {
+                        let child = node_numbers[level - 1][*id as usize];
+                        parent_rows.push((child, vec![SqlValue::Null, SqlValue::Integer(number)]));
+                        child
+                    };
+
+                    let offset = 4 + i * RTREE_CELL_SIZE;
+                    blob[offset..offset + 8].copy_from_slice(&id.to_be_bytes());
+                    let coords = [
+                        f32_round_down(bounds[0]), f32_round_up(bounds[1]),
+                        f32_round_down(bounds[2]), f32_round_up(bounds[3]),
+                    ];
+                    for (k, coord) in coords.iter().enumerate() {
+                        let start = offset + 8 + k * 4;
+                        blob[start..start + 4].copy_from_slice(&coord.to_be_bytes());
+                    }
+                }
+
+                node_rows.push((number, vec![SqlValue::Null, SqlValue::Blob(blob)]));
+            }
+        }
+
+        node_rows.sort_by_key(|(number, _)| *number);
+        parent_rows.sort_by_key(|(number, _)| *number);
+        rowid_rows.sort_by_key(|(id, _)| *id);
+
+        self.schema.push(SchemaEntry {
+            kind: "table",
+            name: name.to_string(),
+            table_name: name.to_string(),
+            root_page: 0,
+            sql: Some(format!("CREATE VIRTUAL TABLE {} USING rtree({})", name, columns.join(", "))),
+        });
+        self.add_table(&format!("{}_node", name),
+                       &format!("CREATE TABLE \"{}_node\"(nodeno INTEGER PRIMARY KEY,data)", name),
+                       node_rows)?;
+        self.add_table(&format!("{}_parent", name),
+                       &format!("CREATE TABLE \"{}_parent\"(nodeno INTEGER PRIMARY KEY,parentnode)", name),
+                       parent_rows)?;
+        self.add_table(&format!("{}_rowid", name),
+                       &format!("CREATE TABLE \"{}_rowid\"(rowid INTEGER PRIMARY KEY,nodeno)", name),
+                       rowid_rows)?;
+
+        Ok(())
+    }
+
+    /// Build the schema table and write the database file
+    ///
+    /// # Arguments
+    /// * `path` - Path of the database to write
+    ///
+    /// # Returns
+    /// Result indicating success or an error
+    pub fn write(mut self, path: &str) -> TiffResult<()> {
+        let schema_cells: Vec<(i64, Vec<u8>)> = self.schema.iter()
+            .enumerate()
+            .map(|(i, entry)| {
+                let rowid = i as i64 + 1;
+                let payload = encode_record(&[
+                    SqlValue::Text(entry.kind.to_string()),
+                    SqlValue::Text(entry.name.clone()),
+                    SqlValue::Text(entry.table_name.clone()),
+                    SqlValue::Integer(entry.root_page as i64),
+                    entry.sql.clone().map_or(SqlValue::Null, SqlValue::Text),
+                ]);
+                let mut cell = Vec::new();
+                put_varint(&mut cell, payload.len() as u64);
+                put_varint(&mut cell, rowid as u64);
+                cell.extend_from_slice(&payload);
+                (rowid, cell)
+            })
+            .collect();
+
+        self.build_table_btree(schema_cells, true)?;
+
+        // Database header
+        let page_count = self.pages.len() as u32;
+        let header = &mut self.pages[0][..DB_HEADER_SIZE];
+        header[0..16].copy_from_slice(b"SQLite format 3\0");
+        header[16..18].copy_from_slice(&(PAGE_SIZE as u16).to_be_bytes());
+        header[18] = 1;                                             // write version (legacy)
+        header[19] = 1;                                             // read version (legacy)
+        header[21] = 64;                                            // max embedded payload fraction
+        header[22] = 32;                                            // min embedded payload fraction
+        header[23] = 32;                                            // leaf payload fraction
+        header[24..28].copy_from_slice(&1u32.to_be_bytes());        // file change counter
+        header[28..32].copy_from_slice(&page_count.to_be_bytes());
+        header[40..44].copy_from_slice(&1u32.to_be_bytes());        // schema cookie
+        header[44..48].copy_from_slice(&4u32.to_be_bytes());        // schema format
+        header[56..60].copy_from_slice(&1u32.to_be_bytes());        // UTF-8
+        header[60..64].copy_from_slice(&self.user_version.to_be_bytes());
+        header[68..72].copy_from_slice(&self.application_id.to_be_bytes());
+        header[92..96].copy_from_slice(&1u32.to_be_bytes());        // version-valid-for
+        header[96..100].copy_from_slice(&3_040_001u32.to_be_bytes());
+
+        let mut writer = BufWriter::new(File::create(path)?);
+        for page in &self.pages {
+            writer.write_all(page)?;
+        }
+        writer.flush()?;
+
+        Ok(())
+    }
+
+    /// Append a page and return its page number
+    fn push_page(&mut self, page: Vec<u8>) -> u32 {
+        self.pages.push(page);
+        self.pages.len() as u32
+    }
+
+    /// Build a table b-tree from sorted leaf cells
+    ///
+    /// # Arguments
+    /// * `cells` - (rowid, encoded leaf cell) pairs in rowid order
+    /// * `root_on_first_page` - Place the root on page 1 (schema table)
+    ///
+    /// # Returns
+    /// The root page number
+    fn build_table_btree(&mut self, cells: Vec<(i64, Vec<u8>)>, root_on_first_page: bool) -> TiffResult<u32> {
+        let root_offset = if root_on_first_page { DB_HEADER_SIZE } else { 0 };
+        let leaf_cells: Vec<Vec<u8>> = cells.iter().map(|(_, cell)| cell.clone()).collect();
+
+        // Everything fits in the root leaf
+        if page_fill(&leaf_cells, 8) + root_offset <= PAGE_SIZE {
+            return Ok(self.place_root(build_page(PAGE_TABLE_LEAF, &leaf_cells, None, root_offset), root_on_first_page));
+        }
+
+        // Leaf level
+        let mut children: Vec<(i64, u32)> = Vec::new();
+        let mut start = 0;
+        while start < cells.len() {
+            let mut end = start;
+            let mut fill = 8;
+            while end < cells.len() && fill + cells[end].1.len() + 2 <= PAGE_SIZE {
+                fill += cells[end].1.len() + 2;
+                end += 1;
+            }
+            let page_cells: Vec<Vec<u8>> = cells[start..end].iter().map(|(_, c)| c.clone()).collect();
+            let page = self.push_page(build_page(PAGE_TABLE_LEAF, &page_cells, None, 0));
+            children.push((cells[end - 1].0, page));
+            start = end;
+        }
+
+        // Interior levels until a single root remains
+        loop {
+            let interior_cell = |&(key, page): &(i64, u32)| {
+                let mut cell = page.to_be_bytes().to_vec();
+                put_varint(&mut cell, key as u64);
+                cell
+            };
+
+            let (_, last_page) = *children.last().unwrap();
+            let root_cells: Vec<Vec<u8>> = children[..children.len() - 1].iter().map(interior_cell).collect();
+            if page_fill(&root_cells, 12) + root_offset <= PAGE_SIZE {
+                let page = build_page(PAGE_TABLE_INTERIOR, &root_cells, Some(last_page), root_offset);
+                return Ok(self.place_root(page, root_on_first_page));
+            }
+
+            let mut parents = Vec::new();
+            let mut start = 0;
+            while start < children.len() {
+                // The last child of a page is its right-most pointer and needs no cell
+                let mut end = start + 1;
+                let mut fill = 12;
+                while end < children.len() && fill + interior_cell(&children[end - 1]).len() + 2 <= PAGE_SIZE {
+                    fill += interior_cell(&children[end - 1]).len() + 2;
+                    end += 1;
+                }
+                let page_cells: Vec<Vec<u8>> = children[start..end - 1].iter().map(interior_cell).collect();
+                let page = self.push_page(build_page(PAGE_TABLE_INTERIOR, &page_cells, Some(children[end - 1].1), 0));
+                parents.push((children[end - 1].0, page));
+                start = end;
+            }
+            children = parents;
+        }
+    }
+
+    /// Store a root page, either on page 1 or as a new page
+    fn place_root(&mut self, page: Vec<u8>, on_first_page: bool) -> u32 {
+        if on_first_page {
+            self.pages[0] = page;
+            1
+        } else {
+            self.push_page(page)
+        }
+    }
+}
+
+/// Bytes used on a page by its header, cell pointers and cells
+fn page_fill(cells: &[Vec<u8>], header_size: usize) -> usize {
+    header_size + cells.iter().map(|c| c.len() + 2).sum::<usize>()
+}
+
+/// Assemble a b-tree page
+///
+/// # Arguments
+/// * `page_type` - B-tree page type flag
+/// * `cells` - Encoded cells in key order
+/// * `right_pointer` - Right-most child for interior pages
+/// * `offset` - Start of the page header (100 on page 1)
+///
+/// # Returns
+/// The page bytes
+fn build_page(page_type: u8, cells: &[Vec<u8>], right_pointer: Option<u32>, offset: usize) -> Vec<u8> {
+    let mut page = vec![0u8; PAGE_SIZE];
+    let header_size = if right_pointer.is_some() { 12 } else { 8 };
+
+    page[offset] = page_type;
+    page[offset + 3..offset + 5].copy_from_slice(&(cells.len() as u16).to_be_bytes());
+    if let Some(pointer) = right_pointer {
+        page[offset + 8..offset + 12].copy_from_slice(&pointer.to_be_bytes());
+    }
+
+    // Cell content grows from the end of the page towards the pointer array
+    let mut content_start = PAGE_SIZE;
+    for (i, cell) in cells.iter().enumerate() {
+        content_start -= cell.len();
+        page[content_start..content_start + cell.len()].copy_from_slice(cell);
+        let pointer = offset + header_size + i * 2;
+        page[pointer..pointer + 2].copy_from_slice(&(content_start as u16).to_be_bytes());
+    }
+    page[offset + 5..offset + 7].copy_from_slice(&(content_start as u16).to_be_bytes());
+
+    page
+}
+
+/// Append a SQLite variable-length integer
+fn put_varint(out: &mut Vec<u8>, value: u64) {
+    if value > 0x00ff_ffff_ffff_ffff {
+        // Nine-byte form: eight 7-bit groups followed by a full byte
+        let mut bytes = [0u8; 9];
+        bytes[8] = value as u8;
+        let mut rest = value >> 8;
+        for byte in bytes[..8].iter_mut().rev() {
+            *byte = (rest & 0x7f) as u8 | 0x80;
+            rest >>= 7;
+        }
+        out.extend_from_slice(&bytes);
+        return;
+    }
+
+    let mut groups = Vec::with_capacity(9);
+    let mut rest = value;
+    loop {
+        groups.push((rest & 0x7f) as u8);
+        rest >>= 7;
+        if rest == 0 {
+            break;
+        }
+    }
+    let last = groups.len() - 1;
+    for (i, group) in groups.iter().rev().enumerate() {
+        out.push(if i < last { group | 0x80 } else { *group });
+    }
+}
+
+/// Number of bytes a varint needs
+fn varint_len(value: u64) -> usize {
+    let mut buffer = Vec::with_capacity(9);
+    put_varint(&mut buffer, value);
+    buffer.len()
+}
+
+/// Encode values in the SQLite record format
+fn encode_record(values: &[SqlValue]) -> Vec<u8> {
+    let mut types = Vec::new();
+    let mut body = Vec::new();
+
+    for value in values {
+        match value {
+            SqlValue::Null => put_varint(&mut types, 0),
+            SqlValue::Integer(0) => put_varint(&mut types, 8),
+            SqlValue::Integer(1) => put_varint(&mut types, 9),
+            SqlValue::Integer(v) => {
+                let (serial_type, size) = match *v {
+                    -128..=127 => (1, 1),
+                    -32_768..=32_767 => (2, 2),
+                    -8_388_608..=8_388_607 => (3, 3),
+                    -2_147_483_648..=2_147_483_647 => (4, 4),
+                    -140_737_488_355_328..=140_737_488_355_327 => (5, 6),
+                    _ => (6, 8),
+                };
+                put_varint(&mut types, serial_type);
+                body.extend_from_slice(&v.to_be_bytes()[8 - size..]);
+            },
+            SqlValue::Real(v) => {
+                put_varint(&mut types, 7);
+                body.extend_from_slice(&v.to_be_bytes());
+            },
+            SqlValue::Text(s) => {
+                put_varint(&mut types, 13 + 2 * s.len() as u64);
+                body.extend_from_slice(s.as_bytes());
+            },
+            SqlValue::Blob(b) => {
+                put_varint(&mut types, 12 + 2 * b.len() as u64);
+                body.extend_from_slice(b);
+            },
+        }
+    }
+
+    // The header size includes the varint holding it
+    let mut header_size = types.len() + 1;
+    while types.len() + varint_len(header_size as u64) != header_size {
+        header_size = types.len() + varint_len(header_size as u64);
+    }
+
+    let mut record = Vec::with_capacity(header_size + body.len());
+    put_varint(&mut record, header_size as u64);
+    record.extend_from_slice(&types);
+    record.extend_from_slice(&body);
+    record
+}
+
+/// Byte key ordering values like SQLite's BINARY collation for text keys
+fn sort_key(value: &SqlValue) -> Vec<u8> {
+    match value {
+        SqlValue::Null => vec![0],
+        SqlValue::Integer(v) => [vec![1], (*v as u64 ^ (1 << 63)).to_be_bytes().to_vec()].concat(),
+        SqlValue::Real(v) => [vec![1], v.to_be_bytes().to_vec()].concat(),
+        SqlValue::Text(s) => [vec![2], s.as_bytes().to_vec()].concat(),
+        SqlValue::Blob(b) => [vec![3], b.clone()].concat(),
+    }
+}
+
+/// Pack cells into R*Tree nodes with sort-tile-recursive ordering
+///
+/// Cells are sorted into vertical slices by X center, then by Y center
+/// within each slice, which keeps neighbouring pixels in the same node.
+fn str_pack(mut cells: Vec<RtreeCell>, capacity: usize) -> Vec<Vec<RtreeCell>> {
+    if cells.is_empty() {
+        return vec![Vec::new()];
+    }
+
+    let center = |b: &[f64; 4], axis: usize| (b[axis * 2] + b[axis * 2 + 1]) / 2.0;
+
+    let node_count = cells.len().div_ceil(capacity);
+    let slice_count = (node_count as f64).sqrt().ceil() as usize;
+    let slice_size = slice_count * capacity;
+
+    cells.sort_by(|a, b| center(&a.1, 0).total_cmp(&center(&b.1, 0)));
+
+    let mut nodes = Vec::with_capacity(node_count);
+    for slice in cells.chunks_mut(slice_size) {
+        slice.sort_by(|a, b| center(&a.1, 1).total_cmp(&center(&b.1, 1)));
+        nodes.extend(slice.chunks(capacity).map(|node| node.to_vec()));
+    }
+
+    nodes
+}
+
+/// Bounding box enclosing all cells of a node
+fn union_bounds(cells: &[RtreeCell]) -> [f64; 4] {
+    cells.iter().fold(
+        [f64::INFINITY, f64::NEG_INFINITY, f64::INFINITY, f64::NEG_INFINITY],
+        |acc, (_, b)| [acc[0].min(b[0]), acc[1].max(b[1]), acc[2].min(b[2]), acc[3].max(b[3])])
+}
+
+/// Nearest f32 that is not greater than the value
+fn f32_round_down(value: f64) -> f32 {
+    let rounded = value as f32;
+    if (rounded as f64) > value { f32_step(rounded, false) } else { rounded }
+}
+
+/// Nearest f32 that is not smaller than the value
+fn f32_round_up(value: f64) -> f32 {
+    let rounded = value as f32;
+    if (rounded as f64) < value { f32_step(rounded, true) } else { rounded }
+}
+
+/// Move a finite f32 by one unit in the last place
+fn f32_step(value: f32, up: bool) -> f32 {
+    if value == 0.0 {
+        let tiny = f32::from_bits(1);
+        return if up { tiny } else { -tiny };
+    }
+    let bits = value.to_bits();
+    let away_from_zero = (value > 0.0) == up;
+    f32::from_bits(if away_from_zero { bits + 1 } else { bits - 1 })
+}
+
+/// Current UTC time in the ISO 8601 form GeoPackage uses
+///
+/// # Returns
+/// A timestamp like "2024-05-01T12:00:00.000Z"
+pub fn utc_timestamp() -> String {
+    let seconds = SystemTime::now().duration_since(UNIX_EPOCH).map(|d| d.as_secs()).unwrap_or(0);
+    let (days, time) = ((seconds / 86_400) as i64, seconds % 86_400);
+
+    // Civil date from days since 1970-01-01 (Howard Hinnant's algorithm)
+    let z = days + 719_468;
+    let era = z.div_euclid(146_097);
+    let day_of_era = z.rem_euclid(146_097);
+    let year_of_era = (day_of_era - day_of_era / 1460 + day_of_era / 36_524 - day_of_era / 146_096) / 365;
+    let day_of_year = day_of_era - (365 * year_of_era + year_of_era / 4 - year_of_era / 100);
+    let mp = (5 * day_of_year + 2) / 153;
+    let day = day_of_year - (153 * mp + 2) / 5 + 1;
+    let month = if mp < 10 { mp + 3 } else { mp - 9 };
+    let year = year_of_era + era * 400 + if month <= 2 { 1 } else { 0 };
+
+    format!("{:04}-{:02}-{:02}T{:02}:{:02}:{:02}.000Z",
+            year, month, day, time / 3600, (time % 3600) / 60, time % 60)
+}
+
+/// Read every row of a table from a SQLite database
+///
+/// Only plain rowid tables are read, by walking their b-tree; overflow
+/// pages are followed, so databases written by SQLite itself work too. A
+/// column declared INTEGER PRIMARY KEY aliases the rowid and is filled in
+/// from it.
+///
+/// # Arguments
+/// * `path` - Path of the database
+/// * `table` - Name of the table to read
+///
+/// # Returns
+/// The column names and the rows in rowid order
+pub fn read_table(path: &str, table: &str) -> TiffResult<(Vec<String>, Vec<Vec<SqlValue>>)> {
+    let database = DatabaseFile::open(path)?;
+
+    let mut schema = Vec::new();
+    database.walk_table(1, &mut schema)?;
+    let (root_page, sql) = schema.iter()
+        .find_map(|(_, values)| match values.as_slice() {
+            [SqlValue::Text(kind), SqlValue::Text(name), _, SqlValue::Integer(root), SqlValue::Text(sql)]
+                if kind == "table" && name.eq_ignore_ascii_case(table) => Some((*root as u32, sql.clone())),
+            _ => None,
+        })
+        .ok_or_else(|| TiffError::GenericError(format!("{} has no table '{}'", path, table)))?;
+
+    let columns = column_definitions(&sql);
+    let rowid_alias = columns.iter().position(|(_, definition)| {
+        let definition = definition.to_uppercase();
+        definition.starts_with("INTEGER") && definition.contains("PRIMARY KEY")
+    });
+
+    let mut rows = Vec::new();
+    database.walk_table(root_page, &mut rows)?;
+    let rows = rows.into_iter()
+        .map(|(rowid, mut values)| {
+            values.resize(columns.len(), SqlValue::Null);
+            if let Some(column) = rowid_alias {
+                values[column] = SqlValue::Integer(rowid);
+            }
+            values
+        })
+        .collect();
+    Ok((columns.into_iter().map(|(name, _)| name).collect(), rows))
+}
+
+/// A database file held in memory for reading
+struct DatabaseFile {
+    bytes: Vec<u8>,
+    page_size: usize,
+    usable_size: usize,
+}
+
+impl DatabaseFile {
+    fn open(path: &str) -> TiffResult<Self> {
+        let bytes = std::fs::read(path)?;
+        if bytes.len() < DB_HEADER_SIZE || !bytes.starts_with(b"SQLite format 3\0") {
+            return Err(TiffError::GenericError(format!("{} is not a SQLite database", path)));
+        }
+        let page_size = match u16::from_be_bytes([bytes[16], bytes[17]]) {
+            1 => 65_536,
+            size => size as usize,
+        };
+        let usable_size = page_size.saturating_sub(bytes[20] as usize);
+        if usable_size < 480 {
+            return Err(TiffError::GenericError(format!("{} has an invalid page size", path)));
+        }
+        Ok(DatabaseFile { bytes, page_size, usable_size })
+    }
+
+    fn corrupt(&self) -> TiffError {
+        TiffError::GenericError("The SQLite database is corrupt".to_string())
+    }
+
+    fn page(&self, number: u32) -> TiffResult<&[u8]> {
+        let start = (number as usize).checked_sub(1).ok_or_else(|| self.corrupt())? * self.page_size;
+        self.bytes.get(start..start + self.page_size).ok_or_else(|| self.corrupt())
+    }
+
+    /// Collect the records of a table b-tree in key order
+    fn walk_table(&self, root_page: u32, rows: &mut Vec<(i64, Vec<SqlValue>)>) -> TiffResult<()> {
+        let mut pending = vec![root_page];
+        while let Some(number) = pending.pop() {
+            let page = self.page(number)?;
+            let header = if number == 1 { DB_HEADER_SIZE } else { 0 };
+            let cells = u16::from_be_bytes([page[header + 3], page[header + 4]]) as usize;
+            let (pointers, leaf) = match page[header] {
+                PAGE_TABLE_LEAF => (header + 8, true),
+                PAGE_TABLE_INTERIOR => (header + 12, false),
+                _ => return Err(self.corrupt()),
+            };
+            let offsets = (0..cells)
+                .map(|cell| page.get(pointers + 2 * cell..pointers + 2 * cell + 2)
+                    .map(|bytes| u16::from_be_bytes([bytes[0], bytes[1]]) as usize)
+                    .ok_or_else(|| self.corrupt()))
+                .collect::<TiffResult<Vec<_>>>()?;
+
+            if leaf {
+                for offset in offsets {
+                    let (payload_size, used) = read_varint(page.get(offset..).ok_or_else(|| self.corrupt())?);
+                    let (rowid, used_rowid) = read_varint(&page[offset + used..]);
+                    let payload = self.payload(page, offset + used + used_rowid, payload_size as usize)?;
+                    rows.push((rowid as i64, decode_record(&payload).ok_or_else(|| self.corrupt())?));
+                }
+            } else {
+                // Children are visited left to right by pushing them in reverse
+                pending.push(u32::from_be_bytes(page[header + 8..header + 12].try_into().unwrap()));
+                for offset in offsets.into_iter().rev() {
+                    let child = page.get(offset..offset + 4).ok_or_else(|| self.corrupt())?;
+                    pending.push(u32::from_be_bytes(child.try_into().unwrap()));
+                }
+            }
+        }
+        Ok(())
+    }
+
+    /// Payload of a leaf cell, gathered from its overflow pages if needed
+    fn payload(&self, page: &[u8], start: usize, size: usize) -> TiffResult<Vec<u8>> {
+        let usable = self.usable_size;
+        let max_local = usable - 35;
+        let local = if size <= max_local {
+            size
+        } else {
+            let min_local = (usable - 12) * 32 / 255 - 23;
+            let local = min_local + (size - min_local) % (usable - 4);
+            if local <= max_local { local } else { min_local }
+        };
+
+        let mut payload = page.get(start..start + local).ok_or_else(|| self.corrupt())?.to_vec();
+        if local < size {
+            let mut next = u32::from_be_bytes(page.get(start + local..start + local + 4)
+                .ok_or_else(|| self.corrupt())?.try_into().unwrap());
+            while payload.len() < size {
+                let overflow = self.page(next)?;
+                let take = (size - payload.len()).min(usable - 4);
+                payload.extend_from_slice(&overflow[4..4 + take]);
+                next = u32::from_be_bytes(overflow[0..4].try_into().unwrap());
+            }
+        }
+        Ok(payload)
+    }
+}
+
+/// Read a SQLite variable-length integer
+///
+/// # Returns
+/// The value and the number of bytes it took
+fn read_varint(bytes: &[u8]) -> (u64, usize) {
+    let mut value = 0u64;
+    for (i, &byte) in bytes.iter().take(9).enumerate() {
+        if i == 8 {
+            return ((value << 8) | byte as u64, 9);
+        }
+        value = (value << 7) | (byte & 0x7f) as u64;
+        if byte & 0x80 == 0 {
+            return (value, i + 1);
+        }
+    }
+    (value, bytes.len().min(9))
+}
+
+/// Decode a record in the SQLite record format
+fn decode_record(record: &[u8]) -> Option<Vec<SqlValue>> {
+    let (header_size, mut position) = read_varint(record);
+    let mut types = Vec::new();
+    while position < header_size as usize {
+        let (serial_type, used) = read_varint(record.get(position..)?);
+        types.push(serial_type);
+        position += used;
+    }
+
+    let mut body = header_size as usize;
+    let mut values = Vec::with_capacity(types.len());
+    for serial_type in types {
+        let size = match serial_type {
+            0 | 8 | 9 => 0,
+            1..=4 => serial_type as usize,
+            5 => 6,
+            6 | 7 => 8,
+            n if n >= 12 => ((n - 12) / 2) as usize,
+            _ => return None,
+        };
+        let bytes = record.get(body..body + size)?;
+        body += size;
+        values.push(match serial_type {
+            0 => SqlValue::Null,
+            8 => SqlValue::Integer(0),
+            9 => SqlValue::Integer(1),
+            7 => SqlValue::Real(f64::from_be_bytes(bytes.try_into().ok()?)),
+            1..=6 => {
+                // Sign-extend the big-endian integer
+                let fill = if bytes[0] & 0x80 != 0 { 0xff } else { 0 };
+                let mut full = [fill; 8];
+                full[8 - size..].copy_from_slice(bytes);
+                SqlValue::Integer(i64::from_be_bytes(full))
+            },
+            n if n % 2 == 0 => SqlValue::Blob(bytes.to_vec()),
+            _ => SqlValue::Text(String::from_utf8_lossy(bytes).into_owned()),
+        });
+    }
+    Some(values)
+}
+
+/// Names and declarations of the columns in a CREATE TABLE statement
+///
+/// Table constraints such as `PRIMARY KEY (a, b)` are skipped.
+fn column_definitions(sql: &str) -> Vec<(String, String)> {
+    let (Some(open), Some(close)) = (sql.find('('), sql.rfind(')')) else {
+        return Vec::new();
+    };
+
+    // Split on commas outside parentheses and quotes
+    let mut parts = Vec::new();
+    let (mut depth, mut quote, mut current) = (0, None, String::new());
+    for c in sql[open + 1..close].chars() {
+        match (c, quote) {
+            (_, Some(q)) if c == q => quote = None,
+            (_, Some(_)) => {},
+            ('"' | '`' | '\'', None) => quote = Some(c),
+            ('[', None) => quote = Some(']'),
+            ('(', None) => depth += 1,
+            (')', None) => depth -= 1,
+            (',', None) if depth == 0 => {
+                parts.push(std::mem::take(&mut current));
+                continue;
+            },
+            _ => {},
+        }
+        current.push(c);
+    }
+    parts.push(current);
+
+    parts.into_iter()
+        .filter_map(|part| {
+            let part = part.trim();
+            let first = part.split_whitespace().next()?.to_uppercase();
+            if ["PRIMARY", "UNIQUE", "CHECK", "FOREIGN", "CONSTRAINT"].contains(&first.as_str()) {
+                return None;
+            }
+            let (name, rest) = match part.chars().next()? {
+                quote @ ('"' | '`' | '[') => {
+                    let end = if quote == '[' { ']' } else { quote };
+                    let close = part[1..].find(end)? + 1;
+                    (part[1..close].to_string(), part[close + 1..].trim().to_string())
+                },
+                _ => match part.split_once(char::is_whitespace) {
+                    Some((name, rest)) => (name.to_string(), rest.trim().to_string()),
+                    None => (part.to_string(), String::new()),
+                },
+            };
+            Some((name, rest))
+        })
+        .collect()
+}
@@ -0,0 +1,300 @@
+//! Band statistics utilities
+//!
+//! Computes the minimum, maximum, mean, standard deviation and a histogram
+//! of each band from the stored sample values. Large rasters are sampled:
+//! blocks are picked at an even stride so that no more than
+//! `MAX_SAMPLED_BLOCKS` are decoded, and the results are then flagged as
+//! approximate, as GDAL does for `-approx_stats`.
+//!
+//! The statistics can be saved to the GDAL PAM sidecar (`<image>.aux.xml`)
+//! as `STATISTICS_*` metadata items and a `HistItem`, where GDAL and QGIS
+//! pick them up instead of scanning the raster again.
+
+use std::fs;
+use std::path::Path;
+
+use log::{debug, info};
+
+use crate::tiff::errors::{TiffError, TiffResult};
+use crate::tiff::ifd::IFD;
+use crate::tiff::TiffReader;
+use crate::utils::logger::Logger;
+use crate::utils::normalize_utils::BandStatistics;
+use crate::utils::sample_utils::{self, SampleFormat, SampleLayout};
+
+/// Most blocks decoded per band before statistics become approximate
+pub const MAX_SAMPLED_BLOCKS: usize = 64;
+
+/// Buckets of the histograms
+pub const HISTOGRAM_BUCKETS: usize = 256;
+
+/// Histogram of the valid values of a band
+#[derive(Debug, Clone, PartialEq)]
+pub struct Histogram {
+    /// Lower edge of the first bucket
+    pub min: f64,
+    /// Upper edge of the last bucket
+    pub max: f64,
+    /// Number of values in each bucket
+    pub counts: Vec<u64>,
+}
+
+impl Histogram {
+    /// Build a histogram of values between two edges
+    ///
+    /// # Arguments
+    /// * `values` - Values to count
+    /// * `min` - Lower edge of the first bucket
+    /// * `max` - Upper edge of the last bucket
+    /// * `buckets` - Number of buckets
+    ///
+    /// # Returns
+    /// The histogram; values outside the edges are not counted
+    pub fn build(values: &[f64], min: f64, max: f64, buckets: usize) -> Histogram {
+        let buckets = buckets.max(1);
+        let mut counts = vec![0u64; buckets];
+        let width = (max - min) / buckets as f64;
+
+        for &value in values {
+            if value < min || value > max {
+                continue;
+            }
+            let bucket = if width > 0.0 { ((value - min) / width) as usize } else { 0 };
+            counts[bucket.min(buckets - 1)] += 1;
+        }
+
+        Histogram { min, max, counts }
+    }
+
+    /// Merge neighbouring buckets down to a coarser histogram
+    ///
+    /// # Arguments
+    /// * `buckets` - Number of buckets wanted, a divisor of the current count
+    ///
+    /// # Returns
+    /// The coarser histogram
+    pub fn coarsen(&self, buckets: usize) -> Histogram {
+        let group = (self.counts.len() / buckets.max(1)).max(1);
+        Histogram {
+            min: self.min,
+            max: self.max,
+            counts: self.counts.chunks(group).map(|chunk| chunk.iter().sum()).collect(),
+        }
+    }
+}
+
+/// Statistics of one band
+#[derive(Debug, Clone)]
+pub struct BandReport {
+    /// One-based band number
+    pub band: usize,
+    /// Minimum, maximum, mean and standard deviation of the valid values
+    pub statistics: BandStatistics,
+    /// Share of the sampled pixels that hold valid values, in percent
+    pub valid_percent: f64,
+    /// Histogram of the valid values
+    pub histogram: Histogram,
+    /// Whether the values were sampled from part of the blocks
+    pub approximate: bool,
+}
+
+/// Compute the statistics of a list of values
+///
+/// # Arguments
+/// * `values` - Valid values of a band
+///
+/// # Returns
+/// The statistics, or None when there are no values
+pub fn describe(values: &[f64]) -> Option<BandStatistics> {
+    if values.is_empty() {
+        return None;
+    }
+
+    let count = values.len() as f64;
+    let (min, max) = values.iter()
+        .fold((f64::INFINITY, f64::NEG_INFINITY), |(min, max), &value| (min.min(value), max.max(value)));
+    let mean = values.iter().sum::<f64>() / count;
+    let variance = values.iter().map(|value| (value - mean).powi(2)).sum::<f64>() / count;
+
+    Some(BandStatistics { min, max, mean, std: variance.sqrt() })
+}
+
+/// Edges of the histogram of a band
+///
+/// 8-bit unsigned bands get one bucket per value, centred on it like
+/// GDAL's default histogram; other bands span their value range.
+fn histogram_range(layout: &SampleLayout, statistics: &BandStatistics) -> (f64, f64) {
+    if layout.format == SampleFormat::Unsigned && layout.bits == 8 {
+        (-0.5, 255.5)
+    } else {
+        (statistics.min, statistics.max)
+    }
+}
+
+/// Compute per-band statistics of a TIFF's first image
+///
+/// # Arguments
+/// * `input_path` - Path to the TIFF
+/// * `nodata` - NoData value excluded from the statistics, if any
+/// * `logger` - Logger for recording operations
+///
+/// # Returns
+/// One report per band holding valid values
+pub fn compute_statistics(input_path: &str, nodata: Option<f64>, logger: &Logger) -> TiffResult<Vec<BandReport>> {
+    let mut reader = TiffReader::new(logger);
+    let tiff = reader.load(input_path)?;
+    let ifd = tiff.main_ifd()
+        .ok_or_else(|| TiffError::GenericError(format!("No IFDs found in {}", input_path)))?;
+    compute_ifd_statistics(&reader, ifd, input_path, nodata)
+}
+
+/// Compute per-band statistics of an image whose header is already parsed
+///
+/// # Arguments
+/// * `reader` - Reader that loaded the image's header
+/// * `ifd` - IFD of the image
+/// * `input_path` - Path to the TIFF
+/// * `nodata` - NoData value excluded from the statistics, if any
+///
+/// # Returns
+/// One report per band holding valid values
+pub fn compute_ifd_statistics(reader: &TiffReader, ifd: &IFD, input_path: &str,
+                              nodata: Option<f64>) -> TiffResult<Vec<BandReport>> {
+    let mut values: Vec<Vec<f64>> = Vec::new();
+    let mut sampled_pixels = 0usize;
+    let mut stride = 1;
+
+    let layout = sample_utils::read_ifd_blocks(
+        reader, ifd, input_path,
+        |layout, index| {
+            stride = layout.blocks_per_band().div_ceil(MAX_SAMPLED_BLOCKS);
+            (index % layout.blocks_per_band()) % stride == 0
+        },
+        |layout, block| {
+            if values.is_empty() {
+                values = vec![Vec::new(); layout.bands];
+            }
+            if block.bands.start == 0 {
+                sampled_pixels += (block.width * block.height) as usize;
+            }
+            for band in block.bands.clone() {
+                for row in 0..block.height {
+                    for column in 0..block.width {
+                        let Some(raw) = block.sample(column, row, band) else {
+                            continue;
+                        };
+                        let value = layout.value(raw);
+                        if !value.is_nan() && nodata != Some(value) {
+                            values[band].push(value);
+                        }
+                    }
+                }
+            }
+        })?;
+
+    let approximate = stride > 1;
+    if approximate {
+        info!("Sampled 1 in {} of {} blocks per band for statistics", stride, layout.blocks_per_band());
+    }
+
+    let reports: Vec<BandReport> = values.iter().enumerate()
+        .filter_map(|(band, band_values)| {
+            let statistics = describe(band_values)?;
+            let (min, max) = histogram_range(&layout, &statistics);
+            let histogram = Histogram::build(band_values, min, max, HISTOGRAM_BUCKETS);
+            debug!("Band {}: {} valid values of {} sampled pixels", band + 1, band_values.len(), sampled_pixels);
+            Some(BandReport {
+                band: band + 1,
+                statistics,
+                valid_percent: 100.0 * band_values.len() as f64 / sampled_pixels.max(1) as f64,
+                histogram,
+                approximate,
+            })
+        })
+        .collect();
+
+    if reports.is_empty() {
+        return Err(TiffError::GenericError(format!("{} holds no valid values", input_path)));
+    }
+    Ok(reports)
+}
+
+/// PAM element holding the statistics of one band
+fn pam_band(report: &BandReport) -> String {
+    let stats = &report.statistics;
+    let counts: Vec<String> = report.histogram.counts.iter().map(|count| count.to_string()).collect();
+    let approximate = report.approximate as u8;
+
+    let mut xml = format!("  <PAMRasterBand band=\"{}\">\n", report.band);
+    xml.push_str("    <Histograms>\n      <HistItem>\n");
+    xml.push_str(&format!("        <HistMin>{}</HistMin>\n", report.histogram.min));
+    xml.push_str(&format!("        <HistMax>{}</HistMax>\n", report.histogram.max));
+    xml.push_str(&format!("        <BucketCount>{}</BucketCount>\n", counts.len()));
+    xml.push_str("        <IncludeOutOfRange>0</IncludeOutOfRange>\n");
+    xml.push_str(&format!("        <Approximate>{}</Approximate>\n", approximate));
+    xml.push_str(&format!("        <HistCounts>{}</HistCounts>\n", counts.join("|")));
+    xml.push_str("      </HistItem>\n    </Histograms>\n");
+    xml.push_str("    <Metadata>\n");
+    if report.approximate {
+        xml.push_str("      <MDI key=\"STATISTICS_APPROXIMATE\">YES</MDI>\n");
+    }
+    for (key, value) in [("MAXIMUM", stats.max), ("MEAN", stats.mean), ("MINIMUM", stats.min),
+                         ("STDDEV", stats.std), ("VALID_PERCENT", report.valid_percent)] {
+        xml.push_str(&format!("      <MDI key=\"STATISTICS_{}\">{}</MDI>\n", key, value));
+    }
+    xml.push_str("    </Metadata>\n  </PAMRasterBand>\n");
+    xml
+}
+
+/// Remove the element of one band from PAM XML
+fn remove_pam_band(xml: &str, band: usize) -> String {
+    let open = format!("<PAMRasterBand band=\"{}\"", band);
+    let close = "</PAMRasterBand>";
+    let Some(start) = xml.find(&open) else {
+        return xml.to_string();
+    };
+    let Some(end) = xml[start..].find(close).map(|end| start + end + close.len()) else {
+        return xml.to_string();
+    };
+
+    // Drop the indentation and line break around the element too
+    let start = xml[..start].trim_end_matches([' ', '\t']).len();
+    let end = xml.len() - xml[end..].trim_start_matches(['\r', '\n']).len();
+    format!("{}{}", &xml[..start], &xml[end..])
+}
+
+/// Save band statistics to the GDAL PAM sidecar of an image
+///
+/// An existing `<image>.aux.xml` keeps its other content (geotransform,
+/// SRS, other bands); only the elements of the reported bands are replaced.
+///
+/// # Arguments
+/// * `image_path` - Path to the image the statistics describe
+/// * `reports` - Statistics per band
+///
+/// # Returns
+/// The path of the sidecar that was written
+pub fn write_pam_statistics(image_path: &str, reports: &[BandReport]) -> TiffResult<String> {
+    let aux_path = format!("{}.aux.xml", image_path);
+
+    let mut xml = if Path::new(&aux_path).exists() {
+        info!("Updating statistics in {}", aux_path);
+        fs::read_to_string(&aux_path)
+            .map_err(|e| TiffError::GenericError(format!("Failed to read {}: {}", aux_path, e)))?
+    } else {
+        "<PAMDataset>\n</PAMDataset>\n".to_string()
+    };
+    for report in reports {
+        xml = remove_pam_band(&xml, report.band);
+    }
+
+    let end = xml.rfind("</PAMDataset>")
+        .ok_or_else(|| TiffError::GenericError(format!("{} is not a PAM dataset", aux_path)))?;
+    let bands: String = reports.iter().map(pam_band).collect();
+    xml.insert_str(end, &bands);
+
+    fs::write(&aux_path, xml)
+        .map_err(|e| TiffError::GenericError(format!("Failed to write {}: {}", aux_path, e)))?;
+    info!("Saved statistics of {} band(s) to {}", reports.len(), aux_path);
+    Ok(aux_path)
+}
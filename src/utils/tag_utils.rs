@@ -2,77 +2,8 @@
 //!
 //! Utilities for working with TIFF tags and their values.
 
-use byteorder::ReadBytesExt;
-
-use crate::io::seekable::SeekableReader;
-use crate::io::byte_order::ByteOrderHandler;
-use crate::tiff::errors::{TiffError, TiffResult};
-use crate::tiff::ifd::IFDEntry;
 use crate::tiff::constants::{field_types, tags, compression, photometric};
 
-/// Reads an array of tag values based on the field type
-///
-/// # Arguments
-/// * `reader` - The seekable reader to use
-/// * `entry` - The IFD entry with tag information
-/// * `handler` - The byte order handler
-/// * `values` - The vector to store values in
-///
-/// # Returns
-/// Result indicating success or failure
-pub fn read_tag_value_array(
-    reader: &mut dyn SeekableReader,
-    entry: &IFDEntry,
-    handler: &Box<dyn ByteOrderHandler>,
-    values: &mut Vec<u64>
-) -> TiffResult<()> {
-    for _ in 0..entry.count {
-        let value = match entry.field_type {
-            field_types::BYTE | field_types::SBYTE | field_types::UNDEFINED => reader.read_u8()? as u64,
-            field_types::SHORT | field_types::SSHORT => handler.read_u16(reader)? as u64,
-            field_types::LONG | field_types::SLONG | field_types::FLOAT => handler.read_u32(reader)? as u64,
-            field_types::RATIONAL | field_types::SRATIONAL => {
-                let (num, den) = handler.read_rational(reader)?;
-                ((num as u64) << 32) | (den as u64)
-            },
-            field_types::LONG8 | field_types::SLONG8 | field_types::IFD8 => handler.read_u64(reader)?,
-            _ => return Err(TiffError::UnsupportedFieldType(entry.field_type)),
-        };
-
-        values.push(value);
-    }
-
-    Ok(())
-}
-
-/// Determines if a tag's value is stored inline or at an offset
-///
-/// # Arguments
-/// * `entry` - The IFD entry to check
-/// * `is_big_tiff` - Whether the file is BigTIFF format
-///
-/// # Returns
-/// true if the value is stored inline, false if it's at an offset
-pub fn is_value_inline(entry: &IFDEntry, is_big_tiff: bool) -> bool {
-    let size_per_value = match entry.field_type {
-        field_types::BYTE | field_types::SBYTE | field_types::UNDEFINED => 1,
-        field_types::ASCII => 1,
-        field_types::SHORT | field_types::SSHORT => 2,
-        field_types::LONG | field_types::SLONG | field_types::FLOAT => 4,
-        field_types::RATIONAL | field_types::SRATIONAL | field_types::DOUBLE |
-        field_types::LONG8 | field_types::SLONG8 | field_types::IFD8 => 8,
-        _ => 1, // Default for unknown types
-    };
-
-    let total_size = size_per_value * entry.count;
-
-    if is_big_tiff {
-        total_size <= 8 // In BigTIFF, 8 bytes are available for inline storage
-    } else {
-        total_size <= 4 // In standard TIFF, 4 bytes are available for inline storage
-    }
-}
-
 /// Get the name of a TIFF tag
 ///
 /// Returns a human-readable name for a tag based on its numeric ID.
@@ -101,7 +32,10 @@ pub fn get_tag_name(tag: u16) -> &'static str {
         tags::PLANAR_CONFIGURATION => "PlanarConfiguration",
         tags::COLOR_MAP => "ColorMap",
         tags::SAMPLE_FORMAT => "SampleFormat",
+        tags::EXTRA_SAMPLES => "ExtraSamples",
         tags::PREDICTOR => "Predictor",
+        tags::JPEG_TABLES => "JPEGTables",
+        tags::YCBCR_SUBSAMPLING => "YCbCrSubsampling",
 
         // Other common tags
         tags::RESOLUTION_UNIT => "ResolutionUnit",
@@ -113,6 +47,14 @@ pub fn get_tag_name(tag: u16) -> &'static str {
         tags::ARTIST => "Artist",
         tags::HOST_COMPUTER => "HostComputer",
         tags::COPYRIGHT => "Copyright",
+        tags::ICC_PROFILE => "InterColorProfile",
+        tags::IMAGE_DESCRIPTION => "ImageDescription",
+        tags::MAKE => "Make",
+        tags::MODEL => "Model",
+        tags::XMP => "XMLPacket",
+        tags::EXIF_IFD => "ExifIFD",
+        tags::GPS_IFD => "GPSInfoIFD",
+        tags::SUB_IFDS => "SubIFDs",
 
         // Tiling tags
         tags::TILE_OFFSETS => "TileOffsets",
@@ -165,6 +107,7 @@ pub fn get_field_type_name(field_type: u16) -> &'static str {
         field_types::SRATIONAL => "SRATIONAL",
         field_types::FLOAT => "FLOAT",
         field_types::DOUBLE => "DOUBLE",
+        field_types::IFD => "IFD",
         field_types::LONG8 => "LONG8",
         field_types::SLONG8 => "SLONG8",
         field_types::IFD8 => "IFD8",
@@ -0,0 +1,327 @@
+//! Tensor container utilities
+//!
+//! Writers for the multi-array formats used by machine-learning tooling:
+//! NumPy `.npz` archives (a store-only zip of `.npy` members) and
+//! Hugging Face `.safetensors` files (an 8-byte header length, a JSON header
+//! describing every tensor, followed by the raw little-endian data).
+
+use std::fs::File;
+use std::io::{BufWriter, Write};
+
+use crate::tiff::errors::{TiffError, TiffResult};
+use crate::utils::{atomic_utils, npy_utils};
+
+/// Element type of a tensor
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum TensorDType {
+    U8,
+    U16,
+    I16,
+    U32,
+    I32,
+    F32,
+    F64,
+}
+
+impl TensorDType {
+    /// NumPy dtype descriptor (little-endian)
+    pub fn npy_descr(&self) -> &'static str {
+        match self {
+            TensorDType::U8 => "<u1",
+            TensorDType::U16 => "<u2",
+            TensorDType::I16 => "<i2",
+            TensorDType::U32 => "<u4",
+            TensorDType::I32 => "<i4",
+            TensorDType::F32 => "<f4",
+            TensorDType::F64 => "<f8",
+        }
+    }
+
+    /// dtype name used in safetensors headers
+    pub fn safetensors_name(&self) -> &'static str {
+        match self {
+            TensorDType::U8 => "U8",
+            TensorDType::U16 => "U16",
+            TensorDType::I16 => "I16",
+            TensorDType::U32 => "U32",
+            TensorDType::I32 => "I32",
+            TensorDType::F32 => "F32",
+            TensorDType::F64 => "F64",
+        }
+    }
+
+    /// Size of one element in bytes
+    pub fn size(&self) -> usize {
+        match self {
+            TensorDType::U8 => 1,
+            TensorDType::U16 | TensorDType::I16 => 2,
+            TensorDType::U32 | TensorDType::I32 | TensorDType::F32 => 4,
+            TensorDType::F64 => 8,
+        }
+    }
+}
+
+/// A named tensor ready to be written
+#[derive(Debug, Clone)]
+pub struct NamedTensor {
+    /// Tensor name (array name in NPZ, key in safetensors)
+    pub name: String,
+    /// Element type
+    pub dtype: TensorDType,
+    /// Tensor shape in C order
+    pub shape: Vec<usize>,
+    /// Raw little-endian element data
+    pub data: Vec<u8>,
+}
+
+impl NamedTensor {
+    /// Create a float32 tensor from values
+    ///
+    /// # Arguments
+    /// * `name` - Tensor name
+    /// * `shape` - Tensor shape
+    /// * `values` - Values in C order
+    ///
+    /// # Returns
+    /// A new NamedTensor
+    pub fn from_f32(name: &str, shape: &[usize], values: &[f32]) -> Self {
+        NamedTensor {
+            name: name.to_string(),
+            dtype: TensorDType::F32,
+            shape: shape.to_vec(),
+            data: values.iter().flat_map(|v| v.to_le_bytes()).collect(),
+        }
+    }
+
+    /// Create an unsigned 8-bit tensor
+    ///
+    /// # Arguments
+    /// * `name` - Tensor name
+    /// * `shape` - Tensor shape
+    /// * `values` - Values in C order
+    ///
+    /// # Returns
+    /// A new NamedTensor
+    pub fn from_u8(name: &str, shape: &[usize], values: &[u8]) -> Self {
+        NamedTensor {
+            name: name.to_string(),
+            dtype: TensorDType::U8,
+            shape: shape.to_vec(),
+            data: values.to_vec(),
+        }
+    }
+
+    /// Create a tensor from little-endian element data
+    ///
+    /// # Arguments
+    /// * `name` - Tensor name
+    /// * `dtype` - Element type of the data
+    /// * `shape` - Tensor shape
+    /// * `data` - Elements in C order, little-endian
+    ///
+    /// # Returns
+    /// A new NamedTensor
+    pub fn from_le_bytes(name: &str, dtype: TensorDType, shape: &[usize], data: Vec<u8>) -> Self {
+        NamedTensor {
+            name: name.to_string(),
+            dtype,
+            shape: shape.to_vec(),
+            data,
+        }
+    }
+
+    /// Check that the data length matches shape and dtype
+    fn validate(&self) -> TiffResult<()> {
+        let expected = self.shape.iter().product::<usize>() * self.dtype.size();
+        if expected != self.data.len() {
+            return Err(TiffError::GenericError(format!(
+                "Tensor '{}' has {} bytes but shape {:?} needs {}",
+                self.name, self.data.len(), self.shape, expected)));
+        }
+        Ok(())
+    }
+}
+
+/// Escape a string for use inside a JSON string literal
+pub(crate) fn escape_json(value: &str) -> String {
+    let mut escaped = String::with_capacity(value.len());
+    for c in value.chars() {
+        match c {
+            '"' => escaped.push_str("\\\""),
+            '\\' => escaped.push_str("\\\\"),
+            '\n' => escaped.push_str("\\n"),
+            '\r' => escaped.push_str("\\r"),
+            '\t' => escaped.push_str("\\t"),
+            c if (c as u32) < 0x20 => escaped.push_str(&format!("\\u{:04x}", c as u32)),
+            c => escaped.push(c),
+        }
+    }
+    escaped
+}
+
+/// Save tensors as a safetensors file
+///
+/// Tensors are stored in the given order. Metadata entries end up in the
+/// `__metadata__` section, which only allows string values.
+///
+/// # Arguments
+/// * `path` - Path of the file to write
+/// * `tensors` - Tensors to store
+/// * `metadata` - Free-form string metadata
+///
+/// # Returns
+/// Result indicating success or an error
+pub fn save_safetensors(path: &str, tensors: &[NamedTensor], metadata: &[(String, String)]) -> TiffResult<()> {
+    let mut entries = Vec::with_capacity(tensors.len() + 1);
+
+    if !metadata.is_empty() {
+        let fields: Vec<String> = metadata.iter()
+            .map(|(key, value)| format!("\"{}\":\"{}\"", escape_json(key), escape_json(value)))
+            .collect();
+        entries.push(format!("\"__metadata__\":{{{}}}", fields.join(",")));
+    }
+
+    let mut offset = 0usize;
+    for tensor in tensors {
+        tensor.validate()?;
+        let shape: Vec<String> = tensor.shape.iter().map(|d| d.to_string()).collect();
+        entries.push(format!(
+            "\"{}\":{{\"dtype\":\"{}\",\"shape\":[{}],\"data_offsets\":[{},{}]}}",
+            escape_json(&tensor.name), tensor.dtype.safetensors_name(),
+            shape.join(","), offset, offset + tensor.data.len()));
+        offset += tensor.data.len();
+    }
+
+    // Pad the header with spaces so the data starts 8-byte aligned
+    let mut header = format!("{{{}}}", entries.join(",")).into_bytes();
+    header.resize(header.len().div_ceil(8) * 8, b' ');
+
+    let mut writer = BufWriter::new(File::create(path)?);
+    writer.write_all(&(header.len() as u64).to_le_bytes())?;
+    writer.write_all(&header)?;
+    for tensor in tensors {
+        writer.write_all(&tensor.data)?;
+    }
+    writer.flush()?;
+
+    Ok(())
+}
+
+/// Save tensors as an uncompressed NumPy `.npz` archive
+///
+/// Every tensor becomes a `<name>.npy` member of a store-only zip, which is
+/// exactly what `numpy.savez` produces.
+///
+/// # Arguments
+/// * `path` - Path of the file to write
+/// * `tensors` - Tensors to store
+///
+/// # Returns
+/// Result indicating success or an error
+pub fn save_npz(path: &str, tensors: &[NamedTensor]) -> TiffResult<()> {
+    let mut writer = BufWriter::new(File::create(path)?);
+    let mut central_directory = Vec::new();
+    let mut offset = 0u64;
+
+    for tensor in tensors {
+        tensor.validate()?;
+
+        let mut member = Vec::new();
+        npy_utils::write_npy(&mut member, tensor.dtype.npy_descr(), &tensor.shape, &tensor.data)?;
+
+        let file_name = format!("{}.npy", tensor.name);
+        if member.len() > u32::MAX as usize || offset > u32::MAX as u64 {
+            return Err(TiffError::GenericError(
+                "NPZ archive exceeds 4 GiB, which requires ZIP64 and is not supported".to_string()));
+        }
+
+        let crc = crc32fast::hash(&member);
+        let size = member.len() as u32;
+        let name_len = file_name.len() as u16;
+
+        // Local file header: stored, no data descriptor
+        let mut local = Vec::with_capacity(30 + file_name.len());
+        local.extend_from_slice(&0x0403_4b50u32.to_le_bytes());
+        local.extend_from_slice(&20u16.to_le_bytes());      // version needed
+        local.extend_from_slice(&0u16.to_le_bytes());       // flags
+        local.extend_from_slice(&0u16.to_le_bytes());       // method: stored
+        local.extend_from_slice(&0u16.to_le_bytes());       // mod time
+        local.extend_from_slice(&0x0021u16.to_le_bytes());  // mod date (1980-01-01)
+        local.extend_from_slice(&crc.to_le_bytes());
+        local.extend_from_slice(&size.to_le_bytes());
+        local.extend_from_slice(&size.to_le_bytes());
+        local.extend_from_slice(&name_len.to_le_bytes());
+        local.extend_from_slice(&0u16.to_le_bytes());       // extra length
+        local.extend_from_slice(file_name.as_bytes());
+
+        // Matching central directory record
+        central_directory.extend_from_slice(&0x0201_4b50u32.to_le_bytes());
+        central_directory.extend_from_slice(&20u16.to_le_bytes());  // version made by
+        central_directory.extend_from_slice(&local[4..30]);        // shared header fields
+        central_directory.extend_from_slice(&0u16.to_le_bytes());   // comment length
+        central_directory.extend_from_slice(&0u16.to_le_bytes());   // disk number
+        central_directory.extend_from_slice(&0u16.to_le_bytes());   // internal attributes
+        central_directory.extend_from_slice(&0u32.to_le_bytes());   // external attributes
+        central_directory.extend_from_slice(&(offset as u32).to_le_bytes());
+        central_directory.extend_from_slice(file_name.as_bytes());
+
+        writer.write_all(&local)?;
+        writer.write_all(&member)?;
+        offset += (local.len() + member.len()) as u64;
+    }
+
+    if offset > u32::MAX as u64 {
+        return Err(TiffError::GenericError(
+            "NPZ archive exceeds 4 GiB, which requires ZIP64 and is not supported".to_string()));
+    }
+
+    writer.write_all(&central_directory)?;
+
+    // End of central directory record
+    let count = tensors.len() as u16;
+    writer.write_all(&0x0605_4b50u32.to_le_bytes())?;
+    writer.write_all(&0u16.to_le_bytes())?;
+    writer.write_all(&0u16.to_le_bytes())?;
+    writer.write_all(&count.to_le_bytes())?;
+    writer.write_all(&count.to_le_bytes())?;
+    writer.write_all(&(central_directory.len() as u32).to_le_bytes())?;
+    writer.write_all(&(offset as u32).to_le_bytes())?;
+    writer.write_all(&0u16.to_le_bytes())?;
+    writer.flush()?;
+
+    Ok(())
+}
+
+/// Save tensors in the requested container format
+///
+/// NPY holds a single array, so only the first tensor is written there.
+///
+/// # Arguments
+/// * `path` - Path of the file to write
+/// * `format` - "npy", "npz" or "safetensors"
+/// * `tensors` - Tensors to store
+/// * `metadata` - String metadata (kept by safetensors only)
+///
+/// # Returns
+/// Result indicating success or an error
+pub fn save_tensors(
+    path: &str,
+    format: &str,
+    tensors: &[NamedTensor],
+    metadata: &[(String, String)]
+) -> TiffResult<()> {
+    let format = format.to_lowercase();
+    if !matches!(format.as_str(), "npy" | "npz" | "safetensors") {
+        return Err(TiffError::GenericError(format!("Unsupported tensor format: {}", format)));
+    }
+    atomic_utils::write_atomically(path, |partial_path| match format.as_str() {
+        "npy" => {
+            let tensor = tensors.first()
+                .ok_or_else(|| TiffError::GenericError("No tensor to write".to_string()))?;
+            tensor.validate()?;
+            npy_utils::save_npy(partial_path, tensor.dtype.npy_descr(), &tensor.shape, &tensor.data)
+        },
+        "npz" => save_npz(partial_path, tensors),
+        _ => save_safetensors(partial_path, tensors, metadata),
+    })
+}
@@ -9,11 +9,14 @@ use log::{debug, info, warn};
 use crate::tiff::errors::{TiffError, TiffResult};
 use crate::tiff::ifd::IFD;
 use crate::tiff::TiffReader;
-use crate::tiff::constants::{tags, field_types, photometric};
+use crate::tiff::constants::{tags, field_types, photometric, sample_format, extra_samples};
 use crate::tiff::IFDEntry;
 use crate::tiff::TiffBuilder;
+use crate::tiff::types::TIFF;
 use crate::extractor::Region;
 use crate::tiff::geo_key_parser::GeoKeyParser;
+use crate::utils::logger::Logger;
+use crate::utils::reference_utils;
 
 /// Statistics about pixel values in an image
 ///
@@ -181,6 +184,234 @@ pub fn process_rgb_image(
     Ok(())
 }
 
+/// Process an RGBA image and set up the appropriate TIFF structures
+///
+/// Writes four 8-bit samples per pixel and declares the fourth as
+/// unassociated alpha via ExtraSamples, so transparency masks survive.
+///
+/// # Arguments
+/// * `image` - The image to process
+/// * `builder` - TIFF builder to configure
+/// * `ifd_index` - Index of the IFD to modify
+///
+/// # Returns
+/// Result indicating success or an error
+pub fn process_rgba_image(
+    image: &DynamicImage,
+    builder: &mut TiffBuilder,
+    ifd_index: usize
+) -> TiffResult<()> {
+    info!("Processing RGBA image data");
+
+    let stats = calculate_rgb_stats(image);
+    builder.ifds[ifd_index].add_entry(IFDEntry::new(
+        tags::MIN_SAMPLE_VALUE, field_types::SHORT, 1, stats.min_value));
+    builder.ifds[ifd_index].add_entry(IFDEntry::new(
+        tags::MAX_SAMPLE_VALUE, field_types::SHORT, 1, stats.max_value));
+
+    let rgba_data = image.to_rgba8().into_raw();
+
+    builder.add_basic_rgba_tags(ifd_index, image.width(), image.height());
+    builder.setup_single_strip(ifd_index, rgba_data);
+
+    Ok(())
+}
+
+/// Process a grayscale image with an alpha channel
+///
+/// Writes two 8-bit samples per pixel, gray and unassociated alpha, so
+/// transparency survives on single-band output as it does on RGBA.
+///
+/// # Arguments
+/// * `image` - The image to process
+/// * `builder` - TIFF builder to configure
+/// * `ifd_index` - Index of the IFD to modify
+///
+/// # Returns
+/// Result indicating success or an error
+pub fn process_gray_alpha_image(
+    image: &DynamicImage,
+    builder: &mut TiffBuilder,
+    ifd_index: usize
+) -> TiffResult<()> {
+    info!("Processing grayscale image data with alpha");
+
+    let stats = calculate_grayscale_stats(image);
+    builder.ifds[ifd_index].add_entry(IFDEntry::new(
+        tags::MIN_SAMPLE_VALUE, field_types::SHORT, 1, stats.min_value));
+    builder.ifds[ifd_index].add_entry(IFDEntry::new(
+        tags::MAX_SAMPLE_VALUE, field_types::SHORT, 1, stats.max_value));
+
+    let gray_alpha_data = image.to_luma_alpha8().into_raw();
+
+    builder.add_basic_gray_alpha_tags(ifd_index, image.width(), image.height());
+    builder.setup_single_strip(ifd_index, gray_alpha_data);
+
+    Ok(())
+}
+
+/// Write 8-bit image data with the layout matching its color type
+///
+/// RGBA and grayscale-alpha images keep their alpha channel, other color
+/// images are written as RGB and everything else as single-band grayscale.
+///
+/// # Arguments
+/// * `image` - The image to process
+/// * `builder` - TIFF builder to configure
+/// * `ifd_index` - Index of the IFD to modify
+///
+/// # Returns
+/// Result indicating success or an error
+pub fn process_image_data(
+    image: &DynamicImage,
+    builder: &mut TiffBuilder,
+    ifd_index: usize
+) -> TiffResult<()> {
+    let color = image.color();
+    if color.has_color() && color.has_alpha() {
+        process_rgba_image(image, builder, ifd_index)
+    } else if color.has_alpha() {
+        process_gray_alpha_image(image, builder, ifd_index)
+    } else if color.has_color() {
+        builder.add_extra_samples(ifd_index, &[]);
+        process_rgb_image(image, builder, ifd_index)
+    } else {
+        builder.add_extra_samples(ifd_index, &[]);
+        process_grayscale_image(image, builder, ifd_index, 8)
+    }
+}
+
+/// Process a 32-bit floating point raster and set up the TIFF structures
+///
+/// Used for derived products (distances, interpolated surfaces, ...) whose
+/// values do not fit into 8 bits. The samples are written little-endian to
+/// match the byte order used by the TIFF writer.
+///
+/// # Arguments
+/// * `data` - Sample values in row-major order
+/// * `width` - Raster width in pixels
+/// * `height` - Raster height in pixels
+/// * `builder` - TIFF builder to configure
+/// * `ifd_index` - Index of the IFD to modify
+///
+/// # Returns
+/// Result indicating success or an error
+pub fn process_float32_image(
+    data: &[f32],
+    width: u32,
+    height: u32,
+    builder: &mut TiffBuilder,
+    ifd_index: usize
+) -> TiffResult<()> {
+    info!("Processing float32 raster data ({}x{})", width, height);
+
+    if data.len() != (width as usize) * (height as usize) {
+        return Err(TiffError::GenericError(format!(
+            "Float raster has {} samples, expected {}x{}", data.len(), width, height)));
+    }
+
+    // Add grayscale tags with a 32-bit depth
+    builder.add_basic_gray_tags(ifd_index, width, height, 32);
+
+    // MinSampleValue/MaxSampleValue are integer tags and meaningless for float data
+    builder.ifds[ifd_index].entries.retain(|e|
+        e.tag != tags::MIN_SAMPLE_VALUE && e.tag != tags::MAX_SAMPLE_VALUE);
+
+    // Mark the samples as IEEE floating point
+    builder.ifds[ifd_index].add_entry(IFDEntry::new(
+        tags::SAMPLE_FORMAT, field_types::SHORT, 1, sample_format::IEEEFP as u64));
+
+    // Serialize the samples
+    let mut bytes = Vec::with_capacity(data.len() * 4);
+    for value in data {
+        bytes.extend_from_slice(&value.to_le_bytes());
+    }
+
+    // Setup the single strip
+    builder.setup_single_strip(ifd_index, bytes);
+
+    Ok(())
+}
+
+/// Save a single-band float32 raster as a GeoTIFF
+///
+/// The georeferencing of `input_path` is copied over, so the output lines up
+/// with the raster it was derived from.
+///
+/// # Arguments
+/// * `data` - Sample values in row-major order
+/// * `width` - Raster width in pixels
+/// * `height` - Raster height in pixels
+/// * `output_path` - Path of the TIFF to write
+/// * `input_path` - Source raster to take georeferencing from
+/// * `nodata_value` - NoData value to record in the output
+/// * `logger` - Logger for recording operations
+///
+/// # Returns
+/// Result indicating success or an error
+pub fn save_float32_tiff(
+    data: &[f32],
+    width: u32,
+    height: u32,
+    output_path: &str,
+    input_path: &str,
+    nodata_value: &str,
+    logger: &Logger
+) -> TiffResult<()> {
+    let mut builder = TiffBuilder::new(logger, false);
+    let ifd_index = builder.add_ifd(IFD::new(0, 0));
+
+    process_float32_image(data, width, height, &mut builder, ifd_index)?;
+
+    // Georeference against the full source extent
+    let region = Region::new(0, 0, width, height);
+    reference_utils::add_georeferencing_to_builder(&mut builder, ifd_index, &region, input_path, logger)?;
+
+    // The derived raster has its own NoData value
+    builder.add_nodata_tag(ifd_index, nodata_value);
+
+    info!("Writing float32 raster to {}", output_path);
+    builder.write(output_path)
+}
+
+/// Save a single-band 8-bit raster as a GeoTIFF
+///
+/// Counterpart of `save_float32_tiff` for byte data, used when an operation
+/// rewrites pixel values but keeps the data type of the source.
+///
+/// # Arguments
+/// * `image` - The grayscale image to save
+/// * `output_path` - Path of the TIFF to write
+/// * `input_path` - Source raster to take georeferencing from
+/// * `nodata_value` - NoData value to record in the output
+/// * `logger` - Logger for recording operations
+///
+/// # Returns
+/// Result indicating success or an error
+pub fn save_grayscale_tiff(
+    image: &image::GrayImage,
+    output_path: &str,
+    input_path: &str,
+    nodata_value: &str,
+    logger: &Logger
+) -> TiffResult<()> {
+    let (width, height) = image.dimensions();
+
+    let mut builder = TiffBuilder::new(logger, false);
+    let ifd_index = builder.add_ifd(IFD::new(0, 0));
+
+    let dynamic_image = DynamicImage::ImageLuma8(image.clone());
+    process_grayscale_image(&dynamic_image, &mut builder, ifd_index, 8)?;
+
+    // Georeference against the full source extent
+    let region = Region::new(0, 0, width, height);
+    reference_utils::add_georeferencing_to_builder(&mut builder, ifd_index, &region, input_path, logger)?;
+    builder.add_nodata_tag(ifd_index, nodata_value);
+
+    info!("Writing 8-bit raster to {}", output_path);
+    builder.write(output_path)
+}
+
 /// Extract a NoData value from a TIFF file
 ///
 /// Reads the NoData value from a TIFF file's GDAL_NODATA tag.
@@ -194,23 +425,14 @@ pub fn process_rgb_image(
 /// The NoData value as a string
 pub fn extract_nodata_value(ifd: &IFD, reader: &TiffReader) -> String {
     // Check if GDAL_NODATA tag exists
-    let nodata_entry = match ifd.get_entry(tags::GDAL_NODATA) {
-        Some(entry) => entry,
-        None => {
-            info!("No NoData tag found in original file, using 255");
-            return "255".to_string();
-        }
-    };
-
-    // Check if the tag is of ASCII type
-    if nodata_entry.field_type != field_types::ASCII {
-        warn!("NoData tag has unexpected field type {}, using default 255", nodata_entry.field_type);
+    if !ifd.has_tag(tags::GDAL_NODATA) {
+        info!("No NoData tag found in original file, using 255");
         return "255".to_string();
     }
 
-    // Try to read the NoData value
-    let nodata_str = match reader.read_ascii_string_at_offset(nodata_entry.value_offset, nodata_entry.count) {
-        Ok(str) => str,
+    // Try to read the NoData value, short values are stored in the entry itself
+    let nodata_str = match reader.read_tag::<String>(ifd, tags::GDAL_NODATA) {
+        Ok(strings) => strings.into_iter().next().unwrap_or_default(),
         Err(e) => {
             warn!("Failed to read NoData value: {:?}, using default 255", e);
             return "255".to_string();
@@ -218,7 +440,7 @@ pub fn extract_nodata_value(ifd: &IFD, reader: &TiffReader) -> String {
     };
 
     // Process the NoData value
-    let trimmed = nodata_str.trim_end_matches('\0');
+    let trimmed = nodata_str.trim();
     info!("Found NoData value in original file: '{}'", trimmed);
 
     if trimmed == ":w" || trimmed.is_empty() {
@@ -239,19 +461,80 @@ pub fn extract_nodata_value(ifd: &IFD, reader: &TiffReader) -> String {
 /// # Returns
 /// Optional metadata string
 pub fn extract_gdal_metadata(ifd: &IFD, reader: &TiffReader) -> Option<String> {
-    // Check if GDAL_METADATA tag exists
-    let meta_entry = match ifd.get_entry(tags::GDAL_METADATA) {
-        Some(entry) => entry,
-        None => return None,
-    };
+    reader.read_tag::<String>(ifd, tags::GDAL_METADATA).ok()?.into_iter().next()
+}
 
-    // Check if the tag is of ASCII type
-    if meta_entry.field_type != field_types::ASCII {
-        return None;
+/// Select the IFD to extract from
+///
+/// # Arguments
+/// * `tiff` - Parsed source file
+/// * `sub_ifd` - SubIFD of the main IFD to read, None for the main IFD itself
+///
+/// # Returns
+/// The IFD, or an error if the file has no such IFD
+pub fn select_source_ifd(tiff: &TIFF, sub_ifd: Option<usize>) -> TiffResult<&IFD> {
+    let main_ifd = tiff.main_ifd()
+        .ok_or_else(|| TiffError::GenericError("No IFDs found in TIFF file".to_string()))?;
+
+    match sub_ifd {
+        None => Ok(main_ifd),
+        Some(index) => {
+            let ifd = tiff.sub_ifd(index).ok_or_else(|| TiffError::GenericError(format!(
+                "SubIFD {} not found, the main IFD has {} SubIFDs", index, main_ifd.sub_ifds.len())))?;
+            info!("Extracting from SubIFD {} of the main IFD", index);
+            Ok(ifd)
+        }
     }
+}
 
-    // Try to read the metadata
-    reader.read_ascii_string_at_offset(meta_entry.value_offset, meta_entry.count).ok()
+/// Scale a region from main-image pixels to another IFD of the same file
+///
+/// Regions are computed against the main image's georeferencing; a mask
+/// SubIFD has the same size, while an overview covers the same area with
+/// fewer pixels. The scaled region covers every pixel the original touches.
+///
+/// # Arguments
+/// * `region` - Region in main-image pixels
+/// * `main_ifd` - The main IFD
+/// * `ifd` - The IFD to read from
+///
+/// # Returns
+/// The region in pixels of `ifd`
+pub fn scale_region_to_ifd(region: Region, main_ifd: &IFD, ifd: &IFD) -> Region {
+    let (factor_x, factor_y) = ifd_scale_factors(main_ifd, ifd);
+    let Some((width, height)) = ifd.get_dimensions() else {
+        return region;
+    };
+    if (factor_x, factor_y) == (1.0, 1.0) {
+        return region;
+    }
+
+    let x = ((region.x as f64 / factor_x).floor() as u32).min(width.saturating_sub(1) as u32);
+    let y = ((region.y as f64 / factor_y).floor() as u32).min(height.saturating_sub(1) as u32);
+    let end_x = ((region.end_x() as f64 / factor_x).ceil() as u32).clamp(x + 1, width as u32);
+    let end_y = ((region.end_y() as f64 / factor_y).ceil() as u32).clamp(y + 1, height as u32);
+
+    let scaled = Region::new(x, y, end_x - x, end_y - y);
+    info!("Scaled region {}x{}+{}+{} to {}x{}+{}+{} for a {}x{} IFD",
+          region.width, region.height, region.x, region.y,
+          scaled.width, scaled.height, scaled.x, scaled.y, width, height);
+    scaled
+}
+
+/// Main-image pixels per pixel of another IFD, along x and y
+///
+/// # Arguments
+/// * `main_ifd` - The main IFD
+/// * `ifd` - Another IFD of the same file
+///
+/// # Returns
+/// The factors, (1.0, 1.0) when the sizes match or are unknown
+pub fn ifd_scale_factors(main_ifd: &IFD, ifd: &IFD) -> (f64, f64) {
+    match (main_ifd.get_dimensions(), ifd.get_dimensions()) {
+        (Some((main_width, main_height)), Some((width, height))) if width > 0 && height > 0 =>
+            (main_width as f64 / width as f64, main_height as f64 / height as f64),
+        _ => (1.0, 1.0),
+    }
 }
 
 /// Determine extraction region from input region and image dimensions
@@ -292,6 +575,62 @@ pub fn determine_extraction_region(region: Option<Region>, ifd: &IFD) -> TiffRes
     Ok(region)
 }
 
+/// Size of the blocks an image is stored in
+///
+/// Tiled images use their tile size; stripped images are read in
+/// full-width strips of RowsPerStrip rows.
+///
+/// # Arguments
+/// * `ifd` - IFD of the image
+///
+/// # Returns
+/// Block width and height in pixels, or None without image dimensions
+pub fn block_size(ifd: &IFD) -> Option<(u32, u32)> {
+    let (img_width, img_height) = ifd.get_dimensions()?;
+
+    if ifd.has_tag(tags::TILE_WIDTH) && ifd.has_tag(tags::TILE_LENGTH) {
+        let tile_width = ifd.get_tag_value(tags::TILE_WIDTH)? as u32;
+        let tile_height = ifd.get_tag_value(tags::TILE_LENGTH)? as u32;
+        return Some((tile_width.max(1), tile_height.max(1)));
+    }
+
+    let rows_per_strip = ifd.get_tag_value(tags::ROWS_PER_STRIP)
+        .unwrap_or(img_height)
+        .min(img_height) as u32;
+    Some((img_width as u32, rows_per_strip.max(1)))
+}
+
+/// Expand a region to the block boundaries of an image
+///
+/// Windows aligned to tiles or strips can be read without decoding
+/// partial blocks, and windows cut on the same grid line up exactly when
+/// merged later. The expanded region is clipped to the image, so edge
+/// blocks may be partial.
+///
+/// # Arguments
+/// * `region` - Region to expand
+/// * `ifd` - IFD of the image
+///
+/// # Returns
+/// The aligned region or an error if the image has no dimensions
+pub fn align_region_to_blocks(region: Region, ifd: &IFD) -> TiffResult<Region> {
+    let (img_width, img_height) = ifd.get_dimensions()
+        .ok_or_else(|| TiffError::GenericError("Missing image dimensions".to_string()))?;
+    let (block_width, block_height) = block_size(ifd)
+        .ok_or_else(|| TiffError::GenericError("Cannot determine block size".to_string()))?;
+
+    let x0 = region.x / block_width * block_width;
+    let y0 = region.y / block_height * block_height;
+    let x1 = (region.end_x().div_ceil(block_width) * block_width).min(img_width as u32);
+    let y1 = (region.end_y().div_ceil(block_height) * block_height).min(img_height as u32);
+
+    let aligned = Region::new(x0, y0, x1.saturating_sub(x0), y1.saturating_sub(y0));
+    info!("Aligned region ({}, {}) {}x{} to {}x{} blocks: ({}, {}) {}x{}",
+          region.x, region.y, region.width, region.height, block_width, block_height,
+          aligned.x, aligned.y, aligned.width, aligned.height);
+    Ok(aligned)
+}
+
 /// Get basic information about a TIFF image
 ///
 /// Extracts basic properties like bits per sample, photometric interpretation,
@@ -325,12 +664,21 @@ pub fn get_tiff_image_properties(ifd: &IFD) -> (u16, u16, u16) {
 /// Set up a common set of tags for a new TIFF file
 ///
 /// Sets up basic dimensions and copies tags from the original IFD
-/// while excluding tags that will be set separately.
+/// while excluding tags that will be set separately. ExtraSamples is never
+/// copied, since it describes the source's sample layout; it is declared
+/// here when the image carries alpha, and the pixel writers keep it in sync.
+/// The ICC profile and capture metadata (EXIF/GPS/XMP, make, model and
+/// date/time) are left to `TiffBuilder::copy_icc_profile` and
+/// `copy_exif_metadata`. SubIFDs are dropped, since the output holds no
+/// child images for them to point to. The remaining tags are deep-copied, so values the
+/// source stores outside the IFD are carried over instead of left pointing
+/// into the source file.
 ///
 /// # Arguments
 /// * `builder` - TIFF builder to configure
 /// * `ifd_index` - Index of the destination IFD
 /// * `original_ifd` - Source IFD to copy tags from
+/// * `reader` - Reader the source IFD was loaded with
 /// * `image` - The image to get dimensions from
 ///
 /// # Returns
@@ -339,6 +687,7 @@ pub fn setup_tiff_tags(
     builder: &mut TiffBuilder,
     ifd_index: usize,
     original_ifd: &IFD,
+    reader: &TiffReader,
     image: &DynamicImage
 ) -> TiffResult<()> {
     // Define tags to exclude when copying from original IFD
@@ -350,11 +699,19 @@ pub fn setup_tiff_tags(
         tags::MIN_SAMPLE_VALUE, tags::MAX_SAMPLE_VALUE,
         tags::TILE_WIDTH, tags::TILE_LENGTH, tags::TILE_OFFSETS, tags::TILE_BYTE_COUNTS,
         tags::MODEL_PIXEL_SCALE_TAG, tags::MODEL_TIEPOINT_TAG,
-        tags::GEO_KEY_DIRECTORY_TAG, tags::GEO_DOUBLE_PARAMS_TAG, tags::GEO_ASCII_PARAMS_TAG
+        tags::GEO_KEY_DIRECTORY_TAG, tags::GEO_DOUBLE_PARAMS_TAG, tags::GEO_ASCII_PARAMS_TAG,
+        tags::EXTRA_SAMPLES, tags::ICC_PROFILE,
+        tags::EXIF_IFD, tags::GPS_IFD, tags::XMP, tags::SUB_IFDS,
+        tags::MAKE, tags::MODEL, tags::DATE_TIME
     ];
 
     // Copy tags from original IFD, excluding the ones we'll handle separately
-    builder.copy_tags_from(ifd_index, original_ifd, &exclude_tags);
+    builder.deep_copy_tags_from(ifd_index, original_ifd, &exclude_tags, reader);
+
+    // RGBA and grayscale-alpha output keep their alpha channel (see process_image_data)
+    if image.color().has_alpha() {
+        builder.add_extra_samples(ifd_index, &[extra_samples::UNASSOCIATED_ALPHA]);
+    }
 
     // Add basic image structure tags
     builder.ifds[ifd_index].add_entry(IFDEntry::new(
@@ -20,7 +20,7 @@ pub fn get_field_type_size(field_type: u16) -> usize {
     match field_type {
         field_types::BYTE | field_types::ASCII | field_types::SBYTE | field_types::UNDEFINED => 1,
         field_types::SHORT | field_types::SSHORT => 2,
-        field_types::LONG | field_types::SLONG | field_types::FLOAT => 4,
+        field_types::LONG | field_types::SLONG | field_types::FLOAT | field_types::IFD => 4,
         field_types::RATIONAL | field_types::SRATIONAL | field_types::DOUBLE => 8,
         field_types::LONG8 | field_types::SLONG8 | field_types::IFD8 => 8,
         _ => 1,  // Default to 1 byte
@@ -0,0 +1,156 @@
+//! Slippy-map tile extents
+//!
+//! Web maps cut the world into tiles addressed by zoom level, column and
+//! row ("z/x/y"). A tile-rendering job asks for exactly one of those
+//! squares, so this module turns a tile address into its extent, both in
+//! the tiling's own coordinate system (for resampling onto the tile grid)
+//! and in WGS84 longitude and latitude (for selecting the source pixels).
+
+use std::f64::consts::PI;
+
+use log::info;
+
+use crate::coordinate::{BoundingBox, Crs};
+use crate::tiff::errors::{TiffError, TiffResult};
+
+/// Half the width of the Web Mercator world, in meters
+const MERCATOR_HALF_WORLD: f64 = 20037508.342789244;
+
+/// Deepest zoom level accepted, where Web Mercator tiles are a few centimeters wide
+const MAX_ZOOM: u8 = 30;
+
+/// How tiles are laid out over the world
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum TileScheme {
+    /// Web Mercator, row 0 at the top (XYZ, as OpenStreetMap and most web maps)
+    WebMercator,
+    /// Web Mercator, row 0 at the bottom (OSGeo TMS)
+    Tms,
+    /// WGS84 longitude and latitude, two tiles at zoom 0, row 0 at the top
+    Geodetic,
+}
+
+impl TileScheme {
+    /// Parse a tile scheme name
+    ///
+    /// # Arguments
+    /// * `name` - "webmercator" (or "xyz"), "tms", or "geodetic" (or "wgs84")
+    ///
+    /// # Returns
+    /// The scheme, or an error listing the known names
+    pub fn from_name(name: &str) -> TiffResult<Self> {
+        match name.trim().to_lowercase().replace(['-', '_'], "").as_str() {
+            "webmercator" | "xyz" | "googlemapscompatible" => Ok(TileScheme::WebMercator),
+            "tms" => Ok(TileScheme::Tms),
+            "geodetic" | "wgs84" | "worldcrs84quad" => Ok(TileScheme::Geodetic),
+            _ => Err(TiffError::GenericError(format!(
+                "Unknown tile scheme '{}', expected webmercator, tms or geodetic", name))),
+        }
+    }
+
+    /// Coordinate system the tiles are square in
+    pub fn crs(self) -> Crs {
+        match self {
+            TileScheme::WebMercator | TileScheme::Tms => Crs::WEB_MERCATOR,
+            TileScheme::Geodetic => Crs::WGS84,
+        }
+    }
+
+    /// Number of tile columns and rows at a zoom level
+    fn matrix_size(self, zoom: u8) -> (u64, u64) {
+        let rows = 1u64 << zoom;
+        match self {
+            TileScheme::Geodetic => (rows * 2, rows),
+            TileScheme::WebMercator | TileScheme::Tms => (rows, rows),
+        }
+    }
+}
+
+/// A tile address
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct Tile {
+    /// Zoom level
+    pub z: u8,
+    /// Column, from the left
+    pub x: u64,
+    /// Row, counted as the tile scheme does
+    pub y: u64,
+}
+
+/// Read a tile address such as "12/2200/1343"
+///
+/// # Arguments
+/// * `input` - Zoom, column and row separated by slashes
+///
+/// # Returns
+/// The tile, or an error if the input is not three whole numbers
+pub fn parse_tile(input: &str) -> TiffResult<Tile> {
+    let invalid = || TiffError::GenericError(format!("Invalid tile '{}', expected z/x/y", input));
+    let parts: Vec<&str> = input.trim().split('/').collect();
+    let [z, x, y] = parts.as_slice() else { return Err(invalid()) };
+    let z: u8 = z.trim().parse().map_err(|_| invalid())?;
+    let x: u64 = x.trim().parse().map_err(|_| invalid())?;
+    let y: u64 = y.trim().parse().map_err(|_| invalid())?;
+    if z > MAX_ZOOM {
+        return Err(TiffError::GenericError(format!("Zoom level {} is beyond the deepest, {}", z, MAX_ZOOM)));
+    }
+    Ok(Tile { z, x, y })
+}
+
+/// Extent of a tile in the coordinate system of its scheme
+///
+/// # Arguments
+/// * `tile` - The tile address
+/// * `scheme` - How the tiles are laid out
+///
+/// # Returns
+/// The tile's square, in meters for Web Mercator and degrees for the
+/// geodetic scheme, or an error if the column or row is off the matrix
+pub fn tile_bounds(tile: Tile, scheme: TileScheme) -> TiffResult<BoundingBox> {
+    let (columns, rows) = scheme.matrix_size(tile.z);
+    if tile.x >= columns || tile.y >= rows {
+        return Err(TiffError::GenericError(format!(
+            "Tile {}/{}/{} is outside the {}x{} tiles of zoom level {}", tile.z, tile.x, tile.y, columns, rows, tile.z)));
+    }
+    // Rows are counted from the top, except in TMS
+    let row_from_top = match scheme {
+        TileScheme::Tms => rows - 1 - tile.y,
+        TileScheme::WebMercator | TileScheme::Geodetic => tile.y,
+    };
+
+    let (left, top, size) = match scheme {
+        TileScheme::WebMercator | TileScheme::Tms =>
+            (-MERCATOR_HALF_WORLD, MERCATOR_HALF_WORLD, 2.0 * MERCATOR_HALF_WORLD / rows as f64),
+        TileScheme::Geodetic => (-180.0, 90.0, 180.0 / rows as f64),
+    };
+    let min_x = left + tile.x as f64 * size;
+    let max_y = top - row_from_top as f64 * size;
+    Ok(BoundingBox::new_with_crs(min_x, max_y - size, min_x + size, max_y, scheme.crs().epsg()))
+}
+
+/// Bounding box string of a tile in WGS84
+///
+/// Web Mercator keeps meridians and parallels straight, so the tile's
+/// corners give its exact longitude and latitude range.
+///
+/// # Arguments
+/// * `tile` - The tile address
+/// * `scheme` - How the tiles are laid out
+///
+/// # Returns
+/// "minlon,minlat,maxlon,maxlat", or an error for tiles off the matrix
+pub fn tile_bbox(tile: Tile, scheme: TileScheme) -> TiffResult<String> {
+    let bounds = tile_bounds(tile, scheme)?;
+    let (min_lon, min_lat, max_lon, max_lat) = match scheme {
+        TileScheme::Geodetic => (bounds.min_x, bounds.min_y, bounds.max_x, bounds.max_y),
+        TileScheme::WebMercator | TileScheme::Tms => {
+            let lon = |x: f64| x / MERCATOR_HALF_WORLD * 180.0;
+            let lat = |y: f64| (y / MERCATOR_HALF_WORLD * PI).sinh().atan().to_degrees();
+            (lon(bounds.min_x), lat(bounds.min_y), lon(bounds.max_x), lat(bounds.max_y))
+        },
+    };
+
+    let bbox = format!("{},{},{},{}", min_lon, min_lat, max_lon, max_lat);
+    info!("Tile {}/{}/{} ({:?}) covers {}", tile.z, tile.x, tile.y, scheme, bbox);
+    Ok(bbox)
+}
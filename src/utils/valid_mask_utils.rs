@@ -0,0 +1,223 @@
+//! Valid-data mask utilities
+//!
+//! A valid-data mask marks the pixels of a raster that hold data: a pixel
+//! is valid when at least one of its bands is neither the NoData value nor
+//! NaN. The mask is written as a GeoTIFF of its own (1-bit, or 8-bit with
+//! GDAL's 0/255 convention) or stored inside a copy of the raster as a
+//! TIFF transparency mask, the way GDAL writes internal masks.
+
+use log::{info, warn};
+
+use crate::extractor::Region;
+use crate::tiff::builder::TiffBuilder;
+use crate::tiff::constants::{compression, field_types, photometric, tags};
+use crate::tiff::errors::{TiffError, TiffResult};
+use crate::tiff::geo_key_parser::GeoKeyParser;
+use crate::tiff::ifd::{IFD, IFDEntry};
+use crate::tiff::validation;
+use crate::tiff::TiffReader;
+use crate::utils::logger::Logger;
+use crate::utils::repair_utils;
+use crate::utils::sample_utils;
+use crate::utils::tiff_extraction_utils;
+
+/// NewSubfileType of a transparency mask
+const SUBFILE_MASK: u64 = 4;
+
+/// Valid-data mask of a raster, possibly at a coarser resolution
+#[derive(Debug, Clone)]
+pub struct ValidMask {
+    /// Cells in a row
+    pub width: usize,
+    /// Rows of cells
+    pub height: usize,
+    /// Edge of a cell in pixels, 1 at full resolution
+    pub cell: usize,
+    /// Whether each cell holds a valid pixel, row by row
+    pub valid: Vec<bool>,
+}
+
+impl ValidMask {
+    /// Number of valid cells
+    pub fn valid_count(&self) -> usize {
+        self.valid.iter().filter(|&&valid| valid).count()
+    }
+
+    /// Pack the mask one bit per cell, most significant bit first
+    ///
+    /// Every row starts on a new byte, as TIFF stores 1-bit images.
+    pub fn pack_bits(&self) -> Vec<u8> {
+        let row_bytes = self.width.div_ceil(8);
+        let mut packed = vec![0u8; row_bytes * self.height];
+        for (index, _) in self.valid.iter().enumerate().filter(|(_, &valid)| valid) {
+            let (row, column) = (index / self.width, index % self.width);
+            packed[row * row_bytes + column / 8] |= 0x80 >> (column % 8);
+        }
+        packed
+    }
+}
+
+/// How to write a valid-data mask
+#[derive(Debug, Clone)]
+pub struct ValidMaskOptions {
+    /// Bits per mask pixel, 1 or 8
+    pub bits: u16,
+    /// Store the mask inside a copy of the raster instead of a file of its own
+    pub internal: bool,
+    /// NoData value overriding the one declared by the raster
+    pub nodata: Option<f64>,
+}
+
+impl Default for ValidMaskOptions {
+    fn default() -> Self {
+        ValidMaskOptions {
+            bits: 1,
+            internal: false,
+            nodata: None,
+        }
+    }
+}
+
+/// Build the valid-data mask of a TIFF's first image
+///
+/// With a cell limit, pixels are merged into square cells so that the
+/// mask is no wider or taller than the limit; a cell is valid when any
+/// of its pixels is.
+///
+/// # Arguments
+/// * `input_path` - Path to the TIFF
+/// * `nodata` - NoData value, if any
+/// * `max_cells` - Largest width or height of the mask, None for full resolution
+/// * `logger` - Logger for recording operations
+///
+/// # Returns
+/// The mask
+pub fn read_valid_mask(input_path: &str, nodata: Option<f64>, max_cells: Option<usize>,
+                       logger: &Logger) -> TiffResult<ValidMask> {
+    let mut mask = ValidMask { width: 0, height: 0, cell: 1, valid: Vec::new() };
+
+    sample_utils::read_blocks(
+        input_path, logger,
+        |_, _| true,
+        |layout, block| {
+            if mask.valid.is_empty() {
+                let longest = layout.width.max(layout.height) as usize;
+                mask.cell = max_cells.map_or(1, |cells| longest.div_ceil(cells.max(1)).max(1));
+                mask.width = (layout.width as usize).div_ceil(mask.cell);
+                mask.height = (layout.height as usize).div_ceil(mask.cell);
+                mask.valid = vec![false; mask.width * mask.height];
+            }
+            for row in 0..block.height {
+                for column in 0..block.width {
+                    let index = (block.y + row) as usize / mask.cell * mask.width
+                        + (block.x + column) as usize / mask.cell;
+                    if mask.valid[index] {
+                        continue;
+                    }
+                    mask.valid[index] = block.bands.clone().any(|band| {
+                        block.sample(column, row, band)
+                            .map(|raw| layout.value(raw))
+                            .is_some_and(|value| !value.is_nan() && nodata != Some(value))
+                    });
+                }
+            }
+        })?;
+
+    Ok(mask)
+}
+
+/// Georeference the mask IFD like the source image
+///
+/// Only the geotransform and GeoTIFF keys are copied; the source's NoData
+/// value means nothing for mask values.
+fn copy_georeferencing(builder: &mut TiffBuilder, ifd_index: usize, source_ifd: &IFD,
+                       reader: &mut TiffReader, input_path: &str, width: u32, height: u32) -> TiffResult<()> {
+    if let Some(handler) = reader.get_byte_order_handler() {
+        let file_path = reader.get_file_path().unwrap_or(input_path).to_string();
+        let pixel_scale = GeoKeyParser::read_model_pixel_scale_values(source_ifd, handler, &file_path);
+        let tiepoint = GeoKeyParser::read_model_tiepoint_values(source_ifd, handler, &file_path);
+        if let (Ok(pixel_scale), Ok(tiepoint)) = (pixel_scale, tiepoint) {
+            let region = Region::new(0, 0, width, height);
+            builder.adjust_geotiff_for_region(ifd_index, &region, &pixel_scale, &tiepoint)?;
+        }
+    }
+    builder.copy_geotiff_tags(ifd_index, source_ifd, reader)
+}
+
+/// Add an IFD holding a mask to a builder
+fn add_mask_ifd(builder: &mut TiffBuilder, mask: &ValidMask, bits: u16) -> usize {
+    let ifd_index = builder.add_ifd(IFD::new(builder.ifds.len(), 0));
+    builder.add_basic_gray_tags(ifd_index, mask.width as u32, mask.height as u32, bits);
+
+    let data = if bits == 1 {
+        mask.pack_bits()
+    } else {
+        mask.valid.iter().map(|&valid| if valid { 255 } else { 0 }).collect()
+    };
+    builder.setup_single_strip(ifd_index, data);
+    ifd_index
+}
+
+/// Write the valid-data mask of a raster
+///
+/// # Arguments
+/// * `input_path` - Path to the raster
+/// * `output_path` - Path of the TIFF to write
+/// * `options` - Bit depth, internal or separate mask, NoData override
+/// * `logger` - Logger for recording operations
+///
+/// # Returns
+/// The mask that was written
+pub fn write_valid_mask(input_path: &str, output_path: &str, options: &ValidMaskOptions,
+                        logger: &Logger) -> TiffResult<ValidMask> {
+    if !matches!(options.bits, 1 | 8) {
+        return Err(TiffError::GenericError(format!("Masks have 1 or 8 bits per pixel, not {}", options.bits)));
+    }
+
+    let mut reader = TiffReader::new(logger);
+    let tiff = reader.load(input_path)?;
+    let ifd = tiff.main_ifd()
+        .ok_or_else(|| TiffError::GenericError("No IFDs found in TIFF file".to_string()))?;
+
+    let nodata = options.nodata.or_else(|| if ifd.has_tag(tags::GDAL_NODATA) {
+        tiff_extraction_utils::extract_nodata_value(ifd, &reader).trim().parse::<f64>().ok()
+    } else {
+        None
+    });
+    if nodata.is_none() {
+        warn!("{} declares no NoData value, only NaN pixels are masked", input_path);
+    }
+
+    let mask = read_valid_mask(input_path, nodata, None, logger)?;
+    let (width, height) = (mask.width as u32, mask.height as u32);
+    info!("{} of {} pixels hold valid data", mask.valid_count(), mask.valid.len());
+
+    let mut builder = TiffBuilder::new(logger, tiff.is_big_tiff);
+    if options.internal {
+        // The image is rebuilt uncompressed, as a repaired copy is, and the mask follows it
+        let file_size = validation::get_file_size(&mut reader.create_reader()?)?;
+        let (raster, _) = repair_utils::salvage_image(&reader, ifd, file_size)
+            .map_err(|e| TiffError::GenericError(format!("Cannot copy the image of {}: {}", input_path, e)))?;
+        let image_index = builder.add_ifd(IFD::new(0, 0));
+        builder.deep_copy_tags_from(image_index, ifd, &repair_utils::REBUILT_TAGS, &reader);
+        builder.ifds[image_index].add_entry(IFDEntry::new(
+            tags::COMPRESSION, field_types::SHORT, 1, compression::NONE as u64));
+        builder.setup_single_strip(image_index, raster);
+
+        let mask_index = add_mask_ifd(&mut builder, &mask, 1);
+        let mask_ifd = &mut builder.ifds[mask_index];
+        mask_ifd.add_entry(IFDEntry::new(tags::NEW_SUBFILE_TYPE, field_types::LONG, 1, SUBFILE_MASK));
+        mask_ifd.add_entry(IFDEntry::new(
+            tags::PHOTOMETRIC_INTERPRETATION, field_types::SHORT, 1, photometric::TRANSPARENCY_MASK as u64));
+        if options.bits != 1 {
+            info!("Internal masks are always stored with 1 bit per pixel");
+        }
+    } else {
+        let mask_index = add_mask_ifd(&mut builder, &mask, options.bits);
+        copy_georeferencing(&mut builder, mask_index, ifd, &mut reader, input_path, width, height)?;
+    }
+
+    builder.write(output_path)?;
+    info!("Wrote {} valid-data mask to {}", if options.internal { "internal" } else { "external" }, output_path);
+    Ok(mask)
+}
@@ -0,0 +1,626 @@
+//! Raster warping utilities
+//!
+//! Resamples an extracted image from its own coordinate system onto a
+//! north-up grid in another one. The target grid is laid over the
+//! transformed footprint of the source, and every target pixel is traced
+//! back to the source pixel under its centre (nearest neighbour), so the
+//! output has no gaps however the projection stretches the image.
+//!
+//! The target resolution can be fixed (`--tr`) and the grid origin snapped
+//! to a multiple of it (`--tap`), so rasters warped from different sources
+//! land on the same pixel grid and stack without resampling.
+//!
+//! With a memory budget, the output is warped block by block and each
+//! block reads only the source window it maps onto, so large warps do not
+//! need the whole source in memory.
+//!
+//! Projecting every output pixel exactly is the bulk of the cost of a warp.
+//! Within an error threshold the transform is instead computed exactly on
+//! a coarse grid of output pixels and interpolated bilinearly in between;
+//! cells where interpolation strays further than the threshold from the
+//! exact transform are split until it does not.
+//!
+//! With `gpu`, each block's pixels are gathered from its source window by
+//! the GPU backend in `gpu_utils`, falling back to the CPU loop if the
+//! backend is unavailable or fails.
+
+use image::{DynamicImage, ImageBuffer, Pixel};
+use log::{debug, info, warn};
+
+use crate::coordinate::{projection, Affine, BoundingBox, CoordinateTransformer};
+use crate::extractor::Region;
+use crate::tiff::errors::{TiffError, TiffResult};
+use crate::tiff::proj_definition::ProjDefinition;
+use crate::utils::gpu_utils::{self, GpuResampler, Sampling};
+
+/// Points sampled along each edge of the source to find its footprint
+const EDGE_SAMPLES: usize = 20;
+
+/// Smallest output block worth warping on its own
+const MIN_BLOCK_SIZE: u32 = 64;
+
+/// Bytes per output pixel of a block's source lookup
+const LOOKUP_BYTES: usize = 16;
+
+/// Bytes in a megabyte, for log messages
+const MEGABYTE: f64 = 1024.0 * 1024.0;
+
+/// Default error threshold of the approximate transform, in source pixels
+pub const DEFAULT_ERROR_THRESHOLD: f64 = 0.125;
+
+/// Cells of at most this many pixels across are transformed exactly
+const MIN_APPROXIMATION_CELL: u32 = 4;
+
+/// How the target grid of a warp is laid out
+#[derive(Debug, Clone, Default)]
+pub struct WarpOptions {
+    /// Target pixel size (x, y) in target units, None to keep roughly the
+    /// number of source pixels
+    pub resolution: Option<(f64, f64)>,
+    /// Snap the grid extent to multiples of the resolution
+    pub target_aligned: bool,
+    /// Area the grid covers in target coordinates, None for the footprint
+    /// of the source region
+    pub target_extent: Option<BoundingBox>,
+    /// Memory in bytes the warp may use, None to warp in one block
+    pub memory_budget: Option<usize>,
+    /// Source value marking pixels without data
+    pub source_nodata: Option<f64>,
+    /// Value given to output pixels without data
+    pub target_nodata: Option<f64>,
+    /// Add an alpha channel, clear where the output has no data
+    pub target_alpha: bool,
+    /// Largest error in source pixels the approximate transform may make,
+    /// 0 to transform every pixel exactly
+    pub error_threshold: f64,
+    /// Gather the output pixels on the GPU when one is available
+    pub gpu: bool,
+}
+
+/// Transforms points between a source and a target coordinate system
+pub struct WarpTransformer<'a> {
+    /// System of the raster being warped
+    source: &'a ProjDefinition,
+    /// System it is warped into
+    target: &'a ProjDefinition,
+    /// Whether the two are the same system
+    identity: bool,
+}
+
+impl<'a> WarpTransformer<'a> {
+    /// Create a transformer between two coordinate systems
+    ///
+    /// # Returns
+    /// The transformer, or an error if either projection method is not implemented
+    pub fn new(source: &'a ProjDefinition, target: &'a ProjDefinition) -> TiffResult<Self> {
+        for definition in [source, target] {
+            if !projection::is_supported(definition) {
+                return Err(TiffError::GenericError(format!(
+                    "Warping through the {} projection ({}) is not supported", definition.method_name, definition.name)));
+            }
+        }
+        Ok(WarpTransformer { source, target, identity: source.is_equivalent(target) })
+    }
+
+    /// Project a point from one system into the other, across datums
+    fn project(x: f64, y: f64, from: &ProjDefinition, to: &ProjDefinition) -> Option<(f64, f64)> {
+        let geographic = projection::inverse(from, x, y)?;
+        let point = CoordinateTransformer.geographic_to_definition(geographic.x, geographic.y, from, to, None)?;
+        (point.x.is_finite() && point.y.is_finite()).then_some((point.x, point.y))
+    }
+
+    /// Transform a source point into target coordinates
+    pub fn source_to_target(&self, x: f64, y: f64) -> Option<(f64, f64)> {
+        if self.identity {
+            return Some((x, y));
+        }
+        Self::project(x, y, self.source, self.target)
+    }
+
+    /// Transform a target point into source coordinates
+    pub fn target_to_source(&self, x: f64, y: f64) -> Option<(f64, f64)> {
+        if self.identity {
+            return Some((x, y));
+        }
+        Self::project(x, y, self.target, self.source)
+    }
+}
+
+/// Footprint of a raster in target coordinates
+///
+/// The edges are sampled, since projections bend straight lines.
+///
+/// # Arguments
+/// * `transformer` - Transformer from the raster's system
+/// * `geotransform` - Geotransform of the raster
+/// * `width` - Raster width in pixels
+/// * `height` - Raster height in pixels
+///
+/// # Returns
+/// The envelope of the transformed edges, or None if no edge point transforms
+pub fn target_extent(transformer: &WarpTransformer, geotransform: &Affine, width: u32, height: u32) -> Option<BoundingBox> {
+    let (width, height) = (width as f64, height as f64);
+    let mut envelope: Option<BoundingBox> = None;
+    for i in 0..=EDGE_SAMPLES {
+        let f = i as f64 / EDGE_SAMPLES as f64;
+        for (column, row) in [(f * width, 0.0), (f * width, height), (0.0, f * height), (width, f * height)] {
+            let (x, y) = geotransform.apply(column, row);
+            let Some((x, y)) = transformer.source_to_target(x, y) else { continue };
+            envelope = Some(match envelope {
+                Some(e) => BoundingBox::new(e.min_x.min(x), e.min_y.min(y), e.max_x.max(x), e.max_y.max(y)),
+                None => BoundingBox::new(x, y, x, y),
+            });
+        }
+    }
+    envelope
+}
+
+/// Lay out the target grid over an extent
+///
+/// Without a fixed resolution, square pixels are chosen so that the
+/// diagonal of the extent spans as many pixels as that of the source.
+/// With `target_aligned`, the extent grows outwards to the nearest
+/// multiples of the resolution, as `gdalwarp -tap` does.
+///
+/// # Arguments
+/// * `extent` - Area to cover, in target coordinates
+/// * `source_width` - Width of the source in pixels
+/// * `source_height` - Height of the source in pixels
+/// * `options` - Resolution and alignment
+///
+/// # Returns
+/// The geotransform, width and height of the grid
+pub fn target_grid(extent: &BoundingBox, source_width: u32, source_height: u32,
+                   options: &WarpOptions) -> TiffResult<(Affine, u32, u32)> {
+    let (x_resolution, y_resolution) = match options.resolution {
+        Some((x, y)) if x > 0.0 && y > 0.0 => (x, y),
+        Some((x, y)) => return Err(TiffError::GenericError(format!(
+            "Target resolution must be positive, got {},{}", x, y))),
+        None if options.target_aligned => return Err(TiffError::GenericError(
+            "Aligning the grid (--tap) needs a target resolution (--tr)".to_string())),
+        None => {
+            let diagonal = (extent.width().powi(2) + extent.height().powi(2)).sqrt();
+            let pixels = ((source_width as f64).powi(2) + (source_height as f64).powi(2)).sqrt();
+            let resolution = diagonal / pixels.max(1.0);
+            if resolution <= 0.0 || !resolution.is_finite() {
+                return Err(TiffError::GenericError("The warped extent is empty".to_string()));
+            }
+            (resolution, resolution)
+        },
+    };
+
+    let (mut min_x, mut min_y, mut max_x, mut max_y) = (extent.min_x, extent.min_y, extent.max_x, extent.max_y);
+    if options.target_aligned {
+        min_x = (min_x / x_resolution).floor() * x_resolution;
+        max_x = (max_x / x_resolution).ceil() * x_resolution;
+        min_y = (min_y / y_resolution).floor() * y_resolution;
+        max_y = (max_y / y_resolution).ceil() * y_resolution;
+    }
+
+    let width = ((max_x - min_x) / x_resolution - 1e-6).ceil().max(1.0) as u32;
+    let height = ((max_y - min_y) / y_resolution - 1e-6).ceil().max(1.0) as u32;
+    info!("Target grid of {}x{} pixels of {}x{} from ({}, {})", width, height, x_resolution, y_resolution, min_x, max_y);
+    Ok((Affine::new(min_x, x_resolution, max_y, -y_resolution), width, height))
+}
+
+/// Side in pixels of the square output blocks warped at a time
+///
+/// Without a budget the output is warped in one block. With one, the
+/// output image is set aside first, and the rest is divided into blocks
+/// whose source window, pixel lookup and share of the output fit it. The
+/// source window of a block is estimated from the ratio of source to
+/// target pixels.
+///
+/// # Arguments
+/// * `width` - Output width in pixels
+/// * `height` - Output height in pixels
+/// * `source_pixels` - Number of source pixels warped
+/// * `bytes_per_pixel` - Bytes per pixel of the source and output images
+/// * `budget` - Memory budget in bytes, None for no limit
+///
+/// # Returns
+/// The block side in pixels
+pub fn block_size(width: u32, height: u32, source_pixels: u64, bytes_per_pixel: usize, budget: Option<usize>) -> u32 {
+    let largest = width.max(height).max(1);
+    let Some(budget) = budget else {
+        return largest;
+    };
+
+    let target_pixels = (width as u64 * height as u64).max(1);
+    let output_bytes = target_pixels as usize * bytes_per_pixel;
+    let Some(remaining) = budget.checked_sub(output_bytes).filter(|&remaining| remaining > 0) else {
+        warn!("The {}x{} output alone takes {:.1} MB, over the {:.1} MB budget; warping in the smallest blocks",
+              width, height, output_bytes as f64 / MEGABYTE, budget as f64 / MEGABYTE);
+        return MIN_BLOCK_SIZE.min(largest);
+    };
+
+    let source_per_target = source_pixels as f64 / target_pixels as f64;
+    let bytes_per_block_pixel = bytes_per_pixel as f64 * source_per_target.max(1.0) + LOOKUP_BYTES as f64;
+    let side = (remaining as f64 / bytes_per_block_pixel).sqrt() as u32;
+    side.clamp(MIN_BLOCK_SIZE.min(largest), largest)
+}
+
+/// Maps output pixels onto the source pixels beneath their centres
+pub struct PixelMapping<'a> {
+    /// Geotransform of the output grid
+    target_geotransform: Affine,
+    /// Transformer from the source to the target system
+    transformer: &'a WarpTransformer<'a>,
+    /// Inverse geotransform of the source
+    to_source_pixels: Affine,
+    /// Largest interpolation error allowed, in source pixels
+    error_threshold: f64,
+}
+
+impl<'a> PixelMapping<'a> {
+    /// Create a mapping from an output grid onto a source grid
+    ///
+    /// # Arguments
+    /// * `target_geotransform` - Geotransform of the output grid
+    /// * `transformer` - Transformer from the source to the target system
+    /// * `source_geotransform` - Geotransform of the source
+    /// * `error_threshold` - Largest error in source pixels, 0 for an exact mapping
+    ///
+    /// # Returns
+    /// The mapping, or an error if the source geotransform cannot be inverted
+    pub fn new(target_geotransform: Affine, transformer: &'a WarpTransformer<'a>, source_geotransform: &Affine,
+               error_threshold: f64) -> TiffResult<Self> {
+        let to_source_pixels = source_geotransform.inverse()
+            .ok_or_else(|| TiffError::GenericError("The source geotransform is degenerate".to_string()))?;
+        Ok(PixelMapping { target_geotransform, transformer, to_source_pixels, error_threshold })
+    }
+
+    /// Exact source pixel position under the centre of an output pixel
+    pub fn exact(&self, column: u32, row: u32) -> Option<(f64, f64)> {
+        let (x, y) = self.target_geotransform.apply(column as f64 + 0.5, row as f64 + 0.5);
+        let (x, y) = self.transformer.target_to_source(x, y)?;
+        Some(self.to_source_pixels.apply(x, y))
+    }
+
+    /// Source pixel positions of a rectangle of output pixels
+    ///
+    /// # Arguments
+    /// * `x0`, `y0` - First output column and row
+    /// * `x1`, `y1` - Output column and row just past the rectangle
+    ///
+    /// # Returns
+    /// The positions row by row, and how many of them were transformed exactly
+    pub fn positions(&self, x0: u32, y0: u32, x1: u32, y1: u32) -> (Vec<Option<(f64, f64)>>, usize) {
+        let mut positions = vec![None; (x1 - x0) as usize * (y1 - y0) as usize];
+        let mut exact = 0;
+        if self.error_threshold <= 0.0 {
+            for row in y0..y1 {
+                for column in x0..x1 {
+                    positions[((row - y0) * (x1 - x0) + column - x0) as usize] = self.exact(column, row);
+                }
+            }
+            let count = positions.len();
+            return (positions, count);
+        }
+
+        // Cells still to fill, split whenever interpolating them is too coarse
+        let stride = (x1 - x0) as usize;
+        let mut cells = vec![(x0, y0, x1, y1)];
+        while let Some((cx0, cy0, cx1, cy1)) = cells.pop() {
+            let (width, height) = (cx1 - cx0, cy1 - cy0);
+            let index = |column: u32, row: u32| (row - y0) as usize * stride + (column - x0) as usize;
+
+            if width.max(height) > MIN_APPROXIMATION_CELL {
+                let (last_column, last_row) = (cx1 - 1, cy1 - 1);
+                let corners = [self.exact(cx0, cy0), self.exact(last_column, cy0),
+                               self.exact(cx0, last_row), self.exact(last_column, last_row)];
+                exact += 4;
+                if let [Some(top_left), Some(top_right), Some(bottom_left), Some(bottom_right)] = corners {
+                    let interpolate = |column: u32, row: u32| {
+                        let fx = if last_column > cx0 { (column - cx0) as f64 / (last_column - cx0) as f64 } else { 0.0 };
+                        let fy = if last_row > cy0 { (row - cy0) as f64 / (last_row - cy0) as f64 } else { 0.0 };
+                        let top = (top_left.0 + (top_right.0 - top_left.0) * fx, top_left.1 + (top_right.1 - top_left.1) * fx);
+                        let bottom = (bottom_left.0 + (bottom_right.0 - bottom_left.0) * fx,
+                                      bottom_left.1 + (bottom_right.1 - bottom_left.1) * fx);
+                        (top.0 + (bottom.0 - top.0) * fy, top.1 + (bottom.1 - top.1) * fy)
+                    };
+
+                    // Check the interpolation at the centre and edge midpoints
+                    let (middle_column, middle_row) = (cx0 + width / 2, cy0 + height / 2);
+                    let checks = [(middle_column, middle_row), (middle_column, cy0), (middle_column, last_row),
+                                  (cx0, middle_row), (last_column, middle_row)];
+                    exact += checks.len();
+                    let within = checks.iter().all(|&(column, row)| {
+                        self.exact(column, row).is_some_and(|(x, y)| {
+                            let (ix, iy) = interpolate(column, row);
+                            (x - ix).abs().max((y - iy).abs()) <= self.error_threshold
+                        })
+                    });
+                    if within {
+                        for row in cy0..cy1 {
+                            for column in cx0..cx1 {
+                                positions[index(column, row)] = Some(interpolate(column, row));
+                            }
+                        }
+                        continue;
+                    }
+                }
+
+                let (middle_column, middle_row) = (cx0 + width.div_ceil(2), cy0 + height.div_ceil(2));
+                for (sx0, sx1) in [(cx0, middle_column), (middle_column, cx1)] {
+                    for (sy0, sy1) in [(cy0, middle_row), (middle_row, cy1)] {
+                        if sx1 > sx0 && sy1 > sy0 {
+                            cells.push((sx0, sy0, sx1, sy1));
+                        }
+                    }
+                }
+                continue;
+            }
+
+            for row in cy0..cy1 {
+                for column in cx0..cx1 {
+                    positions[index(column, row)] = self.exact(column, row);
+                }
+            }
+            exact += (width * height) as usize;
+        }
+        (positions, exact)
+    }
+}
+
+/// Whether a pixel type ends in an alpha channel ("YA", "RGBA")
+fn has_alpha<P: Pixel>() -> bool {
+    P::COLOR_MODEL.ends_with('A')
+}
+
+/// Whether a pixel holds data
+///
+/// Pixels with a zero alpha, or whose colour channels all equal the
+/// NoData value, do not.
+pub(crate) fn has_data<P: Pixel>(pixel: &P, nodata: Option<f64>) -> bool
+where
+    P::Subpixel: Into<f64>,
+{
+    let channels = pixel.channels();
+    let colour = if has_alpha::<P>() {
+        let (colour, alpha) = channels.split_at(channels.len() - 1);
+        if alpha[0].into() == 0.0 {
+            return false;
+        }
+        colour
+    } else {
+        channels
+    };
+    nodata.is_none_or(|nodata| colour.iter().any(|&value| value.into() != nodata))
+}
+
+/// Copy the source pixels with data a block maps onto into the output
+///
+/// # Returns
+/// The number of output pixels that received data
+#[allow(clippy::too_many_arguments)]
+fn copy_block<P: Pixel>(window: &ImageBuffer<P, Vec<P::Subpixel>>, target: &mut ImageBuffer<P, Vec<P::Subpixel>>,
+                        lookup: &[(u32, u32, u32, u32)], origin: (u32, u32), nodata: Option<f64>,
+                        gpu: Option<&GpuResampler>, convert: fn(f64) -> P::Subpixel,
+                        covered: &mut [bool]) -> usize
+where
+    P::Subpixel: Into<f64>,
+{
+    if let Some(resampler) = gpu {
+        match gpu_copy_block(resampler, window, target, lookup, origin, nodata, convert, covered) {
+            Ok(copied) => return copied,
+            Err(e) => warn!("{}, gathering the block on the CPU", e),
+        }
+    }
+
+    let mut copied = 0;
+    for &(column, row, source_column, source_row) in lookup {
+        let (x, y) = (source_column - origin.0, source_row - origin.1);
+        if x >= window.width() || y >= window.height() {
+            continue;
+        }
+        let pixel = window.get_pixel(x, y);
+        if has_data(pixel, nodata) {
+            target.put_pixel(column, row, *pixel);
+            covered[row as usize * target.width() as usize + column as usize] = true;
+            copied += 1;
+        }
+    }
+    copied
+}
+
+/// Gather the source pixels a block maps onto on the GPU, channel by channel
+///
+/// # Returns
+/// The number of output pixels that received data, or an error from the GPU
+#[allow(clippy::too_many_arguments)]
+fn gpu_copy_block<P: Pixel>(resampler: &GpuResampler, window: &ImageBuffer<P, Vec<P::Subpixel>>,
+                            target: &mut ImageBuffer<P, Vec<P::Subpixel>>, lookup: &[(u32, u32, u32, u32)],
+                            origin: (u32, u32), nodata: Option<f64>, convert: fn(f64) -> P::Subpixel,
+                            covered: &mut [bool]) -> TiffResult<usize>
+where
+    P::Subpixel: Into<f64>,
+{
+    let valid: Vec<bool> = window.pixels().map(|pixel| has_data(pixel, nodata)).collect();
+    let positions: Vec<[f32; 2]> = lookup.iter()
+        .map(|&(_, _, source_column, source_row)| {
+            [(source_column - origin.0) as f32 + 0.5, (source_row - origin.1) as f32 + 0.5]
+        })
+        .collect();
+
+    let mut channels = Vec::with_capacity(P::CHANNEL_COUNT as usize);
+    for channel in 0..P::CHANNEL_COUNT as usize {
+        let band: Vec<f32> = window.pixels().zip(&valid)
+            .map(|(pixel, &valid)| if valid { pixel.channels()[channel].into() as f32 } else { gpu_utils::NO_DATA })
+            .collect();
+        channels.push(resampler.resample(&band, window.width(), window.height(), &positions, Sampling::Nearest)?);
+    }
+
+    let mut copied = 0;
+    for (index, &(column, row, _, _)) in lookup.iter().enumerate() {
+        if channels[0][index] == gpu_utils::NO_DATA {
+            continue;
+        }
+        let pixel = target.get_pixel_mut(column, row);
+        for (channel, values) in channels.iter().enumerate() {
+            pixel.channels_mut()[channel] = convert(values[index] as f64);
+        }
+        covered[row as usize * target.width() as usize + column as usize] = true;
+        copied += 1;
+    }
+    Ok(copied)
+}
+
+/// Mark the output pixels that received no data
+///
+/// Their colour channels take the NoData value, when there is one, and
+/// their alpha channel is cleared.
+fn mark_missing<P: Pixel>(target: &mut ImageBuffer<P, Vec<P::Subpixel>>, covered: &[bool], nodata: Option<f64>,
+                          convert: fn(f64) -> P::Subpixel) {
+    let colour_channels = P::CHANNEL_COUNT as usize - usize::from(has_alpha::<P>());
+    for (pixel, _) in target.pixels_mut().zip(covered).filter(|(_, covered)| !**covered) {
+        let channels = pixel.channels_mut();
+        if let Some(nodata) = nodata {
+            channels[..colour_channels].iter_mut().for_each(|channel| *channel = convert(nodata));
+        }
+        if has_alpha::<P>() {
+            channels[colour_channels] = convert(0.0);
+        }
+    }
+}
+
+/// Add an alpha channel to an image that has none
+fn with_alpha(image: DynamicImage) -> DynamicImage {
+    match image {
+        DynamicImage::ImageLuma8(_) => DynamicImage::ImageLumaA8(image.to_luma_alpha8()),
+        DynamicImage::ImageRgb8(_) => DynamicImage::ImageRgba8(image.to_rgba8()),
+        DynamicImage::ImageLuma16(_) => DynamicImage::ImageLumaA16(image.to_luma_alpha16()),
+        DynamicImage::ImageRgb16(_) => DynamicImage::ImageRgba16(image.to_rgba16()),
+        DynamicImage::ImageRgb32F(_) => DynamicImage::ImageRgba32F(image.to_rgba32f()),
+        image => image,
+    }
+}
+
+/// Warp a source window into another coordinate system, block by block
+///
+/// The output is divided into blocks sized by `block_size`; each block
+/// traces its pixels back to the source, reads the source window they
+/// fall in and copies them over, so only one window is in memory at a
+/// time. Blocks are processed one after another.
+///
+/// Source pixels equal to the source NoData value, or with a zero alpha,
+/// are not copied. Output pixels left without data take the target NoData
+/// value (zero without one) and, when an alpha channel is requested or
+/// the source has one, a zero alpha.
+///
+/// # Arguments
+/// * `source` - Window of the source raster to warp, in its pixels
+/// * `geotransform` - Geotransform of the whole source raster
+/// * `transformer` - Transformer from the source to the target system
+/// * `options` - Target grid layout and memory budget
+/// * `bytes_per_pixel` - Estimated bytes per pixel of the images read
+/// * `read_window` - Reads a window of the source raster
+///
+/// # Returns
+/// The warped image and its geotransform in target coordinates
+pub fn warp_windows<F>(source: Region, geotransform: &Affine, transformer: &WarpTransformer, options: &WarpOptions,
+                       bytes_per_pixel: usize, mut read_window: F) -> TiffResult<(DynamicImage, Affine)>
+where
+    F: FnMut(Region) -> TiffResult<DynamicImage>,
+{
+    let window_geotransform = geotransform.compose(&Affine::new(source.x as f64, 1.0, source.y as f64, 1.0));
+    let extent = match options.target_extent {
+        Some(extent) => extent,
+        None => target_extent(transformer, &window_geotransform, source.width, source.height)
+            .ok_or_else(|| TiffError::GenericError("The image cannot be transformed into the target system".to_string()))?,
+    };
+    let (target_geotransform, width, height) = target_grid(&extent, source.width, source.height, options)?;
+    let mapping = PixelMapping::new(target_geotransform, transformer, geotransform, options.error_threshold)?;
+
+    let source_pixels = source.width as u64 * source.height as u64;
+    let side = block_size(width, height, source_pixels, bytes_per_pixel, options.memory_budget);
+    let (blocks_across, blocks_down) = (width.div_ceil(side), height.div_ceil(side));
+    let single_block = blocks_across == 1 && blocks_down == 1;
+    info!("Warping {}x{} source pixels onto {}x{} in {} block(s) of up to {} pixels square",
+          source.width, source.height, width, height, blocks_across * blocks_down, side);
+
+    let mut output: Option<DynamicImage> = None;
+    let mut covered = vec![false; width as usize * height as usize];
+    let mut copied = 0usize;
+    let mut transformed = 0usize;
+    let resampler = if options.gpu { GpuResampler::new() } else { None };
+    let gpu = resampler.as_ref();
+    for block_row in 0..blocks_down {
+        for block_column in 0..blocks_across {
+            let (x0, y0) = (block_column * side, block_row * side);
+            let (x1, y1) = ((x0 + side).min(width), (y0 + side).min(height));
+
+            // Source pixel under the centre of each block pixel
+            let (positions, exact) = mapping.positions(x0, y0, x1, y1);
+            transformed += exact;
+            let mut lookup = Vec::new();
+            for (index, position) in positions.into_iter().enumerate() {
+                let Some((source_column, source_row)) = position else { continue };
+                if source_column >= source.x as f64 && source_row >= source.y as f64
+                    && source_column < source.end_x() as f64 && source_row < source.end_y() as f64 {
+                    let (column, row) = (x0 + index as u32 % (x1 - x0), y0 + index as u32 / (x1 - x0));
+                    lookup.push((column, row, source_column as u32, source_row as u32));
+                }
+            }
+            if lookup.is_empty() {
+                continue;
+            }
+
+            // Read only the source pixels the block needs
+            let window = if single_block {
+                source
+            } else {
+                let (min_column, max_column) = lookup.iter()
+                    .fold((u32::MAX, 0), |(low, high), entry| (low.min(entry.2), high.max(entry.2)));
+                let (min_row, max_row) = lookup.iter()
+                    .fold((u32::MAX, 0), |(low, high), entry| (low.min(entry.3), high.max(entry.3)));
+                Region::new(min_column, min_row, max_column - min_column + 1, max_row - min_row + 1)
+            };
+            debug!("Block ({}, {}) reads source window {:?}", block_column, block_row, window);
+            let image = read_window(window)?;
+
+            let target = output.get_or_insert_with(|| DynamicImage::new(width, height, image.color()));
+            let origin = (window.x, window.y);
+            copied += match (target, &image) {
+                (DynamicImage::ImageLuma8(target), DynamicImage::ImageLuma8(image)) => copy_block(image, target, &lookup, origin, options.source_nodata, gpu, |value| value as u8, &mut covered),
+                (DynamicImage::ImageLumaA8(target), DynamicImage::ImageLumaA8(image)) => copy_block(image, target, &lookup, origin, options.source_nodata, gpu, |value| value as u8, &mut covered),
+                (DynamicImage::ImageRgb8(target), DynamicImage::ImageRgb8(image)) => copy_block(image, target, &lookup, origin, options.source_nodata, gpu, |value| value as u8, &mut covered),
+                (DynamicImage::ImageRgba8(target), DynamicImage::ImageRgba8(image)) => copy_block(image, target, &lookup, origin, options.source_nodata, gpu, |value| value as u8, &mut covered),
+                (DynamicImage::ImageLuma16(target), DynamicImage::ImageLuma16(image)) => copy_block(image, target, &lookup, origin, options.source_nodata, gpu, |value| value as u16, &mut covered),
+                (DynamicImage::ImageLumaA16(target), DynamicImage::ImageLumaA16(image)) => copy_block(image, target, &lookup, origin, options.source_nodata, gpu, |value| value as u16, &mut covered),
+                (DynamicImage::ImageRgb16(target), DynamicImage::ImageRgb16(image)) => copy_block(image, target, &lookup, origin, options.source_nodata, gpu, |value| value as u16, &mut covered),
+                (DynamicImage::ImageRgba16(target), DynamicImage::ImageRgba16(image)) => copy_block(image, target, &lookup, origin, options.source_nodata, gpu, |value| value as u16, &mut covered),
+                (DynamicImage::ImageRgb32F(target), DynamicImage::ImageRgb32F(image)) => copy_block(image, target, &lookup, origin, options.source_nodata, gpu, |value| value as f32, &mut covered),
+                (DynamicImage::ImageRgba32F(target), DynamicImage::ImageRgba32F(image)) => copy_block(image, target, &lookup, origin, options.source_nodata, gpu, |value| value as f32, &mut covered),
+                (target, image) => return Err(TiffError::GenericError(format!(
+                    "Source window read as {:?} into a {:?} output", image.color(), target.color()))),
+            };
+        }
+    }
+
+    let output = output
+        .ok_or_else(|| TiffError::GenericError("No target pixel falls inside the source".to_string()))?;
+    info!("Warped onto {}x{} pixels, {} of them with data", width, height, copied);
+    if options.error_threshold > 0.0 {
+        info!("Transformed {} points exactly for {} output pixels (error threshold {} pixels)",
+              transformed, width as u64 * height as u64, options.error_threshold);
+    }
+
+    // Pixels without data take the target NoData value and a clear alpha
+    let mut output = if options.target_alpha { with_alpha(output) } else { output };
+    let nodata = options.target_nodata;
+    match &mut output {
+        DynamicImage::ImageLuma8(target) => mark_missing(target, &covered, nodata, |value| value as u8),
+        DynamicImage::ImageLumaA8(target) => mark_missing(target, &covered, nodata, |value| value as u8),
+        DynamicImage::ImageRgb8(target) => mark_missing(target, &covered, nodata, |value| value as u8),
+        DynamicImage::ImageRgba8(target) => mark_missing(target, &covered, nodata, |value| value as u8),
+        DynamicImage::ImageLuma16(target) => mark_missing(target, &covered, nodata, |value| value as u16),
+        DynamicImage::ImageLumaA16(target) => mark_missing(target, &covered, nodata, |value| value as u16),
+        DynamicImage::ImageRgb16(target) => mark_missing(target, &covered, nodata, |value| value as u16),
+        DynamicImage::ImageRgba16(target) => mark_missing(target, &covered, nodata, |value| value as u16),
+        DynamicImage::ImageRgb32F(target) => mark_missing(target, &covered, nodata, |value| value as f32),
+        DynamicImage::ImageRgba32F(target) => mark_missing(target, &covered, nodata, |value| value as f32),
+        _ => {},
+    }
+    Ok((output, target_geotransform))
+}
@@ -49,4 +49,9 @@ pub fn add_to_gdal_metadata(xml: &str, item: &str) -> String {
         // Metadata is missing the closing tag, add it
         format!("{}\n  {}\n</GDALMetadata>", xml, item)
     }
-}
\ No newline at end of file
+}
+
+/// Escape text for use inside an XML element
+pub fn escape_text(text: &str) -> String {
+    text.replace('&', "&amp;").replace('<', "&lt;").replace('>', "&gt;")
+}